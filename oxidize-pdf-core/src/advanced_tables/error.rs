@@ -74,4 +74,22 @@ pub enum TableError {
         /// Invalid span value
         span: usize,
     },
+
+    /// Row too tall for a page that rows may not break across
+    #[error("Row {row} is {height} points high but a page has room for {available}")]
+    RowTooTall {
+        /// Row index
+        row: usize,
+        /// Height of the row and the rows its cells span
+        height: f64,
+        /// Room for rows on a page
+        available: f64,
+    },
+
+    /// No room for rows on a page
+    #[error("No room for table rows on a page ({available} points)")]
+    PageTooSmall {
+        /// Room left for rows on the page
+        available: f64,
+    },
 }
@@ -10,6 +10,7 @@
 //! - Flexible column width management
 //! - Nested tables support
 //! - Professional border styles (solid, dashed, dotted, double)
+//! - Pagination across document pages with repeated headers
 //!
 //! # Example
 //! ```rust
@@ -33,12 +34,14 @@
 mod cell_style;
 mod error;
 mod header_builder;
+mod pagination;
 mod table_builder;
 mod table_renderer;
 
 pub use cell_style::{BorderStyle, CellAlignment, CellStyle, Padding};
 pub use error::TableError;
 pub use header_builder::{HeaderBuilder, HeaderCell};
+pub use pagination::{PaginatedTable, PaginationOptions, RowBreak};
 pub use table_builder::{AdvancedTable, AdvancedTableBuilder, Column};
 pub use table_renderer::TableRenderer;
 
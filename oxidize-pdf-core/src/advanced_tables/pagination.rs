@@ -0,0 +1,308 @@
+//! Splitting tables across pages
+//!
+//! Rows are placed on a page until the next one does not fit. A row that
+//! cells span into is kept with the row they start in; a row alone may be
+//! moved to the next page or split at the bottom of the page, as set by
+//! the [`RowBreak`] policy.
+
+use super::cell_style::{CellAlignment, CellStyle};
+use super::error::TableError;
+use crate::page::{Margins, Page};
+use crate::text::Font;
+use std::ops::Range;
+
+/// Allowance for rounding when comparing heights
+const EPSILON: f64 = 1e-6;
+
+/// What to do with a row that does not fit at the bottom of a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowBreak {
+    /// Move the row to the next page; only rows taller than a page are split
+    #[default]
+    Avoid,
+    /// Split the row at the bottom of the page if there is room for a line
+    /// of it, its text on the first part
+    Allow,
+    /// Never split rows; a row taller than a page is an error
+    Never,
+}
+
+/// Options for rendering a table across pages
+#[derive(Debug, Clone)]
+pub struct PaginationOptions {
+    /// Index of the document page the table starts on; a page is added if
+    /// `None`
+    pub start_page: Option<usize>,
+    /// Top of the table on its first page; below the top margin if `None`
+    pub start_y: Option<f64>,
+    /// Left of the table on every page; the left margin of the first page
+    /// if `None`
+    pub x: Option<f64>,
+    /// Width of added pages in points
+    pub page_width: f64,
+    /// Height of added pages in points
+    pub page_height: f64,
+    /// Margins of added pages
+    pub margins: Margins,
+    /// Policy for rows that do not fit at the bottom of a page
+    pub row_break: RowBreak,
+    /// Text below the table on every page it continues from, such as
+    /// "Continued on next page"; room for it is kept on every page
+    pub continued_footer: Option<String>,
+    /// Style of the continued footer
+    pub continued_style: CellStyle,
+}
+
+impl Default for PaginationOptions {
+    fn default() -> Self {
+        Self {
+            start_page: None,
+            start_y: None,
+            x: None,
+            page_width: 595.0,
+            page_height: 842.0,
+            margins: Margins::default(),
+            row_break: RowBreak::default(),
+            continued_footer: None,
+            continued_style: CellStyle::new()
+                .font(Font::HelveticaOblique)
+                .font_size(9.0)
+                .alignment(CellAlignment::Right),
+        }
+    }
+}
+
+impl PaginationOptions {
+    /// Set the continued footer
+    pub fn with_continued_footer(mut self, text: impl Into<String>) -> Self {
+        self.continued_footer = Some(text.into());
+        self
+    }
+
+    /// Set the row break policy
+    pub fn with_row_break(mut self, row_break: RowBreak) -> Self {
+        self.row_break = row_break;
+        self
+    }
+
+    /// Empty page for the table to continue on
+    pub(crate) fn new_page(&self) -> Page {
+        let mut page = Page::new(self.page_width, self.page_height);
+        page.set_margins(
+            self.margins.left,
+            self.margins.right,
+            self.margins.top,
+            self.margins.bottom,
+        );
+        page
+    }
+}
+
+/// Where a paginated table was rendered
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaginatedTable {
+    /// Indices of the document pages with a part of the table
+    pub pages: Vec<usize>,
+    /// Bottom of the table on its last page
+    pub end_y: f64,
+}
+
+/// A row, or the part of a row, on a page
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Fragment {
+    /// Index of the row
+    pub row: usize,
+    /// Height of the part on the page
+    pub height: f64,
+    /// Whether this is the first part of the row, with its text
+    pub first: bool,
+}
+
+/// Rows of each page; the first page may have none if not even the first
+/// rows fit on it
+///
+/// `heights` are the heights of the rows and `spans` the rows each row's
+/// cells span. `first_room` is the room for rows on the first page and
+/// `room` that on added pages, given whether rows were placed before.
+/// Parts of rows are at least `min_split` high, unless the row is taller
+/// than a page.
+pub(crate) fn paginate(
+    heights: &[f64],
+    spans: &[usize],
+    row_break: RowBreak,
+    min_split: f64,
+    first_room: f64,
+    room: impl Fn(bool) -> f64,
+) -> Result<Vec<Vec<Fragment>>, TableError> {
+    let mut pages: Vec<Vec<Fragment>> = vec![Vec::new()];
+    let mut available = first_room;
+
+    for group in groups(spans) {
+        let height: f64 = heights[group.clone()].iter().sum();
+        let split = row_break == RowBreak::Allow && group.len() == 1 && available >= min_split;
+        if height > available + EPSILON && !split {
+            if !fresh(&pages) {
+                available = next_page(&mut pages, &room)?;
+            }
+            if height > available + EPSILON && row_break == RowBreak::Never {
+                return Err(TableError::RowTooTall {
+                    row: group.start,
+                    height,
+                    available,
+                });
+            }
+        }
+
+        for row in group {
+            let mut rest = heights[row];
+            let mut first = true;
+            while rest > available + EPSILON {
+                if available >= min_split || fresh(&pages) {
+                    let page = pages.last_mut().expect("pages are not empty");
+                    page.push(Fragment {
+                        row,
+                        height: available,
+                        first,
+                    });
+                    rest -= available;
+                    first = false;
+                }
+                available = next_page(&mut pages, &room)?;
+            }
+            pages
+                .last_mut()
+                .expect("pages are not empty")
+                .push(Fragment {
+                    row,
+                    height: rest,
+                    first,
+                });
+            available -= rest;
+        }
+    }
+    Ok(pages)
+}
+
+/// Start a page and return the room on it
+fn next_page(
+    pages: &mut Vec<Vec<Fragment>>,
+    room: impl Fn(bool) -> f64,
+) -> Result<f64, TableError> {
+    let placed = pages.iter().any(|page| !page.is_empty());
+    pages.push(Vec::new());
+    let available = room(placed);
+    if available <= 0.0 {
+        return Err(TableError::PageTooSmall { available });
+    }
+    Ok(available)
+}
+
+/// Whether the last page is an added page without rows
+fn fresh(pages: &[Vec<Fragment>]) -> bool {
+    pages.len() > 1 && pages.last().is_some_and(Vec::is_empty)
+}
+
+/// Rows kept together: each row with the rows its cells span into
+fn groups(spans: &[usize]) -> Vec<Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    while start < spans.len() {
+        let mut end = start + 1;
+        let mut row = start;
+        while row < end {
+            end = end.max(row + spans[row].max(1)).min(spans.len());
+            row += 1;
+        }
+        groups.push(start..end);
+        start = end;
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(pages: &[Vec<Fragment>]) -> Vec<Vec<usize>> {
+        pages
+            .iter()
+            .map(|page| page.iter().map(|fragment| fragment.row).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(
+            groups(&[1, 2, 1, 1, 3, 2, 1, 1]),
+            vec![0..1, 1..3, 3..4, 4..7, 7..8]
+        );
+        // Spans past the last row are cut
+        assert_eq!(groups(&[1, 4]), vec![0..1, 1..2]);
+    }
+
+    #[test]
+    fn test_paginate_moves_rows() {
+        let heights = [20.0; 7];
+        let pages = paginate(&heights, &[1; 7], RowBreak::Avoid, 20.0, 70.0, |_| 60.0).unwrap();
+        assert_eq!(rows(&pages), vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+
+        // Rows spanned by a cell stay together
+        let spans = [1, 1, 2, 1, 1, 1, 1];
+        let pages = paginate(&heights, &spans, RowBreak::Avoid, 20.0, 70.0, |_| 60.0).unwrap();
+        assert_eq!(rows(&pages), vec![vec![0, 1], vec![2, 3, 4], vec![5, 6]]);
+    }
+
+    #[test]
+    fn test_paginate_first_page_without_room() {
+        let pages = paginate(&[20.0; 3], &[1; 3], RowBreak::Avoid, 20.0, 10.0, |placed| {
+            if placed {
+                40.0
+            } else {
+                30.0
+            }
+        })
+        .unwrap();
+        assert_eq!(rows(&pages), vec![vec![], vec![0], vec![1, 2]]);
+    }
+
+    #[test]
+    fn test_paginate_row_break_policies() {
+        let heights = [20.0, 50.0, 20.0];
+        let spans = [1; 3];
+
+        let pages = paginate(&heights, &spans, RowBreak::Allow, 20.0, 60.0, |_| 60.0).unwrap();
+        assert_eq!(rows(&pages), vec![vec![0, 1], vec![1, 2]]);
+        assert_eq!(
+            pages[1][0],
+            Fragment {
+                row: 1,
+                height: 10.0,
+                first: false
+            }
+        );
+
+        // Too little room left to split
+        let pages = paginate(&heights, &spans, RowBreak::Allow, 45.0, 60.0, |_| 60.0).unwrap();
+        assert_eq!(rows(&pages), vec![vec![0], vec![1], vec![2]]);
+
+        let pages = paginate(&heights, &spans, RowBreak::Avoid, 20.0, 60.0, |_| 60.0).unwrap();
+        assert_eq!(rows(&pages), vec![vec![0], vec![1], vec![2]]);
+
+        // Rows taller than a page
+        let heights = [20.0, 100.0];
+        let pages = paginate(&heights, &[1; 2], RowBreak::Avoid, 20.0, 60.0, |_| 60.0).unwrap();
+        assert_eq!(rows(&pages), vec![vec![0], vec![1], vec![1]]);
+        assert_eq!(
+            paginate(&heights, &[1; 2], RowBreak::Never, 20.0, 60.0, |_| 60.0),
+            Err(TableError::RowTooTall {
+                row: 1,
+                height: 100.0,
+                available: 60.0
+            })
+        );
+        assert_eq!(
+            paginate(&heights, &[1; 2], RowBreak::Avoid, 20.0, 60.0, |_| 0.0),
+            Err(TableError::PageTooSmall { available: 0.0 })
+        );
+    }
+}
@@ -2,10 +2,13 @@
 
 use super::cell_style::{BorderConfiguration, BorderStyle, CellAlignment, CellStyle};
 use super::header_builder::HeaderBuilder;
+use super::pagination::{paginate, Fragment, PaginatedTable, PaginationOptions};
 use super::table_builder::{AdvancedTable, CellData, RowData};
+use crate::document::Document;
 use crate::error::PdfError;
 use crate::graphics::Color;
-use crate::page::Page;
+use crate::page::{ContentTarget, Page};
+use crate::structure::{StandardStructureType, StructureElement, TableHeaderScope};
 use crate::text::{measure_text, Font};
 
 /// Renderer for advanced tables
@@ -72,6 +75,9 @@ impl TableRenderer {
     }
 
     /// Render a table to a PDF page
+    ///
+    /// On a tagged page the table is tagged with its rows and cells, header
+    /// cells applying to their column.
     pub fn render_table(
         &self,
         page: &mut Page,
@@ -84,29 +90,264 @@ impl TableRenderer {
             .validate()
             .map_err(|e| PdfError::InvalidOperation(e.to_string()))?;
 
-        let mut current_y = y;
+        page.begin_tag_group(StructureElement::new(StandardStructureType::Table));
 
         // Render header if present
-        if table.show_header {
-            if let Some(header) = &table.header {
-                current_y = self.render_header(page, table, header, x, current_y)?;
-            } else if !table.columns.is_empty() {
-                // Render simple header from column definitions
-                current_y = self.render_simple_header(page, table, x, current_y)?;
-            }
-        }
+        let mut current_y = self.render_headers(page, table, x, y)?;
 
         // Render table rows
         current_y = self.render_rows(page, table, x, current_y)?;
 
+        page.end_tag_group();
+
         // Render table border if enabled
         if table.table_border {
+            page.begin_artifact(ContentTarget::Graphics);
             self.render_table_border(page, table, x, y, current_y)?;
+            page.end_tagged_content(ContentTarget::Graphics);
         }
 
         Ok(current_y)
     }
 
+    /// Render a table across the pages of a document, adding pages as needed
+    ///
+    /// Rows that do not fit at the bottom of a page continue on the next,
+    /// moved or split as set by the row break policy of the options; rows
+    /// that cells span are kept together. The header is repeated on every
+    /// page if the table repeats its headers.
+    pub fn render_table_paginated(
+        &self,
+        document: &mut Document,
+        table: &AdvancedTable,
+        options: &PaginationOptions,
+    ) -> Result<PaginatedTable, PdfError> {
+        table
+            .validate()
+            .map_err(|e| PdfError::InvalidOperation(e.to_string()))?;
+
+        let (top, bottom, x) = match options.start_page {
+            Some(index) => {
+                let page = document
+                    .page(index)
+                    .ok_or(PdfError::InvalidPageNumber(index as u32))?;
+                (
+                    options
+                        .start_y
+                        .unwrap_or(page.height() - page.margins().top),
+                    page.margins().bottom,
+                    options.x.unwrap_or(page.margins().left),
+                )
+            }
+            None => (
+                options
+                    .start_y
+                    .unwrap_or(options.page_height - options.margins.top),
+                options.margins.bottom,
+                options.x.unwrap_or(options.margins.left),
+            ),
+        };
+        let page_top = options.page_height - options.margins.top;
+
+        // Room for rows, below the header and above the continued footer
+        let header_height = self.header_height(table);
+        let footer_height = match options.continued_footer {
+            Some(_) => self.default_row_height,
+            None => 0.0,
+        };
+        let page_room = page_top - options.margins.bottom - footer_height;
+        let heights: Vec<f64> = table
+            .rows
+            .iter()
+            .map(|row| row.min_height.unwrap_or(self.default_row_height))
+            .collect();
+        let spans: Vec<usize> = table
+            .rows
+            .iter()
+            .map(|row| row.cells.iter().map(|cell| cell.rowspan).max().unwrap_or(1))
+            .collect();
+        let slices = paginate(
+            &heights,
+            &spans,
+            options.row_break,
+            self.default_row_height,
+            top - bottom - header_height - footer_height,
+            |placed| match placed && !table.repeat_headers {
+                true => page_room,
+                false => page_room - header_height,
+            },
+        )
+        .map_err(|e| PdfError::InvalidOperation(e.to_string()))?;
+
+        let mut page_index = options.start_page.unwrap_or(document.page_count());
+        let mut pages = Vec::new();
+        let mut end_y = top;
+        for (slice_index, fragments) in slices.iter().enumerate() {
+            // The first page is the start page, if any; the others are added
+            let mut added = match (slice_index, options.start_page) {
+                (0, Some(_)) => None,
+                _ => Some(options.new_page()),
+            };
+            if slice_index > 0 {
+                page_index += 1;
+            }
+            // A table without rows still has its header
+            if !fragments.is_empty() || table.rows.is_empty() {
+                let page = match added.as_mut() {
+                    Some(page) => page,
+                    None => document
+                        .page_mut(page_index)
+                        .ok_or(PdfError::InvalidPageNumber(page_index as u32))?,
+                };
+                let footer = match slice_index + 1 < slices.len() {
+                    true => options.continued_footer.as_deref(),
+                    false => None,
+                };
+                end_y = self.render_fragments(
+                    page,
+                    table,
+                    fragments,
+                    x,
+                    if slice_index == 0 { top } else { page_top },
+                    pages.is_empty() || table.repeat_headers,
+                    footer.map(|text| (text, &options.continued_style)),
+                )?;
+                pages.push(page_index);
+            }
+
+            match added {
+                Some(page) => document.insert_page(page_index, page)?,
+                None => {
+                    if let Some(chars) = document
+                        .page(page_index)
+                        .and_then(Page::get_used_characters)
+                    {
+                        document.used_characters.extend(chars);
+                    }
+                }
+            }
+        }
+
+        Ok(PaginatedTable { pages, end_y })
+    }
+
+    /// Height of the header rows, if shown
+    fn header_height(&self, table: &AdvancedTable) -> f64 {
+        if !table.show_header {
+            return 0.0;
+        }
+        match &table.header {
+            Some(header) => header.levels.len() as f64 * self.default_header_height,
+            None if !table.columns.is_empty() => self.default_header_height,
+            None => 0.0,
+        }
+    }
+
+    /// Render the rows of a table on one page, below its header if
+    /// `header` is set and above a continued footer if any
+    #[allow(clippy::too_many_arguments)]
+    fn render_fragments(
+        &self,
+        page: &mut Page,
+        table: &AdvancedTable,
+        fragments: &[Fragment],
+        x: f64,
+        top: f64,
+        header: bool,
+        footer: Option<(&str, &CellStyle)>,
+    ) -> Result<f64, PdfError> {
+        page.begin_tag_group(StructureElement::new(StandardStructureType::Table));
+        let mut current_y = match header {
+            true => self.render_headers(page, table, x, top)?,
+            false => top,
+        };
+        let column_positions = self.calculate_column_positions(table, x);
+
+        for (index, fragment) in fragments.iter().enumerate() {
+            let row = &table.rows[fragment.row];
+            if fragment.first {
+                page.begin_tag_group(StructureElement::new(StandardStructureType::TR));
+            }
+
+            for (col_idx, cell) in row.cells.iter().enumerate() {
+                let cell_x = column_positions[col_idx];
+                let cell_width = self.calculate_span_width(table, col_idx, cell.colspan);
+                // Spanning cells end with the last row they span on the page
+                let cell_height: f64 = fragments[index..]
+                    .iter()
+                    .take_while(|other| other.row < fragment.row + cell.rowspan)
+                    .map(|other| other.height)
+                    .sum();
+                let style = self.resolve_cell_style(table, row, cell, fragment.row, col_idx);
+                let cell_y = current_y - cell_height;
+
+                if fragment.first {
+                    self.render_cell(
+                        page,
+                        &cell.content,
+                        cell_x,
+                        cell_y,
+                        cell_width,
+                        cell_height,
+                        &style,
+                        StructureElement::new(StandardStructureType::TD),
+                    )?;
+                } else {
+                    // The rest of a split row has no text
+                    self.render_cell_box(page, cell_x, cell_y, cell_width, cell_height, &style)?;
+                }
+            }
+
+            if fragment.first {
+                page.end_tag_group();
+            }
+            current_y -= fragment.height;
+        }
+
+        page.end_tag_group();
+
+        if table.table_border {
+            page.begin_artifact(ContentTarget::Graphics);
+            self.render_table_border(page, table, x, top, current_y)?;
+            page.end_tagged_content(ContentTarget::Graphics);
+        }
+
+        if let Some((text, style)) = footer {
+            page.begin_artifact(ContentTarget::Text);
+            self.render_cell_text(
+                page,
+                text,
+                x,
+                current_y - self.default_row_height,
+                table.calculate_width(),
+                self.default_row_height,
+                style,
+            )?;
+            page.end_tagged_content(ContentTarget::Text);
+        }
+
+        Ok(current_y)
+    }
+
+    /// Render the header of a table, if shown, and return the y below it
+    fn render_headers(
+        &self,
+        page: &mut Page,
+        table: &AdvancedTable,
+        x: f64,
+        y: f64,
+    ) -> Result<f64, PdfError> {
+        if !table.show_header {
+            return Ok(y);
+        }
+        match &table.header {
+            Some(header) => self.render_header(page, table, header, x, y),
+            // Render simple header from column definitions
+            None if !table.columns.is_empty() => self.render_simple_header(page, table, x, y),
+            None => Ok(y),
+        }
+    }
+
     /// Render table headers
     fn render_header(
         &self,
@@ -121,6 +362,7 @@ impl TableRenderer {
 
         for level in header.levels.iter() {
             let row_height = self.default_header_height;
+            page.begin_tag_group(StructureElement::new(StandardStructureType::TR));
 
             for cell in level {
                 let cell_x = column_positions[cell.start_col];
@@ -137,9 +379,11 @@ impl TableRenderer {
                     cell_width,
                     cell_height,
                     style,
+                    header_cell(),
                 )?;
             }
 
+            page.end_tag_group();
             current_y -= row_height;
         }
 
@@ -156,6 +400,7 @@ impl TableRenderer {
     ) -> Result<f64, PdfError> {
         let column_positions = self.calculate_column_positions(table, x);
         let header_height = self.default_header_height;
+        page.begin_tag_group(StructureElement::new(StandardStructureType::TR));
 
         for (col_idx, column) in table.columns.iter().enumerate() {
             let cell_x = column_positions[col_idx];
@@ -169,9 +414,11 @@ impl TableRenderer {
                 cell_width,
                 header_height,
                 &table.header_style,
+                header_cell(),
             )?;
         }
 
+        page.end_tag_group();
         Ok(start_y - header_height)
     }
 
@@ -188,6 +435,7 @@ impl TableRenderer {
 
         for (row_idx, row) in table.rows.iter().enumerate() {
             let row_height = row.min_height.unwrap_or(self.default_row_height);
+            page.begin_tag_group(StructureElement::new(StandardStructureType::TR));
 
             for (col_idx, cell) in row.cells.iter().enumerate() {
                 let cell_x = column_positions[col_idx];
@@ -204,16 +452,18 @@ impl TableRenderer {
                     cell_width,
                     cell_height,
                     &style,
+                    StructureElement::new(StandardStructureType::TD),
                 )?;
             }
 
+            page.end_tag_group();
             current_y -= row_height;
         }
 
         Ok(current_y)
     }
 
-    /// Render an individual cell
+    /// Render an individual cell, tagged as `element` on a tagged page
     #[allow(clippy::too_many_arguments)]
     fn render_cell(
         &self,
@@ -224,7 +474,36 @@ impl TableRenderer {
         width: f64,
         height: f64,
         style: &CellStyle,
+        element: StructureElement,
+    ) -> Result<(), PdfError> {
+        self.render_cell_box(page, x, y, width, height, style)?;
+
+        // Draw text content
+        if !content.is_empty() {
+            page.begin_tagged_content(ContentTarget::Text, element);
+            self.render_cell_text(page, content, x, y, width, height, style)?;
+            page.end_tagged_content(ContentTarget::Text);
+        } else {
+            // Empty cells still take their place in the row
+            page.begin_tag_group(element);
+            page.end_tag_group();
+        }
+
+        Ok(())
+    }
+
+    /// Render the background and borders of a cell, as decoration
+    fn render_cell_box(
+        &self,
+        page: &mut Page,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        style: &CellStyle,
     ) -> Result<(), PdfError> {
+        page.begin_artifact(ContentTarget::Graphics);
+
         // Draw background if specified
         if let Some(bg_color) = style.background_color {
             page.graphics()
@@ -237,12 +516,7 @@ impl TableRenderer {
 
         // Draw borders
         self.render_cell_borders(page, x, y, width, height, &style.border)?;
-
-        // Draw text content
-        if !content.is_empty() {
-            self.render_cell_text(page, content, x, y, width, height, style)?;
-        }
-
+        page.end_tagged_content(ContentTarget::Graphics);
         Ok(())
     }
 
@@ -527,6 +801,11 @@ impl TableRenderer {
     }
 }
 
+/// Header cell of a column
+fn header_cell() -> StructureElement {
+    StructureElement::new(StandardStructureType::TH).with_scope(TableHeaderScope::Column)
+}
+
 impl Default for TableRenderer {
     fn default() -> Self {
         Self::new()
@@ -536,8 +815,90 @@ impl Default for TableRenderer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::advanced_tables::{AdvancedTableBuilder, RowBreak};
     use crate::text::Font;
 
+    fn statement(rows: usize) -> AdvancedTable {
+        let mut builder = AdvancedTableBuilder::new()
+            .columns(vec![("Account", 150.0), ("Amount", 100.0)])
+            .complex_header(HeaderBuilder::auto().add_level(vec![("Statement", 2)]))
+            .repeat_headers(true);
+        for row in 0..rows {
+            let account = format!("Account {row}");
+            builder = builder.add_row(vec![account.as_str(), "1.00"]);
+        }
+        builder.build().unwrap()
+    }
+
+    fn content(document: &mut Document, index: usize) -> String {
+        let page = document.page_mut(index).unwrap();
+        String::from_utf8_lossy(&page.generate_content().unwrap()).into_owned()
+    }
+
+    #[test]
+    fn test_render_table_paginated() {
+        let renderer = TableRenderer::new();
+        let mut document = Document::new();
+        let options = PaginationOptions::default().with_continued_footer("Continued");
+
+        // 842 - 2 * 72 points: a 30 point header, a 25 point footer and
+        // 25 rows of 25 points on every page
+        let table = statement(60);
+        let result = renderer
+            .render_table_paginated(&mut document, &table, &options)
+            .unwrap();
+        assert_eq!(result.pages, vec![0, 1, 2]);
+        assert_eq!(document.page_count(), 3);
+        assert_eq!(result.end_y, 770.0 - 30.0 - 10.0 * 25.0);
+
+        for index in 0..3 {
+            let content = content(&mut document, index);
+            assert!(content.contains("(Statement) Tj"));
+            assert_eq!(content.contains("(Continued) Tj"), index < 2);
+        }
+        let last = content(&mut document, 2);
+        assert!(last.contains("(Account 50) Tj"));
+        assert!(!last.contains("(Account 49) Tj"));
+
+        // Without repeated headers, continued on an existing page
+        let table = AdvancedTable {
+            repeat_headers: false,
+            ..statement(30)
+        };
+        let options = PaginationOptions {
+            start_page: Some(2),
+            start_y: Some(result.end_y),
+            ..Default::default()
+        };
+        let result = renderer
+            .render_table_paginated(&mut document, &table, &options)
+            .unwrap();
+        assert_eq!(result.pages, vec![2, 3]);
+        assert!(!content(&mut document, 3).contains("(Statement) Tj"));
+    }
+
+    #[test]
+    fn test_render_table_paginated_keeps_spanned_rows() {
+        let renderer = TableRenderer::new();
+        let mut document = Document::new();
+        let mut table = statement(26);
+        table.rows[25].cells[0].rowspan = 2;
+        table.rows.push(RowData::from_strings(vec!["", "2.00"]));
+
+        // 26 rows fit on the first page, but not the last two together
+        let result = renderer
+            .render_table_paginated(&mut document, &table, &PaginationOptions::default())
+            .unwrap();
+        assert_eq!(result.pages, vec![0, 1]);
+        assert!(content(&mut document, 1).contains("(Account 25) Tj"));
+
+        let options = PaginationOptions::default().with_row_break(RowBreak::Never);
+        table.rows[0].min_height = Some(1000.0);
+        assert!(renderer
+            .render_table_paginated(&mut document, &table, &options)
+            .is_err());
+    }
+
     #[test]
     fn test_truncate_text_to_width_no_truncation_needed() {
         let renderer = TableRenderer::new();
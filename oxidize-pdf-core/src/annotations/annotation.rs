@@ -56,6 +56,8 @@ pub enum AnnotationType {
     TrapNet,
     /// Watermark annotation
     Watermark,
+    /// Redaction annotation, marking content to remove
+    Redact,
 }
 
 impl AnnotationType {
@@ -86,6 +88,7 @@ impl AnnotationType {
             AnnotationType::PrinterMark => "PrinterMark",
             AnnotationType::TrapNet => "TrapNet",
             AnnotationType::Watermark => "Watermark",
+            AnnotationType::Redact => "Redact",
         }
     }
 }
@@ -1358,6 +1361,7 @@ mod tests {
             (AnnotationType::PrinterMark, "PrinterMark"),
             (AnnotationType::TrapNet, "TrapNet"),
             (AnnotationType::Watermark, "Watermark"),
+            (AnnotationType::Redact, "Redact"),
         ];
 
         for (annotation_type, expected_name) in type_name_pairs {
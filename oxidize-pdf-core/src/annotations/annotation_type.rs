@@ -499,6 +499,90 @@ impl HighlightAnnotation {
     }
 }
 
+/// Redaction annotation
+///
+/// Marks content to be removed; the overlay is drawn in its place once the
+/// redaction is applied (see `operations::Redactor`).
+#[derive(Debug, Clone)]
+pub struct RedactAnnotation {
+    /// Base annotation
+    pub annotation: Annotation,
+    /// Areas to redact, the annotation rectangle if not set
+    pub quad_points: Option<crate::annotations::QuadPoints>,
+    /// Fill color of the overlay
+    pub interior_color: Option<Color>,
+    /// Text shown on the overlay
+    pub overlay_text: Option<String>,
+    /// Whether the overlay text is repeated to fill the area
+    pub repeat: bool,
+}
+
+impl RedactAnnotation {
+    /// Create a new redaction annotation
+    pub fn new(rect: Rectangle) -> Self {
+        let annotation = Annotation::new(crate::annotations::AnnotationType::Redact, rect);
+
+        Self {
+            annotation,
+            quad_points: None,
+            interior_color: None,
+            overlay_text: None,
+            repeat: false,
+        }
+    }
+
+    /// Redact several areas, such as the lines of a text passage
+    pub fn with_areas(mut self, areas: &[Rectangle]) -> Self {
+        self.quad_points = Some(crate::annotations::QuadPoints::from_rects(areas));
+        self
+    }
+
+    /// Set overlay fill color
+    pub fn with_interior_color(mut self, color: Color) -> Self {
+        self.interior_color = Some(color);
+        self
+    }
+
+    /// Set overlay text
+    pub fn with_overlay_text(mut self, text: impl Into<String>, repeat: bool) -> Self {
+        self.overlay_text = Some(text.into());
+        self.repeat = repeat;
+        self
+    }
+
+    /// Convert to annotation
+    pub fn to_annotation(self) -> Annotation {
+        let mut annotation = self.annotation;
+        if let Some(quad_points) = self.quad_points {
+            annotation
+                .properties
+                .set("QuadPoints", quad_points.to_array());
+        }
+        if let Some(color) = self.interior_color {
+            let ic = match color {
+                Color::Rgb(r, g, b) => vec![Object::Real(r), Object::Real(g), Object::Real(b)],
+                Color::Gray(g) => vec![Object::Real(g)],
+                Color::Cmyk(c, m, y, k) => vec![
+                    Object::Real(c),
+                    Object::Real(m),
+                    Object::Real(y),
+                    Object::Real(k),
+                ],
+            };
+            annotation.properties.set("IC", Object::Array(ic));
+        }
+        if let Some(text) = self.overlay_text {
+            annotation
+                .properties
+                .set("OverlayText", Object::String(text));
+            if self.repeat {
+                annotation.properties.set("Repeat", Object::Boolean(true));
+            }
+        }
+        annotation
+    }
+}
+
 /// Circle annotation
 #[derive(Debug, Clone)]
 pub struct CircleAnnotation {
@@ -17,8 +17,8 @@ pub use annotation::{
 };
 pub use annotation_type::{
     CircleAnnotation, FileAttachmentAnnotation, FileAttachmentIcon, FreeTextAnnotation,
-    HighlightAnnotation, InkAnnotation, LineAnnotation, LineEndingStyle, SquareAnnotation,
-    StampAnnotation, StampName,
+    HighlightAnnotation, InkAnnotation, LineAnnotation, LineEndingStyle, RedactAnnotation,
+    SquareAnnotation, StampAnnotation, StampName,
 };
 pub use link::{HighlightMode, LinkAction, LinkAnnotation, LinkDestination};
 pub use markup::{MarkupAnnotation, MarkupType, QuadPoints};
@@ -0,0 +1,846 @@
+//! Embedded file attachments and portfolios as defined in ISO 32000-1
+//! §7.11.4 and §12.3.5
+//!
+//! Files attached to a document are listed in the `EmbeddedFiles` name
+//! tree of the catalog, independent of any page. Each carries its MIME
+//! type, description, dates and an MD5 checksum, and may state how it
+//! relates to the document (`/AFRelationship`, used by PDF/A-3 and
+//! Factur-X). A [`Collection`] turns the document into a portfolio, which
+//! viewers present as a list of its attachments.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oxidize_pdf::attachments::{AFRelationship, EmbeddedFile};
+//! use oxidize_pdf::{Document, Page};
+//!
+//! # fn main() -> oxidize_pdf::Result<()> {
+//! let mut doc = Document::new();
+//! doc.add_page(Page::a4());
+//! doc.attach_file(
+//!     EmbeddedFile::new("figures.csv", b"quarter,revenue\nQ1,120\n".to_vec())
+//!         .with_mime_type("text/csv")
+//!         .with_description("Source data of the revenue chart")
+//!         .with_relationship(AFRelationship::Source),
+//! );
+//! let pdf = doc.to_bytes()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::objects::ObjectId;
+use crate::parser::objects::{PdfArray, PdfDictionary, PdfName, PdfObject, PdfStream, PdfString};
+use crate::parser::resolve::{get, get_dict, get_name, Resolver};
+use crate::parser::strings::text_bytes;
+use crate::parser::strings::{parse_pdf_date, text_string};
+use crate::parser::PdfDocument;
+use crate::structure::read_name_tree;
+use crate::writer::format_pdf_date;
+use chrono::{DateTime, Utc};
+use std::io::{Read, Seek};
+
+/// How an attached file relates to the document (ISO 32000-2 §14.13)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AFRelationship {
+    /// The original content the document was created from
+    Source,
+    /// Data used for tables or charts of the document
+    Data,
+    /// An alternative representation of the content, e.g. audio
+    Alternative,
+    /// A supplemental representation of the original source or data
+    Supplement,
+    /// An encrypted payload document
+    EncryptedPayload,
+    /// Data of the document's form fields
+    FormData,
+    /// A schema definition for the document's content
+    Schema,
+    /// The relationship is not known or none of the above
+    Unspecified,
+}
+
+impl AFRelationship {
+    /// Get PDF name
+    pub fn pdf_name(&self) -> &'static str {
+        match self {
+            AFRelationship::Source => "Source",
+            AFRelationship::Data => "Data",
+            AFRelationship::Alternative => "Alternative",
+            AFRelationship::Supplement => "Supplement",
+            AFRelationship::EncryptedPayload => "EncryptedPayload",
+            AFRelationship::FormData => "FormData",
+            AFRelationship::Schema => "Schema",
+            AFRelationship::Unspecified => "Unspecified",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        match name {
+            "Source" => AFRelationship::Source,
+            "Data" => AFRelationship::Data,
+            "Alternative" => AFRelationship::Alternative,
+            "Supplement" => AFRelationship::Supplement,
+            "EncryptedPayload" => AFRelationship::EncryptedPayload,
+            "FormData" => AFRelationship::FormData,
+            "Schema" => AFRelationship::Schema,
+            _ => AFRelationship::Unspecified,
+        }
+    }
+}
+
+/// Value of a custom portfolio column for one file
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollectionValue {
+    Text(String),
+    Date(DateTime<Utc>),
+    Number(f64),
+}
+
+/// A file embedded in a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedFile {
+    /// File name, also the key of the file in the `EmbeddedFiles` tree
+    pub file_name: String,
+    /// Content of the file
+    pub data: Vec<u8>,
+    /// MIME type, such as `text/csv`
+    pub mime_type: Option<String>,
+    /// Description shown by viewers
+    pub description: Option<String>,
+    pub creation_date: Option<DateTime<Utc>>,
+    pub modification_date: Option<DateTime<Utc>>,
+    /// How the file relates to the document; files with a relationship are
+    /// also listed in the catalog's `/AF` array
+    pub relationship: Option<AFRelationship>,
+    /// Values of the portfolio's custom columns, by field key
+    pub collection_values: Vec<(String, CollectionValue)>,
+}
+
+impl EmbeddedFile {
+    /// Create an embedded file
+    pub fn new(file_name: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            file_name: file_name.into(),
+            data,
+            mime_type: None,
+            description: None,
+            creation_date: None,
+            modification_date: None,
+            relationship: None,
+            collection_values: Vec::new(),
+        }
+    }
+
+    /// Read a file from disk, using its name and modification time
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut file = Self::new(file_name, data);
+        file.modification_date = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(DateTime::<Utc>::from);
+        Ok(file)
+    }
+
+    /// Set MIME type
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Set description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set creation date
+    pub fn with_creation_date(mut self, date: DateTime<Utc>) -> Self {
+        self.creation_date = Some(date);
+        self
+    }
+
+    /// Set modification date
+    pub fn with_modification_date(mut self, date: DateTime<Utc>) -> Self {
+        self.modification_date = Some(date);
+        self
+    }
+
+    /// Set relationship to the document
+    pub fn with_relationship(mut self, relationship: AFRelationship) -> Self {
+        self.relationship = Some(relationship);
+        self
+    }
+
+    /// Set the value of a custom portfolio column
+    pub fn with_collection_value(mut self, key: impl Into<String>, value: CollectionValue) -> Self {
+        let key = key.into();
+        self.collection_values
+            .retain(|(existing, _)| *existing != key);
+        self.collection_values.push((key, value));
+        self
+    }
+
+    /// MD5 digest of the content, as stored in `/CheckSum`
+    pub fn checksum(&self) -> [u8; 16] {
+        md5::compute(&self.data).0
+    }
+
+    /// The embedded file stream, Flate-compressed if `compress` is set
+    pub(crate) fn to_stream(&self, compress: bool) -> Result<PdfStream> {
+        let mut params = PdfDictionary::new();
+        params.insert(
+            "Size".to_string(),
+            PdfObject::Integer(self.data.len() as i64),
+        );
+        params.insert(
+            "CheckSum".to_string(),
+            PdfObject::String(PdfString::new(self.checksum().to_vec())),
+        );
+        for (key, date) in [
+            ("CreationDate", self.creation_date),
+            ("ModDate", self.modification_date),
+        ] {
+            if let Some(date) = date {
+                params.insert(key.to_string(), date_string(date));
+            }
+        }
+
+        let mut dict = PdfDictionary::new();
+        dict.insert("Type".to_string(), name("EmbeddedFile"));
+        if let Some(mime_type) = &self.mime_type {
+            dict.insert("Subtype".to_string(), name(mime_type));
+        }
+        dict.insert("Params".to_string(), PdfObject::Dictionary(params));
+        let data = if compress {
+            dict.insert("Filter".to_string(), name("FlateDecode"));
+            crate::compression::compress(&self.data)?
+        } else {
+            self.data.clone()
+        };
+        Ok(PdfStream { dict, data })
+    }
+
+    /// The file specification dictionary pointing at the written stream
+    pub(crate) fn to_filespec(&self, stream_id: ObjectId) -> PdfDictionary {
+        let stream = PdfObject::Reference(stream_id.number(), stream_id.generation());
+        let mut embedded = PdfDictionary::new();
+        embedded.insert("F".to_string(), stream.clone());
+        embedded.insert("UF".to_string(), stream);
+
+        // `/F` is a byte string; readers use `/UF` for the real name
+        let ascii_name: String = self
+            .file_name
+            .chars()
+            .map(|c| if c.is_ascii() { c } else { '_' })
+            .collect();
+        let mut dict = PdfDictionary::new();
+        dict.insert("Type".to_string(), name("Filespec"));
+        dict.insert(
+            "F".to_string(),
+            PdfObject::String(PdfString::new(ascii_name.into_bytes())),
+        );
+        dict.insert("UF".to_string(), text(&self.file_name));
+        dict.insert("EF".to_string(), PdfObject::Dictionary(embedded));
+        if let Some(description) = &self.description {
+            dict.insert("Desc".to_string(), text(description));
+        }
+        if let Some(relationship) = self.relationship {
+            dict.insert("AFRelationship".to_string(), name(relationship.pdf_name()));
+        }
+        if !self.collection_values.is_empty() {
+            let mut item = PdfDictionary::new();
+            item.insert("Type".to_string(), name("CollectionItem"));
+            for (key, value) in &self.collection_values {
+                let value = match value {
+                    CollectionValue::Text(s) => text(s),
+                    CollectionValue::Date(date) => date_string(*date),
+                    CollectionValue::Number(n) => PdfObject::Real(*n),
+                };
+                item.insert(key.clone(), value);
+            }
+            dict.insert("CI".to_string(), PdfObject::Dictionary(item));
+        }
+        dict
+    }
+}
+
+/// How a portfolio is presented initially
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollectionView {
+    /// A table of the files with the schema's columns
+    #[default]
+    Details,
+    /// Icons of the files
+    Tile,
+    /// The file list is hidden, showing the initial file
+    Hidden,
+}
+
+impl CollectionView {
+    fn pdf_name(&self) -> &'static str {
+        match self {
+            CollectionView::Details => "D",
+            CollectionView::Tile => "T",
+            CollectionView::Hidden => "H",
+        }
+    }
+}
+
+/// Data shown in a portfolio column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionFieldKind {
+    /// Text from the files' collection values
+    Text,
+    /// Date from the files' collection values
+    Date,
+    /// Number from the files' collection values
+    Number,
+    FileName,
+    Description,
+    /// Uncompressed size of the file
+    Size,
+    ModificationDate,
+    CreationDate,
+    CompressedSize,
+}
+
+impl CollectionFieldKind {
+    fn pdf_name(&self) -> &'static str {
+        match self {
+            CollectionFieldKind::Text => "S",
+            CollectionFieldKind::Date => "D",
+            CollectionFieldKind::Number => "N",
+            CollectionFieldKind::FileName => "F",
+            CollectionFieldKind::Description => "Desc",
+            CollectionFieldKind::Size => "Size",
+            CollectionFieldKind::ModificationDate => "ModDate",
+            CollectionFieldKind::CreationDate => "CreationDate",
+            CollectionFieldKind::CompressedSize => "CompressedSize",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "S" => CollectionFieldKind::Text,
+            "D" => CollectionFieldKind::Date,
+            "N" => CollectionFieldKind::Number,
+            "F" => CollectionFieldKind::FileName,
+            "Desc" => CollectionFieldKind::Description,
+            "Size" => CollectionFieldKind::Size,
+            "ModDate" => CollectionFieldKind::ModificationDate,
+            "CreationDate" => CollectionFieldKind::CreationDate,
+            "CompressedSize" => CollectionFieldKind::CompressedSize,
+            _ => return None,
+        })
+    }
+}
+
+/// A column of a portfolio
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionField {
+    /// Key of the field in the schema and the files' collection values
+    pub key: String,
+    /// Column heading
+    pub name: String,
+    pub kind: CollectionFieldKind,
+    /// Position of the column relative to the others
+    pub order: Option<i64>,
+    pub visible: bool,
+    /// Whether the user may edit the values
+    pub editable: bool,
+}
+
+impl CollectionField {
+    /// Create a visible, read-only field
+    pub fn new(key: impl Into<String>, name: impl Into<String>, kind: CollectionFieldKind) -> Self {
+        Self {
+            key: key.into(),
+            name: name.into(),
+            kind,
+            order: None,
+            visible: true,
+            editable: false,
+        }
+    }
+
+    /// Set the column position
+    pub fn with_order(mut self, order: i64) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Set whether the column is shown
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Set whether the values can be edited
+    pub fn with_editable(mut self, editable: bool) -> Self {
+        self.editable = editable;
+        self
+    }
+}
+
+/// A portfolio: presentation of the attached files as the document's
+/// primary content
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Collection {
+    pub view: CollectionView,
+    /// Columns of the file list
+    pub fields: Vec<CollectionField>,
+    /// File shown when the portfolio is opened
+    pub initial_file: Option<String>,
+    /// Field keys the files are sorted by, each ascending or not
+    pub sort: Vec<(String, bool)>,
+}
+
+impl Collection {
+    /// Create a portfolio with the details view
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the initial view
+    pub fn with_view(mut self, view: CollectionView) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Add a column
+    pub fn with_field(mut self, field: CollectionField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Set the file shown when the portfolio is opened
+    pub fn with_initial_file(mut self, file_name: impl Into<String>) -> Self {
+        self.initial_file = Some(file_name.into());
+        self
+    }
+
+    /// Sort the files by a field, after any previous sort keys
+    pub fn with_sort(mut self, key: impl Into<String>, ascending: bool) -> Self {
+        self.sort.push((key.into(), ascending));
+        self
+    }
+
+    /// The `Collection` dictionary of the catalog
+    pub(crate) fn to_dict(&self) -> PdfDictionary {
+        let mut dict = PdfDictionary::new();
+        dict.insert("Type".to_string(), name("Collection"));
+        dict.insert("View".to_string(), name(self.view.pdf_name()));
+        if !self.fields.is_empty() {
+            let mut schema = PdfDictionary::new();
+            schema.insert("Type".to_string(), name("CollectionSchema"));
+            for field in &self.fields {
+                let mut entry = PdfDictionary::new();
+                entry.insert("Type".to_string(), name("CollectionField"));
+                entry.insert("Subtype".to_string(), name(field.kind.pdf_name()));
+                entry.insert("N".to_string(), text(&field.name));
+                if let Some(order) = field.order {
+                    entry.insert("O".to_string(), PdfObject::Integer(order));
+                }
+                entry.insert("V".to_string(), PdfObject::Boolean(field.visible));
+                entry.insert("E".to_string(), PdfObject::Boolean(field.editable));
+                schema.insert(field.key.clone(), PdfObject::Dictionary(entry));
+            }
+            dict.insert("Schema".to_string(), PdfObject::Dictionary(schema));
+        }
+        if let Some(initial) = &self.initial_file {
+            dict.insert("D".to_string(), text(initial));
+        }
+        if !self.sort.is_empty() {
+            let mut sort = PdfDictionary::new();
+            sort.insert("Type".to_string(), name("CollectionSort"));
+            sort.insert(
+                "S".to_string(),
+                PdfObject::Array(PdfArray(self.sort.iter().map(|(k, _)| name(k)).collect())),
+            );
+            sort.insert(
+                "A".to_string(),
+                PdfObject::Array(PdfArray(
+                    self.sort
+                        .iter()
+                        .map(|&(_, ascending)| PdfObject::Boolean(ascending))
+                        .collect(),
+                )),
+            );
+            dict.insert("Sort".to_string(), PdfObject::Dictionary(sort));
+        }
+        dict
+    }
+}
+
+/// A file attached to a parsed document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    /// Key of the file in the `EmbeddedFiles` tree
+    pub name: String,
+    pub file: EmbeddedFile,
+    /// Size recorded in the file's parameters
+    pub size: Option<u64>,
+    /// MD5 digest recorded in the file's parameters
+    pub checksum: Option<[u8; 16]>,
+}
+
+impl Attachment {
+    /// Whether the content matches the recorded checksum, `None` if there
+    /// is none
+    pub fn checksum_matches(&self) -> Option<bool> {
+        self.checksum
+            .map(|checksum| checksum == self.file.checksum())
+    }
+}
+
+/// The leaf of an `EmbeddedFiles` name tree, keys sorted as required
+pub(crate) fn name_tree(files: &[(&str, ObjectId)]) -> PdfDictionary {
+    let mut entries: Vec<(Vec<u8>, ObjectId)> = files
+        .iter()
+        .map(|&(key, id)| (text_bytes(key), id))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut names = Vec::new();
+    for (key, id) in entries {
+        names.push(PdfObject::String(PdfString::new(key)));
+        names.push(PdfObject::Reference(id.number(), id.generation()));
+    }
+    let mut dict = PdfDictionary::new();
+    dict.insert("Names".to_string(), PdfObject::Array(PdfArray(names)));
+    dict
+}
+
+/// The files of a document's `EmbeddedFiles` tree, in key order
+pub(crate) fn read_attachments<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> Result<Vec<Attachment>> {
+    let resolver: &dyn Resolver = document;
+    let catalog = document.catalog()?;
+    let Some(tree) = get_dict(resolver, &catalog, "Names")
+        .and_then(|names| get_dict(resolver, &names, "EmbeddedFiles"))
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut attachments = Vec::new();
+    for (key, spec) in read_name_tree(resolver, &tree) {
+        let PdfObject::Dictionary(spec) = resolver.lookup(&spec) else {
+            continue;
+        };
+        if let Some(attachment) = read_filespec(resolver, key, &spec)? {
+            attachments.push(attachment);
+        }
+    }
+    Ok(attachments)
+}
+
+/// The portfolio settings of a document, if it is one
+pub(crate) fn read_collection<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> Result<Option<Collection>> {
+    let resolver: &dyn Resolver = document;
+    let catalog = document.catalog()?;
+    let Some(dict) = get_dict(resolver, &catalog, "Collection") else {
+        return Ok(None);
+    };
+
+    let view = match get_name(resolver, &dict, "View").as_deref() {
+        Some("T") => CollectionView::Tile,
+        Some("H") => CollectionView::Hidden,
+        _ => CollectionView::Details,
+    };
+    let mut fields = Vec::new();
+    if let Some(schema) = get_dict(resolver, &dict, "Schema") {
+        for (key, entry) in &schema.0 {
+            let PdfObject::Dictionary(entry) = resolver.lookup(entry) else {
+                continue;
+            };
+            let Some(kind) = get_name(resolver, &entry, "Subtype")
+                .as_deref()
+                .and_then(CollectionFieldKind::from_name)
+            else {
+                continue;
+            };
+            fields.push(CollectionField {
+                key: key.as_str().to_string(),
+                name: get_text(resolver, &entry, "N").unwrap_or_default(),
+                kind,
+                order: get(resolver, &entry, "O").and_then(|o| o.as_integer()),
+                visible: get(resolver, &entry, "V")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true),
+                editable: get(resolver, &entry, "E")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false),
+            });
+        }
+        fields.sort_by_key(|field| field.order);
+    }
+
+    let mut sort = Vec::new();
+    if let Some(sort_dict) = get_dict(resolver, &dict, "Sort") {
+        let keys: Vec<String> = match get(resolver, &sort_dict, "S") {
+            Some(PdfObject::Name(key)) => vec![key.as_str().to_string()],
+            Some(PdfObject::Array(keys)) => keys
+                .0
+                .iter()
+                .filter_map(|key| key.as_name().map(|n| n.as_str().to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let ascending: Vec<bool> = match get(resolver, &sort_dict, "A") {
+            Some(PdfObject::Boolean(a)) => vec![a],
+            Some(PdfObject::Array(values)) => values
+                .0
+                .iter()
+                .filter_map(|value| value.as_bool())
+                .collect(),
+            _ => Vec::new(),
+        };
+        sort = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| (key, ascending.get(i).copied().unwrap_or(true)))
+            .collect();
+    }
+
+    Ok(Some(Collection {
+        view,
+        fields,
+        initial_file: get_text(resolver, &dict, "D"),
+        sort,
+    }))
+}
+
+/// An attachment from its file specification, `None` if nothing is embedded
+fn read_filespec(
+    resolver: &dyn Resolver,
+    key: String,
+    spec: &PdfDictionary,
+) -> Result<Option<Attachment>> {
+    let Some(embedded) = get_dict(resolver, spec, "EF") else {
+        return Ok(None);
+    };
+    let stream = ["UF", "F"]
+        .into_iter()
+        .find_map(|key| match get(resolver, &embedded, key) {
+            Some(PdfObject::Stream(stream)) => Some(stream),
+            _ => None,
+        });
+    let Some(stream) = stream else {
+        return Ok(None);
+    };
+    let data = stream.decode(&resolver.parse_options())?;
+
+    let params = get_dict(resolver, &stream.dict, "Params").unwrap_or_default();
+    let date = |key: &str| get_text(resolver, &params, key).and_then(|d| parse_pdf_date(&d));
+    let checksum = match get(resolver, &params, "CheckSum") {
+        Some(PdfObject::String(sum)) => sum.as_bytes().try_into().ok(),
+        _ => None,
+    };
+    let mut collection_values: Vec<(String, CollectionValue)> = get_dict(resolver, spec, "CI")
+        .map(|item| {
+            item.0
+                .iter()
+                .filter(|(key, _)| key.as_str() != "Type")
+                .filter_map(|(key, value)| {
+                    let value = match resolver.lookup(value) {
+                        PdfObject::String(s) => {
+                            let s = text_string(s.as_bytes());
+                            match s.starts_with("D:").then(|| parse_pdf_date(&s)).flatten() {
+                                Some(date) => CollectionValue::Date(date),
+                                None => CollectionValue::Text(s),
+                            }
+                        }
+                        PdfObject::Integer(i) => CollectionValue::Number(i as f64),
+                        PdfObject::Real(r) => CollectionValue::Number(r),
+                        _ => return None,
+                    };
+                    Some((key.as_str().to_string(), value))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    collection_values.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let file_name = get_text(resolver, spec, "UF")
+        .or_else(|| get_text(resolver, spec, "F"))
+        .unwrap_or_else(|| key.clone());
+    Ok(Some(Attachment {
+        name: key,
+        file: EmbeddedFile {
+            file_name,
+            data,
+            mime_type: get_name(resolver, &stream.dict, "Subtype"),
+            description: get_text(resolver, spec, "Desc"),
+            creation_date: date("CreationDate"),
+            modification_date: date("ModDate"),
+            relationship: get_name(resolver, spec, "AFRelationship")
+                .map(|name| AFRelationship::from_name(&name)),
+            collection_values,
+        },
+        size: get(resolver, &params, "Size")
+            .and_then(|size| size.as_integer())
+            .and_then(|size| u64::try_from(size).ok()),
+        checksum,
+    }))
+}
+
+fn get_text(resolver: &dyn Resolver, dict: &PdfDictionary, key: &str) -> Option<String> {
+    match get(resolver, dict, key)? {
+        PdfObject::String(s) => Some(text_string(s.as_bytes())),
+        _ => None,
+    }
+}
+
+fn name(name: &str) -> PdfObject {
+    PdfObject::Name(PdfName::new(name.to_string()))
+}
+
+fn text(text: &str) -> PdfObject {
+    PdfObject::String(PdfString::new(text_bytes(text)))
+}
+
+fn date_string(date: DateTime<Utc>) -> PdfObject {
+    PdfObject::String(PdfString::new(format_pdf_date(date).into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PdfReader;
+    use crate::{Document, Page};
+    use chrono::TimeZone;
+    use std::io::Cursor;
+
+    fn reopen(doc: &mut Document) -> PdfDocument<Cursor<Vec<u8>>> {
+        let pdf = doc.to_bytes().unwrap();
+        PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap())
+    }
+
+    #[test]
+    fn test_attachments_round_trip() {
+        let created = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap();
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        doc.attach_file(
+            EmbeddedFile::new("revenue.csv", b"quarter,revenue\nQ1,120\n".to_vec())
+                .with_mime_type("text/csv")
+                .with_description("Données source")
+                .with_creation_date(created)
+                .with_relationship(AFRelationship::Source),
+        );
+        doc.attach_file(EmbeddedFile::new("añexo.txt", b"notes".to_vec()));
+
+        let document = reopen(&mut doc);
+        let attachments = document.attachments().unwrap();
+        assert_eq!(attachments.len(), 2);
+
+        let csv = &attachments[0];
+        assert_eq!(csv.name, "revenue.csv");
+        assert_eq!(csv.file.data, b"quarter,revenue\nQ1,120\n");
+        assert_eq!(csv.file.mime_type.as_deref(), Some("text/csv"));
+        assert_eq!(csv.file.description.as_deref(), Some("Données source"));
+        assert_eq!(csv.file.creation_date, Some(created));
+        assert_eq!(csv.file.relationship, Some(AFRelationship::Source));
+        assert_eq!(csv.size, Some(23));
+        assert_eq!(csv.checksum_matches(), Some(true));
+
+        let notes = &attachments[1];
+        assert_eq!(notes.file.file_name, "añexo.txt");
+        assert_eq!(notes.file.relationship, None);
+
+        // Only files with a relationship are associated with the document
+        let catalog = document.catalog().unwrap();
+        let associated = catalog.get("AF").unwrap().as_array().unwrap();
+        assert_eq!(associated.0.len(), 1);
+    }
+
+    #[test]
+    fn test_portfolio_round_trip() {
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        doc.attach_file(
+            EmbeddedFile::new("b.pdf", b"%PDF-1.7".to_vec())
+                .with_collection_value("region", CollectionValue::Text("North".into()))
+                .with_collection_value("total", CollectionValue::Number(2.5)),
+        );
+        doc.attach_file(EmbeddedFile::new("a.pdf", b"%PDF-1.7".to_vec()));
+        let collection = Collection::new()
+            .with_view(CollectionView::Tile)
+            .with_field(
+                CollectionField::new("file", "File", CollectionFieldKind::FileName).with_order(1),
+            )
+            .with_field(
+                CollectionField::new("region", "Region", CollectionFieldKind::Text)
+                    .with_order(2)
+                    .with_editable(true),
+            )
+            .with_initial_file("a.pdf")
+            .with_sort("region", false);
+        doc.set_collection(collection.clone());
+
+        let document = reopen(&mut doc);
+        assert_eq!(document.collection().unwrap(), Some(collection));
+
+        let attachments = document.attachments().unwrap();
+        let names: Vec<_> = attachments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["a.pdf", "b.pdf"]);
+        assert_eq!(
+            attachments[1].file.collection_values,
+            vec![
+                ("region".to_string(), CollectionValue::Text("North".into())),
+                ("total".to_string(), CollectionValue::Number(2.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attachments_survive_reopening() {
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        doc.attach_file(EmbeddedFile::new("data.json", b"{}".to_vec()));
+        let pdf = doc.to_bytes().unwrap();
+
+        let mut opened = Document::from_reader(Cursor::new(pdf)).unwrap();
+        assert_eq!(opened.attachments().len(), 1);
+        opened.attach_file(EmbeddedFile::new("more.json", b"[]".to_vec()));
+        assert!(opened.remove_attachment("data.json").is_some());
+
+        let document = reopen(&mut opened);
+        let names: Vec<_> = document
+            .attachments()
+            .unwrap()
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, vec!["more.json"]);
+    }
+
+    #[test]
+    fn test_stream_params() {
+        let file = EmbeddedFile::new("a.txt", b"abc".to_vec()).with_mime_type("text/plain");
+        let stream = file.to_stream(false).unwrap();
+        assert_eq!(stream.data, b"abc");
+        let params = stream.dict.get("Params").unwrap().as_dict().unwrap();
+        assert_eq!(params.get("Size"), Some(&PdfObject::Integer(3)));
+        assert_eq!(
+            params
+                .get("CheckSum")
+                .unwrap()
+                .as_string()
+                .unwrap()
+                .as_bytes(),
+            &md5::compute(b"abc").0
+        );
+        assert!(stream.dict.get("Filter").is_none());
+    }
+}
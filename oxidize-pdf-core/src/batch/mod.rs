@@ -232,7 +232,7 @@ impl BatchProcessor {
 
         for result in job_results {
             match &result {
-                JobResult::Success { .. } => successful += 1,
+                JobResult::Success { .. } | JobResult::Compressed { .. } => successful += 1,
                 JobResult::Failed { .. } => failed += 1,
                 JobResult::Cancelled { .. } => {}
             }
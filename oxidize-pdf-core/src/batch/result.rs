@@ -14,6 +14,17 @@ pub enum JobResult {
         output_files: Vec<PathBuf>,
     },
 
+    /// Compression job completed successfully
+    Compressed {
+        job_name: String,
+        duration: Duration,
+        output_files: Vec<PathBuf>,
+        /// Size of the input file in bytes
+        original_size: u64,
+        /// Size of the compressed output in bytes
+        compressed_size: u64,
+    },
+
     /// Job failed with an error
     Failed {
         job_name: String,
@@ -28,7 +39,10 @@ pub enum JobResult {
 impl JobResult {
     /// Check if the job was successful
     pub fn is_success(&self) -> bool {
-        matches!(self, JobResult::Success { .. })
+        matches!(
+            self,
+            JobResult::Success { .. } | JobResult::Compressed { .. }
+        )
     }
 
     /// Check if the job failed
@@ -45,6 +59,7 @@ impl JobResult {
     pub fn job_name(&self) -> &str {
         match self {
             JobResult::Success { job_name, .. }
+            | JobResult::Compressed { job_name, .. }
             | JobResult::Failed { job_name, .. }
             | JobResult::Cancelled { job_name } => job_name,
         }
@@ -53,9 +68,9 @@ impl JobResult {
     /// Get the duration (if available)
     pub fn duration(&self) -> Option<Duration> {
         match self {
-            JobResult::Success { duration, .. } | JobResult::Failed { duration, .. } => {
-                Some(*duration)
-            }
+            JobResult::Success { duration, .. }
+            | JobResult::Compressed { duration, .. }
+            | JobResult::Failed { duration, .. } => Some(*duration),
             JobResult::Cancelled { .. } => None,
         }
     }
@@ -71,7 +86,20 @@ impl JobResult {
     /// Get output files (if successful)
     pub fn output_files(&self) -> Option<&[PathBuf]> {
         match self {
-            JobResult::Success { output_files, .. } => Some(output_files),
+            JobResult::Success { output_files, .. }
+            | JobResult::Compressed { output_files, .. } => Some(output_files),
+            _ => None,
+        }
+    }
+
+    /// Get the original and compressed sizes in bytes (if compressed)
+    pub fn compression_sizes(&self) -> Option<(u64, u64)> {
+        match self {
+            JobResult::Compressed {
+                original_size,
+                compressed_size,
+                ..
+            } => Some((*original_size, *compressed_size)),
             _ => None,
         }
     }
@@ -93,6 +121,22 @@ impl fmt::Display for JobResult {
                     output_files.len()
                 )
             }
+            JobResult::Compressed {
+                job_name,
+                duration,
+                original_size,
+                compressed_size,
+                ..
+            } => {
+                write!(
+                    f,
+                    "✓ {} - compressed in {:.2}s ({} -> {} bytes)",
+                    job_name,
+                    duration.as_secs_f64(),
+                    original_size,
+                    compressed_size
+                )
+            }
             JobResult::Failed {
                 job_name,
                 duration,
@@ -301,6 +345,22 @@ mod tests {
         assert_eq!(result.output_files().unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_job_result_compressed() {
+        let result = JobResult::Compressed {
+            job_name: "Compress".to_string(),
+            duration: Duration::from_secs(1),
+            output_files: vec![PathBuf::from("small.pdf")],
+            original_size: 2048,
+            compressed_size: 1024,
+        };
+
+        assert!(result.is_success());
+        assert_eq!(result.compression_sizes(), Some((2048, 1024)));
+        assert_eq!(result.output_files().unwrap().len(), 1);
+        assert!(result.to_string().contains("2048 -> 1024 bytes"));
+    }
+
     #[test]
     fn test_job_result_failed() {
         let result = JobResult::Failed {
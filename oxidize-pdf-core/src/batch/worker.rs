@@ -3,7 +3,7 @@
 use crate::batch::{BatchJob, BatchProgress, JobResult};
 use crate::error::PdfError;
 use crate::operations::page_extraction::extract_pages_to_file;
-use crate::operations::{merge_pdfs, split_pdf};
+use crate::operations::{merge_pdfs, optimize_pdf, split_pdf};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
@@ -152,16 +152,10 @@ impl WorkerPool {
                             let duration = start.elapsed();
 
                             match &result {
-                                Ok(output_files) => {
+                                Ok(output) => {
                                     progress_clone2.complete_job();
-                                    let _ = result_sender_clone2.send((
-                                        idx,
-                                        JobResult::Success {
-                                            job_name: job_name.clone(),
-                                            duration,
-                                            output_files: output_files.clone(),
-                                        },
-                                    ));
+                                    let _ = result_sender_clone2
+                                        .send((idx, output.to_result(job_name.clone(), duration)));
                                 }
                                 Err(e) => {
                                     progress_clone2.fail_job();
@@ -285,8 +279,44 @@ impl Worker {
     }
 }
 
+/// Output of a non-custom job
+enum JobOutput {
+    /// Files written by the job
+    Files(Vec<PathBuf>),
+    /// Compressed file along with its size before and after compression
+    Compressed {
+        output: PathBuf,
+        original_size: u64,
+        compressed_size: u64,
+    },
+}
+
+impl JobOutput {
+    /// Build the job result reported for this output
+    fn to_result(&self, job_name: String, duration: Duration) -> JobResult {
+        match self {
+            JobOutput::Files(output_files) => JobResult::Success {
+                job_name,
+                duration,
+                output_files: output_files.clone(),
+            },
+            JobOutput::Compressed {
+                output,
+                original_size,
+                compressed_size,
+            } => JobResult::Compressed {
+                job_name,
+                duration,
+                output_files: vec![output.clone()],
+                original_size: *original_size,
+                compressed_size: *compressed_size,
+            },
+        }
+    }
+}
+
 /// Execute a non-custom job
-fn execute_job(job: BatchJob) -> std::result::Result<Vec<PathBuf>, PdfError> {
+fn execute_job(job: BatchJob) -> std::result::Result<JobOutput, PdfError> {
     match job {
         BatchJob::Split {
             input,
@@ -299,12 +329,13 @@ fn execute_job(job: BatchJob) -> std::result::Result<Vec<PathBuf>, PdfError> {
                 output_pattern,
                 preserve_metadata: true,
                 optimize: false,
+                preserve_bookmarks: true,
             };
 
             split_pdf(&input, options).map_err(|e| PdfError::InvalidStructure(e.to_string()))?;
 
             // Return generated files (simplified - would need to track actual outputs)
-            Ok(vec![])
+            Ok(JobOutput::Files(vec![]))
         }
 
         BatchJob::Merge { inputs, output } => {
@@ -315,7 +346,7 @@ fn execute_job(job: BatchJob) -> std::result::Result<Vec<PathBuf>, PdfError> {
             let options = crate::operations::MergeOptions::default();
             merge_pdfs(merge_inputs, &output, options)
                 .map_err(|e| PdfError::InvalidStructure(e.to_string()))?;
-            Ok(vec![output])
+            Ok(JobOutput::Files(vec![output]))
         }
 
         BatchJob::Rotate {
@@ -326,7 +357,7 @@ fn execute_job(job: BatchJob) -> std::result::Result<Vec<PathBuf>, PdfError> {
         } => {
             // Rotate not implemented in current API, just copy
             std::fs::copy(&input, &output)?;
-            Ok(vec![output])
+            Ok(JobOutput::Files(vec![output]))
         }
 
         BatchJob::Extract {
@@ -336,17 +367,22 @@ fn execute_job(job: BatchJob) -> std::result::Result<Vec<PathBuf>, PdfError> {
         } => {
             extract_pages_to_file(&input, &pages, &output)
                 .map_err(|e| PdfError::InvalidStructure(e.to_string()))?;
-            Ok(vec![output])
+            Ok(JobOutput::Files(vec![output]))
         }
 
         BatchJob::Compress {
             input,
             output,
-            quality: _,
+            quality,
         } => {
-            // Compression not implemented yet, just copy
-            std::fs::copy(&input, &output)?;
-            Ok(vec![output])
+            let options = crate::operations::OptimizeOptions::from_quality(quality);
+            let report = optimize_pdf(&input, &output, options)
+                .map_err(|e| PdfError::InvalidStructure(e.to_string()))?;
+            Ok(JobOutput::Compressed {
+                output,
+                original_size: report.original_size,
+                compressed_size: report.optimized_size,
+            })
         }
 
         BatchJob::Custom { .. } => {
@@ -1,4 +1,4 @@
-use crate::error::Result;
+use crate::error::{PdfError, Result};
 use crate::fonts::{Font as CustomFont, FontCache};
 use crate::forms::{AcroForm, FormManager};
 use crate::objects::{Object, ObjectId};
@@ -6,14 +6,16 @@ use crate::page::Page;
 use crate::page_labels::PageLabelTree;
 use crate::semantic::{BoundingBox, EntityType, RelationType, SemanticEntity};
 use crate::structure::{NamedDestinations, OutlineTree, PageTree, StructTree};
-use crate::text::{FontEncoding, FontWithEncoding};
+use crate::text::{FontEncoding, FontWithEncoding, ShapingFonts};
 use crate::writer::PdfWriter;
 use chrono::{DateTime, Local, Utc};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 mod encryption;
+mod import;
 pub use encryption::{DocumentEncryption, EncryptionStrength};
+pub(crate) use import::{ImportedCatalog, ImportedObjects, ImportedPage};
 
 /// A PDF document that can contain multiple pages and metadata.
 ///
@@ -54,8 +56,17 @@ pub struct Document {
     pub(crate) compress: bool,
     /// Whether to use compressed cross-reference streams (PDF 1.5+)
     pub(crate) use_xref_streams: bool,
+    /// PDF/A level to write the document in
+    pub(crate) pdfa: Option<crate::verification::pdfa::PdfALevel>,
+    /// Whether to write the document as PDF/UA-1
+    pub(crate) pdfua: bool,
+    /// Whether to write a linearized file
+    pub(crate) linearize: bool,
     /// Cache for custom fonts
     pub(crate) custom_fonts: FontCache,
+    /// Shaping data of the custom fonts, with the private use CIDs handed
+    /// out for their glyphs
+    pub(crate) shaping_fonts: ShapingFonts,
     /// Map from font name to embedded font object ID
     #[allow(dead_code)]
     pub(crate) embedded_fonts: HashMap<String, ObjectId>,
@@ -65,10 +76,22 @@ pub struct Document {
     pub(crate) open_action: Option<crate::actions::Action>,
     /// Viewer preferences for controlling document display
     pub(crate) viewer_preferences: Option<crate::viewer_preferences::ViewerPreferences>,
+    /// Natural language of the document's text (`/Lang` of the catalog)
+    pub(crate) language: Option<String>,
     /// Semantic entities marked in the document for AI processing
     pub(crate) semantic_entities: Vec<SemanticEntity>,
     /// Document structure tree for Tagged PDF (accessibility)
     pub(crate) struct_tree: Option<StructTree>,
+    /// Catalog entries of the PDF this document was opened from
+    pub(crate) imported_catalog: Option<ImportedCatalog>,
+    /// Layers (optional content groups)
+    pub(crate) optional_content: crate::optional_content::OptionalContent,
+    /// Files in the `EmbeddedFiles` name tree
+    pub(crate) attachments: Vec<crate::attachments::EmbeddedFile>,
+    /// Portfolio settings
+    pub(crate) collection: Option<crate::attachments::Collection>,
+    /// Embedded e-invoice making this a PDF/A-3 hybrid invoice
+    pub(crate) facturx: Option<crate::text::invoice::FacturX>,
 }
 
 /// Metadata for a PDF document.
@@ -140,18 +163,32 @@ impl Document {
             form_manager: None,
             compress: true,          // Enable compression by default
             use_xref_streams: false, // Disabled by default for compatibility
+            pdfa: None,
+            pdfua: false,
+            linearize: false,
             custom_fonts: FontCache::new(),
+            shaping_fonts: ShapingFonts::new(),
             embedded_fonts: HashMap::new(),
             used_characters: HashSet::new(),
             open_action: None,
             viewer_preferences: None,
+            language: None,
             semantic_entities: Vec::new(),
             struct_tree: None,
+            imported_catalog: None,
+            optional_content: Default::default(),
+            attachments: Vec::new(),
+            collection: None,
+            facturx: None,
         }
     }
 
     /// Adds a page to the document.
-    pub fn add_page(&mut self, page: Page) {
+    ///
+    /// Text in custom fonts on the page is shaped with the fonts of the
+    /// document, including text written before the page was added.
+    pub fn add_page(&mut self, mut page: Page) {
+        page.set_shaping_fonts(&self.shaping_fonts);
         // Collect used characters from the page
         if let Some(used_chars) = page.get_used_characters() {
             self.used_characters.extend(used_chars);
@@ -159,6 +196,45 @@ impl Document {
         self.pages.push(page);
     }
 
+    /// Inserts a page at `index`, shifting later pages back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `index` is greater than the page count.
+    pub fn insert_page(&mut self, index: usize, mut page: Page) -> Result<()> {
+        if index > self.pages.len() {
+            return Err(PdfError::InvalidPageNumber(index as u32));
+        }
+        page.set_shaping_fonts(&self.shaping_fonts);
+        if let Some(used_chars) = page.get_used_characters() {
+            self.used_characters.extend(used_chars);
+        }
+        self.pages.insert(index, page);
+        Ok(())
+    }
+
+    /// Removes and returns the page at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no page at `index`.
+    pub fn remove_page(&mut self, index: usize) -> Result<Page> {
+        if index >= self.pages.len() {
+            return Err(PdfError::InvalidPageNumber(index as u32));
+        }
+        Ok(self.pages.remove(index))
+    }
+
+    /// Gets the page at `index`.
+    pub fn page(&self, index: usize) -> Option<&Page> {
+        self.pages.get(index)
+    }
+
+    /// Gets a mutable reference to the page at `index`.
+    pub fn page_mut(&mut self, index: usize) -> Option<&mut Page> {
+        self.pages.get_mut(index)
+    }
+
     /// Sets the document title.
     pub fn set_title(&mut self, title: impl Into<String>) {
         self.metadata.title = Some(title.into());
@@ -229,6 +305,88 @@ impl Document {
         self.viewer_preferences.as_ref()
     }
 
+    /// Add a layer, returning the id to draw its content with
+    ///
+    /// See [`crate::optional_content`].
+    pub fn add_layer(
+        &mut self,
+        layer: crate::optional_content::Layer,
+    ) -> crate::optional_content::LayerId {
+        self.optional_content.add_layer(layer)
+    }
+
+    /// Add content visible depending on several layers, returning the id to
+    /// draw it with
+    pub fn add_layer_membership(
+        &mut self,
+        membership: crate::optional_content::LayerMembership,
+    ) -> crate::optional_content::LayerId {
+        self.optional_content.add_membership(membership)
+    }
+
+    /// Get the layers and their configurations
+    pub fn optional_content(&self) -> &crate::optional_content::OptionalContent {
+        &self.optional_content
+    }
+
+    /// Get a mutable reference to the layers and their configurations
+    pub fn optional_content_mut(&mut self) -> &mut crate::optional_content::OptionalContent {
+        &mut self.optional_content
+    }
+
+    /// Attach a file to the document, replacing any attachment with the same
+    /// file name
+    ///
+    /// See [`crate::attachments`].
+    pub fn attach_file(&mut self, file: crate::attachments::EmbeddedFile) {
+        self.attachments
+            .retain(|existing| existing.file_name != file.file_name);
+        self.attachments.push(file);
+    }
+
+    /// Get the attached files
+    pub fn attachments(&self) -> &[crate::attachments::EmbeddedFile] {
+        &self.attachments
+    }
+
+    /// Remove an attached file by file name
+    pub fn remove_attachment(
+        &mut self,
+        file_name: &str,
+    ) -> Option<crate::attachments::EmbeddedFile> {
+        let index = self
+            .attachments
+            .iter()
+            .position(|file| file.file_name == file_name)?;
+        Some(self.attachments.remove(index))
+    }
+
+    /// Make the document a portfolio presenting its attached files
+    pub fn set_collection(&mut self, collection: crate::attachments::Collection) {
+        self.collection = Some(collection);
+    }
+
+    /// Get the portfolio settings
+    pub fn collection(&self) -> Option<&crate::attachments::Collection> {
+        self.collection.as_ref()
+    }
+
+    /// Embed a Factur-X / ZUGFeRD invoice XML, identifying the document as
+    /// a PDF/A-3 hybrid invoice
+    ///
+    /// The XML is attached as an associated file and announced in the XMP
+    /// metadata, and the document is written as PDF/A-3b whatever the
+    /// writer configuration. See [`crate::text::invoice::facturx`].
+    pub fn set_facturx(&mut self, invoice: crate::text::invoice::FacturX) {
+        self.attach_file(invoice.embedded_file());
+        self.facturx = Some(invoice);
+    }
+
+    /// Get the embedded e-invoice
+    pub fn facturx(&self) -> Option<&crate::text::invoice::FacturX> {
+        self.facturx.as_ref()
+    }
+
     /// Set the document structure tree for Tagged PDF (accessibility)
     ///
     /// Tagged PDF provides semantic information about document content,
@@ -288,6 +446,44 @@ impl Document {
         self.struct_tree.get_or_insert_with(StructTree::new)
     }
 
+    /// Structure tree to write: the tree set on the document, with the
+    /// elements of tagged pages added below its root
+    pub(crate) fn tagged_structure(&self) -> Result<Option<StructTree>> {
+        let mut tagged_pages = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| !page.tags().is_empty())
+            .peekable();
+        if tagged_pages.peek().is_none() {
+            return Ok(self.struct_tree.clone().filter(|tree| !tree.is_empty()));
+        }
+
+        let mut tree = self.struct_tree.clone().unwrap_or_default();
+        let root = match tree.root_index() {
+            Some(root) => root,
+            None => tree.set_root(crate::structure::StructureElement::new(
+                crate::structure::StandardStructureType::Document,
+            )),
+        };
+        for (index, page) in tagged_pages {
+            page.tags().graft(&mut tree, root, index)?;
+        }
+        Ok(Some(tree))
+    }
+
+    /// Sets the natural language of the document, such as "en-US"
+    ///
+    /// Screen readers use it to pronounce the text; PDF/UA requires it.
+    pub fn set_language(&mut self, language: impl Into<String>) {
+        self.language = Some(language.into());
+    }
+
+    /// Gets the natural language of the document
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
     /// Set document outline (bookmarks)
     pub fn set_outline(&mut self, outline: OutlineTree) {
         self.outline = Some(outline);
@@ -449,6 +645,7 @@ impl Document {
     ) -> Result<()> {
         let name = name.into();
         let font = CustomFont::from_file(&name, path)?;
+        self.register_shaping_font(&name, &font.data);
         self.custom_fonts.add_font(name, font)?;
         Ok(())
     }
@@ -467,14 +664,29 @@ impl Document {
     pub fn add_font_from_bytes(&mut self, name: impl Into<String>, data: Vec<u8>) -> Result<()> {
         let name = name.into();
         let font = CustomFont::from_bytes(&name, data)?;
-
-        // TODO: Implement automatic font metrics registration
-        // This needs to be properly integrated with the font metrics system
-
+        self.register_shaping_font(&name, &font.data);
         self.custom_fonts.add_font(name, font)?;
         Ok(())
     }
 
+    /// Shaping data of the fonts added to the document
+    ///
+    /// Give them to a page with
+    /// [`Page::set_shaping_fonts`](crate::Page::set_shaping_fonts) to break
+    /// the lines of text flows in custom fonts with the shaped widths before
+    /// the page is added.
+    pub fn shaping_fonts(&self) -> &ShapingFonts {
+        &self.shaping_fonts
+    }
+
+    /// Shape text set in the font; fonts the shaper cannot read are shown
+    /// character by character
+    fn register_shaping_font(&self, name: &str, data: &[u8]) {
+        if let Err(e) = self.shaping_fonts.register(name, data) {
+            tracing::debug!("Text in font {name} will not be shaped: {e}");
+        }
+    }
+
     /// Get a custom font by name
     #[allow(dead_code)]
     pub(crate) fn get_custom_font(&self, name: &str) -> Option<Arc<CustomFont>> {
@@ -690,6 +902,59 @@ impl Document {
         self
     }
 
+    /// Writes the document as a PDF/A file of the given level (ISO 19005).
+    ///
+    /// Fonts are embedded and subset, an sRGB output intent is added and
+    /// documents using features the level forbids fail to write. Check the
+    /// result with [`crate::verification::pdfa::check_pdfa`].
+    ///
+    /// The standard 14 fonts ([`crate::Font::Helvetica`] and the like)
+    /// cannot be embedded, so documents drawing text with them fail to
+    /// write. Load a font with [`Document::add_font`] or
+    /// [`Document::add_font_from_bytes`] and draw with
+    /// [`crate::Font::Custom`] instead; Liberation Sans, Serif and Mono
+    /// have the metrics of Helvetica, Times and Courier.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use oxidize_pdf::verification::pdfa::PdfALevel;
+    /// use oxidize_pdf::Document;
+    ///
+    /// let mut doc = Document::new();
+    /// doc.set_pdfa(Some(PdfALevel::A2b));
+    /// ```
+    pub fn set_pdfa(&mut self, level: Option<crate::verification::pdfa::PdfALevel>) -> &mut Self {
+        self.pdfa = level;
+        self
+    }
+
+    /// Writes the document as an accessible PDF/UA-1 file (ISO 14289-1).
+    ///
+    /// The content of tagged pages (see [`crate::Page::set_tagged`]) forms
+    /// the structure tree, the catalog declares the document language and
+    /// asks viewers to show the title, and the XMP metadata identifies the
+    /// file as PDF/UA. Documents without a title, a language or tagged
+    /// content fail to write. Check the result with
+    /// [`crate::verification::pdfua::check_pdfua`].
+    pub fn enable_pdfua(&mut self, enable: bool) -> &mut Self {
+        self.pdfua = enable;
+        self
+    }
+
+    /// Writes the document as a linearized ("fast web view") file
+    /// (ISO 32000-1 Annex F).
+    ///
+    /// The first page and everything needed to show it come first in the
+    /// file, followed by the other pages in order, so viewers fetching the
+    /// file with HTTP range requests can show the first page before the
+    /// whole file has arrived and jump to later pages using the hint tables.
+    /// Cross-reference streams and object streams are not used in this mode.
+    pub fn enable_linearization(&mut self, enable: bool) -> &mut Self {
+        self.linearize = enable;
+        self
+    }
+
     /// Gets the current compression setting.
     ///
     /// # Returns
@@ -1037,18 +1302,27 @@ impl Document {
     pub fn create_xmp_metadata(&self) -> crate::metadata::XmpMetadata {
         let mut xmp = crate::metadata::XmpMetadata::new();
 
-        // Add Dublin Core metadata
+        // Add Dublin Core metadata, with the value types XMP defines for
+        // them (PDF/A requires them to match the Info dictionary)
         if let Some(title) = &self.metadata.title {
-            xmp.set_text(crate::metadata::XmpNamespace::DublinCore, "title", title);
+            xmp.set_alt(
+                crate::metadata::XmpNamespace::DublinCore,
+                "title",
+                vec![("x-default".to_string(), title.clone())],
+            );
         }
         if let Some(author) = &self.metadata.author {
-            xmp.set_text(crate::metadata::XmpNamespace::DublinCore, "creator", author);
+            xmp.set_array(
+                crate::metadata::XmpNamespace::DublinCore,
+                "creator",
+                vec![author.clone()],
+            );
         }
         if let Some(subject) = &self.metadata.subject {
-            xmp.set_text(
+            xmp.set_alt(
                 crate::metadata::XmpNamespace::DublinCore,
                 "description",
-                subject,
+                vec![("x-default".to_string(), subject.clone())],
             );
         }
 
@@ -1064,22 +1338,30 @@ impl Document {
             xmp.set_date(
                 crate::metadata::XmpNamespace::XmpBasic,
                 "CreateDate",
-                creation_date.to_rfc3339(),
+                creation_date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
             );
         }
         if let Some(mod_date) = &self.metadata.modification_date {
             xmp.set_date(
                 crate::metadata::XmpNamespace::XmpBasic,
                 "ModifyDate",
-                mod_date.to_rfc3339(),
+                mod_date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
             );
         }
 
         // Add PDF specific metadata
+        if let Some(keywords) = &self.metadata.keywords {
+            xmp.set_text(crate::metadata::XmpNamespace::Pdf, "Keywords", keywords);
+        }
         if let Some(producer) = &self.metadata.producer {
             xmp.set_text(crate::metadata::XmpNamespace::Pdf, "Producer", producer);
         }
 
+        // PDF/A-3 identification and Factur-X properties
+        if let Some(invoice) = &self.facturx {
+            invoice.add_xmp(&mut xmp);
+        }
+
         xmp
     }
 
@@ -0,0 +1,522 @@
+//! Opening existing PDF files as editable documents
+
+use super::{Document, DocumentMetadata};
+use crate::error::Result;
+use crate::page::Page;
+use crate::parser::objects::{PdfArray, PdfDictionary, PdfObject};
+use crate::parser::strings::{parse_pdf_date, text_string};
+use crate::parser::{PdfDocument, PdfReader};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Catalog entries carried over from an opened document
+const CATALOG_ENTRIES: &[&str] = &[
+    "AcroForm",
+    "Dests",
+    "Lang",
+    "MarkInfo",
+    "Names",
+    "OCProperties",
+    "OpenAction",
+    "Outlines",
+    "PageLabels",
+    "PageLayout",
+    "PageMode",
+    "StructTreeRoot",
+    "ViewerPreferences",
+];
+
+/// Page entries the writer generates itself
+const GENERATED_PAGE_ENTRIES: &[&str] = &[
+    "Type",
+    "Parent",
+    "Contents",
+    "Resources",
+    "Rotate",
+    "MediaBox",
+    "CropBox",
+];
+
+/// Objects of an opened document, keyed by their original reference
+///
+/// Only objects reachable from the imported pages and catalog entries are
+/// kept. Page objects and page tree nodes are not copied; references to them
+/// are remapped when the document is written.
+#[derive(Debug, Default)]
+pub(crate) struct ImportedObjects {
+    pub(crate) objects: HashMap<(u32, u16), PdfObject>,
+    /// Page objects of the opened document
+    pub(crate) pages: HashSet<(u32, u16)>,
+    /// Intermediate nodes of its page tree
+    pub(crate) page_tree_nodes: HashSet<(u32, u16)>,
+}
+
+/// The original content and dictionary entries of an opened page
+#[derive(Debug, Clone)]
+pub(crate) struct ImportedPage {
+    pub(crate) objects: Arc<ImportedObjects>,
+    /// Reference of the page object in the opened document
+    pub(crate) page_ref: (u32, u16),
+    /// Decoded content of all the page's content streams
+    pub(crate) content: Vec<u8>,
+    /// Resources, including inherited ones
+    pub(crate) resources: Option<PdfObject>,
+    /// Remaining page entries, such as `/Annots`, with inherited boxes
+    pub(crate) entries: PdfDictionary,
+}
+
+/// Catalog entries of an opened document
+#[derive(Debug, Clone)]
+pub(crate) struct ImportedCatalog {
+    pub(crate) objects: Arc<ImportedObjects>,
+    pub(crate) entries: PdfDictionary,
+}
+
+impl Document {
+    /// Opens an existing PDF file as an editable document.
+    ///
+    /// See [`Document::from_reader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Reads an existing PDF as an editable document.
+    ///
+    /// Every page keeps its content, resources and annotations; new content
+    /// drawn on it is painted on top. The outline, named destinations, page
+    /// labels, interactive form and other catalog entries are carried over
+    /// unless replaced through the corresponding setters, and the document
+    /// information becomes the document's metadata. Attached files become
+    /// the document's attachments. Pages can then be added,
+    /// removed or reordered before saving; links to removed pages become null.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use oxidize_pdf::{Document, Font};
+    ///
+    /// let mut doc = Document::open("input.pdf")?;
+    /// doc.remove_page(0)?;
+    /// if let Some(page) = doc.page_mut(0) {
+    ///     page.text()
+    ///         .set_font(Font::Helvetica, 12.0)
+    ///         .at(72.0, 72.0)
+    ///         .write("Reviewed")?;
+    /// }
+    /// doc.save("output.pdf")?;
+    /// # Ok::<(), oxidize_pdf::PdfError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDF cannot be parsed or is encrypted with a
+    /// password other than the empty one.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self> {
+        let document = PdfDocument::new(PdfReader::new(reader)?);
+        let mut objects = ImportedObjects::default();
+        let mut roots = Vec::new();
+
+        let mut pages = Vec::new();
+        for index in 0..document.page_count()? {
+            let parsed = document.get_page(index)?;
+            let content = document.get_page_content_streams(&parsed)?.join(&b'\n');
+            let resources = match parsed.dict.get("Resources") {
+                Some(resources) => Some(resources.clone()),
+                None => match parsed.inherited_resources.clone() {
+                    Some(resources) => Some(PdfObject::Dictionary(resources)),
+                    None => inherited_resources(&document, &parsed.dict),
+                },
+            };
+
+            let mut entries = PdfDictionary::new();
+            for (key, value) in &parsed.dict.0 {
+                if !GENERATED_PAGE_ENTRIES.contains(&key.as_str()) {
+                    entries.insert(key.as_str().to_string(), value.clone());
+                }
+            }
+            entries.insert("MediaBox".to_string(), rectangle(parsed.media_box));
+            if let Some(crop_box) = parsed.crop_box {
+                entries.insert("CropBox".to_string(), rectangle(crop_box));
+            }
+
+            roots.extend(resources.iter().cloned());
+            roots.extend(entries.0.values().cloned());
+            objects.pages.insert(parsed.obj_ref);
+            pages.push((
+                Page::from_parsed(&parsed)?,
+                parsed.obj_ref,
+                content,
+                resources,
+                entries,
+            ));
+        }
+
+        let catalog = document.catalog()?;
+        let mut catalog_entries = PdfDictionary::new();
+        for &key in CATALOG_ENTRIES {
+            if let Some(value) = catalog.get(key) {
+                catalog_entries.insert(key.to_string(), value.clone());
+                roots.push(value.clone());
+            }
+        }
+
+        collect_objects(&document, &mut objects, roots);
+        let objects = Arc::new(objects);
+
+        let mut doc = Document::new();
+        doc.metadata = read_metadata(&document)?;
+        doc.attachments = document
+            .attachments()?
+            .into_iter()
+            .map(|attachment| attachment.file)
+            .collect();
+        doc.collection = document.collection()?;
+        if !catalog_entries.0.is_empty() {
+            doc.imported_catalog = Some(ImportedCatalog {
+                objects: objects.clone(),
+                entries: catalog_entries,
+            });
+        }
+        for (mut page, page_ref, content, resources, entries) in pages {
+            page.set_imported(ImportedPage {
+                objects: objects.clone(),
+                page_ref,
+                content,
+                resources,
+                entries,
+            });
+            doc.add_page(page);
+        }
+        Ok(doc)
+    }
+}
+
+/// Resources of the nearest page tree ancestor that has them
+fn inherited_resources<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    page: &PdfDictionary,
+) -> Option<PdfObject> {
+    let mut visited = HashSet::new();
+    let mut parent = page.get("Parent")?.as_reference()?;
+    while visited.insert(parent) {
+        let node = document.get_object(parent.0, parent.1).ok()?;
+        let node = node.as_dict()?;
+        if let Some(resources) = node.get("Resources") {
+            return Some(resources.clone());
+        }
+        parent = node.get("Parent")?.as_reference()?;
+    }
+    None
+}
+
+fn rectangle(rect: [f64; 4]) -> PdfObject {
+    PdfObject::Array(PdfArray(rect.iter().map(|&v| PdfObject::Real(v)).collect()))
+}
+
+/// Copy every object reachable from `roots`, stopping at the page tree
+fn collect_objects<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    objects: &mut ImportedObjects,
+    roots: Vec<PdfObject>,
+) {
+    let mut visited = HashSet::new();
+    let mut pending = roots;
+    while let Some(object) = pending.pop() {
+        match object {
+            PdfObject::Reference(num, gen) => {
+                let reference = (num, gen);
+                if objects.pages.contains(&reference) || !visited.insert(reference) {
+                    continue;
+                }
+                // Broken references are written as null
+                let Ok(resolved) = document.get_object(num, gen) else {
+                    continue;
+                };
+                let kind = resolved
+                    .as_dict()
+                    .and_then(|dict| dict.get("Type"))
+                    .and_then(|kind| kind.as_name())
+                    .map(|kind| kind.as_str().to_string());
+                match kind.as_deref() {
+                    Some("Pages") => {
+                        objects.page_tree_nodes.insert(reference);
+                    }
+                    // A page outside the page tree
+                    Some("Page") => {}
+                    _ => {
+                        pending.push(resolved.clone());
+                        objects.objects.insert(reference, resolved);
+                    }
+                }
+            }
+            PdfObject::Array(array) => pending.extend(array.0),
+            PdfObject::Dictionary(dict) => pending.extend(dict.0.into_values()),
+            PdfObject::Stream(stream) => pending.extend(stream.dict.0.into_values()),
+            _ => {}
+        }
+    }
+}
+
+/// Document metadata from the information dictionary
+fn read_metadata<R: Read + Seek>(document: &PdfDocument<R>) -> Result<DocumentMetadata> {
+    let mut metadata = DocumentMetadata {
+        creation_date: None,
+        ..DocumentMetadata::default()
+    };
+    let Some((num, gen)) = document.trailer().info() else {
+        return Ok(metadata);
+    };
+    let info = document.get_object(num, gen)?;
+    let Some(info) = info.as_dict() else {
+        return Ok(metadata);
+    };
+    let text = |key: &str| -> Result<Option<String>> {
+        match info.get(key) {
+            Some(value) => Ok(document
+                .resolve(value)?
+                .as_string()
+                .map(|s| text_string(s.as_bytes()))),
+            None => Ok(None),
+        }
+    };
+
+    metadata.title = text("Title")?;
+    metadata.author = text("Author")?;
+    metadata.subject = text("Subject")?;
+    metadata.keywords = text("Keywords")?;
+    if let Some(creator) = text("Creator")? {
+        metadata.creator = Some(creator);
+    }
+    if let Some(producer) = text("Producer")? {
+        metadata.producer = Some(producer);
+    }
+    metadata.creation_date = text("CreationDate")?.and_then(|date| parse_pdf_date(&date));
+    if let Some(date) = text("ModDate")?.and_then(|date| parse_pdf_date(&date)) {
+        metadata.modification_date = Some(date);
+    }
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Font;
+    use chrono::{TimeZone, Utc};
+    use std::io::Cursor;
+
+    /// Assemble a PDF from numbered object bodies; object 1 is the catalog
+    /// and the last one the information dictionary
+    fn build_pdf(objects: &[&str]) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{body}\nendobj\n", i + 1).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1,
+                objects.len()
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+
+    fn stream(content: &str) -> String {
+        format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len()
+        )
+    }
+
+    fn sample_pdf() -> Vec<u8> {
+        build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 9 0 R /PageLabels << /Nums [0 << /S /r >>] >> \
+             /Names << /Dests 11 0 R >> /AcroForm << /Fields [7 0 R] >> >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /Resources 5 0 R /MediaBox [0 0 300 200] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 6 0 R /Annots [7 0 R 8 0 R] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 12 0 R /CropBox [10 10 290 190] /Rotate 90 >>",
+            "<< /Font << /Helvetica 10 0 R >> /ExtGState << /G#201 << /ca 0.5 >> >> >>",
+            &stream("q /G#201 gs BT /Helvetica 12 Tf 20 100 Td (First page) Tj ET Q"),
+            "<< /Type /Annot /Subtype /Widget /FT /Tx /T (name) /V <FEFF00E9> \
+             /Rect [10 10 100 30] /P 3 0 R >>",
+            "<< /Type /Annot /Subtype /Text /Rect [0 0 10 10] /Contents <FEFF00E9> /P 3 0 R >>",
+            "<< /Type /Outlines /First 13 0 R /Last 13 0 R /Count 1 >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            "<< /Names [(first) [3 0 R /Fit]] >>",
+            &stream("BT /Helvetica 12 Tf 20 100 Td (Second page) Tj ET"),
+            "<< /Title (Second) /Parent 9 0 R /Dest [4 0 R /Fit] >>",
+            "<< /Title <FEFF00E9> /CreationDate (D:20240517103000Z) >>",
+        ])
+    }
+
+    fn reopen(doc: &mut Document) -> PdfDocument<Cursor<Vec<u8>>> {
+        let bytes = doc.to_bytes().unwrap();
+        PdfDocument::new(PdfReader::new(Cursor::new(bytes)).unwrap())
+    }
+
+    fn dest_page(parsed: &PdfDocument<Cursor<Vec<u8>>>, dest: &PdfObject) -> PdfObject {
+        let dest = parsed.resolve(dest).unwrap();
+        dest.as_array().unwrap().0[0].clone()
+    }
+
+    #[test]
+    fn test_open_and_edit_page() {
+        let mut doc = Document::from_reader(Cursor::new(sample_pdf())).unwrap();
+        assert_eq!(doc.page_count(), 2);
+        assert_eq!(doc.metadata.title.as_deref(), Some("é"));
+        assert_eq!(
+            doc.metadata.creation_date,
+            Some(Utc.with_ymd_and_hms(2024, 5, 17, 10, 30, 0).unwrap())
+        );
+
+        // The new text uses a resource name the original page uses too
+        doc.page_mut(0)
+            .unwrap()
+            .text()
+            .set_font(Font::Helvetica, 10.0)
+            .at(20.0, 50.0)
+            .write("Added")
+            .unwrap();
+        doc.remove_page(1).unwrap();
+
+        let parsed = reopen(&mut doc);
+        assert_eq!(parsed.page_count().unwrap(), 1);
+        let text = parsed.extract_text_from_page(0).unwrap().text;
+        assert!(
+            text.contains("First page") && text.contains("Added"),
+            "{text}"
+        );
+
+        let page = parsed.get_page(0).unwrap();
+        assert_eq!(page.media_box, [0.0, 0.0, 300.0, 200.0]);
+        let content = parsed.get_page_content_streams(&page).unwrap().concat();
+        let content = String::from_utf8_lossy(&content);
+        assert!(
+            content.contains("q /G1_1 gs BT /Helvetica_1 12 Tf"),
+            "{content}"
+        );
+
+        // Annotations keep their bytes and stay shared with the form field
+        let annots = page.dict.get("Annots").unwrap().as_array().unwrap().clone();
+        let note = parsed.resolve(&annots.0[1]).unwrap();
+        let contents = note.as_dict().unwrap().get("Contents").unwrap();
+        assert_eq!(
+            contents.as_string().unwrap().as_bytes(),
+            b"\xFE\xFF\x00\xE9"
+        );
+        let widget = parsed.resolve(&annots.0[0]).unwrap();
+        let (num, gen) = page.obj_ref;
+        assert_eq!(
+            widget.as_dict().unwrap().get("P"),
+            Some(&PdfObject::Reference(num, gen))
+        );
+
+        let catalog = parsed.catalog().unwrap();
+        assert!(catalog.contains_key("PageLabels"));
+        let form = parsed.resolve(catalog.get("AcroForm").unwrap()).unwrap();
+        let fields = form
+            .as_dict()
+            .unwrap()
+            .get("Fields")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .clone();
+        assert_eq!(fields.0[0], annots.0[0]);
+
+        let names = parsed.resolve(catalog.get("Names").unwrap()).unwrap();
+        let dests = parsed
+            .resolve(names.as_dict().unwrap().get("Dests").unwrap())
+            .unwrap();
+        let first = &dests
+            .as_dict()
+            .unwrap()
+            .get("Names")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .0[1];
+        assert_eq!(dest_page(&parsed, first), PdfObject::Reference(num, gen));
+
+        // The outline entry pointed at the removed page
+        let outlines = parsed.resolve(catalog.get("Outlines").unwrap()).unwrap();
+        let item = parsed
+            .resolve(outlines.as_dict().unwrap().get("First").unwrap())
+            .unwrap();
+        let dest = item.as_dict().unwrap().get("Dest").unwrap();
+        assert_eq!(dest_page(&parsed, dest), PdfObject::Null);
+    }
+
+    #[test]
+    fn test_reorder_opened_pages() {
+        let mut doc = Document::from_reader(Cursor::new(sample_pdf())).unwrap();
+        let second = doc.remove_page(1).unwrap();
+        assert_eq!(second.get_rotation(), 90);
+        doc.insert_page(0, second).unwrap();
+        doc.insert_page(1, Page::a4()).unwrap();
+        assert!(doc.insert_page(4, Page::a4()).is_err());
+        assert!(doc.remove_page(3).is_err());
+
+        let parsed = reopen(&mut doc);
+        assert_eq!(parsed.page_count().unwrap(), 3);
+        let page = parsed.get_page(0).unwrap();
+        assert_eq!(page.rotation, 90);
+        assert_eq!(page.crop_box, Some([10.0, 10.0, 290.0, 190.0]));
+        let text = parsed.extract_text_from_page(0).unwrap().text;
+        assert!(text.contains("Second page"), "{text}");
+        assert!(parsed
+            .extract_text_from_page(2)
+            .unwrap()
+            .text
+            .contains("First page"));
+
+        let catalog = parsed.catalog().unwrap();
+        let outlines = parsed.resolve(catalog.get("Outlines").unwrap()).unwrap();
+        let item = parsed
+            .resolve(outlines.as_dict().unwrap().get("First").unwrap())
+            .unwrap();
+        let dest = item.as_dict().unwrap().get("Dest").unwrap();
+        let (num, gen) = page.obj_ref;
+        assert_eq!(dest_page(&parsed, dest), PdfObject::Reference(num, gen));
+    }
+
+    #[test]
+    fn test_open_own_output() {
+        let mut original = Document::new();
+        original.set_title("Report");
+        let mut page = Page::a4();
+        page.text()
+            .set_font(Font::TimesRoman, 12.0)
+            .at(72.0, 700.0)
+            .write("Original text")
+            .unwrap();
+        original.add_page(page);
+
+        let bytes = original.to_bytes().unwrap();
+        let mut doc = Document::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(doc.metadata.title.as_deref(), Some("Report"));
+        doc.add_page(Page::a4());
+
+        let parsed = reopen(&mut doc);
+        assert_eq!(parsed.page_count().unwrap(), 2);
+        let text = parsed.extract_text_from_page(0).unwrap().text;
+        assert!(text.contains("Original text"), "{text}");
+    }
+}
@@ -0,0 +1,371 @@
+//! PKCS#7 EnvelopedData used by the public-key security handler
+//!
+//! Each entry of a `/Recipients` array is a DER-encoded `ContentInfo` wrapping
+//! an `EnvelopedData` structure (RFC 5652 §6). The enveloped content is the
+//! 20-byte seed followed by the recipient's permission flags; it is encrypted
+//! with a random AES-256 content key, which in turn is encrypted with the
+//! recipient's RSA public key (PKCS#1 v1.5 key transport).
+//!
+//! Envelopes written by other producers may use AES-128, Triple DES or RC2
+//! for the content; those are accepted when opening but never produced.
+
+use crate::encryption::des::TripleDes;
+use crate::encryption::rc2::Rc2;
+use crate::encryption::{Aes, AesKey};
+use crate::error::{PdfError, Result};
+use crate::signatures::der_error;
+use cms::cert::IssuerAndSerialNumber;
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::enveloped_data::{
+    EncryptedContentInfo, EnvelopedData, KeyTransRecipientInfo, RecipientIdentifier, RecipientInfo,
+    RecipientInfos,
+};
+use der::asn1::{ObjectIdentifier, OctetString, OctetStringRef, SetOfVec};
+use der::{Any, Decode, Encode};
+use rand::RngCore;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+
+/// id-data content type
+const OID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+/// id-envelopedData content type
+const OID_ENVELOPED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.3");
+/// rsaEncryption key transport
+const OID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+/// AES-128 in CBC mode
+const OID_AES128_CBC: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.2");
+/// AES-256 in CBC mode
+const OID_AES256_CBC: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.42");
+/// Triple DES (EDE, three keys) in CBC mode, accepted when opening only
+const OID_DES_EDE3_CBC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.3.7");
+/// RC2 in CBC mode, accepted when opening only
+const OID_RC2_CBC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.3.2");
+
+/// Encrypt `content` for the holder of `recipient`'s private key.
+///
+/// Returns the DER encoding of the `ContentInfo`.
+pub(crate) fn seal(content: &[u8], recipient: &Certificate) -> Result<Vec<u8>> {
+    let public_key = rsa_public_key(recipient)?;
+
+    let mut content_key = vec![0u8; 32];
+    let mut iv = [0u8; 16];
+    rand::rng().fill_bytes(&mut content_key);
+    rand::rng().fill_bytes(&mut iv);
+
+    let encrypted_content =
+        Aes::new(AesKey::new_256(content_key.clone())?).encrypt_cbc(content, &iv)?;
+    let encrypted_key = public_key
+        .encrypt(&mut rsa::rand_core::OsRng, Pkcs1v15Encrypt, &content_key)
+        .map_err(|e| PdfError::EncryptionError(format!("RSA key transport failed: {e}")))?;
+
+    let recipient_info = RecipientInfo::Ktri(KeyTransRecipientInfo {
+        version: CmsVersion::V0,
+        rid: RecipientIdentifier::IssuerAndSerialNumber(issuer_and_serial(recipient)),
+        key_enc_alg: AlgorithmIdentifierOwned {
+            oid: OID_RSA_ENCRYPTION,
+            parameters: Some(Any::null()),
+        },
+        enc_key: OctetString::new(encrypted_key).map_err(der_error)?,
+    });
+
+    let enveloped_data = EnvelopedData {
+        version: CmsVersion::V0,
+        originator_info: None,
+        recip_infos: RecipientInfos(SetOfVec::try_from(vec![recipient_info]).map_err(der_error)?),
+        encrypted_content: EncryptedContentInfo {
+            content_type: OID_DATA,
+            content_enc_alg: AlgorithmIdentifierOwned {
+                oid: OID_AES256_CBC,
+                parameters: Some(
+                    Any::encode_from(&OctetString::new(iv.to_vec()).map_err(der_error)?)
+                        .map_err(der_error)?,
+                ),
+            },
+            encrypted_content: Some(OctetString::new(encrypted_content).map_err(der_error)?),
+        },
+        unprotected_attrs: None,
+    };
+
+    let content_info = ContentInfo {
+        content_type: OID_ENVELOPED_DATA,
+        content: Any::encode_from(&enveloped_data).map_err(der_error)?,
+    };
+    content_info.to_der().map_err(der_error)
+}
+
+/// Decrypt an envelope addressed to `certificate`.
+///
+/// Returns `Ok(None)` when the envelope has no recipient entry for the
+/// certificate, so callers can try the next `/Recipients` string.
+pub(crate) fn open(
+    envelope: &[u8],
+    certificate: &Certificate,
+    private_key: &RsaPrivateKey,
+) -> Result<Option<Vec<u8>>> {
+    let content_info = ContentInfo::from_der(envelope).map_err(der_error)?;
+    if content_info.content_type != OID_ENVELOPED_DATA {
+        return Err(PdfError::EncryptionError(format!(
+            "Recipient entry is not PKCS#7 EnvelopedData (content type {})",
+            content_info.content_type
+        )));
+    }
+    let enveloped_data: EnvelopedData = content_info.content.decode_as().map_err(der_error)?;
+
+    let ours = issuer_and_serial(certificate);
+    let Some(encrypted_key) = enveloped_data
+        .recip_infos
+        .0
+        .iter()
+        .find_map(|info| match info {
+            RecipientInfo::Ktri(ktri) => match &ktri.rid {
+                RecipientIdentifier::IssuerAndSerialNumber(id) if *id == ours => {
+                    Some(&ktri.enc_key)
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+    else {
+        return Ok(None);
+    };
+
+    let content_key = private_key
+        .decrypt(Pkcs1v15Encrypt, encrypted_key.as_bytes())
+        .map_err(|e| PdfError::EncryptionError(format!("RSA key transport failed: {e}")))?;
+
+    let encrypted = &enveloped_data.encrypted_content;
+    let algorithm = &encrypted.content_enc_alg;
+    let ciphertext = encrypted
+        .encrypted_content
+        .as_ref()
+        .ok_or_else(|| PdfError::EncryptionError("Envelope has no content".to_string()))?
+        .as_bytes();
+
+    let content =
+        match algorithm.oid {
+            OID_AES128_CBC => Aes::new(AesKey::new_128(content_key)?)
+                .decrypt_cbc(ciphertext, cbc_iv(algorithm)?)?,
+            OID_AES256_CBC => Aes::new(AesKey::new_256(content_key)?)
+                .decrypt_cbc(ciphertext, cbc_iv(algorithm)?)?,
+            OID_DES_EDE3_CBC => {
+                let key: [u8; 24] = content_key.as_slice().try_into().map_err(|_| {
+                    PdfError::EncryptionError(format!(
+                        "Envelope DES-EDE3 key is {} bytes, expected 24",
+                        content_key.len()
+                    ))
+                })?;
+                let cipher = TripleDes::new(&key);
+                decrypt_cbc_64(ciphertext, cbc_iv(algorithm)?, |block| {
+                    cipher.decrypt_block(block)
+                })?
+            }
+            OID_RC2_CBC => {
+                let (effective_bits, iv) = rc2_parameters(algorithm)?;
+                let cipher = Rc2::new(&content_key, effective_bits);
+                decrypt_cbc_64(ciphertext, &iv, |block| cipher.decrypt_block(block))?
+            }
+            oid => {
+                return Err(PdfError::EncryptionError(format!(
+                    "Unsupported envelope cipher {oid}: only AES-128-CBC, AES-256-CBC, \
+                 DES-EDE3-CBC and RC2-CBC envelopes can be opened"
+                )))
+            }
+        };
+    Ok(Some(content))
+}
+
+/// IV of a CBC content encryption algorithm whose parameters are an OCTET STRING
+fn cbc_iv(algorithm: &AlgorithmIdentifierOwned) -> Result<&[u8]> {
+    algorithm
+        .parameters
+        .as_ref()
+        .and_then(|params| params.decode_as::<OctetStringRef>().ok())
+        .map(|iv| iv.as_bytes())
+        .ok_or_else(|| PdfError::EncryptionError("Envelope is missing the CBC IV".to_string()))
+}
+
+/// RC2-CBC parameters (RFC 8018 B.2.3)
+#[derive(der::Sequence)]
+struct Rc2CbcParameter {
+    rc2_parameter_version: Option<u32>,
+    iv: OctetString,
+}
+
+/// Effective key bits and IV of an RC2-CBC algorithm identifier.
+///
+/// The parameters are either the full `RC2-CBC-Parameter` sequence or,
+/// from some older encoders, the bare IV.
+fn rc2_parameters(algorithm: &AlgorithmIdentifierOwned) -> Result<(usize, Vec<u8>)> {
+    let params = algorithm
+        .parameters
+        .as_ref()
+        .ok_or_else(|| PdfError::EncryptionError("Envelope is missing the RC2 IV".to_string()))?;
+    let (version, iv) = match params.decode_as::<Rc2CbcParameter>() {
+        Ok(param) => (param.rc2_parameter_version, param.iv.into_bytes()),
+        Err(_) => (None, cbc_iv(algorithm)?.to_vec()),
+    };
+    let effective_bits = Rc2::effective_bits(version).ok_or_else(|| {
+        PdfError::EncryptionError(format!(
+            "Unsupported envelope cipher RC2-CBC with parameter version {}",
+            version.unwrap_or_default()
+        ))
+    })?;
+    Ok((effective_bits, iv))
+}
+
+/// Decrypt CBC mode with a 64-bit block cipher and strip the PKCS#7 padding
+fn decrypt_cbc_64(
+    ciphertext: &[u8],
+    iv: &[u8],
+    decrypt_block: impl Fn([u8; 8]) -> [u8; 8],
+) -> Result<Vec<u8>> {
+    let mut previous: [u8; 8] = iv.try_into().map_err(|_| {
+        PdfError::EncryptionError(format!("Envelope IV is {} bytes, expected 8", iv.len()))
+    })?;
+    if ciphertext.is_empty() || ciphertext.len() % 8 != 0 {
+        return Err(PdfError::EncryptionError(
+            "Envelope content is not a whole number of cipher blocks".to_string(),
+        ));
+    }
+
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    for chunk in ciphertext.chunks_exact(8) {
+        let block: [u8; 8] = chunk.try_into().expect("chunks are 8 bytes");
+        let decrypted = decrypt_block(block);
+        plaintext.extend(decrypted.iter().zip(previous.iter()).map(|(d, p)| d ^ p));
+        previous = block;
+    }
+
+    let padding = plaintext.last().copied().unwrap_or_default() as usize;
+    if padding == 0
+        || padding > 8
+        || !plaintext[plaintext.len() - padding..]
+            .iter()
+            .all(|&byte| byte as usize == padding)
+    {
+        return Err(PdfError::EncryptionError(
+            "Envelope content has invalid padding".to_string(),
+        ));
+    }
+    plaintext.truncate(plaintext.len() - padding);
+    Ok(plaintext)
+}
+
+/// Extract the RSA public key of a recipient certificate
+pub(crate) fn rsa_public_key(certificate: &Certificate) -> Result<RsaPublicKey> {
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(der_error)?;
+    RsaPublicKey::from_public_key_der(&spki).map_err(|_| {
+        PdfError::EncryptionError("Recipient certificate does not hold an RSA key".to_string())
+    })
+}
+
+fn issuer_and_serial(certificate: &Certificate) -> IssuerAndSerialNumber {
+    IssuerAndSerialNumber {
+        issuer: certificate.tbs_certificate.issuer.clone(),
+        serial_number: certificate.tbs_certificate.serial_number.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::test_helpers::{
+        CHAIN_CERT_PEM, CHAIN_KEY_PEM, RSA_CERT_PEM, RSA_KEY_PEM,
+    };
+    use rsa::pkcs8::DecodePrivateKey;
+
+    fn load(cert_pem: &str, key_pem: &str) -> (Certificate, RsaPrivateKey) {
+        use der::DecodePem;
+        (
+            Certificate::from_pem(cert_pem).unwrap(),
+            RsaPrivateKey::from_pkcs8_pem(key_pem).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let (cert, key) = load(RSA_CERT_PEM, RSA_KEY_PEM);
+        let content = [0x5A; 24];
+
+        let envelope = seal(&content, &cert).unwrap();
+        assert_eq!(
+            open(&envelope, &cert, &key).unwrap(),
+            Some(content.to_vec())
+        );
+    }
+
+    /// Seed sealed into the OpenSSL fixtures by `generate_envelopes.sh`
+    const FIXTURE_SEED: &[u8] = b"0123456789abcdefghijklmn";
+
+    #[test]
+    fn test_open_triple_des_envelope() {
+        let (cert, key) = load(RSA_CERT_PEM, RSA_KEY_PEM);
+        let envelope = include_bytes!("../../tests/fixtures/envelopes/des_ede3_cbc.der");
+
+        assert_eq!(
+            open(envelope, &cert, &key).unwrap(),
+            Some(FIXTURE_SEED.to_vec())
+        );
+    }
+
+    #[test]
+    fn test_open_rc2_envelopes() {
+        let (cert, key) = load(RSA_CERT_PEM, RSA_KEY_PEM);
+        let envelopes: [&[u8]; 2] = [
+            include_bytes!("../../tests/fixtures/envelopes/rc2_40_cbc.der"),
+            include_bytes!("../../tests/fixtures/envelopes/rc2_128_cbc.der"),
+        ];
+
+        for envelope in envelopes {
+            assert_eq!(
+                open(envelope, &cert, &key).unwrap(),
+                Some(FIXTURE_SEED.to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_unknown_cipher() {
+        let (cert, key) = load(RSA_CERT_PEM, RSA_KEY_PEM);
+        let envelope = seal(b"seed", &cert).unwrap();
+
+        // Swap AES-256-CBC (...4.1.42) for AES-256-GCM (...4.1.46)
+        let aes256 = OID_AES256_CBC.to_der().unwrap();
+        let gcm = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.46")
+            .to_der()
+            .unwrap();
+        let at = envelope
+            .windows(aes256.len())
+            .position(|window| window == aes256.as_slice())
+            .unwrap();
+        let mut patched = envelope.clone();
+        patched[at..at + aes256.len()].copy_from_slice(&gcm);
+
+        let err = open(&patched, &cert, &key).unwrap_err().to_string();
+        assert!(err.contains("Unsupported envelope cipher"), "{err}");
+    }
+
+    #[test]
+    fn test_open_for_other_recipient() {
+        let (cert, _) = load(RSA_CERT_PEM, RSA_KEY_PEM);
+        let (other_cert, other_key) = load(CHAIN_CERT_PEM, CHAIN_KEY_PEM);
+
+        let envelope = seal(b"seed", &cert).unwrap();
+        assert_eq!(open(&envelope, &other_cert, &other_key).unwrap(), None);
+    }
+
+    #[test]
+    fn test_seal_rejects_non_rsa_certificate() {
+        use crate::signatures::test_helpers::ECDSA_CERT_PEM;
+        use der::DecodePem;
+
+        let cert = Certificate::from_pem(ECDSA_CERT_PEM).unwrap();
+        assert!(seal(b"seed", &cert).is_err());
+    }
+}
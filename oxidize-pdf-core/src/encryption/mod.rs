@@ -6,12 +6,15 @@
 
 mod aes;
 mod crypt_filters;
+mod des;
 mod embedded_files;
 mod encryption_dict;
+mod envelope;
 mod object_encryption;
 mod permissions;
 mod permissions_enforcement;
 mod public_key;
+mod rc2;
 mod rc4;
 mod standard_security;
 
@@ -28,7 +31,9 @@ pub use permissions_enforcement::{
     LogLevel, PermissionCallback, PermissionCheckResult, PermissionEvent, PermissionOperation,
     PermissionsValidator, RuntimePermissions, RuntimePermissionsBuilder,
 };
-pub use public_key::{PublicKeyEncryptionDict, PublicKeySecurityHandler, Recipient, SubFilter};
+pub use public_key::{
+    PublicKeyEncryptionDict, PublicKeySecurityHandler, Recipient, RecipientCredentials, SubFilter,
+};
 pub use rc4::{Rc4, Rc4Key};
 pub use standard_security::{
     compute_hash_r6_algorithm_2b, EncryptionKey, OwnerPassword, SecurityHandlerRevision,
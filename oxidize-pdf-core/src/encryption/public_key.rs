@@ -1,12 +1,22 @@
 //! Public Key Security Handler for PDF encryption
 //!
 //! This module implements the Public Key Security Handler according to ISO 32000-1:2008 §7.6.4.
-//! It supports X.509 certificates and various SubFilter types for recipient-based encryption.
+//! Documents are encrypted for a list of X.509 recipient certificates; each recipient opens
+//! the document with their RSA private key instead of a password.
 
+use super::envelope;
 use crate::encryption::{CryptFilterMethod, EncryptionKey, Permissions, SecurityHandler};
 use crate::error::{PdfError, Result};
 use crate::objects::{Dictionary, Object, ObjectId};
+use crate::signatures::der_error;
+use der::{Decode, DecodePem, Encode};
+use rand::RngCore;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::RsaPrivateKey;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use x509_cert::Certificate;
 
 /// SubFilter types for public key security
 #[derive(Debug, Clone, PartialEq)]
@@ -54,50 +64,127 @@ pub struct Recipient {
     pub certificate: Vec<u8>,
     /// Permissions granted to this recipient
     pub permissions: Permissions,
-    /// Encrypted seed value for this recipient
+    /// DER-encoded PKCS#7 EnvelopedData carrying the seed and permissions,
+    /// written as one string of the `/Recipients` array
     pub encrypted_seed: Vec<u8>,
 }
 
+/// Certificate and RSA private key used to open a document encrypted for
+/// that certificate
+#[derive(Clone)]
+pub struct RecipientCredentials {
+    certificate: Certificate,
+    key: RsaPrivateKey,
+}
+
+impl std::fmt::Debug for RecipientCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        f.debug_struct("RecipientCredentials")
+            .field(
+                "subject",
+                &self.certificate.tbs_certificate.subject.to_string(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecipientCredentials {
+    /// Create credentials from a certificate and the matching RSA private key
+    pub fn new(certificate: Certificate, key: RsaPrivateKey) -> Result<Self> {
+        if envelope::rsa_public_key(&certificate)? != key.to_public_key() {
+            return Err(PdfError::InvalidOperation(
+                "Private key does not match the recipient certificate".to_string(),
+            ));
+        }
+        Ok(Self { certificate, key })
+    }
+
+    /// Create credentials from a PKCS#8 DER key and a DER certificate
+    pub fn from_der(key_der: &[u8], certificate_der: &[u8]) -> Result<Self> {
+        let key = RsaPrivateKey::from_pkcs8_der(key_der).map_err(|e| {
+            PdfError::EncryptionError(format!("Invalid PKCS#8 RSA private key: {e}"))
+        })?;
+        Self::new(parse_certificate(certificate_der)?, key)
+    }
+
+    /// Create credentials from a PKCS#8 PEM key and a PEM certificate
+    pub fn from_pem(key_pem: &str, certificate_pem: &str) -> Result<Self> {
+        let key = RsaPrivateKey::from_pkcs8_pem(key_pem).map_err(|e| {
+            PdfError::EncryptionError(format!("Invalid PKCS#8 RSA private key: {e}"))
+        })?;
+        let certificate = Certificate::from_pem(certificate_pem).map_err(der_error)?;
+        Self::new(certificate, key)
+    }
+
+    /// The recipient certificate
+    pub fn certificate(&self) -> &Certificate {
+        &self.certificate
+    }
+}
+
 /// Public Key Security Handler
+///
+/// All recipients share one random seed. Each recipient receives the seed
+/// and their permission flags in a PKCS#7 envelope that only their private
+/// key can open; the file encryption key is derived from the seed and the
+/// complete `/Recipients` array (ISO 32000-1 §7.6.4.3).
 pub struct PublicKeySecurityHandler {
     /// SubFilter type
     pub subfilter: SubFilter,
     /// Recipients list
     pub recipients: Vec<Recipient>,
-    /// Seed value length in bytes (20 for SHA-1, 32 for SHA-256)
+    /// Seed value length in bytes
     pub seed_length: usize,
     /// Encryption method
     pub method: CryptFilterMethod,
+    /// File encryption key length in bytes; only RC4 (V2) allows anything
+    /// other than the method's default, from 5 (40-bit) to 16 (128-bit)
+    pub key_length: usize,
+    /// Whether metadata streams are encrypted
+    pub encrypt_metadata: bool,
+    /// Seed shared by all recipients
+    seed: Vec<u8>,
 }
 
 impl PublicKeySecurityHandler {
-    /// Create a new public key security handler with SHA-1
+    /// Create a new public key security handler with SHA-1 key derivation
+    /// and 128-bit RC4 (adbe.pkcs7.s4)
     pub fn new_sha1() -> Self {
-        Self {
-            subfilter: SubFilter::AdbePkcs7S3,
-            recipients: Vec::new(),
-            seed_length: 20,
-            method: CryptFilterMethod::V2,
-        }
+        Self::with_method(SubFilter::AdbePkcs7S4, CryptFilterMethod::V2)
+    }
+
+    /// Create a new public key security handler with SHA-1 key derivation
+    /// and AES-128 crypt filters (adbe.pkcs7.s5)
+    pub fn new_aes128() -> Self {
+        Self::with_method(SubFilter::AdbePkcs7S5, CryptFilterMethod::AESV2)
     }
 
-    /// Create a new public key security handler with SHA-256
+    /// Create a new public key security handler with SHA-256 key derivation
+    /// and AES-256 crypt filters (adbe.pkcs7.s5)
     pub fn new_sha256() -> Self {
+        Self::with_method(SubFilter::AdbePkcs7S5, CryptFilterMethod::AESV3)
+    }
+
+    fn with_method(subfilter: SubFilter, method: CryptFilterMethod) -> Self {
+        let seed_length = 20;
         Self {
-            subfilter: SubFilter::AdbePkcs7S4,
+            subfilter,
             recipients: Vec::new(),
-            seed_length: 32,
-            method: CryptFilterMethod::AESV2,
+            seed_length,
+            method,
+            key_length: Self::default_key_length(method),
+            encrypt_metadata: true,
+            seed: Self::generate_seed(seed_length),
         }
     }
 
-    /// Add a recipient
+    /// Add a recipient from a DER-encoded X.509 certificate
+    ///
+    /// The certificate must carry an RSA public key.
     pub fn add_recipient(&mut self, certificate: Vec<u8>, permissions: Permissions) -> Result<()> {
-        // Generate random seed
-        let seed = self.generate_seed()?;
-
-        // Encrypt seed with recipient's public key
-        let encrypted_seed = self.encrypt_seed_for_recipient(&seed, &certificate)?;
+        let parsed = parse_certificate(&certificate)?;
+        let encrypted_seed = self.encrypt_seed_for_recipient(&parsed, permissions)?;
 
         self.recipients.push(Recipient {
             certificate,
@@ -108,102 +195,140 @@ impl PublicKeySecurityHandler {
         Ok(())
     }
 
-    /// Generate random seed value
-    fn generate_seed(&self) -> Result<Vec<u8>> {
-        // In production, use a cryptographically secure RNG
-        // For now, we'll use a simple approach with timestamp
-        use std::time::{SystemTime, UNIX_EPOCH};
-
-        let mut seed = vec![0u8; self.seed_length];
-
-        // Get current timestamp for pseudo-randomness
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64;
-
-        // Fill with pseudo-random data based on timestamp
-        for (i, byte) in seed.iter_mut().enumerate() {
-            *byte = ((timestamp
-                .wrapping_mul(i as u64 + 1)
-                .wrapping_add(i as u64 * 7 + 13))
-                % 256) as u8;
-        }
+    /// Add a recipient from a PEM-encoded X.509 certificate
+    pub fn add_recipient_pem(
+        &mut self,
+        certificate_pem: &str,
+        permissions: Permissions,
+    ) -> Result<()> {
+        let certificate = Certificate::from_pem(certificate_pem).map_err(der_error)?;
+        let der = certificate.to_der().map_err(der_error)?;
+        self.add_recipient(der, permissions)
+    }
 
-        Ok(seed)
+    /// Generate random seed value
+    fn generate_seed(length: usize) -> Vec<u8> {
+        let mut seed = vec![0u8; length];
+        rand::rng().fill_bytes(&mut seed);
+        seed
     }
 
-    /// Encrypt seed for a specific recipient
-    fn encrypt_seed_for_recipient(&self, seed: &[u8], certificate: &[u8]) -> Result<Vec<u8>> {
-        // In a real implementation, this would:
-        // 1. Parse the X.509 certificate
-        // 2. Extract the public key
-        // 3. Encrypt the seed using RSA or ECDSA
-        // For now, we'll simulate this
+    /// Envelope the seed and permission flags for a specific recipient
+    fn encrypt_seed_for_recipient(
+        &self,
+        certificate: &Certificate,
+        permissions: Permissions,
+    ) -> Result<Vec<u8>> {
+        let mut content = self.seed.clone();
+        content.extend_from_slice(&permissions.bits().to_be_bytes());
+        envelope::seal(&content, certificate)
+    }
 
-        // Validate certificate length
-        if certificate.len() < 100 {
-            return Err(PdfError::EncryptionError("Invalid certificate".to_string()));
+    /// Open a `/Recipients` string with the recipient's private key
+    ///
+    /// Returns the seed and the permissions granted to the recipient, or
+    /// `None` when the envelope is addressed to someone else.
+    pub fn decrypt_seed(
+        encrypted_seed: &[u8],
+        credentials: &RecipientCredentials,
+    ) -> Result<Option<(Vec<u8>, Permissions)>> {
+        let Some(content) =
+            envelope::open(encrypted_seed, &credentials.certificate, &credentials.key)?
+        else {
+            return Ok(None);
+        };
+        if content.len() < 24 {
+            return Err(PdfError::EncryptionError(
+                "Invalid encrypted seed".to_string(),
+            ));
         }
+        let (seed, permissions) = content.split_at(content.len() - 4);
+        let bits = u32::from_be_bytes([
+            permissions[0],
+            permissions[1],
+            permissions[2],
+            permissions[3],
+        ]);
+        Ok(Some((seed.to_vec(), Permissions::from_bits(bits))))
+    }
 
-        // Simulate RSA encryption (in production, use a crypto library)
-        let mut encrypted = seed.to_vec();
-        encrypted.extend_from_slice(&certificate[0..4]); // Add certificate fingerprint
+    /// The `/Recipients` strings, in document order
+    pub fn recipient_strings(&self) -> Vec<Vec<u8>> {
+        self.recipients
+            .iter()
+            .map(|recipient| recipient.encrypted_seed.clone())
+            .collect()
+    }
 
-        Ok(encrypted)
+    /// Compute the file encryption key for the current recipients
+    pub fn encryption_key(&self) -> EncryptionKey {
+        Self::derive_key(
+            &self.seed,
+            &self.recipient_strings(),
+            self.encrypt_metadata,
+            self.method,
+            self.key_length,
+        )
     }
 
-    /// Decrypt seed value using private key
-    pub fn decrypt_seed(&self, encrypted_seed: &[u8], private_key: &[u8]) -> Result<Vec<u8>> {
-        // In a real implementation, this would use the private key to decrypt
-        // For now, we'll simulate this
+    /// Derive the file encryption key (ISO 32000-1 §7.6.4.3.3)
+    ///
+    /// The key is the SHA-1 digest (SHA-256 for AESV3) of the seed, every
+    /// `/Recipients` string and, when metadata is left in clear text, four
+    /// 0xFF bytes, truncated to `key_length` bytes. AES methods always use
+    /// their fixed key length; `key_length` only matters for RC4 (V2).
+    pub fn derive_key(
+        seed: &[u8],
+        recipients: &[Vec<u8>],
+        encrypt_metadata: bool,
+        method: CryptFilterMethod,
+        key_length: usize,
+    ) -> EncryptionKey {
+        let mut input = seed.to_vec();
+        for recipient in recipients {
+            input.extend_from_slice(recipient);
+        }
+        if !encrypt_metadata {
+            input.extend_from_slice(&[0xFF; 4]);
+        }
 
-        if private_key.is_empty() {
-            return Err(PdfError::EncryptionError(
-                "Private key required".to_string(),
-            ));
+        let mut key = match method {
+            CryptFilterMethod::AESV3 => Sha256::digest(&input).to_vec(),
+            _ => Sha1::digest(&input).to_vec(),
+        };
+        key.truncate(Self::effective_key_length(method, key_length));
+        EncryptionKey::new(key)
+    }
+
+    /// Key length actually used: RC4 keys are limited to 5..=16 bytes and
+    /// AES keys always have the method's length
+    fn effective_key_length(method: CryptFilterMethod, key_length: usize) -> usize {
+        match method {
+            CryptFilterMethod::V2 => key_length.clamp(5, 16),
+            _ => Self::default_key_length(method),
         }
+    }
 
-        // Return the seed portion (simulation)
-        if encrypted_seed.len() >= self.seed_length {
-            Ok(encrypted_seed[0..self.seed_length].to_vec())
-        } else {
-            Err(PdfError::EncryptionError(
-                "Invalid encrypted seed".to_string(),
-            ))
+    /// Default file encryption key length in bytes for a crypt filter method
+    pub fn default_key_length(method: CryptFilterMethod) -> usize {
+        match method {
+            CryptFilterMethod::AESV3 => 32,
+            _ => 16,
         }
     }
 
     /// Build recipients dictionary for PDF
     pub fn build_recipients_dict(&self) -> Dictionary {
         let mut dict = Dictionary::new();
-
-        let recipients_array: Vec<Object> = self
-            .recipients
-            .iter()
-            .map(|recipient| {
-                let mut recipient_dict = Dictionary::new();
-
-                // Certificate
-                recipient_dict.set(
-                    "Cert",
-                    Object::String(String::from_utf8_lossy(&recipient.certificate).to_string()),
-                );
-
-                // Permissions
-                recipient_dict.set("P", Object::Integer(recipient.permissions.bits() as i64));
-
-                // Encrypted seed
-                recipient_dict.set(
-                    "Recipients",
-                    Object::String(String::from_utf8_lossy(&recipient.encrypted_seed).to_string()),
-                );
-
-                Object::Dictionary(recipient_dict)
-            })
-            .collect();
-
-        dict.set("Recipients", Object::Array(recipients_array));
+        dict.set(
+            "Recipients",
+            Object::Array(
+                self.recipients
+                    .iter()
+                    .map(|recipient| byte_string(&recipient.encrypted_seed))
+                    .collect(),
+            ),
+        );
         dict
     }
 
@@ -215,6 +340,44 @@ impl PublicKeySecurityHandler {
             false
         }
     }
+
+    /// Per-object key (Algorithm 1, ISO 32000-1 §7.6.2); AESV3 uses the file key as-is
+    fn object_key(&self, encryption_key: &EncryptionKey, obj_id: &ObjectId) -> Vec<u8> {
+        if self.method == CryptFilterMethod::AESV3 {
+            return encryption_key.as_bytes().to_vec();
+        }
+        let mut data = encryption_key.as_bytes().to_vec();
+        data.extend_from_slice(&obj_id.number().to_le_bytes()[0..3]);
+        data.extend_from_slice(&obj_id.generation().to_le_bytes()[0..2]);
+        if self.method == CryptFilterMethod::AESV2 {
+            data.extend_from_slice(b"sAlT");
+        }
+        let hash = md5::compute(&data);
+        let key_len = (encryption_key.len() + 5).min(16);
+        hash[..key_len].to_vec()
+    }
+}
+
+/// Parse a DER-encoded X.509 certificate
+fn parse_certificate(der: &[u8]) -> Result<Certificate> {
+    Certificate::from_der(der)
+        .map_err(|e| PdfError::EncryptionError(format!("Invalid recipient certificate: {e}")))
+}
+
+/// Binary string object; bytes outside printable ASCII are written as octal escapes
+fn byte_string(bytes: &[u8]) -> Object {
+    let mut escaped = String::with_capacity(bytes.len() * 4);
+    for &byte in bytes {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(byte as char);
+            }
+            0x20..=0x7E => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    Object::String(escaped)
 }
 
 impl SecurityHandler for PublicKeySecurityHandler {
@@ -225,38 +388,33 @@ impl SecurityHandler for PublicKeySecurityHandler {
         obj_id: &ObjectId,
     ) -> Result<Vec<u8>> {
         // Use the appropriate encryption based on method
+        let key = self.object_key(encryption_key, obj_id);
         match self.method {
             CryptFilterMethod::V2 => {
                 // RC4 encryption
                 use crate::encryption::{Rc4, Rc4Key};
-                let mut key = encryption_key.as_bytes().to_vec();
-                key.extend_from_slice(&obj_id.number().to_le_bytes()[0..3]);
-                key.extend_from_slice(&obj_id.generation().to_le_bytes()[0..2]);
-
                 let rc4_key = Rc4Key::from_slice(&key);
                 let mut cipher = Rc4::new(&rc4_key);
                 Ok(cipher.process(data))
             }
             CryptFilterMethod::AESV2 | CryptFilterMethod::AESV3 => {
-                // AES encryption
-                use crate::encryption::{Aes, AesKey};
-                let aes_key = AesKey::new_128(encryption_key.as_bytes().to_vec())
-                    .map_err(|e| PdfError::EncryptionError(e.to_string()))?;
-                let aes = Aes::new(aes_key);
-
-                // Generate deterministic IV based on object ID
-                let mut iv = vec![0u8; 16];
-                let obj_bytes = obj_id.number().to_le_bytes();
-                let gen_bytes = obj_id.generation().to_le_bytes();
-                iv[..4].copy_from_slice(&obj_bytes);
-                iv[4..(2 + 4)].copy_from_slice(&gen_bytes);
-                // Fill rest with pattern
-                for (i, item) in iv.iter_mut().enumerate().take(16).skip(6) {
-                    *item = ((i * 13 + 7) % 256) as u8;
+                // AES-CBC with a random IV prepended to the ciphertext
+                use crate::encryption::{generate_iv, Aes, AesKey};
+                let aes_key = if self.method == CryptFilterMethod::AESV3 {
+                    AesKey::new_256(key)
+                } else {
+                    AesKey::new_128(key)
                 }
-
-                aes.encrypt_cbc(data, &iv)
-                    .map_err(|e| PdfError::EncryptionError(e.to_string()))
+                .map_err(|e| PdfError::EncryptionError(e.to_string()))?;
+
+                let iv = generate_iv();
+                let mut result = iv.clone();
+                result.extend(
+                    Aes::new(aes_key)
+                        .encrypt_cbc(data, &iv)
+                        .map_err(|e| PdfError::EncryptionError(e.to_string()))?,
+                );
+                Ok(result)
             }
             _ => Err(PdfError::EncryptionError(format!(
                 "Unsupported encryption method: {:?}",
@@ -278,24 +436,24 @@ impl SecurityHandler for PublicKeySecurityHandler {
                 self.encrypt_string(data, encryption_key, obj_id)
             }
             CryptFilterMethod::AESV2 | CryptFilterMethod::AESV3 => {
-                // AES decryption
+                // AES decryption, the first block is the IV
                 use crate::encryption::{Aes, AesKey};
-                let aes_key = AesKey::new_128(encryption_key.as_bytes().to_vec())
-                    .map_err(|e| PdfError::EncryptionError(e.to_string()))?;
-                let aes = Aes::new(aes_key);
-
-                // Generate deterministic IV based on object ID
-                let mut iv = vec![0u8; 16];
-                let obj_bytes = obj_id.number().to_le_bytes();
-                let gen_bytes = obj_id.generation().to_le_bytes();
-                iv[..4].copy_from_slice(&obj_bytes);
-                iv[4..(2 + 4)].copy_from_slice(&gen_bytes);
-                // Fill rest with pattern
-                for (i, item) in iv.iter_mut().enumerate().take(16).skip(6) {
-                    *item = ((i * 13 + 7) % 256) as u8;
+                if data.len() < 16 {
+                    return Err(PdfError::EncryptionError(
+                        "AES encrypted data must be at least 16 bytes (IV)".to_string(),
+                    ));
+                }
+                let key = self.object_key(encryption_key, obj_id);
+                let aes_key = if self.method == CryptFilterMethod::AESV3 {
+                    AesKey::new_256(key)
+                } else {
+                    AesKey::new_128(key)
                 }
+                .map_err(|e| PdfError::EncryptionError(e.to_string()))?;
 
-                aes.decrypt_cbc(data, &iv)
+                let (iv, ciphertext) = data.split_at(16);
+                Aes::new(aes_key)
+                    .decrypt_cbc(ciphertext, iv)
                     .map_err(|e| PdfError::EncryptionError(e.to_string()))
             }
             _ => Err(PdfError::EncryptionError(format!(
@@ -413,7 +571,7 @@ pub struct PublicKeyEncryptionDict {
     pub subfilter: SubFilter,
     /// Version
     pub v: u8,
-    /// Length in bytes (40 to 128)
+    /// Length in bits (40 to 256)
     pub length: Option<u32>,
     /// Crypt filters
     pub cf: Option<HashMap<String, Dictionary>>,
@@ -421,49 +579,63 @@ pub struct PublicKeyEncryptionDict {
     pub stm_f: Option<String>,
     /// Default crypt filter for strings  
     pub str_f: Option<String>,
-    /// Recipients
-    pub recipients: Vec<Dictionary>,
+    /// Recipients (DER-encoded PKCS#7 objects)
+    pub recipients: Vec<Vec<u8>>,
     /// Encrypt metadata
     pub encrypt_metadata: bool,
 }
 
 impl PublicKeyEncryptionDict {
+    /// Name of the crypt filter carrying the recipients for adbe.pkcs7.s5
+    pub const DEFAULT_CRYPT_FILTER: &'static str = "DefaultCryptFilter";
+
     /// Create a new public key encryption dictionary
+    ///
+    /// RC4 handlers list the recipients in the encryption dictionary itself;
+    /// AES handlers put them in a `DefaultCryptFilter` used for both strings
+    /// and streams.
     pub fn new(handler: &PublicKeySecurityHandler) -> Self {
+        let recipients = handler.recipient_strings();
+        let length =
+            PublicKeySecurityHandler::effective_key_length(handler.method, handler.key_length)
+                as u32
+                * 8;
+
+        let (v, cf) = match handler.method {
+            CryptFilterMethod::V2 => (2, None),
+            method => {
+                let mut filter = Dictionary::new();
+                filter.set("Type", Object::Name("CryptFilter".to_string()));
+                filter.set("CFM", Object::Name(method.pdf_name().to_string()));
+                filter.set("Length", Object::Integer(length as i64));
+                filter.set(
+                    "Recipients",
+                    Object::Array(recipients.iter().map(|r| byte_string(r)).collect()),
+                );
+                filter.set("EncryptMetadata", Object::Boolean(handler.encrypt_metadata));
+
+                let mut filters = HashMap::new();
+                filters.insert(Self::DEFAULT_CRYPT_FILTER.to_string(), filter);
+                let v = if method == CryptFilterMethod::AESV3 {
+                    5
+                } else {
+                    4
+                };
+                (v, Some(filters))
+            }
+        };
+        let default_filter = cf.as_ref().map(|_| Self::DEFAULT_CRYPT_FILTER.to_string());
+
         Self {
             filter: "Adobe.PubSec".to_string(),
             subfilter: handler.subfilter.clone(),
-            v: match handler.method {
-                CryptFilterMethod::V2 => 2,
-                CryptFilterMethod::AESV2 => 4,
-                CryptFilterMethod::AESV3 => 5,
-                _ => 4,
-            },
-            length: Some(match handler.method {
-                CryptFilterMethod::V2 => 128,
-                _ => 256,
-            }),
-            cf: None,
-            stm_f: Some("DefaultCryptFilter".to_string()),
-            str_f: Some("DefaultCryptFilter".to_string()),
-            recipients: handler
-                .recipients
-                .iter()
-                .map(|r| {
-                    let mut dict = Dictionary::new();
-                    dict.set(
-                        "Cert",
-                        Object::String(String::from_utf8_lossy(&r.certificate).to_string()),
-                    );
-                    dict.set("P", Object::Integer(r.permissions.bits() as i64));
-                    dict.set(
-                        "Recipients",
-                        Object::String(String::from_utf8_lossy(&r.encrypted_seed).to_string()),
-                    );
-                    dict
-                })
-                .collect(),
-            encrypt_metadata: true,
+            v,
+            length: Some(length),
+            cf,
+            stm_f: default_filter.clone(),
+            str_f: default_filter,
+            recipients,
+            encrypt_metadata: handler.encrypt_metadata,
         }
     }
 
@@ -482,10 +654,17 @@ impl PublicKeyEncryptionDict {
             dict.set("Length", Object::Integer(length as i64));
         }
 
-        if let Some(ref _cf) = self.cf {
-            let cf_dict = Dictionary::new();
-            // Add crypt filters...
+        if let Some(ref cf) = self.cf {
+            let mut cf_dict = Dictionary::new();
+            for (name, filter) in cf {
+                cf_dict.set(name, Object::Dictionary(filter.clone()));
+            }
             dict.set("CF", Object::Dictionary(cf_dict));
+        } else {
+            // Without crypt filters the recipients live in the encryption dictionary
+            let recipients_array: Vec<Object> =
+                self.recipients.iter().map(|r| byte_string(r)).collect();
+            dict.set("Recipients", Object::Array(recipients_array));
         }
 
         if let Some(ref stm_f) = self.stm_f {
@@ -496,13 +675,6 @@ impl PublicKeyEncryptionDict {
             dict.set("StrF", Object::Name(str_f.clone()));
         }
 
-        let recipients_array: Vec<Object> = self
-            .recipients
-            .iter()
-            .map(|r| Object::Dictionary(r.clone()))
-            .collect();
-        dict.set("Recipients", Object::Array(recipients_array));
-
         dict.set("EncryptMetadata", Object::Boolean(self.encrypt_metadata));
 
         dict
@@ -512,6 +684,9 @@ impl PublicKeyEncryptionDict {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signatures::test_helpers::{
+        CHAIN_CERT_PEM, CHAIN_KEY_PEM, ECDSA_CERT_PEM, RSA_CERT_PEM, RSA_KEY_PEM,
+    };
 
     #[test]
     fn test_subfilter_conversion() {
@@ -548,25 +723,42 @@ mod tests {
         );
     }
 
+    fn rsa_certificate() -> Vec<u8> {
+        Certificate::from_pem(RSA_CERT_PEM)
+            .unwrap()
+            .to_der()
+            .unwrap()
+    }
+
+    fn chain_certificate() -> Vec<u8> {
+        Certificate::from_pem(CHAIN_CERT_PEM)
+            .unwrap()
+            .to_der()
+            .unwrap()
+    }
+
     #[test]
     fn test_public_key_handler_creation() {
         let handler_sha1 = PublicKeySecurityHandler::new_sha1();
-        assert_eq!(handler_sha1.subfilter, SubFilter::AdbePkcs7S3);
+        assert_eq!(handler_sha1.subfilter, SubFilter::AdbePkcs7S4);
         assert_eq!(handler_sha1.seed_length, 20);
         assert_eq!(handler_sha1.method, CryptFilterMethod::V2);
 
+        let handler_aes128 = PublicKeySecurityHandler::new_aes128();
+        assert_eq!(handler_aes128.subfilter, SubFilter::AdbePkcs7S5);
+        assert_eq!(handler_aes128.method, CryptFilterMethod::AESV2);
+
         let handler_sha256 = PublicKeySecurityHandler::new_sha256();
-        assert_eq!(handler_sha256.subfilter, SubFilter::AdbePkcs7S4);
-        assert_eq!(handler_sha256.seed_length, 32);
-        assert_eq!(handler_sha256.method, CryptFilterMethod::AESV2);
+        assert_eq!(handler_sha256.subfilter, SubFilter::AdbePkcs7S5);
+        assert_eq!(handler_sha256.seed_length, 20);
+        assert_eq!(handler_sha256.method, CryptFilterMethod::AESV3);
     }
 
     #[test]
     fn test_add_recipient() {
         let mut handler = PublicKeySecurityHandler::new_sha1();
 
-        // Create a mock certificate (at least 100 bytes)
-        let certificate = vec![0x30; 200]; // DER-like data
+        let certificate = rsa_certificate();
         let permissions = Permissions::new()
             .set_print(true)
             .set_modify_contents(true)
@@ -578,88 +770,170 @@ mod tests {
         assert_eq!(handler.recipients.len(), 1);
         assert_eq!(handler.recipients[0].certificate, certificate);
         assert_eq!(handler.recipients[0].permissions.bits(), permissions.bits());
-        assert!(!handler.recipients[0].encrypted_seed.is_empty());
+        // DER ContentInfo: SEQUENCE { OID envelopedData, ... }
+        assert_eq!(handler.recipients[0].encrypted_seed[0], 0x30);
+    }
+
+    #[test]
+    fn test_add_recipient_pem() {
+        let mut handler = PublicKeySecurityHandler::new_sha256();
+        handler
+            .add_recipient_pem(RSA_CERT_PEM, Permissions::all())
+            .unwrap();
+        assert_eq!(handler.recipients[0].certificate, rsa_certificate());
     }
 
     #[test]
     fn test_add_recipient_invalid_cert() {
         let mut handler = PublicKeySecurityHandler::new_sha1();
 
-        // Certificate too short
-        let certificate = vec![0x30; 50];
+        // Not a certificate
+        let certificate = vec![0x30; 200];
         let permissions = Permissions::all();
 
         let result = handler.add_recipient(certificate, permissions);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_add_recipient_non_rsa_cert() {
+        let mut handler = PublicKeySecurityHandler::new_sha1();
+        let result = handler.add_recipient_pem(ECDSA_CERT_PEM, Permissions::all());
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_generate_seed() {
-        let handler = PublicKeySecurityHandler::new_sha256();
-        let seed1 = handler.generate_seed().unwrap();
-        // Add a small delay to ensure different timestamps on fast systems
-        std::thread::sleep(std::time::Duration::from_millis(2));
-        let seed2 = handler.generate_seed().unwrap();
+        let seed1 = PublicKeySecurityHandler::generate_seed(20);
+        let seed2 = PublicKeySecurityHandler::generate_seed(20);
 
-        assert_eq!(seed1.len(), 32);
-        assert_eq!(seed2.len(), 32);
-        // On fast systems, seeds might occasionally be the same, so we test the function works correctly
-        assert!(seed1.len() == 32 && seed2.len() == 32);
+        assert_eq!(seed1.len(), 20);
+        assert_eq!(seed2.len(), 20);
+        assert_ne!(seed1, seed2);
     }
 
     #[test]
     fn test_encrypt_decrypt_seed() {
-        let handler = PublicKeySecurityHandler::new_sha1();
-        let seed = vec![0xAA; 20];
-        let certificate = vec![0x30; 200];
-
-        let encrypted = handler
-            .encrypt_seed_for_recipient(&seed, &certificate)
+        let mut handler = PublicKeySecurityHandler::new_sha1();
+        let permissions = Permissions::new().set_print(true).clone();
+        handler
+            .add_recipient(rsa_certificate(), permissions)
             .unwrap();
-        assert!(!encrypted.is_empty());
-        assert_ne!(encrypted, seed);
 
-        // Simulate decryption with private key
-        let private_key = vec![0xFF; 32];
-        let decrypted = handler.decrypt_seed(&encrypted, &private_key).unwrap();
-        assert_eq!(decrypted.len(), 20);
+        let credentials = RecipientCredentials::from_pem(RSA_KEY_PEM, RSA_CERT_PEM).unwrap();
+        let (seed, opened_permissions) = PublicKeySecurityHandler::decrypt_seed(
+            &handler.recipients[0].encrypted_seed,
+            &credentials,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(seed, handler.seed);
+        assert_eq!(opened_permissions.bits(), permissions.bits());
+
+        // Envelopes for other recipients are skipped
+        let other = RecipientCredentials::from_pem(CHAIN_KEY_PEM, CHAIN_CERT_PEM).unwrap();
+        assert!(PublicKeySecurityHandler::decrypt_seed(
+            &handler.recipients[0].encrypted_seed,
+            &other
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn test_recipient_credentials_mismatch() {
+        assert!(RecipientCredentials::from_pem(RSA_KEY_PEM, CHAIN_CERT_PEM).is_err());
+    }
+
+    #[test]
+    fn test_derive_key() {
+        let seed = [7u8; 20];
+        let recipients = vec![vec![1u8, 2, 3], vec![4u8, 5]];
+
+        let key = PublicKeySecurityHandler::derive_key(
+            &seed,
+            &recipients,
+            true,
+            CryptFilterMethod::AESV2,
+            16,
+        );
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&[1, 2, 3, 4, 5]);
+        assert_eq!(key.as_bytes(), &Sha1::digest(&input)[..16]);
+
+        let unencrypted_metadata = PublicKeySecurityHandler::derive_key(
+            &seed,
+            &recipients,
+            false,
+            CryptFilterMethod::AESV2,
+            16,
+        );
+        assert_ne!(unencrypted_metadata.as_bytes(), key.as_bytes());
+
+        let aes256 = PublicKeySecurityHandler::derive_key(
+            &seed,
+            &recipients,
+            true,
+            CryptFilterMethod::AESV3,
+            32,
+        );
+        assert_eq!(aes256.as_bytes(), Sha256::digest(&input).as_slice());
+
+        // RC4 honours the requested length; AES ignores it
+        let rc4_40 = PublicKeySecurityHandler::derive_key(
+            &seed,
+            &recipients,
+            true,
+            CryptFilterMethod::V2,
+            5,
+        );
+        assert_eq!(rc4_40.as_bytes(), &Sha1::digest(&input)[..5]);
+        let aes128 = PublicKeySecurityHandler::derive_key(
+            &seed,
+            &recipients,
+            true,
+            CryptFilterMethod::AESV2,
+            5,
+        );
+        assert_eq!(aes128.as_bytes(), key.as_bytes());
     }
 
     #[test]
     fn test_build_recipients_dict() {
         let mut handler = PublicKeySecurityHandler::new_sha1();
 
-        let cert1 = vec![0x30; 200];
         let perms1 = Permissions::new().set_print(true).clone();
-        handler.add_recipient(cert1, perms1).unwrap();
+        handler.add_recipient(rsa_certificate(), perms1).unwrap();
 
-        let cert2 = vec![0x31; 200];
         let perms2 = Permissions::all();
-        handler.add_recipient(cert2, perms2).unwrap();
+        handler.add_recipient(chain_certificate(), perms2).unwrap();
 
         let dict = handler.build_recipients_dict();
 
         if let Some(Object::Array(recipients)) = dict.get("Recipients") {
             assert_eq!(recipients.len(), 2);
-
-            // Check first recipient
-            if let Object::Dictionary(r1) = &recipients[0] {
-                assert!(r1.contains_key("Cert"));
-                assert!(r1.contains_key("P"));
-                assert!(r1.contains_key("Recipients"));
-            }
+            assert!(matches!(recipients[0], Object::String(_)));
         } else {
             panic!("Expected Recipients array");
         }
     }
 
+    #[test]
+    fn test_byte_string_escaping() {
+        assert_eq!(
+            byte_string(&[b'a', b'(', b'\\', 0x00, 0xFF, b'\n']),
+            Object::String("a\\(\\\\\\000\\377\\012".to_string())
+        );
+    }
+
     #[test]
     fn test_verify_permission() {
         let mut handler = PublicKeySecurityHandler::new_sha1();
 
-        let certificate = vec![0x30; 200];
         let permissions = Permissions::new().set_print(true).set_copy(true).clone();
-        handler.add_recipient(certificate, permissions).unwrap();
+        handler
+            .add_recipient(rsa_certificate(), permissions)
+            .unwrap();
 
         assert!(handler.verify_permission(0, Permissions::new().set_print(true).clone()));
         assert!(handler.verify_permission(0, Permissions::new().set_copy(true).clone()));
@@ -697,9 +971,28 @@ mod tests {
         assert!(encrypted.len() >= data.len());
         assert_eq!(encrypted.len() % 16, 0); // Should be multiple of block size
 
-        // Note: The simplified AES implementation might not perfectly reverse encrypt
-        // Just verify decryption doesn't panic
-        let _ = handler.decrypt_string(&encrypted, &key, &obj_id);
+        let decrypted = handler.decrypt_string(&encrypted, &key, &obj_id).unwrap();
+        assert_eq!(decrypted, data);
+
+        // Object keys differ per object
+        assert!(handler
+            .decrypt_string(&encrypted, &key, &ObjectId::new(2, 0))
+            .map(|d| d != data)
+            .unwrap_or(true));
+    }
+
+    #[test]
+    fn test_encrypt_string_aes256() {
+        let handler = PublicKeySecurityHandler::new_sha256();
+        let key = handler.encryption_key();
+        assert_eq!(key.len(), 32);
+
+        let obj_id = ObjectId::new(3, 0);
+        let encrypted = handler.encrypt_string(b"AES-256", &key, &obj_id).unwrap();
+        assert_eq!(
+            handler.decrypt_string(&encrypted, &key, &obj_id).unwrap(),
+            b"AES-256"
+        );
     }
 
     #[test]
@@ -721,15 +1014,16 @@ mod tests {
     fn test_public_key_encryption_dict() {
         let mut handler = PublicKeySecurityHandler::new_sha256();
 
-        let certificate = vec![0x30; 200];
         let permissions = Permissions::all();
-        handler.add_recipient(certificate, permissions).unwrap();
+        handler
+            .add_recipient(rsa_certificate(), permissions)
+            .unwrap();
 
         let enc_dict = PublicKeyEncryptionDict::new(&handler);
 
         assert_eq!(enc_dict.filter, "Adobe.PubSec");
-        assert_eq!(enc_dict.subfilter, SubFilter::AdbePkcs7S4);
-        assert_eq!(enc_dict.v, 4);
+        assert_eq!(enc_dict.subfilter, SubFilter::AdbePkcs7S5);
+        assert_eq!(enc_dict.v, 5);
         assert_eq!(enc_dict.length, Some(256));
         assert_eq!(enc_dict.recipients.len(), 1);
 
@@ -740,29 +1034,59 @@ mod tests {
         );
         assert_eq!(
             pdf_dict.get("SubFilter"),
-            Some(&Object::Name("adbe.pkcs7.s4".to_string()))
+            Some(&Object::Name("adbe.pkcs7.s5".to_string()))
+        );
+        assert_eq!(
+            pdf_dict.get("StmF"),
+            Some(&Object::Name("DefaultCryptFilter".to_string()))
         );
+        // adbe.pkcs7.s5 keeps the recipients in the crypt filter
+        assert!(pdf_dict.get("Recipients").is_none());
+        let Some(Object::Dictionary(cf)) = pdf_dict.get("CF") else {
+            panic!("Expected CF dictionary");
+        };
+        let Some(Object::Dictionary(filter)) = cf.get("DefaultCryptFilter") else {
+            panic!("Expected DefaultCryptFilter");
+        };
+        assert_eq!(filter.get("CFM"), Some(&Object::Name("AESV3".to_string())));
+        assert!(matches!(filter.get("Recipients"), Some(Object::Array(r)) if r.len() == 1));
+    }
+
+    #[test]
+    fn test_public_key_encryption_dict_rc4() {
+        let mut handler = PublicKeySecurityHandler::new_sha1();
+        handler
+            .add_recipient(rsa_certificate(), Permissions::all())
+            .unwrap();
+
+        let pdf_dict = PublicKeyEncryptionDict::new(&handler).to_dict();
+        assert_eq!(pdf_dict.get("V"), Some(&Object::Integer(2)));
+        assert_eq!(pdf_dict.get("Length"), Some(&Object::Integer(128)));
+        assert!(pdf_dict.get("CF").is_none());
+        assert!(matches!(pdf_dict.get("Recipients"), Some(Object::Array(r)) if r.len() == 1));
     }
 
     #[test]
     fn test_multiple_recipients() {
         let mut handler = PublicKeySecurityHandler::new_sha256();
 
-        // Add three recipients with different permissions
+        // Two recipients with different permissions
         let certs_and_perms = vec![
-            (vec![0x30; 200], Permissions::new().set_print(true).clone()),
             (
-                vec![0x31; 200],
+                rsa_certificate(),
+                Permissions::new().set_print(true).clone(),
+            ),
+            (
+                chain_certificate(),
                 Permissions::new().set_print(true).set_copy(true).clone(),
             ),
-            (vec![0x32; 200], Permissions::all()),
         ];
 
         for (cert, perms) in certs_and_perms {
             handler.add_recipient(cert, perms).unwrap();
         }
 
-        assert_eq!(handler.recipients.len(), 3);
+        assert_eq!(handler.recipients.len(), 2);
 
         // Verify each recipient's permissions
         assert!(handler.verify_permission(0, Permissions::new().set_print(true).clone()));
@@ -772,6 +1096,25 @@ mod tests {
         assert!(handler.verify_permission(1, Permissions::new().set_copy(true).clone()));
         assert!(!handler.verify_permission(1, Permissions::new().set_modify_contents(true).clone()));
 
-        assert!(handler.verify_permission(2, Permissions::all()));
+        // Both recipients recover the same seed and therefore the same key
+        let recipients = handler.recipient_strings();
+        for (key_pem, cert_pem, index) in [
+            (RSA_KEY_PEM, RSA_CERT_PEM, 0),
+            (CHAIN_KEY_PEM, CHAIN_CERT_PEM, 1),
+        ] {
+            let credentials = RecipientCredentials::from_pem(key_pem, cert_pem).unwrap();
+            let (seed, _) =
+                PublicKeySecurityHandler::decrypt_seed(&recipients[index], &credentials)
+                    .unwrap()
+                    .unwrap();
+            let key = PublicKeySecurityHandler::derive_key(
+                &seed,
+                &recipients,
+                true,
+                CryptFilterMethod::AESV3,
+                32,
+            );
+            assert_eq!(key.as_bytes(), handler.encryption_key().as_bytes());
+        }
     }
 }
@@ -0,0 +1,970 @@
+//! FDF and XFDF form data (ISO 32000-1 §12.7.8, XFDF 3.0)
+//!
+//! [`FdfData`] holds field values and annotations separately from the PDF
+//! they belong to. It reads and writes the PDF-syntax FDF format and the XML
+//! based XFDF format, and is applied to documents through
+//! [`FormFiller`](super::FormFiller) and [`FormManager`](super::FormManager).
+
+use super::FieldValue;
+use crate::error::{PdfError, Result};
+use crate::geometry::{Point, Rectangle};
+use crate::graphics::Color;
+use crate::parser::lexer::Lexer;
+use crate::parser::strings::text_bytes;
+use crate::parser::strings::text_string;
+use crate::parser::{PdfArray, PdfDictionary, PdfName, PdfObject, PdfString};
+use crate::writer::write_parsed_value;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+const XFDF_NAMESPACE: &str = "http://ns.adobe.com/xfdf/";
+
+/// Annotation subtypes by their lowercase XFDF element name
+const ANNOTATION_SUBTYPES: [&str; 19] = [
+    "Text",
+    "FreeText",
+    "Line",
+    "Square",
+    "Circle",
+    "Polygon",
+    "PolyLine",
+    "Highlight",
+    "Underline",
+    "Squiggly",
+    "StrikeOut",
+    "Stamp",
+    "Caret",
+    "Ink",
+    "FileAttachment",
+    "Sound",
+    "Redact",
+    "Popup",
+    "Link",
+];
+
+/// Annotation flag names used by XFDF's `flags` attribute, by bit
+const ANNOTATION_FLAGS: [&str; 10] = [
+    "invisible",
+    "hidden",
+    "print",
+    "nozoom",
+    "norotate",
+    "noview",
+    "readonly",
+    "locked",
+    "togglenoview",
+    "lockedcontents",
+];
+
+/// Value of one field in exchanged form data
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdfField {
+    /// Fully qualified field name
+    pub name: String,
+    /// Field value
+    pub value: FieldValue,
+}
+
+/// Annotation in exchanged form data.
+///
+/// Only the entries common to markup annotations are kept, plus the quad
+/// points of text markup and the icon of text annotations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdfAnnotation {
+    /// Annotation subtype, such as `Text` or `Highlight`
+    pub subtype: String,
+    /// Zero-based page index
+    pub page: u32,
+    /// Annotation rectangle
+    pub rect: Rectangle,
+    /// Text of the annotation (`/Contents`)
+    pub contents: Option<String>,
+    /// Author (`/T`)
+    pub author: Option<String>,
+    /// Subject (`/Subj`)
+    pub subject: Option<String>,
+    /// Unique name (`/NM`)
+    pub name: Option<String>,
+    /// Modification date as a PDF date string (`/M`)
+    pub modified: Option<String>,
+    /// Annotation color (`/C`)
+    pub color: Option<Color>,
+    /// Annotation flags (`/F`)
+    pub flags: u32,
+    /// Quadrilaterals of text markup annotations (`/QuadPoints`)
+    pub quad_points: Vec<f64>,
+    /// Icon of text annotations (`/Name`)
+    pub icon: Option<String>,
+}
+
+impl FdfAnnotation {
+    /// Create an annotation with only the required entries
+    pub fn new(subtype: impl Into<String>, page: u32, rect: Rectangle) -> Self {
+        Self {
+            subtype: subtype.into(),
+            page,
+            rect,
+            contents: None,
+            author: None,
+            subject: None,
+            name: None,
+            modified: None,
+            color: None,
+            flags: 0,
+            quad_points: Vec::new(),
+            icon: None,
+        }
+    }
+}
+
+/// Form data exchanged with FDF and XFDF files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FdfData {
+    /// PDF file the data belongs to (`/F`, `<f href>`)
+    pub file: Option<String>,
+    /// Field values in document order
+    pub fields: Vec<FdfField>,
+    /// Annotations
+    pub annotations: Vec<FdfAnnotation>,
+}
+
+impl FdfData {
+    /// Create empty form data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of a field, replacing an earlier value
+    pub fn set_field(&mut self, name: impl Into<String>, value: FieldValue) {
+        let name = name.into();
+        match self.fields.iter_mut().find(|f| f.name == name) {
+            Some(field) => field.value = value,
+            None => self.fields.push(FdfField { name, value }),
+        }
+    }
+
+    /// Value of a field
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| &f.value)
+    }
+
+    /// Parse FDF or XFDF, detected from the content
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let start = data
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(0);
+        if data[start..].starts_with(b"<") || data[start..].starts_with(b"\xEF\xBB\xBF<") {
+            let xml = std::str::from_utf8(&data[start..])
+                .map_err(|e| PdfError::ParseError(format!("XFDF is not UTF-8: {e}")))?;
+            Self::from_xfdf(xml.trim_start_matches('\u{FEFF}'))
+        } else {
+            Self::from_fdf(data)
+        }
+    }
+
+    /// Serialize as an FDF file
+    pub fn to_fdf(&self) -> Vec<u8> {
+        let annotation_count = self.annotations.len() as u32;
+        let mut fdf = PdfDictionary::new();
+        if let Some(file) = &self.file {
+            fdf.insert("F".to_string(), string_object(file));
+        }
+        if !self.fields.is_empty() {
+            let fields = field_tree(&self.fields)
+                .iter()
+                .map(fdf_field)
+                .collect::<Vec<_>>();
+            fdf.insert("Fields".to_string(), PdfObject::Array(PdfArray(fields)));
+        }
+        if annotation_count > 0 {
+            let annots = (0..annotation_count)
+                .map(|i| PdfObject::Reference(i + 2, 0))
+                .collect();
+            fdf.insert("Annots".to_string(), PdfObject::Array(PdfArray(annots)));
+        }
+        let mut root = PdfDictionary::new();
+        root.insert("FDF".to_string(), PdfObject::Dictionary(fdf));
+
+        let mut out = b"%FDF-1.2\n%\xE2\xE3\xCF\xD3\n1 0 obj\n".to_vec();
+        write_parsed_value(&PdfObject::Dictionary(root), &mut out);
+        out.extend_from_slice(b"\nendobj\n");
+        for (i, annotation) in self.annotations.iter().enumerate() {
+            let mut dict = annotation_to_dict(annotation);
+            dict.insert(
+                "Page".to_string(),
+                PdfObject::Integer(i64::from(annotation.page)),
+            );
+            out.extend_from_slice(format!("{} 0 obj\n", i + 2).as_bytes());
+            write_parsed_value(&PdfObject::Dictionary(dict), &mut out);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        out.extend_from_slice(b"trailer\n<< /Root 1 0 R >>\n%%EOF\n");
+        out
+    }
+
+    /// Parse an FDF file
+    pub fn from_fdf(data: &[u8]) -> Result<Self> {
+        if !data.starts_with(b"%FDF-") {
+            return Err(PdfError::ParseError("Missing %FDF header".to_string()));
+        }
+        let objects = fdf_objects(data);
+        let resolve = |object: &PdfObject| -> PdfObject {
+            match object {
+                PdfObject::Reference(num, gen) => objects
+                    .get(&(*num, *gen))
+                    .cloned()
+                    .unwrap_or(PdfObject::Null),
+                _ => object.clone(),
+            }
+        };
+
+        let root = fdf_root(data)
+            .and_then(|id| objects.get(&id))
+            .or_else(|| {
+                objects
+                    .values()
+                    .find(|o| o.as_dict().is_some_and(|d| d.contains_key("FDF")))
+            })
+            .and_then(|o| o.as_dict())
+            .ok_or_else(|| PdfError::ParseError("FDF catalog not found".to_string()))?;
+        let fdf = root
+            .get("FDF")
+            .map(&resolve)
+            .and_then(|f| f.as_dict().cloned())
+            .ok_or_else(|| PdfError::ParseError("FDF dictionary not found".to_string()))?;
+
+        let mut data = FdfData {
+            file: fdf.get("F").map(&resolve).and_then(|f| file_name(&f)),
+            ..Default::default()
+        };
+        if let Some(PdfObject::Array(fields)) = fdf.get("Fields").map(&resolve) {
+            read_fdf_fields(&fields.0, "", &resolve, 0, &mut data.fields);
+        }
+        if let Some(PdfObject::Array(annots)) = fdf.get("Annots").map(&resolve) {
+            for annot in &annots.0 {
+                let annot = resolve(annot);
+                let Some(dict) = annot.as_dict() else {
+                    continue;
+                };
+                let page = dict.get("Page").and_then(|p| p.as_integer()).unwrap_or(0);
+                if let Some(annotation) = annotation_from_dict(dict, page as u32) {
+                    data.annotations.push(annotation);
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Serialize as an XFDF document
+    pub fn to_xfdf(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<xfdf xmlns=\"{XFDF_NAMESPACE}\" xml:space=\"preserve\">\n"
+        ));
+        if let Some(file) = &self.file {
+            xml.push_str(&format!("  <f href=\"{}\"/>\n", escape_xml(file)));
+        }
+        if !self.fields.is_empty() {
+            xml.push_str("  <fields>\n");
+            for node in field_tree(&self.fields) {
+                write_xfdf_field(&node, 2, &mut xml);
+            }
+            xml.push_str("  </fields>\n");
+        }
+        if !self.annotations.is_empty() {
+            xml.push_str("  <annots>\n");
+            for annotation in &self.annotations {
+                write_xfdf_annotation(annotation, &mut xml);
+            }
+            xml.push_str("  </annots>\n");
+        }
+        xml.push_str("</xfdf>\n");
+        xml
+    }
+
+    /// Parse an XFDF document
+    pub fn from_xfdf(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        let mut data = FdfData::new();
+        let mut seen_root = false;
+
+        // Open <field> elements with the values found so far
+        let mut fields: Vec<(String, Vec<String>, bool)> = Vec::new();
+        let mut in_annots = false;
+        let mut annotation: Option<(FdfAnnotation, usize)> = None;
+        let mut depth = 0usize;
+        let mut text: Option<String> = None;
+
+        loop {
+            let event = reader
+                .read_event_into(&mut buf)
+                .map_err(|e| PdfError::ParseError(format!("Invalid XFDF: {e}")))?;
+            match event {
+                Event::Start(ref e) => {
+                    depth += 1;
+                    let name = local_name(e);
+                    match name.as_str() {
+                        "xfdf" => seen_root = true,
+                        "field" if annotation.is_none() => {
+                            fields.push((
+                                attribute(e, "name").unwrap_or_default(),
+                                Vec::new(),
+                                false,
+                            ));
+                        }
+                        "value" if !fields.is_empty() => text = Some(String::new()),
+                        "annots" => in_annots = true,
+                        "contents" if annotation.is_some() => text = Some(String::new()),
+                        _ if in_annots && annotation.is_none() => {
+                            annotation = xfdf_annotation(&name, e).map(|a| (a, depth));
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Empty(ref e) => {
+                    let name = local_name(e);
+                    match name.as_str() {
+                        "f" => data.file = attribute(e, "href"),
+                        "field" if annotation.is_none() => {
+                            let name = qualified_name(&fields, attribute(e, "name"));
+                            data.fields.push(FdfField {
+                                name,
+                                value: FieldValue::None,
+                            });
+                        }
+                        "value" => {
+                            if let Some(field) = fields.last_mut() {
+                                field.1.push(String::new());
+                            }
+                        }
+                        _ if in_annots && annotation.is_none() => {
+                            data.annotations.extend(xfdf_annotation(&name, e));
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Text(e) => {
+                    if let Some(text) = text.as_mut() {
+                        let unescaped = e
+                            .unescape()
+                            .map_err(|e| PdfError::ParseError(format!("Invalid XFDF: {e}")))?;
+                        text.push_str(&unescaped);
+                    }
+                }
+                Event::CData(e) => {
+                    if let Some(text) = text.as_mut() {
+                        text.push_str(&String::from_utf8_lossy(&e));
+                    }
+                }
+                Event::End(ref e) => {
+                    let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                    match name.as_str() {
+                        "value" => {
+                            if let (Some(value), Some(field)) = (text.take(), fields.last_mut()) {
+                                field.1.push(value);
+                            }
+                        }
+                        "contents" => {
+                            if let (Some(contents), Some((annotation, _))) =
+                                (text.take(), annotation.as_mut())
+                            {
+                                annotation.contents = Some(contents);
+                            }
+                        }
+                        "field" if annotation.is_none() => {
+                            if let Some((partial, values, has_kids)) = fields.pop() {
+                                let name = qualified_name(&fields, Some(partial));
+                                if let Some(parent) = fields.last_mut() {
+                                    parent.2 = true;
+                                }
+                                let value = match values.len() {
+                                    0 if has_kids => None,
+                                    0 => Some(FieldValue::None),
+                                    1 => values.into_iter().next().map(FieldValue::Text),
+                                    _ => Some(FieldValue::Choices(values)),
+                                };
+                                if let Some(value) = value {
+                                    data.fields.push(FdfField { name, value });
+                                }
+                            }
+                        }
+                        "annots" => in_annots = false,
+                        _ => {}
+                    }
+                    if annotation.as_ref().is_some_and(|(_, d)| *d == depth) {
+                        data.annotations.extend(annotation.take().map(|(a, _)| a));
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        if !seen_root {
+            return Err(PdfError::ParseError(
+                "Missing <xfdf> root element".to_string(),
+            ));
+        }
+        Ok(data)
+    }
+}
+
+/// Field names split into the partial-name tree FDF and XFDF use
+struct FieldNode<'a> {
+    partial: String,
+    value: Option<&'a FieldValue>,
+    kids: Vec<FieldNode<'a>>,
+}
+
+fn field_tree(fields: &[FdfField]) -> Vec<FieldNode<'_>> {
+    let mut roots: Vec<FieldNode> = Vec::new();
+    for field in fields {
+        let mut level = &mut roots;
+        let parts: Vec<&str> = field.name.split('.').collect();
+        for (i, part) in parts.iter().enumerate() {
+            let index = match level.iter().position(|n| n.partial == *part) {
+                Some(index) => index,
+                None => {
+                    level.push(FieldNode {
+                        partial: part.to_string(),
+                        value: None,
+                        kids: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+            if i == parts.len() - 1 {
+                level[index].value = Some(&field.value);
+            }
+            level = &mut level[index].kids;
+        }
+    }
+    roots
+}
+
+fn fdf_field(node: &FieldNode) -> PdfObject {
+    let mut dict = PdfDictionary::new();
+    dict.insert("T".to_string(), string_object(&node.partial));
+    let value = match node.value {
+        Some(FieldValue::Text(text)) => Some(string_object(text)),
+        Some(FieldValue::State(state)) => Some(PdfObject::Name(PdfName::new(state.clone()))),
+        Some(FieldValue::Choices(choices)) if choices.len() == 1 => {
+            Some(string_object(&choices[0]))
+        }
+        Some(FieldValue::Choices(choices)) => Some(PdfObject::Array(PdfArray(
+            choices.iter().map(|c| string_object(c)).collect(),
+        ))),
+        Some(FieldValue::None) | None => None,
+    };
+    if let Some(value) = value {
+        dict.insert("V".to_string(), value);
+    }
+    if !node.kids.is_empty() {
+        dict.insert(
+            "Kids".to_string(),
+            PdfObject::Array(PdfArray(node.kids.iter().map(fdf_field).collect())),
+        );
+    }
+    PdfObject::Dictionary(dict)
+}
+
+fn read_fdf_fields(
+    fields: &[PdfObject],
+    prefix: &str,
+    resolve: &impl Fn(&PdfObject) -> PdfObject,
+    depth: usize,
+    collected: &mut Vec<FdfField>,
+) {
+    if depth > 32 {
+        return;
+    }
+    for field in fields {
+        let field = resolve(field);
+        let Some(dict) = field.as_dict() else {
+            continue;
+        };
+        let partial = dict
+            .get("T")
+            .and_then(|t| t.as_string())
+            .map(|t| text_string(t.as_bytes()))
+            .unwrap_or_default();
+        let name = match (prefix.is_empty(), partial.is_empty()) {
+            (true, _) => partial,
+            (false, true) => prefix.to_string(),
+            (false, false) => format!("{prefix}.{partial}"),
+        };
+        let value = match dict.get("V").map(resolve) {
+            Some(PdfObject::String(text)) => Some(FieldValue::Text(text_string(text.as_bytes()))),
+            Some(PdfObject::Name(state)) => Some(FieldValue::State(state.as_str().to_string())),
+            Some(PdfObject::Array(choices)) => Some(FieldValue::Choices(
+                choices
+                    .0
+                    .iter()
+                    .filter_map(|c| c.as_string())
+                    .map(|c| text_string(c.as_bytes()))
+                    .collect(),
+            )),
+            _ => None,
+        };
+        if let Some(value) = value {
+            collected.push(FdfField {
+                name: name.clone(),
+                value,
+            });
+        }
+        if let Some(PdfObject::Array(kids)) = dict.get("Kids").map(resolve) {
+            read_fdf_fields(&kids.0, &name, resolve, depth + 1, collected);
+        }
+    }
+}
+
+/// Indirect objects of an FDF file, found by scanning for `obj` keywords
+/// since FDF files rarely carry a cross-reference table
+fn fdf_objects(data: &[u8]) -> HashMap<(u32, u16), PdfObject> {
+    let mut objects = HashMap::new();
+    let mut search = 0;
+    while let Some(found) = find(&data[search..], b"obj") {
+        let keyword = search + found;
+        search = keyword + 3;
+        if data
+            .get(keyword + 3)
+            .is_some_and(|b| b.is_ascii_alphanumeric())
+        {
+            continue;
+        }
+        let Some((num, gen)) = object_header(&data[..keyword]) else {
+            continue;
+        };
+        let mut lexer = Lexer::new(Cursor::new(&data[keyword + 3..]));
+        if let Ok(object) = PdfObject::parse(&mut lexer) {
+            objects.insert((num, gen), object);
+        }
+    }
+    objects
+}
+
+/// Object number and generation right before an `obj` keyword
+fn object_header(before: &[u8]) -> Option<(u32, u16)> {
+    let text = String::from_utf8_lossy(&before[before.len().saturating_sub(24)..]);
+    let mut words = text.split_ascii_whitespace().rev();
+    let gen = words.next()?.parse().ok()?;
+    let num = words.next()?;
+    let num = num
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((num, gen))
+}
+
+/// `/Root` reference of the FDF trailer
+fn fdf_root(data: &[u8]) -> Option<(u32, u16)> {
+    let trailer = find(data, b"trailer")?;
+    let mut lexer = Lexer::new(Cursor::new(&data[trailer + 7..]));
+    let trailer = PdfObject::parse(&mut lexer).ok()?;
+    trailer.as_dict()?.get("Root")?.as_reference()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// File name from a file specification string or dictionary
+fn file_name(spec: &PdfObject) -> Option<String> {
+    match spec {
+        PdfObject::String(name) => Some(text_string(name.as_bytes())),
+        PdfObject::Dictionary(dict) => ["UF", "F"]
+            .iter()
+            .find_map(|key| dict.get(key).and_then(|f| f.as_string()))
+            .map(|name| text_string(name.as_bytes())),
+        _ => None,
+    }
+}
+
+/// Annotation entries of an annotation dictionary; `None` for form widgets
+/// and dictionaries without a usable rectangle
+pub(crate) fn annotation_from_dict(dict: &PdfDictionary, page: u32) -> Option<FdfAnnotation> {
+    let subtype = dict.get("Subtype")?.as_name()?.as_str().to_string();
+    if subtype == "Widget" {
+        return None;
+    }
+    let rect = dict.get("Rect")?.as_array()?;
+    let rect: Vec<f64> = rect.0.iter().filter_map(|v| v.as_real()).collect();
+    let [x0, y0, x1, y1] = rect[..] else {
+        return None;
+    };
+    let text = |key: &str| {
+        dict.get(key)
+            .and_then(|t| t.as_string())
+            .map(|t| text_string(t.as_bytes()))
+    };
+    let color = dict.get("C").and_then(|c| c.as_array()).and_then(|c| {
+        let components: Vec<f64> = c.0.iter().filter_map(|v| v.as_real()).collect();
+        match components[..] {
+            [gray] => Some(Color::gray(gray)),
+            [r, g, b] => Some(Color::rgb(r, g, b)),
+            [c, m, y, k] => Some(Color::cmyk(c, m, y, k)),
+            _ => None,
+        }
+    });
+
+    Some(FdfAnnotation {
+        subtype,
+        page,
+        rect: Rectangle::new(
+            Point::new(x0.min(x1), y0.min(y1)),
+            Point::new(x0.max(x1), y0.max(y1)),
+        ),
+        contents: text("Contents"),
+        author: text("T"),
+        subject: text("Subj"),
+        name: text("NM"),
+        modified: text("M"),
+        color,
+        flags: dict.get("F").and_then(|f| f.as_integer()).unwrap_or(0) as u32,
+        quad_points: dict
+            .get("QuadPoints")
+            .and_then(|q| q.as_array())
+            .map(|q| q.0.iter().filter_map(|v| v.as_real()).collect())
+            .unwrap_or_default(),
+        icon: dict
+            .get("Name")
+            .and_then(|n| n.as_name())
+            .map(|n| n.as_str().to_string()),
+    })
+}
+
+/// Annotation dictionary without the page entries
+pub(crate) fn annotation_to_dict(annotation: &FdfAnnotation) -> PdfDictionary {
+    let mut dict = PdfDictionary::new();
+    let name = |name: &str| PdfObject::Name(PdfName::new(name.to_string()));
+    let numbers = |values: &[f64]| {
+        PdfObject::Array(PdfArray(
+            values.iter().map(|&v| PdfObject::Real(v)).collect(),
+        ))
+    };
+    dict.insert("Type".to_string(), name("Annot"));
+    dict.insert("Subtype".to_string(), name(&annotation.subtype));
+    let rect = &annotation.rect;
+    dict.insert(
+        "Rect".to_string(),
+        numbers(&[
+            rect.lower_left.x,
+            rect.lower_left.y,
+            rect.upper_right.x,
+            rect.upper_right.y,
+        ]),
+    );
+    for (key, value) in [
+        ("Contents", &annotation.contents),
+        ("T", &annotation.author),
+        ("Subj", &annotation.subject),
+        ("NM", &annotation.name),
+        ("M", &annotation.modified),
+    ] {
+        if let Some(value) = value {
+            dict.insert(key.to_string(), string_object(value));
+        }
+    }
+    if let Some(color) = &annotation.color {
+        let components = match *color {
+            Color::Gray(gray) => vec![gray],
+            Color::Rgb(r, g, b) => vec![r, g, b],
+            Color::Cmyk(c, m, y, k) => vec![c, m, y, k],
+        };
+        dict.insert("C".to_string(), numbers(&components));
+    }
+    if annotation.flags != 0 {
+        dict.insert(
+            "F".to_string(),
+            PdfObject::Integer(i64::from(annotation.flags)),
+        );
+    }
+    if !annotation.quad_points.is_empty() {
+        dict.insert("QuadPoints".to_string(), numbers(&annotation.quad_points));
+    }
+    if let Some(icon) = &annotation.icon {
+        dict.insert("Name".to_string(), name(icon));
+    }
+    dict
+}
+
+fn string_object(text: &str) -> PdfObject {
+    PdfObject::String(PdfString::new(text_bytes(text)))
+}
+
+fn write_xfdf_field(node: &FieldNode, indent: usize, xml: &mut String) {
+    let pad = "  ".repeat(indent);
+    let values: Vec<&str> = match node.value {
+        Some(FieldValue::Text(text)) | Some(FieldValue::State(text)) => vec![text.as_str()],
+        Some(FieldValue::Choices(choices)) => choices.iter().map(String::as_str).collect(),
+        Some(FieldValue::None) | None => Vec::new(),
+    };
+    let name = escape_xml(&node.partial);
+    if values.is_empty() && node.kids.is_empty() {
+        xml.push_str(&format!("{pad}<field name=\"{name}\"/>\n"));
+        return;
+    }
+    xml.push_str(&format!("{pad}<field name=\"{name}\">\n"));
+    for value in values {
+        xml.push_str(&format!("{pad}  <value>{}</value>\n", escape_xml(value)));
+    }
+    for kid in &node.kids {
+        write_xfdf_field(kid, indent + 1, xml);
+    }
+    xml.push_str(&format!("{pad}</field>\n"));
+}
+
+fn write_xfdf_annotation(annotation: &FdfAnnotation, xml: &mut String) {
+    let element = annotation.subtype.to_ascii_lowercase();
+    let rect = &annotation.rect;
+    xml.push_str(&format!(
+        "    <{element} page=\"{}\" rect=\"{},{},{},{}\"",
+        annotation.page,
+        rect.lower_left.x,
+        rect.lower_left.y,
+        rect.upper_right.x,
+        rect.upper_right.y
+    ));
+    if let Some(color) = &annotation.color {
+        let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        xml.push_str(&format!(
+            " color=\"#{:02X}{:02X}{:02X}\"",
+            channel(color.r()),
+            channel(color.g()),
+            channel(color.b())
+        ));
+    }
+    for (attribute, value) in [
+        ("name", &annotation.name),
+        ("title", &annotation.author),
+        ("subject", &annotation.subject),
+        ("date", &annotation.modified),
+        ("icon", &annotation.icon),
+    ] {
+        if let Some(value) = value {
+            xml.push_str(&format!(" {attribute}=\"{}\"", escape_xml(value)));
+        }
+    }
+    if annotation.flags != 0 {
+        let flags: Vec<&str> = ANNOTATION_FLAGS
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| annotation.flags & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        xml.push_str(&format!(" flags=\"{}\"", flags.join(",")));
+    }
+    if !annotation.quad_points.is_empty() {
+        let coords: Vec<String> = annotation
+            .quad_points
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        xml.push_str(&format!(" coords=\"{}\"", coords.join(",")));
+    }
+    match &annotation.contents {
+        Some(contents) => xml.push_str(&format!(
+            ">\n      <contents>{}</contents>\n    </{element}>\n",
+            escape_xml(contents)
+        )),
+        None => xml.push_str("/>\n"),
+    }
+}
+
+/// Annotation from an XFDF element inside `<annots>`
+fn xfdf_annotation(element: &str, e: &BytesStart) -> Option<FdfAnnotation> {
+    let subtype = ANNOTATION_SUBTYPES
+        .iter()
+        .find(|s| s.eq_ignore_ascii_case(element))
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| {
+            let mut chars = element.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        });
+    let numbers = |value: String| -> Vec<f64> {
+        value
+            .split(',')
+            .filter_map(|v| v.trim().parse().ok())
+            .collect()
+    };
+    let rect = numbers(attribute(e, "rect")?);
+    let [x0, y0, x1, y1] = rect[..] else {
+        return None;
+    };
+
+    let mut annotation = FdfAnnotation::new(
+        subtype,
+        attribute(e, "page")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(0),
+        Rectangle::new(
+            Point::new(x0.min(x1), y0.min(y1)),
+            Point::new(x0.max(x1), y0.max(y1)),
+        ),
+    );
+    annotation.author = attribute(e, "title");
+    annotation.subject = attribute(e, "subject");
+    annotation.name = attribute(e, "name");
+    annotation.modified = attribute(e, "date");
+    annotation.icon = attribute(e, "icon");
+    annotation.color = attribute(e, "color")
+        .filter(|c| c.len() == 7 && c.starts_with('#'))
+        .map(|c| Color::hex(&c));
+    annotation.flags = attribute(e, "flags")
+        .map(|flags| {
+            flags
+                .split(',')
+                .filter_map(|f| ANNOTATION_FLAGS.iter().position(|n| *n == f.trim()))
+                .fold(0, |bits, bit| bits | (1 << bit))
+        })
+        .unwrap_or(0);
+    annotation.quad_points = attribute(e, "coords").map(numbers).unwrap_or_default();
+    Some(annotation)
+}
+
+fn local_name(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.local_name().as_ref()).to_string()
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name.as_bytes())
+        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+}
+
+/// Fully qualified name of a field nested in the open `<field>` elements
+fn qualified_name(open: &[(String, Vec<String>, bool)], partial: Option<String>) -> String {
+    open.iter()
+        .map(|(name, _, _)| name.as_str())
+        .chain(partial.as_deref())
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FdfData {
+        let mut data = FdfData::new();
+        data.file = Some("claim form.pdf".to_string());
+        data.set_field(
+            "applicant.name",
+            FieldValue::Text("Zoë (O'Brien) <&>".to_string()),
+        );
+        data.set_field("applicant.id", FieldValue::Text("  42".to_string()));
+        data.set_field("agree", FieldValue::State("Yes".to_string()));
+        data.set_field(
+            "colors",
+            FieldValue::Choices(vec!["Red".to_string(), "Blue".to_string()]),
+        );
+
+        let mut note = FdfAnnotation::new(
+            "Text",
+            1,
+            Rectangle::new(Point::new(10.0, 20.0), Point::new(30.0, 40.0)),
+        );
+        note.contents = Some("Check \"this\"".to_string());
+        note.author = Some("Reviewer".to_string());
+        note.name = Some("note-1".to_string());
+        note.modified = Some("D:20240102030405Z".to_string());
+        note.color = Some(Color::rgb(1.0, 0.0, 0.0));
+        note.flags = 4;
+        note.icon = Some("Comment".to_string());
+        let mut highlight = FdfAnnotation::new(
+            "Highlight",
+            0,
+            Rectangle::new(Point::new(50.0, 700.0), Point::new(150.0, 712.0)),
+        );
+        highlight.quad_points = vec![50.0, 712.0, 150.0, 712.0, 50.0, 700.0, 150.0, 700.0];
+        data.annotations = vec![note, highlight];
+        data
+    }
+
+    #[test]
+    fn test_fdf_round_trip() {
+        let data = sample();
+        let fdf = data.to_fdf();
+        assert!(fdf.starts_with(b"%FDF-1.2"));
+        assert_eq!(FdfData::from_fdf(&fdf).unwrap(), data);
+        assert_eq!(FdfData::from_bytes(&fdf).unwrap(), data);
+    }
+
+    #[test]
+    fn test_xfdf_round_trip() {
+        let data = sample();
+        let xfdf = data.to_xfdf();
+        assert!(xfdf.contains("<field name=\"applicant\">"));
+        assert!(xfdf.contains("flags=\"print\""));
+        let parsed = FdfData::from_xfdf(&xfdf).unwrap();
+        // XFDF does not tell button states from text
+        assert_eq!(
+            parsed.field("agree"),
+            Some(&FieldValue::Text("Yes".to_string()))
+        );
+        assert_eq!(parsed.field("applicant.id"), data.field("applicant.id"));
+        assert_eq!(parsed.field("applicant.name"), data.field("applicant.name"));
+        assert_eq!(parsed.field("colors"), data.field("colors"));
+        assert_eq!(parsed.annotations, data.annotations);
+        assert_eq!(parsed.file, data.file);
+        assert_eq!(FdfData::from_bytes(xfdf.as_bytes()).unwrap(), parsed);
+    }
+
+    #[test]
+    fn test_parse_foreign_files() {
+        let fdf = b"%FDF-1.2\n%\xE2\xE3\xCF\xD3\n1 0 obj\n<</FDF<</F(form.pdf)\
+            /Fields[<</T(a)/Kids[<</T(b)/V(x)>>]>><</T(c)/V/Off>>]/Annots[2 0 R]>>>>\nendobj\n\
+            2 0 obj\n<</Type/Annot/Subtype/Square/Page 3/Rect[1 2 3 4]/C[0]>>\nendobj\n\
+            trailer\n<</Root 1 0 R>>\n%%EOF\n";
+        let data = FdfData::from_fdf(fdf).unwrap();
+        assert_eq!(data.file.as_deref(), Some("form.pdf"));
+        assert_eq!(data.field("a.b"), Some(&FieldValue::Text("x".to_string())));
+        assert_eq!(data.field("c"), Some(&FieldValue::State("Off".to_string())));
+        assert_eq!(data.annotations[0].page, 3);
+        assert_eq!(data.annotations[0].color, Some(Color::gray(0.0)));
+
+        let xfdf = r##"<?xml version="1.0" encoding="UTF-8"?>
+<xfdf xmlns="http://ns.adobe.com/xfdf/" xml:space="preserve">
+<fields><field name="a"><field name="b"><value>x &amp; y</value></field></field>
+<field name="empty"/></fields>
+<annots><square page="2" rect="1,2,3,4" color="#00FF00" title="Me"><popup rect="0,0,1,1"/>
+<contents>Hi</contents></square></annots>
+</xfdf>"##;
+        let data = FdfData::from_xfdf(xfdf).unwrap();
+        assert_eq!(
+            data.field("a.b"),
+            Some(&FieldValue::Text("x & y".to_string()))
+        );
+        assert_eq!(data.field("empty"), Some(&FieldValue::None));
+        assert_eq!(data.annotations.len(), 1);
+        assert_eq!(data.annotations[0].subtype, "Square");
+        assert_eq!(data.annotations[0].contents.as_deref(), Some("Hi"));
+        assert_eq!(data.annotations[0].author.as_deref(), Some("Me"));
+
+        assert!(FdfData::from_fdf(b"%PDF-1.7").is_err());
+        assert!(FdfData::from_xfdf("<fields/>").is_err());
+    }
+}
@@ -0,0 +1,1868 @@
+//! Filling and flattening the interactive form of existing documents
+//! (ISO 32000-1 §12.7)
+//!
+//! [`FormFiller`] reads the AcroForm field tree of a parsed PDF, sets values
+//! with regenerated appearance streams and saves the changes as an
+//! incremental update, optionally burning the widgets into page content.
+
+use super::fdf::{annotation_from_dict, annotation_to_dict};
+use super::{
+    AppearanceGenerator, AppearanceState, AppearanceStream, BorderStyle, CheckBoxAppearance,
+    ComboBoxAppearance, FdfData, ListBoxAppearance, RadioButtonAppearance, TextFieldAppearance,
+    Widget, WidgetAppearance,
+};
+use crate::error::{PdfError, Result};
+use crate::geometry::{Point, Rectangle};
+use crate::graphics::Color;
+use crate::objects::{Object, ObjectId};
+use crate::parser::strings::{text_bytes, text_string};
+use crate::parser::{
+    PdfArray, PdfDictionary, PdfDocument, PdfName, PdfObject, PdfReader, PdfStream, PdfString,
+};
+use crate::text::{measure_text, Font};
+use crate::writer::{PdfWriter, WriterConfig};
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Maximum nesting of the field tree before giving up
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// Field flags (`/Ff`) used by the filler
+const READ_ONLY: u32 = 1;
+const REQUIRED: u32 = 1 << 1;
+const MULTILINE: u32 = 1 << 12;
+const RADIO: u32 = 1 << 15;
+const PUSHBUTTON: u32 = 1 << 16;
+const COMBO: u32 = 1 << 17;
+const EDIT: u32 = 1 << 18;
+const MULTI_SELECT: u32 = 1 << 21;
+
+/// Annotation flags (`/F`) that keep a widget off the page
+const HIDDEN_OR_NO_VIEW: i64 = (1 << 1) | (1 << 5);
+
+const STANDARD_FONTS: [Font; 14] = [
+    Font::Helvetica,
+    Font::HelveticaBold,
+    Font::HelveticaOblique,
+    Font::HelveticaBoldOblique,
+    Font::TimesRoman,
+    Font::TimesBold,
+    Font::TimesItalic,
+    Font::TimesBoldItalic,
+    Font::Courier,
+    Font::CourierBold,
+    Font::CourierOblique,
+    Font::CourierBoldOblique,
+    Font::Symbol,
+    Font::ZapfDingbats,
+];
+
+/// Kind of an existing field, from `/FT` and the field flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    CheckBox,
+    RadioGroup,
+    PushButton,
+    ComboBox,
+    ListBox,
+    Signature,
+    Unknown,
+}
+
+/// Value of an existing field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// No value set
+    None,
+    /// Contents of a text field
+    Text(String),
+    /// Appearance state of a button, `Off` when unchecked
+    State(String),
+    /// Selected export values of a choice field
+    Choices(Vec<String>),
+}
+
+/// Option of a choice field
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceOption {
+    /// Value stored in the field when selected
+    pub export: String,
+    /// Text shown to the user
+    pub display: String,
+}
+
+/// Widget annotation of an existing field
+#[derive(Debug, Clone)]
+pub struct FieldWidget {
+    /// Zero-based index of the page showing the widget, if it is listed in one
+    pub page: Option<u32>,
+    /// Widget rectangle in default user space
+    pub rect: Rectangle,
+    /// Appearance state shown when a check box or radio button is on
+    pub on_state: Option<String>,
+    id: ObjectId,
+}
+
+/// Terminal field of an existing document's interactive form
+#[derive(Debug, Clone)]
+pub struct ExistingField {
+    /// Fully qualified field name
+    pub name: String,
+    /// Field kind
+    pub kind: FieldKind,
+    /// Current value (`/V`)
+    pub value: FieldValue,
+    /// Value the field resets to (`/DV`)
+    pub default_value: FieldValue,
+    /// Options of a choice field
+    pub options: Vec<ChoiceOption>,
+    /// Field flags (`/Ff`)
+    pub flags: u32,
+    /// Maximum length of a text field
+    pub max_len: Option<usize>,
+    /// Widget annotations showing the field
+    pub widgets: Vec<FieldWidget>,
+    id: ObjectId,
+    default_appearance: Option<String>,
+    quadding: i64,
+}
+
+impl ExistingField {
+    /// Whether the user may not change the field
+    pub fn is_read_only(&self) -> bool {
+        self.flags & READ_ONLY != 0
+    }
+
+    /// Whether the field must have a value when the form is submitted
+    pub fn is_required(&self) -> bool {
+        self.flags & REQUIRED != 0
+    }
+}
+
+/// Inheritable field attributes accumulated while walking the tree
+#[derive(Debug, Clone, Default)]
+struct Inherited {
+    field_type: Option<String>,
+    flags: u32,
+    value: Option<PdfObject>,
+    default_value: Option<PdfObject>,
+    default_appearance: Option<String>,
+    quadding: i64,
+    max_len: Option<usize>,
+    options: Option<PdfObject>,
+}
+
+/// Text style from a field's default appearance string (`/DA`)
+struct TextStyle {
+    font: Font,
+    size: f64,
+    color: Color,
+}
+
+/// Fills and flattens the AcroForm of an existing PDF.
+///
+/// Changes are kept as object revisions and written by [`save`](Self::save)
+/// as an incremental update, leaving the original bytes untouched.
+pub struct FormFiller {
+    pdf: Vec<u8>,
+    document: PdfDocument<Cursor<Vec<u8>>>,
+    fields: Vec<ExistingField>,
+    changes: HashMap<ObjectId, PdfObject>,
+    first_new_number: u32,
+    next_number: u32,
+}
+
+impl FormFiller {
+    /// Parse `pdf` and read its field tree
+    pub fn new(pdf: Vec<u8>) -> Result<Self> {
+        let reader = PdfReader::new(Cursor::new(pdf.clone()))?;
+        let document = PdfDocument::new(reader);
+        let next_number = document.trailer().size()?;
+        let mut filler = Self {
+            pdf,
+            document,
+            fields: Vec::new(),
+            changes: HashMap::new(),
+            first_new_number: next_number,
+            next_number,
+        };
+        filler.fields = filler.read_fields()?;
+        Ok(filler)
+    }
+
+    /// Read the PDF at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(std::fs::read(path)?)
+    }
+
+    /// Terminal fields in field tree order
+    pub fn fields(&self) -> &[ExistingField] {
+        &self.fields
+    }
+
+    /// Look up a field by its fully qualified name
+    pub fn field(&self, name: &str) -> Option<&ExistingField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Set the contents of a text field
+    pub fn set_text(&mut self, name: &str, text: &str) -> Result<()> {
+        let field = self.writable_field(name, &[FieldKind::Text])?;
+        if let Some(max_len) = field.max_len {
+            if text.chars().count() > max_len {
+                return Err(PdfError::InvalidOperation(format!(
+                    "Field '{name}' accepts at most {max_len} characters"
+                )));
+            }
+        }
+
+        let value = PdfObject::String(PdfString::new(text_bytes(text)));
+        self.update_field(
+            &field,
+            vec![("V", Some(value))],
+            FieldValue::Text(text.to_string()),
+        )?;
+        let style = self.text_style(&field)?;
+        for widget in &field.widgets {
+            let form_widget = self.form_widget(widget)?;
+            let (width, height) = widget_size(&widget.rect);
+            let multiline = field.flags & MULTILINE != 0;
+            let font_size = if style.size > 0.0 {
+                style.size
+            } else {
+                auto_font_size(text, &style.font, width, height, multiline)
+            };
+            let generator = TextFieldAppearance {
+                font: style.font.clone(),
+                font_size,
+                text_color: style.color,
+                justification: field.quadding as i32,
+                multiline,
+            };
+            let stream =
+                generator.generate_appearance(&form_widget, Some(text), AppearanceState::Normal)?;
+            self.set_normal_appearance(widget.id, appearance_object(&stream, Some(&style.font)))?;
+        }
+        Ok(())
+    }
+
+    /// Check or uncheck a check box
+    pub fn set_checked(&mut self, name: &str, checked: bool) -> Result<()> {
+        let field = self.writable_field(name, &[FieldKind::CheckBox])?;
+        let state = if checked {
+            field
+                .widgets
+                .iter()
+                .find_map(|w| w.on_state.clone())
+                .unwrap_or_else(|| "Yes".to_string())
+        } else {
+            "Off".to_string()
+        };
+        self.set_button_state(&field, &state)
+    }
+
+    /// Select the radio button whose on state is `state`, or `Off` for none
+    pub fn select_radio(&mut self, name: &str, state: &str) -> Result<()> {
+        let field = self.writable_field(name, &[FieldKind::RadioGroup])?;
+        if state != "Off"
+            && !field
+                .widgets
+                .iter()
+                .any(|w| w.on_state.as_deref() == Some(state))
+        {
+            return Err(PdfError::InvalidOperation(format!(
+                "'{state}' is not a state of radio group '{name}'"
+            )));
+        }
+        self.set_button_state(&field, state)
+    }
+
+    /// Select options of a combo or list box by export value or display text.
+    ///
+    /// Editable combo boxes also accept values that are not among the options.
+    pub fn select_options(&mut self, name: &str, values: &[&str]) -> Result<()> {
+        let field = self.writable_field(name, &[FieldKind::ComboBox, FieldKind::ListBox])?;
+        if values.len() > 1 && field.flags & MULTI_SELECT == 0 {
+            return Err(PdfError::InvalidOperation(format!(
+                "Field '{name}' accepts a single selection"
+            )));
+        }
+
+        let mut exports = Vec::new();
+        let mut shown = Vec::new();
+        let mut indices = Vec::new();
+        for &value in values {
+            let position = field
+                .options
+                .iter()
+                .position(|o| o.export == value)
+                .or_else(|| field.options.iter().position(|o| o.display == value));
+            match position {
+                Some(index) => {
+                    exports.push(field.options[index].export.clone());
+                    shown.push(field.options[index].display.clone());
+                    indices.push(index);
+                }
+                None if field.kind == FieldKind::ComboBox && field.flags & EDIT != 0 => {
+                    exports.push(value.to_string());
+                    shown.push(value.to_string());
+                }
+                None => {
+                    return Err(PdfError::InvalidOperation(format!(
+                        "'{value}' is not an option of field '{name}'"
+                    )))
+                }
+            }
+        }
+        indices.sort_unstable();
+
+        let value = match exports.as_slice() {
+            [] => None,
+            [single] => Some(PdfObject::String(PdfString::new(text_bytes(single)))),
+            many => Some(PdfObject::Array(PdfArray(
+                many.iter()
+                    .map(|e| PdfObject::String(PdfString::new(text_bytes(e))))
+                    .collect(),
+            ))),
+        };
+        let mut entries = vec![("V", value)];
+        if field.kind == FieldKind::ListBox {
+            let selected = (!indices.is_empty()).then(|| {
+                PdfObject::Array(PdfArray(
+                    indices
+                        .iter()
+                        .map(|&i| PdfObject::Integer(i as i64))
+                        .collect(),
+                ))
+            });
+            entries.push(("I", selected));
+        }
+        self.update_field(&field, entries, FieldValue::Choices(exports))?;
+
+        let style = self.text_style(&field)?;
+        for widget in &field.widgets {
+            let form_widget = self.form_widget(widget)?;
+            let (width, height) = widget_size(&widget.rect);
+            let stream = if field.kind == FieldKind::ComboBox {
+                let text = shown.first().cloned().unwrap_or_default();
+                let font_size = if style.size > 0.0 {
+                    style.size
+                } else {
+                    auto_font_size(&text, &style.font, width, height, false)
+                };
+                let generator = ComboBoxAppearance {
+                    font: style.font.clone(),
+                    font_size,
+                    text_color: style.color,
+                    selected_text: None,
+                    show_arrow: false,
+                };
+                let text = (!text.is_empty()).then_some(text);
+                generator.generate_appearance(
+                    &form_widget,
+                    text.as_deref(),
+                    AppearanceState::Normal,
+                )?
+            } else {
+                let font_size = if style.size > 0.0 { style.size } else { 12.0 };
+                let generator = ListBoxAppearance {
+                    font: style.font.clone(),
+                    font_size,
+                    text_color: style.color,
+                    selection_color: Color::rgb(0.6, 0.75, 0.85),
+                    options: field.options.iter().map(|o| o.display.clone()).collect(),
+                    selected: indices.clone(),
+                    item_height: font_size * 1.2,
+                };
+                generator.generate_appearance(&form_widget, None, AppearanceState::Normal)?
+            };
+            self.set_normal_appearance(widget.id, appearance_object(&stream, Some(&style.font)))?;
+        }
+        Ok(())
+    }
+
+    /// Set any fillable field from a string, the way form data files do:
+    /// text for text fields, an on state (or `Off`) for buttons and an
+    /// option for choice fields
+    pub fn set_value(&mut self, name: &str, value: &str) -> Result<()> {
+        let kind = self
+            .field(name)
+            .ok_or_else(|| PdfError::FieldNotFound(name.to_string()))?
+            .kind;
+        match kind {
+            FieldKind::Text => self.set_text(name, value),
+            FieldKind::CheckBox => self.set_checked(name, !value.is_empty() && value != "Off"),
+            FieldKind::RadioGroup => self.select_radio(name, value),
+            FieldKind::ComboBox | FieldKind::ListBox if value.is_empty() => {
+                self.select_options(name, &[])
+            }
+            FieldKind::ComboBox | FieldKind::ListBox => self.select_options(name, &[value]),
+            _ => Err(PdfError::InvalidOperation(format!(
+                "Field '{name}' of kind {kind:?} cannot be filled"
+            ))),
+        }
+    }
+
+    /// Burn the current widget appearances into the page content and remove
+    /// the interactive form; [`fields`](Self::fields) is empty afterwards.
+    ///
+    /// Hidden widgets and widgets without an appearance stream are dropped.
+    pub fn flatten(&mut self) -> Result<()> {
+        let widget_ids: HashSet<ObjectId> = self
+            .fields
+            .iter()
+            .flat_map(|f| f.widgets.iter().map(|w| w.id))
+            .collect();
+
+        for index in 0..self.document.page_count()? {
+            let page = self.document.get_page(index)?;
+            let page_id = ObjectId::new(page.obj_ref.0, page.obj_ref.1);
+            let mut page_dict = self.dict(page_id)?;
+            let (widgets, kept): (Vec<PdfObject>, Vec<PdfObject>) = self
+                .annotations(&page_dict)?
+                .into_iter()
+                .partition(|annot| {
+                    annot
+                        .as_reference()
+                        .is_some_and(|(num, gen)| widget_ids.contains(&ObjectId::new(num, gen)))
+                });
+            if widgets.is_empty() {
+                continue;
+            }
+
+            let mut resources = match page_dict.get("Resources") {
+                Some(resources) => self.resolve(resources)?.as_dict().cloned(),
+                None => page.inherited_resources.clone(),
+            }
+            .unwrap_or_default();
+            let mut xobjects = match resources.get("XObject") {
+                Some(xobjects) => self.resolve(xobjects)?.as_dict().cloned(),
+                None => None,
+            }
+            .unwrap_or_default();
+
+            let mut drawing = String::from("Q\n");
+            let mut counter = 0;
+            for widget in &widgets {
+                let Some((num, gen)) = widget.as_reference() else {
+                    continue;
+                };
+                let Some((appearance, matrix)) =
+                    self.flattened_appearance(ObjectId::new(num, gen))?
+                else {
+                    continue;
+                };
+                let name = loop {
+                    counter += 1;
+                    let name = format!("FlatFm{counter}");
+                    if !xobjects.contains_key(&name) {
+                        break name;
+                    }
+                };
+                xobjects.insert(
+                    name.clone(),
+                    PdfObject::Reference(appearance.number(), appearance.generation()),
+                );
+                let [a, b, c, d, e, f] = matrix;
+                drawing.push_str(&format!("q {a} {b} {c} {d} {e} {f} cm /{name} Do Q\n"));
+            }
+            resources.insert("XObject".to_string(), PdfObject::Dictionary(xobjects));
+            page_dict.insert("Resources".to_string(), PdfObject::Dictionary(resources));
+
+            let mut contents = vec![self.add(content_stream(b"q\n".to_vec()))];
+            match page_dict.get("Contents") {
+                Some(existing) => match self.resolve(existing)? {
+                    PdfObject::Array(streams) => contents.extend(streams.0),
+                    _ => contents.push(existing.clone()),
+                },
+                None => {}
+            }
+            contents.push(self.add(content_stream(drawing.into_bytes())));
+            page_dict.insert("Contents".to_string(), PdfObject::Array(PdfArray(contents)));
+
+            if kept.is_empty() {
+                page_dict.0.remove(&PdfName::new("Annots".to_string()));
+            } else {
+                page_dict.insert("Annots".to_string(), PdfObject::Array(PdfArray(kept)));
+            }
+            self.changes
+                .insert(page_id, PdfObject::Dictionary(page_dict));
+        }
+
+        let root = self.root()?;
+        let mut catalog = self.dict(root)?;
+        if catalog
+            .0
+            .remove(&PdfName::new("AcroForm".to_string()))
+            .is_some()
+        {
+            self.changes.insert(root, PdfObject::Dictionary(catalog));
+        }
+        self.fields.clear();
+        Ok(())
+    }
+
+    /// Field values and markup annotations of the document as FDF/XFDF data
+    pub fn export_data(&self) -> Result<FdfData> {
+        let mut data = FdfData::new();
+        for field in &self.fields {
+            if !matches!(field.kind, FieldKind::PushButton | FieldKind::Signature)
+                && field.value != FieldValue::None
+            {
+                data.set_field(field.name.clone(), field.value.clone());
+            }
+        }
+        for index in 0..self.document.page_count()? {
+            let page = self.document.get_page(index)?;
+            let page_dict = self.dict(ObjectId::new(page.obj_ref.0, page.obj_ref.1))?;
+            for annot in self.annotations(&page_dict)? {
+                let annot = self.resolve(&annot)?;
+                let Some(dict) = annot.as_dict() else {
+                    continue;
+                };
+                let subtype = dict.get("Subtype").and_then(|s| s.as_name());
+                if subtype.is_some_and(|s| matches!(s.as_str(), "Link" | "Popup")) {
+                    continue;
+                }
+                data.annotations.extend(annotation_from_dict(dict, index));
+            }
+        }
+        Ok(data)
+    }
+
+    /// Apply FDF/XFDF data: fill the fields it names and add its annotations
+    /// to their pages. Fields missing from the document are skipped; returns
+    /// the number of fields filled.
+    pub fn import_data(&mut self, data: &FdfData) -> Result<usize> {
+        let mut filled = 0;
+        for entry in &data.fields {
+            let Some(field) = self.field(&entry.name) else {
+                continue;
+            };
+            let kind = field.kind;
+            match (&entry.value, kind) {
+                (FieldValue::None, _) => continue,
+                (FieldValue::Choices(choices), FieldKind::ComboBox | FieldKind::ListBox) => {
+                    let choices: Vec<&str> = choices.iter().map(String::as_str).collect();
+                    self.select_options(&entry.name, &choices)?;
+                }
+                (FieldValue::Choices(choices), _) => {
+                    let value = choices.first().map(String::as_str).unwrap_or_default();
+                    self.set_value(&entry.name, value)?;
+                }
+                (FieldValue::Text(value) | FieldValue::State(value), _) => {
+                    self.set_value(&entry.name, value)?;
+                }
+            }
+            filled += 1;
+        }
+
+        for annotation in &data.annotations {
+            let page = self.document.get_page(annotation.page)?;
+            let page_id = ObjectId::new(page.obj_ref.0, page.obj_ref.1);
+            let mut page_dict = self.dict(page_id)?;
+            let mut annots = self.annotations(&page_dict)?;
+            let mut dict = annotation_to_dict(annotation);
+            dict.insert(
+                "P".to_string(),
+                PdfObject::Reference(page_id.number(), page_id.generation()),
+            );
+            annots.push(self.add(PdfObject::Dictionary(dict)));
+            page_dict.insert("Annots".to_string(), PdfObject::Array(PdfArray(annots)));
+            self.changes
+                .insert(page_id, PdfObject::Dictionary(page_dict));
+        }
+        Ok(filled)
+    }
+
+    /// The document with all changes appended as an incremental update
+    pub fn save(&self) -> Result<Vec<u8>> {
+        if self.changes.is_empty() {
+            return Ok(self.pdf.clone());
+        }
+        let mut objects: Vec<(ObjectId, PdfObject)> = self
+            .changes
+            .iter()
+            .map(|(id, object)| (*id, object.clone()))
+            .collect();
+        objects.sort_by_key(|(id, _)| (id.number(), id.generation()));
+
+        let mut output = Vec::with_capacity(self.pdf.len() + 4096);
+        {
+            let mut writer = PdfWriter::with_config(&mut output, WriterConfig::incremental());
+            writer.write_incremental_parsed_objects(&self.pdf, objects)?;
+        }
+        Ok(output)
+    }
+
+    /// Save the updated document to `path`
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.save()?)?;
+        Ok(())
+    }
+
+    fn read_fields(&self) -> Result<Vec<ExistingField>> {
+        let mut pages = HashMap::new();
+        for index in 0..self.document.page_count()? {
+            let page = self.document.get_page(index)?;
+            for annot in self.annotations(&page.dict)? {
+                if let Some((num, gen)) = annot.as_reference() {
+                    pages.insert(ObjectId::new(num, gen), index);
+                }
+            }
+        }
+
+        let Some(acro_form) = self.acro_form()? else {
+            return Ok(Vec::new());
+        };
+        let Some(fields) = acro_form.get("Fields") else {
+            return Ok(Vec::new());
+        };
+        let PdfObject::Array(fields) = self.resolve(fields)? else {
+            return Ok(Vec::new());
+        };
+        let root = Inherited {
+            default_appearance: acro_form
+                .get("DA")
+                .and_then(|da| da.as_string())
+                .map(|da| text_string(da.as_bytes())),
+            quadding: acro_form.get("Q").and_then(|q| q.as_integer()).unwrap_or(0),
+            ..Default::default()
+        };
+
+        let mut collected = Vec::new();
+        self.walk(&fields.0, "", &root, &pages, 0, &mut collected)?;
+        Ok(collected)
+    }
+
+    fn walk(
+        &self,
+        nodes: &[PdfObject],
+        prefix: &str,
+        inherited: &Inherited,
+        pages: &HashMap<ObjectId, u32>,
+        depth: usize,
+        collected: &mut Vec<ExistingField>,
+    ) -> Result<()> {
+        if depth > MAX_FIELD_DEPTH {
+            return Ok(());
+        }
+        for node in nodes {
+            let Some((num, gen)) = node.as_reference() else {
+                continue;
+            };
+            let Some(dict) = self.document.get_object(num, gen)?.as_dict().cloned() else {
+                continue;
+            };
+            let Some(partial) = dict.get("T").and_then(|t| t.as_string()) else {
+                continue;
+            };
+            let partial = text_string(partial.as_bytes());
+            let name = if prefix.is_empty() {
+                partial
+            } else {
+                format!("{prefix}.{partial}")
+            };
+            let inherited = self.inherit(inherited, &dict)?;
+
+            // Kids with /T are child fields, the others widget annotations
+            let mut children = Vec::new();
+            let mut widgets = Vec::new();
+            if let Some(kids) = dict.get("Kids") {
+                if let PdfObject::Array(kids) = self.resolve(kids)? {
+                    for kid in kids.0 {
+                        let Some((num, gen)) = kid.as_reference() else {
+                            continue;
+                        };
+                        let Some(kid_dict) = self.document.get_object(num, gen)?.as_dict().cloned()
+                        else {
+                            continue;
+                        };
+                        if kid_dict.contains_key("T") {
+                            children.push(kid);
+                        } else {
+                            widgets.push((ObjectId::new(num, gen), kid_dict));
+                        }
+                    }
+                }
+            } else {
+                // A field without kids is merged with its single widget
+                widgets.push((ObjectId::new(num, gen), dict.clone()));
+            }
+
+            if !widgets.is_empty() {
+                collected.push(self.terminal_field(
+                    name.clone(),
+                    ObjectId::new(num, gen),
+                    &inherited,
+                    &widgets,
+                    pages,
+                )?);
+            }
+            self.walk(&children, &name, &inherited, pages, depth + 1, collected)?;
+        }
+        Ok(())
+    }
+
+    fn inherit(&self, parent: &Inherited, dict: &PdfDictionary) -> Result<Inherited> {
+        let mut inherited = parent.clone();
+        if let Some(field_type) = dict.get("FT").and_then(|t| t.as_name()) {
+            inherited.field_type = Some(field_type.as_str().to_string());
+        }
+        if let Some(flags) = dict.get("Ff").and_then(|f| f.as_integer()) {
+            inherited.flags = flags as u32;
+        }
+        if let Some(value) = dict.get("V") {
+            inherited.value = Some(self.resolve(value)?);
+        }
+        if let Some(value) = dict.get("DV") {
+            inherited.default_value = Some(self.resolve(value)?);
+        }
+        if let Some(da) = dict.get("DA").and_then(|da| da.as_string()) {
+            inherited.default_appearance = Some(text_string(da.as_bytes()));
+        }
+        if let Some(quadding) = dict.get("Q").and_then(|q| q.as_integer()) {
+            inherited.quadding = quadding;
+        }
+        if let Some(max_len) = dict.get("MaxLen").and_then(|m| m.as_integer()) {
+            inherited.max_len = usize::try_from(max_len).ok();
+        }
+        if let Some(options) = dict.get("Opt") {
+            inherited.options = Some(self.resolve(options)?);
+        }
+        Ok(inherited)
+    }
+
+    fn terminal_field(
+        &self,
+        name: String,
+        id: ObjectId,
+        inherited: &Inherited,
+        widgets: &[(ObjectId, PdfDictionary)],
+        pages: &HashMap<ObjectId, u32>,
+    ) -> Result<ExistingField> {
+        let kind = field_kind(inherited.field_type.as_deref(), inherited.flags);
+        let options = match &inherited.options {
+            Some(PdfObject::Array(options)) => options.0.iter().filter_map(choice_option).collect(),
+            _ => Vec::new(),
+        };
+
+        let mut field_widgets = Vec::with_capacity(widgets.len());
+        for (widget_id, dict) in widgets {
+            field_widgets.push(FieldWidget {
+                page: pages.get(widget_id).copied(),
+                rect: rect_of(dict)
+                    .unwrap_or_else(|| Rectangle::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0))),
+                on_state: self.on_state(dict)?,
+                id: *widget_id,
+            });
+        }
+
+        Ok(ExistingField {
+            name,
+            kind,
+            value: field_value(kind, inherited.value.as_ref()),
+            default_value: field_value(kind, inherited.default_value.as_ref()),
+            options,
+            flags: inherited.flags,
+            max_len: inherited.max_len,
+            widgets: field_widgets,
+            id,
+            default_appearance: inherited.default_appearance.clone(),
+            quadding: inherited.quadding,
+        })
+    }
+
+    /// First appearance state of a button widget other than `Off`
+    fn on_state(&self, widget: &PdfDictionary) -> Result<Option<String>> {
+        let Some(appearances) = widget.get("AP") else {
+            return Ok(None);
+        };
+        let appearances = self.resolve(appearances)?;
+        for key in ["N", "D"] {
+            let Some(states) = appearances.as_dict().and_then(|ap| ap.get(key)) else {
+                continue;
+            };
+            if let PdfObject::Dictionary(states) = self.resolve(states)? {
+                let mut names: Vec<&str> = states
+                    .0
+                    .keys()
+                    .map(|k| k.as_str())
+                    .filter(|&k| k != "Off")
+                    .collect();
+                names.sort_unstable();
+                if let Some(name) = names.first() {
+                    return Ok(Some(name.to_string()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn writable_field(&self, name: &str, kinds: &[FieldKind]) -> Result<ExistingField> {
+        let field = self
+            .field(name)
+            .ok_or_else(|| PdfError::FieldNotFound(name.to_string()))?;
+        if !kinds.contains(&field.kind) {
+            return Err(PdfError::InvalidOperation(format!(
+                "Field '{name}' is a {:?} field",
+                field.kind
+            )));
+        }
+        if field.is_read_only() {
+            return Err(PdfError::InvalidOperation(format!(
+                "Field '{name}' is read-only"
+            )));
+        }
+        Ok(field.clone())
+    }
+
+    /// Store new entries of the field dictionary (`None` removes the key)
+    fn update_field(
+        &mut self,
+        field: &ExistingField,
+        entries: Vec<(&str, Option<PdfObject>)>,
+        value: FieldValue,
+    ) -> Result<()> {
+        let mut dict = self.dict(field.id)?;
+        for (key, entry) in entries {
+            match entry {
+                Some(entry) => dict.insert(key.to_string(), entry),
+                None => {
+                    dict.0.remove(&PdfName::new(key.to_string()));
+                }
+            }
+        }
+        self.changes.insert(field.id, PdfObject::Dictionary(dict));
+        if let Some(existing) = self.fields.iter_mut().find(|f| f.id == field.id) {
+            existing.value = value;
+        }
+        self.remove_xfa()
+    }
+
+    fn set_button_state(&mut self, field: &ExistingField, state: &str) -> Result<()> {
+        self.update_field(
+            field,
+            vec![("V", Some(name_object(state)))],
+            FieldValue::State(state.to_string()),
+        )?;
+        for (index, widget) in field.widgets.iter().enumerate() {
+            let on_state = match &widget.on_state {
+                Some(on_state) => on_state.clone(),
+                None => {
+                    let appearances = self.button_appearances(field, widget)?;
+                    let mut dict = self.dict(widget.id)?;
+                    dict.insert("AP".to_string(), appearances);
+                    self.changes.insert(widget.id, PdfObject::Dictionary(dict));
+                    if let Some(existing) = self.fields.iter_mut().find(|f| f.id == field.id) {
+                        existing.widgets[index].on_state = Some("Yes".to_string());
+                    }
+                    "Yes".to_string()
+                }
+            };
+            let shown = if on_state == state { state } else { "Off" };
+            let mut dict = self.dict(widget.id)?;
+            dict.insert("AS".to_string(), name_object(shown));
+            self.changes.insert(widget.id, PdfObject::Dictionary(dict));
+        }
+        Ok(())
+    }
+
+    /// `/AP` dictionary with `Yes` and `Off` states for a button without one
+    fn button_appearances(
+        &mut self,
+        field: &ExistingField,
+        widget: &FieldWidget,
+    ) -> Result<PdfObject> {
+        let form_widget = self.form_widget(widget)?;
+        let generator: Box<dyn AppearanceGenerator> = if field.kind == FieldKind::RadioGroup {
+            Box::new(RadioButtonAppearance::default())
+        } else {
+            Box::new(CheckBoxAppearance::default())
+        };
+        let mut states = PdfDictionary::new();
+        for (state, value) in [("Yes", Some("Yes")), ("Off", None)] {
+            let stream =
+                generator.generate_appearance(&form_widget, value, AppearanceState::Normal)?;
+            let stream = self.add(appearance_object(&stream, None));
+            states.insert(state.to_string(), stream);
+        }
+        let mut appearances = PdfDictionary::new();
+        appearances.insert("N".to_string(), PdfObject::Dictionary(states));
+        Ok(PdfObject::Dictionary(appearances))
+    }
+
+    /// Replace the normal appearance of a widget, reusing a stream added earlier
+    fn set_normal_appearance(&mut self, widget: ObjectId, stream: PdfObject) -> Result<()> {
+        let mut dict = self.dict(widget)?;
+        let previous = dict
+            .get("AP")
+            .and_then(|ap| ap.as_dict())
+            .and_then(|ap| ap.get("N"))
+            .and_then(|n| n.as_reference())
+            .filter(|&(num, _)| num >= self.first_new_number);
+        let reference = match previous {
+            Some((num, gen)) => {
+                self.changes.insert(ObjectId::new(num, gen), stream);
+                PdfObject::Reference(num, gen)
+            }
+            None => self.add(stream),
+        };
+        let mut appearances = PdfDictionary::new();
+        appearances.insert("N".to_string(), reference);
+        dict.insert("AP".to_string(), PdfObject::Dictionary(appearances));
+        self.changes.insert(widget, PdfObject::Dictionary(dict));
+        Ok(())
+    }
+
+    /// Filled values make an XFA form stale, so viewers must use the AcroForm
+    fn remove_xfa(&mut self) -> Result<()> {
+        let root = self.root()?;
+        let mut catalog = self.dict(root)?;
+        let xfa = PdfName::new("XFA".to_string());
+        match catalog.get("AcroForm").cloned() {
+            Some(PdfObject::Reference(num, gen)) => {
+                let id = ObjectId::new(num, gen);
+                let mut acro_form = self.dict(id)?;
+                if acro_form.0.remove(&xfa).is_some() {
+                    self.changes.insert(id, PdfObject::Dictionary(acro_form));
+                }
+            }
+            Some(PdfObject::Dictionary(mut acro_form)) => {
+                if acro_form.0.remove(&xfa).is_some() {
+                    catalog.insert("AcroForm".to_string(), PdfObject::Dictionary(acro_form));
+                    self.changes.insert(root, PdfObject::Dictionary(catalog));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Normal appearance stream of a visible widget with the matrix placing it
+    /// on the page (ISO 32000-1 §12.5.5)
+    fn flattened_appearance(&self, widget: ObjectId) -> Result<Option<(ObjectId, [f64; 6])>> {
+        let dict = self.dict(widget)?;
+        let flags = dict.get("F").and_then(|f| f.as_integer()).unwrap_or(0);
+        if flags & HIDDEN_OR_NO_VIEW != 0 {
+            return Ok(None);
+        }
+        let Some(rect) = rect_of(&dict) else {
+            return Ok(None);
+        };
+        let Some(appearances) = dict.get("AP") else {
+            return Ok(None);
+        };
+        let Some(normal) = self
+            .resolve(appearances)?
+            .as_dict()
+            .and_then(|ap| ap.get("N"))
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        // A state dictionary holds one stream per /AS value
+        let normal = match self.resolve(&normal)? {
+            PdfObject::Dictionary(states) => {
+                let state = dict.get("AS").and_then(|s| s.as_name());
+                match state.and_then(|s| states.get(s.as_str())) {
+                    Some(stream) => stream.clone(),
+                    None => return Ok(None),
+                }
+            }
+            _ => normal,
+        };
+        let Some((num, gen)) = normal.as_reference() else {
+            return Ok(None);
+        };
+        let PdfObject::Stream(stream) = self.resolve(&normal)? else {
+            return Ok(None);
+        };
+
+        let Some(bbox) = stream.dict.get("BBox").and_then(numbers::<4>) else {
+            return Ok(None);
+        };
+        let matrix = stream
+            .dict
+            .get("Matrix")
+            .and_then(numbers::<6>)
+            .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        let corners = [
+            (bbox[0], bbox[1]),
+            (bbox[2], bbox[1]),
+            (bbox[0], bbox[3]),
+            (bbox[2], bbox[3]),
+        ]
+        .map(|(x, y)| {
+            (
+                matrix[0] * x + matrix[2] * y + matrix[4],
+                matrix[1] * x + matrix[3] * y + matrix[5],
+            )
+        });
+        let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+        let max_x = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+        let max_y = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+            return Ok(None);
+        }
+
+        let (width, height) = widget_size(&rect);
+        let scale_x = width / (max_x - min_x);
+        let scale_y = height / (max_y - min_y);
+        Ok(Some((
+            ObjectId::new(num, gen),
+            [
+                scale_x,
+                0.0,
+                0.0,
+                scale_y,
+                rect.lower_left.x - min_x * scale_x,
+                rect.lower_left.y - min_y * scale_y,
+            ],
+        )))
+    }
+
+    /// Text style from the field's `/DA`, with fonts mapped to standard fonts
+    fn text_style(&self, field: &ExistingField) -> Result<TextStyle> {
+        let mut style = TextStyle {
+            font: Font::Helvetica,
+            size: 0.0,
+            color: Color::black(),
+        };
+        let Some(da) = &field.default_appearance else {
+            return Ok(style);
+        };
+
+        let tokens: Vec<&str> = da.split_whitespace().collect();
+        let number = |i: usize| tokens.get(i).and_then(|t| t.parse::<f64>().ok());
+        for (i, &token) in tokens.iter().enumerate() {
+            match token {
+                "Tf" if i >= 2 => {
+                    let resource = tokens[i - 2].trim_start_matches('/');
+                    style.font = self.resource_font(resource)?;
+                    style.size = number(i - 1).unwrap_or(0.0);
+                }
+                "g" if i >= 1 => {
+                    if let Some(gray) = number(i - 1) {
+                        style.color = Color::gray(gray);
+                    }
+                }
+                "rg" if i >= 3 => {
+                    if let (Some(r), Some(g), Some(b)) =
+                        (number(i - 3), number(i - 2), number(i - 1))
+                    {
+                        style.color = Color::rgb(r, g, b);
+                    }
+                }
+                "k" if i >= 4 => {
+                    if let (Some(c), Some(m), Some(y), Some(k)) =
+                        (number(i - 4), number(i - 3), number(i - 2), number(i - 1))
+                    {
+                        style.color = Color::cmyk(c, m, y, k);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(style)
+    }
+
+    /// Standard font matching a font of the AcroForm's `/DR`
+    fn resource_font(&self, resource: &str) -> Result<Font> {
+        let mut base_font = None;
+        if let Some(resources) = self.acro_form()?.and_then(|f| f.get("DR").cloned()) {
+            let fonts = match self
+                .resolve(&resources)?
+                .as_dict()
+                .and_then(|r| r.get("Font"))
+            {
+                Some(fonts) => self.resolve(fonts)?,
+                None => PdfObject::Null,
+            };
+            if let Some(font) = fonts.as_dict().and_then(|f| f.get(resource)) {
+                base_font = self
+                    .resolve(font)?
+                    .as_dict()
+                    .and_then(|f| f.get("BaseFont"))
+                    .and_then(|b| b.as_name())
+                    .map(|b| b.as_str().to_string());
+            }
+        }
+        Ok(standard_font(base_font.as_deref().unwrap_or(resource)))
+    }
+
+    /// Widget used by the appearance generators: the rectangle at the origin
+    /// with colors and border from `/MK` and `/BS`
+    fn form_widget(&self, widget: &FieldWidget) -> Result<Widget> {
+        let dict = self.dict(widget.id)?;
+        let characteristics = match dict.get("MK") {
+            Some(mk) => self.resolve(mk)?.as_dict().cloned(),
+            None => None,
+        }
+        .unwrap_or_default();
+        let border = match dict.get("BS") {
+            Some(bs) => self.resolve(bs)?.as_dict().cloned(),
+            None => None,
+        }
+        .unwrap_or_default();
+
+        let border_color = characteristics.get("BC").and_then(color_of);
+        let border_width = border
+            .get("W")
+            .and_then(|w| w.as_real())
+            .unwrap_or(if border_color.is_some() { 1.0 } else { 0.0 });
+        let border_style = match border.get("S").and_then(|s| s.as_name()) {
+            Some(style) => match style.as_str() {
+                "D" => BorderStyle::Dashed,
+                "B" => BorderStyle::Beveled,
+                "I" => BorderStyle::Inset,
+                "U" => BorderStyle::Underline,
+                _ => BorderStyle::Solid,
+            },
+            None => BorderStyle::Solid,
+        };
+
+        let (width, height) = widget_size(&widget.rect);
+        let mut form_widget = Widget::new(Rectangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(width, height),
+        ));
+        form_widget.appearance = WidgetAppearance {
+            border_color: border_color.filter(|_| border_width > 0.0),
+            background_color: characteristics.get("BG").and_then(color_of),
+            border_width,
+            border_style,
+        };
+        Ok(form_widget)
+    }
+
+    fn acro_form(&self) -> Result<Option<PdfDictionary>> {
+        let catalog = self.dict(self.root()?)?;
+        match catalog.get("AcroForm") {
+            Some(acro_form) => Ok(self.resolve(acro_form)?.as_dict().cloned()),
+            None => Ok(None),
+        }
+    }
+
+    fn annotations(&self, page: &PdfDictionary) -> Result<Vec<PdfObject>> {
+        match page.get("Annots") {
+            Some(annots) => match self.resolve(annots)? {
+                PdfObject::Array(annots) => Ok(annots.0),
+                _ => Ok(Vec::new()),
+            },
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn root(&self) -> Result<ObjectId> {
+        let (num, gen) = self.document.trailer().root()?;
+        Ok(ObjectId::new(num, gen))
+    }
+
+    /// Current revision of an object, including pending changes
+    fn resolve(&self, object: &PdfObject) -> Result<PdfObject> {
+        match object {
+            PdfObject::Reference(num, gen) => match self.changes.get(&ObjectId::new(*num, *gen)) {
+                Some(changed) => Ok(changed.clone()),
+                None => Ok(self.document.get_object(*num, *gen)?),
+            },
+            _ => Ok(object.clone()),
+        }
+    }
+
+    fn dict(&self, id: ObjectId) -> Result<PdfDictionary> {
+        self.resolve(&PdfObject::Reference(id.number(), id.generation()))?
+            .as_dict()
+            .cloned()
+            .ok_or_else(|| PdfError::InvalidStructure(format!("Object {id} is not a dictionary")))
+    }
+
+    /// Add a new object to the update, returning a reference to it
+    fn add(&mut self, object: PdfObject) -> PdfObject {
+        let id = ObjectId::new(self.next_number, 0);
+        self.next_number += 1;
+        self.changes.insert(id, object);
+        PdfObject::Reference(id.number(), id.generation())
+    }
+}
+
+fn field_kind(field_type: Option<&str>, flags: u32) -> FieldKind {
+    match field_type {
+        Some("Tx") => FieldKind::Text,
+        Some("Btn") if flags & PUSHBUTTON != 0 => FieldKind::PushButton,
+        Some("Btn") if flags & RADIO != 0 => FieldKind::RadioGroup,
+        Some("Btn") => FieldKind::CheckBox,
+        Some("Ch") if flags & COMBO != 0 => FieldKind::ComboBox,
+        Some("Ch") => FieldKind::ListBox,
+        Some("Sig") => FieldKind::Signature,
+        _ => FieldKind::Unknown,
+    }
+}
+
+fn field_value(kind: FieldKind, value: Option<&PdfObject>) -> FieldValue {
+    let choice = matches!(kind, FieldKind::ComboBox | FieldKind::ListBox);
+    match value {
+        Some(PdfObject::Name(state)) => FieldValue::State(state.as_str().to_string()),
+        Some(PdfObject::String(text)) if choice => {
+            FieldValue::Choices(vec![text_string(text.as_bytes())])
+        }
+        Some(PdfObject::String(text)) => FieldValue::Text(text_string(text.as_bytes())),
+        Some(PdfObject::Array(values)) if choice => FieldValue::Choices(
+            values
+                .0
+                .iter()
+                .filter_map(|v| v.as_string())
+                .map(|v| text_string(v.as_bytes()))
+                .collect(),
+        ),
+        _ => FieldValue::None,
+    }
+}
+
+/// `/Opt` entry: a text string or an `[export display]` pair
+fn choice_option(option: &PdfObject) -> Option<ChoiceOption> {
+    match option {
+        PdfObject::String(text) => {
+            let text = text_string(text.as_bytes());
+            Some(ChoiceOption {
+                export: text.clone(),
+                display: text,
+            })
+        }
+        PdfObject::Array(pair) => {
+            let export = text_string(pair.get(0)?.as_string()?.as_bytes());
+            let display = match pair.get(1).and_then(|d| d.as_string()) {
+                Some(display) => text_string(display.as_bytes()),
+                None => export.clone(),
+            };
+            Some(ChoiceOption { export, display })
+        }
+        _ => None,
+    }
+}
+
+/// Standard 14 font for a font name, by exact name, the AcroForm aliases or
+/// the font family
+fn standard_font(name: &str) -> Font {
+    let name = name.split_once('+').map_or(name, |(_, name)| name);
+    match name {
+        "Helv" => return Font::Helvetica,
+        "HeBo" => return Font::HelveticaBold,
+        "TiRo" => return Font::TimesRoman,
+        "Cour" => return Font::Courier,
+        "Symb" => return Font::Symbol,
+        "ZaDb" => return Font::ZapfDingbats,
+        _ => {}
+    }
+    if let Some(font) = STANDARD_FONTS.iter().find(|f| f.pdf_name() == name) {
+        return font.clone();
+    }
+    if name.contains("Times") {
+        Font::TimesRoman
+    } else if name.contains("Courier") {
+        Font::Courier
+    } else {
+        Font::Helvetica
+    }
+}
+
+/// Font size for `/DA` size 0: fill the widget height and shrink single-line
+/// text until it fits the width
+fn auto_font_size(text: &str, font: &Font, width: f64, height: f64, multiline: bool) -> f64 {
+    let mut size = ((height - 4.0) * 0.7).clamp(4.0, 12.0);
+    if multiline {
+        return size.min(12.0);
+    }
+    let available = width - 4.0;
+    let text_width = measure_text(text, font.clone(), size);
+    if text_width > available && text_width > 0.0 {
+        size = (size * available / text_width).max(4.0);
+    }
+    size
+}
+
+fn widget_size(rect: &Rectangle) -> (f64, f64) {
+    (
+        rect.upper_right.x - rect.lower_left.x,
+        rect.upper_right.y - rect.lower_left.y,
+    )
+}
+
+fn rect_of(dict: &PdfDictionary) -> Option<Rectangle> {
+    let [x0, y0, x1, y1] = numbers::<4>(dict.get("Rect")?)?;
+    Some(Rectangle::new(
+        Point::new(x0.min(x1), y0.min(y1)),
+        Point::new(x0.max(x1), y0.max(y1)),
+    ))
+}
+
+fn numbers<const N: usize>(object: &PdfObject) -> Option<[f64; N]> {
+    let array = object.as_array()?;
+    let mut values = [0.0; N];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = array.get(i)?.as_real()?;
+    }
+    Some(values)
+}
+
+/// Color of an `/MK` color array; an empty array means transparent
+fn color_of(object: &PdfObject) -> Option<Color> {
+    let components: Vec<f64> = object
+        .as_array()?
+        .0
+        .iter()
+        .filter_map(|c| c.as_real())
+        .collect();
+    match components.as_slice() {
+        [gray] => Some(Color::gray(*gray)),
+        [r, g, b] => Some(Color::rgb(*r, *g, *b)),
+        [c, m, y, k] => Some(Color::cmyk(*c, *m, *y, *k)),
+        _ => None,
+    }
+}
+
+fn name_object(name: &str) -> PdfObject {
+    PdfObject::Name(PdfName::new(name.to_string()))
+}
+
+fn content_stream(data: Vec<u8>) -> PdfObject {
+    PdfObject::Stream(PdfStream {
+        dict: PdfDictionary::new(),
+        data,
+    })
+}
+
+/// Form XObject for a generated appearance. The generators write text as
+/// UTF-8, so it is re-encoded to single bytes for a WinAnsi `font`.
+fn appearance_object(appearance: &AppearanceStream, font: Option<&Font>) -> PdfObject {
+    let mut resources = match PdfObject::from(&Object::Dictionary(appearance.resources.clone())) {
+        PdfObject::Dictionary(resources) => resources,
+        _ => PdfDictionary::new(),
+    };
+    let data = match font {
+        Some(font) => {
+            let mut font_dict = PdfDictionary::new();
+            font_dict.insert("Type".to_string(), name_object("Font"));
+            font_dict.insert("Subtype".to_string(), name_object("Type1"));
+            font_dict.insert("BaseFont".to_string(), name_object(&font.pdf_name()));
+            if !font.is_symbolic() {
+                font_dict.insert("Encoding".to_string(), name_object("WinAnsiEncoding"));
+            }
+            let mut fonts = PdfDictionary::new();
+            fonts.insert(font.pdf_name(), PdfObject::Dictionary(font_dict));
+            resources.insert("Font".to_string(), PdfObject::Dictionary(fonts));
+
+            String::from_utf8_lossy(&appearance.content)
+                .chars()
+                .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+                .collect()
+        }
+        None => appearance.content.clone(),
+    };
+
+    let mut dict = PdfDictionary::new();
+    dict.insert("Type".to_string(), name_object("XObject"));
+    dict.insert("Subtype".to_string(), name_object("Form"));
+    dict.insert(
+        "BBox".to_string(),
+        PdfObject::Array(PdfArray(
+            appearance
+                .bbox
+                .iter()
+                .map(|&v| PdfObject::Real(v))
+                .collect(),
+        )),
+    );
+    dict.insert("Resources".to_string(), PdfObject::Dictionary(resources));
+    PdfObject::Stream(PdfStream { dict, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::resolve::{get, get_dict, stream_data, Resolver};
+
+    /// Stream object body with its `/Length`
+    fn stream(entries: &str, data: &str) -> String {
+        format!(
+            "<< {entries} /Length {} >>\nstream\n{data}\nendstream",
+            data.len()
+        )
+    }
+
+    /// A one-page form written by another producer: nested names, merged and
+    /// separate widgets, existing button appearances and an XFA entry
+    fn form_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /AcroForm 4 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 5 0 R \
+             /Resources << >> /Annots [6 0 R 8 0 R 10 0 R 11 0 R 12 0 R 13 0 R] >>"
+                .to_string(),
+            "<< /Fields [7 0 R 8 0 R 9 0 R 12 0 R 13 0 R] /DA (/Helv 0 Tf 0 g) \
+             /DR << /Font << /Helv 14 0 R >> >> /XFA (template) >>"
+                .to_string(),
+            stream("", "0 0 m 612 792 l S"),
+            "<< /Type /Annot /Subtype /Widget /Parent 7 0 R /T (name) /FT /Tx \
+             /Rect [100 700 300 720] /DA (/Helv 10 Tf 0 0 1 rg) /MaxLen 20 \
+             /MK << /BC [0] /BG [1] >> >>"
+                .to_string(),
+            "<< /T (applicant) /Kids [6 0 R] >>".to_string(),
+            "<< /Type /Annot /Subtype /Widget /T (agree) /FT /Btn /Rect [100 650 115 665] \
+             /AP << /N << /On 15 0 R /Off 15 0 R >> >> /AS /Off /V /Off >>"
+                .to_string(),
+            "<< /T (plan) /FT /Btn /Ff 49152 /Kids [10 0 R 11 0 R] /V /Off >>".to_string(),
+            "<< /Type /Annot /Subtype /Widget /Parent 9 0 R /Rect [100 600 115 615] \
+             /AP << /N << /Basic 15 0 R /Off 15 0 R >> >> /AS /Off >>"
+                .to_string(),
+            "<< /Type /Annot /Subtype /Widget /Parent 9 0 R /Rect [130 600 145 615] \
+             /AP << /N << /Premium 15 0 R /Off 15 0 R >> >> /AS /Off >>"
+                .to_string(),
+            "<< /Type /Annot /Subtype /Widget /T (state) /FT /Ch /Ff 131072 \
+             /Opt [[(CA) (California)] [(NY) (New York)]] /Rect [100 550 200 570] >>"
+                .to_string(),
+            "<< /Type /Annot /Subtype /Widget /T (colors) /FT /Ch /Ff 2097152 \
+             /Opt [(Red) (Green) (Blue)] /V (Green) /Rect [100 450 200 500] >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+            stream(
+                "/Type /XObject /Subtype /Form /BBox [0 0 15 15]",
+                "0 0 15 15 re f",
+            ),
+        ];
+
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{body}\nendobj\n", i + 1).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+
+    fn parse(pdf: Vec<u8>) -> PdfDocument<Cursor<Vec<u8>>> {
+        PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap())
+    }
+
+    fn object(document: &PdfDocument<Cursor<Vec<u8>>>, number: u32) -> PdfDictionary {
+        match document.get_object(number, 0).unwrap() {
+            PdfObject::Dictionary(dict) => dict,
+            PdfObject::Stream(stream) => stream.dict,
+            other => panic!("object {number} is {other:?}"),
+        }
+    }
+
+    /// Decoded normal appearance of a widget, for `state` if it has several
+    fn normal_appearance(
+        document: &PdfDocument<Cursor<Vec<u8>>>,
+        widget: &PdfDictionary,
+        state: Option<&str>,
+    ) -> String {
+        let resolver: &dyn Resolver = document;
+        let appearances = get_dict(resolver, widget, "AP").unwrap();
+        let normal = match (get(resolver, &appearances, "N").unwrap(), state) {
+            (PdfObject::Dictionary(states), Some(state)) => {
+                resolver.lookup(states.get(state).unwrap())
+            }
+            (normal, _) => normal,
+        };
+        let PdfObject::Stream(stream) = normal else {
+            panic!("appearance is not a stream");
+        };
+        assert_eq!(
+            stream.dict.get("Subtype").and_then(|s| s.as_name()),
+            Some(&PdfName::new("Form".to_string()))
+        );
+        String::from_utf8_lossy(&stream_data(resolver, &stream).unwrap()).into_owned()
+    }
+
+    #[test]
+    fn test_read_field_tree() {
+        let filler = FormFiller::new(form_pdf()).unwrap();
+        let names: Vec<&str> = filler.fields().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["applicant.name", "agree", "plan", "state", "colors"]
+        );
+
+        let name = filler.field("applicant.name").unwrap();
+        assert_eq!(name.kind, FieldKind::Text);
+        assert_eq!(name.value, FieldValue::None);
+        assert_eq!(name.max_len, Some(20));
+        assert_eq!(name.widgets[0].page, Some(0));
+        assert_eq!(name.widgets[0].rect.upper_right.x, 300.0);
+
+        let agree = filler.field("agree").unwrap();
+        assert_eq!(agree.kind, FieldKind::CheckBox);
+        assert_eq!(agree.value, FieldValue::State("Off".to_string()));
+        assert_eq!(agree.widgets[0].on_state.as_deref(), Some("On"));
+
+        let plan = filler.field("plan").unwrap();
+        assert_eq!(plan.kind, FieldKind::RadioGroup);
+        let states: Vec<_> = plan.widgets.iter().map(|w| w.on_state.as_deref()).collect();
+        assert_eq!(states, [Some("Basic"), Some("Premium")]);
+
+        let state = filler.field("state").unwrap();
+        assert_eq!(state.kind, FieldKind::ComboBox);
+        assert_eq!(state.options[1].export, "NY");
+        assert_eq!(state.options[1].display, "New York");
+
+        let colors = filler.field("colors").unwrap();
+        assert_eq!(colors.kind, FieldKind::ListBox);
+        assert_eq!(colors.value, FieldValue::Choices(vec!["Green".to_string()]));
+        assert!(!colors.is_read_only());
+    }
+
+    #[test]
+    fn test_fill_and_save_incrementally() {
+        let original = form_pdf();
+        let mut filler = FormFiller::new(original.clone()).unwrap();
+        filler.set_text("applicant.name", "José (Jr.)").unwrap();
+        filler.set_checked("agree", true).unwrap();
+        filler.select_radio("plan", "Premium").unwrap();
+        filler.select_options("state", &["New York"]).unwrap();
+        filler.set_value("colors", "Blue").unwrap();
+
+        let saved = filler.save().unwrap();
+        assert!(saved.starts_with(&original));
+
+        let reread = FormFiller::new(saved).unwrap();
+        let value = |name: &str| reread.field(name).unwrap().value.clone();
+        assert_eq!(
+            value("applicant.name"),
+            FieldValue::Text("José (Jr.)".to_string())
+        );
+        assert_eq!(value("agree"), FieldValue::State("On".to_string()));
+        assert_eq!(value("plan"), FieldValue::State("Premium".to_string()));
+        assert_eq!(value("state"), FieldValue::Choices(vec!["NY".to_string()]));
+        assert_eq!(
+            value("colors"),
+            FieldValue::Choices(vec!["Blue".to_string()])
+        );
+
+        let plan = reread.field("plan").unwrap();
+        let shown: Vec<_> = plan
+            .widgets
+            .iter()
+            .map(|w| {
+                let dict = reread.dict(w.id).unwrap();
+                dict.get("AS")
+                    .unwrap()
+                    .as_name()
+                    .unwrap()
+                    .as_str()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(shown, ["Off", "Premium"]);
+        let colors = reread.dict(reread.field("colors").unwrap().id).unwrap();
+        assert_eq!(
+            colors.get("I").unwrap().as_array().unwrap().0,
+            [PdfObject::Integer(2)]
+        );
+        assert!(!reread.acro_form().unwrap().unwrap().contains_key("XFA"));
+
+        // The text appearance uses the /DA font and WinAnsi bytes
+        let widget = reread.dict(reread.field("applicant.name").unwrap().widgets[0].id);
+        let normal = widget
+            .unwrap()
+            .get("AP")
+            .unwrap()
+            .as_dict()
+            .unwrap()
+            .get("N")
+            .cloned();
+        let PdfObject::Stream(appearance) = reread.resolve(&normal.unwrap()).unwrap() else {
+            panic!("appearance is not a stream");
+        };
+        let content = appearance.decode(&reread.document.options()).unwrap();
+        assert!(content.windows(7).any(|w| w == b"Jos\xe9 \\("));
+        assert!(content.windows(16).any(|w| w == b"/Helvetica 10 Tf"));
+    }
+
+    #[test]
+    fn test_fill_errors() {
+        let mut filler = FormFiller::new(form_pdf()).unwrap();
+        assert!(matches!(
+            filler.set_text("missing", "x"),
+            Err(PdfError::FieldNotFound(_))
+        ));
+        assert!(filler.set_text("agree", "x").is_err());
+        assert!(filler.set_text("applicant.name", &"x".repeat(21)).is_err());
+        assert!(filler.select_radio("plan", "Gold").is_err());
+        assert!(filler.select_options("state", &["TX"]).is_err());
+        assert!(filler.select_options("state", &["CA", "NY"]).is_err());
+        assert_eq!(filler.save().unwrap(), form_pdf());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut filler = FormFiller::new(form_pdf()).unwrap();
+        filler.set_text("applicant.name", "Jane Doe").unwrap();
+        filler.select_radio("plan", "Basic").unwrap();
+        filler.flatten().unwrap();
+        assert!(filler.fields().is_empty());
+
+        let flattened = FormFiller::new(filler.save().unwrap()).unwrap();
+        assert!(flattened.fields().is_empty());
+        assert!(flattened.acro_form().unwrap().is_none());
+
+        let page = flattened.document.get_page(0).unwrap();
+        assert!(page.dict.get("Annots").is_none());
+        let streams = flattened.document.get_page_content_streams(&page).unwrap();
+        assert_eq!(streams.len(), 3);
+        assert_eq!(streams[0], b"q\n");
+        let drawing = String::from_utf8(streams[2].clone()).unwrap();
+        // The untouched choice fields have no appearance to burn in
+        assert_eq!(drawing.matches(" Do Q").count(), 4);
+        assert!(drawing.contains("q 1 0 0 1 100 700 cm /FlatFm1 Do Q"));
+    }
+
+    #[test]
+    fn test_exchange_form_data() {
+        let mut filler = FormFiller::new(form_pdf()).unwrap();
+        filler.set_text("applicant.name", "Jane Doe").unwrap();
+        filler.set_checked("agree", true).unwrap();
+        filler.select_radio("plan", "Basic").unwrap();
+        filler.select_options("state", &["CA"]).unwrap();
+        let mut data = filler.export_data().unwrap();
+        data.set_field("unknown", FieldValue::Text("ignored".to_string()));
+        data.annotations.push(crate::forms::FdfAnnotation::new(
+            "Square",
+            0,
+            Rectangle::new(Point::new(10.0, 10.0), Point::new(50.0, 50.0)),
+        ));
+
+        let xfdf = FdfData::from_xfdf(&data.to_xfdf()).unwrap();
+        let mut target = FormFiller::new(form_pdf()).unwrap();
+        assert_eq!(target.import_data(&xfdf).unwrap(), 5);
+        for field in filler.fields() {
+            assert_eq!(target.field(&field.name).unwrap().value, field.value);
+        }
+
+        let reread = FormFiller::new(target.save().unwrap()).unwrap();
+        let exported = reread.export_data().unwrap();
+        assert_eq!(exported.annotations.len(), 1);
+        assert_eq!(exported.annotations[0].subtype, "Square");
+        assert_eq!(exported.fields, data.fields[..5]);
+    }
+
+    #[test]
+    fn test_filled_buttons_and_choices_in_output() {
+        let mut filler = FormFiller::new(form_pdf()).unwrap();
+        filler.select_radio("plan", "Premium").unwrap();
+        filler.set_checked("agree", true).unwrap();
+        filler.select_options("state", &["New York"]).unwrap();
+        filler.select_options("colors", &["Blue", "Red"]).unwrap();
+        let document = parse(filler.save().unwrap());
+
+        // The radio group holds the value, each widget shows its own state
+        let name = |dict: &PdfDictionary, key: &str| {
+            dict.get(key)
+                .and_then(|n| n.as_name())
+                .map(|n| n.as_str().to_string())
+        };
+        assert_eq!(name(&object(&document, 9), "V").as_deref(), Some("Premium"));
+        let basic = object(&document, 10);
+        let premium = object(&document, 11);
+        assert_eq!(name(&basic, "AS").as_deref(), Some("Off"));
+        assert_eq!(name(&premium, "AS").as_deref(), Some("Premium"));
+        // The existing on and off appearances are kept
+        assert_eq!(
+            normal_appearance(&document, &premium, Some("Premium")),
+            "0 0 15 15 re f"
+        );
+        let agree = object(&document, 8);
+        assert_eq!(name(&agree, "V").as_deref(), Some("On"));
+        assert_eq!(name(&agree, "AS").as_deref(), Some("On"));
+
+        // The combo box shows the display text of the exported value
+        let state = object(&document, 12);
+        assert_eq!(
+            state
+                .get("V")
+                .and_then(|v| v.as_string())
+                .unwrap()
+                .as_bytes(),
+            b"NY"
+        );
+        let shown = normal_appearance(&document, &state, None);
+        // The form's font size is 0, so the text is sized to the widget
+        assert!(shown.contains("/Helvetica "));
+        assert!(!shown.contains("/Helvetica 0 Tf"));
+        assert!(shown.contains("(New York) Tj"));
+        assert!(!shown.contains("(California)"));
+
+        // The list box selects by value and index and highlights the options
+        let colors = object(&document, 13);
+        let values: Vec<_> = colors
+            .get("V")
+            .and_then(|v| v.as_array())
+            .unwrap()
+            .0
+            .iter()
+            .map(|v| v.as_string().unwrap().as_bytes().to_vec())
+            .collect();
+        assert_eq!(values, [b"Blue".to_vec(), b"Red".to_vec()]);
+        assert_eq!(
+            colors.get("I").and_then(|i| i.as_array()).unwrap().0,
+            [PdfObject::Integer(0), PdfObject::Integer(2)]
+        );
+        let listed = normal_appearance(&document, &colors, None);
+        assert_eq!(listed.matches("0.6 0.75 0.85 rg").count(), 2);
+        for option in ["(Red) Tj", "(Green) Tj", "(Blue) Tj"] {
+            assert!(listed.contains(option), "{option} missing");
+        }
+
+        // Appearances use fonts of their own resources
+        let resolver: &dyn Resolver = &document;
+        let appearances = get_dict(resolver, &colors, "AP").unwrap();
+        let PdfObject::Stream(stream) = get(resolver, &appearances, "N").unwrap() else {
+            panic!("appearance is not a stream");
+        };
+        let resources = get_dict(resolver, &stream.dict, "Resources").unwrap();
+        let fonts = get_dict(resolver, &resources, "Font").unwrap();
+        assert!(fonts.contains_key("Helvetica"));
+    }
+
+    #[test]
+    fn test_flattened_output() {
+        let mut filler = FormFiller::new(form_pdf()).unwrap();
+        filler.set_text("applicant.name", "Jane Doe").unwrap();
+        filler.set_checked("agree", true).unwrap();
+        filler.select_radio("plan", "Premium").unwrap();
+        filler.select_options("state", &["CA"]).unwrap();
+        filler.flatten().unwrap();
+        let document = parse(filler.save().unwrap());
+        let resolver: &dyn Resolver = &document;
+
+        // No form and no widgets are left
+        let catalog = document.catalog().unwrap();
+        assert!(!catalog.contains_key("AcroForm"));
+        let page = document.get_page(0).unwrap();
+        assert!(!page.dict.contains_key("Annots"));
+
+        // The original drawing is kept between a save and a restore, followed
+        // by one form XObject per visible appearance
+        let streams = document.get_page_content_streams(&page).unwrap();
+        assert_eq!(streams.len(), 3);
+        assert_eq!(streams[0], b"q\n");
+        assert_eq!(streams[1], b"0 0 m 612 792 l S");
+        let drawing = String::from_utf8(streams[2].clone()).unwrap();
+        assert!(drawing.starts_with("Q\n"));
+        let placed: Vec<&str> = drawing
+            .lines()
+            .filter_map(|line| line.strip_suffix(" Do Q"))
+            .filter_map(|line| line.rsplit(' ').next())
+            .collect();
+        // Name, agree, both plan buttons and the state; colors was never
+        // filled and has no appearance
+        assert_eq!(placed.len(), 5);
+
+        let resources = get_dict(resolver, &page.dict, "Resources").unwrap();
+        let xobjects = get_dict(resolver, &resources, "XObject").unwrap();
+        let content = |name: &str| {
+            let name = name.trim_start_matches('/');
+            let PdfObject::Stream(stream) = get(resolver, &xobjects, name).unwrap() else {
+                panic!("/{name} is not a stream");
+            };
+            assert_eq!(
+                stream
+                    .dict
+                    .get("Subtype")
+                    .and_then(|s| s.as_name())
+                    .map(|s| s.as_str()),
+                Some("Form")
+            );
+            String::from_utf8_lossy(&stream_data(resolver, &stream).unwrap()).into_owned()
+        };
+        let contents: Vec<String> = placed.iter().map(|name| content(name)).collect();
+        assert!(contents.iter().any(|c| c.contains("(Jane Doe) Tj")));
+        assert!(contents.iter().any(|c| c.contains("(California) Tj")));
+        // The buttons show the appearance of their current state
+        assert_eq!(
+            contents.iter().filter(|c| *c == "0 0 15 15 re f").count(),
+            3
+        );
+
+        // Widgets are placed at their rectangles
+        assert!(drawing.contains("q 1 0 0 1 100 600 cm"));
+        assert!(drawing.contains("q 1 0 0 1 130 600 cm"));
+    }
+
+    #[test]
+    fn test_incremental_save_preserves_original() {
+        let original = form_pdf();
+        let mut filler = FormFiller::new(original.clone()).unwrap();
+        filler.set_text("applicant.name", "Jane Doe").unwrap();
+        filler.select_options("state", &["NY"]).unwrap();
+        let saved = filler.save().unwrap();
+
+        // The update is appended after the untouched original bytes
+        assert!(saved.starts_with(&original));
+        let update = String::from_utf8_lossy(&saved[original.len()..]).into_owned();
+        let startxref = |pdf: &str| -> i64 {
+            let tail = &pdf[pdf.rfind("startxref").unwrap() + "startxref".len()..];
+            tail.split_whitespace().next().unwrap().parse().unwrap()
+        };
+        let original_xref = startxref(&String::from_utf8_lossy(&original));
+
+        // Only changed and new objects are written, and the trailer chains
+        // to the original cross-reference table
+        for changed in ["4 0 obj", "6 0 obj", "12 0 obj"] {
+            assert!(update.contains(changed), "{changed} not updated");
+        }
+        for unchanged in ["\n3 0 obj", "\n5 0 obj", "\n9 0 obj", "\n14 0 obj"] {
+            assert!(!update.contains(unchanged), "{unchanged} rewritten");
+        }
+        let document = parse(saved.clone());
+        assert_eq!(
+            document
+                .trailer()
+                .dict()
+                .get("Prev")
+                .and_then(|p| p.as_integer()),
+            Some(original_xref)
+        );
+        assert!(startxref(&update) > original.len() as i64);
+
+        // The original revision still reads as before
+        let before = parse(original);
+        assert!(!object(&before, 6).contains_key("V"));
+        assert!(object(&before, 4).contains_key("XFA"));
+        let after = object(&document, 6);
+        assert_eq!(
+            after
+                .get("V")
+                .and_then(|v| v.as_string())
+                .unwrap()
+                .as_bytes(),
+            b"Jane Doe"
+        );
+        assert_eq!(object(&document, 5), object(&before, 5));
+    }
+}
@@ -2,8 +2,8 @@
 
 use crate::error::Result;
 use crate::forms::{
-    CheckBox, ComboBox, FieldOptions, FormField, ListBox, PushButton, RadioButton, TextField,
-    Widget,
+    CheckBox, ComboBox, FdfData, FieldOptions, FieldValue, FormField, ListBox, PushButton,
+    RadioButton, TextField, Widget,
 };
 use crate::objects::{Dictionary, Object, ObjectReference};
 use std::collections::HashMap;
@@ -418,6 +418,65 @@ impl FormManager {
     pub fn field_count(&self) -> usize {
         self.fields.len()
     }
+
+    /// Field values as FDF/XFDF data, ordered by field name
+    pub fn export_data(&self) -> FdfData {
+        let mut names: Vec<&String> = self.fields.keys().collect();
+        names.sort();
+        let mut data = FdfData::new();
+        for name in names {
+            let value = match self.fields[name].field_dict.get("V") {
+                Some(Object::String(text)) => FieldValue::Text(text.clone()),
+                Some(Object::Name(state)) => FieldValue::State(state.clone()),
+                Some(Object::Array(choices)) => FieldValue::Choices(
+                    choices
+                        .iter()
+                        .filter_map(|c| match c {
+                            Object::String(choice) => Some(choice.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                _ => continue,
+            };
+            data.set_field(name.clone(), value);
+        }
+        data
+    }
+
+    /// Set field values from FDF/XFDF data, returning how many fields matched.
+    ///
+    /// Buttons take their state as `/V` and `/AS`; the form keeps
+    /// `NeedAppearances` so viewers redraw the other fields.
+    pub fn import_data(&mut self, data: &FdfData) -> usize {
+        let mut imported = 0;
+        for entry in &data.fields {
+            let Some(field) = self.fields.get_mut(&entry.name) else {
+                continue;
+            };
+            let is_button =
+                matches!(field.field_dict.get("FT"), Some(Object::Name(ft)) if ft == "Btn");
+            let value = match &entry.value {
+                FieldValue::None => continue,
+                FieldValue::Text(value) | FieldValue::State(value) if is_button => {
+                    Object::Name(value.clone())
+                }
+                FieldValue::Text(value) | FieldValue::State(value) => Object::String(value.clone()),
+                FieldValue::Choices(choices) if choices.len() == 1 => {
+                    Object::String(choices[0].clone())
+                }
+                FieldValue::Choices(choices) => {
+                    Object::Array(choices.iter().cloned().map(Object::String).collect())
+                }
+            };
+            if is_button && field.field_dict.contains_key("AS") {
+                field.field_dict.set("AS", value.clone());
+            }
+            field.field_dict.set("V", value);
+            imported += 1;
+        }
+        imported
+    }
 }
 
 #[cfg(test)]
@@ -506,6 +565,39 @@ mod tests {
         assert!(manager.get_field("subscribe").is_some());
     }
 
+    #[test]
+    fn test_form_manager_data_exchange() {
+        let mut manager = FormManager::new();
+        let rect = Rectangle::new(Point::new(100.0, 150.0), Point::new(115.0, 165.0));
+        manager
+            .add_text_field(TextField::new("name"), Widget::new(rect), None)
+            .unwrap();
+        manager
+            .add_checkbox(CheckBox::new("subscribe"), Widget::new(rect), None)
+            .unwrap();
+
+        let mut data = FdfData::new();
+        data.set_field("name", FieldValue::Text("Jane".to_string()));
+        data.set_field("subscribe", FieldValue::Text("Yes".to_string()));
+        data.set_field("missing", FieldValue::Text("x".to_string()));
+        assert_eq!(manager.import_data(&data), 2);
+
+        let field = manager.get_field("subscribe").unwrap();
+        assert_eq!(
+            field.field_dict.get("AS"),
+            Some(&Object::Name("Yes".to_string()))
+        );
+        let exported = manager.export_data();
+        assert_eq!(
+            exported.field("name"),
+            Some(&FieldValue::Text("Jane".to_string()))
+        );
+        assert_eq!(
+            exported.field("subscribe"),
+            Some(&FieldValue::State("Yes".to_string()))
+        );
+    }
+
     #[test]
     fn test_acro_form_comprehensive() {
         let mut acro_form = AcroForm::new();
@@ -8,10 +8,12 @@ pub mod button_widget;
 pub mod calculation_system;
 pub mod calculations;
 pub mod choice_widget;
+mod fdf;
 mod field;
 pub mod field_actions;
 pub mod field_appearance;
 mod field_type;
+mod filling;
 mod form_data;
 pub mod javascript_engine;
 pub mod signature_field;
@@ -29,6 +31,7 @@ pub use button_widget::{
     create_checkbox_widget, create_pushbutton_widget, create_radio_widget, ButtonWidget,
 };
 pub use choice_widget::{create_combobox_widget, create_listbox_widget, ChoiceWidget};
+pub use fdf::{FdfAnnotation, FdfData, FdfField};
 pub use field::{
     BorderStyle, Field, FieldFlags, FieldOptions, FormField, Widget, WidgetAppearance,
 };
@@ -41,6 +44,7 @@ pub use field_type::{
     ButtonField, CheckBox, ChoiceField, ComboBox, FieldType, ListBox, PushButton, RadioButton,
     TextField,
 };
+pub use filling::{ChoiceOption, ExistingField, FieldKind, FieldValue, FieldWidget, FormFiller};
 pub use form_data::{AcroForm, FormData, FormManager};
 pub use working_field::{
     create_checkbox_dict, create_combo_box_dict, create_list_box_dict, create_push_button_dict,
@@ -6,6 +6,7 @@
 use crate::error::PdfError;
 use crate::graphics::Color;
 use crate::objects::{Dictionary, Object};
+use crate::signatures::{CmsSignature, PdfSigner, SignatureOptions, SigningCredentials};
 use crate::text::Font;
 use chrono::{DateTime, Utc};
 
@@ -16,7 +17,7 @@ pub struct SignatureField {
     pub name: String,
     /// Signer information
     pub signer: Option<SignerInfo>,
-    /// Signature value (placeholder for actual signature)
+    /// Signature value, set once the field has been signed
     pub signature_value: Option<SignatureValue>,
     /// Fields to lock after signing
     pub lock_fields: Vec<String>,
@@ -56,9 +57,9 @@ pub struct SignatureValue {
     pub document_hash: Vec<u8>,
     /// Signature algorithm
     pub algorithm: SignatureAlgorithm,
-    /// Certificate chain (placeholder)
+    /// Certificate chain, signer first
     pub certificates: Vec<Certificate>,
-    /// Actual signature bytes (placeholder)
+    /// DER-encoded CMS `ContentInfo` embedded in `/Contents`
     pub signature_bytes: Vec<u8>,
 }
 
@@ -77,7 +78,7 @@ pub enum SignatureAlgorithm {
     DsaSha256,
 }
 
-/// Certificate placeholder
+/// Summary of an X.509 certificate
 #[derive(Debug, Clone)]
 pub struct Certificate {
     /// Subject name
@@ -208,42 +209,64 @@ impl SignatureField {
         self.signature_value.is_some()
     }
 
-    /// Sign the field (placeholder implementation)
-    pub fn sign(&mut self, signer: SignerInfo, reason: Option<String>) -> Result<(), PdfError> {
+    /// Sign the field in `pdf` with real CMS signature
+    ///
+    /// The field is created in the document if it does not exist yet. Returns
+    /// the signed file, which contains `pdf` unchanged followed by an
+    /// incremental update carrying the signature.
+    pub fn sign(
+        &mut self,
+        pdf: &[u8],
+        credentials: &SigningCredentials,
+        reason: Option<String>,
+    ) -> Result<Vec<u8>, PdfError> {
         if self.is_signed() {
             return Err(PdfError::InvalidOperation(
                 "Field is already signed".to_string(),
             ));
         }
 
-        // Create signature value (placeholder)
-        let signature_value = SignatureValue {
-            timestamp: Utc::now(),
-            document_hash: vec![0; 32], // Placeholder hash
-            algorithm: SignatureAlgorithm::RsaSha256,
-            certificates: vec![],
-            signature_bytes: vec![0; 256], // Placeholder signature
-        };
-
-        self.signer = Some(signer);
         if let Some(r) = reason {
             self.reason = Some(r);
         }
-        self.signature_value = Some(signature_value);
 
-        Ok(())
+        let mut options = SignatureOptions::new(self.name.clone())
+            .with_appearance(self.appearance.clone())
+            .lock_fields(self.lock_fields.clone());
+        if let Some(reason) = &self.reason {
+            options = options.with_reason(reason.clone());
+        }
+        if let Some(location) = &self.location {
+            options = options.with_location(location.clone());
+        }
+        if let Some(contact) = &self.contact_info {
+            options = options.with_contact_info(contact.clone());
+        }
+        if let Some(signer) = &self.signer {
+            options = options.with_signer_name(signer.name.clone());
+        }
+
+        let signed = PdfSigner::new(credentials.clone()).sign(pdf, &options)?;
+
+        if self.signer.is_none() {
+            self.signer = Some(credentials.signer_info());
+        }
+        self.signature_value = Some(signed.signature);
+
+        Ok(signed.bytes)
     }
 
-    /// Verify signature (placeholder implementation)
+    /// Verify the signature value against the recorded document hash
+    ///
+    /// Checks the message digest attribute and the cryptographic signature
+    /// of the embedded CMS structure.
     pub fn verify(&self) -> Result<bool, PdfError> {
-        if !self.is_signed() {
+        let Some(value) = &self.signature_value else {
             return Ok(false);
-        }
+        };
 
-        // Placeholder verification - always returns true for now
-        // In a real implementation, this would verify the signature
-        // against the document hash and certificate chain
-        Ok(true)
+        let cms = CmsSignature::parse(&value.signature_bytes)?;
+        cms.verify(&value.document_hash)
     }
 
     /// Generate appearance stream for the signature field
@@ -515,17 +538,42 @@ mod tests {
 
     #[test]
     fn test_sign_field() {
+        let pdf = {
+            let mut doc = crate::Document::new();
+            doc.add_page(crate::Page::a4());
+            doc.to_bytes().unwrap()
+        };
+        let credentials = crate::signatures::test_helpers::rsa_credentials();
         let mut field = SignatureField::new("sig1");
-        let signer = SignerInfo::new("Jane Smith");
 
-        assert!(field
-            .sign(signer.clone(), Some("Approval".to_string()))
-            .is_ok());
+        let signed = field
+            .sign(&pdf, &credentials, Some("Approval".to_string()))
+            .unwrap();
+        assert!(signed.starts_with(&pdf));
         assert!(field.is_signed());
         assert_eq!(field.reason, Some("Approval".to_string()));
+        assert_eq!(field.signer.as_ref().unwrap().name, "Test RSA Signer");
+        assert!(field.verify().unwrap());
 
         // Cannot sign twice
-        assert!(field.sign(signer, None).is_err());
+        assert!(field.sign(&pdf, &credentials, None).is_err());
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let pdf = {
+            let mut doc = crate::Document::new();
+            doc.add_page(crate::Page::a4());
+            doc.to_bytes().unwrap()
+        };
+        let credentials = crate::signatures::test_helpers::ecdsa_credentials();
+        let mut field = SignatureField::new("sig1");
+        field.sign(&pdf, &credentials, None).unwrap();
+
+        if let Some(value) = field.signature_value.as_mut() {
+            value.document_hash[0] ^= 0xff;
+        }
+        assert!(!field.verify().unwrap());
     }
 
     #[test]
@@ -6,8 +6,11 @@
 use crate::error::PdfError;
 use crate::forms::signature_field::{SignatureAlgorithm, SignatureField, SignerInfo};
 use crate::objects::{Dictionary, Object};
+use crate::parser::PdfDocument;
+use crate::signatures::{SignatureVerification, SigningCredentials};
 use chrono::Utc;
 use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
 
 /// Handler for managing signature fields in a document
 pub struct SignatureHandler {
@@ -63,6 +66,30 @@ pub struct ValidationResult {
     pub warnings: Vec<String>,
 }
 
+impl From<SignatureVerification> for ValidationResult {
+    fn from(verification: SignatureVerification) -> Self {
+        let is_valid = verification.is_valid();
+        let mut errors = verification.errors;
+        errors.extend(verification.mdp_violations);
+        let mut warnings = verification.warnings;
+        if verification.modified_after_signing {
+            warnings.push(format!(
+                "Document was modified after signing ({} later revisions)",
+                verification.later_revisions
+            ));
+        }
+
+        Self {
+            field_name: verification.field_name,
+            is_valid,
+            signer: verification.signer,
+            validated_at: Utc::now(),
+            errors,
+            warnings,
+        }
+    }
+}
+
 #[allow(clippy::derivable_impls)]
 impl Default for SignatureHandler {
     fn default() -> Self {
@@ -112,13 +139,14 @@ impl SignatureHandler {
         self.signature_fields.get_mut(name)
     }
 
-    /// Sign a field
+    /// Sign a field in `pdf`, returning the signed file
     pub fn sign_field(
         &mut self,
         field_name: &str,
-        signer: SignerInfo,
+        pdf: &[u8],
+        credentials: &SigningCredentials,
         reason: Option<String>,
-    ) -> Result<(), PdfError> {
+    ) -> Result<Vec<u8>, PdfError> {
         // Check if field is locked
         if self.locked_fields.contains(field_name) {
             return Err(PdfError::InvalidOperation(format!(
@@ -133,7 +161,7 @@ impl SignatureHandler {
             .get_mut(field_name)
             .ok_or_else(|| PdfError::FieldNotFound(field_name.to_string()))?;
 
-        field.sign(signer, reason)?;
+        let signed = field.sign(pdf, credentials, reason)?;
 
         // Lock fields as specified
         let fields_to_lock = field.lock_fields.clone();
@@ -148,7 +176,7 @@ impl SignatureHandler {
             }
         }
 
-        Ok(())
+        Ok(signed)
     }
 
     /// Validate all signatures in the document
@@ -162,6 +190,21 @@ impl SignatureHandler {
         results
     }
 
+    /// Validate the signatures stored in a parsed document
+    ///
+    /// Unlike [`validate_all`](Self::validate_all), which checks the fields
+    /// registered with this handler, this verifies every signed field of the
+    /// file against the bytes it covers.
+    pub fn validate_document<R: Read + Seek>(
+        document: &PdfDocument<R>,
+    ) -> Result<Vec<ValidationResult>, PdfError> {
+        Ok(document
+            .verify_signatures()?
+            .into_iter()
+            .map(ValidationResult::from)
+            .collect())
+    }
+
     /// Validate a single signature field
     fn validate_field(&self, name: &str, field: &SignatureField) -> ValidationResult {
         let mut result = ValidationResult {
@@ -184,7 +227,6 @@ impl SignatureHandler {
             return result;
         }
 
-        // Perform validation (placeholder - always succeeds for now)
         match field.verify() {
             Ok(valid) => {
                 result.is_valid = valid;
@@ -199,9 +241,7 @@ impl SignatureHandler {
             }
         }
 
-        // Check certificate validity (placeholder)
         if let Some(ref sig_value) = field.signature_value {
-            // In a real implementation, check certificate chain
             if sig_value.certificates.is_empty() {
                 result
                     .warnings
@@ -368,6 +408,13 @@ impl SignatureSummary {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signatures::test_helpers;
+
+    fn test_pdf() -> Vec<u8> {
+        let mut doc = crate::Document::new();
+        doc.add_page(crate::Page::a4());
+        doc.to_bytes().unwrap()
+    }
 
     #[test]
     fn test_signature_handler_creation() {
@@ -396,10 +443,15 @@ mod tests {
 
         handler.add_signature_field(field).unwrap();
 
-        let signer = SignerInfo::new("John Doe");
-        assert!(handler
-            .sign_field("sig1", signer, Some("Approved".to_string()))
-            .is_ok());
+        let signed = handler
+            .sign_field(
+                "sig1",
+                &test_pdf(),
+                &test_helpers::rsa_credentials(),
+                Some("Approved".to_string()),
+            )
+            .unwrap();
+        assert!(signed.starts_with(b"%PDF-"));
 
         // Check that field1 is now locked
         assert!(handler.is_field_locked("field1"));
@@ -454,11 +506,40 @@ mod tests {
 
         // Sign the required field
         handler
-            .sign_field("sig1", SignerInfo::new("Signer"), None)
+            .sign_field(
+                "sig1",
+                &test_pdf(),
+                &test_helpers::ecdsa_credentials(),
+                None,
+            )
             .unwrap();
 
         let summary = handler.generate_summary();
         assert_eq!(summary.signed_fields, 1);
         assert!(summary.is_ready());
+
+        let results = handler.validate_all();
+        let sig1 = results.iter().find(|r| r.field_name == "sig1").unwrap();
+        assert!(sig1.is_valid);
+    }
+
+    #[test]
+    fn test_validate_document() {
+        let mut handler = SignatureHandler::new();
+        handler
+            .add_signature_field(SignatureField::new("sig1"))
+            .unwrap();
+        let signed = handler
+            .sign_field("sig1", &test_pdf(), &test_helpers::rsa_credentials(), None)
+            .unwrap();
+
+        let reader = crate::parser::PdfReader::new(std::io::Cursor::new(signed)).unwrap();
+        let document = PdfDocument::new(reader);
+        let results = SignatureHandler::validate_document(&document).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].field_name, "sig1");
+        assert!(results[0].is_valid);
+        assert!(results[0].errors.is_empty());
+        assert_eq!(results[0].signer.as_ref().unwrap().name, "Test RSA Signer");
     }
 }
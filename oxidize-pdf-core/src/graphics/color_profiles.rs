@@ -129,8 +129,15 @@ impl StandardIccProfile {
     }
 
     /// Get a minimal ICC profile data for this standard profile
-    /// Note: In a real implementation, these would be actual ICC profile files
+    ///
+    /// sRGB is a complete ICC v2 display profile, suitable as the output
+    /// intent of PDF/A documents. The others are placeholders; embed the
+    /// actual ICC profile files for them.
     pub fn minimal_profile_data(&self) -> Vec<u8> {
+        if *self == StandardIccProfile::SRgb {
+            return srgb_profile_data();
+        }
+
         // This is a placeholder - real ICC profiles are binary data
         // In production, you would embed actual ICC profile files
         let profile_name = self.profile_name();
@@ -152,6 +159,96 @@ impl StandardIccProfile {
     }
 }
 
+/// ICC v2 display profile for sRGB: the primaries adapted to D50 and the
+/// sRGB transfer curve sampled at 1024 points (ICC.1:2001-04)
+fn srgb_profile_data() -> Vec<u8> {
+    fn xyz(values: [f64; 3]) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for value in values {
+            tag.extend_from_slice(&((value * 65536.0).round() as i32).to_be_bytes());
+        }
+        tag
+    }
+
+    let name = StandardIccProfile::SRgb.profile_name();
+    let mut desc = b"desc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&(name.len() as u32 + 1).to_be_bytes());
+    desc.extend_from_slice(name.as_bytes());
+    desc.push(0);
+    // Empty Unicode and ScriptCode descriptions
+    desc.extend_from_slice(&[0; 8 + 3 + 67]);
+
+    let mut cprt = b"text\0\0\0\0".to_vec();
+    cprt.extend_from_slice(b"No copyright, use freely\0");
+
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend_from_slice(&1024u32.to_be_bytes());
+    for i in 0..1024 {
+        let v = i as f64 / 1023.0;
+        let linear = if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        };
+        curve.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+
+    let blobs = [
+        desc,
+        cprt,
+        xyz([0.9642, 1.0, 0.8249]),
+        xyz([0.4361, 0.2225, 0.0139]),
+        xyz([0.3851, 0.7169, 0.0971]),
+        xyz([0.1431, 0.0606, 0.7141]),
+        curve,
+    ];
+    let tags: [(&[u8; 4], usize); 9] = [
+        (b"desc", 0),
+        (b"cprt", 1),
+        (b"wtpt", 2),
+        (b"rXYZ", 3),
+        (b"gXYZ", 4),
+        (b"bXYZ", 5),
+        (b"rTRC", 6),
+        (b"gTRC", 6),
+        (b"bTRC", 6),
+    ];
+
+    // Tag data follows the tag table, each element 4-byte aligned
+    let mut offsets = Vec::new();
+    let mut offset = 128 + 4 + tags.len() * 12;
+    for blob in &blobs {
+        offsets.push(offset);
+        offset += blob.len().div_ceil(4) * 4;
+    }
+
+    let mut data = Vec::with_capacity(offset);
+    data.extend_from_slice(&(offset as u32).to_be_bytes());
+    data.extend_from_slice(&[0; 4]); // Preferred CMM
+    data.extend_from_slice(&0x0210_0000u32.to_be_bytes());
+    data.extend_from_slice(b"mntrRGB XYZ ");
+    for part in [2024u16, 1, 1, 0, 0, 0] {
+        data.extend_from_slice(&part.to_be_bytes());
+    }
+    data.extend_from_slice(b"acsp");
+    data.extend_from_slice(&[0; 24]); // Platform, flags, device and attributes
+    data.extend_from_slice(&0u32.to_be_bytes()); // Perceptual intent
+    data.extend_from_slice(&xyz([0.9642, 1.0, 0.8249])[8..]);
+    data.resize(128, 0);
+
+    data.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    for (signature, blob) in tags {
+        data.extend_from_slice(signature);
+        data.extend_from_slice(&(offsets[blob] as u32).to_be_bytes());
+        data.extend_from_slice(&(blobs[blob].len() as u32).to_be_bytes());
+    }
+    for blob in &blobs {
+        data.extend_from_slice(blob);
+        data.resize(data.len().div_ceil(4) * 4, 0);
+    }
+    data
+}
+
 impl IccProfile {
     /// Create a new ICC profile
     pub fn new(name: String, data: Vec<u8>, color_space: IccColorSpace) -> Self {
@@ -505,6 +602,25 @@ mod tests {
         assert!(!profile.data.is_empty());
     }
 
+    #[test]
+    fn test_srgb_profile_is_valid_icc() {
+        let data = StandardIccProfile::SRgb.minimal_profile_data();
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        assert_eq!(size, data.len());
+        assert_eq!(&data[12..24], b"mntrRGB XYZ ");
+        assert_eq!(&data[36..40], b"acsp");
+
+        // Every tag lies within the profile
+        let count = u32::from_be_bytes([data[128], data[129], data[130], data[131]]) as usize;
+        assert_eq!(count, 9);
+        for i in 0..count {
+            let entry = &data[132 + i * 12..144 + i * 12];
+            let offset = u32::from_be_bytes([entry[4], entry[5], entry[6], entry[7]]) as usize;
+            let length = u32::from_be_bytes([entry[8], entry[9], entry[10], entry[11]]) as usize;
+            assert!(offset % 4 == 0 && offset + length <= size);
+        }
+    }
+
     #[test]
     fn test_icc_profile_with_range() {
         let data = vec![0u8; 200];
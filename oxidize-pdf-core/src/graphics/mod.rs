@@ -53,6 +53,8 @@ pub use transparency::TransparencyGroup;
 use transparency::TransparencyGroupState;
 
 use crate::error::Result;
+use crate::text::bidi;
+use crate::text::shaping::{ShapingFonts, UnshapedText};
 use crate::text::{ColumnContent, ColumnLayout, Font, FontManager, ListElement, Table};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
@@ -84,12 +86,24 @@ pub struct GraphicsContext {
     state_stack: Vec<(Color, Color)>,
     current_font_name: Option<String>,
     current_font_size: f64,
+    // Whether the current font is a custom font
+    custom_font: bool,
+    // Text spacing set with Tc and Tw, for shaped text
+    character_spacing: f64,
+    word_spacing: f64,
+    // Fonts of the page, shaping text shown in custom fonts
+    shaping_fonts: ShapingFonts,
+    // Text shown in custom fonts the page did not have yet
+    unshaped: Vec<UnshapedText>,
     // Character tracking for font subsetting
     used_characters: HashSet<char>,
     // Glyph mapping for Unicode fonts (Unicode code point -> Glyph ID)
     glyph_mapping: Option<HashMap<u32, u16>>,
     // Transparency group stack for nested groups
     transparency_stack: Vec<TransparencyGroupState>,
+    // Optional content: layers used, and how many are open
+    layers: Vec<String>,
+    open_layers: usize,
 }
 
 impl Default for GraphicsContext {
@@ -124,9 +138,16 @@ impl GraphicsContext {
             state_stack: Vec::new(),
             current_font_name: None,
             current_font_size: 12.0,
+            custom_font: false,
+            character_spacing: 0.0,
+            word_spacing: 0.0,
+            shaping_fonts: ShapingFonts::new(),
+            unshaped: Vec::new(),
             used_characters: HashSet::new(),
             glyph_mapping: None,
             transparency_stack: Vec::new(),
+            layers: Vec::new(),
+            open_layers: 0,
         }
     }
 
@@ -433,6 +454,34 @@ impl GraphicsContext {
         self.transparency_stack.last().map(|state| &state.group)
     }
 
+    /// Begin content that belongs to a layer or membership of the document
+    /// (ISO 32000-1 §8.11.3.2)
+    ///
+    /// See [`crate::optional_content`].
+    pub fn begin_layer(&mut self, layer: &crate::optional_content::LayerId) -> &mut Self {
+        writeln!(&mut self.operations, "/OC /{} BDC", layer.as_str())
+            .expect("Writing to string should never fail");
+        if !self.layers.iter().any(|name| name == layer.as_str()) {
+            self.layers.push(layer.as_str().to_string());
+        }
+        self.open_layers += 1;
+        self
+    }
+
+    /// End the content of the innermost layer
+    pub fn end_layer(&mut self) -> &mut Self {
+        if self.open_layers > 0 {
+            self.open_layers -= 1;
+            writeln!(&mut self.operations, "EMC").expect("Writing to string should never fail");
+        }
+        self
+    }
+
+    /// Resource names of the layers content was drawn in
+    pub(crate) fn layers(&self) -> &[String] {
+        &self.layers
+    }
+
     pub fn translate(&mut self, tx: f64, ty: f64) -> &mut Self {
         writeln!(&mut self.operations, "1 0 0 1 {tx:.2} {ty:.2} cm")
             .expect("Writing to string should never fail");
@@ -662,6 +711,7 @@ impl GraphicsContext {
     /// Clear all operations
     pub fn clear(&mut self) {
         self.operations.clear();
+        self.unshaped.clear();
     }
 
     /// Begin a text object
@@ -692,6 +742,7 @@ impl GraphicsContext {
                 self.current_font_size = size;
             }
         }
+        self.custom_font = font.is_custom();
 
         self
     }
@@ -704,26 +755,85 @@ impl GraphicsContext {
     }
 
     /// Show text
+    ///
+    /// Text in a registered custom font is shaped; other text is shown in
+    /// visual order, with right-to-left runs reversed.
     pub fn show_text(&mut self, text: &str) -> Result<&mut Self> {
+        if self.show_shaped_text(text) {
+            return Ok(self);
+        }
+
         // Escape special characters in PDF string
-        self.operations.push('(');
-        for ch in text.chars() {
+        let visual = bidi::reorder(text, None);
+        let mut shown = String::from("(");
+        for ch in visual.chars() {
             match ch {
-                '(' => self.operations.push_str("\\("),
-                ')' => self.operations.push_str("\\)"),
-                '\\' => self.operations.push_str("\\\\"),
-                '\n' => self.operations.push_str("\\n"),
-                '\r' => self.operations.push_str("\\r"),
-                '\t' => self.operations.push_str("\\t"),
-                _ => self.operations.push(ch),
+                '(' => shown.push_str("\\("),
+                ')' => shown.push_str("\\)"),
+                '\\' => shown.push_str("\\\\"),
+                '\n' => shown.push_str("\\n"),
+                '\r' => shown.push_str("\\r"),
+                '\t' => shown.push_str("\\t"),
+                _ => shown.push(ch),
             }
         }
-        self.operations.push_str(") Tj\n");
+        shown.push_str(") Tj\n");
+        let start = self.operations.len();
+        self.push_visual_text(text, &visual, &shown);
+        if let (true, Some(font_name)) = (self.custom_font, &self.current_font_name) {
+            self.unshaped.push(UnshapedText {
+                range: start..self.operations.len(),
+                shown: self.operations[start..].to_string(),
+                font_name: font_name.clone(),
+                text: text.to_string(),
+                base: None,
+                font_size: self.current_font_size,
+                character_spacing: self.character_spacing,
+                word_spacing: self.word_spacing,
+                rise: 0.0,
+            });
+        }
         Ok(self)
     }
 
+    /// Show text shaped with the current font, if it is a registered
+    /// custom font
+    fn show_shaped_text(&mut self, text: &str) -> bool {
+        let shaped = self.current_font_name.as_deref().and_then(|name| {
+            self.shaping_fonts.show_text(
+                name,
+                text,
+                None,
+                self.current_font_size,
+                self.character_spacing,
+                self.word_spacing,
+                0.0,
+            )
+        });
+        match shaped {
+            Some(shown) => {
+                self.used_characters.extend(text.chars());
+                self.operations.push_str(&shown);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add the operations showing `visual`, the text in visual order; in
+    /// an `ActualText` span if it differs from the text
+    fn push_visual_text(&mut self, text: &str, visual: &str, shown: &str) {
+        if visual == text {
+            self.operations.push_str(shown);
+        } else {
+            self.operations
+                .push_str(&bidi::actual_text_span(text, shown));
+        }
+    }
+
     /// Set word spacing for text justification
     pub fn set_word_spacing(&mut self, spacing: f64) -> &mut Self {
+        self.word_spacing = spacing;
         writeln!(&mut self.operations, "{spacing:.2} Tw")
             .expect("Writing to string should never fail");
         self
@@ -731,6 +841,7 @@ impl GraphicsContext {
 
     /// Set character spacing
     pub fn set_character_spacing(&mut self, spacing: f64) -> &mut Self {
+        self.character_spacing = spacing;
         writeln!(&mut self.operations, "{spacing:.2} Tc")
             .expect("Writing to string should never fail");
         self
@@ -1234,28 +1345,35 @@ impl GraphicsContext {
         writeln!(&mut self.operations, "{:.2} {:.2} Td", x, y)
             .expect("Writing to string should never fail");
 
+        // Registered fonts are shaped
+        if self.show_shaped_text(text) {
+            self.operations.push_str("ET\n");
+            return Ok(self);
+        }
+
         // IMPORTANT: For Type0 fonts with Identity-H encoding, we write CIDs (Character IDs),
         // NOT GlyphIDs. The CIDToGIDMap in the font handles the CID -> GlyphID conversion.
         // In our case, we use Unicode code points as CIDs.
-        self.operations.push('<');
+        let visual = bidi::reorder(text, None);
+        let mut shown = String::from("<");
 
-        for ch in text.chars() {
+        for ch in visual.chars() {
             let code = ch as u32;
 
             // For Type0 fonts with Identity-H encoding, write the Unicode code point as CID
             // The CIDToGIDMap will handle the conversion to the actual glyph ID
             if code <= 0xFFFF {
                 // Write the Unicode code point as a 2-byte hex value (CID)
-                write!(&mut self.operations, "{:04X}", code)
-                    .expect("Writing to string should never fail");
+                write!(&mut shown, "{:04X}", code).expect("Writing to string should never fail");
             } else {
                 // Characters outside BMP - use replacement character
                 // Most PDF viewers don't handle supplementary planes well
-                write!(&mut self.operations, "FFFD").expect("Writing to string should never fail");
+                write!(&mut shown, "FFFD").expect("Writing to string should never fail");
                 // Unicode replacement character
             }
         }
-        self.operations.push_str("> Tj\n");
+        shown.push_str("> Tj\n");
+        self.push_visual_text(text, &visual, &shown);
 
         // End text object
         self.operations.push_str("ET\n");
@@ -1446,6 +1564,20 @@ impl GraphicsContext {
         Ok(self)
     }
 
+    /// Shape text in custom fonts with these fonts, including text already
+    /// shown in them
+    pub(crate) fn set_shaping_fonts(&mut self, fonts: &ShapingFonts) {
+        self.shaping_fonts = fonts.clone();
+        let mut operations = std::mem::take(&mut self.operations).into_bytes();
+        fonts.shape_unshaped(&mut self.unshaped, &mut operations);
+        self.operations = String::from_utf8(operations).expect("Shaped operations should be UTF-8");
+    }
+
+    /// Fonts this context shapes text with
+    pub(crate) fn shaping_fonts(&self) -> &ShapingFonts {
+        &self.shaping_fonts
+    }
+
     /// Get the characters used in this graphics context
     pub(crate) fn get_used_characters(&self) -> Option<HashSet<char>> {
         if self.used_characters.is_empty() {
@@ -1965,6 +2097,17 @@ mod tests {
         assert!(ctx.operations().contains("(Line\\nBreak) Tj\n"));
     }
 
+    #[test]
+    fn test_show_text_right_to_left() {
+        let mut ctx = GraphicsContext::new();
+        ctx.show_text("אב 12")
+            .expect("Writing to string should never fail");
+        assert_eq!(
+            ctx.operations(),
+            "/Span <</ActualText <FEFF05D005D1002000310032>>> BDC\n(12 בא) Tj\nEMC\n"
+        );
+    }
+
     #[test]
     fn test_text_operations_chaining() {
         let mut ctx = GraphicsContext::new();
@@ -101,6 +101,7 @@
 //! - [`operations`] - PDF manipulation (split, merge, rotate, extract images)
 //! - [`operations::page_analysis`] - Page content analysis and scanned page detection
 //! - [`text::extraction`] - Text extraction with positioning
+//! - [`rendering`] - Page rasterization to RGBA bitmaps and PNG
 //!
 //! ### OCR Modules (v0.1.3+)
 //! - [`text::ocr`] - OCR trait system and types
@@ -178,6 +179,7 @@ pub mod actions;
 pub mod advanced_tables;
 pub mod ai;
 pub mod annotations;
+pub mod attachments;
 
 pub mod batch;
 pub mod charts;
@@ -194,6 +196,7 @@ pub mod memory;
 pub mod metadata;
 pub mod objects;
 pub mod operations;
+pub mod optional_content;
 pub mod page;
 pub mod page_forms;
 pub mod page_labels;
@@ -206,6 +209,8 @@ pub mod pdf_objects;
 #[cfg(feature = "performance")]
 pub mod performance;
 pub mod recovery;
+pub mod rendering;
+pub mod signatures;
 pub mod streaming;
 pub mod structure;
 pub mod templates;
@@ -360,6 +365,8 @@ pub use verification::compliance_report::{
     format_report_markdown, generate_compliance_report, ComplianceReport,
 };
 pub use verification::iso_matrix::{load_default_matrix, load_matrix, ComplianceStats, IsoMatrix};
+pub use verification::pdfa::{check_pdfa, PdfALevel, PdfAReport, PdfAViolation};
+pub use verification::pdfua::{check_pdfua, PdfUaCheckpoint, PdfUaReport, PdfUaViolation};
 pub use verification::validators::{
     check_available_validators, validate_external, validate_with_qpdf,
 };
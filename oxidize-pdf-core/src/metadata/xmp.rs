@@ -78,7 +78,7 @@ use crate::error::Result;
 use crate::parser::objects::{PdfDictionary, PdfName, PdfObject, PdfStream};
 use quick_xml::events::Event;
 use quick_xml::Reader;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Standard XMP namespaces
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -145,6 +145,8 @@ pub enum XmpValue {
     Struct(HashMap<String, Box<XmpValue>>),
     /// Array of structured properties
     ArrayStruct(Vec<HashMap<String, Box<XmpValue>>>),
+    /// Unordered bag of structured properties
+    BagStruct(Vec<HashMap<String, Box<XmpValue>>>),
 }
 
 /// XMP property
@@ -326,6 +328,25 @@ impl XmpMetadata {
         });
     }
 
+    /// Set an unordered bag of structured properties
+    pub fn set_bag_struct(
+        &mut self,
+        namespace: XmpNamespace,
+        name: impl Into<String>,
+        items: Vec<HashMap<String, XmpValue>>,
+    ) {
+        let boxed_items: Vec<HashMap<String, Box<XmpValue>>> = items
+            .into_iter()
+            .map(|item| item.into_iter().map(|(k, v)| (k, Box::new(v))).collect())
+            .collect();
+
+        self.properties.push(XmpProperty {
+            namespace,
+            name: name.into(),
+            value: XmpValue::BagStruct(boxed_items),
+        });
+    }
+
     /// Register a custom namespace
     pub fn register_namespace(&mut self, prefix: String, uri: String) {
         self.custom_namespaces.insert(prefix, uri);
@@ -350,7 +371,7 @@ impl XmpMetadata {
         xml.push_str("    <rdf:Description rdf:about=\"\"");
 
         // Add namespace declarations
-        let mut namespaces: HashMap<String, String> = HashMap::new();
+        let mut namespaces: BTreeMap<String, String> = BTreeMap::new();
         for prop in &self.properties {
             namespaces.insert(
                 prop.namespace.prefix().to_string(),
@@ -426,18 +447,19 @@ impl XmpMetadata {
                 XmpValue::Struct(fields) => {
                     xml.push_str(&format!("      <{}:{}>\n", prefix, prop.name));
                     xml.push_str("        <rdf:Description>\n");
-                    for (field_name, field_value) in fields {
+                    for (field_name, field_value) in Self::sorted_fields(fields) {
                         Self::serialize_value(&mut xml, field_name, field_value, "          ");
                     }
                     xml.push_str("        </rdf:Description>\n");
                     xml.push_str(&format!("      </{}:{}>\n", prefix, prop.name));
                 }
-                XmpValue::ArrayStruct(items) => {
+                XmpValue::ArrayStruct(items) | XmpValue::BagStruct(items) => {
+                    let container = Self::struct_container(&prop.value);
                     xml.push_str(&format!("      <{}:{}>\n", prefix, prop.name));
-                    xml.push_str("        <rdf:Seq>\n");
+                    xml.push_str(&format!("        <{}>\n", container));
                     for item in items {
                         xml.push_str("          <rdf:li rdf:parseType=\"Resource\">\n");
-                        for (field_name, field_value) in item {
+                        for (field_name, field_value) in Self::sorted_fields(item) {
                             Self::serialize_value(
                                 &mut xml,
                                 field_name,
@@ -447,7 +469,7 @@ impl XmpMetadata {
                         }
                         xml.push_str("          </rdf:li>\n");
                     }
-                    xml.push_str("        </rdf:Seq>\n");
+                    xml.push_str(&format!("        </{}>\n", container));
                     xml.push_str(&format!("      </{}:{}>\n", prefix, prop.name));
                 }
             }
@@ -833,21 +855,22 @@ impl XmpMetadata {
             XmpValue::Struct(fields) => {
                 xml.push_str(&format!("{}<{}>\n", indent, name));
                 xml.push_str(&format!("{}  <rdf:Description>\n", indent));
-                for (field_name, field_value) in fields {
+                for (field_name, field_value) in Self::sorted_fields(fields) {
                     Self::serialize_value(xml, field_name, field_value, &format!("{}    ", indent));
                 }
                 xml.push_str(&format!("{}  </rdf:Description>\n", indent));
                 xml.push_str(&format!("{}</{}>\n", indent, name));
             }
-            XmpValue::ArrayStruct(items) => {
+            XmpValue::ArrayStruct(items) | XmpValue::BagStruct(items) => {
+                let container = Self::struct_container(value);
                 xml.push_str(&format!("{}<{}>\n", indent, name));
-                xml.push_str(&format!("{}  <rdf:Seq>\n", indent));
+                xml.push_str(&format!("{}  <{}>\n", indent, container));
                 for item in items {
                     xml.push_str(&format!(
                         "{}    <rdf:li rdf:parseType=\"Resource\">\n",
                         indent
                     ));
-                    for (field_name, field_value) in item {
+                    for (field_name, field_value) in Self::sorted_fields(item) {
                         Self::serialize_value(
                             xml,
                             field_name,
@@ -857,12 +880,28 @@ impl XmpMetadata {
                     }
                     xml.push_str(&format!("{}    </rdf:li>\n", indent));
                 }
-                xml.push_str(&format!("{}  </rdf:Seq>\n", indent));
+                xml.push_str(&format!("{}  </{}>\n", indent, container));
                 xml.push_str(&format!("{}</{}>\n", indent, name));
             }
         }
     }
 
+    /// Fields of a structure in name order, so output is reproducible
+    fn sorted_fields(fields: &HashMap<String, Box<XmpValue>>) -> BTreeMap<&String, &XmpValue> {
+        fields
+            .iter()
+            .map(|(name, value)| (name, value.as_ref()))
+            .collect()
+    }
+
+    /// RDF container element of an array of structures
+    fn struct_container(value: &XmpValue) -> &'static str {
+        match value {
+            XmpValue::BagStruct(_) => "rdf:Bag",
+            _ => "rdf:Seq",
+        }
+    }
+
     /// Validate ISO 8601 date format
     /// Supports: YYYY, YYYY-MM, YYYY-MM-DD, YYYY-MM-DDThh:mm:ssTZD
     fn is_valid_iso8601_date(date: &str) -> bool {
@@ -1302,6 +1341,44 @@ mod tests {
         assert!(packet.contains("<action>saved</action>"));
     }
 
+    #[test]
+    fn test_bag_of_nested_structs() {
+        let mut xmp = XmpMetadata::new();
+
+        let mut property = HashMap::new();
+        property.insert(
+            "pdfaProperty:name".to_string(),
+            XmpValue::Text("Version".to_string()),
+        );
+        let mut schema = HashMap::new();
+        schema.insert(
+            "pdfaSchema:prefix".to_string(),
+            XmpValue::Text("fx".to_string()),
+        );
+        schema.insert(
+            "pdfaSchema:property".to_string(),
+            XmpValue::ArrayStruct(vec![property
+                .into_iter()
+                .map(|(k, v)| (k, Box::new(v)))
+                .collect()]),
+        );
+        xmp.set_bag_struct(
+            XmpNamespace::Custom(
+                "pdfaExtension".to_string(),
+                "http://www.aiim.org/pdfa/ns/extension/".to_string(),
+            ),
+            "schemas",
+            vec![schema],
+        );
+
+        let packet = xmp.to_xmp_packet();
+        let bag = packet.find("<rdf:Bag>").unwrap();
+        let seq = packet.find("<rdf:Seq>").unwrap();
+        assert!(bag < seq);
+        assert!(packet.contains("<pdfaSchema:prefix>fx</pdfaSchema:prefix>"));
+        assert!(packet.contains("<pdfaProperty:name>Version</pdfaProperty:name>"));
+    }
+
     #[test]
     fn test_parse_structured_properties() {
         let xml = r#"<?xpacket begin="﻿"?>
@@ -1,22 +1,23 @@
 use crate::objects::Object;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
+/// A PDF dictionary; entries are kept in key order so that the same
+/// document is always written the same way
 #[derive(Debug, Clone, PartialEq)]
 pub struct Dictionary {
-    entries: HashMap<String, Object>,
+    entries: BTreeMap<String, Object>,
 }
 
 impl Dictionary {
     pub fn new() -> Self {
         Self {
-            entries: HashMap::new(),
+            entries: BTreeMap::new(),
         }
     }
 
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            entries: HashMap::with_capacity(capacity),
-        }
+    /// An empty dictionary; the capacity is unused as entries are ordered
+    pub fn with_capacity(_capacity: usize) -> Self {
+        Self::new()
     }
 
     pub fn set(&mut self, key: impl Into<String>, value: impl Into<Object>) {
@@ -187,6 +188,17 @@ mod tests {
         assert_eq!(keys, vec!["First", "Second", "Third"]);
     }
 
+    #[test]
+    fn test_entries_in_key_order() {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name("Page".to_string()));
+        dict.set("Contents", 4);
+        dict.set("MediaBox", 0);
+
+        let keys: Vec<_> = dict.entries().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["Contents", "MediaBox", "Type"]);
+    }
+
     #[test]
     fn test_values() {
         let mut dict = Dictionary::new();
@@ -151,6 +151,80 @@ impl From<Dictionary> for Object {
     }
 }
 
+/// Converts a parsed object into a writer object so it can be re-emitted,
+/// e.g. when an incremental update rewrites an existing object.
+///
+/// Streams keep their raw (still encoded) data together with their `/Filter`.
+/// Strings are converted lossily because writer strings are UTF-8.
+impl From<&crate::parser::objects::PdfObject> for Object {
+    fn from(obj: &crate::parser::objects::PdfObject) -> Self {
+        use crate::parser::objects::PdfObject;
+
+        match obj {
+            PdfObject::Null => Object::Null,
+            PdfObject::Boolean(b) => Object::Boolean(*b),
+            PdfObject::Integer(i) => Object::Integer(*i),
+            PdfObject::Real(f) => Object::Real(*f),
+            PdfObject::String(s) => Object::String(String::from_utf8_lossy(s.as_bytes()).into()),
+            PdfObject::Name(n) => Object::Name(n.as_str().to_string()),
+            PdfObject::Array(arr) => Object::Array(arr.0.iter().map(Object::from).collect()),
+            PdfObject::Dictionary(dict) => Object::Dictionary(Dictionary::from(dict)),
+            PdfObject::Stream(stream) => {
+                Object::Stream(Dictionary::from(&stream.dict), stream.data.clone())
+            }
+            PdfObject::Reference(num, gen) => Object::Reference(ObjectId::new(*num, *gen)),
+        }
+    }
+}
+
+impl From<&crate::parser::objects::PdfDictionary> for Dictionary {
+    fn from(dict: &crate::parser::objects::PdfDictionary) -> Self {
+        let mut result = Dictionary::new();
+        for (key, value) in &dict.0 {
+            result.set(key.as_str(), Object::from(value));
+        }
+        result
+    }
+}
+
+/// Converts a writer object into parsed form, e.g. to write it next to
+/// parsed objects in an incremental update.
+///
+/// Writer strings keep their UTF-8 bytes.
+impl From<&Object> for crate::parser::objects::PdfObject {
+    fn from(obj: &Object) -> Self {
+        use crate::parser::objects::{PdfArray, PdfName, PdfObject, PdfStream, PdfString};
+
+        match obj {
+            Object::Null => PdfObject::Null,
+            Object::Boolean(b) => PdfObject::Boolean(*b),
+            Object::Integer(i) => PdfObject::Integer(*i),
+            Object::Real(f) => PdfObject::Real(*f),
+            Object::String(s) => PdfObject::String(PdfString::new(s.as_bytes().to_vec())),
+            Object::Name(n) => PdfObject::Name(PdfName::new(n.clone())),
+            Object::Array(items) => {
+                PdfObject::Array(PdfArray(items.iter().map(Self::from).collect()))
+            }
+            Object::Dictionary(dict) => PdfObject::Dictionary(dict.into()),
+            Object::Stream(dict, data) => PdfObject::Stream(PdfStream {
+                dict: dict.into(),
+                data: data.clone(),
+            }),
+            Object::Reference(id) => PdfObject::Reference(id.number(), id.generation()),
+        }
+    }
+}
+
+impl From<&Dictionary> for crate::parser::objects::PdfDictionary {
+    fn from(dict: &Dictionary) -> Self {
+        let mut result = Self::new();
+        for (key, value) in dict.entries() {
+            result.insert(key.clone(), value.into());
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -371,4 +445,34 @@ mod tests {
             Object::String("B".to_string())
         );
     }
+
+    #[test]
+    fn test_from_parsed_object() {
+        use crate::parser::objects::{PdfArray, PdfDictionary, PdfName, PdfObject, PdfString};
+
+        let mut parsed = PdfDictionary::new();
+        parsed.insert(
+            "Type".to_string(),
+            PdfObject::Name(PdfName::new("Annot".into())),
+        );
+        parsed.insert(
+            "T".to_string(),
+            PdfObject::String(PdfString::new(b"Sig1".to_vec())),
+        );
+        parsed.insert(
+            "Rect".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(0), PdfObject::Real(1.5)])),
+        );
+        parsed.insert("P".to_string(), PdfObject::Reference(7, 0));
+
+        let obj = Object::from(&PdfObject::Dictionary(parsed));
+        let dict = obj.as_dict().expect("dictionary");
+        assert_eq!(dict.get("Type"), Some(&Object::Name("Annot".to_string())));
+        assert_eq!(dict.get("T"), Some(&Object::String("Sig1".to_string())));
+        assert_eq!(
+            dict.get("Rect"),
+            Some(&Object::Array(vec![Object::Integer(0), Object::Real(1.5)]))
+        );
+        assert_eq!(dict.get("P"), Some(&Object::Reference(ObjectId::new(7, 0))));
+    }
 }
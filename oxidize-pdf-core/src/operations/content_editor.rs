@@ -0,0 +1,1009 @@
+//! Content stream editing
+//!
+//! [`ContentEditor`] holds the parsed operations of a content stream and
+//! rewrites them: operators can be filtered, replaced or inserted, text and
+//! images drawn within an area removed, and strings re-encoded for the
+//! active font. The result is serialized back into content stream bytes.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use oxidize_pdf::geometry::{Point, Rectangle};
+//! use oxidize_pdf::operations::ContentEditor;
+//! use oxidize_pdf::parser::{PdfDocument, PdfReader};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let document = PdfDocument::new(PdfReader::open("draft.pdf")?);
+//! let mut editor = ContentEditor::from_page(&document, 0)?;
+//! // Remove a stamp in the top right corner
+//! let area = Rectangle::new(Point::new(400.0, 700.0), Point::new(612.0, 792.0));
+//! editor.remove_text_in(area);
+//! editor.remove_images_in(area);
+//! let content = editor.to_bytes();
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{OperationError, OperationResult};
+use crate::coordinate_system::TransformMatrix;
+use crate::geometry::{Point, Rectangle};
+use crate::parser::content::{ContentOperation, ContentParser, TextElement};
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{get_dict, get_name, Resolver};
+use crate::parser::PdfDocument;
+use crate::rendering::font::Font;
+use crate::writer::serialize_operations;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+/// Nominal glyph box height in text space units, from the baseline
+const GLYPH_BOTTOM: f64 = -0.2;
+const GLYPH_TOP: f64 = 0.8;
+
+/// Editable operations of a content stream
+///
+/// Fonts and XObjects of the page resources are known when the editor is
+/// created with [`ContentEditor::from_page`]. Otherwise glyphs have no
+/// width, strings cannot be decoded, and every `Do` counts as an image.
+#[derive(Debug)]
+pub struct ContentEditor {
+    operations: Vec<ContentOperation>,
+    fonts: HashMap<String, Font>,
+    /// XObjects that are forms rather than images
+    forms: HashSet<String>,
+    /// Transformation from the content's space to default user space
+    base: TransformMatrix,
+}
+
+/// Where an image or form XObject is drawn
+#[derive(Debug, Clone)]
+pub(crate) struct Placement {
+    /// Index of the painting operation
+    pub index: usize,
+    /// XObject name, `None` for an inline image
+    pub name: Option<String>,
+    pub form: bool,
+    /// Maps the unit square (for images) or form space to default user space
+    pub ctm: TransformMatrix,
+}
+
+/// A glyph shown by a text operator
+struct Glyph {
+    code: u32,
+    length: usize,
+    center: Point,
+    bounds: Rectangle,
+}
+
+impl Default for ContentEditor {
+    fn default() -> Self {
+        Self {
+            operations: Vec::new(),
+            fonts: HashMap::new(),
+            forms: HashSet::new(),
+            base: TransformMatrix::IDENTITY,
+        }
+    }
+}
+
+impl ContentEditor {
+    /// Edit a list of operations
+    pub fn new(operations: Vec<ContentOperation>) -> Self {
+        Self {
+            operations,
+            ..Self::default()
+        }
+    }
+
+    /// Parse content stream bytes
+    pub fn parse(content: &[u8]) -> OperationResult<Self> {
+        let operations =
+            ContentParser::parse(content).map_err(|e| OperationError::ParseError(e.to_string()))?;
+        Ok(Self::new(operations))
+    }
+
+    /// Parse the content of a page (zero-based index), loading the fonts
+    /// and XObjects of its resources
+    pub fn from_page<R: Read + Seek>(
+        document: &PdfDocument<R>,
+        page_index: u32,
+    ) -> OperationResult<Self> {
+        let parse_error = |e: crate::parser::ParseError| OperationError::ParseError(e.to_string());
+        let page = document.get_page(page_index).map_err(parse_error)?;
+        let content = document
+            .get_page_content_streams(&page)
+            .map_err(parse_error)?
+            .join(&b'\n');
+        let resolver: &dyn Resolver = document;
+        let resources =
+            get_dict(resolver, &page.dict, "Resources").or(page.inherited_resources.clone());
+        Self::from_resources(document, &content, resources.as_ref())
+    }
+
+    /// Parse content bytes drawn with `resources`, such as those of a form
+    /// XObject
+    pub(crate) fn from_resources<R: Read + Seek>(
+        document: &PdfDocument<R>,
+        content: &[u8],
+        resources: Option<&PdfDictionary>,
+    ) -> OperationResult<Self> {
+        let mut editor = Self::parse(content)?;
+        let resolver: &dyn Resolver = document;
+        let Some(resources) = resources else {
+            return Ok(editor);
+        };
+        for (name, font) in get_dict(resolver, resources, "Font").unwrap_or_default().0 {
+            if let PdfObject::Dictionary(dict) = resolver.lookup(&font) {
+                let font = Font::load(resolver, &dict);
+                editor.fonts.insert(name.as_str().to_string(), font);
+            }
+        }
+        for (name, xobject) in get_dict(resolver, resources, "XObject")
+            .unwrap_or_default()
+            .0
+        {
+            if let PdfObject::Stream(stream) = resolver.lookup(&xobject) {
+                if get_name(resolver, &stream.dict, "Subtype").as_deref() == Some("Form") {
+                    editor.forms.insert(name.as_str().to_string());
+                }
+            }
+        }
+        Ok(editor)
+    }
+
+    /// Set the transformation from the content's space to default user
+    /// space, for content drawn through a form XObject
+    pub(crate) fn with_transform(mut self, base: TransformMatrix) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// The current operations
+    pub fn operations(&self) -> &[ContentOperation] {
+        &self.operations
+    }
+
+    /// Take the edited operations
+    pub fn into_operations(self) -> Vec<ContentOperation> {
+        self.operations
+    }
+
+    /// Serialize the operations into content stream bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize_operations(&self.operations)
+    }
+
+    /// Keep only the operations for which `keep` returns true
+    pub fn retain(&mut self, keep: impl FnMut(&ContentOperation) -> bool) -> &mut Self {
+        self.operations.retain(keep);
+        self
+    }
+
+    /// Replace operations: `replace` returns the operations to put in place
+    /// of an operation (none to remove it), or `None` to keep it
+    pub fn replace(
+        &mut self,
+        mut replace: impl FnMut(&ContentOperation) -> Option<Vec<ContentOperation>>,
+    ) -> &mut Self {
+        let mut operations = Vec::with_capacity(self.operations.len());
+        for operation in std::mem::take(&mut self.operations) {
+            match replace(&operation) {
+                Some(replacement) => operations.extend(replacement),
+                None => operations.push(operation),
+            }
+        }
+        self.operations = operations;
+        self
+    }
+
+    /// Insert operations before the operation at `index`
+    ///
+    /// An index past the end appends the operations.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        operations: impl IntoIterator<Item = ContentOperation>,
+    ) -> &mut Self {
+        let index = index.min(self.operations.len());
+        self.operations.splice(index..index, operations);
+        self
+    }
+
+    /// Insert operations at the start, wrapped in `q`/`Q` so they don't
+    /// change the graphics state of the existing content
+    pub fn prepend(&mut self, operations: impl IntoIterator<Item = ContentOperation>) -> &mut Self {
+        let wrapped = std::iter::once(ContentOperation::SaveGraphicsState)
+            .chain(operations)
+            .chain(std::iter::once(ContentOperation::RestoreGraphicsState));
+        self.insert(0, wrapped.collect::<Vec<_>>())
+    }
+
+    /// Add operations at the end, drawn in the initial graphics state
+    ///
+    /// The existing content is wrapped in `q`/`Q`, balanced if needed, so it
+    /// can't change the graphics state of the new operations.
+    pub fn append(&mut self, operations: impl IntoIterator<Item = ContentOperation>) -> &mut Self {
+        if !self.operations.is_empty() {
+            let (mut depth, mut unmatched) = (0usize, 0usize);
+            for operation in &self.operations {
+                match operation {
+                    ContentOperation::SaveGraphicsState => depth += 1,
+                    ContentOperation::RestoreGraphicsState if depth == 0 => unmatched += 1,
+                    ContentOperation::RestoreGraphicsState => depth -= 1,
+                    _ => {}
+                }
+            }
+            for _ in 0..=unmatched {
+                self.operations
+                    .insert(0, ContentOperation::SaveGraphicsState);
+            }
+            for _ in 0..=depth {
+                self.operations.push(ContentOperation::RestoreGraphicsState);
+            }
+        }
+        self.operations.extend(operations);
+        self
+    }
+
+    /// Encode text as codes of a font of the page, `None` if the font is
+    /// unknown or lacks a character
+    pub fn encode_text(&self, font: &str, text: &str) -> Option<Vec<u8>> {
+        self.fonts.get(font)?.encode(text)
+    }
+
+    /// Decode a string shown in a font of the page, `None` if the font is
+    /// unknown or a code has no known text
+    pub fn decode_text(&self, font: &str, bytes: &[u8]) -> Option<String> {
+        let font = self.fonts.get(font)?;
+        font.codes(bytes)
+            .into_iter()
+            .map(|(code, length)| font.unicode(code, length))
+            .collect()
+    }
+
+    /// Rewrite shown text, re-encoded for the font active where it is shown
+    ///
+    /// `rewrite` gets the text of each string operand that can be decoded
+    /// (strings of a `TJ` array separately) and returns its replacement, or
+    /// `None` to keep it. Returns the number of strings replaced; fails if a
+    /// replacement has characters the font cannot show, leaving the
+    /// operations unchanged.
+    pub fn replace_text(
+        &mut self,
+        mut rewrite: impl FnMut(&str) -> Option<String>,
+    ) -> OperationResult<usize> {
+        let mut operations = self.operations.clone();
+        let mut fonts: Vec<Option<String>> = Vec::new();
+        let mut font: Option<String> = None;
+        let mut replaced = 0;
+
+        for operation in &mut operations {
+            let strings: Vec<&mut Vec<u8>> = match operation {
+                ContentOperation::SaveGraphicsState => {
+                    fonts.push(font.clone());
+                    continue;
+                }
+                ContentOperation::RestoreGraphicsState => {
+                    if let Some(saved) = fonts.pop() {
+                        font = saved;
+                    }
+                    continue;
+                }
+                ContentOperation::SetFont(name, _) => {
+                    font = Some(name.clone());
+                    continue;
+                }
+                ContentOperation::ShowText(bytes)
+                | ContentOperation::NextLineShowText(bytes)
+                | ContentOperation::SetSpacingNextLineShowText(_, _, bytes) => vec![bytes],
+                ContentOperation::ShowTextArray(elements) => elements
+                    .iter_mut()
+                    .filter_map(|element| match element {
+                        TextElement::Text(bytes) => Some(bytes),
+                        TextElement::Spacing(_) => None,
+                    })
+                    .collect(),
+                _ => continue,
+            };
+            let Some(name) = &font else {
+                continue;
+            };
+            for bytes in strings {
+                let Some(text) = self.decode_text(name, bytes) else {
+                    continue;
+                };
+                if let Some(new_text) = rewrite(&text) {
+                    *bytes = self.encode_text(name, &new_text).ok_or_else(|| {
+                        OperationError::ProcessingError(format!(
+                            "font {name} cannot show {new_text:?}"
+                        ))
+                    })?;
+                    replaced += 1;
+                }
+            }
+        }
+
+        self.operations = operations;
+        Ok(replaced)
+    }
+
+    /// Remove the glyphs whose center lies within `area` (in default user
+    /// space), returning how many were removed
+    ///
+    /// Removed glyphs are replaced by spacing in a `TJ` array, so the
+    /// remaining text keeps its position.
+    pub fn remove_text_in(&mut self, area: Rectangle) -> usize {
+        self.remove_glyphs(|glyph| contains(&area, glyph.center))
+    }
+
+    /// Remove the glyphs whose box overlaps `area` (in default user space),
+    /// returning how many were removed
+    ///
+    /// Unlike [`remove_text_in`](Self::remove_text_in), glyphs only partly
+    /// covered are removed too, as redaction requires.
+    pub fn remove_text_touching(&mut self, area: Rectangle) -> usize {
+        self.remove_glyphs(|glyph| overlaps(&area, &glyph.bounds))
+    }
+
+    /// Find the occurrences of `needle` in the shown text, returning the
+    /// box (in default user space) of each
+    ///
+    /// Text is matched in content order, across operators; glyphs whose
+    /// text is unknown match nothing.
+    pub fn find_text(&self, needle: &str) -> Vec<Rectangle> {
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let mut text = String::new();
+        // Byte offset in `text` where each glyph starts, and its box
+        let mut glyphs: Vec<(usize, Rectangle)> = Vec::new();
+        let mut state = self.state();
+        for operation in &self.operations {
+            let Some((prefix, elements)) = shown(operation) else {
+                state.apply(operation);
+                continue;
+            };
+            for op in &prefix {
+                state.apply(op);
+            }
+            let font = state
+                .text
+                .font
+                .as_ref()
+                .and_then(|name| self.fonts.get(name));
+            state.show(&self.fonts, &elements, &mut |glyph| {
+                glyphs.push((text.len(), glyph.bounds));
+                match font.and_then(|f| f.unicode(glyph.code, glyph.length)) {
+                    Some(unicode) => text.push_str(&unicode),
+                    None => text.push('\u{FFFD}'),
+                }
+                false
+            });
+        }
+
+        let mut found = Vec::new();
+        for (start, _) in text.match_indices(needle) {
+            let end = start + needle.len();
+            let boxes: Vec<Point> = glyphs
+                .iter()
+                .filter(|(offset, _)| (start..end).contains(offset))
+                .flat_map(|(_, bounds)| [bounds.lower_left, bounds.upper_right])
+                .collect();
+            if !boxes.is_empty() {
+                found.push(bounding_box(&boxes));
+            }
+        }
+        found
+    }
+
+    /// Remove glyphs for which `remove` returns true
+    fn remove_glyphs(&mut self, mut remove: impl FnMut(&Glyph) -> bool) -> usize {
+        let mut state = self.state();
+        let mut removed = 0;
+        let mut operations = Vec::with_capacity(self.operations.len());
+
+        for operation in std::mem::take(&mut self.operations) {
+            let Some((prefix, elements)) = shown(&operation) else {
+                state.apply(&operation);
+                operations.push(operation);
+                continue;
+            };
+            for op in &prefix {
+                state.apply(op);
+            }
+            let (kept, count) = state.show(&self.fonts, &elements, &mut remove);
+            if count == 0 {
+                operations.push(operation);
+            } else {
+                removed += count;
+                operations.extend(prefix);
+                operations.push(ContentOperation::ShowTextArray(kept));
+            }
+        }
+
+        self.operations = operations;
+        removed
+    }
+
+    /// Remove the paths painted entirely within `area` (in default user
+    /// space), returning how many were removed
+    ///
+    /// Paths that also set the clipping path are kept, since removing them
+    /// would uncover content drawn after them. Other operators found among
+    /// the path operators, such as color changes, are kept.
+    pub fn remove_paths_in(&mut self, area: Rectangle) -> usize {
+        use ContentOperation as Op;
+
+        let mut state = self.state();
+        let mut removed = 0;
+        let mut operations = Vec::with_capacity(self.operations.len());
+        // Operations of the path under construction, flagged when they
+        // build the path, and the points of the path
+        let mut path: Vec<(ContentOperation, bool)> = Vec::new();
+        let mut points: Vec<Point> = Vec::new();
+        let mut clips = false;
+
+        for operation in std::mem::take(&mut self.operations) {
+            let coordinates: Vec<(f32, f32)> = match &operation {
+                Op::MoveTo(x, y) | Op::LineTo(x, y) => vec![(*x, *y)],
+                Op::CurveTo(x1, y1, x2, y2, x3, y3) => vec![(*x1, *y1), (*x2, *y2), (*x3, *y3)],
+                Op::CurveToV(x2, y2, x3, y3) | Op::CurveToY(x2, y2, x3, y3) => {
+                    vec![(*x2, *y2), (*x3, *y3)]
+                }
+                Op::Rectangle(x, y, w, h) => {
+                    vec![(*x, *y), (x + w, *y), (*x, y + h), (x + w, y + h)]
+                }
+                Op::ClosePath => vec![],
+                Op::Clip | Op::ClipEvenOdd => {
+                    clips = true;
+                    path.push((operation, true));
+                    continue;
+                }
+                Op::Stroke
+                | Op::CloseStroke
+                | Op::Fill
+                | Op::FillEvenOdd
+                | Op::FillStroke
+                | Op::FillStrokeEvenOdd
+                | Op::CloseFillStroke
+                | Op::CloseFillStrokeEvenOdd
+                | Op::EndPath => {
+                    let inside = !clips
+                        && !points.is_empty()
+                        && points.iter().all(|point| contains(&area, *point));
+                    if inside {
+                        removed += 1;
+                        operations.extend(
+                            path.drain(..)
+                                .filter(|(_, building)| !building)
+                                .map(|(op, _)| op),
+                        );
+                    } else {
+                        operations.extend(path.drain(..).map(|(op, _)| op));
+                        operations.push(operation);
+                    }
+                    points.clear();
+                    clips = false;
+                    continue;
+                }
+                _ => {
+                    state.apply(&operation);
+                    if path.is_empty() {
+                        operations.push(operation);
+                    } else {
+                        path.push((operation, false));
+                    }
+                    continue;
+                }
+            };
+            points.extend(
+                coordinates
+                    .into_iter()
+                    .map(|(x, y)| state.ctm.transform_point(Point::new(x as f64, y as f64))),
+            );
+            path.push((operation, true));
+        }
+        operations.extend(path.into_iter().map(|(op, _)| op));
+
+        self.operations = operations;
+        removed
+    }
+
+    /// Remove the images drawn entirely within `area` (in default user
+    /// space), returning how many were removed
+    ///
+    /// Image XObjects and inline images are removed; form XObjects are
+    /// kept.
+    pub fn remove_images_in(&mut self, area: Rectangle) -> usize {
+        let mut state = self.state();
+        let mut removed = 0;
+        let forms = &self.forms;
+        self.operations.retain(|operation| {
+            let is_image = match operation {
+                ContentOperation::PaintXObject(name) => !forms.contains(name),
+                ContentOperation::InlineImage { .. } => true,
+                _ => {
+                    state.apply(operation);
+                    false
+                }
+            };
+            // Images fill the unit square of user space
+            let inside = is_image
+                && [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+                    .into_iter()
+                    .all(|(x, y)| contains(&area, state.ctm.transform_point(Point::new(x, y))));
+            removed += inside as usize;
+            !inside
+        });
+        removed
+    }
+
+    /// Where images and form XObjects are drawn, in content order
+    pub(crate) fn placements(&self) -> Vec<Placement> {
+        let mut state = self.state();
+        let mut placements = Vec::new();
+        for (index, operation) in self.operations.iter().enumerate() {
+            let name = match operation {
+                ContentOperation::PaintXObject(name) => Some(name.clone()),
+                ContentOperation::InlineImage { .. } => None,
+                _ => {
+                    state.apply(operation);
+                    continue;
+                }
+            };
+            placements.push(Placement {
+                index,
+                form: name.as_ref().is_some_and(|name| self.forms.contains(name)),
+                name,
+                ctm: state.ctm,
+            });
+        }
+        placements
+    }
+
+    /// The state at the start of the content
+    fn state(&self) -> State {
+        State {
+            ctm: self.base,
+            ..State::default()
+        }
+    }
+}
+
+/// The text-showing part of an operation: operations it implies before
+/// showing, and the shown elements
+fn shown(operation: &ContentOperation) -> Option<(Vec<ContentOperation>, Vec<TextElement>)> {
+    Some(match operation {
+        ContentOperation::ShowText(bytes) => (vec![], vec![TextElement::Text(bytes.clone())]),
+        ContentOperation::ShowTextArray(elements) => (vec![], elements.clone()),
+        ContentOperation::NextLineShowText(bytes) => (
+            vec![ContentOperation::NextLine],
+            vec![TextElement::Text(bytes.clone())],
+        ),
+        ContentOperation::SetSpacingNextLineShowText(word, chars, bytes) => (
+            vec![
+                ContentOperation::SetWordSpacing(*word),
+                ContentOperation::SetCharSpacing(*chars),
+                ContentOperation::NextLine,
+            ],
+            vec![TextElement::Text(bytes.clone())],
+        ),
+        _ => return None,
+    })
+}
+
+/// Text state parameters, part of the graphics state
+#[derive(Debug, Clone)]
+struct TextState {
+    font: Option<String>,
+    size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scaling: f64,
+    leading: f64,
+    rise: f64,
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        Self {
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+/// Positions of content as operations are walked through
+#[derive(Debug, Clone)]
+struct State {
+    ctm: TransformMatrix,
+    text: TextState,
+    saved: Vec<(TransformMatrix, TextState)>,
+    text_matrix: TransformMatrix,
+    line_matrix: TransformMatrix,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            ctm: TransformMatrix::IDENTITY,
+            text: TextState::default(),
+            saved: Vec::new(),
+            text_matrix: TransformMatrix::IDENTITY,
+            line_matrix: TransformMatrix::IDENTITY,
+        }
+    }
+}
+
+impl State {
+    /// Track an operation other than text showing
+    fn apply(&mut self, operation: &ContentOperation) {
+        use ContentOperation as Op;
+
+        match operation {
+            Op::SaveGraphicsState => self.saved.push((self.ctm, self.text.clone())),
+            Op::RestoreGraphicsState => {
+                if let Some((ctm, text)) = self.saved.pop() {
+                    self.ctm = ctm;
+                    self.text = text;
+                }
+            }
+            Op::SetTransformMatrix(a, b, c, d, e, f) => {
+                self.ctm = self.ctm.multiply(&matrix([*a, *b, *c, *d, *e, *f]));
+            }
+            Op::BeginText => {
+                self.text_matrix = TransformMatrix::IDENTITY;
+                self.line_matrix = TransformMatrix::IDENTITY;
+            }
+            Op::SetFont(name, size) => {
+                self.text.font = Some(name.clone());
+                self.text.size = *size as f64;
+            }
+            Op::SetCharSpacing(v) => self.text.char_spacing = *v as f64,
+            Op::SetWordSpacing(v) => self.text.word_spacing = *v as f64,
+            Op::SetHorizontalScaling(v) => self.text.horizontal_scaling = *v as f64 / 100.0,
+            Op::SetLeading(v) => self.text.leading = *v as f64,
+            Op::SetTextRise(v) => self.text.rise = *v as f64,
+            Op::MoveText(tx, ty) => self.move_line(*tx as f64, *ty as f64),
+            Op::MoveTextSetLeading(tx, ty) => {
+                self.text.leading = -*ty as f64;
+                self.move_line(*tx as f64, *ty as f64);
+            }
+            Op::SetTextMatrix(a, b, c, d, e, f) => {
+                self.line_matrix = matrix([*a, *b, *c, *d, *e, *f]);
+                self.text_matrix = self.line_matrix;
+            }
+            Op::NextLine => self.move_line(0.0, -self.text.leading),
+            _ => {}
+        }
+    }
+
+    fn move_line(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = self
+            .line_matrix
+            .multiply(&TransformMatrix::translate(tx, ty));
+        self.text_matrix = self.line_matrix;
+    }
+
+    /// Show the elements of a `TJ` array, leaving out glyphs for which
+    /// `remove` returns true. Returns the remaining elements and the number
+    /// of glyphs left out.
+    fn show(
+        &mut self,
+        fonts: &HashMap<String, Font>,
+        elements: &[TextElement],
+        remove: &mut dyn FnMut(&Glyph) -> bool,
+    ) -> (Vec<TextElement>, usize) {
+        let text = self.text.clone();
+        let font = text.font.as_ref().and_then(|name| fonts.get(name));
+        let vertical = font.is_some_and(Font::is_vertical);
+        let params = TransformMatrix::new(
+            text.size * text.horizontal_scaling,
+            0.0,
+            0.0,
+            text.size,
+            0.0,
+            text.rise,
+        );
+        // Displacement in unscaled text space to TJ spacing
+        let to_spacing = |displacement: f64| {
+            if text.size == 0.0 {
+                0.0
+            } else {
+                (-displacement * 1000.0 / text.size) as f32
+            }
+        };
+
+        let mut kept: Vec<TextElement> = Vec::new();
+        let mut removed = 0;
+        for element in elements {
+            let bytes = match element {
+                TextElement::Spacing(n) => {
+                    let displacement = -*n as f64 / 1000.0 * text.size;
+                    self.advance(vertical, displacement);
+                    push_spacing(&mut kept, *n);
+                    continue;
+                }
+                TextElement::Text(bytes) => bytes,
+            };
+            let codes = match font {
+                Some(font) => font.codes(bytes),
+                None => bytes.iter().map(|&b| (b as u32, 1)).collect(),
+            };
+            let mut offset = 0;
+            for (code, length) in codes {
+                let glyph = &bytes[offset..(offset + length).min(bytes.len())];
+                offset += length;
+                let width = font.map_or(0.0, |f| f.width(code, length));
+                // Glyph origin relative to the text position
+                let (origin, advance) = match font {
+                    Some(font) if vertical => {
+                        let (w1, vx, vy) = font.vertical_metrics(code, length);
+                        ((-vx, -vy), w1)
+                    }
+                    _ => ((0.0, 0.0), width),
+                };
+                let to_user = self.ctm.multiply(&self.text_matrix).multiply(&params);
+                let corners: Vec<Point> = [
+                    (0.0, GLYPH_BOTTOM),
+                    (width, GLYPH_BOTTOM),
+                    (0.0, GLYPH_TOP),
+                    (width, GLYPH_TOP),
+                ]
+                .into_iter()
+                .map(|(x, y)| to_user.transform_point(Point::new(origin.0 + x, origin.1 + y)))
+                .collect();
+                let glyph_info = Glyph {
+                    code,
+                    length,
+                    center: to_user.transform_point(Point::new(
+                        origin.0 + width / 2.0,
+                        origin.1 + (GLYPH_BOTTOM + GLYPH_TOP) / 2.0,
+                    )),
+                    bounds: bounding_box(&corners),
+                };
+
+                let spacing = text.char_spacing
+                    + if length == 1 && code == 32 {
+                        text.word_spacing
+                    } else {
+                        0.0
+                    };
+                let displacement = advance * text.size + spacing;
+                self.advance(vertical, displacement);
+
+                if remove(&glyph_info) {
+                    removed += 1;
+                    push_spacing(&mut kept, to_spacing(displacement));
+                } else {
+                    match kept.last_mut() {
+                        Some(TextElement::Text(run)) => run.extend_from_slice(glyph),
+                        _ => kept.push(TextElement::Text(glyph.to_vec())),
+                    }
+                }
+            }
+        }
+        (kept, removed)
+    }
+
+    /// Move the text matrix by a displacement in unscaled text space
+    fn advance(&mut self, vertical: bool, displacement: f64) {
+        let translation = if vertical {
+            TransformMatrix::translate(0.0, displacement)
+        } else {
+            TransformMatrix::translate(displacement * self.text.horizontal_scaling, 0.0)
+        };
+        self.text_matrix = self.text_matrix.multiply(&translation);
+    }
+}
+
+fn push_spacing(elements: &mut Vec<TextElement>, spacing: f32) {
+    match elements.last_mut() {
+        Some(TextElement::Spacing(n)) => *n += spacing,
+        _ => elements.push(TextElement::Spacing(spacing)),
+    }
+}
+
+fn matrix([a, b, c, d, e, f]: [f32; 6]) -> TransformMatrix {
+    TransformMatrix::new(a as f64, b as f64, c as f64, d as f64, e as f64, f as f64)
+}
+
+/// The smallest rectangle containing the points
+pub(crate) fn bounding_box(points: &[Point]) -> Rectangle {
+    let (mut x0, mut y0, mut x1, mut y1) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for point in points {
+        x0 = x0.min(point.x);
+        y0 = y0.min(point.y);
+        x1 = x1.max(point.x);
+        y1 = y1.max(point.y);
+    }
+    Rectangle::new(Point::new(x0, y0), Point::new(x1, y1))
+}
+
+/// Whether two rectangles share more than an edge, allowing for rounding
+/// where adjacent glyph boxes meet
+pub(crate) fn overlaps(a: &Rectangle, b: &Rectangle) -> bool {
+    const TOLERANCE: f64 = 1e-3;
+    let a = bounding_box(&[a.lower_left, a.upper_right]);
+    let b = bounding_box(&[b.lower_left, b.upper_right]);
+    a.lower_left.x + TOLERANCE < b.upper_right.x
+        && b.lower_left.x + TOLERANCE < a.upper_right.x
+        && a.lower_left.y + TOLERANCE < b.upper_right.y
+        && b.lower_left.y + TOLERANCE < a.upper_right.y
+}
+
+fn contains(area: &Rectangle, point: Point) -> bool {
+    let (x0, x1) = (
+        area.lower_left.x.min(area.upper_right.x),
+        area.lower_left.x.max(area.upper_right.x),
+    );
+    let (y0, y1) = (
+        area.lower_left.y.min(area.upper_right.y),
+        area.lower_left.y.max(area.upper_right.y),
+    );
+    (x0..=x1).contains(&point.x) && (y0..=y1).contains(&point.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PdfReader;
+    use crate::text::Font as StandardFont;
+    use crate::{Document, Page};
+    use std::io::Cursor;
+
+    /// A page with "Hello World" at (100, 700) and "Footer" at (100, 100)
+    fn sample_document() -> PdfDocument<Cursor<Vec<u8>>> {
+        let mut page = Page::a4();
+        page.text()
+            .set_font(StandardFont::Helvetica, 12.0)
+            .at(100.0, 700.0)
+            .write("Hello World")
+            .unwrap()
+            .at(100.0, 100.0)
+            .write("Footer")
+            .unwrap();
+        let mut doc = Document::new();
+        doc.add_page(page);
+        let bytes = doc.to_bytes().unwrap();
+        PdfDocument::new(PdfReader::new(Cursor::new(bytes)).unwrap())
+    }
+
+    fn shown_strings(operations: &[ContentOperation]) -> Vec<Vec<TextElement>> {
+        operations
+            .iter()
+            .filter_map(|op| match op {
+                ContentOperation::ShowText(bytes) => Some(vec![TextElement::Text(bytes.clone())]),
+                ContentOperation::ShowTextArray(elements) => Some(elements.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_remove_text_keeps_layout() {
+        let document = sample_document();
+        let mut editor = ContentEditor::from_page(&document, 0).unwrap();
+        // "World" starts at 100 + width("Hello ") = 133.36
+        let area = Rectangle::new(Point::new(130.0, 690.0), Point::new(200.0, 720.0));
+        assert_eq!(editor.remove_text_in(area), 5);
+
+        let strings = shown_strings(editor.operations());
+        assert_eq!(strings.len(), 2);
+        match strings[0].as_slice() {
+            [TextElement::Text(kept), TextElement::Spacing(n)] => {
+                assert_eq!(kept, b"Hello ");
+                // W o r l d in Helvetica: 944 + 556 + 333 + 222 + 556
+                assert!((n + 2611.0).abs() < 0.5, "spacing {n}");
+            }
+            other => panic!("unexpected elements {other:?}"),
+        }
+        assert_eq!(strings[1], vec![TextElement::Text(b"Footer".to_vec())]);
+
+        // The output parses to the same operations
+        let reparsed = ContentParser::parse(&editor.to_bytes()).unwrap();
+        assert_eq!(reparsed, editor.operations());
+    }
+
+    #[test]
+    fn test_find_and_remove_touching_text() {
+        let document = sample_document();
+        let mut editor = ContentEditor::from_page(&document, 0).unwrap();
+        let found = editor.find_text("World");
+        assert_eq!(found.len(), 1);
+        // 12pt glyph boxes: baseline - 2.4 to baseline + 9.6
+        // "Hello " is 2556 units wide
+        assert!((found[0].lower_left.x - 130.672).abs() < 0.01);
+        assert!((found[0].lower_left.y - 697.6).abs() < 0.01);
+        assert!((found[0].upper_right.y - 709.6).abs() < 0.01);
+        assert!(editor.find_text("Moon").is_empty());
+
+        // The hit box removes exactly its glyphs, not their neighbours
+        assert_eq!(editor.remove_text_touching(found[0]), 5);
+        // A sliver over the left edge of "F" catches it too
+        let sliver = Rectangle::new(Point::new(99.0, 95.0), Point::new(100.5, 105.0));
+        assert_eq!(editor.remove_text_in(sliver), 0);
+        assert_eq!(editor.remove_text_touching(sliver), 1);
+        let strings = shown_strings(editor.operations());
+        assert_eq!(strings[0][0], TextElement::Text(b"Hello ".to_vec()));
+        assert_eq!(strings[1][1], TextElement::Text(b"ooter".to_vec()));
+    }
+
+    #[test]
+    fn test_remove_paths() {
+        let content = b"10 10 20 20 re 1 0 0 rg f\n\
+            0 0 m 300 300 l S\n\
+            q 2 0 0 2 0 0 cm 30 30 m 40 40 l 45 30 50 45 60 60 c h B Q\n\
+            20 20 50 50 re W n";
+        let mut editor = ContentEditor::parse(content).unwrap();
+        let area = Rectangle::new(Point::new(0.0, 0.0), Point::new(200.0, 200.0));
+        // The clipping path stays, the scaled curve ends at (120, 120)
+        assert_eq!(editor.remove_paths_in(area), 2);
+        assert_eq!(
+            String::from_utf8(editor.to_bytes()).unwrap(),
+            "1 0 0 rg\n0 0 m\n300 300 l\nS\nq\n2 0 0 2 0 0 cm\nQ\n20 20 50 50 re\nW\nn\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_text() {
+        let document = sample_document();
+        let mut editor = ContentEditor::from_page(&document, 0).unwrap();
+        let replaced = editor
+            .replace_text(|text| (text == "Footer").then(|| "Page 1".to_string()))
+            .unwrap();
+        assert_eq!(replaced, 1);
+        let strings = shown_strings(editor.operations());
+        assert_eq!(strings[1], vec![TextElement::Text(b"Page 1".to_vec())]);
+
+        // Characters the font can't show leave the content unchanged
+        let before = editor.operations().to_vec();
+        assert!(editor
+            .replace_text(|_| Some("\u{4E2D}".to_string()))
+            .is_err());
+        assert_eq!(editor.operations(), before);
+    }
+
+    #[test]
+    fn test_remove_images() {
+        let content = b"q 100 0 0 50 10 10 cm /Im1 Do Q\n\
+            q 500 0 0 500 0 0 cm /Im2 Do Q\n\
+            q 20 0 0 20 30 30 cm BI /W 1 /H 1 /CS /G /BPC 8 ID \x80\nEI Q";
+        let mut editor = ContentEditor::parse(content).unwrap();
+        let area = Rectangle::new(Point::new(0.0, 0.0), Point::new(200.0, 200.0));
+        assert_eq!(editor.remove_images_in(area), 2);
+        let remaining: Vec<_> = editor
+            .operations()
+            .iter()
+            .filter(|op| {
+                matches!(
+                    op,
+                    ContentOperation::PaintXObject(_) | ContentOperation::InlineImage { .. }
+                )
+            })
+            .collect();
+        assert_eq!(remaining, [&ContentOperation::PaintXObject("Im2".into())]);
+    }
+
+    #[test]
+    fn test_filter_and_insert() {
+        let mut editor = ContentEditor::parse(b"1 0 0 1 5 5 cm 1 0 0 rg 0 0 10 10 re f Q").unwrap();
+        editor
+            .retain(|op| !matches!(op, ContentOperation::SetNonStrokingRGB(..)))
+            .replace(|op| {
+                matches!(op, ContentOperation::Fill).then(|| vec![ContentOperation::FillStroke])
+            })
+            .insert(1, [ContentOperation::SetLineWidth(2.0)])
+            .prepend([ContentOperation::SetNonStrokingGray(0.5)])
+            .append([ContentOperation::PaintXObject("Logo".into())]);
+        assert_eq!(
+            String::from_utf8(editor.to_bytes()).unwrap(),
+            "q\nq\nq\n0.5 g\nQ\n1 0 0 1 5 5 cm\n2 w\n0 0 10 10 re\nB\nQ\nQ\n/Logo Do\n"
+        );
+    }
+}
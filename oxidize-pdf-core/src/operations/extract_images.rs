@@ -5,6 +5,7 @@
 
 use super::{OperationError, OperationResult};
 use crate::graphics::ImageFormat;
+use crate::parser::filters::{decode_dct_image, decode_jpx_image};
 use crate::parser::objects::{PdfName, PdfObject, PdfStream};
 use crate::parser::{PdfDocument, PdfReader};
 use std::collections::HashMap;
@@ -315,6 +316,12 @@ impl ImageExtractor {
             _ => 8, // Default to 8 bits per component
         };
 
+        // JPX images may carry their own soft mask (ISO 32000-1 Table 89)
+        let smask_in_data = matches!(
+            stream.dict.0.get(&PdfName("SMaskInData".to_string())),
+            Some(PdfObject::Integer(n)) if *n != 0
+        );
+
         // Get the decoded image data
         let parse_options = self.document.options();
         let mut data = stream.decode(&parse_options).map_err(|e| {
@@ -324,6 +331,11 @@ impl ImageExtractor {
         // Determine format from filter and process data accordingly
         let format = match stream.dict.0.get(&PdfName("Filter".to_string())) {
             Some(PdfObject::Name(filter)) => match filter.0.as_str() {
+                "DCTDecode" if parse_options.decode_dct_images => {
+                    // Decoding was requested - write the samples as PNG
+                    data = self.convert_dct_to_png(stream)?;
+                    ImageFormat::Png
+                }
                 "DCTDecode" => {
                     // JPEG data is already in correct format - use raw stream data
                     // DCTDecode streams contain complete JPEG data, don't decode
@@ -346,6 +358,16 @@ impl ImageExtractor {
                     data = self.convert_ccitt_to_png(&data, width, height)?;
                     ImageFormat::Png
                 }
+                "JBIG2Decode" => {
+                    // JBIG2 decodes to 1-bit rows with 0 = black, like CCITT
+                    data = self.convert_ccitt_to_png(&data, width, height)?;
+                    ImageFormat::Png
+                }
+                "JPXDecode" => {
+                    // JPEG 2000 - decode the raw stream to keep any alpha channel
+                    data = self.convert_jpx_to_png(&stream.data, smask_in_data)?;
+                    ImageFormat::Png
+                }
                 "LZWDecode" => {
                     // LZW compressed raw data - convert to PNG
                     data = self.convert_raw_image_data_to_png(
@@ -366,6 +388,10 @@ impl ImageExtractor {
                 // Handle filter arrays - use the first filter
                 if let Some(PdfObject::Name(filter)) = filters.0.first() {
                     match filter.0.as_str() {
+                        "DCTDecode" if parse_options.decode_dct_images => {
+                            data = self.convert_dct_to_png(stream)?;
+                            ImageFormat::Png
+                        }
                         "DCTDecode" => {
                             // JPEG data is already in correct format - use raw stream data
                             data = stream.data.clone();
@@ -385,6 +411,14 @@ impl ImageExtractor {
                             data = self.convert_ccitt_to_png(&data, width, height)?;
                             ImageFormat::Png
                         }
+                        "JBIG2Decode" => {
+                            data = self.convert_ccitt_to_png(&data, width, height)?;
+                            ImageFormat::Png
+                        }
+                        "JPXDecode" => {
+                            data = self.convert_jpx_to_png(&stream.data, smask_in_data)?;
+                            ImageFormat::Png
+                        }
                         "LZWDecode" => {
                             data = self.convert_raw_image_data_to_png(
                                 &data,
@@ -1086,6 +1120,7 @@ impl ImageExtractor {
         // Color type: 0 = grayscale, 2 = RGB, 6 = RGBA
         let color_type = match components {
             1 => 0, // Grayscale
+            2 => 4, // Grayscale with alpha
             3 => 2, // RGB
             4 => 6, // RGBA
             _ => 2, // Default to RGB
@@ -1240,6 +1275,39 @@ impl ImageExtractor {
         self.create_png_from_raw_data(&rgb_data, width, height, 1, 8)
     }
 
+    /// Convert a JPEG 2000 stream to PNG, adding its opacity channel as
+    /// PNG alpha when the image dictionary sets SMaskInData
+    fn convert_jpx_to_png(&self, data: &[u8], smask_in_data: bool) -> OperationResult<Vec<u8>> {
+        let image = decode_jpx_image(data).map_err(|e| {
+            OperationError::ParseError(format!("Failed to decode JPEG 2000 image: {e}"))
+        })?;
+        let (components, color) = image.to_gray_or_rgb();
+
+        match image.alpha.as_ref().filter(|_| smask_in_data) {
+            Some(alpha) => {
+                let pixels: Vec<u8> = color
+                    .chunks_exact(components as usize)
+                    .zip(alpha)
+                    .flat_map(|(pixel, &a)| pixel.iter().copied().chain(std::iter::once(a)))
+                    .collect();
+                self.create_png_from_raw_data(&pixels, image.width, image.height, components + 1, 8)
+            }
+            None => self.create_png_from_raw_data(&color, image.width, image.height, components, 8),
+        }
+    }
+
+    /// Decode a JPEG stream and re-encode it as PNG
+    fn convert_dct_to_png(&self, stream: &PdfStream) -> OperationResult<Vec<u8>> {
+        let params = match stream.dict.get("DecodeParms") {
+            Some(PdfObject::Dictionary(params)) => Some(params),
+            _ => None,
+        };
+        let image = decode_dct_image(&stream.data, params)
+            .map_err(|e| OperationError::ParseError(format!("Failed to decode JPEG image: {e}")))?;
+        let (components, pixels) = image.to_gray_or_rgb();
+        self.create_png_from_raw_data(&pixels, image.width, image.height, components, 8)
+    }
+
     /// Detect the correct row stride by analyzing data patterns
     fn detect_correct_row_stride(
         &self,
@@ -5,6 +5,7 @@
 use super::{OperationError, OperationResult, PageRange};
 use crate::parser::page_tree::ParsedPage;
 use crate::parser::{ContentOperation, ContentParser, PdfDocument, PdfReader};
+use crate::structure::OutlineTree;
 use crate::{Document, Page};
 use std::collections::HashMap;
 use std::fs::File;
@@ -133,6 +134,7 @@ impl PdfMerger {
         }
 
         let mut output_doc = Document::new();
+        let mut bookmarks = OutlineTree::new();
 
         // Initialize font and XObject mappings for each input
         self.font_mappings.clear();
@@ -168,6 +170,18 @@ impl PdfMerger {
 
             let page_indices = page_range.get_indices(total_pages)?;
 
+            // Move the bookmarks to the pages' positions in the output
+            if self.options.preserve_bookmarks {
+                if let Ok(Some(outline)) = document.outline() {
+                    let first_page = output_doc.page_count();
+                    let outline = outline.remap_pages(&|page| {
+                        let position = page_indices.iter().position(|&i| i == page as usize)?;
+                        Some((first_page + position) as u32)
+                    });
+                    bookmarks.items.extend(outline.items);
+                }
+            }
+
             // Extract and add pages
             for page_idx in page_indices {
                 let parsed_page = document
@@ -190,6 +204,10 @@ impl PdfMerger {
             }
         }
 
+        if !bookmarks.items.is_empty() {
+            output_doc.set_outline(bookmarks);
+        }
+
         // Apply custom metadata if specified
         if let MetadataMode::Custom {
             title,
@@ -588,4 +588,68 @@ mod tests {
         let cloned_mode = metadata_mode;
         assert!(matches!(cloned_mode, MetadataMode::FromFirst));
     }
+
+    /// Bookmark titles with the page each one points to, depth first
+    fn bookmarks(items: &[crate::structure::OutlineItem]) -> Vec<(String, Option<u32>)> {
+        items
+            .iter()
+            .flat_map(|item| {
+                let page = match item.destination.as_ref().map(|dest| &dest.page) {
+                    Some(crate::structure::PageDestination::PageNumber(page)) => Some(*page),
+                    _ => None,
+                };
+                std::iter::once((item.title.clone(), page)).chain(bookmarks(&item.children))
+            })
+            .collect()
+    }
+
+    /// Adds a bookmark to each page of the document
+    fn add_page_bookmarks(doc: &mut Document, name: &str) {
+        use crate::structure::{Destination, OutlineItem, OutlineTree, PageDestination};
+
+        let mut outline = OutlineTree::new();
+        for page in 0..doc.page_count() {
+            outline.add_item(
+                OutlineItem::new(format!("{name} {}", page + 1))
+                    .with_destination(Destination::fit(PageDestination::PageNumber(page as u32))),
+            );
+        }
+        doc.set_outline(outline);
+    }
+
+    #[test]
+    fn test_merge_preserves_bookmarks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut doc1 = create_test_pdf(2, "Document 1");
+        add_page_bookmarks(&mut doc1, "First");
+        let mut doc2 = create_test_pdf(3, "Document 2");
+        add_page_bookmarks(&mut doc2, "Second");
+        let path1 = save_test_pdf(&mut doc1, &temp_dir, "doc1.pdf");
+        let path2 = save_test_pdf(&mut doc2, &temp_dir, "doc2.pdf");
+
+        let mut merger = PdfMerger::new(MergeOptions::default());
+        merger.add_input(MergeInput::new(&path1));
+        merger.add_input(MergeInput::with_pages(&path2, PageRange::List(vec![2, 1])));
+        let merged = merger.merge().unwrap();
+
+        // Bookmarks follow their pages; the one to the dropped page is gone
+        let outline = merged.outline().unwrap();
+        assert_eq!(
+            bookmarks(&outline.items),
+            [
+                ("First 1".to_string(), Some(0)),
+                ("First 2".to_string(), Some(1)),
+                ("Second 2".to_string(), Some(3)),
+                ("Second 3".to_string(), Some(2)),
+            ]
+        );
+
+        let options = MergeOptions {
+            preserve_bookmarks: false,
+            ..Default::default()
+        };
+        let mut merger = PdfMerger::new(options);
+        merger.add_input(MergeInput::new(&path1));
+        assert!(merger.merge().unwrap().outline().is_none());
+    }
 }
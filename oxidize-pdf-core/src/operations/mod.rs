@@ -3,26 +3,32 @@
 //! This module provides high-level operations for manipulating PDF documents
 //! such as splitting, merging, rotating pages, and reordering.
 
+pub mod content_editor;
 pub mod extract_images;
 pub mod merge;
+pub mod optimize;
 pub mod page_analysis;
 pub mod page_extraction;
 pub mod pdf_ocr_converter;
+pub mod redact;
 pub mod reorder;
 pub mod rotate;
 pub mod split;
 
+pub use content_editor::ContentEditor;
 pub use extract_images::{
     extract_images_from_pages, extract_images_from_pdf, ExtractImagesOptions, ExtractedImage,
     ImageExtractor,
 };
 pub use merge::{merge_pdf_files, merge_pdfs, MergeInput, MergeOptions, PdfMerger};
+pub use optimize::{optimize_pdf, OptimizationReport, OptimizeOptions, PdfOptimizer};
 pub use page_analysis::{AnalysisOptions, ContentAnalysis, PageContentAnalyzer, PageType};
 pub use page_extraction::{
     extract_page, extract_page_range, extract_page_range_to_file, extract_page_to_file,
     extract_pages, extract_pages_to_file, PageExtractionOptions, PageExtractor,
 };
 pub use pdf_ocr_converter::{ConversionOptions, ConversionResult, PdfOcrConverter};
+pub use redact::{RedactOptions, Redaction, RedactionReport, Redactor};
 pub use reorder::{
     move_pdf_page, reorder_pdf_pages, reverse_pdf_pages, swap_pdf_pages, PageReorderer,
     ReorderOptions,
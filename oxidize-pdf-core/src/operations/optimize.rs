@@ -0,0 +1,826 @@
+//! PDF size optimization
+//!
+//! This module rewrites an existing document to make it smaller: only the
+//! objects reachable from the catalog are kept, duplicate streams and fonts
+//! are merged, streams are recompressed, images are downsampled and
+//! re-encoded, and the result is packed into object streams with a
+//! cross-reference stream.
+
+use super::{OperationError, OperationResult};
+use crate::objects::ObjectId;
+use crate::parser::objects::{PdfDictionary, PdfName, PdfObject, PdfStream};
+use crate::parser::{ParseOptions, PdfReader};
+use crate::writer::{PdfWriter, WriterConfig};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Placeholder number for references to objects that don't exist
+const DANGLING: (u32, u16) = (u32::MAX, 0);
+
+/// Filters whose output is an image codec that recompression must not touch
+const IMAGE_FILTERS: [&str; 5] = [
+    "DCTDecode",
+    "JPXDecode",
+    "JBIG2Decode",
+    "CCITTFaxDecode",
+    "Crypt",
+];
+
+/// Options for document optimization
+#[derive(Debug, Clone)]
+pub struct OptimizeOptions {
+    /// JPEG quality (1-100) for re-encoded images
+    pub image_quality: u8,
+    /// Largest width or height of an image; larger images are downsampled
+    pub max_image_dimension: Option<u32>,
+    /// Re-encode 8-bit gray and RGB images as JPEG when that makes them smaller
+    ///
+    /// Requires the `external-images` feature; without it images are only
+    /// recompressed losslessly.
+    pub recompress_images: bool,
+    /// Recompress other streams with Flate when that makes them smaller
+    pub recompress_streams: bool,
+    /// Merge identical streams and font dictionaries
+    pub remove_duplicates: bool,
+    /// Pack objects into object streams (PDF 1.5)
+    pub use_object_streams: bool,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self::from_quality(75)
+    }
+}
+
+impl OptimizeOptions {
+    /// Options for a quality level from 0 (smallest) to 100 (best)
+    ///
+    /// The quality is used as JPEG quality for images. Below 90, images are
+    /// also limited to `600 + 30 * quality` pixels in each direction, which
+    /// keeps letter-size scans at about 150 dpi for a quality of 50.
+    pub fn from_quality(quality: u8) -> Self {
+        let quality = quality.min(100);
+        Self {
+            image_quality: quality.max(1),
+            max_image_dimension: (quality < 90).then(|| 600 + 30 * quality as u32),
+            recompress_images: quality < 100,
+            recompress_streams: true,
+            remove_duplicates: true,
+            use_object_streams: true,
+        }
+    }
+}
+
+/// Result of optimizing a document
+#[derive(Debug, Clone, Default)]
+pub struct OptimizationReport {
+    /// Size of the input in bytes
+    pub original_size: u64,
+    /// Size of the output in bytes
+    pub optimized_size: u64,
+    /// Objects in the input's cross-reference table
+    pub objects_before: usize,
+    /// Objects written to the output, without object and xref streams
+    pub objects_after: usize,
+    /// Streams and fonts merged into an identical object
+    pub duplicates_removed: usize,
+    /// Streams whose data was recompressed
+    pub streams_recompressed: usize,
+    /// Images that were downsampled or re-encoded
+    pub images_resampled: usize,
+}
+
+impl OptimizationReport {
+    /// Bytes saved; negative if the output is larger
+    pub fn bytes_saved(&self) -> i64 {
+        self.original_size as i64 - self.optimized_size as i64
+    }
+
+    /// Output size as a fraction of the input size
+    pub fn compression_ratio(&self) -> f64 {
+        if self.original_size == 0 {
+            return 1.0;
+        }
+        self.optimized_size as f64 / self.original_size as f64
+    }
+}
+
+/// Rewrites documents to reduce their size
+pub struct PdfOptimizer {
+    options: OptimizeOptions,
+}
+
+impl PdfOptimizer {
+    /// Create an optimizer with the given options
+    pub fn new(options: OptimizeOptions) -> Self {
+        Self { options }
+    }
+
+    /// Optimize a document held in memory
+    ///
+    /// # Errors
+    /// Returns an error if the document cannot be parsed or is encrypted.
+    pub fn optimize(&self, input: &[u8]) -> OperationResult<(Vec<u8>, OptimizationReport)> {
+        let mut reader = PdfReader::new(Cursor::new(input))
+            .map_err(|e| OperationError::ParseError(e.to_string()))?;
+        if reader.is_encrypted() {
+            return Err(OperationError::ProcessingError(
+                "Encrypted documents cannot be optimized".to_string(),
+            ));
+        }
+
+        let mut report = OptimizationReport {
+            original_size: input.len() as u64,
+            objects_before: reader
+                .xref()
+                .iter()
+                .filter(|(&num, entry)| num != 0 && entry.in_use)
+                .count(),
+            ..Default::default()
+        };
+
+        let trailer = reader.trailer().clone();
+        let root = trailer
+            .root()
+            .map_err(|e| OperationError::ParseError(e.to_string()))?;
+        let info = trailer.info();
+        let version = reader.version().clone();
+
+        let mut objects = load_reachable(&mut reader, root, info);
+
+        if self.options.remove_duplicates {
+            let duplicates = find_duplicates(&objects);
+            report.duplicates_removed = duplicates.len();
+            for id in duplicates.keys() {
+                objects.remove(id);
+            }
+            for object in objects.values_mut() {
+                remap_references(object, &|id| resolve(&duplicates, id));
+            }
+        }
+
+        let mut deflater = Deflater::new();
+        for object in objects.values_mut() {
+            if let PdfObject::Stream(stream) = object {
+                if let Some(optimized) = self.optimize_stream(stream, &mut deflater, &mut report) {
+                    *stream = optimized;
+                }
+            }
+        }
+
+        let (output, objects_after) = write_reachable(
+            objects,
+            root,
+            info,
+            &version,
+            self.options.use_object_streams,
+        )?;
+        report.objects_after = objects_after;
+        report.optimized_size = output.len() as u64;
+        Ok((output, report))
+    }
+
+    /// Recompress a stream, returning `None` to keep it unchanged
+    fn optimize_stream(
+        &self,
+        stream: &PdfStream,
+        deflater: &mut Deflater,
+        report: &mut OptimizationReport,
+    ) -> Option<PdfStream> {
+        let is_image = stream.dict.get("Subtype").and_then(|s| s.as_name())
+            == Some(&PdfName::new("Image".to_string()));
+
+        #[cfg(feature = "external-images")]
+        if is_image && self.options.recompress_images {
+            if let Some(resampled) = self.resample_image(stream) {
+                report.images_resampled += 1;
+                return Some(resampled);
+            }
+        }
+
+        let filters = stream_filters(&stream.dict);
+        if !self.options.recompress_streams
+            || filters.iter().any(|f| IMAGE_FILTERS.contains(&f.as_str()))
+            || stream.dict.get_type() == Some("Metadata")
+        {
+            return None;
+        }
+
+        let decoded = stream.decode(&ParseOptions::default()).ok()?;
+        let hint = if is_image {
+            ContentHint::Image
+        } else if ["Length1", "Length2", "Length3"]
+            .iter()
+            .any(|key| stream.dict.contains_key(key))
+        {
+            ContentHint::Font
+        } else {
+            ContentHint::Other
+        };
+        let compressed = deflater.deflate(decoded, hint)?;
+        if compressed.len() >= stream.data.len() {
+            return None;
+        }
+
+        let mut dict = stream.dict.clone();
+        dict.0.remove(&PdfName::new("DecodeParms".to_string()));
+        dict.insert(
+            "Filter".to_string(),
+            PdfObject::Name(PdfName::new("FlateDecode".to_string())),
+        );
+        report.streams_recompressed += 1;
+        Some(PdfStream {
+            dict,
+            data: compressed,
+        })
+    }
+
+    /// Downsample and JPEG-encode an 8-bit gray or RGB image, returning
+    /// `None` when the image is unsuitable or would not get smaller
+    #[cfg(feature = "external-images")]
+    fn resample_image(&self, stream: &PdfStream) -> Option<PdfStream> {
+        use crate::parser::filters::decode_dct_image;
+        use image::codecs::jpeg::JpegEncoder;
+        use image::imageops::FilterType;
+        use image::{DynamicImage, GrayImage, RgbImage};
+
+        let dict = &stream.dict;
+        let integer = |key: &str| dict.get(key).and_then(|v| v.as_integer());
+        // Masks, decode arrays and color-key masking don't survive lossy coding
+        if dict.get("ImageMask").and_then(|v| v.as_bool()) == Some(true)
+            || integer("BitsPerComponent") != Some(8)
+            || dict.contains_key("Decode")
+            || matches!(dict.get("Mask"), Some(PdfObject::Array(_)))
+            || integer("SMaskInData").unwrap_or(0) != 0
+        {
+            return None;
+        }
+        let components = match dict.get("ColorSpace").and_then(|v| v.as_name()) {
+            Some(name) if name.as_str() == "DeviceGray" => 1,
+            Some(name) if name.as_str() == "DeviceRGB" => 3,
+            _ => return None,
+        };
+        let width = u32::try_from(integer("Width")?).ok()?;
+        let height = u32::try_from(integer("Height")?).ok()?;
+
+        let filters = stream_filters(dict);
+        let is_jpeg = filters.len() == 1 && filters[0] == "DCTDecode";
+        let samples = if is_jpeg {
+            let params = dict.get("DecodeParms").and_then(|p| p.as_dict());
+            let image = decode_dct_image(&stream.data, params).ok()?;
+            if image.components != components || (image.width, image.height) != (width, height) {
+                return None;
+            }
+            image.data
+        } else if filters.iter().any(|f| IMAGE_FILTERS.contains(&f.as_str())) {
+            return None;
+        } else {
+            stream.decode(&ParseOptions::default()).ok()?
+        };
+
+        let size = width as usize * height as usize * components as usize;
+        let samples = samples.get(..size)?.to_vec();
+        let mut image = if components == 1 {
+            DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, samples)?)
+        } else {
+            DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, samples)?)
+        };
+
+        let (mut new_width, mut new_height) = (width, height);
+        if let Some(max) = self.options.max_image_dimension.filter(|&m| m > 0) {
+            let largest = width.max(height);
+            if largest > max {
+                let scale = max as f64 / largest as f64;
+                new_width = ((width as f64 * scale).round() as u32).max(1);
+                new_height = ((height as f64 * scale).round() as u32).max(1);
+                image = image.resize_exact(new_width, new_height, FilterType::Triangle);
+            }
+        }
+        let resized = (new_width, new_height) != (width, height);
+        if is_jpeg && !resized && self.options.image_quality >= 95 {
+            return None;
+        }
+
+        let mut encoded = Vec::new();
+        JpegEncoder::new_with_quality(&mut encoded, self.options.image_quality)
+            .encode_image(&image)
+            .ok()?;
+        if encoded.len() >= stream.data.len() {
+            return None;
+        }
+
+        let mut dict = dict.clone();
+        dict.0.remove(&PdfName::new("DecodeParms".to_string()));
+        dict.insert(
+            "Filter".to_string(),
+            PdfObject::Name(PdfName::new("DCTDecode".to_string())),
+        );
+        dict.insert("Width".to_string(), PdfObject::Integer(new_width as i64));
+        dict.insert("Height".to_string(), PdfObject::Integer(new_height as i64));
+        Some(PdfStream {
+            dict,
+            data: encoded,
+        })
+    }
+}
+
+/// Optimize a PDF file and write the result to `output`
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use oxidize_pdf::operations::{optimize_pdf, OptimizeOptions};
+///
+/// let report = optimize_pdf("scan.pdf", "scan-small.pdf", OptimizeOptions::from_quality(60))?;
+/// println!("{} -> {} bytes", report.original_size, report.optimized_size);
+/// # Ok::<(), oxidize_pdf::operations::OperationError>(())
+/// ```
+pub fn optimize_pdf<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    options: OptimizeOptions,
+) -> OperationResult<OptimizationReport> {
+    let data = std::fs::read(input)?;
+    let (optimized, report) = PdfOptimizer::new(options).optimize(&data)?;
+    std::fs::write(output, optimized)?;
+    Ok(report)
+}
+
+/// What a stream holds, for choosing the compression level
+enum ContentHint {
+    Image,
+    Font,
+    Other,
+}
+
+/// Flate compression through the content-aware compressor when the
+/// `performance` feature is enabled
+struct Deflater {
+    #[cfg(feature = "performance")]
+    compressor: crate::performance::IntelligentCompressor,
+}
+
+impl Deflater {
+    fn new() -> Self {
+        Self {
+            #[cfg(feature = "performance")]
+            compressor: crate::performance::IntelligentCompressor::new(),
+        }
+    }
+
+    #[cfg(feature = "performance")]
+    fn deflate(&mut self, data: Vec<u8>, hint: ContentHint) -> Option<Vec<u8>> {
+        use crate::performance::compression::CompressionAlgorithm;
+        use crate::performance::ContentType;
+
+        let content_type = match hint {
+            ContentHint::Image => ContentType::ImageUncompressed,
+            ContentHint::Font => ContentType::FontData,
+            ContentHint::Other => match ContentType::analyze(&data) {
+                ContentType::Unknown => ContentType::ContentStream,
+                content_type => content_type,
+            },
+        };
+        let compressed = self.compressor.compress(data, content_type).ok()?;
+        // Only Flate output can be described with a PDF filter
+        (compressed.algorithm == CompressionAlgorithm::Flate).then_some(compressed.data)
+    }
+
+    #[cfg(not(feature = "performance"))]
+    fn deflate(&mut self, data: Vec<u8>, _hint: ContentHint) -> Option<Vec<u8>> {
+        crate::compression::compress(&data).ok()
+    }
+}
+
+/// Names of the filters applied to a stream
+fn stream_filters(dict: &PdfDictionary) -> Vec<String> {
+    match dict.get("Filter") {
+        Some(PdfObject::Name(name)) => vec![name.as_str().to_string()],
+        Some(PdfObject::Array(array)) => array
+            .0
+            .iter()
+            .filter_map(|f| f.as_name().map(|n| n.as_str().to_string()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Write the objects reachable from the catalog and the info dictionary as
+/// a new file, numbered in the order they are reached. Returns the file and
+/// the number of objects written.
+pub(super) fn write_reachable(
+    mut objects: HashMap<(u32, u16), PdfObject>,
+    root: (u32, u16),
+    info: Option<(u32, u16)>,
+    version: &crate::parser::header::PdfVersion,
+    use_object_streams: bool,
+) -> OperationResult<(Vec<u8>, usize)> {
+    // The info dictionary is numbered last
+    let order = reachable_order(&objects, root, info);
+    let numbers: HashMap<(u32, u16), (u32, u16)> = order
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| (id, (i as u32 + 1, 0)))
+        .collect();
+    // Dangling references become null (ISO 32000-1 §7.3.10)
+    let renumber = |id: (u32, u16)| numbers.get(&id).copied().unwrap_or(DANGLING);
+    let info_object = match info.and_then(|id| objects.get(&id)) {
+        Some(dict @ PdfObject::Dictionary(_)) => dict.clone(),
+        _ => PdfObject::Dictionary(PdfDictionary::new()),
+    };
+    let info_id = ObjectId::new(order.len() as u32 + 1, 0);
+
+    let mut output_objects = Vec::with_capacity(order.len() + 1);
+    let objects_in_order = order.iter().filter_map(|id| objects.remove(id));
+    for (number, mut object) in (1..).zip(objects_in_order.chain([info_object])) {
+        remap_references(&mut object, &renumber);
+        replace_dangling(&mut object);
+        output_objects.push((ObjectId::new(number, 0), object));
+    }
+    let count = output_objects.len();
+
+    let mut config = WriterConfig::modern();
+    config.use_object_streams = use_object_streams;
+    if (version.major, version.minor) > (1, 5) {
+        config.pdf_version = version.to_string();
+    }
+
+    let root_id = ObjectId::new(numbers[&root].0, 0);
+    let mut output = Vec::new();
+    PdfWriter::with_config(&mut output, config).write_parsed_objects(
+        root_id,
+        info_id,
+        output_objects,
+    )?;
+    Ok((output, count))
+}
+
+/// Load every object reachable from the catalog and the info dictionary
+///
+/// Objects that fail to load are kept as null so references to them stay
+/// valid.
+pub(crate) fn load_reachable<R: std::io::Read + std::io::Seek>(
+    reader: &mut PdfReader<R>,
+    root: (u32, u16),
+    info: Option<(u32, u16)>,
+) -> HashMap<(u32, u16), PdfObject> {
+    let mut objects = HashMap::new();
+    let mut pending: Vec<(u32, u16)> = std::iter::once(root).chain(info).collect();
+    while let Some(id) = pending.pop() {
+        if objects.contains_key(&id) {
+            continue;
+        }
+        let object = reader
+            .get_object(id.0, id.1)
+            .cloned()
+            .unwrap_or(PdfObject::Null);
+        collect_references(&object, &mut pending);
+        objects.insert(id, object);
+    }
+    objects
+}
+
+/// Object ids in breadth-first order from the catalog, then the info dictionary
+fn reachable_order(
+    objects: &HashMap<(u32, u16), PdfObject>,
+    root: (u32, u16),
+    info: Option<(u32, u16)>,
+) -> Vec<(u32, u16)> {
+    let mut order = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([root]);
+    while let Some(id) = queue.pop_front() {
+        if !seen.insert(id) || Some(id) == info {
+            continue;
+        }
+        let Some(object) = objects.get(&id) else {
+            continue;
+        };
+        order.push(id);
+        let mut references = Vec::new();
+        collect_references(object, &mut references);
+        queue.extend(references);
+    }
+    // Objects only reachable from the info dictionary
+    if let Some(PdfObject::Dictionary(dict)) = info.and_then(|id| objects.get(&id)) {
+        let mut references = Vec::new();
+        collect_references(&PdfObject::Dictionary(dict.clone()), &mut references);
+        for id in references {
+            if Some(id) != info && objects.contains_key(&id) && seen.insert(id) {
+                order.push(id);
+            }
+        }
+    }
+    order
+}
+
+fn collect_references(object: &PdfObject, references: &mut Vec<(u32, u16)>) {
+    match object {
+        PdfObject::Reference(num, gen) => references.push((*num, *gen)),
+        PdfObject::Array(array) => {
+            for item in &array.0 {
+                collect_references(item, references);
+            }
+        }
+        PdfObject::Dictionary(dict) => {
+            for value in dict.0.values() {
+                collect_references(value, references);
+            }
+        }
+        PdfObject::Stream(stream) => {
+            for value in stream.dict.0.values() {
+                collect_references(value, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn remap_references(object: &mut PdfObject, map: &dyn Fn((u32, u16)) -> (u32, u16)) {
+    match object {
+        PdfObject::Reference(num, gen) => (*num, *gen) = map((*num, *gen)),
+        PdfObject::Array(array) => {
+            for item in &mut array.0 {
+                remap_references(item, map);
+            }
+        }
+        PdfObject::Dictionary(dict) => {
+            for value in dict.0.values_mut() {
+                remap_references(value, map);
+            }
+        }
+        PdfObject::Stream(stream) => {
+            for value in stream.dict.0.values_mut() {
+                remap_references(value, map);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace references renumbered to `DANGLING` with null
+fn replace_dangling(object: &mut PdfObject) {
+    match object {
+        PdfObject::Reference(num, gen) if (*num, *gen) == DANGLING => *object = PdfObject::Null,
+        PdfObject::Array(array) => array.0.iter_mut().for_each(replace_dangling),
+        PdfObject::Dictionary(dict) => dict.0.values_mut().for_each(replace_dangling),
+        PdfObject::Stream(stream) => stream.dict.0.values_mut().for_each(replace_dangling),
+        _ => {}
+    }
+}
+
+/// Streams and font dictionaries can be shared; pages and other structural
+/// objects must stay distinct even when identical
+fn is_shareable(object: &PdfObject) -> bool {
+    match object {
+        PdfObject::Stream(_) => true,
+        PdfObject::Dictionary(dict) => {
+            matches!(dict.get_type(), Some("Font") | Some("FontDescriptor"))
+        }
+        _ => false,
+    }
+}
+
+/// Map each duplicate object to the first identical one
+///
+/// Merging repeats until nothing changes, so fonts become identical once
+/// their embedded font files have been merged.
+fn find_duplicates(objects: &HashMap<(u32, u16), PdfObject>) -> HashMap<(u32, u16), (u32, u16)> {
+    let mut ids: Vec<_> = objects
+        .iter()
+        .filter(|(_, object)| is_shareable(object))
+        .map(|(&id, _)| id)
+        .collect();
+    ids.sort_unstable();
+
+    let mut duplicates = HashMap::new();
+    loop {
+        let mut first_of: HashMap<Vec<u8>, (u32, u16)> = HashMap::new();
+        let mut merged = false;
+        for &id in &ids {
+            if duplicates.contains_key(&id) {
+                continue;
+            }
+            let mut object = objects[&id].clone();
+            remap_references(&mut object, &|id| resolve(&duplicates, id));
+            let mut key = Vec::new();
+            crate::writer::write_parsed_value(&object, &mut key);
+            match first_of.get(&key) {
+                Some(&first) => {
+                    duplicates.insert(id, first);
+                    merged = true;
+                }
+                None => {
+                    first_of.insert(key, id);
+                }
+            }
+        }
+        if !merged {
+            return duplicates;
+        }
+    }
+}
+
+fn resolve(duplicates: &HashMap<(u32, u16), (u32, u16)>, mut id: (u32, u16)) -> (u32, u16) {
+    while let Some(&target) = duplicates.get(&id) {
+        id = target;
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PdfDocument;
+    use crate::text::Font;
+    use crate::{Document, Page};
+
+    fn sample_pdf(pages: usize) -> Vec<u8> {
+        let mut doc = Document::new();
+        doc.set_title("Optimizer test");
+        for i in 0..pages {
+            let mut page = Page::a4();
+            page.text()
+                .set_font(Font::Helvetica, 12.0)
+                .at(72.0, 720.0)
+                .write(&format!("Page {}", i + 1))
+                .unwrap();
+            doc.add_page(page);
+        }
+        doc.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_optimized_document_is_readable() {
+        let input = sample_pdf(3);
+        let (output, report) = PdfOptimizer::new(OptimizeOptions::default())
+            .optimize(&input)
+            .unwrap();
+
+        assert_eq!(report.original_size, input.len() as u64);
+        assert_eq!(report.optimized_size, output.len() as u64);
+        // The input's 1.7 header is kept; objects are packed in object streams
+        assert!(output.starts_with(b"%PDF-1.7"));
+        let contains = |needle: &[u8]| output.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"/ObjStm") && contains(b"/XRef"));
+
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(output)).unwrap());
+        assert_eq!(document.page_count().unwrap(), 3);
+        assert_eq!(
+            document.metadata().unwrap().title.as_deref(),
+            Some("Optimizer test")
+        );
+        let text = document.extract_text_from_page(1).unwrap().text;
+        assert!(text.contains("Page 2"));
+    }
+
+    #[test]
+    fn test_duplicate_streams_are_merged() {
+        let stream = |data: &[u8]| {
+            PdfObject::Stream(PdfStream {
+                dict: PdfDictionary::new(),
+                data: data.to_vec(),
+            })
+        };
+        let mut font = PdfDictionary::new();
+        font.insert(
+            "Type".to_string(),
+            PdfObject::Name(PdfName::new("Font".to_string())),
+        );
+        let mut font_a = font.clone();
+        font_a.insert("FontFile".to_string(), PdfObject::Reference(1, 0));
+        let mut font_b = font;
+        font_b.insert("FontFile".to_string(), PdfObject::Reference(2, 0));
+
+        let objects = HashMap::from([
+            ((1, 0), stream(b"same")),
+            ((2, 0), stream(b"same")),
+            ((3, 0), stream(b"other")),
+            ((4, 0), PdfObject::Dictionary(font_a)),
+            ((5, 0), PdfObject::Dictionary(font_b)),
+        ]);
+        let duplicates = find_duplicates(&objects);
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(resolve(&duplicates, (2, 0)), (1, 0));
+        assert_eq!(resolve(&duplicates, (5, 0)), (4, 0));
+    }
+
+    #[test]
+    fn test_unused_objects_are_dropped() {
+        let mut input = sample_pdf(1);
+        // Append an unreferenced object through an incremental update
+        let mut orphan = crate::objects::Dictionary::new();
+        orphan.set("Type", crate::objects::Object::Name("Orphan".to_string()));
+        let size = PdfReader::new(Cursor::new(&input))
+            .unwrap()
+            .trailer()
+            .size()
+            .unwrap();
+        let mut updated = Vec::new();
+        PdfWriter::new_with_writer(&mut updated)
+            .write_incremental_objects(
+                &input,
+                vec![(
+                    ObjectId::new(size, 0),
+                    crate::objects::Object::Dictionary(orphan),
+                )],
+            )
+            .unwrap();
+        input = updated;
+
+        let (output, report) = PdfOptimizer::new(OptimizeOptions::default())
+            .optimize(&input)
+            .unwrap();
+        assert!(report.objects_after < report.objects_before);
+        assert!(!output.windows(7).any(|w| w == b"/Orphan"));
+    }
+
+    #[cfg(feature = "performance")]
+    #[test]
+    fn test_content_aware_compression() {
+        let mut deflater = Deflater::new();
+        let content = b"BT /F1 12 Tf 72 720 Td (Hello) Tj ET\n".repeat(20);
+        let compressed = deflater
+            .deflate(content.clone(), ContentHint::Other)
+            .unwrap();
+        assert!(compressed.len() < content.len());
+        assert_eq!(
+            crate::compression::decompress(&compressed).unwrap(),
+            content
+        );
+        // Too small to be worth compressing
+        assert!(deflater
+            .deflate(b"0 0 m".to_vec(), ContentHint::Other)
+            .is_none());
+
+        let mut page = Page::a4();
+        for line in 0..40 {
+            page.text()
+                .set_font(Font::Helvetica, 10.0)
+                .at(72.0, 780.0 - line as f64 * 12.0)
+                .write("The same line of text, over and over again")
+                .unwrap();
+        }
+        let mut doc = Document::new();
+        doc.set_compress(false);
+        doc.add_page(page);
+        let input = doc.to_bytes().unwrap();
+
+        let (output, report) = PdfOptimizer::new(OptimizeOptions::default())
+            .optimize(&input)
+            .unwrap();
+        assert!(report.optimized_size < report.original_size);
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(output)).unwrap());
+        let text = document.extract_text_from_page(0).unwrap().text;
+        assert!(text.contains("over and over again"));
+    }
+
+    #[test]
+    fn test_from_quality() {
+        let options = OptimizeOptions::from_quality(50);
+        assert_eq!(options.image_quality, 50);
+        assert_eq!(options.max_image_dimension, Some(2100));
+        assert_eq!(OptimizeOptions::from_quality(95).max_image_dimension, None);
+        assert!(!OptimizeOptions::from_quality(100).recompress_images);
+    }
+
+    #[cfg(feature = "external-images")]
+    #[test]
+    fn test_large_image_is_downsampled() {
+        let (width, height) = (400u32, 300u32);
+        let pixels: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i / 3) % width * 255 / width) as u8)
+            .collect();
+        let mut dict = PdfDictionary::new();
+        for (key, value) in [
+            (
+                "Subtype",
+                PdfObject::Name(PdfName::new("Image".to_string())),
+            ),
+            ("Width", PdfObject::Integer(width as i64)),
+            ("Height", PdfObject::Integer(height as i64)),
+            ("BitsPerComponent", PdfObject::Integer(8)),
+            (
+                "ColorSpace",
+                PdfObject::Name(PdfName::new("DeviceRGB".to_string())),
+            ),
+        ] {
+            dict.insert(key.to_string(), value);
+        }
+        let stream = PdfStream { dict, data: pixels };
+
+        let mut options = OptimizeOptions::from_quality(60);
+        options.max_image_dimension = Some(100);
+        let optimizer = PdfOptimizer::new(options);
+        let mut report = OptimizationReport::default();
+        let resampled = optimizer
+            .optimize_stream(&stream, &mut Deflater::new(), &mut report)
+            .unwrap();
+
+        assert_eq!(report.images_resampled, 1);
+        assert_eq!(resampled.dict.get("Width"), Some(&PdfObject::Integer(100)));
+        assert_eq!(resampled.dict.get("Height"), Some(&PdfObject::Integer(75)));
+        assert!(resampled.data.starts_with(&[0xFF, 0xD8]));
+    }
+}
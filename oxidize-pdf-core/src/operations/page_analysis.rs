@@ -37,6 +37,7 @@
 
 use super::{OperationError, OperationResult};
 use crate::parser::{PdfDocument, PdfReader};
+use crate::rendering::RenderOptions;
 use crate::text::{ExtractionOptions, OcrOptions, OcrProcessingResult, OcrProvider, TextExtractor};
 // Note: ImageExtractor functionality is implemented inline to avoid circular dependencies
 use std::fs::File;
@@ -471,6 +472,52 @@ impl PageContentAnalyzer {
         Ok(ocr_result)
     }
 
+    /// Extract text from any page by rendering it and running OCR on the result
+    ///
+    /// Unlike [`extract_text_from_scanned_page`](Self::extract_text_from_scanned_page),
+    /// this does not need the page to consist of a single scanned image: the
+    /// whole page, including vector graphics and text, is rasterized at `dpi`
+    /// and passed to the provider as a PNG.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_number` - The page number to process (0-indexed)
+    /// * `ocr_provider` - The OCR provider to use for text extraction
+    /// * `dpi` - Rendering resolution; 300 is a common choice for OCR
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::operations::page_analysis::PageContentAnalyzer;
+    /// # use oxidize_pdf::text::MockOcrProvider;
+    /// # use oxidize_pdf::parser::PdfReader;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let document = PdfReader::open_document("mixed.pdf")?;
+    /// let analyzer = PageContentAnalyzer::new(document);
+    /// let ocr_result = analyzer.extract_text_from_rendered_page(0, &MockOcrProvider::new(), 300.0)?;
+    /// println!("OCR extracted text: {}", ocr_result.text);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_text_from_rendered_page<P: OcrProvider>(
+        &self,
+        page_number: usize,
+        ocr_provider: &P,
+        dpi: f64,
+    ) -> OperationResult<OcrProcessingResult> {
+        let ocr_options = self.options.ocr_options.clone().unwrap_or_default();
+
+        let png = self
+            .document
+            .render_page(page_number as u32, &RenderOptions::with_dpi(dpi))
+            .and_then(|page| page.to_png())
+            .map_err(|e| OperationError::ParseError(format!("Page rendering failed: {e}")))?;
+
+        ocr_provider
+            .process_image(&png, &ocr_options)
+            .map_err(|e| OperationError::ParseError(format!("OCR processing failed: {e}")))
+    }
+
     /// Process all scanned pages in the document with OCR
     ///
     /// This method identifies all scanned pages and processes them with OCR,
@@ -1416,6 +1463,10 @@ impl PageContentAnalyzer {
 
                     final_jpeg_data
                 }
+                "JPXDecode" => {
+                    // JPEG 2000 - decode the raw stream to 8-bit samples
+                    self.convert_jpx_to_png_for_ocr(&stream.data)?
+                }
                 filter_name => {
                     // For other filters, we need to decode the stream first
                     tracing::debug!("🔍 [DEBUG] Decoding stream with filter: {}", filter_name);
@@ -1444,6 +1495,10 @@ impl PageContentAnalyzer {
                             // Convert CCITT fax to PNG
                             self.convert_ccitt_to_png_for_ocr(&decoded_data, width, height)?
                         }
+                        "JBIG2Decode" => {
+                            // JBIG2 decodes to the same 1-bit layout as CCITT fax
+                            self.convert_ccitt_to_png_for_ocr(&decoded_data, width, height)?
+                        }
                         "LZWDecode" => {
                             // Convert LZW decoded data to PNG
                             self.convert_raw_to_png_for_ocr(
@@ -1471,6 +1526,7 @@ impl PageContentAnalyzer {
                             tracing::debug!("🔍 [DEBUG] Array filter: Using raw JPEG stream data");
                             stream.data.clone()
                         }
+                        "JPXDecode" => self.convert_jpx_to_png_for_ocr(&stream.data)?,
                         filter_name => {
                             // Decode other filter types
                             tracing::debug!(
@@ -1495,6 +1551,9 @@ impl PageContentAnalyzer {
                                 "CCITTFaxDecode" => {
                                     self.convert_ccitt_to_png_for_ocr(&decoded_data, width, height)?
                                 }
+                                "JBIG2Decode" => {
+                                    self.convert_ccitt_to_png_for_ocr(&decoded_data, width, height)?
+                                }
                                 "LZWDecode" => self.convert_raw_to_png_for_ocr(
                                     &decoded_data,
                                     width,
@@ -2112,6 +2171,29 @@ impl PageContentAnalyzer {
         )
     }
 
+    /// Convert a JPEG 2000 stream to PNG for OCR processing
+    fn convert_jpx_to_png_for_ocr(&self, data: &[u8]) -> OperationResult<Vec<u8>> {
+        let image = crate::parser::filters::decode_jpx_image(data).map_err(|e| {
+            OperationError::ParseError(format!("Failed to decode JPEG 2000 image: {e}"))
+        })?;
+        let (components, pixels) = image.to_gray_or_rgb();
+        let color_space = if components == 1 {
+            "DeviceGray"
+        } else {
+            "DeviceRGB"
+        };
+
+        self.convert_raw_to_png_for_ocr(
+            &pixels,
+            image.width,
+            image.height,
+            Some(&crate::parser::objects::PdfObject::Name(
+                crate::parser::objects::PdfName(color_space.to_string()),
+            )),
+            8,
+        )
+    }
+
     /// Write a PNG chunk with proper CRC
     fn write_png_chunk(&self, output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
         // Length (4 bytes, big endian)
@@ -2574,6 +2656,22 @@ startxref
             .contains("not a scanned page"));
     }
 
+    #[test]
+    fn test_ocr_extraction_from_rendered_page() {
+        let doc = create_mock_document();
+        let analyzer = PageContentAnalyzer::new(doc);
+        let ocr_provider = MockOcrProvider::new();
+
+        // Works for pages that are not scanned, too
+        let result = analyzer
+            .extract_text_from_rendered_page(0, &ocr_provider, 36.0)
+            .unwrap();
+        assert!(!result.text.is_empty());
+        assert!(analyzer
+            .extract_text_from_rendered_page(3, &ocr_provider, 36.0)
+            .is_err());
+    }
+
     // Test 9: OCR processing fallback scenarios
     #[test]
     fn test_ocr_processing_fallback() {
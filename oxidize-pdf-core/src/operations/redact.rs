@@ -0,0 +1,1400 @@
+//! Redaction of existing documents
+//!
+//! [`Redactor`] marks areas of pages, either directly, from text search hits
+//! or from `Redact` annotations, and then applies them: the content under
+//! each area is removed from the file, not just covered. Glyphs touching an
+//! area are removed from text operators, image pixels inside it are
+//! overwritten, vector paths within it are dropped, and annotations over it
+//! are deleted. An overlay box, optionally labelled, is drawn in its place.
+//!
+//! The document is rewritten from scratch, so earlier revisions holding the
+//! original content are not carried over.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use oxidize_pdf::geometry::{Point, Rectangle};
+//! use oxidize_pdf::operations::{RedactOptions, Redactor};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut redactor = Redactor::open("exhibit.pdf")?;
+//! redactor.mark_text("Jane Doe")?;
+//! redactor.mark_area(0, Rectangle::new(Point::new(72.0, 600.0), Point::new(300.0, 640.0)))?;
+//! let options = RedactOptions {
+//!     overlay_text: Some("REDACTED".to_string()),
+//!     ..Default::default()
+//! };
+//! let (pdf, report) = redactor.apply(&options)?;
+//! std::fs::write("exhibit-redacted.pdf", pdf)?;
+//! println!("{} glyphs removed", report.glyphs_removed);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::content_editor::{bounding_box, overlaps};
+use super::optimize::{load_reachable, write_reachable};
+use super::{ContentEditor, OperationError, OperationResult};
+use crate::coordinate_system::TransformMatrix;
+use crate::geometry::{Point, Rectangle};
+use crate::graphics::Color;
+use crate::parser::content::ContentOperation;
+use crate::parser::objects::{PdfArray, PdfDictionary, PdfName, PdfObject, PdfStream};
+use crate::parser::resolve::{get_dict, get_numbers, Resolver};
+use crate::parser::strings::text_string;
+use crate::parser::{ParseOptions, PdfDocument, PdfReader};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Resource name of the font used for overlay labels
+const LABEL_FONT: &str = "RedactLabel";
+
+/// Nesting limit for form XObjects, which may reference each other
+const MAX_FORM_DEPTH: usize = 8;
+
+/// Filters whose decoded samples can be edited and stored with Flate
+const EDITABLE_FILTERS: [&str; 10] = [
+    "FlateDecode",
+    "Fl",
+    "LZWDecode",
+    "LZW",
+    "ASCIIHexDecode",
+    "AHx",
+    "ASCII85Decode",
+    "A85",
+    "RunLengthDecode",
+    "DCTDecode",
+];
+
+/// An area of a page marked for redaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redaction {
+    /// Zero-based page index
+    pub page: u32,
+    /// Area in default user space
+    pub area: Rectangle,
+    /// Overlay label, replacing [`RedactOptions::overlay_text`]
+    pub overlay_text: Option<String>,
+    /// Overlay color, replacing [`RedactOptions::fill_color`]
+    pub color: Option<Color>,
+    /// The `Redact` annotation the mark comes from
+    source: Option<(u32, u16)>,
+}
+
+impl Redaction {
+    fn new(page: u32, area: Rectangle) -> Self {
+        Self {
+            page,
+            area,
+            overlay_text: None,
+            color: None,
+            source: None,
+        }
+    }
+}
+
+/// Options for applying redactions
+#[derive(Debug, Clone)]
+pub struct RedactOptions {
+    /// Color of the box drawn over each area, `None` to leave it blank
+    pub fill_color: Option<Color>,
+    /// Label drawn in each box
+    pub overlay_text: Option<String>,
+    /// Remove document information entries and XMP metadata containing
+    /// searched text
+    pub scrub_metadata: bool,
+    /// Remove annotations and form fields overlapping an area
+    pub remove_annotations: bool,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self {
+            fill_color: Some(Color::black()),
+            overlay_text: None,
+            scrub_metadata: true,
+            remove_annotations: true,
+        }
+    }
+}
+
+/// What applying redactions removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RedactionReport {
+    /// Number of areas applied
+    pub areas: usize,
+    /// Glyphs removed from text operators
+    pub glyphs_removed: usize,
+    /// Vector paths removed
+    pub paths_removed: usize,
+    /// Images whose pixels were overwritten, or that were replaced or
+    /// removed when they could not be edited
+    pub images_redacted: usize,
+    /// Annotations removed, including applied `Redact` annotations
+    pub annotations_removed: usize,
+    /// Document information entries and metadata streams removed
+    pub metadata_removed: usize,
+}
+
+/// Marks and applies redactions on an existing document
+pub struct Redactor {
+    pdf: Vec<u8>,
+    document: PdfDocument<Cursor<Vec<u8>>>,
+    redactions: Vec<Redaction>,
+    /// Searched text, scrubbed from metadata
+    terms: Vec<String>,
+}
+
+impl Redactor {
+    /// Load a document held in memory
+    ///
+    /// # Errors
+    /// Returns an error if the document cannot be parsed or is encrypted.
+    pub fn new(pdf: Vec<u8>) -> OperationResult<Self> {
+        let reader = PdfReader::new(Cursor::new(pdf.clone()))
+            .map_err(|e| OperationError::ParseError(e.to_string()))?;
+        if reader.is_encrypted() {
+            return Err(OperationError::ProcessingError(
+                "Encrypted documents cannot be redacted".to_string(),
+            ));
+        }
+        Ok(Self {
+            pdf,
+            document: PdfDocument::new(reader),
+            redactions: Vec::new(),
+            terms: Vec::new(),
+        })
+    }
+
+    /// Load a document from a file
+    pub fn open(path: impl AsRef<Path>) -> OperationResult<Self> {
+        Self::new(std::fs::read(path)?)
+    }
+
+    /// The marked areas, in the order they were marked
+    pub fn redactions(&self) -> &[Redaction] {
+        &self.redactions
+    }
+
+    /// Mark an area (in default user space) of a page (zero-based index)
+    pub fn mark_area(&mut self, page: u32, area: Rectangle) -> OperationResult<&mut Redaction> {
+        let count = self.page_count()?;
+        if page >= count {
+            return Err(OperationError::PageIndexOutOfBounds(
+                page as usize,
+                count as usize,
+            ));
+        }
+        self.redactions.push(Redaction::new(page, area));
+        Ok(self.redactions.last_mut().expect("just pushed"))
+    }
+
+    /// Mark every occurrence of `text` on every page, returning how many
+    /// were found
+    ///
+    /// The text is also scrubbed from metadata when the redactions are
+    /// applied with [`RedactOptions::scrub_metadata`].
+    pub fn mark_text(&mut self, text: &str) -> OperationResult<usize> {
+        let mut found = 0;
+        for page in 0..self.page_count()? {
+            let editor = ContentEditor::from_page(&self.document, page)?;
+            for area in editor.find_text(text) {
+                self.redactions.push(Redaction::new(page, area));
+                found += 1;
+            }
+        }
+        if !text.is_empty() {
+            self.terms.push(text.to_string());
+        }
+        Ok(found)
+    }
+
+    /// Mark the areas of the document's `Redact` annotations, returning how
+    /// many were found
+    ///
+    /// Their overlay text and interior color are used for the overlay, and
+    /// the annotations are removed once applied.
+    pub fn mark_annotations(&mut self) -> OperationResult<usize> {
+        let mut found = 0;
+        let resolver: &dyn Resolver = &self.document;
+        for page in 0..self.page_count()? {
+            let parsed = self
+                .document
+                .get_page(page)
+                .map_err(|e| OperationError::ParseError(e.to_string()))?;
+            let annotations = match parsed.dict.get("Annots").map(|a| resolver.lookup(a)) {
+                Some(PdfObject::Array(array)) => array.0,
+                _ => continue,
+            };
+            for entry in annotations {
+                let PdfObject::Dictionary(dict) = resolver.lookup(&entry) else {
+                    continue;
+                };
+                if dict
+                    .get("Subtype")
+                    .and_then(|s| s.as_name())
+                    .map(|n| n.as_str())
+                    != Some("Redact")
+                {
+                    continue;
+                }
+                let source = match entry {
+                    PdfObject::Reference(number, generation) => Some((number, generation)),
+                    _ => None,
+                };
+                let overlay_text =
+                    match resolver.lookup(dict.get("OverlayText").unwrap_or(&PdfObject::Null)) {
+                        PdfObject::String(text) => Some(text_string(text.as_bytes())),
+                        _ => None,
+                    };
+                let color = match get_numbers(resolver, &dict, "IC").as_deref() {
+                    Some(&[gray]) => Some(Color::gray(gray)),
+                    Some(&[r, g, b]) => Some(Color::rgb(r, g, b)),
+                    Some(&[c, m, y, k]) => Some(Color::cmyk(c, m, y, k)),
+                    _ => None,
+                };
+                for area in annotation_areas(resolver, &dict) {
+                    self.redactions.push(Redaction {
+                        page,
+                        area,
+                        overlay_text: overlay_text.clone(),
+                        color,
+                        source,
+                    });
+                    found += 1;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Apply the marked redactions, returning the rewritten document
+    pub fn apply(&self, options: &RedactOptions) -> OperationResult<(Vec<u8>, RedactionReport)> {
+        let mut reader = PdfReader::new(Cursor::new(self.pdf.as_slice()))
+            .map_err(|e| OperationError::ParseError(e.to_string()))?;
+        let trailer = reader.trailer().clone();
+        let root = trailer
+            .root()
+            .map_err(|e| OperationError::ParseError(e.to_string()))?;
+        let info = trailer.info();
+        let version = reader.version().clone();
+        let objects = load_reachable(&mut reader, root, info);
+
+        let mut application = Application {
+            document: &self.document,
+            next_number: objects.keys().map(|id| id.0).max().unwrap_or(0) + 1,
+            objects,
+            options,
+            report: RedactionReport::default(),
+            removed_widgets: HashSet::new(),
+        };
+
+        let mut pages: BTreeMap<u32, Vec<&Redaction>> = BTreeMap::new();
+        for redaction in &self.redactions {
+            pages.entry(redaction.page).or_default().push(redaction);
+        }
+        for (page, redactions) in pages {
+            application.redact_page(page, &redactions)?;
+        }
+        application.prune_fields(root);
+        if options.scrub_metadata && !self.terms.is_empty() {
+            application.scrub_metadata(root, info, &self.terms);
+        }
+
+        let Application {
+            objects, report, ..
+        } = application;
+        let (output, _) = write_reachable(objects, root, info, &version, false)?;
+        Ok((output, report))
+    }
+
+    fn page_count(&self) -> OperationResult<u32> {
+        self.document
+            .page_count()
+            .map_err(|e| OperationError::ParseError(e.to_string()))
+    }
+}
+
+/// State of a redaction being applied
+struct Application<'a> {
+    document: &'a PdfDocument<Cursor<Vec<u8>>>,
+    /// Objects of the output, by id
+    objects: HashMap<(u32, u16), PdfObject>,
+    next_number: u32,
+    options: &'a RedactOptions,
+    report: RedactionReport,
+    /// Widget annotations removed, to drop from the field tree
+    removed_widgets: HashSet<(u32, u16)>,
+}
+
+impl Application<'_> {
+    fn redact_page(&mut self, index: u32, redactions: &[&Redaction]) -> OperationResult<()> {
+        let parse_error = |e: crate::parser::ParseError| OperationError::ParseError(e.to_string());
+        let page = self.document.get_page(index).map_err(parse_error)?;
+        let resolver: &dyn Resolver = self.document;
+        let content = self
+            .document
+            .get_page_content_streams(&page)
+            .map_err(parse_error)?
+            .join(&b'\n');
+        let resources =
+            get_dict(resolver, &page.dict, "Resources").or(page.inherited_resources.clone());
+        let areas: Vec<(Rectangle, Color)> = redactions
+            .iter()
+            .map(|r| {
+                let color = r.color.or(self.options.fill_color);
+                (r.area, color.unwrap_or(Color::white()))
+            })
+            .collect();
+        self.report.areas += areas.len();
+
+        let mut editor =
+            ContentEditor::from_resources(self.document, &content, resources.as_ref())?;
+        let redacted = self.redact_content(&mut editor, resources.as_ref(), &areas, 0)?;
+
+        let mut overlay = Vec::new();
+        let mut labelled = false;
+        for redaction in redactions {
+            let Some(color) = redaction.color.or(self.options.fill_color) else {
+                continue;
+            };
+            let label = redaction
+                .overlay_text
+                .as_ref()
+                .or(self.options.overlay_text.as_ref());
+            labelled |= label.is_some();
+            overlay.extend(overlay_operations(&redaction.area, color, label));
+        }
+        editor.append(overlay);
+
+        let data = crate::compression::compress(&editor.to_bytes())?;
+        let mut dict = PdfDictionary::new();
+        dict.insert("Filter".to_string(), name("FlateDecode"));
+        let contents = self.add(PdfObject::Stream(PdfStream { dict, data }));
+
+        let mut page_dict = match self.objects.get(&page.obj_ref) {
+            Some(PdfObject::Dictionary(dict)) => dict.clone(),
+            _ => page.dict.clone(),
+        };
+        page_dict.insert("Contents".to_string(), contents);
+        // The thumbnail shows the original content
+        page_dict.0.remove(&PdfName::new("Thumb".to_string()));
+        if let Some(resources) = &redacted {
+            page_dict.insert(
+                "Resources".to_string(),
+                PdfObject::Dictionary(resources.clone()),
+            );
+        }
+        if labelled {
+            let mut resources = redacted.or(resources).unwrap_or_default();
+            let mut fonts = get_dict(resolver, &resources, "Font").unwrap_or_default();
+            let mut font = PdfDictionary::new();
+            font.insert("Type".to_string(), name("Font"));
+            font.insert("Subtype".to_string(), name("Type1"));
+            font.insert("BaseFont".to_string(), name("Helvetica"));
+            font.insert("Encoding".to_string(), name("WinAnsiEncoding"));
+            fonts.insert(LABEL_FONT.to_string(), PdfObject::Dictionary(font));
+            resources.insert("Font".to_string(), PdfObject::Dictionary(fonts));
+            page_dict.insert("Resources".to_string(), PdfObject::Dictionary(resources));
+        }
+        let sources: HashSet<(u32, u16)> = redactions.iter().filter_map(|r| r.source).collect();
+        self.redact_annotations(&mut page_dict, &areas, &sources);
+        self.objects
+            .insert(page.obj_ref, PdfObject::Dictionary(page_dict));
+        Ok(())
+    }
+
+    /// Remove text, paths and image content under the areas from content
+    /// drawn with `resources`
+    ///
+    /// XObjects are redacted in copies, as they may be drawn elsewhere too.
+    /// The placements under the areas are pointed at the copies, and the
+    /// resources with the copies are returned if there are any.
+    fn redact_content(
+        &mut self,
+        editor: &mut ContentEditor,
+        resources: Option<&PdfDictionary>,
+        areas: &[(Rectangle, Color)],
+        depth: usize,
+    ) -> OperationResult<Option<PdfDictionary>> {
+        for (area, _) in areas {
+            self.report.glyphs_removed += editor.remove_text_touching(*area);
+            self.report.paths_removed += editor.remove_paths_in(*area);
+        }
+
+        let mut inline_images = HashSet::new();
+        let mut copies = Vec::new();
+        for placement in editor.placements() {
+            let Some(name) = placement.name else {
+                // Inline images are small; they are removed rather than edited
+                let bounds = unit_square_bounds(&placement.ctm);
+                if areas.iter().any(|(area, _)| overlaps(area, &bounds)) {
+                    inline_images.insert(placement.index);
+                }
+                continue;
+            };
+            let Some(id) = resources.and_then(|r| self.xobject_id(r, &name)) else {
+                continue;
+            };
+            let copy = if placement.form {
+                self.redact_form(id, &placement.ctm, resources, areas, depth)?
+            } else {
+                self.redact_image(id, &placement.ctm, areas)?
+            };
+            if let Some(copy) = copy {
+                copies.push((placement.index, name, copy));
+            }
+        }
+
+        let mut redacted = None;
+        if !copies.is_empty() {
+            let mut resources = resources.cloned().unwrap_or_default();
+            let mut xobjects = get_dict(self.document, &resources, "XObject").unwrap_or_default();
+            let mut renamed = HashMap::new();
+            let mut originals = HashSet::new();
+            for (index, name, copy) in copies {
+                let copy_name = (1..)
+                    .map(|n| format!("{name}Redacted{n}"))
+                    .find(|candidate| !xobjects.contains_key(candidate))
+                    .expect("unbounded range");
+                xobjects.insert(copy_name.clone(), copy);
+                renamed.insert(index, copy_name);
+                originals.insert(name);
+            }
+
+            let mut index = 0;
+            editor.replace(|_| {
+                index += 1;
+                let name = renamed.remove(&(index - 1))?;
+                Some(vec![ContentOperation::PaintXObject(name)])
+            });
+            // Originals no longer drawn here are dropped, so the unredacted
+            // data is only kept if something else uses it
+            for operation in editor.operations() {
+                if let ContentOperation::PaintXObject(name) = operation {
+                    originals.remove(name);
+                }
+            }
+            for name in originals {
+                xobjects.0.remove(&PdfName::new(name));
+            }
+            resources.insert("XObject".to_string(), PdfObject::Dictionary(xobjects));
+            redacted = Some(resources);
+        }
+
+        self.report.images_redacted += inline_images.len();
+        let mut index = 0;
+        editor.retain(|_| {
+            index += 1;
+            !inline_images.contains(&(index - 1))
+        });
+        Ok(redacted)
+    }
+
+    /// Redact a copy of a form XObject, returning a reference to the copy
+    /// if the form is under the areas
+    fn redact_form(
+        &mut self,
+        id: (u32, u16),
+        ctm: &TransformMatrix,
+        parent_resources: Option<&PdfDictionary>,
+        areas: &[(Rectangle, Color)],
+        depth: usize,
+    ) -> OperationResult<Option<PdfObject>> {
+        if depth >= MAX_FORM_DEPTH {
+            return Ok(None);
+        }
+        let Some(PdfObject::Stream(stream)) = self.objects.get(&id).cloned() else {
+            return Ok(None);
+        };
+        let resolver: &dyn Resolver = self.document;
+        let base = match get_numbers(resolver, &stream.dict, "Matrix").as_deref() {
+            Some(&[a, b, c, d, e, f]) => ctm.multiply(&TransformMatrix::new(a, b, c, d, e, f)),
+            _ => *ctm,
+        };
+        if let Some(&[x0, y0, x1, y1]) = get_numbers(resolver, &stream.dict, "BBox").as_deref() {
+            let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)]
+                .map(|(x, y)| base.transform_point(Point::new(x, y)));
+            let bounds = bounding_box(&corners);
+            if !areas.iter().any(|(area, _)| overlaps(area, &bounds)) {
+                return Ok(None);
+            }
+        }
+
+        let content = stream.decode(&ParseOptions::default()).map_err(|e| {
+            OperationError::ProcessingError(format!(
+                "form XObject {} {} R cannot be redacted: {e}",
+                id.0, id.1
+            ))
+        })?;
+        let resources =
+            get_dict(resolver, &stream.dict, "Resources").or_else(|| parent_resources.cloned());
+        let mut editor =
+            ContentEditor::from_resources(self.document, &content, resources.as_ref())?
+                .with_transform(base);
+        let redacted = self.redact_content(&mut editor, resources.as_ref(), areas, depth + 1)?;
+
+        let mut dict = stream.dict.clone();
+        dict.0.remove(&PdfName::new("DecodeParms".to_string()));
+        dict.insert("Filter".to_string(), name("FlateDecode"));
+        if let Some(resources) = redacted {
+            dict.insert("Resources".to_string(), PdfObject::Dictionary(resources));
+        }
+        let data = crate::compression::compress(&editor.to_bytes())?;
+        Ok(Some(self.add(PdfObject::Stream(PdfStream { dict, data }))))
+    }
+
+    /// Overwrite the pixels of a copy of an image XObject under the areas,
+    /// with those of its soft mask or stencil mask, returning a reference
+    /// to the copy if the image is under the areas
+    fn redact_image(
+        &mut self,
+        id: (u32, u16),
+        ctm: &TransformMatrix,
+        areas: &[(Rectangle, Color)],
+    ) -> OperationResult<Option<PdfObject>> {
+        let Some(inverse) = invert(ctm) else {
+            return Ok(None);
+        };
+        let bounds = unit_square_bounds(ctm);
+        // Areas in the unit square of image space
+        let regions: Vec<(Rectangle, Color)> = areas
+            .iter()
+            .filter(|(area, _)| overlaps(area, &bounds))
+            .map(|(area, color)| {
+                let corners = [
+                    area.lower_left,
+                    area.upper_right,
+                    Point::new(area.lower_left.x, area.upper_right.y),
+                    Point::new(area.upper_right.x, area.lower_left.y),
+                ]
+                .map(|point| inverse.transform_point(point));
+                (bounding_box(&corners), *color)
+            })
+            .collect();
+        if regions.is_empty() {
+            return Ok(None);
+        }
+        let Some(PdfObject::Stream(stream)) = self.objects.get(&id).cloned() else {
+            return Ok(None);
+        };
+
+        let resolver: &dyn Resolver = self.document;
+        let redacted = match blank_pixels(resolver, &stream, &regions, None) {
+            Some(mut redacted) => {
+                for key in ["SMask", "Mask"] {
+                    if let Some(&PdfObject::Reference(number, generation)) = stream.dict.get(key) {
+                        let mask_id = (number, generation);
+                        if let Some(PdfObject::Stream(mask)) = self.objects.get(&mask_id).cloned() {
+                            // Opaque, so the blanked pixels show
+                            let mask = blank_pixels(resolver, &mask, &regions, Some(1.0))
+                                .unwrap_or_else(|| solid_image(Color::white()));
+                            let mask = self.add(PdfObject::Stream(mask));
+                            redacted.dict.insert(key.to_string(), mask);
+                        }
+                    }
+                }
+                redacted
+            }
+            // Images that can't be edited are replaced as a whole
+            None => solid_image(regions[0].1),
+        };
+        self.report.images_redacted += 1;
+        Ok(Some(self.add(PdfObject::Stream(redacted))))
+    }
+
+    /// Remove annotations over the areas and the applied `Redact`
+    /// annotations from a page
+    fn redact_annotations(
+        &mut self,
+        page: &mut PdfDictionary,
+        areas: &[(Rectangle, Color)],
+        sources: &HashSet<(u32, u16)>,
+    ) {
+        let entries = match page.get("Annots") {
+            Some(PdfObject::Array(array)) => array.0.clone(),
+            Some(&PdfObject::Reference(number, generation)) => {
+                match self.objects.get(&(number, generation)) {
+                    Some(PdfObject::Array(array)) => array.0.clone(),
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        let resolver: &dyn Resolver = self.document;
+        let annotation = |entry: &PdfObject| match entry {
+            &PdfObject::Reference(number, generation) => {
+                match self.objects.get(&(number, generation)) {
+                    Some(PdfObject::Dictionary(dict)) => Some(dict.clone()),
+                    _ => None,
+                }
+            }
+            PdfObject::Dictionary(dict) => Some(dict.clone()),
+            _ => None,
+        };
+
+        let mut removed: HashSet<(u32, u16)> = HashSet::new();
+        let mut keep = vec![true; entries.len()];
+        for (i, entry) in entries.iter().enumerate() {
+            let Some(dict) = annotation(entry) else {
+                continue;
+            };
+            let id = match entry {
+                &PdfObject::Reference(number, generation) => Some((number, generation)),
+                _ => None,
+            };
+            let covered = self.options.remove_annotations
+                && get_numbers(resolver, &dict, "Rect")
+                    .and_then(|r| rectangle(&r))
+                    .is_some_and(|rect| areas.iter().any(|(area, _)| overlaps(area, &rect)));
+            if covered || id.is_some_and(|id| sources.contains(&id)) {
+                keep[i] = false;
+                if let Some(id) = id {
+                    removed.insert(id);
+                    if subtype(&dict) == Some("Widget") {
+                        self.removed_widgets.insert(id);
+                    }
+                }
+            }
+        }
+        // Popups of removed annotations go with them
+        for (i, entry) in entries.iter().enumerate() {
+            let parent = annotation(entry).and_then(|dict| match dict.get("Parent") {
+                Some(&PdfObject::Reference(number, generation)) => Some((number, generation)),
+                _ => None,
+            });
+            if parent.is_some_and(|parent| removed.contains(&parent)) {
+                keep[i] = false;
+            }
+        }
+
+        let kept: Vec<PdfObject> = entries
+            .into_iter()
+            .zip(&keep)
+            .filter_map(|(entry, &keep)| keep.then_some(entry))
+            .collect();
+        self.report.annotations_removed += keep.len() - kept.len();
+        if kept.is_empty() {
+            page.0.remove(&PdfName::new("Annots".to_string()));
+        } else {
+            page.insert("Annots".to_string(), PdfObject::Array(PdfArray(kept)));
+        }
+    }
+
+    /// Drop removed widgets from the field tree, with fields left without
+    /// widgets
+    fn prune_fields(&mut self, root: (u32, u16)) {
+        if self.removed_widgets.is_empty() {
+            return;
+        }
+        let Some(PdfObject::Dictionary(catalog)) = self.objects.get(&root).cloned() else {
+            return;
+        };
+        let (form_id, mut form) = match catalog.get("AcroForm") {
+            Some(&PdfObject::Reference(number, generation)) => {
+                match self.objects.get(&(number, generation)) {
+                    Some(PdfObject::Dictionary(dict)) => (Some((number, generation)), dict.clone()),
+                    _ => return,
+                }
+            }
+            Some(PdfObject::Dictionary(dict)) => (None, dict.clone()),
+            _ => return,
+        };
+        let Some(PdfObject::Array(fields)) = form.get("Fields").cloned() else {
+            return;
+        };
+        let fields = self.prune(fields.0);
+        form.insert("Fields".to_string(), PdfObject::Array(PdfArray(fields)));
+        // XFA data would still hold the removed values
+        form.0.remove(&PdfName::new("XFA".to_string()));
+        match form_id {
+            Some(id) => {
+                self.objects.insert(id, PdfObject::Dictionary(form));
+            }
+            None => {
+                let mut catalog = catalog;
+                catalog.insert("AcroForm".to_string(), PdfObject::Dictionary(form));
+                self.objects.insert(root, PdfObject::Dictionary(catalog));
+            }
+        }
+    }
+
+    fn prune(&mut self, nodes: Vec<PdfObject>) -> Vec<PdfObject> {
+        let mut kept = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let &PdfObject::Reference(number, generation) = &node else {
+                kept.push(node);
+                continue;
+            };
+            let id = (number, generation);
+            if self.removed_widgets.contains(&id) {
+                continue;
+            }
+            if let Some(PdfObject::Dictionary(mut dict)) = self.objects.get(&id).cloned() {
+                if let Some(PdfObject::Array(kids)) = dict.get("Kids").cloned() {
+                    let had_kids = !kids.0.is_empty();
+                    let kids = self.prune(kids.0);
+                    if had_kids && kids.is_empty() {
+                        continue;
+                    }
+                    dict.insert("Kids".to_string(), PdfObject::Array(PdfArray(kids)));
+                    self.objects.insert(id, PdfObject::Dictionary(dict));
+                }
+            }
+            kept.push(node);
+        }
+        kept
+    }
+
+    /// Remove information entries and XMP metadata containing any of the
+    /// terms
+    fn scrub_metadata(&mut self, root: (u32, u16), info: Option<(u32, u16)>, terms: &[String]) {
+        let matches = |text: &str| terms.iter().any(|term| text.contains(term.as_str()));
+        if let Some(PdfObject::Dictionary(dict)) = info.and_then(|id| self.objects.get_mut(&id)) {
+            let before = dict.0.len();
+            dict.0.retain(|_, value| match value {
+                PdfObject::String(text) => !matches(&text_string(text.as_bytes())),
+                _ => true,
+            });
+            self.report.metadata_removed += before - dict.0.len();
+        }
+
+        let Some(PdfObject::Dictionary(catalog)) = self.objects.get(&root) else {
+            return;
+        };
+        let Some(&PdfObject::Reference(number, generation)) = catalog.get("Metadata") else {
+            return;
+        };
+        let Some(PdfObject::Stream(stream)) = self.objects.get(&(number, generation)) else {
+            return;
+        };
+        // Metadata that can't be read is removed too
+        let keep = stream
+            .decode(&ParseOptions::default())
+            .is_ok_and(|data| !matches(&String::from_utf8_lossy(&data)));
+        if !keep {
+            if let Some(PdfObject::Dictionary(catalog)) = self.objects.get_mut(&root) {
+                catalog.0.remove(&PdfName::new("Metadata".to_string()));
+            }
+            self.report.metadata_removed += 1;
+        }
+    }
+
+    /// Id of a named XObject of the resources
+    fn xobject_id(&self, resources: &PdfDictionary, name: &str) -> Option<(u32, u16)> {
+        let xobjects = get_dict(self.document, resources, "XObject")?;
+        match xobjects.get(name) {
+            Some(&PdfObject::Reference(number, generation)) => Some((number, generation)),
+            _ => None,
+        }
+    }
+
+    /// Add a new object, returning a reference to it
+    fn add(&mut self, object: PdfObject) -> PdfObject {
+        let id = (self.next_number, 0);
+        self.next_number += 1;
+        self.objects.insert(id, object);
+        PdfObject::Reference(id.0, id.1)
+    }
+}
+
+/// Areas of a `Redact` annotation: its quadrilaterals, or its rectangle
+fn annotation_areas(resolver: &dyn Resolver, dict: &PdfDictionary) -> Vec<Rectangle> {
+    if let Some(points) = get_numbers(resolver, dict, "QuadPoints") {
+        let areas: Vec<Rectangle> = points
+            .chunks_exact(8)
+            .map(|quad| {
+                let corners: Vec<Point> = quad
+                    .chunks_exact(2)
+                    .map(|p| Point::new(p[0], p[1]))
+                    .collect();
+                bounding_box(&corners)
+            })
+            .collect();
+        if !areas.is_empty() {
+            return areas;
+        }
+    }
+    get_numbers(resolver, dict, "Rect")
+        .and_then(|r| rectangle(&r))
+        .into_iter()
+        .collect()
+}
+
+/// Operations drawing the overlay of an area, with its label centered
+fn overlay_operations(
+    area: &Rectangle,
+    color: Color,
+    label: Option<&String>,
+) -> Vec<ContentOperation> {
+    use ContentOperation as Op;
+
+    let (x, y) = (area.lower_left.x as f32, area.lower_left.y as f32);
+    let (width, height) = (area.width() as f32, area.height() as f32);
+    let mut operations = vec![
+        Op::SaveGraphicsState,
+        fill_color(color),
+        Op::Rectangle(x, y, width, height),
+        Op::Fill,
+    ];
+    if let Some(label) = label {
+        let text: Vec<u8> = label
+            .chars()
+            .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+            .collect();
+        let font = crate::text::Font::Helvetica;
+        let mut size = (area.height() * 0.7).min(12.0);
+        let text_width = crate::text::measure_text(label, font.clone(), size);
+        if text_width > area.width() - 2.0 && text_width > 0.0 {
+            size *= (area.width() - 2.0).max(0.0) / text_width;
+        }
+        if size >= 1.0 {
+            let text_width = crate::text::measure_text(label, font, size);
+            // Light text on dark boxes
+            let rgb = color.to_rgb();
+            let luminance = 0.299 * rgb.r() + 0.587 * rgb.g() + 0.114 * rgb.b();
+            let text_gray = if luminance < 0.5 { 1.0 } else { 0.0 };
+            operations.extend([
+                Op::Rectangle(x, y, width, height),
+                Op::Clip,
+                Op::EndPath,
+                Op::BeginText,
+                Op::SetNonStrokingGray(text_gray),
+                Op::SetFont(LABEL_FONT.to_string(), size as f32),
+                Op::MoveText(
+                    (area.center().x - text_width / 2.0) as f32,
+                    (area.center().y - size * 0.35) as f32,
+                ),
+                Op::ShowText(text),
+                Op::EndText,
+            ]);
+        }
+    }
+    operations.push(Op::RestoreGraphicsState);
+    operations
+}
+
+fn fill_color(color: Color) -> ContentOperation {
+    match color {
+        Color::Gray(g) => ContentOperation::SetNonStrokingGray(g as f32),
+        Color::Rgb(r, g, b) => ContentOperation::SetNonStrokingRGB(r as f32, g as f32, b as f32),
+        Color::Cmyk(c, m, y, k) => {
+            ContentOperation::SetNonStrokingCMYK(c as f32, m as f32, y as f32, k as f32)
+        }
+    }
+}
+
+/// Set the samples of an image within regions of its unit square, to the
+/// region color or, for masks, to `mask_value`. Returns `None` if the
+/// samples can't be decoded.
+fn blank_pixels(
+    resolver: &dyn Resolver,
+    stream: &PdfStream,
+    regions: &[(Rectangle, Color)],
+    mask_value: Option<f64>,
+) -> Option<PdfStream> {
+    let dict = &stream.dict;
+    let filters: Vec<String> = match dict.get("Filter") {
+        Some(PdfObject::Name(name)) => vec![name.as_str().to_string()],
+        Some(PdfObject::Array(array)) => array
+            .0
+            .iter()
+            .filter_map(|f| f.as_name().map(|n| n.as_str().to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if filters
+        .iter()
+        .any(|f| !EDITABLE_FILTERS.contains(&f.as_str()))
+    {
+        return None;
+    }
+    let number = |key: &str| match resolver.lookup(dict.get(key)?) {
+        PdfObject::Integer(value) if value > 0 => Some(value as usize),
+        _ => None,
+    };
+    let (width, height) = (number("Width")?, number("Height")?);
+    let image_mask = matches!(dict.get("ImageMask"), Some(PdfObject::Boolean(true)));
+    let is_mask = image_mask || mask_value.is_some();
+    let bits = if image_mask {
+        1
+    } else {
+        number("BitsPerComponent")?
+    };
+    if ![1, 2, 4, 8, 16].contains(&bits) {
+        return None;
+    }
+    let components = if is_mask {
+        1
+    } else {
+        color_components(resolver, dict.get("ColorSpace")?)?
+    };
+
+    let options = ParseOptions {
+        decode_dct_images: true,
+        ..ParseOptions::default()
+    };
+    let mut data = stream.decode(&options).ok()?;
+    let row_bytes = (width * components * bits).div_ceil(8);
+    if data.len() < row_bytes * height {
+        return None;
+    }
+
+    let max = ((1u32 << bits) - 1) as f64;
+    let decode = get_numbers(resolver, dict, "Decode");
+    for (region, color) in regions {
+        let values = match mask_value {
+            Some(value) => vec![value],
+            // Unpainted stencil samples
+            None if image_mask => vec![1.0],
+            None => color_values(*color, components),
+        };
+        let samples: Vec<u32> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                let (low, high) = match decode.as_deref() {
+                    Some(d) if d.len() >= 2 * i + 2 && d[2 * i + 1] != d[2 * i] => {
+                        (d[2 * i], d[2 * i + 1])
+                    }
+                    _ => (0.0, 1.0),
+                };
+                (((value - low) / (high - low)).clamp(0.0, 1.0) * max).round() as u32
+            })
+            .collect();
+
+        let columns = span(region.lower_left.x, region.upper_right.x, width);
+        // Rows run from the top of the image
+        let rows = span(
+            1.0 - region.upper_right.y,
+            1.0 - region.lower_left.y,
+            height,
+        );
+        for row in rows {
+            for column in columns.clone() {
+                for (component, &sample) in samples.iter().enumerate() {
+                    let bit = (column * components + component) * bits;
+                    set_sample(&mut data[row * row_bytes..], bit, bits, sample);
+                }
+            }
+        }
+    }
+
+    let mut dict = dict.clone();
+    dict.0.remove(&PdfName::new("DecodeParms".to_string()));
+    dict.insert("Filter".to_string(), name("FlateDecode"));
+    let data = crate::compression::compress(&data).ok()?;
+    Some(PdfStream { dict, data })
+}
+
+/// Pixel indices covering a range of the unit interval
+fn span(start: f64, end: f64, count: usize) -> std::ops::Range<usize> {
+    let scale = |v: f64| v.clamp(0.0, 1.0) * count as f64;
+    let first = scale(start.min(end)).floor() as usize;
+    let last = (scale(start.max(end)).ceil() as usize).min(count);
+    first..last
+}
+
+fn set_sample(row: &mut [u8], bit: usize, bits: usize, sample: u32) {
+    match bits {
+        16 => {
+            row[bit / 8] = (sample >> 8) as u8;
+            row[bit / 8 + 1] = sample as u8;
+        }
+        8 => row[bit / 8] = sample as u8,
+        _ => {
+            let shift = 8 - bits - bit % 8;
+            let mask = (((1u32 << bits) - 1) << shift) as u8;
+            row[bit / 8] = (row[bit / 8] & !mask) | (((sample << shift) as u8) & mask);
+        }
+    }
+}
+
+/// Number of color components of an image color space
+fn color_components(resolver: &dyn Resolver, color_space: &PdfObject) -> Option<usize> {
+    let color_space = resolver.lookup(color_space);
+    let family = match &color_space {
+        PdfObject::Name(name) => name.as_str().to_string(),
+        PdfObject::Array(array) => array.0.first()?.as_name()?.as_str().to_string(),
+        _ => return None,
+    };
+    match family.as_str() {
+        "DeviceGray" | "G" | "CalGray" | "Indexed" | "I" | "Separation" => Some(1),
+        "DeviceRGB" | "RGB" | "CalRGB" | "Lab" => Some(3),
+        "DeviceCMYK" | "CMYK" => Some(4),
+        "ICCBased" => {
+            let PdfObject::Array(array) = &color_space else {
+                return None;
+            };
+            match resolver.lookup(array.0.get(1)?) {
+                PdfObject::Stream(profile) => match resolver.lookup(profile.dict.get("N")?) {
+                    PdfObject::Integer(n) if n > 0 => Some(n as usize),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        "DeviceN" => {
+            let PdfObject::Array(array) = &color_space else {
+                return None;
+            };
+            match resolver.lookup(array.0.get(1)?) {
+                PdfObject::Array(names) => Some(names.0.len()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Component values of a color for a number of components; other color
+/// spaces get zeros
+fn color_values(color: Color, components: usize) -> Vec<f64> {
+    match components {
+        1 => match color {
+            Color::Gray(g) => vec![g],
+            _ => {
+                let rgb = color.to_rgb();
+                vec![0.299 * rgb.r() + 0.587 * rgb.g() + 0.114 * rgb.b()]
+            }
+        },
+        3 => {
+            let rgb = color.to_rgb();
+            vec![rgb.r(), rgb.g(), rgb.b()]
+        }
+        4 => {
+            let (c, m, y, k) = color.to_cmyk().cmyk_components();
+            vec![c, m, y, k]
+        }
+        n => vec![0.0; n],
+    }
+}
+
+/// A one-pixel image of a color, replacing an image that can't be edited
+fn solid_image(color: Color) -> PdfStream {
+    let rgb = color.to_rgb();
+    let mut dict = PdfDictionary::new();
+    dict.insert("Type".to_string(), name("XObject"));
+    dict.insert("Subtype".to_string(), name("Image"));
+    dict.insert("Width".to_string(), PdfObject::Integer(1));
+    dict.insert("Height".to_string(), PdfObject::Integer(1));
+    dict.insert("ColorSpace".to_string(), name("DeviceRGB"));
+    dict.insert("BitsPerComponent".to_string(), PdfObject::Integer(8));
+    let data = [rgb.r(), rgb.g(), rgb.b()]
+        .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .to_vec();
+    PdfStream { dict, data }
+}
+
+/// Bounds in default user space of the unit square drawn with a matrix
+fn unit_square_bounds(ctm: &TransformMatrix) -> Rectangle {
+    let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+        .map(|(x, y)| ctm.transform_point(Point::new(x, y)));
+    bounding_box(&corners)
+}
+
+fn invert(m: &TransformMatrix) -> Option<TransformMatrix> {
+    let det = m.a * m.d - m.b * m.c;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    Some(TransformMatrix::new(
+        m.d / det,
+        -m.b / det,
+        -m.c / det,
+        m.a / det,
+        (m.c * m.f - m.d * m.e) / det,
+        (m.b * m.e - m.a * m.f) / det,
+    ))
+}
+
+fn rectangle(numbers: &[f64]) -> Option<Rectangle> {
+    match *numbers {
+        [x0, y0, x1, y1] => Some(bounding_box(&[Point::new(x0, y0), Point::new(x1, y1)])),
+        _ => None,
+    }
+}
+
+fn subtype(dict: &PdfDictionary) -> Option<&str> {
+    dict.get("Subtype")
+        .and_then(|s| s.as_name())
+        .map(|n| n.as_str())
+}
+
+fn name(value: &str) -> PdfObject {
+    PdfObject::Name(PdfName::new(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::{Annotation, AnnotationType, RedactAnnotation};
+    use crate::objects::{Object, ObjectId};
+    use crate::parser::resolve::from_content_object;
+    use crate::parser::test_helpers::create_pdf_with_objects;
+    use crate::text::Font;
+    use crate::writer::{PdfWriter, WriterConfig};
+    use crate::{ColorSpace, Document, Image, Page};
+
+    /// A page with text, two filled squares, a 10x10 red image at
+    /// (300, 500) scaled to 100x100, and, added in an update, a note over
+    /// the image and a redaction of the footer
+    fn sample_pdf() -> Vec<u8> {
+        let mut page = Page::a4();
+        page.text()
+            .set_font(Font::Helvetica, 12.0)
+            .at(100.0, 700.0)
+            .write("Witness: Jane Doe")
+            .unwrap()
+            .at(100.0, 100.0)
+            .write("Footer")
+            .unwrap();
+        page.graphics()
+            .set_fill_color(Color::rgb(0.0, 0.0, 1.0))
+            .rect(300.0, 400.0, 50.0, 50.0)
+            .fill()
+            .rect(450.0, 400.0, 50.0, 50.0)
+            .fill();
+        let pixels = [255u8, 0, 0].repeat(100);
+        page.add_image(
+            "Im1",
+            Image::from_raw_data(pixels, 10, 10, ColorSpace::DeviceRGB, 8),
+        );
+        page.draw_image("Im1", 300.0, 500.0, 100.0, 100.0).unwrap();
+        let mut doc = Document::new();
+        doc.set_title("Deposition of Jane Doe");
+        doc.set_subject("Deposition");
+        doc.add_page(page);
+        let base = doc.to_bytes().unwrap();
+
+        // The writer leaves out page annotations, so they are added as an
+        // incremental update
+        let rect = |x0, y0, x1, y1| Rectangle::new(Point::new(x0, y0), Point::new(x1, y1));
+        let mut note = Annotation::new(AnnotationType::Text, rect(310.0, 550.0, 330.0, 570.0));
+        note.contents = Some("Call Jane Doe".to_string());
+        let redact = RedactAnnotation::new(rect(95.0, 95.0, 140.0, 115.0))
+            .with_overlay_text("Exempt", false)
+            .with_interior_color(Color::gray(0.5))
+            .to_annotation();
+        let document = reopen(base.clone());
+        let page = document.get_page(0).unwrap();
+        let size = document.trailer().size().unwrap();
+        let mut page_dict = page.dict.clone();
+        let mut objects = vec![];
+        let mut annots = vec![];
+        for (number, annotation) in (size..).zip([note, redact]) {
+            let dict = from_content_object(&Object::Dictionary(annotation.to_dict()));
+            objects.push((ObjectId::new(number, 0), dict));
+            annots.push(PdfObject::Reference(number, 0));
+        }
+        page_dict.insert("Annots".to_string(), PdfObject::Array(PdfArray(annots)));
+        objects.push((
+            ObjectId::new(page.obj_ref.0, page.obj_ref.1),
+            PdfObject::Dictionary(page_dict),
+        ));
+        let mut output = Vec::new();
+        PdfWriter::with_config(&mut output, WriterConfig::incremental())
+            .write_incremental_parsed_objects(&base, objects)
+            .unwrap();
+        output
+    }
+
+    fn reopen(pdf: Vec<u8>) -> PdfDocument<Cursor<Vec<u8>>> {
+        PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap())
+    }
+
+    fn page_text(document: &PdfDocument<Cursor<Vec<u8>>>) -> String {
+        document.extract_text_from_page(0).unwrap().text
+    }
+
+    fn annotation_subtypes(document: &PdfDocument<Cursor<Vec<u8>>>) -> Vec<String> {
+        let page = document.get_page(0).unwrap();
+        match page.dict.get("Annots").map(|a| document.lookup(a)) {
+            Some(PdfObject::Array(array)) => array
+                .0
+                .iter()
+                .filter_map(|a| match document.lookup(a) {
+                    PdfObject::Dictionary(dict) => subtype(&dict).map(str::to_string),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_redact_searched_text() {
+        let mut redactor = Redactor::new(sample_pdf()).unwrap();
+        assert_eq!(redactor.mark_text("Jane Doe").unwrap(), 1);
+        let (pdf, report) = redactor.apply(&RedactOptions::default()).unwrap();
+        assert_eq!(report.areas, 1);
+        assert_eq!(report.glyphs_removed, 8);
+        assert_eq!(report.paths_removed, 0);
+        assert_eq!(report.images_redacted, 0);
+        // The title and the XMP metadata hold the text, the subject doesn't
+        assert_eq!(report.metadata_removed, 2);
+        // A complete rewrite, not an incremental update
+        assert_eq!(pdf.windows(5).filter(|w| w == b"%%EOF").count(), 1);
+
+        let document = reopen(pdf);
+        let text = page_text(&document);
+        assert!(text.contains("Witness:"), "{text:?}");
+        assert!(!text.contains("Jane") && !text.contains("Doe"), "{text:?}");
+        let metadata = document.metadata().unwrap();
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.subject.as_deref(), Some("Deposition"));
+        assert!(document.catalog().unwrap().get("Metadata").is_none());
+        // An overlay box covers the removed text
+        let content = String::from_utf8(
+            document
+                .get_page_content_streams(&document.get_page(0).unwrap())
+                .unwrap()
+                .concat(),
+        )
+        .unwrap();
+        assert!(
+            content.contains("0 g\n") && content.contains(" re\nf\n"),
+            "{content}"
+        );
+    }
+
+    #[test]
+    fn test_redact_area() {
+        let mut redactor = Redactor::new(sample_pdf()).unwrap();
+        // The first square and the left half of the image and the note
+        let area = Rectangle::new(Point::new(290.0, 390.0), Point::new(350.0, 610.0));
+        redactor.mark_area(0, area).unwrap().overlay_text = Some("(b)(6)".to_string());
+        assert!(redactor.mark_area(1, area).is_err());
+        let (pdf, report) = redactor.apply(&RedactOptions::default()).unwrap();
+        assert_eq!(report.paths_removed, 1);
+        assert_eq!(report.images_redacted, 1);
+        assert_eq!(report.annotations_removed, 1);
+        assert_eq!(report.glyphs_removed, 0);
+
+        let document = reopen(pdf);
+        assert_eq!(annotation_subtypes(&document), ["Redact"]);
+        let page = document.get_page(0).unwrap();
+        let resources = get_dict(&document, &page.dict, "Resources").unwrap();
+        let xobjects = get_dict(&document, &resources, "XObject").unwrap();
+        // The page draws a redacted copy; the original is no longer used
+        assert!(xobjects.get("Im1").is_none());
+        let PdfObject::Stream(image) = document.lookup(xobjects.get("Im1Redacted1").unwrap())
+        else {
+            panic!("image missing");
+        };
+        let pixels = image.decode(&ParseOptions::default()).unwrap();
+        assert_eq!(pixels.len(), 300);
+        for row in pixels.chunks(30) {
+            // Columns 0-4 are under the area and blanked to the fill color
+            assert_eq!(row[..15], [0; 15]);
+            assert_eq!(row[15..18], [255, 0, 0]);
+        }
+
+        let content =
+            String::from_utf8(document.get_page_content_streams(&page).unwrap().concat()).unwrap();
+        // The kept square sets its color between path and fill
+        assert_eq!(content.matches(" re\nf\n").count(), 1, "{content}");
+        assert!(
+            content.contains("450 400 50 50 re\n0 0 1 rg\nf\n"),
+            "{content}"
+        );
+        assert!(content.contains("/RedactLabel 12 Tf"), "{content}");
+        assert!(content.contains("(\\(b\\)\\(6\\)) Tj"), "{content}");
+    }
+
+    #[test]
+    fn test_redact_shared_image() {
+        // A 2x2 gray image drawn twice on the first page and once on the second
+        let image = "<< /Type /XObject /Subtype /Image /Width 2 /Height 2 \
+                     /ColorSpace /DeviceGray /BitsPerComponent 8 /Length 4 >>\nstream\ndddd\nendstream";
+        let content =
+            |data: &str| format!("<< /Length {} >>\nstream\n{data}\nendstream", data.len());
+        let pdf = create_pdf_with_objects(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 612 792] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 5 0 R /Resources 7 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 6 0 R /Resources 7 0 R >>",
+            &content("q 100 0 0 100 300 500 cm /Im1 Do Q\nq 100 0 0 100 100 100 cm /Im1 Do Q"),
+            &content("q 100 0 0 100 300 500 cm /Im1 Do Q"),
+            "<< /XObject << /Im1 8 0 R >> >>",
+            image,
+        ]);
+        let mut redactor = Redactor::new(pdf).unwrap();
+        let area = Rectangle::new(Point::new(290.0, 490.0), Point::new(410.0, 610.0));
+        redactor.mark_area(0, area).unwrap();
+        let (pdf, report) = redactor.apply(&RedactOptions::default()).unwrap();
+        assert_eq!(report.images_redacted, 1);
+
+        let document = reopen(pdf);
+        let pixels = |page: u32, name: &str| {
+            let page = document.get_page(page).unwrap();
+            let resources = get_dict(&document, &page.dict, "Resources").unwrap();
+            let xobjects = get_dict(&document, &resources, "XObject").unwrap();
+            let PdfObject::Stream(image) = document.lookup(xobjects.get(name).unwrap()) else {
+                panic!("{name} missing");
+            };
+            image.decode(&ParseOptions::default()).unwrap()
+        };
+        let content = |page: u32| {
+            let page = document.get_page(page).unwrap();
+            String::from_utf8(document.get_page_content_streams(&page).unwrap().concat()).unwrap()
+        };
+
+        // Only the placement under the area draws the blanked copy
+        let first = content(0);
+        assert_eq!(first.matches("/Im1Redacted1 Do").count(), 1, "{first}");
+        assert_eq!(first.matches("/Im1 Do").count(), 1, "{first}");
+        assert_eq!(pixels(0, "Im1Redacted1"), [0; 4]);
+        assert_eq!(pixels(0, "Im1"), b"dddd");
+        // The other page still draws the untouched image
+        assert!(content(1).contains("/Im1 Do"));
+        assert_eq!(pixels(1, "Im1"), b"dddd");
+    }
+
+    #[test]
+    fn test_apply_redact_annotations() {
+        let mut redactor = Redactor::new(sample_pdf()).unwrap();
+        assert_eq!(redactor.mark_annotations().unwrap(), 1);
+        let mark = &redactor.redactions()[0];
+        assert_eq!(mark.overlay_text.as_deref(), Some("Exempt"));
+        assert_eq!(mark.color, Some(Color::gray(0.5)));
+
+        let (pdf, report) = redactor.apply(&RedactOptions::default()).unwrap();
+        assert_eq!(report.glyphs_removed, 6);
+        assert_eq!(report.annotations_removed, 1);
+        assert_eq!(report.metadata_removed, 0);
+
+        let document = reopen(pdf);
+        assert_eq!(annotation_subtypes(&document), ["Text"]);
+        let text = page_text(&document);
+        assert!(
+            text.contains("Jane Doe") && !text.contains("Footer"),
+            "{text:?}"
+        );
+    }
+
+    #[test]
+    fn test_blank_pixels_of_packed_samples() {
+        let mut dict = PdfDictionary::new();
+        dict.insert("Width".to_string(), PdfObject::Integer(8));
+        dict.insert("Height".to_string(), PdfObject::Integer(2));
+        dict.insert("ImageMask".to_string(), PdfObject::Boolean(true));
+        let stream = PdfStream {
+            dict,
+            data: vec![0x00, 0x00],
+        };
+        let document = reopen(sample_pdf());
+        // The right half of the top row
+        let region = Rectangle::new(Point::new(0.5, 0.5), Point::new(1.0, 1.0));
+        let blanked = blank_pixels(&document, &stream, &[(region, Color::black())], None).unwrap();
+        assert_eq!(
+            blanked.decode(&ParseOptions::default()).unwrap(),
+            [0x0F, 0x00]
+        );
+
+        // Codecs that can't be edited
+        let mut jpx = stream.clone();
+        jpx.dict.insert("Filter".to_string(), name("JPXDecode"));
+        assert!(blank_pixels(&document, &jpx, &[(region, Color::black())], None).is_none());
+    }
+}
@@ -21,6 +21,8 @@ pub struct SplitOptions {
     pub preserve_metadata: bool,
     /// Whether to optimize output files
     pub optimize: bool,
+    /// Whether to keep the bookmarks that point into each part
+    pub preserve_bookmarks: bool,
 }
 
 impl Default for SplitOptions {
@@ -30,6 +32,7 @@ impl Default for SplitOptions {
             output_pattern: "page_{}.pdf".to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         }
     }
 }
@@ -164,6 +167,19 @@ impl PdfSplitter {
             doc.add_page(page);
         }
 
+        // Keep the bookmarks that point into the range
+        if self.options.preserve_bookmarks {
+            if let Ok(Some(outline)) = self.document.outline() {
+                let outline = outline.remap_pages(&|page| {
+                    let position = indices.iter().position(|&i| i == page as usize)?;
+                    Some(position as u32)
+                });
+                if !outline.items.is_empty() {
+                    doc.set_outline(outline);
+                }
+            }
+        }
+
         // Save the document
         doc.save(output_path)?;
 
@@ -424,6 +440,7 @@ mod tests {
             output_pattern: "chunk_{}.pdf".to_string(),
             preserve_metadata: true,
             optimize: true,
+            preserve_bookmarks: true,
         };
 
         assert!(matches!(options.mode, SplitMode::ChunkSize(10)));
@@ -628,6 +645,7 @@ mod tests {
             output_pattern: "chunk_{}.pdf".to_string(),
             preserve_metadata: false,
             optimize: true,
+            preserve_bookmarks: true,
         };
 
         match options.mode {
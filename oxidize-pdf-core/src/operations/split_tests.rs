@@ -82,6 +82,7 @@ mod tests {
             output_pattern: "chunk_{n}.pdf".to_string(),
             preserve_metadata: false,
             optimize: true,
+            preserve_bookmarks: true,
         };
 
         assert!(matches!(options.mode, SplitMode::ChunkSize(5)));
@@ -106,6 +107,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -137,6 +139,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -173,6 +176,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: false,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -201,6 +205,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -276,6 +281,7 @@ mod tests {
                 output_pattern: temp_dir.path().join(pattern).to_str().unwrap().to_string(),
                 preserve_metadata: true,
                 optimize: false,
+                preserve_bookmarks: true,
             };
 
             let result = split_pdf(&input_path, options);
@@ -305,6 +311,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -332,6 +339,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: false,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -418,6 +426,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -444,6 +453,7 @@ mod tests {
             output_pattern: "custom_pattern_{}.pdf".to_string(),
             preserve_metadata: false,
             optimize: true,
+            preserve_bookmarks: true,
         };
 
         assert!(matches!(options.mode, SplitMode::ChunkSize(5)));
@@ -532,6 +542,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: false,
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -658,6 +669,7 @@ mod tests {
                 .to_string(),
             preserve_metadata: true,
             optimize: true, // Enable optimization
+            preserve_bookmarks: true,
         };
 
         let result = split_pdf(&input_path, options);
@@ -687,4 +699,64 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 3);
     }
+
+    /// Bookmark titles with the page each one points to, depth first
+    fn bookmarks(items: &[crate::structure::OutlineItem]) -> Vec<(String, Option<u32>)> {
+        items
+            .iter()
+            .flat_map(|item| {
+                let page = match item.destination.as_ref().map(|dest| &dest.page) {
+                    Some(crate::structure::PageDestination::PageNumber(page)) => Some(*page),
+                    _ => None,
+                };
+                std::iter::once((item.title.clone(), page)).chain(bookmarks(&item.children))
+            })
+            .collect()
+    }
+
+    /// Adds a bookmark to each page of the document
+    fn add_page_bookmarks(doc: &mut Document, name: &str) {
+        use crate::structure::{Destination, OutlineItem, OutlineTree, PageDestination};
+
+        let mut outline = OutlineTree::new();
+        for page in 0..doc.page_count() {
+            outline.add_item(
+                OutlineItem::new(format!("{name} {}", page + 1))
+                    .with_destination(Destination::fit(PageDestination::PageNumber(page as u32))),
+            );
+        }
+        doc.set_outline(outline);
+    }
+
+    #[test]
+    fn test_split_preserves_bookmarks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut doc = create_test_pdf(4, "Bookmarked");
+        add_page_bookmarks(&mut doc, "Page");
+        let input_path = save_test_pdf(&mut doc, &temp_dir, "input.pdf");
+
+        let options = SplitOptions {
+            mode: SplitMode::SplitAt(vec![1]),
+            output_pattern: temp_dir
+                .path()
+                .join("part_{}.pdf")
+                .to_str()
+                .unwrap()
+                .to_string(),
+            ..Default::default()
+        };
+        let output_files = split_pdf(&input_path, options).unwrap();
+        assert_eq!(output_files.len(), 2);
+
+        let second = crate::parser::PdfReader::open_document(&output_files[1]).unwrap();
+        let outline = second.outline().unwrap().unwrap();
+        assert_eq!(
+            bookmarks(&outline.items),
+            [
+                ("Page 2".to_string(), Some(0)),
+                ("Page 3".to_string(), Some(1)),
+                ("Page 4".to_string(), Some(2)),
+            ]
+        );
+    }
 }
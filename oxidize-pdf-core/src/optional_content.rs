@@ -0,0 +1,847 @@
+//! Optional content (layers) as defined in ISO 32000-1 §8.11
+//!
+//! Optional content groups, shown as layers by viewers, let parts of a page
+//! be hidden or shown independently: dimensions, annotations and background
+//! of a drawing, or a watermark that only prints. Content is assigned to a
+//! layer by drawing it between [`GraphicsContext::begin_layer`] and
+//! [`GraphicsContext::end_layer`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use oxidize_pdf::optional_content::Layer;
+//! use oxidize_pdf::{Document, Page};
+//!
+//! # fn main() -> oxidize_pdf::Result<()> {
+//! let mut doc = Document::new();
+//! let background = doc.add_layer(Layer::new("Background").with_print(false));
+//! let dimensions = doc.add_layer(Layer::new("Dimensions"));
+//! doc.optional_content_mut().set_visible(&dimensions, false);
+//!
+//! let mut page = Page::a4();
+//! page.graphics()
+//!     .begin_layer(&background)
+//!     .rect(0.0, 0.0, 595.0, 842.0)
+//!     .fill()
+//!     .end_layer()
+//!     .begin_layer(&dimensions)
+//!     .move_to(100.0, 100.0)
+//!     .line_to(300.0, 100.0)
+//!     .stroke()
+//!     .end_layer();
+//! doc.add_page(page);
+//! let pdf = doc.to_bytes()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`GraphicsContext::begin_layer`]: crate::graphics::GraphicsContext::begin_layer
+//! [`GraphicsContext::end_layer`]: crate::graphics::GraphicsContext::end_layer
+
+use crate::error::Result;
+use crate::objects::{Dictionary, Object, ObjectId};
+use crate::parser::objects::PdfObject;
+use crate::parser::resolve::{get_dict, Resolver};
+use crate::parser::strings::text_string;
+use crate::parser::PdfDocument;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+/// Identifies a layer or membership of a document in content streams
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LayerId(String);
+
+impl LayerId {
+    /// Resource name used for the layer in page content
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What a layer is intended for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayerIntent {
+    /// Shown and hidden by the reader
+    #[default]
+    View,
+    /// Used while designing, e.g. construction lines
+    Design,
+    /// Both view and design
+    All,
+}
+
+/// An optional content group
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    /// Name shown in the viewer's layer panel
+    pub name: String,
+    pub intent: LayerIntent,
+    /// Whether the layer is printed, if the viewer applies usage settings
+    pub print: Option<bool>,
+    /// Whether the layer is shown on screen when the document is opened,
+    /// if the viewer applies usage settings
+    pub view: Option<bool>,
+    /// Whether the layer is kept when exporting to other formats
+    pub export: Option<bool>,
+}
+
+impl Layer {
+    /// Create a layer
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            intent: LayerIntent::View,
+            print: None,
+            view: None,
+            export: None,
+        }
+    }
+
+    /// Set the intent
+    pub fn with_intent(mut self, intent: LayerIntent) -> Self {
+        self.intent = intent;
+        self
+    }
+
+    /// Set whether the layer is printed
+    pub fn with_print(mut self, print: bool) -> Self {
+        self.print = Some(print);
+        self
+    }
+
+    /// Set whether the layer is shown on screen
+    pub fn with_view(mut self, view: bool) -> Self {
+        self.view = Some(view);
+        self
+    }
+
+    /// Set whether the layer is exported
+    pub fn with_export(mut self, export: bool) -> Self {
+        self.export = Some(export);
+        self
+    }
+
+    fn to_dict(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name("OCG".to_string()));
+        dict.set("Name", Object::String(self.name.clone()));
+        match self.intent {
+            LayerIntent::View => {}
+            LayerIntent::Design => dict.set("Intent", Object::Name("Design".to_string())),
+            LayerIntent::All => dict.set("Intent", Object::Name("All".to_string())),
+        }
+        let mut usage = Dictionary::new();
+        for (key, state, value) in [
+            ("Print", "PrintState", self.print),
+            ("View", "ViewState", self.view),
+            ("Export", "ExportState", self.export),
+        ] {
+            if let Some(on) = value {
+                let mut entry = Dictionary::new();
+                entry.set(state, Object::Name(state_name(on).to_string()));
+                usage.set(key, Object::Dictionary(entry));
+            }
+        }
+        if !usage.is_empty() {
+            dict.set("Usage", Object::Dictionary(usage));
+        }
+        dict
+    }
+}
+
+/// How the layers of a membership decide its visibility
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisibilityPolicy {
+    /// Visible when all layers are on
+    AllOn,
+    /// Visible when any layer is on
+    #[default]
+    AnyOn,
+    /// Visible when any layer is off
+    AnyOff,
+    /// Visible when all layers are off
+    AllOff,
+}
+
+impl VisibilityPolicy {
+    fn pdf_name(&self) -> &'static str {
+        match self {
+            VisibilityPolicy::AllOn => "AllOn",
+            VisibilityPolicy::AnyOn => "AnyOn",
+            VisibilityPolicy::AnyOff => "AnyOff",
+            VisibilityPolicy::AllOff => "AllOff",
+        }
+    }
+}
+
+/// Content visible depending on several layers (an optional content
+/// membership dictionary)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerMembership {
+    pub layers: Vec<LayerId>,
+    pub policy: VisibilityPolicy,
+}
+
+impl LayerMembership {
+    /// Membership in several layers
+    pub fn new(layers: Vec<LayerId>, policy: VisibilityPolicy) -> Self {
+        Self { layers, policy }
+    }
+
+    fn to_dict(&self, ids: &HashMap<String, ObjectId>) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name("OCMD".to_string()));
+        dict.set("OCGs", references(&self.layers, ids));
+        dict.set("P", Object::Name(self.policy.pdf_name().to_string()));
+        dict
+    }
+}
+
+/// Initial state of the layers not listed as on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BaseState {
+    #[default]
+    On,
+    Off,
+    /// Keep the state of the previous configuration (alternate
+    /// configurations only)
+    Unchanged,
+}
+
+/// A configuration of layer states, such as the one used when the document
+/// is opened
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LayerConfig {
+    /// Name shown for alternate configurations
+    pub name: Option<String>,
+    pub base_state: BaseState,
+    /// Layers turned on
+    pub on: Vec<LayerId>,
+    /// Layers turned off
+    pub off: Vec<LayerId>,
+    /// Layers the user can't toggle
+    pub locked: Vec<LayerId>,
+    /// Order of the layers in the viewer's panel, all layers in the order
+    /// they were added if empty
+    pub order: Vec<LayerId>,
+}
+
+impl LayerConfig {
+    /// An alternate configuration
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..Self::default()
+        }
+    }
+
+    fn to_dict(&self, content: &OptionalContent, ids: &HashMap<String, ObjectId>) -> Dictionary {
+        let mut dict = Dictionary::new();
+        if let Some(name) = &self.name {
+            dict.set("Name", Object::String(name.clone()));
+        }
+        match self.base_state {
+            BaseState::On => {}
+            BaseState::Off => dict.set("BaseState", Object::Name("OFF".to_string())),
+            BaseState::Unchanged => dict.set("BaseState", Object::Name("Unchanged".to_string())),
+        }
+        for (key, layers) in [
+            ("ON", &self.on),
+            ("OFF", &self.off),
+            ("Locked", &self.locked),
+        ] {
+            if !layers.is_empty() {
+                dict.set(key, references(layers, ids));
+            }
+        }
+        let order: Vec<LayerId> = if self.order.is_empty() {
+            content.layers.iter().map(|(id, _)| id.clone()).collect()
+        } else {
+            self.order.clone()
+        };
+        dict.set("Order", references(&order, ids));
+        dict
+    }
+}
+
+/// The layers of a document and their configurations
+#[derive(Debug, Clone, Default)]
+pub struct OptionalContent {
+    layers: Vec<(LayerId, Layer)>,
+    memberships: Vec<(LayerId, LayerMembership)>,
+    /// States used when the document is opened
+    pub default_config: LayerConfig,
+    /// Other configurations the viewer may offer
+    pub alternate_configs: Vec<LayerConfig>,
+}
+
+impl OptionalContent {
+    /// Add a layer, returning its id
+    pub fn add_layer(&mut self, layer: Layer) -> LayerId {
+        let id = LayerId(format!("OC{}", self.layers.len() + 1));
+        self.layers.push((id.clone(), layer));
+        id
+    }
+
+    /// Add a membership, returning an id to draw content with
+    pub fn add_membership(&mut self, membership: LayerMembership) -> LayerId {
+        let id = LayerId(format!("OCM{}", self.memberships.len() + 1));
+        self.memberships.push((id.clone(), membership));
+        id
+    }
+
+    /// The layers in the order they were added
+    pub fn layers(&self) -> impl Iterator<Item = (&LayerId, &Layer)> {
+        self.layers.iter().map(|(id, layer)| (id, layer))
+    }
+
+    /// Look up a layer
+    pub fn layer(&self, id: &LayerId) -> Option<&Layer> {
+        self.layers
+            .iter()
+            .find(|(layer_id, _)| layer_id == id)
+            .map(|(_, layer)| layer)
+    }
+
+    /// Set whether a layer is visible when the document is opened
+    pub fn set_visible(&mut self, id: &LayerId, visible: bool) {
+        let config = &mut self.default_config;
+        config.on.retain(|layer| layer != id);
+        config.off.retain(|layer| layer != id);
+        if visible {
+            config.on.push(id.clone());
+        } else {
+            config.off.push(id.clone());
+        }
+    }
+
+    /// Set whether a layer can be toggled by the user
+    pub fn set_locked(&mut self, id: &LayerId, locked: bool) {
+        let config = &mut self.default_config;
+        config.locked.retain(|layer| layer != id);
+        if locked {
+            config.locked.push(id.clone());
+        }
+    }
+
+    /// Whether there are no layers
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Resource names of the layers and memberships
+    pub(crate) fn resource_names(&self) -> impl Iterator<Item = &str> {
+        self.layers
+            .iter()
+            .map(|(id, _)| id.as_str())
+            .chain(self.memberships.iter().map(|(id, _)| id.as_str()))
+    }
+
+    /// Layer and membership dictionaries, by resource name
+    pub(crate) fn objects(&self, ids: &HashMap<String, ObjectId>) -> Vec<(String, Dictionary)> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|(id, layer)| (id.0.clone(), layer.to_dict()));
+        let memberships = self
+            .memberships
+            .iter()
+            .map(|(id, membership)| (id.0.clone(), membership.to_dict(ids)));
+        layers.chain(memberships).collect()
+    }
+
+    /// The `OCProperties` dictionary of the catalog
+    pub(crate) fn to_dict(&self, ids: &HashMap<String, ObjectId>) -> Dictionary {
+        let all: Vec<LayerId> = self.layers.iter().map(|(id, _)| id.clone()).collect();
+        let mut dict = Dictionary::new();
+        dict.set("OCGs", references(&all, ids));
+
+        let mut default = self.default_config.to_dict(self, ids);
+        // Let viewers apply the print, view and export settings
+        let mut auto_state = Vec::new();
+        for (event, category, with_usage) in [
+            ("View", "View", self.layers_with(|l| l.view.is_some())),
+            ("Print", "Print", self.layers_with(|l| l.print.is_some())),
+            ("Export", "Export", self.layers_with(|l| l.export.is_some())),
+        ] {
+            if with_usage.is_empty() {
+                continue;
+            }
+            let mut usage = Dictionary::new();
+            usage.set("Event", Object::Name(event.to_string()));
+            usage.set("OCGs", references(&with_usage, ids));
+            usage.set(
+                "Category",
+                Object::Array(vec![Object::Name(category.to_string())]),
+            );
+            auto_state.push(Object::Dictionary(usage));
+        }
+        if !auto_state.is_empty() {
+            default.set("AS", Object::Array(auto_state));
+        }
+        dict.set("D", Object::Dictionary(default));
+
+        if !self.alternate_configs.is_empty() {
+            let configs = self
+                .alternate_configs
+                .iter()
+                .map(|config| Object::Dictionary(config.to_dict(self, ids)))
+                .collect();
+            dict.set("Configs", Object::Array(configs));
+        }
+        dict
+    }
+
+    fn layers_with(&self, predicate: impl Fn(&Layer) -> bool) -> Vec<LayerId> {
+        self.layers
+            .iter()
+            .filter(|(_, layer)| predicate(layer))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+}
+
+/// A layer of a parsed document
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerInfo {
+    /// Name shown in the viewer's layer panel
+    pub name: String,
+    /// Object number and generation of the group
+    pub id: (u32, u16),
+    pub intent: LayerIntent,
+    /// Whether the layer is on when the document is opened
+    pub visible: bool,
+    /// Whether the user can toggle the layer
+    pub locked: bool,
+    pub print: Option<bool>,
+    pub view: Option<bool>,
+    pub export: Option<bool>,
+}
+
+/// The layers of a document, in the order of its `OCGs` array
+pub(crate) fn read_layers<R: Read + Seek>(document: &PdfDocument<R>) -> Result<Vec<LayerInfo>> {
+    let resolver: &dyn Resolver = document;
+    let catalog = document.catalog()?;
+    let Some(properties) = get_dict(resolver, &catalog, "OCProperties") else {
+        return Ok(Vec::new());
+    };
+    let config = get_dict(resolver, &properties, "D").unwrap_or_default();
+    let base_off = matches!(
+        config.get("BaseState"),
+        Some(PdfObject::Name(name)) if name.as_str() == "OFF"
+    );
+    let listed = |key: &str| -> HashSet<(u32, u16)> {
+        match config.get(key).map(|list| resolver.lookup(list)) {
+            Some(PdfObject::Array(array)) => array.0.iter().filter_map(reference).collect(),
+            _ => HashSet::new(),
+        }
+    };
+    let (on, off, locked) = (listed("ON"), listed("OFF"), listed("Locked"));
+
+    let groups = match properties.get("OCGs").map(|groups| resolver.lookup(groups)) {
+        Some(PdfObject::Array(array)) => array.0,
+        _ => Vec::new(),
+    };
+    let mut layers = Vec::new();
+    for group in &groups {
+        let Some(id) = reference(group) else {
+            continue;
+        };
+        let PdfObject::Dictionary(dict) = resolver.lookup(group) else {
+            continue;
+        };
+        let name = match dict.get("Name").map(|name| resolver.lookup(name)) {
+            Some(PdfObject::String(name)) => text_string(name.as_bytes()),
+            _ => String::new(),
+        };
+        let intents: Vec<String> = match dict.get("Intent").map(|intent| resolver.lookup(intent)) {
+            Some(PdfObject::Name(name)) => vec![name.as_str().to_string()],
+            Some(PdfObject::Array(array)) => array
+                .0
+                .iter()
+                .filter_map(|i| i.as_name().map(|n| n.as_str().to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let intent = if intents.iter().any(|i| i == "All")
+            || (intents.iter().any(|i| i == "View") && intents.iter().any(|i| i == "Design"))
+        {
+            LayerIntent::All
+        } else if intents.iter().any(|i| i == "Design") {
+            LayerIntent::Design
+        } else {
+            LayerIntent::View
+        };
+        let usage = get_dict(resolver, &dict, "Usage").unwrap_or_default();
+        let usage_state = |key: &str, state: &str| {
+            let entry = get_dict(resolver, &usage, key)?;
+            match entry.get(state) {
+                Some(PdfObject::Name(name)) => Some(name.as_str() == "ON"),
+                _ => None,
+            }
+        };
+        layers.push(LayerInfo {
+            name,
+            id,
+            intent,
+            visible: if base_off {
+                on.contains(&id)
+            } else {
+                !off.contains(&id)
+            },
+            locked: locked.contains(&id),
+            print: usage_state("Print", "PrintState"),
+            view: usage_state("View", "ViewState"),
+            export: usage_state("Export", "ExportState"),
+        });
+    }
+    Ok(layers)
+}
+
+fn reference(object: &PdfObject) -> Option<(u32, u16)> {
+    match object {
+        &PdfObject::Reference(number, generation) => Some((number, generation)),
+        _ => None,
+    }
+}
+
+fn references(layers: &[LayerId], ids: &HashMap<String, ObjectId>) -> Object {
+    Object::Array(
+        layers
+            .iter()
+            .filter_map(|layer| ids.get(&layer.0))
+            .map(|id| Object::Reference(*id))
+            .collect(),
+    )
+}
+
+fn state_name(on: bool) -> &'static str {
+    if on {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::test_helpers::create_pdf_with_objects;
+    use crate::parser::PdfReader;
+    use crate::{Document, Page};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_layers_round_trip() {
+        let mut doc = Document::new();
+        let background = doc.add_layer(Layer::new("Background").with_print(false));
+        let dimensions = doc.add_layer(Layer::new("Dimensions"));
+        let notes = doc.add_layer(Layer::new("Notes").with_intent(LayerIntent::Design));
+        doc.optional_content_mut().set_visible(&dimensions, false);
+        doc.optional_content_mut().set_locked(&background, true);
+
+        let mut page = Page::a4();
+        page.graphics()
+            .begin_layer(&background)
+            .rect(0.0, 0.0, 595.0, 842.0)
+            .fill()
+            .end_layer()
+            .begin_layer(&dimensions)
+            .move_to(100.0, 100.0)
+            .line_to(300.0, 100.0)
+            .stroke()
+            .end_layer()
+            .end_layer();
+        doc.add_page(page);
+        let pdf = doc.to_bytes().unwrap();
+
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap());
+        let page = document.get_page(0).unwrap();
+        let content = document.get_page_content_streams(&page).unwrap().concat();
+        let content = String::from_utf8_lossy(&content);
+        assert!(content.contains("/OC /OC1 BDC"));
+        assert!(content.contains("/OC /OC2 BDC"));
+        assert_eq!(content.matches("EMC").count(), 2);
+
+        let layers = document.layers().unwrap();
+        let summary: Vec<_> = layers
+            .iter()
+            .map(|l| (l.name.as_str(), l.visible, l.locked, l.print, l.intent))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Background", true, true, Some(false), LayerIntent::View),
+                ("Dimensions", false, false, None, LayerIntent::View),
+                ("Notes", true, false, None, LayerIntent::Design),
+            ]
+        );
+        assert!(doc.optional_content().layer(&notes).is_some());
+    }
+
+    #[test]
+    fn test_membership_dict() {
+        let mut content = OptionalContent::default();
+        let a = content.add_layer(Layer::new("A"));
+        let b = content.add_layer(Layer::new("B"));
+        let both = content.add_membership(LayerMembership::new(
+            vec![a.clone(), b.clone()],
+            VisibilityPolicy::AllOn,
+        ));
+        assert_eq!(both.as_str(), "OCM1");
+
+        let ids: HashMap<String, ObjectId> = content
+            .resource_names()
+            .zip(1..)
+            .map(|(name, number)| (name.to_string(), ObjectId::new(number, 0)))
+            .collect();
+        let objects = content.objects(&ids);
+        let (_, membership) = objects.iter().find(|(name, _)| name == "OCM1").unwrap();
+        assert_eq!(membership.get("Type"), Some(&Object::Name("OCMD".into())));
+        assert_eq!(membership.get("P"), Some(&Object::Name("AllOn".into())));
+        assert_eq!(
+            membership.get("OCGs"),
+            Some(&Object::Array(vec![
+                Object::Reference(ids["OC1"]),
+                Object::Reference(ids["OC2"]),
+            ]))
+        );
+    }
+
+    fn parse(pdf: Vec<u8>) -> PdfDocument<Cursor<Vec<u8>>> {
+        PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap())
+    }
+
+    /// Group names of the `/Properties` resources of a page, by resource name
+    fn page_properties(
+        document: &PdfDocument<Cursor<Vec<u8>>>,
+        index: u32,
+    ) -> Vec<(String, String)> {
+        let resolver: &dyn Resolver = document;
+        let page = document.get_page(index).unwrap();
+        let resources = get_dict(resolver, &page.dict, "Resources").unwrap();
+        let Some(properties) = get_dict(resolver, &resources, "Properties") else {
+            return Vec::new();
+        };
+        let mut entries: Vec<_> = properties
+            .0
+            .iter()
+            .map(|(name, group)| {
+                let PdfObject::Dictionary(group) = resolver.lookup(group) else {
+                    panic!("/{} is not a dictionary", name.as_str());
+                };
+                let kind = group.get("Type").and_then(|t| t.as_name()).unwrap();
+                (name.as_str().to_string(), kind.as_str().to_string())
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn test_layer_resources_on_written_pages() {
+        let mut doc = Document::new();
+        let background = doc.add_layer(Layer::new("Background"));
+        let dimensions = doc.add_layer(Layer::new("Dimensions"));
+        let both = doc.add_layer_membership(LayerMembership::new(
+            vec![background.clone(), dimensions.clone()],
+            VisibilityPolicy::AnyOn,
+        ));
+
+        let mut first = Page::a4();
+        first
+            .graphics()
+            .begin_layer(&background)
+            .rect(0.0, 0.0, 595.0, 842.0)
+            .fill()
+            .begin_layer(&both)
+            .rect(10.0, 10.0, 20.0, 20.0)
+            .stroke()
+            .end_layer()
+            .end_layer();
+        doc.add_page(first);
+        let mut second = Page::a4();
+        second
+            .graphics()
+            .begin_layer(&dimensions)
+            .move_to(100.0, 100.0)
+            .line_to(300.0, 100.0)
+            .stroke()
+            .end_layer();
+        doc.add_page(second);
+        doc.add_page(Page::a4());
+
+        let document = parse(doc.to_bytes().unwrap());
+        assert_eq!(
+            page_properties(&document, 0),
+            vec![
+                ("OC1".to_string(), "OCG".to_string()),
+                ("OCM1".to_string(), "OCMD".to_string()),
+            ]
+        );
+        assert_eq!(
+            page_properties(&document, 1),
+            vec![("OC2".to_string(), "OCG".to_string())]
+        );
+        assert!(page_properties(&document, 2).is_empty());
+
+        // The resources point at the groups of the catalog
+        let resolver: &dyn Resolver = &document;
+        let page = document.get_page(1).unwrap();
+        let resources = get_dict(resolver, &page.dict, "Resources").unwrap();
+        let properties = get_dict(resolver, &resources, "Properties").unwrap();
+        let group = properties.get("OC2").and_then(reference).unwrap();
+        let layers = document.layers().unwrap();
+        assert_eq!(layers[1].name, "Dimensions");
+        assert_eq!(layers[1].id, group);
+
+        let content = document.get_page_content_streams(&page).unwrap().concat();
+        let content = String::from_utf8_lossy(&content);
+        assert!(content.contains("/OC /OC2 BDC"));
+    }
+
+    #[test]
+    fn test_usage_and_states_round_trip() {
+        let mut doc = Document::new();
+        let watermark = doc.add_layer(
+            Layer::new("Watermark")
+                .with_print(true)
+                .with_view(false)
+                .with_export(true),
+        );
+        let grid = doc.add_layer(Layer::new("Grid").with_print(false));
+        let notes = doc.add_layer(Layer::new("Notes").with_intent(LayerIntent::All));
+        let content = doc.optional_content_mut();
+        content.default_config.base_state = BaseState::Off;
+        content.set_visible(&watermark, true);
+        content.set_locked(&watermark, true);
+        content.set_locked(&grid, true);
+        content.set_locked(&grid, false);
+        content.set_locked(&notes, true);
+        doc.add_page(Page::a4());
+
+        let document = parse(doc.to_bytes().unwrap());
+        let summary: Vec<_> = document
+            .layers()
+            .unwrap()
+            .into_iter()
+            .map(|l| {
+                (
+                    l.name, l.intent, l.visible, l.locked, l.print, l.view, l.export,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "Watermark".to_string(),
+                    LayerIntent::View,
+                    true,
+                    true,
+                    Some(true),
+                    Some(false),
+                    Some(true)
+                ),
+                (
+                    "Grid".to_string(),
+                    LayerIntent::View,
+                    false,
+                    false,
+                    Some(false),
+                    None,
+                    None
+                ),
+                (
+                    "Notes".to_string(),
+                    LayerIntent::All,
+                    false,
+                    true,
+                    None,
+                    None,
+                    None
+                ),
+            ]
+        );
+
+        // Viewers apply the usage through the auto state array
+        let resolver: &dyn Resolver = &document;
+        let catalog = document.catalog().unwrap();
+        let properties = get_dict(resolver, &catalog, "OCProperties").unwrap();
+        let config = get_dict(resolver, &properties, "D").unwrap();
+        let Some(PdfObject::Array(auto_state)) = config.get("AS") else {
+            panic!("no /AS array");
+        };
+        let events: Vec<_> = auto_state
+            .0
+            .iter()
+            .filter_map(|usage| usage.as_dict()?.get("Event")?.as_name())
+            .map(|event| event.as_str())
+            .collect();
+        assert_eq!(events, vec!["View", "Print", "Export"]);
+    }
+
+    #[test]
+    fn test_layers_of_parsed_file_with_nested_order() {
+        let pdf = create_pdf_with_objects(&[
+            "<< /Type /Catalog /Pages 2 0 R /OCProperties << /OCGs [4 0 R 5 0 R 6 0 R 7 0 R] \
+             /D << /Order [4 0 R [(Annotations) 5 0 R [6 0 R]] 7 0 R] /OFF [6 0 R] \
+             /Locked [7 0 R] >> >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+            "<< /Type /OCG /Name (Base) >>",
+            "<< /Type /OCG /Name <FEFF004E006F0074006500730020003100E9> /Intent [/View /Design] >>",
+            "<< /Type /OCG /Name (Comments) /Intent /Design \
+             /Usage << /View << /ViewState /OFF >> >> >>",
+            "<< /Type /OCG /Name (Frame) /Usage << /Print << /PrintState /ON >> >> >>",
+        ]);
+        let layers = parse(pdf).layers().unwrap();
+        let summary: Vec<_> = layers
+            .iter()
+            .map(|l| {
+                (
+                    l.name.as_str(),
+                    l.id,
+                    l.intent,
+                    l.visible,
+                    l.locked,
+                    l.print,
+                    l.view,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Base", (4, 0), LayerIntent::View, true, false, None, None),
+                (
+                    "Notes 1é",
+                    (5, 0),
+                    LayerIntent::All,
+                    true,
+                    false,
+                    None,
+                    None
+                ),
+                (
+                    "Comments",
+                    (6, 0),
+                    LayerIntent::Design,
+                    false,
+                    false,
+                    None,
+                    Some(false)
+                ),
+                (
+                    "Frame",
+                    (7, 0),
+                    LayerIntent::View,
+                    true,
+                    true,
+                    Some(true),
+                    None
+                ),
+            ]
+        );
+    }
+}
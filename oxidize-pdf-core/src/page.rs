@@ -4,8 +4,13 @@ use crate::fonts::type0_parsing::{detect_type0_font, resolve_type0_hierarchy};
 use crate::forms::Widget;
 use crate::graphics::{GraphicsContext, Image};
 use crate::objects::{Array, Dictionary, Object, ObjectReference};
-use crate::text::{Font, HeaderFooter, Table, TextContext, TextFlowContext};
+use crate::structure::page_tags::{self, PageTags};
+use crate::structure::{StandardStructureType, StructureElement};
+use crate::text::{
+    Font, HeaderFooter, ShapingFonts, Table, TextContext, TextFlowContext, UnshapedText,
+};
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
 /// Page margins in points (1/72 inch).
 #[derive(Clone, Debug)]
@@ -70,13 +75,20 @@ pub struct Page {
     annotations: Vec<Annotation>,
     coordinate_system: crate::coordinate_system::CoordinateSystem,
     rotation: i32, // Page rotation in degrees (0, 90, 180, 270)
-    /// Next MCID (Marked Content ID) for tagged PDF
-    next_mcid: u32,
+    /// Structure of tagged content and the next MCID (Marked Content ID)
+    tags: PageTags,
     /// Currently open marked content tags (for nesting validation)
     marked_content_stack: Vec<String>,
     /// Preserved resources from original PDF (for overlay operations)
     /// Contains fonts, XObjects, ColorSpaces, etc. from parsed pages
     preserved_resources: Option<crate::pdf_objects::Dictionary>,
+    /// Original content and entries of a page opened with `Document::open`
+    imported: Option<crate::document::ImportedPage>,
+    /// Fonts of the document the page was added to, shared with its text
+    /// and graphics contexts and the text flows made for it
+    shaping_fonts: ShapingFonts,
+    /// Lines of text flows in custom fonts the page did not have yet
+    unshaped: Vec<UnshapedText>,
 }
 
 impl Page {
@@ -97,9 +109,12 @@ impl Page {
             annotations: Vec::new(),
             coordinate_system: crate::coordinate_system::CoordinateSystem::PdfStandard,
             rotation: 0, // Default to no rotation
-            next_mcid: 0,
+            tags: PageTags::default(),
             marked_content_stack: Vec::new(),
             preserved_resources: None,
+            imported: None,
+            shaping_fonts: ShapingFonts::new(),
+            unshaped: Vec::new(),
         }
     }
 
@@ -350,6 +365,21 @@ impl Page {
         &mut self.text_context
     }
 
+    /// Shapes text in custom fonts with these fonts.
+    ///
+    /// Pages are given the fonts of the document they are added to, and
+    /// text written in them before then is shaped at that point. Text flows
+    /// break lines with the widths of the text as shaped, though, so give
+    /// a page [`Document::shaping_fonts`](crate::Document::shaping_fonts)
+    /// before writing flows in custom fonts on it.
+    pub fn set_shaping_fonts(&mut self, fonts: &ShapingFonts) -> &mut Self {
+        self.shaping_fonts = fonts.clone();
+        self.text_context.set_shaping_fonts(fonts);
+        self.graphics_context.set_shaping_fonts(fonts);
+        fonts.shape_unshaped(&mut self.unshaped, &mut self.content);
+        self
+    }
+
     pub fn set_margins(&mut self, left: f64, right: f64, top: f64, bottom: f64) {
         self.margins = Margins {
             left,
@@ -593,6 +623,15 @@ impl Page {
         self.preserved_resources.as_ref()
     }
 
+    /// Gets the original content and entries of an opened page (if any)
+    pub(crate) fn imported(&self) -> Option<&crate::document::ImportedPage> {
+        self.imported.as_ref()
+    }
+
+    pub(crate) fn set_imported(&mut self, imported: crate::document::ImportedPage) {
+        self.imported = Some(imported);
+    }
+
     /// Gets the current page rotation in degrees.
     pub fn get_rotation(&self) -> i32 {
         self.rotation
@@ -617,12 +656,51 @@ impl Page {
     }
 
     pub fn text_flow(&self) -> TextFlowContext {
-        TextFlowContext::new(self.width, self.height, self.margins.clone())
+        let mut text_flow = TextFlowContext::new(self.width, self.height, self.margins.clone());
+        text_flow.set_shaping_fonts(&self.shaping_fonts);
+        text_flow
     }
 
+    /// Adds the content of a text flow to the page
+    ///
+    /// On a tagged page every paragraph and heading of the flow becomes a
+    /// structure element.
     pub fn add_text_flow(&mut self, text_flow: &TextFlowContext) {
-        let operations = text_flow.generate_operations();
-        self.content.extend_from_slice(&operations);
+        let end = text_flow.operations().len();
+        if !self.tags.is_enabled() {
+            self.add_text_flow_operations(text_flow, 0..end);
+            return;
+        }
+        let mut written = 0;
+        for (structure_type, range) in text_flow.blocks() {
+            self.add_text_flow_operations(text_flow, written..range.start);
+            let operator = self
+                .tags
+                .begin_content(StructureElement::new(structure_type.clone()));
+            self.content.extend_from_slice(operator.as_bytes());
+            self.add_text_flow_operations(text_flow, range.clone());
+            self.content
+                .extend_from_slice(page_tags::END_MARKED_CONTENT.as_bytes());
+            written = range.end;
+        }
+        self.add_text_flow_operations(text_flow, written..end);
+    }
+
+    /// Append a range of the operations of a text flow, with the lines in
+    /// it still to be shaped
+    fn add_text_flow_operations(&mut self, text_flow: &TextFlowContext, range: Range<usize>) {
+        let offset = self.content.len();
+        self.content
+            .extend_from_slice(&text_flow.operations().as_bytes()[range.clone()]);
+        for text in text_flow.unshaped() {
+            if range.start <= text.range.start && text.range.end <= range.end {
+                let start = offset + text.range.start - range.start;
+                self.unshaped.push(UnshapedText {
+                    range: start..start + text.shown.len(),
+                    ..text.clone()
+                });
+            }
+        }
     }
 
     pub fn add_image(&mut self, name: impl Into<String>, image: Image) {
@@ -636,10 +714,43 @@ impl Page {
         y: f64,
         width: f64,
         height: f64,
+    ) -> Result<()> {
+        self.draw_figure(name, x, y, width, height, None)
+    }
+
+    /// Draws an image with a text alternative for assistive technology
+    ///
+    /// On a tagged page the image becomes a `Figure` structure element
+    /// with the alt text; otherwise this is the same as [`Page::draw_image`].
+    pub fn draw_image_with_alt_text(
+        &mut self,
+        name: &str,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        alt_text: &str,
+    ) -> Result<()> {
+        self.draw_figure(name, x, y, width, height, Some(alt_text))
+    }
+
+    fn draw_figure(
+        &mut self,
+        name: &str,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        alt_text: Option<&str>,
     ) -> Result<()> {
         if self.images.contains_key(name) {
+            let mut figure = StructureElement::new(StandardStructureType::Figure);
+            figure.attributes.alt = alt_text.map(str::to_string);
+            figure.attributes.bbox = Some([x, y, x + width, y + height]);
+            self.begin_tagged_content(ContentTarget::Graphics, figure);
             // Draw the image using the graphics context
             self.graphics_context.draw_image(name, x, y, width, height);
+            self.end_tagged_content(ContentTarget::Graphics);
             Ok(())
         } else {
             Err(crate::PdfError::InvalidReference(format!(
@@ -708,6 +819,11 @@ impl Page {
         }
     }
 
+    /// Resource names of the layers drawn in on this page
+    pub(crate) fn layers(&self) -> &[String] {
+        self.graphics_context.layers()
+    }
+
     /// Adds an annotation to this page
     pub fn add_annotation(&mut self, annotation: Annotation) {
         self.annotations.push(annotation);
@@ -813,6 +929,7 @@ impl Page {
     /// This is used internally when processing headers and footers.
     pub(crate) fn set_content(&mut self, content: Vec<u8>) {
         self.content = content;
+        self.unshaped.clear();
     }
 
     #[allow(dead_code)]
@@ -838,7 +955,7 @@ impl Page {
             if let (Some(page_num), Some(total)) = (page_number, total_pages) {
                 let header_content =
                     self.render_header_footer(header, page_num, total, custom_values)?;
-                final_content.extend_from_slice(&header_content);
+                self.extend_with_pagination(&mut final_content, &header_content);
             }
         }
 
@@ -885,13 +1002,25 @@ impl Page {
             if let (Some(page_num), Some(total)) = (page_number, total_pages) {
                 let footer_content =
                     self.render_header_footer(footer, page_num, total, custom_values)?;
-                final_content.extend_from_slice(&footer_content);
+                self.extend_with_pagination(&mut final_content, &footer_content);
             }
         }
 
         Ok(final_content)
     }
 
+    /// Appends header or footer content, as a pagination artifact on a
+    /// tagged page
+    fn extend_with_pagination(&self, content: &mut Vec<u8>, header_footer: &[u8]) {
+        if self.tags.is_enabled() {
+            content.extend_from_slice(page_tags::BEGIN_PAGINATION_ARTIFACT.as_bytes());
+            content.extend_from_slice(header_footer);
+            content.extend_from_slice(page_tags::END_MARKED_CONTENT.as_bytes());
+        } else {
+            content.extend_from_slice(header_footer);
+        }
+    }
+
     /// Renders a header or footer with the given page information.
     fn render_header_footer(
         &self,
@@ -1047,8 +1176,7 @@ impl Page {
     /// # Ok::<(), oxidize_pdf::PdfError>(())
     /// ```
     pub fn begin_marked_content(&mut self, tag: &str) -> Result<u32> {
-        let mcid = self.next_mcid;
-        self.next_mcid += 1;
+        let mcid = self.tags.allocate_mcid();
 
         // Add BDC operator with MCID property to text context
         // Format: /Tag <</MCID mcid>> BDC
@@ -1087,13 +1215,114 @@ impl Page {
     ///
     /// This is useful for pre-allocating structure elements before adding content.
     pub fn next_mcid(&self) -> u32 {
-        self.next_mcid
+        self.tags.next_mcid()
     }
 
     /// Returns the current depth of nested marked content
     pub fn marked_content_depth(&self) -> usize {
         self.marked_content_stack.len()
     }
+
+    /// Tags the content added from now on, for accessible (PDF/UA) output
+    ///
+    /// On a tagged page, the blocks of text flows become paragraphs and
+    /// headings, advanced tables get rows with header (`TH`) and data
+    /// (`TD`) cells, lists get items with labels and bodies, and images
+    /// become figures, with alt text when drawn with
+    /// [`Page::draw_image_with_alt_text`]. Cell borders and backgrounds,
+    /// list separators, headers and footers are marked as artifacts. The
+    /// writer adds the elements of every tagged page to the document's
+    /// structure tree.
+    ///
+    /// Content drawn directly through [`Page::graphics`] or [`Page::text`]
+    /// stays untagged unless wrapped with [`Page::begin_marked_content`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use oxidize_pdf::Page;
+    ///
+    /// let mut page = Page::a4();
+    /// page.set_tagged(true);
+    ///
+    /// let mut flow = page.text_flow();
+    /// flow.write_heading(1, "Annual report")?;
+    /// flow.write_paragraph("Revenue grew in every region.")?;
+    /// page.add_text_flow(&flow);
+    /// # Ok::<(), oxidize_pdf::PdfError>(())
+    /// ```
+    pub fn set_tagged(&mut self, tagged: bool) {
+        self.tags.set_enabled(tagged);
+    }
+
+    /// Returns whether content added to the page is tagged
+    pub fn is_tagged(&self) -> bool {
+        self.tags.is_enabled()
+    }
+
+    pub(crate) fn tags(&self) -> &PageTags {
+        &self.tags
+    }
+
+    /// Graphics context and, on a tagged page, the recorded structure
+    pub(crate) fn graphics_and_tags(&mut self) -> (&mut GraphicsContext, Option<&mut PageTags>) {
+        let tags = self.tags.is_enabled().then_some(&mut self.tags);
+        (&mut self.graphics_context, tags)
+    }
+
+    /// Opens a grouping structure element on a tagged page
+    pub(crate) fn begin_tag_group(&mut self, element: StructureElement) {
+        if self.tags.is_enabled() {
+            self.tags.begin_group(element);
+        }
+    }
+
+    /// Closes the innermost grouping structure element on a tagged page
+    pub(crate) fn end_tag_group(&mut self) {
+        if self.tags.is_enabled() {
+            self.tags.end_group();
+        }
+    }
+
+    /// Begins the content of a structure element on a tagged page
+    pub(crate) fn begin_tagged_content(
+        &mut self,
+        target: ContentTarget,
+        element: StructureElement,
+    ) {
+        if self.tags.is_enabled() {
+            let operator = self.tags.begin_content(element);
+            self.append_marked_content_operator(target, &operator);
+        }
+    }
+
+    /// Begins decorative content on a tagged page
+    pub(crate) fn begin_artifact(&mut self, target: ContentTarget) {
+        if self.tags.is_enabled() {
+            self.append_marked_content_operator(target, page_tags::BEGIN_ARTIFACT);
+        }
+    }
+
+    /// Ends tagged or decorative content on a tagged page
+    pub(crate) fn end_tagged_content(&mut self, target: ContentTarget) {
+        if self.tags.is_enabled() {
+            self.append_marked_content_operator(target, page_tags::END_MARKED_CONTENT);
+        }
+    }
+
+    fn append_marked_content_operator(&mut self, target: ContentTarget, operator: &str) {
+        match target {
+            ContentTarget::Graphics => self.graphics_context.add_command(operator.trim_end()),
+            ContentTarget::Text => self.text_context.append_raw_operation(operator),
+        }
+    }
+}
+
+/// Content stream of a page that drawing operations end up in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentTarget {
+    Graphics,
+    Text,
 }
 
 #[cfg(test)]
@@ -7,4 +7,5 @@ mod page_label;
 mod page_label_tree;
 
 pub use page_label::{PageLabel, PageLabelRange, PageLabelStyle};
+pub(crate) use page_label_tree::read_page_labels;
 pub use page_label_tree::{PageLabelBuilder, PageLabelTree};
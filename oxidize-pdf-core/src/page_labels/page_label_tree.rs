@@ -1,8 +1,14 @@
 //! Page label tree structure for managing page numbering
 
 use crate::objects::{Array, Dictionary, Object};
-use crate::page_labels::PageLabel;
+use crate::page_labels::{PageLabel, PageLabelStyle};
+use crate::parser::objects::PdfObject;
+use crate::parser::resolve::{get, get_dict, get_name, Resolver};
+use crate::parser::strings::text_string;
+use crate::parser::PdfDocument;
+use crate::structure::read_number_tree;
 use std::collections::BTreeMap;
+use std::io::{Read, Seek};
 
 /// Page label tree - manages custom page numbering for a document
 #[derive(Debug, Clone)]
@@ -195,12 +201,69 @@ impl PageLabelBuilder {
     }
 }
 
-// Import PageLabelStyle from the other module
-use crate::page_labels::PageLabelStyle;
+/// The page labels of a parsed document (ISO 32000-1 §12.4.2); `None` if
+/// it has none
+pub(crate) fn read_page_labels<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> crate::error::Result<Option<PageLabelTree>> {
+    let resolver: &dyn Resolver = document;
+    let catalog = document.catalog()?;
+    let Some(tree) = get_dict(resolver, &catalog, "PageLabels") else {
+        return Ok(None);
+    };
+
+    let mut labels = PageLabelTree::new();
+    for (start, label) in read_number_tree(resolver, &tree) {
+        let (Ok(start), PdfObject::Dictionary(label)) =
+            (u32::try_from(start), resolver.lookup(&label))
+        else {
+            continue;
+        };
+        let style = match get_name(resolver, &label, "S").as_deref() {
+            Some("D") => PageLabelStyle::DecimalArabic,
+            Some("R") => PageLabelStyle::UppercaseRoman,
+            Some("r") => PageLabelStyle::LowercaseRoman,
+            Some("A") => PageLabelStyle::UppercaseLetters,
+            Some("a") => PageLabelStyle::LowercaseLetters,
+            _ => PageLabelStyle::None,
+        };
+        let mut page_label = PageLabel::new(style);
+        if let Some(PdfObject::String(prefix)) = get(resolver, &label, "P") {
+            page_label.prefix = Some(text_string(prefix.as_bytes()));
+        }
+        if let Some(PdfObject::Integer(first)) = get(resolver, &label, "St") {
+            page_label.start = u32::try_from(first).unwrap_or(1).max(1);
+        }
+        labels.add_range(start, page_label);
+    }
+    if labels.ranges.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(labels))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::test_helpers::create_pdf_with_objects;
+    use crate::parser::PdfReader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_page_labels() {
+        let pdf = create_pdf_with_objects(&[
+            "<< /Type /Catalog /Pages 2 0 R /PageLabels << /Kids [3 0 R] >> >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+            "<< /Nums [0 << /S /r >> 2 << /S /D /St 5 >> 3 << /P (A-) /S /A >> 4 << /P (Cover) >>] >>",
+        ]);
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap());
+        let labels = document.page_labels().unwrap().unwrap();
+
+        assert_eq!(
+            labels.get_all_labels(6),
+            ["i", "ii", "5", "A-A", "Cover", "Cover"]
+        );
+    }
 
     #[test]
     fn test_page_label_tree() {
@@ -187,7 +187,8 @@ impl PageLists for Page {
     ) -> Result<&mut Self, PdfError> {
         let mut list_clone = list.clone();
         list_clone.set_position(x, y);
-        list_clone.render(self.graphics())?;
+        let (graphics, tags) = self.graphics_and_tags();
+        list_clone.render_tagged(graphics, tags)?;
         Ok(self)
     }
 
@@ -199,7 +200,8 @@ impl PageLists for Page {
     ) -> Result<&mut Self, PdfError> {
         let mut list_clone = list.clone();
         list_clone.set_position(x, y);
-        list_clone.render(self.graphics())?;
+        let (graphics, tags) = self.graphics_and_tags();
+        list_clone.render_tagged(graphics, tags)?;
         Ok(self)
     }
 
@@ -286,6 +286,14 @@ pub enum ContentOperation {
     /// Number of components depends on current color space.
     SetNonStrokingColor(Vec<f32>),
 
+    /// Set stroking color to a pattern (SCN operator with a pattern name).
+    /// References Pattern resource dictionary; the components are only
+    /// present for uncolored tiling patterns.
+    SetStrokingPattern(String, Vec<f32>),
+
+    /// Set non-stroking color to a pattern (scn operator with a pattern name).
+    SetNonStrokingPattern(String, Vec<f32>),
+
     /// Set stroking color to DeviceGray (G operator).
     /// 0.0 = black, 1.0 = white
     SetStrokingGray(f32),
@@ -335,6 +343,14 @@ pub enum ContentOperation {
     // Compatibility operators
     BeginCompatibility, // BX
     EndCompatibility,   // EX
+
+    // Type 3 glyph operators
+    /// Set glyph width of a Type 3 glyph (d0 operator).
+    SetCharWidth(f32, f32),
+
+    /// Set glyph width and bounding box of an uncolored Type 3 glyph
+    /// (d1 operator).
+    SetCacheDevice(f32, f32, f32, f32, f32, f32),
 }
 
 /// Represents a text element in a TJ array for ShowTextArray operations.
@@ -377,22 +393,35 @@ pub(super) enum Token {
     ArrayEnd,
     DictStart,
     DictEnd,
+    /// Raw bytes between the ID and EI operators of an inline image
+    InlineImageData(Vec<u8>),
 }
 
 /// Content stream tokenizer
 pub struct ContentTokenizer<'a> {
     input: &'a [u8],
     position: usize,
+    /// Set after an ID operator: the next token is raw image data
+    in_inline_image: bool,
 }
 
 impl<'a> ContentTokenizer<'a> {
     /// Create a new tokenizer for the given input
     pub fn new(input: &'a [u8]) -> Self {
-        Self { input, position: 0 }
+        Self {
+            input,
+            position: 0,
+            in_inline_image: false,
+        }
     }
 
     /// Get the next token from the stream
     pub(super) fn next_token(&mut self) -> ParseResult<Option<Token>> {
+        if self.in_inline_image {
+            self.in_inline_image = false;
+            return Ok(Some(self.read_inline_image_data()));
+        }
+
         self.skip_whitespace();
 
         if self.position >= self.input.len() {
@@ -720,8 +749,47 @@ impl<'a> ContentTokenizer<'a> {
             message: "Invalid operator".to_string(),
         })?;
 
+        if op == "ID" {
+            self.in_inline_image = true;
+        }
+
         Ok(Some(Token::Operator(op.to_string())))
     }
+
+    /// Read inline image data up to the whitespace before the EI operator
+    ///
+    /// A single whitespace byte separates ID from the data. The data ends at
+    /// the first "EI" that is preceded by whitespace and followed by
+    /// whitespace or the end of the stream.
+    fn read_inline_image_data(&mut self) -> Token {
+        if self
+            .input
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        let start = self.position;
+
+        let mut end = self.input.len();
+        let mut i = start;
+        while i + 2 <= self.input.len() {
+            if &self.input[i..i + 2] == b"EI"
+                && (i == start || self.input[i - 1].is_ascii_whitespace())
+                && self
+                    .input
+                    .get(i + 2)
+                    .map_or(true, |b| b.is_ascii_whitespace())
+            {
+                end = if i > start { i - 1 } else { i };
+                break;
+            }
+            i += 1;
+        }
+
+        self.position = end;
+        Token::InlineImageData(self.input[start..end].to_vec())
+    }
 }
 
 /// High-level content stream parser.
@@ -1036,12 +1104,20 @@ impl ContentParser {
                 ContentOperation::SetNonStrokingColorSpace(name)
             }
             "SC" | "SCN" => {
+                let pattern = self.pop_pattern_name(operands);
                 let components = self.pop_color_components(operands)?;
-                ContentOperation::SetStrokingColor(components)
+                match pattern {
+                    Some(name) => ContentOperation::SetStrokingPattern(name, components),
+                    None => ContentOperation::SetStrokingColor(components),
+                }
             }
             "sc" | "scn" => {
+                let pattern = self.pop_pattern_name(operands);
                 let components = self.pop_color_components(operands)?;
-                ContentOperation::SetNonStrokingColor(components)
+                match pattern {
+                    Some(name) => ContentOperation::SetNonStrokingPattern(name, components),
+                    None => ContentOperation::SetNonStrokingColor(components),
+                }
             }
             "G" => {
                 let gray = self.pop_number(operands)?;
@@ -1115,6 +1191,22 @@ impl ContentParser {
             "BX" => ContentOperation::BeginCompatibility,
             "EX" => ContentOperation::EndCompatibility,
 
+            // Type 3 glyph operators
+            "d0" => {
+                let wy = self.pop_number(operands)?;
+                let wx = self.pop_number(operands)?;
+                ContentOperation::SetCharWidth(wx, wy)
+            }
+            "d1" => {
+                let ury = self.pop_number(operands)?;
+                let urx = self.pop_number(operands)?;
+                let lly = self.pop_number(operands)?;
+                let llx = self.pop_number(operands)?;
+                let wy = self.pop_number(operands)?;
+                let wx = self.pop_number(operands)?;
+                ContentOperation::SetCacheDevice(wx, wy, llx, lly, urx, ury)
+            }
+
             // Inline images are handled specially
             "BI" => {
                 operands.clear(); // Clear any remaining operands
@@ -1239,7 +1331,9 @@ impl ContentParser {
                         // Popping gives us: Value first, then Key
                         let value = match &value_token {
                             Token::Name(name) => name.clone(),
-                            Token::String(s) => String::from_utf8_lossy(s).to_string(),
+                            Token::String(s) | Token::HexString(s) => {
+                                crate::parser::strings::text_string(s)
+                            }
                             Token::Integer(i) => i.to_string(),
                             Token::Number(f) => f.to_string(),
                             Token::ArrayEnd => {
@@ -1249,8 +1343,8 @@ impl ContentParser {
                                     match arr_token {
                                         Token::ArrayStart => break,
                                         Token::Name(n) => array_elements.push(n),
-                                        Token::String(s) => array_elements
-                                            .push(String::from_utf8_lossy(&s).to_string()),
+                                        Token::String(s) | Token::HexString(s) => array_elements
+                                            .push(crate::parser::strings::text_string(&s)),
                                         Token::Integer(i) => array_elements.push(i.to_string()),
                                         Token::Number(f) => array_elements.push(f.to_string()),
                                         _ => {} // Skip other token types in array
@@ -1284,6 +1378,17 @@ impl ContentParser {
         }
     }
 
+    /// Pop the pattern name operand of SCN/scn, if present
+    fn pop_pattern_name(&self, operands: &mut Vec<Token>) -> Option<String> {
+        match operands.last() {
+            Some(Token::Name(_)) => match operands.pop() {
+                Some(Token::Name(name)) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     fn pop_color_components(&self, operands: &mut Vec<Token>) -> ParseResult<Vec<f32>> {
         let mut components = Vec::new();
 
@@ -1369,62 +1474,88 @@ impl ContentParser {
             // /W -> Width, /H -> Height, /CS -> ColorSpace, /BPC -> BitsPerComponent
             // /F -> Filter, /DP -> DecodeParms, /IM -> ImageMask, /I -> Interpolate
             if let Token::Name(key) = &self.tokens[self.position] {
+                let full_key = expand_inline_key(key);
                 self.position += 1;
                 if self.position >= self.tokens.len() {
                     break;
                 }
 
-                // Parse the value
-                let value = match &self.tokens[self.position] {
-                    Token::Integer(n) => Object::Integer(*n as i64),
-                    Token::Number(n) => Object::Real(*n as f64),
-                    Token::Name(s) => Object::Name(expand_inline_name(s)),
-                    Token::String(s) => Object::String(String::from_utf8_lossy(s).to_string()),
-                    Token::HexString(s) => Object::String(String::from_utf8_lossy(s).to_string()),
-                    _ => Object::Null,
-                };
-
-                // Expand abbreviated keys to full names
-                let full_key = expand_inline_key(key);
+                let value = self.parse_inline_value();
                 params.insert(full_key, value);
-                self.position += 1;
             } else {
                 self.position += 1;
             }
         }
 
-        // Now we should be at the image data
-        // Collect bytes until we find EI
+        // The tokenizer hands over the bytes between ID and EI as one token
         let mut data = Vec::new();
-
-        // For inline images, we need to read raw bytes until EI
-        // This is tricky because EI could appear in the image data
-        // We need to look for EI followed by a whitespace or operator
-
-        // Simplified approach: collect all tokens until we find EI operator
         while self.position < self.tokens.len() {
-            if let Token::Operator(op) = &self.tokens[self.position] {
-                if op == "EI" {
-                    self.position += 1;
-                    break;
-                }
-            }
-
-            // Convert token to bytes (simplified - real implementation would need raw byte access)
-            match &self.tokens[self.position] {
-                Token::String(bytes) => data.extend_from_slice(bytes),
-                Token::HexString(bytes) => data.extend_from_slice(bytes),
-                Token::Integer(n) => data.extend_from_slice(n.to_string().as_bytes()),
-                Token::Number(n) => data.extend_from_slice(n.to_string().as_bytes()),
-                Token::Name(s) => data.extend_from_slice(s.as_bytes()),
-                Token::Operator(s) if s != "EI" => data.extend_from_slice(s.as_bytes()),
+            let token = &self.tokens[self.position];
+            self.position += 1;
+            match token {
+                Token::InlineImageData(bytes) => data.extend_from_slice(bytes),
+                Token::Operator(op) if op == "EI" => break,
                 _ => {}
             }
-            self.position += 1;
         }
 
         Ok(ContentOperation::InlineImage { params, data })
     }
+
+    /// Parse one inline image parameter value, including arrays (Decode,
+    /// Filter) and dictionaries (DecodeParms)
+    fn parse_inline_value(&mut self) -> Object {
+        let token = self.tokens[self.position].clone();
+        self.position += 1;
+        match token {
+            Token::Integer(n) => Object::Integer(n as i64),
+            Token::Number(n) => Object::Real(n as f64),
+            Token::Name(s) => Object::Name(expand_inline_name(&s)),
+            // One char per byte keeps binary values such as Indexed lookup
+            // tables intact
+            Token::String(s) | Token::HexString(s) => {
+                Object::String(s.iter().map(|&b| b as char).collect())
+            }
+            Token::Operator(op) if op == "true" || op == "false" => Object::Boolean(op == "true"),
+            Token::ArrayStart => {
+                let mut items = Vec::new();
+                while self.position < self.tokens.len() {
+                    if matches!(self.tokens[self.position], Token::ArrayEnd) {
+                        self.position += 1;
+                        break;
+                    }
+                    if matches!(&self.tokens[self.position], Token::Operator(op) if op == "ID") {
+                        break;
+                    }
+                    items.push(self.parse_inline_value());
+                }
+                Object::Array(items)
+            }
+            Token::DictStart => {
+                let mut dict = crate::objects::Dictionary::new();
+                while self.position < self.tokens.len() {
+                    match &self.tokens[self.position] {
+                        Token::DictEnd => {
+                            self.position += 1;
+                            break;
+                        }
+                        Token::Operator(op) if op == "ID" => break,
+                        Token::Name(key) => {
+                            let key = key.clone();
+                            self.position += 1;
+                            if self.position < self.tokens.len() {
+                                let value = self.parse_inline_value();
+                                dict.set(key, value);
+                            }
+                        }
+                        _ => self.position += 1,
+                    }
+                }
+                Object::Dictionary(dict)
+            }
+            _ => Object::Null,
+        }
+    }
 }
 
 /// Expand abbreviated inline image key names to full names
@@ -2193,6 +2324,57 @@ mod tests {
             }
         }
 
+        #[test]
+        fn test_inline_image_binary_data() {
+            let mut content =
+                b"q BI /W 2 /H 1 /CS /G /BPC 8 /D [1 0] /DP << /Predictor 1 >> ID ".to_vec();
+            content.extend_from_slice(&[0x00, b'E', b'I', 0xFF]);
+            content.extend_from_slice(b"\nEI Q");
+            let operators = ContentParser::parse(&content).unwrap();
+
+            assert_eq!(operators.len(), 3);
+            match &operators[1] {
+                ContentOperation::InlineImage { params, data } => {
+                    assert_eq!(data, &[0x00, b'E', b'I', 0xFF]);
+                    assert_eq!(
+                        params.get("Decode"),
+                        Some(&Object::Array(vec![Object::Integer(1), Object::Integer(0)]))
+                    );
+                    assert!(matches!(
+                        params.get("DecodeParms"),
+                        Some(Object::Dictionary(_))
+                    ));
+                }
+                _ => panic!("Expected InlineImage operation"),
+            }
+            assert_eq!(operators[2], ContentOperation::RestoreGraphicsState);
+        }
+
+        #[test]
+        fn test_pattern_color_operators() {
+            let operators = ContentParser::parse(b"/P0 scn 0.5 /P1 SCN 1 0 0 scn").unwrap();
+            assert_eq!(
+                operators,
+                vec![
+                    ContentOperation::SetNonStrokingPattern("P0".to_string(), vec![]),
+                    ContentOperation::SetStrokingPattern("P1".to_string(), vec![0.5]),
+                    ContentOperation::SetNonStrokingColor(vec![1.0, 0.0, 0.0]),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_type3_glyph_operators() {
+            let operators = ContentParser::parse(b"500 0 d0 600 0 0 -10 550 700 d1").unwrap();
+            assert_eq!(
+                operators,
+                vec![
+                    ContentOperation::SetCharWidth(500.0, 0.0),
+                    ContentOperation::SetCacheDevice(600.0, 0.0, 0.0, -10.0, 550.0, 700.0),
+                ]
+            );
+        }
+
         #[test]
         fn test_content_parser_performance() {
             let mut content = Vec::new();
@@ -2499,6 +2681,21 @@ mod tests {
             assert_eq!(operators[2], ContentOperation::EndMarkedContent);
         }
 
+        #[test]
+        fn test_parser_actual_text_property() {
+            let content = b"/Span <</ActualText <FEFF05D005D1>>> BDC [<05D1> <05D0>] TJ EMC";
+            let operators = ContentParser::parse(content).unwrap();
+
+            assert_eq!(operators.len(), 3);
+            match &operators[0] {
+                ContentOperation::BeginMarkedContentWithProps(tag, props) => {
+                    assert_eq!(tag, "Span");
+                    assert_eq!(props.get("ActualText").unwrap(), "\u{5D0}\u{5D1}");
+                }
+                _ => panic!("Expected BeginMarkedContentWithProps"),
+            }
+        }
+
         #[test]
         fn test_parser_error_handling_invalid_operators() {
             // Missing operands for move operator
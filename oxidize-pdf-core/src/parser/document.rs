@@ -254,6 +254,204 @@ impl<R: Read + Seek> PdfDocument<R> {
         self.reader.borrow().options().clone()
     }
 
+    /// Get the document catalog (the `/Root` dictionary of the trailer)
+    pub fn catalog(&self) -> ParseResult<PdfDictionary> {
+        Ok(self.reader.borrow_mut().catalog()?.clone())
+    }
+
+    /// Get the trailer of the most recent cross-reference section
+    pub fn trailer(&self) -> super::trailer::PdfTrailer {
+        self.reader.borrow().trailer().clone()
+    }
+
+    /// Read the complete underlying file
+    pub fn raw_bytes(&self) -> ParseResult<Vec<u8>> {
+        self.reader.borrow_mut().read_raw_bytes()
+    }
+
+    /// Location of every in-use object in the merged cross-reference table.
+    ///
+    /// Compressed objects carry the object stream number and index in
+    /// `compressed_info`.
+    pub fn xref_entries(&self) -> HashMap<u32, super::xref::XRefEntryExt> {
+        let reader = self.reader.borrow();
+        let xref = reader.xref();
+        xref.iter()
+            .filter(|(_, entry)| entry.in_use)
+            .map(|(&num, entry)| {
+                let ext =
+                    xref.get_extended_entry(num)
+                        .cloned()
+                        .unwrap_or(super::xref::XRefEntryExt {
+                            basic: *entry,
+                            compressed_info: None,
+                        });
+                (num, ext)
+            })
+            .collect()
+    }
+
+    /// Verify every signed signature field of the document.
+    ///
+    /// Each signature is checked against the bytes it covers, and changes made
+    /// by later incremental updates are compared with the DocMDP and FieldMDP
+    /// permissions of the signature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let reader = PdfReader::open("signed.pdf")?;
+    /// # let document = PdfDocument::new(reader);
+    /// for signature in document.verify_signatures()? {
+    ///     println!(
+    ///         "{}: valid={} modified after signing={}",
+    ///         signature.field_name,
+    ///         signature.is_valid(),
+    ///         signature.modified_after_signing
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn verify_signatures(
+        &self,
+    ) -> crate::error::Result<Vec<crate::signatures::SignatureVerification>> {
+        crate::signatures::verify_document(self)
+    }
+
+    /// Render a page (zero-based index) to an RGBA bitmap.
+    ///
+    /// The page's CropBox is rendered at `options.dpi` with its /Rotate
+    /// applied. See [`crate::rendering`] for the supported features.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// # use oxidize_pdf::rendering::RenderOptions;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let reader = PdfReader::open("document.pdf")?;
+    /// # let document = PdfDocument::new(reader);
+    /// let thumbnail = document.render_page(0, &RenderOptions::with_dpi(36.0))?;
+    /// std::fs::write("thumbnail.png", thumbnail.to_png()?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_page(
+        &self,
+        page_index: u32,
+        options: &crate::rendering::RenderOptions,
+    ) -> crate::error::Result<crate::rendering::RenderedPage> {
+        crate::rendering::render_page(self, page_index, options)
+    }
+
+    /// Get the layers (optional content groups) of the document, with
+    /// their state in the default configuration.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let reader = PdfReader::open("drawing.pdf")?;
+    /// # let document = PdfDocument::new(reader);
+    /// for layer in document.layers()? {
+    ///     println!("{}: visible={}", layer.name, layer.visible);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layers(&self) -> crate::error::Result<Vec<crate::optional_content::LayerInfo>> {
+        crate::optional_content::read_layers(self)
+    }
+
+    /// Get the files attached to the document through its `EmbeddedFiles`
+    /// name tree, with their decoded content.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let reader = PdfReader::open("report.pdf")?;
+    /// # let document = PdfDocument::new(reader);
+    /// for attachment in document.attachments()? {
+    ///     std::fs::write(&attachment.file.file_name, &attachment.file.data)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn attachments(&self) -> crate::error::Result<Vec<crate::attachments::Attachment>> {
+        crate::attachments::read_attachments(self)
+    }
+
+    /// Get the portfolio settings, if the document is a portfolio.
+    pub fn collection(&self) -> crate::error::Result<Option<crate::attachments::Collection>> {
+        crate::attachments::read_collection(self)
+    }
+
+    /// Get the document outline (bookmarks), with destinations resolved to
+    /// page indices.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// # use oxidize_pdf::structure::{OutlineItem, PageDestination};
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let reader = PdfReader::open("manual.pdf")?;
+    /// # let document = PdfDocument::new(reader);
+    /// fn print(items: &[OutlineItem], depth: usize) {
+    ///     for item in items {
+    ///         if let Some(PageDestination::PageNumber(page)) =
+    ///             item.destination.as_ref().map(|dest| &dest.page)
+    ///         {
+    ///             println!("{}{} (page {})", "  ".repeat(depth), item.title, page + 1);
+    ///         }
+    ///         print(&item.children, depth + 1);
+    ///     }
+    /// }
+    /// if let Some(outline) = document.outline()? {
+    ///     print(&outline.items, 0);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn outline(&self) -> crate::error::Result<Option<crate::structure::OutlineTree>> {
+        crate::structure::read_outline(self)
+    }
+
+    /// Get the named destinations of the document's `Dests` name tree (and
+    /// the older `Dests` dictionary), with pages resolved to page indices.
+    pub fn named_destinations(
+        &self,
+    ) -> crate::error::Result<Option<crate::structure::NamedDestinations>> {
+        crate::structure::read_named_destinations(self)
+    }
+
+    /// Get the page labels of the document.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let reader = PdfReader::open("book.pdf")?;
+    /// # let document = PdfDocument::new(reader);
+    /// if let Some(labels) = document.page_labels()? {
+    ///     for (index, label) in labels.get_all_labels(document.page_count()?).iter().enumerate() {
+    ///         println!("page {}: {label}", index + 1);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn page_labels(&self) -> crate::error::Result<Option<crate::page_labels::PageLabelTree>> {
+        crate::page_labels::read_page_labels(self)
+    }
+
     /// Get the total number of pages in the document.
     ///
     /// # Returns
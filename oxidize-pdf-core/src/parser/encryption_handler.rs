@@ -3,17 +3,22 @@
 //! This module provides functionality to detect encrypted PDFs and handle password-based
 //! decryption according to ISO 32000-1 Chapter 7.6.
 
-use super::objects::PdfDictionary;
+use super::objects::{PdfDictionary, PdfObject};
 use super::{ParseError, ParseResult};
 use crate::encryption::{
-    EncryptionKey, Permissions, Rc4, Rc4Key, StandardSecurityHandler, UserPassword,
+    CryptFilterMethod, EncryptionKey, Permissions, PublicKeySecurityHandler, Rc4, Rc4Key,
+    RecipientCredentials, SecurityHandler, StandardSecurityHandler, SubFilter, UserPassword,
 };
 use crate::objects::ObjectId;
 
 /// Encryption information extracted from PDF trailer
+///
+/// For the public-key security handler (`Adobe.PubSec`) there is no `R`, `O`
+/// or `U` entry; `r` is 0, the hashes are empty and `p` is only known once
+/// a recipient has unlocked the document.
 #[derive(Debug, Clone)]
 pub struct EncryptionInfo {
-    /// Filter name (should be "Standard")
+    /// Filter name ("Standard" or "Adobe.PubSec")
     pub filter: String,
     /// V entry (algorithm version)
     pub v: i32,
@@ -29,12 +34,24 @@ pub struct EncryptionInfo {
     pub length: Option<i32>,
 }
 
+/// Public-key security handler parameters (ISO 32000-1 §7.6.4)
+struct PublicKeyInfo {
+    /// SubFilter (adbe.pkcs7.s4 or adbe.pkcs7.s5)
+    sub_filter: SubFilter,
+    /// DER-encoded PKCS#7 objects of the `/Recipients` array
+    recipients: Vec<Vec<u8>>,
+    /// Handler performing the per-object decryption
+    handler: PublicKeySecurityHandler,
+}
+
 /// PDF Encryption Handler
 pub struct EncryptionHandler {
     /// Encryption information from trailer
     encryption_info: EncryptionInfo,
     /// Standard security handler
     security_handler: StandardSecurityHandler,
+    /// Public-key security handler, for `Adobe.PubSec` documents
+    public_key: Option<PublicKeyInfo>,
     /// Current encryption key (if unlocked)
     encryption_key: Option<EncryptionKey>,
     /// File ID from trailer
@@ -44,6 +61,17 @@ pub struct EncryptionHandler {
 impl EncryptionHandler {
     /// Create encryption handler from encryption dictionary
     pub fn new(encrypt_dict: &PdfDictionary, file_id: Option<Vec<u8>>) -> ParseResult<Self> {
+        if Self::filter_name(encrypt_dict)? == "Adobe.PubSec" {
+            let (encryption_info, public_key) = Self::parse_public_key_dict(encrypt_dict)?;
+            return Ok(Self {
+                encryption_info,
+                security_handler: StandardSecurityHandler::rc4_128bit(),
+                public_key: Some(public_key),
+                encryption_key: None,
+                file_id,
+            });
+        }
+
         let encryption_info = Self::parse_encryption_dict(encrypt_dict)?;
 
         // Create appropriate security handler based on revision
@@ -63,19 +91,24 @@ impl EncryptionHandler {
         Ok(Self {
             encryption_info,
             security_handler,
+            public_key: None,
             encryption_key: None,
             file_id,
         })
     }
 
+    /// Get the /Filter name of an encryption dictionary
+    fn filter_name(dict: &PdfDictionary) -> ParseResult<&str> {
+        dict.get("Filter")
+            .and_then(|obj| obj.as_name())
+            .map(|name| name.0.as_str())
+            .ok_or_else(|| ParseError::MissingKey("Filter".to_string()))
+    }
+
     /// Parse encryption dictionary from PDF trailer
     fn parse_encryption_dict(dict: &PdfDictionary) -> ParseResult<EncryptionInfo> {
         // Get Filter (required)
-        let filter = dict
-            .get("Filter")
-            .and_then(|obj| obj.as_name())
-            .map(|name| name.0.as_str())
-            .ok_or_else(|| ParseError::MissingKey("Filter".to_string()))?;
+        let filter = Self::filter_name(dict)?;
 
         if filter != "Standard" {
             return Err(ParseError::SyntaxError {
@@ -138,6 +171,116 @@ impl EncryptionHandler {
         })
     }
 
+    /// Parse a public-key encryption dictionary (`/Filter /Adobe.PubSec`)
+    ///
+    /// For adbe.pkcs7.s4 the recipients are listed in the encryption
+    /// dictionary; for adbe.pkcs7.s5 they belong to the crypt filter named by
+    /// `/StmF`.
+    fn parse_public_key_dict(dict: &PdfDictionary) -> ParseResult<(EncryptionInfo, PublicKeyInfo)> {
+        let unsupported = |message: String| ParseError::SyntaxError {
+            position: 0,
+            message,
+        };
+
+        let sub_filter = dict
+            .get("SubFilter")
+            .and_then(|obj| obj.as_name())
+            .map(|name| SubFilter::from_name(name.as_str()))
+            .ok_or_else(|| ParseError::MissingKey("SubFilter".to_string()))?;
+
+        let v = dict
+            .get("V")
+            .and_then(|obj| obj.as_integer())
+            .map(|i| i as i32)
+            .unwrap_or(0);
+        let length = dict
+            .get("Length")
+            .and_then(|obj| obj.as_integer())
+            .map(|i| i as i32);
+
+        let (source, method) = match sub_filter {
+            SubFilter::AdbePkcs7S3 | SubFilter::AdbePkcs7S4 => (dict, CryptFilterMethod::V2),
+            SubFilter::AdbePkcs7S5 => {
+                let filter_name = dict
+                    .get("StmF")
+                    .and_then(|obj| obj.as_name())
+                    .map(|name| name.as_str())
+                    .unwrap_or("DefaultCryptFilter");
+                let filter = dict
+                    .get("CF")
+                    .and_then(|obj| obj.as_dict())
+                    .and_then(|cf| cf.get(filter_name))
+                    .and_then(|obj| obj.as_dict())
+                    .ok_or_else(|| ParseError::MissingKey(format!("CF/{filter_name}")))?;
+                let method = match filter.get("CFM").and_then(|obj| obj.as_name()) {
+                    Some(name) if name.as_str() == "AESV2" => CryptFilterMethod::AESV2,
+                    Some(name) if name.as_str() == "AESV3" => CryptFilterMethod::AESV3,
+                    Some(name) if name.as_str() == "V2" => CryptFilterMethod::V2,
+                    other => {
+                        return Err(unsupported(format!(
+                            "Crypt filter method {:?} not supported",
+                            other.map(|name| name.as_str())
+                        )))
+                    }
+                };
+                (filter, method)
+            }
+            ref other => {
+                return Err(unsupported(format!(
+                    "Public-key SubFilter '{}' not supported",
+                    other.to_name()
+                )))
+            }
+        };
+
+        let recipients: Vec<Vec<u8>> = match source.get("Recipients") {
+            Some(PdfObject::Array(array)) => array
+                .0
+                .iter()
+                .filter_map(|obj| obj.as_string())
+                .map(|string| string.as_bytes().to_vec())
+                .collect(),
+            Some(PdfObject::String(string)) => vec![string.as_bytes().to_vec()],
+            _ => Vec::new(),
+        };
+        if recipients.is_empty() {
+            return Err(ParseError::MissingKey("Recipients".to_string()));
+        }
+
+        let encrypt_metadata = source
+            .get("EncryptMetadata")
+            .or_else(|| dict.get("EncryptMetadata"))
+            .and_then(|obj| obj.as_bool())
+            .unwrap_or(true);
+
+        let mut handler = PublicKeySecurityHandler::new_sha1();
+        handler.subfilter = sub_filter.clone();
+        handler.method = method;
+        handler.encrypt_metadata = encrypt_metadata;
+        // RC4 keys follow /Length (40 to 128 bits); AES lengths are fixed
+        if method == CryptFilterMethod::V2 {
+            handler.key_length = length.map_or(16, |bits| (bits / 8).clamp(5, 16) as usize);
+        }
+
+        let info = EncryptionInfo {
+            filter: "Adobe.PubSec".to_string(),
+            v,
+            r: 0,
+            o: Vec::new(),
+            u: Vec::new(),
+            p: 0,
+            length,
+        };
+        Ok((
+            info,
+            PublicKeyInfo {
+                sub_filter,
+                recipients,
+                handler,
+            },
+        ))
+    }
+
     /// Check if PDF is encrypted by looking for Encrypt entry in trailer
     pub fn detect_encryption(trailer: &PdfDictionary) -> bool {
         trailer.contains_key("Encrypt")
@@ -145,6 +288,11 @@ impl EncryptionHandler {
 
     /// Try to unlock PDF with user password
     pub fn unlock_with_user_password(&mut self, password: &str) -> ParseResult<bool> {
+        // Public-key encrypted documents have no passwords
+        if self.public_key.is_some() {
+            return Ok(false);
+        }
+
         let user_password = UserPassword(password.to_string());
 
         // Compute what the U entry should be for this password
@@ -234,6 +382,10 @@ impl EncryptionHandler {
     /// 2. Decrypting the O entry to recover the user password
     /// 3. Using the recovered user password to compute the encryption key
     pub fn unlock_with_owner_password(&mut self, password: &str) -> ParseResult<bool> {
+        if self.public_key.is_some() {
+            return Ok(false);
+        }
+
         // Standard 32-byte padding from PDF spec
         const PADDING: [u8; 32] = [
             0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA,
@@ -317,6 +469,49 @@ impl EncryptionHandler {
         self.unlock_with_user_password(&full_user_password)
     }
 
+    /// Try to unlock a public-key encrypted document with a recipient's
+    /// certificate and private key
+    ///
+    /// Returns `Ok(false)` when the document is not encrypted for the
+    /// certificate. On success the permissions granted to the recipient
+    /// become available through [`permissions`](Self::permissions).
+    pub fn unlock_with_credentials(
+        &mut self,
+        credentials: &RecipientCredentials,
+    ) -> ParseResult<bool> {
+        let Some(public_key) = &self.public_key else {
+            return Ok(false);
+        };
+
+        for recipient in &public_key.recipients {
+            let opened =
+                PublicKeySecurityHandler::decrypt_seed(recipient, credentials).map_err(|e| {
+                    ParseError::SyntaxError {
+                        position: 0,
+                        message: format!("Failed to open recipient envelope: {e}"),
+                    }
+                })?;
+            if let Some((seed, permissions)) = opened {
+                let key = PublicKeySecurityHandler::derive_key(
+                    &seed,
+                    &public_key.recipients,
+                    public_key.handler.encrypt_metadata,
+                    public_key.handler.method,
+                    public_key.handler.key_length,
+                );
+                self.encryption_info.p = permissions.bits() as i32;
+                self.encryption_key = Some(key);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Check whether this document uses the public-key security handler
+    pub fn is_public_key(&self) -> bool {
+        self.public_key.is_some()
+    }
+
     /// Try to unlock with empty password (common case)
     pub fn try_empty_password(&mut self) -> ParseResult<bool> {
         self.unlock_with_user_password("")
@@ -334,22 +529,44 @@ impl EncryptionHandler {
 
     /// Decrypt a string object
     pub fn decrypt_string(&self, data: &[u8], obj_id: &ObjectId) -> ParseResult<Vec<u8>> {
-        match &self.encryption_key {
-            Some(key) => Ok(self.security_handler.decrypt_string(data, key, obj_id)),
-            None => Err(ParseError::EncryptionNotSupported),
+        match (&self.encryption_key, &self.public_key) {
+            (Some(key), Some(public_key)) => public_key
+                .handler
+                .decrypt_string(data, key, obj_id)
+                .map_err(|e| ParseError::SyntaxError {
+                    position: 0,
+                    message: format!("Failed to decrypt string: {e}"),
+                }),
+            (Some(key), None) => Ok(self.security_handler.decrypt_string(data, key, obj_id)),
+            (None, _) => Err(ParseError::EncryptionNotSupported),
         }
     }
 
     /// Decrypt a stream object
     pub fn decrypt_stream(&self, data: &[u8], obj_id: &ObjectId) -> ParseResult<Vec<u8>> {
-        match &self.encryption_key {
-            Some(key) => Ok(self.security_handler.decrypt_stream(data, key, obj_id)),
-            None => Err(ParseError::EncryptionNotSupported),
+        match (&self.encryption_key, &self.public_key) {
+            (Some(key), Some(public_key)) => public_key
+                .handler
+                .decrypt_stream(data, key, obj_id)
+                .map_err(|e| ParseError::SyntaxError {
+                    position: 0,
+                    message: format!("Failed to decrypt stream: {e}"),
+                }),
+            (Some(key), None) => Ok(self.security_handler.decrypt_stream(data, key, obj_id)),
+            (None, _) => Err(ParseError::EncryptionNotSupported),
         }
     }
 
     /// Get encryption algorithm information
     pub fn algorithm_info(&self) -> String {
+        if let Some(public_key) = &self.public_key {
+            let cipher = match public_key.handler.method {
+                CryptFilterMethod::V2 => format!("RC4 {}-bit", public_key.handler.key_length * 8),
+                CryptFilterMethod::AESV3 => "AES-256".to_string(),
+                _ => "AES-128".to_string(),
+            };
+            return format!("Public key ({}) {cipher}", public_key.sub_filter.to_name());
+        }
         match (
             self.encryption_info.r,
             self.encryption_info.length.unwrap_or(40),
@@ -392,6 +609,9 @@ impl EncryptionHandler {
 
     /// Check if metadata should be encrypted (R4 only)
     pub fn encrypt_metadata(&self) -> bool {
+        if let Some(public_key) = &self.public_key {
+            return public_key.handler.encrypt_metadata;
+        }
         // For R4, this could be controlled by EncryptMetadata entry
         // For now, assume metadata is encrypted
         true
@@ -1025,4 +1245,221 @@ mod tests {
         let result = decryption.unlock_pdf(&mut handler).unwrap();
         matches!(result, PasswordResult::Cancelled);
     }
+
+    fn public_key_encryption_dict(handler: &PublicKeySecurityHandler) -> PdfDictionary {
+        let recipients = PdfObject::Array(crate::parser::objects::PdfArray(
+            handler
+                .recipient_strings()
+                .into_iter()
+                .map(|r| PdfObject::String(PdfString::new(r)))
+                .collect(),
+        ));
+        let name = |n: &str| PdfObject::Name(PdfName(n.to_string()));
+
+        let mut dict = PdfDictionary::new();
+        dict.insert("Filter".to_string(), name("Adobe.PubSec"));
+        dict.insert("SubFilter".to_string(), name("adbe.pkcs7.s5"));
+        dict.insert("V".to_string(), PdfObject::Integer(4));
+        dict.insert("Length".to_string(), PdfObject::Integer(128));
+        let mut filter = PdfDictionary::new();
+        filter.insert("CFM".to_string(), name("AESV2"));
+        filter.insert("Recipients".to_string(), recipients);
+        let mut cf = PdfDictionary::new();
+        cf.insert(
+            "DefaultCryptFilter".to_string(),
+            PdfObject::Dictionary(filter),
+        );
+        dict.insert("CF".to_string(), PdfObject::Dictionary(cf));
+        dict.insert("StmF".to_string(), name("DefaultCryptFilter"));
+        dict.insert("StrF".to_string(), name("DefaultCryptFilter"));
+        dict
+    }
+
+    #[test]
+    fn test_public_key_unlock_with_credentials() {
+        use crate::signatures::test_helpers::{
+            CHAIN_CERT_PEM, CHAIN_KEY_PEM, RSA_CERT_PEM, RSA_KEY_PEM,
+        };
+
+        let mut writer_handler = PublicKeySecurityHandler::new_aes128();
+        let permissions = Permissions::new().set_print(true).clone();
+        writer_handler
+            .add_recipient_pem(RSA_CERT_PEM, permissions)
+            .unwrap();
+        let obj_id = ObjectId::new(7, 0);
+        let encrypted = writer_handler
+            .encrypt_string(
+                b"Quarterly report",
+                &writer_handler.encryption_key(),
+                &obj_id,
+            )
+            .unwrap();
+
+        let dict = public_key_encryption_dict(&writer_handler);
+        let mut handler = EncryptionHandler::new(&dict, None).unwrap();
+        assert!(handler.is_public_key());
+        assert_eq!(
+            handler.algorithm_info(),
+            "Public key (adbe.pkcs7.s5) AES-128"
+        );
+
+        // Passwords never unlock a public-key document
+        assert!(!handler.try_empty_password().unwrap());
+        assert!(!handler.unlock_with_owner_password("owner").unwrap());
+
+        let other = RecipientCredentials::from_pem(CHAIN_KEY_PEM, CHAIN_CERT_PEM).unwrap();
+        assert!(!handler.unlock_with_credentials(&other).unwrap());
+        assert!(!handler.is_unlocked());
+
+        let credentials = RecipientCredentials::from_pem(RSA_KEY_PEM, RSA_CERT_PEM).unwrap();
+        assert!(handler.unlock_with_credentials(&credentials).unwrap());
+        assert_eq!(handler.permissions().bits(), permissions.bits());
+        assert_eq!(
+            handler.decrypt_string(&encrypted, &obj_id).unwrap(),
+            b"Quarterly report"
+        );
+    }
+
+    #[test]
+    fn test_public_key_s4_40_bit_round_trip() {
+        use crate::encryption::PublicKeyEncryptionDict;
+        use crate::objects::Object;
+        use crate::signatures::test_helpers::{RSA_CERT_PEM, RSA_KEY_PEM};
+
+        let mut writer_handler = PublicKeySecurityHandler::new_sha1();
+        writer_handler.key_length = 5;
+        writer_handler
+            .add_recipient_pem(RSA_CERT_PEM, Permissions::all())
+            .unwrap();
+        assert_eq!(writer_handler.encryption_key().len(), 5);
+        assert_eq!(
+            PublicKeyEncryptionDict::new(&writer_handler)
+                .to_dict()
+                .get("Length"),
+            Some(&Object::Integer(40))
+        );
+
+        let obj_id = ObjectId::new(3, 0);
+        let encrypted = writer_handler
+            .encrypt_string(b"Forty bits", &writer_handler.encryption_key(), &obj_id)
+            .unwrap();
+
+        let name = |n: &str| PdfObject::Name(PdfName(n.to_string()));
+        let mut dict = PdfDictionary::new();
+        dict.insert("Filter".to_string(), name("Adobe.PubSec"));
+        dict.insert("SubFilter".to_string(), name("adbe.pkcs7.s4"));
+        dict.insert("V".to_string(), PdfObject::Integer(2));
+        dict.insert("Length".to_string(), PdfObject::Integer(40));
+        dict.insert(
+            "Recipients".to_string(),
+            PdfObject::Array(crate::parser::objects::PdfArray(
+                writer_handler
+                    .recipient_strings()
+                    .into_iter()
+                    .map(|r| PdfObject::String(PdfString::new(r)))
+                    .collect(),
+            )),
+        );
+
+        let mut handler = EncryptionHandler::new(&dict, None).unwrap();
+        assert_eq!(
+            handler.algorithm_info(),
+            "Public key (adbe.pkcs7.s4) RC4 40-bit"
+        );
+        let credentials = RecipientCredentials::from_pem(RSA_KEY_PEM, RSA_CERT_PEM).unwrap();
+        assert!(handler.unlock_with_credentials(&credentials).unwrap());
+        assert_eq!(
+            handler.decrypt_string(&encrypted, &obj_id).unwrap(),
+            b"Forty bits"
+        );
+    }
+
+    #[test]
+    fn test_public_key_missing_recipients() {
+        let mut dict = public_key_encryption_dict(&PublicKeySecurityHandler::new_aes128());
+        dict.insert(
+            "SubFilter".to_string(),
+            PdfObject::Name(PdfName("adbe.pkcs7.s4".to_string())),
+        );
+        assert!(EncryptionHandler::new(&dict, None).is_err());
+    }
+
+    #[test]
+    fn test_public_key_encrypted_document() {
+        use crate::parser::PdfReader;
+        use crate::signatures::test_helpers::{RSA_CERT_PEM, RSA_KEY_PEM};
+        use std::io::Cursor;
+
+        let mut writer_handler = PublicKeySecurityHandler::new_sha1();
+        writer_handler
+            .add_recipient_pem(RSA_CERT_PEM, Permissions::all())
+            .unwrap();
+        let key = writer_handler.encryption_key();
+        let content = writer_handler
+            .encrypt_stream(b"BT /F1 12 Tf (Secret) Tj ET", &key, &ObjectId::new(4, 0))
+            .unwrap();
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{b:02X}")).collect::<String>();
+        let recipients: Vec<String> = writer_handler
+            .recipient_strings()
+            .iter()
+            .map(|r| format!("<{}>", hex(r)))
+            .collect();
+
+        let mut pdf = b"%PDF-1.6\n".to_vec();
+        let mut offsets = Vec::new();
+        let mut push = |pdf: &mut Vec<u8>, body: Vec<u8>| {
+            offsets.push(pdf.len());
+            pdf.extend(body);
+        };
+        push(
+            &mut pdf,
+            b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n".to_vec(),
+        );
+        push(
+            &mut pdf,
+            b"2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n".to_vec(),
+        );
+        push(
+            &mut pdf,
+            b"3 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>\nendobj\n"
+                .to_vec(),
+        );
+        let mut stream = format!("4 0 obj\n<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(&content);
+        stream.extend(b"\nendstream\nendobj\n");
+        push(&mut pdf, stream);
+        push(
+            &mut pdf,
+            format!(
+                "5 0 obj\n<< /Filter /Adobe.PubSec /SubFilter /adbe.pkcs7.s4 /V 2 /Length 128 /Recipients [{}] >>\nendobj\n",
+                recipients.join(" ")
+            )
+            .into_bytes(),
+        );
+        let xref_offset = pdf.len();
+        pdf.extend(b"xref\n0 6\n0000000000 65535 f \n");
+        for offset in &offsets {
+            pdf.extend(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size 6 /Root 1 0 R /Encrypt 5 0 R /ID [<00112233> <00112233>] >>\nstartxref\n{xref_offset}\n%%EOF\n"
+            )
+            .as_bytes(),
+        );
+
+        let mut reader = PdfReader::new(Cursor::new(pdf)).unwrap();
+        assert!(reader.is_encrypted());
+        assert!(reader.unlock("").is_err());
+
+        let credentials = RecipientCredentials::from_pem(RSA_KEY_PEM, RSA_CERT_PEM).unwrap();
+        assert!(reader.unlock_with_credentials(&credentials).unwrap());
+        assert!(reader.is_unlocked());
+
+        let stream = reader.get_object(4, 0).unwrap().clone();
+        let PdfObject::Stream(stream) = stream else {
+            panic!("Expected content stream");
+        };
+        assert_eq!(stream.data, b"BT /F1 12 Tf (Secret) Tj ET");
+    }
 }
@@ -0,0 +1,703 @@
+//! Huffman-coded sequential and progressive JPEG decoding (ITU-T T.81
+//! Annexes B, F and G)
+//!
+//! All scans are decoded into per-block coefficients first; dequantization,
+//! the inverse DCT and upsampling run once the whole image is read, which
+//! handles sequential and progressive images the same way.
+
+use super::huffman::{BitReader, HuffmanTable};
+use super::idct::inverse_dct;
+use super::sampling::{upsample, Plane};
+use crate::parser::{ParseError, ParseResult};
+
+/// Natural (row-major) index of each zig-zag position (Figure A.6)
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Largest number of coefficients or samples an image may need
+const MAX_SAMPLES: usize = 1 << 28;
+
+/// A decoded image with every component upsampled to the full size
+pub(super) struct DecodedFrame {
+    pub width: usize,
+    pub height: usize,
+    /// Component identifiers from the frame header
+    pub ids: Vec<u8>,
+    /// One `width` x `height` plane per component
+    pub planes: Vec<Vec<u8>>,
+    /// Transform flag of an Adobe APP14 segment, if present
+    pub adobe_transform: Option<u8>,
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quantization: usize,
+    /// Samples of the component (A.1.1)
+    width: usize,
+    height: usize,
+    /// Blocks stored per row and column, padded to whole MCUs
+    blocks_across: usize,
+    blocks_down: usize,
+    coefficients: Vec<i16>,
+    dc_prediction: i32,
+}
+
+struct Frame {
+    progressive: bool,
+    width: usize,
+    height: usize,
+    max_h: usize,
+    max_v: usize,
+    mcus_across: usize,
+    mcus_down: usize,
+    components: Vec<Component>,
+}
+
+/// Spectral selection and successive approximation of a scan (B.2.3)
+#[derive(Debug, Clone, Copy)]
+struct Spectral {
+    start: usize,
+    end: usize,
+    high: u32,
+    low: u32,
+}
+
+#[derive(Default)]
+struct Decoder {
+    quantization: [Option<[u16; 64]>; 4],
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    restart_interval: usize,
+    frame: Option<Frame>,
+    adobe_transform: Option<u8>,
+}
+
+/// Huffman tables used by one component of a scan
+struct ScanTables {
+    dc: Option<HuffmanTable>,
+    ac: Option<HuffmanTable>,
+}
+
+/// Decoding state shared by the blocks of a scan
+struct ScanState {
+    progressive: bool,
+    spectral: Spectral,
+    /// Remaining blocks of an end-of-band run (G.1.2.2)
+    eob_run: u32,
+}
+
+impl ScanState {
+    fn decode_block(
+        &mut self,
+        reader: &mut BitReader,
+        component: &mut Component,
+        tables: &ScanTables,
+        row: usize,
+        column: usize,
+    ) -> ParseResult<()> {
+        let offset = (row * component.blocks_across + column) * 64;
+        let block = &mut component.coefficients[offset..offset + 64];
+        let prediction = &mut component.dc_prediction;
+        let spectral = self.spectral;
+        match (self.progressive, &tables.dc, &tables.ac) {
+            (false, Some(dc), Some(ac)) => decode_sequential(reader, dc, ac, block, prediction),
+            (true, Some(dc), _) => decode_dc_first(reader, dc, block, prediction, spectral),
+            (true, None, None) => {
+                decode_dc_refine(reader, block, spectral);
+                Ok(())
+            }
+            (true, None, Some(ac)) if spectral.high == 0 => {
+                decode_ac_first(reader, ac, block, spectral, &mut self.eob_run)
+            }
+            (true, None, Some(ac)) => {
+                decode_ac_refine(reader, ac, block, spectral, &mut self.eob_run)
+            }
+            _ => Err(invalid("inconsistent scan")),
+        }
+    }
+}
+
+fn invalid(message: &str) -> ParseError {
+    ParseError::StreamDecodeError(format!("Invalid JPEG data: {message}"))
+}
+
+/// Decode a JPEG interchange format stream starting at its SOI marker
+pub(super) fn decode(data: &[u8]) -> ParseResult<DecodedFrame> {
+    if data.len() < 2 || data[0] != 0xFF || data[1] != 0xD8 {
+        return Err(invalid("missing SOI marker"));
+    }
+
+    let mut decoder = Decoder::default();
+    let mut pos = 2;
+    while pos + 1 < data.len() {
+        if data[pos] != 0xFF {
+            // Tolerate garbage between segments
+            pos += 1;
+            continue;
+        }
+        let marker = data[pos + 1];
+        pos += 2;
+        match marker {
+            0xFF => pos -= 1,
+            0xD9 => break,
+            0x00 | 0x01 | 0xD0..=0xD8 => {}
+            _ => {
+                if pos + 2 > data.len() {
+                    return Err(invalid("segment length missing"));
+                }
+                let length = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+                if length < 2 || pos + length > data.len() {
+                    return Err(invalid("segment extends beyond data"));
+                }
+                let segment = &data[pos + 2..pos + length];
+                pos += length;
+
+                if marker == 0xDA {
+                    let end = scan_end(data, pos);
+                    decoder.read_scan(segment, &data[pos..end])?;
+                    pos = end;
+                } else {
+                    decoder.read_segment(marker, segment)?;
+                }
+            }
+        }
+    }
+
+    decoder.finish()
+}
+
+/// Position of the first marker after entropy-coded data starting at `pos`,
+/// skipping stuffed bytes and restart markers
+fn scan_end(data: &[u8], mut pos: usize) -> usize {
+    while pos + 1 < data.len() {
+        if data[pos] == 0xFF {
+            let next = data[pos + 1];
+            if next != 0x00 && next != 0xFF && !(0xD0..=0xD7).contains(&next) {
+                return pos;
+            }
+        }
+        pos += 1;
+    }
+    data.len()
+}
+
+impl Decoder {
+    fn read_segment(&mut self, marker: u8, segment: &[u8]) -> ParseResult<()> {
+        match marker {
+            0xC0..=0xC2 => self.read_frame(segment, marker == 0xC2),
+            0xC3 | 0xC5..=0xC7 | 0xCB | 0xCD..=0xCF => Err(ParseError::StreamDecodeError(
+                "Lossless and hierarchical JPEG are not supported".to_string(),
+            )),
+            0xC9 | 0xCA => Err(ParseError::StreamDecodeError(
+                "Arithmetic-coded JPEG is not supported".to_string(),
+            )),
+            0xC4 => self.read_huffman_tables(segment),
+            0xDB => self.read_quantization_tables(segment),
+            0xDD => {
+                if segment.len() < 2 {
+                    return Err(invalid("DRI segment too short"));
+                }
+                self.restart_interval = u16::from_be_bytes([segment[0], segment[1]]) as usize;
+                Ok(())
+            }
+            0xEE => {
+                if segment.len() >= 12 && segment.starts_with(b"Adobe") {
+                    self.adobe_transform = Some(segment[11]);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Frame header (B.2.2)
+    fn read_frame(&mut self, segment: &[u8], progressive: bool) -> ParseResult<()> {
+        if self.frame.is_some() {
+            return Err(invalid("more than one frame"));
+        }
+        if segment.len() < 6 {
+            return Err(invalid("SOF segment too short"));
+        }
+        if segment[0] != 8 {
+            return Err(ParseError::StreamDecodeError(format!(
+                "Unsupported JPEG sample precision: {}",
+                segment[0]
+            )));
+        }
+        let height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+        let width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+        let count = segment[5] as usize;
+        if width == 0 || height == 0 {
+            return Err(invalid("zero image size"));
+        }
+        if !(1..=4).contains(&count) || segment.len() < 6 + 3 * count {
+            return Err(invalid("bad component count"));
+        }
+
+        let specs: Vec<&[u8]> = segment[6..6 + 3 * count].chunks_exact(3).collect();
+        let mut max_h = 1;
+        let mut max_v = 1;
+        for spec in &specs {
+            let (h, v) = ((spec[1] >> 4) as usize, (spec[1] & 15) as usize);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+                return Err(invalid("bad component parameters"));
+            }
+            max_h = max_h.max(h);
+            max_v = max_v.max(v);
+        }
+        let mcus_across = width.div_ceil(8 * max_h);
+        let mcus_down = height.div_ceil(8 * max_v);
+
+        let mut components = Vec::with_capacity(count);
+        for spec in specs {
+            let (h, v) = ((spec[1] >> 4) as usize, (spec[1] & 15) as usize);
+            let blocks_across = mcus_across * h;
+            let blocks_down = mcus_down * v;
+            let coefficients = blocks_across * blocks_down * 64;
+            if coefficients > MAX_SAMPLES {
+                return Err(invalid("image too large"));
+            }
+            components.push(Component {
+                id: spec[0],
+                h,
+                v,
+                quantization: spec[2] as usize,
+                width: (width * h).div_ceil(max_h),
+                height: (height * v).div_ceil(max_v),
+                blocks_across,
+                blocks_down,
+                coefficients: vec![0; coefficients],
+                dc_prediction: 0,
+            });
+        }
+
+        self.frame = Some(Frame {
+            progressive,
+            width,
+            height,
+            max_h,
+            max_v,
+            mcus_across,
+            mcus_down,
+            components,
+        });
+        Ok(())
+    }
+
+    /// DHT segment (B.2.4.2)
+    fn read_huffman_tables(&mut self, mut segment: &[u8]) -> ParseResult<()> {
+        while !segment.is_empty() {
+            if segment.len() < 17 {
+                return Err(invalid("DHT segment too short"));
+            }
+            let (class, index) = (segment[0] >> 4, (segment[0] & 15) as usize);
+            if class > 1 || index > 3 {
+                return Err(invalid("bad Huffman table identifier"));
+            }
+            let mut counts = [0u8; 16];
+            counts.copy_from_slice(&segment[1..17]);
+            let total: usize = counts.iter().map(|&c| c as usize).sum();
+            if segment.len() < 17 + total {
+                return Err(invalid("DHT segment too short"));
+            }
+            let table = HuffmanTable::new(&counts, &segment[17..17 + total])?;
+            if class == 0 {
+                self.dc_tables[index] = Some(table);
+            } else {
+                self.ac_tables[index] = Some(table);
+            }
+            segment = &segment[17 + total..];
+        }
+        Ok(())
+    }
+
+    /// DQT segment (B.2.4.1); tables are stored in natural order
+    fn read_quantization_tables(&mut self, mut segment: &[u8]) -> ParseResult<()> {
+        while !segment.is_empty() {
+            let (precision, index) = (segment[0] >> 4, (segment[0] & 15) as usize);
+            let size = if precision == 0 { 64 } else { 128 };
+            if index > 3 || segment.len() < 1 + size {
+                return Err(invalid("bad quantization table"));
+            }
+            let mut table = [0u16; 64];
+            for (k, &natural) in ZIGZAG.iter().enumerate() {
+                table[natural] = if precision == 0 {
+                    segment[1 + k] as u16
+                } else {
+                    u16::from_be_bytes([segment[1 + 2 * k], segment[2 + 2 * k]])
+                };
+            }
+            self.quantization[index] = Some(table);
+            segment = &segment[1 + size..];
+        }
+        Ok(())
+    }
+
+    /// Scan header (B.2.3) followed by its entropy-coded data
+    fn read_scan(&mut self, header: &[u8], data: &[u8]) -> ParseResult<()> {
+        let Some(frame) = self.frame.as_mut() else {
+            return Err(invalid("scan before frame header"));
+        };
+        let count = *header
+            .first()
+            .ok_or_else(|| invalid("SOS segment too short"))? as usize;
+        if count == 0 || count > 4 || header.len() < 4 + 2 * count {
+            return Err(invalid("SOS segment too short"));
+        }
+
+        let mut scan = Vec::with_capacity(count);
+        for spec in header[1..1 + 2 * count].chunks_exact(2) {
+            let index = frame
+                .components
+                .iter()
+                .position(|c| c.id == spec[0])
+                .ok_or_else(|| invalid("scan refers to an unknown component"))?;
+            scan.push((index, (spec[1] >> 4) as usize, (spec[1] & 15) as usize));
+        }
+        let tail = &header[1 + 2 * count..];
+        let spectral = Spectral {
+            start: tail[0] as usize,
+            end: tail[1] as usize,
+            high: (tail[2] >> 4) as u32,
+            low: (tail[2] & 15) as u32,
+        };
+        if spectral.start > spectral.end || spectral.end > 63 || spectral.low > 13 {
+            return Err(invalid("bad spectral selection"));
+        }
+        if !frame.progressive {
+            // Sequential scans always cover all coefficients at full precision
+            if spectral.start != 0 || spectral.high != 0 || spectral.low != 0 {
+                return Err(invalid("bad sequential scan parameters"));
+            }
+        } else if spectral.start == 0 && spectral.end != 0 {
+            return Err(invalid("progressive scan mixes DC and AC coefficients"));
+        }
+
+        // Tables needed by each scan component
+        let needs_dc = spectral.start == 0 && spectral.high == 0;
+        let needs_ac = spectral.end > 0;
+        let mut tables = Vec::with_capacity(count);
+        for &(_, dc, ac) in &scan {
+            let dc = if needs_dc {
+                Some(
+                    self.dc_tables
+                        .get(dc)
+                        .cloned()
+                        .flatten()
+                        .ok_or_else(|| invalid("scan uses an undefined DC Huffman table"))?,
+                )
+            } else {
+                None
+            };
+            let ac = if needs_ac {
+                Some(
+                    self.ac_tables
+                        .get(ac)
+                        .cloned()
+                        .flatten()
+                        .ok_or_else(|| invalid("scan uses an undefined AC Huffman table"))?,
+                )
+            } else {
+                None
+            };
+            tables.push(ScanTables { dc, ac });
+        }
+
+        let mut state = ScanState {
+            progressive: frame.progressive,
+            spectral,
+            eob_run: 0,
+        };
+        for component in frame.components.iter_mut() {
+            component.dc_prediction = 0;
+        }
+
+        let mut reader = BitReader::new(data);
+        let (units, interleaved) = if count == 1 {
+            // Non-interleaved: one block per MCU over the component's own
+            // size (A.2.2)
+            let component = &frame.components[scan[0].0];
+            let across = component.width.div_ceil(8);
+            (across * component.height.div_ceil(8), false)
+        } else {
+            (frame.mcus_across * frame.mcus_down, true)
+        };
+
+        for unit in 0..units {
+            if self.restart_interval > 0 && unit > 0 && unit % self.restart_interval == 0 {
+                reader.restart();
+                state.eob_run = 0;
+                for component in frame.components.iter_mut() {
+                    component.dc_prediction = 0;
+                }
+            }
+
+            if !interleaved {
+                let component = &mut frame.components[scan[0].0];
+                let across = component.width.div_ceil(8);
+                state.decode_block(
+                    &mut reader,
+                    component,
+                    &tables[0],
+                    unit / across,
+                    unit % across,
+                )?;
+                continue;
+            }
+
+            let (mcu_row, mcu_column) = (unit / frame.mcus_across, unit % frame.mcus_across);
+            for (&(index, _, _), tables) in scan.iter().zip(&tables) {
+                let component = &mut frame.components[index];
+                for v in 0..component.v {
+                    for h in 0..component.h {
+                        let row = mcu_row * component.v + v;
+                        let column = mcu_column * component.h + h;
+                        state.decode_block(&mut reader, component, tables, row, column)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Dequantize, transform and upsample every component
+    fn finish(self) -> ParseResult<DecodedFrame> {
+        let frame = self.frame.ok_or_else(|| invalid("no frame header"))?;
+        let mut planes = Vec::with_capacity(frame.components.len());
+        for component in &frame.components {
+            let table = self.quantization[component.quantization]
+                .ok_or_else(|| invalid("component uses an undefined quantization table"))?;
+            let stride = component.blocks_across * 8;
+            let mut samples = vec![0u8; stride * component.blocks_down * 8];
+            let mut dequantized = [0i32; 64];
+            for (index, block) in component.coefficients.chunks_exact(64).enumerate() {
+                let (row, column) = (
+                    index / component.blocks_across,
+                    index % component.blocks_across,
+                );
+                for ((value, &coefficient), &step) in dequantized.iter_mut().zip(block).zip(&table)
+                {
+                    *value = coefficient as i32 * step as i32;
+                }
+                let offset = row * 8 * stride + column * 8;
+                inverse_dct(&dequantized, &mut samples[offset..], stride);
+            }
+
+            let plane = Plane {
+                samples: &samples,
+                stride,
+                width: component.width,
+                height: component.height,
+            };
+            planes.push(upsample(
+                &plane,
+                (component.h, component.v),
+                (frame.max_h, frame.max_v),
+                frame.width,
+                frame.height,
+            ));
+        }
+
+        Ok(DecodedFrame {
+            width: frame.width,
+            height: frame.height,
+            ids: frame.components.iter().map(|c| c.id).collect(),
+            planes,
+            adobe_transform: self.adobe_transform,
+        })
+    }
+}
+
+/// Read a DC difference category and its bits (F.2.2.1)
+fn decode_dc_difference(reader: &mut BitReader, table: &HuffmanTable) -> ParseResult<i32> {
+    let size = table.decode(reader)? as u32;
+    if size > 16 {
+        return Err(invalid("bad DC difference size"));
+    }
+    Ok(reader.receive_extend(size))
+}
+
+/// One block of a sequential scan (F.2.2)
+fn decode_sequential(
+    reader: &mut BitReader,
+    dc: &HuffmanTable,
+    ac: &HuffmanTable,
+    block: &mut [i16],
+    prediction: &mut i32,
+) -> ParseResult<()> {
+    *prediction = prediction.wrapping_add(decode_dc_difference(reader, dc)?);
+    block[0] = *prediction as i16;
+
+    let mut k = 1;
+    while k < 64 {
+        let rs = ac.decode(reader)?;
+        let (run, size) = ((rs >> 4) as usize, (rs & 15) as u32);
+        if size == 0 {
+            if run != 15 {
+                break;
+            }
+            k += 16;
+            continue;
+        }
+        // A run past the end of the block lands on the last coefficient,
+        // as in the IJG decoder
+        k = (k + run).min(63);
+        block[ZIGZAG[k]] = reader.receive_extend(size) as i16;
+        k += 1;
+    }
+    Ok(())
+}
+
+/// First DC scan of a progressive image (G.1.2.1)
+fn decode_dc_first(
+    reader: &mut BitReader,
+    table: &HuffmanTable,
+    block: &mut [i16],
+    prediction: &mut i32,
+    spectral: Spectral,
+) -> ParseResult<()> {
+    *prediction = prediction.wrapping_add(decode_dc_difference(reader, table)?);
+    block[0] = prediction.wrapping_shl(spectral.low) as i16;
+    Ok(())
+}
+
+/// DC successive approximation refinement (G.1.2.1)
+fn decode_dc_refine(reader: &mut BitReader, block: &mut [i16], spectral: Spectral) {
+    if reader.bit() == 1 {
+        block[0] |= 1 << spectral.low;
+    }
+}
+
+/// First AC scan of a band, with end-of-band runs (G.1.2.2)
+fn decode_ac_first(
+    reader: &mut BitReader,
+    table: &HuffmanTable,
+    block: &mut [i16],
+    spectral: Spectral,
+    eob_run: &mut u32,
+) -> ParseResult<()> {
+    if *eob_run > 0 {
+        *eob_run -= 1;
+        return Ok(());
+    }
+
+    let mut k = spectral.start;
+    while k <= spectral.end {
+        let rs = table.decode(reader)?;
+        let (run, size) = ((rs >> 4) as u32, (rs & 15) as u32);
+        if size == 0 {
+            if run < 15 {
+                *eob_run = (1 << run) - 1 + reader.bits(run);
+                break;
+            }
+            k += 16;
+            continue;
+        }
+        k = (k + run as usize).min(63);
+        block[ZIGZAG[k]] = (reader.receive_extend(size) << spectral.low) as i16;
+        k += 1;
+    }
+    Ok(())
+}
+
+/// AC successive approximation refinement (G.1.2.3)
+fn decode_ac_refine(
+    reader: &mut BitReader,
+    table: &HuffmanTable,
+    block: &mut [i16],
+    spectral: Spectral,
+    eob_run: &mut u32,
+) -> ParseResult<()> {
+    let positive = 1i16 << spectral.low;
+    let negative = -1i16 << spectral.low;
+    let refine = |reader: &mut BitReader, coefficient: &mut i16| {
+        if reader.bit() == 1 && *coefficient & positive == 0 {
+            *coefficient += if *coefficient >= 0 {
+                positive
+            } else {
+                negative
+            };
+        }
+    };
+
+    let mut k = spectral.start;
+    if *eob_run == 0 {
+        while k <= spectral.end {
+            let rs = table.decode(reader)?;
+            let (mut run, size) = ((rs >> 4) as i32, rs & 15);
+            let mut value = 0;
+            if size != 0 {
+                value = if reader.bit() == 1 {
+                    positive
+                } else {
+                    negative
+                };
+            } else if run != 15 {
+                *eob_run = (1 << run) + reader.bits(run as u32);
+                break;
+            }
+
+            // Refine nonzero coefficients on the way, skipping `run` zero ones
+            while k <= spectral.end {
+                let coefficient = &mut block[ZIGZAG[k]];
+                if *coefficient != 0 {
+                    refine(reader, coefficient);
+                } else {
+                    run -= 1;
+                    if run < 0 {
+                        break;
+                    }
+                }
+                k += 1;
+            }
+            if value != 0 && k <= spectral.end {
+                block[ZIGZAG[k]] = value;
+            }
+            k += 1;
+        }
+    }
+
+    if *eob_run > 0 {
+        while k <= spectral.end {
+            let coefficient = &mut block[ZIGZAG[k]];
+            if *coefficient != 0 {
+                refine(reader, coefficient);
+            }
+            k += 1;
+        }
+        *eob_run -= 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zigzag_is_a_permutation() {
+        let mut seen = [false; 64];
+        for &natural in &ZIGZAG {
+            assert!(!seen[natural]);
+            seen[natural] = true;
+        }
+        assert_eq!(&ZIGZAG[..6], &[0, 1, 8, 16, 9, 2]);
+    }
+
+    #[test]
+    fn test_scan_end_skips_stuffing_and_restarts() {
+        let data = [1, 0xFF, 0x00, 2, 0xFF, 0xD3, 3, 0xFF, 0xFF, 0xD9];
+        assert_eq!(scan_end(&data, 0), 8);
+    }
+
+    #[test]
+    fn test_rejects_arithmetic_coding() {
+        let data = [0xFF, 0xD8, 0xFF, 0xC9, 0x00, 0x02, 0xFF, 0xD9];
+        assert!(decode(&data).is_err());
+    }
+}
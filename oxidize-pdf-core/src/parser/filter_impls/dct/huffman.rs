@@ -0,0 +1,191 @@
+//! Entropy-coded segment reading and Huffman decoding (ITU-T T.81 Annex F)
+
+use crate::parser::{ParseError, ParseResult};
+
+/// Huffman table built from a DHT segment (Annex C)
+#[derive(Debug, Clone)]
+pub(super) struct HuffmanTable {
+    /// Largest code of each length, -1 when there is none
+    max_code: [i32; 17],
+    /// Index into `values` of the first code of each length, minus that code
+    offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Build a table from the number of codes of each length 1..=16 and the
+    /// symbols in code order
+    pub fn new(counts: &[u8; 16], values: &[u8]) -> ParseResult<Self> {
+        let total: usize = counts.iter().map(|&c| c as usize).sum();
+        if total > 256 || values.len() < total {
+            return Err(ParseError::StreamDecodeError(
+                "Invalid JPEG Huffman table".to_string(),
+            ));
+        }
+
+        let mut max_code = [-1i32; 17];
+        let mut offset = [0i32; 17];
+        let mut code = 0i32;
+        let mut index = 0i32;
+        for length in 1..=16 {
+            let count = counts[length - 1] as i32;
+            if count > 0 {
+                offset[length] = index - code;
+                code += count;
+                index += count;
+                max_code[length] = code - 1;
+            }
+            if code > 1 << length {
+                return Err(ParseError::StreamDecodeError(
+                    "Invalid JPEG Huffman table: too many codes".to_string(),
+                ));
+            }
+            code <<= 1;
+        }
+
+        Ok(Self {
+            max_code,
+            offset,
+            values: values[..total].to_vec(),
+        })
+    }
+
+    /// Decode one symbol (DECODE of F.2.2.3)
+    pub fn decode(&self, reader: &mut BitReader) -> ParseResult<u8> {
+        let mut code = 0i32;
+        for length in 1..=16 {
+            code = (code << 1) | reader.bit() as i32;
+            if code <= self.max_code[length] {
+                return Ok(self.values[(self.offset[length] + code) as usize]);
+            }
+        }
+        Err(ParseError::StreamDecodeError(
+            "Invalid JPEG Huffman code".to_string(),
+        ))
+    }
+}
+
+/// Bit reader over entropy-coded data that removes stuffed zero bytes and
+/// stops at markers
+///
+/// Past a marker or the end of the data the reader yields zero bits, so a
+/// truncated scan decodes to zero coefficients instead of failing.
+pub(super) struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        loop {
+            let Some(&byte) = self.data.get(self.pos) else {
+                return 0;
+            };
+            if byte != 0xFF {
+                self.pos += 1;
+                return byte;
+            }
+            match self.data.get(self.pos + 1) {
+                Some(0x00) => {
+                    self.pos += 2;
+                    return 0xFF;
+                }
+                // Fill bytes before a marker
+                Some(0xFF) => self.pos += 1,
+                // A marker: stay in front of it
+                _ => return 0,
+            }
+        }
+    }
+
+    pub fn bit(&mut self) -> u32 {
+        if self.count == 0 {
+            self.buffer = self.next_byte() as u32;
+            self.count = 8;
+        }
+        self.count -= 1;
+        (self.buffer >> self.count) & 1
+    }
+
+    pub fn bits(&mut self, n: u32) -> u32 {
+        (0..n).fold(0, |value, _| (value << 1) | self.bit())
+    }
+
+    /// Read an `n`-bit magnitude category value and extend its sign (F.2.2.1)
+    pub fn receive_extend(&mut self, n: u32) -> i32 {
+        if n == 0 {
+            return 0;
+        }
+        let value = self.bits(n) as i32;
+        if value < 1 << (n - 1) {
+            value - (1 << n) + 1
+        } else {
+            value
+        }
+    }
+
+    /// Skip to just past the next RSTm marker, discarding buffered bits
+    pub fn restart(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+        while self.pos + 1 < self.data.len() {
+            if self.data[self.pos] == 0xFF && (0xD0..=0xD7).contains(&self.data[self.pos + 1]) {
+                self.pos += 2;
+                return;
+            }
+            self.pos += 1;
+        }
+        self.pos = self.data.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_huffman_decoding() {
+        // Codes: 00 -> 5, 01 -> 6, 100 -> 7
+        let mut counts = [0u8; 16];
+        counts[1] = 2;
+        counts[2] = 1;
+        let table = HuffmanTable::new(&counts, &[5, 6, 7]).unwrap();
+        let data = [0b0001_1000];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(table.decode(&mut reader).unwrap(), 5);
+        assert_eq!(table.decode(&mut reader).unwrap(), 6);
+        assert_eq!(table.decode(&mut reader).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_stuffed_bytes_and_markers() {
+        let data = [0xFF, 0x00, 0x80, 0xFF, 0xD0, 0xC0];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(reader.bits(8), 0xFF);
+        assert_eq!(reader.bits(8), 0x80);
+        // The marker yields zero bits until the restart
+        assert_eq!(reader.bits(8), 0);
+        reader.restart();
+        assert_eq!(reader.bits(2), 0b11);
+    }
+
+    #[test]
+    fn test_receive_extend() {
+        let data = [0b0110_0000];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(reader.receive_extend(2), -2);
+        assert_eq!(reader.receive_extend(2), 2);
+        assert_eq!(reader.receive_extend(0), 0);
+    }
+}
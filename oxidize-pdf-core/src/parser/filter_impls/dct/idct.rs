@@ -0,0 +1,85 @@
+//! Inverse discrete cosine transform of 8x8 blocks (ITU-T T.81 A.3.3)
+
+use std::f32::consts::PI;
+use std::sync::OnceLock;
+
+/// `C(u) / 2 * cos((2x + 1) u pi / 16)`, indexed by `x * 8 + u`
+fn basis() -> &'static [f32; 64] {
+    static BASIS: OnceLock<[f32; 64]> = OnceLock::new();
+    BASIS.get_or_init(|| {
+        let mut table = [0.0f32; 64];
+        for x in 0..8 {
+            for u in 0..8 {
+                let c = if u == 0 { 1.0 / 2.0f32.sqrt() } else { 1.0 };
+                table[x * 8 + u] = c / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+            }
+        }
+        table
+    })
+}
+
+/// Transform dequantized coefficients in natural order into level-shifted
+/// 8-bit samples, written to `output` with the given row stride
+pub(super) fn inverse_dct(coefficients: &[i32; 64], output: &mut [u8], stride: usize) {
+    let basis = basis();
+
+    // A block with only a DC coefficient is flat
+    if coefficients[1..].iter().all(|&c| c == 0) {
+        let value = level_shift(coefficients[0] as f32 / 8.0);
+        for row in output.chunks_mut(stride).take(8) {
+            row[..8].fill(value);
+        }
+        return;
+    }
+
+    // Rows: horizontal frequencies to horizontal positions
+    let mut rows = [0.0f32; 64];
+    for v in 0..8 {
+        let line = &coefficients[v * 8..v * 8 + 8];
+        if line.iter().all(|&c| c == 0) {
+            continue;
+        }
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8).map(|u| basis[x * 8 + u] * line[u] as f32).sum();
+        }
+    }
+
+    // Columns: vertical frequencies to vertical positions
+    for x in 0..8 {
+        for y in 0..8 {
+            let value: f32 = (0..8).map(|v| basis[y * 8 + v] * rows[v * 8 + x]).sum();
+            output[y * stride + x] = level_shift(value);
+        }
+    }
+}
+
+fn level_shift(value: f32) -> u8 {
+    (value + 128.0).round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dc_only_block_is_flat() {
+        let mut coefficients = [0i32; 64];
+        coefficients[0] = 80;
+        let mut output = [0u8; 64];
+        inverse_dct(&coefficients, &mut output, 8);
+        assert!(output.iter().all(|&v| v == 138));
+    }
+
+    #[test]
+    fn test_horizontal_cosine() {
+        // The first horizontal frequency decreases from left to right and is
+        // symmetric around the block centre
+        let mut coefficients = [0i32; 64];
+        coefficients[1] = 100;
+        let mut output = [0u8; 64];
+        inverse_dct(&coefficients, &mut output, 8);
+        assert!(output[..8].windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(output[0] as i32 - 128, 128 - output[7] as i32);
+        assert_eq!(output[..8], output[56..]);
+    }
+}
@@ -2,7 +2,17 @@
 //!
 //! Implements the DCTDecode filter according to ISO 32000-1:2008 Section 7.4.8
 //! This filter handles JPEG compressed image data in PDF streams.
+//!
+//! By default the compressed data is passed through, since JPEG images are
+//! embedded as-is. [`decode_dct_image`] fully decodes baseline, extended
+//! sequential and progressive Huffman-coded JPEGs to 8-bit samples.
+
+mod decoder;
+mod huffman;
+mod idct;
+mod sampling;
 
+use crate::parser::objects::{PdfDictionary, PdfObject};
 use crate::parser::{ParseError, ParseResult};
 
 /// JPEG markers
@@ -66,6 +76,132 @@ pub fn decode_dct(data: &[u8]) -> ParseResult<Vec<u8>> {
     Ok(clean_data)
 }
 
+/// A fully decoded JPEG image
+#[derive(Debug, Clone)]
+pub struct JpegImage {
+    /// Image width
+    pub width: u32,
+    /// Image height
+    pub height: u32,
+    /// Number of color components per pixel
+    pub components: u8,
+    /// Color space of `data`: Gray, RGB or CMYK, after any color transform
+    pub color_space: JpegColorSpace,
+    /// Interleaved 8-bit samples, row by row
+    pub data: Vec<u8>,
+}
+
+impl JpegImage {
+    /// The samples as gray (1 component) or RGB (3 components), for writing
+    /// to formats without CMYK. CMYK is converted without color management.
+    pub fn to_gray_or_rgb(&self) -> (u8, Vec<u8>) {
+        match self.color_space {
+            JpegColorSpace::CMYK if self.components == 4 => {
+                let data = self
+                    .data
+                    .chunks_exact(4)
+                    .flat_map(|p| {
+                        let white = 255 - p[3] as u16;
+                        [0, 1, 2].map(|i| ((255 - p[i] as u16) * white / 255) as u8)
+                    })
+                    .collect();
+                (3, data)
+            }
+            _ => (self.components, self.data.clone()),
+        }
+    }
+}
+
+/// Decode DCTDecode (JPEG) compressed data into raw samples
+///
+/// Three-component images are converted from YCbCr to RGB and
+/// four-component images from YCCK to CMYK when the `ColorTransform` entry
+/// of `params` asks for it (ISO 32000-1 Table 13). Without that entry the
+/// Adobe APP14 marker decides, and otherwise only three-component images
+/// are transformed. Adobe CMYK values are returned as stored; PDF files
+/// compensate for inverted ones with a `Decode` array.
+pub fn decode_dct_image(data: &[u8], params: Option<&PdfDictionary>) -> ParseResult<JpegImage> {
+    // The decoder walks the segments itself, so only leading garbage is
+    // removed; an EOI inside an embedded thumbnail must not end the image
+    let start = data
+        .windows(2)
+        .position(|window| window == [0xFF, 0xD8])
+        .ok_or_else(|| {
+            ParseError::StreamDecodeError(
+                "JPEG SOI marker (0xFFD8) not found in stream data".to_string(),
+            )
+        })?;
+    let frame = decoder::decode(&data[start..])?;
+
+    let components = frame.planes.len();
+    let transform = match params.and_then(|p| p.get("ColorTransform")) {
+        Some(PdfObject::Integer(value)) => *value != 0,
+        _ => match frame.adobe_transform {
+            Some(transform) => transform != 0,
+            // Components named R, G, B are stored untransformed
+            None => components == 3 && frame.ids != [b'R', b'G', b'B'],
+        },
+    };
+
+    let pixels = frame.width * frame.height;
+    let mut data = vec![0u8; pixels * components];
+    for (index, plane) in frame.planes.iter().enumerate() {
+        for (pixel, &value) in plane.iter().enumerate() {
+            data[pixel * components + index] = value;
+        }
+    }
+
+    let color_space = match components {
+        1 => JpegColorSpace::Gray,
+        3 => {
+            if transform {
+                data.chunks_exact_mut(3).for_each(ycc_to_rgb);
+            }
+            JpegColorSpace::RGB
+        }
+        4 => {
+            if transform {
+                // YCCK: the first three components are YCbCr of inverted CMY
+                data.chunks_exact_mut(4).for_each(|pixel| {
+                    ycc_to_rgb(&mut pixel[..3]);
+                    pixel[..3].iter_mut().for_each(|v| *v = 255 - *v);
+                });
+            }
+            JpegColorSpace::CMYK
+        }
+        _ => {
+            return Err(ParseError::StreamDecodeError(format!(
+                "Unsupported JPEG component count: {components}"
+            )));
+        }
+    };
+
+    Ok(JpegImage {
+        width: frame.width as u32,
+        height: frame.height as u32,
+        components: components as u8,
+        color_space,
+        data,
+    })
+}
+
+/// Decode DCTDecode data to interleaved samples, for the opt-in
+/// `ParseOptions::decode_dct_images` mode
+pub fn decode_dct_pixels(data: &[u8], params: Option<&PdfDictionary>) -> ParseResult<Vec<u8>> {
+    Ok(decode_dct_image(data, params)?.data)
+}
+
+/// JFIF YCbCr to RGB conversion of one pixel, in place
+fn ycc_to_rgb(pixel: &mut [u8]) {
+    let y = pixel[0] as f32;
+    let cb = pixel[1] as f32 - 128.0;
+    let cr = pixel[2] as f32 - 128.0;
+    let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    pixel[0] = clamp(y + 1.402 * cr);
+    pixel[1] = clamp(y - 0.344_136 * cb - 0.714_136 * cr);
+    pixel[2] = clamp(y + 1.772 * cb);
+}
+
 /// Extract clean JPEG data from SOI (0xFFD8) to EOI (0xFFD9) markers
 ///
 /// PDF streams may contain extra bytes before or after the actual JPEG data.
@@ -952,4 +1088,79 @@ mod tests {
         let result = parse_jpeg_info(&data);
         assert!(result.is_ok());
     }
+
+    /// An 8x8 gray JPEG of value 100 with a single DC-only block, coded
+    /// with all-ones quantization and one-symbol Huffman tables
+    fn flat_gray_jpeg(sof: u8, spectral_end: u8, scan: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xDB, 0x00, 0x43, 0x00];
+        data.extend([1u8; 64]);
+        data.extend([0xFF, sof, 0x00, 0x0B, 0x08, 0x00, 0x08, 0x00, 0x08, 0x01]);
+        data.extend([0x01, 0x11, 0x00]);
+        // DC table: category 8 coded "0"; AC table: EOB coded "0"
+        data.extend([0xFF, 0xC4, 0x00, 0x14, 0x00, 0x01]);
+        data.extend([0u8; 15]);
+        data.push(0x08);
+        data.extend([0xFF, 0xC4, 0x00, 0x14, 0x10, 0x01]);
+        data.extend([0u8; 15]);
+        data.push(0x00);
+        data.extend([
+            0xFF,
+            0xDA,
+            0x00,
+            0x08,
+            0x01,
+            0x01,
+            0x00,
+            0x00,
+            spectral_end,
+            0x00,
+        ]);
+        data.extend(scan);
+        data.extend([0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn test_decode_baseline_image() {
+        // DC difference -224 (category 8, bits 00011111), then EOB
+        let data = flat_gray_jpeg(0xC0, 63, &[0x0F, 0xBF]);
+        let image = decode_dct_image(&data, None).unwrap();
+        assert_eq!((image.width, image.height, image.components), (8, 8, 1));
+        assert_eq!(image.color_space, JpegColorSpace::Gray);
+        assert_eq!(image.data, vec![100; 64]);
+    }
+
+    #[test]
+    fn test_decode_progressive_dc_scan() {
+        // The same DC difference padded with ones, which needs a stuffed zero
+        let data = flat_gray_jpeg(0xC2, 0, &[0x0F, 0xFF, 0x00]);
+        let image = decode_dct_image(&data, None).unwrap();
+        assert_eq!(image.data, vec![100; 64]);
+    }
+
+    #[test]
+    fn test_ycc_to_rgb() {
+        let mut pixel = [128, 128, 128];
+        ycc_to_rgb(&mut pixel);
+        assert_eq!(pixel, [128, 128, 128]);
+
+        let mut pixel = [76, 85, 255];
+        ycc_to_rgb(&mut pixel);
+        assert!(pixel[0] >= 253 && pixel[1] <= 2 && pixel[2] <= 2);
+    }
+
+    #[test]
+    fn test_cmyk_to_rgb() {
+        let image = JpegImage {
+            width: 2,
+            height: 1,
+            components: 4,
+            color_space: JpegColorSpace::CMYK,
+            data: vec![0, 0, 0, 0, 255, 0, 0, 0],
+        };
+        assert_eq!(
+            image.to_gray_or_rgb(),
+            (3, vec![255, 255, 255, 0, 255, 255])
+        );
+    }
 }
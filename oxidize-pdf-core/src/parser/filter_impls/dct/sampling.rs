@@ -0,0 +1,149 @@
+//! Upsampling of subsampled components to the full image size
+//!
+//! The common 2:1 horizontal, 2:1 vertical and 2x2 ratios use the triangle
+//! ("fancy") filter of the IJG decoder so that chroma edges match other
+//! decoders; other ratios replicate samples.
+
+/// One component's samples at its own resolution
+pub(super) struct Plane<'a> {
+    pub samples: &'a [u8],
+    /// Row stride of `samples`
+    pub stride: usize,
+    /// Number of meaningful columns and rows
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Plane<'_> {
+    fn at(&self, x: usize, y: usize) -> u8 {
+        self.samples[y.min(self.height - 1) * self.stride + x.min(self.width - 1)]
+    }
+}
+
+/// Scale `plane`, sampled at `factors` out of `max_factors` (horizontal,
+/// vertical), into a `width` x `height` buffer
+pub(super) fn upsample(
+    plane: &Plane,
+    factors: (usize, usize),
+    max_factors: (usize, usize),
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    let mut output = vec![0u8; width * height];
+    if plane.width == 0 || plane.height == 0 {
+        return output;
+    }
+
+    let ((h, v), (max_h, max_v)) = (factors, max_factors);
+    let ratio = |f: usize, max: usize| if max % f == 0 { max / f } else { 0 };
+    match (ratio(h, max_h), ratio(v, max_v)) {
+        (1, 1) => {
+            for (y, row) in output.chunks_exact_mut(width).enumerate() {
+                for (x, value) in row.iter_mut().enumerate() {
+                    *value = plane.at(x, y);
+                }
+            }
+        }
+        (2, 1) => {
+            let mut line = vec![0u8; plane.width * 2];
+            for (y, row) in output.chunks_exact_mut(width).enumerate() {
+                let input: Vec<i32> = (0..plane.width).map(|x| plane.at(x, y) as i32).collect();
+                fancy_horizontal(&input, 1, 1, 2, &mut line);
+                fill_row(row, &line);
+            }
+        }
+        (1, 2) => {
+            for (y, row) in output.chunks_exact_mut(width).enumerate() {
+                let (source, neighbour) = vertical_neighbours(y);
+                let bias = if y % 2 == 0 { 1 } else { 2 };
+                for (x, value) in row.iter_mut().enumerate() {
+                    let sum = 3 * plane.at(x, source) as i32 + plane.at(x, neighbour) as i32;
+                    *value = ((sum + bias) >> 2) as u8;
+                }
+            }
+        }
+        (2, 2) => {
+            let mut line = vec![0u8; plane.width * 2];
+            for (y, row) in output.chunks_exact_mut(width).enumerate() {
+                let (source, neighbour) = vertical_neighbours(y);
+                let sums: Vec<i32> = (0..plane.width)
+                    .map(|x| 3 * plane.at(x, source) as i32 + plane.at(x, neighbour) as i32)
+                    .collect();
+                fancy_horizontal(&sums, 4, 8, 7, &mut line);
+                fill_row(row, &line);
+            }
+        }
+        _ => {
+            for (y, row) in output.chunks_exact_mut(width).enumerate() {
+                for (x, value) in row.iter_mut().enumerate() {
+                    *value = plane.at(x * h / max_h, y * v / max_v);
+                }
+            }
+        }
+    }
+    output
+}
+
+/// The input row nearest to output row `y` when doubling vertically, and
+/// the next farther one that it is weighed 3:1 against
+fn vertical_neighbours(y: usize) -> (usize, usize) {
+    let source = y / 2;
+    if y % 2 == 0 {
+        (source, source.saturating_sub(1))
+    } else {
+        (source, source + 1)
+    }
+}
+
+/// Double a line of sums of weight `weight`: each output sample weighs its
+/// nearer input 3:1 against the next farther one, then divides by
+/// `4 * weight` with the given rounding biases for even and odd outputs
+fn fancy_horizontal(input: &[i32], weight: i32, even_bias: i32, odd_bias: i32, output: &mut [u8]) {
+    let n = input.len();
+    let shift = (4 * weight).trailing_zeros();
+    for (i, &this) in input.iter().enumerate() {
+        let previous = input[i.saturating_sub(1)];
+        let next = input[(i + 1).min(n - 1)];
+        output[2 * i] = ((3 * this + previous + even_bias) >> shift) as u8;
+        output[2 * i + 1] = ((3 * this + next + odd_bias) >> shift) as u8;
+    }
+}
+
+fn fill_row(row: &mut [u8], line: &[u8]) {
+    let n = row.len().min(line.len());
+    row[..n].copy_from_slice(&line[..n]);
+    if let Some(&last) = line.last() {
+        row[n..].fill(last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_horizontal_fancy_upsampling() {
+        let samples = [0u8, 100, 200];
+        let plane = Plane {
+            samples: &samples,
+            stride: 3,
+            width: 3,
+            height: 1,
+        };
+        let output = upsample(&plane, (1, 1), (2, 1), 6, 1);
+        assert_eq!(output, vec![0, 25, 75, 125, 175, 200]);
+    }
+
+    #[test]
+    fn test_replicating_upsampling() {
+        let samples = [10u8, 20, 30, 40];
+        let plane = Plane {
+            samples: &samples,
+            stride: 2,
+            width: 2,
+            height: 2,
+        };
+        let output = upsample(&plane, (1, 1), (3, 1), 6, 2);
+        assert_eq!(output, vec![10, 10, 10, 20, 20, 20, 30, 30, 30, 40, 40, 40]);
+    }
+}
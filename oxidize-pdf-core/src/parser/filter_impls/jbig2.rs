@@ -1,767 +0,0 @@
-//! JBIG2 decode implementation according to ISO 32000-1 Section 7.4.7
-//!
-//! This module provides basic decoding of JBIG2 (Joint Bi-level Image Experts Group)
-//! compressed images as used in PDF streams. JBIG2 is defined in ITU-T T.88.
-
-use crate::parser::objects::PdfDictionary;
-use crate::parser::{ParseError, ParseResult};
-
-/// JBIG2 decode parameters from DecodeParms dictionary
-#[derive(Debug, Clone, Default)]
-pub struct Jbig2DecodeParams {
-    /// JBIG2Globals dictionary containing global data
-    pub jbig2_globals: Option<Vec<u8>>,
-}
-
-impl Jbig2DecodeParams {
-    /// Parse JBIG2 decode parameters from PDF dictionary
-    pub fn from_dict(dict: &PdfDictionary) -> Self {
-        let mut params = Jbig2DecodeParams::default();
-
-        // JBIG2Globals - contains global data stream
-        // In a real implementation, this would extract the global data
-        // For now, we'll just note its presence
-        if dict.contains_key("JBIG2Globals") {
-            // Would extract the global data here
-            params.jbig2_globals = Some(Vec::new());
-        }
-
-        params
-    }
-}
-
-/// JBIG2 segment header information
-#[derive(Debug, Clone)]
-struct Jbig2SegmentHeader {
-    /// Segment number
-    #[allow(dead_code)]
-    segment_number: u32,
-    /// Segment header flags
-    flags: u8,
-    /// Segment type
-    segment_type: u8,
-    /// Page association
-    #[allow(dead_code)]
-    page_association: u32,
-    /// Data length
-    data_length: u32,
-}
-
-/// JBIG2 decoder
-pub struct Jbig2Decoder {
-    params: Jbig2DecodeParams,
-}
-
-impl Jbig2Decoder {
-    /// Create a new JBIG2 decoder
-    pub fn new(params: Jbig2DecodeParams) -> Self {
-        Self { params }
-    }
-
-    /// Decode JBIG2 data
-    pub fn decode(&self, data: &[u8]) -> ParseResult<Vec<u8>> {
-        // Check for JBIG2 file header
-        if data.len() < 9 {
-            return Err(ParseError::StreamDecodeError(
-                "JBIG2 data too short".to_string(),
-            ));
-        }
-
-        // Check file ID (first 8 bytes should be specific pattern)
-        let file_id = &data[0..8];
-        if file_id != [0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A] {
-            // Not a standard JBIG2 file, try to decode as embedded stream
-            return self.decode_embedded_stream(data);
-        }
-
-        // Parse file organization flags
-        let file_org_flags = data[8];
-        let is_sequential = (file_org_flags & 0x01) == 0;
-        let _has_unknown_pages = (file_org_flags & 0x02) != 0;
-
-        if !is_sequential {
-            return Err(ParseError::StreamDecodeError(
-                "Random access JBIG2 files not supported".to_string(),
-            ));
-        }
-
-        // Parse segments sequentially
-        let mut pos = 9;
-        let mut decoded_data = Vec::new();
-
-        while pos < data.len() {
-            // Try to parse segment header, break if not enough data
-            if pos + 11 > data.len() {
-                // Not enough data for minimum segment header
-                break;
-            }
-
-            match self.parse_segment_header(&data[pos..]) {
-                Ok(segment) => {
-                    pos += self.get_segment_header_length(&segment);
-
-                    // Skip the segment data for now
-                    // In a full implementation, we would decode each segment type
-                    if segment.data_length == 0xFFFFFFFF {
-                        // Unknown length - would need to parse until end-of-segment marker
-                        break;
-                    } else {
-                        let segment_end = pos + segment.data_length as usize;
-                        if segment_end > data.len() {
-                            break;
-                        }
-
-                        // For a basic implementation, collect the raw segment data
-                        if segment.segment_type == 0 || segment.segment_type == 4 {
-                            // Symbol dictionary or intermediate text region
-                            decoded_data.extend_from_slice(&data[pos..segment_end]);
-                        }
-
-                        pos = segment_end;
-                    }
-                }
-                Err(_) => {
-                    // Could not parse segment header, break gracefully
-                    break;
-                }
-            }
-        }
-
-        // If we have global data, we would process it here
-        if self.params.jbig2_globals.is_some() {
-            // Process global data
-        }
-
-        // Return the collected data (simplified)
-        if decoded_data.is_empty() {
-            // If no specific segments found, return a portion of the original data
-            let start = if data.len() > 9 { 9 } else { 0 };
-            if start < data.len() {
-                Ok(data[start..].to_vec())
-            } else {
-                // Return minimal data to indicate successful processing
-                Ok(vec![0])
-            }
-        } else {
-            Ok(decoded_data)
-        }
-    }
-
-    /// Decode JBIG2 data embedded in PDF stream (without file header)
-    fn decode_embedded_stream(&self, data: &[u8]) -> ParseResult<Vec<u8>> {
-        // For embedded streams, the data starts directly with segments
-        let mut pos = 0;
-        let mut decoded_data = Vec::new();
-
-        // Try to parse segments
-        while pos + 11 <= data.len() {
-            // Minimum segment header is 11 bytes
-            if let Ok(segment) = self.parse_segment_header(&data[pos..]) {
-                let header_len = self.get_segment_header_length(&segment);
-                pos += header_len;
-
-                if segment.data_length != 0xFFFFFFFF {
-                    let segment_end = pos + segment.data_length as usize;
-                    if segment_end <= data.len() {
-                        // Extract segment data based on type
-                        match segment.segment_type {
-                            0 => {
-                                // Symbol dictionary
-                                decoded_data.extend_from_slice(&data[pos..segment_end]);
-                            }
-                            4 | 6 | 7 => {
-                                // Text region (immediate, intermediate, or lossless)
-                                decoded_data.extend_from_slice(&data[pos..segment_end]);
-                            }
-                            36 | 38 | 39 => {
-                                // Halftone region
-                                decoded_data.extend_from_slice(&data[pos..segment_end]);
-                            }
-                            _ => {
-                                // Other segment types - skip for now
-                            }
-                        }
-                        pos = segment_end;
-                    } else {
-                        break;
-                    }
-                } else {
-                    // Unknown length
-                    break;
-                }
-            } else {
-                // Not a valid segment header, might be raw data
-                decoded_data.extend_from_slice(&data[pos..]);
-                break;
-            }
-        }
-
-        // If no segments were found, return the data as-is (basic fallback)
-        if decoded_data.is_empty() {
-            Ok(data.to_vec())
-        } else {
-            Ok(decoded_data)
-        }
-    }
-
-    /// Parse JBIG2 segment header
-    fn parse_segment_header(&self, data: &[u8]) -> ParseResult<Jbig2SegmentHeader> {
-        if data.len() < 11 {
-            return Err(ParseError::StreamDecodeError(
-                "JBIG2 segment header too short".to_string(),
-            ));
-        }
-
-        let segment_number = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-        let flags = data[4];
-        let segment_type = flags & 0x3F;
-
-        // Parse page association (depends on flags)
-        let page_association = if (flags & 0x40) != 0 {
-            // 4-byte page association
-            u32::from_be_bytes([data[5], data[6], data[7], data[8]])
-        } else {
-            // 1-byte page association
-            data[5] as u32
-        };
-
-        // Parse data length
-        let data_length_start = if (flags & 0x40) != 0 { 9 } else { 6 };
-        if data.len() < data_length_start + 4 {
-            return Err(ParseError::StreamDecodeError(
-                "JBIG2 segment header incomplete".to_string(),
-            ));
-        }
-
-        let data_length = u32::from_be_bytes([
-            data[data_length_start],
-            data[data_length_start + 1],
-            data[data_length_start + 2],
-            data[data_length_start + 3],
-        ]);
-
-        Ok(Jbig2SegmentHeader {
-            segment_number,
-            flags,
-            segment_type,
-            page_association,
-            data_length,
-        })
-    }
-
-    /// Get the length of a segment header
-    fn get_segment_header_length(&self, segment: &Jbig2SegmentHeader) -> usize {
-        // Base header is 11 bytes, but can be shorter for short form
-        let has_long_page_assoc = (segment.flags & 0x40) != 0;
-
-        // Add referred-to segments count if present
-        // This is a simplified calculation
-        if has_long_page_assoc {
-            11
-        } else {
-            7
-        }
-    }
-}
-
-/// Main JBIG2 decode function
-pub fn decode_jbig2(data: &[u8], params: Option<&PdfDictionary>) -> ParseResult<Vec<u8>> {
-    let decode_params = if let Some(dict) = params {
-        Jbig2DecodeParams::from_dict(dict)
-    } else {
-        Jbig2DecodeParams::default()
-    };
-
-    let decoder = Jbig2Decoder::new(decode_params);
-    decoder.decode(data)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::parser::objects::PdfObject;
-
-    #[test]
-    fn test_jbig2_decode_params_default() {
-        let params = Jbig2DecodeParams::default();
-        assert!(params.jbig2_globals.is_none());
-    }
-
-    #[test]
-    fn test_jbig2_decode_params_from_dict() {
-        let mut dict = PdfDictionary::new();
-        dict.insert("JBIG2Globals".to_string(), PdfObject::Reference(10, 0));
-
-        let params = Jbig2DecodeParams::from_dict(&dict);
-        assert!(params.jbig2_globals.is_some());
-    }
-
-    #[test]
-    fn test_jbig2_decoder_creation() {
-        let params = Jbig2DecodeParams::default();
-        let decoder = Jbig2Decoder::new(params);
-        assert!(decoder.params.jbig2_globals.is_none());
-    }
-
-    #[test]
-    fn test_jbig2_decode_too_short() {
-        let data = vec![0x01, 0x02, 0x03]; // Too short
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_jbig2_decode_embedded_stream() {
-        // Test with non-JBIG2 header (embedded stream)
-        let data = vec![0x00; 50]; // Some test data
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_jbig2_decode_with_file_header() {
-        // Test with JBIG2 file header but minimal data
-        // In a real implementation, this would need valid segments
-        let mut data = vec![0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A]; // File ID
-        data.push(0x00); // File organization flags (sequential)
-
-        // For this basic implementation, expect it to handle gracefully
-        let result = decode_jbig2(&data, None);
-        // This should return some data (even if minimal) rather than fail
-        assert!(result.is_ok());
-        let decoded = result.unwrap();
-        assert!(!decoded.is_empty()); // Should return some data
-    }
-
-    #[test]
-    fn test_jbig2_segment_header_parsing() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Create a minimal segment header
-        let data = vec![
-            0x00, 0x00, 0x00, 0x01, // Segment number: 1
-            0x04, // Flags: segment type 4, short page association
-            0x00, // Page association: 0
-            0x00, 0x00, 0x00, 0x10, // Data length: 16
-            0x00, // Extra byte to meet minimum length
-        ];
-
-        let result = decoder.parse_segment_header(&data);
-        assert!(result.is_ok());
-
-        let segment = result.unwrap();
-        assert_eq!(segment.segment_number, 1);
-        assert_eq!(segment.segment_type, 4);
-        assert_eq!(segment.page_association, 0);
-        assert_eq!(segment.data_length, 16);
-    }
-
-    #[test]
-    fn test_jbig2_segment_header_too_short() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-        let data = vec![0x00, 0x01, 0x02]; // Too short
-
-        let result = decoder.parse_segment_header(&data);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_jbig2_decode_no_params() {
-        let data = vec![0x00; 100]; // Some test data
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_jbig2_decode_with_globals() {
-        let mut dict = PdfDictionary::new();
-        dict.insert("JBIG2Globals".to_string(), PdfObject::Reference(5, 0));
-
-        let data = vec![0x00; 100]; // Some test data
-        let result = decode_jbig2(&data, Some(&dict));
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_jbig2_file_id_check() {
-        let mut correct_id = vec![0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A];
-        correct_id.push(0x00); // File org flags
-        correct_id.extend_from_slice(&[0x00; 20]); // More data
-
-        let result = decode_jbig2(&correct_id, None);
-        assert!(result.is_ok());
-
-        // Test with wrong ID
-        let mut wrong_id = vec![0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
-        wrong_id.push(0x00);
-        wrong_id.extend_from_slice(&[0x00; 20]);
-
-        let result2 = decode_jbig2(&wrong_id, None);
-        assert!(result2.is_ok()); // Should fall back to embedded stream decoding
-    }
-
-    #[test]
-    fn test_jbig2_segment_types() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Test different segment types
-        let segment_types = vec![0, 4, 6, 7, 36, 38, 39];
-
-        for seg_type in segment_types {
-            let mut data = vec![
-                0x00, 0x00, 0x00, 0x01,     // Segment number
-                seg_type, // Flags with segment type
-                0x00,     // Page association
-                0x00, 0x00, 0x00, 0x08, // Data length: 8
-                0x00, // Padding
-            ];
-            data.extend_from_slice(&[0xFF; 8]); // Segment data
-
-            let result = decoder.parse_segment_header(&data);
-            assert!(result.is_ok());
-
-            let segment = result.unwrap();
-            assert_eq!(segment.segment_type, seg_type);
-        }
-    }
-
-    // Additional comprehensive tests
-
-    #[test]
-    fn test_jbig2_decode_params_clone() {
-        let params1 = Jbig2DecodeParams {
-            jbig2_globals: Some(vec![1, 2, 3]),
-        };
-
-        let params2 = params1.clone();
-        assert_eq!(params1.jbig2_globals, params2.jbig2_globals);
-    }
-
-    #[test]
-    fn test_jbig2_decode_params_debug() {
-        let params = Jbig2DecodeParams::default();
-        let debug_str = format!("{params:?}");
-        assert!(debug_str.contains("Jbig2DecodeParams"));
-    }
-
-    #[test]
-    fn test_jbig2_segment_header_debug() {
-        let segment = Jbig2SegmentHeader {
-            segment_number: 42,
-            flags: 0x45,
-            segment_type: 5,
-            page_association: 1,
-            data_length: 1024,
-        };
-
-        let debug_str = format!("{segment:?}");
-        assert!(debug_str.contains("Jbig2SegmentHeader"));
-        assert!(debug_str.contains("segment_number: 42"));
-    }
-
-    #[test]
-    fn test_jbig2_segment_header_clone() {
-        let segment1 = Jbig2SegmentHeader {
-            segment_number: 42,
-            flags: 0x45,
-            segment_type: 5,
-            page_association: 1,
-            data_length: 1024,
-        };
-
-        let segment2 = segment1.clone();
-        assert_eq!(segment1.segment_number, segment2.segment_number);
-        assert_eq!(segment1.flags, segment2.flags);
-        assert_eq!(segment1.segment_type, segment2.segment_type);
-        assert_eq!(segment1.page_association, segment2.page_association);
-        assert_eq!(segment1.data_length, segment2.data_length);
-    }
-
-    #[test]
-    fn test_jbig2_decode_empty_data() {
-        let data = vec![];
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("too short"));
-    }
-
-    #[test]
-    fn test_jbig2_decode_params_from_empty_dict() {
-        let dict = PdfDictionary::new();
-        let params = Jbig2DecodeParams::from_dict(&dict);
-        assert!(params.jbig2_globals.is_none());
-    }
-
-    #[test]
-    fn test_jbig2_decode_non_sequential() {
-        let mut data = vec![0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A]; // File ID
-        data.push(0x01); // File organization flags (random access)
-
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Random access"));
-    }
-
-    #[test]
-    fn test_jbig2_segment_header_long_page_association() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Create segment header with long page association
-        let data = vec![
-            0x00, 0x00, 0x00, 0x02, // Segment number: 2
-            0x44, // Flags: segment type 4, long page association (bit 6 set)
-            0x00, 0x00, 0x00, 0x10, // Page association: 16 (4 bytes)
-            0x00, 0x00, 0x00, 0x20, // Data length: 32
-        ];
-
-        let result = decoder.parse_segment_header(&data);
-        assert!(result.is_ok());
-
-        let segment = result.unwrap();
-        assert_eq!(segment.segment_number, 2);
-        assert_eq!(segment.segment_type, 4);
-        assert_eq!(segment.page_association, 16);
-        assert_eq!(segment.data_length, 32);
-    }
-
-    #[test]
-    fn test_jbig2_segment_header_incomplete() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Create incomplete segment header (missing data length)
-        let data = vec![
-            0x00, 0x00, 0x00, 0x03, // Segment number: 3
-            0x44, // Flags: long page association
-            0x00, 0x00, 0x00,
-            0x05, // Page association
-                  // Missing data length
-        ];
-
-        let result = decoder.parse_segment_header(&data);
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("incomplete") || error_msg.contains("too short"));
-    }
-
-    #[test]
-    fn test_jbig2_get_segment_header_length() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Test short form header
-        let short_segment = Jbig2SegmentHeader {
-            segment_number: 1,
-            flags: 0x04, // No long page association
-            segment_type: 4,
-            page_association: 0,
-            data_length: 10,
-        };
-        assert_eq!(decoder.get_segment_header_length(&short_segment), 7);
-
-        // Test long form header
-        let long_segment = Jbig2SegmentHeader {
-            segment_number: 2,
-            flags: 0x44, // Long page association
-            segment_type: 4,
-            page_association: 100,
-            data_length: 20,
-        };
-        assert_eq!(decoder.get_segment_header_length(&long_segment), 11);
-    }
-
-    #[test]
-    fn test_jbig2_decode_with_segments() {
-        let mut data = vec![0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A]; // File ID
-        data.push(0x00); // Sequential
-
-        // Add a valid segment
-        data.extend_from_slice(&[
-            0x00, 0x00, 0x00, 0x01, // Segment number: 1
-            0x00, // Flags: segment type 0 (symbol dictionary)
-            0x00, // Page association: 0
-            0x00, 0x00, 0x00, 0x04, // Data length: 4
-            0x01, 0x02, 0x03, 0x04, // Segment data
-        ]);
-
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_ok());
-        let decoded = result.unwrap();
-        assert!(!decoded.is_empty());
-    }
-
-    #[test]
-    fn test_jbig2_decode_segment_with_unknown_length() {
-        let mut data = vec![0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A]; // File ID
-        data.push(0x00); // Sequential
-
-        // Add segment with unknown length
-        data.extend_from_slice(&[
-            0x00, 0x00, 0x00, 0x01, // Segment number: 1
-            0x00, // Flags
-            0x00, // Page association
-            0xFF, 0xFF, 0xFF, 0xFF, // Data length: unknown (0xFFFFFFFF)
-        ]);
-
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_jbig2_decode_embedded_with_segments() {
-        let mut data = vec![];
-
-        // Add multiple segments of different types
-        let segments = vec![
-            (0, vec![0xAA, 0xBB]),  // Symbol dictionary
-            (4, vec![0xCC, 0xDD]),  // Text region
-            (36, vec![0xEE, 0xFF]), // Halftone region
-        ];
-
-        for (seg_type, seg_data) in segments {
-            data.extend_from_slice(&[
-                0x00,
-                0x00,
-                0x00,
-                0x01,     // Segment number
-                seg_type, // Flags with segment type
-                0x00,     // Page association
-                0x00,
-                0x00,
-                0x00,
-                seg_data.len() as u8, // Data length
-            ]);
-            data.extend_from_slice(&seg_data);
-        }
-
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_ok());
-        let decoded = result.unwrap();
-        assert!(!decoded.is_empty());
-    }
-
-    #[test]
-    fn test_jbig2_decode_segment_beyond_data() {
-        let mut data = vec![0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A]; // File ID
-        data.push(0x00); // Sequential
-
-        // Add segment with data length beyond available data
-        data.extend_from_slice(&[
-            0x00, 0x00, 0x00, 0x01, // Segment number
-            0x00, // Flags
-            0x00, // Page association
-            0x00, 0x00, 0x10, 0x00, // Data length: 4096 (too large)
-        ]);
-
-        let result = decode_jbig2(&data, None);
-        assert!(result.is_ok()); // Should handle gracefully
-    }
-
-    #[test]
-    fn test_jbig2_decoder_with_globals() {
-        let params = Jbig2DecodeParams {
-            jbig2_globals: Some(vec![0x01, 0x02, 0x03]),
-        };
-        let decoder = Jbig2Decoder::new(params);
-
-        let data = vec![0x00; 50];
-        let result = decoder.decode(&data);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_jbig2_embedded_stream_no_valid_segments() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Data that doesn't parse as valid segments
-        let data = vec![0xFF, 0xFE, 0xFD, 0xFC, 0xFB, 0xFA];
-        let result = decoder.decode_embedded_stream(&data);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), data); // Should return data as-is
-    }
-
-    #[test]
-    fn test_jbig2_segment_type_extraction() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Test that segment type is properly extracted from flags
-        let data = vec![
-            0x00, 0x00, 0x00, 0x01, // Segment number
-            0x3F, // Flags: segment type 63 (0x3F = 0b00111111)
-            0x00, // Page association
-            0x00, 0x00, 0x00, 0x00, // Data length
-            0x00, // Padding
-        ];
-
-        let result = decoder.parse_segment_header(&data);
-        assert!(result.is_ok());
-        let segment = result.unwrap();
-        assert_eq!(segment.segment_type, 63);
-    }
-
-    #[test]
-    fn test_jbig2_all_text_region_types() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Test text region segment types (4, 6, 7)
-        for text_type in [4, 6, 7] {
-            let mut data = vec![];
-            data.extend_from_slice(&[
-                0x00, 0x00, 0x00, 0x01,      // Segment number
-                text_type, // Flags with segment type
-                0x00,      // Page association
-                0x00, 0x00, 0x00, 0x02, // Data length: 2
-                0xAB, 0xCD, // Segment data
-            ]);
-
-            let result = decoder.decode_embedded_stream(&data);
-            // Text region types are not fully implemented, so accept either result
-            assert!(result.is_ok() || result.is_err());
-        }
-    }
-
-    #[test]
-    fn test_jbig2_halftone_region_types() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Test halftone region segment types (36, 38, 39)
-        for halftone_type in [36, 38, 39] {
-            let mut data = vec![];
-            data.extend_from_slice(&[
-                0x00,
-                0x00,
-                0x00,
-                0x01,          // Segment number
-                halftone_type, // Flags with segment type
-                0x00,          // Page association
-                0x00,
-                0x00,
-                0x00,
-                0x02, // Data length: 2
-                0x12,
-                0x34, // Segment data
-            ]);
-
-            let result = decoder.decode_embedded_stream(&data);
-            // Halftone region types are not fully implemented, so accept either result
-            assert!(result.is_ok() || result.is_err());
-        }
-    }
-
-    #[test]
-    fn test_jbig2_skip_unknown_segment_types() {
-        let decoder = Jbig2Decoder::new(Jbig2DecodeParams::default());
-
-        // Test that unknown segment types are skipped
-        let mut data = vec![];
-        data.extend_from_slice(&[
-            0x00, 0x00, 0x00, 0x01, // Segment number
-            50,   // Unknown segment type
-            0x00, // Page association
-            0x00, 0x00, 0x00, 0x02, // Data length: 2
-            0x99, 0x88, // Segment data (should be skipped)
-        ]);
-
-        let result = decoder.decode_embedded_stream(&data);
-        // Unknown segment types may or may not be handled properly
-        assert!(result.is_ok() || result.is_err());
-    }
-}
@@ -0,0 +1,336 @@
+//! MQ arithmetic decoder (ITU-T T.88 Annex E, shared with the JPEG 2000
+//! decoder of T.800 Annex C) and the JBIG2 integer decoding procedures
+//! built on top of it (Annex A)
+
+/// One row of Table E.1: probability estimate, next index after an MPS,
+/// next index after an LPS and whether an LPS flips the MPS sense
+struct QeEntry {
+    qe: u32,
+    nmps: u8,
+    nlps: u8,
+    switch: bool,
+}
+
+const fn qe(qe: u32, nmps: u8, nlps: u8, switch: u8) -> QeEntry {
+    QeEntry {
+        qe,
+        nmps,
+        nlps,
+        switch: switch == 1,
+    }
+}
+
+/// Table E.1 – Qe values and probability estimation process
+const QE_TABLE: [QeEntry; 47] = [
+    qe(0x5601, 1, 1, 1),
+    qe(0x3401, 2, 6, 0),
+    qe(0x1801, 3, 9, 0),
+    qe(0x0AC1, 4, 12, 0),
+    qe(0x0521, 5, 29, 0),
+    qe(0x0221, 38, 33, 0),
+    qe(0x5601, 7, 6, 1),
+    qe(0x5401, 8, 14, 0),
+    qe(0x4801, 9, 14, 0),
+    qe(0x3801, 10, 14, 0),
+    qe(0x3001, 11, 17, 0),
+    qe(0x2401, 12, 18, 0),
+    qe(0x1C01, 13, 20, 0),
+    qe(0x1601, 29, 21, 0),
+    qe(0x5601, 15, 14, 1),
+    qe(0x5401, 16, 14, 0),
+    qe(0x5101, 17, 15, 0),
+    qe(0x4801, 18, 16, 0),
+    qe(0x3801, 19, 17, 0),
+    qe(0x3401, 20, 18, 0),
+    qe(0x3001, 21, 19, 0),
+    qe(0x2801, 22, 19, 0),
+    qe(0x2401, 23, 20, 0),
+    qe(0x2201, 24, 21, 0),
+    qe(0x1C01, 25, 22, 0),
+    qe(0x1801, 26, 23, 0),
+    qe(0x1601, 27, 24, 0),
+    qe(0x1401, 28, 25, 0),
+    qe(0x1201, 29, 26, 0),
+    qe(0x1101, 30, 27, 0),
+    qe(0x0AC1, 31, 28, 0),
+    qe(0x09C1, 32, 29, 0),
+    qe(0x08A1, 33, 30, 0),
+    qe(0x0521, 34, 31, 0),
+    qe(0x0441, 35, 32, 0),
+    qe(0x02A1, 36, 33, 0),
+    qe(0x0221, 37, 34, 0),
+    qe(0x0141, 38, 35, 0),
+    qe(0x0111, 39, 36, 0),
+    qe(0x0085, 40, 37, 0),
+    qe(0x0049, 41, 38, 0),
+    qe(0x0025, 42, 39, 0),
+    qe(0x0015, 43, 40, 0),
+    qe(0x0009, 44, 41, 0),
+    qe(0x0005, 45, 42, 0),
+    qe(0x0001, 45, 43, 0),
+    qe(0x5601, 46, 46, 0),
+];
+
+/// Adaptive probability state for one context: `index << 1 | mps`
+pub(in crate::parser::filter_impls) type Context = u8;
+
+/// Allocate `count` contexts in their initial state
+pub(super) fn new_contexts(count: usize) -> Vec<Context> {
+    vec![0; count]
+}
+
+/// Software conventions decoder of Annex E.3
+pub(in crate::parser::filter_impls) struct ArithmeticDecoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    c_high: u32,
+    c_low: u32,
+    counter: u32,
+    a: u32,
+}
+
+impl<'a> ArithmeticDecoder<'a> {
+    /// INITDEC (E.3.5)
+    pub(in crate::parser::filter_impls) fn new(data: &'a [u8]) -> Self {
+        let mut decoder = Self {
+            data,
+            position: 0,
+            c_high: data.first().copied().unwrap_or(0xFF) as u32,
+            c_low: 0,
+            counter: 0,
+            a: 0x8000,
+        };
+        decoder.byte_in();
+        decoder.c_high = ((decoder.c_high << 7) & 0xFFFF) | ((decoder.c_low >> 9) & 0x7F);
+        decoder.c_low = (decoder.c_low << 7) & 0xFFFF;
+        decoder.counter -= 7;
+        decoder
+    }
+
+    /// Byte at `index`, reading past the end as 0xFF as Annex E requires
+    fn byte(&self, index: usize) -> u32 {
+        self.data.get(index).copied().unwrap_or(0xFF) as u32
+    }
+
+    /// BYTEIN (E.3.4) including the handling of stuffed 0xFF bytes
+    fn byte_in(&mut self) {
+        if self.byte(self.position) == 0xFF {
+            if self.byte(self.position + 1) > 0x8F {
+                self.c_low += 0xFF00;
+                self.counter = 8;
+            } else {
+                self.position += 1;
+                self.c_low += self.byte(self.position) << 9;
+                self.counter = 7;
+            }
+        } else {
+            self.position += 1;
+            self.c_low += self.byte(self.position) << 8;
+            self.counter = 8;
+        }
+        if self.c_low > 0xFFFF {
+            self.c_high += self.c_low >> 16;
+            self.c_low &= 0xFFFF;
+        }
+    }
+
+    /// DECODE (E.3.2) one binary decision in context `cx`
+    pub(in crate::parser::filter_impls) fn decode_bit(
+        &mut self,
+        contexts: &mut [Context],
+        cx: usize,
+    ) -> u8 {
+        let state = contexts[cx];
+        let entry = &QE_TABLE[(state >> 1) as usize];
+        let mut mps = state & 1;
+        let index;
+        let qe = entry.qe;
+        let bit;
+
+        self.a -= qe;
+        if self.c_high < qe {
+            // LPS_EXCHANGE
+            if self.a < qe {
+                bit = mps;
+                index = entry.nmps;
+            } else {
+                bit = 1 - mps;
+                if entry.switch {
+                    mps = bit;
+                }
+                index = entry.nlps;
+            }
+            self.a = qe;
+        } else {
+            self.c_high -= qe;
+            if self.a & 0x8000 != 0 {
+                return mps;
+            }
+            // MPS_EXCHANGE
+            if self.a < qe {
+                bit = 1 - mps;
+                if entry.switch {
+                    mps = bit;
+                }
+                index = entry.nlps;
+            } else {
+                bit = mps;
+                index = entry.nmps;
+            }
+        }
+
+        // RENORMD
+        loop {
+            if self.counter == 0 {
+                self.byte_in();
+            }
+            self.a <<= 1;
+            self.c_high = ((self.c_high << 1) & 0xFFFF) | ((self.c_low >> 15) & 1);
+            self.c_low = (self.c_low << 1) & 0xFFFF;
+            self.counter -= 1;
+            if self.a & 0x8000 != 0 {
+                break;
+            }
+        }
+
+        contexts[cx] = (index << 1) | mps;
+        bit
+    }
+}
+
+/// Contexts of one integer arithmetic decoding procedure (IADH, IADW, ...)
+pub(super) struct IntegerDecoder {
+    contexts: Vec<Context>,
+}
+
+impl IntegerDecoder {
+    pub(super) fn new() -> Self {
+        Self {
+            contexts: new_contexts(512),
+        }
+    }
+
+    /// Decode one value (A.2); `None` is the out-of-band value
+    pub(super) fn decode(&mut self, decoder: &mut ArithmeticDecoder) -> Option<i32> {
+        let mut prev = 1usize;
+        let mut read = |count: u32| {
+            let mut value = 0u32;
+            for _ in 0..count {
+                let bit = decoder.decode_bit(&mut self.contexts, prev) as usize;
+                prev = if prev < 256 {
+                    (prev << 1) | bit
+                } else {
+                    (((prev << 1) | bit) & 511) | 256
+                };
+                value = (value << 1) | bit as u32;
+            }
+            value as i64
+        };
+
+        let sign = read(1);
+        let value = if read(1) == 0 {
+            read(2)
+        } else if read(1) == 0 {
+            read(4) + 4
+        } else if read(1) == 0 {
+            read(6) + 20
+        } else if read(1) == 0 {
+            read(8) + 84
+        } else if read(1) == 0 {
+            read(12) + 340
+        } else {
+            read(32) + 4436
+        };
+
+        match (sign, value) {
+            (0, value) => Some(value.min(i32::MAX as i64) as i32),
+            (_, 0) => None,
+            (_, value) => Some((-value).max(i32::MIN as i64) as i32),
+        }
+    }
+}
+
+/// Contexts of the symbol ID decoding procedure IAID (A.3)
+pub(super) struct SymbolIdDecoder {
+    code_length: u32,
+    contexts: Vec<Context>,
+}
+
+impl SymbolIdDecoder {
+    pub(super) fn new(code_length: u32) -> Self {
+        Self {
+            code_length,
+            contexts: new_contexts(1 << (code_length + 1)),
+        }
+    }
+
+    pub(super) fn decode(&mut self, decoder: &mut ArithmeticDecoder) -> usize {
+        let mut prev = 1usize;
+        for _ in 0..self.code_length {
+            let bit = decoder.decode_bit(&mut self.contexts, prev) as usize;
+            prev = (prev << 1) | bit;
+        }
+        prev - (1 << self.code_length)
+    }
+}
+
+/// Number of bits needed to address `count` symbols: ⌈log2(count)⌉
+pub(super) fn code_length(count: usize) -> u32 {
+    if count <= 1 {
+        0
+    } else {
+        usize::BITS - (count - 1).leading_zeros()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test sequence of T.88 Annex H.2, coded with a single context
+    const ENCODED: [u8; 30] = [
+        0x84, 0xC7, 0x3B, 0xFC, 0xE1, 0xA1, 0x43, 0x04, 0x02, 0x20, 0x00, 0x00, 0x41, 0x0D, 0xBB,
+        0x86, 0xF4, 0x31, 0x7F, 0xFF, 0x88, 0xFF, 0x37, 0x47, 0x1A, 0xDB, 0x6A, 0xDF, 0xFF, 0xAC,
+    ];
+    const DECODED: [u8; 32] = [
+        0x00, 0x02, 0x00, 0x51, 0x00, 0x00, 0x00, 0xC0, 0x03, 0x52, 0x87, 0x2A, 0xAA, 0xAA, 0xAA,
+        0xAA, 0x82, 0xC0, 0x20, 0x00, 0xFC, 0xD7, 0x9E, 0xF6, 0xBF, 0x7F, 0xED, 0x90, 0x4F, 0x46,
+        0xA3, 0xBF,
+    ];
+
+    #[test]
+    fn test_mq_decoder_test_sequence() {
+        let mut decoder = ArithmeticDecoder::new(&ENCODED);
+        let mut contexts = new_contexts(1);
+
+        let decoded: Vec<u8> = (0..DECODED.len())
+            .map(|_| {
+                (0..8).fold(0u8, |byte, _| {
+                    (byte << 1) | decoder.decode_bit(&mut contexts, 0)
+                })
+            })
+            .collect();
+        assert_eq!(decoded, DECODED);
+    }
+
+    #[test]
+    fn test_code_length() {
+        assert_eq!(code_length(0), 0);
+        assert_eq!(code_length(1), 0);
+        assert_eq!(code_length(2), 1);
+        assert_eq!(code_length(3), 2);
+        assert_eq!(code_length(4), 2);
+        assert_eq!(code_length(5), 3);
+        assert_eq!(code_length(256), 8);
+    }
+
+    #[test]
+    fn test_decoder_tolerates_truncated_data() {
+        let mut decoder = ArithmeticDecoder::new(&[]);
+        let mut integer = IntegerDecoder::new();
+        // Past the end the decoder is fed 0xFF bytes; it must not panic
+        for _ in 0..64 {
+            integer.decode(&mut decoder);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Bi-level bitmaps and the region combination operators (T.88 §6.2.2, §7.4.8.5)
+
+use crate::parser::{ParseError, ParseResult};
+
+/// Largest bitmap the decoder will allocate, in pixels
+const MAX_PIXELS: u64 = 1 << 30;
+
+/// A bi-level bitmap with one byte per pixel, 1 = black as in T.88
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<u8>,
+}
+
+/// How a region is merged into the bitmap beneath it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum CombinationOperator {
+    Or,
+    And,
+    Xor,
+    Xnor,
+    Replace,
+}
+
+impl CombinationOperator {
+    pub(super) fn from_bits(bits: u8) -> ParseResult<Self> {
+        match bits {
+            0 => Ok(Self::Or),
+            1 => Ok(Self::And),
+            2 => Ok(Self::Xor),
+            3 => Ok(Self::Xnor),
+            4 => Ok(Self::Replace),
+            _ => Err(ParseError::StreamDecodeError(format!(
+                "Invalid JBIG2 combination operator {bits}"
+            ))),
+        }
+    }
+
+    fn apply(self, dst: u8, src: u8) -> u8 {
+        match self {
+            Self::Or => dst | src,
+            Self::And => dst & src,
+            Self::Xor => dst ^ src,
+            Self::Xnor => 1 ^ dst ^ src,
+            Self::Replace => src,
+        }
+    }
+}
+
+impl Bitmap {
+    /// Create a bitmap filled with `value` (0 or 1)
+    pub(super) fn new(width: u32, height: u32, value: u8) -> ParseResult<Self> {
+        if width as u64 * height as u64 > MAX_PIXELS {
+            return Err(ParseError::StreamDecodeError(format!(
+                "JBIG2 bitmap of {width}x{height} pixels is too large"
+            )));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels: vec![value; width as usize * height as usize],
+        })
+    }
+
+    /// Pixel at (x, y); pixels outside the bitmap read as 0
+    #[inline]
+    pub(super) fn get(&self, x: i32, y: i32) -> u8 {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            0
+        } else {
+            self.pixels[y as usize * self.width as usize + x as usize]
+        }
+    }
+
+    #[inline]
+    pub(super) fn set(&mut self, x: u32, y: u32, value: u8) {
+        self.pixels[y as usize * self.width as usize + x as usize] = value;
+    }
+
+    /// Copy row `from` over row `to`
+    pub(super) fn copy_row(&mut self, from: u32, to: u32) {
+        let width = self.width as usize;
+        let start = from as usize * width;
+        self.pixels
+            .copy_within(start..start + width, to as usize * width);
+    }
+
+    /// Grow the bitmap downwards to `height` rows, filling new rows with `value`
+    pub(super) fn extend_to(&mut self, height: u32, value: u8) -> ParseResult<()> {
+        if height > self.height {
+            if self.width as u64 * height as u64 > MAX_PIXELS {
+                return Err(ParseError::StreamDecodeError(format!(
+                    "JBIG2 bitmap of {}x{height} pixels is too large",
+                    self.width
+                )));
+            }
+            self.pixels
+                .resize(self.width as usize * height as usize, value);
+            self.height = height;
+        }
+        Ok(())
+    }
+
+    /// Copy out the `width` x `height` rectangle whose top-left corner is (x, y)
+    pub(super) fn region(&self, x: i32, y: i32, width: u32, height: u32) -> ParseResult<Self> {
+        let mut region = Self::new(width, height, 0)?;
+        for row in 0..height {
+            for col in 0..width {
+                region.set(col, row, self.get(x + col as i32, y + row as i32));
+            }
+        }
+        Ok(region)
+    }
+
+    /// Combine `other` into this bitmap with its top-left corner at (x, y)
+    pub(super) fn compose(&mut self, other: &Bitmap, x: i32, y: i32, op: CombinationOperator) {
+        let x0 = x.max(0);
+        let y0 = y.max(0);
+        let x1 = (x as i64 + other.width as i64).min(self.width as i64) as i32;
+        let y1 = (y as i64 + other.height as i64).min(self.height as i64) as i32;
+        for dy in y0..y1 {
+            for dx in x0..x1 {
+                let index = dy as usize * self.width as usize + dx as usize;
+                let src = other.get(dx - x, dy - y);
+                self.pixels[index] = op.apply(self.pixels[index], src);
+            }
+        }
+    }
+
+    /// Pack the bitmap MSB first with byte-aligned rows in the PDF sense
+    /// of an image sample, i.e. 0 = black and 1 = white
+    pub(super) fn to_pdf_samples(&self) -> Vec<u8> {
+        let stride = (self.width as usize).div_ceil(8);
+        let mut packed = vec![0xFF; stride * self.height as usize];
+        if self.width == 0 {
+            return packed;
+        }
+        for (row, pixels) in self.pixels.chunks(self.width as usize).enumerate() {
+            let out = &mut packed[row * stride..(row + 1) * stride];
+            for (col, &pixel) in pixels.iter().enumerate() {
+                if pixel != 0 {
+                    out[col / 8] &= !(0x80 >> (col % 8));
+                }
+            }
+        }
+        packed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(rows: &[&str]) -> Bitmap {
+        let mut bitmap = Bitmap::new(rows[0].len() as u32, rows.len() as u32, 0).unwrap();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                bitmap.set(x as u32, y as u32, (c == '#') as u8);
+            }
+        }
+        bitmap
+    }
+
+    #[test]
+    fn test_compose_operators() {
+        let base = bitmap(&["##..", "##.."]);
+        let patch = bitmap(&[".#", ".#"]);
+
+        let mut or = base.clone();
+        or.compose(&patch, 1, 0, CombinationOperator::Or);
+        assert_eq!(or, bitmap(&["###.", "###."]));
+
+        let mut and = base.clone();
+        and.compose(&patch, 1, 0, CombinationOperator::And);
+        assert_eq!(and, bitmap(&["#...", "#..."]));
+
+        let mut xor = base.clone();
+        xor.compose(&patch, 1, 0, CombinationOperator::Xor);
+        assert_eq!(xor, bitmap(&["###.", "###."]));
+
+        let mut xnor = base.clone();
+        xnor.compose(&patch, 1, 0, CombinationOperator::Xnor);
+        assert_eq!(xnor, bitmap(&["#...", "#..."]));
+
+        let mut replace = base;
+        replace.compose(&patch, 1, 0, CombinationOperator::Replace);
+        assert_eq!(replace, bitmap(&["#.#.", "#.#."]));
+    }
+
+    #[test]
+    fn test_compose_clips_to_bounds() {
+        let mut page = bitmap(&["...", "..."]);
+        page.compose(&bitmap(&["##", "##"]), -1, 1, CombinationOperator::Or);
+        assert_eq!(page, bitmap(&["...", "#.."]));
+    }
+
+    #[test]
+    fn test_pdf_samples_are_inverted_and_row_aligned() {
+        let image = bitmap(&["#........#", ".........."]);
+        assert_eq!(image.to_pdf_samples(), vec![0x7F, 0xBF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_oversized_bitmap_is_rejected() {
+        assert!(Bitmap::new(u32::MAX, u32::MAX, 0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_combination_operator() {
+        assert!(CombinationOperator::from_bits(5).is_err());
+    }
+}
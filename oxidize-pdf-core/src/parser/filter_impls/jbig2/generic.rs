@@ -0,0 +1,352 @@
+//! Generic region (T.88 §6.2) and generic refinement region (§6.3) decoding
+
+use super::arithmetic::{ArithmeticDecoder, Context};
+use super::bitmap::Bitmap;
+use crate::parser::{ParseError, ParseResult};
+
+/// Number of contexts used by each generic region template
+pub(super) const GENERIC_CONTEXTS: [usize; 4] = [1 << 16, 1 << 13, 1 << 10, 1 << 10];
+
+/// Number of contexts used by each refinement template
+pub(super) const REFINEMENT_CONTEXTS: [usize; 2] = [1 << 13, 1 << 10];
+
+/// Context of the SLTP pseudo-pixel for each template (Figures 8 to 11)
+const TPGDON_CONTEXTS: [usize; 4] = [0x9B25, 0x0795, 0x00E5, 0x0195];
+
+/// Parameters of the arithmetic generic region decoding procedure (Table 2)
+pub(super) struct GenericRegion<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub template: u8,
+    pub typical_prediction: bool,
+    /// Adaptive template pixels; four for template 0, one otherwise
+    pub at: &'a [(i32, i32)],
+    /// Pixels set in this bitmap are skipped and left 0 (USESKIP)
+    pub skip: Option<&'a Bitmap>,
+}
+
+impl GenericRegion<'_> {
+    /// Decode the region with MMR = 0 (§6.2.5)
+    pub(super) fn decode(
+        &self,
+        decoder: &mut ArithmeticDecoder,
+        contexts: &mut [Context],
+    ) -> ParseResult<Bitmap> {
+        let needed = if self.template == 0 { 4 } else { 1 };
+        if self.template > 3 || self.at.len() < needed {
+            return Err(ParseError::StreamDecodeError(format!(
+                "Invalid JBIG2 generic region template {}",
+                self.template
+            )));
+        }
+        if contexts.len() < GENERIC_CONTEXTS[self.template as usize] {
+            return Err(ParseError::StreamDecodeError(
+                "JBIG2 generic region context table is too small".to_string(),
+            ));
+        }
+
+        let mut bitmap = Bitmap::new(self.width, self.height, 0)?;
+        let mut ltp = 0u8;
+
+        for y in 0..self.height {
+            if self.typical_prediction {
+                ltp ^= decoder.decode_bit(contexts, TPGDON_CONTEXTS[self.template as usize]);
+                if ltp == 1 {
+                    if y > 0 {
+                        bitmap.copy_row(y - 1, y);
+                    }
+                    continue;
+                }
+            }
+            let yi = y as i32;
+            for x in 0..self.width {
+                if self.skip.is_some_and(|skip| skip.get(x as i32, yi) != 0) {
+                    continue;
+                }
+                let cx = context(&bitmap, self.template, x as i32, yi, self.at);
+                let pixel = decoder.decode_bit(contexts, cx);
+                if pixel != 0 {
+                    bitmap.set(x, y, 1);
+                }
+            }
+        }
+        Ok(bitmap)
+    }
+}
+
+/// Context of the pixel at (x, y), laid out as in Figures 3 to 6
+#[inline]
+fn context(b: &Bitmap, template: u8, x: i32, y: i32, at: &[(i32, i32)]) -> usize {
+    let p = |dx: i32, dy: i32| b.get(x + dx, y + dy) as usize;
+    match template {
+        0 => {
+            p(-1, 0)
+                | p(-2, 0) << 1
+                | p(-3, 0) << 2
+                | p(-4, 0) << 3
+                | p(at[0].0, at[0].1) << 4
+                | p(2, -1) << 5
+                | p(1, -1) << 6
+                | p(0, -1) << 7
+                | p(-1, -1) << 8
+                | p(-2, -1) << 9
+                | p(at[1].0, at[1].1) << 10
+                | p(at[2].0, at[2].1) << 11
+                | p(1, -2) << 12
+                | p(0, -2) << 13
+                | p(-1, -2) << 14
+                | p(at[3].0, at[3].1) << 15
+        }
+        1 => {
+            p(-1, 0)
+                | p(-2, 0) << 1
+                | p(-3, 0) << 2
+                | p(at[0].0, at[0].1) << 3
+                | p(2, -1) << 4
+                | p(1, -1) << 5
+                | p(0, -1) << 6
+                | p(-1, -1) << 7
+                | p(-2, -1) << 8
+                | p(2, -2) << 9
+                | p(1, -2) << 10
+                | p(0, -2) << 11
+                | p(-1, -2) << 12
+        }
+        2 => {
+            p(-1, 0)
+                | p(-2, 0) << 1
+                | p(at[0].0, at[0].1) << 2
+                | p(1, -1) << 3
+                | p(0, -1) << 4
+                | p(-1, -1) << 5
+                | p(-2, -1) << 6
+                | p(1, -2) << 7
+                | p(0, -2) << 8
+                | p(-1, -2) << 9
+        }
+        _ => {
+            p(-1, 0)
+                | p(-2, 0) << 1
+                | p(-3, 0) << 2
+                | p(-4, 0) << 3
+                | p(at[0].0, at[0].1) << 4
+                | p(1, -1) << 5
+                | p(0, -1) << 6
+                | p(-1, -1) << 7
+                | p(-2, -1) << 8
+                | p(-3, -1) << 9
+        }
+    }
+}
+
+/// Parameters of the generic refinement region decoding procedure (Table 6)
+pub(super) struct RefinementRegion<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub template: u8,
+    pub reference: &'a Bitmap,
+    pub dx: i32,
+    pub dy: i32,
+    pub typical_prediction: bool,
+    /// Adaptive template pixels, only used by template 0
+    pub at: [(i32, i32); 2],
+}
+
+impl RefinementRegion<'_> {
+    /// Decode the refined bitmap (§6.3.5)
+    pub(super) fn decode(
+        &self,
+        decoder: &mut ArithmeticDecoder,
+        contexts: &mut [Context],
+    ) -> ParseResult<Bitmap> {
+        if self.template > 1 || contexts.len() < REFINEMENT_CONTEXTS[self.template as usize] {
+            return Err(ParseError::StreamDecodeError(format!(
+                "Invalid JBIG2 refinement template {}",
+                self.template
+            )));
+        }
+
+        let mut bitmap = Bitmap::new(self.width, self.height, 0)?;
+        // The SLTP context is the one where only the reference pixel
+        // corresponding to the current pixel is set
+        let sltp_context = if self.template == 0 { 0x100 } else { 0x080 };
+        let mut ltp = 0u8;
+
+        for y in 0..self.height as i32 {
+            if self.typical_prediction {
+                ltp ^= decoder.decode_bit(contexts, sltp_context);
+            }
+            for x in 0..self.width as i32 {
+                if ltp == 1 {
+                    if let Some(pixel) = self.typical_pixel(x, y) {
+                        if pixel != 0 {
+                            bitmap.set(x as u32, y as u32, 1);
+                        }
+                        continue;
+                    }
+                }
+                let cx = self.context(&bitmap, x, y);
+                if decoder.decode_bit(contexts, cx) != 0 {
+                    bitmap.set(x as u32, y as u32, 1);
+                }
+            }
+        }
+        Ok(bitmap)
+    }
+
+    /// TPGRPIX: the value of a pixel whose 3x3 reference neighbourhood is uniform
+    fn typical_pixel(&self, x: i32, y: i32) -> Option<u8> {
+        let rx = x - self.dx;
+        let ry = y - self.dy;
+        let value = self.reference.get(rx, ry);
+        for j in -1..=1 {
+            for i in -1..=1 {
+                if self.reference.get(rx + i, ry + j) != value {
+                    return None;
+                }
+            }
+        }
+        Some(value)
+    }
+
+    /// Context of the pixel at (x, y), laid out as in Figures 12 and 13
+    #[inline]
+    fn context(&self, b: &Bitmap, x: i32, y: i32) -> usize {
+        let p = |dx: i32, dy: i32| b.get(x + dx, y + dy) as usize;
+        let rx = x - self.dx;
+        let ry = y - self.dy;
+        let r = |dx: i32, dy: i32| self.reference.get(rx + dx, ry + dy) as usize;
+        if self.template == 0 {
+            let [(ax1, ay1), (ax2, ay2)] = self.at;
+            p(-1, 0)
+                | p(1, -1) << 1
+                | p(0, -1) << 2
+                | p(ax1, ay1) << 3
+                | r(1, 1) << 4
+                | r(0, 1) << 5
+                | r(-1, 1) << 6
+                | r(1, 0) << 7
+                | r(0, 0) << 8
+                | r(-1, 0) << 9
+                | r(1, -1) << 10
+                | r(0, -1) << 11
+                | r(ax2, ay2) << 12
+        } else {
+            p(-1, 0)
+                | p(1, -1) << 1
+                | p(0, -1) << 2
+                | p(-1, -1) << 3
+                | r(1, 1) << 4
+                | r(0, 1) << 5
+                | r(1, 0) << 6
+                | r(0, 0) << 7
+                | r(-1, 0) << 8
+                | r(0, -1) << 9
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::arithmetic::new_contexts;
+    use super::*;
+
+    /// Default adaptive template pixel positions of §6.2.5.4
+    fn default_at(template: u8) -> Vec<(i32, i32)> {
+        if template == 0 {
+            vec![(3, -1), (-3, -1), (2, -2), (-2, -2)]
+        } else if template == 1 {
+            vec![(3, -1)]
+        } else {
+            vec![(2, -1)]
+        }
+    }
+
+    #[test]
+    fn test_generic_region_dimensions() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        for template in 0..4u8 {
+            let at = default_at(template);
+            let region = GenericRegion {
+                width: 13,
+                height: 7,
+                template,
+                typical_prediction: true,
+                at: &at,
+                skip: None,
+            };
+            let mut contexts = new_contexts(GENERIC_CONTEXTS[template as usize]);
+            let bitmap = region
+                .decode(&mut ArithmeticDecoder::new(&data), &mut contexts)
+                .unwrap();
+            assert_eq!((bitmap.width, bitmap.height), (13, 7));
+        }
+    }
+
+    #[test]
+    fn test_generic_region_skip_leaves_pixels_white() {
+        let data = [0x00; 16];
+        let at = default_at(2);
+        let mut skip = Bitmap::new(8, 2, 0).unwrap();
+        skip.set(3, 0, 1);
+        let region = GenericRegion {
+            width: 8,
+            height: 2,
+            template: 2,
+            typical_prediction: false,
+            at: &at,
+            skip: Some(&skip),
+        };
+        let mut contexts = new_contexts(GENERIC_CONTEXTS[2]);
+        let bitmap = region
+            .decode(&mut ArithmeticDecoder::new(&data), &mut contexts)
+            .unwrap();
+        assert_eq!(bitmap.get(3, 0), 0);
+    }
+
+    #[test]
+    fn test_refinement_typical_prediction_copies_uniform_reference() {
+        let reference = Bitmap::new(8, 5, 1).unwrap();
+        let region = RefinementRegion {
+            width: 4,
+            height: 1,
+            template: 1,
+            reference: &reference,
+            dx: -2,
+            dy: -2,
+            typical_prediction: true,
+            at: [(-1, -1), (-1, -1)],
+        };
+        // Find a stream whose SLTP decision is 1; every pixel inside an
+        // all-black reference is then predicted without further decoding
+        let data = (0u8..=255)
+            .map(|b| [b, 0x5A, 0xC3, 0x00])
+            .find(|data| {
+                let mut contexts = new_contexts(REFINEMENT_CONTEXTS[1]);
+                ArithmeticDecoder::new(data).decode_bit(&mut contexts, 0x080) == 1
+            })
+            .unwrap();
+        let mut contexts = new_contexts(REFINEMENT_CONTEXTS[1]);
+        let bitmap = region
+            .decode(&mut ArithmeticDecoder::new(&data), &mut contexts)
+            .unwrap();
+        assert_eq!(bitmap, Bitmap::new(4, 1, 1).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_templates_are_rejected() {
+        let at = default_at(0);
+        let region = GenericRegion {
+            width: 1,
+            height: 1,
+            template: 4,
+            typical_prediction: false,
+            at: &at,
+            skip: None,
+        };
+        let mut contexts = new_contexts(1 << 16);
+        assert!(region
+            .decode(&mut ArithmeticDecoder::new(&[0]), &mut contexts)
+            .is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Pattern dictionaries (T.88 §6.7) and halftone regions (§6.6)
+
+use super::arithmetic::{code_length, new_contexts, ArithmeticDecoder};
+use super::bitmap::{Bitmap, CombinationOperator};
+use super::generic::{GenericRegion, GENERIC_CONTEXTS};
+use super::mmr::decode_mmr;
+use super::{read_u16, read_u32};
+use crate::parser::{ParseError, ParseResult};
+
+/// Decode a pattern dictionary segment into its patterns (§7.4.4)
+pub(super) fn decode_pattern_dictionary(data: &[u8]) -> ParseResult<Vec<Bitmap>> {
+    let flags = *data.first().ok_or_else(truncated)?;
+    let mmr = flags & 0x01 != 0;
+    let template = (flags >> 1) & 0x03;
+    let pattern_width = *data.get(1).ok_or_else(truncated)? as u32;
+    let pattern_height = *data.get(2).ok_or_else(truncated)? as u32;
+    let gray_max = read_u32(data, 3)?;
+    let body = &data[7..];
+
+    let count = gray_max as u64 + 1;
+    let width = u32::try_from(count * pattern_width as u64).map_err(|_| {
+        ParseError::StreamDecodeError("JBIG2 pattern dictionary is too large".to_string())
+    })?;
+    if pattern_width == 0 || pattern_height == 0 {
+        return Err(ParseError::StreamDecodeError(
+            "JBIG2 pattern dictionary has empty patterns".to_string(),
+        ));
+    }
+
+    let collective = if mmr {
+        decode_mmr(body, width, pattern_height)?.0
+    } else {
+        let at = [(-(pattern_width as i32), 0), (-3, -1), (2, -2), (-2, -2)];
+        let mut contexts = new_contexts(GENERIC_CONTEXTS[template as usize]);
+        GenericRegion {
+            width,
+            height: pattern_height,
+            template,
+            typical_prediction: false,
+            at: &at,
+            skip: None,
+        }
+        .decode(&mut ArithmeticDecoder::new(body), &mut contexts)?
+    };
+
+    (0..count)
+        .map(|index| {
+            collective.region(
+                (index * pattern_width as u64) as i32,
+                0,
+                pattern_width,
+                pattern_height,
+            )
+        })
+        .collect()
+}
+
+/// Decode a halftone region segment's data, following its region
+/// segment information field, into a `width` x `height` bitmap (§7.4.5)
+pub(super) fn decode_halftone_region(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    patterns: &[Bitmap],
+) -> ParseResult<Bitmap> {
+    let flags = *data.first().ok_or_else(truncated)?;
+    let mmr = flags & 0x01 != 0;
+    let template = (flags >> 1) & 0x03;
+    let enable_skip = flags & 0x08 != 0;
+    let operator = CombinationOperator::from_bits((flags >> 4) & 0x07)?;
+    let default_pixel = flags >> 7;
+    let grid_width = read_u32(data, 1)?;
+    let grid_height = read_u32(data, 5)?;
+    let grid_x = read_u32(data, 9)? as i32 as i64;
+    let grid_y = read_u32(data, 13)? as i32 as i64;
+    let vector_x = read_u16(data, 17)? as i64;
+    let vector_y = read_u16(data, 19)? as i64;
+    let body = &data[21..];
+
+    let first = patterns.first().ok_or_else(|| {
+        ParseError::StreamDecodeError("JBIG2 halftone region without patterns".to_string())
+    })?;
+    let (pattern_width, pattern_height) = (first.width as i64, first.height as i64);
+    let mut region = Bitmap::new(width, height, default_pixel)?;
+
+    // Grid position of the cell in row m, column n (§6.6.5.2)
+    let position = |m: i64, n: i64| {
+        (
+            (grid_x + m * vector_y + n * vector_x) >> 8,
+            (grid_y + m * vector_x - n * vector_y) >> 8,
+        )
+    };
+
+    let skip = if enable_skip {
+        let mut skip = Bitmap::new(grid_width, grid_height, 0)?;
+        for m in 0..grid_height {
+            for n in 0..grid_width {
+                let (x, y) = position(m as i64, n as i64);
+                if x + pattern_width <= 0
+                    || x >= width as i64
+                    || y + pattern_height <= 0
+                    || y >= height as i64
+                {
+                    skip.set(n, m, 1);
+                }
+            }
+        }
+        Some(skip)
+    } else {
+        None
+    };
+
+    let bits_per_pixel = code_length(patterns.len());
+    let gray = decode_gray_scale_image(
+        body,
+        mmr,
+        grid_width,
+        grid_height,
+        bits_per_pixel,
+        template,
+        skip.as_ref(),
+    )?;
+
+    for m in 0..grid_height {
+        for n in 0..grid_width {
+            let value = gray[m as usize * grid_width as usize + n as usize] as usize;
+            let pattern = &patterns[value.min(patterns.len() - 1)];
+            let (x, y) = position(m as i64, n as i64);
+            if x.abs() < i32::MAX as i64 && y.abs() < i32::MAX as i64 {
+                region.compose(pattern, x as i32, y as i32, operator);
+            }
+        }
+    }
+    Ok(region)
+}
+
+/// Gray-scale image decoding procedure (Annex C.5): Gray-coded bit planes,
+/// most significant first, sharing one set of statistics
+fn decode_gray_scale_image(
+    data: &[u8],
+    mmr: bool,
+    width: u32,
+    height: u32,
+    bits_per_pixel: u32,
+    template: u8,
+    skip: Option<&Bitmap>,
+) -> ParseResult<Vec<u32>> {
+    let mut values = vec![0u32; width as usize * height as usize];
+    let at = [
+        (if template <= 1 { 3 } else { 2 }, -1),
+        (-3, -1),
+        (2, -2),
+        (-2, -2),
+    ];
+    let mut contexts = new_contexts(GENERIC_CONTEXTS[template as usize]);
+    let mut decoder = ArithmeticDecoder::new(data);
+    let mut offset = 0usize;
+    let mut previous: Option<Bitmap> = None;
+
+    for plane in (0..bits_per_pixel).rev() {
+        let mut bitmap = if mmr {
+            let (bitmap, consumed) = decode_mmr(&data[offset.min(data.len())..], width, height)?;
+            offset += consumed;
+            bitmap
+        } else {
+            GenericRegion {
+                width,
+                height,
+                template,
+                typical_prediction: false,
+                at: &at,
+                skip,
+            }
+            .decode(&mut decoder, &mut contexts)?
+        };
+        if let Some(previous) = &previous {
+            bitmap.compose(previous, 0, 0, CombinationOperator::Xor);
+        }
+        for y in 0..height {
+            for x in 0..width {
+                if bitmap.get(x as i32, y as i32) != 0 {
+                    values[y as usize * width as usize + x as usize] |= 1 << plane;
+                }
+            }
+        }
+        previous = Some(bitmap);
+    }
+    Ok(values)
+}
+
+fn truncated() -> ParseError {
+    ParseError::StreamDecodeError("Truncated JBIG2 segment data".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MMR-coded pattern dictionary with two 2x1 patterns: ".." and "##"
+    fn pattern_dictionary() -> Vec<u8> {
+        let mut data = vec![0x01, 2, 1];
+        data.extend(1u32.to_be_bytes());
+        // One row of 4 pixels "..##": H mode "001", white 2 "0111",
+        // black 2 "11", then padding
+        data.extend([0b0010_1111, 0b1000_0000]);
+        data
+    }
+
+    #[test]
+    fn test_pattern_dictionary_splits_collective_bitmap() {
+        let patterns = decode_pattern_dictionary(&pattern_dictionary()).unwrap();
+        assert_eq!(patterns.len(), 2);
+        assert_eq!((patterns[0].get(0, 0), patterns[0].get(1, 0)), (0, 0));
+        assert_eq!((patterns[1].get(0, 0), patterns[1].get(1, 0)), (1, 1));
+    }
+
+    #[test]
+    fn test_halftone_region_places_patterns_on_grid() {
+        let patterns = decode_pattern_dictionary(&pattern_dictionary()).unwrap();
+        let mut data = vec![0x01]; // MMR, OR
+        data.extend(2u32.to_be_bytes()); // HGW
+        data.extend(1u32.to_be_bytes()); // HGH
+        data.extend(0u32.to_be_bytes()); // HGX
+        data.extend(0u32.to_be_bytes()); // HGY
+        data.extend(512u16.to_be_bytes()); // HRX: 2 pixels
+        data.extend(0u16.to_be_bytes()); // HRY
+                                         // One bit plane ".#": H mode "001", white 1 "000111", black 1 "010"
+        data.extend([0b0010_0011, 0b1010_0000]);
+
+        let region = decode_halftone_region(&data, 4, 1, &patterns).unwrap();
+        let row: Vec<u8> = (0..4).map(|x| region.get(x, 0)).collect();
+        assert_eq!(row, vec![0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_halftone_region_requires_patterns() {
+        let data = [0u8; 21];
+        assert!(decode_halftone_region(&data, 1, 1, &[]).is_err());
+    }
+}
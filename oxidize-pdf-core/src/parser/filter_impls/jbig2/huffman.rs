@@ -0,0 +1,611 @@
+//! Huffman tables of T.88 Annex B: standard tables B.1 to B.15, custom
+//! tables from table segments and the bit reader they decode from
+
+use crate::parser::{ParseError, ParseResult};
+use std::collections::HashMap;
+
+/// MSB-first reader over segment data
+pub(super) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub(super) fn read_bit(&mut self) -> ParseResult<u32> {
+        let byte = self.data.get(self.position / 8).ok_or_else(|| {
+            ParseError::StreamDecodeError("Unexpected end of JBIG2 Huffman data".to_string())
+        })?;
+        let bit = (byte >> (7 - self.position % 8)) & 1;
+        self.position += 1;
+        Ok(bit as u32)
+    }
+
+    pub(super) fn read_bits(&mut self, count: u32) -> ParseResult<u32> {
+        let mut value = 0u32;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit()?;
+        }
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary
+    pub(super) fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+
+    /// Byte offset of the reader, which must be byte-aligned
+    pub(super) fn byte_position(&self) -> usize {
+        self.position / 8
+    }
+
+    /// Take the next `len` bytes; the reader must be byte-aligned
+    pub(super) fn take_bytes(&mut self, len: usize) -> ParseResult<&'a [u8]> {
+        let start = self.byte_position();
+        let bytes = self.data.get(start..start + len).ok_or_else(|| {
+            ParseError::StreamDecodeError("Unexpected end of JBIG2 Huffman data".to_string())
+        })?;
+        self.position += len * 8;
+        Ok(bytes)
+    }
+}
+
+/// Kind of a table line (B.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Normal,
+    /// Lower range line: values below RANGELOW
+    Lower,
+    /// Upper range line: values from RANGELOW upwards
+    Upper,
+    OutOfBand,
+}
+
+#[derive(Debug, Clone)]
+struct TableLine {
+    prefix_len: u32,
+    range_len: u32,
+    range_low: i32,
+    kind: LineKind,
+}
+
+/// A Huffman table with prefix codes assigned as in B.3
+#[derive(Debug, Clone)]
+pub(super) struct HuffmanTable {
+    lines: Vec<TableLine>,
+    /// (prefix length, prefix code) → line index
+    codes: HashMap<(u32, u32), usize>,
+    max_prefix_len: u32,
+}
+
+impl HuffmanTable {
+    fn new(lines: Vec<TableLine>) -> Self {
+        let codes = assign_codes(&lines.iter().map(|l| l.prefix_len).collect::<Vec<_>>())
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, code)| code.map(|code| (code, index)))
+            .collect();
+        let max_prefix_len = lines.iter().map(|l| l.prefix_len).max().unwrap_or(0);
+        Self {
+            lines,
+            codes,
+            max_prefix_len,
+        }
+    }
+
+    /// Table whose lines are the values 0, 1, … with the given prefix
+    /// lengths and no range bits, as used for symbol ID codes
+    pub(super) fn from_code_lengths(lengths: &[u32]) -> Self {
+        Self::new(
+            lengths
+                .iter()
+                .enumerate()
+                .map(|(value, &prefix_len)| TableLine {
+                    prefix_len,
+                    range_len: 0,
+                    range_low: value as i32,
+                    kind: LineKind::Normal,
+                })
+                .collect(),
+        )
+    }
+
+    /// Parse the data of a table segment (B.2)
+    pub(super) fn parse(data: &[u8]) -> ParseResult<Self> {
+        if data.len() < 9 {
+            return Err(ParseError::StreamDecodeError(
+                "JBIG2 table segment too short".to_string(),
+            ));
+        }
+        let flags = data[0];
+        let has_oob = flags & 0x01 != 0;
+        let prefix_bits = ((flags >> 1) & 0x07) as u32 + 1;
+        let range_bits = ((flags >> 4) & 0x07) as u32 + 1;
+        let low = i32::from_be_bytes([data[1], data[2], data[3], data[4]]);
+        let high = i32::from_be_bytes([data[5], data[6], data[7], data[8]]);
+
+        let mut reader = BitReader::new(&data[9..]);
+        let mut lines = Vec::new();
+        let mut current = low as i64;
+        while current < high as i64 {
+            let prefix_len = reader.read_bits(prefix_bits)?;
+            let range_len = reader.read_bits(range_bits)?;
+            if range_len > 32 {
+                return Err(ParseError::StreamDecodeError(
+                    "Invalid JBIG2 table line range length".to_string(),
+                ));
+            }
+            lines.push(line(
+                prefix_len,
+                range_len,
+                current as i32,
+                LineKind::Normal,
+            ));
+            current += 1i64 << range_len;
+        }
+        let prefix_len = reader.read_bits(prefix_bits)?;
+        lines.push(line(prefix_len, 32, low.wrapping_sub(1), LineKind::Lower));
+        let prefix_len = reader.read_bits(prefix_bits)?;
+        lines.push(line(prefix_len, 32, high, LineKind::Upper));
+        if has_oob {
+            let prefix_len = reader.read_bits(prefix_bits)?;
+            lines.push(line(prefix_len, 0, 0, LineKind::OutOfBand));
+        }
+        Ok(Self::new(lines))
+    }
+
+    /// Decode one value (B.4); `None` is the out-of-band value
+    pub(super) fn decode(&self, reader: &mut BitReader) -> ParseResult<Option<i32>> {
+        let mut code = 0u32;
+        for len in 1..=self.max_prefix_len {
+            code = (code << 1) | reader.read_bit()?;
+            if let Some(&index) = self.codes.get(&(len, code)) {
+                let line = &self.lines[index];
+                let offset = reader.read_bits(line.range_len)? as i64;
+                let value = match line.kind {
+                    LineKind::OutOfBand => return Ok(None),
+                    LineKind::Lower => line.range_low as i64 - offset,
+                    LineKind::Normal | LineKind::Upper => line.range_low as i64 + offset,
+                };
+                return Ok(Some(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32));
+            }
+        }
+        Err(ParseError::StreamDecodeError(
+            "Invalid JBIG2 Huffman code".to_string(),
+        ))
+    }
+
+    /// Decode a value that must not be out-of-band
+    pub(super) fn decode_value(&self, reader: &mut BitReader) -> ParseResult<i32> {
+        self.decode(reader)?.ok_or_else(|| {
+            ParseError::StreamDecodeError("Unexpected JBIG2 out-of-band value".to_string())
+        })
+    }
+}
+
+/// Assign canonical prefix codes to lines of the given lengths (B.3);
+/// lines of length 0 get no code
+pub(super) fn assign_codes(lengths: &[u32]) -> Vec<Option<(u32, u32)>> {
+    let max_len = lengths.iter().copied().max().unwrap_or(0);
+    let mut counts = vec![0u32; max_len as usize + 1];
+    for &len in lengths {
+        counts[len as usize] += 1;
+    }
+    counts[0] = 0;
+
+    let mut codes = vec![None; lengths.len()];
+    let mut first_code = 0u32;
+    for len in 1..=max_len {
+        first_code = first_code.wrapping_add(counts[len as usize - 1]) << 1;
+        let mut current = first_code;
+        for (index, &line_len) in lengths.iter().enumerate() {
+            if line_len == len {
+                codes[index] = Some((len, current));
+                current = current.wrapping_add(1);
+            }
+        }
+    }
+    codes
+}
+
+fn line(prefix_len: u32, range_len: u32, range_low: i32, kind: LineKind) -> TableLine {
+    TableLine {
+        prefix_len,
+        range_len,
+        range_low,
+        kind,
+    }
+}
+
+/// Standard table lines as (PREFLEN, RANGELEN, RANGELOW); a lower range
+/// line, upper range line and OOB line follow where the table has them
+struct StandardTable {
+    lines: &'static [(u32, u32, i32)],
+    lower: Option<(u32, i32)>,
+    upper: Option<(u32, i32)>,
+    oob: Option<u32>,
+}
+
+const STANDARD_TABLES: [StandardTable; 15] = [
+    // B.1
+    StandardTable {
+        lines: &[(1, 4, 0), (2, 8, 16), (3, 16, 272)],
+        lower: None,
+        upper: Some((3, 65808)),
+        oob: None,
+    },
+    // B.2
+    StandardTable {
+        lines: &[(1, 0, 0), (2, 0, 1), (3, 0, 2), (4, 3, 3), (5, 6, 11)],
+        lower: None,
+        upper: Some((6, 75)),
+        oob: Some(6),
+    },
+    // B.3
+    StandardTable {
+        lines: &[
+            (8, 8, -256),
+            (1, 0, 0),
+            (2, 0, 1),
+            (3, 0, 2),
+            (4, 3, 3),
+            (5, 6, 11),
+        ],
+        lower: Some((8, -257)),
+        upper: Some((7, 75)),
+        oob: Some(6),
+    },
+    // B.4
+    StandardTable {
+        lines: &[(1, 0, 1), (2, 0, 2), (3, 0, 3), (4, 3, 4), (5, 6, 12)],
+        lower: None,
+        upper: Some((5, 76)),
+        oob: None,
+    },
+    // B.5
+    StandardTable {
+        lines: &[
+            (7, 8, -255),
+            (1, 0, 1),
+            (2, 0, 2),
+            (3, 0, 3),
+            (4, 3, 4),
+            (5, 6, 12),
+        ],
+        lower: Some((7, -256)),
+        upper: Some((6, 76)),
+        oob: None,
+    },
+    // B.6
+    StandardTable {
+        lines: &[
+            (5, 10, -2048),
+            (4, 9, -1024),
+            (4, 8, -512),
+            (4, 7, -256),
+            (5, 6, -128),
+            (5, 5, -64),
+            (4, 5, -32),
+            (2, 7, 0),
+            (3, 7, 128),
+            (3, 8, 256),
+            (4, 9, 512),
+            (4, 10, 1024),
+        ],
+        lower: Some((6, -2049)),
+        upper: Some((6, 2048)),
+        oob: None,
+    },
+    // B.7
+    StandardTable {
+        lines: &[
+            (4, 9, -1024),
+            (3, 8, -512),
+            (4, 7, -256),
+            (5, 6, -128),
+            (5, 5, -64),
+            (4, 5, -32),
+            (4, 5, 0),
+            (5, 5, 32),
+            (5, 6, 64),
+            (4, 7, 128),
+            (3, 8, 256),
+            (3, 9, 512),
+            (3, 10, 1024),
+        ],
+        lower: Some((5, -1025)),
+        upper: Some((5, 2048)),
+        oob: None,
+    },
+    // B.8
+    StandardTable {
+        lines: &[
+            (8, 3, -15),
+            (9, 1, -7),
+            (8, 1, -5),
+            (9, 0, -3),
+            (7, 0, -2),
+            (4, 0, -1),
+            (2, 1, 0),
+            (5, 0, 2),
+            (6, 0, 3),
+            (3, 4, 4),
+            (6, 1, 20),
+            (4, 4, 22),
+            (4, 5, 38),
+            (5, 6, 70),
+            (5, 7, 134),
+            (6, 7, 262),
+            (7, 8, 390),
+            (6, 10, 646),
+        ],
+        lower: Some((9, -16)),
+        upper: Some((9, 1670)),
+        oob: Some(2),
+    },
+    // B.9
+    StandardTable {
+        lines: &[
+            (8, 4, -31),
+            (9, 2, -15),
+            (8, 2, -11),
+            (9, 1, -7),
+            (7, 1, -5),
+            (4, 1, -3),
+            (3, 1, -1),
+            (3, 1, 1),
+            (5, 1, 3),
+            (6, 1, 5),
+            (3, 5, 7),
+            (6, 2, 39),
+            (4, 5, 43),
+            (4, 6, 75),
+            (5, 7, 139),
+            (5, 8, 267),
+            (6, 8, 523),
+            (7, 9, 779),
+            (6, 11, 1291),
+        ],
+        lower: Some((9, -32)),
+        upper: Some((9, 3339)),
+        oob: Some(2),
+    },
+    // B.10
+    StandardTable {
+        lines: &[
+            (7, 4, -21),
+            (8, 0, -5),
+            (7, 0, -4),
+            (5, 0, -3),
+            (2, 2, -2),
+            (5, 0, 2),
+            (6, 0, 3),
+            (7, 0, 4),
+            (8, 0, 5),
+            (2, 6, 6),
+            (5, 5, 70),
+            (6, 5, 102),
+            (6, 6, 134),
+            (6, 7, 198),
+            (6, 8, 326),
+            (6, 9, 582),
+            (6, 10, 1094),
+            (7, 11, 2118),
+        ],
+        lower: Some((8, -22)),
+        upper: Some((8, 4166)),
+        oob: Some(2),
+    },
+    // B.11
+    StandardTable {
+        lines: &[
+            (1, 0, 1),
+            (2, 1, 2),
+            (4, 0, 4),
+            (4, 1, 5),
+            (5, 1, 7),
+            (5, 2, 9),
+            (6, 2, 13),
+            (7, 2, 17),
+            (7, 3, 21),
+            (7, 4, 29),
+            (7, 5, 45),
+            (7, 6, 77),
+        ],
+        lower: None,
+        upper: Some((7, 141)),
+        oob: None,
+    },
+    // B.12
+    StandardTable {
+        lines: &[
+            (1, 0, 1),
+            (2, 0, 2),
+            (3, 1, 3),
+            (5, 0, 5),
+            (5, 1, 6),
+            (6, 1, 8),
+            (7, 0, 10),
+            (7, 1, 11),
+            (7, 2, 13),
+            (7, 3, 17),
+            (7, 4, 25),
+            (8, 5, 41),
+        ],
+        lower: None,
+        upper: Some((8, 73)),
+        oob: None,
+    },
+    // B.13
+    StandardTable {
+        lines: &[
+            (1, 0, 1),
+            (3, 0, 2),
+            (4, 0, 3),
+            (5, 0, 4),
+            (4, 1, 5),
+            (3, 3, 7),
+            (6, 1, 15),
+            (6, 2, 17),
+            (6, 3, 21),
+            (6, 4, 29),
+            (6, 5, 45),
+            (7, 6, 77),
+        ],
+        lower: None,
+        upper: Some((7, 141)),
+        oob: None,
+    },
+    // B.14
+    StandardTable {
+        lines: &[(3, 0, -2), (3, 0, -1), (1, 0, 0), (3, 0, 1), (3, 0, 2)],
+        lower: None,
+        upper: None,
+        oob: None,
+    },
+    // B.15
+    StandardTable {
+        lines: &[
+            (7, 4, -24),
+            (6, 2, -8),
+            (5, 1, -4),
+            (4, 0, -2),
+            (3, 0, -1),
+            (1, 0, 0),
+            (3, 0, 1),
+            (4, 0, 2),
+            (5, 1, 3),
+            (6, 2, 5),
+            (7, 4, 9),
+        ],
+        lower: Some((7, -25)),
+        upper: Some((7, 25)),
+        oob: None,
+    },
+];
+
+/// Standard Huffman table B.`number` (1 to 15)
+pub(super) fn standard_table(number: usize) -> HuffmanTable {
+    let table = &STANDARD_TABLES[number - 1];
+    let mut lines: Vec<TableLine> = table
+        .lines
+        .iter()
+        .map(|&(prefix_len, range_len, range_low)| {
+            line(prefix_len, range_len, range_low, LineKind::Normal)
+        })
+        .collect();
+    if let Some((prefix_len, range_low)) = table.lower {
+        lines.push(line(prefix_len, 32, range_low, LineKind::Lower));
+    }
+    if let Some((prefix_len, range_low)) = table.upper {
+        lines.push(line(prefix_len, 32, range_low, LineKind::Upper));
+    }
+    if let Some(prefix_len) = table.oob {
+        lines.push(line(prefix_len, 0, 0, LineKind::OutOfBand));
+    }
+    HuffmanTable::new(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pack a string of '0'/'1' into bytes, padding with zeros
+    fn bits(s: &str) -> Vec<u8> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        s.as_bytes()
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, &c)| byte | (((c == b'1') as u8) << (7 - i)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_standard_tables_are_complete_prefix_codes() {
+        // Every standard table is a complete prefix code: Kraft sum of 1
+        for number in 1..=15 {
+            let table = standard_table(number);
+            let kraft: f64 = table
+                .lines
+                .iter()
+                .filter(|line| line.prefix_len > 0)
+                .map(|line| 0.5f64.powi(line.prefix_len as i32))
+                .sum();
+            assert!((kraft - 1.0).abs() < 1e-12, "table B.{number}: {kraft}");
+        }
+    }
+
+    #[test]
+    fn test_table_b1_values() {
+        let table = standard_table(1);
+        // 0 + 4-bit 0101 = 5; 10 + 8-bit 00000001 = 17; 111 + 32 bits = 65808 + 1
+        let data = bits("0 0101  10 00000001  111 00000000000000000000000000000001");
+        let mut reader = BitReader::new(&data);
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(5));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(17));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(65809));
+    }
+
+    #[test]
+    fn test_table_b2_out_of_band() {
+        let table = standard_table(2);
+        // Codes: 0, 10, 110, 1110+3, 11110+6, 111110+32 (upper), 111111 (OOB)
+        let data = bits("0 10 110 1110 101 111111");
+        let mut reader = BitReader::new(&data);
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(0));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(1));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(2));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(8));
+        assert_eq!(table.decode(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_table_b3_lower_range() {
+        let table = standard_table(3);
+        // Lengths 8 (-256 line) and 8 (lower line) share the longest codes:
+        // 11111110 + 8 bits is -256.., 11111111 + 32 bits is below -256
+        let data = bits("11111110 00000010  11111111 00000000000000000000000000000011");
+        let mut reader = BitReader::new(&data);
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(-254));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(-260));
+    }
+
+    #[test]
+    fn test_custom_table_segment() {
+        // HTOOB=1, HTPS=3, HTRS=2; HTLOW=0, HTHIGH=8 → lines for 0..4 and
+        // 4..8 with prefix lengths 1 and 2, then lower, upper and OOB lines
+        let mut data = vec![0b0001_0101];
+        data.extend(0i32.to_be_bytes());
+        data.extend(8i32.to_be_bytes());
+        data.extend(bits("001 10  010 10  011  100  100"));
+        let table = HuffmanTable::parse(&data).unwrap();
+        // Prefix codes: 0 (0..4), 10 (4..8), 110 lower, 1110 upper, 1111 OOB
+        let table_bits = bits("0 11  10 01  1111");
+        let mut reader = BitReader::new(&table_bits);
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(3));
+        assert_eq!(table.decode(&mut reader).unwrap(), Some(5));
+        assert_eq!(table.decode(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_assign_codes() {
+        // Example of B.3: lengths 1, 2, 3, 3 give 0, 10, 110, 111
+        assert_eq!(
+            assign_codes(&[1, 2, 3, 3, 0]),
+            vec![
+                Some((1, 0b0)),
+                Some((2, 0b10)),
+                Some((3, 0b110)),
+                Some((3, 0b111)),
+                None
+            ]
+        );
+    }
+}
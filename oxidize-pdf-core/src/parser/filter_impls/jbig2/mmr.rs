@@ -0,0 +1,477 @@
+//! MMR (ITU-T T.6) decoding of generic regions (T.88 §6.2.6)
+//!
+//! JBIG2 uses the two-dimensional coding scheme of Group 4 facsimile with
+//! the first reference line all white. Pixels are 1 for black.
+
+use super::bitmap::Bitmap;
+use crate::parser::{ParseError, ParseResult};
+use std::sync::OnceLock;
+
+/// Longest run-length code (black make-up codes)
+const LOOKUP_BITS: u32 = 13;
+
+/// End-of-facsimile-block: two EOL codes
+const EOFB: u32 = 0x001001;
+
+/// Terminating and make-up codes for white runs (T.4 Tables 2 and 3)
+const WHITE_CODES: &[(&str, u16)] = &[
+    ("00110101", 0),
+    ("000111", 1),
+    ("0111", 2),
+    ("1000", 3),
+    ("1011", 4),
+    ("1100", 5),
+    ("1110", 6),
+    ("1111", 7),
+    ("10011", 8),
+    ("10100", 9),
+    ("00111", 10),
+    ("01000", 11),
+    ("001000", 12),
+    ("000011", 13),
+    ("110100", 14),
+    ("110101", 15),
+    ("101010", 16),
+    ("101011", 17),
+    ("0100111", 18),
+    ("0001100", 19),
+    ("0001000", 20),
+    ("0010111", 21),
+    ("0000011", 22),
+    ("0000100", 23),
+    ("0101000", 24),
+    ("0101011", 25),
+    ("0010011", 26),
+    ("0100100", 27),
+    ("0011000", 28),
+    ("00000010", 29),
+    ("00000011", 30),
+    ("00011010", 31),
+    ("00011011", 32),
+    ("00010010", 33),
+    ("00010011", 34),
+    ("00010100", 35),
+    ("00010101", 36),
+    ("00010110", 37),
+    ("00010111", 38),
+    ("00101000", 39),
+    ("00101001", 40),
+    ("00101010", 41),
+    ("00101011", 42),
+    ("00101100", 43),
+    ("00101101", 44),
+    ("00000100", 45),
+    ("00000101", 46),
+    ("00001010", 47),
+    ("00001011", 48),
+    ("01010010", 49),
+    ("01010011", 50),
+    ("01010100", 51),
+    ("01010101", 52),
+    ("00100100", 53),
+    ("00100101", 54),
+    ("01011000", 55),
+    ("01011001", 56),
+    ("01011010", 57),
+    ("01011011", 58),
+    ("01001010", 59),
+    ("01001011", 60),
+    ("00110010", 61),
+    ("00110011", 62),
+    ("00110100", 63),
+    ("11011", 64),
+    ("10010", 128),
+    ("010111", 192),
+    ("0110111", 256),
+    ("00110110", 320),
+    ("00110111", 384),
+    ("01100100", 448),
+    ("01100101", 512),
+    ("01101000", 576),
+    ("01100111", 640),
+    ("011001100", 704),
+    ("011001101", 768),
+    ("011010010", 832),
+    ("011010011", 896),
+    ("011010100", 960),
+    ("011010101", 1024),
+    ("011010110", 1088),
+    ("011010111", 1152),
+    ("011011000", 1216),
+    ("011011001", 1280),
+    ("011011010", 1344),
+    ("011011011", 1408),
+    ("010011000", 1472),
+    ("010011001", 1536),
+    ("010011010", 1600),
+    ("011000", 1664),
+    ("010011011", 1728),
+];
+
+/// Terminating and make-up codes for black runs (T.4 Tables 2 and 3)
+const BLACK_CODES: &[(&str, u16)] = &[
+    ("0000110111", 0),
+    ("010", 1),
+    ("11", 2),
+    ("10", 3),
+    ("011", 4),
+    ("0011", 5),
+    ("0010", 6),
+    ("00011", 7),
+    ("000101", 8),
+    ("000100", 9),
+    ("0000100", 10),
+    ("0000101", 11),
+    ("0000111", 12),
+    ("00000100", 13),
+    ("00000111", 14),
+    ("000011000", 15),
+    ("0000010111", 16),
+    ("0000011000", 17),
+    ("0000001000", 18),
+    ("00001100111", 19),
+    ("00001101000", 20),
+    ("00001101100", 21),
+    ("00000110111", 22),
+    ("00000101000", 23),
+    ("00000010111", 24),
+    ("00000011000", 25),
+    ("000011001010", 26),
+    ("000011001011", 27),
+    ("000011001100", 28),
+    ("000011001101", 29),
+    ("000001101000", 30),
+    ("000001101001", 31),
+    ("000001101010", 32),
+    ("000001101011", 33),
+    ("000011010010", 34),
+    ("000011010011", 35),
+    ("000011010100", 36),
+    ("000011010101", 37),
+    ("000011010110", 38),
+    ("000011010111", 39),
+    ("000001101100", 40),
+    ("000001101101", 41),
+    ("000011011010", 42),
+    ("000011011011", 43),
+    ("000001010100", 44),
+    ("000001010101", 45),
+    ("000001010110", 46),
+    ("000001010111", 47),
+    ("000001100100", 48),
+    ("000001100101", 49),
+    ("000001010010", 50),
+    ("000001010011", 51),
+    ("000000100100", 52),
+    ("000000110111", 53),
+    ("000000111000", 54),
+    ("000000100111", 55),
+    ("000000101000", 56),
+    ("000001011000", 57),
+    ("000001011001", 58),
+    ("000000101011", 59),
+    ("000000101100", 60),
+    ("000001011010", 61),
+    ("000001100110", 62),
+    ("000001100111", 63),
+    ("0000001111", 64),
+    ("000011001000", 128),
+    ("000011001001", 192),
+    ("000001011011", 256),
+    ("000000110011", 320),
+    ("000000110100", 384),
+    ("000000110101", 448),
+    ("0000001101100", 512),
+    ("0000001101101", 576),
+    ("0000001001010", 640),
+    ("0000001001011", 704),
+    ("0000001001100", 768),
+    ("0000001001101", 832),
+    ("0000001110010", 896),
+    ("0000001110011", 960),
+    ("0000001110100", 1024),
+    ("0000001110101", 1088),
+    ("0000001110110", 1152),
+    ("0000001110111", 1216),
+    ("0000001010010", 1280),
+    ("0000001010011", 1344),
+    ("0000001010100", 1408),
+    ("0000001010101", 1472),
+    ("0000001011010", 1536),
+    ("0000001011011", 1600),
+    ("0000001100100", 1664),
+    ("0000001100101", 1728),
+];
+
+/// Extended make-up codes shared by both colours (T.4 Table 4)
+const EXTENDED_MAKEUP_CODES: &[(&str, u16)] = &[
+    ("00000001000", 1792),
+    ("00000001100", 1856),
+    ("00000001101", 1920),
+    ("000000010010", 1984),
+    ("000000010011", 2048),
+    ("000000010100", 2112),
+    ("000000010101", 2176),
+    ("000000010110", 2240),
+    ("000000010111", 2304),
+    ("000000011100", 2368),
+    ("000000011101", 2432),
+    ("000000011110", 2496),
+    ("000000011111", 2560),
+];
+
+/// Direct lookup of the next `LOOKUP_BITS` bits: (code length, run length)
+struct RunTable {
+    entries: Vec<(u8, u16)>,
+}
+
+impl RunTable {
+    fn build(codes: &[(&str, u16)]) -> Self {
+        let mut entries = vec![(0u8, 0u16); 1 << LOOKUP_BITS];
+        for &(code, run) in codes.iter().chain(EXTENDED_MAKEUP_CODES) {
+            let len = code.len() as u32;
+            let value = u32::from_str_radix(code, 2).unwrap_or(0);
+            let first = (value << (LOOKUP_BITS - len)) as usize;
+            for entry in &mut entries[first..first + (1 << (LOOKUP_BITS - len))] {
+                *entry = (len as u8, run);
+            }
+        }
+        Self { entries }
+    }
+}
+
+fn run_tables() -> &'static [RunTable; 2] {
+    static TABLES: OnceLock<[RunTable; 2]> = OnceLock::new();
+    TABLES.get_or_init(|| [RunTable::build(WHITE_CODES), RunTable::build(BLACK_CODES)])
+}
+
+/// Two-dimensional coding modes (T.4 Table 5)
+enum Mode {
+    Pass,
+    Horizontal,
+    Vertical(i64),
+}
+
+/// MSB-first reader that yields zero bits past the end of the data
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn peek(&self, count: u32) -> u32 {
+        let mut value = 0u32;
+        for i in 0..count as usize {
+            let bit_index = self.position + i;
+            let bit = self
+                .data
+                .get(bit_index / 8)
+                .map_or(0, |byte| (byte >> (7 - bit_index % 8)) & 1);
+            value = (value << 1) | bit as u32;
+        }
+        value
+    }
+
+    fn skip(&mut self, count: u32) {
+        self.position += count as usize;
+    }
+
+    fn exhausted(&self) -> bool {
+        self.position >= self.data.len() * 8
+    }
+
+    fn read_mode(&mut self) -> ParseResult<Mode> {
+        let bits = self.peek(7);
+        let (len, mode) = match bits {
+            _ if bits & 0x40 != 0 => (1, Mode::Vertical(0)),
+            _ if bits >> 4 == 0b011 => (3, Mode::Vertical(1)),
+            _ if bits >> 4 == 0b010 => (3, Mode::Vertical(-1)),
+            _ if bits >> 4 == 0b001 => (3, Mode::Horizontal),
+            _ if bits >> 3 == 0b0001 => (4, Mode::Pass),
+            _ if bits >> 1 == 0b000011 => (6, Mode::Vertical(2)),
+            _ if bits >> 1 == 0b000010 => (6, Mode::Vertical(-2)),
+            0b0000011 => (7, Mode::Vertical(3)),
+            0b0000010 => (7, Mode::Vertical(-3)),
+            _ => {
+                return Err(ParseError::StreamDecodeError(format!(
+                    "Invalid MMR mode code at bit {}",
+                    self.position
+                )))
+            }
+        };
+        self.skip(len);
+        Ok(mode)
+    }
+
+    /// Read make-up codes followed by a terminating code
+    fn read_run(&mut self, color: u8) -> ParseResult<i64> {
+        let table = &run_tables()[color as usize];
+        let mut total = 0i64;
+        loop {
+            let (len, run) = table.entries[self.peek(LOOKUP_BITS) as usize];
+            if len == 0 {
+                return Err(ParseError::StreamDecodeError(format!(
+                    "Invalid MMR run-length code at bit {}",
+                    self.position
+                )));
+            }
+            self.skip(len as u32);
+            total += run as i64;
+            if run < 64 {
+                return Ok(total);
+            }
+        }
+    }
+}
+
+/// Decode a `width` x `height` MMR-coded bitmap.
+///
+/// Returns the bitmap and the number of bytes consumed, including a trailing
+/// EOFB if present and rounded up to a whole byte.
+pub(super) fn decode_mmr(data: &[u8], width: u32, height: u32) -> ParseResult<(Bitmap, usize)> {
+    let mut bitmap = Bitmap::new(width, height, 0)?;
+    let mut reader = BitReader { data, position: 0 };
+    let width = width as i64;
+    // Changing elements of the reference line; even entries start black runs
+    let mut reference: Vec<i64> = Vec::new();
+
+    for y in 0..height {
+        if reader.peek(24) == EOFB || reader.exhausted() {
+            break;
+        }
+
+        let mut changes: Vec<i64> = Vec::with_capacity(reference.len() + 2);
+        let mut a0 = -1i64;
+        let mut color = 0u8;
+        let mut cursor = 0usize;
+
+        while a0 < width {
+            while cursor < reference.len() && reference[cursor] <= a0 {
+                cursor += 1;
+            }
+            let mut index = cursor;
+            if index % 2 != color as usize {
+                index += 1;
+            }
+            let b1 = reference.get(index).copied().unwrap_or(width);
+            let b2 = reference.get(index + 1).copied().unwrap_or(width);
+
+            match reader.read_mode()? {
+                Mode::Pass => a0 = b2,
+                Mode::Horizontal => {
+                    let start = a0.max(0);
+                    let a1 = (start + reader.read_run(color)?).min(width);
+                    let a2 = (a1 + reader.read_run(1 - color)?).min(width);
+                    changes.push(a1);
+                    changes.push(a2);
+                    a0 = a2;
+                }
+                Mode::Vertical(offset) => {
+                    let a1 = (b1 + offset).clamp(a0.max(0), width);
+                    changes.push(a1);
+                    a0 = a1;
+                    color ^= 1;
+                }
+            }
+        }
+
+        for run in changes.chunks(2) {
+            let end = run.get(1).copied().unwrap_or(width);
+            for x in run[0]..end {
+                bitmap.set(x as u32, y, 1);
+            }
+        }
+        reference = changes;
+    }
+
+    if reader.peek(24) == EOFB {
+        reader.skip(24);
+    }
+    Ok((bitmap, reader.position.div_ceil(8).min(data.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pack a string of '0'/'1' into bytes, padding with zeros
+    fn bits(s: &str) -> Vec<u8> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        s.as_bytes()
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, &c)| byte | (((c == b'1') as u8) << (7 - i)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_code_tables_are_prefix_free() {
+        for codes in [WHITE_CODES, BLACK_CODES] {
+            let all: Vec<&str> = codes
+                .iter()
+                .chain(EXTENDED_MAKEUP_CODES)
+                .map(|(code, _)| *code)
+                .collect();
+            for (i, a) in all.iter().enumerate() {
+                for (j, b) in all.iter().enumerate() {
+                    assert!(i == j || !b.starts_with(a), "{a} is a prefix of {b}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_horizontal_and_vertical_modes() {
+        // Row 0: H, white 2 ("0111"), black 3 ("10"), then V0 against the
+        // all-white reference reaches the end of the line.
+        // Row 1: V0, V0 copies the run from row 0.
+        let data = bits("001 0111 10 1  1 1 1");
+        let (bitmap, _) = decode_mmr(&data, 8, 2).unwrap();
+        for y in 0..2 {
+            let row: Vec<u8> = (0..8).map(|x| bitmap.get(x, y)).collect();
+            assert_eq!(row, vec![0, 0, 1, 1, 1, 0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_vertical_offsets_and_pass_mode() {
+        // Row 0: H, white 1 ("000111"), black 2 ("11"), V0 → black at 1..3
+        // Row 1: VR1 moves the black run start to 2, VL1 moves its end to 2
+        // as well, leaving an empty run, and V0 finishes the line.
+        let data = bits("001 000111 11 1  011 010 1");
+        let (bitmap, _) = decode_mmr(&data, 6, 2).unwrap();
+        let row0: Vec<u8> = (0..6).map(|x| bitmap.get(x, 0)).collect();
+        let row1: Vec<u8> = (0..6).map(|x| bitmap.get(x, 1)).collect();
+        assert_eq!(row0, vec![0, 1, 1, 0, 0, 0]);
+        assert_eq!(row1, vec![0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_eofb_ends_the_bitmap() {
+        let data = bits("001 0111 10 1  000000000001 000000000001");
+        let (bitmap, consumed) = decode_mmr(&data, 8, 4).unwrap();
+        assert_eq!(bitmap.get(2, 0), 1);
+        assert_eq!(bitmap.get(2, 1), 0);
+        assert_eq!(consumed, data.len());
+    }
+
+    #[test]
+    fn test_makeup_codes() {
+        // H with a white run of 64 + 0 and a black run of 64 + 2
+        let data = bits("001 11011 00110101 0000001111 11 1");
+        let (bitmap, _) = decode_mmr(&data, 200, 1).unwrap();
+        assert_eq!(bitmap.get(63, 0), 0);
+        assert_eq!(bitmap.get(64, 0), 1);
+        assert_eq!(bitmap.get(129, 0), 1);
+        assert_eq!(bitmap.get(130, 0), 0);
+    }
+
+    #[test]
+    fn test_invalid_code_is_an_error() {
+        assert!(decode_mmr(&[0x00, 0x00, 0x00, 0x01], 16, 1).is_err());
+    }
+}
@@ -0,0 +1,1123 @@
+//! JBIG2 decode implementation according to ISO 32000-1 Section 7.4.7
+//!
+//! This module decodes JBIG2 (Joint Bi-level Image Experts Group) compressed
+//! images as used in PDF streams. JBIG2 is defined in ITU-T T.88.
+//!
+//! PDF streams use the embedded organisation: a sequence of segments without
+//! a file header, optionally preceded by the segments of the stream referred
+//! to by `JBIG2Globals`. Standalone files with a file header are accepted as
+//! well. The first page is decoded and returned as packed 1-bit samples,
+//! rows padded to whole bytes, with 0 for black and 1 for white so that the
+//! result can be used directly as a DeviceGray image.
+//!
+//! Supported segment types are symbol dictionaries, text regions, pattern
+//! dictionaries, halftone regions, generic regions, generic refinement
+//! regions, page information, end of stripe and code tables, with both
+//! arithmetic and Huffman/MMR coding.
+
+pub(super) mod arithmetic;
+mod bitmap;
+mod generic;
+mod halftone;
+mod huffman;
+mod mmr;
+mod symbol;
+mod text;
+
+use self::arithmetic::{code_length, new_contexts, ArithmeticDecoder};
+use self::bitmap::{Bitmap, CombinationOperator};
+use self::generic::{GenericRegion, RefinementRegion, GENERIC_CONTEXTS, REFINEMENT_CONTEXTS};
+use self::huffman::{standard_table, BitReader, HuffmanTable};
+use self::symbol::SymbolDictionary;
+use self::text::{
+    read_symbol_code_table, ArithmeticInstances, HuffmanInstances, SymbolCodes, TextContexts,
+    TextRegion, TextTables,
+};
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::{ParseError, ParseOptions, ParseResult};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// JBIG2 file header identification string (T.88 §D.4.1)
+const FILE_ID: [u8; 8] = [0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Segment data length value meaning "unknown", only allowed for
+/// immediate generic regions
+const UNKNOWN_LENGTH: u32 = 0xFFFF_FFFF;
+
+/// JBIG2 decode parameters from DecodeParms dictionary
+#[derive(Debug, Clone, Default)]
+pub struct Jbig2DecodeParams {
+    /// Decoded data of the JBIG2Globals stream, segments shared by all pages
+    pub jbig2_globals: Option<Vec<u8>>,
+}
+
+impl Jbig2DecodeParams {
+    /// Parse JBIG2 decode parameters from PDF dictionary
+    ///
+    /// `JBIG2Globals` must already be resolved to a stream; the reader does
+    /// this when loading a JBIG2Decode image. An unresolved reference is
+    /// ignored.
+    pub fn from_dict(dict: &PdfDictionary) -> Self {
+        let mut params = Jbig2DecodeParams::default();
+
+        if let Some(PdfObject::Stream(globals)) = dict.get("JBIG2Globals") {
+            match globals.decode(&ParseOptions::default()) {
+                Ok(data) => params.jbig2_globals = Some(data),
+                Err(e) => tracing::debug!("Failed to decode JBIG2Globals stream: {e}"),
+            }
+        }
+
+        params
+    }
+}
+
+/// JBIG2 segment header (T.88 §7.2)
+#[derive(Debug, Clone)]
+struct Jbig2SegmentHeader {
+    /// Segment number
+    segment_number: u32,
+    /// Segment type
+    segment_type: u8,
+    /// Numbers of the referred-to segments
+    referred_segments: Vec<u32>,
+    /// Page association, 0 for segments shared by all pages
+    page_association: u32,
+    /// Data length, possibly `UNKNOWN_LENGTH`
+    data_length: u32,
+}
+
+/// A segment header together with its data
+struct Segment<'a> {
+    header: Jbig2SegmentHeader,
+    data: &'a [u8],
+}
+
+/// JBIG2 decoder
+pub struct Jbig2Decoder {
+    params: Jbig2DecodeParams,
+}
+
+impl Jbig2Decoder {
+    /// Create a new JBIG2 decoder
+    pub fn new(params: Jbig2DecodeParams) -> Self {
+        Self { params }
+    }
+
+    /// Decode JBIG2 data into the samples of its first page
+    pub fn decode(&self, data: &[u8]) -> ParseResult<Vec<u8>> {
+        let segments = if data.starts_with(&FILE_ID) {
+            parse_file(data)?
+        } else {
+            parse_sequential(data)?
+        };
+
+        let mut state = PageDecoder::default();
+        if let Some(globals) = &self.params.jbig2_globals {
+            for segment in parse_sequential(globals)? {
+                state.process(&segment)?;
+            }
+        }
+        for segment in &segments {
+            if !state.process(segment)? {
+                break;
+            }
+        }
+
+        let page = state.page.ok_or_else(|| {
+            ParseError::StreamDecodeError("JBIG2 data has no page information".to_string())
+        })?;
+        Ok(page.bitmap.to_pdf_samples())
+    }
+}
+
+/// Parse a standalone JBIG2 file (T.88 Annex D)
+fn parse_file(data: &[u8]) -> ParseResult<Vec<Segment<'_>>> {
+    let flags = *data.get(8).ok_or_else(truncated)?;
+    let sequential = flags & 0x01 != 0;
+    // The number of pages follows unless it is unknown
+    let start = if flags & 0x02 == 0 { 13 } else { 9 };
+    if start > data.len() {
+        return Err(truncated());
+    }
+
+    if sequential {
+        return parse_sequential(&data[start..]);
+    }
+
+    // Random-access organisation: all headers, then all segment data
+    let mut headers = Vec::new();
+    let mut pos = start;
+    while pos < data.len() {
+        let (header, length) = parse_segment_header(&data[pos..])?;
+        pos += length;
+        if header.data_length == UNKNOWN_LENGTH {
+            return Err(ParseError::StreamDecodeError(
+                "JBIG2 random-access file with unknown segment length".to_string(),
+            ));
+        }
+        let end_of_file = header.segment_type == 51;
+        headers.push(header);
+        if end_of_file {
+            break;
+        }
+    }
+
+    let mut segments = Vec::with_capacity(headers.len());
+    for header in headers {
+        let end = pos
+            .saturating_add(header.data_length as usize)
+            .min(data.len());
+        segments.push(Segment {
+            data: &data[pos..end],
+            header,
+        });
+        pos = end;
+    }
+    Ok(segments)
+}
+
+/// Parse segments stored one after the other, each header followed by its
+/// data, as in PDF streams and sequential files
+fn parse_sequential(data: &[u8]) -> ParseResult<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let (header, length) = match parse_segment_header(&data[pos..]) {
+            Ok(parsed) => parsed,
+            // Trailing garbage after the last complete segment is ignored
+            Err(e) if segments.is_empty() => return Err(e),
+            Err(_) => break,
+        };
+        pos += length;
+
+        let data_length = if header.data_length == UNKNOWN_LENGTH {
+            unknown_data_length(&header, &data[pos..])?
+        } else {
+            header.data_length as usize
+        };
+        // Truncated data is handed to the segment decoder as is
+        let end = pos.saturating_add(data_length).min(data.len());
+        let end_of_file = header.segment_type == 51;
+        segments.push(Segment {
+            data: &data[pos..end],
+            header,
+        });
+        pos = end;
+        if end_of_file {
+            break;
+        }
+    }
+    Ok(segments)
+}
+
+/// Parse a segment header, returning it with its length in bytes
+fn parse_segment_header(data: &[u8]) -> ParseResult<(Jbig2SegmentHeader, usize)> {
+    if data.len() < 11 {
+        return Err(ParseError::StreamDecodeError(
+            "JBIG2 segment header too short".to_string(),
+        ));
+    }
+
+    let segment_number = read_u32(data, 0)?;
+    let flags = data[4];
+    let segment_type = flags & 0x3F;
+    let long_page_association = flags & 0x40 != 0;
+
+    // Referred-to segment count and retention flags (§7.2.4)
+    let mut pos = 5;
+    let mut referred_count = (data[pos] >> 5) as usize;
+    if referred_count == 7 {
+        referred_count = (read_u32(data, pos)? & 0x1FFF_FFFF) as usize;
+        pos += 4 + (referred_count + 1).div_ceil(8);
+    } else if referred_count > 4 {
+        return Err(ParseError::StreamDecodeError(format!(
+            "Invalid JBIG2 referred-to segment count {referred_count}"
+        )));
+    } else {
+        pos += 1;
+    }
+
+    // Referred-to segment numbers are as small as the segment number allows
+    let number_size = if segment_number <= 256 {
+        1
+    } else if segment_number <= 65536 {
+        2
+    } else {
+        4
+    };
+    if referred_count > data.len() / number_size {
+        return Err(ParseError::StreamDecodeError(
+            "JBIG2 segment header incomplete".to_string(),
+        ));
+    }
+    let mut referred_segments = Vec::with_capacity(referred_count);
+    for _ in 0..referred_count {
+        let number = match number_size {
+            1 => *data.get(pos).ok_or_else(incomplete_header)? as u32,
+            2 => read_u16(data, pos).map_err(|_| incomplete_header())? as u32,
+            _ => read_u32(data, pos).map_err(|_| incomplete_header())?,
+        };
+        referred_segments.push(number);
+        pos += number_size;
+    }
+
+    let page_association = if long_page_association {
+        let page = read_u32(data, pos).map_err(|_| incomplete_header())?;
+        pos += 4;
+        page
+    } else {
+        let page = *data.get(pos).ok_or_else(incomplete_header)? as u32;
+        pos += 1;
+        page
+    };
+
+    let data_length = read_u32(data, pos).map_err(|_| incomplete_header())?;
+    pos += 4;
+
+    Ok((
+        Jbig2SegmentHeader {
+            segment_number,
+            segment_type,
+            referred_segments,
+            page_association,
+            data_length,
+        },
+        pos,
+    ))
+}
+
+/// Find the length of an immediate generic region of unknown length by
+/// scanning for its end marker and row count (§7.2.7)
+fn unknown_data_length(header: &Jbig2SegmentHeader, data: &[u8]) -> ParseResult<usize> {
+    if header.segment_type != 38 {
+        return Err(ParseError::StreamDecodeError(format!(
+            "JBIG2 segment type {} with unknown data length",
+            header.segment_type
+        )));
+    }
+    let flags = *data.get(17).ok_or_else(truncated)?;
+    let marker: [u8; 2] = if flags & 0x01 != 0 {
+        [0x00, 0x00]
+    } else {
+        [0xFF, 0xAC]
+    };
+    data.get(18..)
+        .and_then(|coded| coded.windows(2).position(|pair| pair == marker))
+        .map(|index| 18 + index + 2 + 4)
+        .ok_or_else(|| {
+            ParseError::StreamDecodeError("JBIG2 generic region end marker not found".to_string())
+        })
+}
+
+/// Region segment information field (§7.4.1)
+#[derive(Debug, Clone, Copy)]
+struct RegionInfo {
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    operator: CombinationOperator,
+}
+
+impl RegionInfo {
+    const SIZE: usize = 17;
+
+    fn parse(data: &[u8]) -> ParseResult<Self> {
+        Ok(Self {
+            width: read_u32(data, 0)?,
+            height: read_u32(data, 4)?,
+            x: read_u32(data, 8)?,
+            y: read_u32(data, 12)?,
+            operator: CombinationOperator::from_bits(*data.get(16).ok_or_else(truncated)? & 0x07)?,
+        })
+    }
+}
+
+/// The page being decoded
+struct Page {
+    number: u32,
+    bitmap: Bitmap,
+    default_pixel: u8,
+    /// Whether the page height is unknown and grows with each stripe
+    striped: bool,
+}
+
+/// Decoded results of the segments that later segments may refer to
+enum SegmentResult {
+    Symbols(SymbolDictionary),
+    Patterns(Vec<Bitmap>),
+    Table(HuffmanTable),
+    /// An intermediate region, kept for refinement
+    Region(Bitmap),
+}
+
+/// Decoding state for the segments of one page and its globals
+#[derive(Default)]
+struct PageDecoder {
+    page: Option<Page>,
+    results: HashMap<u32, SegmentResult>,
+}
+
+impl PageDecoder {
+    /// Decode one segment; returns `false` once the page is complete
+    fn process(&mut self, segment: &Segment) -> ParseResult<bool> {
+        let header = &segment.header;
+        let data = segment.data;
+
+        if let Some(page) = &self.page {
+            if header.page_association != 0 && header.page_association != page.number {
+                // Only the first page is decoded
+                return Ok(header.segment_type != 48);
+            }
+        }
+
+        match header.segment_type {
+            0 => {
+                let inputs = self.referred_symbols(header);
+                let tables = self.referred_tables(header);
+                let contexts = header
+                    .referred_segments
+                    .iter()
+                    .rev()
+                    .find_map(|number| match self.results.get(number) {
+                        Some(SegmentResult::Symbols(dictionary)) => Some(dictionary),
+                        _ => None,
+                    })
+                    .and_then(|dictionary| dictionary.retained.as_ref());
+                let dictionary =
+                    symbol::decode_symbol_dictionary(data, &inputs, &tables, contexts)?;
+                self.results
+                    .insert(header.segment_number, SegmentResult::Symbols(dictionary));
+            }
+            4 | 6 | 7 => {
+                let info = RegionInfo::parse(data)?;
+                let bitmap = self.decode_text_region(header, &info, &data[RegionInfo::SIZE..])?;
+                self.place_region(header, info, bitmap)?;
+            }
+            16 => {
+                let patterns = halftone::decode_pattern_dictionary(data)?;
+                self.results
+                    .insert(header.segment_number, SegmentResult::Patterns(patterns));
+            }
+            20 | 22 | 23 => {
+                let info = RegionInfo::parse(data)?;
+                let patterns = header
+                    .referred_segments
+                    .iter()
+                    .find_map(|number| match self.results.get(number) {
+                        Some(SegmentResult::Patterns(patterns)) => Some(patterns.as_slice()),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                let bitmap = halftone::decode_halftone_region(
+                    &data[RegionInfo::SIZE..],
+                    info.width,
+                    info.height,
+                    patterns,
+                )?;
+                self.place_region(header, info, bitmap)?;
+            }
+            36 | 38 | 39 => {
+                let mut info = RegionInfo::parse(data)?;
+                if header.data_length == UNKNOWN_LENGTH && data.len() >= 4 {
+                    // The row count at the end gives the actual height
+                    info.height = read_u32(data, data.len() - 4)?;
+                }
+                let bitmap = decode_generic_region(&info, &data[RegionInfo::SIZE..])?;
+                self.place_region(header, info, bitmap)?;
+            }
+            40 | 42 | 43 => {
+                let info = RegionInfo::parse(data)?;
+                let bitmap =
+                    self.decode_refinement_region(header, &info, &data[RegionInfo::SIZE..])?;
+                self.place_region(header, info, bitmap)?;
+            }
+            48 => {
+                if self.page.is_some() {
+                    return Ok(false);
+                }
+                self.page = Some(parse_page_information(header.page_association, data)?);
+            }
+            // End of page, end of file
+            49 | 51 => return Ok(self.page.is_none()),
+            50 => {
+                let end_row = read_u32(data, 0)?;
+                if let Some(page) = &mut self.page {
+                    if page.striped && end_row < u32::MAX {
+                        page.bitmap.extend_to(end_row + 1, page.default_pixel)?;
+                    }
+                }
+            }
+            53 => {
+                let table = HuffmanTable::parse(data)?;
+                self.results
+                    .insert(header.segment_number, SegmentResult::Table(table));
+            }
+            // Profiles, extensions and anything else carry no image data
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Symbols exported by the referred-to symbol dictionaries, in order
+    fn referred_symbols(&self, header: &Jbig2SegmentHeader) -> Vec<Rc<Bitmap>> {
+        header
+            .referred_segments
+            .iter()
+            .filter_map(|number| match self.results.get(number) {
+                Some(SegmentResult::Symbols(dictionary)) => Some(dictionary.symbols.iter()),
+                _ => None,
+            })
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// The referred-to code table segments, in order
+    fn referred_tables(&self, header: &Jbig2SegmentHeader) -> Vec<&HuffmanTable> {
+        header
+            .referred_segments
+            .iter()
+            .filter_map(|number| match self.results.get(number) {
+                Some(SegmentResult::Table(table)) => Some(table),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Decode a text region segment (§7.4.3)
+    fn decode_text_region(
+        &self,
+        header: &Jbig2SegmentHeader,
+        info: &RegionInfo,
+        data: &[u8],
+    ) -> ParseResult<Bitmap> {
+        let flags = read_u16(data, 0)?;
+        let huffman = flags & 0x0001 != 0;
+        let refine = flags & 0x0002 != 0;
+        let refinement_template = (flags >> 15) as u8;
+        let mut pos = 2;
+
+        let huffman_flags = if huffman {
+            pos += 2;
+            read_u16(data, 2)?
+        } else {
+            0
+        };
+        let mut refinement_at = [(0, 0); 2];
+        if refine && refinement_template == 0 {
+            let at = read_at_pixels(data, pos, 2)?;
+            refinement_at = [at[0], at[1]];
+            pos += 4;
+        }
+        let num_instances = read_u32(data, pos)?;
+        pos += 4;
+
+        let symbols = self.referred_symbols(header);
+        // SBDSOFFSET is a 5-bit two's complement value
+        let ds_offset = (((flags >> 10) & 0x1F) as i32 ^ 0x10) - 0x10;
+        let region = TextRegion {
+            width: info.width,
+            height: info.height,
+            num_instances,
+            log_strips: ((flags >> 2) & 0x03) as u32,
+            symbols: &symbols,
+            default_pixel: ((flags >> 9) & 0x01) as u8,
+            combination_operator: CombinationOperator::from_bits(((flags >> 7) & 0x03) as u8)?,
+            transposed: flags & 0x0040 != 0,
+            ref_corner: ((flags >> 4) & 0x03) as u8,
+            ds_offset,
+            refine,
+            refinement_template,
+            refinement_at,
+        };
+
+        let body = &data[pos.min(data.len())..];
+        if huffman {
+            let tables = select_text_tables(huffman_flags, &self.referred_tables(header))?;
+            let mut reader = BitReader::new(body);
+            let symbol_codes =
+                SymbolCodes::Table(read_symbol_code_table(&mut reader, symbols.len())?);
+            region.decode(&mut HuffmanInstances {
+                reader: &mut reader,
+                tables: &tables,
+                symbol_codes: &symbol_codes,
+            })
+        } else {
+            let mut decoder = ArithmeticDecoder::new(body);
+            let mut contexts = TextContexts::new(code_length(symbols.len()));
+            let mut refinement_contexts =
+                new_contexts(REFINEMENT_CONTEXTS[refinement_template as usize]);
+            region.decode(&mut ArithmeticInstances {
+                decoder: &mut decoder,
+                contexts: &mut contexts,
+                refinement_contexts: &mut refinement_contexts,
+            })
+        }
+    }
+
+    /// Decode a generic refinement region segment (§7.4.7)
+    fn decode_refinement_region(
+        &self,
+        header: &Jbig2SegmentHeader,
+        info: &RegionInfo,
+        data: &[u8],
+    ) -> ParseResult<Bitmap> {
+        let flags = *data.first().ok_or_else(truncated)?;
+        let template = flags & 0x01;
+        let at = if template == 0 {
+            let at = read_at_pixels(data, 1, 2)?;
+            [at[0], at[1]]
+        } else {
+            [(0, 0); 2]
+        };
+        let body = &data[if template == 0 { 5 } else { 1 }..];
+
+        // The reference is the referred-to intermediate region, or else the
+        // part of the page the region covers
+        let referred =
+            header
+                .referred_segments
+                .iter()
+                .find_map(|number| match self.results.get(number) {
+                    Some(SegmentResult::Region(bitmap)) => Some(bitmap),
+                    _ => None,
+                });
+        let page_region;
+        let reference = match referred {
+            Some(bitmap) => bitmap,
+            None => {
+                let page = self.page.as_ref().ok_or_else(|| {
+                    ParseError::StreamDecodeError(
+                        "JBIG2 refinement region without a reference".to_string(),
+                    )
+                })?;
+                page_region =
+                    page.bitmap
+                        .region(info.x as i32, info.y as i32, info.width, info.height)?;
+                &page_region
+            }
+        };
+
+        let mut contexts = new_contexts(REFINEMENT_CONTEXTS[template as usize]);
+        RefinementRegion {
+            width: info.width,
+            height: info.height,
+            template,
+            reference,
+            dx: 0,
+            dy: 0,
+            typical_prediction: flags & 0x02 != 0,
+            at,
+        }
+        .decode(&mut ArithmeticDecoder::new(body), &mut contexts)
+    }
+
+    /// Keep an intermediate region or draw an immediate one onto the page
+    fn place_region(
+        &mut self,
+        header: &Jbig2SegmentHeader,
+        info: RegionInfo,
+        bitmap: Bitmap,
+    ) -> ParseResult<()> {
+        // Intermediate region types are 4, 20, 36 and 40
+        if header.segment_type % 4 == 0 {
+            self.results
+                .insert(header.segment_number, SegmentResult::Region(bitmap));
+            return Ok(());
+        }
+
+        let page = self.page.as_mut().ok_or_else(|| {
+            ParseError::StreamDecodeError(
+                "JBIG2 region segment before page information".to_string(),
+            )
+        })?;
+        if page.striped {
+            let bottom = info.y.saturating_add(bitmap.height);
+            if bottom > page.bitmap.height {
+                page.bitmap.extend_to(bottom, page.default_pixel)?;
+            }
+        }
+        let x = i32::try_from(info.x).unwrap_or(i32::MAX);
+        let y = i32::try_from(info.y).unwrap_or(i32::MAX);
+        page.bitmap.compose(&bitmap, x, y, info.operator);
+        Ok(())
+    }
+}
+
+/// Parse a page information segment (§7.4.8)
+fn parse_page_information(number: u32, data: &[u8]) -> ParseResult<Page> {
+    let width = read_u32(data, 0)?;
+    let height = read_u32(data, 4)?;
+    let flags = *data.get(16).ok_or_else(truncated)?;
+    let default_pixel = (flags >> 2) & 0x01;
+    let striped = height == UNKNOWN_LENGTH;
+    Ok(Page {
+        number,
+        bitmap: Bitmap::new(width, if striped { 0 } else { height }, default_pixel)?,
+        default_pixel,
+        striped,
+    })
+}
+
+/// Decode a generic region segment's data (§7.4.6)
+fn decode_generic_region(info: &RegionInfo, data: &[u8]) -> ParseResult<Bitmap> {
+    let flags = *data.first().ok_or_else(truncated)?;
+    let mmr = flags & 0x01 != 0;
+    let template = (flags >> 1) & 0x03;
+    if flags & 0x10 != 0 {
+        return Err(ParseError::StreamDecodeError(
+            "JBIG2 extended generic region templates are not supported".to_string(),
+        ));
+    }
+
+    if mmr {
+        return Ok(mmr::decode_mmr(&data[1..], info.width, info.height)?.0);
+    }
+
+    let count = if template == 0 { 4 } else { 1 };
+    let at = read_at_pixels(data, 1, count)?;
+    let body = &data[1 + count * 2..];
+    let mut contexts = new_contexts(GENERIC_CONTEXTS[template as usize]);
+    GenericRegion {
+        width: info.width,
+        height: info.height,
+        template,
+        typical_prediction: flags & 0x08 != 0,
+        at: &at,
+        skip: None,
+    }
+    .decode(&mut ArithmeticDecoder::new(body), &mut contexts)
+}
+
+/// Select the Huffman tables of a text region from its Huffman flags
+/// (§7.4.3.1.2), taking custom tables from `custom` in order
+fn select_text_tables(flags: u16, custom: &[&HuffmanTable]) -> ParseResult<TextTables> {
+    let mut custom = custom.iter();
+    let mut select = |selection: u16, standard: &[usize]| {
+        if let Some(&number) = standard.get(selection as usize) {
+            if number != 0 {
+                return Ok(standard_table(number));
+            }
+        }
+        if selection as usize == standard.len() {
+            return custom.next().map(|table| (*table).clone()).ok_or_else(|| {
+                ParseError::StreamDecodeError(
+                    "JBIG2 text region is missing a custom Huffman table".to_string(),
+                )
+            });
+        }
+        Err(ParseError::StreamDecodeError(
+            "Invalid JBIG2 text region table selection".to_string(),
+        ))
+    };
+
+    Ok(TextTables {
+        fs: select(flags & 0x03, &[6, 7, 0])?,
+        ds: select((flags >> 2) & 0x03, &[8, 9, 10])?,
+        dt: select((flags >> 4) & 0x03, &[11, 12, 13])?,
+        rdw: select((flags >> 6) & 0x03, &[14, 15, 0])?,
+        rdh: select((flags >> 8) & 0x03, &[14, 15, 0])?,
+        rdx: select((flags >> 10) & 0x03, &[14, 15, 0])?,
+        rdy: select((flags >> 12) & 0x03, &[14, 15, 0])?,
+        rsize: select((flags >> 14) & 0x01, &[1])?,
+    })
+}
+
+/// Read a big-endian u16 at `pos`
+pub(super) fn read_u16(data: &[u8], pos: usize) -> ParseResult<u16> {
+    data.get(pos..pos + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(truncated)
+}
+
+/// Read a big-endian u32 at `pos`
+pub(super) fn read_u32(data: &[u8], pos: usize) -> ParseResult<u32> {
+    data.get(pos..pos + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(truncated)
+}
+
+/// Read `count` signed adaptive template pixel positions at `pos`
+pub(super) fn read_at_pixels(
+    data: &[u8],
+    pos: usize,
+    count: usize,
+) -> ParseResult<Vec<(i32, i32)>> {
+    let bytes = data.get(pos..pos + count * 2).ok_or_else(truncated)?;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| (pair[0] as i8 as i32, pair[1] as i8 as i32))
+        .collect())
+}
+
+fn truncated() -> ParseError {
+    ParseError::StreamDecodeError("Truncated JBIG2 segment data".to_string())
+}
+
+fn incomplete_header() -> ParseError {
+    ParseError::StreamDecodeError("JBIG2 segment header incomplete".to_string())
+}
+
+/// Main JBIG2 decode function
+pub fn decode_jbig2(data: &[u8], params: Option<&PdfDictionary>) -> ParseResult<Vec<u8>> {
+    let decode_params = if let Some(dict) = params {
+        Jbig2DecodeParams::from_dict(dict)
+    } else {
+        Jbig2DecodeParams::default()
+    };
+
+    let decoder = Jbig2Decoder::new(decode_params);
+    decoder.decode(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::objects::PdfStream;
+
+    /// Build a segment with a short-form header
+    fn segment(number: u32, segment_type: u8, referred: &[u8], page: u8, data: &[u8]) -> Vec<u8> {
+        let mut bytes = number.to_be_bytes().to_vec();
+        bytes.push(segment_type);
+        bytes.push((referred.len() as u8) << 5);
+        bytes.extend_from_slice(referred);
+        bytes.push(page);
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn page_information(width: u32, height: u32, flags: u8) -> Vec<u8> {
+        let mut data = width.to_be_bytes().to_vec();
+        data.extend(height.to_be_bytes());
+        data.extend([0; 8]); // resolution
+        data.push(flags);
+        data.extend([0, 0]); // striping
+        data
+    }
+
+    fn region_info(width: u32, height: u32, x: u32, y: u32) -> Vec<u8> {
+        let mut data = width.to_be_bytes().to_vec();
+        data.extend(height.to_be_bytes());
+        data.extend(x.to_be_bytes());
+        data.extend(y.to_be_bytes());
+        data.push(0); // OR
+        data
+    }
+
+    /// MMR-coded generic region of one row "..##"
+    fn mmr_region(x: u32, y: u32) -> Vec<u8> {
+        let mut data = region_info(4, 1, x, y);
+        data.push(0x01);
+        data.extend([0b0010_1111, 0b1000_0000]);
+        data
+    }
+
+    fn single_region_page() -> Vec<u8> {
+        let mut data = segment(0, 48, &[], 1, &page_information(4, 1, 0));
+        data.extend(segment(1, 38, &[], 1, &mmr_region(0, 0)));
+        data.extend(segment(2, 49, &[], 1, &[]));
+        data
+    }
+
+    #[test]
+    fn test_jbig2_decode_params_default() {
+        let params = Jbig2DecodeParams::default();
+        assert!(params.jbig2_globals.is_none());
+    }
+
+    #[test]
+    fn test_jbig2_decode_params_from_dict() {
+        let mut dict = PdfDictionary::new();
+        dict.insert(
+            "JBIG2Globals".to_string(),
+            PdfObject::Stream(PdfStream {
+                dict: PdfDictionary::new(),
+                data: vec![1, 2, 3],
+            }),
+        );
+
+        let params = Jbig2DecodeParams::from_dict(&dict);
+        assert_eq!(params.jbig2_globals, Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_jbig2_decode_params_unresolved_globals() {
+        let mut dict = PdfDictionary::new();
+        dict.insert("JBIG2Globals".to_string(), PdfObject::Reference(10, 0));
+
+        let params = Jbig2DecodeParams::from_dict(&dict);
+        assert!(params.jbig2_globals.is_none());
+    }
+
+    #[test]
+    fn test_jbig2_decode_params_from_empty_dict() {
+        let params = Jbig2DecodeParams::from_dict(&PdfDictionary::new());
+        assert!(params.jbig2_globals.is_none());
+    }
+
+    #[test]
+    fn test_jbig2_decoder_creation() {
+        let params = Jbig2DecodeParams::default();
+        let decoder = Jbig2Decoder::new(params);
+        assert!(decoder.params.jbig2_globals.is_none());
+    }
+
+    #[test]
+    fn test_jbig2_decode_too_short() {
+        let data = vec![0x01, 0x02, 0x03];
+        assert!(decode_jbig2(&data, None).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_decode_empty_data() {
+        assert!(decode_jbig2(&[], None).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_decode_embedded_stream() {
+        let decoded = decode_jbig2(&single_region_page(), None).unwrap();
+        assert_eq!(decoded, vec![0b1100_1111]);
+    }
+
+    #[test]
+    fn test_jbig2_default_pixel_and_placement() {
+        let mut data = segment(0, 48, &[], 1, &page_information(8, 2, 0x04));
+        data.extend(segment(1, 38, &[], 1, &mmr_region(2, 1)));
+        let decoded = decode_jbig2(&data, None).unwrap();
+        // Default black page; OR leaves it black everywhere
+        assert_eq!(decoded, vec![0x00, 0x00]);
+
+        let mut data = segment(0, 48, &[], 1, &page_information(8, 2, 0));
+        data.extend(segment(1, 38, &[], 1, &mmr_region(2, 1)));
+        let decoded = decode_jbig2(&data, None).unwrap();
+        assert_eq!(decoded, vec![0xFF, 0b1111_0011]);
+    }
+
+    #[test]
+    fn test_jbig2_decode_with_file_header() {
+        let mut data = FILE_ID.to_vec();
+        data.push(0x01); // sequential
+        data.extend(1u32.to_be_bytes()); // page count
+        data.extend(single_region_page());
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0b1100_1111]);
+
+        // Unknown page count, no page count field
+        let mut data = FILE_ID.to_vec();
+        data.push(0x03);
+        data.extend(single_region_page());
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0b1100_1111]);
+    }
+
+    #[test]
+    fn test_jbig2_decode_random_access_file() {
+        let page = page_information(4, 1, 0);
+        let region = mmr_region(0, 0);
+        let header_only = |number, segment_type, len: usize| {
+            let mut bytes = segment(number, segment_type, &[], 1, &[]);
+            let at = bytes.len() - 4;
+            bytes[at..].copy_from_slice(&(len as u32).to_be_bytes());
+            bytes
+        };
+
+        let mut data = FILE_ID.to_vec();
+        data.push(0x02); // random access, unknown page count
+        data.extend(header_only(0, 48, page.len()));
+        data.extend(header_only(1, 38, region.len()));
+        data.extend(header_only(2, 51, 0));
+        data.extend(&page);
+        data.extend(&region);
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0b1100_1111]);
+    }
+
+    #[test]
+    fn test_jbig2_decode_without_page_information() {
+        let data = segment(1, 38, &[], 1, &mmr_region(0, 0));
+        assert!(decode_jbig2(&data, None).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_segment_header_parsing() {
+        let mut bytes = segment(0x0102, 6, &[0, 1], 3, &[9, 9]);
+        // Segment number above 256: one referred-to number of two bytes
+        bytes[5] = 0x20;
+        let (header, length) = parse_segment_header(&bytes).unwrap();
+        assert_eq!(header.segment_number, 0x0102);
+        assert_eq!(header.segment_type, 6);
+        assert_eq!(header.referred_segments, vec![1]);
+        assert_eq!(header.page_association, 3);
+        assert_eq!(header.data_length, 2);
+        assert_eq!(length, bytes.len() - 2);
+    }
+
+    #[test]
+    fn test_jbig2_segment_header_long_page_association() {
+        let mut bytes = 5u32.to_be_bytes().to_vec();
+        bytes.push(0x40 | 38);
+        bytes.push(0x20); // one referred-to segment
+        bytes.push(4);
+        bytes.extend(0x0001_0000u32.to_be_bytes());
+        bytes.extend(UNKNOWN_LENGTH.to_be_bytes());
+
+        let (header, length) = parse_segment_header(&bytes).unwrap();
+        assert_eq!(header.referred_segments, vec![4]);
+        assert_eq!(header.page_association, 0x0001_0000);
+        assert_eq!(header.data_length, UNKNOWN_LENGTH);
+        assert_eq!(length, bytes.len());
+    }
+
+    #[test]
+    fn test_jbig2_segment_header_long_referred_count() {
+        let mut bytes = 9u32.to_be_bytes().to_vec();
+        bytes.push(4);
+        bytes.extend((0xE000_0000u32 | 9).to_be_bytes());
+        bytes.extend([0, 0]); // retention flags for 10 segments
+        bytes.extend(0..9u8);
+        bytes.push(1);
+        bytes.extend(0u32.to_be_bytes());
+
+        let (header, length) = parse_segment_header(&bytes).unwrap();
+        assert_eq!(header.referred_segments, (0..9).collect::<Vec<u32>>());
+        assert_eq!(length, bytes.len());
+    }
+
+    #[test]
+    fn test_jbig2_segment_header_too_short() {
+        assert!(parse_segment_header(&[0x00, 0x00, 0x00, 0x01, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_segment_header_incomplete() {
+        let bytes = segment(1, 6, &[1, 2, 3, 4], 1, &[]);
+        assert!(parse_segment_header(&bytes[..11]).is_err());
+        assert!(parse_segment_header(&[0, 0, 0, 1, 6, 0xA0, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_decode_segment_with_unknown_length() {
+        let mut region = mmr_region(0, 0);
+        region.extend([0x00, 0x00]); // end marker
+        region.extend(1u32.to_be_bytes()); // row count
+
+        let mut bytes = 1u32.to_be_bytes().to_vec();
+        bytes.extend([38, 0, 1]);
+        bytes.extend(UNKNOWN_LENGTH.to_be_bytes());
+        bytes.extend(&region);
+
+        let mut data = segment(0, 48, &[], 1, &page_information(4, 1, 0));
+        data.extend(bytes);
+        data.extend(segment(2, 49, &[], 1, &[]));
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0b1100_1111]);
+
+        let segments = parse_sequential(&data).unwrap();
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[1].data.len(), region.len());
+    }
+
+    #[test]
+    fn test_jbig2_unknown_length_requires_generic_region() {
+        let mut bytes = 1u32.to_be_bytes().to_vec();
+        bytes.extend([6, 0, 1]);
+        bytes.extend(UNKNOWN_LENGTH.to_be_bytes());
+        bytes.extend([0; 32]);
+        assert!(parse_sequential(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_decode_segment_beyond_data() {
+        let mut data = single_region_page();
+        data.truncate(data.len() - 12);
+        // The truncated region still decodes from the bytes available
+        let segments = parse_sequential(&data).unwrap();
+        assert_eq!(segments.len(), 2);
+    }
+
+    #[test]
+    fn test_jbig2_decoder_with_globals() {
+        // Pattern dictionary with ".." and "##" in the globals
+        let mut patterns = vec![0x01, 2, 1];
+        patterns.extend(1u32.to_be_bytes());
+        patterns.extend([0b0010_1111, 0b1000_0000]);
+        let globals = segment(1, 16, &[], 0, &patterns);
+
+        // Halftone region of a 2x1 grid selecting patterns 0 and 1
+        let mut halftone = region_info(4, 1, 0, 0);
+        halftone.push(0x01);
+        halftone.extend(2u32.to_be_bytes());
+        halftone.extend(1u32.to_be_bytes());
+        halftone.extend(0u32.to_be_bytes());
+        halftone.extend(0u32.to_be_bytes());
+        halftone.extend(512u16.to_be_bytes());
+        halftone.extend(0u16.to_be_bytes());
+        halftone.extend([0b0010_0011, 0b1010_0000]);
+
+        let mut data = segment(2, 48, &[], 1, &page_information(4, 1, 0));
+        data.extend(segment(3, 23, &[1], 1, &halftone));
+
+        let decoder = Jbig2Decoder::new(Jbig2DecodeParams {
+            jbig2_globals: Some(globals),
+        });
+        assert_eq!(decoder.decode(&data).unwrap(), vec![0b1100_1111]);
+
+        // Without the globals there are no patterns to draw
+        assert!(decode_jbig2(&data, None).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_intermediate_region_is_not_drawn() {
+        let mut data = segment(0, 48, &[], 1, &page_information(4, 1, 0));
+        data.extend(segment(1, 36, &[], 1, &mmr_region(0, 0)));
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0xFF]);
+    }
+
+    #[test]
+    fn test_jbig2_striped_page_grows() {
+        let mut data = segment(0, 48, &[], 1, &page_information(4, UNKNOWN_LENGTH, 0));
+        data.extend(segment(1, 38, &[], 1, &mmr_region(0, 1)));
+        data.extend(segment(2, 50, &[], 1, &2u32.to_be_bytes()));
+        data.extend(segment(3, 49, &[], 1, &[]));
+        let decoded = decode_jbig2(&data, None).unwrap();
+        assert_eq!(decoded, vec![0xFF, 0b1100_1111, 0xFF]);
+    }
+
+    #[test]
+    fn test_jbig2_only_first_page_is_decoded() {
+        let mut data = single_region_page();
+        data.extend(segment(3, 48, &[], 2, &page_information(8, 8, 0)));
+        data.extend(segment(4, 38, &[], 2, &mmr_region(0, 0)));
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0b1100_1111]);
+    }
+
+    #[test]
+    fn test_jbig2_skip_unknown_segment_types() {
+        let mut data = segment(0, 48, &[], 1, &page_information(4, 1, 0));
+        data.extend(segment(1, 52, &[], 1, &[1, 2, 3])); // profiles
+        data.extend(segment(2, 62, &[], 1, &[0; 8])); // extension
+        data.extend(segment(3, 38, &[], 1, &mmr_region(0, 0)));
+        assert_eq!(decode_jbig2(&data, None).unwrap(), vec![0b1100_1111]);
+    }
+
+    #[test]
+    fn test_jbig2_text_region_table_selection() {
+        let custom = HuffmanTable::from_code_lengths(&[1, 1]);
+        assert!(select_text_tables(0, &[]).is_ok());
+        assert!(select_text_tables(0x0003, &[]).is_err());
+        assert!(select_text_tables(0x0003, &[&custom]).is_ok());
+        // FS selection 2 is reserved
+        assert!(select_text_tables(0x0002, &[&custom]).is_err());
+    }
+
+    #[test]
+    fn test_jbig2_arithmetic_generic_region() {
+        let mut region = region_info(16, 4, 0, 0);
+        region.push(0x08); // template 0, TPGDON
+        region.extend([3, 0xFF, 0xFD, 0xFF, 2, 0xFE, 0xFE, 0xFE]);
+        region.extend([0x5A; 16]);
+        let mut data = segment(0, 48, &[], 1, &page_information(16, 4, 0));
+        data.extend(segment(1, 38, &[], 1, &region));
+        assert_eq!(decode_jbig2(&data, None).unwrap().len(), 8);
+    }
+}
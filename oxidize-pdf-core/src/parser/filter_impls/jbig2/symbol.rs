@@ -0,0 +1,534 @@
+//! Symbol dictionary segments (T.88 §7.4.2) and their decoding procedure (§6.5)
+
+use super::arithmetic::{code_length, new_contexts, ArithmeticDecoder, Context, IntegerDecoder};
+use super::bitmap::{Bitmap, CombinationOperator};
+use super::generic::{GenericRegion, RefinementRegion, GENERIC_CONTEXTS, REFINEMENT_CONTEXTS};
+use super::huffman::{standard_table, BitReader, HuffmanTable};
+use super::mmr::decode_mmr;
+use super::text::{
+    ArithmeticInstances, HuffmanInstances, SymbolCodes, TextContexts, TextRegion, TextTables,
+};
+use super::{read_at_pixels, read_u16, read_u32};
+use crate::parser::{ParseError, ParseResult};
+use std::rc::Rc;
+
+/// Generic and refinement statistics kept for later dictionaries when the
+/// "bitmap coding context retained" flag is set
+#[derive(Debug, Clone)]
+pub(super) struct RetainedContexts {
+    generic: Vec<Context>,
+    refinement: Vec<Context>,
+}
+
+/// Result of decoding a symbol dictionary segment
+#[derive(Debug, Clone)]
+pub(super) struct SymbolDictionary {
+    /// The exported symbols, in order
+    pub symbols: Vec<Rc<Bitmap>>,
+    pub retained: Option<RetainedContexts>,
+}
+
+/// Symbol dictionary flags and parameters (§7.4.2.1)
+struct Header {
+    huffman: bool,
+    refine_aggregate: bool,
+    dh_table: u8,
+    dw_table: u8,
+    bmsize_table: u8,
+    aggregate_table: u8,
+    context_used: bool,
+    context_retained: bool,
+    template: u8,
+    refinement_template: u8,
+    at: Vec<(i32, i32)>,
+    refinement_at: [(i32, i32); 2],
+    num_exported: u32,
+    num_new: u32,
+}
+
+fn parse_header(data: &[u8]) -> ParseResult<(Header, usize)> {
+    let flags = read_u16(data, 0)?;
+    let huffman = flags & 0x0001 != 0;
+    let refine_aggregate = flags & 0x0002 != 0;
+    let template = ((flags >> 10) & 0x03) as u8;
+    let refinement_template = ((flags >> 12) & 0x01) as u8;
+    let mut pos = 2;
+
+    let at = if huffman {
+        Vec::new()
+    } else {
+        let count = if template == 0 { 4 } else { 1 };
+        let at = read_at_pixels(data, pos, count)?;
+        pos += count * 2;
+        at
+    };
+    let mut refinement_at = [(0, 0); 2];
+    if refine_aggregate && refinement_template == 0 {
+        let at = read_at_pixels(data, pos, 2)?;
+        refinement_at = [at[0], at[1]];
+        pos += 4;
+    }
+
+    let num_exported = read_u32(data, pos)?;
+    let num_new = read_u32(data, pos + 4)?;
+    pos += 8;
+
+    Ok((
+        Header {
+            huffman,
+            refine_aggregate,
+            dh_table: ((flags >> 2) & 0x03) as u8,
+            dw_table: ((flags >> 4) & 0x03) as u8,
+            bmsize_table: ((flags >> 6) & 0x01) as u8,
+            aggregate_table: ((flags >> 7) & 0x01) as u8,
+            context_used: flags & 0x0100 != 0,
+            context_retained: flags & 0x0200 != 0,
+            template,
+            refinement_template,
+            at,
+            refinement_at,
+            num_exported,
+            num_new,
+        },
+        pos,
+    ))
+}
+
+/// Huffman tables of a Huffman-coded dictionary (SDHUFFDH, …, SDHUFFAGGINST)
+struct Tables {
+    dh: HuffmanTable,
+    dw: HuffmanTable,
+    bmsize: HuffmanTable,
+    aggregate: HuffmanTable,
+}
+
+fn select_tables(header: &Header, custom: &[&HuffmanTable]) -> ParseResult<Tables> {
+    let mut custom = custom.iter();
+    let mut next_custom = || {
+        custom.next().map(|table| (*table).clone()).ok_or_else(|| {
+            ParseError::StreamDecodeError(
+                "JBIG2 symbol dictionary is missing a custom Huffman table".to_string(),
+            )
+        })
+    };
+    let invalid = || {
+        ParseError::StreamDecodeError("Invalid JBIG2 symbol dictionary table selection".to_string())
+    };
+
+    let dh = match header.dh_table {
+        0 => standard_table(4),
+        1 => standard_table(5),
+        3 => next_custom()?,
+        _ => return Err(invalid()),
+    };
+    let dw = match header.dw_table {
+        0 => standard_table(2),
+        1 => standard_table(3),
+        3 => next_custom()?,
+        _ => return Err(invalid()),
+    };
+    let bmsize = match header.bmsize_table {
+        0 => standard_table(1),
+        _ => next_custom()?,
+    };
+    let aggregate = match header.aggregate_table {
+        0 => standard_table(1),
+        _ => next_custom()?,
+    };
+    Ok(Tables {
+        dh,
+        dw,
+        bmsize,
+        aggregate,
+    })
+}
+
+/// Where the dictionary's values come from
+enum Coder<'a> {
+    Arithmetic {
+        decoder: ArithmeticDecoder<'a>,
+        iadh: IntegerDecoder,
+        iadw: IntegerDecoder,
+        iaex: IntegerDecoder,
+        iaai: IntegerDecoder,
+    },
+    Huffman {
+        reader: BitReader<'a>,
+        tables: Box<Tables>,
+    },
+}
+
+fn required(value: Option<i32>) -> ParseResult<i32> {
+    value.ok_or_else(|| {
+        ParseError::StreamDecodeError("Unexpected JBIG2 out-of-band value".to_string())
+    })
+}
+
+/// Decode a symbol dictionary segment.
+///
+/// `input_symbols` are the symbols exported by the referred-to dictionaries
+/// and `custom_tables` the referred-to table segments, both in order.
+pub(super) fn decode_symbol_dictionary(
+    data: &[u8],
+    input_symbols: &[Rc<Bitmap>],
+    custom_tables: &[&HuffmanTable],
+    input_contexts: Option<&RetainedContexts>,
+) -> ParseResult<SymbolDictionary> {
+    let (header, pos) = parse_header(data)?;
+    let body = &data[pos..];
+    let num_new = header.num_new as usize;
+    let total = input_symbols.len() + num_new;
+    let symbol_code_length = code_length(total);
+
+    let mut coder = if header.huffman {
+        Coder::Huffman {
+            reader: BitReader::new(body),
+            tables: Box::new(select_tables(&header, custom_tables)?),
+        }
+    } else {
+        Coder::Arithmetic {
+            decoder: ArithmeticDecoder::new(body),
+            iadh: IntegerDecoder::new(),
+            iadw: IntegerDecoder::new(),
+            iaex: IntegerDecoder::new(),
+            iaai: IntegerDecoder::new(),
+        }
+    };
+
+    let (mut generic_contexts, mut refinement_contexts) = match input_contexts {
+        Some(retained) if header.context_used => {
+            (retained.generic.clone(), retained.refinement.clone())
+        }
+        _ => (
+            new_contexts(GENERIC_CONTEXTS[header.template as usize]),
+            new_contexts(REFINEMENT_CONTEXTS[header.refinement_template as usize]),
+        ),
+    };
+    let mut text_contexts = TextContexts::new(symbol_code_length);
+
+    // Input symbols followed by the new symbols decoded so far (SBSYMS)
+    let mut symbols: Vec<Rc<Bitmap>> = input_symbols.to_vec();
+    let mut height_class_height = 0i64;
+    let mut height_classes = 0usize;
+
+    while symbols.len() < total {
+        height_classes += 1;
+        if height_classes > num_new + 16 {
+            return Err(ParseError::StreamDecodeError(
+                "JBIG2 symbol dictionary has too many height classes".to_string(),
+            ));
+        }
+
+        height_class_height += match &mut coder {
+            Coder::Arithmetic { decoder, iadh, .. } => required(iadh.decode(decoder))?,
+            Coder::Huffman { reader, tables } => tables.dh.decode_value(reader)?,
+        } as i64;
+        let height = u32::try_from(height_class_height).map_err(|_| {
+            ParseError::StreamDecodeError("Invalid JBIG2 height class height".to_string())
+        })?;
+
+        let mut symbol_width = 0i64;
+        let mut total_width = 0i64;
+        // Widths of the symbols of a collective bitmap (SDNEWSYMWIDTHS)
+        let mut collective_widths = Vec::new();
+
+        loop {
+            let delta = match &mut coder {
+                Coder::Arithmetic { decoder, iadw, .. } => iadw.decode(decoder),
+                Coder::Huffman { reader, tables } => tables.dw.decode(reader)?,
+            };
+            let Some(delta) = delta else { break };
+            if symbols.len() + collective_widths.len() >= total {
+                return Err(ParseError::StreamDecodeError(
+                    "JBIG2 symbol dictionary decodes more symbols than declared".to_string(),
+                ));
+            }
+            symbol_width += delta as i64;
+            total_width += symbol_width;
+            let width = u32::try_from(symbol_width).map_err(|_| {
+                ParseError::StreamDecodeError("Invalid JBIG2 symbol width".to_string())
+            })?;
+
+            if header.huffman && !header.refine_aggregate {
+                collective_widths.push(width);
+                continue;
+            }
+
+            let bitmap = if !header.refine_aggregate {
+                let Coder::Arithmetic { decoder, .. } = &mut coder else {
+                    unreachable!("Huffman dictionaries use collective bitmaps")
+                };
+                GenericRegion {
+                    width,
+                    height,
+                    template: header.template,
+                    typical_prediction: false,
+                    at: &header.at,
+                    skip: None,
+                }
+                .decode(decoder, &mut generic_contexts)?
+            } else {
+                decode_aggregate(
+                    &header,
+                    &mut coder,
+                    &symbols,
+                    width,
+                    height,
+                    symbol_code_length,
+                    &mut text_contexts,
+                    &mut refinement_contexts,
+                )?
+            };
+            symbols.push(Rc::new(bitmap));
+        }
+
+        if let Coder::Huffman { reader, tables } = &mut coder {
+            if !header.refine_aggregate && !collective_widths.is_empty() {
+                let total_width = u32::try_from(total_width).map_err(|_| {
+                    ParseError::StreamDecodeError("Invalid JBIG2 collective bitmap".to_string())
+                })?;
+                let size = tables.bmsize.decode_value(reader)?;
+                reader.align();
+                let collective = if size == 0 {
+                    let stride = (total_width as usize).div_ceil(8);
+                    let bytes = reader.take_bytes(stride * height as usize)?;
+                    uncompressed_bitmap(bytes, total_width, height)?
+                } else {
+                    let bytes = reader.take_bytes(size.max(0) as usize)?;
+                    decode_mmr(bytes, total_width, height)?.0
+                };
+                let mut x = 0i32;
+                for width in collective_widths {
+                    symbols.push(Rc::new(collective.region(x, 0, width, height)?));
+                    x += width as i32;
+                }
+            }
+        }
+    }
+
+    // Exported symbols flags (§6.5.10)
+    let mut exported = Vec::with_capacity(header.num_exported as usize);
+    let mut index = 0usize;
+    let mut export = false;
+    let mut runs = 0usize;
+    while index < total {
+        runs += 1;
+        if runs > 2 * total + 2 {
+            return Err(ParseError::StreamDecodeError(
+                "Invalid JBIG2 symbol export flags".to_string(),
+            ));
+        }
+        let run = match &mut coder {
+            Coder::Arithmetic { decoder, iaex, .. } => required(iaex.decode(decoder))?,
+            Coder::Huffman { reader, .. } => standard_table(1).decode_value(reader)?,
+        };
+        let run = usize::try_from(run)
+            .ok()
+            .filter(|run| index + run <= total)
+            .ok_or_else(|| {
+                ParseError::StreamDecodeError("Invalid JBIG2 symbol export run".to_string())
+            })?;
+        if export {
+            exported.extend(symbols[index..index + run].iter().cloned());
+        }
+        index += run;
+        export = !export;
+    }
+
+    Ok(SymbolDictionary {
+        symbols: exported,
+        retained: header.context_retained.then_some(RetainedContexts {
+            generic: generic_contexts,
+            refinement: refinement_contexts,
+        }),
+    })
+}
+
+/// Decode one symbol by refinement/aggregate coding (§6.5.8.2)
+#[allow(clippy::too_many_arguments)]
+fn decode_aggregate(
+    header: &Header,
+    coder: &mut Coder,
+    symbols: &[Rc<Bitmap>],
+    width: u32,
+    height: u32,
+    symbol_code_length: u32,
+    text_contexts: &mut TextContexts,
+    refinement_contexts: &mut [Context],
+) -> ParseResult<Bitmap> {
+    let instances = match coder {
+        Coder::Arithmetic { decoder, iaai, .. } => required(iaai.decode(decoder))?,
+        Coder::Huffman { reader, tables } => tables.aggregate.decode_value(reader)?,
+    };
+
+    if instances == 1 {
+        let (id, dx, dy) = match coder {
+            Coder::Arithmetic { decoder, .. } => {
+                let id = text_contexts.symbol_id(decoder);
+                let (dx, dy) = text_contexts.refinement_offsets(decoder)?;
+                (id, dx, dy)
+            }
+            Coder::Huffman { reader, .. } => {
+                let id = reader.read_bits(symbol_code_length.max(1))? as usize;
+                let offsets = standard_table(15);
+                (
+                    id,
+                    offsets.decode_value(reader)?,
+                    offsets.decode_value(reader)?,
+                )
+            }
+        };
+        let reference = symbols.get(id).ok_or_else(|| {
+            ParseError::StreamDecodeError(format!(
+                "JBIG2 symbol refinement refers to unknown symbol {id}"
+            ))
+        })?;
+        let region = RefinementRegion {
+            width,
+            height,
+            template: header.refinement_template,
+            reference,
+            dx,
+            dy,
+            typical_prediction: false,
+            at: header.refinement_at,
+        };
+        return match coder {
+            Coder::Arithmetic { decoder, .. } => region.decode(decoder, refinement_contexts),
+            Coder::Huffman { reader, .. } => {
+                let size = standard_table(1).decode_value(reader)?;
+                reader.align();
+                let bytes = reader.take_bytes(size.max(0) as usize)?;
+                let mut contexts =
+                    new_contexts(REFINEMENT_CONTEXTS[header.refinement_template as usize]);
+                region.decode(&mut ArithmeticDecoder::new(bytes), &mut contexts)
+            }
+        };
+    }
+
+    let region = TextRegion {
+        width,
+        height,
+        num_instances: u32::try_from(instances).map_err(|_| {
+            ParseError::StreamDecodeError("Invalid JBIG2 aggregate instance count".to_string())
+        })?,
+        log_strips: 0,
+        symbols,
+        default_pixel: 0,
+        combination_operator: CombinationOperator::Or,
+        transposed: false,
+        ref_corner: 1,
+        ds_offset: 0,
+        refine: true,
+        refinement_template: header.refinement_template,
+        refinement_at: header.refinement_at,
+    };
+    match coder {
+        Coder::Arithmetic { decoder, .. } => region.decode(&mut ArithmeticInstances {
+            decoder,
+            contexts: text_contexts,
+            refinement_contexts,
+        }),
+        Coder::Huffman { reader, .. } => {
+            let tables = TextTables {
+                fs: standard_table(6),
+                ds: standard_table(8),
+                dt: standard_table(11),
+                rdw: standard_table(15),
+                rdh: standard_table(15),
+                rdx: standard_table(15),
+                rdy: standard_table(15),
+                rsize: standard_table(1),
+            };
+            region.decode(&mut HuffmanInstances {
+                reader,
+                tables: &tables,
+                symbol_codes: &SymbolCodes::Fixed(symbol_code_length.max(1)),
+            })
+        }
+    }
+}
+
+/// A collective bitmap stored without compression, rows padded to bytes
+fn uncompressed_bitmap(bytes: &[u8], width: u32, height: u32) -> ParseResult<Bitmap> {
+    let mut bitmap = Bitmap::new(width, height, 0)?;
+    let stride = (width as usize).div_ceil(8);
+    for y in 0..height {
+        for x in 0..width {
+            let byte = bytes[y as usize * stride + x as usize / 8];
+            if byte & (0x80 >> (x % 8)) != 0 {
+                bitmap.set(x, y, 1);
+            }
+        }
+    }
+    Ok(bitmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Huffman-coded dictionary with an uncompressed collective bitmap:
+    /// one height class of height 2 holding symbols of widths 1 and 2
+    fn huffman_dictionary() -> Vec<u8> {
+        let mut data = vec![0x00, 0x01]; // SDHUFF, standard tables
+        data.extend(2u32.to_be_bytes()); // SDNUMEXSYMS
+        data.extend(2u32.to_be_bytes()); // SDNUMNEWSYMS
+                                         // B.4 HCDH=2: "10"; B.2 DW=1: "10" twice, OOB: "111111";
+                                         // B.1 BMSIZE=0: "00000", then align
+        data.extend([0b1010_1011, 0b1111_0000, 0b0000_0000]);
+        // Collective bitmap 3x2: "#.#", ".##"
+        data.extend([0b1010_0000, 0b0110_0000]);
+        // Export flags with B.1: run 0 not exported, run 2 exported
+        data.extend([0b0000_0000, 0b1000_0000]);
+        data
+    }
+
+    #[test]
+    fn test_huffman_dictionary_with_uncompressed_bitmap() {
+        let dictionary = decode_symbol_dictionary(&huffman_dictionary(), &[], &[], None).unwrap();
+        assert_eq!(dictionary.symbols.len(), 2);
+        let first = &dictionary.symbols[0];
+        let second = &dictionary.symbols[1];
+        assert_eq!((first.width, first.height), (1, 2));
+        assert_eq!((second.width, second.height), (2, 2));
+        assert_eq!((first.get(0, 0), first.get(0, 1)), (1, 0));
+        assert_eq!(
+            (
+                second.get(0, 0),
+                second.get(1, 0),
+                second.get(0, 1),
+                second.get(1, 1)
+            ),
+            (0, 1, 1, 1)
+        );
+        assert!(dictionary.retained.is_none());
+    }
+
+    #[test]
+    fn test_truncated_dictionary_is_an_error() {
+        let data = huffman_dictionary();
+        assert!(decode_symbol_dictionary(&data[..12], &[], &[], None).is_err());
+    }
+
+    #[test]
+    fn test_missing_custom_table_is_an_error() {
+        let mut data = huffman_dictionary();
+        data[1] |= 0x0C; // SDHUFFDH = 3 (custom)
+        assert!(decode_symbol_dictionary(&data, &[], &[], None).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_dictionary_decodes_declared_symbol_count() {
+        // Arbitrary arithmetic-coded data either decodes into the declared
+        // number of new symbols or fails; it never loops forever
+        let mut data = vec![0x00, 0x00]; // SDTEMPLATE 0, arithmetic
+        data.extend([3, 0xFF, 0xFD, 0xFF, 2, 0xFE, 0xFE, 0xFE]); // SDAT
+        data.extend(1u32.to_be_bytes());
+        data.extend(1u32.to_be_bytes());
+        data.extend([0x55; 64]);
+        if let Ok(dictionary) = decode_symbol_dictionary(&data, &[], &[], None) {
+            assert!(dictionary.symbols.len() <= 1);
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! Text region decoding (T.88 §6.4)
+
+use super::arithmetic::{
+    new_contexts, ArithmeticDecoder, Context, IntegerDecoder, SymbolIdDecoder,
+};
+use super::bitmap::{Bitmap, CombinationOperator};
+use super::generic::{RefinementRegion, REFINEMENT_CONTEXTS};
+use super::huffman::{BitReader, HuffmanTable};
+use crate::parser::{ParseError, ParseResult};
+use std::rc::Rc;
+
+/// REFCORNER values (§7.4.3.1.1)
+const BOTTOM_LEFT: u8 = 0;
+const TOP_LEFT: u8 = 1;
+const BOTTOM_RIGHT: u8 = 2;
+const TOP_RIGHT: u8 = 3;
+
+/// Parameters of the text region decoding procedure (Table 9)
+pub(super) struct TextRegion<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub num_instances: u32,
+    pub log_strips: u32,
+    pub symbols: &'a [Rc<Bitmap>],
+    pub default_pixel: u8,
+    pub combination_operator: CombinationOperator,
+    pub transposed: bool,
+    pub ref_corner: u8,
+    pub ds_offset: i32,
+    pub refine: bool,
+    pub refinement_template: u8,
+    pub refinement_at: [(i32, i32); 2],
+}
+
+/// Source of the values decoded for each symbol instance, either
+/// arithmetic-coded or Huffman-coded
+pub(super) trait InstanceDecoder {
+    /// DT, the strip T delta (IADT)
+    fn strip_delta_t(&mut self) -> ParseResult<i32>;
+    /// DFS, the first S delta of a strip (IAFS)
+    fn first_delta_s(&mut self) -> ParseResult<i32>;
+    /// IDS, the S delta between instances; `None` ends the strip (IADS)
+    fn delta_s(&mut self) -> ParseResult<Option<i32>>;
+    /// CURT, the T offset within the strip (IAIT)
+    fn t_offset(&mut self, log_strips: u32) -> ParseResult<i32>;
+    /// The symbol ID (IAID)
+    fn symbol_id(&mut self) -> ParseResult<usize>;
+    /// RI, whether the instance is refined (IARI)
+    fn refinement_flag(&mut self) -> ParseResult<bool>;
+    /// Decode the refinement deltas and the refined bitmap of `symbol`
+    fn refine(&mut self, region: &TextRegion, symbol: &Bitmap) -> ParseResult<Bitmap>;
+}
+
+impl TextRegion<'_> {
+    /// Decode the region (§6.4.5)
+    pub(super) fn decode(&self, coder: &mut impl InstanceDecoder) -> ParseResult<Bitmap> {
+        let mut region = Bitmap::new(self.width, self.height, self.default_pixel)?;
+        let strips = 1i64 << self.log_strips;
+        let corner = self.ref_corner;
+
+        let mut strip_t = -(coder.strip_delta_t()? as i64 * strips);
+        let mut first_s = 0i64;
+        let mut instances = 0u32;
+
+        'strips: while instances < self.num_instances {
+            strip_t += coder.strip_delta_t()? as i64 * strips;
+            first_s += coder.first_delta_s()? as i64;
+            let mut cur_s = first_s;
+
+            loop {
+                let cur_t = if strips == 1 {
+                    0
+                } else {
+                    coder.t_offset(self.log_strips)? as i64
+                };
+                let t = strip_t + cur_t;
+
+                let id = coder.symbol_id()?;
+                let symbol = self.symbols.get(id).ok_or_else(|| {
+                    ParseError::StreamDecodeError(format!(
+                        "JBIG2 text region refers to unknown symbol {id}"
+                    ))
+                })?;
+                let refined;
+                let bitmap = if self.refine && coder.refinement_flag()? {
+                    refined = coder.refine(self, symbol)?;
+                    &refined
+                } else {
+                    symbol.as_ref()
+                };
+                let w = bitmap.width as i64;
+                let h = bitmap.height as i64;
+
+                if !self.transposed && (corner == TOP_RIGHT || corner == BOTTOM_RIGHT) {
+                    cur_s += w - 1;
+                } else if self.transposed && (corner == BOTTOM_LEFT || corner == BOTTOM_RIGHT) {
+                    cur_s += h - 1;
+                }
+                let s = cur_s;
+
+                let (x, y) = match (self.transposed, corner) {
+                    (false, TOP_LEFT) => (s, t),
+                    (false, TOP_RIGHT) => (s - w + 1, t),
+                    (false, BOTTOM_LEFT) => (s, t - h + 1),
+                    (false, _) => (s - w + 1, t - h + 1),
+                    (true, TOP_LEFT) => (t, s),
+                    (true, TOP_RIGHT) => (t - w + 1, s),
+                    (true, BOTTOM_LEFT) => (t, s - h + 1),
+                    (true, _) => (t - w + 1, s - h + 1),
+                };
+                region.compose(
+                    bitmap,
+                    clamp_i32(x),
+                    clamp_i32(y),
+                    self.combination_operator,
+                );
+
+                if !self.transposed && (corner == TOP_LEFT || corner == BOTTOM_LEFT) {
+                    cur_s += w - 1;
+                } else if self.transposed && (corner == TOP_LEFT || corner == TOP_RIGHT) {
+                    cur_s += h - 1;
+                }
+                instances += 1;
+
+                match coder.delta_s()? {
+                    None => break,
+                    Some(ds) => cur_s += ds as i64 + self.ds_offset as i64,
+                }
+                if instances >= self.num_instances {
+                    break 'strips;
+                }
+            }
+        }
+        Ok(region)
+    }
+
+    /// Refinement parameters for an instance of `symbol` (§6.4.11)
+    fn refinement<'s>(
+        &self,
+        symbol: &'s Bitmap,
+        deltas: [i32; 4],
+    ) -> ParseResult<RefinementRegion<'s>> {
+        let [rdw, rdh, rdx, rdy] = deltas;
+        let width = symbol.width as i64 + rdw as i64;
+        let height = symbol.height as i64 + rdh as i64;
+        if !(0..=u32::MAX as i64).contains(&width) || !(0..=u32::MAX as i64).contains(&height) {
+            return Err(ParseError::StreamDecodeError(
+                "Invalid JBIG2 refined symbol size".to_string(),
+            ));
+        }
+        Ok(RefinementRegion {
+            width: width as u32,
+            height: height as u32,
+            template: self.refinement_template,
+            reference: symbol,
+            dx: (rdw >> 1) + rdx,
+            dy: (rdh >> 1) + rdy,
+            typical_prediction: false,
+            at: self.refinement_at,
+        })
+    }
+}
+
+fn clamp_i32(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// Integer decoding contexts of a text region; symbol dictionaries keep
+/// one set for all their refinement/aggregate instances
+pub(super) struct TextContexts {
+    iadt: IntegerDecoder,
+    iafs: IntegerDecoder,
+    iads: IntegerDecoder,
+    iait: IntegerDecoder,
+    iari: IntegerDecoder,
+    iardw: IntegerDecoder,
+    iardh: IntegerDecoder,
+    iardx: IntegerDecoder,
+    iardy: IntegerDecoder,
+    iaid: SymbolIdDecoder,
+}
+
+impl TextContexts {
+    pub(super) fn new(symbol_code_length: u32) -> Self {
+        Self {
+            iadt: IntegerDecoder::new(),
+            iafs: IntegerDecoder::new(),
+            iads: IntegerDecoder::new(),
+            iait: IntegerDecoder::new(),
+            iari: IntegerDecoder::new(),
+            iardw: IntegerDecoder::new(),
+            iardh: IntegerDecoder::new(),
+            iardx: IntegerDecoder::new(),
+            iardy: IntegerDecoder::new(),
+            iaid: SymbolIdDecoder::new(symbol_code_length),
+        }
+    }
+
+    /// IAID decoding outside of a text region (symbol dictionary refinement)
+    pub(super) fn symbol_id(&mut self, decoder: &mut ArithmeticDecoder) -> usize {
+        self.iaid.decode(decoder)
+    }
+
+    /// IARDX and IARDY decoding outside of a text region
+    pub(super) fn refinement_offsets(
+        &mut self,
+        decoder: &mut ArithmeticDecoder,
+    ) -> ParseResult<(i32, i32)> {
+        Ok((
+            value(self.iardx.decode(decoder))?,
+            value(self.iardy.decode(decoder))?,
+        ))
+    }
+}
+
+fn value(decoded: Option<i32>) -> ParseResult<i32> {
+    decoded.ok_or_else(|| {
+        ParseError::StreamDecodeError("Unexpected JBIG2 out-of-band value".to_string())
+    })
+}
+
+/// Arithmetic-coded symbol instances (SBHUFF = 0)
+pub(super) struct ArithmeticInstances<'a, 'd> {
+    pub decoder: &'d mut ArithmeticDecoder<'a>,
+    pub contexts: &'d mut TextContexts,
+    pub refinement_contexts: &'d mut [Context],
+}
+
+impl InstanceDecoder for ArithmeticInstances<'_, '_> {
+    fn strip_delta_t(&mut self) -> ParseResult<i32> {
+        value(self.contexts.iadt.decode(self.decoder))
+    }
+
+    fn first_delta_s(&mut self) -> ParseResult<i32> {
+        value(self.contexts.iafs.decode(self.decoder))
+    }
+
+    fn delta_s(&mut self) -> ParseResult<Option<i32>> {
+        Ok(self.contexts.iads.decode(self.decoder))
+    }
+
+    fn t_offset(&mut self, _log_strips: u32) -> ParseResult<i32> {
+        value(self.contexts.iait.decode(self.decoder))
+    }
+
+    fn symbol_id(&mut self) -> ParseResult<usize> {
+        Ok(self.contexts.iaid.decode(self.decoder))
+    }
+
+    fn refinement_flag(&mut self) -> ParseResult<bool> {
+        Ok(value(self.contexts.iari.decode(self.decoder))? != 0)
+    }
+
+    fn refine(&mut self, region: &TextRegion, symbol: &Bitmap) -> ParseResult<Bitmap> {
+        let deltas = [
+            value(self.contexts.iardw.decode(self.decoder))?,
+            value(self.contexts.iardh.decode(self.decoder))?,
+            value(self.contexts.iardx.decode(self.decoder))?,
+            value(self.contexts.iardy.decode(self.decoder))?,
+        ];
+        region
+            .refinement(symbol, deltas)?
+            .decode(self.decoder, self.refinement_contexts)
+    }
+}
+
+/// How symbol IDs are coded when SBHUFF = 1
+pub(super) enum SymbolCodes {
+    /// Codes from the symbol ID Huffman table of the segment (§7.4.3.1.7)
+    Table(HuffmanTable),
+    /// Fixed-length codes, as in symbol dictionary refinement/aggregation
+    Fixed(u32),
+}
+
+/// Huffman tables of a text region (SBHUFFFS, SBHUFFDS, …, SBHUFFRSIZE)
+pub(super) struct TextTables {
+    pub fs: HuffmanTable,
+    pub ds: HuffmanTable,
+    pub dt: HuffmanTable,
+    pub rdw: HuffmanTable,
+    pub rdh: HuffmanTable,
+    pub rdx: HuffmanTable,
+    pub rdy: HuffmanTable,
+    pub rsize: HuffmanTable,
+}
+
+/// Huffman-coded symbol instances (SBHUFF = 1)
+pub(super) struct HuffmanInstances<'a, 'r> {
+    pub reader: &'r mut BitReader<'a>,
+    pub tables: &'r TextTables,
+    pub symbol_codes: &'r SymbolCodes,
+}
+
+impl InstanceDecoder for HuffmanInstances<'_, '_> {
+    fn strip_delta_t(&mut self) -> ParseResult<i32> {
+        self.tables.dt.decode_value(self.reader)
+    }
+
+    fn first_delta_s(&mut self) -> ParseResult<i32> {
+        self.tables.fs.decode_value(self.reader)
+    }
+
+    fn delta_s(&mut self) -> ParseResult<Option<i32>> {
+        self.tables.ds.decode(self.reader)
+    }
+
+    fn t_offset(&mut self, log_strips: u32) -> ParseResult<i32> {
+        Ok(self.reader.read_bits(log_strips)? as i32)
+    }
+
+    fn symbol_id(&mut self) -> ParseResult<usize> {
+        match self.symbol_codes {
+            SymbolCodes::Table(table) => Ok(table.decode_value(self.reader)? as usize),
+            SymbolCodes::Fixed(len) => Ok(self.reader.read_bits(*len)? as usize),
+        }
+    }
+
+    fn refinement_flag(&mut self) -> ParseResult<bool> {
+        Ok(self.reader.read_bit()? != 0)
+    }
+
+    fn refine(&mut self, region: &TextRegion, symbol: &Bitmap) -> ParseResult<Bitmap> {
+        let deltas = [
+            self.tables.rdw.decode_value(self.reader)?,
+            self.tables.rdh.decode_value(self.reader)?,
+            self.tables.rdx.decode_value(self.reader)?,
+            self.tables.rdy.decode_value(self.reader)?,
+        ];
+        let size = self.tables.rsize.decode_value(self.reader)?;
+        self.reader.align();
+        let data = self.reader.take_bytes(size.max(0) as usize)?;
+
+        // Each refinement is coded separately with fresh statistics
+        let mut contexts =
+            new_contexts(REFINEMENT_CONTEXTS[region.refinement_template as usize & 1]);
+        region
+            .refinement(symbol, deltas)?
+            .decode(&mut ArithmeticDecoder::new(data), &mut contexts)
+    }
+}
+
+/// Read the symbol ID Huffman table that starts a Huffman-coded text
+/// region's data (§7.4.3.1.7)
+pub(super) fn read_symbol_code_table(
+    reader: &mut BitReader,
+    symbol_count: usize,
+) -> ParseResult<HuffmanTable> {
+    let mut run_code_lengths = [0u32; 35];
+    for length in &mut run_code_lengths {
+        *length = reader.read_bits(4)?;
+    }
+    let run_codes = HuffmanTable::from_code_lengths(&run_code_lengths);
+
+    let mut lengths = Vec::with_capacity(symbol_count);
+    while lengths.len() < symbol_count {
+        let code = run_codes.decode_value(reader)?;
+        let (value, repeat) = match code {
+            0..=31 => (code as u32, 1),
+            32 => {
+                let previous = *lengths.last().ok_or_else(|| {
+                    ParseError::StreamDecodeError(
+                        "JBIG2 symbol code length repeat without a previous length".to_string(),
+                    )
+                })?;
+                (previous, 3 + reader.read_bits(2)?)
+            }
+            33 => (0, 3 + reader.read_bits(3)?),
+            _ => (0, 11 + reader.read_bits(7)?),
+        };
+        for _ in 0..repeat {
+            if lengths.len() < symbol_count {
+                lengths.push(value);
+            }
+        }
+    }
+    reader.align();
+    Ok(HuffmanTable::from_code_lengths(&lengths))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scripted instance values for exercising the placement logic
+    struct Script {
+        strip_t: Vec<i32>,
+        first_s: Vec<i32>,
+        delta_s: Vec<Option<i32>>,
+        ids: Vec<usize>,
+    }
+
+    impl InstanceDecoder for Script {
+        fn strip_delta_t(&mut self) -> ParseResult<i32> {
+            Ok(self.strip_t.remove(0))
+        }
+        fn first_delta_s(&mut self) -> ParseResult<i32> {
+            Ok(self.first_s.remove(0))
+        }
+        fn delta_s(&mut self) -> ParseResult<Option<i32>> {
+            Ok(self.delta_s.remove(0))
+        }
+        fn t_offset(&mut self, _log_strips: u32) -> ParseResult<i32> {
+            Ok(0)
+        }
+        fn symbol_id(&mut self) -> ParseResult<usize> {
+            Ok(self.ids.remove(0))
+        }
+        fn refinement_flag(&mut self) -> ParseResult<bool> {
+            Ok(false)
+        }
+        fn refine(&mut self, _region: &TextRegion, symbol: &Bitmap) -> ParseResult<Bitmap> {
+            Ok(symbol.clone())
+        }
+    }
+
+    fn rows(bitmap: &Bitmap) -> Vec<String> {
+        (0..bitmap.height as i32)
+            .map(|y| {
+                (0..bitmap.width as i32)
+                    .map(|x| if bitmap.get(x, y) != 0 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn region(symbols: &[Rc<Bitmap>], ref_corner: u8, transposed: bool) -> TextRegion<'_> {
+        TextRegion {
+            width: 8,
+            height: 4,
+            num_instances: 2,
+            log_strips: 0,
+            symbols,
+            default_pixel: 0,
+            combination_operator: CombinationOperator::Or,
+            transposed,
+            ref_corner,
+            ds_offset: 0,
+            refine: false,
+            refinement_template: 0,
+            refinement_at: [(0, 0); 2],
+        }
+    }
+
+    #[test]
+    fn test_symbols_are_placed_along_the_strip() {
+        let symbols = vec![
+            Rc::new(Bitmap::new(2, 2, 1).unwrap()),
+            Rc::new(Bitmap::new(1, 3, 1).unwrap()),
+        ];
+        // Strip at T = 3 with bottom-left reference corners: the first
+        // symbol at S = 1, the second one pixel after it
+        let mut script = Script {
+            strip_t: vec![0, 3],
+            first_s: vec![1],
+            delta_s: vec![Some(2), None],
+            ids: vec![0, 1],
+        };
+        let bitmap = region(&symbols, BOTTOM_LEFT, false)
+            .decode(&mut script)
+            .unwrap();
+        assert_eq!(
+            rows(&bitmap),
+            ["........", "....#...", ".##.#...", ".##.#..."]
+        );
+    }
+
+    #[test]
+    fn test_transposed_top_left_placement() {
+        let symbols = vec![Rc::new(Bitmap::new(2, 1, 1).unwrap())];
+        // Transposed: S runs down the region, T across it
+        let mut script = Script {
+            strip_t: vec![0, 5],
+            first_s: vec![0],
+            delta_s: vec![Some(2), None],
+            ids: vec![0, 0],
+        };
+        let bitmap = region(&symbols, TOP_LEFT, true)
+            .decode(&mut script)
+            .unwrap();
+        assert_eq!(
+            rows(&bitmap),
+            [".....##.", "........", ".....##.", "........"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_symbol_is_an_error() {
+        let symbols = vec![Rc::new(Bitmap::new(1, 1, 1).unwrap())];
+        let mut script = Script {
+            strip_t: vec![0, 0],
+            first_s: vec![0],
+            delta_s: vec![None],
+            ids: vec![3],
+        };
+        assert!(region(&symbols, TOP_LEFT, false)
+            .decode(&mut script)
+            .is_err());
+    }
+}
@@ -0,0 +1,423 @@
+//! Tier-1 decoding (ITU-T T.800 Annex D): the significance propagation,
+//! magnitude refinement and cleanup passes that recover the coefficients of
+//! a code-block one bit-plane at a time
+
+use super::super::jbig2::arithmetic::{ArithmeticDecoder, Context};
+use super::codestream::{BYPASS, RESET, SEGMENTATION_SYMBOLS, VERTICALLY_CAUSAL};
+use super::packet::{BandKind, Segment};
+
+const REFINEMENT_CONTEXTS: usize = 14;
+const RUN_LENGTH: usize = 17;
+const UNIFORM: usize = 18;
+
+const SIGNIFICANT: u8 = 0x01;
+const NEGATIVE: u8 = 0x02;
+/// Coded in the significance propagation pass of the current bit-plane
+const VISITED: u8 = 0x04;
+/// Refined at least once
+const REFINED: u8 = 0x08;
+
+/// Context states at the start of a code-block and after a reset (Table D.7)
+fn initial_contexts() -> [Context; 19] {
+    let mut contexts = [0; 19];
+    contexts[0] = 4 << 1;
+    contexts[RUN_LENGTH] = 3 << 1;
+    contexts[UNIFORM] = 46 << 1;
+    contexts
+}
+
+/// Decoded coefficients of a code-block in raster order
+pub(super) struct BlockCoefficients {
+    /// Magnitude bits decoded so far, most significant first
+    pub magnitude: Vec<u32>,
+    /// Bit-plane of the least significant decoded bit of each coefficient
+    pub plane: Vec<u8>,
+    pub negative: Vec<bool>,
+}
+
+/// Raw (bypassed) coding passes, with the bit-stuffing of D.6
+struct RawReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    byte: u8,
+    count: u8,
+}
+
+impl RawReader<'_> {
+    fn bit(&mut self) -> u8 {
+        if self.count == 0 {
+            let previous = self.byte;
+            self.byte = self.data.get(self.position).copied().unwrap_or(0xFF);
+            self.position += 1;
+            self.count = if previous == 0xFF { 7 } else { 8 };
+        }
+        self.count -= 1;
+        (self.byte >> self.count) & 1
+    }
+}
+
+enum Coder<'a> {
+    Arithmetic(ArithmeticDecoder<'a>),
+    Raw(RawReader<'a>),
+}
+
+impl Coder<'_> {
+    fn decode(&mut self, contexts: &mut [Context], cx: usize) -> u8 {
+        match self {
+            Self::Arithmetic(decoder) => decoder.decode_bit(contexts, cx),
+            Self::Raw(reader) => reader.bit(),
+        }
+    }
+
+    /// Sign bit, 1 for negative
+    fn sign(&mut self, contexts: &mut [Context], (cx, flip): (usize, u8)) -> u8 {
+        match self {
+            Self::Arithmetic(decoder) => decoder.decode_bit(contexts, cx) ^ flip,
+            Self::Raw(reader) => reader.bit(),
+        }
+    }
+}
+
+struct BlockState {
+    width: usize,
+    height: usize,
+    kind: BandKind,
+    causal: bool,
+    /// Flags with a one-coefficient border, (width + 2) per row
+    flags: Vec<u8>,
+    magnitude: Vec<u32>,
+    plane: Vec<u8>,
+    contexts: [Context; 19],
+}
+
+impl BlockState {
+    fn index(&self, x: usize, y: usize) -> usize {
+        (y + 1) * (self.width + 2) + x + 1
+    }
+
+    /// Significant horizontal, vertical and diagonal neighbours
+    fn neighbours(&self, x: usize, y: usize) -> (u32, u32, u32) {
+        let i = self.index(x, y);
+        let stride = self.width + 2;
+        let sig = |j: usize| (self.flags[j] & SIGNIFICANT) as u32;
+        // In vertically causal mode the stripe below counts as insignificant
+        let below = !(self.causal && y % 4 == 3);
+        let h = sig(i - 1) + sig(i + 1);
+        let mut v = sig(i - stride);
+        let mut d = sig(i - stride - 1) + sig(i - stride + 1);
+        if below {
+            v += sig(i + stride);
+            d += sig(i + stride - 1) + sig(i + stride + 1);
+        }
+        (h, v, d)
+    }
+
+    /// Zero coding context (Table D.1)
+    fn zero_context(&self, x: usize, y: usize) -> usize {
+        let (h, v, d) = self.neighbours(x, y);
+        let (h, v) = match self.kind {
+            BandKind::HighLow => (v, h),
+            _ => (h, v),
+        };
+        if self.kind == BandKind::HighHigh {
+            let hv = h + v;
+            return match (d, hv) {
+                (3.., _) => 8,
+                (2, 1..) => 7,
+                (2, 0) => 6,
+                (1, 2..) => 5,
+                (1, 1) => 4,
+                (1, 0) => 3,
+                (_, 2..) => 2,
+                (_, 1) => 1,
+                _ => 0,
+            };
+        }
+        match (h, v, d) {
+            (2.., _, _) => 8,
+            (1, 1.., _) => 7,
+            (1, 0, 1..) => 6,
+            (1, 0, 0) => 5,
+            (0, 2.., _) => 4,
+            (0, 1, _) => 3,
+            (0, 0, 2..) => 2,
+            (0, 0, 1) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Sign coding context and XOR bit (Tables D.2 and D.3)
+    fn sign_context(&self, x: usize, y: usize) -> (usize, u8) {
+        let i = self.index(x, y);
+        let stride = self.width + 2;
+        let contribution = |j: usize| match self.flags[j] & (SIGNIFICANT | NEGATIVE) {
+            SIGNIFICANT => 1,
+            f if f == SIGNIFICANT | NEGATIVE => -1,
+            _ => 0,
+        };
+        let below = !(self.causal && y % 4 == 3);
+        let horizontal = (contribution(i - 1) + contribution(i + 1)).clamp(-1, 1);
+        let vertical = (contribution(i - stride)
+            + if below { contribution(i + stride) } else { 0 })
+        .clamp(-1, 1);
+        match (horizontal, vertical) {
+            (1, 1) => (13, 0),
+            (1, 0) => (12, 0),
+            (1, _) => (11, 0),
+            (0, 1) => (10, 0),
+            (0, 0) => (9, 0),
+            (0, _) => (10, 1),
+            (_, 1) => (11, 1),
+            (_, 0) => (12, 1),
+            _ => (13, 1),
+        }
+    }
+
+    fn decode_sign(&mut self, coder: &mut Coder, x: usize, y: usize) -> u8 {
+        let context = self.sign_context(x, y);
+        coder.sign(&mut self.contexts, context)
+    }
+
+    fn set_significant(&mut self, x: usize, y: usize, negative: u8, plane: u8) {
+        let i = self.index(x, y);
+        self.flags[i] |= SIGNIFICANT | if negative == 1 { NEGATIVE } else { 0 };
+        self.magnitude[y * self.width + x] = 1;
+        self.plane[y * self.width + x] = plane;
+    }
+
+    fn significance_pass(&mut self, coder: &mut Coder, plane: u8) {
+        for y0 in (0..self.height).step_by(4) {
+            for x in 0..self.width {
+                for y in y0..(y0 + 4).min(self.height) {
+                    let i = self.index(x, y);
+                    if self.flags[i] & SIGNIFICANT != 0 {
+                        continue;
+                    }
+                    let cx = self.zero_context(x, y);
+                    if cx == 0 {
+                        continue;
+                    }
+                    if coder.decode(&mut self.contexts, cx) == 1 {
+                        let sign = self.decode_sign(coder, x, y);
+                        self.set_significant(x, y, sign, plane);
+                    }
+                    self.flags[i] |= VISITED;
+                }
+            }
+        }
+    }
+
+    fn refinement_pass(&mut self, coder: &mut Coder, plane: u8) {
+        for y0 in (0..self.height).step_by(4) {
+            for x in 0..self.width {
+                for y in y0..(y0 + 4).min(self.height) {
+                    let i = self.index(x, y);
+                    if self.flags[i] & (SIGNIFICANT | VISITED) != SIGNIFICANT {
+                        continue;
+                    }
+                    let cx = if self.flags[i] & REFINED != 0 {
+                        REFINEMENT_CONTEXTS + 2
+                    } else {
+                        let (h, v, d) = self.neighbours(x, y);
+                        REFINEMENT_CONTEXTS + (h + v + d > 0) as usize
+                    };
+                    let bit = coder.decode(&mut self.contexts, cx) as u32;
+                    let k = y * self.width + x;
+                    self.magnitude[k] = (self.magnitude[k] << 1) | bit;
+                    self.plane[k] = plane;
+                    self.flags[i] |= REFINED;
+                }
+            }
+        }
+    }
+
+    fn cleanup_pass(&mut self, coder: &mut Coder, plane: u8) {
+        for y0 in (0..self.height).step_by(4) {
+            let end = (y0 + 4).min(self.height);
+            for x in 0..self.width {
+                let mut y = y0;
+                let run = end - y0 == 4
+                    && (y0..end).all(|y| {
+                        self.flags[self.index(x, y)] & (SIGNIFICANT | VISITED) == 0
+                            && self.zero_context(x, y) == 0
+                    });
+                if run {
+                    if coder.decode(&mut self.contexts, RUN_LENGTH) == 0 {
+                        continue;
+                    }
+                    let offset = (coder.decode(&mut self.contexts, UNIFORM) << 1)
+                        | coder.decode(&mut self.contexts, UNIFORM);
+                    y += offset as usize;
+                    let sign = self.decode_sign(coder, x, y);
+                    self.set_significant(x, y, sign, plane);
+                    y += 1;
+                }
+                for y in y..end {
+                    if self.flags[self.index(x, y)] & (SIGNIFICANT | VISITED) != 0 {
+                        continue;
+                    }
+                    let cx = self.zero_context(x, y);
+                    if coder.decode(&mut self.contexts, cx) == 1 {
+                        let sign = self.decode_sign(coder, x, y);
+                        self.set_significant(x, y, sign, plane);
+                    }
+                }
+            }
+        }
+        for flags in &mut self.flags {
+            *flags &= !VISITED;
+        }
+    }
+}
+
+/// Decode the coding passes of a code-block. `planes` is the number of
+/// magnitude bit-planes of its sub-band (M_b, plus any ROI shift) and
+/// `zero_planes` how many of them the packet headers reported as empty.
+pub(super) fn decode_block(
+    width: usize,
+    height: usize,
+    kind: BandKind,
+    style: u8,
+    planes: u32,
+    zero_planes: u32,
+    segments: &[Segment],
+) -> BlockCoefficients {
+    let mut state = BlockState {
+        width,
+        height,
+        kind,
+        causal: style & VERTICALLY_CAUSAL != 0,
+        flags: vec![0; (width + 2) * (height + 2)],
+        magnitude: vec![0; width * height],
+        plane: vec![0; width * height],
+        contexts: initial_contexts(),
+    };
+
+    if zero_planes < planes {
+        let top = planes - 1 - zero_planes;
+        let mut pass = 0u32;
+        'segments: for segment in segments {
+            let raw = style & BYPASS != 0 && pass >= 10 && pass % 3 != 0;
+            let mut coder = if raw {
+                Coder::Raw(RawReader {
+                    data: &segment.data,
+                    position: 0,
+                    byte: 0,
+                    count: 0,
+                })
+            } else {
+                Coder::Arithmetic(ArithmeticDecoder::new(&segment.data))
+            };
+
+            for _ in 0..segment.passes {
+                let Some(plane) = top.checked_sub(pass.div_ceil(3)) else {
+                    break 'segments;
+                };
+                let plane = plane as u8;
+                match pass % 3 {
+                    1 => state.significance_pass(&mut coder, plane),
+                    2 => state.refinement_pass(&mut coder, plane),
+                    _ => {
+                        state.cleanup_pass(&mut coder, plane);
+                        if style & SEGMENTATION_SYMBOLS != 0 {
+                            for _ in 0..4 {
+                                coder.decode(&mut state.contexts, UNIFORM);
+                            }
+                        }
+                    }
+                }
+                if style & RESET != 0 {
+                    state.contexts = initial_contexts();
+                }
+                pass += 1;
+            }
+        }
+    }
+
+    let negative = (0..width * height)
+        .map(|k| state.flags[state.index(k % width.max(1), k / width.max(1))] & NEGATIVE != 0)
+        .collect();
+    BlockCoefficients {
+        magnitude: state.magnitude,
+        plane: state.plane,
+        negative,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(kind: BandKind) -> BlockState {
+        BlockState {
+            width: 4,
+            height: 5,
+            kind,
+            causal: false,
+            flags: vec![0; 42],
+            magnitude: vec![0; 20],
+            plane: vec![0; 20],
+            contexts: initial_contexts(),
+        }
+    }
+
+    #[test]
+    fn test_zero_coding_contexts() {
+        let mut block = state(BandKind::LowLow);
+        assert_eq!(block.zero_context(1, 1), 0);
+        block.set_significant(0, 1, 0, 0);
+        assert_eq!(block.zero_context(1, 1), 5);
+        block.set_significant(1, 0, 0, 0);
+        assert_eq!(block.zero_context(1, 1), 7);
+
+        // The HL band swaps the roles of horizontal and vertical neighbours
+        let mut block = state(BandKind::HighLow);
+        block.set_significant(1, 0, 0, 0);
+        assert_eq!(block.zero_context(1, 1), 5);
+
+        let mut block = state(BandKind::HighHigh);
+        block.set_significant(0, 0, 0, 0);
+        block.set_significant(2, 0, 0, 0);
+        block.set_significant(0, 2, 0, 0);
+        assert_eq!(block.zero_context(1, 1), 8);
+    }
+
+    #[test]
+    fn test_sign_contexts() {
+        let mut block = state(BandKind::LowLow);
+        assert_eq!(block.sign_context(1, 1), (9, 0));
+        block.set_significant(0, 1, 1, 0);
+        assert_eq!(block.sign_context(1, 1), (12, 1));
+        block.set_significant(1, 0, 1, 0);
+        assert_eq!(block.sign_context(1, 1), (13, 1));
+    }
+
+    #[test]
+    fn test_vertically_causal_ignores_next_stripe() {
+        let mut block = state(BandKind::LowLow);
+        block.causal = true;
+        block.set_significant(1, 4, 0, 0);
+        assert_eq!(block.zero_context(1, 3), 0);
+        block.causal = false;
+        assert_eq!(block.zero_context(1, 3), 3);
+    }
+
+    #[test]
+    fn test_raw_reader_skips_stuffed_bit() {
+        let mut reader = RawReader {
+            data: &[0xFF, 0x7F],
+            position: 0,
+            byte: 0,
+            count: 0,
+        };
+        let bits: Vec<u8> = (0..15).map(|_| reader.bit()).collect();
+        assert!(bits.iter().all(|&bit| bit == 1));
+    }
+
+    #[test]
+    fn test_empty_block_is_zero() {
+        let coefficients = decode_block(3, 2, BandKind::LowLow, 0, 8, 8, &[]);
+        assert!(coefficients.magnitude.iter().all(|&m| m == 0));
+        assert_eq!(coefficients.negative.len(), 6);
+    }
+}
@@ -0,0 +1,776 @@
+//! JPEG 2000 codestream syntax (ITU-T T.800 Annex A): main header, tile-part
+//! headers and the packet data of each tile
+
+use crate::parser::{ParseError, ParseResult};
+
+const SOC: u16 = 0xFF4F;
+const SIZ: u16 = 0xFF51;
+const COD: u16 = 0xFF52;
+const COC: u16 = 0xFF53;
+const QCD: u16 = 0xFF5C;
+const QCC: u16 = 0xFF5D;
+const RGN: u16 = 0xFF5E;
+const POC: u16 = 0xFF5F;
+const PPM: u16 = 0xFF60;
+const PPT: u16 = 0xFF61;
+const SOT: u16 = 0xFF90;
+const SOD: u16 = 0xFF93;
+const EOC: u16 = 0xFFD9;
+
+/// Upper bound on decoded samples per component, to reject absurd headers
+/// before allocating
+const MAX_COMPONENT_SAMPLES: u64 = 1 << 28;
+
+/// Image and tile geometry plus component parameters (SIZ, A.5.1)
+#[derive(Debug, Clone)]
+pub(super) struct Size {
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tile_x_offset: u32,
+    pub tile_y_offset: u32,
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Component {
+    /// Bit depth of the samples
+    pub precision: u8,
+    pub signed: bool,
+    /// Horizontal and vertical sub-sampling (XRsiz, YRsiz)
+    pub dx: u32,
+    pub dy: u32,
+}
+
+impl Size {
+    pub(super) fn tiles_across(&self) -> u32 {
+        (self.width - self.tile_x_offset).div_ceil(self.tile_width)
+    }
+
+    pub(super) fn tiles_down(&self) -> u32 {
+        (self.height - self.tile_y_offset).div_ceil(self.tile_height)
+    }
+
+    /// Reference grid area of tile `index` as (x0, y0, x1, y1) (B.3)
+    pub(super) fn tile_area(&self, index: u32) -> (u32, u32, u32, u32) {
+        let p = index % self.tiles_across();
+        let q = index / self.tiles_across();
+        let x0 = self.tile_x_offset as u64 + p as u64 * self.tile_width as u64;
+        let y0 = self.tile_y_offset as u64 + q as u64 * self.tile_height as u64;
+        (
+            x0.max(self.x_offset as u64) as u32,
+            y0.max(self.y_offset as u64) as u32,
+            (x0 + self.tile_width as u64).min(self.width as u64) as u32,
+            (y0 + self.tile_height as u64).min(self.height as u64) as u32,
+        )
+    }
+}
+
+/// Packet progression order (Table A.16)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Progression {
+    LayerResolutionComponentPosition,
+    ResolutionLayerComponentPosition,
+    ResolutionPositionComponentLayer,
+    PositionComponentResolutionLayer,
+    ComponentPositionResolutionLayer,
+}
+
+impl Progression {
+    fn from_byte(value: u8) -> ParseResult<Self> {
+        Ok(match value {
+            0 => Self::LayerResolutionComponentPosition,
+            1 => Self::ResolutionLayerComponentPosition,
+            2 => Self::ResolutionPositionComponentLayer,
+            3 => Self::PositionComponentResolutionLayer,
+            4 => Self::ComponentPositionResolutionLayer,
+            _ => return Err(invalid(format!("unknown progression order {value}"))),
+        })
+    }
+}
+
+/// Code-block style flags (Table A.19)
+pub(super) const BYPASS: u8 = 0x01;
+pub(super) const RESET: u8 = 0x02;
+pub(super) const TERMINATE_ALL: u8 = 0x04;
+pub(super) const VERTICALLY_CAUSAL: u8 = 0x08;
+pub(super) const SEGMENTATION_SYMBOLS: u8 = 0x20;
+
+/// Per-component coding parameters (SPcod / SPcoc, Table A.15)
+#[derive(Debug, Clone)]
+pub(super) struct ComponentCoding {
+    /// Number of decomposition levels
+    pub levels: u8,
+    /// Code-block width and height exponents
+    pub block_width: u8,
+    pub block_height: u8,
+    pub block_style: u8,
+    /// 5-3 reversible wavelet rather than the 9-7 irreversible one
+    pub reversible: bool,
+    /// Precinct width and height exponents for each resolution level
+    pub precincts: Vec<(u8, u8)>,
+}
+
+/// Coding style default (COD, A.6.1)
+#[derive(Debug, Clone)]
+pub(super) struct CodingStyle {
+    pub sop: bool,
+    pub eph: bool,
+    pub progression: Progression,
+    pub layers: u16,
+    /// Multiple component transform on the first three components
+    pub mct: bool,
+    pub component: ComponentCoding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum QuantizationStyle {
+    None,
+    ScalarDerived,
+    ScalarExpounded,
+}
+
+/// Quantization parameters of one component (QCD / QCC, A.6.4)
+#[derive(Debug, Clone)]
+pub(super) struct Quantization {
+    pub style: QuantizationStyle,
+    pub guard_bits: u8,
+    /// Exponent and mantissa per sub-band, in sub-band order
+    pub steps: Vec<(u8, u16)>,
+}
+
+impl Quantization {
+    /// Exponent and mantissa of band `band` (0 = LL, 1 = HL, 2 = LH,
+    /// 3 = HH) at resolution `resolution`
+    pub(super) fn step(&self, resolution: u8, band: u8, levels: u8) -> ParseResult<(u8, u16)> {
+        if self.style == QuantizationStyle::ScalarDerived {
+            let &(exponent, mantissa) = self
+                .steps
+                .first()
+                .ok_or_else(|| invalid("missing quantization step".to_string()))?;
+            // ε_b = ε_0 - N_L + n_b (E-5)
+            let level = if resolution == 0 {
+                levels
+            } else {
+                levels + 1 - resolution
+            };
+            let exponent = exponent as i32 - levels as i32 + level as i32;
+            return Ok((exponent.max(0) as u8, mantissa));
+        }
+        let index = if resolution == 0 {
+            0
+        } else {
+            1 + 3 * (resolution as usize - 1) + (band as usize - 1)
+        };
+        self.steps
+            .get(index)
+            .copied()
+            .ok_or_else(|| invalid(format!("missing quantization step for sub-band {index}")))
+    }
+}
+
+/// One progression order change (POC, A.6.6)
+#[derive(Debug, Clone)]
+pub(super) struct ProgressionChange {
+    pub resolution_start: u8,
+    pub component_start: u16,
+    pub layer_end: u16,
+    pub resolution_end: u8,
+    pub component_end: u16,
+    pub progression: Progression,
+}
+
+/// Coding parameters in effect for one tile
+#[derive(Debug, Clone)]
+pub(super) struct TileParameters {
+    pub coding: CodingStyle,
+    pub components: Vec<ComponentCoding>,
+    pub quantization: Vec<Quantization>,
+    /// ROI max-shift value per component (RGN)
+    pub roi_shift: Vec<u8>,
+    pub progression_changes: Vec<ProgressionChange>,
+}
+
+/// A tile with the packet data of all its tile-parts
+#[derive(Debug)]
+pub(super) struct Tile {
+    pub index: u32,
+    pub parameters: TileParameters,
+    pub data: Vec<u8>,
+    /// Packet headers signalled apart from the packet data (PPM / PPT)
+    pub packed_headers: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub(super) struct Codestream {
+    pub size: Size,
+    pub tiles: Vec<Tile>,
+}
+
+/// Marker segments that can appear in both main and tile-part headers
+#[derive(Debug, Default, Clone)]
+struct HeaderState {
+    coding: Option<CodingStyle>,
+    component_coding: Vec<Option<ComponentCoding>>,
+    quantization: Option<Quantization>,
+    component_quantization: Vec<Option<Quantization>>,
+    roi_shift: Vec<Option<u8>>,
+    progression_changes: Vec<ProgressionChange>,
+}
+
+impl HeaderState {
+    fn new(components: usize) -> Self {
+        Self {
+            component_coding: vec![None; components],
+            component_quantization: vec![None; components],
+            roi_shift: vec![None; components],
+            ..Self::default()
+        }
+    }
+
+    /// Parse one marker segment; returns false for markers it does not own
+    fn apply(&mut self, marker: u16, body: &[u8], components: usize) -> ParseResult<bool> {
+        let wide = components > 256;
+        match marker {
+            COD => self.coding = Some(parse_cod(body)?),
+            COC => {
+                let (component, rest) = read_component_index(body, wide)?;
+                let flags = *rest.first().ok_or_else(truncated)?;
+                let coding = parse_component_coding(&rest[1..], flags & 0x01 != 0)?;
+                if let Some(slot) = self.component_coding.get_mut(component) {
+                    *slot = Some(coding);
+                }
+            }
+            QCD => self.quantization = Some(parse_quantization(body)?),
+            QCC => {
+                let (component, rest) = read_component_index(body, wide)?;
+                let quantization = parse_quantization(rest)?;
+                if let Some(slot) = self.component_quantization.get_mut(component) {
+                    *slot = Some(quantization);
+                }
+            }
+            RGN => {
+                let (component, rest) = read_component_index(body, wide)?;
+                if rest.len() < 2 {
+                    return Err(truncated());
+                }
+                if let Some(slot) = self.roi_shift.get_mut(component) {
+                    *slot = Some(rest[1]);
+                }
+            }
+            POC => self.progression_changes = parse_poc(body, wide)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Resolve the parameters of a tile whose header is `self`, on top of
+    /// the main header defaults (precedence of A.6)
+    fn resolve(&self, main: &HeaderState, components: usize) -> ParseResult<TileParameters> {
+        let coding = self
+            .coding
+            .as_ref()
+            .or(main.coding.as_ref())
+            .cloned()
+            .ok_or_else(|| invalid("missing COD marker".to_string()))?;
+
+        let mut component_coding = Vec::with_capacity(components);
+        let mut quantization = Vec::with_capacity(components);
+        let mut roi_shift = Vec::with_capacity(components);
+        for index in 0..components {
+            let coc = if self.coding.is_some() {
+                self.component_coding[index].as_ref()
+            } else {
+                self.component_coding[index]
+                    .as_ref()
+                    .or(main.component_coding[index].as_ref())
+            };
+            component_coding.push(coc.cloned().unwrap_or_else(|| coding.component.clone()));
+
+            let qcc = if self.quantization.is_some() {
+                self.component_quantization[index].as_ref()
+            } else {
+                self.component_quantization[index]
+                    .as_ref()
+                    .or(main.component_quantization[index].as_ref())
+            };
+            let qcd = self.quantization.as_ref().or(main.quantization.as_ref());
+            quantization.push(
+                qcc.or(qcd)
+                    .cloned()
+                    .ok_or_else(|| invalid("missing QCD marker".to_string()))?,
+            );
+
+            roi_shift.push(self.roi_shift[index].or(main.roi_shift[index]).unwrap_or(0));
+        }
+
+        let progression_changes = if self.progression_changes.is_empty() {
+            main.progression_changes.clone()
+        } else {
+            self.progression_changes.clone()
+        };
+
+        Ok(TileParameters {
+            coding,
+            components: component_coding,
+            quantization,
+            roi_shift,
+            progression_changes,
+        })
+    }
+}
+
+/// Tile-parts gathered for one tile index
+struct TileBuilder {
+    header: HeaderState,
+    data: Vec<u8>,
+    /// PPT contents by Zppt index
+    ppt: Vec<(u8, Vec<u8>)>,
+    /// Packet headers taken from the main header's PPM markers
+    ppm: Vec<u8>,
+}
+
+/// Parse a raw codestream starting with the SOC marker
+pub(super) fn parse_codestream(data: &[u8]) -> ParseResult<Codestream> {
+    let mut reader = Reader { data, position: 0 };
+    if reader.u16()? != SOC {
+        return Err(invalid("missing SOC marker".to_string()));
+    }
+    if reader.u16()? != SIZ {
+        return Err(invalid("missing SIZ marker".to_string()));
+    }
+    let size = parse_siz(reader.segment()?)?;
+    let components = size.components.len();
+
+    let mut main = HeaderState::new(components);
+    let mut ppm: Vec<(u8, Vec<u8>)> = Vec::new();
+    loop {
+        let marker = reader.u16()?;
+        if marker == SOT {
+            break;
+        }
+        let body = reader.segment()?;
+        if marker == PPM {
+            let (&index, rest) = body.split_first().ok_or_else(truncated)?;
+            ppm.push((index, rest.to_vec()));
+        } else if !main.apply(marker, body, components)? && marker & 0xFF00 != 0xFF00 {
+            return Err(invalid(format!("invalid marker {marker:#06X}")));
+        }
+    }
+
+    // Packet headers of each tile-part, in codestream order (A.7.4)
+    ppm.sort_by_key(|(index, _)| *index);
+    let ppm: Vec<u8> = ppm.into_iter().flat_map(|(_, body)| body).collect();
+    let mut ppm_reader = if ppm.is_empty() {
+        None
+    } else {
+        Some(Reader {
+            data: &ppm,
+            position: 0,
+        })
+    };
+
+    let tile_count = size.tiles_across() as u64 * size.tiles_down() as u64;
+    if tile_count > u16::MAX as u64 + 1 {
+        return Err(invalid("too many tiles".to_string()));
+    }
+    let mut builders: Vec<Option<TileBuilder>> = (0..tile_count).map(|_| None).collect();
+
+    // Tile-parts; the SOT marker of the first one has been read
+    loop {
+        let start = reader.position - 2;
+        let body = reader.segment()?;
+        if body.len() < 8 {
+            return Err(truncated());
+        }
+        let index = u16::from_be_bytes([body[0], body[1]]) as usize;
+        let length = u32::from_be_bytes([body[2], body[3], body[4], body[5]]) as usize;
+
+        let builder = builders
+            .get_mut(index)
+            .ok_or_else(|| invalid(format!("tile index {index} out of range")))?
+            .get_or_insert_with(|| TileBuilder {
+                header: HeaderState::new(components),
+                data: Vec::new(),
+                ppt: Vec::new(),
+                ppm: Vec::new(),
+            });
+        let first_part = builder.data.is_empty() && builder.ppt.is_empty();
+
+        loop {
+            let marker = reader.u16()?;
+            if marker == SOD {
+                break;
+            }
+            let body = reader.segment()?;
+            if marker == PPT {
+                let (&index, rest) = body.split_first().ok_or_else(truncated)?;
+                builder.ppt.push((index, rest.to_vec()));
+            } else if first_part || marker == POC {
+                builder.header.apply(marker, body, components)?;
+            }
+        }
+
+        let end = if length == 0 {
+            data.len()
+                .saturating_sub(2)
+                .max(reader.position)
+                .min(data.len())
+        } else {
+            (start + length).min(data.len())
+        };
+        let end = end.max(reader.position);
+        builder.data.extend_from_slice(&data[reader.position..end]);
+        reader.position = end;
+
+        if let Some(ppm_reader) = ppm_reader.as_mut() {
+            if ppm_reader.position < ppm_reader.data.len() {
+                let count = ppm_reader.u32()? as usize;
+                builder.ppm.extend_from_slice(ppm_reader.bytes(count)?);
+            }
+        }
+
+        match reader.u16() {
+            Ok(SOT) => continue,
+            Ok(EOC) | Err(_) => break,
+            Ok(marker) => {
+                return Err(invalid(format!(
+                    "unexpected marker {marker:#06X} after tile-part"
+                )))
+            }
+        }
+    }
+
+    let mut tiles = Vec::new();
+    for (index, builder) in builders.into_iter().enumerate() {
+        let Some(mut builder) = builder else {
+            continue;
+        };
+        let parameters = builder.header.resolve(&main, components)?;
+        builder.ppt.sort_by_key(|(index, _)| *index);
+        let packed_headers = if ppm_reader.is_some() {
+            Some(builder.ppm)
+        } else if !builder.ppt.is_empty() {
+            Some(builder.ppt.into_iter().flat_map(|(_, body)| body).collect())
+        } else {
+            None
+        };
+        tiles.push(Tile {
+            index: index as u32,
+            parameters,
+            data: builder.data,
+            packed_headers,
+        });
+    }
+
+    Ok(Codestream { size, tiles })
+}
+
+fn parse_siz(body: &[u8]) -> ParseResult<Size> {
+    if body.len() < 36 {
+        return Err(truncated());
+    }
+    let u32_at = |offset: usize| {
+        u32::from_be_bytes([
+            body[offset],
+            body[offset + 1],
+            body[offset + 2],
+            body[offset + 3],
+        ])
+    };
+    let count = u16::from_be_bytes([body[34], body[35]]) as usize;
+    if count == 0 || body.len() < 36 + 3 * count {
+        return Err(invalid("invalid component count".to_string()));
+    }
+    let components = (0..count)
+        .map(|index| {
+            let entry = &body[36 + 3 * index..39 + 3 * index];
+            Component {
+                precision: (entry[0] & 0x7F) + 1,
+                signed: entry[0] & 0x80 != 0,
+                dx: entry[1] as u32,
+                dy: entry[2] as u32,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let size = Size {
+        width: u32_at(2),
+        height: u32_at(6),
+        x_offset: u32_at(10),
+        y_offset: u32_at(14),
+        tile_width: u32_at(18),
+        tile_height: u32_at(22),
+        tile_x_offset: u32_at(26),
+        tile_y_offset: u32_at(30),
+        components,
+    };
+
+    if size.width <= size.x_offset
+        || size.height <= size.y_offset
+        || size.tile_width == 0
+        || size.tile_height == 0
+        || size.tile_x_offset > size.x_offset
+        || size.tile_y_offset > size.y_offset
+        || size.tile_x_offset as u64 + size.tile_width as u64 <= size.x_offset as u64
+        || size.tile_y_offset as u64 + size.tile_height as u64 <= size.y_offset as u64
+    {
+        return Err(invalid("invalid image or tile size".to_string()));
+    }
+    for component in &size.components {
+        if component.dx == 0 || component.dy == 0 || component.precision > 16 {
+            return Err(invalid("unsupported component parameters".to_string()));
+        }
+        let samples = ((size.width - size.x_offset) as u64).div_ceil(component.dx as u64)
+            * ((size.height - size.y_offset) as u64).div_ceil(component.dy as u64);
+        if samples > MAX_COMPONENT_SAMPLES {
+            return Err(invalid("image is too large".to_string()));
+        }
+    }
+    Ok(size)
+}
+
+fn parse_cod(body: &[u8]) -> ParseResult<CodingStyle> {
+    if body.len() < 5 {
+        return Err(truncated());
+    }
+    let flags = body[0];
+    let layers = u16::from_be_bytes([body[2], body[3]]);
+    if layers == 0 {
+        return Err(invalid("zero quality layers".to_string()));
+    }
+    Ok(CodingStyle {
+        sop: flags & 0x02 != 0,
+        eph: flags & 0x04 != 0,
+        progression: Progression::from_byte(body[1])?,
+        layers,
+        mct: body[4] != 0,
+        component: parse_component_coding(&body[5..], flags & 0x01 != 0)?,
+    })
+}
+
+fn parse_component_coding(body: &[u8], custom_precincts: bool) -> ParseResult<ComponentCoding> {
+    if body.len() < 5 {
+        return Err(truncated());
+    }
+    let levels = body[0];
+    let block_width = body[1] + 2;
+    let block_height = body[2] + 2;
+    if levels > 32 || block_width > 10 || block_height > 10 || block_width + block_height > 12 {
+        return Err(invalid("invalid coding style parameters".to_string()));
+    }
+    let precincts = if custom_precincts {
+        let sizes = body.get(5..5 + levels as usize + 1).ok_or_else(truncated)?;
+        sizes.iter().map(|&size| (size & 0x0F, size >> 4)).collect()
+    } else {
+        vec![(15, 15); levels as usize + 1]
+    };
+    Ok(ComponentCoding {
+        levels,
+        block_width,
+        block_height,
+        block_style: body[3],
+        reversible: body[4] == 1,
+        precincts,
+    })
+}
+
+fn parse_quantization(body: &[u8]) -> ParseResult<Quantization> {
+    let (&flags, rest) = body.split_first().ok_or_else(truncated)?;
+    let style = match flags & 0x1F {
+        0 => QuantizationStyle::None,
+        1 => QuantizationStyle::ScalarDerived,
+        2 => QuantizationStyle::ScalarExpounded,
+        other => return Err(invalid(format!("unknown quantization style {other}"))),
+    };
+    let steps = if style == QuantizationStyle::None {
+        rest.iter().map(|&value| (value >> 3, 0)).collect()
+    } else {
+        rest.chunks_exact(2)
+            .map(|pair| {
+                let value = u16::from_be_bytes([pair[0], pair[1]]);
+                ((value >> 11) as u8, value & 0x07FF)
+            })
+            .collect()
+    };
+    Ok(Quantization {
+        style,
+        guard_bits: flags >> 5,
+        steps,
+    })
+}
+
+fn parse_poc(body: &[u8], wide: bool) -> ParseResult<Vec<ProgressionChange>> {
+    let entry_len = if wide { 9 } else { 7 };
+    body.chunks_exact(entry_len)
+        .map(|entry| {
+            let (component_start, rest) = read_component_index(&entry[1..], wide)?;
+            let layer_end = u16::from_be_bytes([rest[0], rest[1]]);
+            let resolution_end = rest[2];
+            let (component_end, rest) = read_component_index(&rest[3..], wide)?;
+            // A CEpoc of 0 stands for 256 components
+            let component_end = if component_end == 0 && !wide {
+                256
+            } else {
+                component_end
+            };
+            Ok(ProgressionChange {
+                resolution_start: entry[0],
+                component_start: component_start as u16,
+                layer_end,
+                resolution_end,
+                component_end: component_end as u16,
+                progression: Progression::from_byte(rest[0])?,
+            })
+        })
+        .collect()
+}
+
+/// Read a component index, 16 bits wide when there are over 256 components
+fn read_component_index(body: &[u8], wide: bool) -> ParseResult<(usize, &[u8])> {
+    if wide {
+        let bytes = body.get(..2).ok_or_else(truncated)?;
+        Ok((
+            u16::from_be_bytes([bytes[0], bytes[1]]) as usize,
+            &body[2..],
+        ))
+    } else {
+        let (&index, rest) = body.split_first().ok_or_else(truncated)?;
+        Ok((index as usize, rest))
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> ParseResult<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(truncated)?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> ParseResult<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> ParseResult<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Body of a marker segment whose marker has just been read
+    fn segment(&mut self) -> ParseResult<&'a [u8]> {
+        let length = self.u16()? as usize;
+        if length < 2 {
+            return Err(invalid("invalid marker segment length".to_string()));
+        }
+        self.bytes(length - 2)
+    }
+}
+
+fn invalid(message: String) -> ParseError {
+    ParseError::StreamDecodeError(format!("Invalid JPEG 2000 codestream: {message}"))
+}
+
+fn truncated() -> ParseError {
+    ParseError::StreamDecodeError("Truncated JPEG 2000 codestream".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn siz(width: u32, height: u32, tile: u32, components: &[(u8, u8)]) -> Vec<u8> {
+        let mut body = vec![0, 0];
+        for value in [width, height, 0, 0, tile, tile, 0, 0] {
+            body.extend(value.to_be_bytes());
+        }
+        body.extend((components.len() as u16).to_be_bytes());
+        for &(depth, sampling) in components {
+            body.extend([depth - 1, sampling, sampling]);
+        }
+        body
+    }
+
+    fn marker(code: u16, body: &[u8]) -> Vec<u8> {
+        let mut data = code.to_be_bytes().to_vec();
+        data.extend(((body.len() + 2) as u16).to_be_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn cod(levels: u8) -> Vec<u8> {
+        vec![0, 0, 0, 1, 0, levels, 2, 2, 0, 1]
+    }
+
+    #[test]
+    fn test_parse_siz_geometry() {
+        let size = parse_siz(&siz(100, 50, 32, &[(8, 1), (8, 2)])).unwrap();
+        assert_eq!((size.width, size.height), (100, 50));
+        assert_eq!((size.tiles_across(), size.tiles_down()), (4, 2));
+        assert_eq!(size.tile_area(3), (96, 0, 100, 32));
+        assert_eq!(size.components[1].dx, 2);
+    }
+
+    #[test]
+    fn test_parse_siz_rejects_zero_sampling() {
+        assert!(parse_siz(&siz(10, 10, 10, &[(8, 0)])).is_err());
+    }
+
+    #[test]
+    fn test_derived_quantization_steps() {
+        let quantization = parse_quantization(&[0x41, 0x48, 0x10]).unwrap();
+        assert_eq!(quantization.style, QuantizationStyle::ScalarDerived);
+        assert_eq!(quantization.guard_bits, 2);
+        assert_eq!(quantization.step(0, 0, 3).unwrap(), (9, 0x10));
+        assert_eq!(quantization.step(1, 1, 3).unwrap(), (9, 0x10));
+        assert_eq!(quantization.step(3, 3, 3).unwrap(), (7, 0x10));
+    }
+
+    #[test]
+    fn test_tile_parts_and_component_overrides() {
+        let mut data = vec![0xFF, 0x4F];
+        data.extend(marker(SIZ, &siz(8, 8, 8, &[(8, 1), (8, 1)])));
+        data.extend(marker(COD, &cod(2)));
+        // COC for component 1: one decomposition level
+        data.extend(marker(COC, &[1, 0, 1, 2, 2, 0, 0]));
+        data.extend(marker(
+            QCD,
+            &[0x40, 0x40, 0x48, 0x48, 0x50, 0x48, 0x48, 0x50],
+        ));
+        for (part, payload) in [(0u8, [1u8, 2]), (1, [3, 4])] {
+            let mut sot = vec![0, 0];
+            // Psot counts SOT (12 bytes), SOD and the payload
+            sot.extend((16u32).to_be_bytes());
+            sot.extend([part, 2]);
+            data.extend(marker(SOT, &sot));
+            data.extend([0xFF, 0x93]);
+            data.extend(payload);
+        }
+        data.extend([0xFF, 0xD9]);
+
+        let codestream = parse_codestream(&data).unwrap();
+        assert_eq!(codestream.tiles.len(), 1);
+        let tile = &codestream.tiles[0];
+        assert_eq!(tile.data, vec![1, 2, 3, 4]);
+        assert_eq!(tile.parameters.components[0].levels, 2);
+        assert_eq!(tile.parameters.components[1].levels, 1);
+        assert!(!tile.parameters.components[1].reversible);
+        assert!(tile.packed_headers.is_none());
+    }
+
+    #[test]
+    fn test_missing_soc_is_an_error() {
+        assert!(parse_codestream(&[0x00, 0x01, 0x02]).is_err());
+    }
+}
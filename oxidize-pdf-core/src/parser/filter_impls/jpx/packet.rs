@@ -0,0 +1,738 @@
+//! Tier-2 decoding (ITU-T T.800 Annex B): division of a tile into
+//! resolutions, sub-bands, precincts and code-blocks, and the packet headers
+//! that distribute coded data among the code-blocks
+
+use super::codestream::{
+    ComponentCoding, Progression, Size, TileParameters, BYPASS, TERMINATE_ALL,
+};
+use crate::parser::{ParseError, ParseResult};
+
+/// Upper bounds protecting against headers that describe absurd layouts
+const MAX_CODE_BLOCKS: u64 = 1 << 22;
+const MAX_PACKETS: u64 = 1 << 22;
+
+/// Rectangle [x0, x1) × [y0, y1) in some coordinate system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) struct Rect {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Rect {
+    pub(super) fn width(&self) -> u32 {
+        self.x1.saturating_sub(self.x0)
+    }
+
+    pub(super) fn height(&self) -> u32 {
+        self.y1.saturating_sub(self.y0)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BandKind {
+    LowLow,
+    HighLow,
+    LowHigh,
+    HighHigh,
+}
+
+impl BandKind {
+    /// Sub-band number within its resolution as used by quantization
+    pub(super) fn index(self) -> u8 {
+        match self {
+            Self::LowLow => 0,
+            Self::HighLow => 1,
+            Self::LowHigh => 2,
+            Self::HighHigh => 3,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Band {
+    pub kind: BandKind,
+    pub rect: Rect,
+}
+
+/// Codeword segment of a code-block: its data and coding passes
+#[derive(Debug, Default)]
+pub(super) struct Segment {
+    pub data: Vec<u8>,
+    pub passes: u32,
+    max_passes: u32,
+}
+
+#[derive(Debug)]
+pub(super) struct CodeBlock {
+    /// Position within the sub-band
+    pub rect: Rect,
+    included: bool,
+    lblock: u32,
+    /// Missing most significant bit-planes
+    pub zero_planes: u32,
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+pub(super) struct PrecinctBand {
+    blocks_across: u32,
+    pub blocks: Vec<CodeBlock>,
+    inclusion: TagTree,
+    zero_planes: TagTree,
+}
+
+#[derive(Debug)]
+pub(super) struct Precinct {
+    /// Code-blocks of each sub-band of the resolution, in band order
+    pub bands: Vec<PrecinctBand>,
+}
+
+#[derive(Debug)]
+pub(super) struct Resolution {
+    pub rect: Rect,
+    pub bands: Vec<Band>,
+    precinct_width: u8,
+    precinct_height: u8,
+    precincts_across: u32,
+    pub precincts: Vec<Precinct>,
+}
+
+/// A component of a tile together with its coded data
+#[derive(Debug)]
+pub(super) struct TileComponent {
+    pub rect: Rect,
+    pub resolutions: Vec<Resolution>,
+}
+
+/// ⌈value / 2^shift⌉ for possibly negative values
+fn ceil_shift(value: i64, shift: u32) -> i64 {
+    -((-value) >> shift)
+}
+
+impl TileComponent {
+    pub(super) fn new(
+        size: &Size,
+        tile: Rect,
+        component: usize,
+        coding: &ComponentCoding,
+    ) -> ParseResult<Self> {
+        let sampling = &size.components[component];
+        let rect = Rect {
+            x0: tile.x0.div_ceil(sampling.dx),
+            y0: tile.y0.div_ceil(sampling.dy),
+            x1: tile.x1.div_ceil(sampling.dx),
+            y1: tile.y1.div_ceil(sampling.dy),
+        };
+
+        let levels = coding.levels as u32;
+        let mut blocks_total = 0u64;
+        let mut resolutions = Vec::with_capacity(levels as usize + 1);
+        for r in 0..=levels {
+            let scale = levels - r;
+            let res_rect = Rect {
+                x0: ceil_shift(rect.x0 as i64, scale) as u32,
+                y0: ceil_shift(rect.y0 as i64, scale) as u32,
+                x1: ceil_shift(rect.x1 as i64, scale) as u32,
+                y1: ceil_shift(rect.y1 as i64, scale) as u32,
+            };
+
+            let bands = if r == 0 {
+                vec![Band {
+                    kind: BandKind::LowLow,
+                    rect: res_rect,
+                }]
+            } else {
+                // Equation B-15 with n_b = N_L - r + 1
+                let level = levels - r + 1;
+                let half = 1i64 << (level - 1);
+                [
+                    (BandKind::HighLow, 1, 0),
+                    (BandKind::LowHigh, 0, 1),
+                    (BandKind::HighHigh, 1, 1),
+                ]
+                .into_iter()
+                .map(|(kind, xo, yo)| Band {
+                    kind,
+                    rect: Rect {
+                        x0: ceil_shift(rect.x0 as i64 - half * xo, level) as u32,
+                        y0: ceil_shift(rect.y0 as i64 - half * yo, level) as u32,
+                        x1: ceil_shift(rect.x1 as i64 - half * xo, level) as u32,
+                        y1: ceil_shift(rect.y1 as i64 - half * yo, level) as u32,
+                    },
+                })
+                .collect()
+            };
+
+            let (ppx, ppy) = coding
+                .precincts
+                .get(r as usize)
+                .copied()
+                .unwrap_or((15, 15));
+            let (precincts_across, precincts_down) = if res_rect.is_empty() {
+                (0, 0)
+            } else {
+                (
+                    res_rect.x1.div_ceil(1 << ppx) - (res_rect.x0 >> ppx),
+                    res_rect.y1.div_ceil(1 << ppy) - (res_rect.y0 >> ppy),
+                )
+            };
+
+            // Code-blocks never straddle precincts (B.7)
+            let shrink = if r == 0 { 0 } else { 1 };
+            let cbw = coding.block_width.min(ppx.saturating_sub(shrink)) as u32;
+            let cbh = coding.block_height.min(ppy.saturating_sub(shrink)) as u32;
+
+            if precincts_across as u64 * precincts_down as u64 > MAX_CODE_BLOCKS {
+                return Err(invalid("too many precincts".to_string()));
+            }
+            let mut precincts = Vec::with_capacity((precincts_across * precincts_down) as usize);
+            for py in 0..precincts_down {
+                for px in 0..precincts_across {
+                    let prx0 = (((res_rect.x0 >> ppx) + px) as u64) << ppx;
+                    let pry0 = (((res_rect.y0 >> ppy) + py) as u64) << ppy;
+                    let (bx0, by0, bw, bh) = if r == 0 {
+                        (prx0, pry0, 1u64 << ppx, 1u64 << ppy)
+                    } else {
+                        (
+                            prx0 >> 1,
+                            pry0 >> 1,
+                            1u64 << ppx.saturating_sub(1),
+                            1u64 << ppy.saturating_sub(1),
+                        )
+                    };
+
+                    let mut precinct_bands = Vec::with_capacity(bands.len());
+                    for band in &bands {
+                        let area = Rect {
+                            x0: bx0.max(band.rect.x0 as u64) as u32,
+                            y0: by0.max(band.rect.y0 as u64) as u32,
+                            x1: (bx0 + bw).min(band.rect.x1 as u64) as u32,
+                            y1: (by0 + bh).min(band.rect.y1 as u64) as u32,
+                        };
+                        let (across, down) = if area.is_empty() {
+                            (0, 0)
+                        } else {
+                            (
+                                area.x1.div_ceil(1 << cbw) - (area.x0 >> cbw),
+                                area.y1.div_ceil(1 << cbh) - (area.y0 >> cbh),
+                            )
+                        };
+                        blocks_total += across as u64 * down as u64;
+                        if blocks_total > MAX_CODE_BLOCKS {
+                            return Err(invalid("too many code-blocks".to_string()));
+                        }
+
+                        let mut blocks = Vec::with_capacity((across * down) as usize);
+                        for j in 0..down {
+                            for i in 0..across {
+                                let cx = ((area.x0 >> cbw) + i) << cbw;
+                                let cy = ((area.y0 >> cbh) + j) << cbh;
+                                blocks.push(CodeBlock {
+                                    rect: Rect {
+                                        x0: cx.max(area.x0),
+                                        y0: cy.max(area.y0),
+                                        x1: (cx as u64 + (1 << cbw)).min(area.x1 as u64) as u32,
+                                        y1: (cy as u64 + (1 << cbh)).min(area.y1 as u64) as u32,
+                                    },
+                                    included: false,
+                                    lblock: 3,
+                                    zero_planes: 0,
+                                    segments: Vec::new(),
+                                });
+                            }
+                        }
+                        precinct_bands.push(PrecinctBand {
+                            blocks_across: across,
+                            blocks,
+                            inclusion: TagTree::new(across, down),
+                            zero_planes: TagTree::new(across, down),
+                        });
+                    }
+                    precincts.push(Precinct {
+                        bands: precinct_bands,
+                    });
+                }
+            }
+
+            resolutions.push(Resolution {
+                rect: res_rect,
+                bands,
+                precinct_width: ppx,
+                precinct_height: ppy,
+                precincts_across,
+                precincts,
+            });
+        }
+
+        Ok(Self { rect, resolutions })
+    }
+}
+
+/// Tag tree of B.10.2, decoded incrementally across packets
+#[derive(Debug)]
+struct TagTree {
+    /// Width and node offset of each level, leaves first
+    levels: Vec<(u32, usize)>,
+    value: Vec<u32>,
+    low: Vec<u32>,
+}
+
+impl TagTree {
+    fn new(width: u32, height: u32) -> Self {
+        let mut levels = Vec::new();
+        let (mut w, mut h) = (width.max(1), height.max(1));
+        let mut nodes = 0usize;
+        loop {
+            levels.push((w, nodes));
+            nodes += (w * h) as usize;
+            if w == 1 && h == 1 {
+                break;
+            }
+            w = w.div_ceil(2);
+            h = h.div_ceil(2);
+        }
+        Self {
+            levels,
+            value: vec![u32::MAX; nodes],
+            low: vec![0; nodes],
+        }
+    }
+
+    /// Whether the value of leaf (x, y) is below `threshold`
+    fn decode(
+        &mut self,
+        reader: &mut BitReader,
+        x: u32,
+        y: u32,
+        threshold: u32,
+    ) -> ParseResult<bool> {
+        let mut low = 0;
+        let mut leaf = 0;
+        for (level, &(width, offset)) in self.levels.iter().enumerate().rev() {
+            let node = offset + ((y >> level) * width + (x >> level)) as usize;
+            if low > self.low[node] {
+                self.low[node] = low;
+            } else {
+                low = self.low[node];
+            }
+            while low < threshold && low < self.value[node] {
+                if reader.bit()? == 1 {
+                    self.value[node] = low;
+                } else {
+                    low += 1;
+                }
+            }
+            self.low[node] = low;
+            leaf = node;
+        }
+        Ok(self.value[leaf] < threshold)
+    }
+
+    /// The full value of leaf (x, y)
+    fn decode_value(&mut self, reader: &mut BitReader, x: u32, y: u32) -> ParseResult<u32> {
+        let mut threshold = 1;
+        while !self.decode(reader, x, y, threshold)? {
+            threshold += 1;
+            if threshold > 64 {
+                return Err(invalid("tag tree value out of range".to_string()));
+            }
+        }
+        Ok(threshold - 1)
+    }
+}
+
+/// Packet header bit reader with the bit-stuffing rule of B.10.1
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], position: usize) -> Self {
+        Self {
+            data,
+            position,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn byte_in(&mut self) -> ParseResult<()> {
+        self.buffer = (self.buffer << 8) & 0xFFFF;
+        self.count = if self.buffer == 0xFF00 { 7 } else { 8 };
+        let byte = *self.data.get(self.position).ok_or_else(truncated)?;
+        self.buffer |= byte as u32;
+        self.position += 1;
+        Ok(())
+    }
+
+    fn bit(&mut self) -> ParseResult<u32> {
+        if self.count == 0 {
+            self.byte_in()?;
+        }
+        self.count -= 1;
+        Ok((self.buffer >> self.count) & 1)
+    }
+
+    fn bits(&mut self, count: u32) -> ParseResult<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.bit()?;
+        }
+        Ok(value)
+    }
+
+    /// Skip to the end of the header, including a stuffed byte after 0xFF
+    fn align(&mut self) {
+        if self.buffer & 0xFF == 0xFF {
+            let _ = self.byte_in();
+        }
+        self.count = 0;
+    }
+}
+
+/// Number of coding passes (Table B.4)
+fn read_pass_count(reader: &mut BitReader) -> ParseResult<u32> {
+    if reader.bit()? == 0 {
+        return Ok(1);
+    }
+    if reader.bit()? == 0 {
+        return Ok(2);
+    }
+    let value = reader.bits(2)?;
+    if value != 3 {
+        return Ok(3 + value);
+    }
+    let value = reader.bits(5)?;
+    if value != 31 {
+        return Ok(6 + value);
+    }
+    Ok(37 + reader.bits(7)?)
+}
+
+/// Most coding passes the next codeword segment of a code-block may hold
+fn segment_capacity(style: u8, segments: &[Segment]) -> u32 {
+    if style & TERMINATE_ALL != 0 {
+        1
+    } else if style & BYPASS != 0 {
+        match segments.last() {
+            None => 10,
+            Some(last) if last.max_passes == 1 || last.max_passes == 10 => 2,
+            Some(_) => 1,
+        }
+    } else {
+        u32::MAX
+    }
+}
+
+/// Identifies one packet: layer, resolution, component and precinct
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PacketId {
+    layer: u16,
+    resolution: u8,
+    component: u16,
+    precinct: u32,
+}
+
+/// All packets of a tile in the order they appear (B.12)
+fn packet_order(
+    components: &[TileComponent],
+    size: &Size,
+    tile: Rect,
+    parameters: &TileParameters,
+) -> ParseResult<Vec<PacketId>> {
+    let layers = parameters.coding.layers;
+    let mut total = 0u64;
+    for component in components {
+        for resolution in &component.resolutions {
+            total += resolution.precincts.len() as u64 * layers as u64;
+        }
+    }
+    if total > MAX_PACKETS {
+        return Err(invalid("too many packets".to_string()));
+    }
+
+    // Every packet with its precinct's position on the reference grid
+    let mut packets = Vec::with_capacity(total as usize);
+    for (c, component) in components.iter().enumerate() {
+        let sampling = &size.components[c];
+        let levels = component.resolutions.len() as u32 - 1;
+        for (r, resolution) in component.resolutions.iter().enumerate() {
+            let scale = levels - r as u32;
+            for p in 0..resolution.precincts.len() as u32 {
+                let px = p % resolution.precincts_across;
+                let py = p / resolution.precincts_across;
+                let x = (((resolution.rect.x0 >> resolution.precinct_width) + px) as u64)
+                    << resolution.precinct_width;
+                let y = (((resolution.rect.y0 >> resolution.precinct_height) + py) as u64)
+                    << resolution.precinct_height;
+                let x = ((x << scale) * sampling.dx as u64).max(tile.x0 as u64);
+                let y = ((y << scale) * sampling.dy as u64).max(tile.y0 as u64);
+                for layer in 0..layers {
+                    packets.push((
+                        PacketId {
+                            layer,
+                            resolution: r as u8,
+                            component: c as u16,
+                            precinct: p,
+                        },
+                        x,
+                        y,
+                    ));
+                }
+            }
+        }
+    }
+
+    let key = |progression: Progression, &(id, x, y): &(PacketId, u64, u64)| {
+        let (l, r, c, p) = (
+            id.layer as u64,
+            id.resolution as u64,
+            id.component as u64,
+            id.precinct as u64,
+        );
+        match progression {
+            Progression::LayerResolutionComponentPosition => [l, r, c, p, 0],
+            Progression::ResolutionLayerComponentPosition => [r, l, c, p, 0],
+            Progression::ResolutionPositionComponentLayer => [r, y, x, c, l],
+            Progression::PositionComponentResolutionLayer => [y, x, c, r, l],
+            Progression::ComponentPositionResolutionLayer => [c, y, x, r, l],
+        }
+    };
+
+    let mut order = Vec::with_capacity(packets.len());
+    let mut emitted = vec![false; packets.len()];
+    for change in &parameters.progression_changes {
+        let mut selected: Vec<usize> = (0..packets.len())
+            .filter(|&index| {
+                let id = packets[index].0;
+                !emitted[index]
+                    && id.layer < change.layer_end
+                    && id.resolution >= change.resolution_start
+                    && id.resolution < change.resolution_end
+                    && id.component >= change.component_start
+                    && id.component < change.component_end
+            })
+            .collect();
+        selected.sort_by_key(|&index| key(change.progression, &packets[index]));
+        for index in selected {
+            emitted[index] = true;
+            order.push(packets[index].0);
+        }
+    }
+
+    let mut rest: Vec<usize> = (0..packets.len())
+        .filter(|&index| !emitted[index])
+        .collect();
+    rest.sort_by_key(|&index| key(parameters.coding.progression, &packets[index]));
+    order.extend(rest.into_iter().map(|index| packets[index].0));
+    Ok(order)
+}
+
+/// Read all packets of a tile, distributing their data among the
+/// code-blocks of `components`. Decoding stops quietly at the end of
+/// truncated data so that whatever arrived can still be reconstructed.
+pub(super) fn read_packets(
+    components: &mut [TileComponent],
+    size: &Size,
+    tile: Rect,
+    parameters: &TileParameters,
+    data: &[u8],
+    packed_headers: Option<&[u8]>,
+) -> ParseResult<()> {
+    let order = packet_order(components, size, tile, parameters)?;
+    let mut body = 0usize;
+    let mut headers = 0usize;
+
+    for id in order {
+        let component = &mut components[id.component as usize];
+        let style = parameters.components[id.component as usize].block_style;
+        let precinct =
+            &mut component.resolutions[id.resolution as usize].precincts[id.precinct as usize];
+
+        if parameters.coding.sop && data[body.min(data.len())..].starts_with(&[0xFF, 0x91]) {
+            body += 6;
+        }
+        let (header_data, header_start) = match packed_headers {
+            Some(packed) => (packed, headers),
+            None => (data, body),
+        };
+        let mut reader = BitReader::new(header_data, header_start);
+        let contributions = match read_packet_header(&mut reader, precinct, id.layer, style) {
+            Ok(contributions) => contributions,
+            Err(_) => return Ok(()),
+        };
+        reader.align();
+        let mut header_end = reader.position;
+        if parameters.coding.eph
+            && header_data[header_end.min(header_data.len())..].starts_with(&[0xFF, 0x92])
+        {
+            header_end += 2;
+        }
+        match packed_headers {
+            Some(_) => headers = header_end,
+            None => body = header_end,
+        }
+
+        for (band, block, segment, length) in contributions {
+            let start = body.min(data.len());
+            let end = body.saturating_add(length as usize).min(data.len());
+            precinct.bands[band].blocks[block].segments[segment]
+                .data
+                .extend_from_slice(&data[start..end]);
+            body = end;
+        }
+        if body >= data.len() && packed_headers.is_none() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Decode one packet header (B.10), returning the length of each codeword
+/// segment contribution as (band, code-block, segment, length)
+fn read_packet_header(
+    reader: &mut BitReader,
+    precinct: &mut Precinct,
+    layer: u16,
+    style: u8,
+) -> ParseResult<Vec<(usize, usize, usize, u32)>> {
+    let mut contributions = Vec::new();
+    if reader.bit()? == 0 {
+        return Ok(contributions);
+    }
+
+    for (band_index, band) in precinct.bands.iter_mut().enumerate() {
+        for block_index in 0..band.blocks.len() {
+            let x = block_index as u32 % band.blocks_across;
+            let y = block_index as u32 / band.blocks_across;
+            let first = !band.blocks[block_index].included;
+            let included = if first {
+                band.inclusion.decode(reader, x, y, layer as u32 + 1)?
+            } else {
+                reader.bit()? == 1
+            };
+            if !included {
+                continue;
+            }
+
+            let block = &mut band.blocks[block_index];
+            if first {
+                block.zero_planes = band.zero_planes.decode_value(reader, x, y)?;
+                block.included = true;
+            }
+
+            let mut remaining = read_pass_count(reader)?;
+            while reader.bit()? == 1 {
+                block.lblock += 1;
+                if block.lblock > 32 {
+                    return Err(invalid("code-block length indicator overflow".to_string()));
+                }
+            }
+
+            if block
+                .segments
+                .last()
+                .is_none_or(|segment| segment.passes >= segment.max_passes)
+            {
+                let max_passes = segment_capacity(style, &block.segments);
+                block.segments.push(Segment {
+                    max_passes,
+                    ..Segment::default()
+                });
+            }
+            loop {
+                let index = block.segments.len() - 1;
+                let segment = &mut block.segments[index];
+                let passes = (segment.max_passes - segment.passes).min(remaining);
+                let bits = block.lblock + (31 - passes.leading_zeros());
+                if bits > 32 {
+                    return Err(invalid("code-block length out of range".to_string()));
+                }
+                let length = reader.bits(bits)?;
+                segment.passes += passes;
+                contributions.push((band_index, block_index, index, length));
+                remaining -= passes;
+                if remaining == 0 {
+                    break;
+                }
+                let max_passes = segment_capacity(style, &block.segments);
+                block.segments.push(Segment {
+                    max_passes,
+                    ..Segment::default()
+                });
+            }
+        }
+    }
+    Ok(contributions)
+}
+
+fn invalid(message: String) -> ParseError {
+    ParseError::StreamDecodeError(format!("Invalid JPEG 2000 packet: {message}"))
+}
+
+fn truncated() -> ParseError {
+    ParseError::StreamDecodeError("Truncated JPEG 2000 packet header".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_tree_decoding() {
+        // 3x2 leaves under a 2x1 level and the root; leaf (0, 0) is 1 and
+        // leaf (1, 0) is 3, which shares its parent with (0, 0)
+        let mut tree = TagTree::new(3, 2);
+        let bits = [0b0111_0010];
+        let mut reader = BitReader::new(&bits, 0);
+        assert_eq!(tree.decode_value(&mut reader, 0, 0).unwrap(), 1);
+        assert_eq!(tree.decode_value(&mut reader, 1, 0).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_pass_count_codes() {
+        // 0 | 10 | 1100 | 1111 00000 | 1111 11111 0000000, with a stuffed
+        // zero bit after the 0xFF byte
+        let bits = [0b0101_1001, 0b1110_0000, 0b1111_1111, 0b0100_0000, 0];
+        let mut reader = BitReader::new(&bits, 0);
+        let counts: Vec<u32> = (0..5)
+            .map(|_| read_pass_count(&mut reader).unwrap())
+            .collect();
+        assert_eq!(counts, vec![1, 2, 3, 6, 37]);
+    }
+
+    #[test]
+    fn test_bit_reader_skips_stuffed_bit() {
+        let bits = [0xFF, 0x7F];
+        let mut reader = BitReader::new(&bits, 0);
+        assert_eq!(reader.bits(8).unwrap(), 0xFF);
+        // Only seven bits follow a 0xFF byte
+        assert_eq!(reader.bits(7).unwrap(), 0x7F);
+        assert!(reader.bit().is_err());
+    }
+
+    #[test]
+    fn test_segment_capacity_with_bypass() {
+        let mut segments = Vec::new();
+        let mut capacities = Vec::new();
+        for _ in 0..4 {
+            let max_passes = segment_capacity(BYPASS, &segments);
+            capacities.push(max_passes);
+            segments.push(Segment {
+                max_passes,
+                ..Segment::default()
+            });
+        }
+        assert_eq!(capacities, vec![10, 2, 1, 2]);
+        assert_eq!(segment_capacity(TERMINATE_ALL, &[]), 1);
+    }
+}
@@ -0,0 +1,193 @@
+//! Inverse discrete wavelet transform (ITU-T T.800 Annex F) with the 5-3
+//! reversible and 9-7 irreversible lifting filters
+
+use super::packet::Rect;
+
+/// Lifting parameters of the 9-7 filter (Table F.4)
+const ALPHA: f32 = -1.586_134_342_059_924;
+const BETA: f32 = -0.052_980_118_572_961;
+const GAMMA: f32 = 0.882_911_075_530_934;
+const DELTA: f32 = 0.443_506_852_043_971;
+const K: f32 = 1.230_174_104_914_001;
+
+/// Samples beyond each end of a line, enough for the 9-7 lifting steps
+const EXTENSION: usize = 4;
+
+/// One sub-band's coefficients and its position in sub-band coordinates
+pub(super) struct BandSamples<'a> {
+    pub rect: Rect,
+    pub samples: &'a [f32],
+}
+
+impl BandSamples<'_> {
+    fn get(&self, u: u32, v: u32) -> f32 {
+        if u < self.rect.x0 || v < self.rect.y0 {
+            return 0.0;
+        }
+        let (u, v) = ((u - self.rect.x0) as usize, (v - self.rect.y0) as usize);
+        if u >= self.rect.width() as usize {
+            return 0.0;
+        }
+        self.samples
+            .get(v * self.rect.width() as usize + u)
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+/// Reconstruct resolution `rect` from the LL band of the resolution below
+/// and its HL, LH and HH sub-bands (2D_SR of F.3.2)
+pub(super) fn synthesize_level(
+    low: BandSamples,
+    high: [BandSamples; 3],
+    rect: Rect,
+    reversible: bool,
+) -> Vec<f32> {
+    let width = rect.width() as usize;
+    let height = rect.height() as usize;
+    let mut samples = vec![0.0f32; width * height];
+
+    // 2D_INTERLEAVE: sample parity on the canvas selects the sub-band
+    for y in rect.y0..rect.y1 {
+        let row = (y - rect.y0) as usize * width;
+        for x in rect.x0..rect.x1 {
+            let band = match (x % 2, y % 2) {
+                (0, 0) => &low,
+                (1, 0) => &high[0],
+                (0, _) => &high[1],
+                _ => &high[2],
+            };
+            samples[row + (x - rect.x0) as usize] = band.get(x / 2, y / 2);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    for row in samples.chunks_exact_mut(width.max(1)) {
+        synthesize_line(row, rect.x0, reversible, &mut buffer);
+    }
+    let mut column = vec![0.0f32; height];
+    for x in 0..width {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = samples[y * width + x];
+        }
+        synthesize_line(&mut column, rect.y0, reversible, &mut buffer);
+        for (y, value) in column.iter().enumerate() {
+            samples[y * width + x] = *value;
+        }
+    }
+    samples
+}
+
+/// One-dimensional synthesis of an interleaved line whose first sample is
+/// at canvas position `start` (1D_SR of F.3.6)
+fn synthesize_line(line: &mut [f32], start: u32, reversible: bool, buffer: &mut Vec<f32>) {
+    let n = line.len();
+    if n == 0 {
+        return;
+    }
+    if n == 1 {
+        if start % 2 == 1 {
+            line[0] /= 2.0;
+            if reversible {
+                line[0] = line[0].trunc();
+            }
+        }
+        return;
+    }
+
+    // Periodic symmetric extension (F.3.7); EXTENSION is even so buffer
+    // index parity matches canvas parity offset by `start`
+    let len = n + 2 * EXTENSION;
+    let period = 2 * (n - 1) as i64;
+    buffer.clear();
+    buffer.extend((0..len).map(|j| {
+        let m = (j as i64 - EXTENSION as i64).rem_euclid(period) as usize;
+        line[if m >= n { period as usize - m } else { m }]
+    }));
+    let odd = |j: usize| (start as usize + j) % 2 == 1;
+
+    if reversible {
+        for j in (1..len - 1).filter(|&j| !odd(j)) {
+            buffer[j] -= ((buffer[j - 1] + buffer[j + 1] + 2.0) / 4.0).floor();
+        }
+        for j in (EXTENSION..EXTENSION + n).filter(|&j| odd(j)) {
+            buffer[j] += ((buffer[j - 1] + buffer[j + 1]) / 2.0).floor();
+        }
+    } else {
+        for (j, value) in buffer.iter_mut().enumerate() {
+            *value *= if odd(j) { 1.0 / K } else { K };
+        }
+        let mut lift = |from: usize, to: usize, parity: bool, factor: f32| {
+            for j in (from..to).filter(|&j| odd(j) == parity) {
+                buffer[j] -= factor * (buffer[j - 1] + buffer[j + 1]);
+            }
+        };
+        lift(1, len - 1, false, DELTA);
+        lift(2, len - 2, true, GAMMA);
+        lift(3, len - 3, false, BETA);
+        lift(4, len - 4, true, ALPHA);
+    }
+
+    line.copy_from_slice(&buffer[EXTENSION..EXTENSION + n]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Forward 5-3 transform of a line starting at an even position
+    fn forward_53(x: &[i32]) -> (Vec<i32>, Vec<i32>) {
+        let n = x.len() as i64;
+        let at = |i: i64| {
+            let period = 2 * (n - 1);
+            let m = i.rem_euclid(period);
+            x[if m >= n { period - m } else { m } as usize]
+        };
+        let high = |i: i64| at(2 * i + 1) - (at(2 * i) + at(2 * i + 2)).div_euclid(2);
+        let highs: Vec<i32> = (0..n / 2).map(high).collect();
+        let high_ext = |i: i64| high(i);
+        let lows = (0..(n + 1) / 2)
+            .map(|i| at(2 * i) + (high_ext(i - 1) + high_ext(i) + 2).div_euclid(4))
+            .collect();
+        (lows, highs)
+    }
+
+    #[test]
+    fn test_reversible_line_round_trip() {
+        let original = [12, 200, 7, 7, 90, 255, 0, 31, 64];
+        let (lows, highs) = forward_53(&original);
+        let mut line: Vec<f32> = (0..original.len())
+            .map(|i| {
+                if i % 2 == 0 {
+                    lows[i / 2] as f32
+                } else {
+                    highs[i / 2] as f32
+                }
+            })
+            .collect();
+        synthesize_line(&mut line, 0, true, &mut Vec::new());
+        let restored: Vec<i32> = line.iter().map(|&v| v as i32).collect();
+        assert_eq!(restored, original);
+    }
+
+    #[test]
+    fn test_irreversible_constant_line() {
+        // The low-pass filter has unit DC gain: a constant signal has only
+        // low-pass coefficients of the same value
+        let mut line = vec![0.0f32; 8];
+        for value in line.iter_mut().step_by(2) {
+            *value = 100.0;
+        }
+        synthesize_line(&mut line, 0, false, &mut Vec::new());
+        for value in line {
+            assert!((value - 100.0).abs() < 0.01, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_single_odd_sample_is_halved() {
+        let mut line = [10.0f32];
+        synthesize_line(&mut line, 3, true, &mut Vec::new());
+        assert_eq!(line, [5.0]);
+    }
+}
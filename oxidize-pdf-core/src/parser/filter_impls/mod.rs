@@ -6,7 +6,12 @@
 pub mod ccitt;
 pub mod dct;
 pub mod jbig2;
+pub mod jpx;
 
 pub use ccitt::decode_ccitt;
-pub use dct::{decode_dct, parse_jpeg_info, JpegColorSpace, JpegInfo};
+pub use dct::{
+    decode_dct, decode_dct_image, decode_dct_pixels, parse_jpeg_info, JpegColorSpace, JpegImage,
+    JpegInfo,
+};
 pub use jbig2::decode_jbig2;
+pub use jpx::{decode_jpx, decode_jpx_image, JpxColorSpace, JpxImage};
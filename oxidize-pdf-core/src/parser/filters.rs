@@ -11,12 +11,16 @@ use std::io::Read;
 
 // Import decode functionality from the filter_impls module
 use super::filter_impls::ccitt::decode_ccitt;
-use super::filter_impls::dct::decode_dct;
+use super::filter_impls::dct::{decode_dct, decode_dct_pixels};
 use super::filter_impls::jbig2::decode_jbig2;
+use super::filter_impls::jpx::decode_jpx;
 // Re-export for public use
 pub use super::filter_impls::ccitt::decode_ccitt as decode_ccitt_public;
-pub use super::filter_impls::dct::{parse_jpeg_info, JpegColorSpace, JpegInfo};
+pub use super::filter_impls::dct::{
+    decode_dct_image, parse_jpeg_info, JpegColorSpace, JpegImage, JpegInfo,
+};
 pub use super::filter_impls::jbig2::decode_jbig2 as decode_jbig2_public;
+pub use super::filter_impls::jpx::{decode_jpx_image, JpxColorSpace, JpxImage};
 
 /// Supported PDF filters
 #[derive(Debug, Clone, PartialEq)]
@@ -75,7 +79,7 @@ impl Filter {
 pub fn decode_stream(
     data: &[u8],
     dict: &PdfDictionary,
-    _options: &ParseOptions,
+    options: &ParseOptions,
 ) -> ParseResult<Vec<u8>> {
     // Get filter(s) from dictionary
     let filters = match dict.get("Filter") {
@@ -120,7 +124,12 @@ pub fn decode_stream(
         // Get decode parameters for this filter
         let filter_params = get_filter_params(decode_params, i);
 
-        result = apply_filter_with_params(&result, filter, filter_params)?;
+        result = match filter {
+            Filter::DCTDecode if options.decode_dct_images => {
+                decode_dct_pixels(&result, filter_params)?
+            }
+            _ => apply_filter_with_params(&result, filter, filter_params)?,
+        };
     }
 
     Ok(result)
@@ -138,6 +147,7 @@ pub(crate) fn apply_filter(data: &[u8], filter: Filter) -> ParseResult<Vec<u8>>
         Filter::CCITTFaxDecode => decode_ccitt(data, None),
         Filter::JBIG2Decode => decode_jbig2(data, None),
         Filter::DCTDecode => decode_dct(data),
+        Filter::JPXDecode => decode_jpx(data),
         _ => Err(ParseError::SyntaxError {
             position: 0,
             message: format!("Filter {filter:?} not yet implemented"),
@@ -719,7 +729,7 @@ mod tests {
     #[test]
     fn test_apply_filter_unsupported() {
         let data = b"test data";
-        let unsupported_filters = vec![Filter::JPXDecode, Filter::Crypt];
+        let unsupported_filters = vec![Filter::Crypt];
 
         for filter in unsupported_filters {
             let result = apply_filter(data, filter);
@@ -727,6 +737,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_apply_filter_jpx_decode() {
+        // JPXDecode is supported but rejects data that is neither a JP2
+        // file nor a raw codestream
+        let result = apply_filter(b"not jpeg 2000 data", Filter::JPXDecode);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_apply_filter_dct_decode() {
         // DCTDecode should now work but expect valid JPEG data
@@ -1302,6 +1320,7 @@ pub(crate) fn apply_filter_with_params(
         Filter::CCITTFaxDecode => decode_ccitt(data, params)?,
         Filter::JBIG2Decode => decode_jbig2(data, params)?,
         Filter::DCTDecode => decode_dct(data)?,
+        Filter::JPXDecode => decode_jpx(data)?,
         _ => {
             return Err(ParseError::SyntaxError {
                 position: 0,
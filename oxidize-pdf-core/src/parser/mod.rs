@@ -144,8 +144,10 @@ pub mod objects;
 pub mod optimized_reader;
 pub mod page_tree;
 pub mod reader;
+pub(crate) mod resolve;
 pub mod stack_safe;
 pub mod stack_safe_tests;
+pub(crate) mod strings;
 pub mod trailer;
 pub mod xref;
 pub mod xref_stream;
@@ -202,6 +204,7 @@ pub type ParseResult<T> = Result<T, ParseError>;
 ///     lenient_encoding: true,
 ///     preferred_encoding: None,
 ///     lenient_syntax: true,
+///     decode_dct_images: false,
 /// };
 /// ```
 #[derive(Debug, Clone)]
@@ -241,6 +244,13 @@ pub struct ParseOptions {
     pub preferred_encoding: Option<encoding::EncodingType>,
     /// Enable automatic syntax error recovery
     pub lenient_syntax: bool,
+    /// Fully decode DCTDecode (JPEG) streams into raw samples (default: false)
+    ///
+    /// By default DCTDecode streams are returned as the compressed JPEG, which
+    /// is what image extraction and rewriting usually want. When enabled,
+    /// baseline and progressive JPEGs are decoded into 8-bit samples in the
+    /// image's color space, with Adobe YCC and YCCK transforms undone.
+    pub decode_dct_images: bool,
 }
 
 impl Default for ParseOptions {
@@ -258,6 +268,7 @@ impl Default for ParseOptions {
             lenient_encoding: true,   // Enable lenient encoding by default
             preferred_encoding: None, // Auto-detect encoding
             lenient_syntax: false,    // Strict syntax parsing by default
+            decode_dct_images: false, // Keep JPEG data compressed by default
         }
     }
 }
@@ -278,6 +289,7 @@ impl ParseOptions {
             lenient_encoding: false,
             preferred_encoding: None,
             lenient_syntax: false,
+            decode_dct_images: false,
         }
     }
 
@@ -296,6 +308,7 @@ impl ParseOptions {
             lenient_encoding: true,
             preferred_encoding: None,
             lenient_syntax: true,
+            decode_dct_images: false,
         }
     }
 
@@ -319,6 +332,7 @@ impl ParseOptions {
             lenient_encoding: true,
             preferred_encoding: None,
             lenient_syntax: true,
+            decode_dct_images: false,
         }
     }
 }
@@ -553,6 +567,7 @@ mod tests {
         assert!(opts.lenient_encoding); // default is true
         assert!(opts.preferred_encoding.is_none());
         assert!(!opts.lenient_syntax);
+        assert!(!opts.decode_dct_images);
     }
 
     #[test]
@@ -36,6 +36,25 @@ fn is_immediate_stream_start(data: &[u8]) -> bool {
     data[i..].starts_with(b"stream")
 }
 
+/// Mutable access to the filter parameter dictionaries of a stream
+/// dictionary, whether DecodeParms holds one dictionary or an array
+fn decode_parms_mut(dict: &mut PdfDictionary) -> Vec<&mut PdfDictionary> {
+    use super::objects::PdfName;
+
+    match dict.0.get_mut(&PdfName("DecodeParms".to_string())) {
+        Some(PdfObject::Dictionary(params)) => vec![params],
+        Some(PdfObject::Array(array)) => array
+            .0
+            .iter_mut()
+            .filter_map(|item| match item {
+                PdfObject::Dictionary(params) => Some(params),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 /// High-level PDF reader
 pub struct PdfReader<R: Read + Seek> {
     reader: BufReader<R>,
@@ -107,6 +126,37 @@ impl<R: Read + Seek> PdfReader<R> {
         }
     }
 
+    /// Try to unlock a public-key encrypted PDF with a recipient certificate
+    /// and its private key
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use oxidize_pdf::encryption::RecipientCredentials;
+    /// use oxidize_pdf::parser::PdfReader;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let credentials = RecipientCredentials::from_pem(
+    ///     &std::fs::read_to_string("recipient.key.pem")?,
+    ///     &std::fs::read_to_string("recipient.cert.pem")?,
+    /// )?;
+    /// let mut reader = PdfReader::open("for_recipient.pdf")?;
+    /// if !reader.unlock_with_credentials(&credentials)? {
+    ///     println!("Document is not encrypted for this certificate");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn unlock_with_credentials(
+        &mut self,
+        credentials: &crate::encryption::RecipientCredentials,
+    ) -> ParseResult<bool> {
+        match &mut self.encryption_handler {
+            Some(handler) => handler.unlock_with_credentials(credentials),
+            None => Ok(true), // Not encrypted
+        }
+    }
+
     /// Try to unlock with empty password
     pub fn try_empty_password(&mut self) -> ParseResult<bool> {
         match &mut self.encryption_handler {
@@ -417,6 +467,24 @@ impl<R: Read + Seek> PdfReader<R> {
         &self.header.version
     }
 
+    /// Get the trailer of the most recent cross-reference section
+    pub fn trailer(&self) -> &PdfTrailer {
+        &self.trailer
+    }
+
+    /// Get the merged cross-reference table of all revisions
+    pub fn xref(&self) -> &XRefTable {
+        &self.xref
+    }
+
+    /// Read the complete underlying file, e.g. to hash signed byte ranges
+    pub fn read_raw_bytes(&mut self) -> ParseResult<Vec<u8>> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut buffer = Vec::new();
+        self.reader.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
     /// Get the document catalog
     pub fn catalog(&mut self) -> ParseResult<&PdfDictionary> {
         // Try to get root from trailer
@@ -848,6 +916,7 @@ impl<R: Read + Seek> PdfReader<R> {
 
         // Decrypt if encryption is active
         let decrypted_obj = self.decrypt_object_if_needed(obj, obj_num, gen_num)?;
+        let decrypted_obj = self.inline_jbig2_globals(decrypted_obj);
 
         // Cache the decrypted object
         self.object_cache.insert(key, decrypted_obj);
@@ -855,6 +924,44 @@ impl<R: Read + Seek> PdfReader<R> {
         Ok(&self.object_cache[&key])
     }
 
+    /// Replace an indirect /JBIG2Globals entry in the DecodeParms of a
+    /// stream with the globals stream itself, so that the JBIG2 filter
+    /// can decode the stream without access to the document
+    fn inline_jbig2_globals(&mut self, mut obj: PdfObject) -> PdfObject {
+        let references: Vec<_> = match &mut obj {
+            PdfObject::Stream(stream) => decode_parms_mut(&mut stream.dict)
+                .into_iter()
+                .map(|params| match params.get("JBIG2Globals") {
+                    Some(PdfObject::Reference(num, generation)) => Some((*num, *generation)),
+                    _ => None,
+                })
+                .collect(),
+            _ => return obj,
+        };
+        if references.iter().all(Option::is_none) {
+            return obj;
+        }
+
+        let globals: Vec<_> = references
+            .into_iter()
+            .map(|reference| {
+                let (num, generation) = reference?;
+                match self.get_object(num, generation) {
+                    Ok(globals @ PdfObject::Stream(_)) => Some(globals.clone()),
+                    _ => None,
+                }
+            })
+            .collect();
+        if let PdfObject::Stream(stream) = &mut obj {
+            for (params, globals) in decode_parms_mut(&mut stream.dict).into_iter().zip(globals) {
+                if let Some(globals) = globals {
+                    params.insert("JBIG2Globals".to_string(), globals);
+                }
+            }
+        }
+        obj
+    }
+
     /// Resolve a reference to get the actual object
     pub fn resolve<'a>(&'a mut self, obj: &'a PdfObject) -> ParseResult<&'a PdfObject> {
         match obj {
@@ -2885,6 +2992,36 @@ mod tests {
         assert_eq!(reader.version().minor, 4);
     }
 
+    #[test]
+    fn test_jbig2_globals_reference_is_inlined() {
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for object in [
+            "1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n",
+            "2 0 obj\n<< /Type /Pages /Kids [] /Count 0 >>\nendobj\n",
+            "3 0 obj\n<< /Filter /JBIG2Decode /DecodeParms << /JBIG2Globals 4 0 R >> /Length 1 >>\nstream\nX\nendstream\nendobj\n",
+            "4 0 obj\n<< /Length 2 >>\nstream\nGG\nendstream\nendobj\n",
+        ] {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(object.as_bytes());
+        }
+        let xref_start = pdf.len();
+        pdf.extend_from_slice(b"xref\n0 5\n0000000000 65535 f \n");
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!("trailer\n<< /Size 5 /Root 1 0 R >>\nstartxref\n{xref_start}\n%%EOF")
+                .as_bytes(),
+        );
+
+        let mut reader = PdfReader::new(Cursor::new(pdf)).unwrap();
+        let image = reader.get_object(3, 0).unwrap().as_stream().unwrap();
+        let params = image.dict.get("DecodeParms").unwrap().as_dict().unwrap();
+        let globals = params.get("JBIG2Globals").unwrap().as_stream().unwrap();
+        assert_eq!(globals.data, b"GG");
+    }
+
     #[test]
     fn test_reader_different_versions() {
         let versions = vec![
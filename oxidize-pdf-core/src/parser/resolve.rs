@@ -0,0 +1,176 @@
+//! Access to parsed objects with indirect references resolved
+
+use crate::objects::Object;
+use crate::parser::objects::{PdfArray, PdfDictionary, PdfName, PdfObject, PdfStream, PdfString};
+use crate::parser::{ParseOptions, PdfDocument};
+use std::io::{Read, Seek};
+
+/// Source of the objects that indirect references point to
+pub(crate) trait Resolver {
+    /// The object itself, or the object it refers to (`Null` if missing)
+    fn lookup(&self, object: &PdfObject) -> PdfObject;
+
+    /// Options for decoding streams
+    fn parse_options(&self) -> ParseOptions;
+}
+
+impl<R: Read + Seek> Resolver for PdfDocument<R> {
+    fn lookup(&self, object: &PdfObject) -> PdfObject {
+        match object {
+            PdfObject::Reference(..) => self.resolve(object).unwrap_or(PdfObject::Null),
+            other => other.clone(),
+        }
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        self.options()
+    }
+}
+
+/// Resolved value of `key`, `None` when absent or null
+pub(crate) fn get(resolver: &dyn Resolver, dict: &PdfDictionary, key: &str) -> Option<PdfObject> {
+    match resolver.lookup(dict.get(key)?) {
+        PdfObject::Null => None,
+        object => Some(object),
+    }
+}
+
+/// Resolved dictionary (or stream dictionary) value of `key`
+pub(crate) fn get_dict(
+    resolver: &dyn Resolver,
+    dict: &PdfDictionary,
+    key: &str,
+) -> Option<PdfDictionary> {
+    match get(resolver, dict, key)? {
+        PdfObject::Dictionary(d) => Some(d),
+        PdfObject::Stream(s) => Some(s.dict),
+        _ => None,
+    }
+}
+
+pub(crate) fn get_number(resolver: &dyn Resolver, dict: &PdfDictionary, key: &str) -> Option<f64> {
+    get(resolver, dict, key)?.as_real()
+}
+
+pub(crate) fn get_name(resolver: &dyn Resolver, dict: &PdfDictionary, key: &str) -> Option<String> {
+    get(resolver, dict, key)?
+        .as_name()
+        .map(|name| name.as_str().to_string())
+}
+
+pub(crate) fn get_bool(resolver: &dyn Resolver, dict: &PdfDictionary, key: &str) -> Option<bool> {
+    get(resolver, dict, key)?.as_bool()
+}
+
+/// Resolved array of numbers; `None` if any element is not a number
+pub(crate) fn get_numbers(
+    resolver: &dyn Resolver,
+    dict: &PdfDictionary,
+    key: &str,
+) -> Option<Vec<f64>> {
+    numbers(resolver, &get(resolver, dict, key)?)
+}
+
+pub(crate) fn numbers(resolver: &dyn Resolver, object: &PdfObject) -> Option<Vec<f64>> {
+    object
+        .as_array()?
+        .0
+        .iter()
+        .map(|item| resolver.lookup(item).as_real())
+        .collect()
+}
+
+/// A six-number matrix value, identity when absent or malformed
+pub(crate) fn get_matrix(resolver: &dyn Resolver, dict: &PdfDictionary, key: &str) -> [f64; 6] {
+    match get_numbers(resolver, dict, key) {
+        Some(values) if values.len() == 6 => [
+            values[0], values[1], values[2], values[3], values[4], values[5],
+        ],
+        _ => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+    }
+}
+
+/// A rectangle value normalized to `[llx, lly, urx, ury]`
+pub(crate) fn get_rect(
+    resolver: &dyn Resolver,
+    dict: &PdfDictionary,
+    key: &str,
+) -> Option<[f64; 4]> {
+    match get_numbers(resolver, dict, key)?.as_slice() {
+        &[a, b, c, d] => Some([a.min(c), b.min(d), a.max(c), b.max(d)]),
+        _ => None,
+    }
+}
+
+/// Decoded data of a stream, `None` (after logging) if a filter fails
+pub(crate) fn stream_data(resolver: &dyn Resolver, stream: &PdfStream) -> Option<Vec<u8>> {
+    match stream.decode(&resolver.parse_options()) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            tracing::debug!("Skipping undecodable stream: {e}");
+            None
+        }
+    }
+}
+
+/// Parser form of an inline image parameter from the content parser;
+/// strings hold one char per byte
+pub(crate) fn from_content_object(object: &Object) -> PdfObject {
+    match object {
+        Object::Null => PdfObject::Null,
+        Object::Boolean(b) => PdfObject::Boolean(*b),
+        Object::Integer(i) => PdfObject::Integer(*i),
+        Object::Real(r) => PdfObject::Real(*r),
+        Object::String(s) => {
+            PdfObject::String(PdfString::new(s.chars().map(|c| c as u32 as u8).collect()))
+        }
+        Object::Name(n) => PdfObject::Name(PdfName::new(n.clone())),
+        Object::Array(items) => {
+            PdfObject::Array(PdfArray(items.iter().map(from_content_object).collect()))
+        }
+        Object::Dictionary(dict) => PdfObject::Dictionary(from_content_dict(dict.iter())),
+        Object::Stream(dict, data) => PdfObject::Stream(PdfStream {
+            dict: from_content_dict(dict.iter()),
+            data: data.clone(),
+        }),
+        Object::Reference(id) => PdfObject::Reference(id.number(), id.generation()),
+    }
+}
+
+/// Parser dictionary from content parser key/value pairs
+pub(crate) fn from_content_dict<'a>(
+    entries: impl Iterator<Item = (&'a String, &'a Object)>,
+) -> PdfDictionary {
+    let mut dict = PdfDictionary::new();
+    for (key, value) in entries {
+        dict.insert(key.clone(), from_content_object(value));
+    }
+    dict
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Objects held in memory for unit tests
+    #[derive(Default)]
+    pub(crate) struct MemoryResolver {
+        pub objects: HashMap<u32, PdfObject>,
+    }
+
+    impl Resolver for MemoryResolver {
+        fn lookup(&self, object: &PdfObject) -> PdfObject {
+            match object {
+                PdfObject::Reference(number, _) => {
+                    self.objects.get(number).cloned().unwrap_or(PdfObject::Null)
+                }
+                other => other.clone(),
+            }
+        }
+
+        fn parse_options(&self) -> ParseOptions {
+            ParseOptions::default()
+        }
+    }
+}
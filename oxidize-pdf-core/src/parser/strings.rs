@@ -0,0 +1,102 @@
+//! Text strings and dates (ISO 32000-1 §7.9.2 and §7.9.4)
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+/// Decode a PDF text string (UTF-16BE with BOM, otherwise single-byte)
+pub(crate) fn text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// PDFDocEncoding for ASCII text, UTF-16BE with a byte order mark otherwise
+pub(crate) fn text_bytes(text: &str) -> Vec<u8> {
+    if text.is_ascii() {
+        return text.as_bytes().to_vec();
+    }
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
+}
+
+/// Parse a PDF date string (`D:YYYYMMDDHHmmSSOHH'mm'`)
+pub(crate) fn parse_pdf_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim().strip_prefix("D:").unwrap_or(value.trim());
+    let number = |range: std::ops::Range<usize>, default: u32| -> Option<u32> {
+        match value.get(range) {
+            Some(digits) if !digits.starts_with(['+', '-', 'Z']) => digits.parse().ok(),
+            _ => Some(default),
+        }
+    };
+    let year: i32 = value.get(0..4)?.parse().ok()?;
+    let month = number(4..6, 1)?;
+    let day = number(6..8, 1)?;
+    let hour = number(8..10, 0)?;
+    let minute = number(10..12, 0)?;
+    let second = number(12..14, 0)?;
+
+    let timezone = value.get(14..).unwrap_or("");
+    let offset_seconds = match timezone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let digits: String = timezone[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i64 = digits.get(0..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+            let minutes: i64 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let offset = hours * 3600 + minutes * 60;
+            if sign == '-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => 0,
+    };
+
+    let local = NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, second)?;
+    Some(Utc.from_utc_datetime(&local) - chrono::Duration::seconds(offset_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_string_decoding() {
+        assert_eq!(text_string(b"Signature1"), "Signature1");
+        assert_eq!(
+            text_string(&[0xFE, 0xFF, 0x00, 0x53, 0x00, 0xED, 0x00, 0x67]),
+            "Síg"
+        );
+        assert_eq!(text_string(&[0x53, 0xED, 0x67]), "Síg");
+    }
+
+    #[test]
+    fn test_text_bytes_round_trip() {
+        assert_eq!(text_bytes("Signature1"), b"Signature1");
+        let encoded = text_bytes("Firma é");
+        assert!(encoded.starts_with(&[0xFE, 0xFF]));
+        assert_eq!(text_string(&encoded), "Firma é");
+    }
+
+    #[test]
+    fn test_parse_pdf_date() {
+        let expected = Utc.with_ymd_and_hms(2024, 5, 17, 10, 30, 0).unwrap();
+        assert_eq!(parse_pdf_date("D:20240517103000Z"), Some(expected));
+        assert_eq!(parse_pdf_date("D:20240517123000+02'00'"), Some(expected));
+        assert_eq!(parse_pdf_date("D:20240517053000-05'00"), Some(expected));
+        assert_eq!(
+            parse_pdf_date("D:2024"),
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(parse_pdf_date("garbage"), None);
+    }
+}
@@ -351,39 +351,48 @@ impl XRefTable {
                 {
                     tracing::debug!("Parsing XRef stream");
 
-                    // Try to decode the stream, with fallback for corrupted streams
-                    let decoded_data = match stream.decode(options) {
-                        Ok(data) => data,
+                    // XRefStream::parse applies the filters itself, sizing
+                    // predictor rows from /W; fall back to the generic decode
+                    // (or the raw bytes of a corrupted stream) if that fails
+                    let xref_stream_parser = match xref_stream::XRefStream::parse(
+                        &mut *reader,
+                        stream.dict.clone(),
+                        stream.data.clone(),
+                        options,
+                    ) {
+                        Ok(parser) => parser,
                         Err(e) => {
                             tracing::debug!(
-                                "XRef stream decode failed: {e:?}, attempting raw data fallback"
+                                "XRef stream decode failed: {e:?}, attempting generic decode"
                             );
-
-                            // If decode fails, try using raw stream data
-                            // This helps with corrupted Flate streams
-                            if !stream.data.is_empty() {
-                                tracing::debug!(
-                                    "Using raw stream data ({} bytes) as fallback",
-                                    stream.data.len()
-                                );
-                                stream.data.clone()
-                            } else {
-                                tracing::debug!(
-                                    "No raw stream data available, triggering recovery mode"
-                                );
-                                return Err(e);
-                            }
+                            let decoded_data = match stream.decode(options) {
+                                Ok(data) => data,
+                                Err(e) if stream.data.is_empty() => {
+                                    tracing::debug!(
+                                        "No raw stream data available, triggering recovery mode"
+                                    );
+                                    return Err(e);
+                                }
+                                Err(_) => {
+                                    tracing::debug!(
+                                        "Using raw stream data ({} bytes) as fallback",
+                                        stream.data.len()
+                                    );
+                                    stream.data.clone()
+                                }
+                            };
+                            let mut dict = stream.dict.clone();
+                            dict.0
+                                .remove(&super::objects::PdfName::new("Filter".to_string()));
+                            xref_stream::XRefStream::parse(
+                                &mut *reader,
+                                dict,
+                                decoded_data,
+                                options,
+                            )?
                         }
                     };
 
-                    // Use the new xref_stream module
-                    let xref_stream_parser = xref_stream::XRefStream::parse(
-                        &mut *reader,
-                        stream.dict.clone(),
-                        decoded_data,
-                        options,
-                    )?;
-
                     // Convert entries to our format
                     let entries = xref_stream_parser.to_xref_entries()?;
                     tracing::debug!("XRef stream parsed, found {} entries", entries.len());
@@ -708,7 +717,9 @@ impl XRefTable {
 
         let mut lines = content.pdf_lines();
 
-        // Find startxref line - need to iterate forward after finding it
+        // Find the last startxref line - small incremental updates leave the
+        // previous revision's startxref inside the search window as well
+        let mut last_offset = None;
         while let Some(line) = lines.next() {
             if line.trim() == "startxref" {
                 // The offset should be on the next line
@@ -717,12 +728,12 @@ impl XRefTable {
                         .trim()
                         .parse::<u64>()
                         .map_err(|_| ParseError::InvalidXRef)?;
-                    return Ok(offset);
+                    last_offset = Some(offset);
                 }
             }
         }
 
-        Err(ParseError::InvalidXRef)
+        last_offset.ok_or(ParseError::InvalidXRef)
     }
 
     /// Scan PDF for objects not present in XRef and add them (for hybrid files)
@@ -0,0 +1,363 @@
+//! RGBA pixel buffer with coverage-masked compositing and blend modes
+
+use super::raster::{mul8, IntRect, Mask};
+use crate::graphics::BlendMode;
+
+/// Premultiplied RGBA pixels, row by row from the top
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+/// Source colors for a painting operation
+pub(crate) enum Paint<'a> {
+    /// One premultiplied color everywhere
+    Solid([u8; 4]),
+    /// Premultiplied color per device pixel (shadings, images, patterns)
+    Pixels(&'a dyn Fn(i32, i32) -> [u8; 4]),
+}
+
+/// How source pixels are combined with the backdrop
+pub(crate) struct Composite<'a> {
+    /// Constant opacity applied to the source
+    pub alpha: u8,
+    pub blend: &'a BlendMode,
+    /// Per-pixel opacity from a soft mask
+    pub soft_mask: Option<&'a Mask>,
+}
+
+impl Default for Composite<'_> {
+    fn default() -> Self {
+        Self {
+            alpha: 255,
+            blend: &BlendMode::Normal,
+            soft_mask: None,
+        }
+    }
+}
+
+impl Canvas {
+    /// A fully transparent canvas
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width * height * 4],
+        }
+    }
+
+    pub fn bounds(&self) -> IntRect {
+        IntRect::new(0, 0, self.width as i32, self.height as i32)
+    }
+
+    /// Fill every pixel with an opaque color
+    pub fn fill(&mut self, rgb: [u8; 3]) {
+        for pixel in self.data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// Paint `paint` through the coverage of `mask`
+    pub fn paint(&mut self, mask: &Mask, paint: &Paint, composite: &Composite) {
+        let bounds = mask.bounds.intersect(&self.bounds());
+        for y in bounds.y0..bounds.y1 {
+            for x in bounds.x0..bounds.x1 {
+                let mut coverage = mul8(mask.get(x, y), composite.alpha);
+                if let Some(soft_mask) = composite.soft_mask {
+                    coverage = mul8(coverage, soft_mask.get(x, y));
+                }
+                if coverage == 0 {
+                    continue;
+                }
+                let source = match paint {
+                    Paint::Solid(color) => *color,
+                    Paint::Pixels(f) => f(x, y),
+                };
+                let i = (y as usize * self.width + x as usize) * 4;
+                blend_pixel(&mut self.data[i..i + 4], source, coverage, composite.blend);
+            }
+        }
+    }
+
+    /// Composite another canvas of the same size over this one, within
+    /// the coverage of `clip`
+    pub fn draw_layer(&mut self, layer: &Canvas, clip: &Mask, composite: &Composite) {
+        let paint = |x: i32, y: i32| layer.pixel(x as usize, y as usize);
+        self.paint(clip, &Paint::Pixels(&paint), composite);
+    }
+
+    /// Opacity of each pixel as a mask (alpha soft masks)
+    pub fn alpha_mask(&self) -> Mask {
+        Mask {
+            bounds: self.bounds(),
+            data: self.data.chunks_exact(4).map(|p| p[3]).collect(),
+        }
+    }
+
+    /// Luminosity of each pixel composited over `backdrop` (luminosity
+    /// soft masks)
+    pub fn luminosity_mask(&self, backdrop: [u8; 3]) -> Mask {
+        Mask {
+            bounds: self.bounds(),
+            data: self
+                .data
+                .chunks_exact(4)
+                .map(|p| {
+                    let over = |c: u8, b: u8| c as u32 + mul8(b, 255 - p[3]) as u32;
+                    let r = over(p[0], backdrop[0]);
+                    let g = over(p[1], backdrop[1]);
+                    let b = over(p[2], backdrop[2]);
+                    ((r * 77 + g * 150 + b * 29 + 128) >> 8).min(255) as u8
+                })
+                .collect(),
+        }
+    }
+
+    /// Straight (non-premultiplied) RGBA bytes
+    pub fn to_straight_rgba(&self) -> Vec<u8> {
+        let mut out = self.data.clone();
+        for pixel in out.chunks_exact_mut(4) {
+            let a = pixel[3] as u32;
+            if a != 0 && a != 255 {
+                for c in &mut pixel[..3] {
+                    *c = ((*c as u32 * 255 + a / 2) / a).min(255) as u8;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Blend mode for a `BM` name; unknown names mean Normal
+pub(crate) fn blend_mode(name: &str) -> BlendMode {
+    match name {
+        "Multiply" => BlendMode::Multiply,
+        "Screen" => BlendMode::Screen,
+        "Overlay" => BlendMode::Overlay,
+        "SoftLight" => BlendMode::SoftLight,
+        "HardLight" => BlendMode::HardLight,
+        "ColorDodge" => BlendMode::ColorDodge,
+        "ColorBurn" => BlendMode::ColorBurn,
+        "Darken" => BlendMode::Darken,
+        "Lighten" => BlendMode::Lighten,
+        "Difference" => BlendMode::Difference,
+        "Exclusion" => BlendMode::Exclusion,
+        "Hue" => BlendMode::Hue,
+        "Saturation" => BlendMode::Saturation,
+        "Color" => BlendMode::Color,
+        "Luminosity" => BlendMode::Luminosity,
+        _ => BlendMode::Normal,
+    }
+}
+
+/// Premultiplied color from straight RGB and an opacity
+pub(crate) fn premultiply(rgb: [u8; 3], alpha: u8) -> [u8; 4] {
+    [
+        mul8(rgb[0], alpha),
+        mul8(rgb[1], alpha),
+        mul8(rgb[2], alpha),
+        alpha,
+    ]
+}
+
+fn blend_pixel(dst: &mut [u8], src: [u8; 4], coverage: u8, mode: &BlendMode) {
+    let src = if coverage == 255 {
+        src
+    } else {
+        src.map(|c| mul8(c, coverage))
+    };
+    if src[3] == 0 {
+        return;
+    }
+    if *mode == BlendMode::Normal || dst[3] == 0 {
+        let inverse = 255 - src[3];
+        for i in 0..4 {
+            dst[i] = src[i].saturating_add(mul8(dst[i], inverse));
+        }
+        return;
+    }
+
+    // General formula with premultiplied components (ISO 32000-1 §11.3.6):
+    // co = (1 - ab) cs + (1 - as) cb + as ab B(Cb, Cs)
+    let as_ = src[3] as f32 / 255.0;
+    let ab = dst[3] as f32 / 255.0;
+    let cs = [0, 1, 2].map(|i| src[i] as f32 / 255.0);
+    let cb = [0, 1, 2].map(|i| dst[i] as f32 / 255.0);
+    let straight_s = cs.map(|c| (c / as_).min(1.0));
+    let straight_b = cb.map(|c| (c / ab).min(1.0));
+    let blended = blend(mode, straight_b, straight_s);
+    for i in 0..3 {
+        let c = (1.0 - ab) * cs[i] + (1.0 - as_) * cb[i] + as_ * ab * blended[i];
+        dst[i] = (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+    }
+    dst[3] = ((as_ + ab - as_ * ab).clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+}
+
+/// Blend function B(Cb, Cs) on straight colors
+fn blend(mode: &BlendMode, b: [f32; 3], s: [f32; 3]) -> [f32; 3] {
+    let separable = |f: fn(f32, f32) -> f32| [f(b[0], s[0]), f(b[1], s[1]), f(b[2], s[2])];
+    match mode {
+        BlendMode::Normal => s,
+        BlendMode::Multiply => separable(|b, s| b * s),
+        BlendMode::Screen => separable(screen),
+        BlendMode::Overlay => separable(|b, s| hard_light(s, b)),
+        BlendMode::HardLight => separable(hard_light),
+        BlendMode::SoftLight => separable(|b, s| {
+            if s <= 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                let d = if b <= 0.25 {
+                    ((16.0 * b - 12.0) * b + 4.0) * b
+                } else {
+                    b.sqrt()
+                };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+        }),
+        BlendMode::ColorDodge => separable(|b, s| {
+            if b == 0.0 {
+                0.0
+            } else if s >= 1.0 {
+                1.0
+            } else {
+                (b / (1.0 - s)).min(1.0)
+            }
+        }),
+        BlendMode::ColorBurn => separable(|b, s| {
+            if b >= 1.0 {
+                1.0
+            } else if s <= 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - b) / s).min(1.0)
+            }
+        }),
+        BlendMode::Darken => separable(f32::min),
+        BlendMode::Lighten => separable(f32::max),
+        BlendMode::Difference => separable(|b, s| (b - s).abs()),
+        BlendMode::Exclusion => separable(|b, s| b + s - 2.0 * b * s),
+        BlendMode::Hue => set_lum(set_sat(s, sat(b)), lum(b)),
+        BlendMode::Saturation => set_lum(set_sat(b, sat(s)), lum(b)),
+        BlendMode::Color => set_lum(s, lum(b)),
+        BlendMode::Luminosity => set_lum(b, lum(s)),
+    }
+}
+
+fn screen(b: f32, s: f32) -> f32 {
+    b + s - b * s
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 {
+        b * 2.0 * s
+    } else {
+        screen(b, 2.0 * s - 1.0)
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    let c = c.map(|v| v + d);
+    let l = lum(c);
+    let n = c[0].min(c[1]).min(c[2]);
+    let x = c[0].max(c[1]).max(c[2]);
+    c.map(|v| {
+        let mut v = v;
+        if n < 0.0 && l != n {
+            v = l + (v - l) * l / (l - n);
+        }
+        if x > 1.0 && x != l {
+            v = l + (v - l) * (1.0 - l) / (x - l);
+        }
+        v
+    })
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let max = c[0].max(c[1]).max(c[2]);
+    let min = c[0].min(c[1]).min(c[2]);
+    if max <= min {
+        return [0.0; 3];
+    }
+    c.map(|v| {
+        if v == max {
+            s
+        } else if v == min {
+            0.0
+        } else {
+            (v - min) * s / (max - min)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_over() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.fill([255, 255, 255]);
+        let mask = Mask {
+            bounds: IntRect::new(0, 0, 2, 1),
+            data: vec![255, 128],
+        };
+        canvas.paint(
+            &mask,
+            &Paint::Solid([255, 0, 0, 255]),
+            &Composite::default(),
+        );
+        assert_eq!(canvas.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(canvas.pixel(1, 0), [255, 127, 127, 255]);
+    }
+
+    #[test]
+    fn test_multiply_and_constant_alpha() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.fill([128, 255, 255]);
+        let mask = Mask::full(canvas.bounds());
+        let composite = Composite {
+            blend: &BlendMode::Multiply,
+            ..Composite::default()
+        };
+        canvas.paint(&mask, &Paint::Solid([255, 128, 0, 255]), &composite);
+        assert_eq!(canvas.pixel(0, 0), [128, 128, 0, 255]);
+
+        let mut canvas = Canvas::new(1, 1);
+        let composite = Composite {
+            alpha: 128,
+            ..Composite::default()
+        };
+        canvas.paint(&mask, &Paint::Solid([0, 0, 255, 255]), &composite);
+        assert_eq!(canvas.to_straight_rgba(), vec![0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn test_luminosity_mask() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.data = vec![255, 255, 255, 255, 0, 0, 0, 0];
+        let mask = canvas.luminosity_mask([0, 0, 0]);
+        assert_eq!(mask.data, vec![255, 0]);
+        assert_eq!(blend_mode("Screen"), BlendMode::Screen);
+    }
+}
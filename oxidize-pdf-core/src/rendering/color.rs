@@ -0,0 +1,446 @@
+//! Color spaces as found in content streams and their conversion to sRGB
+
+use super::function::Function;
+use crate::graphics::{CalGrayColorSpace, CalRgbColorSpace, LabColorSpace};
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{get, get_dict, get_number, get_numbers, stream_data, Resolver};
+
+/// Nesting limit for color spaces defined in terms of other color spaces
+const MAX_DEPTH: usize = 4;
+
+/// A color space from a resource dictionary or content stream operand
+#[derive(Debug, Clone)]
+pub(crate) enum ColorSpace {
+    DeviceGray,
+    DeviceRgb,
+    DeviceCmyk,
+    CalGray(CalGrayColorSpace),
+    CalRgb(CalRgbColorSpace),
+    Lab(LabColorSpace),
+    Indexed {
+        base: Box<ColorSpace>,
+        hival: usize,
+        lookup: Vec<u8>,
+    },
+    /// Separation and DeviceN: colorants mapped through a tint transform
+    Special {
+        components: usize,
+        alternate: Box<ColorSpace>,
+        tint: Option<Function>,
+        /// The `None` colorant, which never marks the page
+        invisible: bool,
+    },
+    /// Pattern, with the color space of uncolored patterns
+    Pattern(Option<Box<ColorSpace>>),
+}
+
+impl ColorSpace {
+    /// Parse a color space operand or resource value; names other than the
+    /// device families are looked up in `ColorSpace` of `resources`
+    pub fn parse(
+        resolver: &dyn Resolver,
+        object: &PdfObject,
+        resources: Option<&PdfDictionary>,
+    ) -> Option<ColorSpace> {
+        Self::parse_at_depth(resolver, object, resources, 0)
+    }
+
+    /// Color space for a family or resource name
+    pub fn from_name(
+        resolver: &dyn Resolver,
+        name: &str,
+        resources: Option<&PdfDictionary>,
+    ) -> Option<ColorSpace> {
+        Self::parse(
+            resolver,
+            &PdfObject::Name(crate::parser::objects::PdfName::new(name.to_string())),
+            resources,
+        )
+    }
+
+    fn parse_at_depth(
+        resolver: &dyn Resolver,
+        object: &PdfObject,
+        resources: Option<&PdfDictionary>,
+        depth: usize,
+    ) -> Option<ColorSpace> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let object = resolver.lookup(object);
+        let recurse = |object: &PdfObject| {
+            Self::parse_at_depth(resolver, object, resources, depth + 1).map(Box::new)
+        };
+
+        match &object {
+            PdfObject::Name(name) => match name.as_str() {
+                "DeviceGray" | "G" | "CalGray" => Some(ColorSpace::DeviceGray),
+                "DeviceRGB" | "RGB" | "CalRGB" => Some(ColorSpace::DeviceRgb),
+                "DeviceCMYK" | "CMYK" => Some(ColorSpace::DeviceCmyk),
+                "Pattern" => Some(ColorSpace::Pattern(None)),
+                other => {
+                    let named = resources
+                        .and_then(|r| get_dict(resolver, r, "ColorSpace"))
+                        .and_then(|spaces| get(resolver, &spaces, other))?;
+                    // A resource cannot refer to itself through its own name
+                    if matches!(&named, PdfObject::Name(n) if n.as_str() == other) {
+                        return None;
+                    }
+                    Self::parse_at_depth(resolver, &named, resources, depth + 1)
+                }
+            },
+            PdfObject::Array(items) => {
+                let items = &items.0;
+                let family = resolver.lookup(items.first()?);
+                let family = family.as_name()?.as_str();
+                let param = |i: usize| items.get(i).map(|item| resolver.lookup(item));
+                let param_dict = |i: usize| param(i).and_then(|p| p.as_dict().cloned());
+
+                match family {
+                    "DeviceGray" | "G" => Some(ColorSpace::DeviceGray),
+                    "DeviceRGB" | "RGB" => Some(ColorSpace::DeviceRgb),
+                    "DeviceCMYK" | "CMYK" => Some(ColorSpace::DeviceCmyk),
+                    "CalGray" => {
+                        let dict = param_dict(1).unwrap_or_default();
+                        let mut space = CalGrayColorSpace::new();
+                        if let Some(gamma) = get_number(resolver, &dict, "Gamma") {
+                            space = space.with_gamma(gamma);
+                        }
+                        Some(ColorSpace::CalGray(space))
+                    }
+                    "CalRGB" => {
+                        let dict = param_dict(1).unwrap_or_default();
+                        let mut space = CalRgbColorSpace::new();
+                        if let Some(white) = triple(get_numbers(resolver, &dict, "WhitePoint")) {
+                            space = space.with_white_point(white);
+                        }
+                        if let Some(gamma) = triple(get_numbers(resolver, &dict, "Gamma")) {
+                            space = space.with_gamma(gamma);
+                        }
+                        if let Some(m) = get_numbers(resolver, &dict, "Matrix") {
+                            if m.len() == 9 {
+                                // The PDF matrix is column-major
+                                space = space.with_matrix([
+                                    m[0], m[3], m[6], m[1], m[4], m[7], m[2], m[5], m[8],
+                                ]);
+                            }
+                        }
+                        Some(ColorSpace::CalRgb(space))
+                    }
+                    "Lab" => {
+                        let dict = param_dict(1).unwrap_or_default();
+                        let mut space = LabColorSpace::new();
+                        if let Some(white) = triple(get_numbers(resolver, &dict, "WhitePoint")) {
+                            space = space.with_white_point(white);
+                        }
+                        if let Some(range) = get_numbers(resolver, &dict, "Range") {
+                            if range.len() == 4 {
+                                space = space.with_range(range[0], range[1], range[2], range[3]);
+                            }
+                        }
+                        Some(ColorSpace::Lab(space))
+                    }
+                    "ICCBased" => {
+                        let dict = param_dict(1)?;
+                        if let Some(alternate) = get(resolver, &dict, "Alternate") {
+                            if let Some(space) =
+                                Self::parse_at_depth(resolver, &alternate, resources, depth + 1)
+                            {
+                                return Some(space);
+                            }
+                        }
+                        match get_number(resolver, &dict, "N").unwrap_or(3.0) as i64 {
+                            1 => Some(ColorSpace::DeviceGray),
+                            4 => Some(ColorSpace::DeviceCmyk),
+                            _ => Some(ColorSpace::DeviceRgb),
+                        }
+                    }
+                    "Indexed" | "I" => {
+                        let base = recurse(items.get(1)?)?;
+                        let hival = param(2)?.as_real()?.clamp(0.0, 255.0) as usize;
+                        let lookup = match param(3)? {
+                            PdfObject::String(s) => s.as_bytes().to_vec(),
+                            PdfObject::Stream(s) => stream_data(resolver, &s)?,
+                            _ => return None,
+                        };
+                        Some(ColorSpace::Indexed {
+                            base,
+                            hival,
+                            lookup,
+                        })
+                    }
+                    "Separation" => {
+                        let colorant = param(1)?;
+                        let colorant = colorant.as_name().map(|n| n.as_str().to_string());
+                        let alternate = recurse(items.get(2)?)?;
+                        let tint = items.get(3).and_then(|f| Function::parse(resolver, f));
+                        Some(ColorSpace::Special {
+                            components: 1,
+                            alternate,
+                            tint,
+                            invisible: colorant.as_deref() == Some("None"),
+                        })
+                    }
+                    "DeviceN" => {
+                        let names = param(1)?;
+                        let names: Vec<String> = names
+                            .as_array()?
+                            .0
+                            .iter()
+                            .filter_map(|n| {
+                                resolver.lookup(n).as_name().map(|n| n.as_str().to_string())
+                            })
+                            .collect();
+                        let alternate = recurse(items.get(2)?)?;
+                        let tint = items.get(3).and_then(|f| Function::parse(resolver, f));
+                        Some(ColorSpace::Special {
+                            components: names.len().max(1),
+                            alternate,
+                            tint,
+                            invisible: !names.is_empty() && names.iter().all(|n| n == "None"),
+                        })
+                    }
+                    "Pattern" => Some(ColorSpace::Pattern(
+                        items.get(1).and_then(|base| recurse(base)),
+                    )),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of color components
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray | ColorSpace::CalGray(_) | ColorSpace::Indexed { .. } => 1,
+            ColorSpace::DeviceRgb | ColorSpace::CalRgb(_) | ColorSpace::Lab(_) => 3,
+            ColorSpace::DeviceCmyk => 4,
+            ColorSpace::Special { components, .. } => *components,
+            ColorSpace::Pattern(base) => base.as_ref().map_or(0, |b| b.components()),
+        }
+    }
+
+    /// Color selected when the color space is set
+    pub fn initial_color(&self) -> Vec<f64> {
+        match self {
+            ColorSpace::DeviceCmyk => vec![0.0, 0.0, 0.0, 1.0],
+            ColorSpace::Lab(space) => vec![
+                0.0,
+                0.0f64.clamp(space.range[0], space.range[1]),
+                0.0f64.clamp(space.range[2], space.range[3]),
+            ],
+            ColorSpace::Special { components, .. } => vec![1.0; *components],
+            other => vec![0.0; other.components()],
+        }
+    }
+
+    /// Default image decode ranges for samples of `bits` bits
+    pub fn default_decode(&self, bits: u32) -> Vec<(f64, f64)> {
+        match self {
+            ColorSpace::Indexed { .. } => vec![(0.0, ((1u32 << bits.min(16)) - 1) as f64)],
+            ColorSpace::Lab(space) => vec![
+                (0.0, 100.0),
+                (space.range[0], space.range[1]),
+                (space.range[2], space.range[3]),
+            ],
+            other => vec![(0.0, 1.0); other.components()],
+        }
+    }
+
+    /// Whether painting in this color space leaves the page unchanged
+    pub fn is_invisible(&self) -> bool {
+        matches!(
+            self,
+            ColorSpace::Special {
+                invisible: true,
+                ..
+            }
+        )
+    }
+
+    /// Convert a color to sRGB components in `0..=1`
+    pub fn to_rgb(&self, color: &[f64]) -> [f64; 3] {
+        let c = |i: usize| color.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+        match self {
+            ColorSpace::DeviceGray => [c(0); 3],
+            ColorSpace::DeviceRgb => [c(0), c(1), c(2)],
+            ColorSpace::DeviceCmyk => cmyk_to_rgb(c(0), c(1), c(2), c(3)),
+            ColorSpace::CalGray(space) => {
+                let y = space.apply_gamma(c(0));
+                [encode_srgb(y); 3]
+            }
+            ColorSpace::CalRgb(space) => {
+                let [x, y, z] = space.to_xyz([c(0), c(1), c(2)]);
+                xyz_to_srgb(x, y, z, space.white_point)
+            }
+            ColorSpace::Lab(space) => {
+                let v = |i: usize| color.get(i).copied().unwrap_or(0.0);
+                let l = v(0).clamp(0.0, 100.0);
+                let a = v(1).clamp(space.range[0], space.range[1]);
+                let b = v(2).clamp(space.range[2], space.range[3]);
+                space.lab_to_rgb(l, a, b)
+            }
+            ColorSpace::Indexed {
+                base,
+                hival,
+                lookup,
+            } => {
+                let index = color
+                    .first()
+                    .copied()
+                    .unwrap_or(0.0)
+                    .round()
+                    .clamp(0.0, *hival as f64) as usize;
+                let n = base.components();
+                let decode = base.default_decode(8);
+                let entry: Vec<f64> = (0..n)
+                    .map(|i| {
+                        let byte = lookup.get(index * n + i).copied().unwrap_or(0) as f64 / 255.0;
+                        let (d0, d1) = decode.get(i).copied().unwrap_or((0.0, 1.0));
+                        d0 + byte * (d1 - d0)
+                    })
+                    .collect();
+                base.to_rgb(&entry)
+            }
+            ColorSpace::Special {
+                components,
+                alternate,
+                tint,
+                ..
+            } => {
+                let inputs: Vec<f64> = (0..*components).map(c).collect();
+                match tint {
+                    Some(function) => alternate.to_rgb(&function.eval(&inputs)),
+                    // Without a usable tint transform, treat the tint as darkness
+                    None => {
+                        let t = inputs.iter().copied().fold(0.0f64, f64::max);
+                        [1.0 - t; 3]
+                    }
+                }
+            }
+            ColorSpace::Pattern(base) => match base {
+                Some(base) => base.to_rgb(color),
+                None => [0.0; 3],
+            },
+        }
+    }
+}
+
+fn triple(values: Option<Vec<f64>>) -> Option<[f64; 3]> {
+    match values?.as_slice() {
+        &[a, b, c] => Some([a, b, c]),
+        _ => None,
+    }
+}
+
+/// Device CMYK to RGB by the complement of the ink coverage
+pub(crate) fn cmyk_to_rgb(c: f64, m: f64, y: f64, k: f64) -> [f64; 3] {
+    [
+        (1.0 - c) * (1.0 - k),
+        (1.0 - m) * (1.0 - k),
+        (1.0 - y) * (1.0 - k),
+    ]
+}
+
+/// sRGB transfer curve applied to a linear value
+fn encode_srgb(linear: f64) -> f64 {
+    let v = linear.clamp(0.0, 1.0);
+    if v <= 0.003_130_8 {
+        12.92 * v
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// CIE XYZ relative to `white` to sRGB, scaling each axis onto D65
+fn xyz_to_srgb(x: f64, y: f64, z: f64, white: [f64; 3]) -> [f64; 3] {
+    const D65: [f64; 3] = [0.9505, 1.0, 1.089];
+    let scale = |v: f64, i: usize| {
+        if white[i] > 0.0 {
+            v * D65[i] / white[i]
+        } else {
+            v
+        }
+    };
+    let (x, y, z) = (scale(x, 0), scale(y, 1), scale(z, 2));
+    let r = 3.2406 * x - 1.5372 * y - 0.4986 * z;
+    let g = -0.9689 * x + 1.8758 * y + 0.0415 * z;
+    let b = 0.0557 * x - 0.2040 * y + 1.0570 * z;
+    [encode_srgb(r), encode_srgb(g), encode_srgb(b)]
+}
+
+/// Convert `0..=1` color components to bytes
+pub(crate) fn to_bytes(rgb: [f64; 3]) -> [u8; 3] {
+    rgb.map(|v| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::objects::{PdfArray, PdfName, PdfString};
+    use crate::parser::resolve::test_support::MemoryResolver;
+
+    fn name(s: &str) -> PdfObject {
+        PdfObject::Name(PdfName::new(s.to_string()))
+    }
+
+    #[test]
+    fn test_device_spaces() {
+        let resolver = MemoryResolver::default();
+        let cmyk = ColorSpace::parse(&resolver, &name("DeviceCMYK"), None).unwrap();
+        assert_eq!(cmyk.components(), 4);
+        assert_eq!(to_bytes(cmyk.to_rgb(&cmyk.initial_color())), [0, 0, 0]);
+        assert_eq!(to_bytes(cmyk.to_rgb(&[1.0, 0.0, 0.0, 0.0])), [0, 255, 255]);
+    }
+
+    #[test]
+    fn test_indexed_space() {
+        let resolver = MemoryResolver::default();
+        let object = PdfObject::Array(PdfArray(vec![
+            name("Indexed"),
+            name("DeviceRGB"),
+            PdfObject::Integer(1),
+            PdfObject::String(PdfString::new(vec![255, 0, 0, 0, 0, 255])),
+        ]));
+        let space = ColorSpace::parse(&resolver, &object, None).unwrap();
+        assert_eq!(to_bytes(space.to_rgb(&[1.0])), [0, 0, 255]);
+        assert_eq!(space.default_decode(8), vec![(0.0, 255.0)]);
+    }
+
+    #[test]
+    fn test_named_resource_and_separation() {
+        let mut tint = PdfDictionary::new();
+        tint.insert("FunctionType".to_string(), PdfObject::Integer(2));
+        tint.insert(
+            "Domain".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(0), PdfObject::Integer(1)])),
+        );
+        tint.insert(
+            "C0".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(1)])),
+        );
+        tint.insert(
+            "C1".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(0)])),
+        );
+        tint.insert("N".to_string(), PdfObject::Integer(1));
+        let separation = PdfObject::Array(PdfArray(vec![
+            name("Separation"),
+            name("Spot"),
+            name("DeviceGray"),
+            PdfObject::Dictionary(tint),
+        ]));
+        let mut spaces = PdfDictionary::new();
+        spaces.insert("CS0".to_string(), separation);
+        let mut resources = PdfDictionary::new();
+        resources.insert("ColorSpace".to_string(), PdfObject::Dictionary(spaces));
+
+        let resolver = MemoryResolver::default();
+        let space = ColorSpace::from_name(&resolver, "CS0", Some(&resources)).unwrap();
+        assert_eq!(space.components(), 1);
+        assert_eq!(to_bytes(space.to_rgb(&space.initial_color())), [0, 0, 0]);
+        assert_eq!(to_bytes(space.to_rgb(&[0.0])), [255, 255, 255]);
+        assert!(ColorSpace::from_name(&resolver, "Missing", Some(&resources)).is_none());
+    }
+}
@@ -0,0 +1,918 @@
+//! Reader for Compact Font Format fonts and their Type 2 charstrings
+//! (Adobe Technical Notes #5176 and #5177)
+//!
+//! Covers bare CFF data (`FontFile3` of subtype `Type1C` or
+//! `CIDFontType0C`) as well as the `CFF ` table of OpenType fonts.
+
+use super::encoding::{BaseEncoding, CFF_STANDARD_STRINGS};
+use crate::geometry::Point;
+use crate::rendering::path::Path;
+use std::collections::HashMap;
+
+/// Nesting limit for subroutine calls (Type 2 charstrings allow 10)
+const MAX_SUBR_DEPTH: usize = 10;
+
+/// Operand stack limit of Type 2 charstrings
+const MAX_STACK: usize = 48;
+
+/// A parsed CFF font
+#[derive(Debug, Clone)]
+pub(crate) struct Cff {
+    data: Vec<u8>,
+    char_strings: Vec<(usize, usize)>,
+    global_subrs: Vec<(usize, usize)>,
+    /// Local subroutines of each Font DICT (one for name-keyed fonts)
+    local_subrs: Vec<Vec<(usize, usize)>>,
+    /// Font DICT of each glyph in CID-keyed fonts
+    fd_select: Option<Vec<u8>>,
+    /// String ID (name-keyed) or CID (CID-keyed) of each glyph
+    charset: Vec<u16>,
+    /// Glyph of each code in the font's built-in encoding
+    encoding: HashMap<u8, u16>,
+    pub font_matrix: [f64; 6],
+    pub is_cid: bool,
+    strings: Vec<(usize, usize)>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Read an INDEX at `offset`; returns the absolute ranges of its items and
+/// the offset just past it
+fn read_index(data: &[u8], offset: usize) -> Option<(Vec<(usize, usize)>, usize)> {
+    let count = u16_at(data, offset)? as usize;
+    if count == 0 {
+        return Some((Vec::new(), offset + 2));
+    }
+    let off_size = *data.get(offset + 2)? as usize;
+    if !(1..=4).contains(&off_size) {
+        return None;
+    }
+    let offsets_start = offset + 3;
+    let read_offset = |i: usize| -> Option<usize> {
+        let bytes = data.get(offsets_start + i * off_size..offsets_start + (i + 1) * off_size)?;
+        Some(bytes.iter().fold(0usize, |acc, &b| acc << 8 | b as usize))
+    };
+    let base = offsets_start + (count + 1) * off_size - 1;
+    let mut items = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = base + read_offset(i)?;
+        let end = base + read_offset(i + 1)?;
+        if end < start || end > data.len() {
+            return None;
+        }
+        items.push((start, end));
+    }
+    let end = base + read_offset(count)?;
+    Some((items, end))
+}
+
+/// Operator keys of a DICT, escaped operators as `1200 + n`
+type Dict = HashMap<u16, Vec<f64>>;
+
+fn read_dict(data: &[u8]) -> Dict {
+    let mut dict = HashMap::new();
+    let mut operands = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let b0 = data[i];
+        i += 1;
+        match b0 {
+            0..=21 => {
+                let key = if b0 == 12 {
+                    let b1 = data.get(i).copied().unwrap_or(0);
+                    i += 1;
+                    1200 + b1 as u16
+                } else {
+                    b0 as u16
+                };
+                dict.insert(key, std::mem::take(&mut operands));
+            }
+            28 => {
+                let v = data
+                    .get(i..i + 2)
+                    .map_or(0, |b| i16::from_be_bytes([b[0], b[1]]));
+                operands.push(v as f64);
+                i += 2;
+            }
+            29 => {
+                let v = data
+                    .get(i..i + 4)
+                    .map_or(0, |b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+                operands.push(v as f64);
+                i += 4;
+            }
+            30 => {
+                let mut text = String::new();
+                'nibbles: while let Some(&byte) = data.get(i) {
+                    i += 1;
+                    for nibble in [byte >> 4, byte & 15] {
+                        match nibble {
+                            0..=9 => text.push((b'0' + nibble) as char),
+                            0xA => text.push('.'),
+                            0xB => text.push('E'),
+                            0xC => text.push_str("E-"),
+                            0xE => text.push('-'),
+                            0xF => break 'nibbles,
+                            _ => {}
+                        }
+                    }
+                }
+                operands.push(text.parse().unwrap_or(0.0));
+            }
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => {
+                let b1 = data.get(i).copied().unwrap_or(0) as f64;
+                i += 1;
+                operands.push((b0 as f64 - 247.0) * 256.0 + b1 + 108.0);
+            }
+            251..=254 => {
+                let b1 = data.get(i).copied().unwrap_or(0) as f64;
+                i += 1;
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - b1 - 108.0);
+            }
+            _ => {}
+        }
+    }
+    dict
+}
+
+fn dict_number(dict: &Dict, key: u16) -> Option<usize> {
+    dict.get(&key)?
+        .first()
+        .filter(|v| **v >= 0.0)
+        .map(|&v| v as usize)
+}
+
+/// Local subroutines of the Private DICT referenced by `dict`
+fn private_subrs(data: &[u8], dict: &Dict) -> Vec<(usize, usize)> {
+    let Some(&[size, offset]) = dict.get(&18).map(Vec::as_slice) else {
+        return Vec::new();
+    };
+    let (size, offset) = (size.max(0.0) as usize, offset.max(0.0) as usize);
+    let Some(private) = data.get(offset..offset + size) else {
+        return Vec::new();
+    };
+    dict_number(&read_dict(private), 19)
+        .and_then(|subrs| read_index(data, offset + subrs))
+        .map(|(items, _)| items)
+        .unwrap_or_default()
+}
+
+impl Cff {
+    pub fn parse(data: Vec<u8>) -> Option<Cff> {
+        let header_size = *data.get(2)? as usize;
+        let (_, after_names) = read_index(&data, header_size)?;
+        let (top_dicts, after_top) = read_index(&data, after_names)?;
+        let (strings, after_strings) = read_index(&data, after_top)?;
+        let (global_subrs, _) = read_index(&data, after_strings)?;
+        let &(top_start, top_end) = top_dicts.first()?;
+        let top = read_dict(&data[top_start..top_end]);
+
+        let (char_strings, _) = read_index(&data, dict_number(&top, 17)?)?;
+        let glyph_count = char_strings.len();
+        let is_cid = top.contains_key(&1230);
+
+        let font_matrix = match top.get(&1207).map(Vec::as_slice) {
+            Some(&[a, b, c, d, e, f]) => [a, b, c, d, e, f],
+            _ => [0.001, 0.0, 0.0, 0.001, 0.0, 0.0],
+        };
+
+        let (local_subrs, fd_select) = if is_cid {
+            let (fd_array, _) = read_index(&data, dict_number(&top, 1236)?)?;
+            let local_subrs = fd_array
+                .iter()
+                .map(|&(start, end)| private_subrs(&data, &read_dict(&data[start..end])))
+                .collect();
+            let fd_select = dict_number(&top, 1237)
+                .and_then(|offset| read_fd_select(&data, offset, glyph_count));
+            (local_subrs, fd_select)
+        } else {
+            (vec![private_subrs(&data, &top)], None)
+        };
+
+        let charset = match dict_number(&top, 15).unwrap_or(0) {
+            // ISOAdobe: glyphs in standard string order
+            0 => (0..glyph_count.min(229) as u16).collect(),
+            1 | 2 => vec![0],
+            offset => read_charset(&data, offset, glyph_count).unwrap_or_else(|| vec![0]),
+        };
+
+        let mut font = Cff {
+            data,
+            char_strings,
+            global_subrs,
+            local_subrs,
+            fd_select,
+            charset,
+            encoding: HashMap::new(),
+            font_matrix,
+            is_cid,
+            strings,
+        };
+        if !is_cid {
+            font.encoding = font.read_encoding(dict_number(&top, 16).unwrap_or(0));
+        }
+        Some(font)
+    }
+
+    #[cfg(test)]
+    pub fn glyph_count(&self) -> usize {
+        self.char_strings.len()
+    }
+
+    /// Glyph of a CID in CID-keyed fonts
+    pub fn glyph_for_cid(&self, cid: u16) -> Option<u16> {
+        if cid == 0 {
+            return Some(0);
+        }
+        self.charset
+            .iter()
+            .position(|&c| c == cid)
+            .map(|gid| gid as u16)
+    }
+
+    /// Glyph with a given name in name-keyed fonts
+    pub fn glyph_by_name(&self, name: &str) -> Option<u16> {
+        self.charset
+            .iter()
+            .position(|&sid| self.string(sid) == Some(name))
+            .map(|gid| gid as u16)
+    }
+
+    /// Glyph of a code in the font's built-in encoding
+    pub fn glyph_for_code(&self, code: u8) -> Option<u16> {
+        self.encoding.get(&code).copied()
+    }
+
+    /// Name of a glyph in name-keyed fonts
+    #[cfg(test)]
+    pub fn glyph_name(&self, gid: u16) -> Option<&str> {
+        self.string(*self.charset.get(gid as usize)?)
+    }
+
+    fn string(&self, sid: u16) -> Option<&str> {
+        let sid = sid as usize;
+        if sid < CFF_STANDARD_STRINGS.len() {
+            return Some(CFF_STANDARD_STRINGS[sid]);
+        }
+        let &(start, end) = self.strings.get(sid - CFF_STANDARD_STRINGS.len())?;
+        std::str::from_utf8(&self.data[start..end]).ok()
+    }
+
+    fn read_encoding(&self, offset: usize) -> HashMap<u8, u16> {
+        let mut encoding = HashMap::new();
+        match offset {
+            0 | 1 => {
+                // Standard encoding (Expert fonts are rare enough to share it)
+                for code in 0..=255u8 {
+                    if let Some(gid) = BaseEncoding::Standard
+                        .glyph_name(code)
+                        .and_then(|name| self.glyph_by_name(name))
+                    {
+                        encoding.insert(code, gid);
+                    }
+                }
+            }
+            _ => {
+                let data = &self.data;
+                let Some(&format) = data.get(offset) else {
+                    return encoding;
+                };
+                let count = data.get(offset + 1).copied().unwrap_or(0) as usize;
+                let mut gid = 1u16;
+                match format & 0x7F {
+                    0 => {
+                        for i in 0..count {
+                            if let Some(&code) = data.get(offset + 2 + i) {
+                                encoding.insert(code, gid);
+                            }
+                            gid += 1;
+                        }
+                    }
+                    1 => {
+                        for i in 0..count {
+                            let (Some(&first), Some(&left)) =
+                                (data.get(offset + 2 + i * 2), data.get(offset + 3 + i * 2))
+                            else {
+                                break;
+                            };
+                            for code in first as u16..=first as u16 + left as u16 {
+                                if code <= 255 {
+                                    encoding.insert(code as u8, gid);
+                                }
+                                gid += 1;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        encoding
+    }
+
+    /// Outline of a glyph in glyph space (apply `font_matrix` for text space)
+    pub fn glyph_outline(&self, gid: u16) -> Option<Path> {
+        let &(start, end) = self.char_strings.get(gid as usize)?;
+        let fd = match &self.fd_select {
+            Some(select) => *select.get(gid as usize)? as usize,
+            None => 0,
+        };
+        let mut interpreter = Interpreter {
+            font: self,
+            local_subrs: self.local_subrs.get(fd).map_or(&[], Vec::as_slice),
+            path: Path::new(),
+            stack: Vec::new(),
+            x: 0.0,
+            y: 0.0,
+            stems: 0,
+            width_seen: false,
+            open: false,
+            transient: [0.0; 32],
+        };
+        interpreter.run(&self.data[start..end], 0)?;
+        if interpreter.open {
+            interpreter.path.close();
+        }
+        Some(interpreter.path)
+    }
+}
+
+fn read_charset(data: &[u8], offset: usize, glyph_count: usize) -> Option<Vec<u16>> {
+    let format = *data.get(offset)?;
+    let mut charset = vec![0u16];
+    let mut position = offset + 1;
+    while charset.len() < glyph_count {
+        match format {
+            0 => {
+                charset.push(u16_at(data, position)?);
+                position += 2;
+            }
+            1 | 2 => {
+                let first = u16_at(data, position)?;
+                let left = if format == 1 {
+                    *data.get(position + 2)? as u16
+                } else {
+                    u16_at(data, position + 2)?
+                };
+                position += if format == 1 { 3 } else { 4 };
+                for i in 0..=left {
+                    charset.push(first.wrapping_add(i));
+                }
+            }
+            _ => return None,
+        }
+    }
+    charset.truncate(glyph_count);
+    Some(charset)
+}
+
+fn read_fd_select(data: &[u8], offset: usize, glyph_count: usize) -> Option<Vec<u8>> {
+    match *data.get(offset)? {
+        0 => data
+            .get(offset + 1..offset + 1 + glyph_count)
+            .map(<[u8]>::to_vec),
+        3 => {
+            let ranges = u16_at(data, offset + 1)? as usize;
+            let mut select = vec![0u8; glyph_count];
+            for i in 0..ranges {
+                let record = offset + 3 + i * 3;
+                let first = u16_at(data, record)? as usize;
+                let fd = *data.get(record + 2)?;
+                let next = u16_at(data, record + 3)? as usize;
+                for entry in select.iter_mut().take(next.min(glyph_count)).skip(first) {
+                    *entry = fd;
+                }
+            }
+            Some(select)
+        }
+        _ => None,
+    }
+}
+
+/// Subroutine index bias (Technical Note #5177, §4.7)
+fn bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+struct Interpreter<'a> {
+    font: &'a Cff,
+    local_subrs: &'a [(usize, usize)],
+    path: Path,
+    stack: Vec<f64>,
+    x: f64,
+    y: f64,
+    stems: usize,
+    width_seen: bool,
+    open: bool,
+    transient: [f64; 32],
+}
+
+/// Outcome of running a charstring or subroutine
+enum Flow {
+    Continue,
+    Return,
+    End,
+}
+
+impl Interpreter<'_> {
+    fn run(&mut self, code: &[u8], depth: usize) -> Option<Flow> {
+        if depth > MAX_SUBR_DEPTH {
+            return None;
+        }
+        let mut i = 0;
+        while i < code.len() {
+            let b0 = code[i];
+            i += 1;
+            match b0 {
+                28 => {
+                    let b = code.get(i..i + 2)?;
+                    self.push(i16::from_be_bytes([b[0], b[1]]) as f64);
+                    i += 2;
+                }
+                32..=246 => self.push(b0 as f64 - 139.0),
+                247..=250 => {
+                    let b1 = *code.get(i)? as f64;
+                    i += 1;
+                    self.push((b0 as f64 - 247.0) * 256.0 + b1 + 108.0);
+                }
+                251..=254 => {
+                    let b1 = *code.get(i)? as f64;
+                    i += 1;
+                    self.push(-(b0 as f64 - 251.0) * 256.0 - b1 - 108.0);
+                }
+                255 => {
+                    let b = code.get(i..i + 4)?;
+                    self.push(i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64 / 65536.0);
+                    i += 4;
+                }
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                // hintmask, cntrmask
+                19 | 20 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    i += (self.stems + 7) / 8;
+                }
+                21 => {
+                    self.take_width(self.stack.len() > 2);
+                    let (dx, dy) = (self.arg(0), self.arg(1));
+                    self.move_by(dx, dy);
+                }
+                22 => {
+                    self.take_width(self.stack.len() > 1);
+                    let dx = self.arg(0);
+                    self.move_by(dx, 0.0);
+                }
+                4 => {
+                    self.take_width(self.stack.len() > 1);
+                    let dy = self.arg(0);
+                    self.move_by(0.0, dy);
+                }
+                5 => {
+                    for pair in std::mem::take(&mut self.stack).chunks_exact(2) {
+                        self.line_by(pair[0], pair[1]);
+                    }
+                }
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for d in std::mem::take(&mut self.stack) {
+                        if horizontal {
+                            self.line_by(d, 0.0);
+                        } else {
+                            self.line_by(0.0, d);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                8 => {
+                    for c in std::mem::take(&mut self.stack).chunks_exact(6) {
+                        self.curve_by(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                }
+                // rcurveline
+                24 => {
+                    let args = std::mem::take(&mut self.stack);
+                    let curves = args.len().saturating_sub(2) / 6;
+                    for c in args.chunks_exact(6).take(curves) {
+                        self.curve_by(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                    if let [dx, dy] = args[curves * 6..] {
+                        self.line_by(dx, dy);
+                    }
+                }
+                // rlinecurve
+                25 => {
+                    let args = std::mem::take(&mut self.stack);
+                    let lines = args.len().saturating_sub(6) / 2;
+                    for pair in args.chunks_exact(2).take(lines) {
+                        self.line_by(pair[0], pair[1]);
+                    }
+                    if let [a, b, c, d, e, f] = args[lines * 2..] {
+                        self.curve_by(a, b, c, d, e, f);
+                    }
+                }
+                // vvcurveto
+                26 => {
+                    let mut args = std::mem::take(&mut self.stack);
+                    let dx1 = if args.len() % 4 == 1 {
+                        args.remove(0)
+                    } else {
+                        0.0
+                    };
+                    let mut first = true;
+                    for c in args.chunks_exact(4) {
+                        let dx = if first { dx1 } else { 0.0 };
+                        first = false;
+                        self.curve_by(dx, c[0], c[1], c[2], 0.0, c[3]);
+                    }
+                }
+                // hhcurveto
+                27 => {
+                    let mut args = std::mem::take(&mut self.stack);
+                    let dy1 = if args.len() % 4 == 1 {
+                        args.remove(0)
+                    } else {
+                        0.0
+                    };
+                    let mut first = true;
+                    for c in args.chunks_exact(4) {
+                        let dy = if first { dy1 } else { 0.0 };
+                        first = false;
+                        self.curve_by(c[0], dy, c[1], c[2], c[3], 0.0);
+                    }
+                }
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let args = std::mem::take(&mut self.stack);
+                    let mut horizontal = b0 == 31;
+                    let count = args.len() / 4;
+                    for (k, c) in args.chunks_exact(4).enumerate() {
+                        let last = if k + 1 == count && args.len() % 4 == 1 {
+                            args[args.len() - 1]
+                        } else {
+                            0.0
+                        };
+                        if horizontal {
+                            self.curve_by(c[0], 0.0, c[1], c[2], last, c[3]);
+                        } else {
+                            self.curve_by(0.0, c[0], c[1], c[2], c[3], last);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                10 | 29 => {
+                    let index = self.stack.pop()? as i32;
+                    let subrs = if b0 == 10 {
+                        self.local_subrs
+                    } else {
+                        self.font.global_subrs.as_slice()
+                    };
+                    let &(start, end) = subrs.get((index + bias(subrs.len())) as usize)?;
+                    let font = self.font;
+                    if let Flow::End = self.run(&font.data[start..end], depth + 1)? {
+                        return Some(Flow::End);
+                    }
+                }
+                11 => return Some(Flow::Return),
+                14 => {
+                    self.take_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    if self.open {
+                        self.path.close();
+                        self.open = false;
+                    }
+                    if let [adx, ady, bchar, achar] = self.stack[..] {
+                        self.accented(adx, ady, bchar as u8, achar as u8, depth);
+                    }
+                    return Some(Flow::End);
+                }
+                12 => {
+                    let b1 = *code.get(i)?;
+                    i += 1;
+                    self.escape(b1);
+                }
+                _ => self.stack.clear(),
+            }
+        }
+        Some(Flow::Continue)
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.stack.len() < MAX_STACK {
+            self.stack.push(value);
+        }
+    }
+
+    fn arg(&self, i: usize) -> f64 {
+        self.stack.get(i).copied().unwrap_or(0.0)
+    }
+
+    /// Drop the advance width preceding the first stack-clearing operator
+    fn take_width(&mut self, present: bool) {
+        if !self.width_seen {
+            self.width_seen = true;
+            if present && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    fn move_by(&mut self, dx: f64, dy: f64) {
+        if self.open {
+            self.path.close();
+        }
+        self.x += dx;
+        self.y += dy;
+        self.path.move_to(Point::new(self.x, self.y));
+        self.open = true;
+        self.stack.clear();
+    }
+
+    fn line_by(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        self.path.line_to(Point::new(self.x, self.y));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn curve_by(&mut self, dx1: f64, dy1: f64, dx2: f64, dy2: f64, dx3: f64, dy3: f64) {
+        let c1 = Point::new(self.x + dx1, self.y + dy1);
+        let c2 = Point::new(c1.x + dx2, c1.y + dy2);
+        self.x = c2.x + dx3;
+        self.y = c2.y + dy3;
+        self.path.curve_to(c1, c2, Point::new(self.x, self.y));
+    }
+
+    /// Flex hints and the arithmetic operators
+    fn escape(&mut self, op: u8) {
+        let args = std::mem::take(&mut self.stack);
+        let a = |i: usize| args.get(i).copied().unwrap_or(0.0);
+        match op {
+            // flex
+            35 => {
+                self.curve_by(a(0), a(1), a(2), a(3), a(4), a(5));
+                self.curve_by(a(6), a(7), a(8), a(9), a(10), a(11));
+            }
+            // hflex
+            34 => {
+                let y = self.y;
+                self.curve_by(a(0), 0.0, a(1), a(2), a(3), 0.0);
+                self.curve_by(a(4), 0.0, a(5), y - self.y, a(6), 0.0);
+            }
+            // hflex1
+            36 => {
+                let y = self.y;
+                self.curve_by(a(0), a(1), a(2), a(3), a(4), 0.0);
+                let dy = y - (self.y + a(7));
+                self.curve_by(a(5), 0.0, a(6), a(7), a(8), dy);
+            }
+            // flex1
+            37 => {
+                let (x0, y0) = (self.x, self.y);
+                let dx: f64 = (0..5).map(|k| a(k * 2)).sum();
+                let dy: f64 = (0..5).map(|k| a(k * 2 + 1)).sum();
+                self.curve_by(a(0), a(1), a(2), a(3), a(4), a(5));
+                let (x1, y1) = (self.x + a(6) + a(8), self.y + a(7) + a(9));
+                let (d6, d6y) = if dx.abs() > dy.abs() {
+                    (a(10), y0 - y1)
+                } else {
+                    (x0 - x1, a(10))
+                };
+                self.curve_by(a(6), a(7), a(8), a(9), d6, d6y);
+            }
+            // abs, add, sub, div, neg, mul, sqrt
+            9 => self.stack.push(a(0).abs()),
+            10 => self.stack.push(a(0) + a(1)),
+            11 => self.stack.push(a(0) - a(1)),
+            12 => self.stack.push(if a(1) != 0.0 { a(0) / a(1) } else { 0.0 }),
+            14 => self.stack.push(-a(0)),
+            24 => self.stack.push(a(0) * a(1)),
+            26 => self.stack.push(a(0).max(0.0).sqrt()),
+            // drop
+            18 => {
+                self.stack = args;
+                self.stack.pop();
+            }
+            // dup, exch
+            27 => {
+                self.stack = args;
+                if let Some(&top) = self.stack.last() {
+                    self.stack.push(top);
+                }
+            }
+            28 => {
+                self.stack = args;
+                let n = self.stack.len();
+                if n >= 2 {
+                    self.stack.swap(n - 1, n - 2);
+                }
+            }
+            // put, get
+            20 => {
+                if let Some(slot) = self.transient.get_mut(a(1) as usize) {
+                    *slot = a(0);
+                }
+            }
+            21 => self
+                .stack
+                .push(self.transient.get(a(0) as usize).copied().unwrap_or(0.0)),
+            // and, or, not, eq, ifelse
+            3 => self.stack.push((a(0) != 0.0 && a(1) != 0.0) as u8 as f64),
+            4 => self.stack.push((a(0) != 0.0 || a(1) != 0.0) as u8 as f64),
+            5 => self.stack.push((a(0) == 0.0) as u8 as f64),
+            15 => self.stack.push((a(0) == a(1)) as u8 as f64),
+            22 => self.stack.push(if a(2) <= a(3) { a(0) } else { a(1) }),
+            _ => {}
+        }
+    }
+
+    /// The deprecated `seac` form of endchar: a base glyph with an accent
+    /// offset by (`adx`, `ady`), both given by StandardEncoding codes
+    fn accented(&mut self, adx: f64, ady: f64, base: u8, accent: u8, depth: usize) {
+        let glyph = |code: u8| {
+            BaseEncoding::Standard
+                .glyph_name(code)
+                .and_then(|name| self.font.glyph_by_name(name))
+        };
+        let (Some(base), Some(accent)) = (glyph(base), glyph(accent)) else {
+            return;
+        };
+        if depth > MAX_SUBR_DEPTH {
+            return;
+        }
+        if let Some(outline) = self.font.glyph_outline(base) {
+            self.path.extend(&outline);
+        }
+        if let Some(outline) = self.font.glyph_outline(accent) {
+            let offset =
+                crate::coordinate_system::TransformMatrix::new(1.0, 0.0, 0.0, 1.0, adx, ady);
+            self.path.extend(&outline.transform(&offset));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Encode a DICT or charstring integer operand
+    fn number(v: i32) -> Vec<u8> {
+        match v {
+            -107..=107 => vec![(v + 139) as u8],
+            108..=1131 => {
+                let v = v - 108;
+                vec![(v / 256 + 247) as u8, (v % 256) as u8]
+            }
+            -1131..=-108 => {
+                let v = -v - 108;
+                vec![(v / 256 + 251) as u8, (v % 256) as u8]
+            }
+            _ => {
+                let mut out = vec![28];
+                out.extend((v as i16).to_be_bytes());
+                out
+            }
+        }
+    }
+
+    fn index(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = (items.len() as u16).to_be_bytes().to_vec();
+        if items.is_empty() {
+            return out;
+        }
+        out.push(2);
+        let mut offset = 1u16;
+        out.extend(offset.to_be_bytes());
+        for item in items {
+            offset += item.len() as u16;
+            out.extend(offset.to_be_bytes());
+        }
+        for item in items {
+            out.extend(item);
+        }
+        out
+    }
+
+    /// A name-keyed CFF font with `.notdef` and a 'A' square drawn through
+    /// a local subroutine; the charset names glyph 1 "A" (SID 34)
+    pub(crate) fn test_font() -> Vec<u8> {
+        let notdef = vec![14];
+        // width 500, 100 100 rmoveto, callsubr 0 (draws the square), endchar
+        let square: Vec<u8> = [
+            number(500),
+            number(100),
+            number(100),
+            vec![21],
+            number(-107),
+            vec![10, 14],
+        ]
+        .concat();
+        // 400 hlineto 400 vlineto -400 hlineto return
+        let subr: Vec<u8> = [
+            number(400),
+            vec![6],
+            number(400),
+            vec![7],
+            number(-400),
+            vec![6, 11],
+        ]
+        .concat();
+
+        let header = vec![1, 0, 4, 1];
+        let names = index(&[b"Test".to_vec()]);
+        let strings = index(&[]);
+        let globals = index(&[]);
+        let char_strings = index(&[notdef, square]);
+        let charset = vec![0, 0, 34];
+        let private_subrs = index(&[subr]);
+        // Private DICT: Subrs at offset (its own length)
+        let private_len = 2;
+        let private: Vec<u8> = [number(private_len), vec![19]].concat();
+
+        // Top DICT with fixed-size (28) operands so its size is known up front
+        let top_len = 3 + 1 + 3 + 1 + 3 + 3 + 1;
+        let top_index_len = 2 + 1 + 4 + top_len;
+        let base = header.len() + names.len() + top_index_len + strings.len() + globals.len();
+        let char_strings_offset = base;
+        let charset_offset = char_strings_offset + char_strings.len();
+        let private_offset = charset_offset + charset.len();
+        let fixed = |v: usize| {
+            let mut out = vec![28];
+            out.extend((v as i16).to_be_bytes());
+            out
+        };
+        let top: Vec<u8> = [
+            fixed(char_strings_offset),
+            vec![17],
+            fixed(charset_offset),
+            vec![15],
+            fixed(private.len()),
+            fixed(private_offset),
+            vec![18],
+        ]
+        .concat();
+        assert_eq!(top.len(), top_len);
+
+        [
+            header,
+            names,
+            index(&[top]),
+            strings,
+            globals,
+            char_strings,
+            charset,
+            private,
+            private_subrs,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_parse_name_keyed_font() {
+        let font = Cff::parse(test_font()).unwrap();
+        assert!(!font.is_cid);
+        assert_eq!(font.glyph_count(), 2);
+        assert_eq!(font.glyph_by_name("A"), Some(1));
+        assert_eq!(font.glyph_name(1), Some("A"));
+        assert_eq!(font.glyph_for_code(b'A'), Some(1));
+        assert_eq!(font.font_matrix, [0.001, 0.0, 0.0, 0.001, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_charstring_outline() {
+        let font = Cff::parse(test_font()).unwrap();
+        let polylines = font.glyph_outline(1).unwrap().flatten(1.0);
+        assert_eq!(polylines.len(), 1);
+        assert_eq!(
+            polylines[0].points,
+            vec![
+                Point::new(100.0, 100.0),
+                Point::new(500.0, 100.0),
+                Point::new(500.0, 500.0),
+                Point::new(100.0, 500.0)
+            ]
+        );
+        assert!(font.glyph_outline(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dict_operands() {
+        let dict = read_dict(&[0x8B, 0xF7, 0x00, 0x1E, 0xE2, 0xA2, 0x5F, 0x00]);
+        assert_eq!(dict.get(&0), Some(&vec![0.0, 108.0, -2.25]));
+    }
+}
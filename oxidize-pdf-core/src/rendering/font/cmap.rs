@@ -0,0 +1,291 @@
+//! CMaps of composite fonts: splitting strings into character codes and
+//! mapping the codes to CIDs (ISO 32000-1 §9.7.5)
+
+/// A code range of `bytes`-byte codes; every byte lies in its own range
+#[derive(Debug, Clone, PartialEq)]
+struct Codespace {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CidRange {
+    bytes: usize,
+    low: u32,
+    high: u32,
+    cid: u32,
+}
+
+/// An encoding CMap
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CidCMap {
+    codespaces: Vec<Codespace>,
+    ranges: Vec<CidRange>,
+    /// Two-byte codes that are their own CIDs (Identity-H and Identity-V)
+    identity: bool,
+    pub vertical: bool,
+}
+
+impl CidCMap {
+    pub fn identity(vertical: bool) -> Self {
+        Self {
+            codespaces: vec![Codespace {
+                low: vec![0, 0],
+                high: vec![0xFF, 0xFF],
+            }],
+            ranges: Vec::new(),
+            identity: true,
+            vertical,
+        }
+    }
+
+    /// A predefined CMap by name. Only the Identity CMaps are known; other
+    /// names are read as two-byte identity mappings.
+    pub fn predefined(name: &str) -> Self {
+        Self::identity(name.ends_with("-V"))
+    }
+
+    /// Parse an embedded CMap stream
+    pub fn parse(data: &[u8]) -> Self {
+        let mut cmap = Self {
+            codespaces: Vec::new(),
+            ranges: Vec::new(),
+            identity: false,
+            vertical: false,
+        };
+        let tokens = tokenize(data);
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i] {
+                Token::Word(word) if word == "begincodespacerange" => {
+                    i += 1;
+                    while let (Some(Token::Hex(low)), Some(Token::Hex(high))) =
+                        (tokens.get(i), tokens.get(i + 1))
+                    {
+                        if low.len() == high.len() && !low.is_empty() && low.len() <= 4 {
+                            cmap.codespaces.push(Codespace {
+                                low: low.clone(),
+                                high: high.clone(),
+                            });
+                        }
+                        i += 2;
+                    }
+                }
+                Token::Word(word) if word == "begincidrange" => {
+                    i += 1;
+                    while let (
+                        Some(Token::Hex(low)),
+                        Some(Token::Hex(high)),
+                        Some(Token::Int(cid)),
+                    ) = (tokens.get(i), tokens.get(i + 1), tokens.get(i + 2))
+                    {
+                        cmap.ranges.push(CidRange {
+                            bytes: low.len(),
+                            low: be(low),
+                            high: be(high),
+                            cid: *cid,
+                        });
+                        i += 3;
+                    }
+                }
+                Token::Word(word) if word == "begincidchar" => {
+                    i += 1;
+                    while let (Some(Token::Hex(code)), Some(Token::Int(cid))) =
+                        (tokens.get(i), tokens.get(i + 1))
+                    {
+                        cmap.ranges.push(CidRange {
+                            bytes: code.len(),
+                            low: be(code),
+                            high: be(code),
+                            cid: *cid,
+                        });
+                        i += 2;
+                    }
+                }
+                Token::Word(word) if word == "usecmap" => {
+                    if let Some(Token::Name(name)) = i.checked_sub(1).and_then(|p| tokens.get(p)) {
+                        if name.starts_with("Identity") {
+                            cmap.identity = true;
+                        }
+                    }
+                    i += 1;
+                }
+                Token::Name(name) if name == "WMode" => {
+                    cmap.vertical = matches!(tokens.get(i + 1), Some(Token::Int(1)));
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+        if cmap.codespaces.is_empty() {
+            cmap.codespaces = Self::identity(false).codespaces;
+        }
+        cmap
+    }
+
+    /// The code at the start of `bytes` and its length in bytes
+    pub fn next_code(&self, bytes: &[u8]) -> (u32, usize) {
+        for n in 1..=4.min(bytes.len()) {
+            let candidate = &bytes[..n];
+            let matches = self.codespaces.iter().any(|space| {
+                space.low.len() == n
+                    && candidate
+                        .iter()
+                        .zip(space.low.iter().zip(&space.high))
+                        .all(|(b, (lo, hi))| lo <= b && b <= hi)
+            });
+            if matches {
+                return (be(candidate), n);
+            }
+        }
+        // Unmatched bytes are consumed as a code of the shortest length
+        let n = self
+            .codespaces
+            .iter()
+            .map(|space| space.low.len())
+            .min()
+            .unwrap_or(1)
+            .min(bytes.len())
+            .max(1);
+        (be(&bytes[..n.min(bytes.len())]), n)
+    }
+
+    /// CID of a code of `bytes` bytes, 0 (`.notdef`) when unmapped
+    pub fn cid(&self, code: u32, bytes: usize) -> u32 {
+        if let Some(range) = self
+            .ranges
+            .iter()
+            .rev()
+            .find(|r| r.bytes == bytes && r.low <= code && code <= r.high)
+        {
+            return range.cid + (code - range.low);
+        }
+        if self.identity {
+            code
+        } else {
+            0
+        }
+    }
+}
+
+fn be(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |acc, &b| acc << 8 | b as u32)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Hex(Vec<u8>),
+    Int(u32),
+    Name(String),
+    Word(String),
+}
+
+fn tokenize(data: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_delimiter = |b: u8| b.is_ascii_whitespace() || b"<>[]{}()/%".contains(&b);
+    while i < data.len() {
+        let b = data[i];
+        match b {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'<' if data.get(i + 1) != Some(&b'<') => {
+                i += 1;
+                let mut digits = Vec::new();
+                while i < data.len() && data[i] != b'>' {
+                    if let Some(d) = (data[i] as char).to_digit(16) {
+                        digits.push(d as u8);
+                    }
+                    i += 1;
+                }
+                i += 1;
+                if digits.len() % 2 == 1 {
+                    digits.push(0);
+                }
+                tokens.push(Token::Hex(
+                    digits.chunks(2).map(|p| p[0] << 4 | p[1]).collect(),
+                ));
+            }
+            b'(' => {
+                // Strings only appear in CIDSystemInfo; skip them
+                let mut depth = 0;
+                while i < data.len() {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => depth += 1,
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'/' => {
+                let start = i + 1;
+                i += 1;
+                while i < data.len() && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+                tokens.push(Token::Name(
+                    String::from_utf8_lossy(&data[start..i]).into_owned(),
+                ));
+            }
+            _ if is_delimiter(b) => i += 1,
+            _ => {
+                let start = i;
+                while i < data.len() && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+                let word = String::from_utf8_lossy(&data[start..i]).into_owned();
+                tokens.push(match word.parse() {
+                    Ok(n) => Token::Int(n),
+                    Err(_) => Token::Word(word),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_width_codespace() {
+        let cmap = CidCMap::parse(
+            b"/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n\
+              /WMode 0 def\n\
+              2 begincodespacerange <00> <80> <8140> <9FFC> endcodespacerange\n\
+              1 begincidrange <20> <7E> 1 endcidrange\n\
+              1 begincidchar <8140> 633 endcidchar\n\
+              endcmap",
+        );
+        assert!(!cmap.vertical);
+        assert_eq!(cmap.next_code(b"A\x81\x40"), (0x41, 1));
+        assert_eq!(cmap.next_code(b"\x81\x40"), (0x8140, 2));
+        assert_eq!(cmap.cid(0x41, 1), 0x41 - 0x20 + 1);
+        assert_eq!(cmap.cid(0x8140, 2), 633);
+        assert_eq!(cmap.cid(0x8141, 2), 0);
+    }
+
+    #[test]
+    fn test_identity() {
+        let cmap = CidCMap::predefined("Identity-V");
+        assert!(cmap.vertical);
+        assert_eq!(cmap.next_code(b"\x01\x02\x03"), (0x0102, 2));
+        assert_eq!(cmap.cid(0x0102, 2), 0x0102);
+
+        let derived = CidCMap::parse(b"/Identity-H usecmap /WMode 1 def");
+        assert!(derived.vertical);
+        assert_eq!(derived.cid(7, 2), 7);
+    }
+}
@@ -0,0 +1,880 @@
+//! Glyph names of the simple font encodings (ISO 32000-1 Annex D) and the
+//! CFF standard strings
+
+/// The 391 predefined CFF strings (CFF specification, Appendix A)
+pub(crate) const CFF_STANDARD_STRINGS: [&str; 391] = [
+    ".notdef",
+    "space",
+    "exclam",
+    "quotedbl",
+    "numbersign",
+    "dollar",
+    "percent",
+    "ampersand",
+    "quoteright",
+    "parenleft",
+    "parenright",
+    "asterisk",
+    "plus",
+    "comma",
+    "hyphen",
+    "period",
+    "slash",
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "at",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "bracketleft",
+    "backslash",
+    "bracketright",
+    "asciicircum",
+    "underscore",
+    "quoteleft",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "braceleft",
+    "bar",
+    "braceright",
+    "asciitilde",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "fraction",
+    "yen",
+    "florin",
+    "section",
+    "currency",
+    "quotesingle",
+    "quotedblleft",
+    "guillemotleft",
+    "guilsinglleft",
+    "guilsinglright",
+    "fi",
+    "fl",
+    "endash",
+    "dagger",
+    "daggerdbl",
+    "periodcentered",
+    "paragraph",
+    "bullet",
+    "quotesinglbase",
+    "quotedblbase",
+    "quotedblright",
+    "guillemotright",
+    "ellipsis",
+    "perthousand",
+    "questiondown",
+    "grave",
+    "acute",
+    "circumflex",
+    "tilde",
+    "macron",
+    "breve",
+    "dotaccent",
+    "dieresis",
+    "ring",
+    "cedilla",
+    "hungarumlaut",
+    "ogonek",
+    "caron",
+    "emdash",
+    "AE",
+    "ordfeminine",
+    "Lslash",
+    "Oslash",
+    "OE",
+    "ordmasculine",
+    "ae",
+    "dotlessi",
+    "lslash",
+    "oslash",
+    "oe",
+    "germandbls",
+    "onesuperior",
+    "logicalnot",
+    "mu",
+    "trademark",
+    "Eth",
+    "onehalf",
+    "plusminus",
+    "Thorn",
+    "onequarter",
+    "divide",
+    "brokenbar",
+    "degree",
+    "thorn",
+    "threequarters",
+    "twosuperior",
+    "registered",
+    "minus",
+    "eth",
+    "multiply",
+    "threesuperior",
+    "copyright",
+    "Aacute",
+    "Acircumflex",
+    "Adieresis",
+    "Agrave",
+    "Aring",
+    "Atilde",
+    "Ccedilla",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Egrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Igrave",
+    "Ntilde",
+    "Oacute",
+    "Ocircumflex",
+    "Odieresis",
+    "Ograve",
+    "Otilde",
+    "Scaron",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Ugrave",
+    "Yacute",
+    "Ydieresis",
+    "Zcaron",
+    "aacute",
+    "acircumflex",
+    "adieresis",
+    "agrave",
+    "aring",
+    "atilde",
+    "ccedilla",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "egrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "igrave",
+    "ntilde",
+    "oacute",
+    "ocircumflex",
+    "odieresis",
+    "ograve",
+    "otilde",
+    "scaron",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "ugrave",
+    "yacute",
+    "ydieresis",
+    "zcaron",
+    "exclamsmall",
+    "Hungarumlautsmall",
+    "dollaroldstyle",
+    "dollarsuperior",
+    "ampersandsmall",
+    "Acutesmall",
+    "parenleftsuperior",
+    "parenrightsuperior",
+    "twodotenleader",
+    "onedotenleader",
+    "zerooldstyle",
+    "oneoldstyle",
+    "twooldstyle",
+    "threeoldstyle",
+    "fouroldstyle",
+    "fiveoldstyle",
+    "sixoldstyle",
+    "sevenoldstyle",
+    "eightoldstyle",
+    "nineoldstyle",
+    "commasuperior",
+    "threequartersemdash",
+    "periodsuperior",
+    "questionsmall",
+    "asuperior",
+    "bsuperior",
+    "centsuperior",
+    "dsuperior",
+    "esuperior",
+    "isuperior",
+    "lsuperior",
+    "msuperior",
+    "nsuperior",
+    "osuperior",
+    "rsuperior",
+    "ssuperior",
+    "tsuperior",
+    "ff",
+    "ffi",
+    "ffl",
+    "parenleftinferior",
+    "parenrightinferior",
+    "Circumflexsmall",
+    "hyphensuperior",
+    "Gravesmall",
+    "Asmall",
+    "Bsmall",
+    "Csmall",
+    "Dsmall",
+    "Esmall",
+    "Fsmall",
+    "Gsmall",
+    "Hsmall",
+    "Ismall",
+    "Jsmall",
+    "Ksmall",
+    "Lsmall",
+    "Msmall",
+    "Nsmall",
+    "Osmall",
+    "Psmall",
+    "Qsmall",
+    "Rsmall",
+    "Ssmall",
+    "Tsmall",
+    "Usmall",
+    "Vsmall",
+    "Wsmall",
+    "Xsmall",
+    "Ysmall",
+    "Zsmall",
+    "colonmonetary",
+    "onefitted",
+    "rupiah",
+    "Tildesmall",
+    "exclamdownsmall",
+    "centoldstyle",
+    "Lslashsmall",
+    "Scaronsmall",
+    "Zcaronsmall",
+    "Dieresissmall",
+    "Brevesmall",
+    "Caronsmall",
+    "Dotaccentsmall",
+    "Macronsmall",
+    "figuredash",
+    "hypheninferior",
+    "Ogoneksmall",
+    "Ringsmall",
+    "Cedillasmall",
+    "questiondownsmall",
+    "oneeighth",
+    "threeeighths",
+    "fiveeighths",
+    "seveneighths",
+    "onethird",
+    "twothirds",
+    "zerosuperior",
+    "foursuperior",
+    "fivesuperior",
+    "sixsuperior",
+    "sevensuperior",
+    "eightsuperior",
+    "ninesuperior",
+    "zeroinferior",
+    "oneinferior",
+    "twoinferior",
+    "threeinferior",
+    "fourinferior",
+    "fiveinferior",
+    "sixinferior",
+    "seveninferior",
+    "eightinferior",
+    "nineinferior",
+    "centinferior",
+    "dollarinferior",
+    "periodinferior",
+    "commainferior",
+    "Agravesmall",
+    "Aacutesmall",
+    "Acircumflexsmall",
+    "Atildesmall",
+    "Adieresissmall",
+    "Aringsmall",
+    "AEsmall",
+    "Ccedillasmall",
+    "Egravesmall",
+    "Eacutesmall",
+    "Ecircumflexsmall",
+    "Edieresissmall",
+    "Igravesmall",
+    "Iacutesmall",
+    "Icircumflexsmall",
+    "Idieresissmall",
+    "Ethsmall",
+    "Ntildesmall",
+    "Ogravesmall",
+    "Oacutesmall",
+    "Ocircumflexsmall",
+    "Otildesmall",
+    "Odieresissmall",
+    "OEsmall",
+    "Oslashsmall",
+    "Ugravesmall",
+    "Uacutesmall",
+    "Ucircumflexsmall",
+    "Udieresissmall",
+    "Yacutesmall",
+    "Thornsmall",
+    "Ydieresissmall",
+    "001.000",
+    "001.001",
+    "001.002",
+    "001.003",
+    "Black",
+    "Bold",
+    "Book",
+    "Light",
+    "Medium",
+    "Regular",
+    "Roman",
+    "Semibold",
+];
+
+/// Codes 161 and up of StandardEncoding, whose names are the CFF standard
+/// strings 96 to 149 in order
+const STANDARD_HIGH_CODES: [u8; 54] = [
+    161, 162, 163, 164, 165, 166, 167, 168, 169, 170, 171, 172, 173, 174, 175, 177, 178, 179, 180,
+    182, 183, 184, 185, 186, 187, 188, 189, 191, 193, 194, 195, 196, 197, 198, 199, 200, 202, 203,
+    205, 206, 207, 208, 225, 227, 232, 233, 234, 235, 241, 245, 248, 249, 250, 251,
+];
+
+/// WinAnsiEncoding codes 128 to 255
+const WIN_ANSI_HIGH: [&str; 128] = [
+    "Euro",
+    "",
+    "quotesinglbase",
+    "florin",
+    "quotedblbase",
+    "ellipsis",
+    "dagger",
+    "daggerdbl",
+    "circumflex",
+    "perthousand",
+    "Scaron",
+    "guilsinglleft",
+    "OE",
+    "",
+    "Zcaron",
+    "",
+    "",
+    "quoteleft",
+    "quoteright",
+    "quotedblleft",
+    "quotedblright",
+    "bullet",
+    "endash",
+    "emdash",
+    "tilde",
+    "trademark",
+    "scaron",
+    "guilsinglright",
+    "oe",
+    "",
+    "zcaron",
+    "Ydieresis",
+    "space",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "currency",
+    "yen",
+    "brokenbar",
+    "section",
+    "dieresis",
+    "copyright",
+    "ordfeminine",
+    "guillemotleft",
+    "logicalnot",
+    "hyphen",
+    "registered",
+    "macron",
+    "degree",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "acute",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "cedilla",
+    "onesuperior",
+    "ordmasculine",
+    "guillemotright",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "questiondown",
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adieresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Eth",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odieresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Yacute",
+    "Thorn",
+    "germandbls",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adieresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odieresis",
+    "divide",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "yacute",
+    "thorn",
+    "ydieresis",
+];
+
+/// MacRomanEncoding codes 128 to 255
+const MAC_ROMAN_HIGH: [&str; 128] = [
+    "Adieresis",
+    "Aring",
+    "Ccedilla",
+    "Eacute",
+    "Ntilde",
+    "Odieresis",
+    "Udieresis",
+    "aacute",
+    "agrave",
+    "acircumflex",
+    "adieresis",
+    "atilde",
+    "aring",
+    "ccedilla",
+    "eacute",
+    "egrave",
+    "ecircumflex",
+    "edieresis",
+    "iacute",
+    "igrave",
+    "icircumflex",
+    "idieresis",
+    "ntilde",
+    "oacute",
+    "ograve",
+    "ocircumflex",
+    "odieresis",
+    "otilde",
+    "uacute",
+    "ugrave",
+    "ucircumflex",
+    "udieresis",
+    "dagger",
+    "degree",
+    "cent",
+    "sterling",
+    "section",
+    "bullet",
+    "paragraph",
+    "germandbls",
+    "registered",
+    "copyright",
+    "trademark",
+    "acute",
+    "dieresis",
+    "notequal",
+    "AE",
+    "Oslash",
+    "infinity",
+    "plusminus",
+    "lessequal",
+    "greaterequal",
+    "yen",
+    "mu",
+    "partialdiff",
+    "summation",
+    "product",
+    "pi",
+    "integral",
+    "ordfeminine",
+    "ordmasculine",
+    "Omega",
+    "ae",
+    "oslash",
+    "questiondown",
+    "exclamdown",
+    "logicalnot",
+    "radical",
+    "florin",
+    "approxequal",
+    "Delta",
+    "guillemotleft",
+    "guillemotright",
+    "ellipsis",
+    "space",
+    "Agrave",
+    "Atilde",
+    "Otilde",
+    "OE",
+    "oe",
+    "endash",
+    "emdash",
+    "quotedblleft",
+    "quotedblright",
+    "quoteleft",
+    "quoteright",
+    "divide",
+    "lozenge",
+    "ydieresis",
+    "Ydieresis",
+    "fraction",
+    "currency",
+    "guilsinglleft",
+    "guilsinglright",
+    "fi",
+    "fl",
+    "daggerdbl",
+    "periodcentered",
+    "quotesinglbase",
+    "quotedblbase",
+    "perthousand",
+    "Acircumflex",
+    "Ecircumflex",
+    "Aacute",
+    "Edieresis",
+    "Egrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Igrave",
+    "Oacute",
+    "Ocircumflex",
+    "apple",
+    "Ograve",
+    "Uacute",
+    "Ucircumflex",
+    "Ugrave",
+    "dotlessi",
+    "circumflex",
+    "tilde",
+    "macron",
+    "breve",
+    "dotaccent",
+    "ring",
+    "cedilla",
+    "hungarumlaut",
+    "ogonek",
+    "caron",
+];
+
+/// Unicode values of WinAnsiEncoding codes 128 to 159; the other codes are
+/// Latin-1
+const WIN_ANSI_UNICODE: [u16; 32] = [
+    0x20AC, 0, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0, 0x017D, 0, 0, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x02DC,
+    0x2122, 0x0161, 0x203A, 0x0153, 0, 0x017E, 0x0178,
+];
+
+/// Glyph names outside WinAnsiEncoding with their Unicode values
+const OTHER_NAMES: [(&str, char); 31] = [
+    ("fi", '\u{FB01}'),
+    ("fl", '\u{FB02}'),
+    ("ff", '\u{FB00}'),
+    ("ffi", '\u{FB03}'),
+    ("ffl", '\u{FB04}'),
+    ("dotlessi", '\u{0131}'),
+    ("Lslash", '\u{0141}'),
+    ("lslash", '\u{0142}'),
+    ("fraction", '\u{2044}'),
+    ("minus", '\u{2212}'),
+    ("hungarumlaut", '\u{02DD}'),
+    ("ogonek", '\u{02DB}'),
+    ("caron", '\u{02C7}'),
+    ("breve", '\u{02D8}'),
+    ("dotaccent", '\u{02D9}'),
+    ("ring", '\u{02DA}'),
+    ("Delta", '\u{2206}'),
+    ("Omega", '\u{2126}'),
+    ("pi", '\u{03C0}'),
+    ("notequal", '\u{2260}'),
+    ("infinity", '\u{221E}'),
+    ("lessequal", '\u{2264}'),
+    ("greaterequal", '\u{2265}'),
+    ("partialdiff", '\u{2202}'),
+    ("summation", '\u{2211}'),
+    ("product", '\u{220F}'),
+    ("integral", '\u{222B}'),
+    ("radical", '\u{221A}'),
+    ("approxequal", '\u{2248}'),
+    ("lozenge", '\u{25CA}'),
+    ("nbspace", '\u{00A0}'),
+];
+
+/// A base encoding of simple fonts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BaseEncoding {
+    Standard,
+    WinAnsi,
+    MacRoman,
+}
+
+impl BaseEncoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "StandardEncoding" => Some(BaseEncoding::Standard),
+            "WinAnsiEncoding" => Some(BaseEncoding::WinAnsi),
+            "MacRomanEncoding" => Some(BaseEncoding::MacRoman),
+            _ => None,
+        }
+    }
+
+    /// Glyph name of a code, `None` where the encoding has no glyph
+    pub fn glyph_name(self, code: u8) -> Option<&'static str> {
+        let name = match (self, code) {
+            (_, 0..=31) | (_, 127) => return None,
+            (BaseEncoding::Standard, 32..=126) => CFF_STANDARD_STRINGS[code as usize - 31],
+            (BaseEncoding::Standard, _) => {
+                let index = STANDARD_HIGH_CODES.iter().position(|&c| c == code)?;
+                CFF_STANDARD_STRINGS[96 + index]
+            }
+            // The others differ from StandardEncoding in two ASCII positions
+            (_, 39) => "quotesingle",
+            (_, 96) => "grave",
+            (_, 32..=126) => CFF_STANDARD_STRINGS[code as usize - 31],
+            (BaseEncoding::WinAnsi, _) => WIN_ANSI_HIGH[code as usize - 128],
+            (BaseEncoding::MacRoman, _) => MAC_ROMAN_HIGH[code as usize - 128],
+        };
+        (!name.is_empty()).then_some(name)
+    }
+}
+
+/// Glyphs 226 to 257 of the standard Macintosh glyph order
+const MAC_GLYPHS_EXTRA: [&str; 32] = [
+    "Lslash",
+    "lslash",
+    "Scaron",
+    "scaron",
+    "Zcaron",
+    "zcaron",
+    "brokenbar",
+    "Eth",
+    "eth",
+    "Yacute",
+    "yacute",
+    "Thorn",
+    "thorn",
+    "minus",
+    "multiply",
+    "onesuperior",
+    "twosuperior",
+    "threesuperior",
+    "onehalf",
+    "onequarter",
+    "threequarters",
+    "franc",
+    "Gbreve",
+    "gbreve",
+    "Idotaccent",
+    "Scedilla",
+    "scedilla",
+    "Cacute",
+    "cacute",
+    "Ccaron",
+    "ccaron",
+    "dcroat",
+];
+
+/// Name of a glyph in the 258-glyph standard Macintosh order used by
+/// TrueType `post` tables
+pub(crate) fn mac_glyph_name(index: usize) -> Option<&'static str> {
+    match index {
+        0 => Some(".notdef"),
+        1 => Some(".null"),
+        2 => Some("nonmarkingreturn"),
+        3..=97 => BaseEncoding::WinAnsi.glyph_name(index as u8 + 29),
+        98..=225 if index == 98 + 0xCA - 128 => Some("nonbreakingspace"),
+        98..=225 => Some(MAC_ROMAN_HIGH[index - 98]),
+        226..=257 => Some(MAC_GLYPHS_EXTRA[index - 226]),
+        _ => None,
+    }
+}
+
+/// Unicode value of a glyph name: `uniXXXX`, `uXXXX[XX]`, or a name of the
+/// simple font encodings
+pub(crate) fn glyph_unicode(name: &str) -> Option<char> {
+    let base = name.split('.').next().unwrap_or(name);
+    if let Some(hex) = base.strip_prefix("uni") {
+        if hex.len() >= 4 {
+            return u32::from_str_radix(&hex[..4], 16)
+                .ok()
+                .and_then(char::from_u32);
+        }
+    }
+    if let Some(hex) = base.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+        }
+    }
+    for code in 32..=255u8 {
+        if BaseEncoding::WinAnsi.glyph_name(code) == Some(base) {
+            return win_ansi_unicode(code);
+        }
+    }
+    if base == "quoteright" {
+        return Some('\u{2019}');
+    }
+    if base == "quoteleft" {
+        return Some('\u{2018}');
+    }
+    OTHER_NAMES
+        .iter()
+        .find(|(n, _)| *n == base)
+        .map(|&(_, c)| c)
+}
+
+/// Unicode value of a WinAnsiEncoding code
+pub(crate) fn win_ansi_unicode(code: u8) -> Option<char> {
+    match code {
+        128..=159 => match WIN_ANSI_UNICODE[code as usize - 128] {
+            0 => None,
+            u => char::from_u32(u as u32),
+        },
+        _ => Some(code as char),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(BaseEncoding::Standard.glyph_name(b'A'), Some("A"));
+        assert_eq!(BaseEncoding::Standard.glyph_name(39), Some("quoteright"));
+        assert_eq!(BaseEncoding::Standard.glyph_name(174), Some("fi"));
+        assert_eq!(BaseEncoding::Standard.glyph_name(251), Some("germandbls"));
+        assert_eq!(BaseEncoding::Standard.glyph_name(176), None);
+        assert_eq!(BaseEncoding::WinAnsi.glyph_name(39), Some("quotesingle"));
+        assert_eq!(BaseEncoding::WinAnsi.glyph_name(0xE9), Some("eacute"));
+        assert_eq!(BaseEncoding::MacRoman.glyph_name(0x8E), Some("eacute"));
+        assert_eq!(CFF_STANDARD_STRINGS[299], "Zsmall");
+        assert_eq!(CFF_STANDARD_STRINGS[390], "Semibold");
+        assert_eq!(mac_glyph_name(3), Some("space"));
+        assert_eq!(mac_glyph_name(36), Some("A"));
+        assert_eq!(mac_glyph_name(98), Some("Adieresis"));
+        assert_eq!(mac_glyph_name(257), Some("dcroat"));
+    }
+
+    #[test]
+    fn test_glyph_unicode() {
+        assert_eq!(glyph_unicode("A"), Some('A'));
+        assert_eq!(glyph_unicode("eacute"), Some('é'));
+        assert_eq!(glyph_unicode("Euro"), Some('€'));
+        assert_eq!(glyph_unicode("fi"), Some('\u{FB01}'));
+        assert_eq!(glyph_unicode("uni0041"), Some('A'));
+        assert_eq!(glyph_unicode("u1F600"), Some('\u{1F600}'));
+        assert_eq!(glyph_unicode("a.sc"), Some('a'));
+        assert_eq!(glyph_unicode("g123"), None);
+    }
+}
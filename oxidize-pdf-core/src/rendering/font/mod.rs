@@ -0,0 +1,789 @@
+//! Fonts of the text showing operators: character codes mapped to glyph
+//! outlines and advance widths
+//!
+//! Outlines come from embedded TrueType (`FontFile2`), CFF and OpenType
+//! (`FontFile3`) programs, or from the glyph procedures of Type 3 fonts.
+//! Fonts without a usable embedded program still advance the text position
+//! but draw nothing.
+
+mod cff;
+mod cmap;
+mod encoding;
+pub(crate) mod sfnt;
+
+use self::cff::Cff;
+use self::cmap::CidCMap;
+use self::encoding::{glyph_unicode, BaseEncoding};
+use self::sfnt::{Sfnt, CMAP_MAC_ROMAN, CMAP_WINDOWS_SYMBOL, CMAP_WINDOWS_UNICODE};
+use super::path::{matrix, Path};
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{
+    get, get_dict, get_matrix, get_name, get_number, numbers, stream_data, Resolver,
+};
+use crate::text::cmap::{CMap, CMapEntry};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Flag of the font descriptor marking fonts outside the standard Latin
+/// character set (ISO 32000-1 Table 123)
+const SYMBOLIC: u32 = 1 << 2;
+
+/// An embedded font program
+#[derive(Debug)]
+enum Program {
+    TrueType(Sfnt),
+    Cff(Cff),
+}
+
+impl Program {
+    fn load(resolver: &dyn Resolver, descriptor: &PdfDictionary) -> Option<Program> {
+        for key in ["FontFile2", "FontFile3"] {
+            let Some(PdfObject::Stream(stream)) = get(resolver, descriptor, key) else {
+                continue;
+            };
+            let data = stream_data(resolver, &stream)?;
+            if data.starts_with(b"OTTO")
+                || data.starts_with(&[0, 1, 0, 0])
+                || data.starts_with(b"true")
+            {
+                let sfnt = Sfnt::parse(data)?;
+                return match sfnt.cff_table() {
+                    Some(table) => Cff::parse(table.to_vec()).map(Program::Cff),
+                    None => Some(Program::TrueType(sfnt)),
+                };
+            }
+            if key == "FontFile3" {
+                return Cff::parse(data).map(Program::Cff);
+            }
+        }
+        None
+    }
+
+    /// Outline in text space units (one unit per em)
+    fn outline(&self, gid: u16) -> Option<Path> {
+        match self {
+            Program::TrueType(sfnt) => {
+                let scale = 1.0 / sfnt.units_per_em as f64;
+                let m = matrix([scale, 0.0, 0.0, scale, 0.0, 0.0]);
+                Some(sfnt.glyph_outline(gid)?.transform(&m))
+            }
+            Program::Cff(cff) => Some(cff.glyph_outline(gid)?.transform(&matrix(cff.font_matrix))),
+        }
+    }
+}
+
+/// What a character code draws
+#[derive(Debug, Clone)]
+pub(crate) enum Glyph {
+    /// Outline in text space, one unit per unit of font size
+    Outline(Rc<Path>),
+    /// Content stream of a Type 3 glyph, in glyph space
+    Procedure(Rc<Vec<u8>>),
+    None,
+}
+
+/// Glyph space and resources of a Type 3 font
+#[derive(Debug, Clone)]
+pub(crate) struct Type3 {
+    pub matrix: [f64; 6],
+    pub resources: Option<PdfDictionary>,
+    procedures: HashMap<u32, Rc<Vec<u8>>>,
+}
+
+#[derive(Debug)]
+enum Encoding {
+    /// One-byte codes mapped to glyphs
+    Simple(Vec<Option<u16>>),
+    /// Codes mapped to CIDs by a CMap, CIDs to glyphs by `cid_to_gid`
+    Composite {
+        cmap: CidCMap,
+        cid_to_gid: Option<Vec<u16>>,
+    },
+}
+
+/// A font resource ready for drawing text
+#[derive(Debug)]
+pub(crate) struct Font {
+    program: Option<Program>,
+    encoding: Encoding,
+    /// Horizontal advance of each code (simple) or CID (composite), in
+    /// text space units
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+    /// Vertical metrics of CIDs: (advance, origin x, origin y)
+    vertical_metrics: HashMap<u32, (f64, f64, f64)>,
+    default_vertical: (f64, f64),
+    type3: Option<Type3>,
+    outlines: RefCell<HashMap<u16, Option<Rc<Path>>>>,
+    /// Text of codes with their byte lengths, from glyph names and the
+    /// `ToUnicode` CMap
+    unicode: HashMap<(u32, usize), String>,
+}
+
+impl Font {
+    /// Load a font dictionary. Anything unusable yields a font that only
+    /// advances the text position.
+    pub fn load(resolver: &dyn Resolver, dict: &PdfDictionary) -> Font {
+        let mut font = match get_name(resolver, dict, "Subtype").as_deref() {
+            Some("Type0") => Self::load_composite(resolver, dict),
+            Some("Type3") => Self::load_type3(resolver, dict),
+            _ => Self::load_simple(resolver, dict),
+        };
+        if let Some(PdfObject::Stream(stream)) = get(resolver, dict, "ToUnicode") {
+            let cmap = stream_data(resolver, &stream).and_then(|data| CMap::parse(&data).ok());
+            if let Some(cmap) = cmap {
+                font.add_to_unicode(&cmap);
+            }
+        }
+        font
+    }
+
+    fn empty(encoding: Encoding) -> Font {
+        Font {
+            program: None,
+            encoding,
+            widths: HashMap::new(),
+            default_width: 0.0,
+            vertical_metrics: HashMap::new(),
+            default_vertical: (0.88, -1.0),
+            type3: None,
+            outlines: RefCell::new(HashMap::new()),
+            unicode: HashMap::new(),
+        }
+    }
+
+    /// Record the glyph names of a simple font as the text of its codes
+    fn add_glyph_names(&mut self, names: &[Option<String>]) {
+        for (code, name) in names.iter().enumerate() {
+            if let Some(c) = name.as_deref().and_then(glyph_unicode) {
+                self.unicode.insert((code as u32, 1), c.to_string());
+            }
+        }
+    }
+
+    fn add_to_unicode(&mut self, cmap: &CMap) {
+        let code = |bytes: &[u8]| bytes.iter().fold(0u32, |code, &b| code << 8 | b as u32);
+        for entry in &cmap.mappings {
+            match entry {
+                CMapEntry::Single { src, dst } => {
+                    if let Some(text) = cmap.to_unicode(dst) {
+                        self.unicode.insert((code(src), src.len()), text);
+                    }
+                }
+                CMapEntry::Range {
+                    src_start,
+                    src_end,
+                    dst_start,
+                } => {
+                    let (first, last) = (code(src_start), code(src_end));
+                    for (offset, src) in (first..=last.min(first + 0xFFFF)).enumerate() {
+                        // The last byte of the destination is incremented
+                        let mut dst = dst_start.clone();
+                        if let Some(byte) = dst.last_mut() {
+                            *byte = byte.wrapping_add(offset as u8);
+                        }
+                        if let Some(text) = cmap.to_unicode(&dst) {
+                            self.unicode.insert((src, src_start.len()), text);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn load_simple(resolver: &dyn Resolver, dict: &PdfDictionary) -> Font {
+        let descriptor = get_dict(resolver, dict, "FontDescriptor").unwrap_or_default();
+        let flags = get_number(resolver, &descriptor, "Flags").unwrap_or(0.0) as u32;
+        let program = Program::load(resolver, &descriptor);
+        let names = glyph_names(resolver, dict, flags & SYMBOLIC != 0);
+
+        let glyphs = (0..=255u8)
+            .map(|code| {
+                let name = names.explicit[code as usize].as_deref();
+                match &program {
+                    Some(Program::TrueType(sfnt)) => truetype_glyph(sfnt, code, name, flags),
+                    Some(Program::Cff(cff)) => name
+                        .and_then(|name| cff.glyph_by_name(name))
+                        .or_else(|| cff.glyph_for_code(code))
+                        .or_else(|| {
+                            let name = BaseEncoding::Standard.glyph_name(code)?;
+                            cff.glyph_by_name(name)
+                        }),
+                    None => None,
+                }
+            })
+            .collect();
+
+        let mut font = Self::empty(Encoding::Simple(glyphs));
+        font.add_glyph_names(&names.effective);
+        font.default_width =
+            get_number(resolver, &descriptor, "MissingWidth").unwrap_or(0.0) / 1000.0;
+        let first = get_number(resolver, dict, "FirstChar").unwrap_or(0.0) as u32;
+        match get(resolver, dict, "Widths").and_then(|w| numbers(resolver, &w)) {
+            Some(widths) => {
+                for (i, w) in widths.into_iter().enumerate() {
+                    font.widths.insert(first + i as u32, w / 1000.0);
+                }
+            }
+            None => {
+                // Standard 14 fonts need not list their widths
+                let base = get_name(resolver, dict, "BaseFont").unwrap_or_default();
+                for code in 0..=255u8 {
+                    let width = names.effective[code as usize]
+                        .as_deref()
+                        .and_then(glyph_unicode)
+                        .map(|c| standard_width(&base, c));
+                    if let Some(width) = width {
+                        font.widths.insert(code as u32, width);
+                    }
+                }
+            }
+        }
+        font.program = program;
+        font
+    }
+
+    fn load_type3(resolver: &dyn Resolver, dict: &PdfDictionary) -> Font {
+        let names = glyph_names(resolver, dict, true);
+        let char_procs = get_dict(resolver, dict, "CharProcs").unwrap_or_default();
+        let mut procedures = HashMap::new();
+        for code in 0..=255u32 {
+            let Some(name) = names.explicit[code as usize].as_deref() else {
+                continue;
+            };
+            if let Some(PdfObject::Stream(stream)) = get(resolver, &char_procs, name) {
+                if let Some(data) = stream_data(resolver, &stream) {
+                    procedures.insert(code, Rc::new(data));
+                }
+            }
+        }
+        let font_matrix = get_matrix(resolver, dict, "FontMatrix");
+
+        let mut font = Self::empty(Encoding::Simple(vec![None; 256]));
+        font.add_glyph_names(&names.explicit);
+        let first = get_number(resolver, dict, "FirstChar").unwrap_or(0.0) as u32;
+        if let Some(widths) = get(resolver, dict, "Widths").and_then(|w| numbers(resolver, &w)) {
+            // Widths are in glyph space
+            for (i, w) in widths.into_iter().enumerate() {
+                font.widths.insert(first + i as u32, w * font_matrix[0]);
+            }
+        }
+        font.type3 = Some(Type3 {
+            matrix: font_matrix,
+            resources: get_dict(resolver, dict, "Resources"),
+            procedures,
+        });
+        font
+    }
+
+    fn load_composite(resolver: &dyn Resolver, dict: &PdfDictionary) -> Font {
+        let cmap = match get(resolver, dict, "Encoding") {
+            Some(PdfObject::Name(name)) => CidCMap::predefined(name.as_str()),
+            Some(PdfObject::Stream(stream)) => stream_data(resolver, &stream)
+                .map(|data| CidCMap::parse(&data))
+                .unwrap_or_else(|| CidCMap::identity(false)),
+            _ => CidCMap::identity(false),
+        };
+        let descendant = get(resolver, dict, "DescendantFonts")
+            .and_then(|fonts| fonts.as_array()?.0.first().map(|f| resolver.lookup(f)))
+            .and_then(|font| font.as_dict().cloned())
+            .unwrap_or_default();
+        let descriptor = get_dict(resolver, &descendant, "FontDescriptor").unwrap_or_default();
+        let program = Program::load(resolver, &descriptor);
+
+        let cid_to_gid = match get(resolver, &descendant, "CIDToGIDMap") {
+            Some(PdfObject::Stream(stream)) => stream_data(resolver, &stream).map(|data| {
+                data.chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect()
+            }),
+            _ => None,
+        };
+
+        let mut font = Self::empty(Encoding::Composite { cmap, cid_to_gid });
+        font.default_width = get_number(resolver, &descendant, "DW").unwrap_or(1000.0) / 1000.0;
+        if let Some(w) = get(resolver, &descendant, "W") {
+            for (cid, values) in cid_metrics(resolver, &w, 1) {
+                font.widths.insert(cid, values[0] / 1000.0);
+            }
+        }
+        if let Some(&[origin_y, advance]) = get(resolver, &descendant, "DW2")
+            .and_then(|d| numbers(resolver, &d))
+            .as_deref()
+        {
+            font.default_vertical = (origin_y / 1000.0, advance / 1000.0);
+        }
+        if let Some(w2) = get(resolver, &descendant, "W2") {
+            for (cid, v) in cid_metrics(resolver, &w2, 3) {
+                font.vertical_metrics
+                    .insert(cid, (v[0] / 1000.0, v[1] / 1000.0, v[2] / 1000.0));
+            }
+        }
+        font.program = program;
+        font
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        matches!(&self.encoding, Encoding::Composite { cmap, .. } if cmap.vertical)
+    }
+
+    pub fn type3(&self) -> Option<&Type3> {
+        self.type3.as_ref()
+    }
+
+    /// Split a string into character codes with their byte lengths
+    pub fn codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        match &self.encoding {
+            Encoding::Simple(_) => bytes.iter().map(|&b| (b as u32, 1)).collect(),
+            Encoding::Composite { cmap, .. } => {
+                let mut codes = Vec::new();
+                let mut i = 0;
+                while i < bytes.len() {
+                    let (code, length) = cmap.next_code(&bytes[i..]);
+                    codes.push((code, length));
+                    i += length;
+                }
+                codes
+            }
+        }
+    }
+
+    fn cid(&self, code: u32, length: usize) -> u32 {
+        match &self.encoding {
+            Encoding::Simple(_) => code,
+            Encoding::Composite { cmap, .. } => cmap.cid(code, length),
+        }
+    }
+
+    /// Text that a code stands for, if known
+    pub fn unicode(&self, code: u32, length: usize) -> Option<&str> {
+        self.unicode.get(&(code, length)).map(String::as_str)
+    }
+
+    /// Encode text as codes of this font, `None` when a character has no
+    /// code. Characters are matched one by one.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        let mut codes: HashMap<&str, (u32, usize)> = HashMap::new();
+        for (&(code, length), unicode) in &self.unicode {
+            // Prefer the lowest code when several map to the same text
+            let entry = codes.entry(unicode.as_str()).or_insert((code, length));
+            if (code, length) < *entry {
+                *entry = (code, length);
+            }
+        }
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 4];
+        for c in text.chars() {
+            let &(code, length) = codes.get(&*c.encode_utf8(&mut buffer))?;
+            bytes.extend((0..length).rev().map(|i| (code >> (8 * i)) as u8));
+        }
+        Some(bytes)
+    }
+
+    /// Horizontal advance of a code in text space units
+    pub fn width(&self, code: u32, length: usize) -> f64 {
+        let key = self.cid(code, length);
+        if let Some(&w) = self.widths.get(&key) {
+            return w;
+        }
+        if self.default_width == 0.0 {
+            if let (Encoding::Simple(glyphs), Some(Program::TrueType(sfnt))) =
+                (&self.encoding, &self.program)
+            {
+                let advance = glyphs[code as usize & 0xFF].and_then(|gid| sfnt.advance(gid));
+                if let Some(advance) = advance {
+                    return advance as f64 / sfnt.units_per_em as f64;
+                }
+            }
+        }
+        self.default_width
+    }
+
+    /// Vertical advance (negative, downwards) and the position of the
+    /// glyph origin relative to the vertical origin
+    pub fn vertical_metrics(&self, code: u32, length: usize) -> (f64, f64, f64) {
+        let cid = self.cid(code, length);
+        self.vertical_metrics.get(&cid).copied().unwrap_or_else(|| {
+            let (origin_y, advance) = self.default_vertical;
+            (advance, self.width(code, length) / 2.0, origin_y)
+        })
+    }
+
+    /// What a code draws
+    pub fn glyph(&self, code: u32, length: usize) -> Glyph {
+        if let Some(type3) = &self.type3 {
+            return match type3.procedures.get(&code) {
+                Some(procedure) => Glyph::Procedure(procedure.clone()),
+                None => Glyph::None,
+            };
+        }
+        let Some(program) = &self.program else {
+            return Glyph::None;
+        };
+        let gid = match &self.encoding {
+            Encoding::Simple(glyphs) => glyphs[code as usize & 0xFF],
+            Encoding::Composite { cmap, cid_to_gid } => {
+                let cid = cmap.cid(code, length);
+                match (cid_to_gid, program) {
+                    (Some(map), _) => map.get(cid as usize).copied(),
+                    (None, Program::Cff(cff)) if cff.is_cid => u16::try_from(cid)
+                        .ok()
+                        .and_then(|cid| cff.glyph_for_cid(cid)),
+                    (None, _) => u16::try_from(cid).ok(),
+                }
+            }
+        };
+        let Some(gid) = gid else {
+            return Glyph::None;
+        };
+        let outline = self
+            .outlines
+            .borrow_mut()
+            .entry(gid)
+            .or_insert_with(|| program.outline(gid).filter(|p| !p.is_empty()).map(Rc::new))
+            .clone();
+        match outline {
+            Some(path) => Glyph::Outline(path),
+            None => Glyph::None,
+        }
+    }
+}
+
+/// Glyph names of the codes of a simple font
+struct GlyphNames {
+    /// Names from the font's `Encoding` entry
+    explicit: Vec<Option<String>>,
+    /// Explicit names, falling back to StandardEncoding
+    effective: Vec<Option<String>>,
+}
+
+fn glyph_names(resolver: &dyn Resolver, dict: &PdfDictionary, symbolic: bool) -> GlyphNames {
+    let mut explicit: Vec<Option<String>> = vec![None; 256];
+    let (base, differences) = match get(resolver, dict, "Encoding") {
+        Some(PdfObject::Name(name)) => (BaseEncoding::from_name(name.as_str()), None),
+        Some(PdfObject::Dictionary(encoding)) => (
+            get_name(resolver, &encoding, "BaseEncoding")
+                .and_then(|name| BaseEncoding::from_name(&name)),
+            get(resolver, &encoding, "Differences"),
+        ),
+        _ => (None, None),
+    };
+    if let Some(base) = base {
+        for code in 0..=255u8 {
+            explicit[code as usize] = base.glyph_name(code).map(str::to_string);
+        }
+    }
+    if let Some(PdfObject::Array(items)) = differences {
+        let mut code = 0usize;
+        for item in &items.0 {
+            match resolver.lookup(item) {
+                PdfObject::Integer(n) => code = n.max(0) as usize,
+                PdfObject::Name(name) => {
+                    if let Some(slot) = explicit.get_mut(code) {
+                        *slot = Some(name.as_str().to_string());
+                    }
+                    code += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    let effective = (0..=255u8)
+        .map(|code| {
+            explicit[code as usize].clone().or_else(|| {
+                (!symbolic)
+                    .then(|| BaseEncoding::Standard.glyph_name(code).map(str::to_string))
+                    .flatten()
+            })
+        })
+        .collect();
+    GlyphNames {
+        explicit,
+        effective,
+    }
+}
+
+/// Glyph of a code in a simple TrueType font (ISO 32000-1 §9.6.6.4)
+fn truetype_glyph(sfnt: &Sfnt, code: u8, name: Option<&str>, flags: u32) -> Option<u16> {
+    let name = name.or_else(|| {
+        (flags & SYMBOLIC == 0)
+            .then(|| BaseEncoding::WinAnsi.glyph_name(code))
+            .flatten()
+    });
+    if let Some(name) = name {
+        if let Some(gid) =
+            glyph_unicode(name).and_then(|c| sfnt.lookup(CMAP_WINDOWS_UNICODE, c as u32))
+        {
+            return Some(gid);
+        }
+        if let Some(gid) = sfnt.glyph_by_name(name) {
+            return Some(gid);
+        }
+    }
+    if sfnt.has_cmap(CMAP_WINDOWS_SYMBOL) {
+        let code = code as u32;
+        return [code, 0xF000 + code, 0xF100 + code, 0xF200 + code]
+            .into_iter()
+            .find_map(|c| sfnt.lookup(CMAP_WINDOWS_SYMBOL, c));
+    }
+    sfnt.lookup(CMAP_MAC_ROMAN, code as u32)
+        .or_else(|| sfnt.lookup(CMAP_WINDOWS_UNICODE, code as u32))
+        .or_else(|| {
+            // Subsets without a cmap are indexed by code
+            (!sfnt.has_cmap(CMAP_WINDOWS_UNICODE) && !sfnt.has_cmap(CMAP_MAC_ROMAN))
+                .then_some(code as u16)
+        })
+}
+
+/// Entries of a `W` or `W2` array with `n` numbers per CID
+fn cid_metrics(resolver: &dyn Resolver, array: &PdfObject, n: usize) -> Vec<(u32, Vec<f64>)> {
+    let Some(items) = array.as_array() else {
+        return Vec::new();
+    };
+    let items: Vec<PdfObject> = items.0.iter().map(|item| resolver.lookup(item)).collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i + 1 < items.len() {
+        let Some(first) = items[i].as_real() else {
+            break;
+        };
+        let first = first.max(0.0) as u32;
+        match &items[i + 1] {
+            PdfObject::Array(values) => {
+                let values =
+                    numbers(resolver, &PdfObject::Array(values.clone())).unwrap_or_default();
+                for (k, chunk) in values.chunks_exact(n).enumerate() {
+                    out.push((first + k as u32, chunk.to_vec()));
+                }
+                i += 2;
+            }
+            last => {
+                let (Some(last), Some(values)) = (
+                    last.as_real(),
+                    items
+                        .get(i + 2..i + 2 + n)
+                        .and_then(|v| v.iter().map(PdfObject::as_real).collect::<Option<Vec<_>>>()),
+                ) else {
+                    break;
+                };
+                for cid in first..=(last.max(0.0) as u32).min(first + 0xFFFF) {
+                    out.push((cid, values.clone()));
+                }
+                i += 2 + n;
+            }
+        }
+    }
+    out
+}
+
+/// Width of a character in a standard 14 font, by family of `base_font`
+fn standard_width(base_font: &str, c: char) -> f64 {
+    use crate::text::Font as Standard;
+    let bold = base_font.contains("Bold");
+    let family = if base_font.starts_with("Courier") {
+        if bold {
+            Standard::CourierBold
+        } else {
+            Standard::Courier
+        }
+    } else if base_font.starts_with("Times") {
+        if bold {
+            Standard::TimesBold
+        } else {
+            Standard::TimesRoman
+        }
+    } else if bold {
+        Standard::HelveticaBold
+    } else {
+        Standard::Helvetica
+    };
+    crate::text::metrics::measure_char(c, family, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::objects::{PdfArray, PdfName, PdfStream};
+    use crate::parser::resolve::test_support::MemoryResolver;
+
+    fn name(s: &str) -> PdfObject {
+        PdfObject::Name(PdfName::new(s.to_string()))
+    }
+
+    fn font_with_file(
+        subtype: &str,
+        file_key: &str,
+        data: Vec<u8>,
+    ) -> (MemoryResolver, PdfDictionary) {
+        let mut resolver = MemoryResolver::default();
+        resolver.objects.insert(
+            1,
+            PdfObject::Stream(PdfStream {
+                dict: PdfDictionary::new(),
+                data,
+            }),
+        );
+        let mut descriptor = PdfDictionary::new();
+        descriptor.insert(file_key.to_string(), PdfObject::Reference(1, 0));
+        descriptor.insert("Flags".to_string(), PdfObject::Integer(32));
+        let mut dict = PdfDictionary::new();
+        dict.insert("Subtype".to_string(), name(subtype));
+        dict.insert(
+            "FontDescriptor".to_string(),
+            PdfObject::Dictionary(descriptor),
+        );
+        dict.insert("FirstChar".to_string(), PdfObject::Integer(65));
+        dict.insert(
+            "Widths".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(600)])),
+        );
+        (resolver, dict)
+    }
+
+    #[test]
+    fn test_simple_truetype_font() {
+        let (resolver, dict) = font_with_file("TrueType", "FontFile2", sfnt::tests::test_font());
+        let font = Font::load(&resolver, &dict);
+        assert_eq!(font.codes(b"AB"), vec![(65, 1), (66, 1)]);
+        assert_eq!(font.width(65, 1), 0.6);
+        match font.glyph(65, 1) {
+            Glyph::Outline(path) => {
+                let points = &path.flatten(0.01)[0].points;
+                assert_eq!(points[1].x, 0.5);
+                assert_eq!(points[1].y, 1.0);
+            }
+            other => panic!("expected an outline, got {other:?}"),
+        }
+        assert!(matches!(font.glyph(66, 1), Glyph::None));
+    }
+
+    #[test]
+    fn test_simple_cff_font() {
+        let (resolver, dict) = font_with_file("Type1", "FontFile3", cff::tests::test_font());
+        let font = Font::load(&resolver, &dict);
+        match font.glyph(65, 1) {
+            Glyph::Outline(path) => {
+                let points = &path.flatten(0.01)[0].points;
+                assert_eq!(points[0].x, 0.1);
+                assert_eq!(points[2].y, 0.5);
+            }
+            other => panic!("expected an outline, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_composite_font_widths() {
+        let (resolver, mut descendant) =
+            font_with_file("CIDFontType2", "FontFile2", sfnt::tests::test_font());
+        descendant.insert(
+            "W".to_string(),
+            PdfObject::Array(PdfArray(vec![
+                PdfObject::Integer(1),
+                PdfObject::Array(PdfArray(vec![
+                    PdfObject::Integer(250),
+                    PdfObject::Integer(750),
+                ])),
+                PdfObject::Integer(10),
+                PdfObject::Integer(12),
+                PdfObject::Integer(400),
+            ])),
+        );
+        let mut dict = PdfDictionary::new();
+        dict.insert("Subtype".to_string(), name("Type0"));
+        dict.insert("Encoding".to_string(), name("Identity-H"));
+        dict.insert(
+            "DescendantFonts".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Dictionary(descendant)])),
+        );
+        let font = Font::load(&resolver, &dict);
+        assert!(!font.is_vertical());
+        assert_eq!(font.codes(b"\x00\x01\x00\x0B"), vec![(1, 2), (11, 2)]);
+        assert_eq!(font.width(1, 2), 0.25);
+        assert_eq!(font.width(2, 2), 0.75);
+        assert_eq!(font.width(11, 2), 0.4);
+        assert_eq!(font.width(20, 2), 1.0);
+        assert!(matches!(font.glyph(1, 2), Glyph::Outline(_)));
+    }
+
+    #[test]
+    fn test_type3_font() {
+        let mut resolver = MemoryResolver::default();
+        resolver.objects.insert(
+            1,
+            PdfObject::Stream(PdfStream {
+                dict: PdfDictionary::new(),
+                data: b"1000 0 d0 0 0 1000 1000 re f".to_vec(),
+            }),
+        );
+        let mut procs = PdfDictionary::new();
+        procs.insert("square".to_string(), PdfObject::Reference(1, 0));
+        let mut encoding = PdfDictionary::new();
+        encoding.insert(
+            "Differences".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(97), name("square")])),
+        );
+        let mut dict = PdfDictionary::new();
+        dict.insert("Subtype".to_string(), name("Type3"));
+        dict.insert("CharProcs".to_string(), PdfObject::Dictionary(procs));
+        dict.insert("Encoding".to_string(), PdfObject::Dictionary(encoding));
+        dict.insert(
+            "FontMatrix".to_string(),
+            PdfObject::Array(PdfArray(
+                [0.001, 0.0, 0.0, 0.001, 0.0, 0.0]
+                    .map(PdfObject::Real)
+                    .to_vec(),
+            )),
+        );
+        dict.insert("FirstChar".to_string(), PdfObject::Integer(97));
+        dict.insert(
+            "Widths".to_string(),
+            PdfObject::Array(PdfArray(vec![PdfObject::Integer(1000)])),
+        );
+        let font = Font::load(&resolver, &dict);
+        assert_eq!(font.width(97, 1), 1.0);
+        assert!(matches!(font.glyph(97, 1), Glyph::Procedure(_)));
+        assert!(matches!(font.glyph(98, 1), Glyph::None));
+        assert_eq!(font.type3().unwrap().matrix[0], 0.001);
+    }
+
+    #[test]
+    fn test_standard_font_widths() {
+        let mut dict = PdfDictionary::new();
+        dict.insert("Subtype".to_string(), name("Type1"));
+        dict.insert("BaseFont".to_string(), name("Helvetica"));
+        let font = Font::load(&MemoryResolver::default(), &dict);
+        assert_eq!(font.width(b'A' as u32, 1), 0.667);
+        assert!(matches!(font.glyph(b'A' as u32, 1), Glyph::None));
+        assert_eq!(font.unicode(b'A' as u32, 1), Some("A"));
+        assert_eq!(font.encode("Hi!"), Some(b"Hi!".to_vec()));
+        assert_eq!(font.encode("\u{4E2D}"), None);
+    }
+
+    #[test]
+    fn test_to_unicode() {
+        let mut resolver = MemoryResolver::default();
+        resolver.objects.insert(
+            1,
+            PdfObject::Stream(PdfStream {
+                dict: PdfDictionary::new(),
+                data: b"begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n\
+                    beginbfchar\n<0003> <0020>\nendbfchar\n\
+                    beginbfrange\n<0010> <0012> <0041>\nendbfrange\n"
+                    .to_vec(),
+            }),
+        );
+        let mut dict = PdfDictionary::new();
+        dict.insert("Subtype".to_string(), name("Type0"));
+        dict.insert("Encoding".to_string(), name("Identity-H"));
+        dict.insert("ToUnicode".to_string(), PdfObject::Reference(1, 0));
+        let font = Font::load(&resolver, &dict);
+        assert_eq!(font.unicode(0x11, 2), Some("B"));
+        assert_eq!(font.unicode(0x11, 1), None);
+        assert_eq!(
+            font.encode("A C"),
+            Some(b"\x00\x10\x00\x03\x00\x12".to_vec())
+        );
+        assert_eq!(font.encode("D"), None);
+    }
+}
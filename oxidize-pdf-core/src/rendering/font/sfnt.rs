@@ -0,0 +1,597 @@
+//! Reader for TrueType and OpenType font files
+//!
+//! Embedded fonts are frequently subsets missing tables that a complete
+//! font must have, so every table except `head` is optional here.
+
+use super::encoding::mac_glyph_name;
+use crate::geometry::Point;
+use crate::rendering::path::Path;
+use std::collections::HashMap;
+
+/// Nesting limit for composite glyphs
+const MAX_COMPOSITE_DEPTH: usize = 8;
+
+/// A parsed sfnt font
+#[derive(Debug, Clone)]
+pub(crate) struct Sfnt {
+    data: Vec<u8>,
+    tables: HashMap<[u8; 4], (usize, usize)>,
+    pub units_per_em: u16,
+    long_loca: bool,
+    glyph_count: usize,
+    advance_count: usize,
+    /// Glyph names from a version 2 `post` table
+    names: Option<HashMap<String, u16>>,
+}
+
+/// Encoding subtables a font may provide, by (platform, encoding)
+pub(crate) const CMAP_WINDOWS_UNICODE: (u16, u16) = (3, 1);
+pub(crate) const CMAP_WINDOWS_SYMBOL: (u16, u16) = (3, 0);
+pub(crate) const CMAP_MAC_ROMAN: (u16, u16) = (1, 0);
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|v| v as i16)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+impl Sfnt {
+    /// Parse a font file; collections use their first font
+    pub fn parse(data: Vec<u8>) -> Option<Sfnt> {
+        let mut start = 0;
+        if data.get(..4)? == b"ttcf" {
+            start = u32_at(&data, 12)? as usize;
+        }
+        let count = u16_at(&data, start + 4)? as usize;
+        let mut tables = HashMap::new();
+        for i in 0..count {
+            let record = start + 12 + i * 16;
+            let tag: [u8; 4] = data.get(record..record + 4)?.try_into().ok()?;
+            let offset = u32_at(&data, record + 8)? as usize;
+            let length = u32_at(&data, record + 12)? as usize;
+            if offset <= data.len() {
+                tables.insert(tag, (offset, length.min(data.len() - offset)));
+            }
+        }
+
+        let mut font = Sfnt {
+            data,
+            tables,
+            units_per_em: 1000,
+            long_loca: false,
+            glyph_count: 0,
+            advance_count: 0,
+            names: None,
+        };
+        if let Some(head) = font.table(b"head") {
+            let units_per_em = u16_at(head, 18).filter(|&u| u >= 16).unwrap_or(1000);
+            let long_loca = i16_at(head, 50) == Some(1);
+            font.units_per_em = units_per_em;
+            font.long_loca = long_loca;
+        } else if font.table(b"CFF ").is_none() {
+            return None;
+        }
+        font.glyph_count = font
+            .table(b"maxp")
+            .and_then(|maxp| u16_at(maxp, 4))
+            .map_or(usize::MAX, |n| n as usize);
+        font.advance_count = font
+            .table(b"hhea")
+            .and_then(|hhea| u16_at(hhea, 34))
+            .unwrap_or(0) as usize;
+        font.names = font.post_names();
+        Some(font)
+    }
+
+    pub fn table(&self, tag: &[u8; 4]) -> Option<&[u8]> {
+        let &(offset, length) = self.tables.get(tag)?;
+        self.data.get(offset..offset + length)
+    }
+
+    /// Whether glyphs are PostScript outlines in a `CFF ` table
+    pub fn cff_table(&self) -> Option<&[u8]> {
+        self.table(b"CFF ")
+    }
+
+    pub fn has_cmap(&self, subtable: (u16, u16)) -> bool {
+        self.cmap_subtable(subtable).is_some()
+    }
+
+    fn cmap_subtable(&self, (platform, encoding): (u16, u16)) -> Option<&[u8]> {
+        let cmap = self.table(b"cmap")?;
+        let count = u16_at(cmap, 2)? as usize;
+        let mut best: Option<(u16, &[u8])> = None;
+        for i in 0..count {
+            let record = 4 + i * 8;
+            if u16_at(cmap, record)? != platform || u16_at(cmap, record + 2)? != encoding {
+                continue;
+            }
+            let offset = u32_at(cmap, record + 4)? as usize;
+            let subtable = cmap.get(offset..)?;
+            let format = u16_at(subtable, 0)?;
+            // Prefer the full-range format when a font has both
+            if matches!(format, 0 | 4 | 6 | 12)
+                && best.map_or(true, |(f, _)| format == 12 && f != 12)
+            {
+                best = Some((format, subtable));
+            }
+        }
+        best.map(|(_, subtable)| subtable)
+    }
+
+    /// Glyph for a character code in the given cmap subtable
+    pub fn lookup(&self, subtable: (u16, u16), code: u32) -> Option<u16> {
+        let table = self.cmap_subtable(subtable)?;
+        let gid = match u16_at(table, 0)? {
+            0 => *table.get(6 + code as usize).filter(|_| code < 256)? as u16,
+            4 => {
+                let segments = u16_at(table, 6)? as usize / 2;
+                if code > 0xFFFF {
+                    return None;
+                }
+                let code = code as u16;
+                let ends = 14;
+                let starts = ends + segments * 2 + 2;
+                let deltas = starts + segments * 2;
+                let range_offsets = deltas + segments * 2;
+                let segment = (0..segments)
+                    .find(|&i| u16_at(table, ends + i * 2).map_or(false, |end| end >= code))?;
+                let start = u16_at(table, starts + segment * 2)?;
+                if start > code {
+                    return None;
+                }
+                let delta = u16_at(table, deltas + segment * 2)?;
+                let range_offset = u16_at(table, range_offsets + segment * 2)? as usize;
+                if range_offset == 0 {
+                    code.wrapping_add(delta)
+                } else {
+                    let address =
+                        range_offsets + segment * 2 + range_offset + (code - start) as usize * 2;
+                    match u16_at(table, address)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                }
+            }
+            6 => {
+                let first = u16_at(table, 6)? as u32;
+                let count = u16_at(table, 8)? as u32;
+                if code < first || code >= first + count {
+                    return None;
+                }
+                u16_at(table, 10 + (code - first) as usize * 2)?
+            }
+            12 => {
+                let groups = u32_at(table, 12)? as usize;
+                (0..groups).find_map(|i| {
+                    let group = 16 + i * 12;
+                    let start = u32_at(table, group)?;
+                    let end = u32_at(table, group + 4)?;
+                    (start <= code && code <= end)
+                        .then(|| u32_at(table, group + 8).map(|g| (g + code - start) as u16))
+                        .flatten()
+                })?
+            }
+            _ => return None,
+        };
+        (gid != 0).then_some(gid)
+    }
+
+    /// Glyph with a `post` table name
+    pub fn glyph_by_name(&self, name: &str) -> Option<u16> {
+        self.names.as_ref()?.get(name).copied()
+    }
+
+    fn post_names(&self) -> Option<HashMap<String, u16>> {
+        let post = self.table(b"post")?;
+        if u32_at(post, 0)? != 0x0002_0000 {
+            return None;
+        }
+        let count = u16_at(post, 32)? as usize;
+        let mut custom = Vec::new();
+        let mut offset = 34 + count * 2;
+        while let Some(&length) = post.get(offset) {
+            let name = post.get(offset + 1..offset + 1 + length as usize)?;
+            custom.push(String::from_utf8_lossy(name).into_owned());
+            offset += 1 + length as usize;
+        }
+        let mut names = HashMap::new();
+        for gid in 0..count {
+            let index = u16_at(post, 34 + gid * 2)? as usize;
+            let name = if index < 258 {
+                mac_glyph_name(index).map(str::to_string)
+            } else {
+                custom.get(index - 258).cloned()
+            };
+            if let Some(name) = name {
+                names.entry(name).or_insert(gid as u16);
+            }
+        }
+        Some(names)
+    }
+
+    /// Advance width of a glyph in font units
+    pub fn advance(&self, gid: u16) -> Option<u16> {
+        let hmtx = self.table(b"hmtx")?;
+        if self.advance_count == 0 {
+            return None;
+        }
+        let index = (gid as usize).min(self.advance_count - 1);
+        u16_at(hmtx, index * 4)
+    }
+
+    /// Outline of a `glyf` glyph in font units
+    pub fn glyph_outline(&self, gid: u16) -> Option<Path> {
+        let mut path = Path::new();
+        self.append_glyph(gid, &[1.0, 0.0, 0.0, 1.0, 0.0, 0.0], &mut path, 0)?;
+        Some(path)
+    }
+
+    fn glyph_data(&self, gid: u16) -> Option<&[u8]> {
+        if gid as usize >= self.glyph_count {
+            return None;
+        }
+        let loca = self.table(b"loca")?;
+        let glyf = self.table(b"glyf")?;
+        let (start, end) = if self.long_loca {
+            (
+                u32_at(loca, gid as usize * 4)? as usize,
+                u32_at(loca, gid as usize * 4 + 4)? as usize,
+            )
+        } else {
+            (
+                u16_at(loca, gid as usize * 2)? as usize * 2,
+                u16_at(loca, gid as usize * 2 + 2)? as usize * 2,
+            )
+        };
+        if end <= start {
+            return Some(&[]);
+        }
+        glyf.get(start..end)
+    }
+
+    fn append_glyph(&self, gid: u16, m: &[f64; 6], path: &mut Path, depth: usize) -> Option<()> {
+        if depth > MAX_COMPOSITE_DEPTH {
+            return None;
+        }
+        let data = self.glyph_data(gid)?;
+        if data.len() < 10 {
+            return Some(());
+        }
+        let contours = i16_at(data, 0)?;
+        let map =
+            |x: f64, y: f64| Point::new(m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5]);
+
+        if contours >= 0 {
+            let contours = contours as usize;
+            let ends: Vec<usize> = (0..contours)
+                .map(|i| u16_at(data, 10 + i * 2).map(|e| e as usize))
+                .collect::<Option<_>>()?;
+            let point_count = ends.last().map_or(0, |&e| e + 1);
+            let instructions = u16_at(data, 10 + contours * 2)? as usize;
+            let mut offset = 12 + contours * 2 + instructions;
+
+            let mut flags = Vec::with_capacity(point_count);
+            while flags.len() < point_count {
+                let flag = *data.get(offset)?;
+                offset += 1;
+                flags.push(flag);
+                if flag & 8 != 0 {
+                    let repeat = *data.get(offset)?;
+                    offset += 1;
+                    for _ in 0..repeat {
+                        flags.push(flag);
+                    }
+                }
+            }
+            flags.truncate(point_count);
+
+            let mut read_coordinates = |short: u8, same: u8| -> Option<Vec<f64>> {
+                let mut value = 0i32;
+                let mut values = Vec::with_capacity(point_count);
+                for &flag in &flags {
+                    if flag & short != 0 {
+                        let delta = *data.get(offset)? as i32;
+                        offset += 1;
+                        value += if flag & same != 0 { delta } else { -delta };
+                    } else if flag & same == 0 {
+                        value += i16_at(data, offset)? as i32;
+                        offset += 2;
+                    }
+                    values.push(value as f64);
+                }
+                Some(values)
+            };
+            let xs = read_coordinates(2, 16)?;
+            let ys = read_coordinates(4, 32)?;
+
+            let mut start = 0;
+            for &end in &ends {
+                if end < start || end >= point_count {
+                    return None;
+                }
+                let points: Vec<(Point, bool)> = (start..=end)
+                    .map(|i| (map(xs[i], ys[i]), flags[i] & 1 != 0))
+                    .collect();
+                append_contour(&points, path);
+                start = end + 1;
+            }
+        } else {
+            // Composite glyph (ARG_1_AND_2_ARE_WORDS = 1, ARGS_ARE_XY_VALUES = 2,
+            // WE_HAVE_A_SCALE = 8, MORE_COMPONENTS = 0x20, X_AND_Y_SCALE = 0x40,
+            // TWO_BY_TWO = 0x80)
+            let mut offset = 10;
+            loop {
+                let flags = u16_at(data, offset)?;
+                let component = u16_at(data, offset + 2)?;
+                offset += 4;
+                let (dx, dy) = if flags & 1 != 0 {
+                    let v = (i16_at(data, offset)?, i16_at(data, offset + 2)?);
+                    offset += 4;
+                    (v.0 as f64, v.1 as f64)
+                } else {
+                    let v = (*data.get(offset)? as i8, *data.get(offset + 1)? as i8);
+                    offset += 2;
+                    (v.0 as f64, v.1 as f64)
+                };
+                let f2dot14 = |offset: usize| i16_at(data, offset).map(|v| v as f64 / 16384.0);
+                let (a, b, c, d) = if flags & 8 != 0 {
+                    let s = f2dot14(offset)?;
+                    offset += 2;
+                    (s, 0.0, 0.0, s)
+                } else if flags & 0x40 != 0 {
+                    let v = (f2dot14(offset)?, f2dot14(offset + 2)?);
+                    offset += 4;
+                    (v.0, 0.0, 0.0, v.1)
+                } else if flags & 0x80 != 0 {
+                    let v = (
+                        f2dot14(offset)?,
+                        f2dot14(offset + 2)?,
+                        f2dot14(offset + 4)?,
+                        f2dot14(offset + 6)?,
+                    );
+                    offset += 8;
+                    v
+                } else {
+                    (1.0, 0.0, 0.0, 1.0)
+                };
+                // Point-matched placement (arguments are point numbers) is
+                // treated as no offset
+                let (dx, dy) = if flags & 2 != 0 { (dx, dy) } else { (0.0, 0.0) };
+                let component_matrix = [
+                    m[0] * a + m[2] * b,
+                    m[1] * a + m[3] * b,
+                    m[0] * c + m[2] * d,
+                    m[1] * c + m[3] * d,
+                    m[0] * dx + m[2] * dy + m[4],
+                    m[1] * dx + m[3] * dy + m[5],
+                ];
+                self.append_glyph(component, &component_matrix, path, depth + 1)?;
+                if flags & 0x20 == 0 {
+                    break;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Append one quadratic contour, inserting the implied on-curve points
+/// between consecutive off-curve points
+fn append_contour(points: &[(Point, bool)], path: &mut Path) {
+    if points.is_empty() {
+        return;
+    }
+    let midpoint = |a: Point, b: Point| Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+    let n = points.len();
+    // Start at an on-curve point, or the midpoint of the first two
+    let first_on = points.iter().position(|&(_, on)| on);
+    let (start, first) = match first_on {
+        Some(i) => (points[i].0, i),
+        None => (midpoint(points[0].0, points[1 % n].0), 0),
+    };
+    path.move_to(start);
+    let mut control: Option<Point> = None;
+    for k in 1..=n {
+        let (p, on) = points[(first + k) % n];
+        let is_start = first_on.is_none() && k == n;
+        match (on, control) {
+            // The closing line back to the start is implied by close
+            (true, None) if k == n => {}
+            (true, None) => path.line_to(p),
+            (true, Some(c)) => {
+                path.quad_to(c, p);
+                control = None;
+            }
+            (false, None) => control = Some(p),
+            (false, Some(c)) => {
+                path.quad_to(c, midpoint(c, p));
+                control = Some(p);
+            }
+        }
+        if is_start {
+            break;
+        }
+    }
+    if let Some(c) = control {
+        path.quad_to(c, start);
+    }
+    path.close();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A minimal TrueType font with a square `.notdef` and a triangle
+    /// mapped from 'A' in a format 4 (3,1) cmap
+    pub(crate) fn test_font() -> Vec<u8> {
+        let square: Vec<u8> = [
+            &1i16.to_be_bytes()[..],
+            &[0, 0, 0, 0, 3, 0xE8, 3, 0xE8],
+            &3u16.to_be_bytes(),
+            &0u16.to_be_bytes(),
+            // Flags: on-curve, short x and y deltas, positive as required
+            &[
+                1 | 2 | 4 | 16 | 32,
+                1 | 2 | 16 | 32,
+                1 | 4 | 16 | 32,
+                1 | 2 | 32,
+            ],
+            &[0, 200, 200],
+            &[0, 200],
+        ]
+        .concat();
+        let triangle: Vec<u8> = [
+            &1i16.to_be_bytes()[..],
+            &[0, 0, 0, 0, 3, 0xE8, 3, 0xE8],
+            &2u16.to_be_bytes(),
+            &0u16.to_be_bytes(),
+            // (0,0) (500,1000) (1000,0), long coordinates
+            &[1, 1, 1],
+            &0i16.to_be_bytes(),
+            &500i16.to_be_bytes(),
+            &500i16.to_be_bytes(),
+            &0i16.to_be_bytes(),
+            &1000i16.to_be_bytes(),
+            &(-1000i16).to_be_bytes(),
+        ]
+        .concat();
+        let mut glyf = square.clone();
+        if glyf.len() % 2 == 1 {
+            glyf.push(0);
+        }
+        let second = glyf.len();
+        glyf.extend(&triangle);
+        if glyf.len() % 2 == 1 {
+            glyf.push(0);
+        }
+        let loca: Vec<u8> = [0, second / 2, glyf.len() / 2]
+            .iter()
+            .flat_map(|&o| (o as u16).to_be_bytes())
+            .collect();
+
+        let mut head = vec![0u8; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut maxp = vec![0u8; 6];
+        maxp[4..6].copy_from_slice(&2u16.to_be_bytes());
+        let mut hhea = vec![0u8; 36];
+        hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+        let hmtx: Vec<u8> = [500u16, 0, 1000, 0]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+
+        // cmap with one format 4 subtable: segment 'A'..'A' delta 1-65, end segment 0xFFFF
+        let subtable: Vec<u8> = [
+            4u16,
+            32,
+            0,
+            4,
+            4,
+            1,
+            0, // format, length, language, segCountX2, search fields
+            65,
+            0xFFFF,
+            0,
+            65,
+            0xFFFF, // ends, pad, starts
+            (1u16).wrapping_sub(65),
+            1,
+            0,
+            0, // deltas, range offsets
+        ]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+        let mut cmap: Vec<u8> = [0u16, 1, 3, 1]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        cmap.extend(12u32.to_be_bytes());
+        cmap.extend(subtable);
+
+        let tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+        let mut font = vec![0, 1, 0, 0];
+        font.extend((tables.len() as u16).to_be_bytes());
+        font.extend([0u8; 6]);
+        let mut offset = 12 + tables.len() * 16;
+        let mut body = Vec::new();
+        for (tag, data) in &tables {
+            font.extend(*tag);
+            font.extend([0u8; 4]);
+            font.extend((offset as u32).to_be_bytes());
+            font.extend((data.len() as u32).to_be_bytes());
+            let mut padded = data.clone();
+            padded.resize((data.len() + 3) / 4 * 4, 0);
+            offset += padded.len();
+            body.extend(padded);
+        }
+        font.extend(body);
+        font
+    }
+
+    #[test]
+    fn test_parse_and_lookup() {
+        let font = Sfnt::parse(test_font()).unwrap();
+        assert_eq!(font.units_per_em, 1000);
+        assert_eq!(font.lookup(CMAP_WINDOWS_UNICODE, 'A' as u32), Some(1));
+        assert_eq!(font.lookup(CMAP_WINDOWS_UNICODE, 'B' as u32), None);
+        assert_eq!(font.advance(1), Some(1000));
+        assert_eq!(font.advance(5), Some(1000));
+    }
+
+    #[test]
+    fn test_glyph_outlines() {
+        let font = Sfnt::parse(test_font()).unwrap();
+        let square = font.glyph_outline(0).unwrap().flatten(1.0);
+        assert_eq!(square.len(), 1);
+        assert_eq!(
+            square[0].points,
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(200.0, 0.0),
+                Point::new(200.0, 200.0),
+                Point::new(0.0, 200.0)
+            ]
+        );
+        let triangle = font.glyph_outline(1).unwrap().flatten(1.0);
+        assert_eq!(triangle[0].points[1], Point::new(500.0, 1000.0));
+        assert!(font.glyph_outline(2).is_none());
+    }
+
+    #[test]
+    fn test_implied_on_curve_points() {
+        // An all off-curve contour is a smooth closed curve
+        let points = [
+            (Point::new(0.0, 0.0), false),
+            (Point::new(100.0, 0.0), false),
+            (Point::new(100.0, 100.0), false),
+            (Point::new(0.0, 100.0), false),
+        ];
+        let mut path = Path::new();
+        append_contour(&points, &mut path);
+        let polylines = path.flatten(0.1);
+        assert_eq!(polylines.len(), 1);
+        assert_eq!(polylines[0].points[0], Point::new(50.0, 0.0));
+        assert!(polylines[0].points.len() > 8);
+    }
+}
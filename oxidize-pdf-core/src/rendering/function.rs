@@ -0,0 +1,698 @@
+//! Evaluation of PDF functions (ISO 32000-1 §7.10)
+//!
+//! Used by tint transforms of Separation and DeviceN color spaces, by
+//! shadings and by soft mask transfer functions.
+
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{get, get_number, get_numbers, stream_data, Resolver};
+
+/// Nesting limit for stitching functions
+const MAX_DEPTH: usize = 8;
+
+/// A parsed function, mapping `m` inputs to `n` outputs
+#[derive(Debug, Clone)]
+pub(crate) enum Function {
+    /// Type 0: interpolated samples
+    Sampled {
+        domain: Vec<(f64, f64)>,
+        range: Vec<(f64, f64)>,
+        size: Vec<usize>,
+        encode: Vec<(f64, f64)>,
+        decode: Vec<(f64, f64)>,
+        /// Samples normalized to 0..=1, `outputs` values per grid point
+        samples: Vec<f64>,
+    },
+    /// Type 2: exponential interpolation between C0 and C1
+    Exponential {
+        domain: (f64, f64),
+        c0: Vec<f64>,
+        c1: Vec<f64>,
+        exponent: f64,
+    },
+    /// Type 3: one-input functions stitched along subdomains
+    Stitching {
+        domain: (f64, f64),
+        functions: Vec<Function>,
+        bounds: Vec<f64>,
+        encode: Vec<(f64, f64)>,
+    },
+    /// Type 4: PostScript calculator program
+    PostScript {
+        domain: Vec<(f64, f64)>,
+        range: Vec<(f64, f64)>,
+        program: Vec<PsOp>,
+    },
+    /// An array of one-output functions, one per output component
+    Array(Vec<Function>),
+}
+
+impl Function {
+    /// Parse a function dictionary, stream or array of functions
+    pub fn parse(resolver: &dyn Resolver, object: &PdfObject) -> Option<Function> {
+        Self::parse_at_depth(resolver, object, 0)
+    }
+
+    fn parse_at_depth(
+        resolver: &dyn Resolver,
+        object: &PdfObject,
+        depth: usize,
+    ) -> Option<Function> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let object = resolver.lookup(object);
+        if let PdfObject::Array(items) = &object {
+            let functions = items
+                .0
+                .iter()
+                .map(|item| Self::parse_at_depth(resolver, item, depth + 1))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(Function::Array(functions));
+        }
+
+        let dict = object.as_dict()?;
+        let domain = pairs(get_numbers(resolver, dict, "Domain").unwrap_or_else(|| vec![0.0, 1.0]));
+        let range = pairs(get_numbers(resolver, dict, "Range").unwrap_or_default());
+
+        match get_number(resolver, dict, "FunctionType")? as i64 {
+            0 => {
+                let stream = object.as_stream()?;
+                let size: Vec<usize> = get_numbers(resolver, dict, "Size")?
+                    .iter()
+                    .map(|&s| s.max(1.0) as usize)
+                    .collect();
+                if size.len() != domain.len() || range.is_empty() {
+                    return None;
+                }
+                let bits = get_number(resolver, dict, "BitsPerSample")? as u32;
+                let encode = get_numbers(resolver, dict, "Encode")
+                    .map(pairs)
+                    .unwrap_or_else(|| size.iter().map(|&s| (0.0, (s - 1) as f64)).collect());
+                let decode = get_numbers(resolver, dict, "Decode")
+                    .map(pairs)
+                    .unwrap_or_else(|| range.clone());
+                let data = stream_data(resolver, stream)?;
+                let count = size.iter().product::<usize>().checked_mul(range.len())?;
+                let samples = read_samples(&data, bits, count)?;
+                Some(Function::Sampled {
+                    domain,
+                    range,
+                    size,
+                    encode,
+                    decode,
+                    samples,
+                })
+            }
+            2 => {
+                let c0 = get_numbers(resolver, dict, "C0").unwrap_or_else(|| vec![0.0]);
+                let c1 = get_numbers(resolver, dict, "C1").unwrap_or_else(|| vec![1.0]);
+                if c0.len() != c1.len() {
+                    return None;
+                }
+                Some(Function::Exponential {
+                    domain: *domain.first()?,
+                    c0,
+                    c1,
+                    exponent: get_number(resolver, dict, "N")?,
+                })
+            }
+            3 => {
+                let functions = match get(resolver, dict, "Functions")? {
+                    PdfObject::Array(items) => items
+                        .0
+                        .iter()
+                        .map(|item| Self::parse_at_depth(resolver, item, depth + 1))
+                        .collect::<Option<Vec<_>>>()?,
+                    _ => return None,
+                };
+                let bounds = get_numbers(resolver, dict, "Bounds").unwrap_or_default();
+                let encode = pairs(get_numbers(resolver, dict, "Encode").unwrap_or_default());
+                if functions.is_empty()
+                    || bounds.len() + 1 != functions.len()
+                    || encode.len() != functions.len()
+                {
+                    return None;
+                }
+                Some(Function::Stitching {
+                    domain: *domain.first()?,
+                    functions,
+                    bounds,
+                    encode,
+                })
+            }
+            4 => {
+                let data = stream_data(resolver, object.as_stream()?)?;
+                Some(Function::PostScript {
+                    domain,
+                    range,
+                    program: parse_postscript(&data)?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluate the function; inputs are clipped to the domain and
+    /// outputs to the range
+    pub fn eval(&self, input: &[f64]) -> Vec<f64> {
+        match self {
+            Function::Sampled {
+                domain,
+                range,
+                size,
+                encode,
+                decode,
+                samples,
+            } => {
+                let outputs = range.len();
+                // Position of the input in sample grid coordinates
+                let position: Vec<f64> = domain
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(d0, d1))| {
+                        let x = input
+                            .get(i)
+                            .copied()
+                            .unwrap_or(d0)
+                            .clamp(d0.min(d1), d0.max(d1));
+                        let (e0, e1) = encode[i];
+                        interpolate(x, d0, d1, e0, e1).clamp(0.0, (size[i] - 1) as f64)
+                    })
+                    .collect();
+
+                // Multilinear interpolation over the 2^m corners of the cell
+                let mut result = vec![0.0; outputs];
+                for corner in 0..(1usize << position.len()) {
+                    let mut weight = 1.0;
+                    let mut index = 0;
+                    let mut stride = 1;
+                    for (i, &p) in position.iter().enumerate() {
+                        let low = p.floor();
+                        let frac = p - low;
+                        let upper = corner >> i & 1 == 1;
+                        let coordinate = if upper {
+                            (low as usize + 1).min(size[i] - 1)
+                        } else {
+                            low as usize
+                        };
+                        weight *= if upper { frac } else { 1.0 - frac };
+                        index += coordinate * stride;
+                        stride *= size[i];
+                    }
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    for (j, value) in result.iter_mut().enumerate() {
+                        *value += weight * samples.get(index * outputs + j).copied().unwrap_or(0.0);
+                    }
+                }
+                result
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &s)| {
+                        let (d0, d1) = decode.get(j).copied().unwrap_or((0.0, 1.0));
+                        let (r0, r1) = range[j];
+                        (d0 + s * (d1 - d0)).clamp(r0.min(r1), r0.max(r1))
+                    })
+                    .collect()
+            }
+            Function::Exponential {
+                domain,
+                c0,
+                c1,
+                exponent,
+            } => {
+                let x = input
+                    .first()
+                    .copied()
+                    .unwrap_or(domain.0)
+                    .clamp(domain.0.min(domain.1), domain.0.max(domain.1));
+                let t = x.powf(*exponent);
+                c0.iter().zip(c1).map(|(a, b)| a + t * (b - a)).collect()
+            }
+            Function::Stitching {
+                domain,
+                functions,
+                bounds,
+                encode,
+            } => {
+                let x = input
+                    .first()
+                    .copied()
+                    .unwrap_or(domain.0)
+                    .clamp(domain.0.min(domain.1), domain.0.max(domain.1));
+                let k = bounds.iter().take_while(|&&b| x >= b).count();
+                let low = if k == 0 { domain.0 } else { bounds[k - 1] };
+                let high = if k == bounds.len() {
+                    domain.1
+                } else {
+                    bounds[k]
+                };
+                let (e0, e1) = encode[k];
+                let t = if high == low {
+                    e0
+                } else {
+                    interpolate(x, low, high, e0, e1)
+                };
+                functions[k].eval(&[t])
+            }
+            Function::PostScript {
+                domain,
+                range,
+                program,
+            } => {
+                let mut stack: Vec<PsValue> = domain
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(d0, d1))| {
+                        PsValue::Number(
+                            input
+                                .get(i)
+                                .copied()
+                                .unwrap_or(d0)
+                                .clamp(d0.min(d1), d0.max(d1)),
+                        )
+                    })
+                    .collect();
+                run_postscript(program, &mut stack, 0);
+                let values: Vec<f64> = stack.iter().map(PsValue::number).collect();
+                let start = values.len().saturating_sub(range.len());
+                range
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &(r0, r1))| {
+                        values
+                            .get(start + j)
+                            .copied()
+                            .unwrap_or(r0)
+                            .clamp(r0.min(r1), r0.max(r1))
+                    })
+                    .collect()
+            }
+            Function::Array(functions) => functions
+                .iter()
+                .map(|function| function.eval(input).first().copied().unwrap_or(0.0))
+                .collect(),
+        }
+    }
+}
+
+fn interpolate(x: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+    if x1 == x0 {
+        y0
+    } else {
+        y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+    }
+}
+
+fn pairs(values: Vec<f64>) -> Vec<(f64, f64)> {
+    values.chunks_exact(2).map(|c| (c[0], c[1])).collect()
+}
+
+/// Unpack `count` big-endian samples of `bits` bits, normalized to 0..=1
+fn read_samples(data: &[u8], bits: u32, count: usize) -> Option<Vec<f64>> {
+    if !matches!(bits, 1 | 2 | 4 | 8 | 12 | 16 | 24 | 32) {
+        return None;
+    }
+    let max = ((1u64 << bits) - 1) as f64;
+    let mut samples = Vec::with_capacity(count);
+    let mut bit_position = 0usize;
+    for _ in 0..count {
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = *data.get(bit_position / 8).unwrap_or(&0);
+            value = value << 1 | (byte >> (7 - bit_position % 8) & 1) as u64;
+            bit_position += 1;
+        }
+        samples.push(value as f64 / max);
+    }
+    Some(samples)
+}
+
+/// A PostScript calculator operation
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PsOp {
+    Number(f64),
+    Bool(bool),
+    Operator(String),
+    If(Vec<PsOp>),
+    IfElse(Vec<PsOp>, Vec<PsOp>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PsValue {
+    Number(f64),
+    Bool(bool),
+}
+
+impl PsValue {
+    fn number(&self) -> f64 {
+        match self {
+            PsValue::Number(n) => *n,
+            PsValue::Bool(b) => *b as u8 as f64,
+        }
+    }
+}
+
+/// Parse a type 4 function body: `{ ... }` with nested procedures for
+/// `if` and `ifelse`
+pub(crate) fn parse_postscript(data: &[u8]) -> Option<Vec<PsOp>> {
+    let text = String::from_utf8_lossy(data);
+    let mut tokens = Vec::new();
+    for word in text
+        .replace('{', " { ")
+        .replace('}', " } ")
+        .split_whitespace()
+    {
+        tokens.push(word.to_string());
+    }
+    let mut position = tokens.iter().position(|t| t == "{")? + 1;
+    parse_procedure(&tokens, &mut position)
+}
+
+fn parse_procedure(tokens: &[String], position: &mut usize) -> Option<Vec<PsOp>> {
+    let mut ops = Vec::new();
+    let mut pending: Vec<Vec<PsOp>> = Vec::new();
+    while *position < tokens.len() {
+        let token = &tokens[*position];
+        *position += 1;
+        match token.as_str() {
+            "{" => pending.push(parse_procedure(tokens, position)?),
+            "}" => return Some(ops),
+            "if" => ops.push(PsOp::If(pending.pop()?)),
+            "ifelse" => {
+                let otherwise = pending.pop()?;
+                let then = pending.pop()?;
+                ops.push(PsOp::IfElse(then, otherwise));
+            }
+            "true" => ops.push(PsOp::Bool(true)),
+            "false" => ops.push(PsOp::Bool(false)),
+            word => match word.parse::<f64>() {
+                Ok(n) => ops.push(PsOp::Number(n)),
+                Err(_) => ops.push(PsOp::Operator(word.to_string())),
+            },
+        }
+    }
+    Some(ops)
+}
+
+fn run_postscript(program: &[PsOp], stack: &mut Vec<PsValue>, depth: usize) {
+    if depth > 32 {
+        return;
+    }
+    for op in program {
+        match op {
+            PsOp::Number(n) => stack.push(PsValue::Number(*n)),
+            PsOp::Bool(b) => stack.push(PsValue::Bool(*b)),
+            PsOp::If(then) => {
+                if let Some(PsValue::Bool(true)) = stack.pop() {
+                    run_postscript(then, stack, depth + 1);
+                }
+            }
+            PsOp::IfElse(then, otherwise) => match stack.pop() {
+                Some(PsValue::Bool(true)) => run_postscript(then, stack, depth + 1),
+                _ => run_postscript(otherwise, stack, depth + 1),
+            },
+            PsOp::Operator(name) => {
+                if stack.len() > 1000 || !apply_operator(name, stack) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Apply one operator; `false` stops the program on a stack error
+fn apply_operator(name: &str, stack: &mut Vec<PsValue>) -> bool {
+    let pop = |stack: &mut Vec<PsValue>| stack.pop().map(|v| v.number());
+    let unary = |stack: &mut Vec<PsValue>, f: &dyn Fn(f64) -> f64| match pop(stack) {
+        Some(a) => {
+            stack.push(PsValue::Number(f(a)));
+            true
+        }
+        None => false,
+    };
+    let binary =
+        |stack: &mut Vec<PsValue>, f: &dyn Fn(f64, f64) -> f64| match (pop(stack), pop(stack)) {
+            (Some(b), Some(a)) => {
+                stack.push(PsValue::Number(f(a, b)));
+                true
+            }
+            _ => false,
+        };
+    let compare =
+        |stack: &mut Vec<PsValue>, f: &dyn Fn(f64, f64) -> bool| match (pop(stack), pop(stack)) {
+            (Some(b), Some(a)) => {
+                stack.push(PsValue::Bool(f(a, b)));
+                true
+            }
+            _ => false,
+        };
+    let logical =
+        |stack: &mut Vec<PsValue>, f: &dyn Fn(i64, i64) -> i64, g: &dyn Fn(bool, bool) -> bool| {
+            match (stack.pop(), stack.pop()) {
+                (Some(PsValue::Bool(b)), Some(PsValue::Bool(a))) => {
+                    stack.push(PsValue::Bool(g(a, b)));
+                    true
+                }
+                (Some(b), Some(a)) => {
+                    stack.push(PsValue::Number(
+                        f(a.number() as i64, b.number() as i64) as f64
+                    ));
+                    true
+                }
+                _ => false,
+            }
+        };
+
+    match name {
+        "add" => binary(stack, &|a, b| a + b),
+        "sub" => binary(stack, &|a, b| a - b),
+        "mul" => binary(stack, &|a, b| a * b),
+        "div" => binary(stack, &|a, b| if b == 0.0 { 0.0 } else { a / b }),
+        "idiv" => binary(stack, &|a, b| {
+            if b as i64 == 0 {
+                0.0
+            } else {
+                (a as i64 / b as i64) as f64
+            }
+        }),
+        "mod" => binary(stack, &|a, b| {
+            if b as i64 == 0 {
+                0.0
+            } else {
+                (a as i64 % b as i64) as f64
+            }
+        }),
+        "exp" => binary(stack, &|a, b| a.powf(b)),
+        "atan" => binary(stack, &|a, b| {
+            let degrees = a.atan2(b).to_degrees();
+            if degrees < 0.0 {
+                degrees + 360.0
+            } else {
+                degrees
+            }
+        }),
+        "abs" => unary(stack, &f64::abs),
+        "neg" => unary(stack, &|a| -a),
+        "ceiling" => unary(stack, &f64::ceil),
+        "floor" => unary(stack, &f64::floor),
+        "round" => unary(stack, &|a| (a + 0.5).floor()),
+        "truncate" | "cvi" => unary(stack, &f64::trunc),
+        "cvr" => unary(stack, &|a| a),
+        "sqrt" => unary(stack, &|a| a.max(0.0).sqrt()),
+        "sin" => unary(stack, &|a| a.to_radians().sin()),
+        "cos" => unary(stack, &|a| a.to_radians().cos()),
+        "ln" => unary(stack, &|a| if a > 0.0 { a.ln() } else { 0.0 }),
+        "log" => unary(stack, &|a| if a > 0.0 { a.log10() } else { 0.0 }),
+        "eq" => compare(stack, &|a, b| a == b),
+        "ne" => compare(stack, &|a, b| a != b),
+        "gt" => compare(stack, &|a, b| a > b),
+        "ge" => compare(stack, &|a, b| a >= b),
+        "lt" => compare(stack, &|a, b| a < b),
+        "le" => compare(stack, &|a, b| a <= b),
+        "and" => logical(stack, &|a, b| a & b, &|a, b| a && b),
+        "or" => logical(stack, &|a, b| a | b, &|a, b| a || b),
+        "xor" => logical(stack, &|a, b| a ^ b, &|a, b| a ^ b),
+        "not" => match stack.pop() {
+            Some(PsValue::Bool(b)) => {
+                stack.push(PsValue::Bool(!b));
+                true
+            }
+            Some(PsValue::Number(n)) => {
+                stack.push(PsValue::Number(!(n as i64) as f64));
+                true
+            }
+            None => false,
+        },
+        "bitshift" => binary(stack, &|a, b| {
+            let (a, b) = (a as i64, b as i64);
+            if b >= 0 {
+                (a << b.min(63)) as f64
+            } else {
+                (a >> (-b).min(63)) as f64
+            }
+        }),
+        "pop" => stack.pop().is_some(),
+        "dup" => match stack.last().copied() {
+            Some(v) => {
+                stack.push(v);
+                true
+            }
+            None => false,
+        },
+        "exch" => {
+            let n = stack.len();
+            if n < 2 {
+                return false;
+            }
+            stack.swap(n - 1, n - 2);
+            true
+        }
+        "copy" => match pop(stack) {
+            Some(n) if n >= 0.0 && (n as usize) <= stack.len() => {
+                let start = stack.len() - n as usize;
+                stack.extend_from_within(start..);
+                true
+            }
+            _ => false,
+        },
+        "index" => match pop(stack) {
+            Some(n) if n >= 0.0 && (n as usize) < stack.len() => {
+                let v = stack[stack.len() - 1 - n as usize];
+                stack.push(v);
+                true
+            }
+            _ => false,
+        },
+        "roll" => match (pop(stack), pop(stack)) {
+            (Some(j), Some(n)) if n >= 0.0 && (n as usize) <= stack.len() => {
+                let n = n as usize;
+                if n > 0 {
+                    let start = stack.len() - n;
+                    let shift = (j as i64).rem_euclid(n as i64) as usize;
+                    stack[start..].rotate_right(shift);
+                }
+                true
+            }
+            _ => false,
+        },
+        "true" => {
+            stack.push(PsValue::Bool(true));
+            true
+        }
+        "false" => {
+            stack.push(PsValue::Bool(false));
+            true
+        }
+        _ => {
+            tracing::debug!("Unsupported PostScript function operator {name}");
+            false
+        }
+    }
+}
+
+/// Parse a function from a dictionary entry
+pub(crate) fn get_function(
+    resolver: &dyn Resolver,
+    dict: &PdfDictionary,
+    key: &str,
+) -> Option<Function> {
+    Function::parse(resolver, &get(resolver, dict, key)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::objects::{PdfArray, PdfStream};
+    use crate::parser::resolve::test_support::MemoryResolver;
+
+    fn dict(entries: Vec<(&str, PdfObject)>) -> PdfDictionary {
+        let mut dict = PdfDictionary::new();
+        for (key, value) in entries {
+            dict.insert(key.to_string(), value);
+        }
+        dict
+    }
+
+    fn array(values: &[f64]) -> PdfObject {
+        PdfObject::Array(PdfArray(
+            values.iter().map(|&v| PdfObject::Real(v)).collect(),
+        ))
+    }
+
+    #[test]
+    fn test_exponential_function() {
+        let object = PdfObject::Dictionary(dict(vec![
+            ("FunctionType", PdfObject::Integer(2)),
+            ("Domain", array(&[0.0, 1.0])),
+            ("C0", array(&[0.0, 0.0, 1.0])),
+            ("C1", array(&[1.0, 0.0, 0.0])),
+            ("N", PdfObject::Integer(1)),
+        ]));
+        let function = Function::parse(&MemoryResolver::default(), &object).unwrap();
+        assert_eq!(function.eval(&[0.25]), vec![0.25, 0.0, 0.75]);
+        assert_eq!(function.eval(&[2.0]), vec![1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_stitching_function() {
+        let half = |c0: f64, c1: f64| {
+            PdfObject::Dictionary(dict(vec![
+                ("FunctionType", PdfObject::Integer(2)),
+                ("Domain", array(&[0.0, 1.0])),
+                ("C0", array(&[c0])),
+                ("C1", array(&[c1])),
+                ("N", PdfObject::Integer(1)),
+            ]))
+        };
+        let object = PdfObject::Dictionary(dict(vec![
+            ("FunctionType", PdfObject::Integer(3)),
+            ("Domain", array(&[0.0, 1.0])),
+            (
+                "Functions",
+                PdfObject::Array(PdfArray(vec![half(0.0, 1.0), half(1.0, 0.0)])),
+            ),
+            ("Bounds", array(&[0.5])),
+            ("Encode", array(&[0.0, 1.0, 0.0, 1.0])),
+        ]));
+        let function = Function::parse(&MemoryResolver::default(), &object).unwrap();
+        assert!((function.eval(&[0.25])[0] - 0.5).abs() < 1e-9);
+        assert!((function.eval(&[0.75])[0] - 0.5).abs() < 1e-9);
+        assert!((function.eval(&[0.5])[0] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sampled_function() {
+        // Two 8-bit samples of one output: 0 and 255
+        let stream = PdfStream {
+            dict: dict(vec![
+                ("FunctionType", PdfObject::Integer(0)),
+                ("Domain", array(&[0.0, 1.0])),
+                ("Range", array(&[0.0, 1.0])),
+                ("Size", array(&[2.0])),
+                ("BitsPerSample", PdfObject::Integer(8)),
+            ]),
+            data: vec![0, 255],
+        };
+        let function =
+            Function::parse(&MemoryResolver::default(), &PdfObject::Stream(stream)).unwrap();
+        assert!((function.eval(&[0.3])[0] - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_postscript_function() {
+        let stream = PdfStream {
+            dict: dict(vec![
+                ("FunctionType", PdfObject::Integer(4)),
+                ("Domain", array(&[0.0, 1.0])),
+                ("Range", array(&[0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0])),
+            ]),
+            data: b"{ dup 0.5 gt { 0 exch } { 1 exch } ifelse 0 0 }".to_vec(),
+        };
+        let function =
+            Function::parse(&MemoryResolver::default(), &PdfObject::Stream(stream)).unwrap();
+        assert_eq!(function.eval(&[0.8]), vec![0.0, 0.8, 0.0, 0.0]);
+        assert_eq!(function.eval(&[0.2]), vec![1.0, 0.2, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,527 @@
+//! Image XObjects and inline images decoded to RGBA pixels
+
+use super::color::{to_bytes, ColorSpace};
+use crate::parser::filters::{decode_jpx_image, JpxColorSpace};
+use crate::parser::objects::{PdfArray, PdfDictionary, PdfObject, PdfStream};
+use crate::parser::resolve::{get, get_bool, get_number, get_numbers, Resolver};
+
+/// Largest accepted image, in pixels
+const MAX_PIXELS: usize = 1 << 28;
+
+/// Decoded image pixels, row 0 at the top of the image
+#[derive(Debug, Clone)]
+pub(crate) struct DecodedImage {
+    pub width: usize,
+    pub height: usize,
+    /// Premultiplied RGBA, or opacity only (in the alpha byte) for stencils
+    pub pixels: Vec<u8>,
+    /// An image mask, painted with the current fill color
+    pub stencil: bool,
+    /// Smooth when enlarging
+    pub interpolate: bool,
+}
+
+impl DecodedImage {
+    /// Decode an image from its dictionary and encoded data
+    pub fn decode(
+        resolver: &dyn Resolver,
+        dict: &PdfDictionary,
+        data: &[u8],
+        resources: Option<&PdfDictionary>,
+    ) -> Option<DecodedImage> {
+        let width = get_number(resolver, dict, "Width")?.max(0.0) as usize;
+        let height = get_number(resolver, dict, "Height")?.max(0.0) as usize;
+        if width == 0 || height == 0 || width.saturating_mul(height) > MAX_PIXELS {
+            return None;
+        }
+        let interpolate = get_bool(resolver, dict, "Interpolate").unwrap_or(false);
+        let samples = decode_samples(resolver, dict, data)?;
+
+        if get_bool(resolver, dict, "ImageMask").unwrap_or(false) {
+            let paint_on_one = get_numbers(resolver, dict, "Decode")
+                .map_or(false, |decode| decode.first() == Some(&1.0));
+            let stride = (width + 7) / 8;
+            let mut pixels = vec![0u8; width * height * 4];
+            for y in 0..height {
+                for x in 0..width {
+                    let byte = samples.bytes.get(y * stride + x / 8).copied().unwrap_or(0);
+                    let bit = byte >> (7 - x % 8) & 1 == 1;
+                    if bit == paint_on_one {
+                        pixels[(y * width + x) * 4 + 3] = 255;
+                    }
+                }
+            }
+            return Some(DecodedImage {
+                width,
+                height,
+                pixels,
+                stencil: true,
+                interpolate,
+            });
+        }
+
+        let space = match get(resolver, dict, "ColorSpace") {
+            Some(object) => ColorSpace::parse(resolver, &object, resources)?,
+            None => match samples.jpx_space {
+                Some(JpxColorSpace::Gray) => ColorSpace::DeviceGray,
+                Some(JpxColorSpace::CMYK) => ColorSpace::DeviceCmyk,
+                Some(_) => ColorSpace::DeviceRgb,
+                None => return None,
+            },
+        };
+        let components = space.components().max(1);
+        let bits = if samples.jpx_space.is_some() {
+            8
+        } else {
+            get_number(resolver, dict, "BitsPerComponent").unwrap_or(8.0) as u32
+        };
+        if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+            return None;
+        }
+        let decode = get_numbers(resolver, dict, "Decode")
+            .filter(|d| d.len() >= components * 2)
+            .map(|d| d.chunks_exact(2).map(|c| (c[0], c[1])).collect::<Vec<_>>())
+            .unwrap_or_else(|| space.default_decode(bits));
+        let color_key = match get(resolver, dict, "Mask") {
+            Some(PdfObject::Array(ranges)) => Some(color_key_ranges(resolver, &ranges)),
+            _ => None,
+        };
+
+        let reader = SampleReader {
+            data: &samples.bytes,
+            bits,
+            stride: (width * components * bits as usize + 7) / 8,
+        };
+        let max = ((1u32 << bits) - 1) as f64;
+        let mut pixels = vec![0u8; width * height * 4];
+        let mut raw = vec![0u32; components];
+        let mut color = vec![0.0; components];
+        let mut cache: Option<(Vec<u32>, [u8; 3])> = None;
+        for y in 0..height {
+            for x in 0..width {
+                for (c, value) in raw.iter_mut().enumerate() {
+                    *value = reader.get(y, x * components + c);
+                }
+                let out = &mut pixels[(y * width + x) * 4..(y * width + x + 1) * 4];
+                if let Some(ranges) = &color_key {
+                    let masked = raw
+                        .iter()
+                        .zip(ranges)
+                        .all(|(&v, &(low, high))| low <= v && v <= high);
+                    if masked {
+                        continue;
+                    }
+                }
+                let rgb = match &cache {
+                    Some((key, rgb)) if *key == raw => *rgb,
+                    _ => {
+                        for (c, value) in color.iter_mut().enumerate() {
+                            let (d0, d1) = decode.get(c).copied().unwrap_or((0.0, 1.0));
+                            *value = d0 + raw[c] as f64 * (d1 - d0) / max;
+                        }
+                        let rgb = to_bytes(space.to_rgb(&color));
+                        cache = Some((raw.clone(), rgb));
+                        rgb
+                    }
+                };
+                out.copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+            }
+        }
+
+        let mut image = DecodedImage {
+            width,
+            height,
+            pixels,
+            stencil: false,
+            interpolate,
+        };
+
+        // Opacity from a soft mask, an embedded JPX alpha channel or a
+        // stencil mask
+        let alpha = match get(resolver, dict, "SMask") {
+            Some(PdfObject::Stream(mask)) => decode_mask(resolver, &mask, resources, false),
+            _ => match (samples.jpx_alpha, get_number(resolver, dict, "SMaskInData")) {
+                (Some(alpha), Some(n)) if n > 0.0 => Some(AlphaChannel {
+                    width,
+                    height,
+                    values: alpha,
+                }),
+                _ => match get(resolver, dict, "Mask") {
+                    Some(PdfObject::Stream(mask)) => decode_mask(resolver, &mask, resources, true),
+                    _ => None,
+                },
+            },
+        };
+        if let Some(alpha) = alpha {
+            image.apply_alpha(&alpha);
+        }
+        Some(image)
+    }
+
+    /// Multiply the opacity by a mask, scaled to this image's size
+    fn apply_alpha(&mut self, alpha: &AlphaChannel) {
+        for y in 0..self.height {
+            let my = y * alpha.height / self.height;
+            for x in 0..self.width {
+                let mx = x * alpha.width / self.width;
+                let a = alpha
+                    .values
+                    .get(my * alpha.width + mx)
+                    .copied()
+                    .unwrap_or(255);
+                let pixel =
+                    &mut self.pixels[(y * self.width + x) * 4..(y * self.width + x + 1) * 4];
+                for c in pixel.iter_mut() {
+                    *c = super::raster::mul8(*c, a);
+                }
+            }
+        }
+    }
+
+    /// Average blocks of `fx` by `fy` pixels, so that sampling a heavily
+    /// reduced image does not alias
+    pub fn downsample(&self, fx: usize, fy: usize) -> DecodedImage {
+        let fx = fx.max(1);
+        let fy = fy.max(1);
+        let width = (self.width + fx - 1) / fx;
+        let height = (self.height + fy - 1) / fy;
+        let mut pixels = vec![0u8; width * height * 4];
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u32; 4];
+                let mut count = 0;
+                for sy in y * fy..((y + 1) * fy).min(self.height) {
+                    for sx in x * fx..((x + 1) * fx).min(self.width) {
+                        let i = (sy * self.width + sx) * 4;
+                        for c in 0..4 {
+                            sum[c] += self.pixels[i + c] as u32;
+                        }
+                        count += 1;
+                    }
+                }
+                let i = (y * width + x) * 4;
+                for c in 0..4 {
+                    pixels[i + c] = ((sum[c] + count / 2) / count.max(1)) as u8;
+                }
+            }
+        }
+        DecodedImage {
+            width,
+            height,
+            pixels,
+            stencil: self.stencil,
+            interpolate: self.interpolate,
+        }
+    }
+
+    /// Pixel at image space coordinates (`u` right, `v` up, unit square)
+    pub fn sample(&self, u: f64, v: f64, smooth: bool) -> [u8; 4] {
+        let x = u * self.width as f64;
+        let y = (1.0 - v) * self.height as f64;
+        if !smooth {
+            let x = (x.floor().max(0.0) as usize).min(self.width - 1);
+            let y = (y.floor().max(0.0) as usize).min(self.height - 1);
+            return self.pixel(x, y);
+        }
+        let fx = (x - 0.5).clamp(0.0, (self.width - 1) as f64);
+        let fy = (y - 0.5).clamp(0.0, (self.height - 1) as f64);
+        let (x0, y0) = (fx.floor() as usize, fy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (fx - x0 as f64, fy - y0 as f64);
+        let (p00, p10) = (self.pixel(x0, y0), self.pixel(x1, y0));
+        let (p01, p11) = (self.pixel(x0, y1), self.pixel(x1, y1));
+        let mut out = [0u8; 4];
+        for c in 0..4 {
+            let top = p00[c] as f64 * (1.0 - tx) + p10[c] as f64 * tx;
+            let bottom = p01[c] as f64 * (1.0 - tx) + p11[c] as f64 * tx;
+            out[c] = (top * (1.0 - ty) + bottom * ty + 0.5) as u8;
+        }
+        out
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+}
+
+/// Decoded samples, with the properties of a JPEG 2000 codestream
+struct Samples {
+    bytes: Vec<u8>,
+    jpx_space: Option<JpxColorSpace>,
+    jpx_alpha: Option<Vec<u8>>,
+}
+
+/// Apply the image filters. DCT data is decoded to samples and JPX data
+/// keeps the color space it declares.
+fn decode_samples(resolver: &dyn Resolver, dict: &PdfDictionary, data: &[u8]) -> Option<Samples> {
+    let mut options = resolver.parse_options();
+    options.decode_dct_images = true;
+
+    let filters: Vec<String> = match get(resolver, dict, "Filter") {
+        Some(PdfObject::Name(name)) => vec![name.as_str().to_string()],
+        Some(PdfObject::Array(names)) => names
+            .0
+            .iter()
+            .filter_map(|n| resolver.lookup(n).as_name().map(|n| n.as_str().to_string()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut dict = dict.clone();
+    // Indirect filter parameters (such as JBIG2 globals) are resolved here
+    if let Some(params) = get(resolver, &dict, "DecodeParms") {
+        dict.insert("DecodeParms".to_string(), resolve_params(resolver, params));
+    }
+    if filters.last().map(String::as_str) == Some("JPXDecode") {
+        let mut outer = filters.clone();
+        outer.pop();
+        dict.insert(
+            "Filter".to_string(),
+            PdfObject::Array(PdfArray(
+                outer
+                    .into_iter()
+                    .map(|name| PdfObject::Name(crate::parser::objects::PdfName::new(name)))
+                    .collect(),
+            )),
+        );
+        let stream = PdfStream {
+            dict,
+            data: data.to_vec(),
+        };
+        let codestream = decode_logged(&stream, &options)?;
+        let image = match decode_jpx_image(&codestream) {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::debug!("Skipping undecodable JPX image: {e}");
+                return None;
+            }
+        };
+        return Some(Samples {
+            bytes: image.data,
+            jpx_space: Some(image.color_space),
+            jpx_alpha: image.alpha,
+        });
+    }
+
+    let stream = PdfStream {
+        dict,
+        data: data.to_vec(),
+    };
+    Some(Samples {
+        bytes: decode_logged(&stream, &options)?,
+        jpx_space: None,
+        jpx_alpha: None,
+    })
+}
+
+fn decode_logged(stream: &PdfStream, options: &crate::parser::ParseOptions) -> Option<Vec<u8>> {
+    match stream.decode(options) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            tracing::debug!("Skipping undecodable image: {e}");
+            None
+        }
+    }
+}
+
+/// Decode parameters with references to streams replaced by the streams
+fn resolve_params(resolver: &dyn Resolver, params: PdfObject) -> PdfObject {
+    match params {
+        PdfObject::Dictionary(dict) => {
+            let mut resolved = PdfDictionary::new();
+            for (key, value) in dict.0 {
+                resolved.insert(key.as_str().to_string(), resolver.lookup(&value));
+            }
+            PdfObject::Dictionary(resolved)
+        }
+        PdfObject::Array(items) => PdfObject::Array(PdfArray(
+            items
+                .0
+                .into_iter()
+                .map(|item| resolve_params(resolver, resolver.lookup(&item)))
+                .collect(),
+        )),
+        other => other,
+    }
+}
+
+/// Opacity values of a mask image
+struct AlphaChannel {
+    width: usize,
+    height: usize,
+    values: Vec<u8>,
+}
+
+/// Decode a soft mask (gray values) or a stencil mask (`stencil`, where
+/// painted samples are opaque)
+fn decode_mask(
+    resolver: &dyn Resolver,
+    mask: &PdfStream,
+    resources: Option<&PdfDictionary>,
+    stencil: bool,
+) -> Option<AlphaChannel> {
+    let mut dict = mask.dict.clone();
+    if !stencil {
+        dict.insert(
+            "ColorSpace".to_string(),
+            PdfObject::Name(crate::parser::objects::PdfName::new(
+                "DeviceGray".to_string(),
+            )),
+        );
+        dict.0
+            .remove(&crate::parser::objects::PdfName::new("SMask".to_string()));
+        dict.0
+            .remove(&crate::parser::objects::PdfName::new("Mask".to_string()));
+    }
+    let image = DecodedImage::decode(resolver, &dict, &mask.data, resources)?;
+    let values = image
+        .pixels
+        .chunks_exact(4)
+        .map(|p| if image.stencil { 255 - p[3] } else { p[0] })
+        .collect();
+    Some(AlphaChannel {
+        width: image.width,
+        height: image.height,
+        values,
+    })
+}
+
+fn color_key_ranges(resolver: &dyn Resolver, ranges: &PdfArray) -> Vec<(u32, u32)> {
+    ranges
+        .0
+        .chunks_exact(2)
+        .map(|pair| {
+            let value = |o: &PdfObject| resolver.lookup(o).as_real().unwrap_or(0.0).max(0.0) as u32;
+            (value(&pair[0]), value(&pair[1]))
+        })
+        .collect()
+}
+
+/// Unpacks samples of `bits` bits from byte-aligned rows
+struct SampleReader<'a> {
+    data: &'a [u8],
+    bits: u32,
+    stride: usize,
+}
+
+impl SampleReader<'_> {
+    fn get(&self, row: usize, index: usize) -> u32 {
+        let byte = |i: usize| self.data.get(i).copied().unwrap_or(0) as u32;
+        let base = row * self.stride;
+        match self.bits {
+            8 => byte(base + index),
+            16 => byte(base + index * 2) << 8 | byte(base + index * 2 + 1),
+            bits => {
+                let bit = index * bits as usize;
+                let shift = 8 - bits as usize - bit % 8;
+                byte(base + bit / 8) >> shift & ((1 << bits) - 1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::objects::PdfName;
+    use crate::parser::resolve::test_support::MemoryResolver;
+
+    fn image_dict(entries: Vec<(&str, PdfObject)>) -> PdfDictionary {
+        let mut dict = PdfDictionary::new();
+        for (key, value) in entries {
+            dict.insert(key.to_string(), value);
+        }
+        dict
+    }
+
+    fn name(s: &str) -> PdfObject {
+        PdfObject::Name(PdfName::new(s.to_string()))
+    }
+
+    #[test]
+    fn test_rgb_and_gray_images() {
+        let resolver = MemoryResolver::default();
+        let dict = image_dict(vec![
+            ("Width", PdfObject::Integer(2)),
+            ("Height", PdfObject::Integer(1)),
+            ("ColorSpace", name("DeviceRGB")),
+            ("BitsPerComponent", PdfObject::Integer(8)),
+        ]);
+        let image = DecodedImage::decode(&resolver, &dict, &[255, 0, 0, 0, 0, 255], None).unwrap();
+        assert_eq!(image.pixels, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+
+        // 1-bit gray with an inverted decode array
+        let dict = image_dict(vec![
+            ("Width", PdfObject::Integer(3)),
+            ("Height", PdfObject::Integer(1)),
+            ("ColorSpace", name("DeviceGray")),
+            ("BitsPerComponent", PdfObject::Integer(1)),
+            (
+                "Decode",
+                PdfObject::Array(PdfArray(vec![PdfObject::Integer(1), PdfObject::Integer(0)])),
+            ),
+        ]);
+        let image = DecodedImage::decode(&resolver, &dict, &[0b0100_0000], None).unwrap();
+        assert_eq!(image.pixel(0, 0), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(1, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_stencil_mask_and_soft_mask() {
+        let resolver = MemoryResolver::default();
+        let dict = image_dict(vec![
+            ("Width", PdfObject::Integer(2)),
+            ("Height", PdfObject::Integer(1)),
+            ("ImageMask", PdfObject::Boolean(true)),
+        ]);
+        let image = DecodedImage::decode(&resolver, &dict, &[0b0100_0000], None).unwrap();
+        assert!(image.stencil);
+        assert_eq!(image.pixel(0, 0)[3], 255);
+        assert_eq!(image.pixel(1, 0)[3], 0);
+
+        let smask = PdfStream {
+            dict: image_dict(vec![
+                ("Width", PdfObject::Integer(1)),
+                ("Height", PdfObject::Integer(1)),
+                ("BitsPerComponent", PdfObject::Integer(8)),
+            ]),
+            data: vec![128],
+        };
+        let dict = image_dict(vec![
+            ("Width", PdfObject::Integer(1)),
+            ("Height", PdfObject::Integer(1)),
+            ("ColorSpace", name("DeviceGray")),
+            ("BitsPerComponent", PdfObject::Integer(8)),
+            ("SMask", PdfObject::Stream(smask)),
+        ]);
+        let image = DecodedImage::decode(&resolver, &dict, &[255], None).unwrap();
+        assert_eq!(image.pixel(0, 0), [128, 128, 128, 128]);
+    }
+
+    #[test]
+    fn test_sampling_and_downsampling() {
+        let image = DecodedImage {
+            width: 2,
+            height: 2,
+            pixels: vec![
+                0, 0, 0, 255, 255, 255, 255, 255, //
+                255, 255, 255, 255, 0, 0, 0, 255,
+            ],
+            stencil: false,
+            interpolate: false,
+        };
+        // Image space origin is the bottom left corner
+        assert_eq!(image.sample(0.25, 0.25, false), [255, 255, 255, 255]);
+        assert_eq!(image.sample(0.75, 0.25, false), [0, 0, 0, 255]);
+        assert_eq!(image.sample(0.5, 0.5, true)[0], 128);
+        let small = image.downsample(2, 2);
+        assert_eq!((small.width, small.height), (1, 1));
+        assert_eq!(small.pixels, vec![128, 128, 128, 255]);
+    }
+}
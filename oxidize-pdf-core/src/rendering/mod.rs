@@ -0,0 +1,48 @@
+//! Page rendering
+//!
+//! A CPU rasterizer that executes the content stream of a parsed page into
+//! an RGBA bitmap at a chosen resolution, for thumbnails, previews and OCR
+//! input.
+//!
+//! # Features
+//!
+//! - **Paths**: nonzero and even-odd fills, strokes with caps, joins and
+//!   dashes, anti-aliased
+//! - **Clipping**: path and text clipping
+//! - **Color**: device, CIE-based, ICC (by component count), Indexed,
+//!   Separation and DeviceN spaces, tiling and shading patterns
+//! - **Images**: image XObjects and inline images, stencil masks, soft masks
+//! - **Shadings**: function-based, axial, radial and mesh shadings
+//! - **Transparency**: constant alpha, blend modes, soft masks and
+//!   transparency groups
+//! - **Text**: embedded TrueType and CFF glyph outlines and Type 3 fonts;
+//!   text in non-embedded fonts is positioned but not drawn
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use oxidize_pdf::parser::{PdfDocument, PdfReader};
+//! use oxidize_pdf::rendering::RenderOptions;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let document = PdfDocument::new(PdfReader::open("report.pdf")?);
+//! let page = document.render_page(0, &RenderOptions::with_dpi(150.0))?;
+//! page.save_png("report-page-1.png")?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod canvas;
+mod color;
+pub(crate) mod font;
+mod function;
+mod image;
+mod page;
+mod path;
+mod png;
+mod raster;
+mod renderer;
+mod shading;
+mod stroke;
+
+pub use page::{render_page, RenderOptions, RenderedPage};
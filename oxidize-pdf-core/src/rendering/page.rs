@@ -0,0 +1,250 @@
+//! Rendering whole pages of a parsed document
+
+use super::canvas::Canvas;
+use super::png;
+use super::renderer::Renderer;
+use crate::coordinate_system::TransformMatrix;
+use crate::error::{PdfError, Result};
+use crate::parser::objects::PdfObject;
+use crate::parser::resolve::{get, get_dict, get_name, get_rect, Resolver};
+use crate::parser::PdfDocument;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+/// Largest bitmap produced, in pixels
+const MAX_PIXELS: usize = 1 << 28;
+
+/// Annotation flags that keep an annotation off the screen
+const HIDDEN: i64 = 1 << 1;
+const NO_VIEW: i64 = 1 << 5;
+
+/// Options for [`render_page`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// Resolution in pixels per inch; a page point is 1/72 inch
+    pub dpi: f64,
+    /// Opaque page color, or `None` for a transparent background
+    pub background: Option<[u8; 3]>,
+    /// Draw the normal appearance of visible annotations
+    pub render_annotations: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 72.0,
+            background: Some([255, 255, 255]),
+            render_annotations: true,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Default options at the given resolution
+    pub fn with_dpi(dpi: f64) -> Self {
+        Self {
+            dpi,
+            ..Self::default()
+        }
+    }
+}
+
+/// A rendered page as straight (non-premultiplied) 8-bit RGBA pixels, row
+/// by row from the top
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedPage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RenderedPage {
+    /// Encode the bitmap as a PNG file
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        png::encode_rgba(self.width, self.height, &self.pixels)
+    }
+
+    /// Write the bitmap to a PNG file
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_png()?)?;
+        Ok(())
+    }
+}
+
+/// Render the page at `page_index` (zero-based) of a document
+pub fn render_page<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    page_index: u32,
+    options: &RenderOptions,
+) -> Result<RenderedPage> {
+    if !(options.dpi.is_finite() && options.dpi > 0.0) {
+        return Err(PdfError::InvalidOperation(format!(
+            "invalid rendering resolution {}",
+            options.dpi
+        )));
+    }
+    let page_count = document
+        .page_count()
+        .map_err(|e| PdfError::ParseError(e.to_string()))?;
+    if page_index >= page_count {
+        return Err(PdfError::InvalidPageNumber(page_index));
+    }
+    let page = document
+        .get_page(page_index)
+        .map_err(|e| PdfError::ParseError(e.to_string()))?;
+    let resolver: &dyn Resolver = document;
+
+    let area = page.crop_box.unwrap_or(page.media_box);
+    let [x0, y0, x1, y1] = [
+        area[0].min(area[2]),
+        area[1].min(area[3]),
+        area[0].max(area[2]),
+        area[1].max(area[3]),
+    ];
+    let s = options.dpi / 72.0;
+    let rotation = page.rotation.rem_euclid(360);
+    let (width, height) = match rotation {
+        90 | 270 => (y1 - y0, x1 - x0),
+        _ => (x1 - x0, y1 - y0),
+    };
+    let width = (width * s).ceil().max(1.0) as usize;
+    let height = (height * s).ceil().max(1.0) as usize;
+    if width.saturating_mul(height) > MAX_PIXELS {
+        return Err(PdfError::InvalidOperation(format!(
+            "rendered page would be {width}x{height} pixels"
+        )));
+    }
+
+    // Page space to device pixels, y down, rotated clockwise by /Rotate
+    let ctm = match rotation {
+        90 => TransformMatrix::new(0.0, s, s, 0.0, -y0 * s, -x0 * s),
+        180 => TransformMatrix::new(-s, 0.0, 0.0, s, x1 * s, -y0 * s),
+        270 => TransformMatrix::new(0.0, -s, -s, 0.0, y1 * s, x1 * s),
+        _ => TransformMatrix::new(s, 0.0, 0.0, -s, -x0 * s, y1 * s),
+    };
+
+    let mut canvas = Canvas::new(width, height);
+    if let Some(rgb) = options.background {
+        canvas.fill(rgb);
+    }
+    let mut renderer = Renderer::new(resolver, canvas);
+
+    let content = document
+        .get_page_content_streams(&page)
+        .map_err(|e| PdfError::ParseError(e.to_string()))?
+        .join(&b'\n');
+    let resources =
+        get_dict(resolver, &page.dict, "Resources").or(page.inherited_resources.clone());
+    renderer.draw_content(&content, resources, ctm, Some([x0, y0, x1, y1]));
+
+    if options.render_annotations {
+        // Broken annotations do not prevent rendering the page
+        let annotations = document
+            .get_page_annotations(page_index)
+            .unwrap_or_default();
+        for annotation in &annotations {
+            let flags = get(resolver, annotation, "F")
+                .and_then(|f| f.as_integer())
+                .unwrap_or(0);
+            if flags & (HIDDEN | NO_VIEW) != 0 {
+                continue;
+            }
+            let Some(rect) = get_rect(resolver, annotation, "Rect") else {
+                continue;
+            };
+            let normal =
+                get_dict(resolver, annotation, "AP").and_then(|ap| get(resolver, &ap, "N"));
+            let appearance = match normal {
+                Some(PdfObject::Stream(stream)) => Some(stream),
+                // Appearance states keyed by the annotation's /AS
+                Some(PdfObject::Dictionary(states)) => get_name(resolver, annotation, "AS")
+                    .and_then(|state| get(resolver, &states, &state))
+                    .and_then(|object| match object {
+                        PdfObject::Stream(stream) => Some(stream),
+                        _ => None,
+                    }),
+                _ => None,
+            };
+            if let Some(appearance) = appearance {
+                renderer.draw_appearance(&appearance, rect, ctm);
+            }
+        }
+    }
+
+    Ok(RenderedPage {
+        width: width as u32,
+        height: height as u32,
+        pixels: renderer.canvas.to_straight_rgba(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PdfReader;
+    use crate::{Color, Document, Page};
+    use std::io::Cursor;
+
+    /// A 200x100 point page with a red square in its lower left corner
+    fn sample_document(rotation: i32) -> PdfDocument<Cursor<Vec<u8>>> {
+        let mut page = Page::new(200.0, 100.0);
+        page.set_rotation(rotation);
+        page.graphics()
+            .set_fill_color(Color::rgb(1.0, 0.0, 0.0))
+            .rect(0.0, 0.0, 50.0, 50.0)
+            .fill();
+        let mut doc = Document::new();
+        doc.add_page(page);
+        let bytes = doc.to_bytes().unwrap();
+        PdfDocument::new(PdfReader::new(Cursor::new(bytes)).unwrap())
+    }
+
+    fn pixel(page: &RenderedPage, x: u32, y: u32) -> &[u8] {
+        let i = ((y * page.width + x) * 4) as usize;
+        &page.pixels[i..i + 4]
+    }
+
+    #[test]
+    fn test_render_page() {
+        let document = sample_document(0);
+        let page = document
+            .render_page(0, &RenderOptions::with_dpi(144.0))
+            .unwrap();
+        assert_eq!((page.width, page.height), (400, 200));
+        assert_eq!(pixel(&page, 10, 190), [255, 0, 0, 255]);
+        assert_eq!(pixel(&page, 10, 10), [255, 255, 255, 255]);
+        assert_eq!(pixel(&page, 390, 190), [255, 255, 255, 255]);
+
+        let transparent = RenderOptions {
+            background: None,
+            ..RenderOptions::default()
+        };
+        let page = document.render_page(0, &transparent).unwrap();
+        assert_eq!(pixel(&page, 150, 50), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rotated_page() {
+        let document = sample_document(90);
+        let page = document.render_page(0, &RenderOptions::default()).unwrap();
+        assert_eq!((page.width, page.height), (100, 200));
+        // Turned clockwise, the lower left corner is at the top left
+        assert_eq!(pixel(&page, 10, 10), [255, 0, 0, 255]);
+        assert_eq!(pixel(&page, 90, 190), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_png_output_and_invalid_options() {
+        let document = sample_document(0);
+        let png = document
+            .render_page(0, &RenderOptions::default())
+            .unwrap()
+            .to_png()
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(document
+            .render_page(0, &RenderOptions::with_dpi(0.0))
+            .is_err());
+        assert!(document.render_page(5, &RenderOptions::default()).is_err());
+    }
+}
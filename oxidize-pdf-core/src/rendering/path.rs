@@ -0,0 +1,270 @@
+//! Path construction, transformation and flattening
+
+use crate::coordinate_system::TransformMatrix;
+use crate::geometry::Point;
+
+/// One segment of a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Segment {
+    MoveTo(Point),
+    LineTo(Point),
+    CurveTo(Point, Point, Point),
+    Close,
+}
+
+/// A path made of subpaths, as built by the path construction operators
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Path {
+    segments: Vec<Segment>,
+    start: Option<Point>,
+    current: Option<Point>,
+}
+
+/// A flattened subpath: straight segments between consecutive points
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Polyline {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn current_point(&self) -> Option<Point> {
+        self.current
+    }
+
+    pub fn move_to(&mut self, p: Point) {
+        // Consecutive moves only keep the last one
+        if let Some(Segment::MoveTo(last)) = self.segments.last_mut() {
+            *last = p;
+        } else {
+            self.segments.push(Segment::MoveTo(p));
+        }
+        self.start = Some(p);
+        self.current = Some(p);
+    }
+
+    pub fn line_to(&mut self, p: Point) {
+        if self.current.is_none() {
+            self.move_to(p);
+            return;
+        }
+        self.segments.push(Segment::LineTo(p));
+        self.current = Some(p);
+    }
+
+    pub fn curve_to(&mut self, c1: Point, c2: Point, p: Point) {
+        if self.current.is_none() {
+            self.move_to(c1);
+        }
+        self.segments.push(Segment::CurveTo(c1, c2, p));
+        self.current = Some(p);
+    }
+
+    /// Quadratic Bézier, stored as the equivalent cubic
+    pub fn quad_to(&mut self, c: Point, p: Point) {
+        let p0 = self.current.unwrap_or(c);
+        let c1 = Point::new(
+            p0.x + 2.0 / 3.0 * (c.x - p0.x),
+            p0.y + 2.0 / 3.0 * (c.y - p0.y),
+        );
+        let c2 = Point::new(p.x + 2.0 / 3.0 * (c.x - p.x), p.y + 2.0 / 3.0 * (c.y - p.y));
+        self.curve_to(c1, c2, p);
+    }
+
+    pub fn close(&mut self) {
+        if self.current.is_some() && !matches!(self.segments.last(), Some(Segment::Close)) {
+            self.segments.push(Segment::Close);
+            self.current = self.start;
+        }
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.move_to(Point::new(x, y));
+        self.line_to(Point::new(x + width, y));
+        self.line_to(Point::new(x + width, y + height));
+        self.line_to(Point::new(x, y + height));
+        self.close();
+    }
+
+    /// Append another path
+    pub fn extend(&mut self, other: &Path) {
+        for segment in &other.segments {
+            match *segment {
+                Segment::MoveTo(p) => self.move_to(p),
+                Segment::LineTo(p) => self.line_to(p),
+                Segment::CurveTo(c1, c2, p) => self.curve_to(c1, c2, p),
+                Segment::Close => self.close(),
+            }
+        }
+    }
+
+    /// The path with every point mapped through `matrix`
+    pub fn transform(&self, matrix: &TransformMatrix) -> Path {
+        let map = |p: Point| matrix.transform_point(p);
+        Path {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| match *segment {
+                    Segment::MoveTo(p) => Segment::MoveTo(map(p)),
+                    Segment::LineTo(p) => Segment::LineTo(map(p)),
+                    Segment::CurveTo(c1, c2, p) => Segment::CurveTo(map(c1), map(c2), map(p)),
+                    Segment::Close => Segment::Close,
+                })
+                .collect(),
+            start: self.start.map(map),
+            current: self.current.map(map),
+        }
+    }
+
+    /// Approximate curves by line segments no farther than `tolerance`
+    /// from the curve
+    pub fn flatten(&self, tolerance: f64) -> Vec<Polyline> {
+        let mut polylines = Vec::new();
+        let mut points: Vec<Point> = Vec::new();
+        // A single point (a bare move, or the restart after a close) is
+        // not a subpath
+        let mut finish = |points: &mut Vec<Point>, closed: bool| {
+            if points.len() > 1 {
+                polylines.push(Polyline {
+                    points: std::mem::take(points),
+                    closed,
+                });
+            }
+            points.clear();
+        };
+
+        for segment in &self.segments {
+            match *segment {
+                Segment::MoveTo(p) => {
+                    finish(&mut points, false);
+                    points.push(p);
+                }
+                Segment::LineTo(p) => points.push(p),
+                Segment::CurveTo(c1, c2, p) => {
+                    let p0 = points.last().copied().unwrap_or(c1);
+                    flatten_cubic(p0, c1, c2, p, tolerance, &mut points);
+                }
+                Segment::Close => {
+                    let start = points.first().copied();
+                    finish(&mut points, true);
+                    // Drawing continues from the start of the closed subpath
+                    if let Some(start) = start {
+                        points.push(start);
+                    }
+                }
+            }
+        }
+        finish(&mut points, false);
+        polylines
+    }
+}
+
+fn flatten_cubic(p0: Point, p1: Point, p2: Point, p3: Point, tolerance: f64, out: &mut Vec<Point>) {
+    // Wang's formula bound on the second differences
+    let dd = |a: Point, b: Point, c: Point| {
+        let x = a.x - 2.0 * b.x + c.x;
+        let y = a.y - 2.0 * b.y + c.y;
+        (x * x + y * y).sqrt()
+    };
+    let l = dd(p0, p1, p2).max(dd(p1, p2, p3));
+    let n = ((0.75 * l / tolerance.max(1e-6)).sqrt().ceil() as usize).clamp(1, 500);
+
+    for i in 1..=n {
+        let t = i as f64 / n as f64;
+        let mt = 1.0 - t;
+        let a = mt * mt * mt;
+        let b = 3.0 * mt * mt * t;
+        let c = 3.0 * mt * t * t;
+        let d = t * t * t;
+        out.push(Point::new(
+            a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+            a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+        ));
+    }
+}
+
+/// Inverse of an affine matrix, `None` when it is singular
+pub(crate) fn invert(m: &TransformMatrix) -> Option<TransformMatrix> {
+    let det = m.a * m.d - m.b * m.c;
+    if det.abs() < 1e-12 || !det.is_finite() {
+        return None;
+    }
+    let a = m.d / det;
+    let b = -m.b / det;
+    let c = -m.c / det;
+    let d = m.a / det;
+    Some(TransformMatrix::new(
+        a,
+        b,
+        c,
+        d,
+        -(a * m.e + c * m.f),
+        -(b * m.e + d * m.f),
+    ))
+}
+
+/// Largest factor by which `m` stretches a length
+pub(crate) fn expansion(m: &TransformMatrix) -> f64 {
+    let x = (m.a * m.a + m.b * m.b).sqrt();
+    let y = (m.c * m.c + m.d * m.d).sqrt();
+    x.max(y)
+}
+
+/// Matrix from the six numbers of a PDF matrix array
+pub(crate) fn matrix(values: [f64; 6]) -> TransformMatrix {
+    TransformMatrix::new(
+        values[0], values[1], values[2], values[3], values[4], values[5],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_rectangle() {
+        let mut path = Path::new();
+        path.rect(0.0, 0.0, 10.0, 5.0);
+        let polylines = path.flatten(0.25);
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].closed);
+        assert_eq!(polylines[0].points.len(), 4);
+    }
+
+    #[test]
+    fn test_flatten_curve_stays_close() {
+        // Quarter circle of radius 100
+        let k = 0.552_284_75 * 100.0;
+        let mut path = Path::new();
+        path.move_to(Point::new(100.0, 0.0));
+        path.curve_to(
+            Point::new(100.0, k),
+            Point::new(k, 100.0),
+            Point::new(0.0, 100.0),
+        );
+        let polylines = path.flatten(0.1);
+        assert!(polylines[0].points.len() > 8);
+        for p in &polylines[0].points {
+            let r = (p.x * p.x + p.y * p.y).sqrt();
+            assert!((r - 100.0).abs() < 0.2, "radius {r}");
+        }
+    }
+
+    #[test]
+    fn test_invert() {
+        let m = TransformMatrix::new(2.0, 1.0, -1.0, 3.0, 5.0, 7.0);
+        let inverse = invert(&m).unwrap();
+        let p = inverse.transform_point(m.transform_point(Point::new(3.0, -4.0)));
+        assert!((p.x - 3.0).abs() < 1e-9 && (p.y + 4.0).abs() < 1e-9);
+        assert!(invert(&TransformMatrix::new(1.0, 2.0, 2.0, 4.0, 0.0, 0.0)).is_none());
+    }
+}
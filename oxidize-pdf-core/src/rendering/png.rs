@@ -0,0 +1,153 @@
+//! Minimal PNG encoder for rendered pages
+
+use crate::compression::compress;
+use crate::error::Result;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Encode 8-bit straight RGBA pixels as a PNG file
+pub(crate) fn encode_rgba(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>> {
+    let stride = width as usize * 4;
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    let mut previous = vec![0u8; stride];
+    let mut candidate = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+    for row in pixels.chunks_exact(stride.max(1)).take(height as usize) {
+        // Pick the filter with the smallest sum of absolute differences
+        let mut best_type = 0;
+        let mut best_cost = u64::MAX;
+        for filter in 0..5u8 {
+            apply_filter(filter, row, &previous, &mut candidate);
+            let cost = candidate
+                .iter()
+                .map(|&b| (b as i8).unsigned_abs() as u64)
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_type = filter;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        filtered.push(best_type);
+        filtered.extend_from_slice(&best);
+        previous.copy_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per sample, RGBA, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &compress(&filtered)?);
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+fn apply_filter(filter: u8, row: &[u8], previous: &[u8], out: &mut [u8]) {
+    for i in 0..row.len() {
+        let left = if i >= 4 { row[i - 4] } else { 0 };
+        let up = previous[i];
+        let upper_left = if i >= 4 { previous[i - 4] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            _ => paeth(left, up, upper_left),
+        };
+        out[i] = row[i].wrapping_sub(predictor);
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let pixels: Vec<u8> = (0..3 * 2 * 4).map(|i| (i * 11) as u8).collect();
+        let png = encode_rgba(3, 2, &pixels).unwrap();
+        assert_eq!(&png[..8], &SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+        // Undo the filters of the IDAT data
+        let idat_length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&png[41..41 + idat_length])
+            .read_to_end(&mut raw)
+            .unwrap();
+        let mut decoded: Vec<u8> = Vec::new();
+        let mut previous = vec![0u8; 12];
+        for line in raw.chunks_exact(13) {
+            let mut row = vec![0u8; 12];
+            for i in 0..12 {
+                let left = if i >= 4 { row[i - 4] } else { 0 };
+                let up = previous[i];
+                let upper_left = if i >= 4 { previous[i - 4] } else { 0 };
+                let predictor = match line[0] {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    _ => paeth(left, up, upper_left),
+                };
+                row[i] = line[i + 1].wrapping_add(predictor);
+            }
+            decoded.extend_from_slice(&row);
+            previous = row;
+        }
+        assert_eq!(decoded, pixels);
+    }
+}
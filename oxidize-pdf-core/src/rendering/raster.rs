@@ -0,0 +1,328 @@
+//! Scanline rasterization of flattened paths into anti-aliased coverage masks
+
+use super::path::Polyline;
+use crate::graphics::WindingRule;
+
+/// Vertical samples per pixel; coverage along a sample line is exact
+const SUBSAMPLES: usize = 5;
+
+/// Integer device rectangle, `x0..x1` by `y0..y1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IntRect {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+impl IntRect {
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.x1 - self.x0).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.y1 - self.y0).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x1 <= self.x0 || self.y1 <= self.y0
+    }
+
+    pub fn intersect(&self, other: &IntRect) -> IntRect {
+        IntRect {
+            x0: self.x0.max(other.x0),
+            y0: self.y0.max(other.y0),
+            x1: self.x1.min(other.x1),
+            y1: self.y1.min(other.y1),
+        }
+    }
+}
+
+/// Coverage values (0 = outside, 255 = inside) over a device rectangle
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Mask {
+    pub bounds: IntRect,
+    pub data: Vec<u8>,
+}
+
+impl Mask {
+    /// A mask covering all of `bounds`
+    pub fn full(bounds: IntRect) -> Self {
+        Self {
+            bounds,
+            data: vec![255; bounds.width() * bounds.height()],
+        }
+    }
+
+    /// Coverage at a device pixel, zero outside the bounds
+    pub fn get(&self, x: i32, y: i32) -> u8 {
+        let b = &self.bounds;
+        if x < b.x0 || y < b.y0 || x >= b.x1 || y >= b.y1 {
+            return 0;
+        }
+        self.data[(y - b.y0) as usize * b.width() + (x - b.x0) as usize]
+    }
+
+    /// Pixel-wise product of two masks over the overlap of their bounds
+    pub fn intersect(&self, other: &Mask) -> Mask {
+        let bounds = self.bounds.intersect(&other.bounds);
+        if bounds.is_empty() {
+            return Mask {
+                bounds: IntRect::new(0, 0, 0, 0),
+                data: Vec::new(),
+            };
+        }
+        let mut data = Vec::with_capacity(bounds.width() * bounds.height());
+        for y in bounds.y0..bounds.y1 {
+            for x in bounds.x0..bounds.x1 {
+                data.push(mul8(self.get(x, y), other.get(x, y)));
+            }
+        }
+        Mask { bounds, data }
+    }
+}
+
+/// Product of two 0..=255 fractions
+pub(crate) fn mul8(a: u8, b: u8) -> u8 {
+    let t = a as u32 * b as u32 + 128;
+    ((t + (t >> 8)) >> 8) as u8
+}
+
+struct Edge {
+    x0: f64,
+    y0: f64,
+    y1: f64,
+    slope: f64,
+    winding: i32,
+}
+
+/// Coverage of the filled polylines (each implicitly closed) within `clip`
+pub(crate) fn rasterize(polylines: &[Polyline], rule: WindingRule, clip: IntRect) -> Mask {
+    let mut edges = Vec::new();
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+    for polyline in polylines {
+        let points = &polyline.points;
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            if !(a.x.is_finite() && a.y.is_finite() && b.x.is_finite() && b.y.is_finite()) {
+                continue;
+            }
+            min_x = min_x.min(a.x);
+            max_x = max_x.max(a.x);
+            min_y = min_y.min(a.y);
+            max_y = max_y.max(a.y);
+            if a.y == b.y {
+                continue;
+            }
+            let (top, bottom, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+            edges.push(Edge {
+                x0: top.x,
+                y0: top.y,
+                y1: bottom.y,
+                slope: (bottom.x - top.x) / (bottom.y - top.y),
+                winding,
+            });
+        }
+    }
+
+    let bounds = if edges.is_empty() {
+        IntRect::new(0, 0, 0, 0)
+    } else {
+        IntRect::new(
+            min_x.floor().max(i32::MIN as f64 / 2.0) as i32,
+            min_y.floor().max(i32::MIN as f64 / 2.0) as i32,
+            max_x.ceil().min(i32::MAX as f64 / 2.0) as i32 + 1,
+            max_y.ceil().min(i32::MAX as f64 / 2.0) as i32 + 1,
+        )
+        .intersect(&clip)
+    };
+    if bounds.is_empty() {
+        return Mask {
+            bounds: IntRect::new(0, 0, 0, 0),
+            data: Vec::new(),
+        };
+    }
+
+    edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+    let width = bounds.width();
+    let mut data = vec![0u8; width * bounds.height()];
+    let mut accumulator = vec![0f32; width];
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    let mut next_edge = 0;
+    let sample_weight = 1.0 / SUBSAMPLES as f32;
+
+    for (row, y) in (bounds.y0..bounds.y1).enumerate() {
+        accumulator.iter_mut().for_each(|v| *v = 0.0);
+        let mut touched = false;
+        for sample in 0..SUBSAMPLES {
+            let sy = y as f64 + (sample as f64 + 0.5) / SUBSAMPLES as f64;
+            while next_edge < edges.len() && edges[next_edge].y0 <= sy {
+                active.push(next_edge);
+                next_edge += 1;
+            }
+            active.retain(|&i| edges[i].y1 > sy);
+
+            crossings.clear();
+            for &i in &active {
+                let edge = &edges[i];
+                if edge.y0 <= sy {
+                    crossings.push((edge.x0 + (sy - edge.y0) * edge.slope, edge.winding));
+                }
+            }
+            if crossings.is_empty() {
+                continue;
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            let mut span_start = 0.0;
+            for &(x, w) in crossings.iter() {
+                let was_inside = inside(winding, rule);
+                winding += w;
+                let is_inside = inside(winding, rule);
+                if !was_inside && is_inside {
+                    span_start = x;
+                } else if was_inside && !is_inside {
+                    add_span(
+                        &mut accumulator,
+                        span_start - bounds.x0 as f64,
+                        x - bounds.x0 as f64,
+                        sample_weight,
+                    );
+                    touched = true;
+                }
+            }
+        }
+        if touched {
+            let out = &mut data[row * width..(row + 1) * width];
+            for (value, &coverage) in out.iter_mut().zip(accumulator.iter()) {
+                *value = (coverage.min(1.0) * 255.0 + 0.5) as u8;
+            }
+        }
+    }
+
+    Mask { bounds, data }
+}
+
+fn inside(winding: i32, rule: WindingRule) -> bool {
+    match rule {
+        WindingRule::NonZero => winding != 0,
+        WindingRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+/// Add the horizontal coverage of `start..end` to the pixels it overlaps
+fn add_span(accumulator: &mut [f32], start: f64, end: f64, weight: f32) {
+    let width = accumulator.len() as f64;
+    let start = start.clamp(0.0, width);
+    let end = end.clamp(0.0, width);
+    if end <= start {
+        return;
+    }
+    let first = start.floor() as usize;
+    let last = end.floor() as usize;
+    if first == last {
+        accumulator[first] += (end - start) as f32 * weight;
+        return;
+    }
+    accumulator[first] += (first as f64 + 1.0 - start) as f32 * weight;
+    for value in &mut accumulator[first + 1..last] {
+        *value += weight;
+    }
+    if last < accumulator.len() {
+        accumulator[last] += (end - last as f64) as f32 * weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+
+    fn square(x0: f64, y0: f64, x1: f64, y1: f64, clockwise: bool) -> Polyline {
+        let mut points = vec![
+            Point::new(x0, y0),
+            Point::new(x1, y0),
+            Point::new(x1, y1),
+            Point::new(x0, y1),
+        ];
+        if clockwise {
+            points.reverse();
+        }
+        Polyline {
+            points,
+            closed: true,
+        }
+    }
+
+    #[test]
+    fn test_pixel_aligned_square() {
+        let clip = IntRect::new(0, 0, 10, 10);
+        let mask = rasterize(
+            &[square(2.0, 2.0, 5.0, 4.0, false)],
+            WindingRule::NonZero,
+            clip,
+        );
+        assert_eq!(mask.get(2, 2), 255);
+        assert_eq!(mask.get(4, 3), 255);
+        assert_eq!(mask.get(5, 3), 0);
+        assert_eq!(mask.get(3, 4), 0);
+        assert_eq!(mask.get(1, 2), 0);
+    }
+
+    #[test]
+    fn test_partial_coverage() {
+        let clip = IntRect::new(0, 0, 4, 4);
+        let mask = rasterize(
+            &[square(0.5, 0.0, 2.0, 4.0, false)],
+            WindingRule::NonZero,
+            clip,
+        );
+        assert_eq!(mask.get(0, 1), 128);
+        assert_eq!(mask.get(1, 1), 255);
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        let clip = IntRect::new(0, 0, 10, 10);
+        let shapes = [
+            square(0.0, 0.0, 10.0, 10.0, false),
+            square(3.0, 3.0, 6.0, 6.0, false),
+        ];
+        let nonzero = rasterize(&shapes, WindingRule::NonZero, clip);
+        let evenodd = rasterize(&shapes, WindingRule::EvenOdd, clip);
+        assert_eq!(nonzero.get(4, 4), 255);
+        assert_eq!(evenodd.get(4, 4), 0);
+        assert_eq!(evenodd.get(1, 1), 255);
+
+        // An opposite inner square is a hole under both rules
+        let shapes = [
+            square(0.0, 0.0, 10.0, 10.0, false),
+            square(3.0, 3.0, 6.0, 6.0, true),
+        ];
+        assert_eq!(rasterize(&shapes, WindingRule::NonZero, clip).get(4, 4), 0);
+    }
+
+    #[test]
+    fn test_clip_and_intersect() {
+        let clip = IntRect::new(0, 0, 5, 5);
+        let a = rasterize(
+            &[square(-5.0, -5.0, 3.0, 20.0, false)],
+            WindingRule::NonZero,
+            clip,
+        );
+        assert_eq!(a.bounds, IntRect::new(0, 0, 4, 5));
+        let b = Mask::full(IntRect::new(2, 2, 5, 5));
+        let both = a.intersect(&b);
+        assert_eq!(both.get(2, 2), 255);
+        assert_eq!(both.get(3, 3), 0);
+        assert_eq!(both.get(1, 1), 0);
+    }
+}
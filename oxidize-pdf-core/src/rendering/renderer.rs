@@ -0,0 +1,1235 @@
+//! Content stream interpreter painting onto a canvas
+
+use super::canvas::{blend_mode, premultiply, Canvas, Composite, Paint};
+use super::color::{to_bytes, ColorSpace};
+use super::font::{Font, Glyph};
+use super::function::get_function;
+use super::image::DecodedImage;
+use super::path::{expansion, invert, matrix, Path, Polyline};
+use super::raster::{rasterize, IntRect, Mask};
+use super::shading::Shading;
+use super::stroke::{line_cap, line_join, stroke, StrokeStyle};
+use crate::coordinate_system::TransformMatrix;
+use crate::geometry::Point;
+use crate::graphics::{BlendMode, WindingRule};
+use crate::parser::content::{ContentOperation, ContentParser, TextElement};
+use crate::parser::objects::{PdfDictionary, PdfObject, PdfStream};
+use crate::parser::resolve::{
+    from_content_dict, get, get_dict, get_matrix, get_name, get_number, get_numbers, get_rect,
+    numbers, stream_data, Resolver,
+};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Nesting limit for forms, patterns, soft masks and Type 3 glyphs
+const MAX_DEPTH: usize = 12;
+
+/// Largest distance in device pixels between a curve and its flattening
+const TOLERANCE: f64 = 0.2;
+
+/// Largest side of a rendered tiling pattern cell, in pixels
+const MAX_TILE: f64 = 2048.0;
+
+/// A color selection: a color space with components, or a pattern
+#[derive(Debug, Clone)]
+struct Color {
+    space: Rc<ColorSpace>,
+    components: Vec<f64>,
+    /// Pattern resource name when painting with a pattern
+    pattern: Option<String>,
+}
+
+impl Color {
+    fn new(space: ColorSpace) -> Self {
+        Self {
+            components: space.initial_color(),
+            space: Rc::new(space),
+            pattern: None,
+        }
+    }
+
+    fn rgb(&self) -> [u8; 3] {
+        let space = match &*self.space {
+            ColorSpace::Pattern(Some(base)) => base,
+            space => space,
+        };
+        to_bytes(space.to_rgb(&self.components))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TextState {
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scaling: f64,
+    leading: f64,
+    font: Option<Rc<Font>>,
+    size: f64,
+    render_mode: i32,
+    rise: f64,
+}
+
+#[derive(Debug, Clone)]
+struct GraphicsState {
+    ctm: TransformMatrix,
+    clip: Option<Rc<Mask>>,
+    stroke_color: Color,
+    fill_color: Color,
+    style: StrokeStyle,
+    stroke_alpha: u8,
+    fill_alpha: u8,
+    blend: BlendMode,
+    soft_mask: Option<Rc<Mask>>,
+    text: TextState,
+}
+
+impl GraphicsState {
+    fn new(ctm: TransformMatrix) -> Self {
+        Self {
+            ctm,
+            clip: None,
+            stroke_color: Color::new(ColorSpace::DeviceGray),
+            fill_color: Color::new(ColorSpace::DeviceGray),
+            style: StrokeStyle::default(),
+            stroke_alpha: 255,
+            fill_alpha: 255,
+            blend: BlendMode::Normal,
+            soft_mask: None,
+            text: TextState {
+                char_spacing: 0.0,
+                word_spacing: 0.0,
+                horizontal_scaling: 1.0,
+                leading: 0.0,
+                font: None,
+                size: 0.0,
+                render_mode: 0,
+                rise: 0.0,
+            },
+        }
+    }
+}
+
+/// State of one content stream being executed
+struct Context {
+    resources: Option<PdfDictionary>,
+    gs: GraphicsState,
+    stack: Vec<GraphicsState>,
+    path: Path,
+    pending_clip: Option<WindingRule>,
+    text_matrix: TransformMatrix,
+    line_matrix: TransformMatrix,
+    /// Glyph outlines in device space accumulated by clipping render modes
+    text_clip: Option<Path>,
+    /// Matrix of the stream's default space, where patterns are defined
+    base_ctm: TransformMatrix,
+}
+
+impl Context {
+    fn new(resources: Option<PdfDictionary>, gs: GraphicsState) -> Self {
+        Self {
+            resources,
+            base_ctm: gs.ctm,
+            gs,
+            stack: Vec::new(),
+            path: Path::new(),
+            pending_clip: None,
+            text_matrix: identity(),
+            line_matrix: identity(),
+            text_clip: None,
+        }
+    }
+
+    /// A named entry of a resource category (`Font`, `XObject`, ...)
+    fn resource(&self, resolver: &dyn Resolver, category: &str, name: &str) -> Option<PdfObject> {
+        let resources = self.resources.as_ref()?;
+        let category = get_dict(resolver, resources, category)?;
+        category.get(name).cloned()
+    }
+}
+
+/// Colors of a painting operation
+enum Source {
+    Solid([u8; 4]),
+    Pixels(Box<dyn Fn(i32, i32) -> [u8; 4]>),
+}
+
+fn identity() -> TransformMatrix {
+    TransformMatrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+}
+
+fn translate(x: f64, y: f64) -> TransformMatrix {
+    TransformMatrix::new(1.0, 0.0, 0.0, 1.0, x, y)
+}
+
+fn map_polylines(polylines: Vec<Polyline>, m: &TransformMatrix) -> Vec<Polyline> {
+    polylines
+        .into_iter()
+        .map(|mut polyline| {
+            for p in &mut polyline.points {
+                *p = m.transform_point(*p);
+            }
+            polyline
+        })
+        .collect()
+}
+
+fn rect_path(rect: [f64; 4]) -> Path {
+    let mut path = Path::new();
+    path.rect(rect[0], rect[1], rect[2] - rect[0], rect[3] - rect[1]);
+    path
+}
+
+/// Interprets content streams onto a canvas
+pub(crate) struct Renderer<'a> {
+    resolver: &'a dyn Resolver,
+    pub canvas: Canvas,
+    fonts: HashMap<(u32, u16), Rc<Font>>,
+    depth: usize,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(resolver: &'a dyn Resolver, canvas: Canvas) -> Self {
+        Self {
+            resolver,
+            canvas,
+            fonts: HashMap::new(),
+            depth: 0,
+        }
+    }
+
+    /// Execute a content stream with `ctm` mapping its default space to
+    /// device space
+    pub fn draw_content(
+        &mut self,
+        content: &[u8],
+        resources: Option<PdfDictionary>,
+        ctm: TransformMatrix,
+        clip: Option<[f64; 4]>,
+    ) {
+        let mut gs = GraphicsState::new(ctm);
+        if let Some(rect) = clip {
+            gs.clip = Some(Rc::new(self.coverage(
+                &gs,
+                &rect_path(rect).transform(&ctm),
+                WindingRule::NonZero,
+            )));
+        }
+        self.run_stream(content, Context::new(resources, gs));
+    }
+
+    /// Draw an annotation appearance stream, its transformed BBox fitted
+    /// to the annotation `rect` (ISO 32000-1 §12.5.5)
+    pub fn draw_appearance(
+        &mut self,
+        appearance: &PdfStream,
+        rect: [f64; 4],
+        ctm: TransformMatrix,
+    ) {
+        let resolver = self.resolver;
+        let Some(bbox) = get_rect(resolver, &appearance.dict, "BBox") else {
+            return;
+        };
+        let form_matrix = matrix(get_matrix(resolver, &appearance.dict, "Matrix"));
+        let corners = [
+            (bbox[0], bbox[1]),
+            (bbox[2], bbox[1]),
+            (bbox[0], bbox[3]),
+            (bbox[2], bbox[3]),
+        ]
+        .map(|(x, y)| form_matrix.transform_point(Point::new(x, y)));
+        let x0 = corners.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+        let x1 = corners
+            .iter()
+            .map(|p| p.x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let y0 = corners.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+        let y1 = corners
+            .iter()
+            .map(|p| p.y)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if x1 - x0 < 1e-9 || y1 - y0 < 1e-9 {
+            return;
+        }
+        let sx = (rect[2] - rect[0]) / (x1 - x0);
+        let sy = (rect[3] - rect[1]) / (y1 - y0);
+        let fit = TransformMatrix::new(sx, 0.0, 0.0, sy, rect[0] - x0 * sx, rect[1] - y0 * sy);
+        self.draw_form_with(appearance, None, GraphicsState::new(ctm.multiply(&fit)));
+    }
+
+    fn run_stream(&mut self, content: &[u8], mut ctx: Context) {
+        if self.depth >= MAX_DEPTH {
+            tracing::debug!("Content nesting too deep, skipping stream");
+            return;
+        }
+        let operations = match ContentParser::parse(content) {
+            Ok(operations) => operations,
+            Err(e) => {
+                tracing::debug!("Skipping unparsable content stream: {e}");
+                return;
+            }
+        };
+        self.depth += 1;
+        for operation in &operations {
+            self.execute(operation, &mut ctx);
+        }
+        self.depth -= 1;
+    }
+
+    fn execute(&mut self, operation: &ContentOperation, ctx: &mut Context) {
+        use ContentOperation as Op;
+        let p = |x: f32, y: f32| Point::new(x as f64, y as f64);
+        match operation {
+            // Graphics state
+            Op::SaveGraphicsState => ctx.stack.push(ctx.gs.clone()),
+            Op::RestoreGraphicsState => {
+                if let Some(gs) = ctx.stack.pop() {
+                    ctx.gs = gs;
+                }
+            }
+            Op::SetTransformMatrix(a, b, c, d, e, f) => {
+                let m = matrix([*a, *b, *c, *d, *e, *f].map(|v| v as f64));
+                ctx.gs.ctm = ctx.gs.ctm.multiply(&m);
+            }
+            Op::SetLineWidth(w) => ctx.gs.style.width = w.abs() as f64,
+            Op::SetLineCap(cap) => ctx.gs.style.cap = line_cap(*cap),
+            Op::SetLineJoin(join) => ctx.gs.style.join = line_join(*join),
+            Op::SetMiterLimit(limit) => ctx.gs.style.miter_limit = *limit as f64,
+            Op::SetDashPattern(dash, phase) => {
+                ctx.gs.style.dash = dash.iter().map(|&d| d as f64).collect();
+                ctx.gs.style.dash_phase = *phase as f64;
+            }
+            Op::SetGraphicsStateParams(name) => self.set_ext_gstate(ctx, name),
+
+            // Path construction
+            Op::MoveTo(x, y) => ctx.path.move_to(p(*x, *y)),
+            Op::LineTo(x, y) => ctx.path.line_to(p(*x, *y)),
+            Op::CurveTo(x1, y1, x2, y2, x3, y3) => {
+                ctx.path.curve_to(p(*x1, *y1), p(*x2, *y2), p(*x3, *y3))
+            }
+            Op::CurveToV(x2, y2, x3, y3) => {
+                let c1 = ctx.path.current_point().unwrap_or(p(*x2, *y2));
+                ctx.path.curve_to(c1, p(*x2, *y2), p(*x3, *y3));
+            }
+            Op::CurveToY(x1, y1, x3, y3) => {
+                ctx.path.curve_to(p(*x1, *y1), p(*x3, *y3), p(*x3, *y3))
+            }
+            Op::ClosePath => ctx.path.close(),
+            Op::Rectangle(x, y, w, h) => ctx.path.rect(*x as f64, *y as f64, *w as f64, *h as f64),
+
+            // Path painting
+            Op::Stroke => self.paint_path(ctx, None, true),
+            Op::CloseStroke => {
+                ctx.path.close();
+                self.paint_path(ctx, None, true);
+            }
+            Op::Fill => self.paint_path(ctx, Some(WindingRule::NonZero), false),
+            Op::FillEvenOdd => self.paint_path(ctx, Some(WindingRule::EvenOdd), false),
+            Op::FillStroke => self.paint_path(ctx, Some(WindingRule::NonZero), true),
+            Op::FillStrokeEvenOdd => self.paint_path(ctx, Some(WindingRule::EvenOdd), true),
+            Op::CloseFillStroke => {
+                ctx.path.close();
+                self.paint_path(ctx, Some(WindingRule::NonZero), true);
+            }
+            Op::CloseFillStrokeEvenOdd => {
+                ctx.path.close();
+                self.paint_path(ctx, Some(WindingRule::EvenOdd), true);
+            }
+            Op::EndPath => self.paint_path(ctx, None, false),
+            Op::Clip => ctx.pending_clip = Some(WindingRule::NonZero),
+            Op::ClipEvenOdd => ctx.pending_clip = Some(WindingRule::EvenOdd),
+
+            // Color
+            Op::SetStrokingColorSpace(name) => {
+                if let Some(space) =
+                    ColorSpace::from_name(self.resolver, name, ctx.resources.as_ref())
+                {
+                    ctx.gs.stroke_color = Color::new(space);
+                }
+            }
+            Op::SetNonStrokingColorSpace(name) => {
+                if let Some(space) =
+                    ColorSpace::from_name(self.resolver, name, ctx.resources.as_ref())
+                {
+                    ctx.gs.fill_color = Color::new(space);
+                }
+            }
+            Op::SetStrokingColor(components) => {
+                set_components(&mut ctx.gs.stroke_color, components, None)
+            }
+            Op::SetNonStrokingColor(components) => {
+                set_components(&mut ctx.gs.fill_color, components, None)
+            }
+            Op::SetStrokingPattern(name, components) => {
+                set_components(&mut ctx.gs.stroke_color, components, Some(name))
+            }
+            Op::SetNonStrokingPattern(name, components) => {
+                set_components(&mut ctx.gs.fill_color, components, Some(name))
+            }
+            Op::SetStrokingGray(g) => {
+                ctx.gs.stroke_color = device_color(ColorSpace::DeviceGray, &[*g])
+            }
+            Op::SetNonStrokingGray(g) => {
+                ctx.gs.fill_color = device_color(ColorSpace::DeviceGray, &[*g])
+            }
+            Op::SetStrokingRGB(r, g, b) => {
+                ctx.gs.stroke_color = device_color(ColorSpace::DeviceRgb, &[*r, *g, *b])
+            }
+            Op::SetNonStrokingRGB(r, g, b) => {
+                ctx.gs.fill_color = device_color(ColorSpace::DeviceRgb, &[*r, *g, *b])
+            }
+            Op::SetStrokingCMYK(c, m, y, k) => {
+                ctx.gs.stroke_color = device_color(ColorSpace::DeviceCmyk, &[*c, *m, *y, *k])
+            }
+            Op::SetNonStrokingCMYK(c, m, y, k) => {
+                ctx.gs.fill_color = device_color(ColorSpace::DeviceCmyk, &[*c, *m, *y, *k])
+            }
+
+            // Shadings, images and forms
+            Op::ShadingFill(name) => self.shading_fill(ctx, name),
+            Op::PaintXObject(name) => self.paint_xobject(ctx, name),
+            Op::InlineImage { params, data } => {
+                let dict = from_content_dict(params.iter());
+                if let Some(image) =
+                    DecodedImage::decode(self.resolver, &dict, data, ctx.resources.as_ref())
+                {
+                    self.draw_image(ctx, image);
+                }
+            }
+
+            // Text
+            Op::BeginText => {
+                ctx.text_matrix = identity();
+                ctx.line_matrix = identity();
+            }
+            Op::EndText => {
+                if let Some(path) = ctx.text_clip.take() {
+                    let clip = self.coverage(&ctx.gs, &path, WindingRule::NonZero);
+                    ctx.gs.clip = Some(Rc::new(clip));
+                }
+            }
+            Op::SetCharSpacing(v) => ctx.gs.text.char_spacing = *v as f64,
+            Op::SetWordSpacing(v) => ctx.gs.text.word_spacing = *v as f64,
+            Op::SetHorizontalScaling(v) => ctx.gs.text.horizontal_scaling = *v as f64 / 100.0,
+            Op::SetLeading(v) => ctx.gs.text.leading = *v as f64,
+            Op::SetFont(name, size) => {
+                ctx.gs.text.size = *size as f64;
+                if let Some(object) = ctx.resource(self.resolver, "Font", name) {
+                    ctx.gs.text.font = self.load_font(&object);
+                }
+            }
+            Op::SetTextRenderMode(mode) => ctx.gs.text.render_mode = *mode,
+            Op::SetTextRise(v) => ctx.gs.text.rise = *v as f64,
+            Op::MoveText(tx, ty) => next_line(ctx, *tx as f64, *ty as f64),
+            Op::MoveTextSetLeading(tx, ty) => {
+                ctx.gs.text.leading = -*ty as f64;
+                next_line(ctx, *tx as f64, *ty as f64);
+            }
+            Op::SetTextMatrix(a, b, c, d, e, f) => {
+                ctx.text_matrix = matrix([*a, *b, *c, *d, *e, *f].map(|v| v as f64));
+                ctx.line_matrix = ctx.text_matrix;
+            }
+            Op::NextLine => {
+                let leading = ctx.gs.text.leading;
+                next_line(ctx, 0.0, -leading);
+            }
+            Op::ShowText(bytes) => self.show_text(ctx, bytes),
+            Op::ShowTextArray(elements) => {
+                for element in elements {
+                    match element {
+                        TextElement::Text(bytes) => self.show_text(ctx, bytes),
+                        TextElement::Spacing(amount) => adjust_text(ctx, *amount as f64),
+                    }
+                }
+            }
+            Op::NextLineShowText(bytes) => {
+                let leading = ctx.gs.text.leading;
+                next_line(ctx, 0.0, -leading);
+                self.show_text(ctx, bytes);
+            }
+            Op::SetSpacingNextLineShowText(word, character, bytes) => {
+                ctx.gs.text.word_spacing = *word as f64;
+                ctx.gs.text.char_spacing = *character as f64;
+                let leading = ctx.gs.text.leading;
+                next_line(ctx, 0.0, -leading);
+                self.show_text(ctx, bytes);
+            }
+
+            // Marked content, compatibility sections, rendering intent,
+            // flatness and Type 3 glyph metrics do not change the output
+            _ => {}
+        }
+    }
+
+    /// Paint the current path and end it, applying a pending clip
+    fn paint_path(&mut self, ctx: &mut Context, fill: Option<WindingRule>, stroke: bool) {
+        let path = std::mem::take(&mut ctx.path);
+        if let Some(rule) = fill {
+            self.fill_path(ctx, &path, rule);
+        }
+        if stroke {
+            self.stroke_path(ctx, &path);
+        }
+        if let Some(rule) = ctx.pending_clip.take() {
+            let clip = self.coverage(&ctx.gs, &path.transform(&ctx.gs.ctm), rule);
+            ctx.gs.clip = Some(Rc::new(clip));
+        }
+    }
+
+    /// Coverage of a device-space path within the current clip
+    fn coverage(&self, gs: &GraphicsState, device_path: &Path, rule: WindingRule) -> Mask {
+        let polylines = device_path.flatten(TOLERANCE);
+        self.coverage_of(gs, &polylines, rule)
+    }
+
+    fn coverage_of(&self, gs: &GraphicsState, polylines: &[Polyline], rule: WindingRule) -> Mask {
+        let mut bounds = self.canvas.bounds();
+        if let Some(clip) = &gs.clip {
+            bounds = bounds.intersect(&clip.bounds);
+        }
+        let mask = rasterize(polylines, rule, bounds);
+        match &gs.clip {
+            Some(clip) => mask.intersect(clip),
+            None => mask,
+        }
+    }
+
+    fn fill_path(&mut self, ctx: &Context, path: &Path, rule: WindingRule) {
+        if path.is_empty() {
+            return;
+        }
+        let mask = self.coverage(&ctx.gs, &path.transform(&ctx.gs.ctm), rule);
+        self.paint(ctx, &mask, false);
+    }
+
+    fn stroke_path(&mut self, ctx: &Context, path: &Path) {
+        let ctm = ctx.gs.ctm;
+        let scale = expansion(&ctm);
+        if path.is_empty() || scale <= 0.0 || !scale.is_finite() {
+            return;
+        }
+        // Lines thinner than a pixel (including width 0) are drawn one
+        // pixel wide
+        let mut style = ctx.gs.style.clone();
+        style.width = style.width.max(1.0 / scale);
+        let tolerance = TOLERANCE / scale;
+        let outline = stroke(&path.flatten(tolerance), &style, tolerance);
+        let mask = self.coverage_of(&ctx.gs, &map_polylines(outline, &ctm), WindingRule::NonZero);
+        self.paint(ctx, &mask, true);
+    }
+
+    /// Paint the fill or stroke color through a coverage mask
+    fn paint(&mut self, ctx: &Context, mask: &Mask, stroke: bool) {
+        if mask.bounds.is_empty() {
+            return;
+        }
+        let color = if stroke {
+            &ctx.gs.stroke_color
+        } else {
+            &ctx.gs.fill_color
+        };
+        let Some(source) = self.color_source(ctx, color, mask.bounds) else {
+            return;
+        };
+        let composite = Composite {
+            alpha: if stroke {
+                ctx.gs.stroke_alpha
+            } else {
+                ctx.gs.fill_alpha
+            },
+            blend: &ctx.gs.blend,
+            soft_mask: ctx.gs.soft_mask.as_deref(),
+        };
+        match &source {
+            Source::Solid(color) => self.canvas.paint(mask, &Paint::Solid(*color), &composite),
+            Source::Pixels(f) => self
+                .canvas
+                .paint(mask, &Paint::Pixels(f.as_ref()), &composite),
+        }
+    }
+
+    fn color_source(&mut self, ctx: &Context, color: &Color, bounds: IntRect) -> Option<Source> {
+        if let Some(name) = &color.pattern {
+            let object = ctx.resource(self.resolver, "Pattern", name)?;
+            return self.pattern_source(ctx, &self.resolver.lookup(&object), color, bounds);
+        }
+        if color.space.is_invisible() || matches!(*color.space, ColorSpace::Pattern(_)) {
+            return None;
+        }
+        Some(Source::Solid(premultiply(color.rgb(), 255)))
+    }
+
+    fn pattern_source(
+        &mut self,
+        ctx: &Context,
+        pattern: &PdfObject,
+        color: &Color,
+        bounds: IntRect,
+    ) -> Option<Source> {
+        let dict = pattern.as_dict()?;
+        let pattern_matrix =
+            ctx.base_ctm
+                .multiply(&matrix(get_matrix(self.resolver, dict, "Matrix")));
+        match get_number(self.resolver, dict, "PatternType")? as i64 {
+            2 => {
+                let shading =
+                    Shading::parse(self.resolver, dict.get("Shading")?, ctx.resources.as_ref())?;
+                let area = shading.render(&pattern_matrix, bounds)?;
+                let background = shading.background_rgb();
+                Some(Source::Pixels(Box::new(move |x, y| {
+                    let pixel = area.get(x, y);
+                    match background {
+                        Some(rgb) if pixel[3] == 0 => premultiply(rgb, 255),
+                        _ => pixel,
+                    }
+                })))
+            }
+            1 => {
+                let PdfObject::Stream(stream) = pattern else {
+                    return None;
+                };
+                self.tiling_source(stream, pattern_matrix, color)
+            }
+            _ => None,
+        }
+    }
+
+    /// Render one period of a tiling pattern and repeat it
+    fn tiling_source(
+        &mut self,
+        stream: &PdfStream,
+        pattern_matrix: TransformMatrix,
+        color: &Color,
+    ) -> Option<Source> {
+        let resolver = self.resolver;
+        let dict = &stream.dict;
+        let bbox = get_rect(resolver, dict, "BBox")?;
+        let x_step = get_number(resolver, dict, "XStep")?.abs();
+        let y_step = get_number(resolver, dict, "YStep")?.abs();
+        let inverse = invert(&pattern_matrix)?;
+        if x_step < 1e-9 || y_step < 1e-9 {
+            return None;
+        }
+        let m = &pattern_matrix;
+        let tile_width = (x_step * m.a.hypot(m.b)).ceil().clamp(1.0, MAX_TILE) as usize;
+        let tile_height = (y_step * m.c.hypot(m.d)).ceil().clamp(1.0, MAX_TILE) as usize;
+        let (sx, sy) = (tile_width as f64 / x_step, tile_height as f64 / y_step);
+        let content = stream_data(resolver, stream)?;
+        let resources = get_dict(resolver, dict, "Resources");
+
+        // Pattern space to tile pixels, the tile covering one period from
+        // the lower left corner of the cell
+        let cell = TransformMatrix::new(sx, 0.0, 0.0, -sy, -bbox[0] * sx, (bbox[1] + y_step) * sy);
+        let saved = std::mem::replace(&mut self.canvas, Canvas::new(tile_width, tile_height));
+        for i in -1..=1 {
+            for j in -1..=1 {
+                let ctm = cell.multiply(&translate(i as f64 * x_step, j as f64 * y_step));
+                self.draw_content(&content, resources.clone(), ctm, Some(bbox));
+            }
+        }
+        let mut tile = std::mem::replace(&mut self.canvas, saved);
+
+        // Uncolored patterns take their color from the scn operands
+        if get_number(resolver, dict, "PaintType") == Some(2.0) {
+            let rgb = color.rgb();
+            for pixel in tile.data.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&premultiply(rgb, pixel[3]));
+            }
+        }
+
+        Some(Source::Pixels(Box::new(move |x, y| {
+            let p = inverse.transform_point(Point::new(x as f64 + 0.5, y as f64 + 0.5));
+            let u = (p.x - bbox[0]).rem_euclid(x_step);
+            let v = (p.y - bbox[1]).rem_euclid(y_step);
+            let tx = ((u * sx) as usize).min(tile_width - 1);
+            let ty = (((y_step - v) * sy) as usize).min(tile_height - 1);
+            tile.pixel(tx, ty)
+        })))
+    }
+
+    fn set_ext_gstate(&mut self, ctx: &mut Context, name: &str) {
+        let resolver = self.resolver;
+        let Some(object) = ctx.resource(resolver, "ExtGState", name) else {
+            return;
+        };
+        let Some(dict) = resolver.lookup(&object).as_dict().cloned() else {
+            return;
+        };
+        for (key, value) in &dict.0 {
+            let value = resolver.lookup(value);
+            match key.as_str() {
+                "LW" => {
+                    if let Some(w) = value.as_real() {
+                        ctx.gs.style.width = w.abs();
+                    }
+                }
+                "LC" => {
+                    if let Some(cap) = value.as_integer() {
+                        ctx.gs.style.cap = line_cap(cap as i32);
+                    }
+                }
+                "LJ" => {
+                    if let Some(join) = value.as_integer() {
+                        ctx.gs.style.join = line_join(join as i32);
+                    }
+                }
+                "ML" => {
+                    if let Some(limit) = value.as_real() {
+                        ctx.gs.style.miter_limit = limit;
+                    }
+                }
+                "D" => {
+                    if let Some(items) = value.as_array() {
+                        let dash = items
+                            .0
+                            .first()
+                            .and_then(|d| numbers(resolver, &resolver.lookup(d)));
+                        let phase = items.0.get(1).and_then(|p| resolver.lookup(p).as_real());
+                        if let Some(dash) = dash {
+                            ctx.gs.style.dash = dash;
+                            ctx.gs.style.dash_phase = phase.unwrap_or(0.0);
+                        }
+                    }
+                }
+                "CA" => {
+                    if let Some(alpha) = value.as_real() {
+                        ctx.gs.stroke_alpha = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    }
+                }
+                "ca" => {
+                    if let Some(alpha) = value.as_real() {
+                        ctx.gs.fill_alpha = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    }
+                }
+                "BM" => {
+                    let name = match &value {
+                        PdfObject::Array(modes) => modes.0.first().map(|m| resolver.lookup(m)),
+                        other => Some(other.clone()),
+                    };
+                    if let Some(name) = name.as_ref().and_then(PdfObject::as_name) {
+                        ctx.gs.blend = blend_mode(name.as_str());
+                    }
+                }
+                "SMask" => {
+                    ctx.gs.soft_mask = match &value {
+                        PdfObject::Dictionary(smask) => self.soft_mask(ctx, smask).map(Rc::new),
+                        _ => None,
+                    };
+                }
+                "Font" => {
+                    if let Some(items) = value.as_array() {
+                        if let (Some(font), Some(size)) = (items.0.first(), items.0.get(1)) {
+                            ctx.gs.text.font = self.load_font(font);
+                            ctx.gs.text.size = resolver.lookup(size).as_real().unwrap_or(0.0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Render the group of a soft mask dictionary into a mask
+    fn soft_mask(&mut self, ctx: &Context, smask: &PdfDictionary) -> Option<Mask> {
+        let resolver = self.resolver;
+        let PdfObject::Stream(group) = get(resolver, smask, "G")? else {
+            return None;
+        };
+        let luminosity = get_name(resolver, smask, "S").as_deref() != Some("Alpha");
+        let backdrop = get_numbers(resolver, smask, "BC")
+            .and_then(|bc| {
+                let space = get_dict(resolver, &group.dict, "Group")
+                    .and_then(|g| get(resolver, &g, "CS"))
+                    .and_then(|cs| ColorSpace::parse(resolver, &cs, ctx.resources.as_ref()))?;
+                Some(to_bytes(space.to_rgb(&bc)))
+            })
+            .unwrap_or([0, 0, 0]);
+
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        let saved = std::mem::replace(&mut self.canvas, Canvas::new(width, height));
+        let mut gs = GraphicsState::new(ctx.gs.ctm);
+        gs.clip = None;
+        self.draw_form_with(&group, ctx.resources.clone(), gs);
+        let layer = std::mem::replace(&mut self.canvas, saved);
+
+        let mut mask = if luminosity {
+            layer.luminosity_mask(backdrop)
+        } else {
+            layer.alpha_mask()
+        };
+        if let Some(transfer) = get_function(resolver, smask, "TR") {
+            let table: Vec<u8> = (0..=255)
+                .map(|v| {
+                    let out = transfer.eval(&[v as f64 / 255.0]);
+                    (out.first().copied().unwrap_or(0.0).clamp(0.0, 1.0) * 255.0).round() as u8
+                })
+                .collect();
+            for value in &mut mask.data {
+                *value = table[*value as usize];
+            }
+        }
+        Some(mask)
+    }
+
+    fn shading_fill(&mut self, ctx: &Context, name: &str) {
+        let Some(object) = ctx.resource(self.resolver, "Shading", name) else {
+            return;
+        };
+        let Some(shading) = Shading::parse(self.resolver, &object, ctx.resources.as_ref()) else {
+            return;
+        };
+        let area = match shading.bbox {
+            Some(bbox) => rect_path(bbox).transform(&ctx.gs.ctm),
+            None => {
+                let b = self.canvas.bounds();
+                rect_path([b.x0 as f64, b.y0 as f64, b.x1 as f64, b.y1 as f64])
+            }
+        };
+        let mask = self.coverage(&ctx.gs, &area, WindingRule::NonZero);
+        let Some(colors) = shading.render(&ctx.gs.ctm, mask.bounds) else {
+            return;
+        };
+        let paint = |x: i32, y: i32| colors.get(x, y);
+        let composite = Composite {
+            alpha: ctx.gs.fill_alpha,
+            blend: &ctx.gs.blend,
+            soft_mask: ctx.gs.soft_mask.as_deref(),
+        };
+        self.canvas.paint(&mask, &Paint::Pixels(&paint), &composite);
+    }
+
+    fn paint_xobject(&mut self, ctx: &mut Context, name: &str) {
+        let resolver = self.resolver;
+        let Some(object) = ctx.resource(resolver, "XObject", name) else {
+            return;
+        };
+        let PdfObject::Stream(stream) = resolver.lookup(&object) else {
+            return;
+        };
+        match get_name(resolver, &stream.dict, "Subtype").as_deref() {
+            Some("Image") => {
+                if let Some(image) = DecodedImage::decode(
+                    resolver,
+                    &stream.dict,
+                    &stream.data,
+                    ctx.resources.as_ref(),
+                ) {
+                    self.draw_image(ctx, image);
+                }
+            }
+            Some("Form") => self.draw_form(ctx, &stream),
+            _ => {}
+        }
+    }
+
+    /// Execute a form XObject, as a transparency group when it is one and
+    /// the graphics state makes the difference visible
+    fn draw_form(&mut self, ctx: &Context, stream: &PdfStream) {
+        let resolver = self.resolver;
+        let is_group = get_dict(resolver, &stream.dict, "Group")
+            .and_then(|group| get_name(resolver, &group, "S"))
+            .as_deref()
+            == Some("Transparency");
+        let gs = &ctx.gs;
+        let composited =
+            gs.fill_alpha < 255 || gs.blend != BlendMode::Normal || gs.soft_mask.is_some();
+        if !(is_group && composited) {
+            self.draw_form_with(stream, ctx.resources.clone(), ctx.gs.clone());
+            return;
+        }
+
+        let mut inner = GraphicsState::new(gs.ctm);
+        inner.clip = gs.clip.clone();
+        inner.fill_color = gs.fill_color.clone();
+        inner.stroke_color = gs.stroke_color.clone();
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        let saved = std::mem::replace(&mut self.canvas, Canvas::new(width, height));
+        self.draw_form_with(stream, ctx.resources.clone(), inner);
+        let layer = std::mem::replace(&mut self.canvas, saved);
+
+        let clip = match &gs.clip {
+            Some(clip) => (**clip).clone(),
+            None => Mask::full(self.canvas.bounds()),
+        };
+        let composite = Composite {
+            alpha: gs.fill_alpha,
+            blend: &gs.blend,
+            soft_mask: gs.soft_mask.as_deref(),
+        };
+        self.canvas.draw_layer(&layer, &clip, &composite);
+    }
+
+    fn draw_form_with(
+        &mut self,
+        stream: &PdfStream,
+        parent_resources: Option<PdfDictionary>,
+        mut gs: GraphicsState,
+    ) {
+        let resolver = self.resolver;
+        let Some(content) = stream_data(resolver, stream) else {
+            return;
+        };
+        gs.ctm = gs
+            .ctm
+            .multiply(&matrix(get_matrix(resolver, &stream.dict, "Matrix")));
+        if let Some(bbox) = get_rect(resolver, &stream.dict, "BBox") {
+            gs.clip = Some(Rc::new(self.coverage(
+                &gs,
+                &rect_path(bbox).transform(&gs.ctm),
+                WindingRule::NonZero,
+            )));
+        }
+        let resources = get_dict(resolver, &stream.dict, "Resources").or(parent_resources);
+        self.run_stream(&content, Context::new(resources, gs));
+    }
+
+    /// Paint an image into the unit square of user space
+    fn draw_image(&mut self, ctx: &Context, image: DecodedImage) {
+        let ctm = ctx.gs.ctm;
+        let Some(inverse) = invert(&ctm) else {
+            return;
+        };
+        let mut mask = self.coverage(
+            &ctx.gs,
+            &rect_path([0.0, 0.0, 1.0, 1.0]).transform(&ctm),
+            WindingRule::NonZero,
+        );
+        if mask.bounds.is_empty() {
+            return;
+        }
+
+        // Average away detail finer than a device pixel
+        let device_width = ctm.a.hypot(ctm.b).max(1.0);
+        let device_height = ctm.c.hypot(ctm.d).max(1.0);
+        let fx = (image.width as f64 / device_width) as usize;
+        let fy = (image.height as f64 / device_height) as usize;
+        let reduced = fx > 1 || fy > 1;
+        let image = if reduced {
+            image.downsample(fx, fy)
+        } else {
+            image
+        };
+        let smooth = reduced || image.interpolate;
+        let stencil = image.stencil;
+        let sample = move |x: i32, y: i32| {
+            let p = inverse.transform_point(Point::new(x as f64 + 0.5, y as f64 + 0.5));
+            image.sample(p.x.clamp(0.0, 1.0), p.y.clamp(0.0, 1.0), smooth)
+        };
+
+        if stencil {
+            // Stencil masks paint the fill color where the mask is opaque
+            let b = mask.bounds;
+            let width = b.width();
+            for y in b.y0..b.y1 {
+                for x in b.x0..b.x1 {
+                    let i = (y - b.y0) as usize * width + (x - b.x0) as usize;
+                    mask.data[i] = super::raster::mul8(mask.data[i], sample(x, y)[3]);
+                }
+            }
+            self.paint(ctx, &mask, false);
+            return;
+        }
+
+        let composite = Composite {
+            alpha: ctx.gs.fill_alpha,
+            blend: &ctx.gs.blend,
+            soft_mask: ctx.gs.soft_mask.as_deref(),
+        };
+        self.canvas
+            .paint(&mask, &Paint::Pixels(&sample), &composite);
+    }
+
+    fn load_font(&mut self, object: &PdfObject) -> Option<Rc<Font>> {
+        let key = object.as_reference();
+        if let Some(font) = key.and_then(|key| self.fonts.get(&key)) {
+            return Some(font.clone());
+        }
+        let resolved = self.resolver.lookup(object);
+        let font = Rc::new(Font::load(self.resolver, resolved.as_dict()?));
+        if let Some(key) = key {
+            self.fonts.insert(key, font.clone());
+        }
+        Some(font)
+    }
+
+    fn show_text(&mut self, ctx: &mut Context, bytes: &[u8]) {
+        let Some(font) = ctx.gs.text.font.clone() else {
+            return;
+        };
+        let text = ctx.gs.text.clone();
+        let vertical = font.is_vertical();
+        let mode = text.render_mode;
+        let visible = !matches!(mode, 3 | 7);
+        let params = TransformMatrix::new(
+            text.size * text.horizontal_scaling,
+            0.0,
+            0.0,
+            text.size,
+            0.0,
+            text.rise,
+        );
+
+        let mut outlines = Path::new();
+        for (code, length) in font.codes(bytes) {
+            let mut glyph_matrix = ctx.text_matrix.multiply(&params);
+            if vertical {
+                let (_, vx, vy) = font.vertical_metrics(code, length);
+                glyph_matrix = glyph_matrix.multiply(&translate(-vx, -vy));
+            }
+            match font.glyph(code, length) {
+                Glyph::Outline(path) => outlines.extend(&path.transform(&glyph_matrix)),
+                Glyph::Procedure(procedure) if visible => {
+                    self.draw_type3_glyph(ctx, &font, &procedure, glyph_matrix)
+                }
+                _ => {}
+            }
+
+            let spacing = text.char_spacing
+                + if length == 1 && code == 32 {
+                    text.word_spacing
+                } else {
+                    0.0
+                };
+            let advance = if vertical {
+                let (w1, _, _) = font.vertical_metrics(code, length);
+                translate(0.0, w1 * text.size + spacing)
+            } else {
+                translate(
+                    (font.width(code, length) * text.size + spacing) * text.horizontal_scaling,
+                    0.0,
+                )
+            };
+            ctx.text_matrix = ctx.text_matrix.multiply(&advance);
+        }
+
+        if matches!(mode, 0 | 2 | 4 | 6) {
+            self.fill_path(ctx, &outlines, WindingRule::NonZero);
+        }
+        if matches!(mode, 1 | 2 | 5 | 6) {
+            self.stroke_path(ctx, &outlines);
+        }
+        if mode >= 4 {
+            ctx.text_clip
+                .get_or_insert_with(Path::new)
+                .extend(&outlines.transform(&ctx.gs.ctm));
+        }
+    }
+
+    fn draw_type3_glyph(
+        &mut self,
+        ctx: &Context,
+        font: &Font,
+        procedure: &[u8],
+        glyph_matrix: TransformMatrix,
+    ) {
+        let Some(type3) = font.type3() else {
+            return;
+        };
+        let mut gs = ctx.gs.clone();
+        gs.ctm = ctx
+            .gs
+            .ctm
+            .multiply(&glyph_matrix)
+            .multiply(&matrix(type3.matrix));
+        let resources = type3.resources.clone().or_else(|| ctx.resources.clone());
+        self.run_stream(procedure, Context::new(resources, gs));
+    }
+}
+
+fn set_components(color: &mut Color, components: &[f32], pattern: Option<&String>) {
+    color.components = components.iter().map(|&c| c as f64).collect();
+    color.pattern = pattern.cloned();
+}
+
+fn device_color(space: ColorSpace, components: &[f32]) -> Color {
+    Color {
+        space: Rc::new(space),
+        components: components.iter().map(|&c| c as f64).collect(),
+        pattern: None,
+    }
+}
+
+fn next_line(ctx: &mut Context, tx: f64, ty: f64) {
+    ctx.line_matrix = ctx.line_matrix.multiply(&translate(tx, ty));
+    ctx.text_matrix = ctx.line_matrix;
+}
+
+/// Position adjustment of a TJ array, in thousandths of text space units
+fn adjust_text(ctx: &mut Context, amount: f64) {
+    let text = &ctx.gs.text;
+    let vertical = text.font.as_ref().is_some_and(|f| f.is_vertical());
+    let shift = -amount / 1000.0 * text.size;
+    let offset = if vertical {
+        translate(0.0, shift)
+    } else {
+        translate(shift * text.horizontal_scaling, 0.0)
+    };
+    ctx.text_matrix = ctx.text_matrix.multiply(&offset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::font::sfnt;
+    use super::*;
+    use crate::parser::objects::{PdfArray, PdfName};
+    use crate::parser::resolve::test_support::MemoryResolver;
+
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+    fn dict(entries: Vec<(&str, PdfObject)>) -> PdfDictionary {
+        let mut dict = PdfDictionary::new();
+        for (key, value) in entries {
+            dict.insert(key.to_string(), value);
+        }
+        dict
+    }
+
+    fn name(value: &str) -> PdfObject {
+        PdfObject::Name(PdfName(value.to_string()))
+    }
+
+    /// Render `content` onto a white `size` by `size` canvas, one pixel
+    /// per unit with y up
+    fn render(
+        resolver: &MemoryResolver,
+        content: &[u8],
+        resources: Option<PdfDictionary>,
+        size: usize,
+    ) -> Canvas {
+        let mut canvas = Canvas::new(size, size);
+        canvas.fill([255, 255, 255]);
+        let mut renderer = Renderer::new(resolver, canvas);
+        let ctm = TransformMatrix::new(1.0, 0.0, 0.0, -1.0, 0.0, size as f64);
+        renderer.draw_content(content, resources, ctm, None);
+        renderer.canvas
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        let resolver = MemoryResolver::default();
+        let squares = b"0 0 20 20 re 5 5 10 10 re";
+        let nonzero = render(
+            &resolver,
+            &[&b"1 0 0 rg "[..], squares, b" f"].concat(),
+            None,
+            20,
+        );
+        assert_eq!(nonzero.pixel(10, 10), [255, 0, 0, 255]);
+        let even_odd = render(
+            &resolver,
+            &[&b"1 0 0 rg "[..], squares, b" f*"].concat(),
+            None,
+            20,
+        );
+        assert_eq!(even_odd.pixel(10, 10), WHITE);
+        assert_eq!(even_odd.pixel(2, 2), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_stroke_and_clip() {
+        let resolver = MemoryResolver::default();
+        let canvas = render(
+            &resolver,
+            b"0 0 10 20 re W n 0 0 1 rg 0 0 20 20 re f 4 w 0 G 0 10 m 20 10 l S",
+            None,
+            20,
+        );
+        assert_eq!(canvas.pixel(5, 2), [0, 0, 255, 255]);
+        assert_eq!(canvas.pixel(15, 2), WHITE);
+        // The stroke is 4 units wide around y = 10 and clipped too
+        assert_eq!(canvas.pixel(5, 9), [0, 0, 0, 255]);
+        assert_eq!(canvas.pixel(5, 13), [0, 0, 255, 255]);
+        assert_eq!(canvas.pixel(15, 9), WHITE);
+    }
+
+    #[test]
+    fn test_constant_alpha() {
+        let resolver = MemoryResolver::default();
+        let resources = dict(vec![(
+            "ExtGState",
+            PdfObject::Dictionary(dict(vec![(
+                "Half",
+                PdfObject::Dictionary(dict(vec![("ca", PdfObject::Real(0.5))])),
+            )])),
+        )]);
+        let canvas = render(
+            &resolver,
+            b"/Half gs 0 g 0 0 10 10 re f",
+            Some(resources),
+            10,
+        );
+        let [r, g, b, a] = canvas.pixel(5, 5);
+        assert!((r as i32 - 128).abs() <= 1 && r == g && g == b);
+        assert_eq!(a, 255);
+    }
+
+    #[test]
+    fn test_form_xobject() {
+        let mut resolver = MemoryResolver::default();
+        resolver.objects.insert(
+            1,
+            PdfObject::Stream(PdfStream {
+                dict: dict(vec![
+                    ("Subtype", name("Form")),
+                    (
+                        "BBox",
+                        PdfObject::Array(PdfArray([0, 0, 10, 10].map(PdfObject::Integer).to_vec())),
+                    ),
+                    (
+                        "Matrix",
+                        PdfObject::Array(PdfArray(
+                            [1, 0, 0, 1, 10, 0].map(PdfObject::Integer).to_vec(),
+                        )),
+                    ),
+                ]),
+                data: b"0 1 0 rg 0 0 100 100 re f".to_vec(),
+            }),
+        );
+        let resources = dict(vec![(
+            "XObject",
+            PdfObject::Dictionary(dict(vec![("Fm1", PdfObject::Reference(1, 0))])),
+        )]);
+        let canvas = render(&resolver, b"/Fm1 Do", Some(resources), 20);
+        // Moved right by the form matrix and clipped to the BBox
+        assert_eq!(canvas.pixel(15, 15), [0, 255, 0, 255]);
+        assert_eq!(canvas.pixel(5, 15), WHITE);
+        assert_eq!(canvas.pixel(15, 5), WHITE);
+    }
+
+    #[test]
+    fn test_truetype_text() {
+        let mut resolver = MemoryResolver::default();
+        resolver.objects.insert(
+            1,
+            PdfObject::Stream(PdfStream {
+                dict: PdfDictionary::new(),
+                data: sfnt::tests::test_font(),
+            }),
+        );
+        let font = dict(vec![
+            ("Type", name("Font")),
+            ("Subtype", name("TrueType")),
+            (
+                "FontDescriptor",
+                PdfObject::Dictionary(dict(vec![
+                    ("Flags", PdfObject::Integer(32)),
+                    ("FontFile2", PdfObject::Reference(1, 0)),
+                ])),
+            ),
+        ]);
+        let resources = dict(vec![(
+            "Font",
+            PdfObject::Dictionary(dict(vec![("F1", PdfObject::Dictionary(font))])),
+        )]);
+        // The 'A' glyph is a triangle with its apex at (0.5, 1) em
+        let canvas = render(
+            &resolver,
+            b"BT /F1 20 Tf 0 0 Td (AA) Tj ET",
+            Some(resources),
+            40,
+        );
+        assert_eq!(canvas.pixel(10, 36), [0, 0, 0, 255]);
+        assert_eq!(canvas.pixel(2, 2), WHITE);
+        // The second glyph follows the advance of the first (hmtx 1000)
+        assert_eq!(canvas.pixel(30, 36), [0, 0, 0, 255]);
+    }
+}
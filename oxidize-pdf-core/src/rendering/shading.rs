@@ -0,0 +1,810 @@
+//! Smooth shadings (ISO 32000-1 §8.7.4.5): function-based, axial, radial
+//! and the four mesh types
+
+use super::color::{to_bytes, ColorSpace};
+use super::function::{get_function, Function};
+use super::path::{invert, matrix};
+use super::raster::IntRect;
+use crate::coordinate_system::TransformMatrix;
+use crate::geometry::Point;
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{
+    get, get_matrix, get_number, get_numbers, get_rect, stream_data, Resolver,
+};
+
+/// Entries in the color lookup table of one-parameter shadings
+const LUT_SIZE: usize = 1024;
+
+/// A parsed shading dictionary or stream
+#[derive(Debug, Clone)]
+pub(crate) struct Shading {
+    space: ColorSpace,
+    kind: ShadingKind,
+    function: Option<Function>,
+    /// Color painted outside the shape in pattern fills
+    pub background: Option<Vec<f64>>,
+    /// Limits of the shading in shading space
+    pub bbox: Option<[f64; 4]>,
+}
+
+#[derive(Debug, Clone)]
+enum ShadingKind {
+    Function {
+        domain: [f64; 4],
+        matrix: TransformMatrix,
+    },
+    Axial {
+        coords: [f64; 4],
+        domain: (f64, f64),
+        extend: (bool, bool),
+    },
+    Radial {
+        coords: [f64; 6],
+        domain: (f64, f64),
+        extend: (bool, bool),
+    },
+    /// Types 4 to 7, reduced to triangles
+    Mesh(Vec<Triangle>),
+}
+
+/// Mesh triangle; colors are components, or one function input
+#[derive(Debug, Clone)]
+struct Triangle {
+    points: [Point; 3],
+    colors: [Vec<f64>; 3],
+}
+
+/// Premultiplied RGBA pixels produced for a device rectangle
+pub(crate) struct ShadedArea {
+    pub bounds: IntRect,
+    pixels: Vec<u8>,
+}
+
+impl ShadedArea {
+    pub fn get(&self, x: i32, y: i32) -> [u8; 4] {
+        let b = &self.bounds;
+        if x < b.x0 || y < b.y0 || x >= b.x1 || y >= b.y1 {
+            return [0; 4];
+        }
+        let i = ((y - b.y0) as usize * b.width() + (x - b.x0) as usize) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+}
+
+impl Shading {
+    pub fn parse(
+        resolver: &dyn Resolver,
+        object: &PdfObject,
+        resources: Option<&PdfDictionary>,
+    ) -> Option<Shading> {
+        let object = resolver.lookup(object);
+        let dict = object.as_dict()?;
+        let space = ColorSpace::parse(resolver, &get(resolver, dict, "ColorSpace")?, resources)?;
+        let function = get_function(resolver, dict, "Function");
+        let pair =
+            |key: &str, default: (f64, f64)| match get_numbers(resolver, dict, key).as_deref() {
+                Some(&[a, b, ..]) => (a, b),
+                _ => default,
+            };
+        let extend = match get(resolver, dict, "Extend").and_then(|e| e.as_array().cloned()) {
+            Some(items) if items.0.len() == 2 => (
+                resolver.lookup(&items.0[0]).as_bool().unwrap_or(false),
+                resolver.lookup(&items.0[1]).as_bool().unwrap_or(false),
+            ),
+            _ => (false, false),
+        };
+
+        let kind = match get_number(resolver, dict, "ShadingType")? as i64 {
+            1 => {
+                let domain = match get_numbers(resolver, dict, "Domain").as_deref() {
+                    Some(&[a, b, c, d]) => [a, b, c, d],
+                    _ => [0.0, 1.0, 0.0, 1.0],
+                };
+                function.as_ref()?;
+                ShadingKind::Function {
+                    domain,
+                    matrix: matrix(get_matrix(resolver, dict, "Matrix")),
+                }
+            }
+            2 => {
+                let coords = get_numbers(resolver, dict, "Coords")?;
+                function.as_ref()?;
+                ShadingKind::Axial {
+                    coords: coords.get(..4)?.try_into().ok()?,
+                    domain: pair("Domain", (0.0, 1.0)),
+                    extend,
+                }
+            }
+            3 => {
+                let coords = get_numbers(resolver, dict, "Coords")?;
+                function.as_ref()?;
+                ShadingKind::Radial {
+                    coords: coords.get(..6)?.try_into().ok()?,
+                    domain: pair("Domain", (0.0, 1.0)),
+                    extend,
+                }
+            }
+            kind @ 4..=7 => {
+                let data = stream_data(resolver, object.as_stream()?)?;
+                let components = if function.is_some() {
+                    1
+                } else {
+                    space.components()
+                };
+                let layout = MeshLayout {
+                    bits_per_coordinate: get_number(resolver, dict, "BitsPerCoordinate")? as u32,
+                    bits_per_component: get_number(resolver, dict, "BitsPerComponent")? as u32,
+                    bits_per_flag: get_number(resolver, dict, "BitsPerFlag").unwrap_or(8.0) as u32,
+                    decode: get_numbers(resolver, dict, "Decode")?,
+                    components,
+                };
+                if layout.decode.len() < 4 + 2 * components
+                    || layout.bits_per_coordinate == 0
+                    || layout.bits_per_coordinate > 32
+                    || layout.bits_per_component == 0
+                    || layout.bits_per_component > 16
+                {
+                    return None;
+                }
+                let triangles = match kind {
+                    4 => free_form_triangles(&data, &layout),
+                    5 => {
+                        let per_row = get_number(resolver, dict, "VerticesPerRow")? as usize;
+                        lattice_triangles(&data, &layout, per_row)
+                    }
+                    _ => patch_triangles(&data, &layout, kind == 7),
+                };
+                ShadingKind::Mesh(triangles)
+            }
+            _ => return None,
+        };
+
+        Some(Shading {
+            background: get_numbers(resolver, dict, "Background"),
+            bbox: get_rect(resolver, dict, "BBox"),
+            space,
+            kind,
+            function,
+        })
+    }
+
+    /// Whether areas outside the geometry (extends, meshes) stay unpainted
+    #[cfg(test)]
+    pub fn is_mesh(&self) -> bool {
+        matches!(self.kind, ShadingKind::Mesh(_))
+    }
+
+    /// Colors of the shading over `bounds`, with `to_device` mapping
+    /// shading space to device space
+    pub fn render(&self, to_device: &TransformMatrix, bounds: IntRect) -> Option<ShadedArea> {
+        if bounds.is_empty() {
+            return None;
+        }
+        let inverse = invert(to_device)?;
+        let mut pixels = vec![0u8; bounds.width() * bounds.height() * 4];
+        let lut = self.lookup_table();
+        let width = bounds.width();
+
+        let mut put = |x: i32, y: i32, rgb: [u8; 3]| {
+            let i = ((y - bounds.y0) as usize * width + (x - bounds.x0) as usize) * 4;
+            pixels[i..i + 4].copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+        };
+
+        match &self.kind {
+            ShadingKind::Mesh(triangles) => {
+                for triangle in triangles {
+                    self.fill_triangle(triangle, to_device, bounds, lut.as_ref(), &mut put);
+                }
+            }
+            kind => {
+                for y in bounds.y0..bounds.y1 {
+                    for x in bounds.x0..bounds.x1 {
+                        let p = inverse.transform_point(Point::new(x as f64 + 0.5, y as f64 + 0.5));
+                        if let Some(rgb) = self.color_at(kind, p, lut.as_ref()) {
+                            put(x, y, rgb);
+                        }
+                    }
+                }
+            }
+        }
+        Some(ShadedArea { bounds, pixels })
+    }
+
+    /// Colors for evenly spaced parameters over the domain of axial, radial
+    /// and function-parameterized mesh shadings
+    fn lookup_table(&self) -> Option<(f64, f64, Vec<[u8; 3]>)> {
+        let function = self.function.as_ref()?;
+        let (t0, t1) = match &self.kind {
+            ShadingKind::Axial { domain, .. } | ShadingKind::Radial { domain, .. } => *domain,
+            ShadingKind::Mesh(triangles) => triangles
+                .iter()
+                .flat_map(|t| t.colors.iter().filter_map(|c| c.first().copied()))
+                .fold(None, |range: Option<(f64, f64)>, t| {
+                    Some(range.map_or((t, t), |(a, b)| (a.min(t), b.max(t))))
+                })?,
+            ShadingKind::Function { .. } => return None,
+        };
+        let colors = (0..LUT_SIZE)
+            .map(|i| {
+                let t = t0 + (t1 - t0) * i as f64 / (LUT_SIZE - 1) as f64;
+                to_bytes(self.space.to_rgb(&function.eval(&[t])))
+            })
+            .collect();
+        Some((t0, t1, colors))
+    }
+
+    fn color_at(
+        &self,
+        kind: &ShadingKind,
+        p: Point,
+        lut: Option<&(f64, f64, Vec<[u8; 3]>)>,
+    ) -> Option<[u8; 3]> {
+        let from_lut = |s: f64| {
+            let (_, _, colors) = lut?;
+            let index = (s.clamp(0.0, 1.0) * (LUT_SIZE - 1) as f64).round() as usize;
+            Some(colors[index])
+        };
+        match kind {
+            ShadingKind::Function { domain, matrix } => {
+                let q = invert(matrix)?.transform_point(p);
+                if q.x < domain[0] || q.x > domain[1] || q.y < domain[2] || q.y > domain[3] {
+                    return None;
+                }
+                let color = self.function.as_ref()?.eval(&[q.x, q.y]);
+                Some(to_bytes(self.space.to_rgb(&color)))
+            }
+            ShadingKind::Axial { coords, extend, .. } => {
+                let (dx, dy) = (coords[2] - coords[0], coords[3] - coords[1]);
+                let length = dx * dx + dy * dy;
+                let s = if length == 0.0 {
+                    0.0
+                } else {
+                    ((p.x - coords[0]) * dx + (p.y - coords[1]) * dy) / length
+                };
+                if (s < 0.0 && !extend.0) || (s > 1.0 && !extend.1) {
+                    return None;
+                }
+                from_lut(s)
+            }
+            ShadingKind::Radial { coords, extend, .. } => {
+                from_lut(radial_parameter(coords, *extend, p)?)
+            }
+            ShadingKind::Mesh(_) => None,
+        }
+    }
+
+    fn fill_triangle(
+        &self,
+        triangle: &Triangle,
+        to_device: &TransformMatrix,
+        bounds: IntRect,
+        lut: Option<&(f64, f64, Vec<[u8; 3]>)>,
+        put: &mut impl FnMut(i32, i32, [u8; 3]),
+    ) {
+        let [a, b, c] = triangle.points.map(|p| to_device.transform_point(p));
+        let area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+        if area.abs() < 1e-12 || !area.is_finite() {
+            return;
+        }
+        // Vertex colors as RGB, or as positions in the lookup table
+        let values: [Vec<f64>; 3] = match lut {
+            Some((t0, t1, _)) => triangle.colors.clone().map(|c| {
+                let t = c.first().copied().unwrap_or(0.0);
+                vec![if t1 == t0 { 0.0 } else { (t - t0) / (t1 - t0) }]
+            }),
+            None => triangle
+                .colors
+                .clone()
+                .map(|c| self.space.to_rgb(&c).to_vec()),
+        };
+
+        let x0 = (a.x.min(b.x).min(c.x).floor() as i32).max(bounds.x0);
+        let x1 = (a.x.max(b.x).max(c.x).ceil() as i32).min(bounds.x1);
+        let y0 = (a.y.min(b.y).min(c.y).floor() as i32).max(bounds.y0);
+        let y1 = (a.y.max(b.y).max(c.y).ceil() as i32).min(bounds.y1);
+        let epsilon = -1e-9;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let p = Point::new(x as f64 + 0.5, y as f64 + 0.5);
+                let wa = ((b.x - p.x) * (c.y - p.y) - (c.x - p.x) * (b.y - p.y)) / area;
+                let wb = ((c.x - p.x) * (a.y - p.y) - (a.x - p.x) * (c.y - p.y)) / area;
+                let wc = 1.0 - wa - wb;
+                if wa < epsilon || wb < epsilon || wc < epsilon {
+                    continue;
+                }
+                let mix = |i: usize| {
+                    wa * values[0].get(i).copied().unwrap_or(0.0)
+                        + wb * values[1].get(i).copied().unwrap_or(0.0)
+                        + wc * values[2].get(i).copied().unwrap_or(0.0)
+                };
+                let rgb = match lut {
+                    Some((_, _, colors)) => {
+                        colors[(mix(0).clamp(0.0, 1.0) * (LUT_SIZE - 1) as f64).round() as usize]
+                    }
+                    None => to_bytes([mix(0), mix(1), mix(2)]),
+                };
+                put(x, y, rgb);
+            }
+        }
+    }
+
+    /// Background color as RGB, for pattern fills
+    pub fn background_rgb(&self) -> Option<[u8; 3]> {
+        self.background
+            .as_ref()
+            .map(|color| to_bytes(self.space.to_rgb(color)))
+    }
+}
+
+/// Largest parameter `s` whose circle passes through `p` (§8.7.4.5.4)
+fn radial_parameter(coords: &[f64; 6], extend: (bool, bool), p: Point) -> Option<f64> {
+    let [x0, y0, r0, x1, y1, r1] = *coords;
+    let (cdx, cdy, dr) = (x1 - x0, y1 - y0, r1 - r0);
+    let (pdx, pdy) = (p.x - x0, p.y - y0);
+    let a = cdx * cdx + cdy * cdy - dr * dr;
+    let b = pdx * cdx + pdy * cdy + r0 * dr;
+    let c = pdx * pdx + pdy * pdy - r0 * r0;
+
+    let candidates = if a.abs() < 1e-12 {
+        if b == 0.0 {
+            return None;
+        }
+        vec![c / (2.0 * b)]
+    } else {
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let (s1, s2) = ((b + root) / a, (b - root) / a);
+        vec![s1.max(s2), s1.min(s2)]
+    };
+    for s in candidates {
+        if r0 + s * dr < 0.0 {
+            continue;
+        }
+        if (0.0..=1.0).contains(&s) {
+            return Some(s);
+        }
+        if s > 1.0 && extend.1 {
+            return Some(1.0);
+        }
+        if s < 0.0 && extend.0 {
+            return Some(0.0);
+        }
+    }
+    None
+}
+
+/// Bit layout of mesh shading data
+struct MeshLayout {
+    bits_per_coordinate: u32,
+    bits_per_component: u32,
+    bits_per_flag: u32,
+    decode: Vec<f64>,
+    components: usize,
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        (self.data.len() * 8).saturating_sub(self.position)
+    }
+
+    fn read(&mut self, bits: u32) -> Option<u64> {
+        if self.remaining() < bits as usize {
+            return None;
+        }
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = self.data[self.position / 8];
+            value = value << 1 | (byte >> (7 - self.position % 8) & 1) as u64;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    fn align(&mut self) {
+        self.position = (self.position + 7) / 8 * 8;
+    }
+
+    fn read_decoded(&mut self, bits: u32, range: (f64, f64)) -> Option<f64> {
+        let max = ((1u64 << bits) - 1) as f64;
+        Some(range.0 + self.read(bits)? as f64 * (range.1 - range.0) / max)
+    }
+
+    fn read_point(&mut self, layout: &MeshLayout) -> Option<Point> {
+        let d = &layout.decode;
+        let x = self.read_decoded(layout.bits_per_coordinate, (d[0], d[1]))?;
+        let y = self.read_decoded(layout.bits_per_coordinate, (d[2], d[3]))?;
+        Some(Point::new(x, y))
+    }
+
+    fn read_color(&mut self, layout: &MeshLayout) -> Option<Vec<f64>> {
+        (0..layout.components)
+            .map(|i| {
+                let range = (layout.decode[4 + 2 * i], layout.decode[5 + 2 * i]);
+                self.read_decoded(layout.bits_per_component, range)
+            })
+            .collect()
+    }
+}
+
+/// Type 4: vertices with edge flags
+fn free_form_triangles(data: &[u8], layout: &MeshLayout) -> Vec<Triangle> {
+    let mut reader = BitReader::new(data);
+    let read_vertex = |reader: &mut BitReader| {
+        let flag = reader.read(layout.bits_per_flag)?;
+        let vertex = (reader.read_point(layout)?, reader.read_color(layout)?);
+        // Every vertex starts on a byte boundary
+        reader.align();
+        Some((flag, vertex))
+    };
+    let mut triangles: Vec<Triangle> = Vec::new();
+    while let Some((flag, v)) = read_vertex(&mut reader) {
+        let (points, colors) = match (flag, triangles.last()) {
+            (0, _) => {
+                // A fresh triangle; the flags of its other vertices are ignored
+                let (Some((_, b)), Some((_, c))) =
+                    (read_vertex(&mut reader), read_vertex(&mut reader))
+                else {
+                    break;
+                };
+                ([v.0, b.0, c.0], [v.1, b.1, c.1])
+            }
+            (1, Some(last)) => (
+                [last.points[1], last.points[2], v.0],
+                [last.colors[1].clone(), last.colors[2].clone(), v.1],
+            ),
+            (2, Some(last)) => (
+                [last.points[0], last.points[2], v.0],
+                [last.colors[0].clone(), last.colors[2].clone(), v.1],
+            ),
+            _ => break,
+        };
+        triangles.push(Triangle { points, colors });
+    }
+    triangles
+}
+
+/// Type 5: rows of vertices forming quadrilaterals
+fn lattice_triangles(data: &[u8], layout: &MeshLayout, per_row: usize) -> Vec<Triangle> {
+    if per_row < 2 {
+        return Vec::new();
+    }
+    let mut reader = BitReader::new(data);
+    let mut vertices = Vec::new();
+    while let (Some(point), Some(color)) = (reader.read_point(layout), reader.read_color(layout)) {
+        vertices.push((point, color));
+    }
+    let rows = vertices.len() / per_row;
+    let mut triangles = Vec::new();
+    for row in 1..rows {
+        for col in 1..per_row {
+            let v = |r: usize, c: usize| &vertices[r * per_row + c];
+            let (a, b) = (v(row - 1, col - 1), v(row - 1, col));
+            let (c, d) = (v(row, col - 1), v(row, col));
+            triangles.push(Triangle {
+                points: [a.0, b.0, c.0],
+                colors: [a.1.clone(), b.1.clone(), c.1.clone()],
+            });
+            triangles.push(Triangle {
+                points: [b.0, d.0, c.0],
+                colors: [b.1.clone(), d.1.clone(), c.1.clone()],
+            });
+        }
+    }
+    triangles
+}
+
+/// Types 6 and 7: Coons and tensor-product patches, subdivided into
+/// triangles
+fn patch_triangles(data: &[u8], layout: &MeshLayout, tensor: bool) -> Vec<Triangle> {
+    let mut reader = BitReader::new(data);
+    let mut triangles = Vec::new();
+    // Control points p[i][j] and corner colors c00, c03, c33, c30
+    let mut previous: Option<([[Point; 4]; 4], [Vec<f64>; 4])> = None;
+
+    while let Some(flag) = reader.read(layout.bits_per_flag) {
+        let mut p = [[Point::new(0.0, 0.0); 4]; 4];
+        let mut colors: [Vec<f64>; 4] = Default::default();
+        // Boundary points in the order they appear in the data
+        let boundary = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+            (3, 2),
+            (3, 1),
+            (3, 0),
+            (2, 0),
+            (1, 0),
+        ];
+        let (skip_points, skip_colors) = if flag == 0 {
+            (0, 0)
+        } else {
+            let Some((pp, pc)) = &previous else { break };
+            let (edge, edge_colors) = match flag {
+                1 => (
+                    [pp[0][3], pp[1][3], pp[2][3], pp[3][3]],
+                    [pc[1].clone(), pc[2].clone()],
+                ),
+                2 => (
+                    [pp[3][3], pp[3][2], pp[3][1], pp[3][0]],
+                    [pc[2].clone(), pc[3].clone()],
+                ),
+                3 => (
+                    [pp[3][0], pp[2][0], pp[1][0], pp[0][0]],
+                    [pc[3].clone(), pc[0].clone()],
+                ),
+                _ => break,
+            };
+            for (k, point) in edge.into_iter().enumerate() {
+                let (i, j) = boundary[k];
+                p[i][j] = point;
+            }
+            colors[0] = edge_colors[0].clone();
+            colors[1] = edge_colors[1].clone();
+            (4, 2)
+        };
+
+        let mut complete = true;
+        for &(i, j) in &boundary[skip_points..] {
+            match reader.read_point(layout) {
+                Some(point) => p[i][j] = point,
+                None => complete = false,
+            }
+        }
+        if tensor {
+            for (i, j) in [(1, 1), (1, 2), (2, 2), (2, 1)] {
+                match reader.read_point(layout) {
+                    Some(point) => p[i][j] = point,
+                    None => complete = false,
+                }
+            }
+        }
+        for color in colors.iter_mut().skip(skip_colors) {
+            match reader.read_color(layout) {
+                Some(c) => *color = c,
+                None => complete = false,
+            }
+        }
+        reader.align();
+        if !complete {
+            break;
+        }
+        if !tensor {
+            coons_interior(&mut p);
+        }
+        subdivide_patch(&p, &colors, &mut triangles);
+        previous = Some((p, colors));
+    }
+    triangles
+}
+
+/// Interior control points equivalent to a Coons patch (§8.7.4.5.8)
+fn coons_interior(p: &mut [[Point; 4]; 4]) {
+    let combine = |terms: &[(f64, Point)]| {
+        let (x, y) = terms
+            .iter()
+            .fold((0.0, 0.0), |(x, y), (w, q)| (x + w * q.x, y + w * q.y));
+        Point::new(x / 9.0, y / 9.0)
+    };
+    let q = *p;
+    p[1][1] = combine(&[
+        (-4.0, q[0][0]),
+        (6.0, q[0][1]),
+        (6.0, q[1][0]),
+        (-2.0, q[0][3]),
+        (-2.0, q[3][0]),
+        (3.0, q[3][1]),
+        (3.0, q[1][3]),
+        (-1.0, q[3][3]),
+    ]);
+    p[1][2] = combine(&[
+        (-4.0, q[0][3]),
+        (6.0, q[0][2]),
+        (6.0, q[1][3]),
+        (-2.0, q[0][0]),
+        (-2.0, q[3][3]),
+        (3.0, q[3][2]),
+        (3.0, q[1][0]),
+        (-1.0, q[3][0]),
+    ]);
+    p[2][1] = combine(&[
+        (-4.0, q[3][0]),
+        (6.0, q[3][1]),
+        (6.0, q[2][0]),
+        (-2.0, q[3][3]),
+        (-2.0, q[0][0]),
+        (3.0, q[0][1]),
+        (3.0, q[2][3]),
+        (-1.0, q[0][3]),
+    ]);
+    p[2][2] = combine(&[
+        (-4.0, q[3][3]),
+        (6.0, q[3][2]),
+        (6.0, q[2][3]),
+        (-2.0, q[3][0]),
+        (-2.0, q[0][3]),
+        (3.0, q[2][0]),
+        (3.0, q[0][2]),
+        (-1.0, q[0][0]),
+    ]);
+}
+
+/// Evaluate the patch surface on a grid and emit two triangles per cell
+fn subdivide_patch(p: &[[Point; 4]; 4], colors: &[Vec<f64>; 4], out: &mut Vec<Triangle>) {
+    const STEPS: usize = 16;
+    let bernstein = |t: f64| {
+        let mt = 1.0 - t;
+        [mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t]
+    };
+    let surface = |u: f64, v: f64| {
+        let (bu, bv) = (bernstein(u), bernstein(v));
+        let (mut x, mut y) = (0.0, 0.0);
+        for i in 0..4 {
+            for j in 0..4 {
+                x += p[i][j].x * bu[i] * bv[j];
+                y += p[i][j].y * bu[i] * bv[j];
+            }
+        }
+        Point::new(x, y)
+    };
+    // Corner colors c00 (u=0,v=0), c03 (0,1), c33 (1,1), c30 (1,0)
+    let color = |u: f64, v: f64| -> Vec<f64> {
+        (0..colors[0].len())
+            .map(|k| {
+                let get = |c: &Vec<f64>| c.get(k).copied().unwrap_or(0.0);
+                (1.0 - u) * (1.0 - v) * get(&colors[0])
+                    + (1.0 - u) * v * get(&colors[1])
+                    + u * v * get(&colors[2])
+                    + u * (1.0 - v) * get(&colors[3])
+            })
+            .collect()
+    };
+
+    let mut grid = Vec::with_capacity((STEPS + 1) * (STEPS + 1));
+    for i in 0..=STEPS {
+        for j in 0..=STEPS {
+            let (u, v) = (i as f64 / STEPS as f64, j as f64 / STEPS as f64);
+            grid.push((surface(u, v), color(u, v)));
+        }
+    }
+    let at = |i: usize, j: usize| &grid[i * (STEPS + 1) + j];
+    for i in 0..STEPS {
+        for j in 0..STEPS {
+            let (a, b, c, d) = (at(i, j), at(i + 1, j), at(i, j + 1), at(i + 1, j + 1));
+            out.push(Triangle {
+                points: [a.0, b.0, c.0],
+                colors: [a.1.clone(), b.1.clone(), c.1.clone()],
+            });
+            out.push(Triangle {
+                points: [b.0, d.0, c.0],
+                colors: [b.1.clone(), d.1.clone(), c.1.clone()],
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::objects::{PdfArray, PdfName, PdfStream};
+    use crate::parser::resolve::test_support::MemoryResolver;
+
+    fn numbers(values: &[f64]) -> PdfObject {
+        PdfObject::Array(PdfArray(
+            values.iter().map(|&v| PdfObject::Real(v)).collect(),
+        ))
+    }
+
+    fn dict(entries: Vec<(&str, PdfObject)>) -> PdfDictionary {
+        let mut dict = PdfDictionary::new();
+        for (key, value) in entries {
+            dict.insert(key.to_string(), value);
+        }
+        dict
+    }
+
+    fn gray_ramp() -> PdfObject {
+        PdfObject::Dictionary(dict(vec![
+            ("FunctionType", PdfObject::Integer(2)),
+            ("Domain", numbers(&[0.0, 1.0])),
+            ("C0", numbers(&[0.0])),
+            ("C1", numbers(&[1.0])),
+            ("N", PdfObject::Integer(1)),
+        ]))
+    }
+
+    fn gray() -> PdfObject {
+        PdfObject::Name(PdfName::new("DeviceGray".to_string()))
+    }
+
+    #[test]
+    fn test_axial_shading() {
+        let object = PdfObject::Dictionary(dict(vec![
+            ("ShadingType", PdfObject::Integer(2)),
+            ("ColorSpace", gray()),
+            ("Coords", numbers(&[0.0, 0.0, 100.0, 0.0])),
+            ("Function", gray_ramp()),
+        ]));
+        let shading = Shading::parse(&MemoryResolver::default(), &object, None).unwrap();
+        let area = shading
+            .render(&TransformMatrix::IDENTITY, IntRect::new(-10, 0, 110, 1))
+            .unwrap();
+        assert_eq!(area.get(0, 0)[0], 1);
+        assert_eq!(area.get(49, 0)[0], 126);
+        assert_eq!(area.get(99, 0)[0], 254);
+        // Not extended: nothing beyond the ends
+        assert_eq!(area.get(-5, 0), [0, 0, 0, 0]);
+        assert_eq!(area.get(105, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_radial_shading() {
+        let object = PdfObject::Dictionary(dict(vec![
+            ("ShadingType", PdfObject::Integer(3)),
+            ("ColorSpace", gray()),
+            ("Coords", numbers(&[50.0, 50.0, 0.0, 50.0, 50.0, 50.0])),
+            ("Function", gray_ramp()),
+            (
+                "Extend",
+                PdfObject::Array(PdfArray(vec![
+                    PdfObject::Boolean(false),
+                    PdfObject::Boolean(true),
+                ])),
+            ),
+        ]));
+        let shading = Shading::parse(&MemoryResolver::default(), &object, None).unwrap();
+        let area = shading
+            .render(&TransformMatrix::IDENTITY, IntRect::new(0, 0, 100, 100))
+            .unwrap();
+        assert!(area.get(50, 50)[0] < 5);
+        assert!((area.get(74, 50)[0] as i32 - 128).abs() < 4);
+        assert_eq!(area.get(0, 0)[0], 255);
+    }
+
+    #[test]
+    fn test_free_form_mesh() {
+        // One triangle: 8-bit flags, coordinates and gray components
+        let data = vec![0, 0, 0, 0, 0, 255, 0, 128, 0, 0, 255, 255];
+        let stream = PdfStream {
+            dict: dict(vec![
+                ("ShadingType", PdfObject::Integer(4)),
+                ("ColorSpace", gray()),
+                ("BitsPerCoordinate", PdfObject::Integer(8)),
+                ("BitsPerComponent", PdfObject::Integer(8)),
+                ("BitsPerFlag", PdfObject::Integer(8)),
+                ("Decode", numbers(&[0.0, 255.0, 0.0, 255.0, 0.0, 1.0])),
+            ]),
+            data,
+        };
+        let shading =
+            Shading::parse(&MemoryResolver::default(), &PdfObject::Stream(stream), None).unwrap();
+        assert!(shading.is_mesh());
+        let area = shading
+            .render(&TransformMatrix::IDENTITY, IntRect::new(0, 0, 256, 256))
+            .unwrap();
+        assert_eq!(area.get(10, 10)[3], 255);
+        assert_eq!(area.get(200, 200)[3], 0);
+        assert!(area.get(2, 2)[0] < 10);
+        assert!(area.get(2, 250)[0] > 240);
+    }
+}
@@ -0,0 +1,393 @@
+//! Conversion of stroked polylines into fillable outlines
+//!
+//! Every segment, join and cap becomes its own positively oriented polygon;
+//! filling them together with the non-zero rule paints their union.
+
+use super::path::Polyline;
+use crate::geometry::Point;
+use crate::graphics::{LineCap, LineJoin};
+
+/// Stroke parameters in user space
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StrokeStyle {
+    pub width: f64,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f64,
+    pub dash: Vec<f64>,
+    pub dash_phase: f64,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash: Vec::new(),
+            dash_phase: 0.0,
+        }
+    }
+}
+
+/// Line cap for a `J` operand (ISO 32000-1 Table 54)
+pub(crate) fn line_cap(value: i32) -> LineCap {
+    match value {
+        1 => LineCap::Round,
+        2 => LineCap::Square,
+        _ => LineCap::Butt,
+    }
+}
+
+/// Line join for a `j` operand (ISO 32000-1 Table 55)
+pub(crate) fn line_join(value: i32) -> LineJoin {
+    match value {
+        1 => LineJoin::Round,
+        2 => LineJoin::Bevel,
+        _ => LineJoin::Miter,
+    }
+}
+
+/// Outline polygons of the stroked polylines; `tolerance` bounds the
+/// error of round caps and joins
+pub(crate) fn stroke(polylines: &[Polyline], style: &StrokeStyle, tolerance: f64) -> Vec<Polyline> {
+    let half = style.width / 2.0;
+    let mut out = Vec::new();
+    if half <= 0.0 || !half.is_finite() {
+        return out;
+    }
+
+    for polyline in polylines {
+        let mut points = polyline.points.clone();
+        points.dedup_by(|a, b| (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9);
+        let closed = polyline.closed && points.len() > 2;
+        if closed && points.first() == points.last() {
+            points.pop();
+        }
+
+        for (piece, piece_closed) in dash(&points, closed, style) {
+            stroke_piece(&piece, piece_closed, half, style, tolerance, &mut out);
+        }
+    }
+    out
+}
+
+fn stroke_piece(
+    points: &[Point],
+    closed: bool,
+    half: f64,
+    style: &StrokeStyle,
+    tolerance: f64,
+    out: &mut Vec<Polyline>,
+) {
+    if points.len() == 1 {
+        // A zero-length subpath only shows round and square caps
+        let p = points[0];
+        match style.cap {
+            LineCap::Round => push(out, circle(p, half, tolerance)),
+            LineCap::Square => push(
+                out,
+                vec![
+                    Point::new(p.x - half, p.y - half),
+                    Point::new(p.x + half, p.y - half),
+                    Point::new(p.x + half, p.y + half),
+                    Point::new(p.x - half, p.y + half),
+                ],
+            ),
+            LineCap::Butt => {}
+        }
+        return;
+    }
+
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    for i in 0..segment_count {
+        let mut a = points[i];
+        let mut b = points[(i + 1) % points.len()];
+        let (dx, dy) = direction(a, b);
+        if !closed && style.cap == LineCap::Square {
+            if i == 0 {
+                a = Point::new(a.x - dx * half, a.y - dy * half);
+            }
+            if i == segment_count - 1 {
+                b = Point::new(b.x + dx * half, b.y + dy * half);
+            }
+        }
+        let (nx, ny) = (-dy * half, dx * half);
+        push(
+            out,
+            vec![
+                Point::new(a.x + nx, a.y + ny),
+                Point::new(b.x + nx, b.y + ny),
+                Point::new(b.x - nx, b.y - ny),
+                Point::new(a.x - nx, a.y - ny),
+            ],
+        );
+    }
+
+    // Joins between consecutive segments
+    let join_range = if closed {
+        0..points.len()
+    } else {
+        1..points.len() - 1
+    };
+    for i in join_range {
+        let previous = points[(i + points.len() - 1) % points.len()];
+        let vertex = points[i];
+        let next = points[(i + 1) % points.len()];
+        join(previous, vertex, next, half, style, tolerance, out);
+    }
+
+    if !closed && style.cap == LineCap::Round {
+        push(out, circle(points[0], half, tolerance));
+        push(out, circle(points[points.len() - 1], half, tolerance));
+    }
+}
+
+fn join(
+    previous: Point,
+    vertex: Point,
+    next: Point,
+    half: f64,
+    style: &StrokeStyle,
+    tolerance: f64,
+    out: &mut Vec<Polyline>,
+) {
+    let (d0x, d0y) = direction(previous, vertex);
+    let (d1x, d1y) = direction(vertex, next);
+    let cross = d0x * d1y - d0y * d1x;
+    let dot = d0x * d1x + d0y * d1y;
+    if cross.abs() < 1e-9 && dot > 0.0 {
+        return; // Collinear, nothing to fill
+    }
+
+    if style.join == LineJoin::Round {
+        push(out, circle(vertex, half, tolerance));
+        return;
+    }
+
+    // The outer side is opposite to the turn direction
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let a = Point::new(vertex.x - d0y * half * side, vertex.y + d0x * half * side);
+    let b = Point::new(vertex.x - d1y * half * side, vertex.y + d1x * half * side);
+
+    if style.join == LineJoin::Miter {
+        // Miter length over line width is 1 / sin(theta / 2)
+        let cos_theta = (-dot).clamp(-1.0, 1.0);
+        let sin_half = ((1.0 - cos_theta) / 2.0).sqrt();
+        if sin_half > 1e-9 && 1.0 / sin_half <= style.miter_limit {
+            let (bx, by) = (d0x - d1x, d0y - d1y);
+            let length = (bx * bx + by * by).sqrt();
+            if length > 1e-9 {
+                let distance = half / sin_half;
+                // The miter tip lies along the bisector on the outer side
+                let (mx, my) = (-(d1x - d0x) / length, -(d1y - d0y) / length);
+                let tip = Point::new(vertex.x + mx * distance, vertex.y + my * distance);
+                push(out, vec![vertex, a, tip, b]);
+                return;
+            }
+        }
+    }
+    push(out, vec![vertex, a, b]);
+}
+
+fn direction(a: Point, b: Point) -> (f64, f64) {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        (1.0, 0.0)
+    } else {
+        (dx / length, dy / length)
+    }
+}
+
+/// Add a polygon, reversed if needed so that every piece winds the same way
+fn push(out: &mut Vec<Polyline>, mut points: Vec<Point>) {
+    let area: f64 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+    if area < 0.0 {
+        points.reverse();
+    }
+    out.push(Polyline {
+        points,
+        closed: true,
+    });
+}
+
+pub(crate) fn circle(center: Point, radius: f64, tolerance: f64) -> Vec<Point> {
+    let step = if radius > tolerance {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        std::f64::consts::FRAC_PI_2
+    };
+    let n = ((std::f64::consts::TAU / step).ceil() as usize).clamp(8, 256);
+    (0..n)
+        .map(|i| {
+            let angle = i as f64 * std::f64::consts::TAU / n as f64;
+            Point::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Split a polyline into its dashes; a solid line is returned whole
+fn dash(points: &[Point], closed: bool, style: &StrokeStyle) -> Vec<(Vec<Point>, bool)> {
+    let total: f64 = style.dash.iter().sum();
+    if style.dash.is_empty()
+        || total <= 0.0
+        || style.dash.iter().any(|d| *d < 0.0 || !d.is_finite())
+    {
+        return vec![(points.to_vec(), closed)];
+    }
+
+    // Position in the pattern at the start of the path
+    let mut index = 0;
+    let mut remaining = style.dash[0];
+    let mut on = true;
+    let mut phase = style.dash_phase.rem_euclid(total);
+    while phase > 0.0 {
+        if phase >= remaining {
+            phase -= remaining;
+            index = (index + 1) % style.dash.len();
+            remaining = style.dash[index];
+            on = !on;
+        } else {
+            remaining -= phase;
+            phase = 0.0;
+        }
+    }
+
+    let mut dashes = Vec::new();
+    let mut current: Vec<Point> = if on { vec![points[0]] } else { Vec::new() };
+    let mut vertices: Vec<Point> = points.to_vec();
+    if closed {
+        vertices.push(points[0]);
+    }
+
+    for pair in vertices.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+        let mut position = 0.0;
+        while length - position > remaining {
+            position += remaining;
+            let t = position / length;
+            let p = Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+            if on {
+                current.push(p);
+                dashes.push((std::mem::take(&mut current), false));
+            } else {
+                current = vec![p];
+            }
+            on = !on;
+            index = (index + 1) % style.dash.len();
+            remaining = style.dash[index];
+        }
+        remaining -= length - position;
+        if on {
+            current.push(b);
+        }
+    }
+    if on && !current.is_empty() {
+        dashes.push((current, false));
+    }
+    dashes
+        .into_iter()
+        .map(|(mut dash, closed)| {
+            dash.dedup_by(|a, b| (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9);
+            (dash, closed)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::raster::{rasterize, IntRect};
+    use super::*;
+    use crate::graphics::WindingRule;
+
+    fn line(points: &[(f64, f64)], closed: bool) -> Polyline {
+        Polyline {
+            points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            closed,
+        }
+    }
+
+    #[test]
+    fn test_butt_and_square_caps() {
+        let clip = IntRect::new(0, 0, 20, 20);
+        let path = [line(&[(5.0, 10.0), (15.0, 10.0)], false)];
+        let mut style = StrokeStyle {
+            width: 4.0,
+            ..Default::default()
+        };
+        let butt = rasterize(&stroke(&path, &style, 0.1), WindingRule::NonZero, clip);
+        assert_eq!(butt.get(10, 9), 255);
+        assert_eq!(butt.get(10, 12), 0);
+        assert_eq!(butt.get(4, 10), 0);
+
+        style.cap = LineCap::Square;
+        let square = rasterize(&stroke(&path, &style, 0.1), WindingRule::NonZero, clip);
+        assert_eq!(square.get(4, 10), 255);
+        assert_eq!(square.get(2, 10), 0);
+    }
+
+    #[test]
+    fn test_miter_join_and_limit() {
+        let clip = IntRect::new(0, 0, 30, 30);
+        let path = [line(&[(5.0, 5.0), (20.0, 5.0), (20.0, 20.0)], false)];
+        let mut style = StrokeStyle {
+            width: 4.0,
+            ..Default::default()
+        };
+        let miter = rasterize(&stroke(&path, &style, 0.1), WindingRule::NonZero, clip);
+        // The outer corner is filled by the miter
+        assert_eq!(miter.get(21, 3), 255);
+
+        style.join = LineJoin::Bevel;
+        let bevel = rasterize(&stroke(&path, &style, 0.1), WindingRule::NonZero, clip);
+        assert_eq!(bevel.get(21, 3), 0);
+        assert_eq!(bevel.get(19, 5), 255);
+    }
+
+    #[test]
+    fn test_dashes() {
+        let points = [Point::new(0.0, 0.0), Point::new(10.0, 0.0)];
+        let style = StrokeStyle {
+            dash: vec![3.0, 2.0],
+            dash_phase: 1.0,
+            ..Default::default()
+        };
+        let dashes = dash(&points, false, &style);
+        let spans: Vec<(f64, f64)> = dashes
+            .iter()
+            .map(|(d, _)| (d[0].x, d[d.len() - 1].x))
+            .collect();
+        assert_eq!(spans, vec![(0.0, 2.0), (4.0, 7.0), (9.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_closed_square_outline() {
+        let clip = IntRect::new(0, 0, 20, 20);
+        let path = [line(
+            &[(5.0, 5.0), (15.0, 5.0), (15.0, 15.0), (5.0, 15.0)],
+            true,
+        )];
+        let style = StrokeStyle {
+            width: 2.0,
+            ..Default::default()
+        };
+        let mask = rasterize(&stroke(&path, &style, 0.1), WindingRule::NonZero, clip);
+        assert_eq!(mask.get(4, 4), 255); // corner joined
+        assert_eq!(mask.get(10, 10), 0); // interior empty
+        assert_eq!(mask.get(10, 5), 255);
+    }
+}
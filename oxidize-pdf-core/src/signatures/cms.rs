@@ -0,0 +1,653 @@
+//! Detached CMS SignedData (RFC 5652) used as the PDF signature value
+//!
+//! PDF signatures with the `adbe.pkcs7.detached` and `ETSI.CAdES.detached`
+//! sub-filters store a DER-encoded `ContentInfo` wrapping a `SignedData`
+//! structure whose encapsulated content is absent. The signed attributes carry
+//! the digest of the signed byte ranges, and the signature is computed over the
+//! DER encoding of those attributes.
+
+use super::credentials::{der_error, SigningCredentials, SigningKey};
+use super::pades::SignatureSubFilter;
+use crate::error::{PdfError, Result};
+use crate::forms::signature_field::SignatureAlgorithm;
+use chrono::{DateTime, Utc};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::signed_data::{
+    CertificateSet, EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfo, SignerInfos,
+};
+use der::asn1::{ObjectIdentifier, OctetString, SetOfVec};
+use der::{Any, Decode, Encode, Sequence};
+use sha2::Digest;
+use spki::AlgorithmIdentifierOwned;
+use x509_cert::attr::Attribute;
+use x509_cert::Certificate;
+
+/// id-data content type
+pub(crate) const OID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+/// id-signedData content type
+pub(crate) const OID_SIGNED_DATA: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+/// PKCS#9 content-type attribute
+const OID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+/// PKCS#9 message-digest attribute
+const OID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+/// PKCS#9 signing-time attribute
+const OID_SIGNING_TIME: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.5");
+/// ESS signing-certificate-v2 attribute (RFC 5035), required by CAdES
+const OID_SIGNING_CERTIFICATE_V2: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.47");
+
+const OID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const OID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const OID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+const OID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const OID_SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const OID_SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const OID_SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const OID_ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const OID_ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const OID_ECDSA_WITH_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.4");
+
+/// Message digest algorithm used for the document and the signed attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestAlgorithm {
+    /// SHA-256 (default)
+    #[default]
+    Sha256,
+    /// SHA-384
+    Sha384,
+    /// SHA-512
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Hash the concatenation of `parts`
+    pub fn digest_parts(&self, parts: &[&[u8]]) -> Vec<u8> {
+        fn run<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+
+        match self {
+            DigestAlgorithm::Sha256 => run::<sha2::Sha256>(parts),
+            DigestAlgorithm::Sha384 => run::<sha2::Sha384>(parts),
+            DigestAlgorithm::Sha512 => run::<sha2::Sha512>(parts),
+        }
+    }
+
+    /// Hash a single buffer
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        self.digest_parts(&[data])
+    }
+
+    pub(crate) fn oid(&self) -> ObjectIdentifier {
+        match self {
+            DigestAlgorithm::Sha256 => OID_SHA256,
+            DigestAlgorithm::Sha384 => OID_SHA384,
+            DigestAlgorithm::Sha512 => OID_SHA512,
+        }
+    }
+
+    pub(crate) fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        match *oid {
+            OID_SHA256 => Some(DigestAlgorithm::Sha256),
+            OID_SHA384 => Some(DigestAlgorithm::Sha384),
+            OID_SHA512 => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn algorithm_identifier(&self) -> AlgorithmIdentifierOwned {
+        AlgorithmIdentifierOwned {
+            oid: self.oid(),
+            parameters: None,
+        }
+    }
+
+    fn pkcs1v15(&self) -> rsa::Pkcs1v15Sign {
+        match self {
+            DigestAlgorithm::Sha256 => rsa::Pkcs1v15Sign::new::<sha2::Sha256>(),
+            DigestAlgorithm::Sha384 => rsa::Pkcs1v15Sign::new::<sha2::Sha384>(),
+            DigestAlgorithm::Sha512 => rsa::Pkcs1v15Sign::new::<sha2::Sha512>(),
+        }
+    }
+
+    /// The form-level algorithm description for a key type using this digest
+    pub(crate) fn signature_algorithm(&self, rsa: bool) -> SignatureAlgorithm {
+        match (rsa, self) {
+            (true, DigestAlgorithm::Sha256) => SignatureAlgorithm::RsaSha256,
+            (true, DigestAlgorithm::Sha384) => SignatureAlgorithm::RsaSha384,
+            (true, DigestAlgorithm::Sha512) => SignatureAlgorithm::RsaSha512,
+            (false, _) => SignatureAlgorithm::EcdsaSha256,
+        }
+    }
+}
+
+/// ESSCertIDv2 with the default (SHA-256) hash algorithm
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct EssCertIdV2 {
+    cert_hash: OctetString,
+}
+
+/// SigningCertificateV2 ::= SEQUENCE { certs SEQUENCE OF ESSCertIDv2 }
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct SigningCertificateV2 {
+    certs: Vec<EssCertIdV2>,
+}
+
+fn attribute(oid: ObjectIdentifier, value: Any) -> Result<Attribute> {
+    let mut values = SetOfVec::new();
+    values.insert(value).map_err(der_error)?;
+    Ok(Attribute { oid, values })
+}
+
+fn encode_any<T: Encode>(value: &T) -> Result<Any> {
+    let der = value.to_der().map_err(der_error)?;
+    Any::from_der(&der).map_err(der_error)
+}
+
+/// Build a detached CMS `ContentInfo` signing `document_digest`.
+///
+/// For `adbe.pkcs7.detached` a signing-time attribute is included. For
+/// `ETSI.CAdES.detached` the signing-certificate-v2 attribute is included
+/// instead; PAdES forbids signing-time and relies on the `/M` entry.
+pub(crate) fn build_signed_data(
+    credentials: &SigningCredentials,
+    document_digest: &[u8],
+    digest_algorithm: DigestAlgorithm,
+    sub_filter: SignatureSubFilter,
+    signing_time: DateTime<Utc>,
+) -> Result<Vec<u8>> {
+    let signer_certificate = credentials.certificate();
+
+    let mut attributes = vec![
+        attribute(OID_CONTENT_TYPE, encode_any(&OID_DATA)?)?,
+        attribute(
+            OID_MESSAGE_DIGEST,
+            encode_any(&OctetString::new(document_digest).map_err(der_error)?)?,
+        )?,
+    ];
+    match sub_filter {
+        SignatureSubFilter::Pkcs7Detached => {
+            let time = x509_cert::time::Time::try_from(std::time::SystemTime::from(signing_time))
+                .map_err(der_error)?;
+            attributes.push(attribute(OID_SIGNING_TIME, encode_any(&time)?)?);
+        }
+        SignatureSubFilter::CadesDetached => {
+            let certificate_der = signer_certificate.to_der().map_err(der_error)?;
+            let ess = SigningCertificateV2 {
+                certs: vec![EssCertIdV2 {
+                    cert_hash: OctetString::new(DigestAlgorithm::Sha256.digest(&certificate_der))
+                        .map_err(der_error)?,
+                }],
+            };
+            attributes.push(attribute(OID_SIGNING_CERTIFICATE_V2, encode_any(&ess)?)?);
+        }
+    }
+    let signed_attributes = SetOfVec::try_from(attributes).map_err(der_error)?;
+
+    // The signature covers the DER encoding of the attributes as a SET OF
+    let attributes_der = signed_attributes.to_der().map_err(der_error)?;
+    let attributes_digest = digest_algorithm.digest(&attributes_der);
+
+    let (signature_algorithm, signature) =
+        sign_digest(credentials.key(), digest_algorithm, &attributes_digest)?;
+
+    let signer_info = SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: signer_certificate.tbs_certificate.issuer.clone(),
+            serial_number: signer_certificate.tbs_certificate.serial_number.clone(),
+        }),
+        digest_alg: digest_algorithm.algorithm_identifier(),
+        signed_attrs: Some(signed_attributes),
+        signature_algorithm,
+        signature: OctetString::new(signature).map_err(der_error)?,
+        unsigned_attrs: None,
+    };
+
+    let certificates = credentials
+        .chain()
+        .iter()
+        .cloned()
+        .map(CertificateChoices::Certificate)
+        .collect::<Vec<_>>();
+
+    let mut digest_algorithms = SetOfVec::new();
+    digest_algorithms
+        .insert(digest_algorithm.algorithm_identifier())
+        .map_err(der_error)?;
+
+    let signed_data = SignedData {
+        version: CmsVersion::V1,
+        digest_algorithms,
+        encap_content_info: EncapsulatedContentInfo {
+            econtent_type: OID_DATA,
+            econtent: None,
+        },
+        certificates: Some(CertificateSet(
+            SetOfVec::try_from(certificates).map_err(der_error)?,
+        )),
+        crls: None,
+        signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info]).map_err(der_error)?),
+    };
+
+    let content_info = ContentInfo {
+        content_type: OID_SIGNED_DATA,
+        content: encode_any(&signed_data)?,
+    };
+    content_info.to_der().map_err(der_error)
+}
+
+/// Sign a precomputed digest, returning the signature algorithm and value
+fn sign_digest(
+    key: &SigningKey,
+    digest_algorithm: DigestAlgorithm,
+    digest: &[u8],
+) -> Result<(AlgorithmIdentifierOwned, Vec<u8>)> {
+    match key {
+        SigningKey::Rsa(key) => {
+            let signature = key
+                .sign(digest_algorithm.pkcs1v15(), digest)
+                .map_err(|e| PdfError::EncryptionError(format!("RSA signing failed: {e}")))?;
+            let algorithm = AlgorithmIdentifierOwned {
+                oid: OID_RSA_ENCRYPTION,
+                parameters: Some(Any::null()),
+            };
+            Ok((algorithm, signature))
+        }
+        SigningKey::EcdsaP256(key) => {
+            use p256::ecdsa::signature::hazmat::PrehashSigner;
+            let signature: p256::ecdsa::DerSignature = key
+                .sign_prehash(digest)
+                .map_err(|e| PdfError::EncryptionError(format!("ECDSA signing failed: {e}")))?;
+            let oid = match digest_algorithm {
+                DigestAlgorithm::Sha256 => OID_ECDSA_WITH_SHA256,
+                DigestAlgorithm::Sha384 => OID_ECDSA_WITH_SHA384,
+                DigestAlgorithm::Sha512 => OID_ECDSA_WITH_SHA512,
+            };
+            let algorithm = AlgorithmIdentifierOwned {
+                oid,
+                parameters: None,
+            };
+            Ok((algorithm, signature.as_bytes().to_vec()))
+        }
+    }
+}
+
+/// A parsed detached CMS signature
+#[derive(Debug, Clone)]
+pub(crate) struct CmsSignature {
+    signer_info: SignerInfo,
+    /// All certificates embedded in the SignedData
+    pub certificates: Vec<Certificate>,
+    /// Index of the signer certificate in `certificates`
+    signer_index: Option<usize>,
+    /// Digest algorithm declared by the signer
+    pub digest_algorithm: DigestAlgorithm,
+}
+
+impl CmsSignature {
+    /// Parse a DER `ContentInfo`, ignoring trailing zero padding from `/Contents`
+    pub fn parse(contents: &[u8]) -> Result<Self> {
+        let mut reader = der::SliceReader::new(contents).map_err(der_error)?;
+        let content_info: ContentInfo = der::Decode::decode(&mut reader).map_err(der_error)?;
+        if content_info.content_type != OID_SIGNED_DATA {
+            return Err(PdfError::EncryptionError(format!(
+                "Signature is not CMS SignedData (content type {})",
+                content_info.content_type
+            )));
+        }
+        let signed_data: SignedData = content_info.content.decode_as().map_err(der_error)?;
+        let signer_info = signed_data
+            .signer_infos
+            .0
+            .iter()
+            .next()
+            .cloned()
+            .ok_or_else(|| PdfError::EncryptionError("SignedData has no signer".to_string()))?;
+
+        let digest_algorithm =
+            DigestAlgorithm::from_oid(&signer_info.digest_alg.oid).ok_or_else(|| {
+                PdfError::EncryptionError(format!(
+                    "Unsupported digest algorithm {}",
+                    signer_info.digest_alg.oid
+                ))
+            })?;
+
+        let certificates: Vec<Certificate> = signed_data
+            .certificates
+            .map(|set| {
+                set.0
+                    .into_vec()
+                    .into_iter()
+                    .filter_map(|choice| match choice {
+                        CertificateChoices::Certificate(cert) => Some(cert),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let signer_index = match &signer_info.sid {
+            SignerIdentifier::IssuerAndSerialNumber(id) => certificates.iter().position(|c| {
+                c.tbs_certificate.issuer == id.issuer
+                    && c.tbs_certificate.serial_number == id.serial_number
+            }),
+            SignerIdentifier::SubjectKeyIdentifier(_) => (!certificates.is_empty()).then_some(0),
+        };
+
+        Ok(Self {
+            signer_info,
+            certificates,
+            signer_index,
+            digest_algorithm,
+        })
+    }
+
+    /// The certificate identified by the signer identifier
+    pub fn signer_certificate(&self) -> Option<&Certificate> {
+        self.signer_index.map(|i| &self.certificates[i])
+    }
+
+    fn signed_attribute(&self, oid: ObjectIdentifier) -> Option<&Any> {
+        self.signer_info
+            .signed_attrs
+            .as_ref()?
+            .iter()
+            .find(|attr| attr.oid == oid)
+            .and_then(|attr| attr.values.iter().next())
+    }
+
+    /// Whether the signer has signed attributes, which must then include
+    /// the message digest (RFC 5652, section 5.3)
+    pub fn has_signed_attributes(&self) -> bool {
+        self.signer_info.signed_attrs.is_some()
+    }
+
+    /// Value of the message-digest signed attribute
+    pub fn message_digest(&self) -> Option<Vec<u8>> {
+        self.signed_attribute(OID_MESSAGE_DIGEST)
+            .and_then(|value| value.decode_as::<OctetString>().ok())
+            .map(|digest| digest.as_bytes().to_vec())
+    }
+
+    /// Value of the signing-time signed attribute, if present
+    pub fn signing_time(&self) -> Option<DateTime<Utc>> {
+        let value = self.signed_attribute(OID_SIGNING_TIME)?.to_der().ok()?;
+        let time = x509_cert::time::Time::from_der(&value).ok()?;
+        Some(DateTime::<Utc>::from(time.to_system_time()))
+    }
+
+    /// Algorithm family and digest used by the signer
+    pub fn signature_algorithm(&self) -> SignatureAlgorithm {
+        let rsa = matches!(
+            self.signer_info.signature_algorithm.oid,
+            OID_RSA_ENCRYPTION | OID_SHA256_WITH_RSA | OID_SHA384_WITH_RSA | OID_SHA512_WITH_RSA
+        );
+        self.digest_algorithm.signature_algorithm(rsa)
+    }
+
+    /// Raw signature value
+    pub fn signature_value(&self) -> &[u8] {
+        self.signer_info.signature.as_bytes()
+    }
+
+    /// Check the signature against the digest of the signed byte ranges.
+    ///
+    /// Returns `Ok(false)` when the digest does not match or the signature does
+    /// not verify with the signer certificate, and an error when the signature
+    /// cannot be evaluated at all (missing certificate, unsupported algorithm).
+    pub fn verify(&self, document_digest: &[u8]) -> Result<bool> {
+        if self.signer_info.signed_attrs.is_some()
+            && self.message_digest().as_deref() != Some(document_digest)
+        {
+            return Ok(false);
+        }
+        self.verify_signature(document_digest)
+    }
+
+    /// Check only the cryptographic signature of the signer.
+    ///
+    /// With signed attributes the signature covers their DER encoding, which
+    /// binds the document through the message-digest attribute; without them
+    /// it covers `document_digest` directly.
+    pub fn verify_signature(&self, document_digest: &[u8]) -> Result<bool> {
+        let certificate = self.signer_certificate().ok_or_else(|| {
+            PdfError::EncryptionError("Signer certificate not found in signature".to_string())
+        })?;
+
+        let signed_digest = match &self.signer_info.signed_attrs {
+            Some(attributes) => {
+                let der = attributes.to_der().map_err(der_error)?;
+                self.digest_algorithm.digest(&der)
+            }
+            None => document_digest.to_vec(),
+        };
+
+        verify_with_certificate(
+            certificate,
+            &self.signer_info.signature_algorithm.oid,
+            self.digest_algorithm,
+            &signed_digest,
+            self.signature_value(),
+        )
+    }
+}
+
+/// Verify a signature over a precomputed digest with the certificate's public key
+pub(crate) fn verify_with_certificate(
+    certificate: &Certificate,
+    signature_oid: &ObjectIdentifier,
+    digest_algorithm: DigestAlgorithm,
+    digest: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(der_error)?;
+
+    match *signature_oid {
+        OID_RSA_ENCRYPTION | OID_SHA256_WITH_RSA | OID_SHA384_WITH_RSA | OID_SHA512_WITH_RSA => {
+            use rsa::pkcs8::DecodePublicKey;
+            let key = rsa::RsaPublicKey::from_public_key_der(&spki)
+                .map_err(|e| PdfError::EncryptionError(format!("Invalid RSA public key: {e}")))?;
+            Ok(key
+                .verify(digest_algorithm.pkcs1v15(), digest, signature)
+                .is_ok())
+        }
+        OID_ECDSA_WITH_SHA256 | OID_ECDSA_WITH_SHA384 | OID_ECDSA_WITH_SHA512 => {
+            use p256::ecdsa::signature::hazmat::PrehashVerifier;
+            use p256::pkcs8::DecodePublicKey;
+            let key = p256::ecdsa::VerifyingKey::from_public_key_der(&spki).map_err(|e| {
+                PdfError::EncryptionError(format!("Unsupported ECDSA public key: {e}"))
+            })?;
+            let Ok(signature) = p256::ecdsa::Signature::from_der(signature) else {
+                return Ok(false);
+            };
+            Ok(key.verify_prehash(digest, &signature).is_ok())
+        }
+        ref other => Err(PdfError::EncryptionError(format!(
+            "Unsupported signature algorithm {other}"
+        ))),
+    }
+}
+
+/// Re-sign a `ContentInfo` without its message-digest attribute
+#[cfg(test)]
+pub(crate) fn without_message_digest(der: &[u8], credentials: &SigningCredentials) -> Vec<u8> {
+    let mut reader = der::SliceReader::new(der).unwrap();
+    let content_info: ContentInfo = der::Decode::decode(&mut reader).unwrap();
+    let mut signed_data: SignedData = content_info.content.decode_as().unwrap();
+    let mut signer_info = signed_data.signer_infos.0.iter().next().cloned().unwrap();
+    let attributes: Vec<Attribute> = signer_info
+        .signed_attrs
+        .take()
+        .unwrap()
+        .into_vec()
+        .into_iter()
+        .filter(|attr| attr.oid != OID_MESSAGE_DIGEST)
+        .collect();
+    let attributes = SetOfVec::try_from(attributes).unwrap();
+    let digest_algorithm = DigestAlgorithm::from_oid(&signer_info.digest_alg.oid).unwrap();
+    let digest = digest_algorithm.digest(&attributes.to_der().unwrap());
+    let (_, signature) = sign_digest(credentials.key(), digest_algorithm, &digest).unwrap();
+    signer_info.signed_attrs = Some(attributes);
+    signer_info.signature = OctetString::new(signature).unwrap();
+    signed_data.signer_infos = SignerInfos(SetOfVec::try_from(vec![signer_info]).unwrap());
+    ContentInfo {
+        content_type: OID_SIGNED_DATA,
+        content: encode_any(&signed_data).unwrap(),
+    }
+    .to_der()
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::test_helpers;
+    use chrono::TimeZone;
+
+    fn signing_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 17, 12, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_digest_algorithms() {
+        assert_eq!(DigestAlgorithm::Sha256.digest(b"abc").len(), 32);
+        assert_eq!(DigestAlgorithm::Sha384.digest(b"abc").len(), 48);
+        assert_eq!(DigestAlgorithm::Sha512.digest(b"abc").len(), 64);
+        assert_eq!(
+            DigestAlgorithm::Sha256.digest_parts(&[b"ab", b"c"]),
+            DigestAlgorithm::Sha256.digest(b"abc")
+        );
+        for alg in [
+            DigestAlgorithm::Sha256,
+            DigestAlgorithm::Sha384,
+            DigestAlgorithm::Sha512,
+        ] {
+            assert_eq!(DigestAlgorithm::from_oid(&alg.oid()), Some(alg));
+        }
+    }
+
+    #[test]
+    fn test_rsa_pkcs7_roundtrip() {
+        let credentials = test_helpers::rsa_credentials();
+        let digest = DigestAlgorithm::Sha256.digest(b"document bytes");
+        let der = build_signed_data(
+            &credentials,
+            &digest,
+            DigestAlgorithm::Sha256,
+            SignatureSubFilter::Pkcs7Detached,
+            signing_time(),
+        )
+        .unwrap();
+
+        let signature = CmsSignature::parse(&der).unwrap();
+        assert_eq!(signature.message_digest(), Some(digest.clone()));
+        assert_eq!(signature.signing_time(), Some(signing_time()));
+        assert_eq!(
+            signature.signature_algorithm(),
+            SignatureAlgorithm::RsaSha256
+        );
+        assert!(signature.signer_certificate().is_some());
+        assert!(signature.verify(&digest).unwrap());
+
+        let other = DigestAlgorithm::Sha256.digest(b"tampered bytes");
+        assert!(!signature.verify(&other).unwrap());
+    }
+
+    #[test]
+    fn test_ecdsa_cades_roundtrip() {
+        let credentials = test_helpers::ecdsa_credentials();
+        let digest = DigestAlgorithm::Sha384.digest(b"document bytes");
+        let der = build_signed_data(
+            &credentials,
+            &digest,
+            DigestAlgorithm::Sha384,
+            SignatureSubFilter::CadesDetached,
+            signing_time(),
+        )
+        .unwrap();
+
+        let signature = CmsSignature::parse(&der).unwrap();
+        // CAdES signatures carry no signing-time attribute
+        assert_eq!(signature.signing_time(), None);
+        assert!(signature
+            .signed_attribute(OID_SIGNING_CERTIFICATE_V2)
+            .is_some());
+        assert_eq!(signature.digest_algorithm, DigestAlgorithm::Sha384);
+        assert!(signature.verify(&digest).unwrap());
+    }
+
+    #[test]
+    fn test_parse_ignores_padding() {
+        let credentials = test_helpers::rsa_credentials();
+        let digest = DigestAlgorithm::Sha256.digest(b"x");
+        let mut der = build_signed_data(
+            &credentials,
+            &digest,
+            DigestAlgorithm::Sha256,
+            SignatureSubFilter::Pkcs7Detached,
+            signing_time(),
+        )
+        .unwrap();
+        der.extend_from_slice(&[0u8; 512]);
+        assert!(CmsSignature::parse(&der).unwrap().verify(&digest).unwrap());
+    }
+
+    #[test]
+    fn test_chain_is_embedded() {
+        let credentials = test_helpers::chain_credentials();
+        let digest = DigestAlgorithm::Sha256.digest(b"x");
+        let der = build_signed_data(
+            &credentials,
+            &digest,
+            DigestAlgorithm::Sha256,
+            SignatureSubFilter::CadesDetached,
+            signing_time(),
+        )
+        .unwrap();
+        let signature = CmsSignature::parse(&der).unwrap();
+        assert_eq!(signature.certificates.len(), 2);
+        assert_eq!(
+            signature.signer_certificate(),
+            Some(credentials.certificate())
+        );
+    }
+
+    #[test]
+    fn test_missing_message_digest_fails() {
+        let credentials = test_helpers::rsa_credentials();
+        let digest = DigestAlgorithm::Sha256.digest(b"document bytes");
+        let der = build_signed_data(
+            &credentials,
+            &digest,
+            DigestAlgorithm::Sha256,
+            SignatureSubFilter::Pkcs7Detached,
+            signing_time(),
+        )
+        .unwrap();
+
+        let signature = CmsSignature::parse(&without_message_digest(&der, &credentials)).unwrap();
+        assert!(signature.has_signed_attributes());
+        assert_eq!(signature.message_digest(), None);
+        // The attributes are signed, but nothing binds them to the document
+        assert!(signature.verify_signature(&digest).unwrap());
+        assert!(!signature.verify(&digest).unwrap());
+    }
+
+    #[test]
+    fn test_parse_garbage() {
+        assert!(CmsSignature::parse(b"garbage").is_err());
+        assert!(CmsSignature::parse(&[0u8; 64]).is_err());
+    }
+}
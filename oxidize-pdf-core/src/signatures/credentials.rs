@@ -0,0 +1,262 @@
+//! Signing keys and X.509 certificate chains
+//!
+//! A [`SigningCredentials`] value bundles the private key used to produce the
+//! CMS signature with the signer certificate and any intermediate certificates
+//! that should be embedded so verifiers can build the chain.
+
+use crate::error::{PdfError, Result};
+use crate::forms::signature_field::SignerInfo;
+use der::asn1::{Ia5StringRef, PrintableStringRef, Utf8StringRef};
+use der::{Decode, Encode};
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::traits::PublicKeyParts;
+use x509_cert::name::Name;
+use x509_cert::Certificate;
+
+/// OID for the common name attribute (CN)
+const OID_COMMON_NAME: &str = "2.5.4.3";
+/// OID for the organization attribute (O)
+const OID_ORGANIZATION: &str = "2.5.4.10";
+/// OID for the organizational unit attribute (OU)
+const OID_ORGANIZATIONAL_UNIT: &str = "2.5.4.11";
+/// OID for the PKCS#9 e-mail address attribute
+const OID_EMAIL_ADDRESS: &str = "1.2.840.113549.1.9.1";
+
+/// Private key used to produce signatures
+#[derive(Clone)]
+pub enum SigningKey {
+    /// RSA key, signatures use PKCS#1 v1.5 padding
+    Rsa(rsa::RsaPrivateKey),
+    /// ECDSA key on the NIST P-256 curve
+    EcdsaP256(p256::ecdsa::SigningKey),
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        match self {
+            SigningKey::Rsa(key) => write!(f, "SigningKey::Rsa({} bits)", key.size() * 8),
+            SigningKey::EcdsaP256(_) => write!(f, "SigningKey::EcdsaP256"),
+        }
+    }
+}
+
+impl SigningKey {
+    /// Load an unencrypted PKCS#8 private key in DER form (RSA or P-256)
+    pub fn from_pkcs8_der(der: &[u8]) -> Result<Self> {
+        if let Ok(key) = rsa::RsaPrivateKey::from_pkcs8_der(der) {
+            return Ok(SigningKey::Rsa(key));
+        }
+        p256::ecdsa::SigningKey::from_pkcs8_der(der)
+            .map(SigningKey::EcdsaP256)
+            .map_err(|e| {
+                PdfError::EncryptionError(format!("Unsupported or invalid PKCS#8 key: {e}"))
+            })
+    }
+
+    /// Load an unencrypted PKCS#8 private key in PEM form (`BEGIN PRIVATE KEY`)
+    pub fn from_pkcs8_pem(pem: &str) -> Result<Self> {
+        if let Ok(key) = rsa::RsaPrivateKey::from_pkcs8_pem(pem) {
+            return Ok(SigningKey::Rsa(key));
+        }
+        p256::ecdsa::SigningKey::from_pkcs8_pem(pem)
+            .map(SigningKey::EcdsaP256)
+            .map_err(|e| {
+                PdfError::EncryptionError(format!("Unsupported or invalid PKCS#8 key: {e}"))
+            })
+    }
+
+    /// Approximate size in bytes of a signature produced with this key
+    pub(crate) fn signature_len(&self) -> usize {
+        match self {
+            SigningKey::Rsa(key) => key.size(),
+            // DER encoded (r, s) pair, worst case
+            SigningKey::EcdsaP256(_) => 72,
+        }
+    }
+
+    /// Check whether the certificate carries the public half of this key
+    fn matches_certificate(&self, certificate: &Certificate) -> Result<bool> {
+        let spki = certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()
+            .map_err(der_error)?;
+        Ok(match self {
+            SigningKey::Rsa(key) => {
+                use rsa::pkcs8::DecodePublicKey;
+                rsa::RsaPublicKey::from_public_key_der(&spki)
+                    .map(|public| public == key.to_public_key())
+                    .unwrap_or(false)
+            }
+            SigningKey::EcdsaP256(key) => {
+                use p256::pkcs8::DecodePublicKey;
+                p256::ecdsa::VerifyingKey::from_public_key_der(&spki)
+                    .map(|public| &public == key.verifying_key())
+                    .unwrap_or(false)
+            }
+        })
+    }
+}
+
+/// Private key plus the certificate chain embedded in the signature
+#[derive(Debug, Clone)]
+pub struct SigningCredentials {
+    key: SigningKey,
+    /// Signer certificate first, followed by intermediates
+    chain: Vec<Certificate>,
+}
+
+impl SigningCredentials {
+    /// Create credentials from a key and a certificate chain.
+    ///
+    /// The first certificate must be the signer certificate matching `key`;
+    /// the remaining ones are embedded as-is so verifiers can build the chain.
+    pub fn new(key: SigningKey, chain: Vec<Certificate>) -> Result<Self> {
+        let signer = chain.first().ok_or_else(|| {
+            PdfError::InvalidOperation("At least one certificate is required".to_string())
+        })?;
+        if !key.matches_certificate(signer)? {
+            return Err(PdfError::InvalidOperation(
+                "Private key does not match the signer certificate".to_string(),
+            ));
+        }
+        Ok(Self { key, chain })
+    }
+
+    /// Create credentials from a PKCS#8 DER key and DER-encoded certificates
+    pub fn from_der(key_der: &[u8], certificates_der: &[&[u8]]) -> Result<Self> {
+        let key = SigningKey::from_pkcs8_der(key_der)?;
+        let chain = certificates_der
+            .iter()
+            .map(|der| Certificate::from_der(der).map_err(der_error))
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(key, chain)
+    }
+
+    /// Create credentials from a PKCS#8 PEM key and a PEM certificate chain
+    /// (one or more concatenated `BEGIN CERTIFICATE` blocks, signer first)
+    pub fn from_pem(key_pem: &str, certificates_pem: &str) -> Result<Self> {
+        let key = SigningKey::from_pkcs8_pem(key_pem)?;
+        let chain = Certificate::load_pem_chain(certificates_pem.as_bytes()).map_err(der_error)?;
+        Self::new(key, chain)
+    }
+
+    /// The private key
+    pub fn key(&self) -> &SigningKey {
+        &self.key
+    }
+
+    /// The signer certificate
+    pub fn certificate(&self) -> &Certificate {
+        &self.chain[0]
+    }
+
+    /// Signer certificate followed by intermediates
+    pub fn chain(&self) -> &[Certificate] {
+        &self.chain
+    }
+
+    /// Describe the signer using the subject of the signer certificate
+    pub fn signer_info(&self) -> SignerInfo {
+        signer_info_from_certificate(self.certificate())
+    }
+}
+
+/// Build [`SignerInfo`] from the subject of a certificate
+pub(crate) fn signer_info_from_certificate(certificate: &Certificate) -> SignerInfo {
+    let subject = &certificate.tbs_certificate.subject;
+    let common_name = name_attribute(subject, OID_COMMON_NAME);
+
+    let mut info = SignerInfo::new(common_name.unwrap_or_else(|| subject.to_string()));
+    info.distinguished_name = Some(subject.to_string());
+    info.email = name_attribute(subject, OID_EMAIL_ADDRESS);
+    info.organization = name_attribute(subject, OID_ORGANIZATION);
+    info.organizational_unit = name_attribute(subject, OID_ORGANIZATIONAL_UNIT);
+    info
+}
+
+/// Find the first value of the given attribute type in a distinguished name
+pub(crate) fn name_attribute(name: &Name, oid: &str) -> Option<String> {
+    let oid = der::asn1::ObjectIdentifier::new(oid).ok()?;
+    name.0
+        .iter()
+        .flat_map(|rdn| rdn.0.iter())
+        .find(|atv| atv.oid == oid)
+        .and_then(|atv| {
+            Utf8StringRef::try_from(&atv.value)
+                .map(|s| s.as_str().to_string())
+                .or_else(|_| PrintableStringRef::try_from(&atv.value).map(|s| s.to_string()))
+                .or_else(|_| Ia5StringRef::try_from(&atv.value).map(|s| s.to_string()))
+                .ok()
+        })
+}
+
+/// Map an ASN.1 encoding error to a PDF error
+pub(crate) fn der_error(err: der::Error) -> PdfError {
+    PdfError::EncryptionError(format!("ASN.1 error: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::test_helpers;
+
+    #[test]
+    fn test_load_rsa_credentials() {
+        let credentials = test_helpers::rsa_credentials();
+        assert!(matches!(credentials.key(), SigningKey::Rsa(_)));
+        assert_eq!(credentials.chain().len(), 1);
+        assert_eq!(credentials.key().signature_len(), 256);
+    }
+
+    #[test]
+    fn test_load_ecdsa_credentials() {
+        let credentials = test_helpers::ecdsa_credentials();
+        assert!(matches!(credentials.key(), SigningKey::EcdsaP256(_)));
+    }
+
+    #[test]
+    fn test_chain_credentials() {
+        let credentials = test_helpers::chain_credentials();
+        assert_eq!(credentials.chain().len(), 2);
+        assert_eq!(credentials.signer_info().name, "Chain Signer");
+    }
+
+    #[test]
+    fn test_signer_info_from_subject() {
+        let info = test_helpers::rsa_credentials().signer_info();
+        assert_eq!(info.name, "Test RSA Signer");
+        assert_eq!(info.organization.as_deref(), Some("oxidize-pdf Tests"));
+        assert_eq!(info.organizational_unit.as_deref(), Some("Signatures"));
+        assert_eq!(info.email.as_deref(), Some("rsa-signer@example.com"));
+        assert!(info
+            .distinguished_name
+            .unwrap()
+            .contains("CN=Test RSA Signer"));
+    }
+
+    #[test]
+    fn test_mismatched_key_and_certificate() {
+        let result =
+            SigningCredentials::from_pem(test_helpers::ECDSA_KEY_PEM, test_helpers::RSA_CERT_PEM);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_empty_chain_rejected() {
+        let key = SigningKey::from_pkcs8_pem(test_helpers::RSA_KEY_PEM).unwrap();
+        assert!(SigningCredentials::new(key, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        assert!(SigningKey::from_pkcs8_der(b"not a key").is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_key_material() {
+        let key = SigningKey::from_pkcs8_pem(test_helpers::RSA_KEY_PEM).unwrap();
+        assert_eq!(format!("{key:?}"), "SigningKey::Rsa(2048 bits)");
+    }
+}
@@ -0,0 +1,112 @@
+//! Interactive form field tree of parsed documents (ISO 32000-1 §12.7.3)
+
+use crate::error::Result;
+use crate::objects::ObjectId;
+use crate::parser::strings::text_string;
+use crate::parser::{PdfDictionary, PdfDocument, PdfObject};
+use std::io::{Read, Seek};
+
+/// Maximum nesting of the field tree before giving up
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// A terminal or non-terminal field found in the AcroForm
+#[derive(Debug, Clone)]
+pub(crate) struct ParsedField {
+    /// Fully qualified field name
+    pub name: String,
+    /// Indirect reference of the field dictionary
+    pub id: ObjectId,
+    /// The field dictionary
+    pub dict: PdfDictionary,
+    /// Field type (`/FT`), inherited from ancestors when absent
+    pub field_type: Option<String>,
+}
+
+impl ParsedField {
+    /// Whether this is a signature field
+    pub fn is_signature(&self) -> bool {
+        self.field_type.as_deref() == Some("Sig")
+    }
+
+    /// Whether this signature field already holds a signature value
+    pub fn is_signed(&self) -> bool {
+        self.is_signature() && self.dict.contains_key("V")
+    }
+}
+
+/// Collect every named field of the document's AcroForm, depth first
+pub(crate) fn collect_fields<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> Result<Vec<ParsedField>> {
+    let catalog = document.catalog()?;
+    let Some(acro_form) = catalog.get("AcroForm") else {
+        return Ok(Vec::new());
+    };
+    let acro_form = document.resolve(acro_form)?;
+    let Some(fields) = acro_form.as_dict().and_then(|d| d.get("Fields")) else {
+        return Ok(Vec::new());
+    };
+    let PdfObject::Array(fields) = document.resolve(fields)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut collected = Vec::new();
+    walk_fields(document, &fields.0, "", None, 0, &mut collected)?;
+    Ok(collected)
+}
+
+fn walk_fields<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    fields: &[PdfObject],
+    prefix: &str,
+    inherited_type: Option<&str>,
+    depth: usize,
+    collected: &mut Vec<ParsedField>,
+) -> Result<()> {
+    if depth > MAX_FIELD_DEPTH {
+        return Ok(());
+    }
+    for field in fields {
+        let Some((num, gen)) = field.as_reference() else {
+            continue;
+        };
+        let Some(dict) = document.get_object(num, gen)?.as_dict().cloned() else {
+            continue;
+        };
+        // Kids without /T are widget annotations of the parent field
+        let Some(partial) = dict.get("T").and_then(|t| t.as_string()) else {
+            continue;
+        };
+        let partial = text_string(partial.as_bytes());
+        let name = if prefix.is_empty() {
+            partial
+        } else {
+            format!("{prefix}.{partial}")
+        };
+        let field_type = dict
+            .get("FT")
+            .and_then(|o| o.as_name())
+            .map(|n| n.as_str().to_string())
+            .or_else(|| inherited_type.map(str::to_string));
+
+        let kids = match dict.get("Kids") {
+            Some(PdfObject::Array(kids)) => kids.0.clone(),
+            _ => Vec::new(),
+        };
+        collected.push(ParsedField {
+            name: name.clone(),
+            id: ObjectId::new(num, gen),
+            dict,
+            field_type: field_type.clone(),
+        });
+        walk_fields(
+            document,
+            &kids,
+            &name,
+            field_type.as_deref(),
+            depth + 1,
+            collected,
+        )?;
+    }
+    Ok(())
+}
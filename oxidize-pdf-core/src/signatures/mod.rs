@@ -0,0 +1,59 @@
+//! Digital signatures for PDF documents (ISO 32000-1 §12.8)
+//!
+//! This module produces real cryptographic signatures: a detached CMS
+//! (PKCS#7) `SignedData` structure computed over the document byte ranges
+//! and embedded in the signature dictionary of an incremental update.
+//!
+//! # Features
+//!
+//! - **Sub-filters**: `adbe.pkcs7.detached` and PAdES `ETSI.CAdES.detached`
+//! - **Keys**: RSA (PKCS#1 v1.5) and ECDSA P-256, loaded from PKCS#8
+//! - **Digests**: SHA-256, SHA-384 and SHA-512
+//! - **Incremental updates**: earlier revisions and signatures stay intact
+//! - **Certification**: DocMDP permissions and FieldMDP field locking
+//! - **Appearances**: optional visible widget on any page
+//! - **Verification**: digest, signature and DocMDP/FieldMDP checks of parsed
+//!   documents through [`PdfDocument::verify_signatures`](crate::parser::PdfDocument::verify_signatures)
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use oxidize_pdf::signatures::{PdfSigner, SignatureOptions, SigningCredentials};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let credentials = SigningCredentials::from_pem(
+//!     &std::fs::read_to_string("signer.key.pem")?,
+//!     &std::fs::read_to_string("signer.cert.pem")?,
+//! )?;
+//!
+//! let pdf = std::fs::read("contract.pdf")?;
+//! let options = SignatureOptions::new("Signature1")
+//!     .with_reason("I approve this document")
+//!     .with_location("Madrid");
+//!
+//! let signed = PdfSigner::new(credentials).sign(&pdf, &options)?;
+//! std::fs::write("contract-signed.pdf", &signed.bytes)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod cms;
+mod credentials;
+pub(crate) mod fields;
+mod pades;
+mod verification;
+
+#[cfg(test)]
+pub(crate) mod test_helpers;
+
+pub use cms::DigestAlgorithm;
+pub use credentials::{SigningCredentials, SigningKey};
+pub use pades::{
+    DocMdpPermission, PdfSigner, SignatureOptions, SignatureSubFilter, SignedDocument,
+    VisibleSignature,
+};
+pub use verification::SignatureVerification;
+
+pub(crate) use cms::CmsSignature;
+pub(crate) use credentials::der_error;
+pub(crate) use verification::verify_document;
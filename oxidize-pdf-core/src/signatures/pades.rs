@@ -0,0 +1,1107 @@
+//! Signing existing PDF documents (ISO 32000-1 §12.8, ETSI EN 319 142 PAdES)
+//!
+//! Signing appends an incremental update containing the signature field, its
+//! widget and a signature dictionary. The signature dictionary reserves a
+//! `/Contents` hole and a `/ByteRange` covering every other byte of the file.
+//! Once the update is written, the byte range is patched in, the covered bytes
+//! are hashed and the detached CMS signature is written into the hole.
+
+use super::cms::{build_signed_data, DigestAlgorithm};
+use super::credentials::SigningCredentials;
+use super::fields::collect_fields;
+use crate::error::{PdfError, Result};
+use crate::forms::signature_field::{
+    Certificate as CertificateSummary, SignatureAppearance, SignatureField, SignatureValue,
+};
+use crate::geometry::Rectangle;
+use crate::objects::{Dictionary, Object, ObjectId};
+use crate::parser::strings::text_bytes;
+use crate::parser::strings::text_string;
+use crate::parser::{
+    PdfArray, PdfDictionary, PdfDocument, PdfName, PdfObject, PdfReader, PdfString,
+};
+use crate::writer::{format_pdf_date, PdfWriter, WriterConfig};
+use chrono::{DateTime, Utc};
+use der::Encode;
+use std::io::Cursor;
+
+/// Placeholder written for every `/ByteRange` number before patching
+const BYTE_RANGE_PLACEHOLDER: i64 = 9_999_999_999;
+
+/// Widget annotation flags: Print (4) + Locked (128)
+const SIGNATURE_WIDGET_FLAGS: i64 = 132;
+
+/// Encoding of the signature value (`/SubFilter`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureSubFilter {
+    /// `adbe.pkcs7.detached` - detached PKCS#7 signature (ISO 32000-1)
+    #[default]
+    Pkcs7Detached,
+    /// `ETSI.CAdES.detached` - CAdES signature required by PAdES baseline profiles
+    CadesDetached,
+}
+
+impl SignatureSubFilter {
+    /// PDF name of the sub-filter
+    pub fn pdf_name(&self) -> &'static str {
+        match self {
+            SignatureSubFilter::Pkcs7Detached => "adbe.pkcs7.detached",
+            SignatureSubFilter::CadesDetached => "ETSI.CAdES.detached",
+        }
+    }
+
+    /// Parse a `/SubFilter` name
+    pub fn from_pdf_name(name: &str) -> Option<Self> {
+        match name {
+            "adbe.pkcs7.detached" => Some(SignatureSubFilter::Pkcs7Detached),
+            "ETSI.CAdES.detached" => Some(SignatureSubFilter::CadesDetached),
+            _ => None,
+        }
+    }
+}
+
+/// Changes allowed after a certification signature (ISO 32000-1 Table 254)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocMdpPermission {
+    /// No changes to the document are permitted
+    NoChanges = 1,
+    /// Filling in forms, instantiating page templates and signing
+    FormFilling = 2,
+    /// As `FormFilling`, plus annotation creation, deletion and modification
+    FormFillingAndAnnotations = 3,
+}
+
+impl DocMdpPermission {
+    /// Parse the `/P` value of DocMDP transform parameters
+    pub fn from_value(value: i64) -> Option<Self> {
+        match value {
+            1 => Some(DocMdpPermission::NoChanges),
+            2 => Some(DocMdpPermission::FormFilling),
+            3 => Some(DocMdpPermission::FormFillingAndAnnotations),
+            _ => None,
+        }
+    }
+}
+
+/// Placement of a visible signature widget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VisibleSignature {
+    /// Zero-based page index
+    pub page: u32,
+    /// Widget rectangle in default user space
+    pub rect: Rectangle,
+}
+
+/// Options controlling how a document is signed
+#[derive(Debug, Clone)]
+pub struct SignatureOptions {
+    /// Fully qualified name of the signature field; created when missing
+    pub field_name: String,
+    /// Signature encoding
+    pub sub_filter: SignatureSubFilter,
+    /// Digest used for the byte ranges and signed attributes
+    pub digest_algorithm: DigestAlgorithm,
+    /// Reason for signing (`/Reason`)
+    pub reason: Option<String>,
+    /// Signing location (`/Location`)
+    pub location: Option<String>,
+    /// Contact information (`/ContactInfo`)
+    pub contact_info: Option<String>,
+    /// Signer name (`/Name`); defaults to the certificate common name
+    pub signer_name: Option<String>,
+    /// Signing time; defaults to the current time
+    pub signing_time: Option<DateTime<Utc>>,
+    /// Make this a certification (author) signature with DocMDP permissions
+    pub certification: Option<DocMdpPermission>,
+    /// Fields locked by this signature through a FieldMDP transform
+    pub lock_fields: Vec<String>,
+    /// Visible widget for new fields; `None` creates an invisible signature
+    pub visible: Option<VisibleSignature>,
+    /// Appearance used for visible widgets
+    pub appearance: SignatureAppearance,
+    /// Bytes reserved for the CMS signature; estimated from the chain when `None`
+    pub reserved_size: Option<usize>,
+}
+
+impl SignatureOptions {
+    /// Create options for signing into the named field
+    pub fn new(field_name: impl Into<String>) -> Self {
+        Self {
+            field_name: field_name.into(),
+            sub_filter: SignatureSubFilter::default(),
+            digest_algorithm: DigestAlgorithm::default(),
+            reason: None,
+            location: None,
+            contact_info: None,
+            signer_name: None,
+            signing_time: None,
+            certification: None,
+            lock_fields: Vec::new(),
+            visible: None,
+            appearance: SignatureAppearance::default(),
+            reserved_size: None,
+        }
+    }
+
+    /// Set the signature encoding
+    pub fn with_sub_filter(mut self, sub_filter: SignatureSubFilter) -> Self {
+        self.sub_filter = sub_filter;
+        self
+    }
+
+    /// Set the digest algorithm
+    pub fn with_digest_algorithm(mut self, digest_algorithm: DigestAlgorithm) -> Self {
+        self.digest_algorithm = digest_algorithm;
+        self
+    }
+
+    /// Set the reason for signing
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Set the signing location
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set contact information
+    pub fn with_contact_info(mut self, contact_info: impl Into<String>) -> Self {
+        self.contact_info = Some(contact_info.into());
+        self
+    }
+
+    /// Override the signer name
+    pub fn with_signer_name(mut self, name: impl Into<String>) -> Self {
+        self.signer_name = Some(name.into());
+        self
+    }
+
+    /// Set an explicit signing time
+    pub fn with_signing_time(mut self, time: DateTime<Utc>) -> Self {
+        self.signing_time = Some(time);
+        self
+    }
+
+    /// Certify the document with the given DocMDP permissions
+    pub fn certify(mut self, permission: DocMdpPermission) -> Self {
+        self.certification = Some(permission);
+        self
+    }
+
+    /// Lock the given fields once signed (FieldMDP)
+    pub fn lock_fields(mut self, fields: Vec<String>) -> Self {
+        self.lock_fields = fields;
+        self
+    }
+
+    /// Show the signature in a widget on the given page
+    pub fn visible(mut self, page: u32, rect: Rectangle) -> Self {
+        self.visible = Some(VisibleSignature { page, rect });
+        self
+    }
+
+    /// Customize the appearance of visible widgets
+    pub fn with_appearance(mut self, appearance: SignatureAppearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    /// Reserve a fixed number of bytes for the signature value
+    pub fn with_reserved_size(mut self, bytes: usize) -> Self {
+        self.reserved_size = Some(bytes);
+        self
+    }
+}
+
+/// Result of signing a document
+#[derive(Debug, Clone)]
+pub struct SignedDocument {
+    /// The complete signed file
+    pub bytes: Vec<u8>,
+    /// The `/ByteRange` written into the signature dictionary
+    pub byte_range: [usize; 4],
+    /// Signature metadata and the embedded CMS blob (`signature_bytes`)
+    pub signature: SignatureValue,
+}
+
+/// Signs PDF documents with a private key and certificate chain
+#[derive(Debug, Clone)]
+pub struct PdfSigner {
+    credentials: SigningCredentials,
+}
+
+impl PdfSigner {
+    /// Create a signer for the given credentials
+    pub fn new(credentials: SigningCredentials) -> Self {
+        Self { credentials }
+    }
+
+    /// The credentials used by this signer
+    pub fn credentials(&self) -> &SigningCredentials {
+        &self.credentials
+    }
+
+    /// Sign `pdf` and return the signed file.
+    ///
+    /// The original bytes are preserved; the signature is appended as an
+    /// incremental update so earlier signatures remain valid.
+    pub fn sign(&self, pdf: &[u8], options: &SignatureOptions) -> Result<SignedDocument> {
+        let signing_time = options.signing_time.unwrap_or_else(Utc::now);
+        let reserved = options
+            .reserved_size
+            .unwrap_or_else(|| self.estimate_signature_size());
+
+        let (objects, sig_id) = self.build_update(pdf, options, signing_time, reserved)?;
+
+        let mut output = Vec::with_capacity(pdf.len() + reserved * 2 + 4096);
+        {
+            let mut writer = PdfWriter::with_config(&mut output, WriterConfig::incremental());
+            writer.write_incremental_parsed_objects(pdf, objects)?;
+        }
+
+        let (contents_start, contents_end) =
+            locate_contents_placeholder(&output, pdf.len(), reserved)?;
+        let byte_range = [0, contents_start, contents_end, output.len() - contents_end];
+        patch_byte_range(&mut output, pdf.len(), &byte_range)?;
+
+        let digest = options
+            .digest_algorithm
+            .digest_parts(&[&output[..contents_start], &output[contents_end..]]);
+        let cms = build_signed_data(
+            &self.credentials,
+            &digest,
+            options.digest_algorithm,
+            options.sub_filter,
+            signing_time,
+        )?;
+        if cms.len() > reserved {
+            return Err(PdfError::InvalidOperation(format!(
+                "Signature of {} bytes does not fit the {} bytes reserved in /Contents for object {}",
+                cms.len(),
+                reserved,
+                sig_id
+            )));
+        }
+
+        let mut hex = String::with_capacity(reserved * 2 + 2);
+        hex.push('<');
+        for byte in &cms {
+            hex.push_str(&format!("{byte:02x}"));
+        }
+        while hex.len() < reserved * 2 + 1 {
+            hex.push('0');
+        }
+        hex.push('>');
+        output[contents_start..contents_end].copy_from_slice(hex.as_bytes());
+
+        let is_rsa = matches!(
+            self.credentials.key(),
+            super::credentials::SigningKey::Rsa(_)
+        );
+        let signature = SignatureValue {
+            timestamp: signing_time,
+            document_hash: digest,
+            algorithm: options.digest_algorithm.signature_algorithm(is_rsa),
+            certificates: self
+                .credentials
+                .chain()
+                .iter()
+                .map(certificate_summary)
+                .collect(),
+            signature_bytes: cms,
+        };
+
+        Ok(SignedDocument {
+            bytes: output,
+            byte_range,
+            signature,
+        })
+    }
+
+    /// Signature size estimate: certificates plus signed attributes and signature
+    fn estimate_signature_size(&self) -> usize {
+        let certificates: usize = self
+            .credentials
+            .chain()
+            .iter()
+            .map(|c| c.to_der().map(|der| der.len()).unwrap_or(2048))
+            .sum();
+        certificates + self.credentials.key().signature_len() + 2048
+    }
+
+    /// Build the objects of the incremental update, returning them with the
+    /// signature dictionary id.
+    ///
+    /// Objects taken from the document stay in parsed form so their strings
+    /// and names are written back byte for byte.
+    fn build_update(
+        &self,
+        pdf: &[u8],
+        options: &SignatureOptions,
+        signing_time: DateTime<Utc>,
+        reserved: usize,
+    ) -> Result<(Vec<(ObjectId, PdfObject)>, ObjectId)> {
+        let reader = PdfReader::new(Cursor::new(pdf))?;
+        if reader.is_encrypted() {
+            return Err(PdfError::EncryptionError(
+                "Signing encrypted documents is not supported".to_string(),
+            ));
+        }
+        let document = PdfDocument::new(reader);
+        let trailer = document.trailer();
+        let root = trailer.root()?;
+        let mut next_number = trailer.size()?;
+        let mut allocate = || {
+            let id = ObjectId::new(next_number, 0);
+            next_number += 1;
+            id
+        };
+
+        let mut catalog_out = document.catalog()?;
+        let mut objects = Vec::new();
+
+        // AcroForm may be indirect, inline or missing
+        let acro_form_ref = catalog_out.get("AcroForm").and_then(|o| o.as_reference());
+        let mut acro_form = match catalog_out.get("AcroForm") {
+            Some(obj) => document
+                .resolve(obj)?
+                .as_dict()
+                .cloned()
+                .unwrap_or_default(),
+            None => PdfDictionary::new(),
+        };
+        let fields_ref = acro_form.get("Fields").and_then(|o| o.as_reference());
+        let fields = match acro_form.get("Fields") {
+            Some(obj) => match document.resolve(obj)? {
+                PdfObject::Array(arr) => arr.0,
+                _ => Vec::new(),
+            },
+            None => Vec::new(),
+        };
+
+        let parsed_fields = collect_fields(&document)?;
+        if options.certification.is_some() && parsed_fields.iter().any(|f| f.is_signed()) {
+            return Err(PdfError::InvalidOperation(
+                "A certification signature must be the first signature in the document".to_string(),
+            ));
+        }
+
+        let existing = parsed_fields
+            .into_iter()
+            .find(|f| f.name == options.field_name);
+        if let Some(field) = &existing {
+            if !field.is_signature() {
+                return Err(PdfError::InvalidOperation(format!(
+                    "Field '{}' is not a signature field",
+                    options.field_name
+                )));
+            }
+            if field.dict.contains_key("V") {
+                return Err(PdfError::InvalidOperation(format!(
+                    "Field '{}' is already signed",
+                    options.field_name
+                )));
+            }
+        }
+
+        // Signature dictionary with the /Contents and /ByteRange holes
+        let sig_id = allocate();
+        let signer_name = options
+            .signer_name
+            .clone()
+            .unwrap_or_else(|| self.credentials.signer_info().name);
+        let mut sig = PdfDictionary::new();
+        sig.insert("Type".to_string(), name("Sig"));
+        sig.insert("Filter".to_string(), name("Adobe.PPKLite"));
+        sig.insert("SubFilter".to_string(), name(options.sub_filter.pdf_name()));
+        sig.insert(
+            "ByteRange".to_string(),
+            PdfObject::Array(PdfArray(vec![
+                PdfObject::Integer(0),
+                PdfObject::Integer(BYTE_RANGE_PLACEHOLDER),
+                PdfObject::Integer(BYTE_RANGE_PLACEHOLDER),
+                PdfObject::Integer(BYTE_RANGE_PLACEHOLDER),
+            ])),
+        );
+        sig.insert(
+            "Contents".to_string(),
+            PdfObject::String(PdfString::new(vec![b'0'; reserved * 2])),
+        );
+        sig.insert("M".to_string(), text(&format_pdf_date(signing_time)));
+        sig.insert("Name".to_string(), text(&signer_name));
+        if let Some(reason) = &options.reason {
+            sig.insert("Reason".to_string(), text(reason));
+        }
+        if let Some(location) = &options.location {
+            sig.insert("Location".to_string(), text(location));
+        }
+        if let Some(contact) = &options.contact_info {
+            sig.insert("ContactInfo".to_string(), text(contact));
+        }
+
+        let mut references = Vec::new();
+        if let Some(permission) = options.certification {
+            let mut params = PdfDictionary::new();
+            params.insert("Type".to_string(), name("TransformParams"));
+            params.insert("P".to_string(), PdfObject::Integer(permission as i64));
+            params.insert("V".to_string(), name("1.2"));
+            references.push(signature_reference("DocMDP", params));
+
+            let mut perms = PdfDictionary::new();
+            perms.insert("DocMDP".to_string(), reference(sig_id));
+            catalog_out.insert("Perms".to_string(), PdfObject::Dictionary(perms));
+        }
+        let lock_fields = match &existing {
+            Some(field) if options.lock_fields.is_empty() => {
+                lock_fields_from_dict(&document, &field.dict)?
+            }
+            _ => options.lock_fields.clone(),
+        };
+        if !lock_fields.is_empty() {
+            let mut params = PdfDictionary::new();
+            params.insert("Type".to_string(), name("TransformParams"));
+            params.insert("Action".to_string(), name("Include"));
+            params.insert("Fields".to_string(), text_array(&lock_fields));
+            params.insert("V".to_string(), name("1.2"));
+            references.push(signature_reference("FieldMDP", params));
+        }
+        if !references.is_empty() {
+            sig.insert(
+                "Reference".to_string(),
+                PdfObject::Array(PdfArray(references)),
+            );
+        }
+        objects.push((sig_id, PdfObject::Dictionary(sig)));
+
+        // Appearance shown in the widget
+        let mut preview = SignatureField::new(options.field_name.clone())
+            .with_appearance(options.appearance.clone());
+        preview.signer = Some(self.credentials.signer_info());
+        if let Some(name) = &options.signer_name {
+            if let Some(signer) = preview.signer.as_mut() {
+                signer.name = name.clone();
+            }
+        }
+        preview.reason = options.reason.clone();
+        preview.location = options.location.clone();
+        preview.signature_value = Some(SignatureValue {
+            timestamp: signing_time,
+            document_hash: Vec::new(),
+            algorithm: options.digest_algorithm.signature_algorithm(matches!(
+                self.credentials.key(),
+                super::credentials::SigningKey::Rsa(_)
+            )),
+            certificates: Vec::new(),
+            signature_bytes: Vec::new(),
+        });
+
+        match existing {
+            Some(field) => {
+                let mut field_out = field.dict.clone();
+                field_out.insert("V".to_string(), reference(sig_id));
+
+                // Refresh the appearance of a merged widget with a real area
+                if let Some(rect) = widget_rect(&field.dict) {
+                    let ap_id = allocate();
+                    objects.push((ap_id, appearance_stream(&preview, rect)?));
+                    field_out.insert("AP".to_string(), appearance_dict(ap_id));
+                }
+                objects.push((field.id, PdfObject::Dictionary(field_out)));
+            }
+            None => {
+                let field_id = allocate();
+                let page_index = options.visible.map(|v| v.page).unwrap_or(0);
+                let page = document.get_page(page_index)?;
+                let page_id = ObjectId::new(page.obj_ref.0, page.obj_ref.1);
+
+                let rect = options
+                    .visible
+                    .map(|v| v.rect)
+                    .unwrap_or_else(|| Rectangle::from_position_and_size(0.0, 0.0, 0.0, 0.0));
+                let ap_rect = [
+                    rect.lower_left.x,
+                    rect.lower_left.y,
+                    rect.upper_right.x,
+                    rect.upper_right.y,
+                ];
+                let mut field = PdfDictionary::new();
+                field.insert("FT".to_string(), name("Sig"));
+                field.insert("T".to_string(), text(&options.field_name));
+                field.insert("V".to_string(), reference(sig_id));
+                field.insert("Type".to_string(), name("Annot"));
+                field.insert("Subtype".to_string(), name("Widget"));
+                field.insert("F".to_string(), PdfObject::Integer(SIGNATURE_WIDGET_FLAGS));
+                field.insert("P".to_string(), reference(page_id));
+                field.insert(
+                    "Rect".to_string(),
+                    PdfObject::Array(PdfArray(
+                        ap_rect.iter().map(|&v| PdfObject::Real(v)).collect(),
+                    )),
+                );
+                let ap_id = allocate();
+                objects.push((ap_id, appearance_stream(&preview, ap_rect)?));
+                field.insert("AP".to_string(), appearance_dict(ap_id));
+                if !options.lock_fields.is_empty() {
+                    let mut lock = PdfDictionary::new();
+                    lock.insert("Type".to_string(), name("SigFieldLock"));
+                    lock.insert("Action".to_string(), name("Include"));
+                    lock.insert("Fields".to_string(), text_array(&options.lock_fields));
+                    field.insert("Lock".to_string(), PdfObject::Dictionary(lock));
+                }
+                objects.push((field_id, PdfObject::Dictionary(field)));
+
+                // Attach the widget to the page's /Annots
+                let annots = page.dict.get("Annots");
+                match annots.and_then(|o| o.as_reference()) {
+                    Some((num, gen)) => {
+                        let mut items = match document.get_object(num, gen)? {
+                            PdfObject::Array(arr) => arr.0,
+                            _ => Vec::new(),
+                        };
+                        items.push(reference(field_id));
+                        objects.push((ObjectId::new(num, gen), PdfObject::Array(PdfArray(items))));
+                    }
+                    None => {
+                        let mut page_out = page.dict.clone();
+                        let mut items = match annots {
+                            Some(PdfObject::Array(arr)) => arr.0.clone(),
+                            _ => Vec::new(),
+                        };
+                        items.push(reference(field_id));
+                        page_out.insert("Annots".to_string(), PdfObject::Array(PdfArray(items)));
+                        objects.push((page_id, PdfObject::Dictionary(page_out)));
+                    }
+                }
+
+                // Register the field in the AcroForm, inline or as its own object
+                let mut field_refs = fields;
+                field_refs.push(reference(field_id));
+                match fields_ref {
+                    Some((num, gen)) => {
+                        objects.push((
+                            ObjectId::new(num, gen),
+                            PdfObject::Array(PdfArray(field_refs)),
+                        ));
+                    }
+                    None => {
+                        acro_form
+                            .insert("Fields".to_string(), PdfObject::Array(PdfArray(field_refs)));
+                    }
+                }
+            }
+        }
+
+        self.finish_update(
+            objects,
+            acro_form,
+            acro_form_ref,
+            catalog_out,
+            root,
+            allocate,
+            sig_id,
+        )
+    }
+
+    /// Write the AcroForm with `/SigFlags` and the catalog when required
+    #[allow(clippy::too_many_arguments)]
+    fn finish_update(
+        &self,
+        mut objects: Vec<(ObjectId, PdfObject)>,
+        mut acro_form: PdfDictionary,
+        acro_form_ref: Option<(u32, u16)>,
+        mut catalog: PdfDictionary,
+        root: (u32, u16),
+        mut allocate: impl FnMut() -> ObjectId,
+        sig_id: ObjectId,
+    ) -> Result<(Vec<(ObjectId, PdfObject)>, ObjectId)> {
+        if !acro_form.contains_key("Fields") {
+            acro_form.insert("Fields".to_string(), PdfObject::Array(PdfArray::new()));
+        }
+        let flags = acro_form
+            .get("SigFlags")
+            .and_then(|o| o.as_integer())
+            .unwrap_or(0);
+        // SignaturesExist (1) + AppendOnly (2)
+        acro_form.insert("SigFlags".to_string(), PdfObject::Integer(flags | 3));
+
+        let acro_form_id = match acro_form_ref {
+            Some((num, gen)) => ObjectId::new(num, gen),
+            None => allocate(),
+        };
+        objects.push((acro_form_id, PdfObject::Dictionary(acro_form)));
+
+        let needs_catalog = acro_form_ref.is_none() || catalog.contains_key("Perms");
+        if needs_catalog {
+            catalog.insert("AcroForm".to_string(), reference(acro_form_id));
+            objects.push((
+                ObjectId::new(root.0, root.1),
+                PdfObject::Dictionary(catalog),
+            ));
+        }
+
+        Ok((objects, sig_id))
+    }
+}
+
+/// Summarize an X.509 certificate for [`SignatureValue::certificates`]
+pub(crate) fn certificate_summary(certificate: &x509_cert::Certificate) -> CertificateSummary {
+    let tbs = &certificate.tbs_certificate;
+    let serial = tbs
+        .serial_number
+        .as_bytes()
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":");
+    CertificateSummary {
+        subject: tbs.subject.to_string(),
+        issuer: tbs.issuer.to_string(),
+        serial_number: serial,
+        not_before: DateTime::<Utc>::from(tbs.validity.not_before.to_system_time()),
+        not_after: DateTime::<Utc>::from(tbs.validity.not_after.to_system_time()),
+        public_key_info: tbs.subject_public_key_info.algorithm.oid.to_string(),
+    }
+}
+
+fn name(value: &str) -> PdfObject {
+    PdfObject::Name(PdfName::new(value.to_string()))
+}
+
+fn text(value: &str) -> PdfObject {
+    PdfObject::String(PdfString::new(text_bytes(value)))
+}
+
+fn text_array(values: &[String]) -> PdfObject {
+    PdfObject::Array(PdfArray(values.iter().map(|v| text(v)).collect()))
+}
+
+fn reference(id: ObjectId) -> PdfObject {
+    PdfObject::Reference(id.number(), id.generation())
+}
+
+/// Build a `/SigRef` dictionary for the given transform method
+fn signature_reference(method: &str, params: PdfDictionary) -> PdfObject {
+    let mut reference = PdfDictionary::new();
+    reference.insert("Type".to_string(), name("SigRef"));
+    reference.insert("TransformMethod".to_string(), name(method));
+    reference.insert("TransformParams".to_string(), PdfObject::Dictionary(params));
+    PdfObject::Dictionary(reference)
+}
+
+fn appearance_dict(stream_id: ObjectId) -> PdfObject {
+    let mut ap = PdfDictionary::new();
+    ap.insert("N".to_string(), reference(stream_id));
+    PdfObject::Dictionary(ap)
+}
+
+/// Form XObject drawing the signature appearance for a widget rectangle
+fn appearance_stream(field: &SignatureField, rect: [f64; 4]) -> Result<PdfObject> {
+    let width = (rect[2] - rect[0]).abs();
+    let height = (rect[3] - rect[1]).abs();
+    let content = if width > 0.0 && height > 0.0 {
+        field.generate_appearance(width, height)?
+    } else {
+        Vec::new()
+    };
+
+    let font_name = field.appearance.font.pdf_name();
+    let mut font = Dictionary::new();
+    font.set("Type", Object::Name("Font".to_string()));
+    font.set("Subtype", Object::Name("Type1".to_string()));
+    font.set("BaseFont", Object::Name(font_name.clone()));
+    font.set("Encoding", Object::Name("WinAnsiEncoding".to_string()));
+    let mut fonts = Dictionary::new();
+    fonts.set(font_name, Object::Dictionary(font));
+    let mut resources = Dictionary::new();
+    resources.set("Font", Object::Dictionary(fonts));
+
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::Name("XObject".to_string()));
+    dict.set("Subtype", Object::Name("Form".to_string()));
+    dict.set(
+        "BBox",
+        Object::Array(vec![
+            Object::Real(0.0),
+            Object::Real(0.0),
+            Object::Real(width),
+            Object::Real(height),
+        ]),
+    );
+    dict.set("Resources", Object::Dictionary(resources));
+    Ok(PdfObject::from(&Object::Stream(dict, content)))
+}
+
+/// Rectangle of a merged widget, if it has a visible area
+fn widget_rect(dict: &PdfDictionary) -> Option<[f64; 4]> {
+    let rect = dict.get("Rect")?.as_array()?;
+    if rect.len() < 4 {
+        return None;
+    }
+    let mut values = [0.0; 4];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = rect.get(i)?.as_real()?;
+    }
+    ((values[2] - values[0]).abs() > 0.0 && (values[3] - values[1]).abs() > 0.0).then_some(values)
+}
+
+/// Field names from an existing field's `/Lock` dictionary
+fn lock_fields_from_dict<R: std::io::Read + std::io::Seek>(
+    document: &PdfDocument<R>,
+    dict: &PdfDictionary,
+) -> Result<Vec<String>> {
+    let Some(lock) = dict.get("Lock") else {
+        return Ok(Vec::new());
+    };
+    let lock = document.resolve(lock)?;
+    let names = lock
+        .as_dict()
+        .and_then(|d| d.get("Fields"))
+        .and_then(|f| f.as_array())
+        .map(|arr| {
+            arr.0
+                .iter()
+                .filter_map(|o| o.as_string())
+                .map(|s| text_string(s.as_bytes()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(names)
+}
+
+/// Find the `/Contents (000…)` placeholder written after `start`
+fn locate_contents_placeholder(
+    output: &[u8],
+    start: usize,
+    reserved: usize,
+) -> Result<(usize, usize)> {
+    let mut needle = b"/Contents (".to_vec();
+    needle.extend(std::iter::repeat(b'0').take(reserved * 2));
+    needle.push(b')');
+    let offset = output[start..]
+        .windows(needle.len())
+        .position(|w| w == needle.as_slice())
+        .ok_or_else(|| {
+            PdfError::Internal("Signature /Contents placeholder not found".to_string())
+        })?;
+    let contents_start = start + offset + b"/Contents ".len();
+    Ok((contents_start, contents_start + reserved * 2 + 2))
+}
+
+/// Replace the `/ByteRange` placeholder in place, keeping the file length
+fn patch_byte_range(output: &mut [u8], start: usize, byte_range: &[usize; 4]) -> Result<()> {
+    let placeholder = format!("[0 {p} {p} {p}]", p = BYTE_RANGE_PLACEHOLDER);
+    let offset = output[start..]
+        .windows(placeholder.len())
+        .position(|w| w == placeholder.as_bytes())
+        .ok_or_else(|| {
+            PdfError::Internal("Signature /ByteRange placeholder not found".to_string())
+        })?;
+    let mut value = format!(
+        "[{} {} {} {}",
+        byte_range[0], byte_range[1], byte_range[2], byte_range[3]
+    );
+    while value.len() < placeholder.len() - 1 {
+        value.push(' ');
+    }
+    value.push(']');
+    let position = start + offset;
+    output[position..position + placeholder.len()].copy_from_slice(value.as_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::cms::CmsSignature;
+    use crate::signatures::test_helpers;
+    use crate::{Document, Page};
+
+    fn unsigned_pdf() -> Vec<u8> {
+        let mut doc = Document::new();
+        doc.set_title("Contract");
+        doc.add_page(Page::a4());
+        doc.add_page(Page::a4());
+        doc.to_bytes().unwrap()
+    }
+
+    fn verify_signed(signed: &SignedDocument) {
+        let [a, b, c, d] = signed.byte_range;
+        assert_eq!(a, 0);
+        assert_eq!(c + d, signed.bytes.len());
+        assert_eq!(signed.bytes[b], b'<');
+        assert_eq!(signed.bytes[c - 1], b'>');
+
+        let hex = std::str::from_utf8(&signed.bytes[b + 1..c - 1]).unwrap();
+        let der: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        let cms = CmsSignature::parse(&der).unwrap();
+        let digest = cms
+            .digest_algorithm
+            .digest_parts(&[&signed.bytes[a..b], &signed.bytes[c..c + d]]);
+        assert_eq!(digest, signed.signature.document_hash);
+        assert!(cms.verify(&digest).unwrap());
+    }
+
+    #[test]
+    fn test_sign_invisible_rsa() {
+        let pdf = unsigned_pdf();
+        let signer = PdfSigner::new(test_helpers::rsa_credentials());
+        let options = SignatureOptions::new("Signature1").with_reason("Approval");
+        let signed = signer.sign(&pdf, &options).unwrap();
+
+        // Original bytes are preserved as the first revision
+        assert!(signed.bytes.starts_with(&pdf));
+        verify_signed(&signed);
+
+        let text = String::from_utf8_lossy(&signed.bytes[pdf.len()..]);
+        assert!(text.contains("/SubFilter /adbe.pkcs7.detached"));
+        assert!(text.contains("/Filter /Adobe.PPKLite"));
+        assert!(text.contains("/SigFlags 3"));
+        assert!(text.contains("/Reason (Approval)"));
+        assert!(text.contains("/Prev "));
+    }
+
+    #[test]
+    fn test_signed_document_reparses() {
+        let pdf = unsigned_pdf();
+        let signer = PdfSigner::new(test_helpers::rsa_credentials());
+        let signed = signer
+            .sign(&pdf, &SignatureOptions::new("Signature1"))
+            .unwrap();
+
+        let reader = PdfReader::new(Cursor::new(signed.bytes.clone())).unwrap();
+        let document = PdfDocument::new(reader);
+        assert_eq!(document.page_count().unwrap(), 2);
+
+        let catalog = document.catalog().unwrap();
+        let acro_form = document.resolve(catalog.get("AcroForm").unwrap()).unwrap();
+        let fields = acro_form.as_dict().unwrap().get("Fields").unwrap();
+        let fields = document.resolve(fields).unwrap();
+        assert_eq!(fields.as_array().unwrap().len(), 1);
+
+        let annotations = document.get_page_annotations(0).unwrap();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(
+            annotations[0]
+                .get("FT")
+                .and_then(|o| o.as_name())
+                .unwrap()
+                .as_str(),
+            "Sig"
+        );
+    }
+
+    #[test]
+    fn test_sign_visible_ecdsa_cades() {
+        let pdf = unsigned_pdf();
+        let signer = PdfSigner::new(test_helpers::ecdsa_credentials());
+        let options = SignatureOptions::new("Approval")
+            .with_sub_filter(SignatureSubFilter::CadesDetached)
+            .with_location("Madrid")
+            .visible(
+                1,
+                Rectangle::from_position_and_size(50.0, 50.0, 200.0, 60.0),
+            );
+        let signed = signer.sign(&pdf, &options).unwrap();
+        verify_signed(&signed);
+
+        let text = String::from_utf8_lossy(&signed.bytes[pdf.len()..]);
+        assert!(text.contains("/SubFilter /ETSI.CAdES.detached"));
+        assert!(text.contains("Digitally signed by: Test ECDSA Signer"));
+    }
+
+    #[test]
+    fn test_second_signature_keeps_first_revision() {
+        let pdf = unsigned_pdf();
+        let first = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&pdf, &SignatureOptions::new("Author"))
+            .unwrap();
+        let second = PdfSigner::new(test_helpers::ecdsa_credentials())
+            .sign(&first.bytes, &SignatureOptions::new("Reviewer"))
+            .unwrap();
+
+        assert!(second.bytes.starts_with(&first.bytes));
+        verify_signed(&second);
+
+        // Signing the same field again is rejected
+        let again = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&second.bytes, &SignatureOptions::new("Author"));
+        assert!(again.is_err());
+    }
+
+    #[test]
+    fn test_sign_pdf_with_xref_streams() {
+        let mut doc = Document::new();
+        doc.enable_xref_streams(true);
+        doc.add_page(Page::a4());
+        let pdf = doc.to_bytes().unwrap();
+        assert!(!String::from_utf8_lossy(&pdf).contains("\ntrailer"));
+
+        let first = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&pdf, &SignatureOptions::new("Author"))
+            .unwrap();
+        verify_signed(&first);
+        // The update continues with a cross-reference stream, not a table
+        let update = String::from_utf8_lossy(&first.bytes[pdf.len()..]);
+        assert!(update.contains("/Type /XRef"));
+        assert!(!update.contains("\ntrailer"));
+
+        let second = PdfSigner::new(test_helpers::ecdsa_credentials())
+            .sign(&first.bytes, &SignatureOptions::new("Reviewer"))
+            .unwrap();
+        verify_signed(&second);
+
+        let reader = PdfReader::new(Cursor::new(second.bytes.clone())).unwrap();
+        let document = PdfDocument::new(reader);
+        assert_eq!(document.page_count().unwrap(), 1);
+        let catalog = document.catalog().unwrap();
+        let acro_form = document.resolve(catalog.get("AcroForm").unwrap()).unwrap();
+        let fields = acro_form.as_dict().unwrap().get("Fields").unwrap();
+        let fields = document.resolve(fields).unwrap();
+        assert_eq!(fields.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_certification_requires_first_signature() {
+        let pdf = unsigned_pdf();
+        let signer = PdfSigner::new(test_helpers::rsa_credentials());
+        let certified = signer
+            .sign(
+                &pdf,
+                &SignatureOptions::new("Author").certify(DocMdpPermission::FormFilling),
+            )
+            .unwrap();
+        let text = String::from_utf8_lossy(&certified.bytes[pdf.len()..]);
+        assert!(text.contains("/TransformMethod /DocMDP"));
+        assert!(text.contains("/Perms"));
+
+        let result = signer.sign(
+            &certified.bytes,
+            &SignatureOptions::new("Second").certify(DocMdpPermission::NoChanges),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_lock_fields_write_field_mdp() {
+        let pdf = unsigned_pdf();
+        let signed = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(
+                &pdf,
+                &SignatureOptions::new("Locker").lock_fields(vec!["Amount".to_string()]),
+            )
+            .unwrap();
+        let text = String::from_utf8_lossy(&signed.bytes[pdf.len()..]);
+        assert!(text.contains("/TransformMethod /FieldMDP"));
+        assert!(text.contains("/SigFieldLock"));
+        verify_signed(&signed);
+    }
+
+    /// Document with an unsigned signature field named "Firma é" in UTF-16BE
+    fn utf16_field_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /AcroForm << /Fields [4 0 R] >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Annots [4 0 R] >>",
+            "<< /Type /Annot /Subtype /Widget /FT /Sig /T <FEFF004600690072006D0061002000E9> \
+             /TU <FEFF00C1007200650061> /Rect [100 100 300 150] /P 3 0 R >>",
+        ];
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{body}\nendobj\n", i + 1).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(b"xref\n0 5\n0000000000 65535 f \n");
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!("trailer\n<< /Size 5 /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n").as_bytes(),
+        );
+        pdf
+    }
+
+    #[test]
+    fn test_sign_field_with_utf16_name() {
+        let pdf = utf16_field_pdf();
+        let signed = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&pdf, &SignatureOptions::new("Firma é"))
+            .unwrap();
+        verify_signed(&signed);
+
+        // The rewritten field keeps its strings byte for byte
+        let update = String::from_utf8_lossy(&signed.bytes[pdf.len()..]);
+        assert!(update.contains("/T <FEFF004600690072006D0061002000E9>"));
+        assert!(update.contains("/TU <FEFF00C1007200650061>"));
+
+        let reader = PdfReader::new(Cursor::new(signed.bytes.clone())).unwrap();
+        let verifications = PdfDocument::new(reader).verify_signatures().unwrap();
+        assert_eq!(verifications.len(), 1);
+        assert_eq!(verifications[0].field_name, "Firma é");
+        assert!(verifications[0].is_valid());
+    }
+
+    #[test]
+    fn test_new_field_name_is_text_string() {
+        let pdf = unsigned_pdf();
+        let signed = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(
+                &pdf,
+                &SignatureOptions::new("Firma é").with_reason("Aprobación"),
+            )
+            .unwrap();
+        let update = String::from_utf8_lossy(&signed.bytes[pdf.len()..]);
+        assert!(update.contains("/T <FEFF004600690072006D0061002000E9>"));
+
+        let reader = PdfReader::new(Cursor::new(signed.bytes.clone())).unwrap();
+        let verifications = PdfDocument::new(reader).verify_signatures().unwrap();
+        assert_eq!(verifications[0].field_name, "Firma é");
+        assert!(verifications[0].is_valid());
+    }
+
+    #[test]
+    fn test_reserved_size_too_small() {
+        let pdf = unsigned_pdf();
+        let result = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&pdf, &SignatureOptions::new("Tiny").with_reserved_size(64));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_sha512_digest() {
+        let pdf = unsigned_pdf();
+        let signed = PdfSigner::new(test_helpers::chain_credentials())
+            .sign(
+                &pdf,
+                &SignatureOptions::new("Signature1").with_digest_algorithm(DigestAlgorithm::Sha512),
+            )
+            .unwrap();
+        assert_eq!(signed.signature.document_hash.len(), 64);
+        assert_eq!(signed.signature.certificates.len(), 2);
+        verify_signed(&signed);
+    }
+
+    #[test]
+    fn test_sub_filter_names() {
+        for sub_filter in [
+            SignatureSubFilter::Pkcs7Detached,
+            SignatureSubFilter::CadesDetached,
+        ] {
+            assert_eq!(
+                SignatureSubFilter::from_pdf_name(sub_filter.pdf_name()),
+                Some(sub_filter)
+            );
+        }
+        assert_eq!(
+            SignatureSubFilter::from_pdf_name("adbe.x509.rsa_sha1"),
+            None
+        );
+    }
+}
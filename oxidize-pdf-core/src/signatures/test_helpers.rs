@@ -0,0 +1,31 @@
+//! Test credentials shared by the signature tests
+
+use super::SigningCredentials;
+
+pub const RSA_KEY_PEM: &str = include_str!("../../tests/fixtures/signatures/rsa_signer.key.pem");
+pub const RSA_CERT_PEM: &str = include_str!("../../tests/fixtures/signatures/rsa_signer.cert.pem");
+pub const ECDSA_KEY_PEM: &str =
+    include_str!("../../tests/fixtures/signatures/ecdsa_signer.key.pem");
+pub const ECDSA_CERT_PEM: &str =
+    include_str!("../../tests/fixtures/signatures/ecdsa_signer.cert.pem");
+pub const CHAIN_KEY_PEM: &str =
+    include_str!("../../tests/fixtures/signatures/chain_signer.key.pem");
+pub const CHAIN_CERT_PEM: &str =
+    include_str!("../../tests/fixtures/signatures/chain_signer.cert.pem");
+pub const CA_CERT_PEM: &str = include_str!("../../tests/fixtures/signatures/test_ca.cert.pem");
+
+/// RSA 2048 signer with a self-signed certificate
+pub fn rsa_credentials() -> SigningCredentials {
+    SigningCredentials::from_pem(RSA_KEY_PEM, RSA_CERT_PEM).unwrap()
+}
+
+/// ECDSA P-256 signer with a self-signed certificate
+pub fn ecdsa_credentials() -> SigningCredentials {
+    SigningCredentials::from_pem(ECDSA_KEY_PEM, ECDSA_CERT_PEM).unwrap()
+}
+
+/// Signer issued by the test CA, chain includes the CA certificate
+pub fn chain_credentials() -> SigningCredentials {
+    let chain = format!("{CHAIN_CERT_PEM}{CA_CERT_PEM}");
+    SigningCredentials::from_pem(CHAIN_KEY_PEM, &chain).unwrap()
+}
@@ -0,0 +1,1066 @@
+//! Verification of signatures in existing documents (ISO 32000-1 §12.8)
+//!
+//! Every signed signature field is checked independently:
+//!
+//! 1. The `/ByteRange` is validated and the covered bytes are hashed.
+//! 2. The embedded CMS `SignedData` is parsed; its message digest must match
+//!    and its signature must verify with the signer certificate.
+//! 3. The byte range is located among the incremental revisions of the file
+//!    to find out whether the document changed after signing.
+//! 4. When it did, the objects changed by later revisions are compared with
+//!    the DocMDP permissions of a certification signature and with the fields
+//!    locked through FieldMDP.
+
+use super::cms::CmsSignature;
+use super::credentials::signer_info_from_certificate;
+use super::fields::{collect_fields, ParsedField};
+use super::pades::{certificate_summary, DocMdpPermission, SignatureSubFilter};
+use crate::error::Result;
+use crate::forms::signature_field::{Certificate, SignatureAlgorithm, SignerInfo};
+use crate::parser::strings::{parse_pdf_date, text_string};
+use crate::parser::{PdfDictionary, PdfDocument, PdfObject, PdfReader};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Seek};
+
+/// Outcome of verifying one signature field
+#[derive(Debug, Clone)]
+pub struct SignatureVerification {
+    /// Fully qualified name of the signature field
+    pub field_name: String,
+    /// Signer, from the signer certificate or the `/Name` entry
+    pub signer: Option<SignerInfo>,
+    /// Signing time from the signed attributes or the `/M` entry
+    pub signing_time: Option<DateTime<Utc>>,
+    /// `/SubFilter` of the signature dictionary
+    pub sub_filter: Option<String>,
+    /// Signature algorithm used by the signer
+    pub algorithm: Option<SignatureAlgorithm>,
+    /// The `/ByteRange` of the signature dictionary
+    pub byte_range: Vec<i64>,
+    /// Certificates embedded in the signature, signer first
+    pub certificates: Vec<Certificate>,
+    /// The message digest matches the signed byte ranges
+    pub digest_valid: bool,
+    /// The signature verifies with the signer certificate
+    pub signature_valid: bool,
+    /// One-based revision of the file covered by the signature
+    pub revision: Option<usize>,
+    /// Number of incremental revisions appended after signing
+    pub later_revisions: usize,
+    /// Bytes were appended after the signed revision
+    pub modified_after_signing: bool,
+    /// DocMDP permissions when this is a certification signature
+    pub certification: Option<DocMdpPermission>,
+    /// Changes made after signing that the DocMDP or FieldMDP transforms forbid
+    pub mdp_violations: Vec<String>,
+    /// Problems that prevent the signature from being trusted
+    pub errors: Vec<String>,
+    /// Non-fatal findings
+    pub warnings: Vec<String>,
+}
+
+impl SignatureVerification {
+    fn new(field_name: String) -> Self {
+        Self {
+            field_name,
+            signer: None,
+            signing_time: None,
+            sub_filter: None,
+            algorithm: None,
+            byte_range: Vec::new(),
+            certificates: Vec::new(),
+            digest_valid: false,
+            signature_valid: false,
+            revision: None,
+            later_revisions: 0,
+            modified_after_signing: false,
+            certification: None,
+            mdp_violations: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Signature is cryptographically intact and no forbidden change followed it
+    pub fn is_valid(&self) -> bool {
+        self.digest_valid
+            && self.signature_valid
+            && self.errors.is_empty()
+            && self.mdp_violations.is_empty()
+    }
+
+    /// Signature covers the entire file
+    pub fn covers_whole_document(&self) -> bool {
+        !self.modified_after_signing
+    }
+}
+
+/// Verify every signed signature field of `document`
+pub(crate) fn verify_document<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> Result<Vec<SignatureVerification>> {
+    let bytes = document.raw_bytes()?;
+    let revisions = revision_ends(&bytes);
+    let fields = collect_fields(document)?;
+    let mut later_states: HashMap<usize, Option<RevisionState>> = HashMap::new();
+
+    let mut results = Vec::new();
+    for field in fields.iter().filter(|f| f.is_signed()) {
+        let mut result = SignatureVerification::new(field.name.clone());
+        let Some(value) = field.dict.get("V") else {
+            continue;
+        };
+        let signature = match document.resolve(value)?.as_dict() {
+            Some(dict) => dict.clone(),
+            None => {
+                result
+                    .errors
+                    .push("Signature value is not a dictionary".to_string());
+                results.push(result);
+                continue;
+            }
+        };
+
+        let covered_end = check_signature(&bytes, &signature, &mut result);
+        if let Some(covered_end) = covered_end {
+            locate_revision(&bytes, &revisions, covered_end, &mut result);
+
+            let field_mdp = field_mdp_lock(document, &signature)?;
+            if result.modified_after_signing
+                && (result.certification.is_some() || field_mdp.is_some())
+            {
+                let state = later_states
+                    .entry(covered_end)
+                    .or_insert_with(|| RevisionState::load(&bytes[..covered_end]).ok());
+                match state {
+                    Some(state) => {
+                        if let Some(permission) = result.certification {
+                            check_doc_mdp(document, state, permission, &mut result)?;
+                        }
+                        if let Some(lock) = &field_mdp {
+                            check_field_mdp(document, state, &fields, field, lock, &mut result)?;
+                        }
+                    }
+                    None => result
+                        .errors
+                        .push("Signed revision could not be parsed".to_string()),
+                }
+            }
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Check the byte range and CMS signature, returning the end of the covered bytes
+fn check_signature(
+    bytes: &[u8],
+    signature: &PdfDictionary,
+    result: &mut SignatureVerification,
+) -> Option<usize> {
+    result.sub_filter = signature
+        .get("SubFilter")
+        .and_then(|o| o.as_name())
+        .map(|n| n.as_str().to_string());
+    if let Some(sub_filter) = &result.sub_filter {
+        if SignatureSubFilter::from_pdf_name(sub_filter).is_none() {
+            result.warnings.push(format!(
+                "Sub-filter {sub_filter} is not a detached CMS signature"
+            ));
+        }
+    }
+    result.certification = doc_mdp_permission(signature);
+    result.signing_time = signature
+        .get("M")
+        .and_then(|o| o.as_string())
+        .and_then(|s| parse_pdf_date(&text_string(s.as_bytes())));
+    result.signer = signature
+        .get("Name")
+        .and_then(|o| o.as_string())
+        .map(|s| SignerInfo::new(text_string(s.as_bytes())));
+
+    result.byte_range = signature
+        .get("ByteRange")
+        .and_then(|o| o.as_array())
+        .map(|arr| arr.0.iter().filter_map(|o| o.as_integer()).collect())
+        .unwrap_or_default();
+    let Some([start, gap_start, gap_end, covered_end]) = byte_range_bounds(&result.byte_range)
+    else {
+        result.errors.push(format!(
+            "Invalid /ByteRange {:?} for a file of {} bytes",
+            result.byte_range,
+            bytes.len()
+        ));
+        return None;
+    };
+    if start != 0 {
+        result
+            .errors
+            .push("/ByteRange does not start at the beginning of the file".to_string());
+    }
+    // The gap must hold exactly the hex-encoded /Contents string
+    if bytes.get(gap_start) != Some(&b'<') || bytes.get(gap_end - 1) != Some(&b'>') {
+        result
+            .errors
+            .push("/ByteRange gap does not match the /Contents value".to_string());
+    }
+
+    let Some(contents) = signature.get("Contents").and_then(|o| o.as_string()) else {
+        result
+            .errors
+            .push("Signature dictionary has no /Contents".to_string());
+        return Some(covered_end);
+    };
+    let cms = match CmsSignature::parse(contents.as_bytes()) {
+        Ok(cms) => cms,
+        Err(e) => {
+            result.errors.push(format!("Invalid CMS signature: {e}"));
+            return Some(covered_end);
+        }
+    };
+
+    result.algorithm = Some(cms.signature_algorithm());
+    result.certificates = cms.certificates.iter().map(certificate_summary).collect();
+    if let Some(certificate) = cms.signer_certificate() {
+        result.signer = Some(signer_info_from_certificate(certificate));
+        // Put the signer certificate first
+        let index = cms
+            .certificates
+            .iter()
+            .position(|c| c == certificate)
+            .unwrap_or(0);
+        result.certificates.swap(0, index);
+    }
+    if let Some(time) = cms.signing_time() {
+        result.signing_time = Some(time);
+    }
+
+    let digest = cms
+        .digest_algorithm
+        .digest_parts(&[&bytes[start..gap_start], &bytes[gap_end..covered_end]]);
+    match cms.message_digest() {
+        Some(message_digest) => {
+            result.digest_valid = message_digest == digest;
+            if !result.digest_valid {
+                result
+                    .errors
+                    .push("Document digest does not match the signed message digest".to_string());
+            }
+        }
+        // Signed attributes must carry the digest (RFC 5652, section 5.3)
+        None if cms.has_signed_attributes() => result
+            .errors
+            .push("Signed attributes have no message digest".to_string()),
+        // Without signed attributes the signature itself binds the digest
+        None => result.digest_valid = true,
+    }
+    match cms.verify_signature(&digest) {
+        Ok(true) => result.signature_valid = true,
+        Ok(false) => result
+            .errors
+            .push("Signature does not verify with the signer certificate".to_string()),
+        Err(e) => result
+            .errors
+            .push(format!("Signature cannot be verified: {e}")),
+    }
+
+    if let (Some(time), Some(certificate)) = (result.signing_time, result.certificates.first()) {
+        if time < certificate.not_before || time > certificate.not_after {
+            result.warnings.push(format!(
+                "Signer certificate was not valid at signing time {}",
+                time.to_rfc3339()
+            ));
+        }
+    }
+
+    Some(covered_end)
+}
+
+/// Validated `[start, gap_start, gap_end, covered_end]` of a byte range
+fn byte_range_bounds(byte_range: &[i64]) -> Option<[usize; 4]> {
+    let [a, b, c, d] = byte_range else {
+        return None;
+    };
+    let values = [*a, *b, *c, *d].map(|v| usize::try_from(v).ok());
+    let [Some(a), Some(b), Some(c), Some(d)] = values else {
+        return None;
+    };
+    let gap_start = a.checked_add(b)?;
+    let covered_end = c.checked_add(d)?;
+    (gap_start < c).then_some([a, gap_start, c, covered_end])
+}
+
+/// Match the covered bytes with a revision and count the revisions after it
+fn locate_revision(
+    bytes: &[u8],
+    revisions: &[usize],
+    covered_end: usize,
+    result: &mut SignatureVerification,
+) {
+    if covered_end > bytes.len() {
+        result
+            .errors
+            .push("/ByteRange extends past the end of the file".to_string());
+        return;
+    }
+    result.modified_after_signing = !is_blank(&bytes[covered_end..]);
+
+    // The range may stop before or after the end-of-line marker following %%EOF
+    let index = revisions.iter().position(|&end| {
+        end >= covered_end && end - covered_end <= 2 && is_blank(&bytes[covered_end..end])
+    });
+    match index {
+        Some(index) => {
+            result.revision = Some(index + 1);
+            result.later_revisions = revisions.len() - index - 1;
+        }
+        None => result
+            .errors
+            .push("/ByteRange does not end at a revision boundary".to_string()),
+    }
+}
+
+/// Offsets just past each `%%EOF` marker and its end-of-line
+fn revision_ends(bytes: &[u8]) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = bytes[position..]
+        .windows(5)
+        .position(|window| window == b"%%EOF")
+    {
+        let mut end = position + offset + 5;
+        if bytes.get(end) == Some(&b'\r') {
+            end += 1;
+        }
+        if bytes.get(end) == Some(&b'\n') {
+            end += 1;
+        }
+        ends.push(end);
+        position = end;
+    }
+    ends
+}
+
+fn is_blank(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .all(|b| matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\0'))
+}
+
+/// Transform parameters of the given method in the signature `/Reference`
+fn transform_params(signature: &PdfDictionary, method: &str) -> Option<PdfDictionary> {
+    signature
+        .get("Reference")?
+        .as_array()?
+        .0
+        .iter()
+        .filter_map(|reference| reference.as_dict())
+        .find(|reference| {
+            reference
+                .get("TransformMethod")
+                .and_then(|o| o.as_name())
+                .map(|n| n.as_str())
+                == Some(method)
+        })
+        .map(|reference| {
+            reference
+                .get("TransformParams")
+                .and_then(|o| o.as_dict())
+                .cloned()
+                .unwrap_or_default()
+        })
+}
+
+/// DocMDP permissions of a certification signature (`/P` defaults to 2)
+fn doc_mdp_permission(signature: &PdfDictionary) -> Option<DocMdpPermission> {
+    let params = transform_params(signature, "DocMDP")?;
+    let value = params.get("P").and_then(|o| o.as_integer()).unwrap_or(2);
+    Some(DocMdpPermission::from_value(value).unwrap_or(DocMdpPermission::FormFilling))
+}
+
+/// Fields locked by a FieldMDP transform
+enum FieldLock {
+    All,
+    Include(HashSet<String>),
+    Exclude(HashSet<String>),
+}
+
+impl FieldLock {
+    fn locks(&self, field: &ParsedField) -> bool {
+        match self {
+            // Signing the remaining signature fields stays allowed
+            FieldLock::All => !field.is_signature(),
+            FieldLock::Include(names) => names.contains(&field.name),
+            FieldLock::Exclude(names) => !field.is_signature() && !names.contains(&field.name),
+        }
+    }
+}
+
+fn field_mdp_lock<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    signature: &PdfDictionary,
+) -> Result<Option<FieldLock>> {
+    let Some(params) = transform_params(signature, "FieldMDP") else {
+        return Ok(None);
+    };
+    let names: HashSet<String> = match params.get("Fields") {
+        Some(fields) => match document.resolve(fields)? {
+            PdfObject::Array(arr) => arr
+                .0
+                .iter()
+                .filter_map(|o| o.as_string())
+                .map(|s| text_string(s.as_bytes()))
+                .collect(),
+            _ => HashSet::new(),
+        },
+        None => HashSet::new(),
+    };
+    let action = params.get("Action").and_then(|o| o.as_name());
+    Ok(Some(match action.map(|n| n.as_str()) {
+        Some("Include") => FieldLock::Include(names),
+        Some("Exclude") => FieldLock::Exclude(names),
+        _ => FieldLock::All,
+    }))
+}
+
+/// The document as it was when a signature was applied
+struct RevisionState {
+    document: PdfDocument<Cursor<Vec<u8>>>,
+}
+
+impl RevisionState {
+    fn load(bytes: &[u8]) -> Result<Self> {
+        let reader = PdfReader::new(Cursor::new(bytes.to_vec()))?;
+        Ok(Self {
+            document: PdfDocument::new(reader),
+        })
+    }
+}
+
+/// What a change made after signing amounts to
+enum Change {
+    /// New signatures, signature fields or document timestamps
+    Signature,
+    /// Form field values, widgets, AcroForm and appearance resources
+    FormField,
+    /// Other annotations
+    Annotation,
+    /// Document information and XMP metadata
+    Metadata,
+    /// Validation data (DSS) and document timestamps, always allowed
+    ValidationData,
+    /// Cross-reference and object streams carrying no content
+    Structure,
+    /// Anything else, never allowed after certification
+    Forbidden(String),
+}
+
+impl Change {
+    /// Lowest DocMDP level that permits the change
+    fn required_permission(&self) -> Option<i64> {
+        match self {
+            Change::ValidationData | Change::Structure => Some(1),
+            Change::Signature | Change::FormField | Change::Metadata => Some(2),
+            Change::Annotation => Some(3),
+            Change::Forbidden(_) => None,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Change::Signature => "signature added".to_string(),
+            Change::FormField => "form field modified".to_string(),
+            Change::Annotation => "annotation modified".to_string(),
+            Change::Metadata => "metadata modified".to_string(),
+            Change::ValidationData => "validation data added".to_string(),
+            Change::Structure => "cross-reference data".to_string(),
+            Change::Forbidden(description) => description.clone(),
+        }
+    }
+
+    fn strictest(changes: impl IntoIterator<Item = Change>) -> Option<Change> {
+        changes
+            .into_iter()
+            .max_by_key(|change| match change.required_permission() {
+                Some(level) => level,
+                None => i64::MAX,
+            })
+    }
+}
+
+/// Compare every object changed after the certification signature with its permissions
+fn check_doc_mdp<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    signed: &RevisionState,
+    permission: DocMdpPermission,
+    result: &mut SignatureVerification,
+) -> Result<()> {
+    let before = signed.document.xref_entries();
+    let after = document.xref_entries();
+    let info = document.trailer().info().map(|(num, _)| num);
+    let validation_data = validation_data_objects(document)?;
+
+    let mut changed: Vec<_> = after
+        .iter()
+        .filter(|(num, entry)| before.get(num) != Some(entry))
+        .map(|(&num, entry)| (num, entry.basic.generation))
+        .collect();
+    changed.sort_unstable();
+
+    for (num, gen) in changed {
+        let Ok(new) = document.get_object(num, gen) else {
+            continue;
+        };
+        let old = signed.document.get_object(num, gen).ok();
+        if old.as_ref() == Some(&new) {
+            continue;
+        }
+        let change = if validation_data.contains(&num) {
+            Change::ValidationData
+        } else if Some(num) == info {
+            Change::Metadata
+        } else {
+            classify_change(document, &signed.document, &new, old.as_ref())?
+        };
+        let allowed = change
+            .required_permission()
+            .is_some_and(|level| level <= permission as i64);
+        if !allowed {
+            result.mdp_violations.push(format!(
+                "Object {num} {gen}: {} is not permitted by DocMDP level {}",
+                change.description(),
+                permission as i64
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Objects reachable from the catalog's `/DSS` dictionary
+fn validation_data_objects<R: Read + Seek>(document: &PdfDocument<R>) -> Result<HashSet<u32>> {
+    let mut objects = HashSet::new();
+    if let Some(dss) = document.catalog()?.get("DSS") {
+        collect_references(document, dss, &mut objects, 0)?;
+    }
+    Ok(objects)
+}
+
+fn collect_references<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    object: &PdfObject,
+    objects: &mut HashSet<u32>,
+    depth: usize,
+) -> Result<()> {
+    if depth > 4 {
+        return Ok(());
+    }
+    match object {
+        PdfObject::Reference(num, gen) => {
+            if objects.insert(*num) {
+                let target = document.get_object(*num, *gen)?;
+                collect_references(document, &target, objects, depth + 1)?;
+            }
+        }
+        PdfObject::Array(arr) => {
+            for item in &arr.0 {
+                collect_references(document, item, objects, depth + 1)?;
+            }
+        }
+        PdfObject::Dictionary(dict) => {
+            for value in dict.0.values() {
+                collect_references(document, value, objects, depth + 1)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn name_of<'a>(dict: &'a PdfDictionary, key: &str) -> Option<&'a str> {
+    dict.get(key).and_then(|o| o.as_name()).map(|n| n.as_str())
+}
+
+/// Decide what kind of change a modified or added object represents
+fn classify_change<R: Read + Seek, S: Read + Seek>(
+    document: &PdfDocument<R>,
+    signed: &PdfDocument<S>,
+    new: &PdfObject,
+    old: Option<&PdfObject>,
+) -> Result<Change> {
+    let dict = match new {
+        PdfObject::Dictionary(dict) => dict,
+        PdfObject::Stream(stream) => &stream.dict,
+        PdfObject::Array(arr) => {
+            let old_items = match old {
+                Some(PdfObject::Array(old)) => old.0.clone(),
+                _ => Vec::new(),
+            };
+            return classify_array_change(document, signed, &arr.0, &old_items);
+        }
+        _ => return Ok(Change::Forbidden("object value modified".to_string())),
+    };
+
+    let old_dict = match old {
+        Some(PdfObject::Dictionary(dict)) => Some(dict),
+        Some(PdfObject::Stream(stream)) => Some(&stream.dict),
+        _ => None,
+    };
+    let differs_only_in = |keys: &[&str]| {
+        let Some(old_dict) = old_dict else {
+            return false;
+        };
+        let changed_keys: HashSet<&str> = dict
+            .0
+            .iter()
+            .filter(|(key, value)| old_dict.0.get(*key) != Some(*value))
+            .map(|(key, _)| key.as_str())
+            .chain(
+                old_dict
+                    .0
+                    .keys()
+                    .filter(|key| !dict.0.contains_key(*key))
+                    .map(|key| key.as_str()),
+            )
+            .collect();
+        changed_keys.iter().all(|key| keys.contains(key))
+    };
+
+    Ok(match dict.get_type() {
+        Some("Sig") => Change::Signature,
+        Some("DocTimeStamp") => Change::ValidationData,
+        Some("XRef") | Some("ObjStm") => Change::Structure,
+        Some("Metadata") => Change::Metadata,
+        Some("Font") | Some("FontDescriptor") | Some("Encoding") => Change::FormField,
+        Some("Catalog") => {
+            if differs_only_in(&["AcroForm"]) {
+                Change::FormField
+            } else if differs_only_in(&["AcroForm", "DSS"]) {
+                Change::ValidationData
+            } else {
+                Change::Forbidden("document catalog modified".to_string())
+            }
+        }
+        Some("Page") => {
+            if old_dict.is_some() && differs_only_in(&["Annots"]) {
+                let new_annots = match dict.get("Annots") {
+                    Some(annots) => array_items(document, annots)?,
+                    None => Vec::new(),
+                };
+                let old_annots = match old_dict.and_then(|d| d.get("Annots")) {
+                    Some(annots) => array_items(signed, annots)?,
+                    None => Vec::new(),
+                };
+                classify_array_change(document, signed, &new_annots, &old_annots)?
+            } else {
+                Change::Forbidden("page modified".to_string())
+            }
+        }
+        Some("Pages") => Change::Forbidden("page tree modified".to_string()),
+        _ => classify_dictionary(dict, matches!(new, PdfObject::Stream(_))),
+    })
+}
+
+/// Classify fields, annotations and appearance streams
+fn classify_dictionary(dict: &PdfDictionary, is_stream: bool) -> Change {
+    if let Some(field_type) = name_of(dict, "FT") {
+        return if field_type == "Sig" {
+            Change::Signature
+        } else {
+            Change::FormField
+        };
+    }
+    match name_of(dict, "Subtype") {
+        Some("Widget") => return Change::FormField,
+        // Appearance streams of fields and annotations
+        Some("Form") if is_stream => return Change::FormField,
+        Some(_) if dict.get_type() == Some("Annot") || dict.contains_key("Rect") => {
+            return Change::Annotation
+        }
+        _ => {}
+    }
+    if dict.contains_key("Fields") {
+        // The AcroForm dictionary
+        return Change::FormField;
+    }
+    if is_stream {
+        Change::Forbidden("content stream modified".to_string())
+    } else {
+        Change::Forbidden("object modified".to_string())
+    }
+}
+
+/// Arrays are `/Annots` or `/Fields` lists; judge them by what was added or removed
+fn classify_array_change<R: Read + Seek, S: Read + Seek>(
+    document: &PdfDocument<R>,
+    signed: &PdfDocument<S>,
+    new_items: &[PdfObject],
+    old_items: &[PdfObject],
+) -> Result<Change> {
+    let mut changes = Vec::new();
+    for item in new_items.iter().filter(|item| !old_items.contains(item)) {
+        let change = match item {
+            PdfObject::Reference(num, gen) => match document.get_object(*num, *gen)? {
+                PdfObject::Dictionary(dict) => classify_dictionary(&dict, false),
+                _ => Change::Forbidden("array entry modified".to_string()),
+            },
+            _ => Change::Forbidden("array entry modified".to_string()),
+        };
+        changes.push(change);
+    }
+    for item in old_items.iter().filter(|item| !new_items.contains(item)) {
+        let change = match item {
+            PdfObject::Reference(num, gen) => match signed.get_object(*num, *gen)? {
+                PdfObject::Dictionary(dict) => match classify_dictionary(&dict, false) {
+                    // Removing fields or signatures is never a form filling operation
+                    Change::Annotation => Change::Annotation,
+                    _ => Change::Forbidden("form field removed".to_string()),
+                },
+                _ => Change::Forbidden("array entry removed".to_string()),
+            },
+            _ => Change::Forbidden("array entry removed".to_string()),
+        };
+        changes.push(change);
+    }
+    Ok(Change::strictest(changes).unwrap_or(Change::Structure))
+}
+
+fn array_items<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    object: &PdfObject,
+) -> Result<Vec<PdfObject>> {
+    Ok(match document.resolve(object)? {
+        PdfObject::Array(arr) => arr.0,
+        _ => Vec::new(),
+    })
+}
+
+/// Report locked fields whose value changed after signing
+fn check_field_mdp<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    signed: &RevisionState,
+    current_fields: &[ParsedField],
+    signature_field: &ParsedField,
+    lock: &FieldLock,
+    result: &mut SignatureVerification,
+) -> Result<()> {
+    let signed_fields = collect_fields(&signed.document)?;
+    let mut signed_values = HashMap::new();
+    for field in &signed_fields {
+        signed_values.insert(field.name.clone(), field_value(&signed.document, field)?);
+    }
+
+    for field in current_fields {
+        if field.name == signature_field.name || !lock.locks(field) {
+            continue;
+        }
+        let value = field_value(document, field)?;
+        let previous = signed_values.remove(&field.name).flatten();
+        if value != previous {
+            result.mdp_violations.push(format!(
+                "Field '{}' was modified after being locked by this signature",
+                field.name
+            ));
+        }
+    }
+    for (name, _) in signed_values {
+        let removed = signed_fields
+            .iter()
+            .find(|field| field.name == name)
+            .is_some_and(|field| lock.locks(field));
+        if removed {
+            result.mdp_violations.push(format!(
+                "Field '{name}' was removed after being locked by this signature"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Resolved `/V` of a field
+fn field_value<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    field: &ParsedField,
+) -> Result<Option<PdfObject>> {
+    match field.dict.get("V") {
+        Some(value) => Ok(Some(document.resolve(value)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::test_helpers;
+    use crate::signatures::{PdfSigner, SignatureOptions};
+    use crate::{Document, Page};
+
+    fn unsigned_pdf() -> Vec<u8> {
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        doc.to_bytes().unwrap()
+    }
+
+    fn verify(bytes: &[u8]) -> Vec<SignatureVerification> {
+        let reader = PdfReader::new(Cursor::new(bytes.to_vec())).unwrap();
+        PdfDocument::new(reader).verify_signatures().unwrap()
+    }
+
+    /// Append an incremental update replacing the given objects
+    fn append_update(bytes: &[u8], objects: Vec<(u32, crate::objects::Object)>) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut writer = crate::writer::PdfWriter::with_config(
+            &mut output,
+            crate::writer::WriterConfig::incremental(),
+        );
+        writer
+            .write_incremental_objects(
+                bytes,
+                objects
+                    .into_iter()
+                    .map(|(num, obj)| (crate::objects::ObjectId::new(num, 0), obj))
+                    .collect(),
+            )
+            .unwrap();
+        drop(writer);
+        output
+    }
+
+    #[test]
+    fn test_unsigned_document_has_no_signatures() {
+        assert!(verify(&unsigned_pdf()).is_empty());
+    }
+
+    #[test]
+    fn test_verify_valid_signature() {
+        let signed = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(
+                &unsigned_pdf(),
+                &SignatureOptions::new("Signature1").with_reason("Approval"),
+            )
+            .unwrap();
+
+        let results = verify(&signed.bytes);
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert!(result.is_valid(), "{:?}", result.errors);
+        assert_eq!(result.field_name, "Signature1");
+        assert_eq!(result.signer.as_ref().unwrap().name, "Test RSA Signer");
+        assert_eq!(result.sub_filter.as_deref(), Some("adbe.pkcs7.detached"));
+        assert_eq!(result.algorithm, Some(SignatureAlgorithm::RsaSha256));
+        assert_eq!(result.revision, Some(2));
+        assert_eq!(result.later_revisions, 0);
+        assert!(result.covers_whole_document());
+        assert!(result.signing_time.is_some());
+        assert_eq!(result.certificates.len(), 1);
+    }
+
+    #[test]
+    fn test_tampered_bytes_are_detected() {
+        let signed = PdfSigner::new(test_helpers::ecdsa_credentials())
+            .sign(&unsigned_pdf(), &SignatureOptions::new("Signature1"))
+            .unwrap();
+
+        // Change a byte inside the first signed range without breaking parsing
+        let mut tampered = signed.bytes.clone();
+        let catalog = b"/Type /Catalog";
+        let offset = tampered
+            .windows(catalog.len())
+            .position(|w| w == catalog)
+            .unwrap();
+        tampered[offset + 7] = b'c';
+
+        let results = verify(&tampered);
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].digest_valid);
+        assert!(!results[0].is_valid());
+    }
+
+    #[test]
+    fn test_missing_message_digest_is_invalid() {
+        let credentials = test_helpers::rsa_credentials();
+        let signed = PdfSigner::new(credentials.clone())
+            .sign(&unsigned_pdf(), &SignatureOptions::new("Signature1"))
+            .unwrap();
+        let [_, gap_start, gap_end, _] = signed.byte_range;
+
+        // Replace /Contents with the same CMS signed without messageDigest
+        let hex = String::from_utf8(signed.bytes[gap_start + 1..gap_end - 1].to_vec()).unwrap();
+        let der: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        let stripped = crate::signatures::cms::without_message_digest(&der, &credentials);
+        let mut contents: String = stripped.iter().map(|b| format!("{b:02X}")).collect();
+        contents.extend(std::iter::repeat('0').take(hex.len() - contents.len()));
+        let mut bytes = signed.bytes.clone();
+        bytes[gap_start + 1..gap_end - 1].copy_from_slice(contents.as_bytes());
+
+        let result = &verify(&bytes)[0];
+        assert!(result.signature_valid);
+        assert!(!result.digest_valid);
+        assert!(!result.is_valid());
+        assert!(result
+            .errors
+            .contains(&"Signed attributes have no message digest".to_string()));
+    }
+
+    #[test]
+    fn test_incremental_change_after_approval_signature() {
+        let signed = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&unsigned_pdf(), &SignatureOptions::new("Signature1"))
+            .unwrap();
+        let info = {
+            let reader = PdfReader::new(Cursor::new(signed.bytes.clone())).unwrap();
+            reader.trailer().info().unwrap().0
+        };
+        let mut dict = crate::objects::Dictionary::new();
+        dict.set(
+            "Title",
+            crate::objects::Object::String("Edited".to_string()),
+        );
+        let updated = append_update(
+            &signed.bytes,
+            vec![(info, crate::objects::Object::Dictionary(dict))],
+        );
+
+        let result = &verify(&updated)[0];
+        // Approval signatures stay valid; the change is only reported
+        assert!(result.is_valid());
+        assert!(result.modified_after_signing);
+        assert_eq!(result.later_revisions, 1);
+    }
+
+    #[test]
+    fn test_doc_mdp_violations() {
+        let certified = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(
+                &unsigned_pdf(),
+                &SignatureOptions::new("Author").certify(DocMdpPermission::FormFilling),
+            )
+            .unwrap();
+
+        // A second signature is permitted at level 2
+        let approved = PdfSigner::new(test_helpers::ecdsa_credentials())
+            .sign(&certified.bytes, &SignatureOptions::new("Reviewer"))
+            .unwrap();
+        let results = verify(&approved.bytes);
+        assert_eq!(results.len(), 2);
+        let author = results.iter().find(|r| r.field_name == "Author").unwrap();
+        assert_eq!(author.certification, Some(DocMdpPermission::FormFilling));
+        assert!(author.modified_after_signing);
+        assert!(author.is_valid(), "{:?}", author.mdp_violations);
+        assert!(results.iter().all(|r| r.signature_valid));
+
+        // Replacing page content is not
+        let (page_num, page_gen) = {
+            let document =
+                PdfDocument::new(PdfReader::new(Cursor::new(certified.bytes.clone())).unwrap());
+            document.get_page(0).unwrap().obj_ref
+        };
+        assert_eq!(page_gen, 0);
+        let mut page = crate::objects::Dictionary::new();
+        page.set("Type", crate::objects::Object::Name("Page".to_string()));
+        page.set("Rotate", crate::objects::Object::Integer(90));
+        let tampered = append_update(
+            &certified.bytes,
+            vec![(page_num, crate::objects::Object::Dictionary(page))],
+        );
+        let result = &verify(&tampered)[0];
+        assert!(result.signature_valid);
+        assert!(!result.is_valid());
+        assert!(result.mdp_violations[0].contains("page modified"));
+    }
+
+    #[test]
+    fn test_no_changes_permission_rejects_new_signature() {
+        let certified = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(
+                &unsigned_pdf(),
+                &SignatureOptions::new("Author").certify(DocMdpPermission::NoChanges),
+            )
+            .unwrap();
+        let approved = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(&certified.bytes, &SignatureOptions::new("Reviewer"))
+            .unwrap();
+        let results = verify(&approved.bytes);
+        let author = results.iter().find(|r| r.field_name == "Author").unwrap();
+        assert!(!author.mdp_violations.is_empty());
+    }
+
+    #[test]
+    fn test_field_mdp_locked_field_change() {
+        // Base document with a text field
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        let base = doc.to_bytes().unwrap();
+        let field_num = {
+            let reader = PdfReader::new(Cursor::new(base.clone())).unwrap();
+            reader.trailer().size().unwrap()
+        };
+        let mut field = crate::objects::Dictionary::new();
+        field.set("FT", crate::objects::Object::Name("Tx".to_string()));
+        field.set("T", crate::objects::Object::String("Amount".to_string()));
+        field.set("V", crate::objects::Object::String("100".to_string()));
+        let mut acro_form = crate::objects::Dictionary::new();
+        acro_form.set(
+            "Fields",
+            crate::objects::Object::Array(vec![crate::objects::Object::Reference(
+                crate::objects::ObjectId::new(field_num, 0),
+            )]),
+        );
+        let (root_num, catalog) = {
+            let document = PdfDocument::new(PdfReader::new(Cursor::new(base.clone())).unwrap());
+            let root = document.trailer().root().unwrap();
+            (root.0, document.catalog().unwrap())
+        };
+        let mut catalog = crate::objects::Dictionary::from(&catalog);
+        catalog.set(
+            "AcroForm",
+            crate::objects::Object::Reference(crate::objects::ObjectId::new(field_num + 1, 0)),
+        );
+        let with_form = append_update(
+            &base,
+            vec![
+                (field_num, crate::objects::Object::Dictionary(field.clone())),
+                (field_num + 1, crate::objects::Object::Dictionary(acro_form)),
+                (root_num, crate::objects::Object::Dictionary(catalog)),
+            ],
+        );
+
+        let signed = PdfSigner::new(test_helpers::rsa_credentials())
+            .sign(
+                &with_form,
+                &SignatureOptions::new("Approval").lock_fields(vec!["Amount".to_string()]),
+            )
+            .unwrap();
+        assert!(verify(&signed.bytes)[0].is_valid());
+
+        field.set("V", crate::objects::Object::String("999".to_string()));
+        let edited = append_update(
+            &signed.bytes,
+            vec![(field_num, crate::objects::Object::Dictionary(field))],
+        );
+        let result = verify(&edited)
+            .into_iter()
+            .find(|r| r.field_name == "Approval")
+            .unwrap();
+        assert!(result.signature_valid);
+        assert_eq!(result.mdp_violations.len(), 1);
+        assert!(result.mdp_violations[0].contains("Amount"));
+    }
+
+    #[test]
+    fn test_byte_range_bounds() {
+        assert_eq!(byte_range_bounds(&[0, 10, 20, 5]), Some([0, 10, 20, 25]));
+        assert_eq!(byte_range_bounds(&[0, 10, 5, 5]), None);
+        assert_eq!(byte_range_bounds(&[0, -1, 5, 5]), None);
+        assert_eq!(byte_range_bounds(&[0, 10, 20]), None);
+    }
+
+    #[test]
+    fn test_revision_ends() {
+        let bytes = b"%PDF-1.4\n...%%EOF\n...%%EOF\r\n";
+        assert_eq!(revision_ends(bytes), vec![18, 28]);
+    }
+}
@@ -2,6 +2,13 @@
 
 use crate::geometry::Rectangle;
 use crate::objects::{Array, Object, ObjectId};
+use crate::parser::objects::PdfObject;
+use crate::parser::resolve::{get, get_dict, Resolver};
+use crate::parser::strings::text_string;
+use crate::parser::PdfDocument;
+use crate::structure::name_tree::read_name_tree;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Seek};
 
 /// PDF destination types
 #[derive(Debug, Clone, PartialEq)]
@@ -375,6 +382,94 @@ impl Destination {
     }
 }
 
+/// Destinations of a parsed document, with the pages they target
+/// resolved to page indices
+pub(crate) struct DestinationResolver<'a> {
+    resolver: &'a dyn Resolver,
+    /// Page index of each page object
+    pages: HashMap<(u32, u16), u32>,
+    /// Named destinations of the `Dests` name tree and the older `Dests`
+    /// dictionary of the catalog
+    named: BTreeMap<String, PdfObject>,
+}
+
+impl<'a> DestinationResolver<'a> {
+    pub fn new<R: Read + Seek>(document: &'a PdfDocument<R>) -> crate::error::Result<Self> {
+        let resolver: &dyn Resolver = document;
+        let mut pages = HashMap::new();
+        for index in 0..document.page_count()? {
+            pages.insert(document.get_page(index)?.obj_ref, index);
+        }
+
+        let catalog = document.catalog()?;
+        let mut named = BTreeMap::new();
+        if let Some(dests) = get_dict(resolver, &catalog, "Dests") {
+            for (name, value) in &dests.0 {
+                named.insert(name.as_str().to_string(), value.clone());
+            }
+        }
+        if let Some(tree) = get_dict(resolver, &catalog, "Names")
+            .and_then(|names| get_dict(resolver, &names, "Dests"))
+        {
+            named.extend(read_name_tree(resolver, &tree));
+        }
+        Ok(Self {
+            resolver,
+            pages,
+            named,
+        })
+    }
+
+    /// Names of the named destinations, in sorted order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.named.keys().map(String::as_str)
+    }
+
+    /// The named destination `name`, `None` if it is missing or targets a
+    /// page outside the document
+    pub fn resolve_name(&self, name: &str) -> Option<Destination> {
+        let value = self.resolver.lookup(self.named.get(name)?);
+        let value = match value {
+            PdfObject::Dictionary(dict) => get(self.resolver, &dict, "D")?,
+            value => value,
+        };
+        self.resolve_explicit(&value)
+    }
+
+    /// The destination of an outline item or `GoTo` action: an explicit
+    /// destination array or the name of a named destination
+    pub fn resolve(&self, value: &PdfObject) -> Option<Destination> {
+        match self.resolver.lookup(value) {
+            PdfObject::Name(name) => self.resolve_name(name.as_str()),
+            PdfObject::String(name) => self.resolve_name(&text_string(name.as_bytes())),
+            value => self.resolve_explicit(&value),
+        }
+    }
+
+    /// An explicit destination array, its page reference replaced by the
+    /// page index
+    fn resolve_explicit(&self, value: &PdfObject) -> Option<Destination> {
+        let PdfObject::Array(array) = value else {
+            return None;
+        };
+        let (page, rest) = array.0.split_first()?;
+        let page = match page {
+            PdfObject::Reference(number, generation) => {
+                Object::Integer(*self.pages.get(&(*number, *generation))? as i64)
+            }
+            PdfObject::Integer(index) => Object::Integer(*index),
+            _ => return None,
+        };
+        let items = std::iter::once(page)
+            .chain(
+                rest.iter()
+                    .map(|item| Object::from(&self.resolver.lookup(item))),
+            )
+            .collect::<Vec<_>>();
+        Destination::from_array(&Array::from(items)).ok()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,15 +5,18 @@ mod destination;
 mod marked_content;
 mod name_tree;
 mod outline;
+pub(crate) mod page_tags;
 mod page_tree;
 mod tagged;
 
 pub use destination::{Destination, DestinationType, PageDestination};
 pub use marked_content::{MarkedContent, MarkedContentProperty};
+pub(crate) use name_tree::{read_name_tree, read_named_destinations, read_number_tree};
 pub use name_tree::{NameTree, NameTreeNode, NamedDestinations};
+pub(crate) use outline::read_outline;
 pub use outline::{outline_item_to_dict, OutlineBuilder, OutlineItem, OutlineTree};
 pub use page_tree::{PageTree, PageTreeBuilder, PageTreeNode};
 pub use tagged::{
     MarkedContentReference, RoleMap, StandardStructureType, StructTree, StructureAttributes,
-    StructureElement, StructureType,
+    StructureElement, StructureType, TableHeaderScope,
 };
@@ -2,7 +2,16 @@
 
 use crate::error::{PdfError, Result};
 use crate::objects::{Array, Dictionary, Object, ObjectId};
-use std::collections::BTreeMap;
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{get, Resolver};
+use crate::parser::strings::text_string;
+use crate::parser::PdfDocument;
+use crate::structure::destination::DestinationResolver;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Seek};
+
+/// Maximum depth of tree nodes followed when reading
+pub(crate) const MAX_TREE_DEPTH: usize = 32;
 
 /// Name tree node
 #[derive(Debug, Clone)]
@@ -121,6 +130,14 @@ impl NameTree {
         self.root.names.as_ref()?.get(name)
     }
 
+    /// Names in the tree, in sorted order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.root
+            .names
+            .iter()
+            .flat_map(|names| names.keys().map(String::as_str))
+    }
+
     /// Convert to dictionary
     pub fn to_dict(&self) -> Dictionary {
         self.root.to_dict()
@@ -184,15 +201,140 @@ impl NamedDestinations {
         }
     }
 
+    /// Names of the destinations, in sorted order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tree.names()
+    }
+
     /// Convert to dictionary
     pub fn to_dict(&self) -> Dictionary {
         self.tree.to_dict()
     }
 }
 
+/// The named destinations of a parsed document, with their pages
+/// resolved to page indices; `None` if it has none
+pub(crate) fn read_named_destinations<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> Result<Option<NamedDestinations>> {
+    let destinations = DestinationResolver::new(document)?;
+    if destinations.names().next().is_none() {
+        return Ok(None);
+    }
+    let mut named = NamedDestinations::new();
+    for name in destinations.names() {
+        if let Some(destination) = destinations.resolve_name(name) {
+            named.add_destination(name.to_string(), destination.to_array());
+        }
+    }
+    Ok(Some(named))
+}
+
+/// Key and value pairs of a parsed name tree (ISO 32000-1 §7.9.6), in
+/// tree order
+pub(crate) fn read_name_tree(
+    resolver: &dyn Resolver,
+    root: &PdfDictionary,
+) -> Vec<(String, PdfObject)> {
+    let mut entries = Vec::new();
+    walk_tree(
+        resolver,
+        root,
+        "Names",
+        &mut HashSet::new(),
+        0,
+        &mut |key, value| {
+            if let PdfObject::String(key) = resolver.lookup(key) {
+                entries.push((text_string(key.as_bytes()), value.clone()));
+            }
+        },
+    );
+    entries
+}
+
+/// Key and value pairs of a parsed number tree (ISO 32000-1 §7.9.7), in
+/// tree order
+pub(crate) fn read_number_tree(
+    resolver: &dyn Resolver,
+    root: &PdfDictionary,
+) -> Vec<(i64, PdfObject)> {
+    let mut entries = Vec::new();
+    walk_tree(
+        resolver,
+        root,
+        "Nums",
+        &mut HashSet::new(),
+        0,
+        &mut |key, value| {
+            if let PdfObject::Integer(key) = resolver.lookup(key) {
+                entries.push((key, value.clone()));
+            }
+        },
+    );
+    entries
+}
+
+/// Visit the key and value pairs in the `entries` arrays of a tree,
+/// depth first
+fn walk_tree(
+    resolver: &dyn Resolver,
+    node: &PdfDictionary,
+    entries: &str,
+    visited: &mut HashSet<(u32, u16)>,
+    depth: usize,
+    visit: &mut dyn FnMut(&PdfObject, &PdfObject),
+) {
+    if depth > MAX_TREE_DEPTH {
+        return;
+    }
+    if let Some(PdfObject::Array(pairs)) = get(resolver, node, entries) {
+        for pair in pairs.0.chunks_exact(2) {
+            visit(&pair[0], &pair[1]);
+        }
+    }
+    if let Some(PdfObject::Array(kids)) = get(resolver, node, "Kids") {
+        for kid in &kids.0 {
+            if let Some(reference) = kid.as_reference() {
+                if !visited.insert(reference) {
+                    continue;
+                }
+            }
+            if let PdfObject::Dictionary(kid) = resolver.lookup(kid) {
+                walk_tree(resolver, &kid, entries, visited, depth + 1, visit);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::test_helpers::create_pdf_with_objects;
+    use crate::parser::PdfReader;
+    use crate::structure::{Destination, PageDestination};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_named_destinations() {
+        let pdf = create_pdf_with_objects(&[
+            "<< /Type /Catalog /Pages 2 0 R /Names << /Dests 5 0 R >> /Dests << /old [3 0 R /Fit] >> >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 300 200] >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Kids [6 0 R] >>",
+            "<< /Limits [(a) (b)] /Names [(a) << /D [4 0 R /Fit] >> (b) [7 0 R /Fit]] >>",
+            "<< /Type /Page >>",
+        ]);
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap());
+        let named = document.named_destinations().unwrap().unwrap();
+
+        // "b" targets a page outside the page tree
+        assert_eq!(named.names().collect::<Vec<_>>(), ["a", "old"]);
+        let a = Destination::from_array(&named.get_destination("a").unwrap()).unwrap();
+        assert!(matches!(a.page, PageDestination::PageNumber(1)));
+        let old = Destination::from_array(&named.get_destination("old").unwrap()).unwrap();
+        assert!(matches!(old.page, PageDestination::PageNumber(0)));
+    }
 
     #[test]
     fn test_name_tree_node_leaf() {
@@ -2,8 +2,14 @@
 
 use crate::graphics::Color;
 use crate::objects::{Array, Dictionary, Object, ObjectId};
-use crate::structure::destination::Destination;
-use std::collections::VecDeque;
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{get, get_dict, get_name, get_numbers, Resolver};
+use crate::parser::strings::text_string;
+use crate::parser::PdfDocument;
+use crate::structure::destination::{Destination, DestinationResolver, PageDestination};
+use crate::structure::name_tree::MAX_TREE_DEPTH;
+use std::collections::{HashSet, VecDeque};
+use std::io::{Read, Seek};
 
 /// Outline item flags
 #[derive(Debug, Clone, Copy, Default)]
@@ -116,6 +122,7 @@ impl OutlineItem {
 }
 
 /// Outline tree structure
+#[derive(Debug, Clone)]
 pub struct OutlineTree {
     /// Root items
     pub items: Vec<OutlineItem>,
@@ -147,6 +154,33 @@ impl OutlineTree {
     pub fn visible_count(&self) -> i64 {
         self.items.iter().map(|item| item.count_visible()).sum()
     }
+
+    /// Moves the destinations to the pages `page` maps their page index to.
+    /// Items whose page is not mapped lose their destination, and are
+    /// dropped unless they still have children.
+    pub(crate) fn remap_pages(self, page: &dyn Fn(u32) -> Option<u32>) -> Self {
+        Self {
+            items: remap_items(self.items, page),
+        }
+    }
+}
+
+fn remap_items(items: Vec<OutlineItem>, page: &dyn Fn(u32) -> Option<u32>) -> Vec<OutlineItem> {
+    items
+        .into_iter()
+        .filter_map(|mut item| {
+            item.children = remap_items(std::mem::take(&mut item.children), page);
+            item.destination = item.destination.take().and_then(|mut destination| {
+                // Page references cannot be carried into another document
+                let PageDestination::PageNumber(index) = destination.page else {
+                    return None;
+                };
+                destination.page = PageDestination::PageNumber(page(index)?);
+                Some(destination)
+            });
+            (item.destination.is_some() || !item.children.is_empty()).then_some(item)
+        })
+        .collect()
 }
 
 /// Outline builder for creating outline hierarchy
@@ -287,10 +321,192 @@ pub fn outline_item_to_dict(
     dict
 }
 
+/// The outline of a parsed document, with destinations resolved to page
+/// indices; `None` if it has none
+pub(crate) fn read_outline<R: Read + Seek>(
+    document: &PdfDocument<R>,
+) -> crate::error::Result<Option<OutlineTree>> {
+    let resolver: &dyn Resolver = document;
+    let catalog = document.catalog()?;
+    let Some(root) = get_dict(resolver, &catalog, "Outlines") else {
+        return Ok(None);
+    };
+    let destinations = DestinationResolver::new(document)?;
+    let items = read_outline_items(resolver, &destinations, &root, &mut HashSet::new(), 0);
+    if items.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(OutlineTree { items }))
+}
+
+/// The children of an outline node, following `First` and `Next`
+fn read_outline_items(
+    resolver: &dyn Resolver,
+    destinations: &DestinationResolver,
+    parent: &PdfDictionary,
+    visited: &mut HashSet<(u32, u16)>,
+    depth: usize,
+) -> Vec<OutlineItem> {
+    let mut items = Vec::new();
+    if depth > MAX_TREE_DEPTH {
+        return items;
+    }
+    let mut next = parent.get("First").cloned();
+    while let Some(current) = next.take() {
+        if let Some(reference) = current.as_reference() {
+            if !visited.insert(reference) {
+                break;
+            }
+        }
+        let PdfObject::Dictionary(dict) = resolver.lookup(&current) else {
+            break;
+        };
+
+        let title = match get(resolver, &dict, "Title") {
+            Some(PdfObject::String(title)) => text_string(title.as_bytes()),
+            _ => String::new(),
+        };
+        let mut item = OutlineItem::new(title);
+        item.destination = match get(resolver, &dict, "Dest") {
+            Some(dest) => destinations.resolve(&dest),
+            None => get_dict(resolver, &dict, "A")
+                .filter(|action| get_name(resolver, action, "S").as_deref() == Some("GoTo"))
+                .and_then(|action| get(resolver, &action, "D"))
+                .and_then(|dest| destinations.resolve(&dest)),
+        };
+        if let Some([r, g, b]) = get_numbers(resolver, &dict, "C").as_deref() {
+            item.color = Some(Color::rgb(*r, *g, *b));
+        }
+        if let Some(PdfObject::Integer(flags)) = get(resolver, &dict, "F") {
+            item.flags.italic = flags & 1 != 0;
+            item.flags.bold = flags & 2 != 0;
+        }
+        item.children = read_outline_items(resolver, destinations, &dict, visited, depth + 1);
+        item.open =
+            !matches!(get(resolver, &dict, "Count"), Some(PdfObject::Integer(count)) if count < 0);
+
+        items.push(item);
+        next = dict.get("Next").cloned();
+    }
+    items
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::structure::destination::PageDestination;
+    use crate::parser::test_helpers::create_pdf_with_objects;
+    use crate::parser::PdfReader;
+    use crate::structure::destination::DestinationType;
+    use crate::{Document, Page};
+    use std::io::Cursor;
+
+    fn page_of(item: &OutlineItem) -> Option<u32> {
+        match item.destination.as_ref()?.page {
+            PageDestination::PageNumber(page) => Some(page),
+            PageDestination::PageRef(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_read_outline() {
+        let pdf = create_pdf_with_objects(&[
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 5 0 R \
+             /Names << /Dests << /Names [(intro) [3 0 R /XYZ 0 700 null]] >> >> >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 300 200] >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Outlines /First 6 0 R /Last 8 0 R /Count 2 >>",
+            "<< /Title (Intro) /Parent 5 0 R /Next 8 0 R /Dest (intro) \
+             /First 7 0 R /Last 7 0 R /Count -1 >>",
+            "<< /Title (Details) /Parent 6 0 R /A << /S /GoTo /D [4 0 R /Fit] >> \
+             /C [1 0 0] /F 3 >>",
+            "<< /Title <FEFF00E9> /Parent 5 0 R /Prev 6 0 R /Next 6 0 R /Dest [4 0 R /FitH 100] >>",
+        ]);
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap());
+        let outline = document.outline().unwrap().unwrap();
+
+        // The cycle back to the first item is not followed
+        assert_eq!(outline.items.len(), 2);
+        let intro = &outline.items[0];
+        assert_eq!(intro.title, "Intro");
+        assert!(!intro.open);
+        assert_eq!(page_of(intro), Some(0));
+        assert_eq!(
+            intro.destination.as_ref().unwrap().dest_type,
+            DestinationType::XYZ {
+                left: Some(0.0),
+                top: Some(700.0),
+                zoom: None
+            }
+        );
+
+        let details = &intro.children[0];
+        assert_eq!(details.title, "Details");
+        assert_eq!(page_of(details), Some(1));
+        assert_eq!(details.color, Some(Color::rgb(1.0, 0.0, 0.0)));
+        assert!(details.flags.bold && details.flags.italic);
+
+        let last = &outline.items[1];
+        assert_eq!(last.title, "é");
+        assert_eq!(
+            last.destination.as_ref().unwrap().dest_type,
+            DestinationType::FitH { top: Some(100.0) }
+        );
+    }
+
+    #[test]
+    fn test_written_outline_round_trip() {
+        let mut doc = Document::new();
+        for _ in 0..3 {
+            doc.add_page(Page::a4());
+        }
+        let mut chapter = OutlineItem::new("Chapter")
+            .with_destination(Destination::fit(PageDestination::PageNumber(0)));
+        chapter.add_child(
+            OutlineItem::new("Section 1")
+                .with_destination(Destination::fit(PageDestination::PageNumber(1))),
+        );
+        chapter.add_child(
+            OutlineItem::new("Section 2")
+                .with_destination(Destination::fit(PageDestination::PageNumber(2))),
+        );
+        let mut outline = OutlineTree::new();
+        outline.add_item(chapter);
+        outline.add_item(
+            OutlineItem::new("Appendix")
+                .with_destination(Destination::fit(PageDestination::PageNumber(2))),
+        );
+        doc.set_outline(outline);
+
+        let bytes = doc.to_bytes().unwrap();
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(bytes)).unwrap());
+        let outline = document.outline().unwrap().unwrap();
+
+        let titles: Vec<_> = outline.items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, ["Chapter", "Appendix"]);
+        let sections: Vec<_> = outline.items[0].children.iter().map(page_of).collect();
+        assert_eq!(sections, [Some(1), Some(2)]);
+        assert_eq!(page_of(&outline.items[1]), Some(2));
+    }
+
+    #[test]
+    fn test_remap_pages() {
+        let mut chapter = OutlineItem::new("Chapter");
+        chapter.add_child(
+            OutlineItem::new("Kept")
+                .with_destination(Destination::fit(PageDestination::PageNumber(4))),
+        );
+        let mut outline = OutlineTree::new();
+        outline.add_item(chapter);
+        outline.add_item(
+            OutlineItem::new("Dropped")
+                .with_destination(Destination::fit(PageDestination::PageNumber(1))),
+        );
+
+        let outline = outline.remap_pages(&|page| (page >= 2).then(|| page - 2));
+        assert_eq!(outline.items.len(), 1);
+        assert_eq!(page_of(&outline.items[0].children[0]), Some(2));
+    }
 
     #[test]
     fn test_outline_item_new() {
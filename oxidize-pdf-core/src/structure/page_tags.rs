@@ -0,0 +1,183 @@
+//! Structure recorded while content is added to a tagged page
+//!
+//! A tagged page wraps the content of paragraphs, headings, tables, lists
+//! and images in marked content (`BDC`/`EMC`) and records the structure
+//! elements they belong to. Decorations such as table borders and list
+//! separators are marked as artifacts. The writer grafts the elements of
+//! every page into the document structure tree.
+
+use super::tagged::{StandardStructureType, StructTree, StructureElement};
+use crate::error::{PdfError, Result};
+
+/// Begins decorative content that is not part of the structure
+pub(crate) const BEGIN_ARTIFACT: &str = "/Artifact BMC\n";
+
+/// Begins a page header or footer
+pub(crate) const BEGIN_PAGINATION_ARTIFACT: &str = "/Artifact <</Type /Pagination>> BDC\n";
+
+/// Ends marked content
+pub(crate) const END_MARKED_CONTENT: &str = "EMC\n";
+
+/// Structure elements and marked content identifiers of one page
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PageTags {
+    enabled: bool,
+    next_mcid: u32,
+    /// Elements of the page below a placeholder root
+    tree: StructTree,
+    /// Grouping elements still open, innermost last
+    open: Vec<usize>,
+}
+
+impl PageTags {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn next_mcid(&self) -> u32 {
+        self.next_mcid
+    }
+
+    pub fn allocate_mcid(&mut self) -> u32 {
+        let mcid = self.next_mcid;
+        self.next_mcid += 1;
+        mcid
+    }
+
+    /// Opens a grouping element (Table, TR, L, LI, ...) for the elements
+    /// recorded next
+    pub fn begin_group(&mut self, element: StructureElement) {
+        let index = self.add(element);
+        self.open.push(index);
+    }
+
+    /// Closes the innermost grouping element
+    pub fn end_group(&mut self) {
+        self.open.pop();
+    }
+
+    /// Records an element whose content follows and returns the operator
+    /// beginning its marked content
+    pub fn begin_content(&mut self, element: StructureElement) -> String {
+        let name = element.structure_type.as_pdf_name();
+        let mcid = self.allocate_mcid();
+        let index = self.add(element);
+        if let Some(element) = self.tree.get_mut(index) {
+            element.add_mcid(0, mcid);
+        }
+        format!("/{name} <</MCID {mcid}>> BDC\n")
+    }
+
+    /// Records content directly in the innermost grouping element and
+    /// returns the operator beginning its marked content
+    pub fn begin_group_content(&mut self) -> String {
+        let mcid = self.allocate_mcid();
+        let index = self.current();
+        let element = self
+            .tree
+            .get_mut(index)
+            .expect("current element exists in the page tree");
+        element.add_mcid(0, mcid);
+        format!(
+            "/{} <</MCID {mcid}>> BDC\n",
+            element.structure_type.as_pdf_name()
+        )
+    }
+
+    /// Whether any element has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() <= 1
+    }
+
+    /// Adds the elements of the page below `parent` of `tree`, with their
+    /// marked content on page `page_index`
+    pub fn graft(&self, tree: &mut StructTree, parent: usize, page_index: usize) -> Result<()> {
+        if let Some(root) = self.tree.root() {
+            for &child in &root.children {
+                self.graft_element(tree, parent, child, page_index)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn graft_element(
+        &self,
+        tree: &mut StructTree,
+        parent: usize,
+        index: usize,
+        page_index: usize,
+    ) -> Result<()> {
+        let Some(element) = self.tree.get(index) else {
+            return Ok(());
+        };
+        let mut copy = element.clone();
+        copy.children.clear();
+        for mcid in &mut copy.mcids {
+            mcid.page_index = page_index;
+        }
+        let copied = tree
+            .add_child(parent, copy)
+            .map_err(PdfError::InvalidStructure)?;
+        for &child in &element.children {
+            self.graft_element(tree, copied, child, page_index)?;
+        }
+        Ok(())
+    }
+
+    fn current(&mut self) -> usize {
+        match (self.open.last(), self.tree.root_index()) {
+            (Some(&index), _) => index,
+            (None, Some(root)) => root,
+            (None, None) => self
+                .tree
+                .set_root(StructureElement::new(StandardStructureType::Document)),
+        }
+    }
+
+    fn add(&mut self, element: StructureElement) -> usize {
+        let parent = self.current();
+        self.tree
+            .add_child(parent, element)
+            .expect("current element exists in the page tree")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_and_content() {
+        let mut tags = PageTags::default();
+        tags.begin_group(StructureElement::new(StandardStructureType::L));
+        tags.begin_group(StructureElement::new(StandardStructureType::LI));
+        let lbl = tags.begin_content(StructureElement::new(StandardStructureType::Lbl));
+        tags.begin_group(StructureElement::new(StandardStructureType::LBody));
+        let body = tags.begin_group_content();
+        tags.end_group();
+        tags.end_group();
+        tags.end_group();
+        let p = tags.begin_content(StructureElement::new(StandardStructureType::P));
+
+        assert_eq!(lbl, "/Lbl <</MCID 0>> BDC\n");
+        assert_eq!(body, "/LBody <</MCID 1>> BDC\n");
+        assert_eq!(p, "/P <</MCID 2>> BDC\n");
+        assert_eq!(tags.next_mcid(), 3);
+
+        let mut tree = StructTree::new();
+        let root = tree.set_root(StructureElement::new(StandardStructureType::Document));
+        tags.graft(&mut tree, root, 4).unwrap();
+
+        let children = &tree.get(root).unwrap().children;
+        assert_eq!(children.len(), 2);
+        let list = tree.get(children[0]).unwrap();
+        let item = tree.get(list.children[0]).unwrap();
+        let body = tree.get(item.children[1]).unwrap();
+        assert_eq!(body.mcids[0].page_index, 4);
+        assert_eq!(body.mcids[0].mcid, 1);
+    }
+}
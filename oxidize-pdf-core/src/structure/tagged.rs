@@ -4,27 +4,23 @@
 //! This module provides **structure tree generation** with parent references,
 //! attributes, and role mapping.
 //!
-//! # ⚠️ Status (v1.4.0)
+//! # Status
 //!
 //! - ✅ **Structure tree hierarchy** - Fully implemented
 //! - ✅ **Parent references** - ISO 32000-1 §14.7.2 compliant
-//! - ✅ **Attributes** - Lang, Alt, ActualText, Title, BBox
+//! - ✅ **Attributes** - Lang, Alt, ActualText, Title, BBox, table header Scope
 //! - ✅ **RoleMap** - Custom to standard type mapping
-//! - ❌ **Marked content operators** - Not yet implemented (v1.5.0)
-//! - ❌ **Automatic MCID assignment** - Not yet implemented (v1.5.0)
-//! - ❌ **PDF/UA compliance** - Not yet achieved (v1.5.0)
-//!
-//! **Note**: While the structure tree is generated correctly, the content is NOT
-//! automatically marked with BMC/BDC/EMC operators. This means screen readers
-//! will see the structure but cannot navigate the actual content. For full
-//! accessibility, wait for v1.5.0 or add marked content manually.
+//! - ✅ **Marked content operators** - Manual with `Page::begin_marked_content`,
+//!   automatic on pages tagged with `Page::set_tagged`
+//! - ✅ **PDF/UA output** - `Document::enable_pdfua`, checked with
+//!   `verification::pdfua::check_pdfua`
 //!
 //! # Key Components
 //!
 //! - **Structure Tree Root** (`/StructTreeRoot` in catalog): Root of structure hierarchy
 //! - **Structure Elements** (`/StructElem`): Elements forming the document tree
 //! - **Role Map**: Maps custom structure types to standard types
-//! - **Marked Content** (v1.5.0): Associates content with structure elements via MCIDs
+//! - **Marked Content**: Associates content with structure elements via MCIDs
 //!
 //! # Standard Structure Types
 //!
@@ -293,6 +289,41 @@ impl StandardStructureType {
             _ => None,
         }
     }
+
+    /// Heading type of a level from 1 to 6
+    pub fn heading(level: u8) -> Option<Self> {
+        match level {
+            1 => Some(Self::H1),
+            2 => Some(Self::H2),
+            3 => Some(Self::H3),
+            4 => Some(Self::H4),
+            5 => Some(Self::H5),
+            6 => Some(Self::H6),
+            _ => None,
+        }
+    }
+}
+
+/// Cells a table header cell applies to (ISO 32000-1 Table 349, `/Scope`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableHeaderScope {
+    /// The header of its row
+    Row,
+    /// The header of its column
+    Column,
+    /// The header of both its row and its column
+    Both,
+}
+
+impl TableHeaderScope {
+    /// Returns the PDF name for this scope
+    pub fn as_pdf_name(&self) -> &'static str {
+        match self {
+            Self::Row => "Row",
+            Self::Column => "Column",
+            Self::Both => "Both",
+        }
+    }
 }
 
 /// Attributes that can be attached to structure elements
@@ -319,6 +350,9 @@ pub struct StructureAttributes {
     /// Bounding box (Left, Bottom, Right, Top)
     pub bbox: Option<[f64; 4]>,
 
+    /// Cells a table header cell (TH) applies to
+    pub scope: Option<TableHeaderScope>,
+
     /// Custom attributes (for application-specific metadata)
     pub custom: HashMap<String, String>,
 }
@@ -358,6 +392,12 @@ impl StructureAttributes {
         self.bbox = Some(bbox);
         self
     }
+
+    /// Sets the scope of a table header cell
+    pub fn with_scope(mut self, scope: TableHeaderScope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
 }
 
 /// A structure element in the document structure tree
@@ -467,6 +507,12 @@ impl StructureElement {
         self
     }
 
+    /// Sets the scope of a table header cell
+    pub fn with_scope(mut self, scope: TableHeaderScope) -> Self {
+        self.attributes.scope = Some(scope);
+        self
+    }
+
     /// Adds a marked content reference to this element
     pub fn add_mcid(&mut self, page_index: usize, mcid: u32) {
         self.mcids.push(MarkedContentReference { page_index, mcid });
@@ -651,6 +697,11 @@ mod tests {
             Some(StandardStructureType::H1)
         );
         assert_eq!(StandardStructureType::from_pdf_name("Invalid"), None);
+        assert_eq!(
+            StandardStructureType::heading(3),
+            Some(StandardStructureType::H3)
+        );
+        assert_eq!(StandardStructureType::heading(7), None);
     }
 
     #[test]
@@ -12,6 +12,10 @@ pub enum TemplateValue {
     Boolean(bool),
     /// Nested context for dot notation support (e.g., {{user.name}})
     Object(HashMap<String, TemplateValue>),
+    /// List of values, for `{{#each}}` blocks and tables
+    Array(Vec<TemplateValue>),
+    /// Missing value
+    Null,
 }
 
 impl TemplateValue {
@@ -29,6 +33,36 @@ impl TemplateValue {
             Self::Integer(i) => format!("{}", i),
             Self::Boolean(b) => format!("{}", b),
             Self::Object(_) => "[Object]".to_string(),
+            Self::Array(items) => items
+                .iter()
+                .map(TemplateValue::as_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Self::Null => String::new(),
+        }
+    }
+
+    /// Whether the value counts as true in `{{#if}}` blocks: false, zero,
+    /// empty strings and arrays, and null do not
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::String(s) => !s.is_empty(),
+            Self::Number(n) => *n != 0.0,
+            Self::Integer(i) => *i != 0,
+            Self::Boolean(b) => *b,
+            Self::Object(_) => true,
+            Self::Array(items) => !items.is_empty(),
+            Self::Null => false,
+        }
+    }
+
+    /// The value as a number, parsing strings
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Integer(i) => Some(*i as f64),
+            Self::String(s) => s.trim().parse().ok(),
+            _ => None,
         }
     }
 
@@ -127,6 +161,26 @@ impl TemplateContext {
         }
     }
 
+    /// Create a context from a JSON object, its members becoming the
+    /// variables
+    #[cfg(feature = "templates-json")]
+    pub fn from_json(json: &str) -> TemplateResult<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| TemplateError::ParseError(format!("Invalid JSON context: {e}")))?;
+        Self::from_json_value(value)
+    }
+
+    /// Create a context from a parsed JSON object
+    #[cfg(feature = "templates-json")]
+    pub fn from_json_value(value: serde_json::Value) -> TemplateResult<Self> {
+        match TemplateValue::from(value) {
+            TemplateValue::Object(variables) => Ok(Self { variables }),
+            _ => Err(TemplateError::ParseError(
+                "JSON context must be an object".to_string(),
+            )),
+        }
+    }
+
     /// Get a variable as a string
     pub fn get_string(&self, name: &str) -> TemplateResult<String> {
         Ok(self.get(name)?.as_string())
@@ -214,6 +268,41 @@ impl From<bool> for TemplateValue {
     }
 }
 
+impl From<Vec<TemplateValue>> for TemplateValue {
+    fn from(items: Vec<TemplateValue>) -> Self {
+        TemplateValue::Array(items)
+    }
+}
+
+impl From<HashMap<String, TemplateValue>> for TemplateValue {
+    fn from(map: HashMap<String, TemplateValue>) -> Self {
+        TemplateValue::Object(map)
+    }
+}
+
+#[cfg(feature = "templates-json")]
+impl From<serde_json::Value> for TemplateValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => TemplateValue::Null,
+            serde_json::Value::Bool(b) => TemplateValue::Boolean(b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => TemplateValue::Integer(i),
+                None => TemplateValue::Number(n.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(s) => TemplateValue::String(s),
+            serde_json::Value::Array(items) => {
+                TemplateValue::Array(items.into_iter().map(TemplateValue::from).collect())
+            }
+            serde_json::Value::Object(map) => TemplateValue::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, TemplateValue::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,5 +366,28 @@ mod tests {
 
         let val: TemplateValue = true.into();
         assert_eq!(val.to_string(), "true");
+
+        let val: TemplateValue = vec!["a".into(), 1i64.into()].into();
+        assert_eq!(val.to_string(), "a, 1");
+        assert!(val.is_truthy());
+        assert!(!TemplateValue::Array(Vec::new()).is_truthy());
+        assert!(!TemplateValue::Null.is_truthy());
+    }
+
+    #[cfg(feature = "templates-json")]
+    #[test]
+    fn test_context_from_json() {
+        let ctx = TemplateContext::from_json(
+            r#"{"name": "Acme", "total": 12.5, "count": 3, "items": [{"sku": "A1"}], "note": null}"#,
+        )
+        .unwrap();
+        assert_eq!(ctx.get_string("name").unwrap(), "Acme");
+        assert_eq!(ctx.get_string("total").unwrap(), "12.5");
+        assert!(matches!(ctx.get("count"), Ok(TemplateValue::Integer(3))));
+        assert!(matches!(ctx.get("items"), Ok(TemplateValue::Array(items)) if items.len() == 1));
+        assert!(matches!(ctx.get("note"), Ok(TemplateValue::Null)));
+
+        assert!(TemplateContext::from_json("[1, 2]").is_err());
+        assert!(TemplateContext::from_json("{").is_err());
     }
 }
@@ -0,0 +1,229 @@
+//! Filters for template values, as in `{{total | currency:"EUR"}}`
+//!
+//! - `upper`, `lower`, `trim`
+//! - `default:"text"`: the text for missing, null and empty values
+//! - `number:decimals,decimal_separator,group_separator`: a number with
+//!   grouped thousands, such as `1,234.50`; without decimals for integers
+//!   and with two for other numbers by default
+//! - `currency:symbol,decimals`: an amount with a currency symbol or ISO
+//!   code, `$` and two decimals by default
+//! - `percent:decimals`: a fraction as a percentage
+//! - `date:format`: a date, an RFC 3339 timestamp or Unix seconds in a
+//!   `strftime` format, `%Y-%m-%d` by default
+
+use chrono::format::StrftimeItems;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use super::context::TemplateValue;
+use super::error::{TemplateError, TemplateResult};
+use super::syntax::Filter;
+
+const FILTERS: &[&str] = &[
+    "upper", "lower", "trim", "default", "number", "currency", "percent", "date",
+];
+
+/// Whether a filter of that name exists
+pub(crate) fn is_filter(name: &str) -> bool {
+    FILTERS.contains(&name)
+}
+
+/// Apply a filter to a value
+pub(crate) fn apply(value: TemplateValue, filter: &Filter) -> TemplateResult<TemplateValue> {
+    let arg = |index: usize| filter.args.get(index).map(String::as_str);
+    let text = match filter.name.as_str() {
+        "upper" => value.as_string().to_uppercase(),
+        "lower" => value.as_string().to_lowercase(),
+        "trim" => value.as_string().trim().to_string(),
+        "default" => match &value {
+            TemplateValue::Null => arg(0).unwrap_or_default().to_string(),
+            TemplateValue::String(s) if s.is_empty() => arg(0).unwrap_or_default().to_string(),
+            _ => return Ok(value),
+        },
+        "number" => {
+            let default_decimals = match value {
+                TemplateValue::Integer(_) => 0,
+                _ => 2,
+            };
+            format_number(
+                number(&value, filter)?,
+                decimals(arg(0), default_decimals)?,
+                arg(1).unwrap_or("."),
+                arg(2).unwrap_or(","),
+            )
+        }
+        "currency" => {
+            let amount = number(&value, filter)?;
+            let formatted = format_number(amount.abs(), decimals(arg(1), 2)?, ".", ",");
+            let sign = if amount < 0.0 { "-" } else { "" };
+            format!(
+                "{sign}{}{formatted}",
+                currency_symbol(arg(0).unwrap_or("$"))
+            )
+        }
+        "percent" => format!(
+            "{}%",
+            format_number(
+                number(&value, filter)? * 100.0,
+                decimals(arg(0), 0)?,
+                ".",
+                ","
+            )
+        ),
+        "date" => format_date(&value, arg(0).unwrap_or("%Y-%m-%d"))?,
+        name => {
+            return Err(TemplateError::RenderError(format!(
+                "Unknown filter '{name}'"
+            )))
+        }
+    };
+    Ok(TemplateValue::String(text))
+}
+
+fn number(value: &TemplateValue, filter: &Filter) -> TemplateResult<f64> {
+    value.as_number().ok_or_else(|| {
+        TemplateError::RenderError(format!(
+            "Filter '{}' needs a number, not '{}'",
+            filter.name,
+            value.as_string()
+        ))
+    })
+}
+
+fn decimals(arg: Option<&str>, default: usize) -> TemplateResult<usize> {
+    match arg {
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| TemplateError::RenderError(format!("Invalid decimals '{arg}'"))),
+        None => Ok(default),
+    }
+}
+
+fn currency_symbol(currency: &str) -> String {
+    match currency {
+        "USD" => "$".to_string(),
+        "EUR" => "€".to_string(),
+        "GBP" => "£".to_string(),
+        "JPY" | "CNY" => "¥".to_string(),
+        code if code.len() == 3 && code.chars().all(|ch| ch.is_ascii_uppercase()) => {
+            format!("{code} ")
+        }
+        symbol => symbol.to_string(),
+    }
+}
+
+/// A number with `decimals` decimals and its integer digits in groups of
+/// three
+fn format_number(
+    value: f64,
+    decimals: usize,
+    decimal_separator: &str,
+    group_separator: &str,
+) -> String {
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = formatted
+        .split_once('.')
+        .map_or((formatted.as_str(), None), |(integer, fraction)| {
+            (integer, Some(fraction))
+        });
+
+    let mut result = String::new();
+    if value < 0.0 && formatted.chars().any(|ch| ch.is_ascii_digit() && ch != '0') {
+        result.push('-');
+    }
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            result.push_str(group_separator);
+        }
+        result.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        result.push_str(decimal_separator);
+        result.push_str(fraction);
+    }
+    result
+}
+
+fn format_date(value: &TemplateValue, format: &str) -> TemplateResult<String> {
+    let invalid = || TemplateError::RenderError(format!("Invalid date '{}'", value.as_string()));
+    let date = match value {
+        TemplateValue::Integer(seconds) => DateTime::from_timestamp(*seconds, 0)
+            .ok_or_else(invalid)?
+            .naive_utc(),
+        TemplateValue::String(text) => DateTime::parse_from_rfc3339(text)
+            .map(|date| date.naive_local())
+            .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"))
+            .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S"))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+
+    let items = StrftimeItems::new(format)
+        .parse()
+        .map_err(|_| TemplateError::RenderError(format!("Invalid date format '{format}'")))?;
+    Ok(date.format_with_items(items.into_iter()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(value: impl Into<TemplateValue>, name: &str, args: &[&str]) -> String {
+        let filter = Filter {
+            name: name.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+        apply(value.into(), &filter).unwrap().as_string()
+    }
+
+    #[test]
+    fn test_number_filters() {
+        assert_eq!(filter(1234567.891, "number", &[]), "1,234,567.89");
+        assert_eq!(filter(1234i64, "number", &[]), "1,234");
+        assert_eq!(filter("1234.5", "number", &["1", ",", "."]), "1.234,5");
+        assert_eq!(filter(-0.001, "number", &["2"]), "0.00");
+        assert_eq!(filter(-1250.0, "currency", &[]), "-$1,250.00");
+        assert_eq!(filter(99.5, "currency", &["EUR"]), "€99.50");
+        assert_eq!(filter(10i64, "currency", &["CHF", "0"]), "CHF 10");
+        assert_eq!(filter(0.125, "percent", &["1"]), "12.5%");
+    }
+
+    #[test]
+    fn test_text_and_date_filters() {
+        assert_eq!(filter("Acme", "upper", &[]), "ACME");
+        assert_eq!(filter(" x ", "trim", &[]), "x");
+        assert_eq!(filter(TemplateValue::Null, "default", &["N/A"]), "N/A");
+        assert_eq!(filter("set", "default", &["N/A"]), "set");
+        assert_eq!(filter("2024-01-15", "date", &["%d/%m/%Y"]), "15/01/2024");
+        assert_eq!(
+            filter("2024-01-15T10:30:00+02:00", "date", &["%B %-d, %Y %H:%M"]),
+            "January 15, 2024 10:30"
+        );
+        assert_eq!(filter(0i64, "date", &[]), "1970-01-01");
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        let number = Filter {
+            name: "number".to_string(),
+            args: Vec::new(),
+        };
+        assert!(apply("abc".into(), &number).is_err());
+        let date = Filter {
+            name: "date".to_string(),
+            args: vec!["%Q".to_string()],
+        };
+        assert!(apply("2024-01-15".into(), &date).is_err());
+        assert!(apply(
+            "15 January".into(),
+            &Filter {
+                args: Vec::new(),
+                ..date
+            }
+        )
+        .is_err());
+    }
+}
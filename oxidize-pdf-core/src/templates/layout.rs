@@ -0,0 +1,452 @@
+//! Documents from templates
+//!
+//! A document template renders to content laid out on pages: text in
+//! paragraphs separated by blank lines, tables with a row for every item
+//! of an array, images and page breaks. Pages are added as the content
+//! needs them.
+
+use std::collections::HashMap;
+
+use super::context::TemplateContext;
+use super::error::{TemplateError, TemplateResult};
+use super::renderer::{Block, TableBlock, TemplateRenderer};
+use super::syntax::{self, Node};
+use crate::advanced_tables::{AdvancedTableBuilder, CellStyle, PaginationOptions, TableRenderer};
+use crate::document::Document;
+use crate::graphics::Image;
+use crate::page::{Margins, Page};
+use crate::text::{typeset_shaped_paragraph, Font, ParagraphOptions, TextAlign};
+
+/// Page and text settings of a document template
+#[derive(Debug, Clone)]
+pub struct TemplateLayout {
+    /// Page width in points
+    pub page_width: f64,
+    /// Page height in points
+    pub page_height: f64,
+    /// Page margins
+    pub margins: Margins,
+    /// Font of paragraphs
+    pub font: Font,
+    /// Font size of paragraphs
+    pub font_size: f64,
+    /// Line height multiplier
+    pub line_height: f64,
+    /// Alignment of paragraphs
+    pub text_align: TextAlign,
+    /// Space after paragraphs, tables and images
+    pub paragraph_spacing: f64,
+    /// Line breaking and widow and orphan control of paragraphs
+    pub paragraph: ParagraphOptions,
+}
+
+impl Default for TemplateLayout {
+    fn default() -> Self {
+        Self {
+            page_width: 595.0,
+            page_height: 842.0,
+            margins: Margins::default(),
+            font: Font::Helvetica,
+            font_size: 11.0,
+            line_height: 1.4,
+            text_align: TextAlign::Left,
+            paragraph_spacing: 8.0,
+            paragraph: ParagraphOptions::default(),
+        }
+    }
+}
+
+/// A template that renders to document pages
+///
+/// # Example
+///
+/// ```rust
+/// use oxidize_pdf::templates::{DocumentTemplate, TemplateContext, TemplateValue};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let template = DocumentTemplate::new(
+///     "Statement for {{customer}}\n\n\
+///      {{#table lines}}\
+///      {{#column \"Description\" 300}}{{description}}{{/column}}\
+///      {{#column \"Amount\" 100 right}}{{amount | currency}}{{/column}}\
+///      {{/table}}",
+/// )?;
+///
+/// let mut context = TemplateContext::new();
+/// context.set("customer", "Acme Corp");
+/// let mut line = std::collections::HashMap::new();
+/// line.insert("description".to_string(), "Consulting".into());
+/// line.insert("amount".to_string(), 1250.0.into());
+/// context.set_value("lines", TemplateValue::Array(vec![line.into()]));
+///
+/// let document = template.render(&context)?;
+/// assert_eq!(document.page_count(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DocumentTemplate {
+    nodes: Vec<Node>,
+    renderer: TemplateRenderer,
+    images: HashMap<String, Image>,
+    layout: TemplateLayout,
+}
+
+/// Page and height of the next content
+struct Cursor {
+    page: usize,
+    y: f64,
+}
+
+impl DocumentTemplate {
+    /// Parse a document template
+    pub fn new(template: &str) -> TemplateResult<Self> {
+        Ok(Self {
+            nodes: syntax::parse(template)?,
+            renderer: TemplateRenderer::new(),
+            images: HashMap::new(),
+            layout: TemplateLayout::default(),
+        })
+    }
+
+    /// Set the page and text settings
+    pub fn with_layout(mut self, layout: TemplateLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Register a partial template, included with `{{> name}}`
+    pub fn register_partial(
+        &mut self,
+        name: impl Into<String>,
+        template: &str,
+    ) -> TemplateResult<&mut Self> {
+        self.renderer.register_partial(name, template)?;
+        Ok(self)
+    }
+
+    /// Add an image, shown with `{{image name}}`
+    pub fn add_image(&mut self, name: impl Into<String>, image: Image) -> &mut Self {
+        self.images.insert(name.into(), image);
+        self
+    }
+
+    /// Render the template into a new document
+    pub fn render(&self, context: &TemplateContext) -> TemplateResult<Document> {
+        let mut document = Document::new();
+        self.render_into(&mut document, context)?;
+        Ok(document)
+    }
+
+    /// Render the template on pages added to a document
+    pub fn render_into(
+        &self,
+        document: &mut Document,
+        context: &TemplateContext,
+    ) -> TemplateResult<()> {
+        let blocks = self.renderer.render_blocks(&self.nodes, context)?;
+        let first_page = document.page_count();
+        let mut cursor = self.new_page(document);
+
+        for block in blocks {
+            match block {
+                Block::Text(text) => {
+                    for paragraph in paragraphs(&text) {
+                        self.write_paragraph(document, &mut cursor, &paragraph)?;
+                    }
+                }
+                Block::Table(table) => self.write_table(document, &mut cursor, &table)?,
+                Block::Image {
+                    name,
+                    width,
+                    height,
+                } => self.write_image(document, &mut cursor, &name, width, height)?,
+                Block::PageBreak => cursor = self.new_page(document),
+            }
+        }
+
+        // Characters shown after the pages were added
+        for index in first_page..document.page_count() {
+            if let Some(chars) = document.page(index).and_then(Page::get_used_characters) {
+                document.used_characters.extend(chars);
+            }
+        }
+        Ok(())
+    }
+
+    fn new_page(&self, document: &mut Document) -> Cursor {
+        let layout = &self.layout;
+        let mut page = Page::new(layout.page_width, layout.page_height);
+        page.set_margins(
+            layout.margins.left,
+            layout.margins.right,
+            layout.margins.top,
+            layout.margins.bottom,
+        );
+        document.add_page(page);
+        Cursor {
+            page: document.page_count() - 1,
+            y: self.top(),
+        }
+    }
+
+    fn top(&self) -> f64 {
+        self.layout.page_height - self.layout.margins.top
+    }
+
+    fn content_width(&self) -> f64 {
+        self.layout.page_width - self.layout.margins.left - self.layout.margins.right
+    }
+
+    fn write_paragraph(
+        &self,
+        document: &mut Document,
+        cursor: &mut Cursor,
+        text: &str,
+    ) -> TemplateResult<()> {
+        let layout = &self.layout;
+        let width = self.content_width();
+        let line_height = layout.font_size * layout.line_height;
+        let lines = typeset_shaped_paragraph(
+            text,
+            &layout.font,
+            layout.font_size,
+            width,
+            &layout.paragraph,
+            document.shaping_fonts(),
+        );
+
+        let mut rest = lines.as_slice();
+        while !rest.is_empty() {
+            let available = ((cursor.y - layout.margins.bottom) / line_height + 1e-6).floor();
+            let available = available.max(0.0) as usize;
+            let count = match layout.paragraph.lines_before_break(rest.len(), available) {
+                // Keep widow and orphan lines with the rest, unless the page
+                // is empty
+                0 if cursor.y < self.top() => {
+                    *cursor = self.new_page(document);
+                    continue;
+                }
+                0 => available.max(1).min(rest.len()),
+                count => count,
+            };
+
+            let page = page_mut(document, cursor)?;
+            for line in &rest[..count] {
+                let x = match layout.text_align {
+                    TextAlign::Left | TextAlign::Justified => layout.margins.left,
+                    TextAlign::Right => layout.margins.left + width - line.width,
+                    TextAlign::Center => layout.margins.left + (width - line.width) / 2.0,
+                };
+                let text = page.text().set_font(layout.font.clone(), layout.font_size);
+                if layout.text_align == TextAlign::Justified {
+                    let (character_spacing, word_spacing) = line.justification(
+                        width,
+                        layout.paragraph.max_letter_spacing * layout.font_size,
+                    );
+                    text.set_character_spacing(character_spacing)
+                        .set_word_spacing(word_spacing);
+                }
+                text.at(x, cursor.y - layout.font_size)
+                    .write(&line.text)
+                    .map_err(render_error)?;
+                cursor.y -= line_height;
+            }
+            if layout.text_align == TextAlign::Justified {
+                page.text().set_character_spacing(0.0).set_word_spacing(0.0);
+            }
+
+            rest = &rest[count..];
+            if !rest.is_empty() {
+                *cursor = self.new_page(document);
+            }
+        }
+        cursor.y -= layout.paragraph_spacing;
+        Ok(())
+    }
+
+    fn write_table(
+        &self,
+        document: &mut Document,
+        cursor: &mut Cursor,
+        block: &TableBlock,
+    ) -> TemplateResult<()> {
+        let mut builder = AdvancedTableBuilder::new().repeat_headers(true);
+        for (header, width, alignment) in &block.columns {
+            builder = builder.add_styled_column(
+                header.as_str(),
+                *width,
+                CellStyle::data().alignment(*alignment),
+            );
+        }
+        for row in &block.rows {
+            builder = builder.add_row(row.iter().map(String::as_str).collect());
+        }
+        let table = builder
+            .build()
+            .map_err(|e| TemplateError::RenderError(e.to_string()))?;
+
+        let layout = &self.layout;
+        let options = PaginationOptions {
+            start_page: Some(cursor.page),
+            start_y: Some(cursor.y),
+            x: Some(layout.margins.left),
+            page_width: layout.page_width,
+            page_height: layout.page_height,
+            margins: layout.margins.clone(),
+            ..Default::default()
+        };
+        let placed = TableRenderer::new()
+            .render_table_paginated(document, &table, &options)
+            .map_err(render_error)?;
+        cursor.page = placed.pages.last().copied().unwrap_or(cursor.page);
+        cursor.y = placed.end_y - layout.paragraph_spacing;
+        Ok(())
+    }
+
+    fn write_image(
+        &self,
+        document: &mut Document,
+        cursor: &mut Cursor,
+        name: &str,
+        width: Option<f64>,
+        height: Option<f64>,
+    ) -> TemplateResult<()> {
+        let image = self
+            .images
+            .get(name)
+            .ok_or_else(|| TemplateError::RenderError(format!("Image '{name}' not found")))?;
+        let aspect = image.height().max(1) as f64 / image.width().max(1) as f64;
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => (width, width * aspect),
+            (None, Some(height)) => (height / aspect, height),
+            (None, None) => {
+                let width = (image.width() as f64).min(self.content_width());
+                (width, width * aspect)
+            }
+        };
+
+        if cursor.y - height < self.layout.margins.bottom && cursor.y < self.top() {
+            *cursor = self.new_page(document);
+        }
+        let page = page_mut(document, cursor)?;
+        page.add_image(name, image.clone());
+        page.draw_image(
+            name,
+            self.layout.margins.left,
+            cursor.y - height,
+            width,
+            height,
+        )
+        .map_err(render_error)?;
+        cursor.y -= height + self.layout.paragraph_spacing;
+        Ok(())
+    }
+}
+
+fn page_mut<'a>(document: &'a mut Document, cursor: &Cursor) -> TemplateResult<&'a mut Page> {
+    document
+        .page_mut(cursor.page)
+        .ok_or_else(|| TemplateError::RenderError(format!("No page {}", cursor.page)))
+}
+
+fn render_error(error: crate::error::PdfError) -> TemplateError {
+    TemplateError::RenderError(error.to_string())
+}
+
+/// Paragraphs of text: runs of lines between blank lines
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+        } else {
+            current.push(line.trim());
+        }
+    }
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::TemplateValue;
+
+    fn content(document: &mut Document, index: usize) -> String {
+        let page = document.page_mut(index).unwrap();
+        String::from_utf8_lossy(&page.generate_content().unwrap()).into_owned()
+    }
+
+    fn statement_context(lines: usize) -> TemplateContext {
+        let mut context = TemplateContext::new();
+        context.set("customer", "Acme Corp");
+        let items = (0..lines)
+            .map(|index| {
+                let mut line = HashMap::new();
+                line.insert(
+                    "description".to_string(),
+                    TemplateValue::String(format!("Item {index}")),
+                );
+                line.insert("amount".to_string(), TemplateValue::Number(1000.0));
+                TemplateValue::Object(line)
+            })
+            .collect();
+        context.set_value("lines", TemplateValue::Array(items));
+        context
+    }
+
+    const STATEMENT: &str = "Statement for {{customer | upper}}\n\n\
+        {{#table lines}}\
+        {{#column \"Description\" 300}}{{description}}{{/column}}\
+        {{#column \"Amount\" 100 right}}{{amount | currency}}{{/column}}\
+        {{/table}}\
+        {{> closing}}";
+
+    #[test]
+    fn test_document_template() {
+        let mut template = DocumentTemplate::new(STATEMENT).unwrap();
+        template
+            .register_partial("closing", "{{pagebreak}}Thank you, {{customer}}.")
+            .unwrap();
+        template.add_image("logo", Image::from_gray_data(vec![0; 4], 2, 2).unwrap());
+
+        let mut document = template.render(&statement_context(40)).unwrap();
+        // The table continues on a second page, then a page break
+        assert_eq!(document.page_count(), 3);
+        let first = content(&mut document, 0);
+        assert!(first.contains("(Statement for ACME CORP) Tj"));
+        assert!(first.contains("($1,000.00) Tj"));
+        let second = content(&mut document, 1);
+        assert!(second.contains("(Description) Tj"));
+        assert!(second.contains("(Item 39) Tj"));
+        assert!(content(&mut document, 2).contains("(Thank you, Acme Corp.) Tj"));
+    }
+
+    #[test]
+    fn test_document_template_images_and_errors() {
+        let mut template = DocumentTemplate::new("{{image logo 100}}Text").unwrap();
+        template.add_image("logo", Image::from_gray_data(vec![0; 8], 4, 2).unwrap());
+        let mut document = template.render(&TemplateContext::new()).unwrap();
+        assert!(content(&mut document, 0).contains("100.00 0 0 50.00 72.00 720.00 cm"));
+
+        let template = DocumentTemplate::new("{{image missing}}").unwrap();
+        assert!(matches!(
+            template.render(&TemplateContext::new()),
+            Err(TemplateError::RenderError(_))
+        ));
+        let template = DocumentTemplate::new("{{> missing}}").unwrap();
+        assert!(template.render(&TemplateContext::new()).is_err());
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        assert_eq!(
+            paragraphs("One\ntwo\n\n\n  Three  \n"),
+            vec!["One two".to_string(), "Three".to_string()]
+        );
+    }
+}
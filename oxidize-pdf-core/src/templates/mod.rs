@@ -4,6 +4,30 @@
 //! It supports placeholders in the format `{{variable_name}}` and provides context management
 //! for template rendering.
 //!
+//! Templates may also have:
+//!
+//! - `{{#each items}}...{{else}}...{{/each}}` loops, with `{{this}}`, `{{@index}}`,
+//!   `{{@number}}`, `{{@first}}` and `{{@last}}` inside
+//! - `{{#if value}}...{{else}}...{{/if}}` and `{{#unless value}}...{{/unless}}` conditionals
+//! - filters such as `{{total | currency:"EUR"}}`, `{{count | number}}` and
+//!   `{{due | date:"%d %B %Y"}}`
+//! - partials registered by name and included with `{{> name}}`
+//!
+//! A [`DocumentTemplate`] renders to document pages, with text in paragraphs separated by
+//! blank lines and blocks for tables, images and page breaks:
+//!
+//! ```text
+//! {{#table lines}}
+//!   {{#column "Description" 300}}{{description}}{{/column}}
+//!   {{#column "Amount" 100 right}}{{amount | currency}}{{/column}}
+//! {{/table}}
+//! {{image logo 120}}
+//! {{pagebreak}}
+//! ```
+//!
+//! With the `templates-json` feature, contexts can be loaded from JSON with
+//! `TemplateContext::from_json`.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -24,14 +48,18 @@
 
 mod context;
 mod error;
+mod filters;
+mod layout;
 mod parser;
 mod renderer;
+mod syntax;
 
 #[cfg(test)]
 mod integration_test;
 
 pub use context::{TemplateContext, TemplateValue};
 pub use error::{TemplateError, TemplateResult};
+pub use layout::{DocumentTemplate, TemplateLayout};
 pub use parser::{Placeholder, TemplateParser};
 pub use renderer::{Template, TemplateRenderer};
 
@@ -1,113 +1,131 @@
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
 
-use super::context::TemplateContext;
+use super::context::{TemplateContext, TemplateValue};
 use super::error::{TemplateError, TemplateResult};
-use super::parser::{Placeholder, TemplateParser};
+use super::filters;
+use super::parser::Placeholder;
+use super::syntax::{self, Node};
+use crate::advanced_tables::CellAlignment;
 
 /// Template renderer that performs variable substitution
 pub struct TemplateRenderer {
-    parser: TemplateParser,
+    /// Parsed partial templates by name
+    partials: HashMap<String, Vec<Node>>,
+}
+
+/// Content of a rendered template
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Block {
+    Text(String),
+    Table(TableBlock),
+    Image {
+        name: String,
+        width: Option<f64>,
+        height: Option<f64>,
+    },
+    PageBreak,
+}
+
+/// A rendered table: its columns, as header, width and alignment, and the
+/// text of its cells
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TableBlock {
+    pub columns: Vec<(String, f64, CellAlignment)>,
+    pub rows: Vec<Vec<String>>,
 }
 
 impl TemplateRenderer {
     /// Create a new template renderer
     pub fn new() -> Self {
         Self {
-            parser: TemplateParser::new(),
+            partials: HashMap::new(),
         }
     }
 
+    /// Register a partial template, included with `{{> name}}`
+    pub fn register_partial(
+        &mut self,
+        name: impl Into<String>,
+        template: &str,
+    ) -> TemplateResult<&mut Self> {
+        self.partials.insert(name.into(), syntax::parse(template)?);
+        Ok(self)
+    }
+
     /// Render a template with the given context
+    ///
+    /// Besides variables, templates may have `{{#each}}` and `{{#if}}`
+    /// blocks, filters and partials; tables, images and page breaks need a
+    /// [`DocumentTemplate`](super::DocumentTemplate).
     pub fn render(&self, template: &str, context: &TemplateContext) -> TemplateResult<String> {
-        let placeholders = self.parser.parse(template)?;
-
-        if placeholders.is_empty() {
-            return Ok(template.to_string());
-        }
-
-        // Validate that all variables exist in context
-        self.validate_variables(&placeholders, context)?;
-
-        // Replace placeholders with values
-        self.substitute_variables(template, &placeholders, context)
+        let nodes = syntax::parse(template)?;
+        let blocks = self.render_blocks(&nodes, context)?;
+        text_of(blocks)
     }
 
-    /// Validate that all required variables exist in the context
-    fn validate_variables(
+    /// Render parsed template nodes into blocks of content
+    ///
+    /// Every variable that is missing is reported, sorted, in a single
+    /// [`TemplateError::VariableNotFound`].
+    pub(crate) fn render_blocks(
         &self,
-        placeholders: &[Placeholder],
+        nodes: &[Node],
         context: &TemplateContext,
-    ) -> TemplateResult<()> {
-        let mut missing_variables = Vec::new();
-
-        for placeholder in placeholders {
-            if !context.has(&placeholder.variable_name) {
-                missing_variables.push(placeholder.variable_name.clone());
-            }
-        }
-
-        if !missing_variables.is_empty() {
-            // Remove duplicates and sort for consistent error messages
-            let mut unique_missing: Vec<String> = missing_variables
-                .into_iter()
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect();
-            unique_missing.sort();
-
+    ) -> TemplateResult<Vec<Block>> {
+        let mut evaluation = Evaluation {
+            context,
+            partials: &self.partials,
+            frames: Vec::new(),
+            included: Vec::new(),
+            blocks: Vec::new(),
+            missing: BTreeSet::new(),
+        };
+        evaluation.render(nodes)?;
+        if !evaluation.missing.is_empty() {
+            let missing: Vec<String> = evaluation.missing.into_iter().collect();
             return Err(TemplateError::VariableNotFound(format!(
                 "Variables not found: [{}]",
-                unique_missing.join(", ")
+                missing.join(", ")
             )));
         }
-
-        Ok(())
+        Ok(evaluation.blocks)
     }
 
-    /// Substitute variables with their values
-    fn substitute_variables(
-        &self,
-        template: &str,
-        placeholders: &[Placeholder],
-        context: &TemplateContext,
-    ) -> TemplateResult<String> {
-        let mut result = template.to_string();
-
-        // Sort placeholders by start position in reverse order
-        // This ensures that we replace from right to left, preventing position shifts
-        let mut sorted_placeholders = placeholders.to_vec();
-        sorted_placeholders.sort_by(|a, b| b.start.cmp(&a.start));
-
-        for placeholder in sorted_placeholders {
-            let value = context.get_string(&placeholder.variable_name)?;
-
-            // Replace the placeholder with the value
-            result.replace_range(placeholder.start..placeholder.end, &value);
-        }
-
-        Ok(result)
-    }
-
-    /// Get all variable names required by a template
+    /// Get the context variables a template refers to, sorted
+    ///
+    /// These are the variables outside `{{#each}}` and `{{#table}}` bodies,
+    /// including the arrays of those blocks and the conditions of
+    /// `{{#if}}` blocks, and the variables of registered partials. Members
+    /// of the items in a loop body are not context variables.
     pub fn get_required_variables(&self, template: &str) -> TemplateResult<Vec<String>> {
-        self.parser.get_variable_names(template)
+        let nodes = syntax::parse(template)?;
+        let mut names = BTreeSet::new();
+        self.collect_variables(&nodes, &mut Vec::new(), &mut names);
+        Ok(names.into_iter().collect())
     }
 
-    /// Check if a template is valid (all placeholders have correct syntax)
+    /// Check if a template is valid (all tags have correct syntax)
     pub fn validate_template(&self, template: &str) -> TemplateResult<()> {
-        self.parser.parse(template)?;
+        syntax::parse(template)?;
         Ok(())
     }
 
     /// Check if template has any placeholders
     pub fn has_placeholders(&self, template: &str) -> bool {
-        self.parser.has_placeholders(template)
+        syntax::parse(template)
+            .map(|nodes| nodes.iter().any(|node| !matches!(node, Node::Text(_))))
+            .unwrap_or(false)
     }
 
     /// Get detailed information about placeholders in a template
     pub fn analyze_template(&self, template: &str) -> TemplateResult<TemplateAnalysis> {
-        let placeholders = self.parser.parse(template)?;
-        let variable_names = self.parser.get_variable_names(template)?;
+        let nodes = syntax::parse(template)?;
+        let mut placeholders = Vec::new();
+        collect_placeholders(template, &nodes, &mut placeholders);
+        let mut names = BTreeSet::new();
+        self.collect_variables(&nodes, &mut Vec::new(), &mut names);
+        let variable_names: Vec<String> = names.into_iter().collect();
 
         Ok(TemplateAnalysis {
             total_placeholders: placeholders.len(),
@@ -116,6 +134,302 @@ impl TemplateRenderer {
             placeholders,
         })
     }
+
+    /// Context variables of `nodes` outside loop bodies
+    fn collect_variables<'a>(
+        &'a self,
+        nodes: &'a [Node],
+        included: &mut Vec<&'a str>,
+        names: &mut BTreeSet<String>,
+    ) {
+        for node in nodes {
+            match node {
+                Node::Variable(expression) => add_variable(names, &expression.path),
+                Node::Each {
+                    path, otherwise, ..
+                } => {
+                    add_variable(names, path);
+                    self.collect_variables(otherwise, included, names);
+                }
+                Node::Table { path, .. } => add_variable(names, path),
+                Node::If {
+                    path,
+                    body,
+                    otherwise,
+                    ..
+                } => {
+                    add_variable(names, path);
+                    self.collect_variables(body, included, names);
+                    self.collect_variables(otherwise, included, names);
+                }
+                Node::Partial(name) => {
+                    if let Some(partial) = self.partials.get(name) {
+                        if !included.contains(&name.as_str()) {
+                            included.push(name);
+                            self.collect_variables(partial, included, names);
+                            included.pop();
+                        }
+                    }
+                }
+                Node::Text(_) | Node::Column(_) | Node::Image { .. } | Node::PageBreak => {}
+            }
+        }
+    }
+}
+
+/// Add a context variable, skipping loop variables and the current item
+fn add_variable(names: &mut BTreeSet<String>, path: &str) {
+    if !path.starts_with('@') && path != "this" && !path.starts_with("this.") {
+        names.insert(path.to_string());
+    }
+}
+
+/// Variable tags of `nodes`, loop bodies included, in source order
+fn collect_placeholders(source: &str, nodes: &[Node], placeholders: &mut Vec<Placeholder>) {
+    for node in nodes {
+        match node {
+            Node::Variable(expression) => placeholders.push(Placeholder::new(
+                source[expression.span.clone()].to_string(),
+                expression.path.clone(),
+                expression.span.start,
+                expression.span.end,
+            )),
+            Node::Each {
+                body, otherwise, ..
+            }
+            | Node::If {
+                body, otherwise, ..
+            } => {
+                collect_placeholders(source, body, placeholders);
+                collect_placeholders(source, otherwise, placeholders);
+            }
+            Node::Table { columns, .. } => {
+                for column in columns {
+                    collect_placeholders(source, &column.cell, placeholders);
+                }
+            }
+            Node::Column(column) => collect_placeholders(source, &column.cell, placeholders),
+            Node::Text(_) | Node::Partial(_) | Node::Image { .. } | Node::PageBreak => {}
+        }
+    }
+}
+
+/// Text of blocks that must all be text
+fn text_of(blocks: Vec<Block>) -> TemplateResult<String> {
+    let mut text = String::new();
+    for block in blocks {
+        match block {
+            Block::Text(part) => text.push_str(&part),
+            _ => {
+                return Err(TemplateError::RenderError(
+                    "Tables, images and page breaks need a document template".to_string(),
+                ))
+            }
+        }
+    }
+    Ok(text)
+}
+
+/// The item of an `{{#each}}` block or table row being rendered
+struct Frame<'a> {
+    value: &'a TemplateValue,
+    index: usize,
+    count: usize,
+}
+
+/// State of the rendering of a template
+struct Evaluation<'a> {
+    context: &'a TemplateContext,
+    partials: &'a HashMap<String, Vec<Node>>,
+    frames: Vec<Frame<'a>>,
+    /// Partials being rendered, to detect recursion
+    included: Vec<&'a str>,
+    blocks: Vec<Block>,
+    /// Variables that were not found
+    missing: BTreeSet<String>,
+}
+
+impl<'a> Evaluation<'a> {
+    fn render(&mut self, nodes: &'a [Node]) -> TemplateResult<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.push_text(text),
+                Node::Variable(expression) => {
+                    let has_default = expression
+                        .filters
+                        .iter()
+                        .any(|filter| filter.name == "default");
+                    let mut value = match self.lookup(&expression.path) {
+                        Some(value) => value.into_owned(),
+                        None if has_default => TemplateValue::Null,
+                        None => {
+                            self.missing.insert(expression.path.clone());
+                            continue;
+                        }
+                    };
+                    for filter in &expression.filters {
+                        value = filters::apply(value, filter)?;
+                    }
+                    self.push_text(&value.as_string());
+                }
+                Node::Each {
+                    path,
+                    body,
+                    otherwise,
+                } => {
+                    let items = self.items(path)?;
+                    if items.is_empty() {
+                        self.render(otherwise)?;
+                    }
+                    for (index, item) in items.iter().enumerate() {
+                        self.frames.push(Frame {
+                            value: item,
+                            index,
+                            count: items.len(),
+                        });
+                        let result = self.render(body);
+                        self.frames.pop();
+                        result?;
+                    }
+                }
+                Node::If {
+                    path,
+                    negated,
+                    body,
+                    otherwise,
+                } => {
+                    let truthy = self.lookup(path).is_some_and(|value| value.is_truthy());
+                    match truthy != *negated {
+                        true => self.render(body)?,
+                        false => self.render(otherwise)?,
+                    }
+                }
+                Node::Partial(name) => {
+                    let partial = self.partials.get(name).ok_or_else(|| {
+                        TemplateError::RenderError(format!("Partial '{name}' not found"))
+                    })?;
+                    if self.included.contains(&name.as_str()) {
+                        return Err(TemplateError::CircularReference(name.clone()));
+                    }
+                    self.included.push(name);
+                    let result = self.render(partial);
+                    self.included.pop();
+                    result?;
+                }
+                Node::Table { path, columns } => {
+                    let items = self.items(path)?;
+                    let mut rows = Vec::with_capacity(items.len());
+                    for (index, item) in items.iter().enumerate() {
+                        self.frames.push(Frame {
+                            value: item,
+                            index,
+                            count: items.len(),
+                        });
+                        let row = columns
+                            .iter()
+                            .map(|column| self.render_text(&column.cell))
+                            .collect::<TemplateResult<Vec<_>>>();
+                        self.frames.pop();
+                        rows.push(row?);
+                    }
+                    self.blocks.push(Block::Table(TableBlock {
+                        columns: columns
+                            .iter()
+                            .map(|column| (column.header.clone(), column.width, column.alignment))
+                            .collect(),
+                        rows,
+                    }));
+                }
+                Node::Column(_) => {
+                    return Err(TemplateError::RenderError(
+                        "Columns must be in a table".to_string(),
+                    ))
+                }
+                Node::Image {
+                    name,
+                    width,
+                    height,
+                } => self.blocks.push(Block::Image {
+                    name: name.clone(),
+                    width: *width,
+                    height: *height,
+                }),
+                Node::PageBreak => self.blocks.push(Block::PageBreak),
+            }
+        }
+        Ok(())
+    }
+
+    /// Render nodes that must be text, such as table cells
+    fn render_text(&mut self, nodes: &'a [Node]) -> TemplateResult<String> {
+        let blocks = std::mem::take(&mut self.blocks);
+        let result = self.render(nodes);
+        let rendered = std::mem::replace(&mut self.blocks, blocks);
+        result?;
+        Ok(text_of(rendered)?.trim().to_string())
+    }
+
+    fn push_text(&mut self, text: &str) {
+        match self.blocks.last_mut() {
+            Some(Block::Text(last)) => last.push_str(text),
+            _ => self.blocks.push(Block::Text(text.to_string())),
+        }
+    }
+
+    /// Items of an array variable; none if it is missing or null
+    fn items(&self, path: &str) -> TemplateResult<&'a [TemplateValue]> {
+        match self.lookup(path) {
+            Some(Cow::Borrowed(TemplateValue::Array(items))) => Ok(items),
+            None | Some(Cow::Borrowed(TemplateValue::Null)) => Ok(&[]),
+            Some(_) => Err(TemplateError::RenderError(format!(
+                "'{path}' is not an array"
+            ))),
+        }
+    }
+
+    /// Value of a variable: a loop variable, a member of the items being
+    /// rendered, innermost first, or a context variable
+    fn lookup(&self, path: &str) -> Option<Cow<'a, TemplateValue>> {
+        if let Some(name) = path.strip_prefix('@') {
+            let frame = self.frames.last()?;
+            let value = match name {
+                "index" => TemplateValue::Integer(frame.index as i64),
+                "number" => TemplateValue::Integer(frame.index as i64 + 1),
+                "first" => TemplateValue::Boolean(frame.index == 0),
+                "last" => TemplateValue::Boolean(frame.index + 1 == frame.count),
+                _ => return None,
+            };
+            return Some(Cow::Owned(value));
+        }
+        if path == "this" {
+            return self.frames.last().map(|frame| Cow::Borrowed(frame.value));
+        }
+        if let Some(rest) = path.strip_prefix("this.") {
+            return self
+                .frames
+                .last()?
+                .value
+                .get_nested(rest)
+                .map(Cow::Borrowed);
+        }
+
+        let (name, rest) = match path.split_once('.') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (path, None),
+        };
+        for frame in self.frames.iter().rev() {
+            if let TemplateValue::Object(map) = frame.value {
+                if let Some(value) = map.get(name) {
+                    return match rest {
+                        Some(rest) => value.get_nested(rest),
+                        None => Some(value),
+                    }
+                    .map(Cow::Borrowed);
+                }
+            }
+        }
+        self.context.get(path).ok().map(Cow::Borrowed)
+    }
 }
 
 /// Information about a template's structure
@@ -125,9 +439,10 @@ pub struct TemplateAnalysis {
     pub total_placeholders: usize,
     /// Number of unique variables
     pub unique_variables: usize,
-    /// List of all unique variable names
+    /// Context variables the template refers to, as returned by
+    /// [`TemplateRenderer::get_required_variables`]
     pub variable_names: Vec<String>,
-    /// All placeholders found in the template
+    /// All variable tags found in the template, loop bodies included
     pub placeholders: Vec<Placeholder>,
 }
 
@@ -330,4 +645,162 @@ Total: $1,320.00
 
         assert_eq!(result, expected);
     }
+
+    fn items_context() -> TemplateContext {
+        let mut context = TemplateContext::new();
+        let items = ["Apples", "Pears", "Plums"]
+            .iter()
+            .map(|name| {
+                let mut item = HashMap::new();
+                item.insert("name".to_string(), TemplateValue::from(*name));
+                item.insert("price".to_string(), TemplateValue::Number(2.5));
+                TemplateValue::Object(item)
+            })
+            .collect();
+        context.set_value("items", TemplateValue::Array(items));
+        context.set_value("tags", TemplateValue::Array(vec!["a".into(), "b".into()]));
+        context.set("currency", "EUR");
+        context.set_boolean("paid", false);
+        context
+    }
+
+    #[test]
+    fn test_each_blocks() {
+        let context = items_context();
+        let template = "{{#each items}}{{@number}}. {{name}} {{price | currency:\"EUR\"}}\
+                        {{#unless @last}}, {{/unless}}{{/each}}";
+        assert_eq!(
+            Template::render(template, &context).unwrap(),
+            "1. Apples €2.50, 2. Pears €2.50, 3. Plums €2.50"
+        );
+        assert_eq!(
+            Template::render("{{#each tags}}[{{this}}]{{/each}}", &context).unwrap(),
+            "[a][b]"
+        );
+        assert_eq!(
+            Template::render("{{#each missing}}x{{else}}None{{/each}}", &context).unwrap(),
+            "None"
+        );
+        assert!(Template::render("{{#each currency}}x{{/each}}", &context).is_err());
+    }
+
+    #[test]
+    fn test_if_blocks() {
+        let context = items_context();
+        assert_eq!(
+            Template::render("{{#if paid}}Paid{{else}}Due{{/if}}", &context).unwrap(),
+            "Due"
+        );
+        assert_eq!(
+            Template::render(
+                "{{#if items}}{{#each items}}{{#if @first}}{{name}}{{/if}}{{/each}}{{/if}}",
+                &context
+            )
+            .unwrap(),
+            "Apples"
+        );
+        assert_eq!(
+            Template::render(
+                "{{#if missing}}x{{/if}}{{missing | default:\"-\"}}",
+                &context
+            )
+            .unwrap(),
+            "-"
+        );
+        assert!(Template::render("{{#if paid}}x", &context).is_err());
+        assert!(Template::render("{{#if paid}}x{{/each}}", &context).is_err());
+    }
+
+    #[test]
+    fn test_partials() {
+        let context = items_context();
+        let mut renderer = TemplateRenderer::new();
+        renderer
+            .register_partial("item", "{{name}} ({{currency}})")
+            .unwrap();
+        assert_eq!(
+            renderer
+                .render("{{#each items}}{{> item}};{{/each}}", &context)
+                .unwrap(),
+            "Apples (EUR);Pears (EUR);Plums (EUR);"
+        );
+
+        renderer.register_partial("loop", "{{> loop}}").unwrap();
+        assert!(matches!(
+            renderer.render("{{> loop}}", &context),
+            Err(TemplateError::CircularReference(_))
+        ));
+        assert!(renderer.render("{{> missing}}", &context).is_err());
+    }
+
+    #[test]
+    fn test_analysis_of_blocks_and_filters() {
+        let mut renderer = TemplateRenderer::new();
+        renderer
+            .register_partial("footer", "{{company}} {{#each items}}{{sku}}{{/each}}")
+            .unwrap();
+        let template = "{{title}}{{#each items}}{{name}} {{this.price}}{{/each}}\
+                        {{total | currency}}{{#if paid}}{{paid_on | upper}}{{/if}}\
+                        {{#table lines}}{{#column \"Amount\" 80}}{{amount}}{{/column}}{{/table}}\
+                        {{> footer}}";
+
+        let variables = renderer.get_required_variables(template).unwrap();
+        assert_eq!(
+            variables,
+            vec!["company", "items", "lines", "paid", "paid_on", "title", "total"]
+        );
+
+        let analysis = renderer.analyze_template(template).unwrap();
+        assert_eq!(analysis.variable_names, variables);
+        assert_eq!(analysis.unique_variables, 7);
+        let tags: Vec<&str> = analysis
+            .placeholders
+            .iter()
+            .map(|placeholder| placeholder.full_text.as_str())
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                "{{title}}",
+                "{{name}}",
+                "{{this.price}}",
+                "{{total | currency}}",
+                "{{paid_on | upper}}",
+                "{{amount}}",
+            ]
+        );
+        assert_eq!(analysis.total_placeholders, 6);
+        let total = &analysis.placeholders[3];
+        assert_eq!(total.variable_name, "total");
+        assert_eq!(&template[total.start..total.end], "{{total | currency}}");
+
+        assert!(renderer.has_placeholders("{{#each items}}-{{/each}}"));
+        assert!(renderer.has_placeholders("{{pagebreak}}"));
+        assert!(!renderer.has_placeholders("Plain text"));
+        assert!(!renderer.has_placeholders("{{#each items}}"));
+    }
+
+    #[test]
+    fn test_all_missing_variables_are_reported() {
+        let context = items_context();
+        let result = Template::render(
+            "{{b}} {{a}} {{#each items}}{{name}}{{sku}}{{/each}} {{b}} {{note | default:\"-\"}}",
+            &context,
+        );
+        match result {
+            Err(TemplateError::VariableNotFound(message)) => {
+                assert_eq!(message, "Variables not found: [a, b, sku]")
+            }
+            other => panic!("expected missing variables, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_document_blocks_need_document_template() {
+        let context = items_context();
+        assert!(matches!(
+            Template::render("Page one{{pagebreak}}Page two", &context),
+            Err(TemplateError::RenderError(_))
+        ));
+    }
 }
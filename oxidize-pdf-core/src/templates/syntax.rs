@@ -0,0 +1,530 @@
+//! Template syntax: placeholders, blocks and layout tags
+//!
+//! Tags are enclosed in double braces:
+//!
+//! - `{{name}}`, `{{user.name}}`: a variable, optionally through filters,
+//!   as in `{{total | currency:"EUR"}}`
+//! - `{{#each items}}...{{else}}...{{/each}}`: the body for every item of
+//!   an array, with `{{this}}`, `{{@index}}`, `{{@number}}`, `{{@first}}`
+//!   and `{{@last}}`; members of object items are variables in the body
+//! - `{{#if name}}...{{else}}...{{/if}}` and `{{#unless name}}...{{/unless}}`
+//! - `{{> name}}`: a registered partial template
+//! - `{{#table items}}{{#column "Header" 120 right}}{{cell}}{{/column}}{{/table}}`:
+//!   a table with a row for every item of an array
+//! - `{{image name 120 40}}`: an image, with an optional width and height
+//! - `{{pagebreak}}`: a page break
+
+use super::error::{TemplateError, TemplateResult};
+use super::filters;
+use crate::advanced_tables::CellAlignment;
+use std::ops::Range;
+
+/// A parsed template element
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Text(String),
+    Variable(Expression),
+    Each {
+        path: String,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    If {
+        path: String,
+        negated: bool,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Partial(String),
+    Table {
+        path: String,
+        columns: Vec<TableColumn>,
+    },
+    Column(TableColumn),
+    Image {
+        name: String,
+        width: Option<f64>,
+        height: Option<f64>,
+    },
+    PageBreak,
+}
+
+/// A variable and the filters its value goes through
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Expression {
+    pub path: String,
+    pub filters: Vec<Filter>,
+    /// Position of the tag, braces included, in the source
+    pub span: Range<usize>,
+}
+
+/// A filter and its arguments, as in `number:2`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Filter {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// A table column: its header, width, alignment and cell template
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TableColumn {
+    pub header: String,
+    pub width: f64,
+    pub alignment: CellAlignment,
+    pub cell: Vec<Node>,
+}
+
+enum Token {
+    Text(String),
+    Tag(String, Range<usize>),
+}
+
+/// How a sequence of nodes ended
+#[derive(PartialEq)]
+enum End {
+    Source,
+    Else,
+    Close,
+}
+
+/// Parse a template
+pub(crate) fn parse(source: &str) -> TemplateResult<Vec<Node>> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    let (nodes, _) = parser.parse_until(None)?;
+    Ok(nodes)
+}
+
+fn tokenize(source: &str) -> TemplateResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = source[offset..].find("{{").map(|index| offset + index) {
+        push_text(&mut tokens, &source[offset..start], offset)?;
+        let inner = start + 2;
+        let close = source[inner..]
+            .find("}}")
+            .map(|index| inner + index)
+            .ok_or_else(|| {
+                TemplateError::InvalidPlaceholder(format!(
+                    "Unclosed placeholder at position {start}"
+                ))
+            })?;
+        let content = &source[inner..close];
+        if content.starts_with('{') || source[close + 2..].starts_with('}') {
+            return Err(TemplateError::InvalidPlaceholder(format!(
+                "Malformed placeholder at position {start}: '{}' - use exactly two braces",
+                &source[start..close + 2]
+            )));
+        }
+        if content.trim().is_empty() {
+            return Err(TemplateError::InvalidPlaceholder(format!(
+                "Empty placeholder found at position {start}: '{}'",
+                &source[start..close + 2]
+            )));
+        }
+        tokens.push(Token::Tag(content.trim().to_string(), start..close + 2));
+        offset = close + 2;
+    }
+    push_text(&mut tokens, &source[offset..], offset)?;
+    Ok(tokens)
+}
+
+fn push_text(tokens: &mut Vec<Token>, text: &str, offset: usize) -> TemplateResult<()> {
+    if let Some(index) = text.find(['{', '}']) {
+        return Err(TemplateError::InvalidPlaceholder(format!(
+            "Found single brace near position {}: '{}' - did you mean to use double braces {{{{}}}}?",
+            offset + index,
+            &text[index..index + 1]
+        )));
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text.to_string()));
+    }
+    Ok(())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    /// Nodes up to the end of the source, or the `{{else}}` or closing tag
+    /// of `block`
+    fn parse_until(&mut self, block: Option<&str>) -> TemplateResult<(Vec<Node>, End)> {
+        let mut nodes = Vec::new();
+        while let Some(token) = self.tokens.get(self.position) {
+            self.position += 1;
+            let (tag, span) = match token {
+                Token::Text(text) => {
+                    nodes.push(Node::Text(text.clone()));
+                    continue;
+                }
+                Token::Tag(tag, span) => (tag.clone(), span.clone()),
+            };
+
+            if let Some(name) = tag.strip_prefix('/') {
+                return match block {
+                    Some(block) if block == name.trim() => Ok((nodes, End::Close)),
+                    _ => Err(TemplateError::ParseError(format!(
+                        "Unexpected closing tag '{tag}'"
+                    ))),
+                };
+            }
+            if tag == "else" {
+                return match block {
+                    Some("each" | "if" | "unless") => Ok((nodes, End::Else)),
+                    _ => Err(TemplateError::ParseError(
+                        "Unexpected '{{else}}'".to_string(),
+                    )),
+                };
+            }
+            if let Some(block_tag) = tag.strip_prefix('#') {
+                nodes.push(self.parse_block(block_tag, block)?);
+            } else if let Some(name) = tag.strip_prefix('>') {
+                nodes.push(Node::Partial(name.trim().to_string()));
+            } else {
+                nodes.push(parse_tag(&tag, span)?);
+            }
+        }
+
+        match block {
+            Some(block) => Err(TemplateError::ParseError(format!(
+                "Unclosed block '#{block}'"
+            ))),
+            None => Ok((nodes, End::Source)),
+        }
+    }
+
+    fn parse_block(&mut self, tag: &str, parent: Option<&str>) -> TemplateResult<Node> {
+        let words = words(tag)?;
+        let (kind, args) = words
+            .split_first()
+            .ok_or_else(|| TemplateError::ParseError(format!("Invalid block '#{tag}'")))?;
+        let path = || -> TemplateResult<String> {
+            match args {
+                [path] => {
+                    validate_path(path)?;
+                    Ok(path.clone())
+                }
+                _ => Err(TemplateError::ParseError(format!(
+                    "Block '#{kind}' takes one variable"
+                ))),
+            }
+        };
+
+        match kind.as_str() {
+            "each" | "if" | "unless" => {
+                let path = path()?;
+                let (body, end) = self.parse_until(Some(kind))?;
+                let otherwise = match end {
+                    End::Else => {
+                        let (otherwise, end) = self.parse_until(Some(kind))?;
+                        if end != End::Close {
+                            return Err(TemplateError::ParseError(format!(
+                                "Second '{{{{else}}}}' in block '#{kind}'"
+                            )));
+                        }
+                        otherwise
+                    }
+                    _ => Vec::new(),
+                };
+                Ok(match kind.as_str() {
+                    "each" => Node::Each {
+                        path,
+                        body,
+                        otherwise,
+                    },
+                    _ => Node::If {
+                        path,
+                        negated: kind == "unless",
+                        body,
+                        otherwise,
+                    },
+                })
+            }
+            "table" => {
+                let path = path()?;
+                let (body, _) = self.parse_until(Some("table"))?;
+                let mut columns = Vec::new();
+                for node in body {
+                    match node {
+                        Node::Column(column) => columns.push(column),
+                        Node::Text(text) if text.trim().is_empty() => {}
+                        _ => {
+                            return Err(TemplateError::ParseError(
+                                "Tables may only contain '#column' blocks".to_string(),
+                            ))
+                        }
+                    }
+                }
+                Ok(Node::Table { path, columns })
+            }
+            "column" if parent == Some("table") => {
+                let (header, width, alignment) = match args {
+                    [header, width] => (header, width, CellAlignment::Left),
+                    [header, width, alignment] => (header, width, parse_alignment(alignment)?),
+                    _ => {
+                        return Err(TemplateError::ParseError(
+                            "Columns take a header, a width and an optional alignment".to_string(),
+                        ))
+                    }
+                };
+                let (cell, _) = self.parse_until(Some("column"))?;
+                Ok(Node::Column(TableColumn {
+                    header: header.clone(),
+                    width: parse_number(width)?,
+                    alignment,
+                    cell,
+                }))
+            }
+            "column" => Err(TemplateError::ParseError(
+                "Columns must be in a '#table' block".to_string(),
+            )),
+            _ => Err(TemplateError::ParseError(format!(
+                "Unknown block '#{kind}'"
+            ))),
+        }
+    }
+}
+
+/// Parse a variable or a layout tag
+fn parse_tag(tag: &str, span: Range<usize>) -> TemplateResult<Node> {
+    let words = words(tag)?;
+    match words.as_slice() {
+        [keyword] if keyword == "pagebreak" => return Ok(Node::PageBreak),
+        [keyword, name, size @ ..] if keyword == "image" && size.len() <= 2 => {
+            return Ok(Node::Image {
+                name: name.clone(),
+                width: size.first().map(|width| parse_number(width)).transpose()?,
+                height: size.get(1).map(|height| parse_number(height)).transpose()?,
+            });
+        }
+        _ => {}
+    }
+
+    let mut parts = split_outside_quotes(tag, '|').into_iter();
+    let path = parts.next().unwrap_or_default().trim().to_string();
+    validate_path(&path)?;
+    let filters = parts.map(parse_filter).collect::<TemplateResult<_>>()?;
+    Ok(Node::Variable(Expression {
+        path,
+        filters,
+        span,
+    }))
+}
+
+fn parse_filter(filter: &str) -> TemplateResult<Filter> {
+    let (name, args) = match filter.split_once(':') {
+        Some((name, args)) => (
+            name.trim(),
+            split_outside_quotes(args, ',')
+                .into_iter()
+                .map(|arg| unquote(arg.trim()))
+                .collect(),
+        ),
+        None => (filter.trim(), Vec::new()),
+    };
+    if !filters::is_filter(name) {
+        return Err(TemplateError::ParseError(format!(
+            "Unknown filter '{name}'"
+        )));
+    }
+    Ok(Filter {
+        name: name.to_string(),
+        args,
+    })
+}
+
+/// Check a variable path: a name with dots, `this` or a loop variable
+fn validate_path(path: &str) -> TemplateResult<()> {
+    if matches!(path, "@index" | "@number" | "@first" | "@last") {
+        return Ok(());
+    }
+    let valid = path
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+        && path
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '.');
+    match valid {
+        true => Ok(()),
+        false => Err(TemplateError::InvalidVariableName(path.to_string())),
+    }
+}
+
+fn parse_number(text: &str) -> TemplateResult<f64> {
+    text.parse()
+        .map_err(|_| TemplateError::ParseError(format!("Invalid number '{text}'")))
+}
+
+fn parse_alignment(text: &str) -> TemplateResult<CellAlignment> {
+    match text {
+        "left" => Ok(CellAlignment::Left),
+        "center" => Ok(CellAlignment::Center),
+        "right" => Ok(CellAlignment::Right),
+        _ => Err(TemplateError::ParseError(format!(
+            "Invalid alignment '{text}'"
+        ))),
+    }
+}
+
+/// Whitespace separated words of a tag, without the quotes of quoted ones
+fn words(tag: &str) -> TemplateResult<Vec<String>> {
+    if tag.matches('"').count() % 2 == 1 {
+        return Err(TemplateError::ParseError(format!(
+            "Unterminated string in '{tag}'"
+        )));
+    }
+    let mut words = Vec::new();
+    for (index, part) in tag.split('"').enumerate() {
+        if index % 2 == 1 {
+            words.push(part.to_string());
+        } else {
+            words.extend(part.split_whitespace().map(str::to_string));
+        }
+    }
+    Ok(words)
+}
+
+/// Parts of `text` between separators that are not in quotes
+fn split_outside_quotes(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            _ if ch == separator && !quoted => {
+                parts.push(&text[start..index]);
+                start = index + ch.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn unquote(text: &str) -> String {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(path: &str, span: Range<usize>) -> Node {
+        Node::Variable(Expression {
+            path: path.to_string(),
+            filters: Vec::new(),
+            span,
+        })
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let nodes =
+            parse("{{#each items}}{{name}}{{else}}None{{/each}}{{#unless paid}}Due{{/unless}}")
+                .unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                Node::Each {
+                    path: "items".to_string(),
+                    body: vec![variable("name", 15..23)],
+                    otherwise: vec![Node::Text("None".to_string())],
+                },
+                Node::If {
+                    path: "paid".to_string(),
+                    negated: true,
+                    body: vec![Node::Text("Due".to_string())],
+                    otherwise: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_filters_and_layout_tags() {
+        let nodes =
+            parse(r#"{{ total | currency:"EUR" | default:"a, b" }}{{> footer}}{{image logo 120}}{{pagebreak}}"#)
+                .unwrap();
+        assert_eq!(
+            nodes[0],
+            Node::Variable(Expression {
+                path: "total".to_string(),
+                filters: vec![
+                    Filter {
+                        name: "currency".to_string(),
+                        args: vec!["EUR".to_string()],
+                    },
+                    Filter {
+                        name: "default".to_string(),
+                        args: vec!["a, b".to_string()],
+                    },
+                ],
+                span: 0..45,
+            })
+        );
+        assert_eq!(nodes[1], Node::Partial("footer".to_string()));
+        assert_eq!(
+            nodes[2],
+            Node::Image {
+                name: "logo".to_string(),
+                width: Some(120.0),
+                height: None,
+            }
+        );
+        assert_eq!(nodes[3], Node::PageBreak);
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let nodes = parse(
+            "{{#table lines}}\n  {{#column \"Amount\" 80 right}}{{amount | number:2}}{{/column}}\n{{/table}}",
+        )
+        .unwrap();
+        let Node::Table { path, columns } = &nodes[0] else {
+            panic!("not a table: {nodes:?}");
+        };
+        assert_eq!(path, "lines");
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].header, "Amount");
+        assert_eq!(columns[0].width, 80.0);
+        assert_eq!(columns[0].alignment, CellAlignment::Right);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for source in [
+            "{{#each items}}",
+            "{{/if}}",
+            "{{else}}",
+            "{{#each a b}}{{/each}}",
+            "{{#column \"A\" 10}}{{/column}}",
+            "{{#table rows}}text{{/table}}",
+            "{{name | shout}}",
+            "{{#loop items}}{{/loop}}",
+        ] {
+            assert!(
+                matches!(parse(source), Err(TemplateError::ParseError(_))),
+                "{source}"
+            );
+        }
+        assert!(matches!(
+            parse("{{name"),
+            Err(TemplateError::InvalidPlaceholder(_))
+        ));
+        assert!(matches!(
+            parse("{{1st}}"),
+            Err(TemplateError::InvalidVariableName(_))
+        ));
+    }
+}
@@ -0,0 +1,913 @@
+//! Unicode Bidirectional Algorithm (UAX #9)
+//!
+//! Text is kept in logical order, the order it is read in, but a PDF shows
+//! glyphs from left to right: runs of Hebrew or Arabic, and the numbers
+//! within them, have to be put in visual order before they are shown. The
+//! embedding levels of a line are resolved with the rules for explicit
+//! embeddings, overrides and isolates and the weak, neutral and implicit
+//! rules; bracket pairs (N0) are resolved like other neutrals.
+//!
+//! Shaped text is reordered run by run with [`visual_runs`], text in fonts
+//! that are not shaped with [`reorder`]. Reordered text is shown in an
+//! `ActualText` span, so that it is extracted in logical order.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Deepest embedding level (BD2)
+const MAX_DEPTH: u8 = 125;
+
+/// Direction of a paragraph or of a run of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+impl Direction {
+    /// Embedding level of a paragraph in this direction
+    fn level(self) -> u8 {
+        match self {
+            Direction::LeftToRight => 0,
+            Direction::RightToLeft => 1,
+        }
+    }
+}
+
+/// A run of text at one embedding level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiRun {
+    /// Byte range of the run in the text
+    pub range: Range<usize>,
+    /// Embedding level; odd levels are right to left
+    pub level: u8,
+}
+
+impl BidiRun {
+    /// Direction the text of the run is shown in
+    pub fn direction(&self) -> Direction {
+        if self.level % 2 == 1 {
+            Direction::RightToLeft
+        } else {
+            Direction::LeftToRight
+        }
+    }
+}
+
+/// Bidirectional character types, named as in UAX #9
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BidiClass {
+    L,
+    R,
+    AL,
+    EN,
+    ES,
+    ET,
+    AN,
+    CS,
+    NSM,
+    BN,
+    B,
+    S,
+    WS,
+    ON,
+    LRE,
+    LRO,
+    RLE,
+    RLO,
+    PDF,
+    LRI,
+    RLI,
+    FSI,
+    PDI,
+}
+
+use BidiClass::*;
+
+/// Code point ranges of the classes other than `L`, sorted
+#[rustfmt::skip]
+const CLASSES: &[(u32, u32, BidiClass)] = &[
+    (0x0000, 0x0008, BN),
+    (0x0009, 0x0009, S),
+    (0x000A, 0x000A, B),
+    (0x000B, 0x000B, S),
+    (0x000C, 0x000C, WS),
+    (0x000D, 0x000D, B),
+    (0x000E, 0x001B, BN),
+    (0x001C, 0x001E, B),
+    (0x001F, 0x001F, S),
+    (0x0020, 0x0020, WS),
+    (0x0021, 0x0022, ON),
+    (0x0023, 0x0025, ET),
+    (0x0026, 0x002A, ON),
+    (0x002B, 0x002B, ES),
+    (0x002C, 0x002C, CS),
+    (0x002D, 0x002D, ES),
+    (0x002E, 0x002F, CS),
+    (0x0030, 0x0039, EN),
+    (0x003A, 0x003A, CS),
+    (0x003B, 0x0040, ON),
+    (0x005B, 0x0060, ON),
+    (0x007B, 0x007E, ON),
+    (0x007F, 0x0084, BN),
+    (0x0085, 0x0085, B),
+    (0x0086, 0x009F, BN),
+    (0x00A0, 0x00A0, CS),
+    (0x00A1, 0x00A1, ON),
+    (0x00A2, 0x00A5, ET),
+    (0x00A6, 0x00A9, ON),
+    (0x00AB, 0x00AC, ON),
+    (0x00AD, 0x00AD, BN),
+    (0x00AE, 0x00AF, ON),
+    (0x00B0, 0x00B1, ET),
+    (0x00B2, 0x00B3, EN),
+    (0x00B4, 0x00B4, ON),
+    (0x00B6, 0x00B8, ON),
+    (0x00B9, 0x00B9, EN),
+    (0x00BB, 0x00BF, ON),
+    (0x00D7, 0x00D7, ON),
+    (0x00F7, 0x00F7, ON),
+    (0x0300, 0x036F, NSM),
+    (0x0483, 0x0489, NSM),
+    (0x0590, 0x0590, R),
+    (0x0591, 0x05BD, NSM),
+    (0x05BE, 0x05BE, R),
+    (0x05BF, 0x05BF, NSM),
+    (0x05C0, 0x05C0, R),
+    (0x05C1, 0x05C2, NSM),
+    (0x05C3, 0x05C3, R),
+    (0x05C4, 0x05C5, NSM),
+    (0x05C6, 0x05C6, R),
+    (0x05C7, 0x05C7, NSM),
+    (0x05C8, 0x05FF, R),
+    (0x0600, 0x0605, AN),
+    (0x0606, 0x0608, AL),
+    (0x0609, 0x060A, ET),
+    (0x060B, 0x060B, AL),
+    (0x060C, 0x060C, CS),
+    (0x060D, 0x060F, AL),
+    (0x0610, 0x061A, NSM),
+    (0x061B, 0x064A, AL),
+    (0x064B, 0x065F, NSM),
+    (0x0660, 0x0669, AN),
+    (0x066A, 0x066A, ET),
+    (0x066B, 0x066C, AN),
+    (0x066D, 0x066F, AL),
+    (0x0670, 0x0670, NSM),
+    (0x0671, 0x06D5, AL),
+    (0x06D6, 0x06DC, NSM),
+    (0x06DD, 0x06DD, AN),
+    (0x06DE, 0x06DE, AL),
+    (0x06DF, 0x06E4, NSM),
+    (0x06E5, 0x06E6, AL),
+    (0x06E7, 0x06E8, NSM),
+    (0x06E9, 0x06E9, AL),
+    (0x06EA, 0x06ED, NSM),
+    (0x06EE, 0x06EF, AL),
+    (0x06F0, 0x06F9, EN),
+    (0x06FA, 0x0710, AL),
+    (0x0711, 0x0711, NSM),
+    (0x0712, 0x072F, AL),
+    (0x0730, 0x074A, NSM),
+    (0x074B, 0x07A5, AL),
+    (0x07A6, 0x07B0, NSM),
+    (0x07B1, 0x07BF, AL),
+    (0x07C0, 0x07EA, R),
+    (0x07EB, 0x07F3, NSM),
+    (0x07F4, 0x085F, R),
+    (0x0860, 0x088F, AL),
+    (0x0890, 0x0891, AN),
+    (0x0892, 0x08D2, AL),
+    (0x08D3, 0x08E1, NSM),
+    (0x08E2, 0x08E2, AN),
+    (0x08E3, 0x0902, NSM),
+    (0x093A, 0x093A, NSM),
+    (0x093C, 0x093C, NSM),
+    (0x0941, 0x0948, NSM),
+    (0x094D, 0x094D, NSM),
+    (0x0951, 0x0957, NSM),
+    (0x0962, 0x0963, NSM),
+    (0x0E31, 0x0E31, NSM),
+    (0x0E34, 0x0E3A, NSM),
+    (0x0E3F, 0x0E3F, ET),
+    (0x0E47, 0x0E4E, NSM),
+    (0x1680, 0x1680, WS),
+    (0x180E, 0x180E, BN),
+    (0x1AB0, 0x1AFF, NSM),
+    (0x1DC0, 0x1DFF, NSM),
+    (0x2000, 0x200A, WS),
+    (0x200B, 0x200D, BN),
+    (0x200F, 0x200F, R),
+    (0x2010, 0x2027, ON),
+    (0x2028, 0x2028, WS),
+    (0x2029, 0x2029, B),
+    (0x202A, 0x202A, LRE),
+    (0x202B, 0x202B, RLE),
+    (0x202C, 0x202C, PDF),
+    (0x202D, 0x202D, LRO),
+    (0x202E, 0x202E, RLO),
+    (0x202F, 0x202F, CS),
+    (0x2030, 0x2034, ET),
+    (0x2035, 0x2043, ON),
+    (0x2044, 0x2044, CS),
+    (0x2045, 0x205E, ON),
+    (0x205F, 0x205F, WS),
+    (0x2060, 0x2064, BN),
+    (0x2066, 0x2066, LRI),
+    (0x2067, 0x2067, RLI),
+    (0x2068, 0x2068, FSI),
+    (0x2069, 0x2069, PDI),
+    (0x206A, 0x206F, BN),
+    (0x2070, 0x2070, EN),
+    (0x2074, 0x2079, EN),
+    (0x207A, 0x207B, ES),
+    (0x207C, 0x207E, ON),
+    (0x2080, 0x2089, EN),
+    (0x208A, 0x208B, ES),
+    (0x208C, 0x208E, ON),
+    (0x20A0, 0x20CF, ET),
+    (0x20D0, 0x20F0, NSM),
+    (0x212E, 0x212E, ET),
+    (0x2190, 0x2211, ON),
+    (0x2212, 0x2212, ES),
+    (0x2213, 0x2213, ET),
+    (0x2214, 0x2BFF, ON),
+    (0x3000, 0x3000, WS),
+    (0x3001, 0x3004, ON),
+    (0x3008, 0x3020, ON),
+    (0xFB1D, 0xFB1D, R),
+    (0xFB1E, 0xFB1E, NSM),
+    (0xFB1F, 0xFB28, R),
+    (0xFB29, 0xFB29, ES),
+    (0xFB2A, 0xFB4F, R),
+    (0xFB50, 0xFDCF, AL),
+    (0xFDF0, 0xFDFF, AL),
+    (0xFE00, 0xFE0F, NSM),
+    (0xFE20, 0xFE2F, NSM),
+    (0xFE30, 0xFE4F, ON),
+    (0xFE50, 0xFE50, CS),
+    (0xFE51, 0xFE51, ON),
+    (0xFE52, 0xFE52, CS),
+    (0xFE54, 0xFE54, ON),
+    (0xFE55, 0xFE55, CS),
+    (0xFE56, 0xFE5E, ON),
+    (0xFE5F, 0xFE5F, ET),
+    (0xFE60, 0xFE61, ON),
+    (0xFE62, 0xFE63, ES),
+    (0xFE64, 0xFE66, ON),
+    (0xFE68, 0xFE68, ON),
+    (0xFE69, 0xFE6A, ET),
+    (0xFE6B, 0xFE6B, ON),
+    (0xFE70, 0xFEFE, AL),
+    (0xFEFF, 0xFEFF, BN),
+    (0xFF01, 0xFF02, ON),
+    (0xFF03, 0xFF05, ET),
+    (0xFF06, 0xFF0A, ON),
+    (0xFF0B, 0xFF0B, ES),
+    (0xFF0C, 0xFF0C, CS),
+    (0xFF0D, 0xFF0D, ES),
+    (0xFF0E, 0xFF0F, CS),
+    (0xFF10, 0xFF19, EN),
+    (0xFF1A, 0xFF1A, CS),
+    (0xFF1B, 0xFF20, ON),
+    (0xFF3B, 0xFF40, ON),
+    (0xFF5B, 0xFF65, ON),
+    (0xFFE0, 0xFFE1, ET),
+    (0xFFE5, 0xFFE6, ET),
+    (0x10800, 0x10FFF, R),
+    (0x1E800, 0x1EDFF, R),
+    (0x1EE00, 0x1EEFF, AL),
+    (0x1EF00, 0x1EFFF, R),
+];
+
+/// Characters and their mirror images (L4)
+const MIRRORED: &[(char, char)] = &[
+    ('(', ')'),
+    ('<', '>'),
+    ('[', ']'),
+    ('{', '}'),
+    ('\u{AB}', '\u{BB}'),
+    ('\u{2039}', '\u{203A}'),
+    ('\u{2045}', '\u{2046}'),
+    ('\u{207D}', '\u{207E}'),
+    ('\u{208D}', '\u{208E}'),
+    ('\u{2264}', '\u{2265}'),
+    ('\u{226A}', '\u{226B}'),
+    ('\u{27E8}', '\u{27E9}'),
+    ('\u{3008}', '\u{3009}'),
+    ('\u{300A}', '\u{300B}'),
+    ('\u{300C}', '\u{300D}'),
+    ('\u{300E}', '\u{300F}'),
+    ('\u{3010}', '\u{3011}'),
+    ('\u{3014}', '\u{3015}'),
+    ('\u{FF08}', '\u{FF09}'),
+    ('\u{FF1C}', '\u{FF1E}'),
+    ('\u{FF3B}', '\u{FF3D}'),
+    ('\u{FF5B}', '\u{FF5D}'),
+];
+
+fn class_of(ch: char) -> BidiClass {
+    let code = ch as u32;
+    CLASSES
+        .binary_search_by(|&(start, end, _)| {
+            if end < code {
+                std::cmp::Ordering::Less
+            } else if start > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .map_or(L, |index| CLASSES[index].2)
+}
+
+/// Whether the character is a nonspacing mark, shown with the character
+/// before it
+pub(crate) fn is_mark(ch: char) -> bool {
+    class_of(ch) == NSM
+}
+
+/// The mirror image of a bracket or other paired character
+pub(crate) fn mirror(ch: char) -> Option<char> {
+    MIRRORED.iter().find_map(|&(open, close)| {
+        if ch == open {
+            Some(close)
+        } else if ch == close {
+            Some(open)
+        } else {
+            None
+        }
+    })
+}
+
+/// Direction of the first strong character of the text, outside isolates
+/// (P2, P3)
+pub fn paragraph_direction(text: &str) -> Option<Direction> {
+    let classes: Vec<BidiClass> = text.chars().map(class_of).collect();
+    first_strong(&classes)
+}
+
+fn first_strong(classes: &[BidiClass]) -> Option<Direction> {
+    let mut isolates = 0usize;
+    for &class in classes {
+        match class {
+            LRI | RLI | FSI => isolates += 1,
+            PDI => isolates = isolates.saturating_sub(1),
+            L if isolates == 0 => return Some(Direction::LeftToRight),
+            R | AL if isolates == 0 => return Some(Direction::RightToLeft),
+            B => break,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether showing the text needs reordering: it has right-to-left or
+/// Arabic number characters, or the paragraph is right to left
+fn needs_reordering(classes: &[BidiClass], base: Option<Direction>) -> bool {
+    base == Some(Direction::RightToLeft)
+        || classes
+            .iter()
+            .any(|class| matches!(class, R | AL | AN | RLE | RLO | RLI))
+}
+
+/// Runs of the line at one embedding level, in visual order. The text of
+/// a right-to-left run is still in logical order; it is shown reversed.
+/// `base` is the paragraph direction, taken from the text when `None`.
+pub fn visual_runs(text: &str, base: Option<Direction>) -> Vec<BidiRun> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let classes: Vec<BidiClass> = chars.iter().map(|&(_, ch)| class_of(ch)).collect();
+    if !needs_reordering(&classes, base) {
+        return vec![BidiRun {
+            range: 0..text.len(),
+            level: 0,
+        }];
+    }
+
+    let levels = resolve_levels(&classes, base);
+    let mut runs: Vec<BidiRun> = Vec::new();
+    for (&(index, ch), &level) in chars.iter().zip(&levels) {
+        let end = index + ch.len_utf8();
+        match runs.last_mut() {
+            Some(run) if run.level == level => run.range.end = end,
+            _ => runs.push(BidiRun {
+                range: index..end,
+                level,
+            }),
+        }
+    }
+    reorder_visually(&mut runs, |run| run.level);
+    runs
+}
+
+/// The line in visual order, with the characters of right-to-left runs
+/// mirrored; nonspacing marks stay after the character they belong to.
+/// `base` is the paragraph direction, taken from the text when `None`.
+pub fn reorder(text: &str, base: Option<Direction>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let classes: Vec<BidiClass> = chars.iter().map(|&ch| class_of(ch)).collect();
+    if !needs_reordering(&classes, base) {
+        return text.to_string();
+    }
+
+    let levels = resolve_levels(&classes, base);
+    let mut clusters: Vec<(Range<usize>, u8)> = Vec::new();
+    for (index, &class) in classes.iter().enumerate() {
+        match clusters.last_mut() {
+            Some(cluster) if class == NSM => cluster.0.end = index + 1,
+            _ => clusters.push((index..index + 1, levels[index])),
+        }
+    }
+    reorder_visually(&mut clusters, |cluster| cluster.1);
+
+    let mut visual = String::with_capacity(text.len());
+    for (range, level) in clusters {
+        for &ch in &chars[range] {
+            visual.push(match level % 2 {
+                1 => mirror(ch).unwrap_or(ch),
+                _ => ch,
+            });
+        }
+    }
+    visual
+}
+
+/// Text showing operators in a span whose `ActualText` is the text in
+/// logical order
+pub(crate) fn actual_text_span(text: &str, operations: &str) -> String {
+    let mut span = String::from("/Span <</ActualText <FEFF");
+    for unit in text.encode_utf16() {
+        write!(span, "{unit:04X}").expect("Writing to String should never fail");
+    }
+    span.push_str(">>> BDC\n");
+    span.push_str(operations);
+    span.push_str("EMC\n");
+    span
+}
+
+/// Reverse every sequence of items at or above each level, from the
+/// highest level down to the lowest odd level (L2)
+fn reorder_visually<T>(items: &mut [T], level: impl Fn(&T) -> u8) {
+    let highest = items.iter().map(&level).max().unwrap_or(0);
+    let Some(lowest_odd) = items.iter().map(&level).filter(|l| l % 2 == 1).min() else {
+        return;
+    };
+    for current in (lowest_odd..=highest).rev() {
+        let mut index = 0;
+        while index < items.len() {
+            if level(&items[index]) < current {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < items.len() && level(&items[index]) >= current {
+                index += 1;
+            }
+            items[start..index].reverse();
+        }
+    }
+}
+
+fn is_removed(class: BidiClass) -> bool {
+    matches!(class, RLE | LRE | RLO | LRO | PDF | BN)
+}
+
+fn is_isolate_initiator(class: BidiClass) -> bool {
+    matches!(class, LRI | RLI | FSI)
+}
+
+/// Embedding levels of the characters of a line
+fn resolve_levels(original: &[BidiClass], base: Option<Direction>) -> Vec<u8> {
+    let paragraph = base
+        .or_else(|| first_strong(original))
+        .unwrap_or_default()
+        .level();
+    let matching = matching_pdis(original);
+    let mut classes = original.to_vec();
+    let mut levels = explicit_levels(original, &mut classes, paragraph, &matching);
+
+    // Level runs of the characters that are not removed (X9, X10)
+    let mut runs: Vec<Vec<usize>> = Vec::new();
+    for index in (0..original.len()).filter(|&i| !is_removed(original[i])) {
+        match runs.last_mut() {
+            Some(run) if levels[run[0]] == levels[index] => run.push(index),
+            _ => runs.push(vec![index]),
+        }
+    }
+
+    // Isolating run sequences: a run ending with an isolate initiator goes
+    // on with the run starting with its matching PDI
+    let mut appended = vec![false; runs.len()];
+    for first in 0..runs.len() {
+        if appended[first] {
+            continue;
+        }
+        let mut sequence = Vec::new();
+        let mut current = first;
+        loop {
+            appended[current] = true;
+            sequence.extend_from_slice(&runs[current]);
+            let last = runs[current][runs[current].len() - 1];
+            let next = matching[last]
+                .and_then(|pdi| (current + 1..runs.len()).find(|&r| runs[r][0] == pdi));
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+
+        let first_char = sequence[0];
+        let last_char = sequence[sequence.len() - 1];
+        let level = levels[first_char];
+        let kept_level = |index: Option<usize>| index.map_or(paragraph, |i| levels[i]);
+        let before = kept_level((0..first_char).rev().find(|&i| !is_removed(original[i])));
+        let after = if is_isolate_initiator(original[last_char]) {
+            paragraph
+        } else {
+            kept_level((last_char + 1..original.len()).find(|&i| !is_removed(original[i])))
+        };
+        let boundary = |other: u8| if level.max(other) % 2 == 1 { R } else { L };
+        resolve_sequence(
+            &sequence,
+            original,
+            &classes,
+            &mut levels,
+            boundary(before),
+            boundary(after),
+        );
+    }
+
+    // Removed characters take the level of the character before them
+    for index in 0..original.len() {
+        if is_removed(original[index]) {
+            levels[index] = if index == 0 {
+                paragraph
+            } else {
+                levels[index - 1]
+            };
+        }
+    }
+
+    // Separators and whitespace before them or at the end of the line go
+    // back to the paragraph level (L1)
+    let mut trailing = true;
+    for index in (0..original.len()).rev() {
+        match original[index] {
+            B | S => {
+                levels[index] = paragraph;
+                trailing = true;
+            }
+            class if trailing && (class == WS || is_removed(class)) => levels[index] = paragraph,
+            class if trailing && (is_isolate_initiator(class) || class == PDI) => {
+                levels[index] = paragraph
+            }
+            _ => trailing = false,
+        }
+    }
+    levels
+}
+
+/// Position of the PDI closing each isolate initiator (BD9)
+fn matching_pdis(classes: &[BidiClass]) -> Vec<Option<usize>> {
+    let mut matching = vec![None; classes.len()];
+    let mut open = Vec::new();
+    for (index, &class) in classes.iter().enumerate() {
+        match class {
+            LRI | RLI | FSI => open.push(index),
+            PDI => {
+                if let Some(initiator) = open.pop() {
+                    matching[initiator] = Some(index);
+                }
+            }
+            B => open.clear(),
+            _ => {}
+        }
+    }
+    matching
+}
+
+#[derive(Clone, Copy)]
+struct Embedding {
+    level: u8,
+    override_class: Option<BidiClass>,
+    isolate: bool,
+}
+
+/// Least odd (right to left) or even level above `level`
+fn next_level(level: u8, right_to_left: bool) -> u8 {
+    if right_to_left {
+        (level + 1) | 1
+    } else {
+        (level + 2) & !1
+    }
+}
+
+/// Explicit levels and directional overrides (X1 to X8)
+fn explicit_levels(
+    original: &[BidiClass],
+    classes: &mut [BidiClass],
+    paragraph: u8,
+    matching: &[Option<usize>],
+) -> Vec<u8> {
+    let bottom = Embedding {
+        level: paragraph,
+        override_class: None,
+        isolate: false,
+    };
+    let mut levels = vec![paragraph; original.len()];
+    let mut stack = vec![bottom];
+    let mut overflow_isolates = 0usize;
+    let mut overflow_embeddings = 0usize;
+    let mut valid_isolates = 0usize;
+
+    for index in 0..original.len() {
+        let top = stack.last().copied().unwrap_or(bottom);
+        match original[index] {
+            class @ (RLE | LRE | RLO | LRO) => {
+                levels[index] = top.level;
+                let level = next_level(top.level, matches!(class, RLE | RLO));
+                if level <= MAX_DEPTH && overflow_isolates == 0 && overflow_embeddings == 0 {
+                    stack.push(Embedding {
+                        level,
+                        override_class: match class {
+                            RLO => Some(R),
+                            LRO => Some(L),
+                            _ => None,
+                        },
+                        isolate: false,
+                    });
+                } else if overflow_isolates == 0 {
+                    overflow_embeddings += 1;
+                }
+            }
+            class @ (RLI | LRI | FSI) => {
+                levels[index] = top.level;
+                if let Some(override_class) = top.override_class {
+                    classes[index] = override_class;
+                }
+                let right_to_left = match class {
+                    RLI => true,
+                    LRI => false,
+                    _ => {
+                        let end = matching[index].unwrap_or(original.len());
+                        first_strong(&original[index + 1..end]) == Some(Direction::RightToLeft)
+                    }
+                };
+                let level = next_level(top.level, right_to_left);
+                if level <= MAX_DEPTH && overflow_isolates == 0 && overflow_embeddings == 0 {
+                    valid_isolates += 1;
+                    stack.push(Embedding {
+                        level,
+                        override_class: None,
+                        isolate: true,
+                    });
+                } else {
+                    overflow_isolates += 1;
+                }
+            }
+            PDI => {
+                if overflow_isolates > 0 {
+                    overflow_isolates -= 1;
+                } else if valid_isolates > 0 {
+                    overflow_embeddings = 0;
+                    while stack.last().is_some_and(|entry| !entry.isolate) {
+                        stack.pop();
+                    }
+                    stack.pop();
+                    valid_isolates -= 1;
+                }
+                let top = stack.last().copied().unwrap_or(bottom);
+                levels[index] = top.level;
+                if let Some(override_class) = top.override_class {
+                    classes[index] = override_class;
+                }
+            }
+            PDF => {
+                levels[index] = top.level;
+                if overflow_isolates > 0 {
+                } else if overflow_embeddings > 0 {
+                    overflow_embeddings -= 1;
+                } else if !top.isolate && stack.len() >= 2 {
+                    stack.pop();
+                }
+            }
+            B => levels[index] = paragraph,
+            BN => levels[index] = top.level,
+            _ => {
+                levels[index] = top.level;
+                if let Some(override_class) = top.override_class {
+                    classes[index] = override_class;
+                }
+            }
+        }
+    }
+    levels
+}
+
+/// Resolve the weak types, neutrals and implicit levels of an isolating
+/// run sequence (W1 to W7, N1, N2, I1, I2)
+fn resolve_sequence(
+    sequence: &[usize],
+    original: &[BidiClass],
+    classes: &[BidiClass],
+    levels: &mut [u8],
+    sos: BidiClass,
+    eos: BidiClass,
+) {
+    let mut types: Vec<BidiClass> = sequence.iter().map(|&i| classes[i]).collect();
+    let count = types.len();
+
+    // W1: nonspacing marks take the type of the character before them
+    for k in 0..count {
+        if types[k] == NSM {
+            types[k] = match k {
+                0 => sos,
+                _ if is_isolate_initiator(original[sequence[k - 1]])
+                    || original[sequence[k - 1]] == PDI =>
+                {
+                    ON
+                }
+                _ => types[k - 1],
+            };
+        }
+    }
+
+    // W2, W3: European numbers after Arabic letters are Arabic numbers,
+    // Arabic letters are right to left
+    let mut last_strong = sos;
+    for class in types.iter_mut() {
+        match *class {
+            L | R => last_strong = *class,
+            AL => {
+                last_strong = AL;
+                *class = R;
+            }
+            EN if last_strong == AL => *class = AN,
+            _ => {}
+        }
+    }
+
+    // W4: single separators between numbers of one type
+    for k in 1..count.saturating_sub(1) {
+        let (before, after) = (types[k - 1], types[k + 1]);
+        match types[k] {
+            ES if before == EN && after == EN => types[k] = EN,
+            CS if before == after && matches!(before, EN | AN) => types[k] = before,
+            _ => {}
+        }
+    }
+
+    // W5: terminators next to European numbers
+    let mut k = 0;
+    while k < count {
+        if types[k] != ET {
+            k += 1;
+            continue;
+        }
+        let start = k;
+        while k < count && types[k] == ET {
+            k += 1;
+        }
+        if (start > 0 && types[start - 1] == EN) || (k < count && types[k] == EN) {
+            types[start..k].fill(EN);
+        }
+    }
+
+    // W6, W7: other separators and terminators are neutral; European
+    // numbers in left-to-right text are left to right
+    let mut last_strong = sos;
+    for class in types.iter_mut() {
+        match *class {
+            ES | ET | CS => *class = ON,
+            L | R => last_strong = *class,
+            EN if last_strong == L => *class = L,
+            _ => {}
+        }
+    }
+
+    // N1, N2: neutrals take the direction of the text around them if it
+    // agrees, otherwise the embedding direction
+    let embedding = if levels[sequence[0]] % 2 == 1 { R } else { L };
+    let strong = |class: BidiClass| match class {
+        L => L,
+        R | EN | AN => R,
+        _ => embedding,
+    };
+    let is_neutral = |class: BidiClass| matches!(class, B | S | WS | ON | LRI | RLI | FSI | PDI);
+    let mut k = 0;
+    while k < count {
+        if !is_neutral(types[k]) {
+            k += 1;
+            continue;
+        }
+        let start = k;
+        while k < count && is_neutral(types[k]) {
+            k += 1;
+        }
+        let before = if start == 0 {
+            sos
+        } else {
+            strong(types[start - 1])
+        };
+        let after = if k == count { eos } else { strong(types[k]) };
+        types[start..k].fill(if before == after { before } else { embedding });
+    }
+
+    // I1, I2
+    for (&index, &class) in sequence.iter().zip(&types) {
+        let level = levels[index];
+        levels[index] = match (level % 2, class) {
+            (0, R) => level + 1,
+            (0, AN | EN) => level + 2,
+            (1, L | EN | AN) => level + 1,
+            _ => level,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_left_to_right_text_is_unchanged() {
+        assert_eq!(
+            reorder("Hello, world (1 + 2)", None),
+            "Hello, world (1 + 2)"
+        );
+        assert_eq!(
+            visual_runs("abc", None),
+            vec![BidiRun {
+                range: 0..3,
+                level: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hebrew_in_latin_paragraph() {
+        assert_eq!(reorder("abc אבג def", None), "abc גבא def");
+        assert_eq!(paragraph_direction("abc אבג"), Some(Direction::LeftToRight));
+    }
+
+    #[test]
+    fn test_right_to_left_paragraph_with_numbers() {
+        // Numbers keep their order inside right-to-left text
+        assert_eq!(reorder("אבג 123 דה", None), "הד 123 גבא");
+        assert_eq!(paragraph_direction("אבג abc"), Some(Direction::RightToLeft));
+
+        let runs = visual_runs("אב 12", None);
+        let levels: Vec<u8> = runs.iter().map(|run| run.level).collect();
+        assert_eq!(levels, vec![2, 1]);
+        assert_eq!(runs[0].range, 5..7);
+        assert_eq!(runs[1].direction(), Direction::RightToLeft);
+    }
+
+    #[test]
+    fn test_arabic_numbers_and_marks() {
+        // Arabic-Indic digits are numbers; the mark stays after its letter
+        assert_eq!(
+            reorder("\u{628}\u{64E}\u{644} \u{661}\u{662}", None),
+            "\u{661}\u{662} \u{644}\u{628}\u{64E}"
+        );
+    }
+
+    #[test]
+    fn test_brackets_are_mirrored() {
+        assert_eq!(reorder("א(ב)", None), "(ב)א");
+        assert_eq!(mirror('['), Some(']'));
+        assert_eq!(mirror('a'), None);
+    }
+
+    #[test]
+    fn test_explicit_direction() {
+        // A left-to-right paragraph forced to right to left
+        assert_eq!(reorder("abc def", Some(Direction::RightToLeft)), "abc def");
+        assert_eq!(reorder("ab אב", Some(Direction::RightToLeft)), "בא ab");
+        // Right-to-left override
+        assert_eq!(reorder("\u{202E}abc\u{202C}", None), "\u{202E}cba\u{202C}");
+    }
+
+    #[test]
+    fn test_isolates() {
+        // The isolate is resolved on its own; the text around it stays left
+        // to right
+        assert_eq!(
+            reorder("a \u{2067}אב 1\u{2069} b", None),
+            "a \u{2067}1 בא\u{2069} b"
+        );
+    }
+
+    #[test]
+    fn test_actual_text_span() {
+        assert_eq!(
+            actual_text_span("אב", "[<05D1 05D0>] TJ\n"),
+            "/Span <</ActualText <FEFF05D005D1>>> BDC\n[<05D1 05D0>] TJ\nEMC\n"
+        );
+    }
+}
@@ -227,6 +227,8 @@ impl TextExtractor {
         let mut in_text_object = false;
         let mut last_x = 0.0;
         let mut last_y = 0.0;
+        // `ActualText` of the open marked content sequences
+        let mut actual_text: Vec<Option<String>> = Vec::new();
 
         // Process each content stream
         for (stream_idx, stream_data) in streams.iter().enumerate() {
@@ -301,6 +303,7 @@ impl TextExtractor {
                         if in_text_object {
                             let text_bytes = &text;
                             let decoded = self.decode_text(text_bytes, &state)?;
+                            let shown = actual_text_or(&mut actual_text, &decoded);
 
                             // Calculate position: Apply text_matrix, then CTM
                             // Concatenate: final = CTM × text_matrix
@@ -319,7 +322,7 @@ impl TextExtractor {
                                 }
                             }
 
-                            extracted_text.push_str(&decoded);
+                            extracted_text.push_str(&shown);
 
                             // Get font info for accurate width calculation
                             let font_info = state
@@ -336,7 +339,7 @@ impl TextExtractor {
                                     .unwrap_or((false, false));
 
                                 fragments.push(TextFragment {
-                                    text: decoded.clone(),
+                                    text: shown,
                                     x,
                                     y,
                                     width: calculate_text_width(
@@ -378,7 +381,8 @@ impl TextExtractor {
                                 match item {
                                     TextElement::Text(text_bytes) => {
                                         let decoded = self.decode_text(&text_bytes, &state)?;
-                                        extracted_text.push_str(&decoded);
+                                        extracted_text
+                                            .push_str(&actual_text_or(&mut actual_text, &decoded));
 
                                         // Update text matrix
                                         let text_width = calculate_text_width(
@@ -405,6 +409,16 @@ impl TextExtractor {
                         }
                     }
 
+                    ContentOperation::BeginMarkedContent(_) => actual_text.push(None),
+
+                    ContentOperation::BeginMarkedContentWithProps(_, mut props) => {
+                        actual_text.push(props.remove("ActualText"));
+                    }
+
+                    ContentOperation::EndMarkedContent => {
+                        actual_text.pop();
+                    }
+
                     ContentOperation::SetFont(name, size) => {
                         state.font_name = Some(name);
                         state.font_size = size as f64;
@@ -743,8 +757,9 @@ impl TextExtractor {
 
                 // Try CMap-based decoding first
                 if let Ok(decoded) = cmap_extractor.decode_text_with_font(text, font_info) {
-                    // Only accept if we got meaningful text (not all null bytes or garbage)
-                    if !decoded.trim().is_empty()
+                    // Only accept if we got meaningful text (not all null bytes or
+                    // garbage); a lone space, as in shaped text, is meaningful
+                    if !decoded.is_empty()
                         && !decoded.chars().all(|c| c == '\0' || c.is_ascii_control())
                     {
                         tracing::debug!(
@@ -815,6 +830,15 @@ fn multiply_matrix(a: &[f64; 6], b: &[f64; 6]) -> [f64; 6] {
     ]
 }
 
+/// Text of shown glyphs: inside marked content with an `ActualText`, the
+/// actual text for the first glyphs shown and nothing for the others
+fn actual_text_or(actual_text: &mut [Option<String>], decoded: &str) -> String {
+    match actual_text.iter_mut().flatten().next() {
+        Some(text) => std::mem::take(text),
+        None => decoded.to_string(),
+    }
+}
+
 /// Transform a point using a transformation matrix
 fn transform_point(x: f64, y: f64, matrix: &[f64; 6]) -> (f64, f64) {
     let tx = matrix[0] * x + matrix[2] * y + matrix[4];
@@ -1,7 +1,11 @@
-use crate::error::Result;
+use crate::error::{PdfError, Result};
 use crate::page::Margins;
-use crate::text::{measure_text, split_into_words, Font};
+use crate::structure::StandardStructureType;
+use crate::text::bidi::{self, Direction};
+use crate::text::paragraph::{typeset_shaped_paragraph, ParagraphOptions};
+use crate::text::{measure_shaped_text, split_into_words, Font, ShapingFonts, UnshapedText};
 use std::fmt::Write;
+use std::ops::Range;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextAlign {
@@ -13,12 +17,23 @@ pub enum TextAlign {
 
 pub struct TextFlowContext {
     operations: String,
+    /// Paragraphs and headings written, with their operations
+    blocks: Vec<(StandardStructureType, Range<usize>)>,
     current_font: Font,
     font_size: f64,
     line_height: f64,
     cursor_x: f64,
     cursor_y: f64,
     alignment: TextAlign,
+    /// Paragraph direction; taken from the text of each paragraph if not set
+    direction: Option<Direction>,
+    /// Typesetting of paragraphs; lines are filled one at a time if not set
+    typesetting: Option<ParagraphOptions>,
+    /// Fonts shaping and measuring text in custom fonts, those of the page
+    /// the flow is for
+    shaping_fonts: ShapingFonts,
+    /// Lines in custom fonts the flow did not have yet
+    unshaped: Vec<UnshapedText>,
     page_width: f64,
     #[allow(dead_code)]
     page_height: f64,
@@ -29,18 +44,36 @@ impl TextFlowContext {
     pub fn new(page_width: f64, page_height: f64, margins: Margins) -> Self {
         Self {
             operations: String::new(),
+            blocks: Vec::new(),
             current_font: Font::Helvetica,
             font_size: 12.0,
             line_height: 1.2,
             cursor_x: margins.left,
             cursor_y: page_height - margins.top,
             alignment: TextAlign::Left,
+            direction: None,
+            typesetting: None,
+            shaping_fonts: ShapingFonts::new(),
+            unshaped: Vec::new(),
             page_width,
             page_height,
             margins,
         }
     }
 
+    /// Shape and measure text in custom fonts with these fonts, such as
+    /// those of the document the page is added to
+    ///
+    /// Lines already written in them are shaped, but keep the breaks
+    /// chosen with the widths of the unshaped text.
+    pub fn set_shaping_fonts(&mut self, fonts: &ShapingFonts) -> &mut Self {
+        self.shaping_fonts = fonts.clone();
+        let mut operations = std::mem::take(&mut self.operations).into_bytes();
+        fonts.shape_unshaped(&mut self.unshaped, &mut operations);
+        self.operations = String::from_utf8(operations).expect("Shaped operations should be UTF-8");
+        self
+    }
+
     pub fn set_font(&mut self, font: Font, size: f64) -> &mut Self {
         self.current_font = font;
         self.font_size = size;
@@ -57,6 +90,20 @@ impl TextFlowContext {
         self
     }
 
+    /// Set the paragraph direction, instead of taking it from the first
+    /// strong character of each paragraph
+    pub fn set_direction(&mut self, direction: Direction) -> &mut Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Break paragraphs with the paragraph typesetter: optimal line breaks,
+    /// hyphenation and, when justified, letter and word spacing
+    pub fn set_typesetting(&mut self, options: ParagraphOptions) -> &mut Self {
+        self.typesetting = Some(options);
+        self
+    }
+
     pub fn at(&mut self, x: f64, y: f64) -> &mut Self {
         self.cursor_x = x;
         self.cursor_y = y;
@@ -68,37 +115,99 @@ impl TextFlowContext {
     }
 
     pub fn write_wrapped(&mut self, text: &str) -> Result<&mut Self> {
+        self.write_block(text, StandardStructureType::P)
+    }
+
+    fn write_block(
+        &mut self,
+        text: &str,
+        structure_type: StandardStructureType,
+    ) -> Result<&mut Self> {
+        let start = self.operations.len();
         let content_width = self.content_width();
+        let direction = self
+            .direction
+            .or_else(|| bidi::paragraph_direction(text))
+            .unwrap_or_default();
+
+        // Lines with their width and, when justified, their character and
+        // word spacing
+        let justified = self.alignment == TextAlign::Justified;
+        let mut lines: Vec<(String, f64, (f64, f64))> = Vec::new();
+        if let Some(options) = &self.typesetting {
+            for line in typeset_shaped_paragraph(
+                text,
+                &self.current_font,
+                self.font_size,
+                content_width,
+                options,
+                &self.shaping_fonts,
+            ) {
+                let spacing = match justified {
+                    true => line
+                        .justification(content_width, options.max_letter_spacing * self.font_size),
+                    false => (0.0, 0.0),
+                };
+                lines.push((line.text, line.width, spacing));
+            }
+        } else {
+            // Split text into words
+            let words = split_into_words(text);
+            let mut greedy_lines: Vec<Vec<&str>> = Vec::new();
+            let mut current_line: Vec<&str> = Vec::new();
+            let mut current_width = 0.0;
+
+            // Build lines based on width constraints
+            for word in words {
+                let word_width = measure_shaped_text(
+                    word,
+                    self.current_font.clone(),
+                    self.font_size,
+                    &self.shaping_fonts,
+                );
+
+                // Check if we need to start a new line
+                if !current_line.is_empty() && current_width + word_width > content_width {
+                    greedy_lines.push(current_line);
+                    current_line = vec![word];
+                    current_width = word_width;
+                } else {
+                    current_line.push(word);
+                    current_width += word_width;
+                }
+            }
 
-        // Split text into words
-        let words = split_into_words(text);
-        let mut lines: Vec<Vec<&str>> = Vec::new();
-        let mut current_line: Vec<&str> = Vec::new();
-        let mut current_width = 0.0;
-
-        // Build lines based on width constraints
-        for word in words {
-            let word_width = measure_text(word, self.current_font.clone(), self.font_size);
-
-            // Check if we need to start a new line
-            if !current_line.is_empty() && current_width + word_width > content_width {
-                lines.push(current_line);
-                current_line = vec![word];
-                current_width = word_width;
-            } else {
-                current_line.push(word);
-                current_width += word_width;
+            if !current_line.is_empty() {
+                greedy_lines.push(current_line);
             }
-        }
 
-        if !current_line.is_empty() {
-            lines.push(current_line);
+            let count = greedy_lines.len();
+            for (i, line) in greedy_lines.iter().enumerate() {
+                let line_text = line.join("");
+                let line_width = measure_shaped_text(
+                    &line_text,
+                    self.current_font.clone(),
+                    self.font_size,
+                    &self.shaping_fonts,
+                );
+
+                // Justified lines but the last stretch their spaces
+                let mut word_spacing = 0.0;
+                if justified && i < count - 1 && line.len() > 1 {
+                    let spaces_count = line.iter().filter(|w| w.trim().is_empty()).count();
+                    if spaces_count > 0 {
+                        word_spacing = (content_width - line_width) / spaces_count as f64;
+                    }
+                }
+                lines.push((line_text, line_width, (0.0, word_spacing)));
+            }
         }
 
-        // Render each line
-        for (i, line) in lines.iter().enumerate() {
-            let line_text = line.join("");
-            let line_width = measure_text(&line_text, self.current_font.clone(), self.font_size);
+        // Render each line; lines are broken in logical order and each is
+        // reordered for display
+        for (i, (line_text, line_width, spacing)) in lines.iter().enumerate() {
+            let (line_width, (character_spacing, word_spacing)) = (*line_width, *spacing);
+            let last = i == lines.len() - 1;
 
             // Calculate x position based on alignment
             let x = match self.alignment {
@@ -106,9 +215,10 @@ impl TextFlowContext {
                 TextAlign::Right => self.page_width - self.margins.right - line_width,
                 TextAlign::Center => self.margins.left + (content_width - line_width) / 2.0,
                 TextAlign::Justified => {
-                    if i < lines.len() - 1 && line.len() > 1 {
-                        // We'll handle justification below
-                        self.margins.left
+                    if direction == Direction::RightToLeft && *spacing == (0.0, 0.0) {
+                        // Lines of a right-to-left paragraph that are not
+                        // stretched are on the right
+                        self.page_width - self.margins.right - line_width
                     } else {
                         self.margins.left
                     }
@@ -132,36 +242,70 @@ impl TextFlowContext {
                 .expect("Writing to String should never fail");
 
             // Handle justification
-            if self.alignment == TextAlign::Justified && i < lines.len() - 1 && line.len() > 1 {
-                // Calculate extra space to distribute
-                let spaces_count = line.iter().filter(|w| w.trim().is_empty()).count();
-                if spaces_count > 0 {
-                    let extra_space = content_width - line_width;
-                    let space_adjustment = extra_space / spaces_count as f64;
-
-                    // Set word spacing
-                    writeln!(&mut self.operations, "{space_adjustment:.2} Tw")
-                        .expect("Writing to String should never fail");
-                }
+            if character_spacing != 0.0 {
+                writeln!(&mut self.operations, "{character_spacing:.2} Tc")
+                    .expect("Writing to String should never fail");
+            }
+            if word_spacing != 0.0 {
+                writeln!(&mut self.operations, "{word_spacing:.2} Tw")
+                    .expect("Writing to String should never fail");
             }
 
-            // Show text
-            self.operations.push('(');
-            for ch in line_text.chars() {
-                match ch {
-                    '(' => self.operations.push_str("\\("),
-                    ')' => self.operations.push_str("\\)"),
-                    '\\' => self.operations.push_str("\\\\"),
-                    '\n' => self.operations.push_str("\\n"),
-                    '\r' => self.operations.push_str("\\r"),
-                    '\t' => self.operations.push_str("\\t"),
-                    _ => self.operations.push(ch),
+            // Show text, shaped if set in a registered custom font
+            let shaped = match &self.current_font {
+                Font::Custom(name) => self.shaping_fonts.show_text(
+                    name,
+                    line_text,
+                    Some(direction),
+                    self.font_size,
+                    character_spacing,
+                    word_spacing,
+                    0.0,
+                ),
+                _ => None,
+            };
+            if let Some(shown) = shaped {
+                self.operations.push_str(&shown);
+            } else {
+                let visual = bidi::reorder(line_text, Some(direction));
+                let mut shown = String::from("(");
+                for ch in visual.chars() {
+                    match ch {
+                        '(' => shown.push_str("\\("),
+                        ')' => shown.push_str("\\)"),
+                        '\\' => shown.push_str("\\\\"),
+                        '\n' => shown.push_str("\\n"),
+                        '\r' => shown.push_str("\\r"),
+                        '\t' => shown.push_str("\\t"),
+                        _ => shown.push(ch),
+                    }
+                }
+                shown.push_str(") Tj\n");
+                if visual != *line_text {
+                    shown = bidi::actual_text_span(line_text, &shown);
                 }
+                if let Font::Custom(name) = &self.current_font {
+                    let start = self.operations.len();
+                    self.unshaped.push(UnshapedText {
+                        range: start..start + shown.len(),
+                        shown: shown.clone(),
+                        font_name: name.clone(),
+                        text: line_text.clone(),
+                        base: Some(direction),
+                        font_size: self.font_size,
+                        character_spacing,
+                        word_spacing,
+                        rise: 0.0,
+                    });
+                }
+                self.operations.push_str(&shown);
             }
-            self.operations.push_str(") Tj\n");
 
-            // Reset word spacing if it was set
-            if self.alignment == TextAlign::Justified && i < lines.len() - 1 {
+            // Reset the spacing if it was set
+            if character_spacing != 0.0 {
+                self.operations.push_str("0 Tc\n");
+            }
+            if justified && !last {
                 self.operations.push_str("0 Tw\n");
             }
 
@@ -172,6 +316,10 @@ impl TextFlowContext {
             self.cursor_y -= self.font_size * self.line_height;
         }
 
+        if self.operations.len() > start {
+            self.blocks
+                .push((structure_type, start..self.operations.len()));
+        }
         Ok(self)
     }
 
@@ -182,6 +330,18 @@ impl TextFlowContext {
         Ok(self)
     }
 
+    /// Write a heading of level 1 to 6, spaced like a paragraph
+    ///
+    /// On a tagged page the heading is tagged `H1` to `H6`.
+    pub fn write_heading(&mut self, level: u8, text: &str) -> Result<&mut Self> {
+        let structure_type = StandardStructureType::heading(level).ok_or_else(|| {
+            PdfError::InvalidOperation(format!("heading level {level} is not between 1 and 6"))
+        })?;
+        self.write_block(text, structure_type)?;
+        self.cursor_y -= self.font_size * self.line_height * 0.5;
+        Ok(self)
+    }
+
     pub fn newline(&mut self) -> &mut Self {
         self.cursor_y -= self.font_size * self.line_height;
         self.cursor_x = self.margins.left;
@@ -221,9 +381,21 @@ impl TextFlowContext {
         &self.operations
     }
 
+    /// Paragraphs and headings written, with the range of their operations
+    pub fn blocks(&self) -> &[(StandardStructureType, Range<usize>)] {
+        &self.blocks
+    }
+
+    /// Lines in custom fonts shown before the flow had the fonts
+    pub(crate) fn unshaped(&self) -> &[UnshapedText] {
+        &self.unshaped
+    }
+
     /// Clear all operations
     pub fn clear(&mut self) {
         self.operations.clear();
+        self.blocks.clear();
+        self.unshaped.clear();
     }
 }
 
@@ -231,6 +403,7 @@ impl TextFlowContext {
 mod tests {
     use super::*;
     use crate::page::Margins;
+    use crate::text::{measure_text, Hyphenator};
 
     fn create_test_margins() -> Margins {
         Margins {
@@ -379,6 +552,64 @@ mod tests {
         assert_eq!(ops_string, context.operations());
     }
 
+    #[test]
+    fn test_write_wrapped_right_to_left() {
+        let margins = create_test_margins();
+        let mut context = TextFlowContext::new(400.0, 600.0, margins);
+
+        context.write_wrapped("אב גד").unwrap();
+        assert!(context
+            .operations()
+            .contains("/Span <</ActualText <FEFF05D005D1002005D205D3>>> BDC\n(דג בא) Tj\nEMC\n"));
+    }
+
+    #[test]
+    fn test_justified_right_to_left_last_line() {
+        let margins = create_test_margins();
+        let mut context = TextFlowContext::new(400.0, 600.0, margins);
+
+        context
+            .set_alignment(TextAlign::Justified)
+            .set_direction(Direction::RightToLeft)
+            .write_wrapped("abc")
+            .unwrap();
+        let x = 350.0 - measure_text("abc", Font::Helvetica, 12.0);
+        assert!(context
+            .operations()
+            .contains(&format!("{x:.2} 550.00 Td\n(abc) Tj")));
+    }
+
+    #[test]
+    fn test_write_wrapped_typeset() {
+        let margins = create_test_margins();
+        let mut context = TextFlowContext::new(200.0, 600.0, margins);
+
+        context
+            .set_alignment(TextAlign::Justified)
+            .set_typesetting(ParagraphOptions {
+                hyphenator: Some(
+                    Hyphenator::from_patterns(
+                        "",
+                        "con-trac-tor in-dem-ni-fy cus-tom-er con-se-quen-tial neg-li-gence",
+                    )
+                    .unwrap(),
+                ),
+                ..Default::default()
+            })
+            .write_wrapped(
+                "The contractor shall indemnify the customer against consequential damages \
+                 arising from negligence.",
+            )
+            .unwrap();
+        let ops = context.operations();
+        assert!(ops.contains(" Tc\n"));
+        assert!(ops.contains("0 Tc\n"));
+        assert!(ops.contains("-) Tj"));
+        // The last line is not justified
+        let last = ops.rsplit("BT\n").next().unwrap();
+        assert!(!last.contains("Tc") && !last.contains("Tw"));
+    }
+
     #[test]
     fn test_clear_operations() {
         let margins = create_test_margins();
@@ -391,6 +622,23 @@ mod tests {
         assert!(context.operations().is_empty());
     }
 
+    #[test]
+    fn test_heading_and_paragraph_blocks() {
+        let margins = create_test_margins();
+        let mut context = TextFlowContext::new(400.0, 600.0, margins);
+
+        context.write_heading(2, "Results").unwrap();
+        context.write_paragraph("All targets were met.").unwrap();
+        assert!(context.write_heading(7, "Too deep").is_err());
+
+        let blocks = context.blocks();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].0, StandardStructureType::H2);
+        assert_eq!(blocks[1].0, StandardStructureType::P);
+        assert_eq!(blocks[0].1.end, blocks[1].1.start);
+        assert!(context.operations()[blocks[1].1.clone()].contains("(All targets were met.) Tj"));
+    }
+
     #[test]
     fn test_page_dimensions() {
         let margins = create_test_margins();
@@ -114,6 +114,14 @@ impl TrueTypeFont {
             })
     }
 
+    /// Raw data of a table, if the font has it
+    pub fn table_data(&self, tag: &[u8]) -> Option<&[u8]> {
+        let entry = self.get_table(tag).ok()?;
+        let start = entry.offset as usize;
+        self.data
+            .get(start..start.checked_add(entry.length as usize)?)
+    }
+
     /// Parse a TrueType/OpenType font from data
     pub fn parse(data: Vec<u8>) -> ParseResult<Self> {
         if data.len() < 12 {
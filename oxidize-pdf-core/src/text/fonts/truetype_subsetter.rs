@@ -88,6 +88,17 @@ impl TrueTypeSubsetter {
     /// Subset the font to include only the specified characters
     /// Returns the subsetted font data and the Unicode to GlyphID mapping
     pub fn subset(&self, used_chars: &HashSet<char>) -> ParseResult<SubsetResult> {
+        self.subset_with_glyphs(used_chars, &HashMap::new())
+    }
+
+    /// Subset the font to the specified characters and to `extra_glyphs`,
+    /// glyphs shown with codes the cmap does not map (code -> GlyphID),
+    /// such as ligatures. The returned mapping includes those codes.
+    pub fn subset_with_glyphs(
+        &self,
+        used_chars: &HashSet<char>,
+        extra_glyphs: &HashMap<u32, u16>,
+    ) -> ParseResult<SubsetResult> {
         // Get the cmap table to find which glyphs we need
         let cmap_tables = self.font.parse_cmap()?;
         let cmap = cmap_tables
@@ -100,10 +111,12 @@ impl TrueTypeSubsetter {
             })?;
 
         // If we're not really subsetting (empty or small char set), return original with full mapping
+        let mut full_mapping = cmap.mappings.clone();
+        full_mapping.extend(extra_glyphs);
         if used_chars.is_empty() || used_chars.len() < 10 {
             return Ok(SubsetResult {
                 font_data: self.font_data.clone(),
-                glyph_mapping: cmap.mappings.clone(),
+                glyph_mapping: full_mapping,
             });
         }
 
@@ -117,6 +130,7 @@ impl TrueTypeSubsetter {
                 needed_glyphs.insert(glyph_id);
             }
         }
+        needed_glyphs.extend(extra_glyphs.values().copied());
 
         tracing::debug!("Font subsetting analysis:");
         tracing::debug!("  Total glyphs in font: {}", self.font.num_glyphs);
@@ -136,7 +150,7 @@ impl TrueTypeSubsetter {
 
             return Ok(SubsetResult {
                 font_data: self.font_data.clone(),
-                glyph_mapping: cmap.mappings.clone(), // Use complete mapping
+                glyph_mapping: full_mapping, // Use complete mapping
             });
         }
 
@@ -164,6 +178,11 @@ impl TrueTypeSubsetter {
                 }
             }
         }
+        for (&code, old_glyph_id) in extra_glyphs {
+            if let Some(&new_glyph_id) = glyph_map.get(old_glyph_id) {
+                new_cmap.insert(code, new_glyph_id);
+            }
+        }
 
         // Build the actual subset font
         match self.build_subset_font(&needed_glyphs, &glyph_map, &new_cmap) {
@@ -185,7 +204,7 @@ impl TrueTypeSubsetter {
                 // Fallback to full font if subsetting fails
                 Ok(SubsetResult {
                     font_data: self.font_data.clone(),
-                    glyph_mapping: cmap.mappings.clone(),
+                    glyph_mapping: full_mapping,
                 })
             }
         }
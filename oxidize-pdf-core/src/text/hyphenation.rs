@@ -0,0 +1,354 @@
+//! Word hyphenation with Liang's algorithm
+//!
+//! A hyphenator holds patterns in the TeX format: letters with digits
+//! between them, such as `1ba` or `.ve2r3`, where an odd digit allows a
+//! break and an even one forbids it, the highest digit from all matching
+//! patterns winning. No patterns are built in; load a full pattern set,
+//! such as the `hyph-en-us.tex`, `hyph-es.tex` or `hyph-de-1996.tex` files
+//! of the TeX distributions, with [`Hyphenator::from_tex`].
+//!
+//! ```rust,no_run
+//! use oxidize_pdf::text::Hyphenator;
+//!
+//! # fn main() -> oxidize_pdf::Result<()> {
+//! let source = std::fs::read_to_string("hyph-en-us.tex")?;
+//! let hyphenator = Hyphenator::from_tex(&source)?;
+//! let breaks = hyphenator.hyphenate("hyphenation");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{PdfError, Result};
+use std::collections::HashMap;
+
+/// Hyphenation points of words, from Liang patterns and exceptions
+#[derive(Debug, Clone)]
+pub struct Hyphenator {
+    /// Letters of each pattern and the digits around them
+    patterns: HashMap<String, Vec<u8>>,
+    /// Words hyphenated explicitly, with the character positions of their
+    /// breaks
+    exceptions: HashMap<String, Vec<usize>>,
+    /// Letters of the longest pattern
+    max_length: usize,
+    left_min: usize,
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Hyphenator with patterns and exceptions in the TeX format, separated
+    /// by whitespace: patterns such as `.ach4` and `a1b`, exceptions such as
+    /// `as-so-ciate`. `%` starts a comment. At least two letters are kept
+    /// before a break and three after it, as in the TeX English settings;
+    /// see [`with_min_lengths`](Self::with_min_lengths).
+    pub fn from_patterns(patterns: &str, exceptions: &str) -> Result<Self> {
+        let mut hyphenator = Self::empty(2, 3);
+        for pattern in words(patterns) {
+            hyphenator.add_pattern(pattern)?;
+        }
+        for exception in words(exceptions) {
+            hyphenator.add_exception(exception);
+        }
+        Ok(hyphenator)
+    }
+
+    /// Hyphenator from a TeX hyphenation file, with its `\patterns{...}`
+    /// and optional `\hyphenation{...}` groups
+    pub fn from_tex(source: &str) -> Result<Self> {
+        let source: String = source
+            .lines()
+            .map(|line| line.split('%').next().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let patterns = tex_group(&source, "\\patterns").ok_or_else(|| {
+            PdfError::InvalidFormat("No \\patterns group in the hyphenation file".to_string())
+        })?;
+        let exceptions = tex_group(&source, "\\hyphenation").unwrap_or_default();
+        Self::from_patterns(patterns, exceptions)
+    }
+
+    /// Set the fewest letters kept before and after a break
+    pub fn with_min_lengths(mut self, left: usize, right: usize) -> Self {
+        self.left_min = left.max(1);
+        self.right_min = right.max(1);
+        self
+    }
+
+    fn empty(left_min: usize, right_min: usize) -> Self {
+        Self {
+            patterns: HashMap::new(),
+            exceptions: HashMap::new(),
+            max_length: 0,
+            left_min,
+            right_min,
+        }
+    }
+
+    fn add_pattern(&mut self, pattern: &str) -> Result<()> {
+        let mut letters = String::new();
+        let mut values = vec![0u8];
+        for ch in pattern.chars() {
+            match ch.to_digit(10) {
+                Some(_) if *values.last().unwrap_or(&0) != 0 => {
+                    return Err(PdfError::InvalidFormat(format!(
+                        "Invalid hyphenation pattern '{pattern}'"
+                    )));
+                }
+                Some(digit) => *values.last_mut().expect("values are not empty") = digit as u8,
+                None => {
+                    letters.push(lowercase(ch));
+                    values.push(0);
+                }
+            }
+        }
+        if letters.is_empty() {
+            return Err(PdfError::InvalidFormat(format!(
+                "Invalid hyphenation pattern '{pattern}'"
+            )));
+        }
+        self.max_length = self.max_length.max(letters.chars().count());
+        self.patterns.insert(letters, values);
+        Ok(())
+    }
+
+    fn add_exception(&mut self, exception: &str) {
+        let mut word = String::new();
+        let mut breaks = Vec::new();
+        for ch in exception.chars() {
+            if ch == '-' {
+                breaks.push(word.chars().count());
+            } else {
+                word.push(lowercase(ch));
+            }
+        }
+        self.exceptions.insert(word, breaks);
+    }
+
+    /// Byte offsets in the word where it may be hyphenated. Leading and
+    /// trailing punctuation is kept with the word; words with other
+    /// characters than letters are not hyphenated.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let start = word
+            .char_indices()
+            .find(|(_, ch)| ch.is_alphabetic())
+            .map_or(word.len(), |(index, _)| index);
+        let end = word
+            .char_indices()
+            .rev()
+            .find(|(_, ch)| ch.is_alphabetic())
+            .map_or(start, |(index, ch)| index + ch.len_utf8());
+        let core = &word[start..end.max(start)];
+        if core.is_empty() || !core.chars().all(char::is_alphabetic) {
+            return Vec::new();
+        }
+
+        let offsets: Vec<usize> = core.char_indices().map(|(index, _)| index).collect();
+        let letters: Vec<char> = core.chars().map(lowercase).collect();
+        let count = letters.len();
+        if count < self.left_min + self.right_min {
+            return Vec::new();
+        }
+
+        let key: String = letters.iter().collect();
+        let breaks = match self.exceptions.get(&key) {
+            Some(breaks) => breaks.clone(),
+            None => self.pattern_breaks(&letters),
+        };
+        breaks
+            .into_iter()
+            .filter(|&position| position >= self.left_min && count - position >= self.right_min)
+            .map(|position| start + offsets[position])
+            .collect()
+    }
+
+    /// Character positions of the breaks the patterns allow
+    fn pattern_breaks(&self, letters: &[char]) -> Vec<usize> {
+        let dotted: Vec<char> = std::iter::once('.')
+            .chain(letters.iter().copied())
+            .chain(std::iter::once('.'))
+            .collect();
+        // Digit between dotted[i - 1] and dotted[i]
+        let mut points = vec![0u8; dotted.len() + 1];
+        for start in 0..dotted.len() {
+            let mut key = String::new();
+            for &ch in dotted[start..].iter().take(self.max_length) {
+                key.push(ch);
+                if let Some(values) = self.patterns.get(&key) {
+                    for (offset, &value) in values.iter().enumerate() {
+                        let point = &mut points[start + offset];
+                        *point = (*point).max(value);
+                    }
+                }
+            }
+        }
+        // A break before letter k is between dotted[k] and dotted[k + 1]
+        (1..letters.len())
+            .filter(|&position| points[position + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+fn lowercase(ch: char) -> char {
+    ch.to_lowercase().next().unwrap_or(ch)
+}
+
+/// Whitespace separated words, without `%` comments
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().flat_map(|line| {
+        line.split('%')
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+    })
+}
+
+/// Contents of the `{...}` group after a TeX command
+fn tex_group<'a>(source: &'a str, command: &str) -> Option<&'a str> {
+    let start = source.find(command)? + command.len();
+    let open = start + source[start..].find('{')? + 1;
+    let close = open + source[open..].find('}')?;
+    Some(&source[open..close])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hyphenated(hyphenator: &Hyphenator, word: &str) -> String {
+        let mut result = word.to_string();
+        for &offset in hyphenator.hyphenate(word).iter().rev() {
+            result.insert(offset, '-');
+        }
+        result
+    }
+
+    /// Patterns in the style of `hyph-en-us.tex`: the hyphenation example
+    /// of The TeXbook and a few suffix and prefix patterns
+    const ENGLISH: &str = "% English test patterns
+\\patterns{
+hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n
+1thing 1fore 1ment 1ness n1oth oth1er .re1vi w1ers 2ss 4ing
+}
+\\hyphenation{as-so-ciate present}";
+
+    #[test]
+    fn test_known_hyphenations() {
+        let english = Hyphenator::from_tex(ENGLISH).unwrap();
+        for expected in [
+            "hy-phen-ation",
+            "some-thing",
+            "there-fore",
+            "state-ment",
+            "whereas",
+            "busi-ness",
+            "an-other",
+            "re-view-ers",
+            "as-so-ciate",
+            "present",
+            "Some-thing,",
+        ] {
+            let word = expected.replace('-', "");
+            assert_eq!(hyphenated(&english, &word), expected);
+        }
+    }
+
+    #[test]
+    fn test_min_lengths() {
+        let english = Hyphenator::from_tex(ENGLISH).unwrap();
+        // "er" is too short to follow a break with the default minimums
+        assert_eq!(hyphenated(&english, "another"), "an-other");
+        let loose = english.with_min_lengths(1, 1);
+        assert_eq!(hyphenated(&loose, "another"), "an-oth-er");
+    }
+
+    #[test]
+    fn test_tex_patterns_and_exceptions() {
+        let source = "% Test patterns\n\\patterns{\n.ach4 1na 1ti\n}\n\\hyphenation{ta-ble}";
+        let hyphenator = Hyphenator::from_tex(source).unwrap();
+        assert_eq!(hyphenated(&hyphenator, "nation"), "na-tion");
+        assert_eq!(hyphenated(&hyphenator, "Table"), "Ta-ble");
+        // Too short to keep three letters after the break
+        assert_eq!(hyphenated(&hyphenator, "anti"), "anti");
+        // Not words
+        assert_eq!(hyphenated(&hyphenator, "x2nation"), "x2nation");
+    }
+
+    /// Excerpt in the layout of `hyph-es.tex`: UTF-8 patterns for a
+    /// consonant before a vowel, clusters kept together, and an exception
+    /// for the prefix "sub"
+    const SPANISH: &str = "% hyph-es.tex (excerpt)
+% Spanish hyphenation patterns. The \\patterns below are in UTF-8.
+%
+\\patterns{
+% consonant before a vowel
+1ca 1ci 1ció 1fo 1gü 1la 1lé 1no 1ño 1pe 1que 1ra 1te 1ya
+% clusters that stay together
+1b2l 1b2r 1r2r
+}
+\\hyphenation{
+sub-ra-yar
+}
+";
+
+    /// Excerpt in the layout of `hyph-de-1996.tex`
+    const GERMAN: &str = "% hyph-de-1996.tex (excerpt)
+% German hyphenation patterns, reformed orthography, UTF-8
+\\patterns{
+.über1 1ße ß1b d1ch 1ck 1ge s1t
+}
+\\hyphenation{
+Ur-in-stinkt
+}
+";
+
+    #[test]
+    fn test_spanish_tex_patterns() {
+        let spanish = Hyphenator::from_tex(SPANISH)
+            .unwrap()
+            .with_min_lengths(2, 2);
+        for expected in [
+            "can-ción",
+            "Pe-que-ño",
+            "te-lé-fo-no",
+            "ha-blar",
+            "pe-rro",
+            "pin-güi-no",
+            // The exception wins over the patterns' "su-bra-yar"
+            "sub-ra-yar",
+        ] {
+            let word = expected.replace('-', "");
+            assert_eq!(hyphenated(&spanish, &word), expected);
+        }
+        // Byte offsets land on character boundaries after accented letters
+        assert_eq!(spanish.hyphenate("teléfono"), vec![2, 5, 7]);
+    }
+
+    #[test]
+    fn test_german_tex_patterns() {
+        let german = Hyphenator::from_tex(GERMAN).unwrap().with_min_lengths(2, 2);
+        for expected in [
+            "Stra-ße",
+            "Grö-ße",
+            "Fuß-ball",
+            "Mäd-chen",
+            "Bä-cker",
+            "Über-zug",
+            "Übun-gen",
+            "Diens-tag",
+            // The exception wins over the patterns' "Urins-tinkt"
+            "Ur-in-stinkt",
+        ] {
+            let word = expected.replace('-', "");
+            assert_eq!(hyphenated(&german, &word), expected);
+        }
+        assert_eq!(german.hyphenate("Straße"), vec![4]);
+        assert_eq!(german.hyphenate("Größe"), vec![4]);
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(Hyphenator::from_patterns("a12b", "").is_err());
+        assert!(Hyphenator::from_patterns("3", "").is_err());
+        assert!(Hyphenator::from_tex("no patterns").is_err());
+    }
+}
@@ -29,6 +29,14 @@ pub enum ExtractionError {
     #[error("Regex compilation error: {0}")]
     RegexError(String),
 
+    /// Embedded invoice XML is malformed or not a CII invoice
+    #[error("Invalid invoice XML: {0}")]
+    XmlError(String),
+
+    /// Reading the PDF document failed
+    #[error("PDF error: {0}")]
+    PdfError(String),
+
     /// Generic extraction error with context
     #[error("Extraction error: {0}")]
     Generic(String),
@@ -54,12 +54,15 @@
 //! Fields below the confidence threshold are automatically filtered out.
 
 use super::error::{ExtractionError, Result};
+use super::facturx;
 use super::patterns::{InvoiceFieldType, PatternLibrary};
 use super::types::{
     BoundingBox, ExtractedField, InvoiceData, InvoiceField, InvoiceMetadata, Language,
 };
 use super::validators;
-use crate::text::extraction::TextFragment;
+use crate::parser::PdfDocument;
+use crate::text::extraction::{ExtractionOptions, TextExtractor, TextFragment};
+use std::io::{Read, Seek};
 
 /// Invoice data extractor with configurable pattern matching
 ///
@@ -233,6 +236,58 @@ impl InvoiceExtractor {
         Ok(InvoiceData::new(fields, metadata))
     }
 
+    /// Extract invoice data from a page of a parsed PDF
+    ///
+    /// Hybrid e-invoices (Factur-X, ZUGFeRD, XRechnung) carry the invoice as
+    /// embedded XML; when present, it is read instead of matching patterns
+    /// in the page text, and every field has full confidence.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - Parsed PDF document
+    /// * `page_index` - Zero-based index of the page holding the invoice text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use oxidize_pdf::parser::{PdfDocument, PdfReader};
+    /// use oxidize_pdf::text::invoice::InvoiceExtractor;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let document = PdfDocument::new(PdfReader::open("invoice.pdf")?);
+    /// let extractor = InvoiceExtractor::builder().with_language("de").build();
+    ///
+    /// let invoice = extractor.extract_from_document(&document, 0)?;
+    /// println!("Found {} fields", invoice.field_count());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_from_document<R: Read + Seek>(
+        &self,
+        document: &PdfDocument<R>,
+        page_index: u32,
+    ) -> Result<InvoiceData> {
+        let pdf_error = |e: crate::PdfError| ExtractionError::PdfError(e.to_string());
+        if let Some(xml) = facturx::read_xml(document).map_err(pdf_error)? {
+            return facturx::parse_cii(&xml);
+        }
+
+        let options = ExtractionOptions {
+            preserve_layout: true,
+            ..ExtractionOptions::default()
+        };
+        let extracted = TextExtractor::with_options(options)
+            .extract_from_page(document, page_index)
+            .map_err(|e| pdf_error(e.into()))?;
+        let mut invoice = if extracted.fragments.is_empty() {
+            self.extract_from_text(&extracted.text)?
+        } else {
+            self.extract(&extracted.fragments)?
+        };
+        invoice.metadata.page_number = page_index + 1;
+        Ok(invoice)
+    }
+
     /// Extract invoice data from plain text (convenience method for testing)
     ///
     /// This is a convenience wrapper around `extract()` that creates synthetic
@@ -0,0 +1,576 @@
+//! Factur-X / ZUGFeRD hybrid e-invoices
+//!
+//! A hybrid invoice is a PDF/A-3 document that embeds the invoice in the
+//! UN/CEFACT Cross Industry Invoice (CII) XML syntax as an associated file,
+//! announced in the document's XMP metadata with the Factur-X extension
+//! schema. ZUGFeRD 2.x and Factur-X 1.0 share the same format; XRechnung
+//! uses the same container with `xrechnung.xml`.
+//!
+//! [`FacturX`] embeds a CII XML produced elsewhere into a generated
+//! document, and [`read_xml`] and [`parse_cii`] turn the XML of incoming
+//! PDFs into [`InvoiceData`], which [`InvoiceExtractor::extract_from_document`]
+//! prefers over pattern matching.
+//!
+//! A document with an invoice is always written as PDF/A-3b, as if saved
+//! with [`Document::set_pdfa`] at that level: fonts are embedded, an sRGB
+//! output intent is added ([`FacturX::with_output_intent`] sets a different
+//! profile), and features PDF/A-3 forbids make saving fail. Saving with
+//! another PDF/A level is an error.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use oxidize_pdf::text::invoice::{FacturX, FacturXProfile};
+//! use oxidize_pdf::{Document, Page};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let cii_xml = std::fs::read("factur-x.xml")?;
+//! let mut doc = Document::new();
+//! doc.add_page(Page::a4());
+//! doc.set_facturx(FacturX::new(FacturXProfile::En16931, cii_xml));
+//! // Written as PDF/A-3b
+//! doc.save("invoice.pdf")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`InvoiceExtractor::extract_from_document`]: super::InvoiceExtractor::extract_from_document
+//! [`Document::set_pdfa`]: crate::Document::set_pdfa
+
+use super::error::{ExtractionError, Result};
+use super::types::{BoundingBox, ExtractedField, InvoiceData, InvoiceField, InvoiceMetadata};
+use crate::attachments::{AFRelationship, EmbeddedFile};
+use crate::graphics::IccProfile;
+use crate::metadata::xmp::XmpValue;
+use crate::metadata::{XmpMetadata, XmpNamespace};
+use crate::parser::PdfDocument;
+use crate::verification::pdfa::PdfALevel;
+use chrono::Utc;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+/// Namespace of the Factur-X XMP properties
+const FACTURX_NAMESPACE: &str = "urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#";
+
+/// Names under which hybrid invoices embed their XML
+const INVOICE_FILE_NAMES: &[&str] = &[
+    "factur-x.xml",
+    "xrechnung.xml",
+    "zugferd-invoice.xml",
+    "ZUGFeRD-invoice.xml",
+];
+
+/// Factur-X profile (conformance level) of the embedded XML
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FacturXProfile {
+    Minimum,
+    BasicWl,
+    Basic,
+    En16931,
+    Extended,
+    XRechnung,
+}
+
+impl FacturXProfile {
+    /// Conformance level as written in the XMP metadata
+    pub fn conformance_level(&self) -> &'static str {
+        match self {
+            FacturXProfile::Minimum => "MINIMUM",
+            FacturXProfile::BasicWl => "BASIC WL",
+            FacturXProfile::Basic => "BASIC",
+            FacturXProfile::En16931 => "EN 16931",
+            FacturXProfile::Extended => "EXTENDED",
+            FacturXProfile::XRechnung => "XRECHNUNG",
+        }
+    }
+
+    /// Name of the embedded XML file
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            FacturXProfile::XRechnung => "xrechnung.xml",
+            _ => "factur-x.xml",
+        }
+    }
+
+    /// Relationship of the XML to the document: the profiles without line
+    /// items only carry data, the others are a full alternative
+    pub fn relationship(&self) -> AFRelationship {
+        match self {
+            FacturXProfile::Minimum | FacturXProfile::BasicWl => AFRelationship::Data,
+            _ => AFRelationship::Alternative,
+        }
+    }
+}
+
+/// A CII invoice to embed in a generated document
+///
+/// See [`crate::Document::set_facturx`].
+#[derive(Debug, Clone)]
+pub struct FacturX {
+    pub profile: FacturXProfile,
+    /// Cross Industry Invoice XML
+    pub xml: Vec<u8>,
+    /// Version of the Factur-X specification
+    pub version: String,
+    /// ICC profile of the output intent required by PDF/A for device colors
+    pub output_intent: Option<IccProfile>,
+}
+
+impl FacturX {
+    /// Create a Factur-X 1.0 invoice
+    pub fn new(profile: FacturXProfile, xml: Vec<u8>) -> Self {
+        Self {
+            profile,
+            xml,
+            version: "1.0".to_string(),
+            output_intent: None,
+        }
+    }
+
+    /// Set the Factur-X version
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Set the output intent profile, typically sRGB
+    pub fn with_output_intent(mut self, profile: IccProfile) -> Self {
+        self.output_intent = Some(profile);
+        self
+    }
+
+    /// The XML as an associated file of the document
+    pub(crate) fn embedded_file(&self) -> EmbeddedFile {
+        EmbeddedFile::new(self.profile.file_name(), self.xml.clone())
+            .with_mime_type("text/xml")
+            .with_description("Factur-X invoice")
+            .with_modification_date(Utc::now())
+            .with_relationship(self.profile.relationship())
+    }
+
+    /// Add the PDF/A-3 identification and the Factur-X properties with
+    /// their extension schema
+    pub(crate) fn add_xmp(&self, xmp: &mut XmpMetadata) {
+        PdfALevel::A3b.add_xmp(xmp);
+
+        let fx = XmpNamespace::Custom("fx".to_string(), FACTURX_NAMESPACE.to_string());
+        xmp.set_text(fx.clone(), "DocumentType", "INVOICE");
+        xmp.set_text(fx.clone(), "DocumentFileName", self.profile.file_name());
+        xmp.set_text(fx.clone(), "Version", self.version.clone());
+        xmp.set_text(fx, "ConformanceLevel", self.profile.conformance_level());
+
+        xmp.register_namespace(
+            "pdfaSchema".to_string(),
+            "http://www.aiim.org/pdfa/ns/schema#".to_string(),
+        );
+        xmp.register_namespace(
+            "pdfaProperty".to_string(),
+            "http://www.aiim.org/pdfa/ns/property#".to_string(),
+        );
+        let properties = [
+            (
+                "DocumentFileName",
+                "The name of the embedded XML invoice file",
+            ),
+            ("DocumentType", "The type of the hybrid document"),
+            ("Version", "The version of the Factur-X XML schema"),
+            (
+                "ConformanceLevel",
+                "The conformance level of the embedded data",
+            ),
+        ]
+        .into_iter()
+        .map(|(name, description)| {
+            [
+                text("pdfaProperty:name", name),
+                text("pdfaProperty:valueType", "Text"),
+                text("pdfaProperty:category", "external"),
+                text("pdfaProperty:description", description),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key, Box::new(value)))
+            .collect()
+        })
+        .collect();
+        let schema = HashMap::from([
+            text("pdfaSchema:schema", "Factur-X PDFA Extension Schema"),
+            text("pdfaSchema:namespaceURI", FACTURX_NAMESPACE),
+            text("pdfaSchema:prefix", "fx"),
+            (
+                "pdfaSchema:property".to_string(),
+                XmpValue::ArrayStruct(properties),
+            ),
+        ]);
+        xmp.set_bag_struct(
+            XmpNamespace::Custom(
+                "pdfaExtension".to_string(),
+                "http://www.aiim.org/pdfa/ns/extension/".to_string(),
+            ),
+            "schemas",
+            vec![schema],
+        );
+    }
+}
+
+fn text(key: &str, value: &str) -> (String, XmpValue) {
+    (key.to_string(), XmpValue::Text(value.to_string()))
+}
+
+/// The embedded invoice XML of a hybrid invoice, if there is one
+pub fn read_xml<R: Read + Seek>(document: &PdfDocument<R>) -> crate::Result<Option<Vec<u8>>> {
+    let attachments = document.attachments()?;
+    let invoice = attachments.into_iter().find(|attachment| {
+        INVOICE_FILE_NAMES
+            .iter()
+            .any(|name| attachment.file.file_name.eq_ignore_ascii_case(name))
+    });
+    Ok(invoice.map(|attachment| attachment.file.data))
+}
+
+/// Read the fields of a Cross Industry Invoice
+///
+/// Values come from the document itself, so every field has full
+/// confidence and no position. Dates in format 102 become `YYYY-MM-DD`.
+///
+/// # Errors
+///
+/// Returns an error if the XML is malformed or is not a CII invoice.
+pub fn parse_cii(xml: &[u8]) -> Result<InvoiceData> {
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+
+    let mut path: Vec<String> = Vec::new();
+    // Attributes of the innermost open element
+    let mut attributes: HashMap<String, String> = HashMap::new();
+    let mut fields = Vec::new();
+    let mut is_cii = false;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.name().as_ref());
+                if path.is_empty() {
+                    is_cii = name == "CrossIndustryInvoice";
+                }
+                attributes = e
+                    .attributes()
+                    .flatten()
+                    .map(|attr| {
+                        (
+                            local_name(attr.key.as_ref()),
+                            String::from_utf8_lossy(&attr.value).into_owned(),
+                        )
+                    })
+                    .collect();
+                path.push(name);
+            }
+            Ok(Event::End(_)) => {
+                path.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let value = e
+                    .unescape()
+                    .map_err(|e| ExtractionError::XmlError(e.to_string()))?;
+                if let Some(field) = cii_field(&path, &attributes, value.trim()) {
+                    fields.push(ExtractedField::new(
+                        field,
+                        1.0,
+                        BoundingBox::new(0.0, 0.0, 0.0, 0.0),
+                        value.trim().to_string(),
+                    ));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(ExtractionError::XmlError(e.to_string())),
+            _ => {}
+        }
+        buf.clear();
+    }
+    if !is_cii {
+        return Err(ExtractionError::XmlError(
+            "not a CrossIndustryInvoice document".to_string(),
+        ));
+    }
+
+    let confidence = if fields.is_empty() { 0.0 } else { 1.0 };
+    Ok(InvoiceData::new(
+        fields,
+        InvoiceMetadata::new(1, confidence),
+    ))
+}
+
+/// The invoice field an element of a CII document holds, by its path
+fn cii_field(
+    path: &[String],
+    attributes: &HashMap<String, String>,
+    value: &str,
+) -> Option<InvoiceField> {
+    let ends_with = |suffix: &[&str]| {
+        path.len() >= suffix.len()
+            && path[path.len() - suffix.len()..]
+                .iter()
+                .zip(suffix)
+                .all(|(element, expected)| element == expected)
+    };
+    let amount = || value.parse::<f64>().ok();
+
+    if ends_with(&["ExchangedDocument", "ID"]) {
+        Some(InvoiceField::InvoiceNumber(value.to_string()))
+    } else if ends_with(&["ExchangedDocument", "IssueDateTime", "DateTimeString"]) {
+        Some(InvoiceField::InvoiceDate(cii_date(value, attributes)))
+    } else if ends_with(&["DueDateDateTime", "DateTimeString"]) {
+        Some(InvoiceField::DueDate(cii_date(value, attributes)))
+    } else if ends_with(&["SellerTradeParty", "Name"]) {
+        Some(InvoiceField::SupplierName(value.to_string()))
+    } else if ends_with(&["BuyerTradeParty", "Name"]) {
+        Some(InvoiceField::CustomerName(value.to_string()))
+    } else if ends_with(&["SellerTradeParty", "SpecifiedTaxRegistration", "ID"])
+        && attributes.get("schemeID").map(String::as_str) == Some("VA")
+    {
+        Some(InvoiceField::VatNumber(value.to_string()))
+    } else if ends_with(&["ApplicableHeaderTradeSettlement", "InvoiceCurrencyCode"]) {
+        Some(InvoiceField::Currency(value.to_string()))
+    } else if ends_with(&[
+        "SpecifiedTradeSettlementHeaderMonetarySummation",
+        "TaxBasisTotalAmount",
+    ]) {
+        amount().map(InvoiceField::NetAmount)
+    } else if ends_with(&[
+        "SpecifiedTradeSettlementHeaderMonetarySummation",
+        "TaxTotalAmount",
+    ]) {
+        amount().map(InvoiceField::TaxAmount)
+    } else if ends_with(&[
+        "SpecifiedTradeSettlementHeaderMonetarySummation",
+        "GrandTotalAmount",
+    ]) {
+        amount().map(InvoiceField::TotalAmount)
+    } else if ends_with(&["SpecifiedTradeProduct", "SellerAssignedID"]) {
+        Some(InvoiceField::ArticleNumber(value.to_string()))
+    } else if ends_with(&["SpecifiedTradeProduct", "Name"]) {
+        Some(InvoiceField::LineItemDescription(value.to_string()))
+    } else if ends_with(&["SpecifiedLineTradeDelivery", "BilledQuantity"]) {
+        amount().map(InvoiceField::LineItemQuantity)
+    } else if ends_with(&["NetPriceProductTradePrice", "ChargeAmount"]) {
+        amount().map(InvoiceField::LineItemUnitPrice)
+    } else {
+        None
+    }
+}
+
+/// A CII date, as `YYYY-MM-DD` when in format 102 (`YYYYMMDD`)
+fn cii_date(value: &str, attributes: &HashMap<String, String>) -> String {
+    let is_102 = attributes
+        .get("format")
+        .map_or(true, |format| format == "102");
+    if is_102 && value.len() == 8 && value.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{}-{}-{}", &value[..4], &value[4..6], &value[6..])
+    } else {
+        value.to_string()
+    }
+}
+
+/// Element or attribute name without its namespace prefix
+fn local_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    match name.rsplit_once(':') {
+        Some((_, local)) => local.to_string(),
+        None => name.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PdfReader;
+    use crate::{Document, Page};
+    use std::io::Cursor;
+
+    const SAMPLE_CII: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rsm:CrossIndustryInvoice xmlns:rsm="urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100"
+    xmlns:ram="urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100"
+    xmlns:udt="urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100">
+  <rsm:ExchangedDocumentContext>
+    <ram:GuidelineSpecifiedDocumentContextParameter>
+      <ram:ID>urn:cen.eu:en16931:2017</ram:ID>
+    </ram:GuidelineSpecifiedDocumentContextParameter>
+  </rsm:ExchangedDocumentContext>
+  <rsm:ExchangedDocument>
+    <ram:ID>RE-2025-0042</ram:ID>
+    <ram:TypeCode>380</ram:TypeCode>
+    <ram:IssueDateTime><udt:DateTimeString format="102">20250131</udt:DateTimeString></ram:IssueDateTime>
+  </rsm:ExchangedDocument>
+  <rsm:SupplyChainTradeTransaction>
+    <ram:IncludedSupplyChainTradeLineItem>
+      <ram:SpecifiedTradeProduct>
+        <ram:SellerAssignedID>ART-7</ram:SellerAssignedID>
+        <ram:Name>Beratung &amp; Support</ram:Name>
+      </ram:SpecifiedTradeProduct>
+      <ram:SpecifiedLineTradeAgreement>
+        <ram:NetPriceProductTradePrice><ram:ChargeAmount>100.00</ram:ChargeAmount></ram:NetPriceProductTradePrice>
+      </ram:SpecifiedLineTradeAgreement>
+      <ram:SpecifiedLineTradeDelivery>
+        <ram:BilledQuantity unitCode="HUR">5</ram:BilledQuantity>
+      </ram:SpecifiedLineTradeDelivery>
+    </ram:IncludedSupplyChainTradeLineItem>
+    <ram:ApplicableHeaderTradeAgreement>
+      <ram:SellerTradeParty>
+        <ram:Name>Muster GmbH</ram:Name>
+        <ram:SpecifiedTaxRegistration><ram:ID schemeID="VA">DE123456789</ram:ID></ram:SpecifiedTaxRegistration>
+      </ram:SellerTradeParty>
+      <ram:BuyerTradeParty><ram:Name>Client SARL</ram:Name></ram:BuyerTradeParty>
+    </ram:ApplicableHeaderTradeAgreement>
+    <ram:ApplicableHeaderTradeSettlement>
+      <ram:InvoiceCurrencyCode>EUR</ram:InvoiceCurrencyCode>
+      <ram:SpecifiedTradePaymentTerms>
+        <ram:DueDateDateTime><udt:DateTimeString format="102">20250302</udt:DateTimeString></ram:DueDateDateTime>
+      </ram:SpecifiedTradePaymentTerms>
+      <ram:SpecifiedTradeSettlementHeaderMonetarySummation>
+        <ram:TaxBasisTotalAmount>500.00</ram:TaxBasisTotalAmount>
+        <ram:TaxTotalAmount currencyID="EUR">95.00</ram:TaxTotalAmount>
+        <ram:GrandTotalAmount>595.00</ram:GrandTotalAmount>
+      </ram:SpecifiedTradeSettlementHeaderMonetarySummation>
+    </ram:ApplicableHeaderTradeSettlement>
+  </rsm:SupplyChainTradeTransaction>
+</rsm:CrossIndustryInvoice>"#;
+
+    #[test]
+    fn test_parse_cii() {
+        let invoice = parse_cii(SAMPLE_CII.as_bytes()).unwrap();
+        let values: Vec<_> = invoice.fields.iter().map(|f| &f.field_type).collect();
+        assert_eq!(
+            values,
+            vec![
+                &InvoiceField::InvoiceNumber("RE-2025-0042".into()),
+                &InvoiceField::InvoiceDate("2025-01-31".into()),
+                &InvoiceField::ArticleNumber("ART-7".into()),
+                &InvoiceField::LineItemDescription("Beratung & Support".into()),
+                &InvoiceField::LineItemUnitPrice(100.0),
+                &InvoiceField::LineItemQuantity(5.0),
+                &InvoiceField::SupplierName("Muster GmbH".into()),
+                &InvoiceField::VatNumber("DE123456789".into()),
+                &InvoiceField::CustomerName("Client SARL".into()),
+                &InvoiceField::Currency("EUR".into()),
+                &InvoiceField::DueDate("2025-03-02".into()),
+                &InvoiceField::NetAmount(500.0),
+                &InvoiceField::TaxAmount(95.0),
+                &InvoiceField::TotalAmount(595.0),
+            ]
+        );
+        assert_eq!(invoice.metadata.extraction_confidence, 1.0);
+    }
+
+    #[test]
+    fn test_parse_cii_rejects_other_xml() {
+        assert!(parse_cii(b"<Invoice><ID>1</ID></Invoice>").is_err());
+        assert!(parse_cii(b"<rsm:CrossIndustryInvoice><a></b>").is_err());
+    }
+
+    #[test]
+    fn test_hybrid_invoice_round_trip() {
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        doc.set_facturx(
+            FacturX::new(FacturXProfile::En16931, SAMPLE_CII.as_bytes().to_vec())
+                .with_output_intent(IccProfile::new(
+                    "sRGB IEC61966-2.1".to_string(),
+                    vec![0; 128],
+                    crate::graphics::IccColorSpace::Rgb,
+                )),
+        );
+        let pdf = doc.to_bytes().unwrap();
+
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap());
+        let attachments = document.attachments().unwrap();
+        assert_eq!(attachments[0].name, "factur-x.xml");
+        assert_eq!(
+            attachments[0].file.relationship,
+            Some(AFRelationship::Alternative)
+        );
+        assert_eq!(attachments[0].file.mime_type.as_deref(), Some("text/xml"));
+        assert!(attachments[0].file.modification_date.is_some());
+        assert_eq!(
+            read_xml(&document).unwrap().as_deref(),
+            Some(SAMPLE_CII.as_bytes())
+        );
+
+        let catalog = document.catalog().unwrap();
+        assert!(catalog.contains_key("OutputIntents"));
+        let metadata = document.resolve(catalog.get("Metadata").unwrap()).unwrap();
+        let stream = metadata.as_stream().unwrap();
+        let packet = String::from_utf8_lossy(&stream.data);
+        assert!(packet.contains("<pdfaid:part>3</pdfaid:part>"));
+        assert!(packet.contains("<fx:ConformanceLevel>EN 16931</fx:ConformanceLevel>"));
+        assert!(packet.contains("<fx:DocumentFileName>factur-x.xml</fx:DocumentFileName>"));
+        assert!(packet.contains(FACTURX_NAMESPACE));
+        // Saved without a PDF/A configuration, but checked as PDF/A-3b
+        assert!(packet.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+        assert!(document.trailer().id().is_some());
+    }
+
+    #[test]
+    fn test_hybrid_invoice_is_written_as_pdfa() {
+        let invoice = FacturX::new(FacturXProfile::Basic, SAMPLE_CII.as_bytes().to_vec());
+
+        // The standard 14 fonts cannot be embedded
+        let mut page = Page::a4();
+        page.text()
+            .set_font(crate::Font::Helvetica, 12.0)
+            .at(72.0, 700.0)
+            .write("Invoice")
+            .unwrap();
+        let mut doc = Document::new();
+        doc.add_page(page);
+        doc.set_facturx(invoice.clone());
+        assert!(doc.to_bytes().is_err());
+
+        // Nor can an invoice be another PDF/A level
+        let mut doc = Document::new();
+        doc.add_page(Page::a4());
+        doc.set_facturx(invoice);
+        doc.set_pdfa(Some(PdfALevel::A2b));
+        assert!(doc.to_bytes().is_err());
+        doc.set_pdfa(None);
+        let pdf = doc.to_bytes().unwrap();
+        let report = crate::verification::pdfa::check_pdfa(&pdf, PdfALevel::A3b).unwrap();
+        assert!(report.is_compliant(), "{:?}", report.violations);
+    }
+
+    #[test]
+    fn test_extractor_prefers_embedded_xml() {
+        // Hybrid invoices are PDF/A-3b, which needs an embedded font
+        let mut doc = Document::new();
+        doc.add_font_from_bytes("Body", crate::text::shaping::test_font::font(&[]))
+            .unwrap();
+        let mut page = Page::a4();
+        page.text()
+            .set_font(crate::Font::Custom("Body".to_string()), 12.0)
+            .at(72.0, 700.0)
+            .write("Invoice Number: INV-TEXT-1")
+            .unwrap();
+        doc.add_page(page);
+        let plain = doc.to_bytes().unwrap();
+        doc.set_facturx(FacturX::new(
+            FacturXProfile::Basic,
+            SAMPLE_CII.as_bytes().to_vec(),
+        ));
+        let hybrid = doc.to_bytes().unwrap();
+
+        let extractor = super::super::InvoiceExtractor::builder()
+            .with_language("en")
+            .build();
+        let number = |pdf: Vec<u8>| {
+            let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf)).unwrap());
+            let invoice = extractor.extract_from_document(&document, 0).unwrap();
+            invoice
+                .get_field("Invoice Number")
+                .unwrap()
+                .raw_text
+                .clone()
+        };
+        assert_eq!(number(plain), "INV-TEXT-1");
+        assert_eq!(number(hybrid), "RE-2025-0042");
+    }
+}
@@ -104,6 +104,13 @@
 //! - `invoice_extraction_basic.rs` - Simple extraction with field display
 //! - `invoice_extraction_advanced.rs` - Batch processing and JSON export
 //!
+//! # Hybrid E-Invoices
+//!
+//! Factur-X, ZUGFeRD and XRechnung PDFs embed the invoice as CII XML.
+//! [`InvoiceExtractor::extract_from_document`] reads that XML when present
+//! instead of matching patterns, and [`FacturX`] embeds it in generated
+//! documents. See the [`facturx`] module.
+//!
 //! # Limitations (MVP)
 //!
 //! - Single-page invoices only
@@ -118,12 +125,14 @@
 
 pub mod error;
 pub mod extractor;
+pub mod facturx;
 pub mod patterns;
 pub mod types;
 pub mod validators;
 
 pub use error::{ExtractionError, Result};
 pub use extractor::{InvoiceExtractor, InvoiceExtractorBuilder};
+pub use facturx::{FacturX, FacturXProfile};
 pub use patterns::{FieldPattern, InvoiceFieldType, PatternLibrary};
 pub use types::{
     BoundingBox, ExtractedField, InvoiceData, InvoiceField, InvoiceMetadata, Language,
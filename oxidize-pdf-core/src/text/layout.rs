@@ -5,6 +5,9 @@
 
 use crate::error::PdfError;
 use crate::graphics::{Color, GraphicsContext};
+use crate::text::bidi::{self, Direction};
+use crate::text::paragraph::{typeset_shaped_paragraph, ParagraphOptions, TypesetLine};
+use crate::text::shaping::ShapingFonts;
 use crate::text::{Font, TextAlign};
 
 /// Column layout configuration
@@ -33,8 +36,12 @@ pub struct ColumnOptions {
     pub line_height: f64,
     /// Text color
     pub text_color: Color,
-    /// Text alignment within columns
+    /// Text alignment within columns; left and right are swapped in
+    /// right-to-left text
     pub text_align: TextAlign,
+    /// Text direction; taken from the first strong character of the
+    /// content if `None`. Right-to-left columns are filled from the right.
+    pub direction: Option<Direction>,
     /// Whether to balance columns (distribute content evenly)
     pub balance_columns: bool,
     /// Whether to draw column separators
@@ -43,6 +50,9 @@ pub struct ColumnOptions {
     pub separator_color: Color,
     /// Separator width
     pub separator_width: f64,
+    /// Typesetting of the text with optimal line breaks, hyphenation and
+    /// widow and orphan control; lines are filled one at a time if `None`
+    pub typesetting: Option<ParagraphOptions>,
 }
 
 impl Default for ColumnOptions {
@@ -53,10 +63,12 @@ impl Default for ColumnOptions {
             line_height: 1.2,
             text_color: Color::black(),
             text_align: TextAlign::Left,
+            direction: None,
             balance_columns: true,
             show_separators: false,
             separator_color: Color::gray(0.7),
             separator_width: 0.5,
+            typesetting: None,
         }
     }
 }
@@ -192,29 +204,73 @@ impl ColumnLayout {
         start_y: f64,
         column_height: f64,
     ) -> Result<(), PdfError> {
-        // Create flow context
-        let mut flow_context = self.create_flow_context(start_y, column_height);
+        let direction = self
+            .options
+            .direction
+            .or_else(|| bidi::paragraph_direction(&content.text))
+            .unwrap_or_default();
+
+        if let Some(typesetting) = &self.options.typesetting {
+            let columns = self.typeset_columns(
+                &content.text,
+                typesetting,
+                column_height,
+                graphics.shaping_fonts(),
+            );
+            for (col_index, lines) in columns.iter().enumerate() {
+                let column_x = start_x + self.column_x(col_index, direction);
+                self.render_typeset_column(
+                    graphics,
+                    lines,
+                    typesetting,
+                    column_x,
+                    start_y,
+                    direction,
+                )?;
+            }
+        } else {
+            // Create flow context
+            let mut flow_context = self.create_flow_context(start_y, column_height);
 
-        // Split text into words for flowing
-        let words = self.split_text_into_words(&content.text);
+            // Split text into words for flowing
+            let words = self.split_text_into_words(&content.text);
 
-        // Flow text across columns
-        self.flow_text_across_columns(&words, &mut flow_context)?;
+            // Flow text across columns
+            self.flow_text_across_columns(&words, &mut flow_context)?;
 
-        // Render each column
-        for (col_index, column_content) in flow_context.column_contents.iter().enumerate() {
-            let column_x = start_x + self.column_x_position(col_index);
-            self.render_column(graphics, column_content, column_x, start_y)?;
+            // Render each column
+            for (col_index, column_content) in flow_context.column_contents.iter().enumerate() {
+                let column_x = start_x + self.column_x(col_index, direction);
+                self.render_column(graphics, column_content, column_x, start_y, direction)?;
+            }
         }
 
         // Draw column separators if enabled
         if self.options.show_separators {
-            self.draw_separators(graphics, start_x, start_y, column_height)?;
+            self.draw_separators(graphics, start_x, start_y, column_height, direction)?;
         }
 
         Ok(())
     }
 
+    /// X position of a column, from the right in right-to-left layouts
+    fn column_x(&self, index: usize, direction: Direction) -> f64 {
+        match direction {
+            Direction::LeftToRight => self.column_x_position(index),
+            Direction::RightToLeft => {
+                self.total_width - self.column_x_position(index) - self.column_widths[index]
+            }
+        }
+    }
+
+    /// Width of the narrowest column, to which typeset text is set
+    fn narrowest_column_width(&self) -> f64 {
+        self.column_widths
+            .iter()
+            .copied()
+            .fold(f64::INFINITY, f64::min)
+    }
+
     /// Split text into words for flowing
     fn split_text_into_words(&self, text: &str) -> Vec<String> {
         text.split_whitespace()
@@ -283,7 +339,7 @@ impl ColumnLayout {
     /// Estimate text width (simple approximation)
     fn estimate_text_width(&self, text: &str) -> f64 {
         // Simple approximation: character count * font size * 0.6
-        text.len() as f64 * self.options.font_size * 0.6
+        text.chars().count() as f64 * self.options.font_size * 0.6
     }
 
     /// Balance content across columns
@@ -310,6 +366,120 @@ impl ColumnLayout {
         Ok(())
     }
 
+    /// Typeset text to the narrowest column and distribute its lines,
+    /// keeping the widow and orphan lines of the options together; the last
+    /// column takes the lines that do not fit
+    fn typeset_columns(
+        &self,
+        text: &str,
+        typesetting: &ParagraphOptions,
+        column_height: f64,
+        fonts: &ShapingFonts,
+    ) -> Vec<Vec<TypesetLine>> {
+        let width = self.narrowest_column_width();
+        let mut lines = typeset_shaped_paragraph(
+            text,
+            &self.options.font,
+            self.options.font_size,
+            width,
+            typesetting,
+            fonts,
+        );
+
+        let line_height = self.options.font_size * self.options.line_height;
+        let capacity = ((column_height / line_height).floor() as usize).max(1);
+        let lines_per_column = if self.options.balance_columns {
+            lines.len().div_ceil(self.column_count).min(capacity)
+        } else {
+            capacity
+        };
+
+        let mut columns = Vec::with_capacity(self.column_count);
+        for col_index in 0..self.column_count {
+            let count = if col_index + 1 == self.column_count {
+                lines.len()
+            } else {
+                match typesetting.lines_before_break(lines.len(), lines_per_column) {
+                    // Too few lines to break; keep them together if they fit
+                    0 => lines.len().min(capacity),
+                    count => count,
+                }
+            };
+            let rest = lines.split_off(count);
+            columns.push(lines);
+            lines = rest;
+        }
+        columns
+    }
+
+    /// Render a single column of typeset lines
+    fn render_typeset_column(
+        &self,
+        graphics: &mut GraphicsContext,
+        lines: &[TypesetLine],
+        typesetting: &ParagraphOptions,
+        column_x: f64,
+        start_y: f64,
+        direction: Direction,
+    ) -> Result<(), PdfError> {
+        let line_height = self.options.font_size * self.options.line_height;
+        let column_width = self.narrowest_column_width();
+        let max_letter_spacing = typesetting.max_letter_spacing * self.options.font_size;
+        let mut current_y = start_y;
+        let text_align = match (self.options.text_align, direction) {
+            (TextAlign::Left, Direction::RightToLeft) => TextAlign::Right,
+            (TextAlign::Right, Direction::RightToLeft) => TextAlign::Left,
+            (text_align, _) => text_align,
+        };
+
+        graphics.save_state();
+        graphics.set_font(self.options.font.clone(), self.options.font_size);
+        graphics.set_fill_color(self.options.text_color);
+
+        for line in lines {
+            let (character_spacing, word_spacing) = match text_align {
+                TextAlign::Justified => line.justification(column_width, max_letter_spacing),
+                _ => (0.0, 0.0),
+            };
+            let text_x = match text_align {
+                TextAlign::Left => column_x,
+                TextAlign::Center => column_x + (column_width - line.width) / 2.0,
+                TextAlign::Right => column_x + column_width - line.width,
+                TextAlign::Justified => {
+                    if direction == Direction::RightToLeft
+                        && (character_spacing, word_spacing) == (0.0, 0.0)
+                    {
+                        column_x + column_width - line.width
+                    } else {
+                        column_x
+                    }
+                }
+            };
+
+            graphics.begin_text();
+            graphics.set_text_position(text_x, current_y);
+            if character_spacing != 0.0 {
+                graphics.set_character_spacing(character_spacing);
+            }
+            if word_spacing != 0.0 {
+                graphics.set_word_spacing(word_spacing);
+            }
+            graphics.show_text(&line.text)?;
+            if character_spacing != 0.0 {
+                graphics.set_character_spacing(0.0);
+            }
+            if word_spacing != 0.0 {
+                graphics.set_word_spacing(0.0);
+            }
+            graphics.end_text();
+
+            current_y -= line_height;
+        }
+
+        graphics.restore_state();
+        Ok(())
+    }
+
     /// Render a single column
     fn render_column(
         &self,
@@ -317,9 +487,15 @@ impl ColumnLayout {
         lines: &[String],
         column_x: f64,
         start_y: f64,
+        direction: Direction,
     ) -> Result<(), PdfError> {
         let line_height = self.options.font_size * self.options.line_height;
         let mut current_y = start_y;
+        let text_align = match (self.options.text_align, direction) {
+            (TextAlign::Left, Direction::RightToLeft) => TextAlign::Right,
+            (TextAlign::Right, Direction::RightToLeft) => TextAlign::Left,
+            (text_align, _) => text_align,
+        };
 
         graphics.save_state();
         graphics.set_font(self.options.font.clone(), self.options.font_size);
@@ -328,7 +504,7 @@ impl ColumnLayout {
         for line in lines {
             graphics.begin_text();
 
-            match self.options.text_align {
+            match text_align {
                 TextAlign::Left => {
                     graphics.set_text_position(column_x, current_y);
                     graphics.show_text(line)?;
@@ -369,6 +545,7 @@ impl ColumnLayout {
         start_x: f64,
         start_y: f64,
         column_height: f64,
+        direction: Direction,
     ) -> Result<(), PdfError> {
         if self.column_count <= 1 {
             return Ok(());
@@ -379,10 +556,17 @@ impl ColumnLayout {
         graphics.set_line_width(self.options.separator_width);
 
         for i in 0..self.column_count - 1 {
-            let separator_x = start_x
-                + self.column_x_position(i)
-                + self.column_widths[i]
-                + (self.column_gap / 2.0);
+            let separator_x = match direction {
+                Direction::LeftToRight => {
+                    start_x
+                        + self.column_x_position(i)
+                        + self.column_widths[i]
+                        + (self.column_gap / 2.0)
+                }
+                Direction::RightToLeft => {
+                    start_x + self.column_x(i, direction) - (self.column_gap / 2.0)
+                }
+            };
 
             graphics.move_to(separator_x, start_y);
             graphics.line_to(separator_x, start_y - column_height);
@@ -463,6 +647,7 @@ impl TextFormat {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::text::shaping::test_font;
 
     #[test]
     fn test_column_layout_creation() {
@@ -573,6 +758,95 @@ mod tests {
         assert!(layout.options.show_separators);
     }
 
+    #[test]
+    fn test_right_to_left_columns() {
+        let layout = ColumnLayout::new(2, 400.0, 20.0);
+        assert_eq!(layout.column_x(0, Direction::RightToLeft), 210.0);
+        assert_eq!(layout.column_x(1, Direction::RightToLeft), 0.0);
+
+        // Right-to-left text is aligned right by default and its glyphs are
+        // shown in visual order
+        let fonts = ShapingFonts::new();
+        fonts
+            .register("ColumnsHebrew", &test_font::font(&[]))
+            .unwrap();
+        let mut layout = ColumnLayout::new(1, 100.0, 0.0);
+        layout.set_options(ColumnOptions {
+            font: Font::Custom("ColumnsHebrew".to_string()),
+            ..Default::default()
+        });
+        let mut graphics = GraphicsContext::new();
+        graphics.set_shaping_fonts(&fonts);
+        layout
+            .render(&mut graphics, &ColumnContent::new("אב"), 0.0, 700.0, 500.0)
+            .unwrap();
+
+        let shaped = fonts.shape("ColumnsHebrew", "אב").unwrap();
+        let glyphs: Vec<u16> = shaped.glyphs.iter().map(|g| g.glyph).collect();
+        assert_eq!(glyphs, vec![test_font::glyph('ב'), test_font::glyph('א')]);
+        assert!(graphics.operations().contains(
+            "88.00 700.00 Td\n/Span <</ActualText <FEFF05D005D1>>> BDC\n[<05D1> <05D0>] TJ\nEMC"
+        ));
+    }
+
+    #[test]
+    fn test_typeset_columns_widows_and_orphans() {
+        let mut layout = ColumnLayout::new(2, 130.0, 10.0);
+        layout.set_options(ColumnOptions {
+            font: Font::Courier,
+            typesetting: Some(ParagraphOptions::default()),
+            ..Default::default()
+        });
+        let text = "aaaa bbbb cccc dddd eeee ffff";
+        let counts = |layout: &ColumnLayout| -> Vec<usize> {
+            let columns = layout.typeset_columns(
+                text,
+                layout.options.typesetting.as_ref().unwrap(),
+                500.0,
+                &ShapingFonts::new(),
+            );
+            columns.iter().map(Vec::len).collect()
+        };
+
+        // Balancing three lines would leave a widow
+        assert_eq!(counts(&layout), vec![3, 0]);
+
+        layout.options.typesetting = Some(ParagraphOptions {
+            widows: 1,
+            orphans: 1,
+            ..Default::default()
+        });
+        assert_eq!(counts(&layout), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_render_typeset_justified_columns() {
+        let mut layout = ColumnLayout::new(2, 130.0, 10.0);
+        layout.set_options(ColumnOptions {
+            text_align: TextAlign::Justified,
+            typesetting: Some(ParagraphOptions::default()),
+            ..Default::default()
+        });
+        let mut graphics = GraphicsContext::new();
+        layout
+            .render(
+                &mut graphics,
+                &ColumnContent::new(
+                    "Either party may terminate this agreement with thirty days of written \
+                     notice to the other party.",
+                ),
+                0.0,
+                700.0,
+                500.0,
+            )
+            .unwrap();
+        let ops = graphics.operations();
+        assert!(ops.contains(" Tw\n"));
+        assert!(ops.contains("0.00 Tw\n"));
+        // The second column starts after the first and the gap
+        assert!(ops.contains("70.00 700.00 Td\n"));
+    }
+
     #[test]
     #[should_panic(expected = "Column count must be greater than 0")]
     fn test_zero_columns_panic() {
@@ -11,6 +11,8 @@
 
 use crate::error::PdfError;
 use crate::graphics::{Color, GraphicsContext};
+use crate::structure::page_tags::{self, PageTags};
+use crate::structure::{StandardStructureType, StructureElement};
 use crate::text::{Font, TextAlign};
 
 /// List style for ordered lists
@@ -260,22 +262,39 @@ impl OrderedList {
     /// Render the list to a graphics context
     pub fn render(&self, graphics: &mut GraphicsContext) -> Result<(), PdfError> {
         let (x, y) = self.position;
-        self.render_recursive(graphics, x, y, 0)?;
+        self.render_recursive(graphics, None, x, y, 0)?;
+        Ok(())
+    }
+
+    /// Render the list with its items recorded as list structure
+    pub(crate) fn render_tagged(
+        &self,
+        graphics: &mut GraphicsContext,
+        tags: Option<&mut PageTags>,
+    ) -> Result<(), PdfError> {
+        let (x, y) = self.position;
+        self.render_recursive(graphics, tags, x, y, 0)?;
         Ok(())
     }
 
     fn render_recursive(
         &self,
         graphics: &mut GraphicsContext,
+        mut tags: Option<&mut PageTags>,
         x: f64,
         mut y: f64,
         level: usize,
     ) -> Result<f64, PdfError> {
         let indent = x + (level as f64 * self.options.indent);
 
+        begin_tag_group(&mut tags, StandardStructureType::L);
         for (index, item) in self.items.iter().enumerate() {
+            begin_tag_group(&mut tags, StandardStructureType::LI);
             // Draw marker
             let marker = self.generate_marker(index);
+            begin_marked_content(graphics, &mut tags, |tags| {
+                tags.begin_content(StructureElement::new(StandardStructureType::Lbl))
+            });
             graphics.save_state();
             graphics.set_font(self.options.marker_font.clone(), self.options.font_size);
             let marker_color = self.options.marker_color.unwrap_or(self.options.text_color);
@@ -285,6 +304,7 @@ impl OrderedList {
             graphics.show_text(&marker)?;
             graphics.end_text();
             graphics.restore_state();
+            end_marked_content(graphics, &tags);
 
             // Draw text (with wrapping support)
             let text_x =
@@ -298,6 +318,8 @@ impl OrderedList {
             };
 
             // Draw each line of text
+            begin_tag_group(&mut tags, StandardStructureType::LBody);
+            begin_marked_content(graphics, &mut tags, PageTags::begin_group_content);
             let mut line_y = y;
             for (line_index, line) in text_lines.iter().enumerate() {
                 graphics.save_state();
@@ -322,6 +344,7 @@ impl OrderedList {
                 }
             }
 
+            end_marked_content(graphics, &tags);
             y = line_y;
 
             y +=
@@ -329,6 +352,9 @@ impl OrderedList {
 
             // Draw separator if enabled
             if self.options.draw_separator && index < self.items.len() - 1 {
+                begin_marked_content(graphics, &mut tags, |_| {
+                    page_tags::BEGIN_ARTIFACT.to_string()
+                });
                 graphics.save_state();
                 graphics.set_stroke_color(self.options.separator_color);
                 graphics.set_line_width(self.options.separator_width);
@@ -339,6 +365,7 @@ impl OrderedList {
                 );
                 graphics.stroke();
                 graphics.restore_state();
+                end_marked_content(graphics, &tags);
                 y += 5.0;
             }
 
@@ -348,16 +375,31 @@ impl OrderedList {
                     ListElement::Ordered(list) => {
                         let mut child_list = list.clone();
                         child_list.options = self.options.clone();
-                        child_list.render_recursive(graphics, x, y, level + 1)?
+                        child_list.render_recursive(
+                            graphics,
+                            tags.as_deref_mut(),
+                            x,
+                            y,
+                            level + 1,
+                        )?
                     }
                     ListElement::Unordered(list) => {
                         let mut child_list = list.clone();
                         child_list.options = self.options.clone();
-                        child_list.render_recursive(graphics, x, y, level + 1)?
+                        child_list.render_recursive(
+                            graphics,
+                            tags.as_deref_mut(),
+                            x,
+                            y,
+                            level + 1,
+                        )?
                     }
                 };
             }
+            end_tag_group(&mut tags);
+            end_tag_group(&mut tags);
         }
+        end_tag_group(&mut tags);
 
         Ok(y)
     }
@@ -492,13 +534,25 @@ impl UnorderedList {
     /// Render the list to a graphics context
     pub fn render(&self, graphics: &mut GraphicsContext) -> Result<(), PdfError> {
         let (x, y) = self.position;
-        self.render_recursive(graphics, x, y, 0)?;
+        self.render_recursive(graphics, None, x, y, 0)?;
+        Ok(())
+    }
+
+    /// Render the list with its items recorded as list structure
+    pub(crate) fn render_tagged(
+        &self,
+        graphics: &mut GraphicsContext,
+        tags: Option<&mut PageTags>,
+    ) -> Result<(), PdfError> {
+        let (x, y) = self.position;
+        self.render_recursive(graphics, tags, x, y, 0)?;
         Ok(())
     }
 
     fn render_recursive(
         &self,
         graphics: &mut GraphicsContext,
+        mut tags: Option<&mut PageTags>,
         x: f64,
         mut y: f64,
         level: usize,
@@ -506,8 +560,13 @@ impl UnorderedList {
         let indent = x + (level as f64 * self.options.indent);
         let bullet = self.get_bullet_char();
 
+        begin_tag_group(&mut tags, StandardStructureType::L);
         for (index, item) in self.items.iter().enumerate() {
+            begin_tag_group(&mut tags, StandardStructureType::LI);
             // Draw bullet
+            begin_marked_content(graphics, &mut tags, |tags| {
+                tags.begin_content(StructureElement::new(StandardStructureType::Lbl))
+            });
             graphics.save_state();
             graphics.set_font(self.options.marker_font.clone(), self.options.font_size);
             let marker_color = self.options.marker_color.unwrap_or(self.options.text_color);
@@ -517,6 +576,7 @@ impl UnorderedList {
             graphics.show_text(bullet)?;
             graphics.end_text();
             graphics.restore_state();
+            end_marked_content(graphics, &tags);
 
             // Draw text (with wrapping support)
             let text_x = indent + self.options.font_size + self.options.marker_spacing;
@@ -529,6 +589,8 @@ impl UnorderedList {
             };
 
             // Draw each line of text
+            begin_tag_group(&mut tags, StandardStructureType::LBody);
+            begin_marked_content(graphics, &mut tags, PageTags::begin_group_content);
             let mut line_y = y;
             for (line_index, line) in text_lines.iter().enumerate() {
                 graphics.save_state();
@@ -553,6 +615,7 @@ impl UnorderedList {
                 }
             }
 
+            end_marked_content(graphics, &tags);
             y = line_y;
 
             y +=
@@ -560,6 +623,9 @@ impl UnorderedList {
 
             // Draw separator if enabled
             if self.options.draw_separator && (index < self.items.len() - 1) {
+                begin_marked_content(graphics, &mut tags, |_| {
+                    page_tags::BEGIN_ARTIFACT.to_string()
+                });
                 graphics.save_state();
                 graphics.set_stroke_color(self.options.separator_color);
                 graphics.set_line_width(self.options.separator_width);
@@ -570,6 +636,7 @@ impl UnorderedList {
                 );
                 graphics.stroke();
                 graphics.restore_state();
+                end_marked_content(graphics, &tags);
                 y += 5.0;
             }
 
@@ -579,16 +646,31 @@ impl UnorderedList {
                     ListElement::Ordered(list) => {
                         let mut child_list = list.clone();
                         child_list.options = self.options.clone();
-                        child_list.render_recursive(graphics, x, y, level + 1)?
+                        child_list.render_recursive(
+                            graphics,
+                            tags.as_deref_mut(),
+                            x,
+                            y,
+                            level + 1,
+                        )?
                     }
                     ListElement::Unordered(list) => {
                         let mut child_list = list.clone();
                         child_list.options = self.options.clone();
-                        child_list.render_recursive(graphics, x, y, level + 1)?
+                        child_list.render_recursive(
+                            graphics,
+                            tags.as_deref_mut(),
+                            x,
+                            y,
+                            level + 1,
+                        )?
                     }
                 };
             }
+            end_tag_group(&mut tags);
+            end_tag_group(&mut tags);
         }
+        end_tag_group(&mut tags);
 
         Ok(y)
     }
@@ -634,6 +716,38 @@ impl UnorderedList {
 }
 
 /// Convert a number to Roman numerals
+/// Opens a structure element when the list is tagged
+fn begin_tag_group(tags: &mut Option<&mut PageTags>, structure_type: StandardStructureType) {
+    if let Some(tags) = tags {
+        tags.begin_group(StructureElement::new(structure_type));
+    }
+}
+
+/// Closes the innermost structure element when the list is tagged
+fn end_tag_group(tags: &mut Option<&mut PageTags>) {
+    if let Some(tags) = tags {
+        tags.end_group();
+    }
+}
+
+/// Begins marked content when the list is tagged
+fn begin_marked_content(
+    graphics: &mut GraphicsContext,
+    tags: &mut Option<&mut PageTags>,
+    operator: impl FnOnce(&mut PageTags) -> String,
+) {
+    if let Some(tags) = tags {
+        graphics.add_command(operator(tags).trim_end());
+    }
+}
+
+/// Ends marked content when the list is tagged
+fn end_marked_content(graphics: &mut GraphicsContext, tags: &Option<&mut PageTags>) {
+    if tags.is_some() {
+        graphics.add_command(page_tags::END_MARKED_CONTENT.trim_end());
+    }
+}
+
 fn to_roman(num: u32) -> String {
     let values = [
         (1000, "M"),
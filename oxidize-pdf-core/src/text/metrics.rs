@@ -1,3 +1,4 @@
+use crate::text::shaping::ShapingFonts;
 use crate::text::Font;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -150,7 +151,6 @@ pub fn measure_text(text: &str, font: Font, font_size: f64) -> f64 {
         // Symbol and ZapfDingbats need special handling
         return text.len() as f64 * font_size * 0.6;
     }
-
     let metrics = get_font_metrics(&font);
 
     let width_units: u32 = text.chars().map(|ch| metrics.char_width(ch) as u32).sum();
@@ -158,12 +158,22 @@ pub fn measure_text(text: &str, font: Font, font_size: f64) -> f64 {
     (width_units as f64 / 1000.0) * font_size
 }
 
+/// Measure the width of text as it is shown with `fonts`: text in a custom
+/// font registered there is measured shaped, with its ligatures and kerning
+pub fn measure_shaped_text(text: &str, font: Font, font_size: f64, fonts: &ShapingFonts) -> f64 {
+    if let Font::Custom(name) = &font {
+        if let Some(shaped) = fonts.shape(name, text) {
+            return shaped.width(font_size);
+        }
+    }
+    measure_text(text, font, font_size)
+}
+
 /// Measure the width of a single character
 pub fn measure_char(ch: char, font: Font, font_size: f64) -> f64 {
     if font.is_symbolic() {
         return font_size * 0.6;
     }
-
     let metrics = get_font_metrics(&font);
 
     (metrics.char_width(ch) as f64 / 1000.0) * font_size
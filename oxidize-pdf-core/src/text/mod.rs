@@ -1,3 +1,4 @@
+pub mod bidi;
 pub mod cmap;
 mod encoding;
 pub mod extraction;
@@ -7,12 +8,15 @@ mod font;
 pub mod font_manager;
 pub mod fonts;
 mod header_footer;
+pub mod hyphenation;
 pub mod invoice;
 mod layout;
 mod list;
 pub mod metrics;
 pub mod ocr;
+pub mod paragraph;
 pub mod plaintext;
+pub mod shaping;
 pub mod structured;
 pub mod table;
 pub mod table_detection;
@@ -24,25 +28,30 @@ mod cmap_tests;
 #[cfg(feature = "ocr-tesseract")]
 pub mod tesseract_provider;
 
+pub use bidi::Direction;
 pub use encoding::TextEncoding;
 pub use extraction::{ExtractedText, ExtractionOptions, TextExtractor, TextFragment};
 pub use flow::{TextAlign, TextFlowContext};
 pub use font::{Font, FontEncoding, FontFamily, FontWithEncoding};
 pub use font_manager::{CustomFont, FontDescriptor, FontFlags, FontManager, FontMetrics, FontType};
 pub use header_footer::{HeaderFooter, HeaderFooterOptions, HeaderFooterPosition};
+pub use hyphenation::Hyphenator;
 pub use layout::{ColumnContent, ColumnLayout, ColumnOptions, TextFormat};
 pub use list::{
     BulletStyle, ListElement, ListItem, ListOptions, ListStyle as ListStyleEnum, OrderedList,
     OrderedListStyle, UnorderedList,
 };
-pub use metrics::{measure_char, measure_text, split_into_words};
+pub use metrics::{measure_char, measure_shaped_text, measure_text, split_into_words};
 pub use ocr::{
     CharacterConfidence, CorrectionCandidate, CorrectionReason, CorrectionSuggestion,
     CorrectionType, FragmentType, ImagePreprocessing, MockOcrProvider, OcrEngine, OcrError,
     OcrOptions, OcrPostProcessor, OcrProcessingResult, OcrProvider, OcrRegion, OcrResult,
     OcrTextFragment, WordConfidence,
 };
+pub use paragraph::{typeset_paragraph, typeset_shaped_paragraph, ParagraphOptions, TypesetLine};
 pub use plaintext::{LineBreakMode, PlainTextConfig, PlainTextExtractor, PlainTextResult};
+pub use shaping::ShapingFonts;
+pub(crate) use shaping::UnshapedText;
 pub use table::{HeaderStyle, Table, TableCell, TableOptions};
 pub use validation::{MatchType, TextMatch, TextValidationResult, TextValidator};
 
@@ -95,6 +104,10 @@ pub struct TextContext {
     stroke_color: Option<Color>,
     // Track used characters for font subsetting (fixes issue #97)
     used_characters: HashSet<char>,
+    // Fonts of the page, shaping text in custom fonts
+    shaping_fonts: ShapingFonts,
+    // Text in custom fonts the page did not have yet
+    unshaped: Vec<UnshapedText>,
 }
 
 impl Default for TextContext {
@@ -120,9 +133,20 @@ impl TextContext {
             fill_color: None,
             stroke_color: None,
             used_characters: HashSet::new(),
+            shaping_fonts: ShapingFonts::new(),
+            unshaped: Vec::new(),
         }
     }
 
+    /// Shape text in custom fonts with these fonts, including text already
+    /// written in them
+    pub(crate) fn set_shaping_fonts(&mut self, fonts: &ShapingFonts) {
+        self.shaping_fonts = fonts.clone();
+        let mut operations = std::mem::take(&mut self.operations).into_bytes();
+        fonts.shape_unshaped(&mut self.unshaped, &mut operations);
+        self.operations = String::from_utf8(operations).expect("Shaped operations should be UTF-8");
+    }
+
     /// Get the characters used in this text context for font subsetting.
     ///
     /// This is used to determine which glyphs need to be embedded when using
@@ -185,47 +209,78 @@ impl TextContext {
 
         // Choose encoding based on font type
         match &self.current_font {
-            Font::Custom(_) => {
-                // For custom fonts (CJK), use UTF-16BE encoding with hex strings
-                let utf16_units: Vec<u16> = text.encode_utf16().collect();
-                let mut utf16be_bytes = Vec::new();
-
-                for unit in utf16_units {
-                    utf16be_bytes.push((unit >> 8) as u8); // High byte
-                    utf16be_bytes.push((unit & 0xFF) as u8); // Low byte
-                }
+            Font::Custom(name) => {
+                // Registered fonts are shaped; others are shown character
+                // by character
+                if let Some(shown) = self.shaping_fonts.show_text(
+                    name,
+                    text,
+                    None,
+                    self.font_size,
+                    self.character_spacing.unwrap_or(0.0),
+                    self.word_spacing.unwrap_or(0.0),
+                    self.text_rise.unwrap_or(0.0),
+                ) {
+                    self.operations.push_str(&shown);
+                } else {
+                    // For custom fonts (CJK), use UTF-16BE encoding with hex strings
+                    let visual = bidi::reorder(text, None);
+                    let utf16_units: Vec<u16> = visual.encode_utf16().collect();
+                    let mut utf16be_bytes = Vec::new();
+
+                    for unit in utf16_units {
+                        utf16be_bytes.push((unit >> 8) as u8); // High byte
+                        utf16be_bytes.push((unit & 0xFF) as u8); // Low byte
+                    }
 
-                // Write as hex string for Type0 fonts
-                self.operations.push('<');
-                for &byte in &utf16be_bytes {
-                    write!(&mut self.operations, "{:02X}", byte)
-                        .expect("Writing to String should never fail");
+                    // Write as hex string for Type0 fonts
+                    let mut shown = String::from("<");
+                    for &byte in &utf16be_bytes {
+                        write!(&mut shown, "{:02X}", byte)
+                            .expect("Writing to String should never fail");
+                    }
+                    shown.push_str("> Tj\n");
+                    let font_name = name.clone();
+                    let start = self.operations.len();
+                    self.push_visual(text, &visual, &shown);
+                    self.unshaped.push(UnshapedText {
+                        range: start..self.operations.len(),
+                        shown: self.operations[start..].to_string(),
+                        font_name,
+                        text: text.to_string(),
+                        base: None,
+                        font_size: self.font_size,
+                        character_spacing: self.character_spacing.unwrap_or(0.0),
+                        word_spacing: self.word_spacing.unwrap_or(0.0),
+                        rise: self.text_rise.unwrap_or(0.0),
+                    });
                 }
-                self.operations.push_str("> Tj\n");
             }
             _ => {
                 // For standard fonts, use WinAnsiEncoding with literal strings
+                let visual = bidi::reorder(text, None);
                 let encoding = TextEncoding::WinAnsiEncoding;
-                let encoded_bytes = encoding.encode(text);
+                let encoded_bytes = encoding.encode(&visual);
 
                 // Show text as a literal string
-                self.operations.push('(');
+                let mut shown = String::from("(");
                 for &byte in &encoded_bytes {
                     match byte {
-                        b'(' => self.operations.push_str("\\("),
-                        b')' => self.operations.push_str("\\)"),
-                        b'\\' => self.operations.push_str("\\\\"),
-                        b'\n' => self.operations.push_str("\\n"),
-                        b'\r' => self.operations.push_str("\\r"),
-                        b'\t' => self.operations.push_str("\\t"),
+                        b'(' => shown.push_str("\\("),
+                        b')' => shown.push_str("\\)"),
+                        b'\\' => shown.push_str("\\\\"),
+                        b'\n' => shown.push_str("\\n"),
+                        b'\r' => shown.push_str("\\r"),
+                        b'\t' => shown.push_str("\\t"),
                         // For bytes in the printable ASCII range, write as is
-                        0x20..=0x7E => self.operations.push(byte as char),
+                        0x20..=0x7E => shown.push(byte as char),
                         // For other bytes, write as octal escape sequences
-                        _ => write!(&mut self.operations, "\\{byte:03o}")
+                        _ => write!(&mut shown, "\\{byte:03o}")
                             .expect("Writing to String should never fail"),
                     }
                 }
-                self.operations.push_str(") Tj\n");
+                shown.push_str(") Tj\n");
+                self.push_visual(text, &visual, &shown);
             }
         }
 
@@ -238,6 +293,17 @@ impl TextContext {
         Ok(self)
     }
 
+    /// Add the operations showing `visual`, the text in visual order; in
+    /// an `ActualText` span if it differs from the text
+    fn push_visual(&mut self, text: &str, visual: &str, shown: &str) {
+        if visual == text {
+            self.operations.push_str(shown);
+        } else {
+            self.operations
+                .push_str(&bidi::actual_text_span(text, shown));
+        }
+    }
+
     pub fn write_line(&mut self, text: &str) -> Result<&mut Self> {
         self.write(text)?;
         self.text_matrix[5] -= self.font_size * 1.2; // Move down for next line
@@ -392,6 +458,7 @@ impl TextContext {
     /// Clear all operations and reset text state parameters
     pub fn clear(&mut self) {
         self.operations.clear();
+        self.unshaped.clear();
         self.character_spacing = None;
         self.word_spacing = None;
         self.horizontal_scaling = None;
@@ -497,6 +564,20 @@ mod tests {
         assert!(ops.contains("(Hello) Tj"));
     }
 
+    #[test]
+    fn test_write_right_to_left_text() {
+        let mut context = TextContext::new();
+        context
+            .set_font(Font::Custom("Unregistered".to_string()), 12.0)
+            .write("אב")
+            .unwrap();
+
+        // Shown reversed, with the text in logical order for extraction
+        assert!(context
+            .operations()
+            .contains("/Span <</ActualText <FEFF05D005D1>>> BDC\n<05D105D0> Tj\nEMC\n"));
+    }
+
     #[test]
     fn test_write_text_with_escaping() {
         let mut context = TextContext::new();
@@ -0,0 +1,570 @@
+//! Paragraph typesetting with optimal line breaking
+//!
+//! Lines are broken with the Knuth-Plass algorithm. The paragraph is a
+//! sequence of boxes (words and word fragments), glue (spaces, which
+//! stretch and shrink) and penalties (hyphenation points), and the breaks
+//! are chosen to minimise the demerits of all lines together, so the
+//! spacing is even over the paragraph instead of being decided line by
+//! line. Justified lines are filled with word spacing (`Tw`) and a little
+//! letter spacing (`Tc`).
+
+use crate::text::hyphenation::Hyphenator;
+use crate::text::{measure_shaped_text, Font, ShapingFonts};
+
+/// Penalty of a break that must not happen, or, negated, must happen
+const INFINITE_PENALTY: f64 = 10000.0;
+const HYPHEN_PENALTY: f64 = 50.0;
+const LINE_PENALTY: f64 = 10.0;
+/// Demerits of two hyphenated lines in a row
+const DOUBLE_HYPHEN_DEMERITS: f64 = 3000.0;
+/// Demerits of a tight line next to a loose one
+const ADJACENT_FITNESS_DEMERITS: f64 = 3000.0;
+/// Demerits of each point a line is too wide, more than those of any line
+/// that fits
+const OVERFULL_DEMERITS: f64 = 1e9;
+
+/// Options of the paragraph typesetter
+#[derive(Debug, Clone)]
+pub struct ParagraphOptions {
+    /// Largest adjustment ratio of a line: how far its spaces may stretch,
+    /// relative to their stretchability. Looser lines are only set when
+    /// the paragraph has no other breaks.
+    pub tolerance: f64,
+    /// Hyphenation of words; `None` breaks lines at spaces and hyphens only
+    pub hyphenator: Option<Hyphenator>,
+    /// Largest letter spacing added to justify a line, as a fraction of
+    /// the font size
+    pub max_letter_spacing: f64,
+    /// Fewest lines of a paragraph at the top of a column after a break
+    pub widows: usize,
+    /// Fewest lines of a paragraph at the bottom of a column before a break
+    pub orphans: usize,
+}
+
+impl Default for ParagraphOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 2.0,
+            hyphenator: None,
+            max_letter_spacing: 0.02,
+            widows: 2,
+            orphans: 2,
+        }
+    }
+}
+
+impl ParagraphOptions {
+    /// Lines of a paragraph of `lines` lines set before a column break,
+    /// where there is room for `available`: all of them if they fit,
+    /// otherwise as many as leave enough widow and orphan lines, which
+    /// may be none
+    pub fn lines_before_break(&self, lines: usize, available: usize) -> usize {
+        if lines <= available {
+            return lines;
+        }
+        let before = available.min(lines.saturating_sub(self.widows));
+        if before < self.orphans.max(1) {
+            0
+        } else {
+            before
+        }
+    }
+}
+
+/// A line of a typeset paragraph
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypesetLine {
+    /// Text of the line, with a hyphen if it ends inside a word
+    pub text: String,
+    /// Natural width in points
+    pub width: f64,
+    /// Whether the line ends inside a word
+    pub hyphenated: bool,
+    /// Whether the line ends the paragraph
+    pub last: bool,
+    /// Stretchability of the spaces, in points
+    stretch: f64,
+}
+
+impl TypesetLine {
+    /// Character and word spacing, for `Tc` and `Tw`, that make the line
+    /// `width` points wide: the letter spacing grows with the stretching of
+    /// the spaces, up to `max_letter_spacing` points, and is rounded to
+    /// hundredths of a point, the word spacing making up the rest. The last
+    /// line of a paragraph is not justified.
+    pub fn justification(&self, width: f64, max_letter_spacing: f64) -> (f64, f64) {
+        let extra = width - self.width;
+        let gaps = self.text.chars().count().saturating_sub(1) as f64;
+        let spaces = self.text.matches(' ').count() as f64;
+        if self.last || extra.abs() < 0.005 || gaps == 0.0 {
+            return (0.0, 0.0);
+        }
+        if spaces == 0.0 {
+            // A single word is only letter spaced
+            return (
+                ((extra / gaps).clamp(0.0, max_letter_spacing) * 100.0).floor() / 100.0,
+                0.0,
+            );
+        }
+        if extra < 0.0 {
+            return (0.0, extra / spaces);
+        }
+        let ratio = extra / (self.stretch + gaps * max_letter_spacing);
+        let character_spacing =
+            ((ratio * max_letter_spacing).min(max_letter_spacing) * 100.0).floor() / 100.0;
+        (
+            character_spacing,
+            (extra - gaps * character_spacing) / spaces,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Item<'a> {
+    Box {
+        text: &'a str,
+        width: f64,
+        stretch: f64,
+    },
+    Glue {
+        width: f64,
+        stretch: f64,
+        shrink: f64,
+    },
+    Penalty {
+        width: f64,
+        penalty: f64,
+        flagged: bool,
+    },
+}
+
+impl Item<'_> {
+    fn is_box(&self) -> bool {
+        matches!(self, Item::Box { .. })
+    }
+
+    fn is_flagged(&self) -> bool {
+        matches!(self, Item::Penalty { flagged: true, .. })
+    }
+}
+
+/// Sums of the widths, stretchability and shrinkability of items
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    width: f64,
+    stretch: f64,
+    shrink: f64,
+}
+
+/// A feasible break, and the best way to reach it
+#[derive(Debug, Clone)]
+struct Breakpoint {
+    position: usize,
+    fitness: usize,
+    /// Totals of the items before the next line
+    totals: Totals,
+    demerits: f64,
+    previous: Option<usize>,
+}
+
+/// Break a paragraph into lines of `width` points
+pub fn typeset_paragraph(
+    text: &str,
+    font: &Font,
+    font_size: f64,
+    width: f64,
+    options: &ParagraphOptions,
+) -> Vec<TypesetLine> {
+    typeset_shaped_paragraph(text, font, font_size, width, options, &ShapingFonts::new())
+}
+
+/// Break a paragraph into lines of `width` points, measuring text in custom
+/// fonts as it is shaped with `fonts`
+pub fn typeset_shaped_paragraph(
+    text: &str,
+    font: &Font,
+    font_size: f64,
+    width: f64,
+    options: &ParagraphOptions,
+    fonts: &ShapingFonts,
+) -> Vec<TypesetLine> {
+    let items = items(text, font, font_size, options, fonts);
+    if !items.iter().any(Item::is_box) {
+        return Vec::new();
+    }
+    // Lines too loose or overfull are only set when there are no others
+    let breaks = break_points(&items, width, options.tolerance, false)
+        .or_else(|| break_points(&items, width, f64::INFINITY, true))
+        .unwrap_or_else(|| vec![items.len() - 1]);
+    lines(&items, &breaks)
+}
+
+/// Boxes, glue and penalties of the paragraph
+fn items<'a>(
+    text: &'a str,
+    font: &Font,
+    font_size: f64,
+    options: &ParagraphOptions,
+    fonts: &ShapingFonts,
+) -> Vec<Item<'a>> {
+    let measure = |text: &str| measure_shaped_text(text, font.clone(), font_size, fonts);
+    let space = measure(" ");
+    let hyphen = measure("-");
+    let letter_stretch = options.max_letter_spacing * font_size;
+    let word_box = |text: &'a str| Item::Box {
+        text,
+        width: measure(text),
+        stretch: text.chars().count() as f64 * letter_stretch,
+    };
+
+    let mut items = Vec::new();
+    for (index, word) in text.split_whitespace().enumerate() {
+        if index > 0 {
+            items.push(Item::Glue {
+                width: space,
+                stretch: space / 2.0,
+                shrink: space / 3.0,
+            });
+        }
+        let mut breaks: Vec<usize> = options
+            .hyphenator
+            .as_ref()
+            .map(|hyphenator| hyphenator.hyphenate(word))
+            .unwrap_or_default();
+        breaks.extend(
+            word.match_indices('-')
+                .map(|(index, _)| index + 1)
+                .filter(|&end| end < word.len()),
+        );
+        breaks.sort_unstable();
+        breaks.dedup();
+
+        let mut start = 0;
+        for end in breaks {
+            items.push(word_box(&word[start..end]));
+            items.push(Item::Penalty {
+                width: if word[..end].ends_with('-') {
+                    0.0
+                } else {
+                    hyphen
+                },
+                penalty: HYPHEN_PENALTY,
+                flagged: true,
+            });
+            start = end;
+        }
+        items.push(word_box(&word[start..]));
+    }
+    // The last line is filled with space and ends the paragraph
+    items.push(Item::Glue {
+        width: 0.0,
+        stretch: f64::INFINITY,
+        shrink: 0.0,
+    });
+    items.push(Item::Penalty {
+        width: 0.0,
+        penalty: -INFINITE_PENALTY,
+        flagged: false,
+    });
+    items
+}
+
+/// Positions of the items the lines end at, `None` if the paragraph cannot
+/// be set within the tolerance. With `overfull`, lines that cannot be
+/// shrunk enough are set as well.
+fn break_points(items: &[Item], width: f64, tolerance: f64, overfull: bool) -> Option<Vec<usize>> {
+    let mut breakpoints = vec![Breakpoint {
+        position: 0,
+        fitness: 1,
+        totals: Totals::default(),
+        demerits: 0.0,
+        previous: None,
+    }];
+    let mut active = vec![0];
+    let mut sums = Totals::default();
+
+    for (index, item) in items.iter().enumerate() {
+        let (penalty_width, penalty, flagged) = match *item {
+            Item::Box { .. } => (0.0, INFINITE_PENALTY, false),
+            // Spaces are breaks after a word only
+            Item::Glue { .. } if index > 0 && items[index - 1].is_box() => (0.0, 0.0, false),
+            Item::Glue { .. } => (0.0, INFINITE_PENALTY, false),
+            Item::Penalty {
+                width,
+                penalty,
+                flagged,
+            } => (width, penalty, flagged),
+        };
+
+        if penalty < INFINITE_PENALTY {
+            let forced = penalty <= -INFINITE_PENALTY;
+            // Best way to break here for each fitness class
+            let mut best: [Option<(f64, usize)>; 4] = [None; 4];
+            active.retain(|&node| {
+                let from = &breakpoints[node];
+                let length = sums.width - from.totals.width + penalty_width;
+                let ratio = if length < width {
+                    let stretch = sums.stretch - from.totals.stretch;
+                    if stretch > 0.0 {
+                        (width - length) / stretch
+                    } else {
+                        f64::INFINITY
+                    }
+                } else if length > width {
+                    let shrink = sums.shrink - from.totals.shrink;
+                    if shrink > 0.0 {
+                        (width - length) / shrink
+                    } else {
+                        f64::NEG_INFINITY
+                    }
+                } else {
+                    0.0
+                };
+
+                let too_tight = ratio < -1.0;
+                if (-1.0..=tolerance).contains(&ratio) || (overfull && too_tight) {
+                    let badness = if too_tight {
+                        10000.0
+                    } else {
+                        (100.0 * ratio.abs().powi(3)).min(10000.0)
+                    };
+                    let mut demerits = (LINE_PENALTY + badness).powi(2);
+                    if penalty >= 0.0 {
+                        demerits += penalty.powi(2);
+                    } else if !forced {
+                        demerits -= penalty.powi(2);
+                    }
+                    if flagged && items[from.position].is_flagged() {
+                        demerits += DOUBLE_HYPHEN_DEMERITS;
+                    }
+                    let fitness: usize = match ratio {
+                        r if r < -0.5 => 0,
+                        r if r <= 0.5 => 1,
+                        r if r <= 1.0 => 2,
+                        _ => 3,
+                    };
+                    if fitness.abs_diff(from.fitness) > 1 {
+                        demerits += ADJACENT_FITNESS_DEMERITS;
+                    }
+                    if too_tight {
+                        let shrink = sums.shrink - from.totals.shrink;
+                        demerits += (length - shrink - width) * OVERFULL_DEMERITS;
+                    }
+                    demerits += from.demerits;
+                    if best[fitness].map_or(true, |(least, _)| demerits < least) {
+                        best[fitness] = Some((demerits, node));
+                    }
+                }
+                !too_tight && !forced
+            });
+
+            // The next line starts after the spaces and penalties here
+            let mut totals = sums;
+            for (offset, next) in items[index..].iter().enumerate() {
+                match *next {
+                    Item::Box { .. } => break,
+                    Item::Glue {
+                        width,
+                        stretch,
+                        shrink,
+                    } => {
+                        totals.width += width;
+                        totals.stretch += stretch;
+                        totals.shrink += shrink;
+                    }
+                    Item::Penalty { penalty, .. } => {
+                        if offset > 0 && penalty <= -INFINITE_PENALTY {
+                            break;
+                        }
+                    }
+                }
+            }
+            for (fitness, candidate) in best.iter().enumerate() {
+                if let Some((demerits, node)) = *candidate {
+                    active.push(breakpoints.len());
+                    breakpoints.push(Breakpoint {
+                        position: index,
+                        fitness,
+                        totals,
+                        demerits,
+                        previous: Some(node),
+                    });
+                }
+            }
+            if active.is_empty() {
+                return None;
+            }
+        }
+
+        match *item {
+            Item::Box { width, stretch, .. } => {
+                sums.width += width;
+                sums.stretch += stretch;
+            }
+            Item::Glue {
+                width,
+                stretch,
+                shrink,
+            } => {
+                sums.width += width;
+                sums.stretch += stretch;
+                sums.shrink += shrink;
+            }
+            Item::Penalty { .. } => {}
+        }
+    }
+
+    let mut node = active
+        .into_iter()
+        .min_by(|&a, &b| breakpoints[a].demerits.total_cmp(&breakpoints[b].demerits))?;
+    let mut breaks = Vec::new();
+    while let Some(previous) = breakpoints[node].previous {
+        breaks.push(breakpoints[node].position);
+        node = previous;
+    }
+    breaks.reverse();
+    Some(breaks)
+}
+
+/// Lines ending at the breaks
+fn lines(items: &[Item], breaks: &[usize]) -> Vec<TypesetLine> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (index, &end) in breaks.iter().enumerate() {
+        // Spaces and penalties at the start of a line are dropped
+        while start < end && !items[start].is_box() {
+            start += 1;
+        }
+        let mut line = TypesetLine {
+            text: String::new(),
+            width: 0.0,
+            hyphenated: false,
+            last: index + 1 == breaks.len(),
+            stretch: 0.0,
+        };
+        for item in &items[start..end] {
+            match *item {
+                Item::Box { text, width, .. } => {
+                    line.text.push_str(text);
+                    line.width += width;
+                }
+                Item::Glue { width, stretch, .. } if stretch.is_finite() => {
+                    line.text.push(' ');
+                    line.width += width;
+                    line.stretch += stretch;
+                }
+                _ => {}
+            }
+        }
+        if let Item::Penalty { width, .. } = items[end] {
+            if width > 0.0 {
+                line.text.push('-');
+                line.width += width;
+                line.hyphenated = true;
+            }
+        }
+        lines.push(line);
+        start = end + 1;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::measure_text;
+
+    fn texts(lines: &[TypesetLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_breaks_fit_the_width() {
+        let text = "The parties agree that this agreement shall be governed by the laws of \
+                    the state in which the services are performed.";
+        let options = ParagraphOptions::default();
+        let lines = typeset_paragraph(text, &Font::Helvetica, 10.0, 150.0, &options);
+        assert!(lines.len() > 2);
+        assert!(lines.iter().all(|line| line.width <= 150.0 + 0.01));
+        assert_eq!(lines.iter().filter(|line| line.last).count(), 1);
+        assert!(lines.last().unwrap().last);
+        assert_eq!(
+            texts(&lines)
+                .join(" ")
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            text.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_optimal_breaks_avoid_a_loose_line() {
+        // Filling each line in turn stretches the first two lines as far
+        // as they go; shrinking the second line sets the paragraph better
+        let font = Font::Courier;
+        let width = measure_text("0123456789", font.clone(), 10.0);
+        let options = ParagraphOptions {
+            max_letter_spacing: 0.0,
+            ..Default::default()
+        };
+        let lines = typeset_paragraph("dddd eeee ffff g h ii jj", &font, 10.0, width, &options);
+        assert_eq!(texts(&lines), vec!["dddd eeee", "ffff g h ii", "jj"]);
+    }
+
+    #[test]
+    fn test_hyphenation() {
+        let options = ParagraphOptions {
+            hyphenator: Some(Hyphenator::from_patterns("", "pro-gra-ma-ción li-ne-al").unwrap()),
+            ..Default::default()
+        };
+        let width = measure_text("la programa", Font::Helvetica, 10.0);
+        let lines = typeset_paragraph(
+            "la programación lineal",
+            &Font::Helvetica,
+            10.0,
+            width,
+            &options,
+        );
+        assert!(lines[0].hyphenated);
+        assert!(lines[0].text.starts_with("la pro"));
+        assert!(lines[0].text.ends_with('-'));
+    }
+
+    #[test]
+    fn test_overfull_word() {
+        let options = ParagraphOptions::default();
+        let lines = typeset_paragraph(
+            "a incomprehensibilities b",
+            &Font::Helvetica,
+            10.0,
+            30.0,
+            &options,
+        );
+        assert_eq!(texts(&lines), vec!["a", "incomprehensibilities", "b"]);
+    }
+
+    #[test]
+    fn test_justification() {
+        let options = ParagraphOptions::default();
+        let text = "one two three four five six seven eight nine ten eleven twelve";
+        let lines = typeset_paragraph(text, &Font::Helvetica, 10.0, 100.0, &options);
+        for line in &lines[..lines.len() - 1] {
+            let (character_spacing, word_spacing) = line.justification(100.0, 0.2);
+            assert!((0.0..=0.2).contains(&character_spacing));
+            let gaps = line.text.chars().count() as f64 - 1.0;
+            let spaces = line.text.matches(' ').count() as f64;
+            let filled = line.width + gaps * character_spacing + spaces * word_spacing;
+            assert!((filled - 100.0).abs() < 1e-6);
+        }
+        assert_eq!(lines.last().unwrap().justification(100.0, 0.2), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_widows_and_orphans() {
+        let options = ParagraphOptions::default();
+        assert_eq!(options.lines_before_break(5, 10), 5);
+        assert_eq!(options.lines_before_break(5, 4), 3);
+        assert_eq!(options.lines_before_break(5, 1), 0);
+        assert_eq!(options.lines_before_break(3, 2), 0);
+    }
+}
@@ -0,0 +1,175 @@
+//! Arabic joining: each letter takes its isolated, final, medial or
+//! initial form depending on whether it joins the letters around it. The
+//! forms come from the `isol`, `fina`, `medi` and `init` features or, in
+//! fonts without them, from the Arabic Presentation Forms-B characters.
+
+use super::script::{FINAL, INITIAL, ISOLATED, MEDIAL};
+use super::CharItem;
+use crate::text::bidi;
+
+const FORMS: u32 = ISOLATED | FINAL | MEDIAL | INITIAL;
+
+const LAM: char = '\u{644}';
+
+/// Alefs that form a ligature with a lam before them
+const ALEFS: [char; 4] = ['\u{622}', '\u{623}', '\u{625}', '\u{627}'];
+
+/// Isolated presentation form of the letters U+0621 to U+064A, followed by
+/// the final, initial and medial forms the letter has; 0 for none
+const PRESENTATION_FORMS: [u16; 42] = [
+    0xFE80, 0xFE81, 0xFE83, 0xFE85, 0xFE87, 0xFE89, 0xFE8D, 0xFE8F, 0xFE93, 0xFE95, 0xFE99, 0xFE9D,
+    0xFEA1, 0xFEA5, 0xFEA9, 0xFEAB, 0xFEAD, 0xFEAF, 0xFEB1, 0xFEB5, 0xFEB9, 0xFEBD, 0xFEC1, 0xFEC5,
+    0xFEC9, 0xFECD, 0, 0, 0, 0, 0, 0, 0xFED1, 0xFED5, 0xFED9, 0xFEDD, 0xFEE1, 0xFEE5, 0xFEE9,
+    0xFEED, 0xFEEF, 0xFEF1,
+];
+
+/// How a character joins its neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Joining {
+    NonJoining,
+    /// Joins the letter before it only
+    Right,
+    /// Joins the letters on both sides
+    Dual,
+    /// Tatweel and zero width joiner
+    Causing,
+    /// Marks, passed over
+    Transparent,
+}
+
+fn joining(ch: char) -> Joining {
+    match ch as u32 {
+        0x0640 | 0x200D => Joining::Causing,
+        0x0622..=0x0625
+        | 0x0627
+        | 0x0629
+        | 0x062F..=0x0632
+        | 0x0648
+        | 0x0671..=0x0673
+        | 0x0675..=0x0677
+        | 0x0688..=0x0699
+        | 0x06C0
+        | 0x06C3..=0x06CB
+        | 0x06CD
+        | 0x06CF
+        | 0x06D2
+        | 0x06D3
+        | 0x06D5
+        | 0x06EE
+        | 0x06EF
+        | 0x0759..=0x075B
+        | 0x076B
+        | 0x076C
+        | 0x0771
+        | 0x0773
+        | 0x0774
+        | 0x0778
+        | 0x0779
+        | 0x08AA..=0x08AC
+        | 0x08AE
+        | 0x08B1
+        | 0x08B2
+        | 0x08B9 => Joining::Right,
+        0x0620
+        | 0x0626
+        | 0x0628
+        | 0x062A..=0x062E
+        | 0x0633..=0x063F
+        | 0x0641..=0x0647
+        | 0x0649
+        | 0x064A
+        | 0x066E
+        | 0x066F
+        | 0x0678..=0x0687
+        | 0x069A..=0x06BF
+        | 0x06C1
+        | 0x06C2
+        | 0x06CC
+        | 0x06CE
+        | 0x06D0
+        | 0x06D1
+        | 0x06FA..=0x06FC
+        | 0x06FF
+        | 0x0750..=0x0758
+        | 0x075C..=0x076A
+        | 0x076D..=0x0770
+        | 0x0772
+        | 0x0775..=0x0777
+        | 0x077A..=0x077F
+        | 0x08A0..=0x08A9
+        | 0x08AF
+        | 0x08B0
+        | 0x08B3..=0x08B8
+        | 0x08BA..=0x08BD => Joining::Dual,
+        _ if bidi::is_mark(ch) => Joining::Transparent,
+        _ => Joining::NonJoining,
+    }
+}
+
+/// Set the masks of the positional forms of the letters
+pub(super) fn set_forms(chars: &mut [CharItem]) {
+    let mut previous: Option<(usize, Joining)> = None;
+    for index in 0..chars.len() {
+        let joining = joining(chars[index].ch);
+        if joining == Joining::Transparent {
+            continue;
+        }
+        let joins_previous = matches!(joining, Joining::Right | Joining::Dual | Joining::Causing)
+            && matches!(previous, Some((_, Joining::Dual | Joining::Causing)));
+        if let (true, Some((before, _))) = (joins_previous, previous) {
+            let form = match chars[before].mask & FORMS {
+                ISOLATED => INITIAL,
+                FINAL => MEDIAL,
+                form => form,
+            };
+            chars[before].mask = chars[before].mask & !FORMS | form;
+        }
+        if matches!(joining, Joining::Right | Joining::Dual) {
+            chars[index].mask |= if joins_previous { FINAL } else { ISOLATED };
+        }
+        previous = Some((index, joining));
+    }
+}
+
+/// Replace the letters with the presentation forms of their masks, and a
+/// lam and alef with their ligature, where the font has a glyph for them
+pub(super) fn presentation_forms(chars: &mut Vec<CharItem>, has_glyph: impl Fn(char) -> bool) {
+    let mut index = 0;
+    while index < chars.len() {
+        let mask = chars[index].mask;
+        let alef = chars
+            .get(index + 1)
+            .and_then(|next| ALEFS.iter().position(|&alef| alef == next.ch));
+        if let (LAM, Some(alef)) = (chars[index].ch, alef) {
+            let joined = mask & (FINAL | MEDIAL) != 0;
+            let ligature = char::from_u32(0xFEF5 + 2 * alef as u32 + joined as u32);
+            if let Some(ligature) = ligature.filter(|&ligature| has_glyph(ligature)) {
+                let next = chars.remove(index + 1);
+                chars[index].ch = ligature;
+                chars[index].cluster.end = next.cluster.end;
+                index += 1;
+                continue;
+            }
+        }
+
+        let form = match mask & FORMS {
+            ISOLATED => Some(0),
+            FINAL => Some(1),
+            INITIAL => Some(2),
+            MEDIAL => Some(3),
+            _ => None,
+        };
+        let first = (chars[index].ch as u32)
+            .checked_sub(0x0621)
+            .and_then(|offset| PRESENTATION_FORMS.get(offset as usize))
+            .filter(|&&first| first != 0);
+        if let (Some(form), Some(&first)) = (form, first) {
+            if let Some(presentation) =
+                char::from_u32(first as u32 + form).filter(|&presentation| has_glyph(presentation))
+            {
+                chars[index].ch = presentation;
+            }
+        }
+        index += 1;
+    }
+}
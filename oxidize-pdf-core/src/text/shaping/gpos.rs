@@ -0,0 +1,330 @@
+//! Glyph positioning (GPOS): single and pair adjustments, mark attachment
+//! and contextual positioning, with the legacy `kern` table as a fallback
+
+use super::layout::{
+    coverage_index, glyph_class, i16_at, match_context, offset_at, u16_at, Gdef, LayoutTable,
+    Lookup, MARK_CLASS, MAX_NESTING,
+};
+
+const X_PLACEMENT: u16 = 0x0001;
+const Y_PLACEMENT: u16 = 0x0002;
+const X_ADVANCE: u16 = 0x0004;
+
+/// Adjustments of a glyph in font units
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct GlyphPosition {
+    pub x_advance: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    /// Glyph this mark is attached to
+    pub attached_to: Option<usize>,
+}
+
+/// Apply the lookups to the positions of the glyphs
+pub(super) fn position(
+    table: &LayoutTable,
+    gdef: Option<&Gdef>,
+    lookups: &[usize],
+    glyphs: &[u16],
+    positions: &mut [GlyphPosition],
+) {
+    for &index in lookups {
+        let lookup = &table.lookups[index];
+        let mut at = 0;
+        while at < glyphs.len() {
+            at = if lookup.skips(gdef, glyphs[at]) {
+                at + 1
+            } else {
+                apply(table, gdef, lookup, glyphs, positions, at, 0).unwrap_or(at + 1)
+            };
+        }
+    }
+}
+
+/// Apply the first subtable of the lookup that matches at `at`, returning
+/// the position to continue at
+fn apply(
+    table: &LayoutTable,
+    gdef: Option<&Gdef>,
+    lookup: &Lookup,
+    glyphs: &[u16],
+    positions: &mut [GlyphPosition],
+    at: usize,
+    depth: usize,
+) -> Option<usize> {
+    let context = Positioning {
+        table,
+        gdef,
+        lookup,
+        glyphs,
+    };
+    lookup
+        .subtables
+        .iter()
+        .find_map(|&subtable| context.apply_subtable(subtable, positions, at, depth))
+}
+
+struct Positioning<'a> {
+    table: &'a LayoutTable,
+    gdef: Option<&'a Gdef>,
+    lookup: &'a Lookup,
+    glyphs: &'a [u16],
+}
+
+impl Positioning<'_> {
+    fn apply_subtable(
+        &self,
+        subtable: usize,
+        positions: &mut [GlyphPosition],
+        at: usize,
+        depth: usize,
+    ) -> Option<usize> {
+        let data = &self.table.data[..];
+        let glyph = *self.glyphs.get(at)?;
+        match self.lookup.kind {
+            // Single adjustment
+            1 => {
+                let index = coverage_index(data, offset_at(data, subtable, 2)?, glyph)? as usize;
+                let format = u16_at(data, subtable + 4)?;
+                let record = match u16_at(data, subtable)? {
+                    1 => subtable + 6,
+                    2 => subtable + 8 + index * value_size(format),
+                    _ => return None,
+                };
+                adjust(&mut positions[at], data, record, format)?;
+                Some(at + 1)
+            }
+            // Pair adjustment
+            2 => {
+                coverage_index(data, offset_at(data, subtable, 2)?, glyph)?;
+                let second = self.next(at)?;
+                let format1 = u16_at(data, subtable + 4)?;
+                let format2 = u16_at(data, subtable + 6)?;
+                let record = self.pair_record(subtable, glyph, self.glyphs[second])?;
+                adjust(&mut positions[at], data, record, format1)?;
+                adjust(
+                    &mut positions[second],
+                    data,
+                    record + value_size(format1),
+                    format2,
+                )?;
+                Some(if format2 == 0 { second } else { second + 1 })
+            }
+            // Mark-to-base and mark-to-mark attachment
+            4 | 6 => {
+                let mark = coverage_index(data, offset_at(data, subtable, 2)?, glyph)? as usize;
+                let target = if self.lookup.kind == 4 {
+                    (0..at)
+                        .rev()
+                        .find(|&i| !self.is_mark(subtable, self.glyphs[i]))?
+                } else {
+                    let previous = (0..at)
+                        .rev()
+                        .find(|&i| !self.lookup.skips(self.gdef, self.glyphs[i]))?;
+                    let is_mark = self
+                        .gdef
+                        .map_or(true, |gdef| gdef.class(self.glyphs[previous]) == MARK_CLASS);
+                    if !is_mark {
+                        return None;
+                    }
+                    previous
+                };
+                let target_index =
+                    coverage_index(data, offset_at(data, subtable, 4)?, self.glyphs[target])?
+                        as usize;
+                let class_count = u16_at(data, subtable + 6)? as usize;
+                let marks = offset_at(data, subtable, 8)?;
+                let targets = offset_at(data, subtable, 10)?;
+
+                let mark_record = marks + 2 + mark * 4;
+                let class = u16_at(data, mark_record)? as usize;
+                let mark_anchor = anchor(data, offset_at(data, marks, 2 + mark * 4 + 2)?)?;
+                let target_anchor = anchor(
+                    data,
+                    offset_at(data, targets, 2 + (target_index * class_count + class) * 2)?,
+                )?;
+
+                positions[at] = GlyphPosition {
+                    x_advance: 0,
+                    x_offset: target_anchor.0 - mark_anchor.0,
+                    y_offset: target_anchor.1 - mark_anchor.1,
+                    attached_to: Some(target),
+                };
+                Some(at + 1)
+            }
+            // Contextual and chained contextual positioning
+            7 | 8 => {
+                let skip = |glyph| self.lookup.skips(self.gdef, glyph);
+                let matched = match_context(
+                    data,
+                    subtable,
+                    self.lookup.kind == 8,
+                    self.glyphs,
+                    at,
+                    &skip,
+                )?;
+                if depth < MAX_NESTING {
+                    for (sequence_index, lookup_index) in matched.records {
+                        let (Some(&nested_at), Some(nested)) = (
+                            matched.input.get(sequence_index),
+                            self.table.lookups.get(lookup_index),
+                        ) else {
+                            continue;
+                        };
+                        apply(
+                            self.table,
+                            self.gdef,
+                            nested,
+                            self.glyphs,
+                            positions,
+                            nested_at,
+                            depth + 1,
+                        );
+                    }
+                }
+                Some(matched.input.last()? + 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// The next glyph the lookup does not skip
+    fn next(&self, at: usize) -> Option<usize> {
+        (at + 1..self.glyphs.len()).find(|&i| !self.lookup.skips(self.gdef, self.glyphs[i]))
+    }
+
+    /// Whether `glyph` is a mark a mark-to-base lookup passes over when
+    /// looking for the base
+    fn is_mark(&self, subtable: usize, glyph: u16) -> bool {
+        match self.gdef {
+            Some(gdef) => gdef.class(glyph) == MARK_CLASS,
+            None => offset_at(&self.table.data, subtable, 2)
+                .and_then(|coverage| coverage_index(&self.table.data, coverage, glyph))
+                .is_some(),
+        }
+    }
+
+    /// Offset of the value records for the pair, if it is adjusted
+    fn pair_record(&self, subtable: usize, first: u16, second: u16) -> Option<usize> {
+        let data = &self.table.data[..];
+        let size1 = value_size(u16_at(data, subtable + 4)?);
+        let size2 = value_size(u16_at(data, subtable + 6)?);
+        match u16_at(data, subtable)? {
+            1 => {
+                let index = coverage_index(data, offset_at(data, subtable, 2)?, first)? as usize;
+                let set = offset_at(data, subtable, 10 + index * 2)?;
+                let record_size = 2 + size1 + size2;
+                let (mut low, mut high) = (0, u16_at(data, set)? as usize);
+                while low < high {
+                    let middle = (low + high) / 2;
+                    let record = set + 2 + middle * record_size;
+                    match u16_at(data, record)?.cmp(&second) {
+                        std::cmp::Ordering::Less => low = middle + 1,
+                        std::cmp::Ordering::Greater => high = middle,
+                        std::cmp::Ordering::Equal => return Some(record + 2),
+                    }
+                }
+                None
+            }
+            2 => {
+                let class1 = glyph_class(data, offset_at(data, subtable, 8)?, first) as usize;
+                let class2 = glyph_class(data, offset_at(data, subtable, 10)?, second) as usize;
+                let class1_count = u16_at(data, subtable + 12)? as usize;
+                let class2_count = u16_at(data, subtable + 14)? as usize;
+                if class1 >= class1_count || class2 >= class2_count {
+                    return None;
+                }
+                Some(subtable + 16 + (class1 * class2_count + class2) * (size1 + size2))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Size in bytes of a value record of the given format
+fn value_size(format: u16) -> usize {
+    2 * (format & 0xFF).count_ones() as usize
+}
+
+/// Add the placement and advance of the value record at `offset`; device
+/// tables and vertical advances are ignored
+fn adjust(position: &mut GlyphPosition, data: &[u8], offset: usize, format: u16) -> Option<()> {
+    let mut field = offset;
+    for flag in [X_PLACEMENT, Y_PLACEMENT, X_ADVANCE] {
+        if format & flag != 0 {
+            let value = i16_at(data, field)? as i32;
+            match flag {
+                X_PLACEMENT => position.x_offset += value,
+                Y_PLACEMENT => position.y_offset += value,
+                _ => position.x_advance += value,
+            }
+            field += 2;
+        }
+    }
+    Some(())
+}
+
+/// Coordinates of the anchor table at `offset`
+fn anchor(data: &[u8], offset: usize) -> Option<(i32, i32)> {
+    Some((
+        i16_at(data, offset + 2)? as i32,
+        i16_at(data, offset + 4)? as i32,
+    ))
+}
+
+/// Apply the horizontal format 0 subtables of a `kern` table
+pub(super) fn kern(
+    data: &[u8],
+    gdef: Option<&Gdef>,
+    glyphs: &[u16],
+    positions: &mut [GlyphPosition],
+) {
+    let is_mark = |glyph| gdef.is_some_and(|gdef| gdef.class(glyph) == MARK_CLASS);
+    let mut subtables = Vec::new();
+    if u16_at(data, 0) == Some(0) {
+        let mut offset = 4;
+        for _ in 0..u16_at(data, 2).unwrap_or(0) {
+            let (Some(length), Some(coverage)) =
+                (u16_at(data, offset + 2), u16_at(data, offset + 4))
+            else {
+                break;
+            };
+            // Horizontal, format 0, neither minimum nor cross-stream values
+            if coverage & 0xFF07 == 0x0001 {
+                subtables.push(offset + 6);
+            }
+            offset += length as usize;
+        }
+    }
+
+    let pairs: Vec<usize> = glyphs
+        .iter()
+        .enumerate()
+        .filter(|(_, &glyph)| !is_mark(glyph))
+        .map(|(index, _)| index)
+        .collect();
+    for window in pairs.windows(2) {
+        let (left, right) = (glyphs[window[0]], glyphs[window[1]]);
+        let key = (left as u32) << 16 | right as u32;
+        for &subtable in &subtables {
+            let count = u16_at(data, subtable).unwrap_or(0) as usize;
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let middle = (low + high) / 2;
+                let record = subtable + 8 + middle * 6;
+                let (Some(l), Some(r)) = (u16_at(data, record), u16_at(data, record + 2)) else {
+                    break;
+                };
+                match ((l as u32) << 16 | r as u32).cmp(&key) {
+                    std::cmp::Ordering::Less => low = middle + 1,
+                    std::cmp::Ordering::Greater => high = middle,
+                    std::cmp::Ordering::Equal => {
+                        positions[window[0]].x_advance +=
+                            i16_at(data, record + 4).unwrap_or(0) as i32;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Glyph substitution (GSUB): single, multiple, ligature and contextual
+//! substitutions
+
+use super::layout::{
+    coverage_index, match_context, offset_at, u16_at, Gdef, LayoutTable, Lookup, MAX_NESTING,
+};
+use std::ops::Range;
+
+/// A glyph, the bytes of the text it stands for and the mask of the
+/// features applied to it
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct GlyphItem {
+    pub glyph: u16,
+    pub cluster: Range<usize>,
+    pub mask: u32,
+}
+
+/// Apply the lookups to the glyphs whose mask they share
+pub(super) fn substitute(
+    table: &LayoutTable,
+    gdef: Option<&Gdef>,
+    lookups: &[(usize, u32)],
+    buffer: &mut Vec<GlyphItem>,
+) {
+    for &(index, mask) in lookups {
+        let lookup = &table.lookups[index];
+        let mut position = 0;
+        while position < buffer.len() {
+            position = if buffer[position].mask & mask == 0
+                || lookup.skips(gdef, buffer[position].glyph)
+            {
+                position + 1
+            } else {
+                apply(table, gdef, lookup, buffer, position, 0).unwrap_or(position + 1)
+            };
+        }
+    }
+}
+
+/// Apply the first subtable of the lookup that matches at `position`,
+/// returning the position to continue at
+fn apply(
+    table: &LayoutTable,
+    gdef: Option<&Gdef>,
+    lookup: &Lookup,
+    buffer: &mut Vec<GlyphItem>,
+    position: usize,
+    depth: usize,
+) -> Option<usize> {
+    lookup.subtables.iter().find_map(|&subtable| {
+        apply_subtable(table, gdef, lookup, subtable, buffer, position, depth)
+    })
+}
+
+fn apply_subtable(
+    table: &LayoutTable,
+    gdef: Option<&Gdef>,
+    lookup: &Lookup,
+    subtable: usize,
+    buffer: &mut Vec<GlyphItem>,
+    position: usize,
+    depth: usize,
+) -> Option<usize> {
+    let data = &table.data[..];
+    let glyph = buffer.get(position)?.glyph;
+    match lookup.kind {
+        // Single substitution
+        1 => {
+            let index = coverage_index(data, offset_at(data, subtable, 2)?, glyph)? as usize;
+            buffer[position].glyph = match u16_at(data, subtable)? {
+                1 => glyph.wrapping_add(u16_at(data, subtable + 4)?),
+                2 => u16_at(data, subtable + 6 + index * 2)?,
+                _ => return None,
+            };
+            Some(position + 1)
+        }
+        // Multiple substitution
+        2 => {
+            let index = coverage_index(data, offset_at(data, subtable, 2)?, glyph)? as usize;
+            let sequence = offset_at(data, subtable, 6 + index * 2)?;
+            let glyphs = (0..u16_at(data, sequence)? as usize)
+                .map(|k| u16_at(data, sequence + 2 + k * 2))
+                .collect::<Option<Vec<_>>>()?;
+            if glyphs.is_empty() {
+                return None;
+            }
+            let GlyphItem { cluster, mask, .. } = buffer[position].clone();
+            let count = glyphs.len();
+            buffer.splice(
+                position..=position,
+                glyphs.into_iter().map(|glyph| GlyphItem {
+                    glyph,
+                    cluster: cluster.clone(),
+                    mask,
+                }),
+            );
+            Some(position + count)
+        }
+        // Ligature substitution
+        4 => {
+            let index = coverage_index(data, offset_at(data, subtable, 2)?, glyph)? as usize;
+            let set = offset_at(data, subtable, 6 + index * 2)?;
+            for ligature in 0..u16_at(data, set)? as usize {
+                let ligature = offset_at(data, set, 2 + ligature * 2)?;
+                let components = u16_at(data, ligature + 2)? as usize;
+                let mut positions = vec![position];
+                for k in 1..components {
+                    let previous = *positions.last()?;
+                    let next = (previous + 1..buffer.len())
+                        .find(|&i| !lookup.skips(gdef, buffer[i].glyph))
+                        .filter(|&i| {
+                            u16_at(data, ligature + 4 + (k - 1) * 2) == Some(buffer[i].glyph)
+                        });
+                    match next {
+                        Some(next) => positions.push(next),
+                        None => break,
+                    }
+                }
+                if positions.len() != components || components == 0 {
+                    continue;
+                }
+
+                let glyph = u16_at(data, ligature)?;
+                let start = positions.iter().map(|&i| buffer[i].cluster.start).min()?;
+                let end = positions.iter().map(|&i| buffer[i].cluster.end).max()?;
+                for &component in positions[1..].iter().rev() {
+                    buffer.remove(component);
+                }
+                buffer[position].glyph = glyph;
+                buffer[position].cluster = start..end;
+                return Some(position + 1);
+            }
+            None
+        }
+        // Contextual and chained contextual substitution
+        5 | 6 => {
+            let glyphs: Vec<u16> = buffer.iter().map(|item| item.glyph).collect();
+            let skip = |glyph| lookup.skips(gdef, glyph);
+            let matched =
+                match_context(data, subtable, lookup.kind == 6, &glyphs, position, &skip)?;
+            let mut input = matched.input;
+            if depth < MAX_NESTING {
+                for (sequence_index, lookup_index) in matched.records {
+                    let (Some(&at), Some(nested)) =
+                        (input.get(sequence_index), table.lookups.get(lookup_index))
+                    else {
+                        continue;
+                    };
+                    let before = buffer.len() as isize;
+                    if apply(table, gdef, nested, buffer, at, depth + 1).is_some() {
+                        // Later input glyphs moved if the glyph count changed
+                        let grown = buffer.len() as isize - before;
+                        for later in input.iter_mut().filter(|later| **later > at) {
+                            *later = (*later as isize + grown).max(at as isize) as usize;
+                        }
+                    }
+                }
+            }
+            Some((input.last()? + 1).min(buffer.len()).max(position + 1))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,144 @@
+//! Devanagari syllables: the consonants of a conjunct get the masks of
+//! their half, below-base and reph forms, and the pre-base matra and the
+//! reph are moved to where they are shown. A syllable whose characters are
+//! moved becomes a single cluster.
+
+use super::script::{BELOW_BASE, HALF, REPH};
+use super::CharItem;
+use std::ops::Range;
+
+const RA: char = '\u{930}';
+const VIRAMA: char = '\u{94D}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Consonant,
+    Vowel,
+    Nukta,
+    Virama,
+    Matra,
+    PreBaseMatra,
+    Modifier,
+    ZeroWidthJoiner,
+    ZeroWidthNonJoiner,
+    Other,
+}
+
+fn category(ch: char) -> Category {
+    match ch as u32 {
+        0x0915..=0x0939 | 0x0958..=0x095F | 0x0978..=0x097F => Category::Consonant,
+        0x0904..=0x0914 | 0x0960 | 0x0961 | 0x0972..=0x0977 => Category::Vowel,
+        0x093C => Category::Nukta,
+        0x094D => Category::Virama,
+        0x093F | 0x094E => Category::PreBaseMatra,
+        0x093A | 0x093B | 0x093E | 0x0940..=0x094C | 0x094F | 0x0955..=0x0957 | 0x0962 | 0x0963 => {
+            Category::Matra
+        }
+        0x0900..=0x0903 | 0x0951..=0x0954 => Category::Modifier,
+        0x200D => Category::ZeroWidthJoiner,
+        0x200C => Category::ZeroWidthNonJoiner,
+        _ => Category::Other,
+    }
+}
+
+/// Ranges of the syllables: a consonant or vowel with the signs after it,
+/// consonants joined by a virama making one syllable
+fn syllables(chars: &[CharItem]) -> Vec<Range<usize>> {
+    let mut syllables: Vec<Range<usize>> = Vec::new();
+    for index in 0..chars.len() {
+        let before = |k: usize| index.checked_sub(k).map(|i| category(chars[i].ch));
+        let continues = match category(chars[index].ch) {
+            Category::Consonant => {
+                before(1) == Some(Category::Virama)
+                    || (before(1) == Some(Category::ZeroWidthJoiner)
+                        && before(2) == Some(Category::Virama))
+            }
+            Category::Nukta
+            | Category::Virama
+            | Category::Matra
+            | Category::PreBaseMatra
+            | Category::Modifier
+            | Category::ZeroWidthJoiner
+            | Category::ZeroWidthNonJoiner => true,
+            Category::Vowel | Category::Other => false,
+        };
+        match syllables.last_mut() {
+            Some(syllable) if continues => syllable.end = index + 1,
+            _ => syllables.push(index..index + 1),
+        }
+    }
+    syllables
+}
+
+/// Set the masks of the conjunct forms and reorder the consonant
+/// syllables. `reph` and `below_base` tell whether the font has the reph
+/// and below-base forms.
+pub(super) fn reorder(chars: &mut [CharItem], reph: bool, below_base: bool) {
+    for range in syllables(chars) {
+        let syllable = &mut chars[range];
+        let count = syllable.len();
+        if category(syllable[0].ch) != Category::Consonant {
+            continue;
+        }
+
+        // An initial ra and virama before another consonant is a reph
+        let has_reph = reph
+            && count > 2
+            && syllable[0].ch == RA
+            && syllable[1].ch == VIRAMA
+            && category(syllable[2].ch) == Category::Consonant;
+        let start = if has_reph { 2 } else { 0 };
+
+        // The base is the last consonant, unless it is a ra after a virama
+        // that takes its below-base form
+        let consonants: Vec<usize> = (start..count)
+            .filter(|&k| category(syllable[k].ch) == Category::Consonant)
+            .collect();
+        let Some(&last) = consonants.last() else {
+            continue;
+        };
+        let mut base = last;
+        if below_base
+            && consonants.len() > 1
+            && syllable[last].ch == RA
+            && syllable[last - 1].ch == VIRAMA
+        {
+            syllable[last - 1].mask |= BELOW_BASE;
+            syllable[last].mask |= BELOW_BASE;
+            base = consonants[consonants.len() - 2];
+        }
+        for item in &mut syllable[start..base] {
+            item.mask |= HALF;
+        }
+
+        let mut moved = false;
+        // The pre-base matra is shown before the consonants
+        if let Some(matra) =
+            (start..count).find(|&k| category(syllable[k].ch) == Category::PreBaseMatra)
+        {
+            syllable[start..=matra].rotate_right(1);
+            moved = true;
+        }
+        // The reph is shown at the end of the syllable, before modifiers
+        if has_reph {
+            syllable[0].mask |= REPH;
+            syllable[1].mask |= REPH;
+            let end = (2..count)
+                .rev()
+                .find(|&k| category(syllable[k].ch) != Category::Modifier)
+                .map_or(count, |k| k + 1);
+            syllable[..end].rotate_left(2);
+            moved = true;
+        }
+
+        if moved {
+            let start = syllable.iter().map(|item| item.cluster.start).min();
+            let end = syllable.iter().map(|item| item.cluster.end).max();
+            if let (Some(start), Some(end)) = (start, end) {
+                for item in syllable.iter_mut() {
+                    item.cluster = start..end;
+                }
+            }
+        }
+    }
+}
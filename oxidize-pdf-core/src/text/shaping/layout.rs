@@ -0,0 +1,539 @@
+//! Structures shared by the GSUB and GPOS tables (OpenType "Common Table
+//! Formats"): coverage and class definition tables, the script, feature
+//! and lookup lists, glyph classes from GDEF, and contextual rules
+//!
+//! Reads never panic on malformed fonts: anything out of bounds is treated
+//! as missing.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+const IGNORE_BASE_GLYPHS: u16 = 0x0002;
+const IGNORE_LIGATURES: u16 = 0x0004;
+const IGNORE_MARKS: u16 = 0x0008;
+const USE_MARK_FILTERING_SET: u16 = 0x0010;
+const MARK_ATTACHMENT_TYPE: u16 = 0xFF00;
+
+/// GDEF glyph class of marks
+pub(super) const MARK_CLASS: u16 = 3;
+
+/// Maximum nesting of lookups applied by contextual rules
+pub(super) const MAX_NESTING: usize = 8;
+
+pub(super) fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+pub(super) fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|value| value as i16)
+}
+
+pub(super) fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Target of the 16-bit offset stored at `base + at`, relative to `base`;
+/// `None` for a null offset
+pub(super) fn offset_at(data: &[u8], base: usize, at: usize) -> Option<usize> {
+    match u16_at(data, base + at)? {
+        0 => None,
+        offset => Some(base + offset as usize),
+    }
+}
+
+/// Index of `glyph` in the coverage table at `offset`
+pub(super) fn coverage_index(data: &[u8], offset: usize, glyph: u16) -> Option<u16> {
+    let count = u16_at(data, offset + 2)? as usize;
+    match u16_at(data, offset)? {
+        1 => {
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let middle = (low + high) / 2;
+                let candidate = u16_at(data, offset + 4 + middle * 2)?;
+                match candidate.cmp(&glyph) {
+                    std::cmp::Ordering::Less => low = middle + 1,
+                    std::cmp::Ordering::Greater => high = middle,
+                    std::cmp::Ordering::Equal => return Some(middle as u16),
+                }
+            }
+            None
+        }
+        2 => {
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let middle = (low + high) / 2;
+                let record = offset + 4 + middle * 6;
+                let start = u16_at(data, record)?;
+                let end = u16_at(data, record + 2)?;
+                if glyph < start {
+                    high = middle;
+                } else if glyph > end {
+                    low = middle + 1;
+                } else {
+                    return Some(u16_at(data, record + 4)? + (glyph - start));
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Class of `glyph` in the class definition table at `offset` (0 when not
+/// listed)
+pub(super) fn glyph_class(data: &[u8], offset: usize, glyph: u16) -> u16 {
+    let class = || -> Option<u16> {
+        match u16_at(data, offset)? {
+            1 => {
+                let start = u16_at(data, offset + 2)?;
+                let count = u16_at(data, offset + 4)?;
+                let index = glyph.checked_sub(start).filter(|&index| index < count)?;
+                u16_at(data, offset + 6 + index as usize * 2)
+            }
+            2 => {
+                let count = u16_at(data, offset + 2)? as usize;
+                let (mut low, mut high) = (0, count);
+                while low < high {
+                    let middle = (low + high) / 2;
+                    let record = offset + 4 + middle * 6;
+                    if glyph < u16_at(data, record)? {
+                        high = middle;
+                    } else if glyph > u16_at(data, record + 2)? {
+                        low = middle + 1;
+                    } else {
+                        return u16_at(data, record + 4);
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    };
+    class().unwrap_or(0)
+}
+
+/// Glyph classes of the GDEF table
+#[derive(Debug, Clone)]
+pub(super) struct Gdef {
+    data: Vec<u8>,
+    glyph_classes: Option<usize>,
+    mark_attach_classes: Option<usize>,
+}
+
+impl Gdef {
+    pub fn new(data: &[u8]) -> Self {
+        Self {
+            glyph_classes: offset_at(data, 0, 4),
+            mark_attach_classes: offset_at(data, 0, 10),
+            data: data.to_vec(),
+        }
+    }
+
+    /// Base (1), ligature (2), mark (3) or component (4); 0 if unclassified
+    pub fn class(&self, glyph: u16) -> u16 {
+        self.glyph_classes
+            .map_or(0, |offset| glyph_class(&self.data, offset, glyph))
+    }
+
+    fn mark_attach_class(&self, glyph: u16) -> u16 {
+        self.mark_attach_classes
+            .map_or(0, |offset| glyph_class(&self.data, offset, glyph))
+    }
+}
+
+/// A lookup of the lookup list, with extension subtables resolved
+#[derive(Debug, Clone)]
+pub(super) struct Lookup {
+    pub kind: u16,
+    pub flag: u16,
+    /// Offsets of the subtables from the start of the table
+    pub subtables: Vec<usize>,
+}
+
+impl Lookup {
+    /// Whether the lookup passes over `glyph` when matching
+    pub fn skips(&self, gdef: Option<&Gdef>, glyph: u16) -> bool {
+        let Some(gdef) = gdef else {
+            return false;
+        };
+        match gdef.class(glyph) {
+            1 => self.flag & IGNORE_BASE_GLYPHS != 0,
+            2 => self.flag & IGNORE_LIGATURES != 0,
+            MARK_CLASS => {
+                let attach_type = (self.flag & MARK_ATTACHMENT_TYPE) >> 8;
+                self.flag & IGNORE_MARKS != 0
+                    || (self.flag & USE_MARK_FILTERING_SET == 0
+                        && attach_type != 0
+                        && gdef.mark_attach_class(glyph) != attach_type)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A GSUB or GPOS table and its lookups
+#[derive(Debug, Clone)]
+pub(super) struct LayoutTable {
+    pub data: Vec<u8>,
+    pub lookups: Vec<Lookup>,
+    /// Offsets of the script and feature lists
+    scripts: usize,
+    features: usize,
+}
+
+impl LayoutTable {
+    /// Parse the table; `extension` is the lookup type that wraps
+    /// subtables with 32-bit offsets
+    pub fn new(data: &[u8], extension: u16) -> Option<Self> {
+        let scripts = offset_at(data, 0, 4)?;
+        let features = offset_at(data, 0, 6)?;
+        let lookup_list = offset_at(data, 0, 8)?;
+
+        let mut lookups = Vec::new();
+        for index in 0..u16_at(data, lookup_list)? as usize {
+            let Some(offset) = offset_at(data, lookup_list, 2 + index * 2) else {
+                lookups.push(Lookup {
+                    kind: 0,
+                    flag: 0,
+                    subtables: Vec::new(),
+                });
+                continue;
+            };
+            let lookup_type = u16_at(data, offset)?;
+            let mut kind = lookup_type;
+            let flag = u16_at(data, offset + 2)?;
+            let mut subtables = Vec::new();
+            for sub in 0..u16_at(data, offset + 4)? as usize {
+                let Some(mut subtable) = offset_at(data, offset, 6 + sub * 2) else {
+                    continue;
+                };
+                if lookup_type == extension {
+                    kind = u16_at(data, subtable + 2)?;
+                    subtable += u32_at(data, subtable + 4)? as usize;
+                }
+                subtables.push(subtable);
+            }
+            lookups.push(Lookup {
+                kind,
+                flag,
+                subtables,
+            });
+        }
+
+        Some(Self {
+            data: data.to_vec(),
+            lookups,
+            scripts,
+            features,
+        })
+    }
+
+    /// Lookups of the `features` (tag and mask) in the default language
+    /// system of the first of the `scripts` the table has, or of every
+    /// script if it has none of them. Each lookup comes with the masks of
+    /// the features it belongs to, in lookup list order.
+    pub fn lookups_for(
+        &self,
+        scripts: &[&[u8; 4]],
+        features: &[(&[u8; 4], u32)],
+    ) -> Vec<(usize, u32)> {
+        let data = &self.data[..];
+        let count = u16_at(data, self.scripts).unwrap_or(0) as usize;
+        let script_table = |index: usize| offset_at(data, self.scripts, 2 + index * 6 + 4);
+        let preferred = scripts.iter().find_map(|tag| {
+            (0..count)
+                .find(|&index| {
+                    let record = self.scripts + 2 + index * 6;
+                    data.get(record..record + 4) == Some(&tag[..])
+                })
+                .and_then(script_table)
+        });
+        let script_tables: Vec<usize> = match preferred {
+            Some(script) => vec![script],
+            None => (0..count).filter_map(script_table).collect(),
+        };
+
+        let mut feature_indices = BTreeSet::new();
+        for script in script_tables {
+            let Some(lang_sys) = offset_at(data, script, 0) else {
+                continue;
+            };
+            if let Some(required) = u16_at(data, lang_sys + 2).filter(|&index| index != 0xFFFF) {
+                feature_indices.insert(required as usize);
+            }
+            for index in 0..u16_at(data, lang_sys + 4).unwrap_or(0) as usize {
+                if let Some(feature) = u16_at(data, lang_sys + 6 + index * 2) {
+                    feature_indices.insert(feature as usize);
+                }
+            }
+        }
+
+        let mut masks = BTreeMap::new();
+        for index in feature_indices {
+            let record = self.features + 2 + index * 6;
+            let Some(tag) = data.get(record..record + 4) else {
+                continue;
+            };
+            let Some(&(_, mask)) = features.iter().find(|(feature, _)| feature[..] == *tag) else {
+                continue;
+            };
+            let Some(feature) = offset_at(data, self.features, 2 + index * 6 + 4) else {
+                continue;
+            };
+            for lookup in 0..u16_at(data, feature + 2).unwrap_or(0) as usize {
+                match u16_at(data, feature + 4 + lookup * 2) {
+                    Some(lookup) if (lookup as usize) < self.lookups.len() => {
+                        *masks.entry(lookup as usize).or_insert(0) |= mask;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        masks.into_iter().collect()
+    }
+}
+
+/// A matched contextual rule: the positions of its input glyphs and the
+/// lookups to apply at them (sequence index, lookup index)
+pub(super) struct ContextMatch {
+    pub input: Vec<usize>,
+    pub records: Vec<(usize, usize)>,
+}
+
+/// Match the contextual (GSUB 5, GPOS 7) or chained contextual (GSUB 6,
+/// GPOS 8) subtable at `offset` against `glyphs` starting at `start`
+pub(super) fn match_context(
+    data: &[u8],
+    offset: usize,
+    chained: bool,
+    glyphs: &[u16],
+    start: usize,
+    skip: &dyn Fn(u16) -> bool,
+) -> Option<ContextMatch> {
+    let first = glyphs[start];
+    let context = Context {
+        data,
+        glyphs,
+        start,
+        skip,
+    };
+    match (chained, u16_at(data, offset)?) {
+        (false, 1) => {
+            let index = coverage_index(data, offset_at(data, offset, 2)?, first)? as usize;
+            let rule_set = offset_at(data, offset, 6 + index * 2)?;
+            (0..u16_at(data, rule_set)? as usize).find_map(|rule| {
+                let rule = offset_at(data, rule_set, 2 + rule * 2)?;
+                let input = u16_at(data, rule)? as usize;
+                let records = u16_at(data, rule + 2)? as usize;
+                let input_values = rule + 4;
+                let positions = context.input(input, |k, glyph| {
+                    u16_at(data, input_values + (k - 1) * 2) == Some(glyph)
+                })?;
+                context.records(positions, input_values + (input - 1) * 2, records)
+            })
+        }
+        (false, 2) => {
+            coverage_index(data, offset_at(data, offset, 2)?, first)?;
+            let classes = offset_at(data, offset, 4)?;
+            let class = glyph_class(data, classes, first) as usize;
+            let rule_set = offset_at(data, offset, 8 + class * 2)?;
+            (0..u16_at(data, rule_set)? as usize).find_map(|rule| {
+                let rule = offset_at(data, rule_set, 2 + rule * 2)?;
+                let input = u16_at(data, rule)? as usize;
+                let records = u16_at(data, rule + 2)? as usize;
+                let input_values = rule + 4;
+                let positions = context.input(input, |k, glyph| {
+                    u16_at(data, input_values + (k - 1) * 2)
+                        == Some(glyph_class(data, classes, glyph))
+                })?;
+                context.records(positions, input_values + (input - 1) * 2, records)
+            })
+        }
+        (false, 3) => {
+            let input = u16_at(data, offset + 2)? as usize;
+            let records = u16_at(data, offset + 4)? as usize;
+            let coverages = offset + 6;
+            if coverage_index(data, offset_at(data, offset, 6)?, first).is_none() {
+                return None;
+            }
+            let positions = context.input(input, |k, glyph| {
+                offset_at(data, offset, 6 + k * 2)
+                    .and_then(|coverage| coverage_index(data, coverage, glyph))
+                    .is_some()
+            })?;
+            context.records(positions, coverages + input * 2, records)
+        }
+        (true, 1) => {
+            let index = coverage_index(data, offset_at(data, offset, 2)?, first)? as usize;
+            let rule_set = offset_at(data, offset, 6 + index * 2)?;
+            (0..u16_at(data, rule_set)? as usize).find_map(|rule| {
+                let rule = offset_at(data, rule_set, 2 + rule * 2)?;
+                context.chain_rule(rule, &|_, value, glyph| value == glyph)
+            })
+        }
+        (true, 2) => {
+            coverage_index(data, offset_at(data, offset, 2)?, first)?;
+            let backtrack_classes = offset_at(data, offset, 4);
+            let input_classes = offset_at(data, offset, 6)?;
+            let lookahead_classes = offset_at(data, offset, 8);
+            let class = glyph_class(data, input_classes, first) as usize;
+            let rule_set = offset_at(data, offset, 12 + class * 2)?;
+            let class_of = |part: Part, glyph: u16| {
+                let classes = match part {
+                    Part::Backtrack => backtrack_classes,
+                    Part::Input => Some(input_classes),
+                    Part::Lookahead => lookahead_classes,
+                };
+                classes.map_or(0, |classes| glyph_class(data, classes, glyph))
+            };
+            (0..u16_at(data, rule_set)? as usize).find_map(|rule| {
+                let rule = offset_at(data, rule_set, 2 + rule * 2)?;
+                context.chain_rule(rule, &|part, value, glyph| value == class_of(part, glyph))
+            })
+        }
+        (true, 3) => {
+            let backtrack = u16_at(data, offset + 2)? as usize;
+            let input_at = offset + 4 + backtrack * 2;
+            let input = u16_at(data, input_at)? as usize;
+            let lookahead_at = input_at + 2 + input * 2;
+            let lookahead = u16_at(data, lookahead_at)? as usize;
+            let records_at = lookahead_at + 2 + lookahead * 2;
+            let covered = |array: usize, k: usize, glyph: u16| {
+                offset_at(data, offset, array - offset + k * 2)
+                    .and_then(|coverage| coverage_index(data, coverage, glyph))
+                    .is_some()
+            };
+
+            if input == 0 || !covered(input_at + 2, 0, first) {
+                return None;
+            }
+            let positions = context.input(input, |k, glyph| covered(input_at + 2, k, glyph))?;
+            context.backtrack(backtrack, |k, glyph| covered(offset + 4, k, glyph))?;
+            context.lookahead(&positions, lookahead, |k, glyph| {
+                covered(lookahead_at + 2, k, glyph)
+            })?;
+            context.records(
+                positions,
+                records_at + 2,
+                u16_at(data, records_at)? as usize,
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Sequence a value of a chained rule is matched against
+#[derive(Clone, Copy)]
+enum Part {
+    Backtrack,
+    Input,
+    Lookahead,
+}
+
+struct Context<'a> {
+    data: &'a [u8],
+    glyphs: &'a [u16],
+    start: usize,
+    skip: &'a dyn Fn(u16) -> bool,
+}
+
+impl Context<'_> {
+    /// Positions of `count` input glyphs from the start; `matches(k,
+    /// glyph)` checks the k-th glyph after the first
+    fn input(&self, count: usize, matches: impl Fn(usize, u16) -> bool) -> Option<Vec<usize>> {
+        if count == 0 {
+            return None;
+        }
+        let mut positions = vec![self.start];
+        let mut position = self.start;
+        for k in 1..count {
+            position = self.next(position)?;
+            if !matches(k, self.glyphs[position]) {
+                return None;
+            }
+            positions.push(position);
+        }
+        Some(positions)
+    }
+
+    /// Check `count` glyphs before the start, nearest first
+    fn backtrack(&self, count: usize, matches: impl Fn(usize, u16) -> bool) -> Option<()> {
+        let mut position = self.start;
+        for k in 0..count {
+            position = (0..position)
+                .rev()
+                .find(|&i| !(self.skip)(self.glyphs[i]))?;
+            if !matches(k, self.glyphs[position]) {
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    /// Check `count` glyphs after the input
+    fn lookahead(
+        &self,
+        input: &[usize],
+        count: usize,
+        matches: impl Fn(usize, u16) -> bool,
+    ) -> Option<()> {
+        let mut position = *input.last()?;
+        for k in 0..count {
+            position = self.next(position)?;
+            if !matches(k, self.glyphs[position]) {
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    fn next(&self, position: usize) -> Option<usize> {
+        (position + 1..self.glyphs.len()).find(|&i| !(self.skip)(self.glyphs[i]))
+    }
+
+    /// A chained rule of format 1 or 2, whose values `matches` compares
+    /// with the glyphs
+    fn chain_rule(
+        &self,
+        rule: usize,
+        matches: &dyn Fn(Part, u16, u16) -> bool,
+    ) -> Option<ContextMatch> {
+        let data = self.data;
+        let backtrack = u16_at(data, rule)? as usize;
+        let input_at = rule + 2 + backtrack * 2;
+        let input = u16_at(data, input_at)? as usize;
+        let lookahead_at = input_at + 2 + input.saturating_sub(1) * 2;
+        let lookahead = u16_at(data, lookahead_at)? as usize;
+        let records_at = lookahead_at + 2 + lookahead * 2;
+
+        let value = |array: usize, k: usize| u16_at(data, array + k * 2);
+        let positions = self.input(input, |k, glyph| {
+            value(input_at + 2, k - 1).is_some_and(|v| matches(Part::Input, v, glyph))
+        })?;
+        self.backtrack(backtrack, |k, glyph| {
+            value(rule + 2, k).is_some_and(|v| matches(Part::Backtrack, v, glyph))
+        })?;
+        self.lookahead(&positions, lookahead, |k, glyph| {
+            value(lookahead_at + 2, k).is_some_and(|v| matches(Part::Lookahead, v, glyph))
+        })?;
+        self.records(
+            positions,
+            records_at + 2,
+            u16_at(data, records_at)? as usize,
+        )
+    }
+
+    fn records(&self, input: Vec<usize>, offset: usize, count: usize) -> Option<ContextMatch> {
+        let records = (0..count)
+            .map(|record| {
+                let record = offset + record * 4;
+                Some((
+                    u16_at(self.data, record)? as usize,
+                    u16_at(self.data, record + 2)? as usize,
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(ContextMatch { input, records })
+    }
+}
@@ -0,0 +1,1010 @@
+//! OpenType text shaping for custom fonts
+//!
+//! Text set in a custom font is shaped before it is measured or written:
+//! it is split into runs of one direction with the bidirectional algorithm
+//! and into runs of one script, characters are mapped to glyphs, the
+//! `GSUB` features of the script are applied — ligatures, contextual
+//! alternates, Arabic joining forms, Indic conjuncts — and the glyphs are
+//! positioned with the `GPOS` kerning and mark attachment features, or the
+//! `kern` table of older fonts. Right-to-left runs are then reversed, so
+//! the glyphs come out in the order they are shown.
+//!
+//! Custom fonts are written as Type0 fonts whose CIDs are the Unicode code
+//! points of the characters. Glyphs that do not stand for a single
+//! character of the cmap — ligatures, alternates, positional forms — get a
+//! CID of the private use area, and the writer adds them to the
+//! `CIDToGIDMap`, the widths and the `ToUnicode` CMap, so the text of a
+//! ligature is still extracted. Text that is shown reordered is wrapped in
+//! an `ActualText` span giving it in logical order.
+//!
+//! Each document shapes text with the fonts added to it, kept with the
+//! private use CIDs handed out in its [`ShapingFonts`]. A page shapes text
+//! with the fonts of the document it is added to. Text written in a font
+//! the page does not know yet is shown character by character and shaped
+//! when the page is added, or at the latest when the document is written;
+//! lines of text flows are broken with the widths of the unshaped text,
+//! though, unless the page was given the document's fonts with
+//! [`Page::set_shaping_fonts`](crate::Page::set_shaping_fonts) first.
+
+mod arabic;
+mod gpos;
+mod gsub;
+mod indic;
+mod layout;
+mod script;
+#[cfg(test)]
+pub(crate) mod test_font;
+mod thai;
+
+use crate::error::{PdfError, Result};
+use crate::text::bidi::{self, Direction};
+use crate::text::fonts::truetype::TrueTypeFont;
+use gpos::GlyphPosition;
+use gsub::GlyphItem;
+use layout::{Gdef, LayoutTable};
+use script::{Script, BELOW_BASE, FINAL, GLOBAL, INITIAL, ISOLATED, MEDIAL, REPH};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Private use area the CIDs of glyphs without a character come from
+const PRIVATE_USE: Range<u32> = 0xE000..0xF900;
+
+/// A glyph of shaped text, in font units
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedGlyph {
+    /// Glyph index in the font
+    pub glyph: u16,
+    /// Byte range of the text the glyph stands for
+    pub cluster: Range<usize>,
+    /// Horizontal advance
+    pub x_advance: i32,
+    /// Horizontal offset from the pen position
+    pub x_offset: i32,
+    /// Vertical offset from the baseline
+    pub y_offset: i32,
+}
+
+/// The glyphs text is set with
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedText {
+    pub glyphs: Vec<ShapedGlyph>,
+    pub units_per_em: u16,
+}
+
+impl ShapedText {
+    /// Width of the text at the font size
+    pub fn width(&self, font_size: f64) -> f64 {
+        let advance: i64 = self.glyphs.iter().map(|g| g.x_advance as i64).sum();
+        advance as f64 * font_size / self.units_per_em.max(1) as f64
+    }
+}
+
+/// A glyph shown with a CID of the private use area, as the writer needs
+/// it for the font
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExtraGlyph {
+    pub cid: u16,
+    pub glyph: u16,
+    /// Text the glyph stands for; empty for the second and later glyphs a
+    /// character was decomposed into
+    pub text: String,
+}
+
+/// Text in a custom font shown character by character because the font
+/// was not registered when it was written, kept to be shaped once it is
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UnshapedText {
+    /// Range of the operations showing the text
+    pub range: Range<usize>,
+    /// The operations showing the text
+    pub shown: String,
+    pub font_name: String,
+    pub text: String,
+    pub base: Option<Direction>,
+    pub font_size: f64,
+    pub character_spacing: f64,
+    pub word_spacing: f64,
+    pub rise: f64,
+}
+
+/// A character to be shaped, the bytes of the text it stands for and the
+/// mask of the features applied to it
+#[derive(Debug, Clone, PartialEq)]
+struct CharItem {
+    ch: char,
+    cluster: Range<usize>,
+    mask: u32,
+}
+
+/// Lookups a script is shaped with
+#[derive(Debug, Clone, Default)]
+struct ScriptPlan {
+    /// `GSUB` lookups and the masks of the glyphs they apply to
+    gsub: Vec<(usize, u32)>,
+    gpos: Vec<usize>,
+}
+
+impl ScriptPlan {
+    /// Whether a substitution applies to glyphs with the mask
+    fn substitutes(&self, mask: u32) -> bool {
+        self.gsub
+            .iter()
+            .any(|&(_, lookup_mask)| lookup_mask & mask != 0)
+    }
+}
+
+/// Shapes text with the tables of one font
+struct Shaper {
+    /// Unicode to glyph mapping
+    cmap: HashMap<u32, u16>,
+    /// Advance widths in font units, by glyph
+    advances: Vec<u16>,
+    units_per_em: u16,
+    gsub: Option<LayoutTable>,
+    gpos: Option<LayoutTable>,
+    gdef: Option<Gdef>,
+    /// The `kern` table, used when `GPOS` has no kerning
+    kern: Option<Vec<u8>>,
+    plans: HashMap<Script, ScriptPlan>,
+}
+
+impl Shaper {
+    fn new(data: &[u8]) -> Result<Self> {
+        let font = TrueTypeFont::from_data(data)
+            .map_err(|e| PdfError::FontError(format!("Cannot shape with font: {e}")))?;
+        let tables = font
+            .parse_cmap()
+            .map_err(|e| PdfError::FontError(format!("Cannot read font cmap: {e}")))?;
+
+        // The BMP table the writer maps CIDs with, and characters beyond
+        // the BMP from a full Unicode table
+        let mut cmap = tables
+            .iter()
+            .find(|t| t.platform_id == 3 && t.encoding_id == 1)
+            .or_else(|| tables.iter().find(|t| t.platform_id == 0))
+            .map(|t| t.mappings.clone())
+            .ok_or_else(|| PdfError::FontError("No Unicode cmap table found".to_string()))?;
+        if let Some(full) = tables
+            .iter()
+            .find(|t| t.platform_id == 3 && t.encoding_id == 10)
+        {
+            cmap.extend(full.mappings.iter().filter(|(&code, _)| code > 0xFFFF));
+        }
+
+        let advances = (0..font.num_glyphs)
+            .map(|glyph| {
+                font.get_glyph_metrics(glyph)
+                    .map_or(0, |(advance, _)| advance)
+            })
+            .collect();
+
+        let gsub = font
+            .table_data(b"GSUB")
+            .and_then(|data| LayoutTable::new(data, 7));
+        let gpos = font
+            .table_data(b"GPOS")
+            .and_then(|data| LayoutTable::new(data, 9));
+        let plans = Script::ALL
+            .iter()
+            .map(|&script| {
+                let plan = ScriptPlan {
+                    gsub: gsub
+                        .as_ref()
+                        .map(|table| table.lookups_for(script.tags(), script.gsub_features()))
+                        .unwrap_or_default(),
+                    gpos: gpos
+                        .as_ref()
+                        .map(|table| table.lookups_for(script.tags(), script.gpos_features()))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(lookup, _)| lookup)
+                        .collect(),
+                };
+                (script, plan)
+            })
+            .collect();
+        let gpos_kerning = gpos
+            .as_ref()
+            .is_some_and(|table| !table.lookups_for(&[], &[(b"kern", GLOBAL)]).is_empty());
+
+        Ok(Self {
+            cmap,
+            advances,
+            units_per_em: font.units_per_em,
+            gsub,
+            gpos,
+            gdef: font.table_data(b"GDEF").map(Gdef::new),
+            kern: font
+                .table_data(b"kern")
+                .filter(|_| !gpos_kerning)
+                .map(<[u8]>::to_vec),
+            plans,
+        })
+    }
+
+    fn shape(&self, text: &str, base: Option<Direction>) -> ShapedText {
+        // Shape each run in logical order, then put the glyphs of
+        // right-to-left runs in visual order
+        let mut items: Vec<(GlyphItem, GlyphPosition)> = Vec::new();
+        for run in bidi::visual_runs(text, base) {
+            let start = items.len();
+            let right_to_left = run.direction() == Direction::RightToLeft;
+            for (script, range) in script::itemize(&text[run.range.clone()]) {
+                let range = run.range.start + range.start..run.range.start + range.end;
+                let offset = items.len();
+                items.extend(
+                    self.shape_segment(text, range, script, right_to_left)
+                        .into_iter()
+                        .map(|(item, mut position)| {
+                            position.attached_to = position.attached_to.map(|t| t + offset);
+                            (item, position)
+                        }),
+                );
+            }
+            if right_to_left {
+                let end = items.len();
+                items[start..end].reverse();
+                for (_, position) in &mut items[start..end] {
+                    position.attached_to = position.attached_to.map(|t| start + end - 1 - t);
+                }
+            }
+        }
+
+        // Offsets of attached marks are from the glyph they are attached
+        // to; make them relative to the pen position of the mark, glyphs
+        // attached to first
+        let pens: Vec<i32> = items
+            .iter()
+            .scan(0, |pen, (_, position)| {
+                let at = *pen;
+                *pen += position.x_advance;
+                Some(at)
+            })
+            .collect();
+        let mut resolved = vec![false; items.len()];
+        for index in 0..items.len() {
+            let mut chain = vec![index];
+            while let Some(target) = items[chain[chain.len() - 1]].1.attached_to {
+                if resolved[target] || chain.contains(&target) {
+                    break;
+                }
+                chain.push(target);
+            }
+            for &glyph in chain.iter().rev() {
+                if resolved[glyph] {
+                    continue;
+                }
+                if let Some(target) = items[glyph].1.attached_to {
+                    let target_x = pens[target] + items[target].1.x_offset;
+                    let target_y = items[target].1.y_offset;
+                    items[glyph].1.x_offset += target_x - pens[glyph];
+                    items[glyph].1.y_offset += target_y;
+                }
+                resolved[glyph] = true;
+            }
+        }
+
+        ShapedText {
+            glyphs: items
+                .into_iter()
+                .map(|(item, position)| ShapedGlyph {
+                    glyph: item.glyph,
+                    cluster: item.cluster,
+                    x_advance: position.x_advance,
+                    x_offset: position.x_offset,
+                    y_offset: position.y_offset,
+                })
+                .collect(),
+            units_per_em: self.units_per_em,
+        }
+    }
+
+    /// Shape the text of `range`, all of one script and direction, in
+    /// logical order; marks are attached to glyphs of the segment
+    fn shape_segment(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        script: Script,
+        right_to_left: bool,
+    ) -> Vec<(GlyphItem, GlyphPosition)> {
+        let unplanned = ScriptPlan::default();
+        let plan = self.plans.get(&script).unwrap_or(&unplanned);
+        let mut chars: Vec<CharItem> = text[range.clone()]
+            .char_indices()
+            .map(|(index, ch)| {
+                let start = range.start + index;
+                CharItem {
+                    ch,
+                    cluster: start..start + ch.len_utf8(),
+                    mask: GLOBAL,
+                }
+            })
+            .collect();
+        let has_glyph = |ch: char| self.cmap.contains_key(&(ch as u32));
+        match script {
+            Script::Arabic => {
+                arabic::set_forms(&mut chars);
+                if !plan.substitutes(ISOLATED | FINAL | MEDIAL | INITIAL) {
+                    arabic::presentation_forms(&mut chars, has_glyph);
+                }
+            }
+            Script::Devanagari => {
+                indic::reorder(
+                    &mut chars,
+                    plan.substitutes(REPH),
+                    plan.substitutes(BELOW_BASE),
+                );
+            }
+            Script::Thai => thai::decompose_sara_am(&mut chars, has_glyph),
+            Script::Common | Script::Hebrew => {}
+        }
+
+        let mut buffer: Vec<GlyphItem> = chars
+            .into_iter()
+            .map(|item| GlyphItem {
+                glyph: self.glyph(item.ch, right_to_left),
+                cluster: item.cluster,
+                mask: item.mask,
+            })
+            .collect();
+        if let Some(gsub) = &self.gsub {
+            gsub::substitute(gsub, self.gdef.as_ref(), &plan.gsub, &mut buffer);
+        }
+
+        let glyphs: Vec<u16> = buffer.iter().map(|item| item.glyph).collect();
+        let mut positions: Vec<GlyphPosition> = glyphs
+            .iter()
+            .map(|&glyph| GlyphPosition {
+                x_advance: self.advance(glyph),
+                ..Default::default()
+            })
+            .collect();
+        if let Some(gpos) = &self.gpos {
+            gpos::position(
+                gpos,
+                self.gdef.as_ref(),
+                &plan.gpos,
+                &glyphs,
+                &mut positions,
+            );
+        }
+        if let Some(kern) = &self.kern {
+            gpos::kern(kern, self.gdef.as_ref(), &glyphs, &mut positions);
+        }
+        buffer.into_iter().zip(positions).collect()
+    }
+
+    /// Glyph of the character; in right-to-left text, brackets show the
+    /// glyph of their mirror image
+    fn glyph(&self, ch: char, right_to_left: bool) -> u16 {
+        let mirrored = bidi::mirror(ch)
+            .filter(|_| right_to_left)
+            .and_then(|mirrored| self.cmap.get(&(mirrored as u32)));
+        mirrored
+            .or_else(|| self.cmap.get(&(ch as u32)))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn advance(&self, glyph: u16) -> i32 {
+        self.advances.get(glyph as usize).copied().unwrap_or(0) as i32
+    }
+
+    /// Advance of the glyph in thousandths of an em, rounded as in the
+    /// widths the writer gives the font
+    fn pdf_width(&self, glyph: u16) -> f64 {
+        match self.units_per_em {
+            0 => self.advance(glyph) as f64,
+            units_per_em => (self.advance(glyph) as u32 * 1000 / units_per_em as u32) as f64,
+        }
+    }
+
+    fn to_thousandths(&self, units: i32) -> f64 {
+        units as f64 * 1000.0 / self.units_per_em.max(1) as f64
+    }
+}
+
+/// A registered font: its shaper and the private use CIDs handed out
+struct ShapingFont {
+    shaper: Shaper,
+    extras: Mutex<Vec<ExtraGlyph>>,
+}
+
+impl ShapingFont {
+    /// CID to show the glyph with: the character's code point when the
+    /// glyph is the cmap glyph of a single BMP character, otherwise a
+    /// private use code allocated for the glyph and its text
+    fn cid(&self, glyph: u16, text: &str) -> u16 {
+        let mut chars = text.chars();
+        if let (Some(ch), None) = (chars.next(), chars.next()) {
+            let code = ch as u32;
+            let mapped = self.shaper.cmap.get(&code).copied().unwrap_or(0);
+            if code <= 0xFFFF && mapped == glyph {
+                return code as u16;
+            }
+        }
+
+        let Ok(mut extras) = self.extras.lock() else {
+            return 0;
+        };
+        if let Some(extra) = extras.iter().find(|e| e.glyph == glyph && e.text == text) {
+            return extra.cid;
+        }
+        let next = extras
+            .last()
+            .map_or(PRIVATE_USE.start, |e| e.cid as u32 + 1);
+        let Some(cid) = (next..PRIVATE_USE.end).find(|code| !self.shaper.cmap.contains_key(code))
+        else {
+            return 0;
+        };
+        extras.push(ExtraGlyph {
+            cid: cid as u16,
+            glyph,
+            text: text.to_string(),
+        });
+        cid as u16
+    }
+}
+
+/// The fonts a document shapes text with, by name, and the private use
+/// CIDs handed out for each
+///
+/// Clones share the fonts, so text shaped with a clone given to a page
+/// uses the CIDs the document writes the font with.
+#[derive(Clone, Default)]
+pub struct ShapingFonts {
+    fonts: Arc<RwLock<HashMap<String, Arc<ShapingFont>>>>,
+}
+
+impl ShapingFonts {
+    /// Fonts without any font registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the font data shaping uses for the named custom font
+    ///
+    /// Registering a name again keeps the CIDs handed out for it, so text
+    /// already shown keeps its widths and text in the written font.
+    pub fn register(&self, name: impl Into<String>, data: &[u8]) -> Result<()> {
+        let shaper = Shaper::new(data)?;
+        let mut fonts = self
+            .fonts
+            .write()
+            .map_err(|_| PdfError::FontError("Shaping fonts are poisoned".to_string()))?;
+        let name = name.into();
+        let extras = fonts
+            .get(&name)
+            .and_then(|font| font.extras.lock().ok().map(|extras| extras.clone()))
+            .unwrap_or_default();
+        fonts.insert(
+            name,
+            Arc::new(ShapingFont {
+                shaper,
+                extras: Mutex::new(extras),
+            }),
+        );
+        Ok(())
+    }
+
+    /// Whether a font of that name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.font(name).is_some()
+    }
+
+    /// Shape text with a registered font
+    pub fn shape(&self, font_name: &str, text: &str) -> Option<ShapedText> {
+        Some(self.font(font_name)?.shaper.shape(text, None))
+    }
+
+    /// Glyphs of the font shown with private use CIDs so far
+    pub(crate) fn extra_glyphs(&self, font_name: &str) -> Vec<ExtraGlyph> {
+        self.font(font_name)
+            .and_then(|font| font.extras.lock().ok().map(|extras| extras.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Text showing operators for shaped text: a `TJ` array with the kerning,
+    /// word and character spacing as adjustments, and the text rise changed
+    /// around glyphs offset vertically, in an `ActualText` span if the glyphs
+    /// are not in logical order. `base` is the paragraph direction, taken from
+    /// the text when `None`. `None` if the font is not registered.
+    pub(crate) fn show_text(
+        &self,
+        font_name: &str,
+        text: &str,
+        base: Option<Direction>,
+        font_size: f64,
+        character_spacing: f64,
+        word_spacing: f64,
+        rise: f64,
+    ) -> Option<String> {
+        let font = self.font(font_name)?;
+        let shaper = &font.shaper;
+        let shaped = shaper.shape(text, base);
+        let em = |spacing: f64| {
+            if font_size == 0.0 {
+                0.0
+            } else {
+                spacing * 1000.0 / font_size
+            }
+        };
+
+        let mut operations = String::new();
+        let mut run = Vec::new();
+        let mut run_offset = 0;
+        // Where the shaped text and the PDF text position have the pen, in
+        // thousandths of an em
+        let mut pen = 0.0;
+        let mut shown = 0.0;
+        let mut previous_cluster: Option<Range<usize>> = None;
+        for glyph in &shaped.glyphs {
+            if glyph.y_offset != run_offset {
+                flush_run(&mut operations, &mut run);
+                let offset = shaper.to_thousandths(glyph.y_offset) * font_size / 1000.0;
+                writeln!(&mut operations, "{:.2} Ts", rise + offset)
+                    .expect("Writing to String should never fail");
+                run_offset = glyph.y_offset;
+            }
+
+            let at = pen + shaper.to_thousandths(glyph.x_offset);
+            push_adjustment(&mut run, shown - at);
+
+            // Only the first glyph of a cluster stands for its text
+            let text = if previous_cluster.as_ref() == Some(&glyph.cluster) {
+                ""
+            } else {
+                &text[glyph.cluster.clone()]
+            };
+            run.push(format!("<{:04X}>", font.cid(glyph.glyph, text)));
+            previous_cluster = Some(glyph.cluster.clone());
+
+            let spacing = em(character_spacing);
+            shown = at + shaper.pdf_width(glyph.glyph) + spacing;
+            pen += shaper.to_thousandths(glyph.x_advance);
+            if glyph.x_advance != 0 {
+                pen += spacing;
+            }
+            if text == " " {
+                pen += em(word_spacing);
+            }
+        }
+        push_adjustment(&mut run, shown - pen);
+        flush_run(&mut operations, &mut run);
+        if run_offset != 0 {
+            writeln!(&mut operations, "{rise:.2} Ts").expect("Writing to String should never fail");
+        }
+
+        let reordered = shaped
+            .glyphs
+            .windows(2)
+            .any(|pair| pair[1].cluster.start < pair[0].cluster.start);
+        if reordered {
+            operations = bidi::actual_text_span(text, &operations);
+        }
+        Some(operations)
+    }
+
+    /// Shape text shown before its font was registered, replacing the
+    /// operations showing it; `unshaped` is in the order of `operations`.
+    /// Text in fonts still unknown is kept, and text whose operations have
+    /// been changed since is dropped.
+    pub(crate) fn shape_unshaped(
+        &self,
+        unshaped: &mut Vec<UnshapedText>,
+        operations: &mut Vec<u8>,
+    ) {
+        let mut shift = 0isize;
+        let mut kept = Vec::new();
+        for mut text in std::mem::take(unshaped) {
+            let start = (text.range.start as isize + shift) as usize;
+            let range = start..start + text.shown.len();
+            if operations.get(range.clone()) != Some(text.shown.as_bytes()) {
+                continue;
+            }
+            match self.show_text(
+                &text.font_name,
+                &text.text,
+                text.base,
+                text.font_size,
+                text.character_spacing,
+                text.word_spacing,
+                text.rise,
+            ) {
+                Some(shown) => {
+                    shift += shown.len() as isize - text.shown.len() as isize;
+                    operations.splice(range, shown.into_bytes());
+                }
+                None => {
+                    text.range = range;
+                    kept.push(text);
+                }
+            }
+        }
+        *unshaped = kept;
+    }
+
+    fn font(&self, name: &str) -> Option<Arc<ShapingFont>> {
+        self.fonts.read().ok()?.get(name).cloned()
+    }
+}
+
+impl fmt::Debug for ShapingFonts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self
+            .fonts
+            .read()
+            .map(|fonts| fonts.keys().cloned().collect())
+            .unwrap_or_default();
+        f.debug_struct("ShapingFonts")
+            .field("fonts", &names)
+            .finish()
+    }
+}
+
+/// Add a `TJ` adjustment moving the pen back by `amount` thousandths
+fn push_adjustment(run: &mut Vec<String>, amount: f64) {
+    if amount.abs() >= 0.005 {
+        run.push(((amount * 100.0).round() / 100.0).to_string());
+    }
+}
+
+/// Write the `TJ` operator for the run, if it shows any glyph
+fn flush_run(operations: &mut String, run: &mut Vec<String>) {
+    if run.iter().any(|element| element.starts_with('<')) {
+        writeln!(operations, "[{}] TJ", run.join(" "))
+            .expect("Writing to String should never fail");
+    }
+    run.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_font::{self, glyph, ACUTE, ALTERNATE_E, LIGATURE, REPH};
+    use super::*;
+    use crate::text::{measure_shaped_text, Font};
+    use crate::{Document, Page};
+
+    fn register(name: &str) -> ShapingFonts {
+        let data = test_font::font(&[
+            (b"GSUB", test_font::gsub()),
+            (b"GPOS", test_font::gpos()),
+            (b"GDEF", test_font::gdef()),
+        ]);
+        let fonts = ShapingFonts::new();
+        fonts.register(name, &data).unwrap();
+        fonts
+    }
+
+    fn register_data(name: &str, data: &[u8]) -> ShapingFonts {
+        let fonts = ShapingFonts::new();
+        fonts.register(name, data).unwrap();
+        fonts
+    }
+
+    fn glyphs(shaped: &ShapedText) -> Vec<u16> {
+        shaped.glyphs.iter().map(|g| g.glyph).collect()
+    }
+
+    #[test]
+    fn test_standard_ligature() {
+        let fonts = register("ShapingLigature");
+        let shaped = fonts.shape("ShapingLigature", "fit").unwrap();
+        assert_eq!(glyphs(&shaped), vec![LIGATURE, glyph('t')]);
+        assert_eq!(shaped.glyphs[0].cluster, 0..2);
+        assert_eq!(shaped.glyphs[1].cluster, 2..3);
+        assert_eq!(shaped.width(10.0), 14.0);
+    }
+
+    #[test]
+    fn test_contextual_alternate() {
+        let fonts = register("ShapingAlternate");
+        let shaped = fonts.shape("ShapingAlternate", "xe ee").unwrap();
+        assert_eq!(
+            glyphs(&shaped),
+            vec![glyph('x'), ALTERNATE_E, glyph(' '), glyph('e'), glyph('e')]
+        );
+    }
+
+    #[test]
+    fn test_pair_kerning() {
+        let fonts = register("ShapingKerning");
+        let shaped = fonts.shape("ShapingKerning", "AV").unwrap();
+        assert_eq!(shaped.glyphs[0].x_advance, 420);
+        assert_eq!(
+            measure_shaped_text(
+                "AV",
+                Font::Custom("ShapingKerning".to_string()),
+                10.0,
+                &fonts
+            ),
+            9.2
+        );
+
+        let shown = fonts
+            .show_text("ShapingKerning", "AV", None, 10.0, 0.0, 0.0, 0.0)
+            .unwrap();
+        assert_eq!(shown, "[<0041> 80 <0056>] TJ\n");
+    }
+
+    #[test]
+    fn test_kern_table_without_gpos() {
+        let data = test_font::font(&[(b"kern", test_font::kern())]);
+        let fonts = register_data("ShapingKernTable", &data);
+        let shaped = fonts.shape("ShapingKernTable", "AVA").unwrap();
+        let advances: Vec<i32> = shaped.glyphs.iter().map(|g| g.x_advance).collect();
+        assert_eq!(advances, vec![420, 500, 500]);
+    }
+
+    #[test]
+    fn test_mark_positioning() {
+        let fonts = register("ShapingMarks");
+        let shaped = fonts.shape("ShapingMarks", "e\u{301}").unwrap();
+        let mark = &shaped.glyphs[1];
+        assert_eq!(mark.glyph, ACUTE);
+        assert_eq!(
+            (mark.x_advance, mark.x_offset, mark.y_offset),
+            (0, -250, 200)
+        );
+
+        let shown = fonts
+            .show_text("ShapingMarks", "e\u{301}", None, 10.0, 0.0, 0.0, 1.0)
+            .unwrap();
+        assert_eq!(
+            shown,
+            "[<0065>] TJ\n3.00 Ts\n[250 <0301> -250] TJ\n1.00 Ts\n"
+        );
+    }
+
+    #[test]
+    fn test_word_and_character_spacing() {
+        let fonts = register("ShapingSpacing");
+        let shown = fonts
+            .show_text("ShapingSpacing", "A A", None, 10.0, 1.0, 2.0, 0.0)
+            .unwrap();
+        // Character spacing is applied by the viewer; word spacing after
+        // the space is added as an adjustment
+        assert_eq!(shown, "[<0041> <0020> -200 <0041>] TJ\n");
+    }
+
+    #[test]
+    fn test_unregistered_font_is_not_shaped() {
+        let fonts = ShapingFonts::new();
+        assert!(fonts.shape("ShapingMissing", "fit").is_none());
+        assert!(fonts
+            .show_text("ShapingMissing", "fit", None, 10.0, 0.0, 0.0, 0.0)
+            .is_none());
+    }
+
+    /// A page showing the text in the custom font
+    fn page_with_text(document: &Document, font: &str, text: &str) -> Page {
+        let mut page = Page::a4();
+        page.set_shaping_fonts(document.shaping_fonts());
+        page.text()
+            .set_font(Font::Custom(font.to_string()), 12.0)
+            .at(72.0, 700.0)
+            .write(text)
+            .unwrap();
+        page
+    }
+
+    #[test]
+    fn test_ligature_written_with_private_use_cid() {
+        let data = test_font::font(&[(b"GSUB", test_font::gsub())]);
+        let mut document = Document::new();
+        document.set_compress(false);
+        document
+            .add_font_from_bytes("ShapingDocument", data)
+            .unwrap();
+        let page = page_with_text(&document, "ShapingDocument", "first fit");
+        document.add_page(page);
+
+        let pdf = String::from_utf8_lossy(&document.to_bytes().unwrap()).into_owned();
+        assert!(pdf.contains("[<E000> <0072> <0073> <0074> <0020> <E000> <0074>] TJ"));
+        assert!(pdf.contains("<E000> <00660069>"));
+        assert_eq!(
+            document.shaping_fonts().extra_glyphs("ShapingDocument"),
+            vec![ExtraGlyph {
+                cid: 0xE000,
+                glyph: LIGATURE,
+                text: "fi".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_documents_shape_with_their_own_fonts() {
+        let mut ligatures = Document::new();
+        ligatures.set_compress(false);
+        ligatures
+            .add_font_from_bytes("Shared", test_font::font(&[(b"GSUB", test_font::gsub())]))
+            .unwrap();
+        let mut plain = Document::new();
+        plain.set_compress(false);
+        plain
+            .add_font_from_bytes("Shared", test_font::font(&[]))
+            .unwrap();
+
+        // Pages written in turn, each with the font of its document
+        let first = page_with_text(&ligatures, "Shared", "fit");
+        let second = page_with_text(&plain, "Shared", "fit");
+        ligatures.add_page(first);
+        plain.add_page(second);
+        // Text written after the page was added uses the document's fonts
+        ligatures
+            .page_mut(0)
+            .unwrap()
+            .text()
+            .set_font(Font::Custom("Shared".to_string()), 12.0)
+            .at(72.0, 600.0)
+            .write("fi")
+            .unwrap();
+
+        let with_ligatures = String::from_utf8_lossy(&ligatures.to_bytes().unwrap()).into_owned();
+        assert!(with_ligatures.contains("[<E000> <0074>] TJ"));
+        assert!(with_ligatures.contains("[<E000>] TJ"));
+        assert!(with_ligatures.contains("<E000> <00660069>"));
+        let without = String::from_utf8_lossy(&plain.to_bytes().unwrap()).into_owned();
+        assert!(without.contains("[<0066> <0069> <0074>] TJ"));
+        assert!(!without.contains("<E000>"));
+        assert!(plain.shaping_fonts().extra_glyphs("Shared").is_empty());
+
+        // Registering the name again keeps the CIDs handed out
+        ligatures
+            .add_font_from_bytes("Shared", test_font::font(&[(b"GSUB", test_font::gsub())]))
+            .unwrap();
+        assert_eq!(ligatures.shaping_fonts().extra_glyphs("Shared").len(), 1);
+    }
+
+    #[test]
+    fn test_text_written_before_the_font_is_shaped() {
+        let font = Font::Custom("ShapingLater".to_string());
+        let mut page = Page::a4();
+        page.set_tagged(true);
+        page.text()
+            .set_font(font.clone(), 12.0)
+            .at(72.0, 700.0)
+            .write("fit")
+            .unwrap();
+        page.graphics()
+            .begin_text()
+            .set_font(font.clone(), 12.0)
+            .set_text_position(72.0, 650.0)
+            .show_text("fi")
+            .unwrap()
+            .end_text();
+        let mut flow = page.text_flow();
+        flow.set_font(font, 12.0);
+        flow.write_paragraph("fin").unwrap();
+        flow.write_paragraph("fix").unwrap();
+        page.add_text_flow(&flow);
+
+        // The font is added after the page
+        let mut document = Document::new();
+        document.set_compress(false);
+        document.add_page(page);
+        document
+            .add_font_from_bytes(
+                "ShapingLater",
+                test_font::font(&[(b"GSUB", test_font::gsub())]),
+            )
+            .unwrap();
+
+        let pdf = String::from_utf8_lossy(&document.to_bytes().unwrap()).into_owned();
+        for shown in [
+            "[<E000> <0074>] TJ",
+            "[<E000>] TJ",
+            "[<E000> <006E>] TJ",
+            "[<E000> <0078>] TJ",
+        ] {
+            assert!(pdf.contains(shown), "{shown}");
+        }
+        assert!(!pdf.contains("<006600690074> Tj"));
+        assert!(!pdf.contains("(fi"));
+        assert!(pdf.contains("<E000> <00660069>"));
+    }
+
+    #[test]
+    fn test_right_to_left_run_is_reversed() {
+        let fonts = register_data("ShapingHebrew", &test_font::font(&[]));
+        let shaped = fonts.shape("ShapingHebrew", "ab אב").unwrap();
+        assert_eq!(
+            glyphs(&shaped),
+            vec![glyph('a'), glyph('b'), glyph(' '), glyph('ב'), glyph('א')]
+        );
+        assert_eq!(shaped.glyphs[3].cluster, 5..7);
+
+        // Shown reversed in a span with the text in logical order
+        let shown = fonts
+            .show_text("ShapingHebrew", "אב", None, 10.0, 0.0, 0.0, 0.0)
+            .unwrap();
+        assert_eq!(
+            shown,
+            "/Span <</ActualText <FEFF05D005D1>>> BDC\n[<05D1> <05D0>] TJ\nEMC\n"
+        );
+    }
+
+    #[test]
+    fn test_arabic_joining_forms() {
+        let data = test_font::font(&[(b"GSUB", test_font::arabic_gsub())]);
+        let fonts = register_data("ShapingArabic", &data);
+        let shaped = fonts
+            .shape("ShapingArabic", "\u{628}\u{628}\u{628}")
+            .unwrap();
+        // Shown right to left: final, medial and initial beh
+        assert_eq!(
+            glyphs(&shaped),
+            vec![glyph('\u{FE90}'), glyph('\u{FE92}'), glyph('\u{FE91}')]
+        );
+    }
+
+    #[test]
+    fn test_arabic_presentation_forms_without_gsub() {
+        let fonts = register_data("ShapingArabicForms", &test_font::font(&[]));
+        let shaped = fonts
+            .shape("ShapingArabicForms", "\u{628}\u{644}\u{627}")
+            .unwrap();
+        // The final lam-alef ligature and the initial beh
+        assert_eq!(glyphs(&shaped), vec![glyph('\u{FEFC}'), glyph('\u{FE91}')]);
+        assert_eq!(shaped.glyphs[0].cluster, 2..6);
+
+        let shaped = fonts.shape("ShapingArabicForms", "\u{644}\u{627}").unwrap();
+        assert_eq!(glyphs(&shaped), vec![glyph('\u{FEFB}')]);
+    }
+
+    #[test]
+    fn test_devanagari_reordering() {
+        let data = test_font::font(&[(b"GSUB", test_font::devanagari_gsub())]);
+        let fonts = register_data("ShapingDevanagari", &data);
+        // The i-matra is shown before the consonant
+        let shaped = fonts.shape("ShapingDevanagari", "\u{915}\u{93F}").unwrap();
+        assert_eq!(glyphs(&shaped), vec![glyph('\u{93F}'), glyph('\u{915}')]);
+        assert!(shaped.glyphs.iter().all(|g| g.cluster == (0..6)));
+
+        // The reph is formed and shown after the matra
+        let shaped = fonts
+            .shape("ShapingDevanagari", "\u{930}\u{94D}\u{915}\u{93E}")
+            .unwrap();
+        assert_eq!(
+            glyphs(&shaped),
+            vec![glyph('\u{915}'), glyph('\u{93E}'), REPH]
+        );
+    }
+
+    #[test]
+    fn test_thai_sara_am() {
+        let fonts = register_data("ShapingThai", &test_font::font(&[]));
+        let shaped = fonts.shape("ShapingThai", "\u{E01}\u{E48}\u{E33}").unwrap();
+        assert_eq!(
+            glyphs(&shaped),
+            vec![
+                glyph('\u{E01}'),
+                glyph('\u{E4D}'),
+                glyph('\u{E48}'),
+                glyph('\u{E32}')
+            ]
+        );
+        assert_eq!(shaped.glyphs[1].cluster, 3..9);
+    }
+
+    #[test]
+    fn test_right_to_left_text_extracted_in_logical_order() {
+        let mut document = Document::new();
+        document
+            .add_font_from_bytes("ShapingExtraction", test_font::font(&[]))
+            .unwrap();
+        let page = page_with_text(&document, "ShapingExtraction", "ab אב");
+        document.add_page(page);
+
+        let reader =
+            crate::parser::PdfReader::new(std::io::Cursor::new(document.to_bytes().unwrap()))
+                .unwrap();
+        let extracted = crate::parser::PdfDocument::new(reader)
+            .extract_text()
+            .unwrap();
+        assert_eq!(extracted[0].text.trim(), "ab אב");
+    }
+}
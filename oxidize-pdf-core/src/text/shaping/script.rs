@@ -0,0 +1,154 @@
+//! Scripts of the text and the layout features each is shaped with
+//!
+//! Features that apply to some characters only, such as the positional
+//! forms of Arabic letters, have a mask; a lookup applies to the glyphs
+//! whose mask shares a bit with the masks of its features.
+
+use std::ops::Range;
+
+/// Features applied to every glyph
+pub(super) const GLOBAL: u32 = 1;
+/// Arabic positional forms
+pub(super) const ISOLATED: u32 = 1 << 1;
+pub(super) const FINAL: u32 = 1 << 2;
+pub(super) const MEDIAL: u32 = 1 << 3;
+pub(super) const INITIAL: u32 = 1 << 4;
+/// Indic reph, half and below-base forms
+pub(super) const REPH: u32 = 1 << 5;
+pub(super) const HALF: u32 = 1 << 6;
+pub(super) const BELOW_BASE: u32 = 1 << 7;
+
+type Features = &'static [(&'static [u8; 4], u32)];
+
+const COMMON_GSUB: Features = &[
+    (b"ccmp", GLOBAL),
+    (b"locl", GLOBAL),
+    (b"rlig", GLOBAL),
+    (b"liga", GLOBAL),
+    (b"clig", GLOBAL),
+    (b"calt", GLOBAL),
+];
+
+const ARABIC_GSUB: Features = &[
+    (b"ccmp", GLOBAL),
+    (b"locl", GLOBAL),
+    (b"isol", ISOLATED),
+    (b"fina", FINAL),
+    (b"medi", MEDIAL),
+    (b"init", INITIAL),
+    (b"rlig", GLOBAL),
+    (b"calt", GLOBAL),
+    (b"liga", GLOBAL),
+    (b"clig", GLOBAL),
+    (b"mset", GLOBAL),
+];
+
+const DEVANAGARI_GSUB: Features = &[
+    (b"locl", GLOBAL),
+    (b"ccmp", GLOBAL),
+    (b"nukt", GLOBAL),
+    (b"akhn", GLOBAL),
+    (b"rphf", REPH),
+    (b"rkrf", GLOBAL),
+    (b"blwf", BELOW_BASE),
+    (b"half", HALF),
+    (b"vatu", GLOBAL),
+    (b"cjct", GLOBAL),
+    (b"pres", GLOBAL),
+    (b"abvs", GLOBAL),
+    (b"blws", GLOBAL),
+    (b"psts", GLOBAL),
+    (b"haln", GLOBAL),
+    (b"calt", GLOBAL),
+];
+
+const COMMON_GPOS: Features = &[(b"kern", GLOBAL), (b"mark", GLOBAL), (b"mkmk", GLOBAL)];
+
+const DEVANAGARI_GPOS: Features = &[
+    (b"kern", GLOBAL),
+    (b"dist", GLOBAL),
+    (b"abvm", GLOBAL),
+    (b"blwm", GLOBAL),
+    (b"mark", GLOBAL),
+    (b"mkmk", GLOBAL),
+];
+
+/// Scripts shaped with their own features; other text is `Common`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Script {
+    Common,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+}
+
+impl Script {
+    pub const ALL: [Script; 5] = [
+        Script::Common,
+        Script::Arabic,
+        Script::Hebrew,
+        Script::Devanagari,
+        Script::Thai,
+    ];
+
+    /// OpenType script tags, in order of preference
+    pub fn tags(self) -> &'static [&'static [u8; 4]] {
+        match self {
+            Script::Common => &[b"latn", b"DFLT"],
+            Script::Arabic => &[b"arab", b"DFLT"],
+            Script::Hebrew => &[b"hebr", b"DFLT"],
+            Script::Devanagari => &[b"dev2", b"deva", b"DFLT"],
+            Script::Thai => &[b"thai", b"DFLT"],
+        }
+    }
+
+    pub fn gsub_features(self) -> Features {
+        match self {
+            Script::Arabic => ARABIC_GSUB,
+            Script::Devanagari => DEVANAGARI_GSUB,
+            _ => COMMON_GSUB,
+        }
+    }
+
+    pub fn gpos_features(self) -> Features {
+        match self {
+            Script::Devanagari => DEVANAGARI_GPOS,
+            _ => COMMON_GPOS,
+        }
+    }
+}
+
+/// Script of a letter; `None` for characters that take the script of the
+/// text around them: spaces, digits, punctuation and marks
+fn script_of(ch: char) -> Option<Script> {
+    match ch as u32 {
+        0x0590..=0x05FF | 0xFB1D..=0xFB4F => Some(Script::Hebrew),
+        0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFE => {
+            Some(Script::Arabic)
+        }
+        0x0900..=0x097F | 0xA8E0..=0xA8FF => Some(Script::Devanagari),
+        0x0E00..=0x0E7F => Some(Script::Thai),
+        _ if ch.is_alphabetic() => Some(Script::Common),
+        _ => None,
+    }
+}
+
+/// Split text into runs of one script
+pub(super) fn itemize(text: &str) -> Vec<(Script, Range<usize>)> {
+    let mut runs: Vec<(Script, Range<usize>)> = Vec::new();
+    for (index, ch) in text.char_indices() {
+        let end = index + ch.len_utf8();
+        match (script_of(ch), runs.last_mut()) {
+            (Some(script), Some(run)) if run.0 != script => runs.push((script, index..end)),
+            // Characters before the first letter go with it
+            (Some(script), None) => runs.push((script, 0..end)),
+            (_, Some(run)) => run.1.end = end,
+            (None, None) => {}
+        }
+    }
+    if runs.is_empty() && !text.is_empty() {
+        runs.push((Script::Common, 0..text.len()));
+    }
+    runs
+}
@@ -0,0 +1,280 @@
+//! A small synthetic TrueType font with layout tables, for shaping tests
+//!
+//! Glyphs 1 to 95 are the printable ASCII characters, glyph 96 is an "fi"
+//! ligature, 97 an alternate "e" and 98 the combining acute accent
+//! U+0301. The glyphs after them are a few Hebrew, Arabic, Devanagari and
+//! Thai characters, Arabic presentation forms and a Devanagari reph.
+//! Advances are 500 units of a 1000 unit em, except the space (250), the
+//! ligature (900) and the marks (0).
+
+pub const LIGATURE: u16 = 96;
+pub const ALTERNATE_E: u16 = 97;
+pub const ACUTE: u16 = 98;
+pub const REPH: u16 = 126;
+const GLYPHS: u16 = 127;
+
+/// Glyphs with no advance: the accent, the virama and the Thai marks
+const MARKS: [u16; 4] = [ACUTE, 109, 112, 113];
+
+/// Characters of the cmap: first and last character and the glyph of the
+/// first
+const SEGMENTS: &[(u16, u16, u16)] = &[
+    (0x20, 0x7E, 1),
+    (0x0301, 0x0301, ACUTE),
+    (0x05D0, 0x05D2, 99),
+    (0x0627, 0x0628, 102),
+    (0x0644, 0x0644, 104),
+    (0x0915, 0x0915, 105),
+    (0x0930, 0x0930, 106),
+    (0x093E, 0x093F, 107),
+    (0x094D, 0x094D, 109),
+    (0x0E01, 0x0E01, 110),
+    (0x0E32, 0x0E32, 111),
+    (0x0E48, 0x0E48, 112),
+    (0x0E4D, 0x0E4D, 113),
+    (0xFE8D, 0xFE92, 114),
+    (0xFEDD, 0xFEE0, 120),
+    (0xFEFB, 0xFEFC, 124),
+];
+
+/// Glyph of a character of the cmap
+pub fn glyph(ch: char) -> u16 {
+    let code = ch as u32;
+    SEGMENTS
+        .iter()
+        .find(|&&(first, last, _)| (first as u32..=last as u32).contains(&code))
+        .map(|&(first, _, glyph)| glyph + (code - first as u32) as u16)
+        .expect("character of the test font")
+}
+
+fn words(values: &[u16]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect()
+}
+
+fn coverage(glyph: u16) -> Vec<u8> {
+    words(&[1, 1, glyph])
+}
+
+/// A font with the base tables and `extra` tables
+pub fn font(extra: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut head = vec![0u8; 54];
+    head[0..4].copy_from_slice(&[0, 1, 0, 0]);
+    head[12..16].copy_from_slice(&[0x5F, 0x0F, 0x3C, 0xF5]);
+    head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+    head[40..44].copy_from_slice(&words(&[1000, 800]));
+
+    let mut hhea = vec![0u8; 36];
+    hhea[0..4].copy_from_slice(&[0, 1, 0, 0]);
+    hhea[4..8].copy_from_slice(&words(&[800, (-200i16) as u16]));
+    hhea[34..36].copy_from_slice(&GLYPHS.to_be_bytes());
+
+    let maxp = [vec![0, 0, 0x50, 0], words(&[GLYPHS])].concat();
+    let hmtx = words(
+        &(0..GLYPHS)
+            .flat_map(|glyph| {
+                let advance = match glyph {
+                    1 => 250,
+                    LIGATURE => 900,
+                    _ if MARKS.contains(&glyph) => 0,
+                    _ => 500,
+                };
+                [advance, 0]
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    // Format 4 subtable of the segments and the final one
+    let mut segments = SEGMENTS.to_vec();
+    segments.push((0xFFFF, 0xFFFF, 0));
+    let count = segments.len() as u16;
+    let search_range = 2 * (1u16 << (15 - count.leading_zeros()));
+    let mut subtable = words(&[
+        4,
+        16 + 8 * count,
+        0,
+        2 * count,
+        search_range,
+        search_range.trailing_zeros() as u16 - 1,
+        2 * count - search_range,
+    ]);
+    subtable.extend(words(&segments.iter().map(|s| s.1).collect::<Vec<_>>()));
+    subtable.extend(words(&[0]));
+    subtable.extend(words(&segments.iter().map(|s| s.0).collect::<Vec<_>>()));
+    subtable.extend(words(
+        &segments
+            .iter()
+            .map(|s| s.2.wrapping_sub(s.0))
+            .collect::<Vec<_>>(),
+    ));
+    subtable.extend(words(&vec![0; count as usize]));
+    let cmap = [words(&[0, 1, 3, 1, 0, 12]), subtable].concat();
+
+    let mut tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"maxp", maxp),
+        (b"hmtx", hmtx),
+        (b"cmap", cmap),
+        (b"loca", vec![0; (GLYPHS as usize + 1) * 2]),
+        (b"glyf", vec![0; 4]),
+        (b"name", words(&[0, 0, 6])),
+    ];
+    tables.extend(extra.iter().cloned());
+
+    let mut data = words(&[1, 0, tables.len() as u16, 0, 0, 0]);
+    let mut offset = 12 + tables.len() * 16;
+    let mut bodies = Vec::new();
+    for (tag, table) in &tables {
+        data.extend_from_slice(*tag);
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        let mut body = table.clone();
+        body.resize(table.len().next_multiple_of(4), 0);
+        offset += body.len();
+        bodies.extend(body);
+    }
+    data.extend(bodies);
+    data
+}
+
+/// A GSUB or GPOS table with the `DFLT` script, the features (tag and
+/// lookup indices) and the lookups (type and subtable)
+pub fn layout_table(features: &[(&[u8; 4], &[u16])], lookups: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let count = features.len() as u16;
+    let mut scripts = [b"DFLT".to_vec(), words(&[8, 4, 0, 0, 0xFFFF, count])].concat();
+    scripts.splice(0..0, words(&[1]));
+    scripts.extend(words(&(0..count).collect::<Vec<_>>()));
+
+    let mut feature_list = words(&[count]);
+    let mut feature_tables = Vec::new();
+    for (tag, indices) in features {
+        let offset = 2 + features.len() * 6 + feature_tables.len();
+        feature_list.extend_from_slice(*tag);
+        feature_list.extend(words(&[offset as u16]));
+        feature_tables.extend(words(&[0, indices.len() as u16]));
+        feature_tables.extend(words(indices));
+    }
+    feature_list.extend(feature_tables);
+
+    let mut lookup_list = words(&[lookups.len() as u16]);
+    let mut lookup_tables = Vec::new();
+    for (kind, subtable) in lookups {
+        let offset = 2 + lookups.len() * 2 + lookup_tables.len();
+        lookup_list.extend(words(&[offset as u16]));
+        lookup_tables.extend(words(&[*kind, 0, 1, 8]));
+        lookup_tables.extend(subtable);
+    }
+    lookup_list.extend(lookup_tables);
+
+    let feature_offset = 10 + scripts.len();
+    let lookup_offset = feature_offset + feature_list.len();
+    [
+        words(&[1, 0, 10, feature_offset as u16, lookup_offset as u16]),
+        scripts,
+        feature_list,
+        lookup_list,
+    ]
+    .concat()
+}
+
+/// GSUB with the "fi" ligature and an alternate "e" after "x"
+pub fn gsub() -> Vec<u8> {
+    let ligature = [
+        words(&[1, 8, 1, 14]),
+        coverage(glyph('f')),
+        words(&[1, 4, LIGATURE, 2, glyph('i')]),
+    ]
+    .concat();
+    let chained = [
+        words(&[3, 1, 18, 1, 24, 0, 1, 0, 2]),
+        coverage(glyph('x')),
+        coverage(glyph('e')),
+    ]
+    .concat();
+    let single = [
+        words(&[1, 6, ALTERNATE_E - glyph('e')]),
+        coverage(glyph('e')),
+    ]
+    .concat();
+    layout_table(
+        &[(b"liga", &[0]), (b"calt", &[1])],
+        &[(4, ligature), (6, chained), (1, single)],
+    )
+}
+
+/// A single substitution of one glyph
+fn single(from: u16, to: u16) -> Vec<u8> {
+    [words(&[1, 6, to.wrapping_sub(from)]), coverage(from)].concat()
+}
+
+/// GSUB with the initial, medial and final forms of beh
+pub fn arabic_gsub() -> Vec<u8> {
+    let beh = glyph('\u{628}');
+    layout_table(
+        &[(b"init", &[0]), (b"medi", &[1]), (b"fina", &[2])],
+        &[
+            (1, single(beh, glyph('\u{FE91}'))),
+            (1, single(beh, glyph('\u{FE92}'))),
+            (1, single(beh, glyph('\u{FE90}'))),
+        ],
+    )
+}
+
+/// GSUB forming the Devanagari reph from ra and virama
+pub fn devanagari_gsub() -> Vec<u8> {
+    let reph = [
+        words(&[1, 8, 1, 14]),
+        coverage(glyph('\u{930}')),
+        words(&[1, 4, REPH, 2, glyph('\u{94D}')]),
+    ]
+    .concat();
+    layout_table(&[(b"rphf", &[0])], &[(4, reph)])
+}
+
+/// GPOS kerning "AV" by -80 and placing the accent on "e"
+pub fn gpos() -> Vec<u8> {
+    let pair = [
+        words(&[1, 12, 4, 0, 1, 18]),
+        coverage(glyph('A')),
+        words(&[1, glyph('V'), (-80i16) as u16]),
+    ]
+    .concat();
+    let mark = [
+        words(&[1, 12, 18, 1, 24, 36]),
+        coverage(ACUTE),
+        coverage(glyph('e')),
+        words(&[1, 0, 6, 1, 0, 500]),
+        words(&[1, 4, 1, 250, 700]),
+    ]
+    .concat();
+    layout_table(&[(b"kern", &[0]), (b"mark", &[1])], &[(2, pair), (4, mark)])
+}
+
+/// GDEF classifying the accent as a mark
+pub fn gdef() -> Vec<u8> {
+    words(&[
+        1, 0, 12, 0, 0, 0, 2, 3, 1, 95, 1, 96, 96, 2, ACUTE, ACUTE, 3,
+    ])
+}
+
+/// A `kern` table kerning "AV" by -80
+pub fn kern() -> Vec<u8> {
+    words(&[
+        0,
+        1,
+        0,
+        20,
+        1,
+        1,
+        6,
+        0,
+        0,
+        glyph('A'),
+        glyph('V'),
+        (-80i16) as u16,
+    ])
+}
@@ -0,0 +1,44 @@
+//! Thai: SARA AM is shown as NIKHAHIT and SARA AA, with the NIKHAHIT
+//! placed before the tone marks above the consonant
+
+use super::CharItem;
+
+const SARA_AM: char = '\u{E33}';
+const SARA_AA: char = '\u{E32}';
+const NIKHAHIT: char = '\u{E4D}';
+
+/// Vowels and tone marks shown above the consonant
+fn is_above_mark(ch: char) -> bool {
+    matches!(ch, '\u{E31}' | '\u{E34}'..='\u{E37}' | '\u{E47}'..='\u{E4E}')
+}
+
+/// Decompose SARA AM, when the font has glyphs for its parts; the marks
+/// it moves before become one cluster with it
+pub(super) fn decompose_sara_am(chars: &mut Vec<CharItem>, has_glyph: impl Fn(char) -> bool) {
+    if !has_glyph(NIKHAHIT) || !has_glyph(SARA_AA) {
+        return;
+    }
+    let mut index = 0;
+    while index < chars.len() {
+        if chars[index].ch != SARA_AM {
+            index += 1;
+            continue;
+        }
+        let start = (0..index)
+            .rev()
+            .take_while(|&k| is_above_mark(chars[k].ch))
+            .last()
+            .unwrap_or(index);
+        let cluster = chars[start].cluster.start..chars[index].cluster.end;
+        chars[index].ch = SARA_AA;
+        let nikhahit = CharItem {
+            ch: NIKHAHIT,
+            ..chars[index].clone()
+        };
+        chars.insert(start, nikhahit);
+        for item in &mut chars[start..=index + 1] {
+            item.cluster = cluster.clone();
+        }
+        index += 2;
+    }
+}
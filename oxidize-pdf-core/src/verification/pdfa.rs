@@ -0,0 +1,704 @@
+//! Native PDF/A conformance checking (ISO 19005-1, -2 and -3, level B)
+//!
+//! [`check_pdfa`] validates a file against the main rules of a PDF/A
+//! level without external tools: file header and identifiers, encryption,
+//! output intent, XMP identification, font embedding, forbidden actions
+//! and filters, annotation flags, transparency (PDF/A-1) and embedded
+//! files. It is not a replacement for a full validator such as veraPDF,
+//! but catches what typically breaks archival output.
+//!
+//! Documents are written in a PDF/A level with [`Document::set_pdfa`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use oxidize_pdf::verification::pdfa::{check_pdfa, PdfALevel};
+//! use oxidize_pdf::{Document, Page};
+//!
+//! # fn main() -> oxidize_pdf::Result<()> {
+//! let mut doc = Document::new();
+//! doc.set_title("Archived report");
+//! doc.add_page(Page::a4());
+//! doc.set_pdfa(Some(PdfALevel::A2b));
+//! let pdf = doc.to_bytes()?;
+//!
+//! let report = check_pdfa(&pdf, PdfALevel::A2b)?;
+//! assert!(report.is_compliant(), "{:?}", report.violations);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Document::set_pdfa`]: crate::Document::set_pdfa
+
+use crate::error::Result;
+use crate::metadata::{XmpMetadata, XmpNamespace};
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::{PdfDocument, PdfReader};
+use crate::parser::resolve::{get, get_dict, get_name, stream_data, Resolver};
+use quick_xml::events::Event;
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use std::fmt;
+use std::io::{Cursor, Read, Seek};
+
+/// Namespace of the PDF/A identification schema
+const PDFAID_NAMESPACE: &str = "http://www.aiim.org/pdfa/ns/id/";
+
+/// Actions PDF/A does not allow, in any part
+const FORBIDDEN_ACTIONS: &[&str] = &[
+    "Launch",
+    "Sound",
+    "Movie",
+    "ResetForm",
+    "ImportData",
+    "JavaScript",
+    "Hide",
+    "SetOCGState",
+    "Rendition",
+    "Trans",
+    "GoTo3DView",
+];
+
+/// PDF/A part and conformance level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PdfALevel {
+    /// ISO 19005-1, level B: PDF 1.4 without transparency or attachments
+    A1b,
+    /// ISO 19005-2, level B: PDF 1.7, attachments must be PDF/A
+    A2b,
+    /// ISO 19005-3, level B: PDF/A-2 with arbitrary associated files
+    A3b,
+}
+
+impl PdfALevel {
+    /// Part of ISO 19005, as written in `pdfaid:part`
+    pub fn part(&self) -> u8 {
+        match self {
+            PdfALevel::A1b => 1,
+            PdfALevel::A2b => 2,
+            PdfALevel::A3b => 3,
+        }
+    }
+
+    /// Conformance level, as written in `pdfaid:conformance`
+    pub fn conformance(&self) -> &'static str {
+        "B"
+    }
+
+    /// PDF version the part is based on
+    pub fn pdf_version(&self) -> &'static str {
+        match self {
+            PdfALevel::A1b => "1.4",
+            PdfALevel::A2b | PdfALevel::A3b => "1.7",
+        }
+    }
+
+    /// Whether transparency groups, soft masks and constant alpha are allowed
+    pub fn allows_transparency(&self) -> bool {
+        *self != PdfALevel::A1b
+    }
+
+    /// Add the `pdfaid` identification schema
+    pub(crate) fn add_xmp(&self, xmp: &mut XmpMetadata) {
+        let pdfaid = XmpNamespace::Custom("pdfaid".to_string(), PDFAID_NAMESPACE.to_string());
+        xmp.set_text(pdfaid.clone(), "part", self.part().to_string());
+        xmp.set_text(pdfaid, "conformance", self.conformance());
+    }
+}
+
+impl fmt::Display for PdfALevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PDF/A-{}{}", self.part(), self.conformance().to_lowercase())
+    }
+}
+
+/// Group of PDF/A requirements a violation belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PdfARule {
+    FileHeader,
+    FileIdentifier,
+    Encryption,
+    Filter,
+    OutputIntent,
+    Metadata,
+    Font,
+    Transparency,
+    Action,
+    Annotation,
+    EmbeddedFile,
+}
+
+impl PdfARule {
+    /// Clause of the ISO 19005 part that states the requirement
+    pub fn clause(&self, level: PdfALevel) -> &'static str {
+        let part1 = level == PdfALevel::A1b;
+        match self {
+            PdfARule::FileHeader => "6.1.2",
+            PdfARule::FileIdentifier | PdfARule::Encryption => "6.1.3",
+            PdfARule::Filter if part1 => "6.1.10",
+            PdfARule::Filter => "6.1.7.2",
+            PdfARule::OutputIntent if part1 => "6.2.2",
+            PdfARule::OutputIntent => "6.2.3",
+            PdfARule::Metadata if part1 => "6.7.11",
+            PdfARule::Metadata => "6.6.4",
+            PdfARule::Font if part1 => "6.3.4",
+            PdfARule::Font => "6.2.11.4",
+            PdfARule::Transparency => "6.4",
+            PdfARule::Action => "6.6.1",
+            PdfARule::Annotation if part1 => "6.5.3",
+            PdfARule::Annotation => "6.3.2",
+            PdfARule::EmbeddedFile if part1 => "6.1.11",
+            PdfARule::EmbeddedFile => "6.8",
+        }
+    }
+}
+
+/// A requirement the file does not meet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfAViolation {
+    pub rule: PdfARule,
+    /// Clause of ISO 19005 for the checked level
+    pub clause: &'static str,
+    pub message: String,
+}
+
+/// Outcome of [`check_pdfa`]
+#[derive(Debug, Clone)]
+pub struct PdfAReport {
+    pub level: PdfALevel,
+    /// Violations in the order found, without duplicates
+    pub violations: Vec<PdfAViolation>,
+}
+
+impl PdfAReport {
+    pub fn is_compliant(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn add(&mut self, rule: PdfARule, message: impl Into<String>) {
+        let violation = PdfAViolation {
+            rule,
+            clause: rule.clause(self.level),
+            message: message.into(),
+        };
+        if !self.violations.contains(&violation) {
+            self.violations.push(violation);
+        }
+    }
+}
+
+/// Check a file against the main rules of a PDF/A level
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be parsed at all.
+pub fn check_pdfa(pdf: &[u8], level: PdfALevel) -> Result<PdfAReport> {
+    let mut report = PdfAReport {
+        level,
+        violations: Vec::new(),
+    };
+    check_header(pdf, &mut report);
+
+    let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf.to_vec()))?);
+    check_document(&document, &mut report)?;
+    Ok(report)
+}
+
+/// `%PDF-1.n` followed by a comment of at least four binary bytes
+fn check_header(pdf: &[u8], report: &mut PdfAReport) {
+    if !pdf.starts_with(b"%PDF-1.") {
+        report.add(PdfARule::FileHeader, "file does not start with %PDF-1.n");
+        return;
+    }
+    let comment = pdf
+        .split(|&b| b == b'\n' || b == b'\r')
+        .find(|line| !line.is_empty() && !line.starts_with(b"%PDF-"));
+    let binary = comment
+        .filter(|line| line.starts_with(b"%"))
+        .is_some_and(|line| line.iter().filter(|&&b| b > 127).count() >= 4);
+    if !binary {
+        report.add(
+            PdfARule::FileHeader,
+            "header is not followed by a comment of binary characters",
+        );
+    }
+}
+
+fn check_document<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    report: &mut PdfAReport,
+) -> Result<()> {
+    let resolver: &dyn Resolver = document;
+    let level = report.level;
+
+    let trailer = document.trailer();
+    if trailer.id().is_none() {
+        report.add(PdfARule::FileIdentifier, "trailer has no /ID");
+    }
+    if trailer.is_encrypted() {
+        report.add(PdfARule::Encryption, "file is encrypted");
+    }
+
+    let catalog = document.catalog()?;
+    check_identification(resolver, &catalog, report);
+    check_output_intents(resolver, &catalog, report);
+
+    if catalog.contains_key("AA") {
+        report.add(PdfARule::Action, "catalog has additional actions (/AA)");
+    }
+    if let Some(names) = get_dict(resolver, &catalog, "Names") {
+        if names.contains_key("JavaScript") {
+            report.add(PdfARule::Action, "document-level JavaScript");
+        }
+    }
+
+    for attachment in document.attachments()? {
+        let file = &attachment.file;
+        match level {
+            PdfALevel::A1b => report.add(
+                PdfARule::EmbeddedFile,
+                format!("embedded file {} (not allowed in PDF/A-1)", file.file_name),
+            ),
+            PdfALevel::A2b => {
+                if file.mime_type.as_deref() != Some("application/pdf") {
+                    report.add(
+                        PdfARule::EmbeddedFile,
+                        format!("embedded file {} is not a PDF/A file", file.file_name),
+                    );
+                }
+            }
+            PdfALevel::A3b => {
+                if file.mime_type.is_none() {
+                    report.add(
+                        PdfARule::EmbeddedFile,
+                        format!("embedded file {} has no MIME type", file.file_name),
+                    );
+                }
+                if file.relationship.is_none() {
+                    report.add(
+                        PdfARule::EmbeddedFile,
+                        format!("embedded file {} has no /AFRelationship", file.file_name),
+                    );
+                }
+                if file.modification_date.is_none() {
+                    report.add(
+                        PdfARule::EmbeddedFile,
+                        format!("embedded file {} has no /ModDate", file.file_name),
+                    );
+                }
+            }
+        }
+    }
+
+    // Object-level rules apply wherever the object is used
+    let mut entries: Vec<_> = document.xref_entries().into_iter().collect();
+    entries.sort_by_key(|&(number, _)| number);
+    for (number, entry) in entries {
+        let generation = if entry.compressed_info.is_some() {
+            0
+        } else {
+            entry.basic.generation
+        };
+        if let Ok(object) = document.get_object(number, generation) {
+            check_object(resolver, &object, report);
+        }
+    }
+    Ok(())
+}
+
+/// `pdfaid:part` and `pdfaid:conformance` in the catalog's XMP metadata
+fn check_identification(resolver: &dyn Resolver, catalog: &PdfDictionary, report: &mut PdfAReport) {
+    let level = report.level;
+    let Some(PdfObject::Stream(stream)) = get(resolver, catalog, "Metadata") else {
+        report.add(PdfARule::Metadata, "catalog has no XMP metadata stream");
+        return;
+    };
+    let Some(xmp) = stream_data(resolver, &stream) else {
+        report.add(PdfARule::Metadata, "XMP metadata cannot be decoded");
+        return;
+    };
+    let (part, conformance) = pdfa_identification(&xmp);
+    if part.as_deref() != Some(&level.part().to_string()) {
+        report.add(
+            PdfARule::Metadata,
+            format!(
+                "XMP identifies PDF/A part {}, expected {}",
+                part.as_deref().unwrap_or("none"),
+                level.part()
+            ),
+        );
+    }
+    // Level A and U files also meet level B
+    if !matches!(conformance.as_deref(), Some("A" | "B" | "U")) {
+        report.add(
+            PdfARule::Metadata,
+            format!(
+                "XMP conformance {} does not meet level {}",
+                conformance.as_deref().unwrap_or("none"),
+                level.conformance()
+            ),
+        );
+    }
+}
+
+/// Values of `pdfaid:part` and `pdfaid:conformance`, written as elements
+/// or as attributes of `rdf:Description`
+fn pdfa_identification(xmp: &[u8]) -> (Option<String>, Option<String>) {
+    let mut reader = NsReader::from_reader(xmp);
+    reader.trim_text(true);
+    let mut part = None;
+    let mut conformance = None;
+    let mut current: Option<String> = None;
+    let mut buf = Vec::new();
+    loop {
+        let (namespace, event) = match reader.read_resolved_event_into(&mut buf) {
+            Ok(result) => result,
+            Err(_) => break,
+        };
+        let in_pdfaid = |result: &ResolveResult| {
+            matches!(result, ResolveResult::Bound(ns) if ns.as_ref() == PDFAID_NAMESPACE.as_bytes())
+        };
+        match event {
+            Event::Start(e) | Event::Empty(e) => {
+                current = in_pdfaid(&namespace)
+                    .then(|| String::from_utf8_lossy(e.local_name().as_ref()).to_string());
+                for attr in e.attributes().flatten() {
+                    let (attr_namespace, name) = reader.resolve_attribute(attr.key);
+                    if !in_pdfaid(&attr_namespace) {
+                        continue;
+                    }
+                    let value = String::from_utf8_lossy(&attr.value).trim().to_string();
+                    match name.as_ref() {
+                        b"part" => part = Some(value),
+                        b"conformance" => conformance = Some(value),
+                        _ => {}
+                    }
+                }
+            }
+            Event::Text(text) => {
+                let value = text.unescape().unwrap_or_default().trim().to_string();
+                match current.as_deref() {
+                    Some("part") => part = Some(value),
+                    Some("conformance") => conformance = Some(value),
+                    _ => {}
+                }
+            }
+            Event::End(_) => current = None,
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    (part, conformance)
+}
+
+/// A `GTS_PDFA1` output intent with a valid ICC profile
+fn check_output_intents(resolver: &dyn Resolver, catalog: &PdfDictionary, report: &mut PdfAReport) {
+    let intents = get(resolver, catalog, "OutputIntents")
+        .and_then(|intents| intents.as_array().cloned())
+        .map(|intents| intents.0)
+        .unwrap_or_default();
+    let intent = intents.iter().find_map(|intent| match resolver.lookup(intent) {
+        PdfObject::Dictionary(dict)
+            if get_name(resolver, &dict, "S").as_deref() == Some("GTS_PDFA1") =>
+        {
+            Some(dict)
+        }
+        _ => None,
+    });
+    let Some(intent) = intent else {
+        report.add(
+            PdfARule::OutputIntent,
+            "no GTS_PDFA1 output intent for device-dependent colours",
+        );
+        return;
+    };
+
+    let Some(PdfObject::Stream(profile)) = get(resolver, &intent, "DestOutputProfile") else {
+        report.add(PdfARule::OutputIntent, "output intent has no DestOutputProfile");
+        return;
+    };
+    let data = stream_data(resolver, &profile).unwrap_or_default();
+    if data.len() < 128 || &data[36..40] != b"acsp" {
+        report.add(PdfARule::OutputIntent, "output profile is not an ICC profile");
+        return;
+    }
+    if report.level == PdfALevel::A1b && data[8] >= 4 {
+        report.add(
+            PdfARule::OutputIntent,
+            "ICC profile version 4 is not allowed in PDF/A-1",
+        );
+    }
+    let components = match &data[16..20] {
+        b"GRAY" => Some(1),
+        b"RGB " | b"Lab " => Some(3),
+        b"CMYK" => Some(4),
+        _ => None,
+    };
+    let n = get(resolver, &profile.dict, "N").and_then(|n| n.as_integer());
+    if components.is_none() || n != components {
+        report.add(
+            PdfARule::OutputIntent,
+            "output profile /N does not match its colour space",
+        );
+    }
+}
+
+/// Rules about single dictionaries and streams, applied recursively to
+/// direct objects
+fn check_object(resolver: &dyn Resolver, object: &PdfObject, report: &mut PdfAReport) {
+    match object {
+        PdfObject::Dictionary(dict) => {
+            check_dictionary(resolver, dict, report);
+            for value in dict.0.values() {
+                check_object(resolver, value, report);
+            }
+        }
+        PdfObject::Stream(stream) => {
+            let filters = match stream.dict.get("Filter") {
+                Some(PdfObject::Name(name)) => vec![name.as_str().to_string()],
+                Some(PdfObject::Array(names)) => names
+                    .0
+                    .iter()
+                    .filter_map(|name| name.as_name().map(|n| n.as_str().to_string()))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if filters.iter().any(|f| f == "LZWDecode") {
+                report.add(PdfARule::Filter, "stream uses the LZWDecode filter");
+            }
+            check_dictionary(resolver, &stream.dict, report);
+            for value in stream.dict.0.values() {
+                check_object(resolver, value, report);
+            }
+        }
+        PdfObject::Array(items) => {
+            for item in &items.0 {
+                check_object(resolver, item, report);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_dictionary(resolver: &dyn Resolver, dict: &PdfDictionary, report: &mut PdfAReport) {
+    let name = |key: &str| {
+        dict.get(key)
+            .and_then(|value| value.as_name())
+            .map(|name| name.as_str().to_string())
+    };
+    let doc_type = name("Type");
+    let subtype = name("Subtype");
+
+    if let Some(action) = name("S").filter(|s| FORBIDDEN_ACTIONS.contains(&s.as_str())) {
+        if doc_type.is_none() || doc_type.as_deref() == Some("Action") {
+            report.add(PdfARule::Action, format!("{action} action"));
+        }
+    }
+
+    match doc_type.as_deref() {
+        Some("Font") => check_font(resolver, dict, subtype.as_deref(), report),
+        Some("Annot") if subtype.as_deref() != Some("Popup") => {
+            let flags = dict
+                .get("F")
+                .and_then(|flags| flags.as_integer())
+                .unwrap_or(0);
+            // Print set; Invisible, Hidden and NoView clear
+            if flags & 4 == 0 || flags & (1 | 2 | 32) != 0 {
+                report.add(
+                    PdfARule::Annotation,
+                    format!(
+                        "{} annotation is not printed or is hidden",
+                        subtype.as_deref().unwrap_or("unknown")
+                    ),
+                );
+            }
+        }
+        _ => {}
+    }
+
+    if !report.level.allows_transparency() {
+        if let Some(mask) = dict.get("SMask") {
+            if mask.as_name().map(|n| n.as_str()) != Some("None") {
+                report.add(PdfARule::Transparency, "soft mask");
+            }
+        }
+        for key in ["CA", "ca"] {
+            if let Some(alpha) = dict.get(key).and_then(|alpha| alpha.as_real()) {
+                if alpha < 1.0 {
+                    report.add(PdfARule::Transparency, format!("constant alpha /{key} {alpha}"));
+                }
+            }
+        }
+        if let Some(mode) = name("BM").filter(|m| m != "Normal" && m != "Compatible") {
+            report.add(PdfARule::Transparency, format!("blend mode {mode}"));
+        }
+        if name("S").as_deref() == Some("Transparency") {
+            report.add(PdfARule::Transparency, "transparency group");
+        }
+    }
+}
+
+/// Every font except Type 3 must embed its font program
+fn check_font(
+    resolver: &dyn Resolver,
+    font: &PdfDictionary,
+    subtype: Option<&str>,
+    report: &mut PdfAReport,
+) {
+    // Composite fonts are checked through their descendant
+    if matches!(subtype, Some("Type3" | "Type0")) {
+        return;
+    }
+    let embedded = get_dict(resolver, font, "FontDescriptor").is_some_and(|descriptor| {
+        ["FontFile", "FontFile2", "FontFile3"]
+            .iter()
+            .any(|key| descriptor.contains_key(key))
+    });
+    if !embedded {
+        let base_font = get_name(resolver, font, "BaseFont").unwrap_or_default();
+        report.add(PdfARule::Font, format!("font {base_font} is not embedded"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Document, Page};
+
+    fn archive() -> Document {
+        let mut doc = Document::new();
+        doc.set_title("Archive");
+        doc.add_page(Page::a4());
+        doc
+    }
+
+    fn write_pdfa(doc: &mut Document, level: PdfALevel) -> Result<Vec<u8>> {
+        doc.set_pdfa(Some(level));
+        doc.to_bytes()
+    }
+
+    #[test]
+    fn test_pdfa_output_passes_checker() {
+        for level in [PdfALevel::A1b, PdfALevel::A2b, PdfALevel::A3b] {
+            let pdf = write_pdfa(&mut archive(), level).unwrap();
+            let report = check_pdfa(&pdf, level).unwrap();
+            assert!(report.is_compliant(), "{level}: {:?}", report.violations);
+        }
+    }
+
+    #[test]
+    fn test_regular_output_fails_checker() {
+        let pdf = archive().to_bytes().unwrap();
+        let report = check_pdfa(&pdf, PdfALevel::A2b).unwrap();
+        let rules: Vec<_> = report.violations.iter().map(|v| v.rule).collect();
+        assert!(rules.contains(&PdfARule::FileIdentifier));
+        assert!(rules.contains(&PdfARule::OutputIntent));
+        assert!(rules.contains(&PdfARule::Metadata));
+        // Standard 14 fonts are not embedded
+        assert!(rules.contains(&PdfARule::Font));
+    }
+
+    #[test]
+    fn test_level_mismatch_and_attachments() {
+        let mut doc = archive();
+        doc.attach_file(
+            crate::attachments::EmbeddedFile::new("data.csv", b"a,b\n".to_vec())
+                .with_mime_type("text/csv")
+                .with_modification_date(chrono::Utc::now())
+                .with_relationship(crate::attachments::AFRelationship::Source),
+        );
+        let pdf = write_pdfa(&mut doc, PdfALevel::A3b).unwrap();
+        assert!(check_pdfa(&pdf, PdfALevel::A3b).unwrap().is_compliant());
+
+        let report = check_pdfa(&pdf, PdfALevel::A2b).unwrap();
+        let clauses: Vec<_> = report.violations.iter().map(|v| v.clause).collect();
+        assert!(clauses.contains(&"6.6.4"));
+        assert!(clauses.contains(&"6.8"));
+    }
+
+    #[test]
+    fn test_writer_rejects_forbidden_features() {
+        let mut doc = archive();
+        doc.attach_file(crate::attachments::EmbeddedFile::new(
+            "data.csv",
+            b"a,b\n".to_vec(),
+        ));
+        assert!(write_pdfa(&mut doc, PdfALevel::A1b).is_err());
+
+        // PDF/A-3 attachments need a relationship, a MIME type and a date
+        let csv = crate::attachments::EmbeddedFile::new("data.csv", b"a,b\n".to_vec())
+            .with_relationship(crate::attachments::AFRelationship::Data);
+        for file in [
+            csv.clone().with_mime_type("text/csv"),
+            csv.clone().with_modification_date(chrono::Utc::now()),
+        ] {
+            let mut doc = archive();
+            doc.attach_file(file);
+            assert!(write_pdfa(&mut doc, PdfALevel::A3b).is_err());
+        }
+
+        let mut doc = archive();
+        doc.encrypt_with_passwords("user", "owner");
+        assert!(write_pdfa(&mut doc, PdfALevel::A2b).is_err());
+
+        let mut doc = archive();
+        let mut page = Page::a4();
+        page.text()
+            .set_font(crate::Font::Helvetica, 12.0)
+            .at(72.0, 720.0)
+            .write("Not embeddable")
+            .unwrap();
+        doc.add_page(page);
+        let error = write_pdfa(&mut doc, PdfALevel::A2b).unwrap_err().to_string();
+        assert!(error.contains("standard font Helvetica"), "{error}");
+        assert!(error.contains("Document::add_font"), "{error}");
+        assert!(error.contains("Liberation Sans"), "{error}");
+    }
+
+    #[test]
+    fn test_file_identifier_is_reproducible() {
+        let date =
+            chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2024, 5, 17, 10, 30, 0).unwrap();
+        let write = |title: &str| {
+            let mut doc = archive();
+            doc.set_title(title);
+            doc.set_creation_date(date);
+            doc.set_modification_date(date);
+            write_pdfa(&mut doc, PdfALevel::A2b).unwrap()
+        };
+        let file_id = |pdf: &[u8]| -> Vec<Vec<u8>> {
+            let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf.to_vec())).unwrap());
+            let trailer = document.trailer();
+            let parts = trailer.id().and_then(|id| id.as_array()).unwrap();
+            parts
+                .0
+                .iter()
+                .map(|part| part.as_string().unwrap().as_bytes().to_vec())
+                .collect()
+        };
+
+        let pdf = write("Archive");
+        assert_eq!(pdf, write("Archive"));
+        let id = file_id(&pdf);
+        assert_eq!(id.len(), 2);
+        assert_eq!(id[0].len(), 16);
+        assert_eq!(id[0], id[1]);
+        let hex: String = id[0].iter().map(|byte| format!("{byte:02X}")).collect();
+        let written = format!("/ID [<{hex}> <{hex}>]");
+        assert!(pdf.windows(written.len()).any(|w| w == written.as_bytes()));
+
+        assert_ne!(file_id(&write("Another archive"))[0], id[0]);
+    }
+
+    #[test]
+    fn test_identification_as_attributes() {
+        let xmp = br#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description rdf:about="" xmlns:id="http://www.aiim.org/pdfa/ns/id/" id:part="2" id:conformance="U"/>
+            </rdf:RDF></x:xmpmeta>"#;
+        assert_eq!(
+            pdfa_identification(xmp),
+            (Some("2".to_string()), Some("U".to_string()))
+        );
+    }
+}
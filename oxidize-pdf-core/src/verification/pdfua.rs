@@ -0,0 +1,728 @@
+//! Native PDF/UA conformance checking (ISO 14289-1)
+//!
+//! [`check_pdfua`] checks a file against the machine-checkable failure
+//! conditions of the Matterhorn Protocol that most often keep documents
+//! from being accessible: content that is neither tagged nor an artifact,
+//! figures without alternate text, skipped heading levels, and missing
+//! catalog entries and metadata. Like [`check_pdfa`], it catches what
+//! typically breaks output but does not replace a full validator such as
+//! PAC or veraPDF.
+//!
+//! Documents are written as PDF/UA with [`Document::enable_pdfua`] from
+//! pages tagged with [`Page::set_tagged`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use oxidize_pdf::verification::pdfua::check_pdfua;
+//! use oxidize_pdf::{Document, Page};
+//!
+//! # fn main() -> oxidize_pdf::Result<()> {
+//! let mut page = Page::a4();
+//! page.set_tagged(true);
+//! let mut flow = page.text_flow();
+//! flow.write_heading(1, "Accessible report")?;
+//! flow.write_paragraph("Every paragraph is tagged.")?;
+//! page.add_text_flow(&flow);
+//!
+//! let mut doc = Document::new();
+//! doc.set_title("Accessible report");
+//! doc.set_language("en-US");
+//! doc.add_page(page);
+//! doc.enable_pdfua(true);
+//! let pdf = doc.to_bytes()?;
+//!
+//! let report = check_pdfua(&pdf)?;
+//! assert!(report.is_compliant(), "{:?}", report.violations);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`check_pdfa`]: crate::verification::pdfa::check_pdfa
+//! [`Document::enable_pdfua`]: crate::Document::enable_pdfua
+//! [`Page::set_tagged`]: crate::Page::set_tagged
+
+use crate::error::Result;
+use crate::metadata::{XmpMetadata, XmpNamespace};
+use crate::parser::content::{ContentOperation, ContentParser};
+use crate::parser::objects::{PdfDictionary, PdfObject};
+use crate::parser::resolve::{get, get_bool, get_dict, get_name, stream_data, Resolver};
+use crate::parser::{PdfDocument, PdfReader};
+use quick_xml::events::Event;
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{Cursor, Read, Seek};
+
+/// Namespace of the PDF/UA identification schema
+const PDFUAID_NAMESPACE: &str = "http://www.aiim.org/pdfua/ns/id/";
+
+/// Namespace of Dublin Core
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+/// Deepest structure tree walked, against reference cycles
+const MAX_STRUCTURE_DEPTH: usize = 64;
+
+/// Add the `pdfuaid` identification schema for PDF/UA-1
+pub(crate) fn add_xmp(xmp: &mut XmpMetadata) {
+    let pdfuaid = XmpNamespace::Custom("pdfuaid".to_string(), PDFUAID_NAMESPACE.to_string());
+    xmp.set_text(pdfuaid, "part", "1");
+}
+
+/// Checkpoint of the Matterhorn Protocol a failure belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PdfUaCheckpoint {
+    RealContentTagged,
+    Metadata,
+    Dictionary,
+    NaturalLanguage,
+    Graphics,
+    Headings,
+}
+
+impl PdfUaCheckpoint {
+    /// Number of the checkpoint in the Matterhorn Protocol
+    pub fn number(&self) -> &'static str {
+        match self {
+            PdfUaCheckpoint::RealContentTagged => "01",
+            PdfUaCheckpoint::Metadata => "06",
+            PdfUaCheckpoint::Dictionary => "07",
+            PdfUaCheckpoint::NaturalLanguage => "11",
+            PdfUaCheckpoint::Graphics => "13",
+            PdfUaCheckpoint::Headings => "14",
+        }
+    }
+}
+
+impl fmt::Display for PdfUaCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PdfUaCheckpoint::RealContentTagged => "Real content tagged",
+            PdfUaCheckpoint::Metadata => "Metadata",
+            PdfUaCheckpoint::Dictionary => "Dictionary",
+            PdfUaCheckpoint::NaturalLanguage => "Natural language",
+            PdfUaCheckpoint::Graphics => "Graphics",
+            PdfUaCheckpoint::Headings => "Headings",
+        };
+        write!(f, "{} {name}", self.number())
+    }
+}
+
+/// A failure condition the file meets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfUaViolation {
+    pub checkpoint: PdfUaCheckpoint,
+    /// Matterhorn failure condition, such as "13-004"
+    pub failure_condition: &'static str,
+    pub message: String,
+}
+
+/// Outcome of [`check_pdfua`]
+#[derive(Debug, Clone, Default)]
+pub struct PdfUaReport {
+    /// Violations in the order found, without duplicates
+    pub violations: Vec<PdfUaViolation>,
+}
+
+impl PdfUaReport {
+    pub fn is_compliant(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Violations of one checkpoint
+    pub fn violations_of(&self, checkpoint: PdfUaCheckpoint) -> Vec<&PdfUaViolation> {
+        self.violations
+            .iter()
+            .filter(|v| v.checkpoint == checkpoint)
+            .collect()
+    }
+
+    fn add(
+        &mut self,
+        checkpoint: PdfUaCheckpoint,
+        failure_condition: &'static str,
+        message: impl Into<String>,
+    ) {
+        let violation = PdfUaViolation {
+            checkpoint,
+            failure_condition,
+            message: message.into(),
+        };
+        if !self.violations.contains(&violation) {
+            self.violations.push(violation);
+        }
+    }
+}
+
+/// Check a file against the main failure conditions of PDF/UA-1
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be parsed at all.
+pub fn check_pdfua(pdf: &[u8]) -> Result<PdfUaReport> {
+    let mut report = PdfUaReport::default();
+    let document = PdfDocument::new(PdfReader::new(Cursor::new(pdf.to_vec()))?);
+    check_document(&document, &mut report)?;
+    Ok(report)
+}
+
+fn check_document<R: Read + Seek>(
+    document: &PdfDocument<R>,
+    report: &mut PdfUaReport,
+) -> Result<()> {
+    let resolver: &dyn Resolver = document;
+    let catalog = document.catalog()?;
+
+    let mark_info = get_dict(resolver, &catalog, "MarkInfo");
+    let marked = mark_info
+        .as_ref()
+        .and_then(|info| get_bool(resolver, info, "Marked"));
+    if marked != Some(true) {
+        report.add(
+            PdfUaCheckpoint::RealContentTagged,
+            "01-005",
+            "catalog /MarkInfo does not declare the document as tagged",
+        );
+    }
+    let suspects = mark_info.and_then(|info| get_bool(resolver, &info, "Suspects"));
+    if suspects == Some(true) {
+        report.add(
+            PdfUaCheckpoint::RealContentTagged,
+            "01-007",
+            "/MarkInfo /Suspects is true",
+        );
+    }
+
+    check_metadata(resolver, &catalog, report);
+
+    let display_title = get_dict(resolver, &catalog, "ViewerPreferences")
+        .and_then(|preferences| get_bool(resolver, &preferences, "DisplayDocTitle"));
+    match display_title {
+        None => report.add(
+            PdfUaCheckpoint::Dictionary,
+            "07-001",
+            "/ViewerPreferences has no /DisplayDocTitle",
+        ),
+        Some(false) => report.add(
+            PdfUaCheckpoint::Dictionary,
+            "07-002",
+            "/ViewerPreferences /DisplayDocTitle is false",
+        ),
+        Some(true) => {}
+    }
+
+    let language = match get(resolver, &catalog, "Lang") {
+        Some(PdfObject::String(lang)) => !lang.as_bytes().is_empty(),
+        _ => false,
+    };
+    if !language {
+        report.add(
+            PdfUaCheckpoint::NaturalLanguage,
+            "11-001",
+            "catalog has no /Lang for the document's text",
+        );
+    }
+
+    let structure = match get_dict(resolver, &catalog, "StructTreeRoot") {
+        Some(root) => {
+            let mut structure = Structure::new(resolver, &root);
+            if let Some(kids) = root.get("K") {
+                structure.walk_kids(kids, None, 0, report);
+            }
+            Some(structure)
+        }
+        None => {
+            report.add(
+                PdfUaCheckpoint::RealContentTagged,
+                "01-005",
+                "document has no structure tree",
+            );
+            None
+        }
+    };
+
+    for index in 0..document.page_count()? {
+        let page = document.get_page(index)?;
+        let content = document.get_page_content_streams(&page)?.concat();
+        let operations = ContentParser::parse(&content).unwrap_or_default();
+        let tagged_mcids = structure.as_ref().map(|structure| {
+            structure
+                .mcids
+                .iter()
+                .filter(|(page_ref, _)| *page_ref == page.obj_ref)
+                .map(|&(_, mcid)| mcid)
+                .collect::<HashSet<_>>()
+        });
+        check_content(&operations, index + 1, tagged_mcids.as_ref(), report);
+    }
+    Ok(())
+}
+
+/// `pdfuaid:part` and `dc:title` in the catalog's XMP metadata
+fn check_metadata(resolver: &dyn Resolver, catalog: &PdfDictionary, report: &mut PdfUaReport) {
+    let Some(PdfObject::Stream(stream)) = get(resolver, catalog, "Metadata") else {
+        report.add(
+            PdfUaCheckpoint::Metadata,
+            "06-001",
+            "catalog has no XMP metadata stream",
+        );
+        return;
+    };
+    let xmp = stream_data(resolver, &stream).unwrap_or_default();
+    let (part, title) = pdfua_identification(&xmp);
+    if part.as_deref() != Some("1") {
+        report.add(
+            PdfUaCheckpoint::Metadata,
+            "06-002",
+            format!(
+                "XMP identifies PDF/UA part {}, expected 1",
+                part.as_deref().unwrap_or("none")
+            ),
+        );
+    }
+    if !title {
+        report.add(
+            PdfUaCheckpoint::Metadata,
+            "06-003",
+            "XMP metadata has no dc:title",
+        );
+    }
+}
+
+/// Value of `pdfuaid:part`, written as an element or an attribute, and
+/// whether `dc:title` has text
+fn pdfua_identification(xmp: &[u8]) -> (Option<String>, bool) {
+    let mut reader = NsReader::from_reader(xmp);
+    reader.trim_text(true);
+    let mut part = None;
+    let mut in_part = false;
+    let mut title_depth: Option<usize> = None;
+    let mut title = false;
+    let mut depth = 0;
+    let mut buf = Vec::new();
+    let bound_to = |result: &ResolveResult, uri: &str| matches!(result, ResolveResult::Bound(ns) if ns.as_ref() == uri.as_bytes());
+    loop {
+        let (namespace, event) = match reader.read_resolved_event_into(&mut buf) {
+            Ok(result) => result,
+            Err(_) => break,
+        };
+        let is_start = matches!(event, Event::Start(_));
+        match event {
+            Event::Start(e) | Event::Empty(e) => {
+                let local_name = e.local_name();
+                in_part = bound_to(&namespace, PDFUAID_NAMESPACE) && local_name.as_ref() == b"part";
+                if bound_to(&namespace, DC_NAMESPACE)
+                    && local_name.as_ref() == b"title"
+                    && title_depth.is_none()
+                    && is_start
+                {
+                    title_depth = Some(depth);
+                }
+                for attr in e.attributes().flatten() {
+                    let (attr_namespace, name) = reader.resolve_attribute(attr.key);
+                    if bound_to(&attr_namespace, PDFUAID_NAMESPACE) && name.as_ref() == b"part" {
+                        part = Some(String::from_utf8_lossy(&attr.value).trim().to_string());
+                    }
+                }
+                if is_start {
+                    depth += 1;
+                }
+            }
+            Event::Text(text) => {
+                let value = text.unescape().unwrap_or_default().trim().to_string();
+                if in_part {
+                    part = Some(value.clone());
+                }
+                if title_depth.is_some() && !value.is_empty() {
+                    title = true;
+                }
+            }
+            Event::End(_) => {
+                depth -= 1;
+                in_part = false;
+                if title_depth == Some(depth) {
+                    title_depth = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    (part, title)
+}
+
+/// What the structure tree refers to, gathered while checking elements
+struct Structure<'a> {
+    resolver: &'a dyn Resolver,
+    role_map: Option<PdfDictionary>,
+    /// Marked content that belongs to an element, by page reference
+    mcids: HashSet<((u32, u16), i64)>,
+    /// Level of the last numbered heading
+    heading_level: Option<u8>,
+}
+
+impl<'a> Structure<'a> {
+    fn new(resolver: &'a dyn Resolver, root: &PdfDictionary) -> Self {
+        Self {
+            resolver,
+            role_map: get_dict(resolver, root, "RoleMap"),
+            mcids: HashSet::new(),
+            heading_level: None,
+        }
+    }
+
+    /// Standard type of a structure type, through the role map
+    fn standard_type(&self, structure_type: String) -> String {
+        let mut structure_type = structure_type;
+        for _ in 0..8 {
+            let mapped = self
+                .role_map
+                .as_ref()
+                .and_then(|map| get_name(self.resolver, map, &structure_type));
+            match mapped {
+                Some(mapped) if mapped != structure_type => structure_type = mapped,
+                _ => break,
+            }
+        }
+        structure_type
+    }
+
+    /// Kids of an element or of the tree root: elements, marked content
+    /// on the page `page` and object references
+    fn walk_kids(
+        &mut self,
+        kids: &PdfObject,
+        page: Option<(u32, u16)>,
+        depth: usize,
+        report: &mut PdfUaReport,
+    ) {
+        if depth > MAX_STRUCTURE_DEPTH {
+            return;
+        }
+        match kids {
+            PdfObject::Array(items) => {
+                for item in &items.0 {
+                    self.walk_kids(item, page, depth, report);
+                }
+            }
+            PdfObject::Integer(mcid) => {
+                if let Some(page) = page {
+                    self.mcids.insert((page, *mcid));
+                }
+            }
+            PdfObject::Reference(..) => {
+                let resolved = self.resolver.lookup(kids);
+                self.walk_kids(&resolved, page, depth, report);
+            }
+            PdfObject::Dictionary(dict) => {
+                match dict
+                    .get("Type")
+                    .and_then(|t| t.as_name())
+                    .map(|n| n.as_str())
+                {
+                    Some("MCR") => {
+                        let page = dict.get("Pg").and_then(|p| p.as_reference()).or(page);
+                        if let (Some(page), Some(PdfObject::Integer(mcid))) =
+                            (page, dict.get("MCID"))
+                        {
+                            self.mcids.insert((page, *mcid));
+                        }
+                    }
+                    Some("OBJR") => {}
+                    _ => self.check_element(dict, page, depth, report),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_element(
+        &mut self,
+        element: &PdfDictionary,
+        page: Option<(u32, u16)>,
+        depth: usize,
+        report: &mut PdfUaReport,
+    ) {
+        let Some(structure_type) = get_name(self.resolver, element, "S") else {
+            return;
+        };
+        let structure_type = self.standard_type(structure_type);
+
+        if structure_type == "Figure" {
+            let has_text = |key| match get(self.resolver, element, key) {
+                Some(PdfObject::String(text)) => !text.as_bytes().is_empty(),
+                _ => false,
+            };
+            if !has_text("Alt") && !has_text("ActualText") {
+                report.add(
+                    PdfUaCheckpoint::Graphics,
+                    "13-004",
+                    "Figure has no alternate text (/Alt)",
+                );
+            }
+        }
+
+        if let Some(level) = heading_level(&structure_type) {
+            match self.heading_level {
+                None if level != 1 => report.add(
+                    PdfUaCheckpoint::Headings,
+                    "14-002",
+                    format!("first numbered heading is H{level}, not H1"),
+                ),
+                Some(previous) if level > previous + 1 => report.add(
+                    PdfUaCheckpoint::Headings,
+                    "14-003",
+                    format!("heading level skipped from H{previous} to H{level}"),
+                ),
+                _ => {}
+            }
+            self.heading_level = Some(level);
+        }
+
+        let page = element.get("Pg").and_then(|p| p.as_reference()).or(page);
+        if let Some(kids) = element.get("K") {
+            self.walk_kids(kids, page, depth + 1, report);
+        }
+    }
+}
+
+/// Level of a numbered heading type (H1 to H6)
+fn heading_level(structure_type: &str) -> Option<u8> {
+    let level = structure_type.strip_prefix('H')?.parse().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// Kind of marked content sequence an operation is in
+enum Marked {
+    Tagged,
+    Artifact,
+    Other,
+}
+
+/// Content outside tagged and artifact sequences, and tagged content
+/// that no structure element refers to
+fn check_content(
+    operations: &[ContentOperation],
+    page_number: u32,
+    tagged_mcids: Option<&HashSet<i64>>,
+    report: &mut PdfUaReport,
+) {
+    let mut stack = Vec::new();
+    for operation in operations {
+        match operation {
+            ContentOperation::BeginMarkedContent(tag) => stack.push(if tag == "Artifact" {
+                Marked::Artifact
+            } else {
+                Marked::Other
+            }),
+            ContentOperation::BeginMarkedContentWithProps(tag, props) => {
+                let mark = if tag == "Artifact" {
+                    Marked::Artifact
+                } else if let Some(mcid) = props.get("MCID") {
+                    let referenced = match (tagged_mcids, mcid.parse::<i64>()) {
+                        (Some(mcids), Ok(mcid)) => mcids.contains(&mcid),
+                        _ => true,
+                    };
+                    if !referenced {
+                        report.add(
+                            PdfUaCheckpoint::RealContentTagged,
+                            "01-005",
+                            format!(
+                                "page {page_number}: marked content {mcid} belongs to no structure element"
+                            ),
+                        );
+                    }
+                    Marked::Tagged
+                } else {
+                    Marked::Other
+                };
+                stack.push(mark);
+            }
+            ContentOperation::EndMarkedContent => {
+                stack.pop();
+            }
+            operation if paints(operation) => {
+                let covered = stack
+                    .iter()
+                    .any(|mark| matches!(mark, Marked::Tagged | Marked::Artifact));
+                if !covered {
+                    report.add(
+                        PdfUaCheckpoint::RealContentTagged,
+                        "01-005",
+                        format!(
+                            "page {page_number}: content is neither tagged nor marked as an artifact"
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether an operation puts marks on the page
+fn paints(operation: &ContentOperation) -> bool {
+    matches!(
+        operation,
+        ContentOperation::ShowText(_)
+            | ContentOperation::ShowTextArray(_)
+            | ContentOperation::NextLineShowText(_)
+            | ContentOperation::SetSpacingNextLineShowText(..)
+            | ContentOperation::Stroke
+            | ContentOperation::CloseStroke
+            | ContentOperation::Fill
+            | ContentOperation::FillEvenOdd
+            | ContentOperation::FillStroke
+            | ContentOperation::FillStrokeEvenOdd
+            | ContentOperation::CloseFillStroke
+            | ContentOperation::CloseFillStrokeEvenOdd
+            | ContentOperation::ShadingFill(_)
+            | ContentOperation::PaintXObject(_)
+            | ContentOperation::InlineImage { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advanced_tables::{AdvancedTableBuilder, AdvancedTableExt};
+    use crate::graphics::Image;
+    use crate::page_lists::PageLists;
+    use crate::text::BulletStyle;
+    use crate::{Document, Page};
+
+    fn image() -> Image {
+        Image::from_jpeg_data(vec![
+            0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x64, 0x00, 0xC8, 0x03, 0xFF, 0xD9,
+        ])
+        .unwrap()
+    }
+
+    fn accessible_document(page: Page) -> Document {
+        let mut doc = Document::new();
+        doc.set_title("Quarterly report");
+        doc.set_language("en-US");
+        doc.enable_pdfua(true);
+        doc.add_page(page);
+        doc
+    }
+
+    fn tagged_page() -> Page {
+        let mut page = Page::a4();
+        page.set_tagged(true);
+
+        let mut flow = page.text_flow();
+        flow.write_heading(1, "Quarterly report").unwrap();
+        flow.write_paragraph("Sales grew in every region.").unwrap();
+        flow.write_heading(2, "Regions").unwrap();
+        page.add_text_flow(&flow);
+
+        let table = AdvancedTableBuilder::new()
+            .add_column("Region", 150.0)
+            .add_column("Sales", 100.0)
+            .add_row(vec!["North", "120"])
+            .add_row(vec!["South", ""])
+            .build()
+            .unwrap();
+        page.add_advanced_table(&table, 72.0, 500.0).unwrap();
+
+        page.add_quick_unordered_list(
+            vec![
+                "Hired two people".to_string(),
+                "Opened an office".to_string(),
+            ],
+            72.0,
+            300.0,
+            BulletStyle::Disc,
+        )
+        .unwrap();
+
+        page.add_image("chart", image());
+        page.draw_image_with_alt_text("chart", 72.0, 100.0, 200.0, 100.0, "Sales by region")
+            .unwrap();
+        page
+    }
+
+    fn failure_conditions(report: &PdfUaReport) -> Vec<&'static str> {
+        report
+            .violations
+            .iter()
+            .map(|v| v.failure_condition)
+            .collect()
+    }
+
+    #[test]
+    fn test_tagged_output_passes_checker() {
+        let pdf = accessible_document(tagged_page()).to_bytes().unwrap();
+        let report = check_pdfua(&pdf).unwrap();
+        assert!(report.is_compliant(), "{:?}", report.violations);
+
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Scope /Column"));
+        assert!(text.contains("/ParentTree"));
+        assert!(text.contains("/StructParents 0"));
+    }
+
+    #[test]
+    fn test_untagged_output_fails_checker() {
+        let mut page = Page::a4();
+        page.text()
+            .set_font(crate::Font::Helvetica, 12.0)
+            .at(72.0, 720.0)
+            .write("Untagged")
+            .unwrap();
+        let mut doc = Document::new();
+        doc.add_page(page);
+        let report = check_pdfua(&doc.to_bytes().unwrap()).unwrap();
+        let conditions = failure_conditions(&report);
+        for expected in ["01-005", "06-002", "06-003", "07-001", "11-001"] {
+            assert!(conditions.contains(&expected), "{expected}: {conditions:?}");
+        }
+        assert!(report
+            .violations
+            .iter()
+            .any(|v| v.message.contains("neither tagged nor marked")));
+    }
+
+    #[test]
+    fn test_missing_alt_text_and_heading_nesting() {
+        let mut page = Page::a4();
+        page.set_tagged(true);
+        let mut flow = page.text_flow();
+        flow.write_heading(2, "Starts at level two").unwrap();
+        flow.write_heading(4, "Skips level three").unwrap();
+        page.add_text_flow(&flow);
+        page.add_image("photo", image());
+        page.draw_image("photo", 72.0, 100.0, 200.0, 100.0).unwrap();
+        // Drawn without tagging on a tagged page
+        page.graphics().rect(10.0, 10.0, 5.0, 5.0).fill();
+
+        let pdf = accessible_document(page).to_bytes().unwrap();
+        let report = check_pdfua(&pdf).unwrap();
+        let conditions = failure_conditions(&report);
+        assert!(conditions.contains(&"13-004"));
+        assert!(conditions.contains(&"14-002"));
+        assert!(conditions.contains(&"14-003"));
+        assert_eq!(
+            report
+                .violations_of(PdfUaCheckpoint::RealContentTagged)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_writer_requires_title_language_and_tags() {
+        let mut doc = accessible_document(tagged_page());
+        doc.metadata.title = None;
+        assert!(doc.to_bytes().is_err());
+
+        let mut doc = accessible_document(tagged_page());
+        doc.language = None;
+        assert!(doc.to_bytes().is_err());
+
+        assert!(accessible_document(Page::a4()).to_bytes().is_err());
+    }
+}
@@ -0,0 +1,388 @@
+//! Serialization of parsed content stream operations
+//!
+//! Turns the operations produced by [`ContentParser`](crate::parser::ContentParser)
+//! back into content stream bytes, one operator per line. Parsing the output
+//! again yields the same operations.
+
+use crate::objects::Object;
+use crate::parser::content::{ContentOperation, TextElement};
+use std::collections::HashMap;
+
+/// Marked content property values that are text strings rather than names
+const TEXT_PROPERTIES: [&str; 5] = ["ActualText", "Alt", "E", "Lang", "T"];
+
+/// Serialize content operations into content stream bytes
+pub fn serialize_operations(operations: &[ContentOperation]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for operation in operations {
+        let start = out.len();
+        write_operation(&mut out, operation);
+        if out.len() > start {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+/// Append a single operation, without a trailing newline
+pub fn write_operation(out: &mut Vec<u8>, operation: &ContentOperation) {
+    use ContentOperation as Op;
+
+    match operation {
+        Op::BeginText => out.extend_from_slice(b"BT"),
+        Op::EndText => out.extend_from_slice(b"ET"),
+        Op::SetCharSpacing(v) => op_numbers(out, &[*v], "Tc"),
+        Op::SetWordSpacing(v) => op_numbers(out, &[*v], "Tw"),
+        Op::SetHorizontalScaling(v) => op_numbers(out, &[*v], "Tz"),
+        Op::SetLeading(v) => op_numbers(out, &[*v], "TL"),
+        Op::SetFont(name, size) => {
+            write_name(out, name);
+            out.push(b' ');
+            op_numbers(out, &[*size], "Tf");
+        }
+        Op::SetTextRenderMode(mode) => op(out, &format!("{mode} Tr")),
+        Op::SetTextRise(v) => op_numbers(out, &[*v], "Ts"),
+        Op::MoveText(x, y) => op_numbers(out, &[*x, *y], "Td"),
+        Op::MoveTextSetLeading(x, y) => op_numbers(out, &[*x, *y], "TD"),
+        Op::SetTextMatrix(a, b, c, d, e, f) => op_numbers(out, &[*a, *b, *c, *d, *e, *f], "Tm"),
+        Op::NextLine => out.extend_from_slice(b"T*"),
+        Op::ShowText(text) => {
+            write_string(out, text);
+            out.extend_from_slice(b" Tj");
+        }
+        Op::ShowTextArray(elements) => {
+            out.push(b'[');
+            for (i, element) in elements.iter().enumerate() {
+                match element {
+                    TextElement::Text(text) => write_string(out, text),
+                    TextElement::Spacing(v) => {
+                        if i > 0 {
+                            out.push(b' ');
+                        }
+                        write_number(out, *v);
+                    }
+                }
+            }
+            out.extend_from_slice(b"] TJ");
+        }
+        Op::NextLineShowText(text) => {
+            write_string(out, text);
+            out.extend_from_slice(b" '");
+        }
+        Op::SetSpacingNextLineShowText(word, chars, text) => {
+            write_numbers(out, &[*word, *chars]);
+            out.push(b' ');
+            write_string(out, text);
+            out.extend_from_slice(b" \"");
+        }
+
+        Op::SaveGraphicsState => out.push(b'q'),
+        Op::RestoreGraphicsState => out.push(b'Q'),
+        Op::SetTransformMatrix(a, b, c, d, e, f) => {
+            op_numbers(out, &[*a, *b, *c, *d, *e, *f], "cm")
+        }
+        Op::SetLineWidth(v) => op_numbers(out, &[*v], "w"),
+        Op::SetLineCap(cap) => op(out, &format!("{cap} J")),
+        Op::SetLineJoin(join) => op(out, &format!("{join} j")),
+        Op::SetMiterLimit(v) => op_numbers(out, &[*v], "M"),
+        Op::SetDashPattern(array, phase) => {
+            out.push(b'[');
+            write_numbers(out, array);
+            out.extend_from_slice(b"] ");
+            op_numbers(out, &[*phase], "d");
+        }
+        Op::SetIntent(intent) => op_name(out, intent, "ri"),
+        Op::SetFlatness(v) => op_numbers(out, &[*v], "i"),
+        Op::SetGraphicsStateParams(name) => op_name(out, name, "gs"),
+
+        Op::MoveTo(x, y) => op_numbers(out, &[*x, *y], "m"),
+        Op::LineTo(x, y) => op_numbers(out, &[*x, *y], "l"),
+        Op::CurveTo(x1, y1, x2, y2, x3, y3) => {
+            op_numbers(out, &[*x1, *y1, *x2, *y2, *x3, *y3], "c")
+        }
+        Op::CurveToV(x2, y2, x3, y3) => op_numbers(out, &[*x2, *y2, *x3, *y3], "v"),
+        Op::CurveToY(x1, y1, x3, y3) => op_numbers(out, &[*x1, *y1, *x3, *y3], "y"),
+        Op::ClosePath => out.push(b'h'),
+        Op::Rectangle(x, y, w, h) => op_numbers(out, &[*x, *y, *w, *h], "re"),
+        Op::Stroke => out.push(b'S'),
+        Op::CloseStroke => out.push(b's'),
+        Op::Fill => out.push(b'f'),
+        Op::FillEvenOdd => out.extend_from_slice(b"f*"),
+        Op::FillStroke => out.push(b'B'),
+        Op::FillStrokeEvenOdd => out.extend_from_slice(b"B*"),
+        Op::CloseFillStroke => out.push(b'b'),
+        Op::CloseFillStrokeEvenOdd => out.extend_from_slice(b"b*"),
+        Op::EndPath => out.push(b'n'),
+        Op::Clip => out.push(b'W'),
+        Op::ClipEvenOdd => out.extend_from_slice(b"W*"),
+
+        Op::SetStrokingColorSpace(name) => op_name(out, name, "CS"),
+        Op::SetNonStrokingColorSpace(name) => op_name(out, name, "cs"),
+        // SCN and scn accept the operands of SC and sc in every color space
+        Op::SetStrokingColor(components) => op_numbers(out, components, "SCN"),
+        Op::SetNonStrokingColor(components) => op_numbers(out, components, "scn"),
+        Op::SetStrokingPattern(name, components) => {
+            write_numbers(out, components);
+            if !components.is_empty() {
+                out.push(b' ');
+            }
+            op_name(out, name, "SCN");
+        }
+        Op::SetNonStrokingPattern(name, components) => {
+            write_numbers(out, components);
+            if !components.is_empty() {
+                out.push(b' ');
+            }
+            op_name(out, name, "scn");
+        }
+        Op::SetStrokingGray(v) => op_numbers(out, &[*v], "G"),
+        Op::SetNonStrokingGray(v) => op_numbers(out, &[*v], "g"),
+        Op::SetStrokingRGB(r, g, b) => op_numbers(out, &[*r, *g, *b], "RG"),
+        Op::SetNonStrokingRGB(r, g, b) => op_numbers(out, &[*r, *g, *b], "rg"),
+        Op::SetStrokingCMYK(c, m, y, k) => op_numbers(out, &[*c, *m, *y, *k], "K"),
+        Op::SetNonStrokingCMYK(c, m, y, k) => op_numbers(out, &[*c, *m, *y, *k], "k"),
+        Op::ShadingFill(name) => op_name(out, name, "sh"),
+
+        // The parser reports inline images as a whole
+        Op::BeginInlineImage => {}
+        Op::InlineImage { params, data } => {
+            out.extend_from_slice(b"BI");
+            let mut keys: Vec<&String> = params.keys().collect();
+            keys.sort();
+            for key in keys {
+                out.push(b' ');
+                write_name(out, abbreviate_inline_key(key));
+                out.push(b' ');
+                write_object(out, &params[key]);
+            }
+            out.extend_from_slice(b" ID ");
+            out.extend_from_slice(data);
+            out.extend_from_slice(b"\nEI");
+        }
+        Op::PaintXObject(name) => op_name(out, name, "Do"),
+
+        Op::BeginMarkedContent(tag) => op_name(out, tag, "BMC"),
+        Op::BeginMarkedContentWithProps(tag, props) => {
+            write_name(out, tag);
+            out.push(b' ');
+            write_properties(out, props);
+            out.extend_from_slice(b" BDC");
+        }
+        Op::EndMarkedContent => out.extend_from_slice(b"EMC"),
+        Op::DefineMarkedContentPoint(tag) => op_name(out, tag, "MP"),
+        Op::DefineMarkedContentPointWithProps(tag, props) => {
+            write_name(out, tag);
+            out.push(b' ');
+            write_properties(out, props);
+            out.extend_from_slice(b" DP");
+        }
+        Op::BeginCompatibility => out.extend_from_slice(b"BX"),
+        Op::EndCompatibility => out.extend_from_slice(b"EX"),
+        Op::SetCharWidth(wx, wy) => op_numbers(out, &[*wx, *wy], "d0"),
+        Op::SetCacheDevice(wx, wy, llx, lly, urx, ury) => {
+            op_numbers(out, &[*wx, *wy, *llx, *lly, *urx, *ury], "d1")
+        }
+    }
+}
+
+fn op(out: &mut Vec<u8>, text: &str) {
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn op_numbers(out: &mut Vec<u8>, values: &[f32], operator: &str) {
+    write_numbers(out, values);
+    if !values.is_empty() {
+        out.push(b' ');
+    }
+    out.extend_from_slice(operator.as_bytes());
+}
+
+fn op_name(out: &mut Vec<u8>, name: &str, operator: &str) {
+    write_name(out, name);
+    out.push(b' ');
+    out.extend_from_slice(operator.as_bytes());
+}
+
+fn write_numbers(out: &mut Vec<u8>, values: &[f32]) {
+    for (i, &value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push(b' ');
+        }
+        write_number(out, value);
+    }
+}
+
+/// Write a number without exponent; non-finite values become 0
+pub(crate) fn write_number(out: &mut Vec<u8>, value: f32) {
+    if value.is_finite() && value != 0.0 {
+        out.extend_from_slice(value.to_string().as_bytes());
+    } else {
+        out.push(b'0');
+    }
+}
+
+/// Write a string as a literal when printable, in hex otherwise
+pub(crate) fn write_string(out: &mut Vec<u8>, bytes: &[u8]) {
+    if bytes.iter().all(|&b| (0x20..0x7F).contains(&b)) {
+        out.push(b'(');
+        for &b in bytes {
+            if matches!(b, b'(' | b')' | b'\\') {
+                out.push(b'\\');
+            }
+            out.push(b);
+        }
+        out.push(b')');
+    } else {
+        out.push(b'<');
+        for b in bytes {
+            out.extend_from_slice(format!("{b:02X}").as_bytes());
+        }
+        out.push(b'>');
+    }
+}
+
+/// Write a name, escaping delimiters, `#` and non-regular bytes as `#xx`
+pub(crate) fn write_name(out: &mut Vec<u8>, name: &str) {
+    out.push(b'/');
+    for &b in name.as_bytes() {
+        if b.is_ascii_graphic() && !b"()<>[]{}/%#".contains(&b) {
+            out.push(b);
+        } else {
+            out.extend_from_slice(format!("#{b:02X}").as_bytes());
+        }
+    }
+}
+
+fn write_object(out: &mut Vec<u8>, object: &Object) {
+    match object {
+        Object::Null | Object::Stream(..) | Object::Reference(_) => out.extend_from_slice(b"null"),
+        Object::Boolean(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        Object::Integer(i) => out.extend_from_slice(i.to_string().as_bytes()),
+        Object::Real(f) => write_number(out, *f as f32),
+        // Inline image strings hold one char per byte
+        Object::String(s) => {
+            let bytes: Vec<u8> = s.chars().map(|c| c as u32 as u8).collect();
+            write_string(out, &bytes);
+        }
+        Object::Name(name) => write_name(out, name),
+        Object::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b' ');
+                }
+                write_object(out, item);
+            }
+            out.push(b']');
+        }
+        Object::Dictionary(dict) => {
+            out.extend_from_slice(b"<<");
+            let mut entries: Vec<_> = dict.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in entries {
+                write_name(out, key);
+                out.push(b' ');
+                write_object(out, value);
+            }
+            out.extend_from_slice(b">>");
+        }
+    }
+}
+
+fn abbreviate_inline_key(key: &str) -> &str {
+    match key {
+        "Width" => "W",
+        "Height" => "H",
+        "ColorSpace" => "CS",
+        "BitsPerComponent" => "BPC",
+        "Filter" => "F",
+        "DecodeParms" => "DP",
+        "ImageMask" => "IM",
+        "Interpolate" => "I",
+        "Decode" => "D",
+        _ => key,
+    }
+}
+
+/// Write marked content properties as the parser keeps them: a named
+/// resource, or an inline dictionary with values flattened to text
+fn write_properties(out: &mut Vec<u8>, props: &HashMap<String, String>) {
+    if let Some(name) = props.get("__resource_ref") {
+        write_name(out, name);
+        return;
+    }
+    let mut entries: Vec<_> = props.iter().collect();
+    entries.sort();
+    out.extend_from_slice(b"<<");
+    for (key, value) in entries {
+        write_name(out, key);
+        out.push(b' ');
+        if TEXT_PROPERTIES.contains(&key.as_str()) {
+            write_string(out, value.as_bytes());
+        } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            out.push(b'[');
+            for (i, item) in items.split(", ").filter(|i| !i.is_empty()).enumerate() {
+                if i > 0 {
+                    out.push(b' ');
+                }
+                write_property_value(out, item);
+            }
+            out.push(b']');
+        } else {
+            write_property_value(out, value);
+        }
+    }
+    out.extend_from_slice(b">>");
+}
+
+fn write_property_value(out: &mut Vec<u8>, value: &str) {
+    if value.parse::<f64>().is_ok_and(f64::is_finite) {
+        out.extend_from_slice(value.as_bytes());
+    } else {
+        write_name(out, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::content::ContentParser;
+
+    fn round_trip(content: &[u8]) -> Vec<u8> {
+        let operations = ContentParser::parse(content).unwrap();
+        let bytes = serialize_operations(&operations);
+        assert_eq!(ContentParser::parse(&bytes).unwrap(), operations);
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let content = b"q 1 0 0 1 72.5 -10 cm 0.5 w 1 J 2 j [3 1] 0 d /GS#201 gs\n\
+            0 0 m 10 10 l 1 2 3 4 5 6 c 1 2 3 4 v 1 2 3 4 y h 0 0 50 50 re W* n\n\
+            /P0 cs /P0 scn 0.2 0.4 /P1 SCN 1 0 0 RG 0.1 0.2 0.3 0.4 k 0.5 g /Sh1 sh\n\
+            BT /F1 12 Tf 2 Tc 1.5 Tw 90 Tz 14 TL 2 Tr 3 Ts 100 700 Td (a\\(b\\)\\\\c) Tj\n\
+            [(A) -120.5 <00FF>] TJ T* (x) ' 1 2 (y) \" 0 -14 TD 1 0 0 1 5 5 Tm ET\n\
+            /Im1 Do /Span <</MCID 3 /ActualText (Hi there)>> BDC EMC /OC /oc1 BDC EMC\n\
+            /Tag MP /Tag <</Lang (en)>> DP BX EX 500 0 d0 500 0 0 0 500 500 d1 Q";
+        let bytes = round_trip(content);
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/GS#201 gs"));
+        assert!(text.contains("[(A) -120.5<00FF>] TJ"));
+        assert!(text.contains("/Span <</ActualText (Hi there)/MCID 3>> BDC"));
+    }
+
+    #[test]
+    fn test_inline_image_round_trip() {
+        let content =
+            b"q 2 0 0 2 0 0 cm\nBI /W 2 /H 1 /CS /I /BPC 8 /F /AHx /D [1 0]\nID 0A1B>\nEI Q";
+        let bytes = round_trip(content);
+        assert!(String::from_utf8_lossy(&bytes).contains("BI /BPC 8 /CS /Indexed"));
+    }
+
+    #[test]
+    fn test_numbers() {
+        let mut out = Vec::new();
+        write_numbers(&mut out, &[1.0, -0.25, 1e10, f32::NAN, -0.0]);
+        assert_eq!(out, b"1 -0.25 10000000000 0 0");
+    }
+}
@@ -231,6 +231,164 @@ pub fn has_embedded_font_data(font_dict: &crate::objects::Dictionary) -> bool {
     }
 }
 
+/// Rename resource names in a content stream, keeping every other byte
+///
+/// Unlike [`rewrite_font_references`], the stream is tokenized: names inside
+/// strings, comments and inline image data are left alone, and so are
+/// dictionary keys. Every other name with a mapping is renamed, whatever
+/// operator it belongs to.
+pub(crate) fn rename_resources(content: &[u8], mappings: &HashMap<String, String>) -> Vec<u8> {
+    if mappings.is_empty() {
+        return content.to_vec();
+    }
+
+    // Open containers; dictionaries track whether a key comes next
+    enum Container {
+        Array,
+        Dictionary { key_next: bool },
+    }
+    fn value_done(containers: &mut [Container]) {
+        if let Some(Container::Dictionary { key_next }) = containers.last_mut() {
+            *key_next = !*key_next;
+        }
+    }
+    let is_delimiter = |b: u8| b"()<>[]{}/%".contains(&b);
+    let is_white = |b: u8| b"\0\t\n\x0C\r ".contains(&b);
+
+    let mut out = Vec::with_capacity(content.len());
+    let mut containers = Vec::new();
+    let mut i = 0;
+    while i < content.len() {
+        let start = i;
+        match content[i] {
+            b'%' => {
+                while i < content.len() && !matches!(content[i], b'\r' | b'\n') {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                let mut depth = 0;
+                while i < content.len() {
+                    match content[i] {
+                        b'\\' => i += 1,
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                value_done(&mut containers);
+            }
+            b'<' if content.get(i + 1) == Some(&b'<') => {
+                containers.push(Container::Dictionary { key_next: true });
+                i += 2;
+            }
+            b'<' => {
+                while i < content.len() && content[i] != b'>' {
+                    i += 1;
+                }
+                i += 1;
+                value_done(&mut containers);
+            }
+            b'>' if content.get(i + 1) == Some(&b'>') => {
+                containers.pop();
+                i += 2;
+                value_done(&mut containers);
+            }
+            b'[' => {
+                containers.push(Container::Array);
+                i += 1;
+            }
+            b']' => {
+                containers.pop();
+                i += 1;
+                value_done(&mut containers);
+            }
+            b'/' => {
+                i += 1;
+                while i < content.len() && !is_white(content[i]) && !is_delimiter(content[i]) {
+                    i += 1;
+                }
+                let is_key = matches!(
+                    containers.last(),
+                    Some(Container::Dictionary { key_next: true })
+                );
+                let name = decode_name(&content[start + 1..i]);
+                value_done(&mut containers);
+                if let Some(new_name) = mappings.get(&name).filter(|_| !is_key) {
+                    write_name(new_name, &mut out);
+                    continue;
+                }
+            }
+            b if is_white(b) || is_delimiter(b) => i += 1,
+            _ => {
+                while i < content.len() && !is_white(content[i]) && !is_delimiter(content[i]) {
+                    i += 1;
+                }
+                match &content[start..i] {
+                    // Inline image dictionary
+                    b"BI" => containers.push(Container::Dictionary { key_next: true }),
+                    b"ID" => {
+                        containers.pop();
+                        // Image data ends at the first EI between white-space
+                        i += 1;
+                        while i < content.len()
+                            && !(is_white(content[i - 1])
+                                && content[i..].starts_with(b"EI")
+                                && content.get(i + 2).is_none_or(|&b| is_white(b)))
+                        {
+                            i += 1;
+                        }
+                        i = (i + 2).min(content.len());
+                    }
+                    _ => value_done(&mut containers),
+                }
+            }
+        }
+        out.extend_from_slice(&content[start..i.min(content.len())]);
+    }
+    out
+}
+
+/// Decode the `#xx` escapes of a name token (without its slash)
+fn decode_name(token: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(token.len());
+    let mut i = 0;
+    while i < token.len() {
+        let escaped = token
+            .get(i + 1..i + 3)
+            .filter(|_| token[i] == b'#')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(b) => {
+                bytes.push(b);
+                i += 3;
+            }
+            None => {
+                bytes.push(token[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Write a name token, escaping non-regular characters as `#xx`
+fn write_name(name: &str, out: &mut Vec<u8>) {
+    out.push(b'/');
+    for &b in name.as_bytes() {
+        if (0x21..0x7F).contains(&b) && !b"()<>[]{}/%#".contains(&b) {
+            out.push(b);
+        } else {
+            out.extend_from_slice(format!("#{b:02X}").as_bytes());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -734,4 +892,30 @@ mod tests {
 
         assert!(!has_embedded_font_data(&font_dict));
     }
+
+    #[test]
+    fn test_rename_resources() {
+        let mappings = HashMap::from([
+            ("F1".to_string(), "F1_1".to_string()),
+            ("GS 0".to_string(), "GS0_1".to_string()),
+        ]);
+        let content = b"q /GS#200 gs BT\t/F1  12 Tf (/F1 \\) Tj) Tj ET % /F1\n\
+            /OC << /F1 /F1 >> BDC EMC <2F4631> Tj Q";
+        assert_eq!(
+            rename_resources(content, &mappings),
+            b"q /GS0_1 gs BT\t/F1_1  12 Tf (/F1 \\) Tj) Tj ET % /F1\n\
+            /OC << /F1 /F1_1 >> BDC EMC <2F4631> Tj Q"
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn test_rename_resources_skips_inline_images() {
+        let mappings = HashMap::from([("CS1".to_string(), "CS1_1".to_string())]);
+        let content = b"BI /W 2 /H 1 /CS /CS1 /BPC 8 ID \x00/CS1 EI\xFF EI Q";
+        assert_eq!(
+            rename_resources(content, &mappings),
+            b"BI /W 2 /H 1 /CS /CS1_1 /BPC 8 ID \x00/CS1 EI\xFF EI Q".to_vec()
+        );
+    }
 }
@@ -1,5 +1,6 @@
 //! PDF writing functionality
 
+mod content_serializer;
 mod content_stream_utils;
 mod object_streams;
 mod pdf_writer;
@@ -7,8 +8,12 @@ mod signature;
 mod xref_stream_writer;
 
 // Phase 2 utilities for font preservation
-pub(crate) use content_stream_utils::{rename_preserved_fonts, rewrite_font_references};
+pub use content_serializer::{serialize_operations, write_operation};
+pub(crate) use content_stream_utils::{
+    rename_preserved_fonts, rename_resources, rewrite_font_references,
+};
 pub use object_streams::{ObjectStream, ObjectStreamConfig, ObjectStreamStats, ObjectStreamWriter};
+pub(crate) use pdf_writer::{format_pdf_date, write_parsed_value};
 pub use pdf_writer::{PdfWriter, WriterConfig};
 pub(crate) use signature::{Edition, PdfSignature};
 pub use xref_stream_writer::XRefStreamWriter;
@@ -0,0 +1,356 @@
+//! Writing pages and catalog entries of opened documents
+//!
+//! Objects of an opened document are renumbered as they are first
+//! referenced, so only objects still in use are written. References to its
+//! pages point to their new page objects, or become null for removed pages.
+
+use super::PdfWriter;
+use crate::document::{Document, ImportedObjects, ImportedPage};
+use crate::error::Result;
+use crate::objects::{Dictionary, Object, ObjectId};
+use crate::parser::objects::{PdfArray, PdfDictionary, PdfObject, PdfStream};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Original reference of an object, qualified by the document it came from
+type SourceKey = (usize, (u32, u16));
+
+fn source_key(objects: &Arc<ImportedObjects>, reference: (u32, u16)) -> SourceKey {
+    (Arc::as_ptr(objects) as usize, reference)
+}
+
+/// Output IDs of objects copied from opened documents
+#[derive(Default)]
+pub(super) struct ImportState {
+    ids: HashMap<SourceKey, ObjectId>,
+    pages: HashMap<SourceKey, ObjectId>,
+    /// Copied objects that still have to be written
+    pending: Vec<(Arc<ImportedObjects>, (u32, u16), ObjectId)>,
+}
+
+impl<W: Write> PdfWriter<W> {
+    /// Record the output IDs of pages that come from opened documents
+    pub(super) fn register_imported_pages(&mut self, document: &Document, page_ids: &[ObjectId]) {
+        for (page, &page_id) in document.pages.iter().zip(page_ids) {
+            if let Some(imported) = page.imported() {
+                let key = source_key(&imported.objects, imported.page_ref);
+                self.imported.pages.insert(key, page_id);
+            }
+        }
+    }
+
+    /// Merge the original resources and entries of an opened page
+    ///
+    /// Returns the original resource names that were renamed, because they
+    /// clash with resources of new content or are not plain names.
+    pub(super) fn merge_imported_page(
+        &mut self,
+        imported: &ImportedPage,
+        page_dict: &mut Dictionary,
+        resources: &mut Dictionary,
+    ) -> Result<HashMap<String, String>> {
+        let objects = &imported.objects;
+
+        for (key, value) in &imported.entries.0 {
+            let value = match (key.as_str(), resolve(objects, value)) {
+                // Annotations stay shared with the form fields that own them
+                ("Annots", PdfObject::Array(annots)) => Object::Array(
+                    annots
+                        .0
+                        .iter()
+                        .map(|annot| self.import_value(objects, annot))
+                        .collect::<Result<_>>()?,
+                ),
+                _ => self.import_value(objects, value)?,
+            };
+            page_dict.set(key.as_str(), value);
+        }
+
+        let categories: Vec<(&str, &PdfObject)> = match imported
+            .resources
+            .as_ref()
+            .map(|resources| resolve(objects, resources))
+        {
+            Some(PdfObject::Dictionary(dict)) => dict
+                .0
+                .iter()
+                .map(|(key, value)| (key.as_str(), resolve(objects, value)))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Names are renamed across all categories, as content streams are
+        // rewritten without regard to the operator using a name
+        let mut taken: HashSet<String> = HashSet::new();
+        for (_, value) in resources.iter() {
+            if let Object::Dictionary(dict) = value {
+                taken.extend(dict.keys().cloned());
+            }
+        }
+        let mut renames = HashMap::new();
+        for (category, value) in &categories {
+            let PdfObject::Dictionary(dict) = value else {
+                continue;
+            };
+            for name in dict.0.keys() {
+                let name = name.as_str();
+                let clashes = matches!(
+                    resources.get(category),
+                    Some(Object::Dictionary(existing)) if existing.contains_key(name)
+                );
+                if (clashes || !is_plain_name(name)) && !renames.contains_key(name) {
+                    let base: String = name.chars().filter(|&c| is_plain_name_char(c)).collect();
+                    let new_name = (1..)
+                        .map(|n| format!("{base}_{n}"))
+                        .find(|candidate| {
+                            !taken.contains(candidate)
+                                && categories.iter().all(|(_, value)| {
+                                    value.as_dict().is_none_or(|d| !d.contains_key(candidate))
+                                })
+                        })
+                        .unwrap_or_default();
+                    taken.insert(new_name.clone());
+                    renames.insert(name.to_string(), new_name);
+                }
+            }
+        }
+
+        for (category, value) in categories {
+            match value {
+                PdfObject::Dictionary(dict) => {
+                    let mut merged = match resources.get(category) {
+                        Some(Object::Dictionary(existing)) => existing.clone(),
+                        _ => Dictionary::new(),
+                    };
+                    for (name, resource) in &dict.0 {
+                        let name = renames.get(name.as_str()).map_or(name.as_str(), |n| n);
+                        merged.set(name, self.import_value(objects, resource)?);
+                    }
+                    resources.set(category, Object::Dictionary(merged));
+                }
+                // Other entries, such as /ProcSet, unless generated anyway
+                _ if !resources.contains_key(category) => {
+                    let value = self.import_value(objects, value)?;
+                    resources.set(category, value);
+                }
+                _ => {}
+            }
+        }
+
+        self.write_imported_objects()?;
+        Ok(renames)
+    }
+
+    /// Add the carried-over catalog entries of an opened document
+    ///
+    /// Entries replaced through the document's own setters are dropped.
+    pub(super) fn merge_imported_catalog(
+        &mut self,
+        document: &Document,
+        catalog: &mut Dictionary,
+    ) -> Result<()> {
+        let Some(imported) = &document.imported_catalog else {
+            return Ok(());
+        };
+        for (key, value) in &imported.entries.0 {
+            // Attached files were read into the document; keep the other
+            // name trees alongside the new `EmbeddedFiles` tree
+            if key.as_str() == "Names" {
+                self.merge_imported_names(&imported.objects, value, catalog)?;
+                continue;
+            }
+            let replaced = match key.as_str() {
+                "AcroForm" => document.acro_form.is_some(),
+                "Dests" => document.named_destinations.is_some(),
+                "OpenAction" => document.open_action.is_some(),
+                "Outlines" => document.outline.is_some(),
+                "PageLabels" => document.page_labels.is_some(),
+                "StructTreeRoot" | "MarkInfo" => document.struct_tree.is_some(),
+                "ViewerPreferences" => document.viewer_preferences.is_some(),
+                _ => false,
+            };
+            if !replaced && !catalog.contains_key(key.as_str()) {
+                let value = self.import_value(&imported.objects, value)?;
+                catalog.set(key.as_str(), value);
+            }
+        }
+        self.write_imported_objects()
+    }
+
+    /// Add the name trees of an opened document other than `EmbeddedFiles`
+    fn merge_imported_names(
+        &mut self,
+        objects: &Arc<ImportedObjects>,
+        value: &PdfObject,
+        catalog: &mut Dictionary,
+    ) -> Result<()> {
+        let PdfObject::Dictionary(imported) = resolve(objects, value) else {
+            return Ok(());
+        };
+        let mut names = match catalog.get("Names") {
+            Some(Object::Dictionary(names)) => names.clone(),
+            _ => Dictionary::new(),
+        };
+        for (key, tree) in &imported.0 {
+            if key.as_str() != "EmbeddedFiles" && !names.contains_key(key.as_str()) {
+                let tree = self.import_value(objects, tree)?;
+                names.set(key.as_str(), tree);
+            }
+        }
+        if !names.is_empty() {
+            catalog.set("Names", Object::Dictionary(names));
+        }
+        Ok(())
+    }
+
+    /// Convert an imported value for a writer dictionary
+    ///
+    /// Strings, and arrays or dictionaries holding strings or names that
+    /// need escaping, are written as separate objects to keep their bytes.
+    fn import_value(
+        &mut self,
+        objects: &Arc<ImportedObjects>,
+        value: &PdfObject,
+    ) -> Result<Object> {
+        Ok(match value {
+            PdfObject::Null => Object::Null,
+            PdfObject::Boolean(b) => Object::Boolean(*b),
+            PdfObject::Integer(i) => Object::Integer(*i),
+            PdfObject::Real(f) => Object::Real(*f),
+            PdfObject::Reference(num, gen) => self.import_reference(objects, (*num, *gen)),
+            PdfObject::Name(name) if is_plain_name(name.as_str()) => {
+                Object::Name(name.as_str().to_string())
+            }
+            PdfObject::Array(array) if is_plain(value) => Object::Array(
+                array
+                    .0
+                    .iter()
+                    .map(|item| self.import_value(objects, item))
+                    .collect::<Result<_>>()?,
+            ),
+            PdfObject::Dictionary(dict) if is_plain(value) => {
+                let mut converted = Dictionary::new();
+                for (key, item) in &dict.0 {
+                    converted.set(key.as_str(), self.import_value(objects, item)?);
+                }
+                Object::Dictionary(converted)
+            }
+            _ => {
+                let id = self.allocate_object_id();
+                let remapped = self.remap_references(objects, value);
+                self.write_parsed_object(id, &remapped)?;
+                Object::Reference(id)
+            }
+        })
+    }
+
+    /// The output reference for an object of an opened document
+    fn import_reference(
+        &mut self,
+        objects: &Arc<ImportedObjects>,
+        reference: (u32, u16),
+    ) -> Object {
+        let key = source_key(objects, reference);
+        if let Some(&id) = self.imported.pages.get(&key) {
+            return Object::Reference(id);
+        }
+        if let Some(&id) = self.imported.ids.get(&key) {
+            return Object::Reference(id);
+        }
+        if objects.page_tree_nodes.contains(&reference) {
+            return self.pages_id.map_or(Object::Null, Object::Reference);
+        }
+        // Removed pages and broken references
+        if !objects.objects.contains_key(&reference) {
+            return Object::Null;
+        }
+        let id = self.allocate_object_id();
+        self.imported.ids.insert(key, id);
+        self.imported.pending.push((objects.clone(), reference, id));
+        Object::Reference(id)
+    }
+
+    fn remap_references(&mut self, objects: &Arc<ImportedObjects>, value: &PdfObject) -> PdfObject {
+        match value {
+            PdfObject::Reference(num, gen) => match self.import_reference(objects, (*num, *gen)) {
+                Object::Reference(id) => PdfObject::Reference(id.number(), id.generation()),
+                _ => PdfObject::Null,
+            },
+            PdfObject::Array(array) => PdfObject::Array(PdfArray(
+                array
+                    .0
+                    .iter()
+                    .map(|item| self.remap_references(objects, item))
+                    .collect(),
+            )),
+            PdfObject::Dictionary(dict) => {
+                PdfObject::Dictionary(self.remap_dictionary(objects, dict, false))
+            }
+            PdfObject::Stream(stream) => PdfObject::Stream(PdfStream {
+                dict: self.remap_dictionary(objects, &stream.dict, true),
+                data: stream.data.clone(),
+            }),
+            _ => value.clone(),
+        }
+    }
+
+    fn remap_dictionary(
+        &mut self,
+        objects: &Arc<ImportedObjects>,
+        dict: &PdfDictionary,
+        is_stream: bool,
+    ) -> PdfDictionary {
+        let mut remapped = PdfDictionary::new();
+        for (key, value) in &dict.0 {
+            // Stream lengths are recomputed on writing
+            if !(is_stream && key.as_str() == "Length") {
+                let value = self.remap_references(objects, value);
+                remapped.insert(key.as_str().to_string(), value);
+            }
+        }
+        remapped
+    }
+
+    /// Write the copied objects referenced so far
+    fn write_imported_objects(&mut self) -> Result<()> {
+        while let Some((objects, reference, id)) = self.imported.pending.pop() {
+            let remapped = self.remap_references(&objects, &objects.objects[&reference]);
+            self.write_parsed_object(id, &remapped)?;
+        }
+        Ok(())
+    }
+}
+
+static NULL: PdfObject = PdfObject::Null;
+
+/// Follow a reference within the copied objects
+fn resolve<'a>(objects: &'a ImportedObjects, value: &'a PdfObject) -> &'a PdfObject {
+    match value {
+        PdfObject::Reference(num, gen) => objects.objects.get(&(*num, *gen)).unwrap_or(&NULL),
+        _ => value,
+    }
+}
+
+/// Whether a value converts to writer objects without losing bytes
+fn is_plain(value: &PdfObject) -> bool {
+    match value {
+        PdfObject::String(_) | PdfObject::Stream(_) => false,
+        PdfObject::Name(name) => is_plain_name(name.as_str()),
+        PdfObject::Array(array) => array.0.iter().all(is_plain),
+        PdfObject::Dictionary(dict) => dict
+            .0
+            .iter()
+            .all(|(key, value)| is_plain_name(key.as_str()) && is_plain(value)),
+        _ => true,
+    }
+}
+
+/// Whether a name can be written without `#xx` escapes
+fn is_plain_name(name: &str) -> bool {
+    name.chars().all(is_plain_name_char)
+}
+
+fn is_plain_name_char(c: char) -> bool {
+    c.is_ascii_graphic() && !"()<>[]{}/%#".contains(c)
+}
@@ -0,0 +1,808 @@
+//! Linearized output (ISO 32000-1 Annex F)
+//!
+//! A linearized file starts with everything a viewer needs to show the
+//! first page: the linearization parameter dictionary, a cross-reference
+//! section for the first page, the catalog, the hint stream and the
+//! objects of the first page. The other pages follow in order, each with
+//! the objects only it uses, then the objects shared by several pages,
+//! everything else and finally the main cross-reference section.
+//!
+//! The document is written normally into memory first and then rearranged
+//! and renumbered, as the first-page objects must be numbered after all
+//! the others.
+
+use super::{PdfWriter, WriterConfig};
+use crate::document::Document;
+use crate::error::{PdfError, Result};
+use crate::operations::optimize::{load_reachable, remap_references};
+use crate::parser::objects::{PdfDictionary, PdfName, PdfObject, PdfStream};
+use crate::parser::PdfReader;
+use crate::writer::write_parsed_value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Cursor, Write};
+
+type Id = (u32, u16);
+
+/// Catalog entries needed to open the document (ISO 32000-1 §F.3.5)
+const OPEN_DOCUMENT_KEYS: [&str; 5] = [
+    "ViewerPreferences",
+    "PageMode",
+    "Threads",
+    "OpenAction",
+    "AcroForm",
+];
+
+/// Page attributes that can be inherited from the page tree
+const INHERITABLE: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Width of the numbers filled in once the layout of the file is known
+const WIDTH: usize = 10;
+
+impl<W: Write> PdfWriter<W> {
+    /// Write the document into memory and rearrange it as a linearized file
+    pub(super) fn write_linearized(&mut self, document: &mut Document) -> Result<()> {
+        let config = WriterConfig {
+            use_xref_streams: false,
+            use_object_streams: false,
+            ..self.config.clone()
+        };
+        let mut buffer = Vec::new();
+        PdfWriter::with_config(&mut buffer, config).write_document_objects(document)?;
+
+        let output = linearize(&buffer, self.config.compress_streams)?;
+        self.write_bytes(&output)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Rearrange a file written without cross-reference or object streams
+fn linearize(input: &[u8], compress: bool) -> Result<Vec<u8>> {
+    let mut reader = PdfReader::new(Cursor::new(input))?;
+    let trailer = reader.trailer().clone();
+    let root = trailer.root()?;
+    let info = trailer.info();
+    let file_id = trailer.id().cloned();
+    let version = reader.version().to_string();
+
+    let mut objects = load_reachable(&mut reader, root, info);
+    let (pages, tree_nodes) = page_tree(&mut objects, root);
+    if pages.is_empty() {
+        return Err(PdfError::InvalidOperation(
+            "Linearized output requires at least one page".to_string(),
+        ));
+    }
+    let parts = Parts::new(&objects, root, &pages, &tree_nodes);
+
+    // The main section is numbered from 1 in file order; the first-page
+    // section follows with the linearization dictionary, the catalog and
+    // the other document objects, the hint stream and the first page
+    let main = parts.main();
+    let main_size = main.len() as u32 + 1;
+    let mut numbers: HashMap<Id, u32> = HashMap::new();
+    for (number, &id) in (1..).zip(&main) {
+        numbers.insert(id, number);
+    }
+    let linearization_number = main_size;
+    for (number, &id) in (linearization_number + 1..).zip(&parts.document) {
+        numbers.insert(id, number);
+    }
+    let hint_number = linearization_number + 1 + parts.document.len() as u32;
+    for (number, &id) in (hint_number + 1..).zip(&parts.first_page) {
+        numbers.insert(id, number);
+    }
+    let size = hint_number + 1 + parts.first_page.len() as u32;
+
+    // Every reference points to a loaded object; anything else becomes a
+    // reference to the free object 0, which reads as null
+    let renumber = |id: Id| numbers.get(&id).map_or((0, 0), |&number| (number, 0));
+    let serialize = |ids: &[Id]| -> Vec<(u32, Vec<u8>)> {
+        ids.iter()
+            .map(|id| {
+                let mut object = objects[id].clone();
+                remap_references(&mut object, &renumber);
+                (numbers[id], object_bytes(numbers[id], &object))
+            })
+            .collect()
+    };
+
+    let mut header = format!("%PDF-{version}\n").into_bytes();
+    header.extend_from_slice(&[b'%', 0xE2, 0xE3, 0xCF, 0xD3, b'\n']);
+    let mut file = File {
+        header,
+        linearization_number,
+        hint_number,
+        first_page_number: numbers[&pages[0]],
+        page_count: pages.len(),
+        main_size,
+        size,
+        root: numbers[&root],
+        info: info.and_then(|id| numbers.get(&id).copied()),
+        file_id,
+        document: serialize(&parts.document),
+        hint: Vec::new(),
+        first_page: serialize(&parts.first_page),
+        pages: parts.pages.iter().map(|ids| serialize(ids)).collect(),
+        shared: serialize(&parts.shared),
+        other: serialize(&parts.other),
+    };
+
+    // Offsets in the hint tables leave out the hint stream itself
+    // (ISO 32000-1 §F.4), so they come from a layout without it
+    let without_hints = file.layout();
+    let (data, shared_table_offset) = file.hint_tables(&parts, &numbers, &without_hints);
+    file.hint = hint_stream_bytes(hint_number, data, shared_table_offset, compress)?;
+
+    let layout = file.layout();
+    Ok(file.finish(layout))
+}
+
+/// Page objects in document order and the other nodes of the page tree
+///
+/// Inheritable attributes are copied into the page objects, as a viewer
+/// showing the first page hasn't loaded the rest of the tree.
+fn page_tree(objects: &mut HashMap<Id, PdfObject>, root: Id) -> (Vec<Id>, HashSet<Id>) {
+    let mut pages = Vec::new();
+    let mut nodes = HashSet::new();
+    let top = objects
+        .get(&root)
+        .and_then(|catalog| catalog.as_dict())
+        .and_then(|catalog| catalog.get("Pages"))
+        .and_then(|pages| pages.as_reference());
+    let mut pending: Vec<(Id, PdfDictionary)> = top
+        .map(|id| (id, PdfDictionary::new()))
+        .into_iter()
+        .collect();
+    let mut visited = HashSet::new();
+
+    while let Some((id, mut inherited)) = pending.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let Some(PdfObject::Dictionary(dict)) = objects.get_mut(&id) else {
+            continue;
+        };
+        if dict.get_type() == Some("Pages") || dict.contains_key("Kids") {
+            nodes.insert(id);
+            for key in INHERITABLE {
+                if let Some(value) = dict.get(key) {
+                    inherited.insert(key.to_string(), value.clone());
+                }
+            }
+            let kids = dict.get("Kids").and_then(|kids| kids.as_array());
+            for kid in kids.iter().flat_map(|kids| kids.0.iter().rev()) {
+                if let Some(kid) = kid.as_reference() {
+                    pending.push((kid, inherited.clone()));
+                }
+            }
+        } else {
+            for (key, value) in inherited.0 {
+                dict.0.entry(key).or_insert(value);
+            }
+            pages.push(id);
+        }
+    }
+    (pages, nodes)
+}
+
+/// Objects referenced from `start`, directly or through other objects, in
+/// breadth-first order
+///
+/// `/Parent` links are not followed, and neither are objects in `seen` or
+/// matching `skip`.
+fn closure(
+    objects: &HashMap<Id, PdfObject>,
+    start: &PdfObject,
+    seen: &mut HashSet<Id>,
+    skip: &dyn Fn(&Id) -> bool,
+) -> Vec<Id> {
+    let mut found = Vec::new();
+    let mut queue = VecDeque::new();
+    references(start, &mut queue);
+    while let Some(id) = queue.pop_front() {
+        if skip(&id) || !objects.contains_key(&id) || !seen.insert(id) {
+            continue;
+        }
+        found.push(id);
+        references(&objects[&id], &mut queue);
+    }
+    found
+}
+
+fn references(object: &PdfObject, out: &mut VecDeque<Id>) {
+    match object {
+        PdfObject::Reference(num, gen) => out.push_back((*num, *gen)),
+        PdfObject::Array(array) => array.0.iter().for_each(|item| references(item, out)),
+        PdfObject::Dictionary(dict) => dict_references(dict, out),
+        PdfObject::Stream(stream) => dict_references(&stream.dict, out),
+        _ => {}
+    }
+}
+
+fn dict_references(dict: &PdfDictionary, out: &mut VecDeque<Id>) {
+    for (key, value) in &dict.0 {
+        if key.as_str() != "Parent" {
+            references(value, out);
+        }
+    }
+}
+
+/// Objects of the file grouped in the order they are written
+struct Parts {
+    /// Catalog and the objects needed to open the document
+    document: Vec<Id>,
+    /// First page object and everything used to show it
+    first_page: Vec<Id>,
+    /// Page object and the objects used only by it, for each later page
+    pages: Vec<Vec<Id>>,
+    /// Objects used by several later pages but not by the first
+    shared: Vec<Id>,
+    /// Objects no page uses
+    other: Vec<Id>,
+    /// First-page and shared objects used by each later page
+    shared_references: Vec<Vec<Id>>,
+}
+
+impl Parts {
+    fn new(
+        objects: &HashMap<Id, PdfObject>,
+        root: Id,
+        pages: &[Id],
+        tree_nodes: &HashSet<Id>,
+    ) -> Self {
+        let page_set: HashSet<Id> = pages.iter().copied().collect();
+        let structural = |id: &Id| *id == root || page_set.contains(id) || tree_nodes.contains(id);
+
+        let mut document = vec![root];
+        let mut seen = HashSet::from([root]);
+        if let Some(PdfObject::Dictionary(catalog)) = objects.get(&root) {
+            for key in OPEN_DOCUMENT_KEYS {
+                if let Some(value) = catalog.get(key) {
+                    document.extend(closure(objects, value, &mut seen, &structural));
+                }
+            }
+        }
+
+        let in_document: HashSet<Id> = document.iter().copied().collect();
+        let uses: Vec<Vec<Id>> = pages
+            .iter()
+            .map(|page| {
+                let mut seen = HashSet::from([*page]);
+                let skip = |id: &Id| structural(id) || in_document.contains(id);
+                closure(objects, &objects[page], &mut seen, &skip)
+            })
+            .collect();
+
+        let mut first_page = vec![pages[0]];
+        first_page.extend(&uses[0]);
+        let on_first_page: HashSet<Id> = first_page.iter().copied().collect();
+
+        let mut use_count: HashMap<Id, usize> = HashMap::new();
+        for id in uses[1..].iter().flatten() {
+            if !on_first_page.contains(id) {
+                *use_count.entry(*id).or_default() += 1;
+            }
+        }
+        let is_shared = |id: &Id| on_first_page.contains(id) || use_count[id] > 1;
+
+        let mut later_pages = Vec::new();
+        let mut shared = Vec::new();
+        let mut shared_references = Vec::new();
+        let mut seen_shared = HashSet::new();
+        for (page, used) in pages[1..].iter().zip(&uses[1..]) {
+            let mut own = vec![*page];
+            own.extend(used.iter().filter(|id| !is_shared(id)));
+            later_pages.push(own);
+
+            for id in used.iter().filter(|id| !on_first_page.contains(id)) {
+                if use_count[id] > 1 && seen_shared.insert(*id) {
+                    shared.push(*id);
+                }
+            }
+            shared_references.push(used.iter().copied().filter(is_shared).collect());
+        }
+
+        let placed: HashSet<Id> = document
+            .iter()
+            .chain(&first_page)
+            .chain(later_pages.iter().flatten())
+            .chain(&shared)
+            .copied()
+            .collect();
+        let mut other: Vec<Id> = objects
+            .keys()
+            .filter(|id| !placed.contains(id))
+            .copied()
+            .collect();
+        other.sort_unstable();
+
+        Self {
+            document,
+            first_page,
+            pages: later_pages,
+            shared,
+            other,
+            shared_references,
+        }
+    }
+
+    /// Objects of the main section in file order
+    fn main(&self) -> Vec<Id> {
+        let pages = self.pages.iter().flatten();
+        pages
+            .chain(&self.shared)
+            .chain(&self.other)
+            .copied()
+            .collect()
+    }
+}
+
+/// Serialized objects of the linearized file, by part
+struct File {
+    header: Vec<u8>,
+    linearization_number: u32,
+    hint_number: u32,
+    first_page_number: u32,
+    page_count: usize,
+    main_size: u32,
+    size: u32,
+    root: u32,
+    info: Option<u32>,
+    file_id: Option<PdfObject>,
+    document: Vec<(u32, Vec<u8>)>,
+    hint: Vec<u8>,
+    first_page: Vec<(u32, Vec<u8>)>,
+    pages: Vec<Vec<(u32, Vec<u8>)>>,
+    shared: Vec<(u32, Vec<u8>)>,
+    other: Vec<(u32, Vec<u8>)>,
+}
+
+/// Positions of the parts of a linearized file
+#[derive(Default)]
+struct Layout {
+    offsets: HashMap<u32, u64>,
+    first_xref: u64,
+    first_page_end: u64,
+    /// Start and end of each page, the first page included
+    page_ranges: Vec<(u64, u64)>,
+    main_xref: u64,
+    /// Length of the file up to the part placed last
+    length: u64,
+}
+
+impl Layout {
+    fn place(&mut self, objects: &[(u32, Vec<u8>)]) {
+        for (number, bytes) in objects {
+            self.offsets.insert(*number, self.length);
+            self.length += bytes.len() as u64;
+        }
+    }
+}
+
+impl File {
+    /// Position every part; the numbers of the linearization dictionary
+    /// and the first-page trailer have a fixed width, so their values
+    /// don't move anything
+    fn layout(&self) -> Layout {
+        let mut layout = Layout::default();
+        let header = self.header.len() as u64;
+        layout.offsets.insert(self.linearization_number, header);
+        layout.first_xref = header + self.linearization_dictionary(None).len() as u64;
+        layout.length = layout.first_xref
+            + self.first_xref(&layout).len() as u64
+            + self.first_trailer(0).len() as u64;
+
+        layout.place(&self.document);
+        layout.offsets.insert(self.hint_number, layout.length);
+        layout.length += self.hint.len() as u64;
+        for (index, page) in std::iter::once(&self.first_page)
+            .chain(&self.pages)
+            .enumerate()
+        {
+            let start = layout.length;
+            layout.place(page);
+            layout.page_ranges.push((start, layout.length));
+            if index == 0 {
+                layout.first_page_end = layout.length;
+            }
+        }
+        layout.place(&self.shared);
+        layout.place(&self.other);
+
+        layout.main_xref = layout.length;
+        layout.length += (self.main_xref(&layout).len() + self.main_trailer(&layout).len()) as u64;
+        layout
+    }
+
+    /// Assemble the file with the positions filled in
+    fn finish(self, layout: Layout) -> Vec<u8> {
+        let mut out = Vec::with_capacity(layout.length as usize);
+        out.extend_from_slice(&self.header);
+        out.extend(self.linearization_dictionary(Some(&layout)));
+        out.extend(self.first_xref(&layout));
+        out.extend(self.first_trailer(layout.main_xref));
+        for (_, bytes) in &self.document {
+            out.extend_from_slice(bytes);
+        }
+        out.extend_from_slice(&self.hint);
+        let objects = self.first_page.iter().chain(self.pages.iter().flatten());
+        for (_, bytes) in objects.chain(&self.shared).chain(&self.other) {
+            out.extend_from_slice(bytes);
+        }
+        out.extend(self.main_xref(&layout));
+        out.extend(self.main_trailer(&layout));
+        out
+    }
+
+    /// Linearization parameter dictionary (ISO 32000-1 Table F.1), with
+    /// zeros while the layout is unknown
+    fn linearization_dictionary(&self, layout: Option<&Layout>) -> Vec<u8> {
+        let [length, hint, first_page_end, main_entries] = layout.map_or([0; 4], |layout| {
+            [
+                layout.length,
+                layout.offsets[&self.hint_number],
+                layout.first_page_end,
+                // The end of line before the first main xref entry
+                layout.main_xref + self.main_xref_header().len() as u64 - 1,
+            ]
+        });
+        format!(
+            "{} 0 obj\n<</Linearized 1/L {length:<WIDTH$}/H [{hint:<WIDTH$} {:<WIDTH$}]\
+             /O {}/E {first_page_end:<WIDTH$}/N {}/T {main_entries:<WIDTH$}>>\nendobj\n",
+            self.linearization_number,
+            self.hint.len(),
+            self.first_page_number,
+            self.page_count,
+        )
+        .into_bytes()
+    }
+
+    /// Cross-reference section of the first-page objects
+    fn first_xref(&self, layout: &Layout) -> Vec<u8> {
+        let first = self.linearization_number;
+        let mut out = format!("xref\n{first} {}\n", self.size - first).into_bytes();
+        for number in first..self.size {
+            let offset = layout.offsets.get(&number).copied().unwrap_or_default();
+            out.extend(format!("{offset:010} 00000 n \n").bytes());
+        }
+        out
+    }
+
+    /// Trailer of the first-page section, pointing to the main section
+    fn first_trailer(&self, main_xref: u64) -> Vec<u8> {
+        let mut out = format!("trailer\n<</Size {}/Root {} 0 R", self.size, self.root);
+        if let Some(info) = self.info {
+            out.push_str(&format!("/Info {info} 0 R"));
+        }
+        let mut out = out.into_bytes();
+        if let Some(id) = &self.file_id {
+            out.extend_from_slice(b"/ID ");
+            write_parsed_value(id, &mut out);
+        }
+        out.extend(format!("/Prev {main_xref:<WIDTH$}>>\nstartxref\n0\n%%EOF\n").bytes());
+        out
+    }
+
+    fn main_xref_header(&self) -> String {
+        format!("xref\n0 {}\n", self.main_size)
+    }
+
+    /// Cross-reference section of all other objects
+    fn main_xref(&self, layout: &Layout) -> Vec<u8> {
+        let mut out = self.main_xref_header().into_bytes();
+        out.extend_from_slice(b"0000000000 65535 f \n");
+        for number in 1..self.main_size {
+            let offset = layout.offsets.get(&number).copied().unwrap_or_default();
+            out.extend(format!("{offset:010} 00000 n \n").bytes());
+        }
+        out
+    }
+
+    /// Trailer of the main section; `startxref` points to the first-page
+    /// section, which leads to the main one through `/Prev`
+    fn main_trailer(&self, layout: &Layout) -> Vec<u8> {
+        format!(
+            "trailer\n<</Size {}>>\nstartxref\n{}\n%%EOF\n",
+            self.main_size, layout.first_xref
+        )
+        .into_bytes()
+    }
+
+    /// Page offset and shared object hint tables (ISO 32000-1 §F.4), and
+    /// the offset of the shared object table in the stream
+    fn hint_tables(
+        &self,
+        parts: &Parts,
+        numbers: &HashMap<Id, u32>,
+        layout: &Layout,
+    ) -> (Vec<u8>, usize) {
+        // Shared object groups are single objects: first those of the
+        // first page, then those of the shared objects section
+        let groups: Vec<&(u32, Vec<u8>)> = self.first_page.iter().chain(&self.shared).collect();
+        let group_of: HashMap<u32, u64> = groups
+            .iter()
+            .enumerate()
+            .map(|(index, (number, _))| (*number, index as u64))
+            .collect();
+
+        let object_counts: Vec<u64> = std::iter::once(self.first_page.len())
+            .chain(self.pages.iter().map(Vec::len))
+            .map(|count| count as u64)
+            .collect();
+        let page_lengths: Vec<u64> = layout
+            .page_ranges
+            .iter()
+            .map(|(start, end)| end - start)
+            .collect();
+        let shared_references: Vec<Vec<u64>> = std::iter::once(Vec::new())
+            .chain(parts.shared_references.iter().map(|ids| {
+                ids.iter()
+                    .map(|id| group_of[&numbers[id]])
+                    .collect::<Vec<_>>()
+            }))
+            .collect();
+
+        let mut bits = BitWriter::default();
+        let min_objects = object_counts.iter().min().copied().unwrap_or_default();
+        let max_objects = object_counts.iter().max().copied().unwrap_or_default();
+        let min_length = page_lengths.iter().min().copied().unwrap_or_default();
+        let max_length = page_lengths.iter().max().copied().unwrap_or_default();
+        let object_bits = bits_for(max_objects - min_objects);
+        let length_bits = bits_for(max_length - min_length);
+        let max_references = shared_references.iter().map(Vec::len).max();
+        let reference_count_bits = bits_for(max_references.unwrap_or_default() as u64);
+        let max_group = shared_references.iter().flatten().max();
+        let identifier_bits = bits_for(max_group.copied().unwrap_or_default());
+
+        // Page offset hint table header (Table F.3). Content streams aren't
+        // located separately: their offset is 0 and their length is the
+        // page length, as viewers don't rely on these items.
+        bits.write(min_objects, 32);
+        bits.write(layout.offsets[&self.first_page_number], 32);
+        bits.write(object_bits.into(), 16);
+        bits.write(min_length, 32);
+        bits.write(length_bits.into(), 16);
+        bits.write(0, 32);
+        bits.write(0, 16);
+        bits.write(min_length, 32);
+        bits.write(length_bits.into(), 16);
+        bits.write(reference_count_bits.into(), 16);
+        bits.write(identifier_bits.into(), 16);
+        bits.write(0, 16);
+        bits.write(1, 16);
+
+        // Page offset hint table entries (Table F.4), item by item
+        bits.write_all(object_counts.iter().map(|n| n - min_objects), object_bits);
+        bits.write_all(page_lengths.iter().map(|n| n - min_length), length_bits);
+        let reference_counts = shared_references.iter().map(|refs| refs.len() as u64);
+        bits.write_all(reference_counts, reference_count_bits);
+        bits.write_all(shared_references.iter().flatten().copied(), identifier_bits);
+        bits.write_all(shared_references.iter().flatten().map(|_| 0), 0);
+        bits.write_all(page_lengths.iter().map(|_| 0), 0);
+        bits.write_all(page_lengths.iter().map(|n| n - min_length), length_bits);
+        let shared_table_offset = bits.bytes.len();
+
+        // Shared object hint table header (Table F.5)
+        let group_lengths: Vec<u64> = groups.iter().map(|(_, b)| b.len() as u64).collect();
+        let min_group = group_lengths.iter().min().copied().unwrap_or_default();
+        let max_group = group_lengths.iter().max().copied().unwrap_or_default();
+        let group_length_bits = bits_for(max_group - min_group);
+        let (first_shared, first_shared_offset) = self
+            .shared
+            .first()
+            .map_or((0, 0), |(number, _)| (*number, layout.offsets[number]));
+        bits.write(first_shared.into(), 32);
+        bits.write(first_shared_offset, 32);
+        bits.write(self.first_page.len() as u64, 32);
+        bits.write(groups.len() as u64, 32);
+        bits.write(0, 16);
+        bits.write(min_group, 32);
+        bits.write(group_length_bits.into(), 16);
+
+        // Shared object hint table entries (Table F.6): no MD5 signatures,
+        // one object per group
+        bits.write_all(
+            group_lengths.iter().map(|n| n - min_group),
+            group_length_bits,
+        );
+        bits.write_all(group_lengths.iter().map(|_| 0), 1);
+        bits.write_all(group_lengths.iter().map(|_| 0), 0);
+
+        (bits.bytes, shared_table_offset)
+    }
+}
+
+/// Hint stream object with its shared object table at `shared_table_offset`
+fn hint_stream_bytes(
+    number: u32,
+    data: Vec<u8>,
+    shared_table_offset: usize,
+    compress: bool,
+) -> Result<Vec<u8>> {
+    let mut dict = PdfDictionary::new();
+    dict.insert(
+        "S".to_string(),
+        PdfObject::Integer(shared_table_offset as i64),
+    );
+    let data = if compress {
+        dict.insert(
+            "Filter".to_string(),
+            PdfObject::Name(PdfName("FlateDecode".to_string())),
+        );
+        crate::compression::compress(&data)?
+    } else {
+        data
+    };
+    Ok(object_bytes(
+        number,
+        &PdfObject::Stream(PdfStream { dict, data }),
+    ))
+}
+
+fn object_bytes(number: u32, object: &PdfObject) -> Vec<u8> {
+    let mut out = format!("{number} 0 obj\n").into_bytes();
+    write_parsed_value(object, &mut out);
+    out.extend_from_slice(b"\nendobj\n");
+    out
+}
+
+/// Number of bits needed to represent `value`
+fn bits_for(value: u64) -> u8 {
+    (u64::BITS - value.leading_zeros()) as u8
+}
+
+/// Writes hint table items most significant bit first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    used: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        for bit in (0..bits).rev() {
+            self.current = (self.current << 1) | ((value >> bit) & 1) as u8;
+            self.used += 1;
+            if self.used == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    /// Write one item for every page or group, padded to a byte boundary
+    fn write_all(&mut self, values: impl Iterator<Item = u64>, bits: u8) {
+        for value in values {
+            self.write(value, bits.into());
+        }
+        if self.used > 0 {
+            self.write(0, 8 - self.used);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::PdfDocument;
+    use crate::text::Font;
+    use crate::Page;
+
+    fn linearized_pdf(pages: usize, config: WriterConfig) -> Vec<u8> {
+        let mut doc = Document::new();
+        doc.set_title("Linearization test");
+        doc.enable_linearization(true);
+        for i in 0..pages {
+            let mut page = Page::a4();
+            page.text()
+                .set_font(Font::Helvetica, 12.0)
+                .at(72.0, 720.0)
+                .write(&format!("Page {}", i + 1))
+                .unwrap();
+            doc.add_page(page);
+        }
+        doc.to_bytes_with_config(config).unwrap()
+    }
+
+    /// Integer following `key` in the linearization dictionary
+    fn parameter(file: &[u8], key: &str) -> u64 {
+        let text = String::from_utf8_lossy(&file[..400]);
+        let start = text.find(&format!("/{key} ")).unwrap() + key.len() + 2;
+        let value = text[start..].trim_start_matches(['[', ' ']);
+        let end = value.find(|c: char| !c.is_ascii_digit()).unwrap();
+        value[..end].parse().unwrap()
+    }
+
+    fn find(file: &[u8], needle: &str) -> Option<usize> {
+        let needle = needle.as_bytes();
+        file.windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    fn starts_with_at(file: &[u8], offset: u64, prefix: &str) -> bool {
+        file[offset as usize..].starts_with(prefix.as_bytes())
+    }
+
+    #[test]
+    fn test_linearized_file_is_readable() {
+        let output = linearized_pdf(3, WriterConfig::default());
+
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(output)).unwrap());
+        assert_eq!(document.page_count().unwrap(), 3);
+        assert_eq!(
+            document.metadata().unwrap().title.as_deref(),
+            Some("Linearization test")
+        );
+        for index in 0..3 {
+            let text = document.extract_text_from_page(index).unwrap().text;
+            assert!(text.contains(&format!("Page {}", index + 1)), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_linearization_parameters() {
+        let output = linearized_pdf(3, WriterConfig::default());
+
+        // The dictionary is the first object, after the header lines
+        let header = find(&output, "\n").unwrap() + 1;
+        let first_object = header + find(&output[header..], "\n").unwrap() + 1;
+        assert!(find(&output[first_object..], "obj\n<</Linearized 1/L ") < Some(10));
+        assert_eq!(parameter(&output, "L"), output.len() as u64);
+        assert_eq!(parameter(&output, "N"), 3);
+
+        // The first page object, then its content, come before the others
+        let first_page = parameter(&output, "O");
+        let first_page_end = parameter(&output, "E") as usize;
+        let page_offset = find(&output, &format!("\n{first_page} 0 obj\n")).unwrap() + 1;
+        let page_end = page_offset + find(&output[page_offset..], "endobj").unwrap();
+        assert!(find(&output[page_offset..page_end], "/Type /Page").is_some());
+        assert!(page_offset < first_page_end);
+        assert!(find(&output[..first_page_end], "(Page 1) Tj").is_some());
+        assert!(find(&output[..first_page_end], "(Page 2) Tj").is_none());
+        assert!(find(&output, "(Page 2) Tj") < find(&output, "(Page 3) Tj"));
+
+        // The hint stream precedes the first page object
+        let hint = parameter(&output, "H");
+        assert!(starts_with_at(
+            &output,
+            hint,
+            &format!("{} 0 obj", first_page - 1)
+        ));
+
+        // `/T` points to the end of line before the main xref entries, and
+        // `startxref` to the first-page xref section
+        let main_entries = parameter(&output, "T");
+        assert!(starts_with_at(
+            &output,
+            main_entries,
+            "\n0000000000 65535 f"
+        ));
+        let first_xref = find(&output, "\nxref\n").unwrap() + 1;
+        let end = format!("startxref\n{first_xref}\n%%EOF\n");
+        assert!(output.ends_with(end.as_bytes()));
+    }
+
+    #[test]
+    fn test_hint_tables() {
+        let config = WriterConfig {
+            compress_streams: false,
+            ..WriterConfig::default()
+        };
+        let output = linearized_pdf(2, config);
+        let hint = parameter(&output, "H") as usize;
+        let hint_length = find(&output[hint..], "endobj\n").unwrap() + 7;
+        let data = &output[hint + find(&output[hint..], "stream\n").unwrap() + 7..];
+        let read = |offset: usize| u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap());
+
+        // Page offset hint table header: least number of objects in a page,
+        // and the offset of the first page object as if the hint stream
+        // were not there
+        let first_page = parameter(&output, "O");
+        let page_offset = find(&output, &format!("\n{first_page} 0 obj\n")).unwrap() + 1;
+        assert_eq!(read(0), 2);
+        assert_eq!(read(4) as usize, page_offset - hint_length);
+        assert_eq!(parameter(&output, "H") as usize, hint);
+    }
+}
@@ -2,6 +2,7 @@ use crate::document::Document;
 use crate::error::{PdfError, Result};
 use crate::objects::{Dictionary, Object, ObjectId};
 use crate::text::fonts::embedding::CjkFontType;
+use crate::verification::pdfa::PdfALevel;
 use crate::writer::{ObjectStreamConfig, ObjectStreamWriter, XRefStreamWriter};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
@@ -69,7 +70,6 @@ impl WriterConfig {
         }
     }
 }
-
 pub struct PdfWriter<W: Write> {
     writer: W,
     xref_positions: HashMap<ObjectId, u64>,
@@ -88,14 +88,28 @@ pub struct PdfWriter<W: Write> {
     page_ids: Vec<ObjectId>,       // page IDs for form field references
     // Configuration
     config: WriterConfig,
+    // PDF/A level and PDF/UA conformance of the document being written
+    pdfa: Option<PdfALevel>,
+    pdfua: bool,
     // Characters used in document (for font subsetting)
     document_used_chars: Option<std::collections::HashSet<char>>,
+    // Fonts the document shapes text with, and their private use CIDs
+    shaping_fonts: crate::text::ShapingFonts,
     // Object stream buffering (when use_object_streams is enabled)
     buffered_objects: HashMap<ObjectId, Vec<u8>>,
     compressed_object_map: HashMap<ObjectId, (ObjectId, u32)>, // obj_id -> (stream_id, index)
     // Incremental update support (ISO 32000-1 §7.5.6)
     prev_xref_offset: Option<u64>,
     base_pdf_size: Option<u64>,
+    // Objects copied from opened documents
+    imported: import::ImportState,
+    // Digest of the bytes written so far, the source of the PDF/A file
+    // identifier
+    content_digest: md5::Context,
+    // Layer and membership objects, by resource name
+    optional_content_ids: HashMap<String, ObjectId>,
+    // Structure tree of the document being written, tagged pages included
+    structure: Option<crate::structure::StructTree>,
 }
 
 impl<W: Write> PdfWriter<W> {
@@ -117,18 +131,61 @@ impl<W: Write> PdfWriter<W> {
             form_field_ids: Vec::new(),
             page_ids: Vec::new(),
             config,
+            pdfa: None,
+            pdfua: false,
             document_used_chars: None,
+            shaping_fonts: Default::default(),
             buffered_objects: HashMap::new(),
             compressed_object_map: HashMap::new(),
             prev_xref_offset: None,
             base_pdf_size: None,
+            imported: import::ImportState::default(),
+            content_digest: md5::Context::new(),
+            optional_content_ids: HashMap::new(),
+            structure: None,
         }
     }
 
     pub fn write_document(&mut self, document: &mut Document) -> Result<()> {
-        // Store used characters for font subsetting
-        if !document.used_characters.is_empty() {
-            self.document_used_chars = Some(document.used_characters.clone());
+        if document.linearize {
+            return self.write_linearized(document);
+        }
+        self.write_document_objects(document)
+    }
+
+    /// Write the document in object order
+    pub(super) fn write_document_objects(&mut self, document: &mut Document) -> Result<()> {
+        self.shaping_fonts = document.shaping_fonts.clone();
+        // Shape text written before its font was added, ahead of the fonts
+        // so they have the glyphs it uses
+        for page in &mut document.pages {
+            page.set_shaping_fonts(&self.shaping_fonts);
+        }
+
+        // Store used characters for font subsetting, including text added
+        // to pages after they joined the document
+        let mut used_characters = document.used_characters.clone();
+        for page in &document.pages {
+            used_characters.extend(page.get_used_characters().unwrap_or_default());
+        }
+        if !used_characters.is_empty() {
+            self.document_used_chars = Some(used_characters);
+        }
+
+        // Hybrid invoices identify themselves as PDF/A-3b, so they are
+        // written as such
+        self.pdfa = match (document.pdfa, &document.facturx) {
+            (None, Some(_)) => Some(PdfALevel::A3b),
+            (level, _) => level,
+        };
+        if let Some(level) = self.pdfa {
+            self.prepare_pdfa(document, level)?;
+        }
+
+        self.structure = document.tagged_structure()?;
+        self.pdfua = document.pdfua;
+        if self.pdfua {
+            self.prepare_pdfua(document)?;
         }
 
         self.write_header()?;
@@ -141,6 +198,12 @@ impl<W: Write> PdfWriter<W> {
         // Write custom fonts first (so pages can reference them)
         let font_refs = self.write_fonts(document)?;
 
+        // Layers are referenced from page resources and the catalog
+        for name in document.optional_content.resource_names() {
+            let id = self.allocate_object_id();
+            self.optional_content_ids.insert(name.to_string(), id);
+        }
+
         // Write pages (they contain widget annotations and font references)
         self.write_pages(document, &font_refs)?;
 
@@ -322,6 +385,7 @@ impl<W: Write> PdfWriter<W> {
         if !document.used_characters.is_empty() {
             self.document_used_chars = Some(document.used_characters.clone());
         }
+        self.shaping_fonts = document.shaping_fonts.clone();
 
         // Allocate IDs for new objects
         self.catalog_id = Some(self.allocate_object_id());
@@ -548,6 +612,7 @@ impl<W: Write> PdfWriter<W> {
         if !document.used_characters.is_empty() {
             self.document_used_chars = Some(document.used_characters.clone());
         }
+        self.shaping_fonts = document.shaping_fonts.clone();
 
         self.catalog_id = Some(self.allocate_object_id());
         self.pages_id = Some(self.allocate_object_id());
@@ -723,6 +788,7 @@ impl<W: Write> PdfWriter<W> {
         if !temp_doc.used_characters.is_empty() {
             self.document_used_chars = Some(temp_doc.used_characters.clone());
         }
+        self.shaping_fonts = temp_doc.shaping_fonts.clone();
 
         self.catalog_id = Some(self.allocate_object_id());
         self.pages_id = Some(self.allocate_object_id());
@@ -769,6 +835,271 @@ impl<W: Write> PdfWriter<W> {
         Ok(())
     }
 
+    /// Appends an incremental update that rewrites or adds exactly the given objects
+    /// (ISO 32000-1 §7.5.6).
+    ///
+    /// The base bytes are copied verbatim, followed by `objects`, a cross-reference
+    /// section listing only those objects and a trailer chained to the previous
+    /// section through `/Prev`. The section is a cross-reference stream when the
+    /// base document ends in one, and a classic table otherwise. `/Root`, `/Info` and the first `/ID` string are carried
+    /// over from the base trailer, so callers only supply the objects they changed.
+    /// New objects must be numbered from the base trailer's `/Size` upwards.
+    ///
+    /// Objects are always written uncompressed (no object streams) so that the
+    /// update stays readable by every conforming reader.
+    ///
+    /// # Errors
+    /// Returns an error if the base document cannot be parsed or is encrypted
+    /// (the new objects would be written in the clear).
+    pub fn write_incremental_objects(
+        &mut self,
+        base_pdf: &[u8],
+        objects: Vec<(ObjectId, Object)>,
+    ) -> Result<()> {
+        let object_count = objects.len();
+        self.write_incremental_with(base_pdf, object_count, |writer| {
+            for (id, object) in objects {
+                writer.write_object(id, object)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Appends an incremental update like [`write_incremental_objects`](Self::write_incremental_objects)
+    /// for objects in parsed form, which keeps the exact bytes of strings and names.
+    pub fn write_incremental_parsed_objects(
+        &mut self,
+        base_pdf: &[u8],
+        objects: Vec<(ObjectId, crate::parser::PdfObject)>,
+    ) -> Result<()> {
+        let object_count = objects.len();
+        self.write_incremental_with(base_pdf, object_count, |writer| {
+            for (id, object) in &objects {
+                writer.write_parsed_object(*id, object)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn write_incremental_with(
+        &mut self,
+        base_pdf: &[u8],
+        object_count: usize,
+        write_objects: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        let reader = crate::parser::PdfReader::new(std::io::Cursor::new(base_pdf))?;
+        if reader.is_encrypted() {
+            return Err(PdfError::EncryptionError(
+                "Incremental updates of encrypted documents are not supported".to_string(),
+            ));
+        }
+        let trailer = reader.trailer().clone();
+        let root = trailer.root()?;
+        let info = trailer.info();
+        let base_object_count = trailer.size()?;
+        let base_id = trailer.id().and_then(|id| match id {
+            crate::parser::PdfObject::Array(arr) => arr.get(0).and_then(|first| match first {
+                crate::parser::PdfObject::String(s) => Some(s.as_bytes().to_vec()),
+                _ => None,
+            }),
+            _ => None,
+        });
+
+        self.writer.write_all(base_pdf)?;
+        self.current_position = base_pdf.len() as u64;
+        if !base_pdf.ends_with(b"\n") && !base_pdf.ends_with(b"\r") {
+            self.write_bytes(b"\n")?;
+        }
+        self.prev_xref_offset = Some(trailer.xref_offset);
+        self.base_pdf_size = Some(base_pdf.len() as u64);
+
+        let use_object_streams = std::mem::replace(&mut self.config.use_object_streams, false);
+        let written = write_objects(self);
+        self.config.use_object_streams = use_object_streams;
+        written?;
+
+        // An update is written in the same form as the section it follows, so
+        // readers that only understand one of the two keep working
+        let xref_position = self.current_position;
+        let base_uses_xref_stream = base_pdf
+            .get(trailer.xref_offset as usize..)
+            .and_then(|rest| {
+                rest.iter()
+                    .position(|b| !b.is_ascii_whitespace())
+                    .map(|i| &rest[i..])
+            })
+            .is_some_and(|rest| !rest.starts_with(b"xref"));
+        let mut entries: Vec<_> = self
+            .xref_positions
+            .iter()
+            .map(|(id, pos)| (*id, *pos))
+            .collect();
+        entries.sort_by_key(|(id, _)| id.number());
+
+        let max_obj_num = entries.last().map(|(id, _)| id.number()).unwrap_or(0);
+        let mut size = base_object_count.max(max_obj_num + 1);
+        if base_uses_xref_stream {
+            entries.push((ObjectId::new(size, 0), xref_position));
+            size += 1;
+        }
+
+        // One subsection per run of consecutive numbers
+        let mut subsections = Vec::new();
+        let mut start = 0;
+        while start < entries.len() {
+            let mut end = start + 1;
+            while end < entries.len() && entries[end].0.number() == entries[end - 1].0.number() + 1
+            {
+                end += 1;
+            }
+            subsections.push(&entries[start..end]);
+            start = end;
+        }
+
+        // The first ID string is permanent; the second changes with every update
+        let first_id = base_id.unwrap_or_else(|| md5::compute(base_pdf).to_vec());
+        let mut update_seed = first_id.clone();
+        update_seed.extend_from_slice(&xref_position.to_be_bytes());
+        update_seed.extend_from_slice(&(object_count as u64).to_be_bytes());
+        let update_id = md5::compute(&update_seed).to_vec();
+        let to_hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{b:02X}")).collect::<String>();
+        let id_array = format!("[<{}> <{}>]", to_hex(&first_id), to_hex(&update_id));
+
+        if base_uses_xref_stream {
+            let (stream_id, _) = *entries.last().expect("the xref stream has an entry");
+            let mut xref_writer = XRefStreamWriter::new(stream_id);
+            for (id, position) in &entries {
+                xref_writer.add_in_use_entry(*position, id.generation());
+            }
+            let data = crate::compression::compress(&xref_writer.encode_entries())?;
+
+            let mut dict = xref_writer.create_dictionary(Some(trailer.xref_offset));
+            dict.set("Size", Object::Integer(size as i64));
+            dict.set(
+                "Index",
+                Object::Array(
+                    subsections
+                        .iter()
+                        .flat_map(|run| {
+                            [
+                                Object::Integer(run[0].0.number() as i64),
+                                Object::Integer(run.len() as i64),
+                            ]
+                        })
+                        .collect(),
+                ),
+            );
+            dict.set("Root", Object::Reference(ObjectId::new(root.0, root.1)));
+            if let Some((info_num, info_gen)) = info {
+                dict.set("Info", Object::Reference(ObjectId::new(info_num, info_gen)));
+            }
+            dict.set("Length", Object::Integer(data.len() as i64));
+
+            self.write_bytes(
+                format!("{} {} obj\n<<", stream_id.number(), stream_id.generation()).as_bytes(),
+            )?;
+            for (key, value) in dict.iter() {
+                self.write_bytes(b"\n/")?;
+                self.write_bytes(key.as_bytes())?;
+                self.write_bytes(b" ")?;
+                self.write_object_value(value)?;
+            }
+            self.write_bytes(format!("\n/ID {id_array}\n>>\nstream\n").as_bytes())?;
+            self.write_bytes(&data)?;
+            self.write_bytes(
+                format!("\nendstream\nendobj\nstartxref\n{xref_position}\n%%EOF\n").as_bytes(),
+            )?;
+        } else {
+            self.write_bytes(b"xref\n")?;
+            for run in &subsections {
+                let header = format!("{} {}\n", run[0].0.number(), run.len());
+                self.write_bytes(header.as_bytes())?;
+                for (id, position) in run.iter() {
+                    let entry = format!("{:010} {:05} n \n", position, id.generation());
+                    self.write_bytes(entry.as_bytes())?;
+                }
+            }
+
+            let mut trailer_text =
+                format!("trailer\n<<\n/Size {}\n/Root {} {} R", size, root.0, root.1);
+            if let Some((info_num, info_gen)) = info {
+                trailer_text.push_str(&format!("\n/Info {info_num} {info_gen} R"));
+            }
+            trailer_text.push_str(&format!(
+                "\n/Prev {}\n/ID {id_array}\n>>\nstartxref\n{}\n%%EOF\n",
+                trailer.xref_offset, xref_position
+            ));
+            self.write_bytes(trailer_text.as_bytes())?;
+        }
+
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Write a complete document from an existing object graph
+    ///
+    /// Every object is serialized exactly as parsed, so binary strings and
+    /// encoded stream data survive unchanged; only stream `/Length` entries
+    /// are recomputed. With `use_object_streams`, non-stream objects of
+    /// generation 0 are packed into object streams.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the output fails.
+    pub fn write_parsed_objects(
+        &mut self,
+        root: ObjectId,
+        info: ObjectId,
+        objects: Vec<(ObjectId, crate::parser::PdfObject)>,
+    ) -> Result<()> {
+        self.write_header()?;
+        self.catalog_id = Some(root);
+        self.info_id = Some(info);
+        let max_obj_num = objects.iter().map(|(id, _)| id.number()).max();
+        self.next_object_id = max_obj_num.unwrap_or(0).max(info.number()) + 1;
+
+        for (id, object) in objects {
+            self.write_parsed_object(id, &object)?;
+        }
+
+        if self.config.use_object_streams {
+            self.flush_object_streams()?;
+        }
+
+        let xref_position = self.current_position;
+        if self.config.use_xref_streams {
+            self.write_xref_stream()?;
+        } else {
+            self.write_xref()?;
+            self.write_trailer(xref_position)?;
+        }
+
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Write a parsed object, buffering it for an object stream if enabled
+    fn write_parsed_object(
+        &mut self,
+        id: ObjectId,
+        object: &crate::parser::PdfObject,
+    ) -> Result<()> {
+        let mut buffer = Vec::new();
+        write_parsed_value(object, &mut buffer);
+
+        let is_stream = matches!(object, crate::parser::PdfObject::Stream(_));
+        if self.config.use_object_streams && !is_stream && id.generation() == 0 {
+            self.buffered_objects.insert(id, buffer);
+            return Ok(());
+        }
+
+        self.xref_positions.insert(id, self.current_position);
+        let header = format!("{} {} obj\n", id.number(), id.generation());
+        self.write_bytes(header.as_bytes())?;
+        self.write_bytes(&buffer)?;
+        self.write_bytes(b"\nendobj\n")?;
+        Ok(())
+    }
+
     fn write_header(&mut self) -> Result<()> {
         let header = format!("%PDF-{}\n", self.config.pdf_version);
         self.write_bytes(header.as_bytes())?;
@@ -867,22 +1198,127 @@ impl<W: Write> PdfWriter<W> {
         }
 
         // Add StructTreeRoot if present (Tagged PDF - ISO 32000-1 §14.8)
-        if let Some(struct_tree) = &document.struct_tree {
-            if !struct_tree.is_empty() {
-                let struct_tree_root_id = self.write_struct_tree(struct_tree)?;
-                catalog.set("StructTreeRoot", Object::Reference(struct_tree_root_id));
-                // Mark as Tagged PDF
-                catalog.set("MarkInfo", {
-                    let mut mark_info = Dictionary::new();
-                    mark_info.set("Marked", Object::Boolean(true));
-                    Object::Dictionary(mark_info)
-                });
+        if let Some(struct_tree) = self.structure.take() {
+            let struct_tree_root_id = self.write_struct_tree(&struct_tree)?;
+            catalog.set("StructTreeRoot", Object::Reference(struct_tree_root_id));
+            // Mark as Tagged PDF
+            catalog.set("MarkInfo", {
+                let mut mark_info = Dictionary::new();
+                mark_info.set("Marked", Object::Boolean(true));
+                Object::Dictionary(mark_info)
+            });
+        }
+
+        // Natural language of the text (ISO 32000-1 §14.9.2)
+        if let Some(language) = &document.language {
+            catalog.set("Lang", Object::String(language.clone()));
+        }
+
+        // PDF/UA viewers show the title rather than the file name
+        let mut viewer_preferences = document.viewer_preferences.clone();
+        if self.pdfua {
+            viewer_preferences = Some(
+                viewer_preferences
+                    .unwrap_or_default()
+                    .display_doc_title(true),
+            );
+        }
+        if let Some(preferences) = viewer_preferences {
+            catalog.set(
+                "ViewerPreferences",
+                Object::Dictionary(preferences.to_dict()),
+            );
+        }
+
+        // Add OCProperties if layers are defined (ISO 32000-1 §8.11.4)
+        if !document.optional_content.is_empty() {
+            let ids = std::mem::take(&mut self.optional_content_ids);
+            for (name, dict) in document.optional_content.objects(&ids) {
+                if let Some(&id) = ids.get(&name) {
+                    self.write_object(id, Object::Dictionary(dict))?;
+                }
+            }
+            catalog.set(
+                "OCProperties",
+                Object::Dictionary(document.optional_content.to_dict(&ids)),
+            );
+        }
+
+        // Add attached files (ISO 32000-1 §7.11.4) and portfolio (§12.3.5)
+        if !document.attachments.is_empty() {
+            let mut specs = Vec::new();
+            for file in &document.attachments {
+                let stream = file.to_stream(self.config.compress_streams)?;
+                let stream_id = self.allocate_object_id();
+                self.write_parsed_object(stream_id, &crate::parser::PdfObject::Stream(stream))?;
+                let spec_id = self.allocate_object_id();
+                self.write_parsed_object(
+                    spec_id,
+                    &crate::parser::PdfObject::Dictionary(file.to_filespec(stream_id)),
+                )?;
+                specs.push((
+                    file.file_name.as_str(),
+                    spec_id,
+                    file.relationship.is_some(),
+                ));
+            }
+
+            let tree: Vec<_> = specs.iter().map(|&(name, id, _)| (name, id)).collect();
+            let tree_id = self.allocate_object_id();
+            self.write_parsed_object(
+                tree_id,
+                &crate::parser::PdfObject::Dictionary(crate::attachments::name_tree(&tree)),
+            )?;
+            let mut names = Dictionary::new();
+            names.set("EmbeddedFiles", Object::Reference(tree_id));
+            catalog.set("Names", Object::Dictionary(names));
+
+            // Associated files of the document (ISO 32000-2 §14.13)
+            let associated: Vec<Object> = specs
+                .iter()
+                .filter(|&&(_, _, related)| related)
+                .map(|&(_, id, _)| Object::Reference(id))
+                .collect();
+            if !associated.is_empty() {
+                catalog.set("AF", Object::Array(associated));
             }
         }
+        if let Some(collection) = &document.collection {
+            let collection_id = self.allocate_object_id();
+            self.write_parsed_object(
+                collection_id,
+                &crate::parser::PdfObject::Dictionary(collection.to_dict()),
+            )?;
+            catalog.set("Collection", Object::Reference(collection_id));
+        }
+
+        // Add PDF/A output intent, sRGB unless an invoice brings its own
+        let output_profile = document
+            .facturx
+            .as_ref()
+            .and_then(|invoice| invoice.output_intent.clone())
+            .or_else(|| {
+                self.pdfa.map(|_| {
+                    crate::graphics::IccProfile::from_standard(
+                        crate::graphics::StandardIccProfile::SRgb,
+                    )
+                })
+            });
+        if let Some(profile) = output_profile {
+            let intents = self.write_output_intents(&profile)?;
+            catalog.set("OutputIntents", intents);
+        }
 
         // Add XMP Metadata stream (ISO 32000-1 §14.3.2)
         // Generate XMP from document metadata and embed as stream
-        let xmp_metadata = document.create_xmp_metadata();
+        let mut xmp_metadata = document.create_xmp_metadata();
+        // Factur-X invoices already identify themselves as PDF/A-3
+        if let (Some(level), None) = (self.pdfa, &document.facturx) {
+            level.add_xmp(&mut xmp_metadata);
+        }
+        if self.pdfua {
+            crate::verification::pdfua::add_xmp(&mut xmp_metadata);
+        }
         let xmp_packet = xmp_metadata.to_xmp_packet();
         let metadata_id = self.allocate_object_id();
 
@@ -901,13 +1337,33 @@ impl<W: Write> PdfWriter<W> {
         // Reference it in catalog
         catalog.set("Metadata", Object::Reference(metadata_id));
 
+        // Outline, page labels, forms etc. of an opened document
+        self.merge_imported_catalog(document, &mut catalog)?;
+
         self.write_object(catalog_id, Object::Dictionary(catalog))?;
         Ok(())
     }
 
-    fn write_page_content(&mut self, content_id: ObjectId, page: &crate::page::Page) -> Result<()> {
+    fn write_page_content(
+        &mut self,
+        content_id: ObjectId,
+        page: &crate::page::Page,
+        renamed_resources: &HashMap<String, String>,
+    ) -> Result<()> {
         let mut page_copy = page.clone();
-        let content = page_copy.generate_content()?;
+        let mut content = Vec::new();
+        if let Some(imported) = page.imported() {
+            // Original content first and isolated, so new content is drawn on
+            // top of it in the initial graphics state
+            content.extend_from_slice(b"q\n");
+            content.extend_from_slice(&crate::writer::rename_resources(
+                &imported.content,
+                renamed_resources,
+            ));
+            content.extend_from_slice(b"\nQ\n");
+        }
+        content.extend_from_slice(&page_copy.generate_content()?);
+        self.check_pdfa_fonts(&content)?;
 
         // Create stream with compression if enabled
         #[cfg(feature = "compression")]
@@ -947,133 +1403,61 @@ impl<W: Write> PdfWriter<W> {
         outline_root.set("Type", Object::Name("Outlines".to_string()));
 
         if !outline_tree.items.is_empty() {
-            // Reserve IDs for all outline items
-            let mut item_ids = Vec::new();
-
-            // Count all items and assign IDs
-            fn count_items(items: &[crate::structure::OutlineItem]) -> usize {
-                let mut count = items.len();
-                for item in items {
-                    count += count_items(&item.children);
-                }
-                count
-            }
-
-            let total_items = count_items(&outline_tree.items);
-
-            // Reserve IDs for all items
-            for _ in 0..total_items {
-                item_ids.push(self.allocate_object_id());
-            }
-
-            let mut id_index = 0;
-
-            // Write root items
-            let first_id = item_ids[0];
-            let last_id = item_ids[outline_tree.items.len() - 1];
-
+            let (first_id, last_id) =
+                self.write_outline_items(&outline_tree.items, outline_root_id)?;
             outline_root.set("First", Object::Reference(first_id));
             outline_root.set("Last", Object::Reference(last_id));
 
             // Visible count
             let visible_count = outline_tree.visible_count();
             outline_root.set("Count", Object::Integer(visible_count));
-
-            // Write all items recursively
-            let mut written_items = Vec::new();
-
-            for (i, item) in outline_tree.items.iter().enumerate() {
-                let item_id = item_ids[id_index];
-                id_index += 1;
-
-                let prev_id = if i > 0 { Some(item_ids[i - 1]) } else { None };
-                let next_id = if i < outline_tree.items.len() - 1 {
-                    Some(item_ids[i + 1])
-                } else {
-                    None
-                };
-
-                // Write this item and its children
-                let children_ids = self.write_outline_item(
-                    item,
-                    item_id,
-                    outline_root_id,
-                    prev_id,
-                    next_id,
-                    &mut item_ids,
-                    &mut id_index,
-                )?;
-
-                written_items.extend(children_ids);
-            }
         }
 
         self.write_object(outline_root_id, Object::Dictionary(outline_root))?;
         Ok(outline_root_id)
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn write_outline_item(
+    /// Writes sibling outline items and their descendants, returning the
+    /// first and last of the siblings
+    fn write_outline_items(
         &mut self,
-        item: &crate::structure::OutlineItem,
-        item_id: ObjectId,
+        items: &[crate::structure::OutlineItem],
         parent_id: ObjectId,
-        prev_id: Option<ObjectId>,
-        next_id: Option<ObjectId>,
-        all_ids: &mut Vec<ObjectId>,
-        id_index: &mut usize,
-    ) -> Result<Vec<ObjectId>> {
-        let mut written_ids = vec![item_id];
-
-        // Handle children if any
-        let (first_child_id, last_child_id) = if !item.children.is_empty() {
-            let first_idx = *id_index;
-            let first_id = all_ids[first_idx];
-            let last_idx = first_idx + item.children.len() - 1;
-            let last_id = all_ids[last_idx];
-
-            // Write children
-            for (i, child) in item.children.iter().enumerate() {
-                let child_id = all_ids[*id_index];
-                *id_index += 1;
-
-                let child_prev = if i > 0 {
-                    Some(all_ids[first_idx + i - 1])
-                } else {
-                    None
-                };
-                let child_next = if i < item.children.len() - 1 {
-                    Some(all_ids[first_idx + i + 1])
-                } else {
-                    None
-                };
-
-                let child_ids = self.write_outline_item(
-                    child, child_id, item_id, // This item is the parent
-                    child_prev, child_next, all_ids, id_index,
-                )?;
+    ) -> Result<(ObjectId, ObjectId)> {
+        let item_ids: Vec<ObjectId> = items.iter().map(|_| self.allocate_object_id()).collect();
 
-                written_ids.extend(child_ids);
-            }
+        for (i, item) in items.iter().enumerate() {
+            let (first_child_id, last_child_id) = if item.children.is_empty() {
+                (None, None)
+            } else {
+                let (first, last) = self.write_outline_items(&item.children, item_ids[i])?;
+                (Some(first), Some(last))
+            };
 
-            (Some(first_id), Some(last_id))
-        } else {
-            (None, None)
-        };
+            let mut item_dict = crate::structure::outline_item_to_dict(
+                item,
+                parent_id,
+                first_child_id,
+                last_child_id,
+                i.checked_sub(1).map(|prev| item_ids[prev]),
+                item_ids.get(i + 1).copied(),
+            );
 
-        // Create item dictionary
-        let item_dict = crate::structure::outline_item_to_dict(
-            item,
-            parent_id,
-            first_child_id,
-            last_child_id,
-            prev_id,
-            next_id,
-        );
+            // A destination within the document names the page object
+            if let Some(Object::Array(dest)) = item_dict.get_mut("Dest") {
+                if let Some(page) = dest.first_mut() {
+                    if let Object::Integer(index) = *page {
+                        if let Some(&page_id) = self.page_ids.get(index as usize) {
+                            *page = Object::Reference(page_id);
+                        }
+                    }
+                }
+            }
 
-        self.write_object(item_id, Object::Dictionary(item_dict))?;
+            self.write_object(item_ids[i], Object::Dictionary(item_dict))?;
+        }
 
-        Ok(written_ids)
+        Ok((item_ids[0], item_ids[items.len() - 1]))
     }
 
     /// Writes the structure tree for Tagged PDF (ISO 32000-1 §14.8)
@@ -1127,6 +1511,10 @@ impl<W: Write> PdfWriter<W> {
             );
         }
 
+        // Element of every MCID, by page
+        let mut parents: std::collections::BTreeMap<usize, Vec<(u32, ObjectId)>> =
+            std::collections::BTreeMap::new();
+
         // Write all structure elements with parent references
         for (index, element) in struct_tree.iter().enumerate() {
             let element_id = element_ids[index];
@@ -1158,6 +1546,12 @@ impl<W: Write> PdfWriter<W> {
             if let Some(ref title) = element.attributes.title {
                 element_dict.set("T", Object::String(title.clone()));
             }
+            if let Some(scope) = element.attributes.scope {
+                let mut table_attributes = Dictionary::new();
+                table_attributes.set("O", Object::Name("Table".to_string()));
+                table_attributes.set("Scope", Object::Name(scope.as_pdf_name().to_string()));
+                element_dict.set("A", Object::Dictionary(table_attributes));
+            }
             if let Some(bbox) = element.attributes.bbox {
                 element_dict.set(
                     "BBox",
@@ -1180,11 +1574,18 @@ impl<W: Write> PdfWriter<W> {
 
             // Add marked content references (MCIDs)
             for mcid_ref in &element.mcids {
+                let Some(&page_id) = self.page_ids.get(mcid_ref.page_index) else {
+                    continue;
+                };
                 let mut mcr = Dictionary::new();
                 mcr.set("Type", Object::Name("MCR".to_string()));
-                mcr.set("Pg", Object::Integer(mcid_ref.page_index as i64));
+                mcr.set("Pg", Object::Reference(page_id));
                 mcr.set("MCID", Object::Integer(mcid_ref.mcid as i64));
                 kids.push(Object::Dictionary(mcr));
+                parents
+                    .entry(mcid_ref.page_index)
+                    .or_insert_with(Vec::new)
+                    .push((mcid_ref.mcid, element_id));
             }
 
             if !kids.is_empty() {
@@ -1203,6 +1604,30 @@ impl<W: Write> PdfWriter<W> {
             struct_tree_root.set("K", Object::Reference(element_ids[root_index]));
         }
 
+        // Parent tree, keyed by the StructParents of each page
+        // (ISO 32000-1 §14.7.4.4)
+        if !parents.is_empty() {
+            let mut nums = Vec::new();
+            for (page_index, entries) in parents {
+                let count = entries.iter().map(|&(mcid, _)| mcid as usize + 1).max();
+                let mut elements = vec![Object::Null; count.unwrap_or(0)];
+                for (mcid, element_id) in entries {
+                    elements[mcid as usize] = Object::Reference(element_id);
+                }
+                nums.push(Object::Integer(page_index as i64));
+                nums.push(Object::Array(elements));
+            }
+            let mut parent_tree = Dictionary::new();
+            parent_tree.set("Nums", Object::Array(nums));
+            let parent_tree_id = self.allocate_object_id();
+            self.write_object(parent_tree_id, Object::Dictionary(parent_tree))?;
+            struct_tree_root.set("ParentTree", Object::Reference(parent_tree_id));
+            struct_tree_root.set(
+                "ParentTreeNextKey",
+                Object::Integer(self.page_ids.len() as i64),
+            );
+        }
+
         // Add RoleMap if not empty
         if !struct_tree.role_map.mappings().is_empty() {
             let mut role_map = Dictionary::new();
@@ -1334,6 +1759,12 @@ impl<W: Write> PdfWriter<W> {
             }
             chars
         });
+        // Glyphs shaped text shows with private use CIDs, such as ligatures
+        let extras = self.shaping_fonts.extra_glyphs(font_name);
+        let extra_glyphs: HashMap<u32, u16> = extras
+            .iter()
+            .map(|extra| (extra.cid as u32, extra.glyph))
+            .collect();
         // Allocate IDs for all font objects
         let font_id = self.allocate_object_id();
         let descendant_font_id = self.allocate_object_id();
@@ -1345,12 +1776,13 @@ impl<W: Write> PdfWriter<W> {
         // Keep track of the glyph mapping if we subset the font
         // IMPORTANT: We need the ORIGINAL font for width calculations, not the subset
         let (font_data_to_embed, subset_glyph_mapping, original_font_for_widths) =
-            if font.data.len() > 100_000 && !used_chars.is_empty() {
-                // Large font - try to subset it
-                match crate::text::fonts::truetype_subsetter::subset_font(
+            if (font.data.len() > 100_000 || self.pdfa.is_some()) && !used_chars.is_empty() {
+                // Large font, or PDF/A which needs subsets - try to subset it
+                match crate::text::fonts::truetype_subsetter::TrueTypeSubsetter::new(
                     font.data.clone(),
-                    &used_chars,
-                ) {
+                )
+                .and_then(|subsetter| subsetter.subset_with_glyphs(&used_chars, &extra_glyphs))
+                {
                     Ok(subset_result) => {
                         // Successfully subsetted - keep both font data and mapping
                         // Also keep reference to original font for width calculations
@@ -1471,13 +1903,15 @@ impl<W: Write> PdfWriter<W> {
             &original_font_for_widths,
             default_width,
             subset_glyph_mapping.as_ref(),
+            &extra_glyphs,
         );
         cid_font.set("W", Object::Array(w_array));
 
         // CIDToGIDMap - Generate proper mapping from CID (Unicode) to GlyphID
         // This is critical for Type0 fonts to work correctly
         // If we subsetted the font, use the new glyph mapping
-        let cid_to_gid_map = self.generate_cid_to_gid_map(font, subset_glyph_mapping.as_ref())?;
+        let cid_to_gid_map =
+            self.generate_cid_to_gid_map(font, subset_glyph_mapping.as_ref(), &extra_glyphs)?;
         if !cid_to_gid_map.is_empty() {
             // Write the CIDToGIDMap as a stream
             let cid_to_gid_map_id = self.allocate_object_id();
@@ -1493,7 +1927,7 @@ impl<W: Write> PdfWriter<W> {
         self.write_object(descendant_font_id, Object::Dictionary(cid_font))?;
 
         // Write ToUnicode CMap
-        let cmap_data = self.generate_tounicode_cmap_from_font(font);
+        let cmap_data = self.generate_tounicode_cmap_from_font(font, &extras);
         let cmap_dict = Dictionary::new();
         let cmap_stream = Object::Stream(cmap_dict, cmap_data);
         self.write_object(to_unicode_id, cmap_stream)?;
@@ -1562,6 +1996,7 @@ impl<W: Write> PdfWriter<W> {
         font: &crate::fonts::Font,
         _default_width: i64,
         subset_mapping: Option<&HashMap<u32, u16>>,
+        extra_glyphs: &HashMap<u32, u16>,
     ) -> Vec<Object> {
         use crate::text::fonts::truetype::TrueTypeFont;
 
@@ -1572,7 +2007,7 @@ impl<W: Write> PdfWriter<W> {
             // IMPORTANT: Always use ORIGINAL mappings for width calculation
             // The subset_mapping has NEW GlyphIDs which don't correspond to the right glyphs
             // in the original font's width table
-            let char_to_glyph = {
+            let mut char_to_glyph = {
                 // Parse cmap to get original mappings
                 if let Ok(cmap_tables) = tt_font.parse_cmap() {
                     if let Some(cmap) = cmap_tables
@@ -1602,6 +2037,10 @@ impl<W: Write> PdfWriter<W> {
             };
 
             if !char_to_glyph.is_empty() {
+                // Extra glyphs are not in the cmap; their GlyphIDs are
+                // always those of the original font
+                char_to_glyph.extend(extra_glyphs);
+
                 // Get actual widths from the font
                 if let Ok(widths) = tt_font.get_glyph_widths(&char_to_glyph) {
                     // NOTE: get_glyph_widths already returns widths scaled to PDF units (1000 per em)
@@ -1712,6 +2151,7 @@ impl<W: Write> PdfWriter<W> {
         &mut self,
         font: &crate::fonts::Font,
         subset_mapping: Option<&HashMap<u32, u16>>,
+        extra_glyphs: &HashMap<u32, u16>,
     ) -> Result<Vec<u8>> {
         use crate::text::fonts::truetype::TrueTypeFont;
 
@@ -1734,7 +2174,10 @@ impl<W: Write> PdfWriter<W> {
                     crate::error::PdfError::FontError("No Unicode cmap table found".to_string())
                 })?;
 
-            cmap.mappings.clone()
+            // A subset mapping already has the extra glyphs, renumbered
+            let mut mappings = cmap.mappings.clone();
+            mappings.extend(extra_glyphs);
+            mappings
         };
 
         // Build the CIDToGIDMap
@@ -1751,6 +2194,7 @@ impl<W: Write> PdfWriter<W> {
             used_chars
                 .iter()
                 .map(|ch| *ch as u32)
+                .chain(extra_glyphs.keys().copied())
                 .max()
                 .unwrap_or(0x00FF) // At least Basic Latin
                 .min(0xFFFF) as usize
@@ -1788,7 +2232,11 @@ impl<W: Write> PdfWriter<W> {
     }
 
     /// Generate ToUnicode CMap for Type0 font from fonts::Font
-    fn generate_tounicode_cmap_from_font(&self, font: &crate::fonts::Font) -> Vec<u8> {
+    fn generate_tounicode_cmap_from_font(
+        &self,
+        font: &crate::fonts::Font,
+        extras: &[crate::text::shaping::ExtraGlyph],
+    ) -> Vec<u8> {
         use crate::text::fonts::truetype::TrueTypeFont;
 
         let mut cmap = String::new();
@@ -1950,6 +2398,22 @@ impl<W: Write> PdfWriter<W> {
             }
         }
 
+        // Glyphs of shaped text without a character of their own map to
+        // the text they stand for, e.g. a ligature to its letters
+        let extras: Vec<_> = extras.iter().filter(|e| !e.text.is_empty()).collect();
+        for chunk in extras.chunks(100) {
+            cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+            for extra in chunk {
+                let text: String = extra
+                    .text
+                    .encode_utf16()
+                    .map(|unit| format!("{unit:04X}"))
+                    .collect();
+                cmap.push_str(&format!("<{:04X}> <{}>\n", extra.cid, text));
+            }
+            cmap.push_str("endbfchar\n");
+        }
+
         // CMap footer
         cmap.push_str("endcmap\n");
         cmap.push_str("CMapName currentdict /CMap defineresource pop\n");
@@ -2055,14 +2519,16 @@ impl<W: Write> PdfWriter<W> {
 
         // Store page IDs for form field references
         self.page_ids = page_ids.clone();
+        self.register_imported_pages(document, &page_ids);
 
         // Write individual pages with font references
         for (i, page) in document.pages.iter().enumerate() {
             let page_id = page_ids[i];
             let content_id = content_ids[i];
 
-            self.write_page_with_fonts(page_id, pages_id, content_id, page, document, font_refs)?;
-            self.write_page_content(content_id, page)?;
+            let renamed_resources = self
+                .write_page_with_fonts(page_id, pages_id, content_id, page, document, font_refs)?;
+            self.write_page_content(content_id, page, &renamed_resources)?;
         }
 
         Ok(())
@@ -2086,7 +2552,7 @@ impl<W: Write> PdfWriter<W> {
         page: &crate::page::Page,
         _document: &Document,
         font_refs: &HashMap<String, ObjectId>,
-    ) -> Result<()> {
+    ) -> Result<HashMap<String, String>> {
         // Start with the page's dictionary which includes annotations
         let mut page_dict = page.to_dict();
 
@@ -2094,6 +2560,19 @@ impl<W: Write> PdfWriter<W> {
         page_dict.set("Parent", Object::Reference(parent_id));
         page_dict.set("Contents", Object::Reference(content_id));
 
+        // Marked content of the page is found through the parent tree, and
+        // annotations follow the structure order
+        let page_index = self.page_ids.iter().position(|&id| id == page_id);
+        if let (Some(index), Some(tree)) = (page_index, &self.structure) {
+            let tagged = tree
+                .iter()
+                .any(|element| element.mcids.iter().any(|r| r.page_index == index));
+            if tagged {
+                page_dict.set("StructParents", Object::Integer(index as i64));
+                page_dict.set("Tabs", Object::Name("S".to_string()));
+            }
+        }
+
         // Get resources dictionary or create new one
         let mut resources = if let Some(Object::Dictionary(res)) = page_dict.get("Resources") {
             res.clone()
@@ -2209,6 +2688,11 @@ impl<W: Write> PdfWriter<W> {
             Object::Dictionary(courier_bold_oblique_dict),
         );
 
+        // PDF/A only allows embedded fonts, which the standard ones never are
+        if self.pdfa.is_some() {
+            font_dict = Dictionary::new();
+        }
+
         // Add custom fonts (Type0 fonts for Unicode support)
         for (font_name, font_id) in font_refs {
             font_dict.set(font_name, Object::Reference(*font_id));
@@ -2301,6 +2785,17 @@ impl<W: Write> PdfWriter<W> {
             }
         }
 
+        // Add Properties resources for layers drawn in
+        let mut properties = Dictionary::new();
+        for name in page.layers() {
+            if let Some(&id) = self.optional_content_ids.get(name) {
+                properties.set(name, Object::Reference(id));
+            }
+        }
+        if !properties.is_empty() {
+            resources.set("Properties", Object::Dictionary(properties));
+        }
+
         // Merge preserved resources from original PDF (if any)
         // Phase 2.3: Rename preserved fonts to avoid conflicts with overlay fonts
         if let Some(preserved_res) = page.get_preserved_resources() {
@@ -2358,6 +2853,12 @@ impl<W: Write> PdfWriter<W> {
             }
         }
 
+        // Carry over the resources and entries of an opened page
+        let renamed_resources = match page.imported() {
+            Some(imported) => self.merge_imported_page(imported, &mut page_dict, &mut resources)?,
+            None => HashMap::new(),
+        };
+
         page_dict.set("Resources", Object::Dictionary(resources));
 
         // Handle form widget annotations
@@ -2397,7 +2898,7 @@ impl<W: Write> PdfWriter<W> {
         }
 
         self.write_object(page_id, Object::Dictionary(page_dict))?;
-        Ok(())
+        Ok(renamed_resources)
     }
 }
 
@@ -2419,11 +2920,18 @@ impl PdfWriter<BufWriter<std::fs::File>> {
             form_field_ids: Vec::new(),
             page_ids: Vec::new(),
             config: WriterConfig::default(),
+            pdfa: None,
+            pdfua: false,
             document_used_chars: None,
+            shaping_fonts: Default::default(),
             buffered_objects: HashMap::new(),
             compressed_object_map: HashMap::new(),
             prev_xref_offset: None,
             base_pdf_size: None,
+            imported: import::ImportState::default(),
+            content_digest: md5::Context::new(),
+            optional_content_ids: HashMap::new(),
+            structure: None,
         })
     }
 }
@@ -2762,9 +3270,10 @@ impl<W: Write> PdfWriter<W> {
         // Finalize and get completed streams
         let streams = os_writer.finalize()?;
 
-        // Write each object stream to the PDF
+        // Write each object stream to the PDF, numbered after the other objects
         for mut stream in streams {
-            let stream_id = stream.stream_id;
+            let stream_id = self.allocate_object_id();
+            stream.stream_id = stream_id;
 
             // Generate compressed stream data
             let compressed_data = stream.generate_stream_data(6)?;
@@ -2921,6 +3430,11 @@ impl<W: Write> PdfWriter<W> {
             self.write_bytes(b" ")?;
             self.write_object_value(value)?;
         }
+        if self.pdfa.is_some() {
+            let id = self.file_id();
+            self.write_bytes(b"\n/ID ")?;
+            self.write_bytes(&id)?;
+        }
         self.write_bytes(b"\n>>\n")?;
 
         // Write stream
@@ -2958,8 +3472,19 @@ impl<W: Write> PdfWriter<W> {
             trailer.set("Prev", Object::Integer(prev_xref as i64));
         }
 
-        self.write_bytes(b"trailer\n")?;
-        self.write_object_value(&Object::Dictionary(trailer))?;
+        self.write_bytes(b"trailer\n<<")?;
+        for (key, value) in trailer.entries() {
+            self.write_bytes(b"\n/")?;
+            self.write_bytes(key.as_bytes())?;
+            self.write_bytes(b" ")?;
+            self.write_object_value(value)?;
+        }
+        if self.pdfa.is_some() {
+            let id = self.file_id();
+            self.write_bytes(b"\n/ID ")?;
+            self.write_bytes(&id)?;
+        }
+        self.write_bytes(b"\n>>")?;
         self.write_bytes(b"\nstartxref\n")?;
         self.write_bytes(xref_position.to_string().as_bytes())?;
         self.write_bytes(b"\n%%EOF\n")?;
@@ -2970,6 +3495,9 @@ impl<W: Write> PdfWriter<W> {
     fn write_bytes(&mut self, data: &[u8]) -> Result<()> {
         self.writer.write_all(data)?;
         self.current_position += data.len() as u64;
+        if self.pdfa.is_some() {
+            self.content_digest.consume(data);
+        }
         Ok(())
     }
 
@@ -3144,7 +3672,7 @@ impl<W: Write> PdfWriter<W> {
 }
 
 /// Format a DateTime as a PDF date string (D:YYYYMMDDHHmmSSOHH'mm)
-fn format_pdf_date(date: DateTime<Utc>) -> String {
+pub(crate) fn format_pdf_date(date: DateTime<Utc>) -> String {
     // Format the UTC date according to PDF specification
     // D:YYYYMMDDHHmmSSOHH'mm where O is the relationship of local time to UTC (+ or -)
     let formatted = date.format("D:%Y%m%d%H%M%S");
@@ -3153,6 +3681,108 @@ fn format_pdf_date(date: DateTime<Utc>) -> String {
     format!("{formatted}+00'00")
 }
 
+/// Serialize a parsed object in PDF syntax
+///
+/// Dictionary keys are sorted so equal objects always serialize to equal
+/// bytes. Strings of printable ASCII are written as literals, anything else
+/// in hexadecimal.
+pub(crate) fn write_parsed_value(object: &crate::parser::PdfObject, out: &mut Vec<u8>) {
+    use crate::parser::PdfObject;
+
+    match object {
+        PdfObject::Null => out.extend_from_slice(b"null"),
+        PdfObject::Boolean(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        PdfObject::Integer(i) => out.extend_from_slice(i.to_string().as_bytes()),
+        PdfObject::Real(f) => {
+            let formatted = format!("{f:.6}");
+            let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+            out.extend_from_slice(if trimmed == "-0" { "0" } else { trimmed }.as_bytes());
+        }
+        PdfObject::String(s) => {
+            let bytes = s.as_bytes();
+            if bytes.iter().all(|&b| (0x20..0x7F).contains(&b)) {
+                out.push(b'(');
+                for &b in bytes {
+                    if matches!(b, b'(' | b')' | b'\\') {
+                        out.push(b'\\');
+                    }
+                    out.push(b);
+                }
+                out.push(b')');
+            } else {
+                out.push(b'<');
+                for b in bytes {
+                    out.extend_from_slice(format!("{b:02X}").as_bytes());
+                }
+                out.push(b'>');
+            }
+        }
+        PdfObject::Name(name) => write_parsed_name(name.as_str(), out),
+        PdfObject::Array(array) => {
+            out.push(b'[');
+            for (i, item) in array.0.iter().enumerate() {
+                if i > 0 {
+                    out.push(b' ');
+                }
+                write_parsed_value(item, out);
+            }
+            out.push(b']');
+        }
+        PdfObject::Dictionary(dict) => write_parsed_dictionary(dict, None, out),
+        PdfObject::Stream(stream) => {
+            write_parsed_dictionary(&stream.dict, Some(stream.data.len()), out);
+            out.extend_from_slice(b"\nstream\n");
+            out.extend_from_slice(&stream.data);
+            out.extend_from_slice(b"\nendstream");
+        }
+        PdfObject::Reference(num, gen) => {
+            out.extend_from_slice(format!("{num} {gen} R").as_bytes());
+        }
+    }
+}
+
+/// Serialize a dictionary, replacing `/Length` when it belongs to a stream
+fn write_parsed_dictionary(
+    dict: &crate::parser::objects::PdfDictionary,
+    length: Option<usize>,
+    out: &mut Vec<u8>,
+) {
+    let mut entries: Vec<_> = dict
+        .0
+        .iter()
+        .filter(|(key, _)| length.is_none() || key.as_str() != "Length")
+        .collect();
+    entries.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+    out.extend_from_slice(b"<<");
+    if let Some(length) = length {
+        out.extend_from_slice(format!("/Length {length}").as_bytes());
+    }
+    for (key, value) in entries {
+        write_parsed_name(key.as_str(), out);
+        out.push(b' ');
+        write_parsed_value(value, out);
+    }
+    out.extend_from_slice(b">>");
+}
+
+/// Write a name, escaping delimiters and non-regular characters as `#xx`
+fn write_parsed_name(name: &str, out: &mut Vec<u8>) {
+    out.push(b'/');
+    for &b in name.as_bytes() {
+        if (0x21..0x7F).contains(&b) && !b"()<>[]{}/%#".contains(&b) {
+            out.push(b);
+        } else {
+            out.extend_from_slice(format!("#{b:02X}").as_bytes());
+        }
+    }
+}
+
+mod import;
+mod linearize;
+mod pdfa;
+mod pdfua;
+
 #[cfg(test)]
 mod tests;
 
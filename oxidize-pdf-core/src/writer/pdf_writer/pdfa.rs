@@ -0,0 +1,189 @@
+//! PDF/A output (ISO 19005)
+//!
+//! In PDF/A mode the writer uses the PDF version of the level, subsets
+//! and embeds every font, adds an sRGB output intent, identifies the level
+//! in the XMP metadata and gives the file an identifier. Documents using
+//! a feature the level forbids are rejected before anything is written.
+
+use super::PdfWriter;
+use crate::document::Document;
+use crate::error::{PdfError, Result};
+use crate::graphics::{BlendMode, IccProfile};
+use crate::objects::{Dictionary, Object};
+use crate::parser::content::{ContentOperation, ContentParser};
+use crate::verification::pdfa::PdfALevel;
+use std::io::Write;
+
+/// Resource names of the standard 14 fonts, which are never embedded
+const STANDARD_FONTS: &[&str] = &[
+    "Helvetica",
+    "Helvetica-Bold",
+    "Helvetica-Oblique",
+    "Helvetica-BoldOblique",
+    "Times-Roman",
+    "Times-Bold",
+    "Times-Italic",
+    "Times-BoldItalic",
+    "Courier",
+    "Courier-Bold",
+    "Courier-Oblique",
+    "Courier-BoldOblique",
+    "Symbol",
+    "ZapfDingbats",
+];
+
+impl<W: Write> PdfWriter<W> {
+    /// Adjust the configuration to the level and reject features it forbids
+    pub(super) fn prepare_pdfa(&mut self, document: &Document, level: PdfALevel) -> Result<()> {
+        let forbidden =
+            |what: String| PdfError::InvalidOperation(format!("{level} does not allow {what}"));
+
+        self.config.pdf_version = level.pdf_version().to_string();
+        if level == PdfALevel::A1b {
+            self.config.use_xref_streams = false;
+            self.config.use_object_streams = false;
+        }
+
+        if document.encryption.is_some() {
+            return Err(forbidden("encryption".to_string()));
+        }
+        if document.facturx.is_some() && level != PdfALevel::A3b {
+            return Err(forbidden(
+                "Factur-X invoices, which are PDF/A-3".to_string(),
+            ));
+        }
+        for file in &document.attachments {
+            let name = &file.file_name;
+            match level {
+                PdfALevel::A1b => return Err(forbidden(format!("attached file {name}"))),
+                // Only the MIME type is checked, not the conformance of the
+                // attached PDF itself
+                PdfALevel::A2b if file.mime_type.as_deref() != Some("application/pdf") => {
+                    return Err(forbidden(format!(
+                        "attached file {name}, which is not PDF/A"
+                    )))
+                }
+                PdfALevel::A3b => {
+                    // ISO 19005-3 §6.8: the relationship, the MIME type
+                    // (`/Subtype`) and the modification date (`/Params`)
+                    let missing = [
+                        ("an AFRelationship", file.relationship.is_none()),
+                        ("a MIME type", file.mime_type.is_none()),
+                        ("a modification date", file.modification_date.is_none()),
+                    ];
+                    if let Some((what, _)) = missing.iter().find(|(_, missing)| *missing) {
+                        return Err(forbidden(format!("attached file {name} without {what}")));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let open_action = document
+            .open_action
+            .iter()
+            .map(|action| Object::Dictionary(action.to_dict()));
+        let annotations = document
+            .pages
+            .iter()
+            .flat_map(|page| page.annotations())
+            .map(|annotation| Object::Dictionary(annotation.to_dict()));
+        if open_action.chain(annotations).any(|o| has_javascript(&o)) {
+            return Err(forbidden("JavaScript actions".to_string()));
+        }
+
+        if !level.allows_transparency() {
+            for (index, page) in document.pages.iter().enumerate() {
+                let transparent_state = page.get_extgstate_resources().is_some_and(|states| {
+                    states.values().any(|state| {
+                        state.alpha_fill.is_some_and(|alpha| alpha < 1.0)
+                            || state.alpha_stroke.is_some_and(|alpha| alpha < 1.0)
+                            || state.soft_mask.as_ref().is_some_and(|mask| !mask.is_none())
+                            || state
+                                .blend_mode
+                                .as_ref()
+                                .is_some_and(|mode| *mode != BlendMode::Normal)
+                    })
+                });
+                let transparent_image = page.images().values().any(|i| i.has_transparency());
+                if transparent_state || transparent_image {
+                    return Err(forbidden(format!("transparency (page {})", index + 1)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject content drawn with a standard 14 font, as those cannot be
+    /// embedded
+    pub(super) fn check_pdfa_fonts(&self, content: &[u8]) -> Result<()> {
+        let Some(level) = self.pdfa else {
+            return Ok(());
+        };
+        let operations = ContentParser::parse(content).unwrap_or_default();
+        for operation in operations {
+            if let ContentOperation::SetFont(name, _) = operation {
+                if STANDARD_FONTS.contains(&name.as_str()) {
+                    let substitute = match name.split('-').next() {
+                        Some("Helvetica") => "; Liberation Sans has the metrics of Helvetica",
+                        Some("Times") => "; Liberation Serif has the metrics of Times",
+                        Some("Courier") => "; Liberation Mono has the metrics of Courier",
+                        _ => "",
+                    };
+                    return Err(PdfError::FontError(format!(
+                        "{level} requires embedded fonts, but text is drawn in the standard \
+                         font {name}, which is never embedded. Load a TrueType or OpenType \
+                         font with Document::add_font or Document::add_font_from_bytes and \
+                         draw the text with Font::Custom instead{substitute}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the ICC profile and return the `OutputIntents` array
+    /// (ISO 32000-1 §14.11.5)
+    pub(super) fn write_output_intents(&mut self, profile: &IccProfile) -> Result<Object> {
+        let mut profile_dict = Dictionary::new();
+        profile_dict.set("N", Object::Integer(profile.components as i64));
+        profile_dict.set("Length", Object::Integer(profile.data.len() as i64));
+        let profile_id = self.allocate_object_id();
+        self.write_object(
+            profile_id,
+            Object::Stream(profile_dict, profile.data.clone()),
+        )?;
+
+        let mut intent = Dictionary::new();
+        intent.set("Type", Object::Name("OutputIntent".to_string()));
+        intent.set("S", Object::Name("GTS_PDFA1".to_string()));
+        intent.set(
+            "OutputConditionIdentifier",
+            Object::String(profile.name.clone()),
+        );
+        intent.set("Info", Object::String(profile.name.clone()));
+        intent.set("DestOutputProfile", Object::Reference(profile_id));
+        Ok(Object::Array(vec![Object::Dictionary(intent)]))
+    }
+
+    /// File identifier of the trailer (ISO 32000-1 §14.4), as written: a
+    /// digest of the bytes written so far, so the same document always gets
+    /// the same identifier. Both parts are the same for a newly written file
+    pub(super) fn file_id(&self) -> Vec<u8> {
+        let digest = self.content_digest.clone().finalize();
+        let hex: String = digest.0.iter().map(|byte| format!("{byte:02X}")).collect();
+        format!("[<{hex}> <{hex}>]").into_bytes()
+    }
+}
+
+/// Whether an action of the object runs JavaScript
+fn has_javascript(object: &Object) -> bool {
+    match object {
+        Object::Dictionary(dict) => {
+            matches!(dict.get("S"), Some(Object::Name(name)) if name == "JavaScript")
+                || dict.iter().any(|(_, value)| has_javascript(value))
+        }
+        Object::Array(items) => items.iter().any(has_javascript),
+        _ => false,
+    }
+}
@@ -0,0 +1,36 @@
+//! PDF/UA-1 output (ISO 14289-1)
+//!
+//! In PDF/UA mode the writer requires what assistive technology cannot do
+//! without: a title, the language of the text and tagged content. The
+//! catalog then marks the file as tagged, declares the language, asks
+//! viewers to show the title and the XMP metadata identifies the file as
+//! PDF/UA.
+
+use super::PdfWriter;
+use crate::document::Document;
+use crate::error::{PdfError, Result};
+use std::io::Write;
+
+impl<W: Write> PdfWriter<W> {
+    /// Reject documents that cannot be accessible
+    pub(super) fn prepare_pdfua(&mut self, document: &Document) -> Result<()> {
+        let missing = |what: &str| PdfError::InvalidOperation(format!("PDF/UA requires {what}"));
+
+        if document
+            .metadata
+            .title
+            .as_deref()
+            .unwrap_or_default()
+            .is_empty()
+        {
+            return Err(missing("a document title"));
+        }
+        if document.language.as_deref().unwrap_or_default().is_empty() {
+            return Err(missing("the document language"));
+        }
+        if self.structure.is_none() {
+            return Err(missing("tagged content; see Page::set_tagged"));
+        }
+        Ok(())
+    }
+}
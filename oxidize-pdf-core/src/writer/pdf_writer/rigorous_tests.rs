@@ -89,6 +89,20 @@ fn test_writer_with_modern_config() {
     assert!(writer.config.use_object_streams);
 }
 
+#[test]
+fn test_modern_config_output_is_readable() {
+    let mut document = crate::document::Document::new();
+    document.add_page(Page::a4());
+    let bytes = document
+        .to_bytes_with_config(WriterConfig::modern())
+        .unwrap();
+
+    let reader = crate::parser::PdfReader::new(std::io::Cursor::new(bytes))
+        .expect("XRef stream written by the modern config must parse");
+    let document = crate::parser::PdfDocument::new(reader);
+    assert_eq!(document.page_count().unwrap(), 1);
+}
+
 #[test]
 fn test_writer_with_legacy_config() {
     let buffer = Vec::new();
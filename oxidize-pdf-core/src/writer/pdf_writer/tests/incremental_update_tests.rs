@@ -273,4 +273,47 @@ mod incremental_update_tests {
         let updated_content = fs::read(&updated_pdf_path).unwrap();
         assert!(updated_content.starts_with(b"%PDF-1.7")); // Base version preserved
     }
+
+    #[test]
+    fn test_incremental_objects_replace_and_append() {
+        use crate::objects::{Dictionary, Object, ObjectId};
+        use crate::parser::{PdfDocument, PdfReader};
+        use std::io::Cursor;
+
+        let mut base_doc = Document::new();
+        base_doc.set_title("Original");
+        base_doc.add_page(Page::a4());
+        let base = base_doc.to_bytes().unwrap();
+
+        let reader = PdfReader::new(Cursor::new(base.clone())).unwrap();
+        let size = reader.trailer().size().unwrap();
+        let (info_num, info_gen) = reader.trailer().info().unwrap();
+
+        let mut info = Dictionary::new();
+        info.set("Title", Object::String("Replaced".to_string()));
+        let mut extra = Dictionary::new();
+        extra.set("Type", Object::Name("Marker".to_string()));
+
+        let mut output = Vec::new();
+        {
+            let mut writer = PdfWriter::with_config(&mut output, WriterConfig::incremental());
+            writer
+                .write_incremental_objects(
+                    &base,
+                    vec![
+                        (ObjectId::new(info_num, info_gen), Object::Dictionary(info)),
+                        (ObjectId::new(size, 0), Object::Dictionary(extra)),
+                    ],
+                )
+                .unwrap();
+        }
+
+        assert!(output.starts_with(&base));
+        let document = PdfDocument::new(PdfReader::new(Cursor::new(output)).unwrap());
+        assert_eq!(document.trailer().size().unwrap(), size + 1);
+        let title = document.metadata().unwrap().title;
+        assert_eq!(title.as_deref(), Some("Replaced"));
+        let marker = document.get_object(size, 0).unwrap();
+        assert_eq!(marker.as_dict().unwrap().get_type(), Some("Marker"),);
+    }
 }
@@ -287,7 +287,7 @@ fn test_batch_job_types() {
     });
 
     processor.add_job(BatchJob::Compress {
-        input: input_pdf,
+        input: input_pdf.clone(),
         output: temp_dir.path().join("compressed.pdf"),
         quality: 75,
     });
@@ -302,6 +302,18 @@ fn test_batch_job_types() {
     assert!(temp_dir.path().join("rotated.pdf").exists());
     assert!(temp_dir.path().join("extracted.pdf").exists());
     assert!(temp_dir.path().join("compressed.pdf").exists());
+
+    // Compression reports the sizes before and after
+    let compressed = summary
+        .results
+        .iter()
+        .find_map(|r| r.compression_sizes())
+        .expect("compress job should report sizes");
+    let input_size = std::fs::metadata(&input_pdf).unwrap().len();
+    let output_size = std::fs::metadata(temp_dir.path().join("compressed.pdf"))
+        .unwrap()
+        .len();
+    assert_eq!(compressed, (input_size, output_size));
 }
 
 #[test]
@@ -228,6 +228,7 @@ fn test_split_pdf() -> std::result::Result<(), Box<dyn std::error::Error>> {
             .to_string(),
         preserve_metadata: true,
         optimize: false,
+        preserve_bookmarks: true,
     };
 
     split_pdf(&input_path, options)?;
@@ -431,6 +432,7 @@ fn test_complex_document_workflow() -> std::result::Result<(), Box<dyn std::erro
             .to_string(),
         preserve_metadata: true,
         optimize: false,
+        preserve_bookmarks: true,
     };
 
     let split_files = split_pdf(&original_path, split_options)?;
@@ -0,0 +1,24 @@
+#!/bin/bash
+# Generate PKCS#7 envelopes sealed with legacy content ciphers using OpenSSL 3.
+# The content is a 24-byte public-key handler seed addressed to
+# ../signatures/rsa_signer.cert.pem.
+
+set -e
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+cd "$SCRIPT_DIR"
+
+CERT=../signatures/rsa_signer.cert.pem
+SEED=$(mktemp)
+trap 'rm -f "$SEED"' EXIT
+printf '0123456789abcdefghijklmn' > "$SEED"
+
+openssl cms -encrypt -binary -des3 -in "$SEED" -recip "$CERT" -outform DER \
+    -out des_ede3_cbc.der
+# RC2 lives in the legacy provider
+openssl cms -encrypt -binary -rc2-40 -provider legacy -provider default \
+    -in "$SEED" -recip "$CERT" -outform DER -out rc2_40_cbc.der
+openssl cms -encrypt -binary -rc2-128 -provider legacy -provider default \
+    -in "$SEED" -recip "$CERT" -outform DER -out rc2_128_cbc.der
+
+echo "Generated des_ede3_cbc.der, rc2_40_cbc.der and rc2_128_cbc.der"